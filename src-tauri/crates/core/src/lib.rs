@@ -9,7 +9,10 @@ pub mod logger;
 pub mod models;
 
 // 重新导出常用类型
-pub use logger::{LogEntry, LogStore, LogStoreConfig, SharedLogStore};
+pub use logger::{
+    write_ndjson, LogBuffer, LogEntry, LogPage, LogQuery, LogStats, LogStore, LogStoreConfig,
+    SharedLogStore,
+};
 pub use models::provider_type::ProviderType;
 pub use models::*;
 
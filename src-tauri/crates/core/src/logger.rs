@@ -1,18 +1,22 @@
 //! 日志管理模块
 use chrono::{Duration, Local, Utc};
+use regex::RegexBuilder;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::fs::{self, OpenOptions};
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
 
 #[derive(Debug, Clone)]
 pub struct LogStoreConfig {
+    /// 每个日志级别保留的最大条数
     pub max_logs: usize,
     pub retention_days: u32,
     pub max_file_size: u64,
     pub enable_file_logging: bool,
+    /// 内存中日志占用的上限（字节），超出时丢弃最旧的日志
+    pub max_memory_bytes: usize,
 }
 
 impl Default for LogStoreConfig {
@@ -22,22 +26,288 @@ impl Default for LogStoreConfig {
             retention_days: 7,
             max_file_size: 10 * 1024 * 1024,
             enable_file_logging: true,
+            max_memory_bytes: 8 * 1024 * 1024,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LogEntry {
     pub timestamp: String,
     pub level: String,
     pub message: String,
+    /// 产生该日志的代理请求 ID（请求处理过程之外的日志为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl LogEntry {
+    /// 估算日志在内存中的占用（字节）
+    fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.timestamp.len()
+            + self.level.len()
+            + self.message.len()
+            + self.request_id.as_ref().map_or(0, String::len)
+    }
+}
+
+/// 将日志以 NDJSON（每行一个 JSON 对象）格式写出，返回写出的条数
+///
+/// 每行包含 `timestamp`、`level`、`message`，请求处理过程中产生的日志额外带
+/// `request_id`，导入外部日志工具后可按请求关联。
+pub fn write_ndjson<'a, W: Write>(
+    mut writer: W,
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+) -> io::Result<usize> {
+    let mut count = 0;
+    for entry in entries {
+        serde_json::to_writer(&mut writer, entry)?;
+        writer.write_all(b"\n")?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// 单页最多返回的日志条数
+pub const MAX_LOG_PAGE_SIZE: usize = 1000;
+
+fn default_log_page_size() -> usize {
+    200
+}
+
+/// 日志查询条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogQuery {
+    /// 日志级别（为空时不过滤）
+    #[serde(default)]
+    pub levels: Vec<String>,
+    /// 只返回指定请求产生的日志
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// 搜索关键词（忽略大小写）
+    #[serde(default)]
+    pub search: Option<String>,
+    /// 是否将 `search` 作为正则表达式
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default = "default_log_page_size")]
+    pub limit: usize,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+impl Default for LogQuery {
+    fn default() -> Self {
+        Self {
+            levels: Vec::new(),
+            request_id: None,
+            search: None,
+            regex: false,
+            limit: default_log_page_size(),
+            offset: 0,
+        }
+    }
+}
+
+/// 日志分页查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPage {
+    /// 符合条件的总条数
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+    /// 当前页日志（按时间倒序）
+    pub entries: Vec<LogEntry>,
+}
+
+/// 内存日志统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogStats {
+    /// 当前保留的日志条数
+    pub total: usize,
+    /// 当前内存占用（字节）
+    pub memory_bytes: usize,
+    /// 内存占用上限（字节）
+    pub max_memory_bytes: usize,
+    /// 各级别保留的日志条数
+    pub by_level: BTreeMap<String, usize>,
+    /// 各级别因容量或内存上限被丢弃的日志条数
+    pub dropped: BTreeMap<String, u64>,
+}
+
+/// 按级别分开保存的内存日志环形缓冲
+///
+/// 每个级别单独限制条数，避免大量 debug 日志挤掉 error 日志；
+/// 总内存超过上限时从所有级别中丢弃最旧的日志，并按级别累计丢弃数。
+#[derive(Debug, Clone)]
+pub struct LogBuffer {
+    /// 级别 -> (序号, 日志)，序号单调递增，用于跨级别恢复时间顺序
+    levels: BTreeMap<String, VecDeque<(u64, LogEntry)>>,
+    next_seq: u64,
+    max_per_level: usize,
+    max_memory_bytes: usize,
+    memory_bytes: usize,
+    dropped: BTreeMap<String, u64>,
+}
+
+impl LogBuffer {
+    pub fn new(max_per_level: usize, max_memory_bytes: usize) -> Self {
+        Self {
+            levels: BTreeMap::new(),
+            next_seq: 0,
+            max_per_level: max_per_level.max(1),
+            max_memory_bytes,
+            memory_bytes: 0,
+            dropped: BTreeMap::new(),
+        }
+    }
+
+    /// 追加日志，超出条数或内存上限时丢弃最旧的日志
+    pub fn push(&mut self, entry: LogEntry) {
+        let level = entry.level.to_lowercase();
+        self.memory_bytes += entry.memory_size();
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        let buffer = self.levels.entry(level.clone()).or_default();
+        buffer.push_back((seq, entry));
+        if buffer.len() > self.max_per_level {
+            if let Some((_, old)) = buffer.pop_front() {
+                self.memory_bytes -= old.memory_size();
+                *self.dropped.entry(level).or_default() += 1;
+            }
+        }
+
+        while self.memory_bytes > self.max_memory_bytes && self.len() > 1 {
+            self.drop_oldest();
+        }
+    }
+
+    /// 当前保留的日志条数
+    pub fn len(&self) -> usize {
+        self.levels.values().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.values().all(VecDeque::is_empty)
+    }
+
+    /// 所有日志（按时间正序）
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.ordered().into_iter().cloned().collect()
+    }
+
+    /// 按级别和关键词过滤并分页，结果按时间倒序
+    ///
+    /// 正则表达式无效时返回错误
+    pub fn query(&self, query: &LogQuery) -> Result<LogPage, String> {
+        let mut matched = self.matching(query)?;
+        matched.reverse();
+
+        let limit = query.limit.min(MAX_LOG_PAGE_SIZE);
+        Ok(LogPage {
+            total: matched.len(),
+            limit,
+            offset: query.offset,
+            entries: matched
+                .into_iter()
+                .skip(query.offset)
+                .take(limit)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// 符合过滤条件的全部日志（按时间正序，忽略分页参数）
+    pub fn matching(&self, query: &LogQuery) -> Result<Vec<&LogEntry>, String> {
+        let levels: Vec<String> = query.levels.iter().map(|l| l.to_lowercase()).collect();
+        let search = query.search.as_deref().filter(|s| !s.is_empty());
+        let matcher = match search {
+            Some(pattern) if query.regex => Some(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| format!("无效的正则表达式: {}", e))?,
+            ),
+            _ => None,
+        };
+        let needle = search.map(str::to_lowercase);
+
+        Ok(self
+            .ordered()
+            .into_iter()
+            .filter(|e| levels.is_empty() || levels.contains(&e.level.to_lowercase()))
+            .filter(|e| {
+                query.request_id.is_none() || e.request_id.as_deref() == query.request_id.as_deref()
+            })
+            .filter(|e| match (&matcher, &needle) {
+                (Some(re), _) => re.is_match(&e.message),
+                (None, Some(needle)) => e.message.to_lowercase().contains(needle),
+                (None, None) => true,
+            })
+            .collect())
+    }
+
+    /// 统计信息
+    pub fn stats(&self) -> LogStats {
+        LogStats {
+            total: self.len(),
+            memory_bytes: self.memory_bytes,
+            max_memory_bytes: self.max_memory_bytes,
+            by_level: self
+                .levels
+                .iter()
+                .filter(|(_, buffer)| !buffer.is_empty())
+                .map(|(level, buffer)| (level.clone(), buffer.len()))
+                .collect(),
+            dropped: self.dropped.clone(),
+        }
+    }
+
+    /// 清空日志（丢弃计数保留）
+    pub fn clear(&mut self) {
+        self.levels.clear();
+        self.memory_bytes = 0;
+    }
+
+    fn ordered(&self) -> Vec<&LogEntry> {
+        let mut entries: Vec<&(u64, LogEntry)> = self.levels.values().flatten().collect();
+        entries.sort_unstable_by_key(|(seq, _)| *seq);
+        entries.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// 丢弃所有级别中最旧的一条日志
+    fn drop_oldest(&mut self) {
+        let oldest = self
+            .levels
+            .iter()
+            .filter_map(|(level, buffer)| buffer.front().map(|(seq, _)| (*seq, level.clone())))
+            .min();
+        let Some((_, level)) = oldest else {
+            return;
+        };
+        if let Some((_, old)) = self.levels.get_mut(&level).and_then(VecDeque::pop_front) {
+            self.memory_bytes -= old.memory_size();
+            *self.dropped.entry(level).or_default() += 1;
+        }
+    }
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        let config = LogStoreConfig::default();
+        Self::new(config.max_logs, config.max_memory_bytes)
+    }
 }
 
 pub struct LogStore {
-    logs: VecDeque<LogEntry>,
-    max_logs: usize,
+    logs: LogBuffer,
     config: LogStoreConfig,
     log_file_path: Option<PathBuf>,
+    /// 新日志订阅者（前端实时日志视图）
+    subscribers: Vec<mpsc::Sender<LogEntry>>,
 }
 
 impl Default for LogStore {
@@ -56,10 +326,10 @@ impl Default for LogStore {
         let config = LogStoreConfig::default();
 
         Self {
-            logs: VecDeque::new(),
-            max_logs: config.max_logs,
+            logs: LogBuffer::new(config.max_logs, config.max_memory_bytes),
             config,
             log_file_path: Some(log_file),
+            subscribers: Vec::new(),
         }
     }
 }
@@ -74,7 +344,6 @@ impl LogStore {
         let mut store = Self::default();
         store.config.retention_days = retention_days;
         store.config.enable_file_logging = enabled;
-        store.max_logs = store.config.max_logs;
         store
     }
 
@@ -85,9 +354,13 @@ impl LogStore {
             timestamp: now.to_rfc3339(),
             level: level.to_string(),
             message: sanitized.clone(),
+            request_id: None,
         };
 
-        self.logs.push_back(entry.clone());
+        self.logs.push(entry.clone());
+        // 断开的订阅者在发送失败时移除
+        self.subscribers
+            .retain(|subscriber| subscriber.send(entry.clone()).is_ok());
 
         // 写入日志文件
         if self.config.enable_file_logging {
@@ -102,11 +375,6 @@ impl LogStore {
                 self.prune_old_logs(path);
             }
         }
-
-        // 保持日志数量在限制内
-        if self.logs.len() > self.max_logs {
-            self.logs.pop_front();
-        }
     }
 
     /// 记录原始响应到单独的文件（用于调试）
@@ -127,8 +395,32 @@ impl LogStore {
         }
     }
 
+    /// 订阅新日志
+    pub fn subscribe(&mut self) -> mpsc::Receiver<LogEntry> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
     pub fn get_logs(&self) -> Vec<LogEntry> {
-        self.logs.iter().cloned().collect()
+        self.logs.entries()
+    }
+
+    /// 按级别、关键词过滤并分页查询
+    pub fn query(&self, query: &LogQuery) -> Result<LogPage, String> {
+        self.logs.query(query)
+    }
+
+    /// 内存日志统计
+    pub fn stats(&self) -> LogStats {
+        self.logs.stats()
+    }
+
+    /// 将符合条件的日志以 NDJSON 格式导出到文件，返回导出的条数
+    pub fn export_ndjson(&self, path: &std::path::Path, query: &LogQuery) -> Result<usize, String> {
+        let entries = self.logs.matching(query)?;
+        let file = fs::File::create(path).map_err(|e| e.to_string())?;
+        write_ndjson(io::BufWriter::new(file), entries).map_err(|e| e.to_string())
     }
 
     pub fn clear(&mut self) {
@@ -217,7 +509,135 @@ pub fn sanitize_log_message(message: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::sanitize_log_message;
+    use super::{sanitize_log_message, write_ndjson, LogBuffer, LogEntry, LogQuery};
+
+    fn entry(level: &str, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: "2026-10-18T00:00:00Z".to_string(),
+            level: level.to_string(),
+            message: message.to_string(),
+            request_id: None,
+        }
+    }
+
+    fn request_entry(request_id: &str, message: &str) -> LogEntry {
+        LogEntry {
+            request_id: Some(request_id.to_string()),
+            ..entry("info", message)
+        }
+    }
+
+    #[test]
+    fn test_write_ndjson_with_request_correlation() {
+        let mut buffer = LogBuffer::new(100, usize::MAX);
+        buffer.push(request_entry("req-1", "收到请求"));
+        buffer.push(entry("info", "服务器已启动"));
+        buffer.push(request_entry("req-2", "收到请求"));
+        buffer.push(request_entry("req-1", "请求完成"));
+
+        let query = LogQuery {
+            request_id: Some("req-1".to_string()),
+            ..LogQuery::default()
+        };
+        let mut out = Vec::new();
+        let count = write_ndjson(&mut out, buffer.matching(&query).unwrap()).unwrap();
+        assert_eq!(count, 2);
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["request_id"], "req-1");
+        assert_eq!(lines[0]["message"], "收到请求");
+        assert_eq!(lines[1]["message"], "请求完成");
+
+        let mut out = Vec::new();
+        write_ndjson(&mut out, &buffer.entries()).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        // 请求之外的日志不输出 request_id 字段
+        assert!(text.lines().nth(1).unwrap().find("request_id").is_none());
+    }
+
+    #[test]
+    fn test_log_buffer_per_level_capacity() {
+        let mut buffer = LogBuffer::new(2, usize::MAX);
+        buffer.push(entry("error", "e1"));
+        for i in 0..5 {
+            buffer.push(entry("debug", &format!("d{i}")));
+        }
+
+        // debug 日志不会挤掉 error 日志
+        let messages: Vec<String> = buffer.entries().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, vec!["e1", "d3", "d4"]);
+        let stats = buffer.stats();
+        assert_eq!(stats.by_level["error"], 1);
+        assert_eq!(stats.dropped["debug"], 3);
+    }
+
+    #[test]
+    fn test_log_buffer_memory_cap_drops_oldest() {
+        let size = std::mem::size_of::<LogEntry>() + "2026-10-18T00:00:00Z".len() + 4 + 2;
+        let mut buffer = LogBuffer::new(100, size * 2);
+        buffer.push(entry("info", "i1"));
+        buffer.push(entry("warn", "w1"));
+        buffer.push(entry("info", "i2"));
+
+        let messages: Vec<String> = buffer.entries().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, vec!["w1", "i2"]);
+        let stats = buffer.stats();
+        assert!(stats.memory_bytes <= stats.max_memory_bytes);
+        assert_eq!(stats.dropped["info"], 1);
+    }
+
+    #[test]
+    fn test_log_buffer_query() {
+        let mut buffer = LogBuffer::new(100, usize::MAX);
+        buffer.push(entry("info", "request ok"));
+        buffer.push(entry("error", "Upstream timeout after 30s"));
+        buffer.push(entry("warn", "retrying request"));
+        buffer.push(entry("error", "invalid api key"));
+
+        let page = buffer
+            .query(&LogQuery {
+                levels: vec!["ERROR".to_string()],
+                ..LogQuery::default()
+            })
+            .unwrap();
+        assert_eq!(page.total, 2);
+        // 按时间倒序
+        assert_eq!(page.entries[0].message, "invalid api key");
+
+        let page = buffer
+            .query(&LogQuery {
+                search: Some("REQUEST".to_string()),
+                limit: 1,
+                offset: 1,
+                ..LogQuery::default()
+            })
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].message, "request ok");
+
+        let page = buffer
+            .query(&LogQuery {
+                search: Some(r"timeout after \d+s".to_string()),
+                regex: true,
+                ..LogQuery::default()
+            })
+            .unwrap();
+        assert_eq!(page.total, 1);
+
+        assert!(buffer
+            .query(&LogQuery {
+                search: Some("(".to_string()),
+                regex: true,
+                ..LogQuery::default()
+            })
+            .is_err());
+    }
 
     #[test]
     fn test_sanitize_bearer_token() {
@@ -64,6 +64,18 @@ pub enum CredentialData {
         #[serde(default)]
         model_aliases: std::collections::HashMap<String, String>,
     },
+    /// Vertex AI 服务账号凭证（服务账号 JSON 文件路径）
+    ///
+    /// 通过 JWT 换取 access token，调用 Vertex AI 的 OpenAI 兼容端点
+    VertexServiceAccount {
+        creds_file_path: String,
+        /// GCP 项目 ID（默认使用服务账号所属项目）
+        #[serde(default)]
+        project_id: Option<String>,
+        /// 区域（默认 `us-central1`）
+        #[serde(default)]
+        region: Option<String>,
+    },
     /// Gemini API Key 凭证（多账号负载均衡）
     GeminiApiKey {
         api_key: String,
@@ -81,12 +93,67 @@ pub enum CredentialData {
     },
     /// Claude OAuth 凭证（Anthropic OAuth）
     ClaudeOAuth { creds_file_path: String },
+    /// Qwen OAuth 凭证（文件路径，qwen-code CLI 格式）
+    QwenOAuth { creds_file_path: String },
 
     /// Anthropic API Key 凭证（直接使用 Anthropic API）
     AnthropicKey {
         api_key: String,
         base_url: Option<String>,
     },
+    /// Azure OpenAI 凭证
+    ///
+    /// 请求按模型名映射到部署：`model_deployments`（支持通配符）> 默认 `deployment`
+    AzureOpenaiKey {
+        api_key: String,
+        /// 资源名（`{resource}.openai.azure.com`）或完整 endpoint
+        resource: String,
+        /// 默认部署名（模型未命中映射时使用）
+        #[serde(default)]
+        deployment: Option<String>,
+        /// API 版本（默认使用 `AZURE_OPENAI_DEFAULT_API_VERSION`）
+        #[serde(default)]
+        api_version: Option<String>,
+        /// 模型名 -> 部署名
+        #[serde(default)]
+        model_deployments: HashMap<String, String>,
+    },
+    /// AWS Bedrock 凭证
+    ///
+    /// 访问密钥来源优先级：显式密钥 > profile（`~/.aws/credentials`）> 环境变量 > default profile；
+    /// 配置 `role_arn` 时再通过 STS AssumeRole 换取临时凭证
+    AwsBedrock {
+        region: String,
+        #[serde(default)]
+        profile: Option<String>,
+        #[serde(default)]
+        role_arn: Option<String>,
+        #[serde(default)]
+        access_key_id: Option<String>,
+        #[serde(default)]
+        secret_access_key: Option<String>,
+    },
+    /// 本地 OpenAI 兼容服务（Ollama / LM Studio / llama.cpp），无需 API Key
+    Ollama {
+        /// 服务地址（默认 `http://localhost:11434`）
+        #[serde(default)]
+        base_url: Option<String>,
+    },
+    /// Mock 凭证，不访问上游，按模板返回响应（用于离线开发和测试）
+    Mock {
+        /// 响应模板，支持 `{{model}}`、`{{last_user_message}}`、`{{message_count}}` 占位符
+        #[serde(default)]
+        response: Option<String>,
+        /// 人为延迟（毫秒）
+        #[serde(default)]
+        latency_ms: Option<u64>,
+        /// 注入错误的概率（0.0 ~ 1.0）
+        #[serde(default)]
+        error_rate: Option<f64>,
+        /// 注入错误时返回的状态码（默认 500）
+        #[serde(default)]
+        error_status: Option<u16>,
+    },
 }
 
 impl CredentialData {
@@ -116,6 +183,17 @@ impl CredentialData {
             CredentialData::VertexKey { api_key, .. } => {
                 format!("Vertex AI: {}", mask_key(api_key))
             }
+            CredentialData::VertexServiceAccount {
+                creds_file_path,
+                region,
+                ..
+            } => {
+                format!(
+                    "Vertex AI 服务账号: {} ({})",
+                    mask_path(creds_file_path),
+                    region.as_deref().unwrap_or("us-central1")
+                )
+            }
             CredentialData::GeminiApiKey { api_key, .. } => {
                 format!("Gemini API Key: {}", mask_key(api_key))
             }
@@ -127,10 +205,52 @@ impl CredentialData {
             CredentialData::ClaudeOAuth { creds_file_path } => {
                 format!("Claude OAuth: {}", mask_path(creds_file_path))
             }
+            CredentialData::QwenOAuth { creds_file_path } => {
+                format!("Qwen OAuth: {}", mask_path(creds_file_path))
+            }
 
             CredentialData::AnthropicKey { api_key, .. } => {
                 format!("Anthropic: {}", mask_key(api_key))
             }
+            CredentialData::AzureOpenaiKey {
+                api_key, resource, ..
+            } => {
+                format!("Azure OpenAI: {} ({})", resource, mask_key(api_key))
+            }
+            CredentialData::AwsBedrock {
+                region,
+                profile,
+                role_arn,
+                access_key_id,
+                ..
+            } => {
+                let source = match (access_key_id, profile) {
+                    (Some(key), _) => mask_key(key),
+                    (None, Some(profile)) => format!("profile {}", profile),
+                    (None, None) => "default".to_string(),
+                };
+                match role_arn {
+                    Some(role) => format!("AWS Bedrock: {} ({}, {})", region, source, role),
+                    None => format!("AWS Bedrock: {} ({})", region, source),
+                }
+            }
+            CredentialData::Ollama { base_url } => {
+                format!(
+                    "Local: {}",
+                    base_url.as_deref().unwrap_or("http://localhost:11434")
+                )
+            }
+            CredentialData::Mock {
+                latency_ms,
+                error_rate,
+                ..
+            } => {
+                format!(
+                    "Mock: {}ms, {:.0}% errors",
+                    latency_ms.unwrap_or(0),
+                    error_rate.unwrap_or(0.0) * 100.0
+                )
+            }
         }
     }
 
@@ -144,15 +264,49 @@ impl CredentialData {
             CredentialData::OpenAIKey { .. } => PoolProviderType::OpenAI,
             CredentialData::ClaudeKey { .. } => PoolProviderType::Claude,
             CredentialData::VertexKey { .. } => PoolProviderType::Vertex,
+            CredentialData::VertexServiceAccount { .. } => PoolProviderType::Vertex,
             CredentialData::GeminiApiKey { .. } => PoolProviderType::GeminiApiKey,
             CredentialData::CodexOAuth { .. } => PoolProviderType::Codex,
             CredentialData::ClaudeOAuth { .. } => PoolProviderType::ClaudeOAuth,
+            CredentialData::QwenOAuth { .. } => PoolProviderType::Qwen,
 
             CredentialData::AnthropicKey { .. } => PoolProviderType::Anthropic,
+            CredentialData::AzureOpenaiKey { .. } => PoolProviderType::AzureOpenai,
+            CredentialData::AwsBedrock { .. } => PoolProviderType::AwsBedrock,
+            CredentialData::Ollama { .. } => PoolProviderType::Ollama,
+            CredentialData::Mock { .. } => PoolProviderType::Mock,
         }
     }
 }
 
+/// 解析 Azure OpenAI 请求使用的部署名
+///
+/// 优先精确匹配 `model_deployments` 的键，其次按通配符键匹配（按键排序，保证结果稳定），
+/// 都未命中时使用默认部署
+pub fn resolve_azure_deployment(
+    model_deployments: &HashMap<String, String>,
+    default_deployment: Option<&str>,
+    model: &str,
+) -> Option<String> {
+    if let Some(deployment) = model_deployments.get(model) {
+        return Some(deployment.clone());
+    }
+    let mut patterns: Vec<_> = model_deployments
+        .iter()
+        .filter(|(pattern, _)| pattern.contains('*'))
+        .collect();
+    patterns.sort_by(|a, b| a.0.cmp(b.0));
+    patterns
+        .into_iter()
+        .find(|(pattern, _)| pattern_matches(pattern, model))
+        .map(|(_, deployment)| deployment.clone())
+        .or_else(|| {
+            default_deployment
+                .filter(|d| !d.is_empty())
+                .map(str::to_string)
+        })
+}
+
 /// 通配符模式匹配
 ///
 /// 支持的通配符模式：
@@ -232,12 +386,19 @@ pub struct ProviderCredential {
     pub source: CredentialSource,
     /// 代理 URL（可覆盖全局代理设置）
     pub proxy_url: Option<String>,
+    /// 负载均衡权重（加权策略使用，0 表示仅在其他凭证不可用时使用）
+    #[serde(default = "default_weight")]
+    pub weight: u32,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn default_weight() -> u32 {
+    1
+}
+
 impl ProviderCredential {
     /// 创建新凭证
     pub fn new(provider_type: PoolProviderType, credential: CredentialData) -> Self {
@@ -265,6 +426,7 @@ impl ProviderCredential {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            weight: 1,
         }
     }
 
@@ -290,6 +452,7 @@ impl ProviderCredential {
     /// 1. `not_supported_models` - 通用的不支持模型列表（精确匹配）
     /// 2. `excluded_models` - 来自 CredentialData::GeminiApiKey 的排除列表（支持通配符）
     /// 3. Antigravity 凭证只支持特定的模型列表
+    /// 4. Azure OpenAI 凭证只支持能映射到部署的模型
     pub fn supports_model(&self, model: &str) -> bool {
         // 检查通用的不支持模型列表（精确匹配）
         if self.not_supported_models.contains(&model.to_string()) {
@@ -315,6 +478,16 @@ impl ProviderCredential {
             return ANTIGRAVITY_MODELS_FALLBACK.contains(&model);
         }
 
+        if let CredentialData::AzureOpenaiKey {
+            deployment,
+            model_deployments,
+            ..
+        } = &self.credential
+        {
+            return resolve_azure_deployment(model_deployments, deployment.as_deref(), model)
+                .is_some();
+        }
+
         true
     }
 
@@ -505,11 +678,13 @@ pub fn get_default_check_model(provider_type: PoolProviderType) -> &'static str
         PoolProviderType::Vertex => "gemini-2.0-flash",
         PoolProviderType::GeminiApiKey => "gemini-2.5-flash",
         PoolProviderType::Codex => "gpt-4o-mini",
+        PoolProviderType::Qwen => "qwen3-coder-plus",
         // API Key Provider 类型
         PoolProviderType::Anthropic => "claude-sonnet-4-5-20250929",
         PoolProviderType::AzureOpenai => "gpt-4o-mini",
         PoolProviderType::AwsBedrock => "claude-sonnet-4-5-20250929",
         PoolProviderType::Ollama => "llama3.2",
+        PoolProviderType::Mock => "mock-model",
     }
 }
 
@@ -546,6 +721,8 @@ pub struct CredentialDisplay {
     pub api_key: Option<String>,
     /// 凭证级代理 URL（可覆盖全局代理设置）
     pub proxy_url: Option<String>,
+    /// 负载均衡权重
+    pub weight: u32,
 }
 
 /// 获取凭证类型字符串
@@ -557,10 +734,16 @@ fn get_credential_type(cred: &CredentialData) -> String {
         CredentialData::OpenAIKey { .. } => "openai_key".to_string(),
         CredentialData::ClaudeKey { .. } => "claude_key".to_string(),
         CredentialData::VertexKey { .. } => "vertex_key".to_string(),
+        CredentialData::VertexServiceAccount { .. } => "vertex_service_account".to_string(),
         CredentialData::GeminiApiKey { .. } => "gemini_api_key".to_string(),
         CredentialData::CodexOAuth { .. } => "codex_oauth".to_string(),
         CredentialData::ClaudeOAuth { .. } => "claude_oauth".to_string(),
+        CredentialData::QwenOAuth { .. } => "qwen_oauth".to_string(),
         CredentialData::AnthropicKey { .. } => "anthropic_key".to_string(),
+        CredentialData::AzureOpenaiKey { .. } => "azure_openai_key".to_string(),
+        CredentialData::AwsBedrock { .. } => "aws_bedrock".to_string(),
+        CredentialData::Ollama { .. } => "ollama".to_string(),
+        CredentialData::Mock { .. } => "mock".to_string(),
     }
 }
 
@@ -578,6 +761,7 @@ pub fn get_oauth_creds_path(cred: &CredentialData) -> Option<String> {
             creds_file_path, ..
         } => Some(creds_file_path.clone()),
         CredentialData::ClaudeOAuth { creds_file_path } => Some(creds_file_path.clone()),
+        CredentialData::QwenOAuth { creds_file_path } => Some(creds_file_path.clone()),
         _ => None,
     }
 }
@@ -588,6 +772,8 @@ fn get_base_url(cred: &CredentialData) -> Option<String> {
         CredentialData::OpenAIKey { base_url, .. } => base_url.clone(),
         CredentialData::ClaudeKey { base_url, .. } => base_url.clone(),
         CredentialData::AnthropicKey { base_url, .. } => base_url.clone(),
+        CredentialData::AzureOpenaiKey { resource, .. } => Some(resource.clone()),
+        CredentialData::Ollama { base_url } => base_url.clone(),
         _ => None,
     }
 }
@@ -598,6 +784,7 @@ fn get_api_key(cred: &CredentialData) -> Option<String> {
         CredentialData::OpenAIKey { api_key, .. } => Some(api_key.clone()),
         CredentialData::ClaudeKey { api_key, .. } => Some(api_key.clone()),
         CredentialData::AnthropicKey { api_key, .. } => Some(api_key.clone()),
+        CredentialData::AzureOpenaiKey { api_key, .. } => Some(api_key.clone()),
         _ => None,
     }
 }
@@ -642,6 +829,7 @@ impl From<&ProviderCredential> for CredentialDisplay {
             base_url: get_base_url(&cred.credential),
             api_key: get_api_key(&cred.credential),
             proxy_url: cred.proxy_url.clone(),
+            weight: cred.weight,
         }
     }
 }
@@ -701,6 +889,9 @@ pub struct UpdateCredentialRequest {
     pub new_api_key: Option<String>,
     /// 新的代理 URL（可覆盖全局代理设置）
     pub new_proxy_url: Option<String>,
+    /// 负载均衡权重
+    #[serde(default)]
+    pub weight: Option<u32>,
 }
 
 pub type ProviderPools = HashMap<PoolProviderType, Vec<ProviderCredential>>;
@@ -770,6 +961,7 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            weight: 1,
         };
 
         assert!(!cred.supports_model("claude-opus"));
@@ -805,6 +997,7 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            weight: 1,
         };
 
         // Exact match exclusion
@@ -842,6 +1035,7 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            weight: 1,
         };
 
         // Prefix wildcard exclusion
@@ -883,6 +1077,7 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            weight: 1,
         };
 
         // Contains wildcard exclusion
@@ -921,6 +1116,7 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            weight: 1,
         };
 
         // Excluded by not_supported_models (exact match)
@@ -960,6 +1156,7 @@ mod tests {
             cached_token: None,
             source: CredentialSource::Manual,
             proxy_url: None,
+            weight: 1,
         };
 
         // All models should be supported since not_supported_models is empty
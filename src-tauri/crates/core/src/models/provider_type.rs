@@ -23,6 +23,8 @@ pub enum ProviderType {
     #[serde(rename = "gemini_api_key")]
     GeminiApiKey,
     Codex,
+    /// Qwen Code OAuth（通义千问）
+    Qwen,
     // API Key Provider 类型
     Anthropic,
     #[serde(rename = "azure_openai")]
@@ -30,6 +32,8 @@ pub enum ProviderType {
     #[serde(rename = "aws_bedrock")]
     AwsBedrock,
     Ollama,
+    /// 内置 Mock（离线开发和测试）
+    Mock,
 }
 
 impl std::fmt::Display for ProviderType {
@@ -45,10 +49,12 @@ impl std::fmt::Display for ProviderType {
             ProviderType::Vertex => write!(f, "vertex"),
             ProviderType::GeminiApiKey => write!(f, "gemini_api_key"),
             ProviderType::Codex => write!(f, "codex"),
+            ProviderType::Qwen => write!(f, "qwen"),
             ProviderType::Anthropic => write!(f, "anthropic"),
             ProviderType::AzureOpenai => write!(f, "azure_openai"),
             ProviderType::AwsBedrock => write!(f, "aws_bedrock"),
             ProviderType::Ollama => write!(f, "ollama"),
+            ProviderType::Mock => write!(f, "mock"),
         }
     }
 }
@@ -70,13 +76,15 @@ impl std::str::FromStr for ProviderType {
             "vertex" => Ok(ProviderType::Vertex),
             "gemini_api_key" => Ok(ProviderType::GeminiApiKey),
             "codex" => Ok(ProviderType::Codex),
+            "qwen" => Ok(ProviderType::Qwen),
             "anthropic" => Ok(ProviderType::Anthropic),
             "azure_openai" | "azure-openai" => Ok(ProviderType::AzureOpenai),
             "aws_bedrock" | "aws-bedrock" => Ok(ProviderType::AwsBedrock),
             "ollama" => Ok(ProviderType::Ollama),
+            "mock" => Ok(ProviderType::Mock),
             // OpenAI 兼容的第三方 Provider 映射到 OpenAI
             "deepseek" | "deep_seek" | "deep-seek" => Ok(ProviderType::OpenAI),
-            "qwen" | "tongyi" | "dashscope" => Ok(ProviderType::OpenAI),
+            "tongyi" | "dashscope" => Ok(ProviderType::OpenAI),
             "zhipu" | "glm" | "chatglm" => Ok(ProviderType::OpenAI),
             "moonshot" | "kimi" => Ok(ProviderType::OpenAI),
             "baichuan" => Ok(ProviderType::OpenAI),
@@ -151,6 +159,11 @@ mod tests {
             "VERTEX".parse::<ProviderType>().unwrap(),
             ProviderType::Vertex
         );
+        assert_eq!("qwen".parse::<ProviderType>().unwrap(), ProviderType::Qwen);
+        assert_eq!(
+            "dashscope".parse::<ProviderType>().unwrap(),
+            ProviderType::OpenAI
+        );
         assert!("invalid".parse::<ProviderType>().is_err());
     }
 
@@ -162,6 +175,7 @@ mod tests {
         assert_eq!(ProviderType::Claude.to_string(), "claude");
         assert_eq!(ProviderType::Vertex.to_string(), "vertex");
         assert_eq!(ProviderType::GeminiApiKey.to_string(), "gemini_api_key");
+        assert_eq!(ProviderType::Qwen.to_string(), "qwen");
     }
 
     #[test]
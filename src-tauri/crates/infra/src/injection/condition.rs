@@ -0,0 +1,349 @@
+//! 注入条件表达式
+//!
+//! 类 JSONPath 的简单谓词，针对请求体求值，例如：
+//! - `$.tools`：字段为真值（与 JMESPath 一致，缺失、null、false、空字符串、空数组、空对象为假）
+//! - `!$.temperature`：字段缺失或为假值
+//! - `$.messages[0].role == "system"`
+//! - `$.max_tokens > 4096 && !$.stream`
+//!
+//! 支持 `&&`、`||`、`!` 和括号，比较运算符为 `==`、`!=`、`>`、`>=`、`<`、`<=`，
+//! 字面量使用 JSON 语法（数字、字符串、`true` / `false` / `null`）。路径的 `$.` 前缀可省略。
+
+use serde_json::Value;
+
+/// 比较运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// 路径片段
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// 表达式
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Truthy(Vec<Segment>),
+    Compare(Vec<Segment>, CompareOp, Value),
+}
+
+/// 词法单元
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Not,
+    And,
+    Or,
+    Op(CompareOp),
+    Path(Vec<Segment>),
+    Literal(Value),
+}
+
+/// 已解析的条件表达式
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    expr: Expr,
+}
+
+impl Condition {
+    /// 解析条件表达式
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err("条件表达式为空".to_string());
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(format!(
+                "条件表达式 '{}' 第 {} 个词法单元后存在多余内容",
+                input, parser.pos
+            ));
+        }
+        Ok(Self { expr })
+    }
+
+    /// 针对请求体求值
+    pub fn evaluate(&self, payload: &Value) -> bool {
+        evaluate(&self.expr, payload)
+    }
+}
+
+fn evaluate(expr: &Expr, payload: &Value) -> bool {
+    match expr {
+        Expr::Or(exprs) => exprs.iter().any(|e| evaluate(e, payload)),
+        Expr::And(exprs) => exprs.iter().all(|e| evaluate(e, payload)),
+        Expr::Not(inner) => !evaluate(inner, payload),
+        Expr::Truthy(path) => resolve(payload, path).is_some_and(is_truthy),
+        Expr::Compare(path, op, literal) => {
+            let value = resolve(payload, path).unwrap_or(&Value::Null);
+            compare(value, *op, literal)
+        }
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+        Value::Number(_) => true,
+    }
+}
+
+fn resolve<'a>(payload: &'a Value, path: &[Segment]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(payload, |current, segment| match segment {
+            Segment::Key(key) => current.get(key),
+            Segment::Index(index) => current.get(index),
+        })
+}
+
+fn compare(value: &Value, op: CompareOp, literal: &Value) -> bool {
+    let ordering = match (value, literal) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        },
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    match op {
+        CompareOp::Eq => ordering.map_or(value == literal, |o| o.is_eq()),
+        CompareOp::Ne => ordering.map_or(value != literal, |o| o.is_ne()),
+        CompareOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+        CompareOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+        CompareOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+        CompareOp::Le => ordering.is_some_and(|o| o.is_le()),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            exprs.push(self.parse_and()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            Expr::Or(exprs)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut exprs = vec![self.parse_unary()?];
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            exprs.push(self.parse_unary()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            Expr::And(exprs)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err("条件表达式缺少右括号".to_string()),
+                }
+            }
+            Some(Token::Path(path)) => {
+                let Some(Token::Op(op)) = self.peek().cloned() else {
+                    return Ok(Expr::Truthy(path));
+                };
+                self.pos += 1;
+                match self.next() {
+                    Some(Token::Literal(literal)) => Ok(Expr::Compare(path, op, literal)),
+                    _ => Err("比较运算符右侧必须是字面量".to_string()),
+                }
+            }
+            Some(token) => Err(format!("条件表达式中出现意外的 {:?}", token)),
+            None => Err("条件表达式意外结束".to_string()),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '&' if next == Some('&') => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            '!' if next == Some('=') => {
+                tokens.push(Token::Op(CompareOp::Ne));
+                i += 2;
+            }
+            '!' => {
+                tokens.push(Token::Not);
+                i += 1;
+            }
+            '=' if next == Some('=') => {
+                tokens.push(Token::Op(CompareOp::Eq));
+                i += 2;
+            }
+            '>' | '<' => {
+                let inclusive = next == Some('=');
+                let op = match (c, inclusive) {
+                    ('>', true) => CompareOp::Ge,
+                    ('>', false) => CompareOp::Gt,
+                    (_, true) => CompareOp::Le,
+                    (_, false) => CompareOp::Lt,
+                };
+                tokens.push(Token::Op(op));
+                i += if inclusive { 2 } else { 1 };
+            }
+            '"' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err("字符串字面量缺少结束引号".to_string());
+                }
+                i += 1;
+                let raw: String = chars[start..i].iter().collect();
+                let literal = serde_json::from_str(&raw)
+                    .map_err(|e| format!("无效的字符串字面量 {}: {}", raw, e))?;
+                tokens.push(Token::Literal(literal));
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let start = i;
+                i += 1;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '.' | '+' | '-'))
+                {
+                    i += 1;
+                }
+                let raw: String = chars[start..i].iter().collect();
+                let literal = raw
+                    .parse::<serde_json::Number>()
+                    .map_err(|_| format!("无效的数字字面量 {}", raw))?;
+                tokens.push(Token::Literal(Value::Number(literal)));
+            }
+            c if c == '$' || is_ident_char(c) => {
+                let start = i;
+                while i < chars.len() && (is_ident_char(chars[i]) || "$.[]".contains(chars[i])) {
+                    i += 1;
+                }
+                let raw: String = chars[start..i].iter().collect();
+                tokens.push(match raw.as_str() {
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    "null" => Token::Literal(Value::Null),
+                    _ => Token::Path(parse_path(&raw)?),
+                });
+            }
+            _ => return Err(format!("条件表达式中出现无效字符 '{}'", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// 解析 `$.a.b[0]` 形式的路径（`$.` 前缀可省略）
+fn parse_path(raw: &str) -> Result<Vec<Segment>, String> {
+    let rest = raw
+        .strip_prefix("$.")
+        .or_else(|| raw.strip_prefix('$'))
+        .unwrap_or(raw);
+    let mut segments = Vec::new();
+
+    for part in rest.split('.') {
+        let (key, indexes) = match part.find('[') {
+            Some(pos) => (&part[..pos], &part[pos..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            segments.push(Segment::Key(key.to_string()));
+        } else if indexes.is_empty() && !rest.is_empty() {
+            return Err(format!("无效的路径 {}", raw));
+        }
+
+        let mut indexes = indexes;
+        while let Some(stripped) = indexes.strip_prefix('[') {
+            let end = stripped
+                .find(']')
+                .ok_or_else(|| format!("路径 {} 缺少 ']'", raw))?;
+            let index = stripped[..end]
+                .parse::<usize>()
+                .map_err(|_| format!("路径 {} 的下标无效", raw))?;
+            segments.push(Segment::Index(index));
+            indexes = &stripped[end + 1..];
+        }
+        if !indexes.is_empty() {
+            return Err(format!("无效的路径 {}", raw));
+        }
+    }
+
+    if segments.is_empty() {
+        return Err(format!("路径 {} 未指定字段", raw));
+    }
+    Ok(segments)
+}
@@ -0,0 +1,24 @@
+//! 上游请求头注入作用域
+//!
+//! Provider 调用在作用域内执行，各 Provider 发送模型请求前通过
+//! `injected_headers()` 取出匹配规则计算出的请求头
+
+use reqwest::header::HeaderMap;
+use std::future::Future;
+
+tokio::task_local! {
+    /// 当前 Provider 调用需要注入的请求头
+    static INJECTED_HEADERS: HeaderMap;
+}
+
+/// 在指定注入请求头的作用域内执行
+pub async fn with_injected_headers<F: Future>(headers: HeaderMap, fut: F) -> F::Output {
+    INJECTED_HEADERS.scope(headers, fut).await
+}
+
+/// 当前作用域内需要注入的请求头（不在作用域内时为空）
+pub fn injected_headers() -> HeaderMap {
+    INJECTED_HEADERS
+        .try_with(|headers| headers.clone())
+        .unwrap_or_default()
+}
@@ -4,10 +4,22 @@
 //! - 模型通配符匹配规则
 //! - merge 和 override 两种注入模式
 //! - 规则优先级排序
+//! - 按模型 / Provider 注入上游请求头
+//! - 按模型 / Provider 前置或追加 system prompt
+//! - 基于请求体的条件表达式（如仅在存在 `tools` 时注入）
 
+mod condition;
+mod headers;
 mod types;
 
-pub use types::{InjectionConfig, InjectionMode, InjectionResult, InjectionRule, Injector};
+pub use condition::Condition;
+pub use headers::{injected_headers, with_injected_headers};
+
+pub(crate) use types::pattern_matches;
+pub use types::{
+    validate_conditions, HeaderInjectionResult, InjectionConfig, InjectionMode, InjectionResult,
+    InjectionRule, Injector, SystemPromptInjection, SystemPromptPosition,
+};
 
 #[cfg(test)]
 mod tests;
@@ -224,3 +224,270 @@ mod injector_tests {
         assert!(matches.iter().any(|r| r.id == "r3"));
     }
 }
+
+#[cfg(test)]
+mod header_tests {
+    use super::*;
+
+    #[test]
+    fn test_inject_headers_per_provider() {
+        let mut injector = Injector::new();
+        injector.add_rule(
+            InjectionRule::new("beta", "claude-*", json!({}))
+                .with_headers(&[("anthropic-beta", "context-1m-2025-08-07")])
+                .with_providers(&["claude"]),
+        );
+        injector.add_rule(
+            InjectionRule::new("org", "*", json!({})).with_headers(&[("X-Org-Id", "org-1")]),
+        );
+
+        let result = injector.inject_headers("claude-sonnet-4-5", "Claude", &json!({}));
+        assert_eq!(result.applied_rules, vec!["beta", "org"]);
+        assert_eq!(result.headers["anthropic-beta"], "context-1m-2025-08-07");
+        assert_eq!(result.headers["x-org-id"], "org-1");
+
+        let result = injector.inject_headers("claude-sonnet-4-5", "kiro", &json!({}));
+        assert_eq!(result.applied_rules, vec!["org"]);
+        assert!(!result.headers.contains_key("anthropic-beta"));
+    }
+
+    #[test]
+    fn test_inject_headers_merge_and_override() {
+        let mut injector = Injector::new();
+        injector.add_rule(
+            InjectionRule::new("r1", "*", json!({}))
+                .with_headers(&[("x-tier", "gold")])
+                .with_priority(10),
+        );
+        injector.add_rule(
+            InjectionRule::new("r2", "*", json!({}))
+                .with_headers(&[("x-tier", "silver")])
+                .with_priority(20),
+        );
+        assert_eq!(
+            injector
+                .inject_headers("gpt-4", "openai", &json!({}))
+                .headers["x-tier"],
+            "gold"
+        );
+
+        injector.add_rule(
+            InjectionRule::new("r3", "*", json!({}))
+                .with_headers(&[("x-tier", "bronze")])
+                .with_mode(InjectionMode::Override)
+                .with_priority(30),
+        );
+        assert_eq!(
+            injector
+                .inject_headers("gpt-4", "openai", &json!({}))
+                .headers["x-tier"],
+            "bronze"
+        );
+    }
+
+    #[test]
+    fn test_inject_headers_blocked_and_invalid() {
+        let mut injector = Injector::new();
+        injector.add_rule(InjectionRule::new("r1", "*", json!({})).with_headers(&[
+            ("Authorization", "Bearer stolen"),
+            ("bad header", "x"),
+            ("x-ok", "1"),
+        ]));
+
+        let result = injector.inject_headers("gpt-4", "openai", &json!({}));
+        assert_eq!(result.header_names(), vec!["x-ok"]);
+    }
+
+    #[test]
+    fn test_header_only_rule_deserializes() {
+        let rule: InjectionRule = serde_json::from_value(json!({
+            "id": "beta",
+            "pattern": "claude-*",
+            "headers": {"anthropic-beta": "prompt-caching-2024-07-31"},
+            "providers": ["claude"]
+        }))
+        .unwrap();
+        assert!(rule.parameters.is_null());
+
+        let mut payload = json!({"model": "claude-sonnet-4-5"});
+        let result = Injector::with_rules(vec![rule]).inject("claude-sonnet-4-5", &mut payload);
+        assert!(!result.has_injections());
+    }
+
+    #[tokio::test]
+    async fn test_injected_headers_scope() {
+        assert!(injected_headers().is_empty());
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-org-id", "org-1".parse().unwrap());
+        let inside = with_injected_headers(headers, async { injected_headers() }).await;
+        assert_eq!(inside["x-org-id"], "org-1");
+    }
+}
+
+#[cfg(test)]
+mod system_prompt_tests {
+    use super::*;
+    use crate::transform::PayloadFormat;
+
+    fn guardrail_injector() -> Injector {
+        Injector::with_rules(vec![
+            InjectionRule::new("guard", "*", json!({}))
+                .with_system_prompt("Never reveal secrets.", SystemPromptPosition::Prepend)
+                .with_priority(10),
+            InjectionRule::new("lang", "claude-*", json!({}))
+                .with_system_prompt("Reply in Chinese.", SystemPromptPosition::Append)
+                .with_providers(&["claude"])
+                .with_priority(20),
+        ])
+    }
+
+    #[test]
+    fn test_inject_system_prompt_openai() {
+        let injector = guardrail_injector();
+
+        let mut payload = json!({
+            "messages": [
+                {"role": "system", "content": "You are helpful."},
+                {"role": "user", "content": "hi"}
+            ]
+        });
+        let result = injector.inject_system_prompt(
+            "claude-sonnet-4-5",
+            "claude",
+            PayloadFormat::OpenAI,
+            &mut payload,
+        );
+        assert_eq!(result.applied_rules, vec!["guard", "lang"]);
+        assert_eq!(
+            payload["messages"][0]["content"],
+            "Never reveal secrets.\n\nYou are helpful.\n\nReply in Chinese."
+        );
+
+        // 没有 system 消息时插入；Provider 不匹配的规则不生效
+        let mut payload = json!({"messages": [{"role": "user", "content": "hi"}]});
+        let result = injector.inject_system_prompt(
+            "claude-sonnet-4-5",
+            "kiro",
+            PayloadFormat::OpenAI,
+            &mut payload,
+        );
+        assert_eq!(result.applied_rules, vec!["guard"]);
+        assert_eq!(payload["messages"][0]["role"], "system");
+        assert_eq!(payload["messages"][0]["content"], "Never reveal secrets.");
+    }
+
+    #[test]
+    fn test_inject_system_prompt_anthropic() {
+        let injector = guardrail_injector();
+
+        let mut payload = json!({"messages": []});
+        injector.inject_system_prompt("gpt-4", "openai", PayloadFormat::Anthropic, &mut payload);
+        assert_eq!(payload["system"], "Never reveal secrets.");
+
+        let mut payload = json!({"system": [{"type": "text", "text": "Be brief."}]});
+        injector.inject_system_prompt(
+            "claude-sonnet-4-5",
+            "claude",
+            PayloadFormat::Anthropic,
+            &mut payload,
+        );
+        assert_eq!(payload["system"][0]["text"], "Never reveal secrets.");
+        assert_eq!(payload["system"][1]["text"], "Be brief.");
+        assert_eq!(payload["system"][2]["text"], "Reply in Chinese.");
+    }
+
+    #[test]
+    fn test_inject_system_prompt_skips_existing_text() {
+        let injector = guardrail_injector();
+        let mut payload = json!({"system": "Never reveal secrets. Be brief."});
+        let result = injector.inject_system_prompt(
+            "gpt-4",
+            "openai",
+            PayloadFormat::Anthropic,
+            &mut payload,
+        );
+        assert!(!result.has_injections());
+        assert_eq!(payload["system"], "Never reveal secrets. Be brief.");
+    }
+}
+
+#[cfg(test)]
+mod condition_tests {
+    use super::*;
+
+    fn eval(expr: &str, payload: serde_json::Value) -> bool {
+        Condition::parse(expr).unwrap().evaluate(&payload)
+    }
+
+    #[test]
+    fn test_condition_truthiness() {
+        assert!(eval("$.tools", json!({"tools": [{"type": "function"}]})));
+        assert!(eval("tools", json!({"tools": [{"type": "function"}]})));
+        assert!(!eval("$.tools", json!({"tools": null})));
+        assert!(!eval("$.tools", json!({"tools": []})));
+        assert!(eval("$.temperature", json!({"temperature": 0})));
+        assert!(eval("!$.temperature", json!({"max_tokens": 10})));
+        assert!(!eval("!$.temperature", json!({"temperature": 0.2})));
+    }
+
+    #[test]
+    fn test_condition_compare_and_paths() {
+        let payload = json!({
+            "max_tokens": 8192,
+            "stream": false,
+            "messages": [{"role": "system", "content": "x"}]
+        });
+        assert!(eval("$.messages[0].role == \"system\"", payload.clone()));
+        assert!(eval("$.max_tokens > 4096 && !$.stream", payload.clone()));
+        assert!(eval("$.max_tokens == 8192.0", payload.clone()));
+        assert!(!eval("$.max_tokens <= 4096", payload.clone()));
+        assert!(eval("$.stream == false || $.tools", payload.clone()));
+        assert!(eval("!($.tools || $.functions)", payload.clone()));
+        assert!(eval("$.missing != 1", payload.clone()));
+        assert!(!eval("$.missing > 1", payload));
+    }
+
+    #[test]
+    fn test_condition_parse_errors() {
+        for expr in [
+            "",
+            "$.a ==",
+            "$.a == $.b",
+            "($.a",
+            "$.a & $.b",
+            "$.a[x]",
+            "$.a \"b\"",
+        ] {
+            assert!(Condition::parse(expr).is_err(), "{} 应解析失败", expr);
+        }
+    }
+
+    #[test]
+    fn test_conditional_rules() {
+        let injector = Injector::with_rules(vec![
+            InjectionRule::new("tools", "*", json!({"temperature": 0.0}))
+                .with_condition("$.tools")
+                .with_priority(10),
+            InjectionRule::new("default-temp", "*", json!({"top_p": 0.9}))
+                .with_condition("!$.temperature")
+                .with_priority(20),
+        ]);
+
+        let mut payload = json!({"model": "gpt-4", "tools": [{"type": "function"}]});
+        let result = injector.inject("gpt-4", &mut payload);
+        // 条件针对注入前的请求体求值
+        assert_eq!(result.applied_rules, vec!["tools", "default-temp"]);
+
+        let mut payload = json!({"model": "gpt-4", "temperature": 1.0});
+        let result = injector.inject("gpt-4", &mut payload);
+        assert!(!result.has_injections());
+
+        let invalid = vec![InjectionRule::new("bad", "*", json!({})).with_condition("$.a ==")];
+        assert!(validate_conditions(&invalid).is_err());
+        let mut payload = json!({"a": 1});
+        assert!(!Injector::with_rules(invalid)
+            .inject("gpt-4", &mut payload)
+            .has_injections());
+    }
+}
@@ -2,7 +2,13 @@
 //!
 //! 定义注入规则、注入模式和注入器
 
+use super::condition::Condition;
+use crate::transform::{
+    rewrite_system_prompt, system_prompt_contains, PayloadFormat, SystemPromptMode,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// 允许注入的参数白名单
 /// 这些参数是安全的，不会影响请求的核心行为
@@ -28,6 +34,19 @@ const BLOCKED_OVERRIDE_PARAMS: &[&str] = &[
     "response_format",
 ];
 
+/// 禁止注入的请求头（认证和传输相关，由 Provider 自行设置）
+const BLOCKED_INJECTION_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "cookie",
+    "host",
+    "content-length",
+    "content-type",
+    "transfer-encoding",
+];
+
 /// 注入模式
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -39,6 +58,27 @@ pub enum InjectionMode {
     Override,
 }
 
+/// system prompt 注入位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SystemPromptPosition {
+    /// 添加到原有 system prompt 之前
+    #[default]
+    Prepend,
+    /// 添加到原有 system prompt 之后
+    Append,
+}
+
+/// system prompt 注入
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SystemPromptInjection {
+    /// 注入的文本
+    pub text: String,
+    /// 注入位置
+    #[serde(default)]
+    pub position: SystemPromptPosition,
+}
+
 /// 注入规则
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InjectionRule {
@@ -47,7 +87,20 @@ pub struct InjectionRule {
     /// 模型匹配模式（支持通配符）
     pub pattern: String,
     /// 要注入的参数
+    #[serde(default)]
     pub parameters: serde_json::Value,
+    /// 要注入到上游请求的 HTTP 头
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// 要注入的 system prompt（不存在 system prompt 时插入）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<SystemPromptInjection>,
+    /// 请求头和 system prompt 注入限定的 Provider（为空时对所有 Provider 生效）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<String>,
+    /// 条件表达式（针对请求体求值，为空时只按模型匹配）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
     /// 注入模式
     #[serde(default)]
     pub mode: InjectionMode,
@@ -74,6 +127,10 @@ impl InjectionRule {
             id: id.to_string(),
             pattern: pattern.to_string(),
             parameters,
+            headers: BTreeMap::new(),
+            system_prompt: None,
+            providers: Vec::new(),
+            condition: None,
             mode: InjectionMode::Merge,
             priority: default_priority(),
             enabled: true,
@@ -92,6 +149,59 @@ impl InjectionRule {
         self
     }
 
+    /// 设置要注入的请求头
+    pub fn with_headers(mut self, headers: &[(&str, &str)]) -> Self {
+        self.headers = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self
+    }
+
+    /// 设置要注入的 system prompt
+    pub fn with_system_prompt(mut self, text: &str, position: SystemPromptPosition) -> Self {
+        self.system_prompt = Some(SystemPromptInjection {
+            text: text.to_string(),
+            position,
+        });
+        self
+    }
+
+    /// 限定请求头和 system prompt 注入的 Provider
+    pub fn with_providers(mut self, providers: &[&str]) -> Self {
+        self.providers = providers.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// 设置条件表达式
+    pub fn with_condition(mut self, condition: &str) -> Self {
+        self.condition = Some(condition.to_string());
+        self
+    }
+
+    /// 检查请求体是否满足条件（无条件时总是满足，表达式无效时不满足）
+    pub fn condition_matches(&self, payload: &serde_json::Value) -> bool {
+        let Some(condition) = &self.condition else {
+            return true;
+        };
+        match Condition::parse(condition) {
+            Ok(condition) => condition.evaluate(payload),
+            Err(e) => {
+                tracing::warn!("[INJECTION] 规则 {} 的条件无效: {}", self.id, e);
+                false
+            }
+        }
+    }
+
+    /// 检查 Provider 是否匹配此规则
+    pub fn matches_provider(&self, provider: &str) -> bool {
+        self.providers.is_empty()
+            || self
+                .providers
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(provider))
+    }
+
     /// 检查模型是否匹配此规则
     ///
     /// 支持的通配符模式：
@@ -153,6 +263,27 @@ impl InjectionResult {
     }
 }
 
+/// 请求头注入结果
+#[derive(Debug, Clone, Default)]
+pub struct HeaderInjectionResult {
+    /// 应用的规则 ID 列表
+    pub applied_rules: Vec<String>,
+    /// 注入的请求头
+    pub headers: HeaderMap,
+}
+
+impl HeaderInjectionResult {
+    /// 检查是否有注入
+    pub fn has_injections(&self) -> bool {
+        !self.headers.is_empty()
+    }
+
+    /// 注入的请求头名称列表
+    pub fn header_names(&self) -> Vec<String> {
+        self.headers.keys().map(|k| k.to_string()).collect()
+    }
+}
+
 /// 注入配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct InjectionConfig {
@@ -213,6 +344,22 @@ impl Injector {
         self.rules.clear();
     }
 
+    /// 获取适用于本次请求的规则（模型、Provider 和条件均匹配）
+    ///
+    /// 条件针对注入前的请求体求值
+    fn applicable_rules(
+        &self,
+        model: &str,
+        provider: Option<&str>,
+        payload: &serde_json::Value,
+    ) -> Vec<&InjectionRule> {
+        self.matching_rules(model)
+            .into_iter()
+            .filter(|r| provider.is_none_or(|p| r.matches_provider(p)))
+            .filter(|r| r.condition_matches(payload))
+            .collect()
+    }
+
     /// 注入参数到请求
     ///
     /// 按规则优先级顺序应用注入：
@@ -220,6 +367,7 @@ impl Injector {
     /// - Override 模式：覆盖已有参数
     pub fn inject(&self, model: &str, payload: &mut serde_json::Value) -> InjectionResult {
         let mut result = InjectionResult::new();
+        let rules = self.applicable_rules(model, None, payload);
 
         // 确保 payload 是对象
         let obj = match payload.as_object_mut() {
@@ -228,7 +376,7 @@ impl Injector {
         };
 
         // 按优先级顺序应用匹配的规则
-        for rule in self.matching_rules(model) {
+        for rule in rules {
             let params = match rule.parameters.as_object() {
                 Some(params) => params,
                 None => continue,
@@ -272,6 +420,114 @@ impl Injector {
 
         result
     }
+
+    /// 计算发往上游的请求头
+    ///
+    /// 按规则优先级顺序合并匹配规则的请求头：
+    /// - Merge 模式：不覆盖更高优先级规则已设置的请求头
+    /// - Override 模式：覆盖更高优先级规则已设置的请求头
+    ///
+    /// 注入的请求头在 Provider 设置的默认请求头之后应用（如 `anthropic-version`）
+    pub fn inject_headers(
+        &self,
+        model: &str,
+        provider: &str,
+        payload: &serde_json::Value,
+    ) -> HeaderInjectionResult {
+        let mut result = HeaderInjectionResult::default();
+
+        for rule in self.applicable_rules(model, Some(provider), payload) {
+            let mut rule_applied = false;
+
+            for (name, value) in &rule.headers {
+                let name = match HeaderName::from_bytes(name.trim().as_bytes()) {
+                    Ok(name) => name,
+                    Err(_) => {
+                        tracing::warn!("[INJECTION] 请求头名称 {} 无效，跳过注入", name);
+                        continue;
+                    }
+                };
+                if BLOCKED_INJECTION_HEADERS.contains(&name.as_str()) {
+                    tracing::warn!("[INJECTION] 请求头 {} 禁止注入", name);
+                    continue;
+                }
+                let value = match HeaderValue::from_str(value) {
+                    Ok(value) => value,
+                    Err(_) => {
+                        tracing::warn!("[INJECTION] 请求头 {} 的值无效，跳过注入", name);
+                        continue;
+                    }
+                };
+
+                let should_inject = match rule.mode {
+                    InjectionMode::Merge => !result.headers.contains_key(&name),
+                    InjectionMode::Override => true,
+                };
+
+                if should_inject {
+                    result.headers.insert(name, value);
+                    rule_applied = true;
+                }
+            }
+
+            if rule_applied {
+                result.applied_rules.push(rule.id.clone());
+            }
+        }
+
+        result
+    }
+
+    /// 注入 system prompt
+    ///
+    /// 按规则优先级顺序依次前置或追加；没有 system prompt 时插入，
+    /// 已包含相同文本时跳过（客户端自带同样的约束不会重复注入）
+    pub fn inject_system_prompt(
+        &self,
+        model: &str,
+        provider: &str,
+        format: PayloadFormat,
+        payload: &mut serde_json::Value,
+    ) -> InjectionResult {
+        let mut result = InjectionResult::new();
+        if !payload.is_object() {
+            return result;
+        }
+
+        for rule in self.applicable_rules(model, Some(provider), payload) {
+            let Some(injection) = &rule.system_prompt else {
+                continue;
+            };
+            if injection.text.is_empty() || system_prompt_contains(payload, format, &injection.text)
+            {
+                continue;
+            }
+
+            let mode = match injection.position {
+                SystemPromptPosition::Prepend => SystemPromptMode::Prepend,
+                SystemPromptPosition::Append => SystemPromptMode::Append,
+            };
+            if rewrite_system_prompt(payload, format, mode, &injection.text) {
+                result.applied_rules.push(rule.id.clone());
+                if result.injected_params.is_empty() {
+                    result.injected_params.push("system".to_string());
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// 校验规则的条件表达式，返回第一个错误
+pub fn validate_conditions(rules: &[InjectionRule]) -> Result<(), String> {
+    for rule in rules {
+        if let Some(condition) = &rule.condition {
+            Condition::parse(condition)
+                .map_err(|e| format!("注入规则 {} 的条件无效: {}", rule.id, e))?;
+        }
+    }
+    Ok(())
 }
 
 /// 检查模式是否匹配模型名
@@ -281,7 +537,7 @@ impl Injector {
 /// - 前缀匹配: `claude-*`
 /// - 后缀匹配: `*-preview`
 /// - 包含匹配: `*flash*`
-fn pattern_matches(pattern: &str, model: &str) -> bool {
+pub(crate) fn pattern_matches(pattern: &str, model: &str) -> bool {
     if !pattern.contains('*') {
         return pattern == model;
     }
@@ -2,8 +2,9 @@
 //!
 //! 包含独立的基础设施组件，不依赖业务逻辑：
 //! - proxy: HTTP 代理客户端
-//! - resilience: 重试、熔断、故障转移
+//! - resilience: 重试、熔断、故障转移、请求对冲
 //! - injection: 请求参数注入
+//! - transform: 请求转换管道
 //! - telemetry: 遥测统计
 //!
 //! 注意：plugin 模块因依赖 Tauri 无法迁移，保留在主 crate
@@ -12,18 +13,26 @@ pub mod injection;
 pub mod proxy;
 pub mod resilience;
 pub mod telemetry;
+pub mod transform;
 
 // 重新导出常用类型
 pub use injection::{InjectionConfig, InjectionMode, InjectionResult, InjectionRule, Injector};
-pub use proxy::{ProxyClientFactory, ProxyError, ProxyProtocol};
+pub use proxy::{
+    redact_proxy_url, ConnectionPoolSettings, ProxyClientFactory, ProxyError, ProxyProtocol,
+    DIRECT_PROXY,
+};
 pub use resilience::{
-    Failover, FailoverConfig, Retrier, RetryConfig, TimeoutConfig, TimeoutController,
+    ChaosConfig, ChaosInjector, CircuitBreaker, CircuitBreakerConfig, CircuitState,
+    ConcurrencyConfig, ConcurrencyLimiter, Failover, FailoverConfig, HedgeConfig, Hedger, Retrier,
+    RetryConfig, TimeoutConfig, TimeoutController,
 };
 pub use telemetry::{
-    LogRotationConfig, LoggerError, ModelStats, ModelTokenStats, PeriodTokenStats, ProviderStats,
-    ProviderTokenStats, RequestLog, RequestLogger, RequestStatus, StatsAggregator, StatsSummary,
+    AlertConfig, CostReport, LogRotationConfig, LoggerError, ModelStats, ModelTokenStats,
+    OtlpConfig, PeriodTokenStats, PricingConfig, PricingTable, ProviderStats, ProviderTokenStats,
+    ReportConfig, RequestLog, RequestLogger, RequestStatus, StatsAggregator, StatsSummary,
     TimeRange, TokenSource, TokenStatsSummary, TokenTracker, TokenUsageRecord,
 };
+pub use transform::{TransformConfig, TransformRule, Transformer};
 
 pub fn version() -> &'static str {
     env!("CARGO_PKG_VERSION")
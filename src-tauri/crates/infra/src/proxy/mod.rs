@@ -1,9 +1,12 @@
 //! 代理模块
 //!
-//! 提供 Per-Key 代理支持，允许为每个凭证配置独立的代理设置
+//! 提供 Per-Key 和 Per-Provider 代理支持，允许为每个凭证或 Provider 配置独立的出站代理
 
 mod client_factory;
 #[cfg(test)]
 mod tests;
 
-pub use client_factory::{ProxyClientFactory, ProxyError, ProxyProtocol};
+pub use client_factory::{
+    redact_proxy_url, ConnectionPoolSettings, ProxyClientFactory, ProxyError, ProxyProtocol,
+    DIRECT_PROXY,
+};
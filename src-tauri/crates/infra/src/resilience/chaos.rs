@@ -0,0 +1,183 @@
+//! 故障注入（混沌测试）
+//!
+//! 按概率对选定 Provider 的请求注入上游错误、响应延迟或损坏的流式帧，
+//! 用于端到端验证重试、故障转移、熔断和超时逻辑。默认关闭，只应在测试环境中启用。
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 注入到流式响应中的损坏帧（截断的 JSON）
+pub const MALFORMED_SSE_FRAME: &[u8] = b"data: {\"id\":\"chaos\",\"choices\":[{\"delta\":\n\n";
+
+/// 故障注入配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChaosConfig {
+    /// 是否启用故障注入
+    #[serde(default)]
+    pub enabled: bool,
+    /// 注入故障的 Provider（为空表示所有 Provider）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<String>,
+    /// 注入上游错误的概率（0.0 ~ 1.0）
+    #[serde(default)]
+    pub error_rate: f64,
+    /// 注入错误时返回的状态码
+    #[serde(default = "default_chaos_error_status")]
+    pub error_status: u16,
+    /// 注入响应延迟的概率（0.0 ~ 1.0）
+    #[serde(default)]
+    pub latency_rate: f64,
+    /// 注入的延迟（毫秒）
+    #[serde(default = "default_chaos_latency_ms")]
+    pub latency_ms: u64,
+    /// 在流式响应中插入损坏帧的概率（0.0 ~ 1.0）
+    #[serde(default)]
+    pub malformed_stream_rate: f64,
+}
+
+fn default_chaos_error_status() -> u16 {
+    503
+}
+
+fn default_chaos_latency_ms() -> u64 {
+    5000
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            providers: Vec::new(),
+            error_rate: 0.0,
+            error_status: default_chaos_error_status(),
+            latency_rate: 0.0,
+            latency_ms: default_chaos_latency_ms(),
+            malformed_stream_rate: 0.0,
+        }
+    }
+}
+
+impl ChaosConfig {
+    /// 检查 Provider 是否注入故障
+    pub fn applies_to(&self, provider: &str) -> bool {
+        self.enabled
+            && (self.providers.is_empty()
+                || self
+                    .providers
+                    .iter()
+                    .any(|p| p.eq_ignore_ascii_case(provider)))
+    }
+}
+
+/// 单个请求被注入的故障
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChaosFault {
+    /// 请求上游前的延迟
+    pub delay: Option<Duration>,
+    /// 不请求上游，直接返回该状态码的错误
+    pub error_status: Option<u16>,
+    /// 在流式响应中插入损坏帧
+    pub malformed_stream: bool,
+}
+
+impl ChaosFault {
+    /// 是否没有注入任何故障
+    pub fn is_none(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 故障注入器
+#[derive(Debug, Clone, Default)]
+pub struct ChaosInjector {
+    config: ChaosConfig,
+}
+
+impl ChaosInjector {
+    /// 创建新的故障注入器
+    pub fn new(config: ChaosConfig) -> Self {
+        Self { config }
+    }
+
+    /// 获取配置
+    pub fn config(&self) -> &ChaosConfig {
+        &self.config
+    }
+
+    /// 为一次请求抽取要注入的故障
+    pub fn decide(&self, provider: &str) -> ChaosFault {
+        self.decide_with(provider, rand::random::<f64>)
+    }
+
+    /// 使用给定的随机数源（返回 `[0, 1)` 的均匀分布）抽取故障
+    ///
+    /// 三类故障相互独立，各抽取一次随机数。
+    pub fn decide_with(&self, provider: &str, mut sample: impl FnMut() -> f64) -> ChaosFault {
+        if !self.config.applies_to(provider) {
+            return ChaosFault::default();
+        }
+        let mut hit = |rate: f64| rate > 0.0 && sample() < rate;
+        ChaosFault {
+            delay: hit(self.config.latency_rate)
+                .then_some(Duration::from_millis(self.config.latency_ms)),
+            error_status: hit(self.config.error_rate).then_some(self.config.error_status),
+            malformed_stream: hit(self.config.malformed_stream_rate),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn injector(providers: Vec<String>) -> ChaosInjector {
+        ChaosInjector::new(ChaosConfig {
+            enabled: true,
+            providers,
+            error_rate: 0.5,
+            latency_rate: 0.5,
+            latency_ms: 200,
+            malformed_stream_rate: 0.5,
+            ..ChaosConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_applies_to_selected_providers() {
+        assert!(!ChaosConfig::default().applies_to("openai"));
+
+        let injector = injector(vec!["Claude".to_string()]);
+        assert!(injector.config().applies_to("claude"));
+        assert!(!injector.config().applies_to("openai"));
+        assert!(injector.decide_with("openai", || 0.0).is_none());
+    }
+
+    #[test]
+    fn test_decide_with_samples_each_fault() {
+        let injector = injector(Vec::new());
+
+        let fault = injector.decide_with("openai", || 0.1);
+        assert_eq!(fault.delay, Some(Duration::from_millis(200)));
+        assert_eq!(fault.error_status, Some(503));
+        assert!(fault.malformed_stream);
+
+        assert!(injector.decide_with("openai", || 0.9).is_none());
+
+        // 延迟命中、错误未命中、损坏帧命中
+        let mut samples = [0.1, 0.9, 0.1].into_iter();
+        let fault = injector.decide_with("openai", || samples.next().unwrap());
+        assert!(fault.delay.is_some());
+        assert_eq!(fault.error_status, None);
+        assert!(fault.malformed_stream);
+    }
+
+    #[test]
+    fn test_zero_rate_never_samples() {
+        let injector = ChaosInjector::new(ChaosConfig {
+            enabled: true,
+            ..ChaosConfig::default()
+        });
+        let fault = injector.decide_with("openai", || unreachable!("概率为 0 时不应抽样"));
+        assert!(fault.is_none());
+    }
+}
@@ -0,0 +1,321 @@
+//! 熔断器实现
+//!
+//! 按 key（如凭证 UUID）维护独立的熔断状态：
+//! - Closed：正常放行，连续失败达到阈值后进入 Open
+//! - Open：拒绝请求，冷却时间结束后进入 HalfOpen
+//! - HalfOpen：放行有限数量的试探请求，成功则恢复 Closed，失败则重新 Open
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 熔断器配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// 连续失败多少次后熔断
+    pub failure_threshold: u32,
+    /// 熔断冷却时间（毫秒）
+    pub cooldown_ms: u64,
+    /// 半开状态下允许同时进行的试探请求数
+    pub half_open_max_requests: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown_ms: 60_000,
+            half_open_max_requests: 1,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// 获取冷却时间
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_millis(self.cooldown_ms)
+    }
+}
+
+/// 熔断状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// 正常
+    Closed,
+    /// 熔断中
+    Open,
+    /// 半开（试探恢复）
+    HalfOpen,
+}
+
+/// 单个 key 的熔断状态
+#[derive(Debug, Clone)]
+struct BreakerEntry {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// 进入 Open 状态的时间
+    opened_at: Option<Instant>,
+    /// 半开状态下已放行的试探请求数
+    half_open_requests: u32,
+    /// 最近一次放行试探请求的时间
+    half_open_since: Option<Instant>,
+}
+
+impl Default for BreakerEntry {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            half_open_requests: 0,
+            half_open_since: None,
+        }
+    }
+}
+
+/// 熔断器
+///
+/// 线程安全，可在多个请求间共享。
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    entries: DashMap<String, BreakerEntry>,
+}
+
+impl CircuitBreaker {
+    /// 创建新的熔断器
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            entries: DashMap::new(),
+        }
+    }
+
+    /// 使用默认配置创建熔断器
+    pub fn with_defaults() -> Self {
+        Self::new(CircuitBreakerConfig::default())
+    }
+
+    /// 获取配置
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// 获取当前熔断状态（没有记录的 key 视为 Closed）
+    pub fn state(&self, key: &str) -> CircuitState {
+        self.entries
+            .get(key)
+            .map(|e| e.state)
+            .unwrap_or(CircuitState::Closed)
+    }
+
+    /// 检查是否可以放行请求（不修改状态）
+    pub fn can_attempt(&self, key: &str) -> bool {
+        match self.entries.get(key) {
+            Some(entry) => self.can_attempt_entry(&entry, Instant::now()),
+            None => true,
+        }
+    }
+
+    /// 尝试放行请求
+    ///
+    /// Open 状态冷却结束后转为 HalfOpen 并占用一个试探名额。
+    pub fn allow_request(&self, key: &str) -> bool {
+        let Some(mut entry) = self.entries.get_mut(key) else {
+            return true;
+        };
+        let now = Instant::now();
+        if !self.can_attempt_entry(&entry, now) {
+            return false;
+        }
+
+        match entry.state {
+            CircuitState::Closed => {}
+            CircuitState::Open => {
+                entry.state = CircuitState::HalfOpen;
+                entry.half_open_requests = 1;
+                entry.half_open_since = Some(now);
+                tracing::info!("[CIRCUIT_BREAKER] {} 冷却结束，进入半开状态", key);
+            }
+            CircuitState::HalfOpen => {
+                if self.half_open_trial_expired(&entry, now) {
+                    entry.half_open_requests = 0;
+                }
+                entry.half_open_requests += 1;
+                entry.half_open_since = Some(now);
+            }
+        }
+        true
+    }
+
+    /// 记录成功，恢复 Closed 状态
+    pub fn record_success(&self, key: &str) {
+        if let Some(mut entry) = self.entries.get_mut(key) {
+            if entry.state != CircuitState::Closed {
+                tracing::info!("[CIRCUIT_BREAKER] {} 已恢复", key);
+            }
+            *entry = BreakerEntry::default();
+        }
+    }
+
+    /// 记录失败，返回记录后的状态
+    pub fn record_failure(&self, key: &str) -> CircuitState {
+        let mut entry = self.entries.entry(key.to_string()).or_default();
+        let now = Instant::now();
+        entry.consecutive_failures += 1;
+
+        let should_open = match entry.state {
+            CircuitState::Closed => entry.consecutive_failures >= self.config.failure_threshold,
+            // 试探失败，重新熔断
+            CircuitState::HalfOpen => true,
+            // 熔断期间仍有失败上报（如熔断前已发出的请求），刷新冷却时间
+            CircuitState::Open => true,
+        };
+
+        if should_open {
+            if entry.state != CircuitState::Open {
+                tracing::warn!(
+                    "[CIRCUIT_BREAKER] {} 连续失败 {} 次，熔断 {}ms",
+                    key,
+                    entry.consecutive_failures,
+                    self.config.cooldown_ms
+                );
+            }
+            entry.state = CircuitState::Open;
+            entry.opened_at = Some(now);
+            entry.half_open_requests = 0;
+            entry.half_open_since = None;
+        }
+
+        entry.state
+    }
+
+    /// 重置指定 key 的熔断状态
+    pub fn reset(&self, key: &str) {
+        self.entries.remove(key);
+    }
+
+    /// 重置所有熔断状态
+    pub fn reset_all(&self) {
+        self.entries.clear();
+    }
+
+    /// 获取 Open 状态剩余冷却时间
+    pub fn remaining_cooldown(&self, key: &str) -> Option<Duration> {
+        let entry = self.entries.get(key)?;
+        if entry.state != CircuitState::Open {
+            return None;
+        }
+        let elapsed = entry.opened_at?.elapsed();
+        Some(self.config.cooldown().saturating_sub(elapsed))
+    }
+
+    fn can_attempt_entry(&self, entry: &BreakerEntry, now: Instant) -> bool {
+        match entry.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => entry
+                .opened_at
+                .is_none_or(|opened| now.duration_since(opened) >= self.config.cooldown()),
+            CircuitState::HalfOpen => {
+                entry.half_open_requests < self.config.half_open_max_requests
+                    || self.half_open_trial_expired(entry, now)
+            }
+        }
+    }
+
+    /// 试探请求长时间未上报结果（如客户端断开）时，允许发起新的试探
+    fn half_open_trial_expired(&self, entry: &BreakerEntry, now: Instant) -> bool {
+        entry
+            .half_open_since
+            .is_some_and(|since| now.duration_since(since) >= self.config.cooldown())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(failure_threshold: u32, cooldown_ms: u64) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold,
+            cooldown_ms,
+            half_open_max_requests: 1,
+        })
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let cb = breaker(3, 60_000);
+        assert_eq!(cb.record_failure("a"), CircuitState::Closed);
+        assert_eq!(cb.record_failure("a"), CircuitState::Closed);
+        assert!(cb.can_attempt("a"));
+        assert_eq!(cb.record_failure("a"), CircuitState::Open);
+        assert!(!cb.can_attempt("a"));
+        assert!(!cb.allow_request("a"));
+        assert!(cb.remaining_cooldown("a").is_some());
+
+        // 其他 key 不受影响
+        assert!(cb.allow_request("b"));
+        assert_eq!(cb.state("b"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let cb = breaker(2, 60_000);
+        cb.record_failure("a");
+        cb.record_success("a");
+        assert_eq!(cb.record_failure("a"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_single_trial() {
+        let cb = breaker(1, 0);
+        assert_eq!(cb.record_failure("a"), CircuitState::Open);
+
+        // 冷却结束：第一个请求进入半开试探
+        assert!(cb.allow_request("a"));
+        assert_eq!(cb.state("a"), CircuitState::HalfOpen);
+
+        // 试探成功后恢复
+        cb.record_success("a");
+        assert_eq!(cb.state("a"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_limits_concurrent_trials() {
+        let cb = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            cooldown_ms: 60_000,
+            half_open_max_requests: 1,
+        });
+        cb.record_failure("a");
+        // 手动模拟冷却结束
+        cb.entries.get_mut("a").unwrap().opened_at =
+            Instant::now().checked_sub(Duration::from_secs(61));
+
+        assert!(cb.allow_request("a"));
+        assert!(!cb.allow_request("a"));
+        assert!(!cb.can_attempt("a"));
+    }
+
+    #[test]
+    fn test_half_open_failure_reopens() {
+        let cb = breaker(1, 0);
+        cb.record_failure("a");
+        assert!(cb.allow_request("a"));
+        assert_eq!(cb.record_failure("a"), CircuitState::Open);
+    }
+
+    #[test]
+    fn test_reset() {
+        let cb = breaker(1, 60_000);
+        cb.record_failure("a");
+        cb.record_failure("b");
+        cb.reset("a");
+        assert!(cb.can_attempt("a"));
+        assert!(!cb.can_attempt("b"));
+        cb.reset_all();
+        assert!(cb.can_attempt("b"));
+    }
+}
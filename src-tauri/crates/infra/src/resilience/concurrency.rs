@@ -0,0 +1,303 @@
+//! 并发限制实现
+//!
+//! 全局以及按 Provider / 凭证维度限制同时进行的上游请求数。
+//! 没有空闲名额时请求进入有界等待队列，队列已满或等待超时返回错误（对应 HTTP 429），
+//! 避免客户端突发请求触发上游限流。
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// 并发限制配置（各项为 0 表示不限制）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConcurrencyConfig {
+    /// 是否启用并发限制
+    #[serde(default)]
+    pub enabled: bool,
+    /// 全局最大并发请求数
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize,
+    /// 单个 Provider 最大并发请求数
+    #[serde(default = "default_per_provider")]
+    pub per_provider: usize,
+    /// 单个凭证最大并发请求数
+    #[serde(default = "default_per_credential")]
+    pub per_credential: usize,
+    /// 等待队列长度上限，队列已满时直接拒绝
+    #[serde(default = "default_max_queue")]
+    pub max_queue: usize,
+    /// 排队最长等待时间（毫秒）
+    #[serde(default = "default_queue_timeout_ms")]
+    pub queue_timeout_ms: u64,
+}
+
+fn default_max_concurrent() -> usize {
+    64
+}
+
+fn default_per_provider() -> usize {
+    16
+}
+
+fn default_per_credential() -> usize {
+    4
+}
+
+fn default_max_queue() -> usize {
+    128
+}
+
+fn default_queue_timeout_ms() -> u64 {
+    30_000
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrent: default_max_concurrent(),
+            per_provider: default_per_provider(),
+            per_credential: default_per_credential(),
+            max_queue: default_max_queue(),
+            queue_timeout_ms: default_queue_timeout_ms(),
+        }
+    }
+}
+
+/// 并发限制错误
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConcurrencyError {
+    /// 等待队列已满
+    #[error("并发请求过多，等待队列已满 ({0})")]
+    QueueFull(String),
+    /// 排队等待超时
+    #[error("并发请求过多，排队等待超时 ({0})")]
+    Timeout(String),
+}
+
+/// 并发名额，释放时归还
+#[derive(Debug, Default)]
+pub struct ConcurrencyPermit {
+    permits: Vec<OwnedSemaphorePermit>,
+}
+
+impl ConcurrencyPermit {
+    /// 合并另一个名额（一起持有、一起释放）
+    pub fn merge(mut self, other: ConcurrencyPermit) -> Self {
+        self.permits.extend(other.permits);
+        self
+    }
+}
+
+/// 当前并发状态
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConcurrencyStats {
+    /// 全局进行中的请求数
+    pub active: usize,
+    /// 排队中的请求数
+    pub queued: usize,
+}
+
+/// 并发限制器
+///
+/// 克隆共享同一组信号量；配置变化时创建新的限制器替换即可，
+/// 旧限制器发出的名额在请求结束后自然释放。
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimiter {
+    config: ConcurrencyConfig,
+    global: Arc<Semaphore>,
+    keyed: Arc<DashMap<String, Arc<Semaphore>>>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl Default for ConcurrencyLimiter {
+    fn default() -> Self {
+        Self::new(ConcurrencyConfig::default())
+    }
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        let global = Arc::new(Semaphore::new(config.max_concurrent));
+        Self {
+            config,
+            global,
+            keyed: Arc::new(DashMap::new()),
+            queued: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn config(&self) -> &ConcurrencyConfig {
+        &self.config
+    }
+
+    /// 获取全局名额
+    pub async fn acquire_global(&self) -> Result<ConcurrencyPermit, ConcurrencyError> {
+        if !self.config.enabled || self.config.max_concurrent == 0 {
+            return Ok(ConcurrencyPermit::default());
+        }
+        self.acquire(self.global.clone(), "global").await
+    }
+
+    /// 获取 Provider 和凭证维度的名额
+    pub async fn acquire_credential(
+        &self,
+        provider: &str,
+        credential_uuid: &str,
+    ) -> Result<ConcurrencyPermit, ConcurrencyError> {
+        if !self.config.enabled {
+            return Ok(ConcurrencyPermit::default());
+        }
+        let provider_permit = self
+            .acquire_keyed(&format!("provider:{}", provider), self.config.per_provider)
+            .await?;
+        let credential_permit = self
+            .acquire_keyed(
+                &format!("credential:{}", credential_uuid),
+                self.config.per_credential,
+            )
+            .await?;
+        Ok(provider_permit.merge(credential_permit))
+    }
+
+    /// 当前并发状态
+    pub fn stats(&self) -> ConcurrencyStats {
+        let active = if self.config.enabled {
+            self.config
+                .max_concurrent
+                .saturating_sub(self.global.available_permits())
+        } else {
+            0
+        };
+        ConcurrencyStats {
+            active,
+            queued: self.queued.load(Ordering::SeqCst),
+        }
+    }
+
+    async fn acquire_keyed(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> Result<ConcurrencyPermit, ConcurrencyError> {
+        if limit == 0 {
+            return Ok(ConcurrencyPermit::default());
+        }
+        let semaphore = self
+            .keyed
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+            .clone();
+        self.acquire(semaphore, key).await
+    }
+
+    async fn acquire(
+        &self,
+        semaphore: Arc<Semaphore>,
+        key: &str,
+    ) -> Result<ConcurrencyPermit, ConcurrencyError> {
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            return Ok(ConcurrencyPermit {
+                permits: vec![permit],
+            });
+        }
+
+        // 进入等待队列（队列长度在所有维度间共享）
+        let position = self.queued.fetch_add(1, Ordering::SeqCst);
+        let _queued = QueueSlot(self.queued.clone());
+        if position >= self.config.max_queue {
+            return Err(ConcurrencyError::QueueFull(key.to_string()));
+        }
+
+        let timeout = Duration::from_millis(self.config.queue_timeout_ms);
+        match tokio::time::timeout(timeout, semaphore.acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(ConcurrencyPermit {
+                permits: vec![permit],
+            }),
+            _ => Err(ConcurrencyError::Timeout(key.to_string())),
+        }
+    }
+}
+
+/// 排队计数守卫
+struct QueueSlot(Arc<AtomicUsize>);
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(
+        max_concurrent: usize,
+        max_queue: usize,
+        queue_timeout_ms: u64,
+    ) -> ConcurrencyLimiter {
+        ConcurrencyLimiter::new(ConcurrencyConfig {
+            enabled: true,
+            max_concurrent,
+            per_provider: 1,
+            per_credential: 0,
+            max_queue,
+            queue_timeout_ms,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_disabled_never_blocks() {
+        let limiter = ConcurrencyLimiter::default();
+        let _a = limiter.acquire_global().await.unwrap();
+        let _b = limiter.acquire_credential("kiro", "c1").await.unwrap();
+        assert_eq!(limiter.stats(), ConcurrencyStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_queue_full_rejects() {
+        let limiter = limiter(1, 0, 1000);
+        let _held = limiter.acquire_global().await.unwrap();
+        assert_eq!(limiter.stats().active, 1);
+        assert!(matches!(
+            limiter.acquire_global().await,
+            Err(ConcurrencyError::QueueFull(_))
+        ));
+        assert_eq!(limiter.stats().queued, 0);
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_times_out() {
+        let limiter = limiter(1, 4, 50);
+        let _held = limiter.acquire_global().await.unwrap();
+        assert!(matches!(
+            limiter.acquire_global().await,
+            Err(ConcurrencyError::Timeout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_acquires_after_release() {
+        let limiter = limiter(4, 4, 1000);
+        let held = limiter.acquire_credential("kiro", "c1").await.unwrap();
+
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire_credential("kiro", "c2").await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(limiter.stats().queued, 1);
+
+        // 其他 Provider 不受影响
+        assert!(limiter.acquire_credential("gemini", "c3").await.is_ok());
+
+        drop(held);
+        assert!(waiter.await.unwrap().is_ok());
+        assert_eq!(limiter.stats().queued, 0);
+    }
+}
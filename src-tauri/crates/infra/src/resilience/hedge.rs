@@ -0,0 +1,255 @@
+//! 请求对冲实现
+//!
+//! 首个请求在配置的延迟内未完成时，向另一个凭证/Provider 发送相同请求，
+//! 采用先成功返回的结果，并丢弃（取消）另一个请求。
+//! 适用于上游偶发卡顿、对延迟敏感的模型。
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+
+/// 对冲配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HedgeConfig {
+    /// 是否启用请求对冲
+    #[serde(default)]
+    pub enabled: bool,
+    /// 发送对冲请求前等待首个请求的时间（毫秒）
+    #[serde(default = "default_hedge_delay_ms")]
+    pub delay_ms: u64,
+    /// 启用对冲的模型（支持 `*` 后缀前缀匹配，为空表示所有模型）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+}
+
+fn default_hedge_delay_ms() -> u64 {
+    2000
+}
+
+impl Default for HedgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay_ms: default_hedge_delay_ms(),
+            models: Vec::new(),
+        }
+    }
+}
+
+impl HedgeConfig {
+    /// 检查模型是否启用对冲
+    pub fn applies_to(&self, model: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        self.models.is_empty()
+            || self
+                .models
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => model.starts_with(prefix),
+                    None => pattern == model,
+                })
+    }
+}
+
+/// 对冲结果中被采用的请求
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HedgeWinner {
+    /// 首个请求
+    Primary,
+    /// 对冲请求
+    Hedge,
+}
+
+/// 对冲执行结果
+#[derive(Debug)]
+pub struct HedgeOutcome<T> {
+    /// 被采用的结果
+    pub value: T,
+    /// 结果来自哪个请求
+    pub winner: HedgeWinner,
+    /// 是否发出了对冲请求
+    pub hedged: bool,
+}
+
+/// 请求对冲器
+#[derive(Debug, Clone, Default)]
+pub struct Hedger {
+    config: HedgeConfig,
+}
+
+impl Hedger {
+    /// 创建新的对冲器
+    pub fn new(config: HedgeConfig) -> Self {
+        Self { config }
+    }
+
+    /// 获取配置
+    pub fn config(&self) -> &HedgeConfig {
+        &self.config
+    }
+
+    /// 发送对冲请求前的等待时间
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(self.config.delay_ms)
+    }
+
+    /// 执行对冲请求
+    ///
+    /// - 首个请求在延迟内完成时直接返回，不发送对冲请求
+    /// - 否则调用 `hedge` 构造对冲请求（返回 `None` 表示没有可用的对冲目标），两者并发执行
+    /// - 先完成且 `is_success` 为真的结果胜出，另一个请求被丢弃
+    /// - 两者都失败时返回首个请求的结果
+    pub async fn execute<T, P, H, HF, S>(
+        &self,
+        primary: P,
+        hedge: HF,
+        is_success: S,
+    ) -> HedgeOutcome<T>
+    where
+        P: Future<Output = T>,
+        H: Future<Output = T>,
+        HF: FnOnce() -> Option<H>,
+        S: Fn(&T) -> bool,
+    {
+        tokio::pin!(primary);
+
+        let primary_result = tokio::select! {
+            value = &mut primary => Some(value),
+            _ = tokio::time::sleep(self.delay()) => None,
+        };
+        if let Some(value) = primary_result {
+            return HedgeOutcome {
+                value,
+                winner: HedgeWinner::Primary,
+                hedged: false,
+            };
+        }
+
+        let Some(hedge) = hedge() else {
+            return HedgeOutcome {
+                value: primary.await,
+                winner: HedgeWinner::Primary,
+                hedged: false,
+            };
+        };
+        tokio::pin!(hedge);
+
+        tokio::select! {
+            value = &mut primary => {
+                if is_success(&value) {
+                    return HedgeOutcome { value, winner: HedgeWinner::Primary, hedged: true };
+                }
+                let hedge_value = hedge.await;
+                if is_success(&hedge_value) {
+                    HedgeOutcome { value: hedge_value, winner: HedgeWinner::Hedge, hedged: true }
+                } else {
+                    HedgeOutcome { value, winner: HedgeWinner::Primary, hedged: true }
+                }
+            }
+            value = &mut hedge => {
+                if is_success(&value) {
+                    return HedgeOutcome { value, winner: HedgeWinner::Hedge, hedged: true };
+                }
+                HedgeOutcome { value: primary.await, winner: HedgeWinner::Primary, hedged: true }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn hedger(delay_ms: u64) -> Hedger {
+        Hedger::new(HedgeConfig {
+            enabled: true,
+            delay_ms,
+            models: Vec::new(),
+        })
+    }
+
+    async fn respond(
+        delay_ms: u64,
+        value: Result<&'static str, &'static str>,
+    ) -> Result<&'static str, &'static str> {
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        value
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let mut config = HedgeConfig::default();
+        assert!(!config.applies_to("gpt-4o"));
+
+        config.enabled = true;
+        assert!(config.applies_to("gpt-4o"));
+
+        config.models = vec!["claude-*".to_string(), "gpt-4o".to_string()];
+        assert!(config.applies_to("claude-sonnet-4-5"));
+        assert!(config.applies_to("gpt-4o"));
+        assert!(!config.applies_to("gpt-4o-mini"));
+    }
+
+    #[tokio::test]
+    async fn test_fast_primary_skips_hedge() {
+        let hedge_started = AtomicBool::new(false);
+        let outcome = hedger(100)
+            .execute(
+                respond(10, Ok("primary")),
+                || {
+                    hedge_started.store(true, Ordering::SeqCst);
+                    Some(respond(10, Ok("hedge")))
+                },
+                |r| r.is_ok(),
+            )
+            .await;
+        assert_eq!(outcome.value, Ok("primary"));
+        assert_eq!(outcome.winner, HedgeWinner::Primary);
+        assert!(!outcome.hedged);
+        assert!(!hedge_started.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_slow_primary_loses_to_hedge() {
+        let outcome = hedger(100)
+            .execute(
+                respond(1000, Ok("primary")),
+                || Some(respond(10, Ok("hedge"))),
+                |r| r.is_ok(),
+            )
+            .await;
+        assert_eq!(outcome.value, Ok("hedge"));
+        assert_eq!(outcome.winner, HedgeWinner::Hedge);
+        assert!(outcome.hedged);
+    }
+
+    #[tokio::test]
+    async fn test_failed_hedge_waits_for_primary() {
+        let outcome = hedger(100)
+            .execute(
+                respond(500, Ok("primary")),
+                || Some(respond(10, Err("hedge failed"))),
+                |r| r.is_ok(),
+            )
+            .await;
+        assert_eq!(outcome.value, Ok("primary"));
+        assert_eq!(outcome.winner, HedgeWinner::Primary);
+        assert!(outcome.hedged);
+    }
+
+    #[tokio::test]
+    async fn test_no_hedge_target() {
+        let outcome = hedger(100)
+            .execute(
+                respond(500, Ok("primary")),
+                || None::<std::future::Ready<Result<&'static str, &'static str>>>,
+                |r| r.is_ok(),
+            )
+            .await;
+        assert_eq!(outcome.value, Ok("primary"));
+        assert!(!outcome.hedged);
+    }
+}
@@ -1,20 +1,35 @@
 //! 容错机制模块
 //!
-//! 提供重试、故障转移和超时控制功能
+//! 提供重试、熔断、故障转移、请求对冲、并发限制、超时控制、上游错误分类和故障注入功能
 
+mod chaos;
+mod circuit_breaker;
+mod concurrency;
 mod failover;
+mod hedge;
 mod retry;
 mod timeout;
+mod upstream_error;
 
+pub use chaos::{ChaosConfig, ChaosFault, ChaosInjector, MALFORMED_SSE_FRAME};
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+pub use concurrency::{
+    ConcurrencyConfig, ConcurrencyError, ConcurrencyLimiter, ConcurrencyPermit, ConcurrencyStats,
+};
 pub use failover::{
     Failover, FailoverConfig, FailoverManager, FailoverResult, FailureType, SwitchEvent,
     QUOTA_EXCEEDED_KEYWORDS, QUOTA_EXCEEDED_STATUS_CODES,
 };
-pub use retry::{Retrier, RetryConfig, RetryError};
+pub use hedge::{HedgeConfig, HedgeOutcome, HedgeWinner, Hedger};
+pub use retry::{BackoffStrategy, Retrier, RetryConfig, RetryError};
 pub use timeout::{
     CancellationToken, StreamIdleDetector, StreamWithIdleTimeout, TimeoutConfig, TimeoutController,
     TimeoutError,
 };
+pub use upstream_error::{
+    parse_retry_after, retry_after_from_headers, retry_delay_from_body, UpstreamError,
+    UpstreamErrorKind,
+};
 
 #[cfg(test)]
 mod tests;
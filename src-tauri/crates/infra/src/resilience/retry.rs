@@ -1,13 +1,15 @@
 //! 重试机制实现
 //!
-//! 提供带指数退避和抖动的重试逻辑
+//! 提供带指数退避和抖动的重试逻辑，按 Provider 选择退避策略，并遵循上游的 `Retry-After`
 
+use super::upstream_error::UpstreamError;
+use proxycast_core::ProviderType;
 use serde::{Deserialize, Serialize};
 use std::future::Future;
 use std::time::Duration;
 
 /// 可重试的 HTTP 状态码
-pub const RETRYABLE_STATUS_CODES: &[u16] = &[408, 429, 500, 502, 503, 504];
+pub const RETRYABLE_STATUS_CODES: &[u16] = &[408, 429, 500, 502, 503, 504, 529];
 
 /// 重试配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -55,6 +57,33 @@ impl RetryConfig {
     }
 }
 
+/// 退避策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackoffStrategy {
+    /// 指数退避 + 抖动，避免多个请求同时重试
+    #[default]
+    ExponentialJitter,
+    /// 固定间隔（`base_delay_ms`）
+    Fixed,
+}
+
+impl BackoffStrategy {
+    /// Provider 默认的退避策略
+    ///
+    /// Gemini 系列按固定窗口限流（每分钟配额），固定间隔重试即可；
+    /// 其余 Provider（如 Kiro）的限流与并发相关，使用指数退避 + 抖动分散重试。
+    pub fn for_provider(provider: ProviderType) -> Self {
+        match provider {
+            ProviderType::Gemini
+            | ProviderType::GeminiApiKey
+            | ProviderType::Vertex
+            | ProviderType::Antigravity => BackoffStrategy::Fixed,
+            _ => BackoffStrategy::ExponentialJitter,
+        }
+    }
+}
+
 /// 重试错误
 #[derive(Debug, Clone)]
 pub struct RetryError {
@@ -127,6 +156,37 @@ impl Retrier {
         Duration::from_millis(delay as u64)
     }
 
+    /// 按退避策略计算第 N 次重试的退避时间
+    pub fn backoff_delay_with_strategy(&self, strategy: BackoffStrategy, attempt: u32) -> Duration {
+        match strategy {
+            BackoffStrategy::ExponentialJitter => self.backoff_delay(attempt),
+            BackoffStrategy::Fixed => {
+                Duration::from_millis(self.config.base_delay_ms.min(self.config.max_delay_ms))
+            }
+        }
+    }
+
+    /// 计算某个 Provider 第 N 次重试前的等待时间
+    ///
+    /// 上游给出 `Retry-After` 时以其为准；超过 `max_delay_ms` 时返回 `None`，
+    /// 表示不值得在本地等待（应交给故障转移或直接返回给客户端）。
+    pub fn retry_delay(
+        &self,
+        provider: ProviderType,
+        attempt: u32,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        match retry_after {
+            Some(retry_after) if retry_after > Duration::from_millis(self.config.max_delay_ms) => {
+                None
+            }
+            Some(retry_after) => Some(retry_after),
+            None => Some(
+                self.backoff_delay_with_strategy(BackoffStrategy::for_provider(provider), attempt),
+            ),
+        }
+    }
+
     /// 带重试执行异步操作
     ///
     /// 操作函数返回 `Result<T, (String, Option<u16>)>`，
@@ -174,6 +234,54 @@ impl Retrier {
         }
     }
 
+    /// 带重试执行上游调用，按错误分类判断是否重试，并使用 Provider 的退避策略
+    pub async fn execute_upstream<F, Fut, T>(
+        &self,
+        provider: ProviderType,
+        mut operation: F,
+    ) -> Result<T, RetryError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, UpstreamError>>,
+    {
+        let mut attempts = 0u32;
+
+        loop {
+            attempts += 1;
+
+            let error = match operation().await {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+
+            let delay = if error.is_retryable() && attempts <= self.config.max_retries {
+                self.retry_delay(provider, attempts - 1, error.retry_after)
+            } else {
+                None
+            };
+
+            match delay {
+                Some(delay) => {
+                    tracing::debug!(
+                        "[RETRY] provider={} attempt={} kind={:?} delay={}ms",
+                        provider,
+                        attempts,
+                        error.kind,
+                        delay.as_millis()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                None => {
+                    return Err(RetryError {
+                        attempts,
+                        last_error: error.to_string(),
+                        last_status_code: error.status,
+                    })
+                }
+            }
+        }
+    }
+
     /// 同步计算重试序列的所有退避时间（用于测试）
     pub fn compute_backoff_sequence(&self, jitter_factor: f64) -> Vec<Duration> {
         (0..self.config.max_retries)
@@ -226,6 +334,7 @@ mod unit_tests {
         assert!(config.is_retryable(502));
         assert!(config.is_retryable(503));
         assert!(config.is_retryable(504));
+        assert!(config.is_retryable(529));
 
         // 不可重试的状态码
         assert!(!config.is_retryable(200));
@@ -307,6 +416,101 @@ mod unit_tests {
         assert_eq!(sequence[2], Duration::from_millis(4000));
     }
 
+    #[test]
+    fn test_backoff_strategy_for_provider() {
+        assert_eq!(
+            BackoffStrategy::for_provider(ProviderType::Kiro),
+            BackoffStrategy::ExponentialJitter
+        );
+        assert_eq!(
+            BackoffStrategy::for_provider(ProviderType::Gemini),
+            BackoffStrategy::Fixed
+        );
+        assert_eq!(
+            BackoffStrategy::for_provider(ProviderType::Antigravity),
+            BackoffStrategy::Fixed
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let retrier = Retrier::new(RetryConfig::new(3, 1000, 30000));
+
+        // 固定间隔
+        assert_eq!(
+            retrier.retry_delay(ProviderType::Gemini, 2, None),
+            Some(Duration::from_millis(1000))
+        );
+
+        // 指数退避 + 抖动：[base * 2^n, base * 2^n + base)
+        let delay = retrier.retry_delay(ProviderType::Kiro, 2, None).unwrap();
+        assert!(delay >= Duration::from_millis(4000) && delay < Duration::from_millis(5000));
+
+        // Retry-After 优先，超过最大延迟时放弃重试
+        assert_eq!(
+            retrier.retry_delay(ProviderType::Kiro, 0, Some(Duration::from_secs(5))),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            retrier.retry_delay(ProviderType::Gemini, 0, Some(Duration::from_secs(60))),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_upstream_honors_retry_after() {
+        let retrier = Retrier::new(RetryConfig::new(2, 1000, 30000));
+        let mut calls = 0;
+
+        let started = std::time::Instant::now();
+        let result = retrier
+            .execute_upstream(ProviderType::Kiro, || {
+                calls += 1;
+                let attempt = calls;
+                async move {
+                    if attempt == 1 {
+                        Err(UpstreamError::new(Some(429), "Too Many Requests")
+                            .with_retry_after(Some(Duration::from_millis(10))))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert!(started.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[tokio::test]
+    async fn test_execute_upstream_stops_on_long_retry_after() {
+        let retrier = Retrier::new(RetryConfig::new(3, 1000, 5000));
+
+        let result: Result<(), RetryError> = retrier
+            .execute_upstream(ProviderType::Kiro, || async {
+                Err(UpstreamError::new(Some(529), "overloaded")
+                    .with_retry_after(Some(Duration::from_secs(60))))
+            })
+            .await;
+
+        let err = result.unwrap_err();
+        assert_eq!(err.attempts, 1);
+        assert_eq!(err.last_status_code, Some(529));
+    }
+
+    #[tokio::test]
+    async fn test_execute_upstream_non_retryable() {
+        let retrier = Retrier::with_defaults();
+
+        let result: Result<(), RetryError> = retrier
+            .execute_upstream(ProviderType::Gemini, || async {
+                Err(UpstreamError::new(Some(400), "invalid argument"))
+            })
+            .await;
+
+        assert_eq!(result.unwrap_err().attempts, 1);
+    }
+
     #[tokio::test]
     async fn test_execute_success_first_try() {
         let retrier = Retrier::with_defaults();
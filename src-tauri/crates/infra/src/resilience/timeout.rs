@@ -17,6 +17,13 @@ pub struct TimeoutConfig {
     /// 流式响应空闲超时（毫秒），0 表示无超时
     /// 当流式响应中两个 chunk 之间的间隔超过此值时触发超时
     pub stream_idle_timeout_ms: u64,
+    /// 上游连接超时（毫秒），0 表示无超时
+    #[serde(default)]
+    pub connect_timeout_ms: u64,
+    /// 首字节超时（毫秒），0 表示只受请求超时限制
+    /// 流式请求发出后等待上游响应头的最长时间
+    #[serde(default)]
+    pub first_byte_timeout_ms: u64,
 }
 
 impl Default for TimeoutConfig {
@@ -24,6 +31,8 @@ impl Default for TimeoutConfig {
         Self {
             request_timeout_ms: 120_000,    // 2 分钟
             stream_idle_timeout_ms: 30_000, // 30 秒
+            connect_timeout_ms: 0,
+            first_byte_timeout_ms: 0,
         }
     }
 }
@@ -34,14 +43,29 @@ impl TimeoutConfig {
         Self {
             request_timeout_ms,
             stream_idle_timeout_ms,
+            connect_timeout_ms: 0,
+            first_byte_timeout_ms: 0,
         }
     }
 
+    /// 设置连接超时和首字节超时（毫秒）
+    pub fn with_upstream_timeouts(
+        mut self,
+        connect_timeout_ms: u64,
+        first_byte_timeout_ms: u64,
+    ) -> Self {
+        self.connect_timeout_ms = connect_timeout_ms;
+        self.first_byte_timeout_ms = first_byte_timeout_ms;
+        self
+    }
+
     /// 创建无超时的配置
     pub fn no_timeout() -> Self {
         Self {
             request_timeout_ms: 0,
             stream_idle_timeout_ms: 0,
+            connect_timeout_ms: 0,
+            first_byte_timeout_ms: 0,
         }
     }
 
@@ -63,6 +87,16 @@ impl TimeoutConfig {
         }
     }
 
+    /// 获取连接超时 Duration
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        (self.connect_timeout_ms > 0).then(|| Duration::from_millis(self.connect_timeout_ms))
+    }
+
+    /// 获取首字节超时 Duration
+    pub fn first_byte_timeout(&self) -> Option<Duration> {
+        (self.first_byte_timeout_ms > 0).then(|| Duration::from_millis(self.first_byte_timeout_ms))
+    }
+
     /// 检查是否启用请求超时
     pub fn has_request_timeout(&self) -> bool {
         self.request_timeout_ms > 0
@@ -79,6 +113,8 @@ impl TimeoutConfig {
 pub enum TimeoutError {
     /// 请求超时
     RequestTimeout { timeout_ms: u64, elapsed_ms: u64 },
+    /// 等待上游响应头超时
+    FirstByteTimeout { timeout_ms: u64, elapsed_ms: u64 },
     /// 流式响应空闲超时
     StreamIdleTimeout { timeout_ms: u64, idle_ms: u64 },
     /// 操作被取消
@@ -98,6 +134,16 @@ impl std::fmt::Display for TimeoutError {
                     timeout_ms, elapsed_ms
                 )
             }
+            TimeoutError::FirstByteTimeout {
+                timeout_ms,
+                elapsed_ms,
+            } => {
+                write!(
+                    f,
+                    "等待上游响应超时: 配置 {}ms, 已耗时 {}ms",
+                    timeout_ms, elapsed_ms
+                )
+            }
             TimeoutError::StreamIdleTimeout {
                 timeout_ms,
                 idle_ms,
@@ -117,6 +163,18 @@ impl std::fmt::Display for TimeoutError {
 
 impl std::error::Error for TimeoutError {}
 
+impl TimeoutError {
+    /// 配置的超时时间（毫秒），取消时为 0
+    pub fn timeout_ms(&self) -> u64 {
+        match self {
+            TimeoutError::RequestTimeout { timeout_ms, .. }
+            | TimeoutError::FirstByteTimeout { timeout_ms, .. }
+            | TimeoutError::StreamIdleTimeout { timeout_ms, .. } => *timeout_ms,
+            TimeoutError::Cancelled => 0,
+        }
+    }
+}
+
 /// 取消令牌
 ///
 /// 用于取消正在进行的请求
@@ -263,6 +321,44 @@ impl TimeoutController {
             }
         }
     }
+
+    /// 执行上游调用直到拿到响应
+    ///
+    /// 流式请求在收到响应头后即返回，受首字节超时限制（未配置时使用请求超时）；
+    /// 非流式请求需要读完整个响应体，只受请求超时限制。
+    /// 超时后丢弃 `operation`，进行中的上游请求随之取消。
+    pub async fn execute_until_response<F, T>(
+        &self,
+        operation: F,
+        streaming: bool,
+    ) -> Result<T, TimeoutError>
+    where
+        F: Future<Output = T>,
+    {
+        let first_byte = self
+            .config
+            .first_byte_timeout()
+            .filter(|_| streaming)
+            .filter(|t| self.config.request_timeout().is_none_or(|total| *t < total));
+        let Some(timeout) = first_byte.or_else(|| self.config.request_timeout()) else {
+            return Ok(operation.await);
+        };
+
+        let start = Instant::now();
+        tokio::time::timeout(timeout, operation).await.map_err(|_| {
+            let elapsed_ms = start.elapsed().as_millis() as u64;
+            match first_byte {
+                Some(_) => TimeoutError::FirstByteTimeout {
+                    timeout_ms: self.config.first_byte_timeout_ms,
+                    elapsed_ms,
+                },
+                None => TimeoutError::RequestTimeout {
+                    timeout_ms: self.config.request_timeout_ms,
+                    elapsed_ms,
+                },
+            }
+        })
+    }
 }
 
 impl Default for TimeoutController {
@@ -496,8 +592,16 @@ mod unit_tests {
         assert!(err.to_string().contains("1000"));
         assert!(err.to_string().contains("1200"));
 
+        let err = TimeoutError::FirstByteTimeout {
+            timeout_ms: 3000,
+            elapsed_ms: 3005,
+        };
+        assert!(err.to_string().contains("3000"));
+        assert!(err.to_string().contains("上游响应"));
+
         let err = TimeoutError::Cancelled;
         assert!(err.to_string().contains("取消"));
+        assert_eq!(err.timeout_ms(), 0);
     }
 
     #[tokio::test]
@@ -583,6 +687,41 @@ mod unit_tests {
         assert_eq!(result.unwrap_err(), TimeoutError::Cancelled);
     }
 
+    #[tokio::test]
+    async fn test_execute_until_response_first_byte() {
+        let controller =
+            TimeoutController::new(TimeoutConfig::new(5000, 0).with_upstream_timeouts(0, 50));
+        let slow = || tokio::time::sleep(Duration::from_millis(200));
+
+        // 流式请求受首字节超时限制
+        match controller.execute_until_response(slow(), true).await {
+            Err(TimeoutError::FirstByteTimeout { timeout_ms, .. }) => assert_eq!(timeout_ms, 50),
+            other => panic!("Expected FirstByteTimeout error, got {:?}", other),
+        }
+
+        // 非流式请求需要读完响应体，只受请求超时限制
+        assert!(controller
+            .execute_until_response(slow(), false)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_execute_until_response_total() {
+        let controller =
+            TimeoutController::new(TimeoutConfig::new(50, 0).with_upstream_timeouts(0, 1000));
+
+        // 首字节超时大于请求超时时以请求超时为准
+        let result = controller
+            .execute_until_response(tokio::time::sleep(Duration::from_millis(200)), true)
+            .await;
+        assert!(matches!(
+            result,
+            Err(TimeoutError::RequestTimeout { timeout_ms: 50, .. })
+        ));
+        assert_eq!(result.unwrap_err().timeout_ms(), 50);
+    }
+
     #[test]
     fn test_stream_idle_detector_activity() {
         let detector = StreamIdleDetector::new(TimeoutConfig::new(0, 1000));
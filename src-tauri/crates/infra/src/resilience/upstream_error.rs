@@ -0,0 +1,315 @@
+//! 上游错误分类
+//!
+//! 将上游的 429 / 529 / 限流类响应归类为类型化错误，并解析服务端建议的重试等待时间
+//! （`Retry-After` / `retry-after-ms` 响应头，Gemini 响应体中的 `RetryInfo.retryDelay`）。
+
+use crate::telemetry::RequestStatus;
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+
+/// 表示上游限流的错误消息关键词（不区分大小写）
+pub const THROTTLING_KEYWORDS: &[&str] = &[
+    "throttl",
+    "rate limit",
+    "rate_limit",
+    "ratelimit",
+    "too many requests",
+    "resource_exhausted",
+];
+
+/// 表示上游过载的错误消息关键词（不区分大小写）
+pub const OVERLOADED_KEYWORDS: &[&str] = &["overloaded"];
+
+/// 上游错误分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpstreamErrorKind {
+    /// 限流（429、ThrottlingException、RESOURCE_EXHAUSTED 等）
+    RateLimited,
+    /// 上游过载（Anthropic 529 overloaded_error 等）
+    Overloaded,
+    /// 上游服务异常（500 / 502 / 503）
+    Unavailable,
+    /// 超时（408 / 504）
+    Timeout,
+    /// 认证失败（401 / 403）
+    Authentication,
+    /// 请求本身有误（其余 4xx）
+    InvalidRequest,
+    /// 网络错误等没有状态码的错误
+    Network,
+}
+
+impl UpstreamErrorKind {
+    /// 根据状态码和错误消息分类
+    pub fn classify(status: Option<u16>, message: &str) -> Self {
+        let message = message.to_lowercase();
+        let contains_any = |keywords: &[&str]| keywords.iter().any(|k| message.contains(k));
+
+        match status {
+            None => Self::Network,
+            Some(429) => Self::RateLimited,
+            Some(529) => Self::Overloaded,
+            Some(408 | 504) => Self::Timeout,
+            Some(401 | 403) => Self::Authentication,
+            // 部分上游（如 AWS）以 400 / 503 返回限流与过载
+            Some(_) if contains_any(THROTTLING_KEYWORDS) => Self::RateLimited,
+            Some(_) if contains_any(OVERLOADED_KEYWORDS) => Self::Overloaded,
+            Some(code) if code >= 500 => Self::Unavailable,
+            Some(_) => Self::InvalidRequest,
+        }
+    }
+
+    /// 是否为限流或过载
+    pub fn is_rate_limited(self) -> bool {
+        matches!(self, Self::RateLimited | Self::Overloaded)
+    }
+
+    /// 是否值得重试
+    pub fn is_retryable(self) -> bool {
+        !matches!(self, Self::Authentication | Self::InvalidRequest)
+    }
+
+    /// 对应的遥测请求状态
+    pub fn request_status(self) -> RequestStatus {
+        match self {
+            Self::RateLimited | Self::Overloaded => RequestStatus::RateLimited,
+            Self::Timeout => RequestStatus::Timeout,
+            _ => RequestStatus::Failed,
+        }
+    }
+}
+
+/// 类型化的上游错误
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpstreamError {
+    pub kind: UpstreamErrorKind,
+    /// 上游 HTTP 状态码（网络错误时为空）
+    pub status: Option<u16>,
+    pub message: String,
+    /// 上游建议的重试等待时间
+    pub retry_after: Option<Duration>,
+}
+
+impl UpstreamError {
+    /// 根据状态码和错误消息构造，响应体中的 `retryDelay` 会被解析为重试等待时间
+    pub fn new(status: Option<u16>, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self {
+            kind: UpstreamErrorKind::classify(status, &message),
+            status,
+            retry_after: retry_delay_from_body(&message),
+            message,
+        }
+    }
+
+    /// 根据上游响应的状态码、响应头和响应体构造
+    pub fn from_response(status: u16, headers: &HeaderMap, body: impl Into<String>) -> Self {
+        let error = Self::new(Some(status), body);
+        match retry_after_from_headers(headers) {
+            Some(retry_after) => error.with_retry_after(Some(retry_after)),
+            None => error,
+        }
+    }
+
+    /// 网络错误
+    pub fn network(message: impl Into<String>) -> Self {
+        Self::new(None, message)
+    }
+
+    pub fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.kind.is_retryable()
+    }
+}
+
+impl std::fmt::Display for UpstreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "HTTP {}: {}", status, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for UpstreamError {}
+
+/// 解析 `Retry-After` 头的值
+///
+/// 支持秒数（`120`）和 HTTP 日期（`Wed, 21 Oct 2015 07:28:00 GMT`）两种格式，
+/// 日期早于当前时间时返回零等待。
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.signed_duration_since(chrono::Utc::now());
+    Some(wait.to_std().unwrap_or_default())
+}
+
+/// 从响应头读取重试等待时间
+///
+/// 优先使用精度更高的 `retry-after-ms`（OpenAI），其次是标准的 `Retry-After`。
+pub fn retry_after_from_headers(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    header("retry-after-ms")
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .or_else(|| header("retry-after").and_then(parse_retry_after))
+}
+
+/// 从 Google 风格的错误响应体中读取 `RetryInfo.retryDelay`
+///
+/// ```json
+/// {"error": {"code": 429, "details": [
+///   {"@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "38s"}]}}
+/// ```
+pub fn retry_delay_from_body(body: &str) -> Option<Duration> {
+    if !body.contains("retryDelay") {
+        return None;
+    }
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let error = value.get("error").unwrap_or(&value);
+    error
+        .get("details")?
+        .as_array()?
+        .iter()
+        .filter_map(|detail| detail.get("retryDelay")?.as_str())
+        .find_map(parse_protobuf_duration)
+}
+
+/// 解析 protobuf Duration 的 JSON 表示（如 `38s`、`0.5s`）
+fn parse_protobuf_duration(value: &str) -> Option<Duration> {
+    let seconds = value.trim().strip_suffix('s')?.parse::<f64>().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_classify_by_status() {
+        let classify = |status| UpstreamErrorKind::classify(status, "error");
+        assert_eq!(classify(Some(429)), UpstreamErrorKind::RateLimited);
+        assert_eq!(classify(Some(529)), UpstreamErrorKind::Overloaded);
+        assert_eq!(classify(Some(503)), UpstreamErrorKind::Unavailable);
+        assert_eq!(classify(Some(504)), UpstreamErrorKind::Timeout);
+        assert_eq!(classify(Some(401)), UpstreamErrorKind::Authentication);
+        assert_eq!(classify(Some(400)), UpstreamErrorKind::InvalidRequest);
+        assert_eq!(classify(None), UpstreamErrorKind::Network);
+    }
+
+    #[test]
+    fn test_classify_by_message() {
+        assert_eq!(
+            UpstreamErrorKind::classify(
+                Some(400),
+                r#"{"__type":"com.amazon#ThrottlingException","message":"Rate exceeded"}"#
+            ),
+            UpstreamErrorKind::RateLimited
+        );
+        assert_eq!(
+            UpstreamErrorKind::classify(Some(503), r#"{"status":"RESOURCE_EXHAUSTED"}"#),
+            UpstreamErrorKind::RateLimited
+        );
+        assert_eq!(
+            UpstreamErrorKind::classify(
+                Some(503),
+                r#"{"type":"error","error":{"type":"overloaded_error"}}"#
+            ),
+            UpstreamErrorKind::Overloaded
+        );
+        // 认证错误不受消息关键词影响
+        assert_eq!(
+            UpstreamErrorKind::classify(Some(403), "rate limit"),
+            UpstreamErrorKind::Authentication
+        );
+    }
+
+    #[test]
+    fn test_kind_properties() {
+        assert!(UpstreamErrorKind::RateLimited.is_rate_limited());
+        assert!(UpstreamErrorKind::Overloaded.is_retryable());
+        assert!(!UpstreamErrorKind::InvalidRequest.is_retryable());
+        assert_eq!(
+            UpstreamErrorKind::Overloaded.request_status(),
+            RequestStatus::RateLimited
+        );
+        assert_eq!(
+            UpstreamErrorKind::Timeout.request_status(),
+            RequestStatus::Timeout
+        );
+        assert_eq!(
+            UpstreamErrorKind::Unavailable.request_status(),
+            RequestStatus::Failed
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after(" 1.5 "),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(parse_retry_after("-1"), None);
+        assert_eq!(parse_retry_after("soon"), None);
+        // 过去的日期视为无需等待
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+
+        let future = chrono::Utc::now() + chrono::Duration::seconds(90);
+        let wait = parse_retry_after(&future.to_rfc2822()).unwrap();
+        assert!(wait > Duration::from_secs(85) && wait <= Duration::from_secs(90));
+    }
+
+    #[test]
+    fn test_retry_after_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after_from_headers(&headers), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(
+            retry_after_from_headers(&headers),
+            Some(Duration::from_secs(7))
+        );
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("250"));
+        assert_eq!(
+            retry_after_from_headers(&headers),
+            Some(Duration::from_millis(250))
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_from_body() {
+        let body = r#"{"error":{"code":429,"status":"RESOURCE_EXHAUSTED","details":[
+            {"@type":"type.googleapis.com/google.rpc.QuotaFailure"},
+            {"@type":"type.googleapis.com/google.rpc.RetryInfo","retryDelay":"38s"}]}}"#;
+        assert_eq!(retry_delay_from_body(body), Some(Duration::from_secs(38)));
+        assert_eq!(retry_delay_from_body("retryDelay: soon"), None);
+        assert_eq!(retry_delay_from_body(r#"{"error":{}}"#), None);
+    }
+
+    #[test]
+    fn test_from_response_prefers_headers() {
+        let body = r#"{"error":{"details":[{"retryDelay":"38s"}]}}"#;
+        let error = UpstreamError::from_response(429, &HeaderMap::new(), body);
+        assert_eq!(error.kind, UpstreamErrorKind::RateLimited);
+        assert_eq!(error.retry_after, Some(Duration::from_secs(38)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("3"));
+        let error = UpstreamError::from_response(429, &headers, body);
+        assert_eq!(error.retry_after, Some(Duration::from_secs(3)));
+        assert_eq!(error.to_string(), format!("HTTP 429: {}", body));
+    }
+}
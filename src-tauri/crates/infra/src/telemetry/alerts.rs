@@ -0,0 +1,499 @@
+//! 异常告警模块
+//!
+//! 按可配置规则检测异常：短时间窗口内错误率过高、当日估算费用超限、凭证持续不健康。
+//! 本模块只负责判定，告警的投递（日志、桌面事件、Webhook）由调用方完成
+
+use super::cost::PricingTable;
+use super::report::is_error;
+use super::tokens::TokenUsageRecord;
+use super::types::RequestLog;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// 保留的最近告警条数
+const MAX_ALERT_HISTORY: usize = 100;
+
+fn default_window_minutes() -> u32 {
+    5
+}
+
+fn default_min_requests() -> u64 {
+    10
+}
+
+fn default_unhealthy_minutes() -> u32 {
+    10
+}
+
+fn default_cooldown_minutes() -> u32 {
+    30
+}
+
+fn default_rules() -> Vec<AlertRule> {
+    vec![
+        AlertRule::ErrorRate {
+            threshold_percent: 20.0,
+            window_minutes: default_window_minutes(),
+            min_requests: default_min_requests(),
+        },
+        AlertRule::CredentialUnhealthy {
+            minutes: default_unhealthy_minutes(),
+        },
+    ]
+}
+
+/// 告警规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertRule {
+    /// 时间窗口内错误率（失败 / 超时 / 限流）超过阈值
+    ErrorRate {
+        /// 错误率阈值（百分比）
+        threshold_percent: f64,
+        /// 统计窗口（分钟）
+        #[serde(default = "default_window_minutes")]
+        window_minutes: u32,
+        /// 窗口内请求数不足时不判定，避免少量请求导致误报
+        #[serde(default = "default_min_requests")]
+        min_requests: u64,
+    },
+    /// 当日（UTC）估算费用超过阈值，每天最多触发一次
+    DailySpend {
+        /// 费用阈值（单价表货币单位）
+        threshold: f64,
+    },
+    /// 凭证持续不健康超过指定分钟数，每次不健康期间最多触发一次
+    CredentialUnhealthy {
+        /// 持续不健康的分钟数
+        #[serde(default = "default_unhealthy_minutes")]
+        minutes: u32,
+    },
+}
+
+/// 告警配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertConfig {
+    /// 是否启用告警
+    #[serde(default)]
+    pub enabled: bool,
+    /// 告警规则
+    #[serde(default = "default_rules")]
+    pub rules: Vec<AlertRule>,
+    /// 告警触发时 POST 的 Webhook 地址（可选）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// 错误率告警的冷却时间（分钟），冷却期内不重复触发
+    #[serde(default = "default_cooldown_minutes")]
+    pub cooldown_minutes: u32,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: default_rules(),
+            webhook_url: None,
+            cooldown_minutes: default_cooldown_minutes(),
+        }
+    }
+}
+
+/// 告警类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    ErrorRate,
+    DailySpend,
+    CredentialUnhealthy,
+}
+
+/// 告警事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    /// 唯一 ID
+    pub id: String,
+    /// 告警类型
+    pub kind: AlertKind,
+    /// 告警描述
+    pub message: String,
+    /// 触发时的观测值（错误率百分比 / 费用 / 不健康分钟数）
+    pub value: f64,
+    /// 规则阈值
+    pub threshold: f64,
+    /// 相关凭证 ID（仅凭证告警）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<String>,
+    /// 触发时间
+    pub fired_at: DateTime<Utc>,
+}
+
+impl Alert {
+    fn new(
+        kind: AlertKind,
+        message: String,
+        value: f64,
+        threshold: f64,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            message,
+            value,
+            threshold,
+            credential_id: None,
+            fired_at: now,
+        }
+    }
+}
+
+/// 凭证健康状态（告警检测输入）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CredentialStatus {
+    /// 凭证 ID
+    pub id: String,
+    /// 凭证名称（用于告警描述）
+    pub name: Option<String>,
+    /// 是否健康
+    pub healthy: bool,
+}
+
+/// 告警检测器
+///
+/// 记录各规则的上次触发时间和凭证开始不健康的时间，需要定期调用 `evaluate`
+#[derive(Debug, Default)]
+pub struct AlertMonitor {
+    /// 去重键 -> 上次触发时间
+    last_fired: HashMap<String, DateTime<Utc>>,
+    /// 凭证 ID -> 首次观测到不健康的时间
+    unhealthy_since: HashMap<String, DateTime<Utc>>,
+    /// 最近触发的告警（最新的在后）
+    history: VecDeque<Alert>,
+}
+
+impl AlertMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按配置的规则检测异常，返回本次新触发的告警
+    pub fn evaluate(
+        &mut self,
+        config: &AlertConfig,
+        now: DateTime<Utc>,
+        logs: &[RequestLog],
+        tokens: &[TokenUsageRecord],
+        pricing: &PricingTable,
+        credentials: &[CredentialStatus],
+    ) -> Vec<Alert> {
+        self.track_credentials(now, credentials);
+        if !config.enabled {
+            return Vec::new();
+        }
+
+        let cooldown = Duration::minutes(config.cooldown_minutes as i64);
+        let mut fired = Vec::new();
+        for rule in &config.rules {
+            match *rule {
+                AlertRule::ErrorRate {
+                    threshold_percent,
+                    window_minutes,
+                    min_requests,
+                } => {
+                    let since = now - Duration::minutes(window_minutes as i64);
+                    let window: Vec<&RequestLog> = logs
+                        .iter()
+                        .filter(|l| l.timestamp >= since && l.timestamp <= now)
+                        .collect();
+                    let total = window.len() as u64;
+                    if total == 0 || total < min_requests {
+                        continue;
+                    }
+                    let errors = window.iter().filter(|l| is_error(l.status)).count() as u64;
+                    let rate = errors as f64 * 100.0 / total as f64;
+                    if rate > threshold_percent && self.cooled_down("error_rate", now, cooldown) {
+                        fired.push(Alert::new(
+                            AlertKind::ErrorRate,
+                            format!(
+                                "最近 {} 分钟错误率 {:.1}%（{}/{}），超过阈值 {}%",
+                                window_minutes, rate, errors, total, threshold_percent
+                            ),
+                            rate,
+                            threshold_percent,
+                            now,
+                        ));
+                    }
+                }
+                AlertRule::DailySpend { threshold } => {
+                    let today = now.date_naive();
+                    let spend: f64 = tokens
+                        .iter()
+                        .filter(|r| r.timestamp.date_naive() == today)
+                        .filter_map(|r| pricing.cost_of(r))
+                        .sum();
+                    let key = format!("daily_spend:{}", today);
+                    if spend > threshold && !self.last_fired.contains_key(&key) {
+                        self.last_fired.insert(key, now);
+                        fired.push(Alert::new(
+                            AlertKind::DailySpend,
+                            format!(
+                                "今日估算费用 {:.2} {}，超过阈值 {:.2}",
+                                spend,
+                                pricing.currency(),
+                                threshold
+                            ),
+                            spend,
+                            threshold,
+                            now,
+                        ));
+                    }
+                }
+                AlertRule::CredentialUnhealthy { minutes } => {
+                    for credential in credentials.iter().filter(|c| !c.healthy) {
+                        let Some(since) = self.unhealthy_since.get(&credential.id) else {
+                            continue;
+                        };
+                        let elapsed = (now - *since).num_minutes();
+                        let key = format!("credential_unhealthy:{}", credential.id);
+                        if elapsed < minutes as i64 || self.last_fired.contains_key(&key) {
+                            continue;
+                        }
+                        self.last_fired.insert(key, now);
+                        let mut alert = Alert::new(
+                            AlertKind::CredentialUnhealthy,
+                            format!(
+                                "凭证 {} 已持续不健康 {} 分钟",
+                                credential.name.as_deref().unwrap_or(&credential.id),
+                                elapsed
+                            ),
+                            elapsed as f64,
+                            minutes as f64,
+                            now,
+                        );
+                        alert.credential_id = Some(credential.id.clone());
+                        fired.push(alert);
+                    }
+                }
+            }
+        }
+
+        // 清理过期的每日费用去重键
+        let today = format!("daily_spend:{}", now.date_naive());
+        self.last_fired
+            .retain(|key, _| !key.starts_with("daily_spend:") || *key == today);
+
+        for alert in &fired {
+            if self.history.len() >= MAX_ALERT_HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back(alert.clone());
+        }
+        fired
+    }
+
+    /// 最近触发的告警（最新的在前）
+    pub fn recent(&self) -> Vec<Alert> {
+        self.history.iter().rev().cloned().collect()
+    }
+
+    /// 更新凭证不健康的起始时间，恢复健康的凭证重新计时
+    fn track_credentials(&mut self, now: DateTime<Utc>, credentials: &[CredentialStatus]) {
+        for credential in credentials {
+            if credential.healthy {
+                self.unhealthy_since.remove(&credential.id);
+                self.last_fired
+                    .remove(&format!("credential_unhealthy:{}", credential.id));
+            } else {
+                self.unhealthy_since
+                    .entry(credential.id.clone())
+                    .or_insert(now);
+            }
+        }
+        self.unhealthy_since
+            .retain(|id, _| credentials.iter().any(|c| &c.id == id));
+    }
+
+    /// 冷却期已过时记录本次触发并返回 true
+    fn cooled_down(&mut self, key: &str, now: DateTime<Utc>, cooldown: Duration) -> bool {
+        match self.last_fired.get(key) {
+            Some(last) if now - *last < cooldown => false,
+            _ => {
+                self.last_fired.insert(key.to_string(), now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod alerts_tests {
+    use super::*;
+    use crate::telemetry::{PricingConfig, RequestStatus, TokenSource};
+    use proxycast_core::ProviderType;
+
+    fn log(at: DateTime<Utc>, status: RequestStatus) -> RequestLog {
+        let mut log = RequestLog::new(
+            uuid::Uuid::new_v4().to_string(),
+            ProviderType::Claude,
+            "claude-sonnet-4-5".to_string(),
+            false,
+        );
+        log.timestamp = at;
+        log.status = status;
+        log
+    }
+
+    fn config(rules: Vec<AlertRule>) -> AlertConfig {
+        AlertConfig {
+            enabled: true,
+            rules,
+            ..AlertConfig::default()
+        }
+    }
+
+    fn credential(id: &str, healthy: bool) -> CredentialStatus {
+        CredentialStatus {
+            id: id.to_string(),
+            name: None,
+            healthy,
+        }
+    }
+
+    #[test]
+    fn test_error_rate_alert_respects_min_requests_and_cooldown() {
+        let now = Utc::now();
+        let config = config(vec![AlertRule::ErrorRate {
+            threshold_percent: 20.0,
+            window_minutes: 5,
+            min_requests: 4,
+        }]);
+        let pricing = PricingTable::default();
+        let mut monitor = AlertMonitor::new();
+
+        let mut logs = vec![
+            log(now, RequestStatus::Failed),
+            log(now, RequestStatus::Success),
+            log(now, RequestStatus::Success),
+            // 窗口外的请求不计入
+            log(now - Duration::minutes(10), RequestStatus::Success),
+        ];
+        assert!(monitor
+            .evaluate(&config, now, &logs, &[], &pricing, &[])
+            .is_empty());
+
+        logs.push(log(now, RequestStatus::RateLimited));
+        let alerts = monitor.evaluate(&config, now, &logs, &[], &pricing, &[]);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, AlertKind::ErrorRate);
+        assert!((alerts[0].value - 50.0).abs() < 1e-9);
+
+        // 冷却期内不重复触发
+        let later = now + Duration::minutes(1);
+        assert!(monitor
+            .evaluate(&config, later, &logs, &[], &pricing, &[])
+            .is_empty());
+        assert_eq!(monitor.recent().len(), 1);
+    }
+
+    #[test]
+    fn test_daily_spend_alert_fires_once_per_day() {
+        let now = Utc::now();
+        let config = config(vec![AlertRule::DailySpend { threshold: 2.0 }]);
+        let pricing = PricingTable::new(&PricingConfig::default());
+        let mut record = TokenUsageRecord::new(
+            "t1".to_string(),
+            ProviderType::Claude,
+            "claude-sonnet-4-5".to_string(),
+            1_000_000,
+            0,
+            TokenSource::Actual,
+        );
+        record.timestamp = now;
+        let mut monitor = AlertMonitor::new();
+
+        let alerts = monitor.evaluate(&config, now, &[], &[record.clone()], &pricing, &[]);
+        assert_eq!(alerts.len(), 1);
+        assert!((alerts[0].value - 3.0).abs() < 1e-9);
+        assert!(monitor
+            .evaluate(&config, now, &[], &[record], &pricing, &[])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_credential_unhealthy_alert() {
+        let start = Utc::now();
+        let config = config(vec![AlertRule::CredentialUnhealthy { minutes: 10 }]);
+        let pricing = PricingTable::default();
+        let mut monitor = AlertMonitor::new();
+        let unhealthy = [credential("cred-a", false), credential("cred-b", true)];
+
+        assert!(monitor
+            .evaluate(&config, start, &[], &[], &pricing, &unhealthy)
+            .is_empty());
+        let later = start + Duration::minutes(11);
+        let alerts = monitor.evaluate(&config, later, &[], &[], &pricing, &unhealthy);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].credential_id.as_deref(), Some("cred-a"));
+        assert!(monitor
+            .evaluate(&config, later, &[], &[], &pricing, &unhealthy)
+            .is_empty());
+
+        // 恢复后重新计时
+        let recovered = [credential("cred-a", true)];
+        monitor.evaluate(&config, later, &[], &[], &pricing, &recovered);
+        let again = later + Duration::minutes(1);
+        assert!(monitor
+            .evaluate(&config, again, &[], &[], &pricing, &unhealthy)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_disabled_config_still_tracks_credentials() {
+        let start = Utc::now();
+        let mut config = config(vec![AlertRule::CredentialUnhealthy { minutes: 5 }]);
+        config.enabled = false;
+        let pricing = PricingTable::default();
+        let mut monitor = AlertMonitor::new();
+        let unhealthy = [credential("cred-a", false)];
+
+        assert!(monitor
+            .evaluate(&config, start, &[], &[], &pricing, &unhealthy)
+            .is_empty());
+        config.enabled = true;
+        let later = start + Duration::minutes(6);
+        assert_eq!(
+            monitor
+                .evaluate(&config, later, &[], &[], &pricing, &unhealthy)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_alert_config_deserialize() {
+        let config: AlertConfig = serde_json::from_str(
+            r#"{
+                "enabled": true,
+                "rules": [
+                    {"type": "error_rate", "threshold_percent": 10},
+                    {"type": "daily_spend", "threshold": 50},
+                    {"type": "credential_unhealthy"}
+                ],
+                "webhook_url": "https://example.com/hook"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.rules.len(), 3);
+        assert_eq!(
+            config.rules[0],
+            AlertRule::ErrorRate {
+                threshold_percent: 10.0,
+                window_minutes: 5,
+                min_requests: 10,
+            }
+        );
+        assert_eq!(config.cooldown_minutes, 30);
+        assert_eq!(AlertConfig::default().rules.len(), 2);
+    }
+}
@@ -0,0 +1,443 @@
+//! 费用估算模块
+//!
+//! 根据模型单价表估算 Token 使用费用，并按 Provider、凭证、模型和天汇总
+
+use super::tokens::{CacheTokens, TokenUsageRecord};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 模型单价（每百万 Token）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// 输入单价
+    pub input: f64,
+    /// 输出单价
+    pub output: f64,
+    /// 缓存读取单价（未设置时按输入单价计）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read: Option<f64>,
+    /// 缓存写入单价（未设置时按输入单价计）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write: Option<f64>,
+}
+
+impl ModelPrice {
+    /// 创建只区分输入 / 输出的单价
+    pub const fn new(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_read: None,
+            cache_write: None,
+        }
+    }
+
+    /// 设置缓存读取 / 写入单价
+    pub const fn with_cache(mut self, cache_read: f64, cache_write: f64) -> Self {
+        self.cache_read = Some(cache_read);
+        self.cache_write = Some(cache_write);
+        self
+    }
+
+    /// 计算输入费用
+    ///
+    /// 缓存 Token 已包含在 `input_tokens` 中，按各自单价单独计费
+    pub fn input_cost(&self, input_tokens: u64, cache: CacheTokens) -> f64 {
+        let cache_read = cache.cache_read_input_tokens as u64;
+        let cache_write = cache.cache_creation_input_tokens as u64;
+        let uncached = input_tokens.saturating_sub(cache_read + cache_write);
+
+        (uncached as f64 * self.input
+            + cache_read as f64 * self.cache_read.unwrap_or(self.input)
+            + cache_write as f64 * self.cache_write.unwrap_or(self.input))
+            / 1_000_000.0
+    }
+
+    /// 计算输出费用
+    pub fn output_cost(&self, output_tokens: u64) -> f64 {
+        output_tokens as f64 * self.output / 1_000_000.0
+    }
+}
+
+/// 内置单价表（美元 / 百万 Token），键为模型名前缀
+const BUILTIN_PRICES: &[(&str, ModelPrice)] = &[
+    (
+        "claude-opus-4",
+        ModelPrice::new(15.0, 75.0).with_cache(1.5, 18.75),
+    ),
+    (
+        "claude-opus-4-5",
+        ModelPrice::new(5.0, 25.0).with_cache(0.5, 6.25),
+    ),
+    (
+        "claude-sonnet-4",
+        ModelPrice::new(3.0, 15.0).with_cache(0.3, 3.75),
+    ),
+    (
+        "claude-3-7-sonnet",
+        ModelPrice::new(3.0, 15.0).with_cache(0.3, 3.75),
+    ),
+    (
+        "claude-haiku-4-5",
+        ModelPrice::new(1.0, 5.0).with_cache(0.1, 1.25),
+    ),
+    (
+        "claude-3-5-haiku",
+        ModelPrice::new(0.8, 4.0).with_cache(0.08, 1.0),
+    ),
+    ("gpt-4o", ModelPrice::new(2.5, 10.0).with_cache(1.25, 2.5)),
+    (
+        "gpt-4o-mini",
+        ModelPrice::new(0.15, 0.6).with_cache(0.075, 0.15),
+    ),
+    ("gpt-4.1", ModelPrice::new(2.0, 8.0).with_cache(0.5, 2.0)),
+    (
+        "gpt-4.1-mini",
+        ModelPrice::new(0.4, 1.6).with_cache(0.1, 0.4),
+    ),
+    ("gpt-5", ModelPrice::new(1.25, 10.0).with_cache(0.125, 1.25)),
+    (
+        "gpt-5-mini",
+        ModelPrice::new(0.25, 2.0).with_cache(0.025, 0.25),
+    ),
+    ("o3", ModelPrice::new(2.0, 8.0).with_cache(0.5, 2.0)),
+    ("o3-mini", ModelPrice::new(1.1, 4.4).with_cache(0.55, 1.1)),
+    ("o4-mini", ModelPrice::new(1.1, 4.4).with_cache(0.275, 1.1)),
+    ("gemini-2.5-pro", ModelPrice::new(1.25, 10.0)),
+    ("gemini-2.5-flash", ModelPrice::new(0.3, 2.5)),
+    ("gemini-2.5-flash-lite", ModelPrice::new(0.1, 0.4)),
+];
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+fn default_include_builtin() -> bool {
+    true
+}
+
+/// 费用估算配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricingConfig {
+    /// 货币单位（仅用于展示）
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// 是否使用内置单价表（同名条目以配置为准）
+    #[serde(default = "default_include_builtin")]
+    pub include_builtin: bool,
+    /// 模型单价，键为模型名或模型名前缀（最长前缀优先）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub models: BTreeMap<String, ModelPrice>,
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            currency: default_currency(),
+            include_builtin: default_include_builtin(),
+            models: BTreeMap::new(),
+        }
+    }
+}
+
+/// 模型单价表
+#[derive(Debug, Clone, PartialEq)]
+pub struct PricingTable {
+    currency: String,
+    /// 归一化后的模型名前缀 -> 单价
+    prices: BTreeMap<String, ModelPrice>,
+}
+
+impl PricingTable {
+    /// 根据配置构建单价表
+    pub fn new(config: &PricingConfig) -> Self {
+        let builtin = BUILTIN_PRICES
+            .iter()
+            .filter(|_| config.include_builtin)
+            .map(|(model, price)| (*model, *price));
+        let configured = config
+            .models
+            .iter()
+            .map(|(model, price)| (model.as_str(), *price));
+
+        Self {
+            currency: config.currency.clone(),
+            prices: builtin
+                .chain(configured)
+                .map(|(model, price)| (normalize_model(model), price))
+                .collect(),
+        }
+    }
+
+    /// 货币单位
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    /// 查找模型单价
+    ///
+    /// 忽略大小写和 `vendor/` 前缀，`.` 与 `-` 视为等价（`claude-sonnet-4.5` 匹配
+    /// `claude-sonnet-4-5`），无精确匹配时使用最长的前缀匹配。
+    pub fn price_for(&self, model: &str) -> Option<&ModelPrice> {
+        let model = normalize_model(model.rsplit('/').next().unwrap_or(model));
+        self.prices
+            .range(..=model.clone())
+            .rev()
+            .find(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .map(|(_, price)| price)
+    }
+
+    /// 估算单条记录的费用，未找到单价时返回 None
+    pub fn cost_of(&self, record: &TokenUsageRecord) -> Option<f64> {
+        self.price_for(&record.model).map(|price| {
+            price.input_cost(record.input_tokens as u64, record.cache)
+                + price.output_cost(record.output_tokens as u64)
+        })
+    }
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        Self::new(&PricingConfig::default())
+    }
+}
+
+/// 归一化模型名：小写，`.` 替换为 `-`
+fn normalize_model(model: &str) -> String {
+    model.trim().to_lowercase().replace('.', "-")
+}
+
+/// 费用汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostSummary {
+    /// 输入 Token 数
+    pub input_tokens: u64,
+    /// 输出 Token 数
+    pub output_tokens: u64,
+    /// 输入费用（含缓存读写）
+    pub input_cost: f64,
+    /// 输出费用
+    pub output_cost: f64,
+    /// 总费用
+    pub total_cost: f64,
+    /// 记录数量
+    pub record_count: u64,
+    /// 未找到单价、未计入费用的记录数
+    pub unpriced_count: u64,
+}
+
+impl CostSummary {
+    fn add(&mut self, record: &TokenUsageRecord, price: Option<&ModelPrice>) {
+        self.input_tokens += record.input_tokens as u64;
+        self.output_tokens += record.output_tokens as u64;
+        self.record_count += 1;
+
+        match price {
+            Some(price) => {
+                let input_cost = price.input_cost(record.input_tokens as u64, record.cache);
+                let output_cost = price.output_cost(record.output_tokens as u64);
+                self.input_cost += input_cost;
+                self.output_cost += output_cost;
+                self.total_cost += input_cost + output_cost;
+            }
+            None => self.unpriced_count += 1,
+        }
+    }
+}
+
+/// 单日费用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyCost {
+    /// 日期（UTC）
+    pub date: NaiveDate,
+    /// 费用汇总
+    #[serde(flatten)]
+    pub summary: CostSummary,
+}
+
+/// 费用报告
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostReport {
+    /// 货币单位
+    pub currency: String,
+    /// 时间段开始
+    pub period_start: Option<DateTime<Utc>>,
+    /// 时间段结束
+    pub period_end: Option<DateTime<Utc>>,
+    /// 总计
+    pub total: CostSummary,
+    /// 按 Provider 汇总
+    pub by_provider: BTreeMap<String, CostSummary>,
+    /// 按凭证汇总（仅包含记录了凭证 ID 的请求）
+    pub by_credential: BTreeMap<String, CostSummary>,
+    /// 按调用方工具汇总（仅包含识别出工具名称的请求）
+    #[serde(default)]
+    pub by_client: BTreeMap<String, CostSummary>,
+    /// 按模型汇总
+    pub by_model: BTreeMap<String, CostSummary>,
+    /// 按天汇总（日期升序）
+    pub by_day: Vec<DailyCost>,
+    /// 未找到单价的模型
+    pub unpriced_models: Vec<String>,
+}
+
+impl CostReport {
+    /// 从 Token 使用记录计算费用报告
+    pub fn from_records(records: &[TokenUsageRecord], pricing: &PricingTable) -> Self {
+        let mut report = Self {
+            currency: pricing.currency().to_string(),
+            ..Self::default()
+        };
+        let mut by_day: BTreeMap<NaiveDate, CostSummary> = BTreeMap::new();
+        let mut unpriced_models = BTreeSet::new();
+
+        for record in records {
+            let price = pricing.price_for(&record.model);
+            if price.is_none() {
+                unpriced_models.insert(record.model.clone());
+            }
+
+            report.total.add(record, price);
+            report
+                .by_provider
+                .entry(record.provider.to_string())
+                .or_default()
+                .add(record, price);
+            if let Some(credential_id) = &record.credential_id {
+                report
+                    .by_credential
+                    .entry(credential_id.clone())
+                    .or_default()
+                    .add(record, price);
+            }
+            if let Some(client_name) = &record.client_name {
+                report
+                    .by_client
+                    .entry(client_name.clone())
+                    .or_default()
+                    .add(record, price);
+            }
+            report
+                .by_model
+                .entry(record.model.clone())
+                .or_default()
+                .add(record, price);
+            by_day
+                .entry(record.timestamp.date_naive())
+                .or_default()
+                .add(record, price);
+        }
+
+        report.by_day = by_day
+            .into_iter()
+            .map(|(date, summary)| DailyCost { date, summary })
+            .collect();
+        report.unpriced_models = unpriced_models.into_iter().collect();
+        report
+    }
+
+    /// 设置报告的时间段
+    pub fn with_period(mut self, start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> Self {
+        self.period_start = start;
+        self.period_end = end;
+        self
+    }
+}
+
+#[cfg(test)]
+mod cost_tests {
+    use super::*;
+    use crate::telemetry::TokenSource;
+    use proxycast_core::ProviderType;
+
+    fn record(provider: ProviderType, model: &str, input: u32, output: u32) -> TokenUsageRecord {
+        TokenUsageRecord::new(
+            uuid::Uuid::new_v4().to_string(),
+            provider,
+            model.to_string(),
+            input,
+            output,
+            TokenSource::Actual,
+        )
+    }
+
+    #[test]
+    fn test_price_lookup_prefers_longest_prefix() {
+        let table = PricingTable::new(&PricingConfig::default());
+
+        let opus = table.price_for("claude-opus-4-1-20250805").unwrap();
+        assert_eq!(opus.input, 15.0);
+        let opus_45 = table.price_for("claude-opus-4.5").unwrap();
+        assert_eq!(opus_45.input, 5.0);
+        let mini = table.price_for("openai/GPT-4o-mini-2024-07-18").unwrap();
+        assert_eq!(mini.output, 0.6);
+        assert!(table.price_for("unknown-model").is_none());
+    }
+
+    #[test]
+    fn test_configured_prices_override_builtin() {
+        let mut config = PricingConfig::default();
+        config
+            .models
+            .insert("claude-sonnet-4".to_string(), ModelPrice::new(1.0, 2.0));
+        config
+            .models
+            .insert("my-local-model".to_string(), ModelPrice::new(0.0, 0.0));
+
+        let table = PricingTable::new(&config);
+        assert_eq!(table.price_for("claude-sonnet-4-5").unwrap().input, 1.0);
+        assert!(table.price_for("my-local-model").is_some());
+
+        config.include_builtin = false;
+        let table = PricingTable::new(&config);
+        assert!(table.price_for("gpt-4o").is_none());
+    }
+
+    #[test]
+    fn test_input_cost_with_cache_tokens() {
+        let price = ModelPrice::new(3.0, 15.0).with_cache(0.3, 3.75);
+        let cache = CacheTokens {
+            cache_creation_input_tokens: 100_000,
+            cache_read_input_tokens: 800_000,
+        };
+
+        // 100k 未缓存 * 3 + 800k 读取 * 0.3 + 100k 写入 * 3.75
+        let cost = price.input_cost(1_000_000, cache);
+        assert!((cost - (0.3 + 0.24 + 0.375)).abs() < 1e-9);
+        assert!((price.output_cost(1_000_000) - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cost_report_groups_records() {
+        let table = PricingTable::new(&PricingConfig::default());
+        let records = vec![
+            record(
+                ProviderType::Claude,
+                "claude-sonnet-4-5",
+                1_000_000,
+                100_000,
+            )
+            .with_credential_id("cred-a".to_string())
+            .with_client_name("claude-code".to_string()),
+            record(ProviderType::Claude, "claude-sonnet-4-5", 1_000_000, 0)
+                .with_credential_id("cred-b".to_string())
+                .with_client_name("claude-code".to_string()),
+            record(ProviderType::Kiro, "mystery-model", 500, 500),
+        ];
+
+        let report = CostReport::from_records(&records, &table);
+        assert_eq!(report.currency, "USD");
+        assert!((report.total.total_cost - 7.5).abs() < 1e-9);
+        assert_eq!(report.total.record_count, 3);
+        assert_eq!(report.total.unpriced_count, 1);
+        assert!((report.by_provider["claude"].total_cost - 7.5).abs() < 1e-9);
+        assert!((report.by_credential["cred-a"].total_cost - 4.5).abs() < 1e-9);
+        assert_eq!(report.by_credential.len(), 2);
+        assert!((report.by_client["claude-code"].total_cost - 7.5).abs() < 1e-9);
+        assert_eq!(report.by_client.len(), 1);
+        assert_eq!(report.by_day.len(), 1);
+        assert_eq!(report.by_day[0].summary.record_count, 3);
+        assert_eq!(report.unpriced_models, vec!["mystery-model".to_string()]);
+    }
+}
@@ -0,0 +1,544 @@
+//! 延迟分位数统计
+//!
+//! 使用对数-线性直方图流式估算端到端延迟和首字节延迟（TTFB）的 p50 / p95 / p99，
+//! 按分钟分桶存储，支持按 Provider、模型和凭证分组并按时间范围查询。
+//!
+//! 直方图在 64ms 以下精确计数，之上每个 2 的幂区间再等分为 32 个子桶，
+//! 相对误差不超过约 3%，内存占用与请求数无关。
+
+use super::types::TimeRange;
+use chrono::{DateTime, Duration, Utc};
+use proxycast_core::ProviderType;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+
+/// 精确计数的上限（毫秒），低于该值的延迟每毫秒一个桶
+const LINEAR_LIMIT: u64 = 64;
+/// 每个 2 的幂区间的子桶数量位数（2^5 = 32 个子桶）
+const SUB_BUCKET_BITS: u32 = 5;
+/// 时间分桶粒度（秒）
+const TIME_BUCKET_SECS: i64 = 60;
+
+/// 延迟直方图
+///
+/// 稀疏存储各桶计数，可合并，用于流式估算分位数
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    buckets: BTreeMap<u16, u64>,
+    count: u64,
+    sum: u64,
+    min: u64,
+    max: u64,
+}
+
+impl LatencyHistogram {
+    /// 创建空直方图
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一个延迟样本（毫秒）
+    pub fn record(&mut self, value_ms: u64) {
+        *self.buckets.entry(bucket_index(value_ms)).or_default() += 1;
+        if self.count == 0 {
+            self.min = value_ms;
+            self.max = value_ms;
+        } else {
+            self.min = self.min.min(value_ms);
+            self.max = self.max.max(value_ms);
+        }
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value_ms);
+    }
+
+    /// 合并另一个直方图
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if other.count == 0 {
+            return;
+        }
+        for (index, count) in &other.buckets {
+            *self.buckets.entry(*index).or_default() += count;
+        }
+        if self.count == 0 {
+            self.min = other.min;
+            self.max = other.max;
+        } else {
+            self.min = self.min.min(other.min);
+            self.max = self.max.max(other.max);
+        }
+        self.count += other.count;
+        self.sum = self.sum.saturating_add(other.sum);
+    }
+
+    /// 样本数
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// 检查是否为空
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// 估算分位数（`quantile` 取值 0.0 - 1.0），空直方图返回 None
+    pub fn percentile(&self, quantile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in &self.buckets {
+            seen += count;
+            if seen >= rank {
+                return Some(bucket_midpoint(*index).clamp(self.min, self.max));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// 汇总为分位数统计
+    pub fn summary(&self) -> LatencyPercentiles {
+        if self.count == 0 {
+            return LatencyPercentiles::default();
+        }
+        LatencyPercentiles {
+            count: self.count,
+            min_ms: self.min,
+            max_ms: self.max,
+            mean_ms: self.sum as f64 / self.count as f64,
+            p50_ms: self.percentile(0.50).unwrap_or_default(),
+            p95_ms: self.percentile(0.95).unwrap_or_default(),
+            p99_ms: self.percentile(0.99).unwrap_or_default(),
+        }
+    }
+}
+
+/// 计算延迟所在的桶序号
+fn bucket_index(value_ms: u64) -> u16 {
+    if value_ms < LINEAR_LIMIT {
+        return value_ms as u16;
+    }
+    let exponent = 63 - value_ms.leading_zeros();
+    let shift = exponent - SUB_BUCKET_BITS;
+    let sub = (value_ms >> shift) & ((1 << SUB_BUCKET_BITS) - 1);
+    let linear_exponent = LINEAR_LIMIT.trailing_zeros();
+    (LINEAR_LIMIT + u64::from(exponent - linear_exponent) * (1 << SUB_BUCKET_BITS) + sub) as u16
+}
+
+/// 桶的代表值（区间中点）
+fn bucket_midpoint(index: u16) -> u64 {
+    let index = u64::from(index);
+    if index < LINEAR_LIMIT {
+        return index;
+    }
+    let offset = index - LINEAR_LIMIT;
+    let exponent = u64::from(LINEAR_LIMIT.trailing_zeros()) + (offset >> SUB_BUCKET_BITS);
+    let shift = exponent - u64::from(SUB_BUCKET_BITS);
+    let sub = offset & ((1 << SUB_BUCKET_BITS) - 1);
+    let lower = ((1 << SUB_BUCKET_BITS) + sub) << shift;
+    lower + ((1 << shift) >> 1)
+}
+
+/// 延迟分位数统计（毫秒）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    /// 样本数
+    pub count: u64,
+    /// 最小延迟
+    pub min_ms: u64,
+    /// 最大延迟
+    pub max_ms: u64,
+    /// 平均延迟
+    pub mean_ms: f64,
+    /// 中位数
+    pub p50_ms: u64,
+    /// 95 分位
+    pub p95_ms: u64,
+    /// 99 分位
+    pub p99_ms: u64,
+}
+
+/// 延迟分组维度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyGroupBy {
+    /// 按 Provider 分组
+    #[default]
+    Provider,
+    /// 按模型分组
+    Model,
+    /// 按凭证分组
+    Credential,
+    /// 按 Provider + 模型 + 凭证组合分组
+    Combined,
+}
+
+/// 单个分组的延迟统计
+///
+/// 未参与分组的维度为 None
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    /// Provider 类型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<ProviderType>,
+    /// 模型名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 凭证 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<String>,
+    /// 端到端延迟
+    pub total: LatencyPercentiles,
+    /// 首字节延迟（仅流式请求）
+    pub ttfb: LatencyPercentiles,
+}
+
+/// 延迟分位数报告
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyReport {
+    /// 分组维度
+    pub group_by: LatencyGroupBy,
+    /// 全部请求的延迟统计
+    pub overall: LatencyStats,
+    /// 各分组的延迟统计（按样本数降序）
+    pub groups: Vec<LatencyStats>,
+}
+
+/// 分组键（Provider, 模型, 凭证 ID），未参与分组的维度为 None
+type GroupKey = (Option<ProviderType>, Option<String>, Option<String>);
+
+/// 延迟样本的归属维度
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct LatencyKey {
+    provider: ProviderType,
+    model: String,
+    credential_id: Option<String>,
+}
+
+impl LatencyKey {
+    /// 按分组维度投影，未参与分组的维度置空
+    fn project(&self, group_by: LatencyGroupBy) -> GroupKey {
+        match group_by {
+            LatencyGroupBy::Provider => (Some(self.provider), None, None),
+            LatencyGroupBy::Model => (None, Some(self.model.clone()), None),
+            LatencyGroupBy::Credential => (None, None, self.credential_id.clone()),
+            LatencyGroupBy::Combined => (
+                Some(self.provider),
+                Some(self.model.clone()),
+                self.credential_id.clone(),
+            ),
+        }
+    }
+}
+
+/// 同一维度下的端到端与首字节直方图
+#[derive(Debug, Clone, Default)]
+struct LatencyHistograms {
+    total: LatencyHistogram,
+    ttfb: LatencyHistogram,
+}
+
+impl LatencyHistograms {
+    fn merge(&mut self, other: &LatencyHistograms) {
+        self.total.merge(&other.total);
+        self.ttfb.merge(&other.ttfb);
+    }
+
+    fn into_stats(self, (provider, model, credential_id): GroupKey) -> LatencyStats {
+        LatencyStats {
+            provider,
+            model,
+            credential_id,
+            total: self.total.summary(),
+            ttfb: self.ttfb.summary(),
+        }
+    }
+}
+
+/// 延迟追踪器
+///
+/// 按分钟分桶保存各维度的直方图，与请求日志条数上限无关
+#[derive(Debug)]
+pub struct LatencyTracker {
+    /// 分钟起始时间戳 -> 各维度直方图
+    buckets: BTreeMap<i64, HashMap<LatencyKey, LatencyHistograms>>,
+    /// 保留时长
+    retention: Duration,
+}
+
+impl LatencyTracker {
+    /// 创建新的延迟追踪器
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            buckets: BTreeMap::new(),
+            retention,
+        }
+    }
+
+    /// 记录端到端延迟
+    pub fn record_total(
+        &mut self,
+        at: DateTime<Utc>,
+        provider: ProviderType,
+        model: &str,
+        credential_id: Option<&str>,
+        duration_ms: u64,
+    ) {
+        self.entry(at, provider, model, credential_id)
+            .total
+            .record(duration_ms);
+        self.prune(Utc::now() - self.retention);
+    }
+
+    /// 记录首字节延迟
+    pub fn record_ttfb(
+        &mut self,
+        at: DateTime<Utc>,
+        provider: ProviderType,
+        model: &str,
+        credential_id: Option<&str>,
+        ttfb_ms: u64,
+    ) {
+        self.entry(at, provider, model, credential_id)
+            .ttfb
+            .record(ttfb_ms);
+        self.prune(Utc::now() - self.retention);
+    }
+
+    fn entry(
+        &mut self,
+        at: DateTime<Utc>,
+        provider: ProviderType,
+        model: &str,
+        credential_id: Option<&str>,
+    ) -> &mut LatencyHistograms {
+        let key = LatencyKey {
+            provider,
+            model: model.to_string(),
+            credential_id: credential_id.map(str::to_string),
+        };
+        self.buckets
+            .entry(time_bucket(&at))
+            .or_default()
+            .entry(key)
+            .or_default()
+    }
+
+    /// 删除早于 `cutoff` 的分桶，返回删除的分桶数
+    pub fn prune(&mut self, cutoff: DateTime<Utc>) -> usize {
+        let cutoff = time_bucket(&cutoff);
+        let initial_len = self.buckets.len();
+        self.buckets = self.buckets.split_off(&cutoff);
+        initial_len - self.buckets.len()
+    }
+
+    /// 清空所有数据
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+
+    /// 查询时间范围内的延迟分位数
+    ///
+    /// 时间范围按分钟对齐，`range` 为 None 时统计全部数据
+    pub fn report(&self, range: Option<TimeRange>, group_by: LatencyGroupBy) -> LatencyReport {
+        let bounds = match range {
+            Some(r) => (
+                Bound::Included(time_bucket(&r.start)),
+                Bound::Included(time_bucket(&r.end)),
+            ),
+            None => (Bound::Unbounded, Bound::Unbounded),
+        };
+
+        let mut overall = LatencyHistograms::default();
+        let mut grouped: HashMap<GroupKey, LatencyHistograms> = HashMap::new();
+        for keys in self.buckets.range(bounds).map(|(_, keys)| keys) {
+            for (key, histograms) in keys {
+                overall.merge(histograms);
+                grouped
+                    .entry(key.project(group_by))
+                    .or_default()
+                    .merge(histograms);
+            }
+        }
+
+        let mut groups: Vec<LatencyStats> = grouped
+            .into_iter()
+            .map(|(key, histograms)| histograms.into_stats(key))
+            .collect();
+        groups.sort_by(|a, b| {
+            (b.total.count + b.ttfb.count)
+                .cmp(&(a.total.count + a.ttfb.count))
+                .then_with(|| a.model.cmp(&b.model))
+                .then_with(|| a.credential_id.cmp(&b.credential_id))
+        });
+
+        LatencyReport {
+            group_by,
+            overall: overall.into_stats((None, None, None)),
+            groups,
+        }
+    }
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new(Duration::days(7))
+    }
+}
+
+/// 计算时间戳所在分钟的起始秒数
+fn time_bucket(at: &DateTime<Utc>) -> i64 {
+    at.timestamp().div_euclid(TIME_BUCKET_SECS) * TIME_BUCKET_SECS
+}
+
+#[cfg(test)]
+mod latency_tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_index_is_monotonic_and_bounded() {
+        let mut previous = 0;
+        for value in (0..100_000).chain([u64::MAX / 2, u64::MAX]) {
+            let index = bucket_index(value);
+            assert!(index >= previous);
+            previous = index;
+            let midpoint = bucket_midpoint(index);
+            let error = midpoint.abs_diff(value) as f64 / value.max(1) as f64;
+            assert!(error <= 1.0 / 32.0, "value={value} midpoint={midpoint}");
+        }
+    }
+
+    #[test]
+    fn test_histogram_exact_below_linear_limit() {
+        let mut histogram = LatencyHistogram::new();
+        for value in 1..=50 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.percentile(0.5), Some(25));
+        assert_eq!(histogram.percentile(0.99), Some(50));
+        assert_eq!(histogram.percentile(0.0), Some(1));
+    }
+
+    #[test]
+    fn test_histogram_percentiles_within_error() {
+        let mut histogram = LatencyHistogram::new();
+        for value in 1..=10_000 {
+            histogram.record(value);
+        }
+        let summary = histogram.summary();
+        assert_eq!(summary.count, 10_000);
+        assert_eq!(summary.min_ms, 1);
+        assert_eq!(summary.max_ms, 10_000);
+        assert!((summary.mean_ms - 5000.5).abs() < 1e-9);
+        for (actual, expected) in [
+            (summary.p50_ms, 5000.0),
+            (summary.p95_ms, 9500.0),
+            (summary.p99_ms, 9900.0),
+        ] {
+            assert!((actual as f64 - expected).abs() / expected <= 0.035);
+        }
+    }
+
+    #[test]
+    fn test_histogram_merge() {
+        let mut a = LatencyHistogram::new();
+        let mut b = LatencyHistogram::new();
+        a.record(10);
+        b.record(2000);
+        b.record(5);
+        a.merge(&b);
+        assert_eq!(a.count(), 3);
+        assert_eq!(a.summary().min_ms, 5);
+        assert_eq!(a.summary().max_ms, 2000);
+        assert_eq!(a.percentile(0.5), Some(10));
+    }
+
+    #[test]
+    fn test_empty_histogram() {
+        let histogram = LatencyHistogram::new();
+        assert!(histogram.is_empty());
+        assert_eq!(histogram.percentile(0.5), None);
+        assert_eq!(histogram.summary(), LatencyPercentiles::default());
+    }
+
+    #[test]
+    fn test_tracker_groups_by_dimension() {
+        let mut tracker = LatencyTracker::default();
+        let now = Utc::now();
+        tracker.record_total(
+            now,
+            ProviderType::Claude,
+            "claude-sonnet-4",
+            Some("c1"),
+            100,
+        );
+        tracker.record_total(
+            now,
+            ProviderType::Claude,
+            "claude-sonnet-4",
+            Some("c2"),
+            300,
+        );
+        tracker.record_total(now, ProviderType::OpenAI, "gpt-4o", Some("c3"), 50);
+        tracker.record_ttfb(now, ProviderType::Claude, "claude-sonnet-4", Some("c1"), 20);
+
+        let report = tracker.report(None, LatencyGroupBy::Provider);
+        assert_eq!(report.overall.total.count, 3);
+        assert_eq!(report.overall.ttfb.count, 1);
+        assert_eq!(report.groups.len(), 2);
+        assert_eq!(report.groups[0].provider, Some(ProviderType::Claude));
+        assert_eq!(report.groups[0].total.count, 2);
+        assert_eq!(report.groups[0].ttfb.p50_ms, 20);
+        assert!(report.groups[0].model.is_none());
+
+        let report = tracker.report(None, LatencyGroupBy::Credential);
+        assert_eq!(report.groups.len(), 3);
+
+        let report = tracker.report(None, LatencyGroupBy::Combined);
+        assert!(report
+            .groups
+            .iter()
+            .all(|g| g.provider.is_some() && g.model.is_some() && g.credential_id.is_some()));
+    }
+
+    #[test]
+    fn test_tracker_filters_by_time_range() {
+        let mut tracker = LatencyTracker::default();
+        let now = Utc::now();
+        tracker.record_total(
+            now - Duration::hours(3),
+            ProviderType::Claude,
+            "m",
+            None,
+            100,
+        );
+        tracker.record_total(now, ProviderType::Claude, "m", None, 200);
+
+        let report = tracker.report(Some(TimeRange::last_hours(1)), LatencyGroupBy::Model);
+        assert_eq!(report.overall.total.count, 1);
+        assert_eq!(report.overall.total.p50_ms, 200);
+
+        let report = tracker.report(None, LatencyGroupBy::Model);
+        assert_eq!(report.overall.total.count, 2);
+    }
+
+    #[test]
+    fn test_tracker_prunes_expired_buckets() {
+        let mut tracker = LatencyTracker::new(Duration::hours(1));
+        let now = Utc::now();
+        tracker.record_total(
+            now - Duration::hours(2),
+            ProviderType::Claude,
+            "m",
+            None,
+            100,
+        );
+        tracker.record_total(now, ProviderType::Claude, "m", None, 200);
+
+        let report = tracker.report(None, LatencyGroupBy::Model);
+        assert_eq!(report.overall.total.count, 1);
+        assert_eq!(tracker.prune(now + Duration::minutes(2)), 1);
+        assert_eq!(tracker.report(None, LatencyGroupBy::Model).groups.len(), 0);
+    }
+}
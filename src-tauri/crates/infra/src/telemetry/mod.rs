@@ -1,17 +1,35 @@
 //! 监控与日志模块
 //!
-//! 提供请求日志记录、统计聚合和 Token 追踪功能
+//! 提供请求日志记录、统计聚合、Token 追踪、延迟分位数、费用估算、用量报告、异常告警、OTLP 链路追踪导出和终端会话指标功能
 
+mod alerts;
+mod cost;
+mod latency;
 mod logger;
+mod otlp;
+mod report;
 mod stats;
+mod terminal;
 mod tokens;
 mod types;
 
+pub use alerts::{Alert, AlertConfig, AlertKind, AlertMonitor, AlertRule, CredentialStatus};
+pub use cost::{CostReport, CostSummary, DailyCost, ModelPrice, PricingConfig, PricingTable};
+pub use latency::{
+    LatencyGroupBy, LatencyHistogram, LatencyPercentiles, LatencyReport, LatencyStats,
+    LatencyTracker,
+};
 pub use logger::{LogRotationConfig, LoggerError, RequestLogger};
+pub use otlp::{otlp_layer, OtlpConfig, OtlpLayer, ROOT_SPAN_NAME};
+pub use report::{
+    write_due_reports, ReportConfig, ReportFormat, ReportGenerator, ReportPeriod, UsageRankItem,
+    UsageReport, DEFAULT_CLIENT_NAME, UNKNOWN_TOOL_NAME,
+};
 pub use stats::StatsAggregator;
+pub use terminal::{TerminalMetrics, TerminalMetricsSnapshot};
 pub use tokens::{
-    ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenSource, TokenStatsSummary,
-    TokenTracker, TokenUsageRecord,
+    CacheTokens, ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenEstimator,
+    TokenSource, TokenStatsSummary, TokenTracker, TokenUsageRecord,
 };
 pub use types::{ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange};
 
@@ -0,0 +1,620 @@
+//! OTLP 链路追踪导出模块
+//!
+//! 基于 `tracing` span 构建请求链路，并以 OTLP/HTTP JSON 格式推送到 Collector。
+//!
+//! 只有以 [`ROOT_SPAN_NAME`] 为根的 span 树会被导出，其余 span 被忽略，
+//! 因此业务代码可以放心使用 `tracing::info_span!` 而不会产生噪声。
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// 代理请求根 span 名称
+pub const ROOT_SPAN_NAME: &str = "proxy.request";
+
+/// 缓冲区最大 span 数，超出后丢弃最旧的 span（避免 Collector 不可用时内存无限增长）
+const MAX_BUFFERED_SPANS: usize = 10_000;
+
+/// OTLP 导出配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OtlpConfig {
+    /// 是否启用 OTLP 导出
+    #[serde(default)]
+    pub enabled: bool,
+    /// OTLP/HTTP 端点（如 http://localhost:4318），自动追加 /v1/traces
+    #[serde(default = "default_otlp_endpoint")]
+    pub endpoint: String,
+    /// 上报的服务名称（resource 属性 service.name）
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    /// 附加请求头（如认证信息）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// 导出间隔（毫秒）
+    #[serde(default = "default_export_interval_ms")]
+    pub export_interval_ms: u64,
+    /// 单次导出的最大 span 数
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+}
+
+fn default_otlp_endpoint() -> String {
+    "http://localhost:4318".to_string()
+}
+
+fn default_service_name() -> String {
+    "proxycast".to_string()
+}
+
+fn default_export_interval_ms() -> u64 {
+    5000
+}
+
+fn default_max_batch_size() -> usize {
+    512
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_otlp_endpoint(),
+            service_name: default_service_name(),
+            headers: HashMap::new(),
+            export_interval_ms: default_export_interval_ms(),
+            max_batch_size: default_max_batch_size(),
+        }
+    }
+}
+
+impl OtlpConfig {
+    /// 获取 traces 导出地址
+    pub fn traces_url(&self) -> String {
+        let endpoint = self.endpoint.trim_end_matches('/');
+        if endpoint.ends_with("/v1/traces") {
+            endpoint.to_string()
+        } else {
+            format!("{}/v1/traces", endpoint)
+        }
+    }
+}
+
+/// OTLP span 类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Internal = 1,
+    Server = 2,
+    Client = 3,
+}
+
+impl SpanKind {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "internal" => Some(SpanKind::Internal),
+            "server" => Some(SpanKind::Server),
+            "client" => Some(SpanKind::Client),
+            _ => None,
+        }
+    }
+}
+
+/// span 属性值
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    String(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+impl AttributeValue {
+    fn to_otlp(&self) -> serde_json::Value {
+        match self {
+            // OTLP JSON 中 64 位整数以字符串表示
+            AttributeValue::String(v) => serde_json::json!({ "stringValue": v }),
+            AttributeValue::Int(v) => serde_json::json!({ "intValue": v.to_string() }),
+            AttributeValue::Double(v) => serde_json::json!({ "doubleValue": v }),
+            AttributeValue::Bool(v) => serde_json::json!({ "boolValue": v }),
+        }
+    }
+}
+
+/// 已结束的 span 数据
+#[derive(Debug, Clone)]
+pub struct SpanData {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub kind: SpanKind,
+    pub start_time_unix_nano: u64,
+    pub end_time_unix_nano: u64,
+    pub attributes: Vec<(String, AttributeValue)>,
+    /// 错误信息（设置后 span 状态为 ERROR）
+    pub error: Option<String>,
+}
+
+impl SpanData {
+    fn to_otlp(&self) -> serde_json::Value {
+        let mut span = serde_json::json!({
+            "traceId": self.trace_id,
+            "spanId": self.span_id,
+            "name": self.name,
+            "kind": self.kind as i32,
+            "startTimeUnixNano": self.start_time_unix_nano.to_string(),
+            "endTimeUnixNano": self.end_time_unix_nano.to_string(),
+            "attributes": self
+                .attributes
+                .iter()
+                .map(|(key, value)| serde_json::json!({ "key": key, "value": value.to_otlp() }))
+                .collect::<Vec<_>>(),
+            "status": match &self.error {
+                Some(message) => serde_json::json!({ "code": 2, "message": message }),
+                None => serde_json::json!({ "code": 1 }),
+            },
+        });
+        if let Some(parent) = &self.parent_span_id {
+            span["parentSpanId"] = serde_json::json!(parent);
+        }
+        span
+    }
+}
+
+/// 构建 OTLP/HTTP JSON 导出请求体
+pub fn build_export_request(service_name: &str, spans: &[SpanData]) -> serde_json::Value {
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{
+                    "key": "service.name",
+                    "value": { "stringValue": service_name }
+                }]
+            },
+            "scopeSpans": [{
+                "scope": {
+                    "name": "proxycast",
+                    "version": env!("CARGO_PKG_VERSION")
+                },
+                "spans": spans.iter().map(SpanData::to_otlp).collect::<Vec<_>>()
+            }]
+        }]
+    })
+}
+
+/// OTLP 导出器
+///
+/// 缓冲已结束的 span，由后台任务按间隔或批量大小推送到 Collector。
+/// 后台任务在第一个 span 结束时懒启动（此时必然处于 tokio 运行时中）。
+pub struct OtlpExporter {
+    config: OtlpConfig,
+    client: reqwest::Client,
+    buffer: Mutex<VecDeque<SpanData>>,
+    notify: tokio::sync::Notify,
+    started: AtomicBool,
+}
+
+impl OtlpExporter {
+    pub fn new(config: OtlpConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            buffer: Mutex::new(VecDeque::new()),
+            notify: tokio::sync::Notify::new(),
+            started: AtomicBool::new(false),
+        }
+    }
+
+    /// 添加已结束的 span
+    pub fn push(self: &Arc<Self>, span: SpanData) {
+        let should_flush = {
+            let mut buffer = self.buffer.lock();
+            if buffer.len() >= MAX_BUFFERED_SPANS {
+                buffer.pop_front();
+            }
+            buffer.push_back(span);
+            buffer.len() >= self.config.max_batch_size
+        };
+
+        self.ensure_started();
+        if should_flush {
+            self.notify.notify_one();
+        }
+    }
+
+    /// 取出待导出的 span
+    fn drain(&self) -> Vec<SpanData> {
+        let mut buffer = self.buffer.lock();
+        let count = buffer.len().min(self.config.max_batch_size.max(1));
+        buffer.drain(..count).collect()
+    }
+
+    fn ensure_started(self: &Arc<Self>) {
+        if self.started.load(Ordering::Acquire) {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if self.started.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let exporter = self.clone();
+        handle.spawn(async move {
+            let interval = Duration::from_millis(exporter.config.export_interval_ms.max(100));
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = exporter.notify.notified() => {}
+                }
+                loop {
+                    let batch = exporter.drain();
+                    if batch.is_empty() {
+                        break;
+                    }
+                    exporter.export(&batch).await;
+                }
+            }
+        });
+    }
+
+    /// 推送一批 span 到 Collector
+    async fn export(&self, spans: &[SpanData]) {
+        let body = build_export_request(&self.config.service_name, spans);
+        let mut request = self.client.post(self.config.traces_url()).json(&body);
+        for (key, value) in &self.config.headers {
+            request = request.header(key, value);
+        }
+
+        match request.send().await {
+            Ok(resp) if resp.status().is_success() => {
+                tracing::debug!("[OTLP] 已导出 {} 个 span", spans.len());
+            }
+            Ok(resp) => {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                tracing::warn!("[OTLP] 导出失败: {} - {}", status, body);
+            }
+            Err(e) => {
+                tracing::warn!("[OTLP] 导出失败: {}", e);
+            }
+        }
+    }
+}
+
+/// 存放在 span 扩展中的进行中 span 状态
+struct SpanState {
+    trace_id: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+    start_time_unix_nano: u64,
+    kind: SpanKind,
+    attributes: Vec<(String, AttributeValue)>,
+    error: Option<String>,
+}
+
+/// 字段访问器：将 tracing 字段转换为 span 属性
+///
+/// 特殊字段：
+/// - `otel.kind`: span 类型（server / client / internal）
+/// - `error`: 错误信息，设置后 span 状态为 ERROR
+struct SpanFieldVisitor<'a> {
+    state: &'a mut SpanState,
+}
+
+impl SpanFieldVisitor<'_> {
+    fn set(&mut self, field: &Field, value: AttributeValue) {
+        let name = field.name();
+        match (name, &value) {
+            ("otel.kind", AttributeValue::String(kind)) => {
+                if let Some(kind) = SpanKind::parse(kind) {
+                    self.state.kind = kind;
+                }
+            }
+            ("error", AttributeValue::String(message)) => {
+                self.state.error = Some(message.clone());
+            }
+            _ => {
+                if let Some(existing) = self.state.attributes.iter_mut().find(|(k, _)| k == name) {
+                    existing.1 = value;
+                } else {
+                    self.state.attributes.push((name.to_string(), value));
+                }
+            }
+        }
+    }
+}
+
+impl Visit for SpanFieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, AttributeValue::String(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, AttributeValue::Int(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, AttributeValue::Int(value as i64));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field, AttributeValue::Double(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, AttributeValue::Bool(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.set(field, AttributeValue::String(format!("{:?}", value)));
+    }
+}
+
+/// 将代理请求 span 树导出为 OTLP 的 tracing Layer
+pub struct OtlpLayer {
+    exporter: Arc<OtlpExporter>,
+}
+
+impl OtlpLayer {
+    pub fn new(exporter: Arc<OtlpExporter>) -> Self {
+        Self { exporter }
+    }
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        // 子 span 继承父 span 的 trace_id；根 span 仅接受代理请求
+        let parent = span.parent();
+        let (trace_id, parent_span_id, kind) = match &parent {
+            Some(parent) => {
+                let extensions = parent.extensions();
+                let Some(parent_state) = extensions.get::<SpanState>() else {
+                    return;
+                };
+                (
+                    parent_state.trace_id.clone(),
+                    Some(parent_state.span_id.clone()),
+                    SpanKind::Internal,
+                )
+            }
+            None if span.name() == ROOT_SPAN_NAME => (new_trace_id(), None, SpanKind::Server),
+            None => return,
+        };
+
+        let mut state = SpanState {
+            trace_id,
+            span_id: new_span_id(),
+            parent_span_id,
+            start_time_unix_nano: now_unix_nano(),
+            kind,
+            attributes: Vec::new(),
+            error: None,
+        };
+        attrs.record(&mut SpanFieldVisitor { state: &mut state });
+        span.extensions_mut().insert(state);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(state) = extensions.get_mut::<SpanState>() {
+            values.record(&mut SpanFieldVisitor { state });
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(state) = span.extensions_mut().remove::<SpanState>() else {
+            return;
+        };
+
+        self.exporter.push(SpanData {
+            trace_id: state.trace_id,
+            span_id: state.span_id,
+            parent_span_id: state.parent_span_id,
+            name: span.name().to_string(),
+            kind: state.kind,
+            start_time_unix_nano: state.start_time_unix_nano,
+            end_time_unix_nano: now_unix_nano(),
+            attributes: state.attributes,
+            error: state.error,
+        });
+    }
+}
+
+/// 创建 OTLP 链路追踪 Layer，未启用时返回 `None`
+///
+/// 由应用与日志输出等其他 Layer 组合成全局 subscriber；
+/// 全局 subscriber 只能设置一次，修改配置后需重启应用生效。
+pub fn otlp_layer(config: &OtlpConfig) -> Option<OtlpLayer> {
+    config
+        .enabled
+        .then(|| OtlpLayer::new(Arc::new(OtlpExporter::new(config.clone()))))
+}
+
+fn new_trace_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn new_span_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+fn now_unix_nano() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn collect_spans(f: impl FnOnce()) -> Vec<SpanData> {
+        let exporter = Arc::new(OtlpExporter::new(OtlpConfig::default()));
+        let subscriber = tracing_subscriber::registry().with(OtlpLayer::new(exporter.clone()));
+        tracing::subscriber::with_default(subscriber, f);
+        let spans = exporter.buffer.lock().iter().cloned().collect();
+        spans
+    }
+
+    #[test]
+    fn test_otlp_layer_disabled_by_default() {
+        assert!(otlp_layer(&OtlpConfig::default()).is_none());
+        let config = OtlpConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(otlp_layer(&config).is_some());
+    }
+
+    #[test]
+    fn test_buffer_drops_oldest_span() {
+        let exporter = Arc::new(OtlpExporter::new(OtlpConfig::default()));
+        let span = |name: &str| SpanData {
+            trace_id: String::new(),
+            span_id: String::new(),
+            parent_span_id: None,
+            name: name.to_string(),
+            kind: SpanKind::Internal,
+            start_time_unix_nano: 0,
+            end_time_unix_nano: 0,
+            attributes: Vec::new(),
+            error: None,
+        };
+        exporter
+            .buffer
+            .lock()
+            .extend((0..MAX_BUFFERED_SPANS).map(|i| span(&i.to_string())));
+        exporter.push(span("newest"));
+
+        let buffer = exporter.buffer.lock();
+        assert_eq!(buffer.len(), MAX_BUFFERED_SPANS);
+        assert_eq!(buffer.front().unwrap().name, "1");
+        assert_eq!(buffer.back().unwrap().name, "newest");
+    }
+
+    #[test]
+    fn test_traces_url() {
+        let mut config = OtlpConfig::default();
+        assert_eq!(config.traces_url(), "http://localhost:4318/v1/traces");
+
+        config.endpoint = "https://collector.example.com/v1/traces/".to_string();
+        assert_eq!(
+            config.traces_url(),
+            "https://collector.example.com/v1/traces"
+        );
+    }
+
+    #[test]
+    fn test_only_request_span_trees_are_exported() {
+        let spans = collect_spans(|| {
+            let _unrelated = tracing::info_span!("unrelated").entered();
+            let _nested = tracing::info_span!("unrelated.child").entered();
+        });
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn test_child_spans_share_trace_id() {
+        let spans = collect_spans(|| {
+            let root = tracing::info_span!(ROOT_SPAN_NAME, model = "claude-sonnet-4");
+            let _root = root.enter();
+            {
+                let _call =
+                    tracing::info_span!("provider.call", otel.kind = "client", provider = "kiro")
+                        .entered();
+                let _refresh = tracing::info_span!("token.refresh", error = "expired").entered();
+            }
+        });
+
+        assert_eq!(spans.len(), 3);
+        // span 按结束顺序入队：token.refresh -> provider.call -> proxy.request
+        let (refresh, call, root) = (&spans[0], &spans[1], &spans[2]);
+        assert_eq!(root.name, ROOT_SPAN_NAME);
+        assert_eq!(root.kind, SpanKind::Server);
+        assert!(root.parent_span_id.is_none());
+        assert_eq!(call.kind, SpanKind::Client);
+        assert_eq!(call.parent_span_id.as_deref(), Some(root.span_id.as_str()));
+        assert_eq!(
+            refresh.parent_span_id.as_deref(),
+            Some(call.span_id.as_str())
+        );
+        assert_eq!(refresh.error.as_deref(), Some("expired"));
+        assert!(spans.iter().all(|s| s.trace_id == root.trace_id));
+        assert_eq!(root.trace_id.len(), 32);
+        assert_eq!(call.span_id.len(), 16);
+    }
+
+    #[test]
+    fn test_recorded_fields_become_attributes() {
+        let spans = collect_spans(|| {
+            let root =
+                tracing::info_span!(ROOT_SPAN_NAME, http.status_code = tracing::field::Empty);
+            root.record("http.status_code", 200u64);
+        });
+
+        assert_eq!(spans.len(), 1);
+        assert_eq!(
+            spans[0].attributes,
+            vec![("http.status_code".to_string(), AttributeValue::Int(200))]
+        );
+    }
+
+    #[test]
+    fn test_build_export_request_format() {
+        let span = SpanData {
+            trace_id: "0af7651916cd43dd8448eb211c80319c".to_string(),
+            span_id: "b7ad6b7169203331".to_string(),
+            parent_span_id: None,
+            name: ROOT_SPAN_NAME.to_string(),
+            kind: SpanKind::Server,
+            start_time_unix_nano: 1,
+            end_time_unix_nano: 2,
+            attributes: vec![(
+                "model".to_string(),
+                AttributeValue::String("gpt-4o".to_string()),
+            )],
+            error: Some("HTTP 502".to_string()),
+        };
+
+        let body = build_export_request("proxycast", &[span]);
+        let resource = &body["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "proxycast"
+        );
+        let span = &resource["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["kind"], 2);
+        assert_eq!(span["startTimeUnixNano"], "1");
+        assert_eq!(span["attributes"][0]["value"]["stringValue"], "gpt-4o");
+        assert_eq!(span["status"]["code"], 2);
+        assert!(span.get("parentSpanId").is_none());
+    }
+}
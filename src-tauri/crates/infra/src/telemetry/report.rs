@@ -0,0 +1,684 @@
+//! 用量报告模块
+//!
+//! 生成日报 / 周报（请求数、Token、费用、错误率、Top 模型、Top 客户端），
+//! 支持导出为 JSON / CSV / Markdown，并可定期写入报告目录
+
+use super::cost::PricingTable;
+use super::tokens::TokenUsageRecord;
+use super::types::{RequestLog, RequestStatus};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// 未使用客户端 Key（主 API Key）的请求在报告中的名称
+pub const DEFAULT_CLIENT_NAME: &str = "default";
+
+/// 未识别调用方工具的请求在报告中的名称
+pub const UNKNOWN_TOOL_NAME: &str = "unknown";
+
+/// 报告周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    /// 日报（UTC 自然日）
+    Daily,
+    /// 周报（ISO 周，周一开始）
+    Weekly,
+}
+
+impl ReportPeriod {
+    /// 包含指定日期的报告时间窗口 `[start, end)`
+    pub fn window(self, date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+        let (first_day, days) = match self {
+            Self::Daily => (date, 1),
+            Self::Weekly => (
+                date - Duration::days(date.weekday().num_days_from_monday() as i64),
+                7,
+            ),
+        };
+        let start = first_day.and_time(chrono::NaiveTime::MIN).and_utc();
+        (start, start + Duration::days(days))
+    }
+
+    /// 截至 `now` 最近一个已结束周期内的日期
+    pub fn last_complete(self, now: DateTime<Utc>) -> NaiveDate {
+        let today = now.date_naive();
+        match self {
+            Self::Daily => today - Duration::days(1),
+            Self::Weekly => today - Duration::days(7),
+        }
+    }
+
+    /// 周期标签（日报为 `2026-10-17`，周报为 `2026-W42`）
+    pub fn label(self, date: NaiveDate) -> String {
+        match self {
+            Self::Daily => date.format("%Y-%m-%d").to_string(),
+            Self::Weekly => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Daily => "日报",
+            Self::Weekly => "周报",
+        }
+    }
+}
+
+impl std::fmt::Display for ReportPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Daily => write!(f, "daily"),
+            Self::Weekly => write!(f, "weekly"),
+        }
+    }
+}
+
+/// 报告导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ReportFormat {
+    /// 文件扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+/// 排行榜条目（模型或客户端）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageRankItem {
+    /// 模型名或客户端 Key ID
+    pub name: String,
+    /// 请求数
+    pub requests: u64,
+    /// 出错请求数（失败 / 超时 / 限流）
+    pub errors: u64,
+    /// 输入 Token 数
+    pub input_tokens: u64,
+    /// 输出 Token 数
+    pub output_tokens: u64,
+    /// 估算费用
+    pub cost: f64,
+}
+
+impl UsageRankItem {
+    fn add_log(&mut self, log: &RequestLog) {
+        self.requests += 1;
+        if is_error(log.status) {
+            self.errors += 1;
+        }
+    }
+
+    fn add_tokens(&mut self, record: &TokenUsageRecord, cost: Option<f64>) {
+        self.input_tokens += record.input_tokens as u64;
+        self.output_tokens += record.output_tokens as u64;
+        self.cost += cost.unwrap_or(0.0);
+    }
+}
+
+/// 用量报告
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// 报告周期
+    pub period: ReportPeriod,
+    /// 周期标签
+    pub label: String,
+    /// 周期开始（含）
+    pub period_start: DateTime<Utc>,
+    /// 周期结束（不含）
+    pub period_end: DateTime<Utc>,
+    /// 生成时间
+    pub generated_at: DateTime<Utc>,
+    /// 货币单位
+    pub currency: String,
+    /// 总请求数
+    pub total_requests: u64,
+    /// 成功请求数
+    pub successful_requests: u64,
+    /// 出错请求数（失败 / 超时 / 限流）
+    pub error_requests: u64,
+    /// 错误率（0.0 - 1.0）
+    pub error_rate: f64,
+    /// 平均延迟（毫秒）
+    pub avg_latency_ms: f64,
+    /// 输入 Token 数
+    pub input_tokens: u64,
+    /// 输出 Token 数
+    pub output_tokens: u64,
+    /// 估算费用
+    pub total_cost: f64,
+    /// 按请求数排序的 Top 模型
+    pub top_models: Vec<UsageRankItem>,
+    /// 按请求数排序的 Top 客户端
+    pub top_clients: Vec<UsageRankItem>,
+    /// 按请求数排序的 Top 调用方工具
+    #[serde(default)]
+    pub top_tools: Vec<UsageRankItem>,
+}
+
+pub(super) fn is_error(status: RequestStatus) -> bool {
+    matches!(
+        status,
+        RequestStatus::Failed | RequestStatus::Timeout | RequestStatus::RateLimited
+    )
+}
+
+/// 用量报告生成器
+#[derive(Debug, Clone, Copy)]
+pub struct ReportGenerator {
+    /// 排行榜保留的条目数
+    top_n: usize,
+}
+
+impl Default for ReportGenerator {
+    fn default() -> Self {
+        Self::new(default_top_n())
+    }
+}
+
+impl ReportGenerator {
+    pub fn new(top_n: usize) -> Self {
+        Self { top_n }
+    }
+
+    /// 生成包含 `date` 的周期的报告
+    ///
+    /// 请求日志和 Token 记录按时间窗口过滤，Token 记录通过请求 ID 关联到客户端和调用方工具
+    pub fn generate(
+        &self,
+        period: ReportPeriod,
+        date: NaiveDate,
+        logs: &[RequestLog],
+        tokens: &[TokenUsageRecord],
+        pricing: &PricingTable,
+    ) -> UsageReport {
+        let (start, end) = period.window(date);
+        let in_window = |timestamp: &DateTime<Utc>| *timestamp >= start && *timestamp < end;
+        let logs: Vec<&RequestLog> = logs.iter().filter(|l| in_window(&l.timestamp)).collect();
+        let tokens: Vec<&TokenUsageRecord> =
+            tokens.iter().filter(|r| in_window(&r.timestamp)).collect();
+
+        let mut models: HashMap<String, UsageRankItem> = HashMap::new();
+        let mut clients: HashMap<String, UsageRankItem> = HashMap::new();
+        let mut tools: HashMap<String, UsageRankItem> = HashMap::new();
+        let mut request_clients: HashMap<&str, (&str, Option<&str>)> = HashMap::new();
+        for log in &logs {
+            let client = log.client_key_id.as_deref().unwrap_or(DEFAULT_CLIENT_NAME);
+            let tool = log.client_name.as_deref();
+            request_clients.insert(log.id.as_str(), (client, tool));
+            models.entry(log.model.clone()).or_default().add_log(log);
+            clients.entry(client.to_string()).or_default().add_log(log);
+            tools
+                .entry(tool.unwrap_or(UNKNOWN_TOOL_NAME).to_string())
+                .or_default()
+                .add_log(log);
+        }
+
+        let mut total_cost = 0.0;
+        for record in &tokens {
+            let cost = pricing.cost_of(record);
+            total_cost += cost.unwrap_or(0.0);
+            models
+                .entry(record.model.clone())
+                .or_default()
+                .add_tokens(record, cost);
+            let (client, tool) = record
+                .request_id
+                .as_deref()
+                .and_then(|id| request_clients.get(id).copied())
+                .unwrap_or((DEFAULT_CLIENT_NAME, None));
+            clients
+                .entry(client.to_string())
+                .or_default()
+                .add_tokens(record, cost);
+            let tool = record.client_name.as_deref().or(tool);
+            tools
+                .entry(tool.unwrap_or(UNKNOWN_TOOL_NAME).to_string())
+                .or_default()
+                .add_tokens(record, cost);
+        }
+
+        let total_requests = logs.len() as u64;
+        let error_requests = logs.iter().filter(|l| is_error(l.status)).count() as u64;
+        let ratio = |count: u64| {
+            if total_requests > 0 {
+                count as f64 / total_requests as f64
+            } else {
+                0.0
+            }
+        };
+
+        UsageReport {
+            period,
+            label: period.label(date),
+            period_start: start,
+            period_end: end,
+            generated_at: Utc::now(),
+            currency: pricing.currency().to_string(),
+            total_requests,
+            successful_requests: logs.iter().filter(|l| l.is_success()).count() as u64,
+            error_requests,
+            error_rate: ratio(error_requests),
+            avg_latency_ms: ratio(logs.iter().map(|l| l.duration_ms).sum()),
+            input_tokens: tokens.iter().map(|r| r.input_tokens as u64).sum(),
+            output_tokens: tokens.iter().map(|r| r.output_tokens as u64).sum(),
+            total_cost,
+            top_models: self.rank(models),
+            top_clients: self.rank(clients),
+            top_tools: self.rank(tools),
+        }
+    }
+
+    /// 按请求数、费用降序排列并截取前 N 项
+    fn rank(&self, items: HashMap<String, UsageRankItem>) -> Vec<UsageRankItem> {
+        let mut items: Vec<UsageRankItem> = items
+            .into_iter()
+            .map(|(name, item)| UsageRankItem { name, ..item })
+            .collect();
+        items.sort_by(|a, b| {
+            b.requests
+                .cmp(&a.requests)
+                .then(b.cost.total_cmp(&a.cost))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        items.truncate(self.top_n);
+        items
+    }
+}
+
+impl UsageReport {
+    /// 是否没有任何用量数据
+    pub fn is_empty(&self) -> bool {
+        self.total_requests == 0 && self.input_tokens == 0 && self.output_tokens == 0
+    }
+
+    /// 渲染为指定格式
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self).unwrap_or_default(),
+            ReportFormat::Csv => self.to_csv(),
+            ReportFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// 报告文件名（如 `daily-2026-10-17.md`）
+    pub fn file_name(&self, format: ReportFormat) -> String {
+        format!("{}-{}.{}", self.period, self.label, format.extension())
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("section,name,requests,errors,input_tokens,output_tokens,cost\n");
+        let mut row = |section: &str, name: &str, requests, errors, input, output, cost: f64| {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{:.6}",
+                section,
+                csv_field(name),
+                requests,
+                errors,
+                input,
+                output,
+                cost
+            );
+        };
+
+        row(
+            "total",
+            &self.label,
+            self.total_requests,
+            self.error_requests,
+            self.input_tokens,
+            self.output_tokens,
+            self.total_cost,
+        );
+        for (section, items) in [
+            ("model", &self.top_models),
+            ("client", &self.top_clients),
+            ("tool", &self.top_tools),
+        ] {
+            for item in items {
+                row(
+                    section,
+                    &item.name,
+                    item.requests,
+                    item.errors,
+                    item.input_tokens,
+                    item.output_tokens,
+                    item.cost,
+                );
+            }
+        }
+        csv
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# ProxyCast {} {}\n", self.period.title(), self.label);
+        let _ = writeln!(
+            md,
+            "统计区间：{} ~ {}（UTC）\n",
+            self.period_start.format("%Y-%m-%d %H:%M"),
+            self.period_end.format("%Y-%m-%d %H:%M")
+        );
+
+        md.push_str("| 指标 | 数值 |\n| --- | --- |\n");
+        let _ = writeln!(md, "| 请求数 | {} |", self.total_requests);
+        let _ = writeln!(md, "| 成功请求数 | {} |", self.successful_requests);
+        let _ = writeln!(md, "| 错误率 | {:.2}% |", self.error_rate * 100.0);
+        let _ = writeln!(md, "| 平均延迟 | {:.0} ms |", self.avg_latency_ms);
+        let _ = writeln!(md, "| 输入 Token | {} |", self.input_tokens);
+        let _ = writeln!(md, "| 输出 Token | {} |", self.output_tokens);
+        let _ = writeln!(
+            md,
+            "| 估算费用 | {:.4} {} |",
+            self.total_cost, self.currency
+        );
+
+        for (title, items) in [
+            ("Top 模型", &self.top_models),
+            ("Top 客户端", &self.top_clients),
+            ("Top 工具", &self.top_tools),
+        ] {
+            let _ = writeln!(md, "\n## {}\n", title);
+            if items.is_empty() {
+                md.push_str("无数据\n");
+                continue;
+            }
+            md.push_str("| 名称 | 请求数 | 错误数 | 输入 Token | 输出 Token | 费用 |\n");
+            md.push_str("| --- | ---: | ---: | ---: | ---: | ---: |\n");
+            for item in items {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} | {:.4} |",
+                    item.name.replace('|', "\\|"),
+                    item.requests,
+                    item.errors,
+                    item.input_tokens,
+                    item.output_tokens,
+                    item.cost
+                );
+            }
+        }
+        md
+    }
+}
+
+/// 转义 CSV 字段
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn default_reports_directory() -> String {
+    "~/.proxycast/reports".to_string()
+}
+
+fn default_formats() -> Vec<ReportFormat> {
+    vec![ReportFormat::Markdown]
+}
+
+fn default_weekly() -> bool {
+    true
+}
+
+fn default_top_n() -> usize {
+    10
+}
+
+/// 定期报告配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportConfig {
+    /// 是否定期写入报告
+    #[serde(default)]
+    pub enabled: bool,
+    /// 报告目录（支持 ~ 展开）
+    #[serde(default = "default_reports_directory")]
+    pub directory: String,
+    /// 写入的格式
+    #[serde(default = "default_formats")]
+    pub formats: Vec<ReportFormat>,
+    /// 是否同时生成周报
+    #[serde(default = "default_weekly")]
+    pub weekly: bool,
+    /// 排行榜保留的条目数
+    #[serde(default = "default_top_n")]
+    pub top_n: usize,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: default_reports_directory(),
+            formats: default_formats(),
+            weekly: default_weekly(),
+            top_n: default_top_n(),
+        }
+    }
+}
+
+/// 写入最近一个已结束周期的日报（及周报）
+///
+/// 已存在的报告文件不会覆盖；周期内没有任何用量数据时不写入，
+/// 避免应用重启丢失内存统计后生成空报告。
+///
+/// # 返回
+/// 本次新写入的文件路径
+pub fn write_due_reports(
+    config: &ReportConfig,
+    dir: &Path,
+    now: DateTime<Utc>,
+    logs: &[RequestLog],
+    tokens: &[TokenUsageRecord],
+    pricing: &PricingTable,
+) -> std::io::Result<Vec<PathBuf>> {
+    let generator = ReportGenerator::new(config.top_n);
+    let periods: &[ReportPeriod] = if config.weekly {
+        &[ReportPeriod::Daily, ReportPeriod::Weekly]
+    } else {
+        &[ReportPeriod::Daily]
+    };
+
+    let mut written = Vec::new();
+    for &period in periods {
+        let date = period.last_complete(now);
+        let label = period.label(date);
+        let pending: Vec<(ReportFormat, PathBuf)> = config
+            .formats
+            .iter()
+            .map(|&format| {
+                let name = format!("{}-{}.{}", period, label, format.extension());
+                (format, dir.join(name))
+            })
+            .filter(|(_, path)| !path.exists())
+            .collect();
+        if pending.is_empty() {
+            continue;
+        }
+
+        let report = generator.generate(period, date, logs, tokens, pricing);
+        if report.is_empty() {
+            continue;
+        }
+
+        std::fs::create_dir_all(dir)?;
+        for (format, path) in pending {
+            std::fs::write(&path, report.render(format))?;
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod report_tests {
+    use super::*;
+    use crate::telemetry::{PricingConfig, TokenSource};
+    use proxycast_core::ProviderType;
+
+    fn at(date: &str, hour: u32) -> DateTime<Utc> {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    fn log(id: &str, model: &str, client: Option<&str>, status: RequestStatus) -> RequestLog {
+        let mut log = RequestLog::new(
+            id.to_string(),
+            ProviderType::Claude,
+            model.to_string(),
+            false,
+        );
+        log.timestamp = at("2026-10-14", 10);
+        log.status = status;
+        log.duration_ms = 100;
+        log.client_key_id = client.map(str::to_string);
+        log.client_name = Some("claude-code".to_string());
+        log
+    }
+
+    fn tokens(request_id: &str, model: &str, input: u32, output: u32) -> TokenUsageRecord {
+        let mut record = TokenUsageRecord::new(
+            format!("t-{}", request_id),
+            ProviderType::Claude,
+            model.to_string(),
+            input,
+            output,
+            TokenSource::Actual,
+        )
+        .with_request_id(request_id.to_string());
+        record.timestamp = at("2026-10-14", 10);
+        record
+    }
+
+    #[test]
+    fn test_report_windows() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap(); // 周三
+        assert_eq!(
+            ReportPeriod::Daily.window(date),
+            (at("2026-10-14", 0), at("2026-10-15", 0))
+        );
+        assert_eq!(
+            ReportPeriod::Weekly.window(date),
+            (at("2026-10-12", 0), at("2026-10-19", 0))
+        );
+        assert_eq!(ReportPeriod::Weekly.label(date), "2026-W42");
+        assert_eq!(
+            ReportPeriod::Daily.last_complete(at("2026-10-15", 0)),
+            NaiveDate::from_ymd_opt(2026, 10, 14).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generate_report() {
+        let logs = vec![
+            log(
+                "r1",
+                "claude-sonnet-4-5",
+                Some("key-a"),
+                RequestStatus::Success,
+            ),
+            {
+                let mut r2 = log("r2", "claude-sonnet-4-5", None, RequestStatus::Success);
+                r2.client_name = None;
+                r2
+            },
+            log("r3", "gpt-4o", Some("key-a"), RequestStatus::RateLimited),
+            {
+                let mut old = log("r0", "gpt-4o", None, RequestStatus::Failed);
+                old.timestamp = at("2026-10-13", 23);
+                old
+            },
+        ];
+        let records = vec![
+            tokens("r1", "claude-sonnet-4-5", 1_000_000, 0),
+            tokens("r2", "claude-sonnet-4-5", 0, 100_000),
+        ];
+        let pricing = PricingTable::new(&PricingConfig::default());
+        let date = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+
+        let report =
+            ReportGenerator::new(10).generate(ReportPeriod::Daily, date, &logs, &records, &pricing);
+        assert_eq!(report.total_requests, 3);
+        assert_eq!(report.error_requests, 1);
+        assert!((report.error_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert!((report.total_cost - 4.5).abs() < 1e-9);
+        assert_eq!(report.top_models[0].name, "claude-sonnet-4-5");
+        assert_eq!(report.top_models[0].requests, 2);
+        assert_eq!(report.top_clients[0].name, "key-a");
+        assert!((report.top_clients[0].cost - 3.0).abs() < 1e-9);
+        assert_eq!(report.top_clients[1].name, DEFAULT_CLIENT_NAME);
+        assert_eq!(report.top_tools[0].name, "claude-code");
+        assert_eq!(report.top_tools[0].requests, 2);
+        assert_eq!(report.top_tools[1].name, UNKNOWN_TOOL_NAME);
+        assert!((report.top_tools[1].cost - 1.5).abs() < 1e-9);
+
+        let csv = report.render(ReportFormat::Csv);
+        assert!(csv.starts_with("section,name,requests"));
+        assert!(csv.contains("total,2026-10-14,3,1,1000000,100000,4.500000"));
+        assert!(csv.contains("client,key-a,2,1,1000000,0,3.000000"));
+        assert!(csv.contains("tool,claude-code,2,1,1000000,0,3.000000"));
+
+        let md = report.render(ReportFormat::Markdown);
+        assert!(md.starts_with("# ProxyCast 日报 2026-10-14"));
+        assert!(md.contains("| 错误率 | 33.33% |"));
+        assert!(md.contains("## Top 工具"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&report.render(ReportFormat::Json)).unwrap();
+        assert_eq!(json["period"], "daily");
+        assert_eq!(
+            report.file_name(ReportFormat::Markdown),
+            "daily-2026-10-14.md"
+        );
+    }
+
+    #[test]
+    fn test_write_due_reports_skips_existing_and_empty() {
+        let dir = std::env::temp_dir().join(format!("proxycast-reports-{}", uuid::Uuid::new_v4()));
+        let config = ReportConfig {
+            enabled: true,
+            formats: vec![ReportFormat::Markdown, ReportFormat::Csv],
+            ..ReportConfig::default()
+        };
+        let logs = vec![log("r1", "gpt-4o", None, RequestStatus::Success)];
+        let pricing = PricingTable::default();
+        let now = at("2026-10-15", 1);
+
+        let written = write_due_reports(&config, &dir, now, &logs, &[], &pricing).unwrap();
+        // 周报对应的上一周（10-05 ~ 10-11）没有数据，不写入
+        assert_eq!(
+            written,
+            vec![
+                dir.join("daily-2026-10-14.md"),
+                dir.join("daily-2026-10-14.csv")
+            ]
+        );
+
+        let written = write_due_reports(&config, &dir, now, &logs, &[], &pricing).unwrap();
+        assert!(written.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
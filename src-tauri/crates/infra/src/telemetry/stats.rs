@@ -2,6 +2,7 @@
 //!
 //! 提供请求统计的聚合、分组和查询功能
 
+use super::latency::{LatencyGroupBy, LatencyReport, LatencyTracker};
 use super::types::{ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange};
 use chrono::{Duration, Utc};
 use parking_lot::RwLock;
@@ -18,6 +19,8 @@ pub struct StatsAggregator {
     retention: Duration,
     /// 最大日志条数
     max_logs: usize,
+    /// 延迟分位数追踪器
+    latency: RwLock<LatencyTracker>,
 }
 
 impl StatsAggregator {
@@ -31,6 +34,7 @@ impl StatsAggregator {
             logs: RwLock::new(VecDeque::with_capacity(max_logs)),
             retention,
             max_logs,
+            latency: RwLock::new(LatencyTracker::new(retention)),
         }
     }
 
@@ -41,8 +45,11 @@ impl StatsAggregator {
 
     /// 记录请求日志
     ///
-    /// 将日志添加到聚合器中，并自动清理过期日志
+    /// 将日志添加到聚合器中，并自动清理过期日志。
+    /// 已记录首字节延迟的流式请求此时不计入端到端延迟，由 [`Self::complete_stream`] 补记
     pub fn record(&self, log: RequestLog) {
+        self.record_latency(&log);
+
         let mut logs = self.logs.write();
         logs.push_back(log);
 
@@ -62,6 +69,66 @@ impl StatsAggregator {
         }
     }
 
+    /// 将日志的延迟写入分位数追踪器
+    fn record_latency(&self, log: &RequestLog) {
+        let mut latency = self.latency.write();
+        let credential_id = log.credential_id.as_deref();
+        if let Some(ttfb_ms) = log.ttfb_ms {
+            latency.record_ttfb(
+                log.timestamp,
+                log.provider,
+                &log.model,
+                credential_id,
+                ttfb_ms,
+            );
+        }
+        if !(log.is_streaming && log.ttfb_ms.is_some()) {
+            latency.record_total(
+                log.timestamp,
+                log.provider,
+                &log.model,
+                credential_id,
+                log.duration_ms,
+            );
+        }
+    }
+
+    /// 流式响应结束时补记端到端延迟
+    ///
+    /// 更新对应日志的持续时间并写入分位数追踪器，找不到已记录首字节延迟的流式日志时返回 false
+    pub fn complete_stream(&self, request_id: &str, duration_ms: u64) -> bool {
+        let mut logs = self.logs.write();
+        let Some(log) = logs
+            .iter_mut()
+            .rev()
+            .find(|l| l.id == request_id && l.is_streaming && l.ttfb_ms.is_some())
+        else {
+            return false;
+        };
+        log.duration_ms = duration_ms;
+        self.latency.write().record_total(
+            log.timestamp,
+            log.provider,
+            &log.model,
+            log.credential_id.as_deref(),
+            duration_ms,
+        );
+        true
+    }
+
+    /// 查询延迟分位数（p50 / p95 / p99）
+    ///
+    /// # Arguments
+    /// * `range` - 可选的时间范围，如果为 None 则统计所有数据
+    /// * `group_by` - 分组维度
+    pub fn latency_percentiles(
+        &self,
+        range: Option<TimeRange>,
+        group_by: LatencyGroupBy,
+    ) -> LatencyReport {
+        self.latency.read().report(range, group_by)
+    }
+
     /// 获取统计摘要
     ///
     /// # Arguments
@@ -102,6 +169,7 @@ impl StatsAggregator {
     /// 清空所有日志
     pub fn clear(&self) {
         self.logs.write().clear();
+        self.latency.write().clear();
     }
 
     /// 清理过期日志
@@ -111,6 +179,7 @@ impl StatsAggregator {
         let mut logs = self.logs.write();
         let cutoff = Utc::now() - self.retention;
         let initial_len = logs.len();
+        self.latency.write().prune(cutoff);
 
         while let Some(front) = logs.front() {
             if front.timestamp < cutoff {
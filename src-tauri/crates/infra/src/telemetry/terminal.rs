@@ -0,0 +1,264 @@
+//! 终端会话指标
+//!
+//! 按会话统计输入 / 输出字节数、输出速率、输入到回显的延迟估算和调整大小次数，
+//! 用于诊断"终端卡顿"类问题。
+//!
+//! 回显延迟按"交互式输入后的第一次输出"估算：短输入（按键、方向键等）写入后开始计时，
+//! 下一次读到输出时结束；粘贴等长输入不计时，超过 1 秒未回显（如输入密码）的样本丢弃。
+
+use super::latency::{LatencyHistogram, LatencyPercentiles};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// 计入回显延迟的最大输入长度（字节），更长的输入视为粘贴
+const ECHO_MAX_INPUT_LEN: usize = 8;
+/// 回显超时，超过该时间的输出不视为回显
+const ECHO_TIMEOUT: Duration = Duration::from_secs(1);
+/// 输出速率的统计窗口（秒）
+const RATE_WINDOW_SECS: u64 = 10;
+
+/// 终端会话指标采集器
+///
+/// 读取线程和写入方并发记录，计数使用原子变量，回显计时和速率窗口使用互斥锁
+#[derive(Debug)]
+pub struct TerminalMetrics {
+    started: Instant,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    writes: AtomicU64,
+    reads: AtomicU64,
+    resizes: AtomicU64,
+    state: Mutex<MetricsState>,
+}
+
+/// 需要加锁更新的指标状态
+#[derive(Debug, Default)]
+struct MetricsState {
+    /// 等待回显的输入时间
+    pending_echo: Option<Instant>,
+    /// 回显延迟直方图
+    echo_latency: LatencyHistogram,
+    /// 最近每秒的输出字节数（秒序号，字节数）
+    rate_window: VecDeque<(u64, u64)>,
+    /// 单秒最大输出字节数
+    peak_rate: u64,
+}
+
+impl TerminalMetrics {
+    /// 创建采集器，会话时长从此刻开始计算
+    pub fn new() -> Self {
+        Self::started_at(Instant::now())
+    }
+
+    fn started_at(started: Instant) -> Self {
+        Self {
+            started,
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+            reads: AtomicU64::new(0),
+            resizes: AtomicU64::new(0),
+            state: Mutex::new(MetricsState::default()),
+        }
+    }
+
+    /// 记录写入终端的输入
+    pub fn record_input(&self, len: usize) {
+        self.record_input_at(len, Instant::now());
+    }
+
+    fn record_input_at(&self, len: usize, now: Instant) {
+        if len == 0 {
+            return;
+        }
+        self.bytes_in.fetch_add(len as u64, Ordering::Relaxed);
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        if len <= ECHO_MAX_INPUT_LEN {
+            self.state.lock().pending_echo.get_or_insert(now);
+        }
+    }
+
+    /// 记录读取到的输出
+    pub fn record_output(&self, len: usize) {
+        self.record_output_at(len, Instant::now());
+    }
+
+    fn record_output_at(&self, len: usize, now: Instant) {
+        if len == 0 {
+            return;
+        }
+        self.bytes_out.fetch_add(len as u64, Ordering::Relaxed);
+        self.reads.fetch_add(1, Ordering::Relaxed);
+
+        let second = self.second_of(now);
+        let mut state = self.state.lock();
+        if let Some(input_at) = state.pending_echo.take() {
+            let elapsed = now.saturating_duration_since(input_at);
+            if elapsed <= ECHO_TIMEOUT {
+                state.echo_latency.record(elapsed.as_millis() as u64);
+            }
+        }
+
+        let bytes = match state.rate_window.back_mut() {
+            Some((last, bytes)) if *last == second => {
+                *bytes += len as u64;
+                *bytes
+            }
+            _ => {
+                state.rate_window.push_back((second, len as u64));
+                len as u64
+            }
+        };
+        state.peak_rate = state.peak_rate.max(bytes);
+        while state
+            .rate_window
+            .front()
+            .is_some_and(|(first, _)| first + RATE_WINDOW_SECS <= second)
+        {
+            state.rate_window.pop_front();
+        }
+    }
+
+    /// 记录一次调整大小
+    pub fn record_resize(&self) {
+        self.resizes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 获取指标快照
+    pub fn snapshot(&self) -> TerminalMetricsSnapshot {
+        self.snapshot_at(Instant::now())
+    }
+
+    fn snapshot_at(&self, now: Instant) -> TerminalMetricsSnapshot {
+        let uptime = now.saturating_duration_since(self.started);
+        let second = self.second_of(now);
+        let state = self.state.lock();
+
+        // 窗口不足 10 秒时按会话时长计算，避免刚启动时速率偏低
+        let window_bytes: u64 = state
+            .rate_window
+            .iter()
+            .filter(|(start, _)| start + RATE_WINDOW_SECS > second)
+            .map(|(_, bytes)| bytes)
+            .sum();
+        let window_secs = uptime.as_secs_f64().clamp(1.0, RATE_WINDOW_SECS as f64);
+
+        TerminalMetricsSnapshot {
+            uptime_secs: uptime.as_secs(),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+            reads: self.reads.load(Ordering::Relaxed),
+            output_rate_bps: window_bytes as f64 / window_secs,
+            peak_output_rate_bps: state.peak_rate,
+            echo_latency: state.echo_latency.summary(),
+            resize_count: self.resizes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 时间点相对会话开始的秒序号
+    fn second_of(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.started).as_secs()
+    }
+}
+
+impl Default for TerminalMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 终端会话指标快照
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TerminalMetricsSnapshot {
+    /// 会话时长（秒）
+    pub uptime_secs: u64,
+    /// 输入字节数
+    pub bytes_in: u64,
+    /// 输出字节数
+    pub bytes_out: u64,
+    /// 输入写入次数
+    pub writes: u64,
+    /// 输出读取次数
+    pub reads: u64,
+    /// 最近 10 秒的平均输出速率（字节/秒）
+    pub output_rate_bps: f64,
+    /// 单秒最大输出字节数
+    pub peak_output_rate_bps: u64,
+    /// 输入到回显的延迟估算
+    pub echo_latency: LatencyPercentiles,
+    /// 调整大小次数
+    pub resize_count: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_counters_and_resizes() {
+        let start = Instant::now();
+        let metrics = TerminalMetrics::started_at(start);
+        metrics.record_input_at(3, start);
+        metrics.record_input_at(0, start);
+        metrics.record_output_at(100, start);
+        metrics.record_output_at(50, start);
+        metrics.record_resize();
+
+        let snapshot = metrics.snapshot_at(start + Duration::from_secs(2));
+        assert_eq!(snapshot.uptime_secs, 2);
+        assert_eq!(snapshot.bytes_in, 3);
+        assert_eq!(snapshot.writes, 1);
+        assert_eq!(snapshot.bytes_out, 150);
+        assert_eq!(snapshot.reads, 2);
+        assert_eq!(snapshot.resize_count, 1);
+    }
+
+    #[test]
+    fn test_echo_latency_estimate() {
+        let start = Instant::now();
+        let metrics = TerminalMetrics::started_at(start);
+
+        // 按键后 20ms 回显；等待回显期间的后续按键不重新计时
+        metrics.record_input_at(1, start);
+        metrics.record_input_at(1, start + Duration::from_millis(10));
+        metrics.record_output_at(2, start + Duration::from_millis(20));
+        // 没有输入时的输出不计入
+        metrics.record_output_at(10, start + Duration::from_millis(500));
+        // 粘贴不计时
+        metrics.record_input_at(64, start + Duration::from_millis(600));
+        metrics.record_output_at(64, start + Duration::from_millis(700));
+        // 超时未回显的输入丢弃
+        metrics.record_input_at(1, start + Duration::from_secs(1));
+        metrics.record_output_at(1, start + Duration::from_secs(3));
+
+        let latency = metrics
+            .snapshot_at(start + Duration::from_secs(3))
+            .echo_latency;
+        assert_eq!(latency.count, 1);
+        assert_eq!(latency.p50_ms, 20);
+    }
+
+    #[test]
+    fn test_output_rate_window() {
+        let start = Instant::now();
+        let metrics = TerminalMetrics::started_at(start);
+        metrics.record_output_at(1000, start);
+        metrics.record_output_at(3000, start + Duration::from_millis(1500));
+        metrics.record_output_at(1000, start + Duration::from_millis(1900));
+
+        // 会话不足 10 秒时按时长计算
+        let snapshot = metrics.snapshot_at(start + Duration::from_secs(2));
+        assert_eq!(snapshot.output_rate_bps, 2500.0);
+        assert_eq!(snapshot.peak_output_rate_bps, 4000);
+
+        // 超出窗口的输出不再计入速率，峰值保留
+        metrics.record_output_at(500, start + Duration::from_secs(15));
+        let snapshot = metrics.snapshot_at(start + Duration::from_secs(15));
+        assert_eq!(snapshot.output_rate_bps, 50.0);
+        assert_eq!(snapshot.peak_output_rate_bps, 4000);
+    }
+}
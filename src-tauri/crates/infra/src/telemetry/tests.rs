@@ -3,7 +3,8 @@
 //! 使用 proptest 进行属性测试
 
 use super::{
-    LogRotationConfig, RequestLog, RequestLogger, RequestStatus, StatsAggregator, TimeRange,
+    LatencyGroupBy, LogRotationConfig, RequestLog, RequestLogger, RequestStatus, StatsAggregator,
+    TimeRange,
 };
 use chrono::{Duration, Utc};
 use proptest::prelude::*;
@@ -26,6 +27,7 @@ fn arb_request_status() -> impl Strategy<Value = RequestStatus> {
         Just(RequestStatus::Success),
         Just(RequestStatus::Failed),
         Just(RequestStatus::Timeout),
+        Just(RequestStatus::RateLimited),
         Just(RequestStatus::Cancelled),
     ]
 }
@@ -79,6 +81,9 @@ fn arb_request_log() -> impl Strategy<Value = RequestLog> {
                     RequestStatus::Timeout => {
                         log.mark_timeout(duration_ms);
                     }
+                    RequestStatus::RateLimited => {
+                        log.mark_rate_limited(duration_ms, http_status, "Test error".to_string());
+                    }
                     RequestStatus::Cancelled => {
                         log.mark_cancelled(duration_ms);
                     }
@@ -282,6 +287,10 @@ proptest! {
         let success_count = logs.iter().filter(|l| l.status == RequestStatus::Success).count();
         let failed_count = logs.iter().filter(|l| l.status == RequestStatus::Failed).count();
         let timeout_count = logs.iter().filter(|l| l.status == RequestStatus::Timeout).count();
+        let rate_limited_count = logs
+            .iter()
+            .filter(|l| l.status == RequestStatus::RateLimited)
+            .count();
 
         // 记录所有日志
         for log in logs {
@@ -319,6 +328,13 @@ proptest! {
             "统计的超时请求数应正确"
         );
 
+        // 验证：限流请求数应正确
+        prop_assert_eq!(
+            summary.rate_limited_requests as usize,
+            rate_limited_count,
+            "统计的限流请求数应正确"
+        );
+
         // 验证：成功率计算正确
         if total_count > 0 {
             let expected_rate = success_count as f64 / total_count as f64;
@@ -511,6 +527,10 @@ proptest! {
         let success_count = logs.iter().filter(|l| l.status == RequestStatus::Success).count();
         let failed_count = logs.iter().filter(|l| l.status == RequestStatus::Failed).count();
         let timeout_count = logs.iter().filter(|l| l.status == RequestStatus::Timeout).count();
+        let rate_limited_count = logs
+            .iter()
+            .filter(|l| l.status == RequestStatus::RateLimited)
+            .count();
 
         // 记录所有日志
         for log in logs {
@@ -548,6 +568,13 @@ proptest! {
             "统计的超时请求数应正确"
         );
 
+        // 验证：限流请求数应正确
+        prop_assert_eq!(
+            summary.rate_limited_requests as usize,
+            rate_limited_count,
+            "统计的限流请求数应正确"
+        );
+
         // 验证：成功率计算正确
         if total_count > 0 {
             let expected_rate = success_count as f64 / total_count as f64;
@@ -840,3 +867,61 @@ fn test_stats_aggregator_max_logs_limit() {
     // 验证日志数量不超过限制
     assert_eq!(aggregator.len(), 10);
 }
+
+#[test]
+fn test_stats_aggregator_latency_percentiles() {
+    let aggregator = create_test_aggregator();
+
+    // 非流式请求在记录时即计入端到端延迟
+    for (credential, duration) in [("cred-a", 100), ("cred-a", 300), ("cred-b", 500)] {
+        let mut log = RequestLog::new(
+            uuid::Uuid::new_v4().to_string(),
+            ProviderType::Claude,
+            "claude-sonnet-4".to_string(),
+            false,
+        );
+        log.mark_success(duration, 200);
+        log.set_credential_id(credential.to_string());
+        aggregator.record(log);
+    }
+
+    let report = aggregator.latency_percentiles(None, LatencyGroupBy::Credential);
+    assert_eq!(report.overall.total.count, 3);
+    assert_eq!(report.overall.total.p50_ms, 300);
+    assert_eq!(report.overall.ttfb.count, 0);
+    assert_eq!(report.groups[0].credential_id.as_deref(), Some("cred-a"));
+    assert_eq!(report.groups[0].total.count, 2);
+
+    aggregator.clear();
+    let report = aggregator.latency_percentiles(None, LatencyGroupBy::Credential);
+    assert_eq!(report.overall.total.count, 0);
+}
+
+#[test]
+fn test_stats_aggregator_complete_stream() {
+    let aggregator = create_test_aggregator();
+
+    let mut log = RequestLog::new(
+        "stream-1".to_string(),
+        ProviderType::Claude,
+        "claude-sonnet-4".to_string(),
+        true,
+    );
+    log.mark_success(40, 200);
+    log.set_ttfb(40);
+    aggregator.record(log);
+
+    // 流结束前只有首字节延迟
+    let report = aggregator.latency_percentiles(None, LatencyGroupBy::Model);
+    assert_eq!(report.overall.ttfb.count, 1);
+    assert_eq!(report.overall.total.count, 0);
+
+    assert!(aggregator.complete_stream("stream-1", 2000));
+    assert!(!aggregator.complete_stream("missing", 2000));
+
+    let report = aggregator.latency_percentiles(None, LatencyGroupBy::Model);
+    assert_eq!(report.overall.ttfb.p50_ms, 40);
+    assert_eq!(report.overall.total.count, 1);
+    assert_eq!(report.overall.total.max_ms, 2000);
+    assert_eq!(aggregator.get_all()[0].duration_ms, 2000);
+}
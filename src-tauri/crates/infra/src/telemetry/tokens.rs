@@ -4,6 +4,7 @@
 
 #![allow(dead_code)]
 
+use super::cost::{CostReport, PricingTable};
 use chrono::{DateTime, Duration, Utc};
 use parking_lot::RwLock;
 use proxycast_core::ProviderType;
@@ -31,6 +32,15 @@ pub struct TokenUsageRecord {
     pub source: TokenSource,
     /// 关联的请求 ID
     pub request_id: Option<String>,
+    /// 使用的凭证 ID（如果有）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<String>,
+    /// 调用方工具名称（如果可识别）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    /// Prompt 缓存 Token 数（已包含在输入 Token 数中）
+    #[serde(flatten, default)]
+    pub cache: CacheTokens,
 }
 
 impl TokenUsageRecord {
@@ -53,6 +63,9 @@ impl TokenUsageRecord {
             total_tokens: input_tokens + output_tokens,
             source,
             request_id: None,
+            credential_id: None,
+            client_name: None,
+            cache: CacheTokens::default(),
         }
     }
 
@@ -61,6 +74,38 @@ impl TokenUsageRecord {
         self.request_id = Some(request_id);
         self
     }
+
+    /// 设置使用的凭证 ID
+    pub fn with_credential_id(mut self, credential_id: String) -> Self {
+        self.credential_id = Some(credential_id);
+        self
+    }
+
+    /// 设置调用方工具名称
+    pub fn with_client_name(mut self, client_name: String) -> Self {
+        self.client_name = Some(client_name);
+        self
+    }
+
+    /// 设置 Prompt 缓存 Token 数
+    pub fn with_cache_tokens(mut self, cache: CacheTokens) -> Self {
+        self.cache = cache;
+        self
+    }
+}
+
+/// Prompt 缓存 Token 数
+///
+/// 对应 Anthropic 的 `cache_creation_input_tokens` / `cache_read_input_tokens`，
+/// OpenAI 的 `prompt_tokens_details.cached_tokens` 计为缓存读取。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheTokens {
+    /// 写入缓存的输入 Token 数
+    #[serde(default)]
+    pub cache_creation_input_tokens: u32,
+    /// 命中缓存的输入 Token 数
+    #[serde(default)]
+    pub cache_read_input_tokens: u32,
 }
 
 /// Token 来源
@@ -101,6 +146,15 @@ pub struct TokenStatsSummary {
     pub avg_input_tokens: f64,
     /// 平均输出 Token 数
     pub avg_output_tokens: f64,
+    /// 总缓存写入 Token 数
+    #[serde(default)]
+    pub total_cache_creation_input_tokens: u64,
+    /// 总缓存读取 Token 数
+    #[serde(default)]
+    pub total_cache_read_input_tokens: u64,
+    /// 缓存命中率（缓存读取 Token 数 / 总输入 Token 数）
+    #[serde(default)]
+    pub cache_hit_rate: f64,
 }
 
 impl TokenStatsSummary {
@@ -114,6 +168,19 @@ impl TokenStatsSummary {
         let total_input_tokens: u64 = records.iter().map(|r| r.input_tokens as u64).sum();
         let total_output_tokens: u64 = records.iter().map(|r| r.output_tokens as u64).sum();
         let total_tokens = total_input_tokens + total_output_tokens;
+        let total_cache_creation_input_tokens: u64 = records
+            .iter()
+            .map(|r| r.cache.cache_creation_input_tokens as u64)
+            .sum();
+        let total_cache_read_input_tokens: u64 = records
+            .iter()
+            .map(|r| r.cache.cache_read_input_tokens as u64)
+            .sum();
+        let cache_hit_rate = if total_input_tokens > 0 {
+            total_cache_read_input_tokens as f64 / total_input_tokens as f64
+        } else {
+            0.0
+        };
         let actual_count = records
             .iter()
             .filter(|r| r.source == TokenSource::Actual)
@@ -132,6 +199,9 @@ impl TokenStatsSummary {
             estimated_count,
             avg_input_tokens: total_input_tokens as f64 / record_count as f64,
             avg_output_tokens: total_output_tokens as f64 / record_count as f64,
+            total_cache_creation_input_tokens,
+            total_cache_read_input_tokens,
+            cache_hit_rate,
         }
     }
 }
@@ -411,6 +481,20 @@ impl TokenTracker {
         result
     }
 
+    /// 按单价表估算费用，按 Provider / 凭证 / 模型 / 天汇总
+    pub fn cost_report(
+        &self,
+        pricing: &PricingTable,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> CostReport {
+        let records = match (start, end) {
+            (Some(s), Some(e)) => self.get_by_time_range(s, e),
+            _ => self.get_all(),
+        };
+        CostReport::from_records(&records, pricing).with_period(start, end)
+    }
+
     /// 清理过期记录
     ///
     /// 返回清理的记录数量
@@ -500,7 +584,14 @@ impl TokenEstimator {
     /// 根据模型名称选择合适的 BPE 编码器
     fn select_bpe(&self, model: Option<&str>) -> &tiktoken_rs::CoreBPE {
         match model {
-            Some(m) if m.contains("gpt-4o") || m.contains("o1") || m.contains("o3") => {
+            Some(m)
+                if m.contains("gpt-4o")
+                    || m.contains("gpt-4.1")
+                    || m.contains("gpt-5")
+                    || m.contains("o1")
+                    || m.contains("o3")
+                    || m.contains("o4") =>
+            {
                 &self.o200k_bpe
             }
             _ => &self.default_bpe,
@@ -642,6 +733,35 @@ mod token_tests {
         assert!((summary.avg_output_tokens - 75.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_token_stats_summary_cache_tokens() {
+        let cached = TokenUsageRecord::new(
+            "1".to_string(),
+            ProviderType::Claude,
+            "claude-sonnet".to_string(),
+            1000,
+            50,
+            TokenSource::Actual,
+        )
+        .with_cache_tokens(CacheTokens {
+            cache_creation_input_tokens: 100,
+            cache_read_input_tokens: 800,
+        });
+        let uncached = TokenUsageRecord::new(
+            "2".to_string(),
+            ProviderType::Claude,
+            "claude-sonnet".to_string(),
+            1000,
+            50,
+            TokenSource::Actual,
+        );
+
+        let summary = TokenStatsSummary::from_records(&[cached, uncached]);
+        assert_eq!(summary.total_cache_creation_input_tokens, 100);
+        assert_eq!(summary.total_cache_read_input_tokens, 800);
+        assert!((summary.cache_hit_rate - 0.4).abs() < 0.001);
+    }
+
     #[test]
     fn test_token_tracker_basic_operations() {
         let tracker = TokenTracker::with_defaults();
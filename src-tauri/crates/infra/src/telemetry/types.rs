@@ -16,6 +16,9 @@ pub enum RequestStatus {
     Failed,
     /// 超时
     Timeout,
+    /// 被上游限流（429 / 529 / Throttling）
+    #[serde(rename = "rate_limited")]
+    RateLimited,
     /// 重试中
     Retrying,
     /// 已取消
@@ -28,12 +31,28 @@ impl std::fmt::Display for RequestStatus {
             RequestStatus::Success => write!(f, "success"),
             RequestStatus::Failed => write!(f, "failed"),
             RequestStatus::Timeout => write!(f, "timeout"),
+            RequestStatus::RateLimited => write!(f, "rate_limited"),
             RequestStatus::Retrying => write!(f, "retrying"),
             RequestStatus::Cancelled => write!(f, "cancelled"),
         }
     }
 }
 
+impl RequestStatus {
+    /// 根据最终响应的 HTTP 状态码确定请求状态
+    ///
+    /// 408 / 504 视为超时，499（客户端关闭请求）视为已取消
+    pub fn from_http_status(status: u16) -> Self {
+        match status {
+            200..=299 => RequestStatus::Success,
+            429 | 529 => RequestStatus::RateLimited,
+            408 | 504 => RequestStatus::Timeout,
+            499 => RequestStatus::Cancelled,
+            _ => RequestStatus::Failed,
+        }
+    }
+}
+
 /// 请求日志条目
 ///
 /// 记录每个 API 请求的详细信息，包括时间戳、Provider、模型、持续时间和状态
@@ -49,6 +68,9 @@ pub struct RequestLog {
     pub model: String,
     /// 请求持续时间（毫秒）
     pub duration_ms: u64,
+    /// 首字节延迟（毫秒，仅流式请求）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttfb_ms: Option<u64>,
     /// 请求状态
     pub status: RequestStatus,
     /// HTTP 状态码（如果有）
@@ -65,6 +87,15 @@ pub struct RequestLog {
     pub is_streaming: bool,
     /// 使用的凭证 ID（如果有）
     pub credential_id: Option<String>,
+    /// 发起请求的客户端 Key ID（如果有）
+    #[serde(default)]
+    pub client_key_id: Option<String>,
+    /// 调用方工具名称（`x-proxycast-client` 头或 User-Agent 推断）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    /// 降级前请求的模型（请求超出上下文窗口而改用长上下文模型时记录）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_from_model: Option<String>,
     /// 重试次数
     pub retry_count: u32,
 }
@@ -78,6 +109,7 @@ impl RequestLog {
             provider,
             model,
             duration_ms: 0,
+            ttfb_ms: None,
             status: RequestStatus::Retrying,
             http_status: None,
             input_tokens: None,
@@ -86,6 +118,9 @@ impl RequestLog {
             error_message: None,
             is_streaming,
             credential_id: None,
+            client_key_id: None,
+            client_name: None,
+            fallback_from_model: None,
             retry_count: 0,
         }
     }
@@ -112,6 +147,14 @@ impl RequestLog {
         self.error_message = Some("Request timeout".to_string());
     }
 
+    /// 标记请求被上游限流
+    pub fn mark_rate_limited(&mut self, duration_ms: u64, http_status: Option<u16>, error: String) {
+        self.status = RequestStatus::RateLimited;
+        self.duration_ms = duration_ms;
+        self.http_status = http_status;
+        self.error_message = Some(error);
+    }
+
     /// 标记请求取消
     pub fn mark_cancelled(&mut self, duration_ms: u64) {
         self.status = RequestStatus::Cancelled;
@@ -128,11 +171,31 @@ impl RequestLog {
         };
     }
 
+    /// 设置首字节延迟
+    pub fn set_ttfb(&mut self, ttfb_ms: u64) {
+        self.ttfb_ms = Some(ttfb_ms);
+    }
+
     /// 设置凭证 ID
     pub fn set_credential_id(&mut self, id: String) {
         self.credential_id = Some(id);
     }
 
+    /// 设置客户端 Key ID
+    pub fn set_client_key_id(&mut self, id: String) {
+        self.client_key_id = Some(id);
+    }
+
+    /// 设置调用方工具名称
+    pub fn set_client_name(&mut self, name: String) {
+        self.client_name = Some(name);
+    }
+
+    /// 设置降级前请求的模型
+    pub fn set_fallback_from_model(&mut self, model: String) {
+        self.fallback_from_model = Some(model);
+    }
+
     /// 增加重试次数
     pub fn increment_retry(&mut self) {
         self.retry_count += 1;
@@ -190,6 +253,9 @@ pub struct StatsSummary {
     pub failed_requests: u64,
     /// 超时请求数
     pub timeout_requests: u64,
+    /// 被上游限流的请求数
+    #[serde(default)]
+    pub rate_limited_requests: u64,
     /// 成功率（0.0 - 1.0）
     pub success_rate: f64,
     /// 平均延迟（毫秒）
@@ -223,6 +289,10 @@ impl StatsSummary {
             .iter()
             .filter(|l| l.status == RequestStatus::Timeout)
             .count() as u64;
+        let rate_limited_requests = logs
+            .iter()
+            .filter(|l| l.status == RequestStatus::RateLimited)
+            .count() as u64;
 
         let success_rate = if total_requests > 0 {
             successful_requests as f64 / total_requests as f64
@@ -257,6 +327,7 @@ impl StatsSummary {
             successful_requests,
             failed_requests,
             timeout_requests,
+            rate_limited_requests,
             success_rate,
             avg_latency_ms,
             min_latency_ms,
@@ -329,6 +400,22 @@ mod type_tests {
         assert_eq!(log.retry_count, 0);
     }
 
+    #[test]
+    fn test_request_status_from_http_status() {
+        assert_eq!(RequestStatus::from_http_status(200), RequestStatus::Success);
+        assert_eq!(
+            RequestStatus::from_http_status(529),
+            RequestStatus::RateLimited
+        );
+        assert_eq!(RequestStatus::from_http_status(504), RequestStatus::Timeout);
+        assert_eq!(RequestStatus::from_http_status(408), RequestStatus::Timeout);
+        assert_eq!(
+            RequestStatus::from_http_status(499),
+            RequestStatus::Cancelled
+        );
+        assert_eq!(RequestStatus::from_http_status(502), RequestStatus::Failed);
+    }
+
     #[test]
     fn test_request_log_mark_success() {
         let mut log = RequestLog::new(
@@ -364,6 +451,30 @@ mod type_tests {
         assert!(!log.is_success());
     }
 
+    #[test]
+    fn test_request_log_mark_rate_limited() {
+        let mut log = RequestLog::new(
+            "test-id".to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet".to_string(),
+            false,
+        );
+
+        log.mark_rate_limited(30, Some(429), "Too many requests".to_string());
+
+        assert_eq!(log.status, RequestStatus::RateLimited);
+        assert_eq!(log.http_status, Some(429));
+        assert_eq!(log.status.to_string(), "rate_limited");
+        assert_eq!(
+            serde_json::to_value(log.status).unwrap(),
+            serde_json::json!("rate_limited")
+        );
+
+        let summary = StatsSummary::from_logs(&[log]);
+        assert_eq!(summary.rate_limited_requests, 1);
+        assert_eq!(summary.failed_requests, 0);
+    }
+
     #[test]
     fn test_request_log_set_tokens() {
         let mut log = RequestLog::new(
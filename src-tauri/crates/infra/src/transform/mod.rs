@@ -0,0 +1,19 @@
+//! 请求转换模块
+//!
+//! 在参数注入之外提供按顺序执行的请求转换管道，支持：
+//! - 删除字段（可按 Provider 删除不支持的参数）
+//! - 改写 system prompt（替换 / 前置 / 追加）
+//! - 限制 max_tokens 上限
+//! - 为 Anthropic 请求插入 Prompt 缓存断点（`cache_control`）
+//! - dry-run：只记录将要发生的变更，不修改请求
+
+mod types;
+
+pub(crate) use types::{rewrite_system_prompt, system_prompt_contains};
+pub use types::{
+    PayloadFormat, SystemPromptMode, TransformAction, TransformChange, TransformConfig,
+    TransformResult, TransformRule, Transformer,
+};
+
+#[cfg(test)]
+mod tests;
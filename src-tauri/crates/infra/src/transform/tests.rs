@@ -0,0 +1,289 @@
+//! 请求转换模块测试
+
+use super::*;
+use serde_json::json;
+
+fn transformer(rules: Vec<TransformRule>) -> Transformer {
+    Transformer::new(TransformConfig {
+        enabled: true,
+        dry_run: false,
+        rules,
+    })
+}
+
+#[test]
+fn test_disabled_transformer_is_noop() {
+    let transformer = Transformer::new(TransformConfig {
+        enabled: false,
+        dry_run: false,
+        rules: vec![TransformRule::new(
+            "strip",
+            TransformAction::StripFields {
+                fields: vec!["user".to_string()],
+            },
+        )],
+    });
+    let mut payload = json!({"model": "gpt-4", "user": "u1"});
+    let result = transformer.apply("gpt-4", "openai", PayloadFormat::OpenAI, &mut payload);
+
+    assert!(!result.has_changes());
+    assert_eq!(payload["user"], "u1");
+}
+
+#[test]
+fn test_strip_fields_per_provider() {
+    let transformer = transformer(vec![TransformRule::new(
+        "gemini-unsupported",
+        TransformAction::StripFields {
+            fields: vec![
+                "frequency_penalty".to_string(),
+                "metadata.user_id".to_string(),
+                "model".to_string(),
+            ],
+        },
+    )
+    .with_providers(&["gemini"])]);
+
+    let original = json!({
+        "model": "gemini-2.5-pro",
+        "frequency_penalty": 0.5,
+        "metadata": {"user_id": "u1", "tag": "x"}
+    });
+
+    let mut payload = original.clone();
+    let result = transformer.apply(
+        "gemini-2.5-pro",
+        "kiro",
+        PayloadFormat::OpenAI,
+        &mut payload,
+    );
+    assert!(!result.has_changes());
+    assert_eq!(payload, original);
+
+    let result = transformer.apply(
+        "gemini-2.5-pro",
+        "Gemini",
+        PayloadFormat::OpenAI,
+        &mut payload,
+    );
+    assert_eq!(result.changes.len(), 2);
+    assert!(payload.get("frequency_penalty").is_none());
+    assert_eq!(payload["metadata"], json!({"tag": "x"}));
+    // 受保护字段不会被删除
+    assert_eq!(payload["model"], "gemini-2.5-pro");
+}
+
+#[test]
+fn test_rewrite_system_prompt() {
+    let transformer = transformer(vec![TransformRule::new(
+        "prefix",
+        TransformAction::RewriteSystemPrompt {
+            mode: SystemPromptMode::Prepend,
+            text: "Be concise.".to_string(),
+        },
+    )
+    .with_pattern("claude-*")]);
+
+    // OpenAI：已有 system 消息
+    let mut payload = json!({
+        "messages": [
+            {"role": "system", "content": "You are helpful."},
+            {"role": "user", "content": "hi"}
+        ]
+    });
+    transformer.apply(
+        "claude-sonnet-4-5",
+        "kiro",
+        PayloadFormat::OpenAI,
+        &mut payload,
+    );
+    assert_eq!(
+        payload["messages"][0]["content"],
+        "Be concise.\n\nYou are helpful."
+    );
+
+    // OpenAI：没有 system 消息时插入
+    let mut payload = json!({"messages": [{"role": "user", "content": "hi"}]});
+    transformer.apply(
+        "claude-sonnet-4-5",
+        "kiro",
+        PayloadFormat::OpenAI,
+        &mut payload,
+    );
+    assert_eq!(payload["messages"][0]["role"], "system");
+    assert_eq!(payload["messages"].as_array().unwrap().len(), 2);
+
+    // Anthropic：内容块数组
+    let mut payload = json!({"system": [{"type": "text", "text": "You are helpful."}]});
+    transformer.apply(
+        "claude-sonnet-4-5",
+        "kiro",
+        PayloadFormat::Anthropic,
+        &mut payload,
+    );
+    assert_eq!(payload["system"][0]["text"], "Be concise.");
+    assert_eq!(payload["system"][1]["text"], "You are helpful.");
+
+    // 模型不匹配
+    let mut payload = json!({"messages": []});
+    let result = transformer.apply("gpt-4", "kiro", PayloadFormat::OpenAI, &mut payload);
+    assert!(!result.has_changes());
+}
+
+#[test]
+fn test_cap_max_tokens() {
+    let transformer = transformer(vec![TransformRule::new(
+        "cap",
+        TransformAction::CapMaxTokens { max_tokens: 4096 },
+    )]);
+
+    let mut payload = json!({"max_tokens": 8192});
+    let result = transformer.apply("any", "kiro", PayloadFormat::Anthropic, &mut payload);
+    assert!(result.modified());
+    assert_eq!(payload["max_tokens"], 4096);
+
+    let mut payload = json!({"max_tokens": 1024});
+    let result = transformer.apply("any", "kiro", PayloadFormat::Anthropic, &mut payload);
+    assert!(!result.has_changes());
+
+    // 未设置时不添加
+    let mut payload = json!({"model": "any"});
+    transformer.apply("any", "kiro", PayloadFormat::OpenAI, &mut payload);
+    assert!(payload.get("max_tokens").is_none());
+}
+
+#[test]
+fn test_insert_cache_control() {
+    let transformer = transformer(vec![TransformRule::new(
+        "prompt-cache",
+        TransformAction::InsertCacheControl {
+            ttl: Some("1h".to_string()),
+        },
+    )
+    .with_pattern("claude-*")]);
+
+    let mut payload = json!({
+        "system": "You are helpful.",
+        "tools": [{"name": "a"}, {"name": "b"}],
+        "messages": [
+            {"role": "user", "content": "first"},
+            {"role": "assistant", "content": "ok"},
+            {"role": "user", "content": [{"type": "text", "text": "second"}]},
+            {"role": "assistant", "content": "ok"},
+            {"role": "user", "content": "third"}
+        ]
+    });
+    let result = transformer.apply(
+        "claude-sonnet-4-5",
+        "claude",
+        PayloadFormat::Anthropic,
+        &mut payload,
+    );
+
+    let cache_control = json!({"type": "ephemeral", "ttl": "1h"});
+    assert_eq!(result.changes.len(), 4);
+    assert!(payload["tools"][0].get("cache_control").is_none());
+    assert_eq!(payload["tools"][1]["cache_control"], cache_control);
+    assert_eq!(payload["system"][0]["text"], "You are helpful.");
+    assert_eq!(payload["system"][0]["cache_control"], cache_control);
+    assert_eq!(payload["messages"][0]["content"], "first");
+    assert_eq!(
+        payload["messages"][2]["content"][0]["cache_control"],
+        cache_control
+    );
+    assert_eq!(
+        payload["messages"][4]["content"][0]["cache_control"],
+        cache_control
+    );
+
+    // 客户端已设置断点时不修改
+    let original = json!({
+        "system": [{"type": "text", "text": "s", "cache_control": {"type": "ephemeral"}}],
+        "messages": [{"role": "user", "content": "hi"}]
+    });
+    let mut payload = original.clone();
+    let result = transformer.apply(
+        "claude-sonnet-4-5",
+        "claude",
+        PayloadFormat::Anthropic,
+        &mut payload,
+    );
+    assert!(!result.has_changes());
+    assert_eq!(payload, original);
+
+    // OpenAI 格式不处理
+    let mut payload = json!({"messages": [{"role": "user", "content": "hi"}]});
+    let result = transformer.apply(
+        "claude-sonnet-4-5",
+        "kiro",
+        PayloadFormat::OpenAI,
+        &mut payload,
+    );
+    assert!(!result.has_changes());
+}
+
+#[test]
+fn test_dry_run_does_not_modify() {
+    let mut rule = TransformRule::new("cap", TransformAction::CapMaxTokens { max_tokens: 100 });
+    rule.dry_run = true;
+    let transformer = transformer(vec![
+        rule,
+        TransformRule::new(
+            "strip",
+            TransformAction::StripFields {
+                fields: vec!["user".to_string()],
+            },
+        ),
+    ]);
+
+    let mut payload = json!({"max_tokens": 1000, "user": "u1"});
+    let result = transformer.apply("any", "kiro", PayloadFormat::OpenAI, &mut payload);
+
+    assert_eq!(result.changes.len(), 2);
+    assert!(result.changes[0].dry_run);
+    assert!(!result.changes[1].dry_run);
+    assert_eq!(payload, json!({"max_tokens": 1000}));
+}
+
+#[test]
+fn test_rule_deserialization() {
+    let config: TransformConfig = serde_json::from_value(json!({
+        "enabled": true,
+        "dry_run": true,
+        "rules": [
+            {
+                "id": "gemini-unsupported",
+                "providers": ["gemini"],
+                "type": "strip_fields",
+                "fields": ["logit_bias"]
+            },
+            {
+                "id": "cap",
+                "pattern": "claude-*",
+                "type": "cap_max_tokens",
+                "max_tokens": 8192
+            },
+            {
+                "id": "system",
+                "type": "rewrite_system_prompt",
+                "mode": "append",
+                "text": "Reply in Chinese."
+            }
+        ]
+    }))
+    .unwrap();
+    assert!(config.dry_run);
+    assert_eq!(config.rules.len(), 3);
+    assert_eq!(config.rules[0].pattern, "*");
+    assert_eq!(
+        config.rules[1].action,
+        TransformAction::CapMaxTokens { max_tokens: 8192 }
+    );
+    assert_eq!(
+        config.rules[2].action,
+        TransformAction::RewriteSystemPrompt {
+            mode: SystemPromptMode::Append,
+            text: "Reply in Chinese.".to_string()
+        }
+    );
+}
@@ -0,0 +1,506 @@
+//! 请求转换类型定义
+//!
+//! 定义转换规则、转换动作和转换器
+
+use crate::injection::pattern_matches;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// 禁止删除的顶层字段（删除后请求无法处理）
+const PROTECTED_FIELDS: &[&str] = &["model", "messages"];
+
+/// 需要限制上限的输出 Token 字段
+const MAX_TOKENS_FIELDS: &[&str] = &["max_tokens", "max_completion_tokens", "max_output_tokens"];
+
+/// Anthropic 单个请求最多允许的 `cache_control` 断点数
+const MAX_CACHE_BREAKPOINTS: usize = 4;
+
+/// 请求体格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadFormat {
+    /// OpenAI Chat Completions（system prompt 位于 messages 中）
+    OpenAI,
+    /// Anthropic Messages（system prompt 位于顶层 system 字段）
+    Anthropic,
+}
+
+/// system prompt 改写方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SystemPromptMode {
+    /// 替换原有 system prompt
+    #[default]
+    Replace,
+    /// 添加到原有 system prompt 之前
+    Prepend,
+    /// 添加到原有 system prompt 之后
+    Append,
+}
+
+/// 转换动作
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransformAction {
+    /// 删除字段，支持点号路径（如 `metadata.user_id`）
+    StripFields { fields: Vec<String> },
+    /// 改写 system prompt
+    RewriteSystemPrompt {
+        #[serde(default)]
+        mode: SystemPromptMode,
+        text: String,
+    },
+    /// 限制 max_tokens 上限（未设置时不添加）
+    CapMaxTokens { max_tokens: u64 },
+    /// 为 Anthropic 请求插入 `cache_control` 断点（工具定义、system prompt、最近的用户消息）
+    ///
+    /// 请求中已有 `cache_control` 时视为客户端自行管理缓存，不做修改
+    InsertCacheControl {
+        /// 缓存有效期（如 `1h`），不设置时使用上游默认的 5 分钟
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ttl: Option<String>,
+    },
+}
+
+/// 转换规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransformRule {
+    /// 规则 ID
+    pub id: String,
+    /// 模型匹配模式（支持通配符，默认匹配全部）
+    #[serde(default = "default_pattern")]
+    pub pattern: String,
+    /// 限定的 Provider（为空时对所有 Provider 生效）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<String>,
+    /// 是否启用
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 仅记录将要发生的变更，不修改请求
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 转换动作
+    #[serde(flatten)]
+    pub action: TransformAction,
+}
+
+fn default_pattern() -> String {
+    "*".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl TransformRule {
+    /// 创建新的转换规则（匹配所有模型和 Provider）
+    pub fn new(id: &str, action: TransformAction) -> Self {
+        Self {
+            id: id.to_string(),
+            pattern: default_pattern(),
+            providers: Vec::new(),
+            enabled: true,
+            dry_run: false,
+            action,
+        }
+    }
+
+    /// 设置模型匹配模式
+    pub fn with_pattern(mut self, pattern: &str) -> Self {
+        self.pattern = pattern.to_string();
+        self
+    }
+
+    /// 限定 Provider
+    pub fn with_providers(mut self, providers: &[&str]) -> Self {
+        self.providers = providers.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// 检查规则是否适用于该模型和 Provider
+    pub fn matches(&self, model: &str, provider: &str) -> bool {
+        self.enabled
+            && pattern_matches(&self.pattern, model)
+            && (self.providers.is_empty()
+                || self
+                    .providers
+                    .iter()
+                    .any(|p| p.eq_ignore_ascii_case(provider)))
+    }
+}
+
+/// 转换配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct TransformConfig {
+    /// 是否启用转换
+    #[serde(default)]
+    pub enabled: bool,
+    /// 全局 dry-run：所有规则只记录不生效
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 转换规则（按顺序执行）
+    #[serde(default)]
+    pub rules: Vec<TransformRule>,
+}
+
+/// 单条变更记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransformChange {
+    /// 规则 ID
+    pub rule_id: String,
+    /// 变更描述
+    pub description: String,
+    /// 是否为 dry-run（未实际修改请求）
+    pub dry_run: bool,
+}
+
+impl std::fmt::Display for TransformChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.rule_id, self.description)?;
+        if self.dry_run {
+            write!(f, " (dry-run)")?;
+        }
+        Ok(())
+    }
+}
+
+/// 转换结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransformResult {
+    /// 变更列表（按执行顺序）
+    pub changes: Vec<TransformChange>,
+}
+
+impl TransformResult {
+    /// 是否有任何变更（包括 dry-run）
+    pub fn has_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// 是否实际修改了请求
+    pub fn modified(&self) -> bool {
+        self.changes.iter().any(|c| !c.dry_run)
+    }
+}
+
+/// 请求转换器
+#[derive(Debug, Clone, Default)]
+pub struct Transformer {
+    config: TransformConfig,
+}
+
+impl Transformer {
+    /// 从配置创建转换器
+    pub fn new(config: TransformConfig) -> Self {
+        Self { config }
+    }
+
+    /// 当前配置
+    pub fn config(&self) -> &TransformConfig {
+        &self.config
+    }
+
+    /// 是否启用且存在规则
+    pub fn is_active(&self) -> bool {
+        self.config.enabled && !self.config.rules.is_empty()
+    }
+
+    /// 按顺序应用匹配的规则
+    ///
+    /// dry-run 规则在请求副本上执行，只返回变更描述
+    pub fn apply(
+        &self,
+        model: &str,
+        provider: &str,
+        format: PayloadFormat,
+        payload: &mut Value,
+    ) -> TransformResult {
+        let mut result = TransformResult::default();
+        if !self.config.enabled || !payload.is_object() {
+            return result;
+        }
+
+        for rule in self
+            .config
+            .rules
+            .iter()
+            .filter(|r| r.matches(model, provider))
+        {
+            let dry_run = self.config.dry_run || rule.dry_run;
+            let descriptions = if dry_run {
+                apply_action(&rule.action, format, &mut payload.clone())
+            } else {
+                apply_action(&rule.action, format, payload)
+            };
+            result
+                .changes
+                .extend(descriptions.into_iter().map(|description| TransformChange {
+                    rule_id: rule.id.clone(),
+                    description,
+                    dry_run,
+                }));
+        }
+
+        result
+    }
+}
+
+/// 执行单个动作，返回实际发生的变更描述
+fn apply_action(
+    action: &TransformAction,
+    format: PayloadFormat,
+    payload: &mut Value,
+) -> Vec<String> {
+    match action {
+        TransformAction::StripFields { fields } => fields
+            .iter()
+            .filter_map(|field| {
+                if PROTECTED_FIELDS.contains(&field.as_str()) {
+                    tracing::warn!("[TRANSFORM] 字段 {} 禁止删除，跳过", field);
+                    return None;
+                }
+                remove_path(payload, field).then(|| format!("strip {}", field))
+            })
+            .collect(),
+        TransformAction::RewriteSystemPrompt { mode, text } => {
+            if !rewrite_system_prompt(payload, format, *mode, text) {
+                return Vec::new();
+            }
+            let verb = match mode {
+                SystemPromptMode::Replace => "replace",
+                SystemPromptMode::Prepend => "prepend",
+                SystemPromptMode::Append => "append",
+            };
+            vec![format!("{} system prompt", verb)]
+        }
+        TransformAction::CapMaxTokens { max_tokens } => {
+            let Some(obj) = payload.as_object_mut() else {
+                return Vec::new();
+            };
+            MAX_TOKENS_FIELDS
+                .iter()
+                .filter_map(|field| {
+                    let value = obj.get_mut(*field)?;
+                    let current = value.as_u64()?;
+                    if current <= *max_tokens {
+                        return None;
+                    }
+                    *value = json!(max_tokens);
+                    Some(format!("cap {} {} -> {}", field, current, max_tokens))
+                })
+                .collect()
+        }
+        TransformAction::InsertCacheControl { ttl } => {
+            if format != PayloadFormat::Anthropic {
+                return Vec::new();
+            }
+            insert_cache_control(payload, ttl.as_deref())
+        }
+    }
+}
+
+/// 插入 `cache_control` 断点，返回插入位置的描述
+fn insert_cache_control(payload: &mut Value, ttl: Option<&str>) -> Vec<String> {
+    let already_cached = ["tools", "system", "messages"]
+        .iter()
+        .filter_map(|key| payload.get(*key))
+        .any(contains_cache_control);
+    if already_cached {
+        return Vec::new();
+    }
+
+    let mut cache_control = json!({"type": "ephemeral"});
+    if let Some(ttl) = ttl {
+        cache_control["ttl"] = json!(ttl);
+    }
+    let mut changes = Vec::new();
+
+    // 工具定义位于缓存前缀的最前面，标记最后一个工具即可缓存全部工具
+    if let Some(tool) = payload
+        .get_mut("tools")
+        .and_then(Value::as_array_mut)
+        .and_then(|tools| tools.last_mut())
+        .and_then(Value::as_object_mut)
+    {
+        tool.insert("cache_control".to_string(), cache_control.clone());
+        changes.push("cache_control on tools".to_string());
+    }
+
+    if let Some(system) = payload.get_mut("system") {
+        if mark_last_block(system, &cache_control) {
+            changes.push("cache_control on system".to_string());
+        }
+    }
+
+    // 最近两条用户消息：一条写入本轮缓存，一条命中上一轮写入的缓存
+    let remaining = MAX_CACHE_BREAKPOINTS.saturating_sub(changes.len()).min(2);
+    if let Some(messages) = payload.get_mut("messages").and_then(Value::as_array_mut) {
+        let user_indices: Vec<usize> = messages
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, m)| m.get("role").and_then(Value::as_str) == Some("user"))
+            .map(|(i, _)| i)
+            .take(remaining)
+            .collect();
+        for index in user_indices {
+            if let Some(content) = messages[index].get_mut("content") {
+                if mark_last_block(content, &cache_control) {
+                    changes.push(format!("cache_control on messages[{}]", index));
+                }
+            }
+        }
+    }
+
+    changes
+}
+
+/// 在内容（字符串或内容块数组）的最后一个块上设置 `cache_control`，返回是否修改
+///
+/// 字符串内容会转换为单个 text 块；空文本块不能作为断点
+fn mark_last_block(content: &mut Value, cache_control: &Value) -> bool {
+    if let Value::String(text) = content {
+        if text.is_empty() {
+            return false;
+        }
+        *content = json!([{"type": "text", "text": text}]);
+    }
+    let Some(block) = content
+        .as_array_mut()
+        .and_then(|blocks| blocks.last_mut())
+        .and_then(Value::as_object_mut)
+    else {
+        return false;
+    };
+    let is_empty_text = block.get("type").and_then(Value::as_str) == Some("text")
+        && block
+            .get("text")
+            .and_then(Value::as_str)
+            .is_none_or(str::is_empty);
+    if is_empty_text {
+        return false;
+    }
+    block.insert("cache_control".to_string(), cache_control.clone());
+    true
+}
+
+/// 递归检查是否已包含 `cache_control`
+fn contains_cache_control(value: &Value) -> bool {
+    match value {
+        Value::Object(obj) => {
+            obj.contains_key("cache_control") || obj.values().any(contains_cache_control)
+        }
+        Value::Array(items) => items.iter().any(contains_cache_control),
+        _ => false,
+    }
+}
+
+/// 按点号路径删除字段，返回是否删除成功
+fn remove_path(payload: &mut Value, path: &str) -> bool {
+    let mut segments: Vec<&str> = path.split('.').collect();
+    let Some(last) = segments.pop() else {
+        return false;
+    };
+    let mut current = payload;
+    for segment in segments {
+        match current.get_mut(segment) {
+            Some(next) => current = next,
+            None => return false,
+        }
+    }
+    current
+        .as_object_mut()
+        .is_some_and(|obj| obj.remove(last).is_some())
+}
+
+/// 改写 system prompt（不存在时插入），返回是否修改
+pub(crate) fn rewrite_system_prompt(
+    payload: &mut Value,
+    format: PayloadFormat,
+    mode: SystemPromptMode,
+    text: &str,
+) -> bool {
+    match format {
+        PayloadFormat::OpenAI => rewrite_openai_system(payload, mode, text),
+        PayloadFormat::Anthropic => rewrite_anthropic_system(payload, mode, text),
+    }
+}
+
+/// 检查 system prompt 是否已包含指定文本
+pub(crate) fn system_prompt_contains(payload: &Value, format: PayloadFormat, text: &str) -> bool {
+    let system = match format {
+        PayloadFormat::OpenAI => payload
+            .get("messages")
+            .and_then(Value::as_array)
+            .and_then(|messages| {
+                messages
+                    .iter()
+                    .find(|m| m.get("role").and_then(Value::as_str) == Some("system"))
+            })
+            .and_then(|message| message.get("content")),
+        PayloadFormat::Anthropic => payload.get("system"),
+    };
+    match system {
+        Some(Value::String(existing)) => existing.contains(text),
+        Some(Value::Array(blocks)) => blocks.iter().any(|block| {
+            block
+                .get("text")
+                .and_then(Value::as_str)
+                .is_some_and(|t| t.contains(text))
+        }),
+        _ => false,
+    }
+}
+
+fn combine_prompt(existing: &str, text: &str, mode: SystemPromptMode) -> String {
+    match mode {
+        SystemPromptMode::Replace => text.to_string(),
+        SystemPromptMode::Prepend => format!("{}\n\n{}", text, existing),
+        SystemPromptMode::Append => format!("{}\n\n{}", existing, text),
+    }
+}
+
+/// 改写内容字段（字符串或内容块数组）
+fn rewrite_content(content: &mut Value, mode: SystemPromptMode, text: &str) {
+    match content {
+        Value::String(existing) => *existing = combine_prompt(existing, text, mode),
+        Value::Array(blocks) if mode != SystemPromptMode::Replace => {
+            let block = json!({"type": "text", "text": text});
+            if mode == SystemPromptMode::Prepend {
+                blocks.insert(0, block);
+            } else {
+                blocks.push(block);
+            }
+        }
+        other => *other = json!(text),
+    }
+}
+
+fn rewrite_openai_system(payload: &mut Value, mode: SystemPromptMode, text: &str) -> bool {
+    let Some(messages) = payload.get_mut("messages").and_then(Value::as_array_mut) else {
+        return false;
+    };
+    match messages
+        .iter_mut()
+        .find(|m| m.get("role").and_then(Value::as_str) == Some("system"))
+    {
+        Some(message) => match message.get_mut("content") {
+            Some(content) => rewrite_content(content, mode, text),
+            None => message["content"] = json!(text),
+        },
+        None => messages.insert(0, json!({"role": "system", "content": text})),
+    }
+    true
+}
+
+fn rewrite_anthropic_system(payload: &mut Value, mode: SystemPromptMode, text: &str) -> bool {
+    let Some(obj) = payload.as_object_mut() else {
+        return false;
+    };
+    match obj.get_mut("system") {
+        Some(system) if !system.is_null() => rewrite_content(system, mode, text),
+        _ => {
+            obj.insert("system".to_string(), json!(text));
+        }
+    }
+    true
+}
@@ -197,6 +197,12 @@ impl CredentialBridge {
                 ("anthropic".to_string(), Some(token), None)
             }
 
+            // Qwen OAuth - OpenAI 兼容接口
+            CredentialData::QwenOAuth { creds_file_path } => {
+                let (token, base_url) = self.get_qwen_token(creds_file_path).await?;
+                ("openai".to_string(), Some(token), Some(base_url))
+            }
+
             // Antigravity OAuth
             CredentialData::AntigravityOAuth {
                 creds_file_path, ..
@@ -204,6 +210,47 @@ impl CredentialBridge {
                 let token = self.get_oauth_token(creds_file_path).await?;
                 ("google".to_string(), Some(token), None)
             }
+
+            // Azure OpenAI
+            CredentialData::AzureOpenaiKey {
+                api_key, resource, ..
+            } => {
+                let endpoint = crate::providers::AzureOpenAIProvider::new(
+                    api_key.clone(),
+                    resource.clone(),
+                    None,
+                )
+                .endpoint();
+                ("azure".to_string(), Some(api_key.clone()), Some(endpoint))
+            }
+
+            // AWS Bedrock - 需要 SigV4 签名，无法用单个 API Key 表示
+            CredentialData::AwsBedrock { .. } => {
+                return Err(CredentialBridgeError::UnsupportedCredentialType(
+                    "AWS Bedrock 凭证请通过 API 代理使用".to_string(),
+                ));
+            }
+
+            // Vertex AI 服务账号 - 需要 JWT 换取 access token
+            CredentialData::VertexServiceAccount { .. } => {
+                return Err(CredentialBridgeError::UnsupportedCredentialType(
+                    "Vertex AI 服务账号凭证请通过 API 代理使用".to_string(),
+                ));
+            }
+
+            // 本地模型服务 - 不需要 API Key
+            CredentialData::Ollama { base_url } => (
+                "ollama".to_string(),
+                None,
+                Some(crate::providers::OllamaProvider::new(base_url.clone()).root_url()),
+            ),
+
+            // Mock - 只在 API 代理中生成响应
+            CredentialData::Mock { .. } => {
+                return Err(CredentialBridgeError::UnsupportedCredentialType(
+                    "Mock 凭证请通过 API 代理使用".to_string(),
+                ));
+            }
         };
 
         Ok(AsterProviderConfig {
@@ -289,6 +336,28 @@ impl CredentialBridge {
         })
     }
 
+    /// 获取 Qwen OAuth Token 及其 API Base URL
+    async fn get_qwen_token(
+        &self,
+        creds_path: &str,
+    ) -> Result<(String, String), CredentialBridgeError> {
+        use crate::providers::qwen::QwenProvider;
+
+        let mut provider = QwenProvider::new();
+        provider
+            .load_credentials_from_path(creds_path)
+            .await
+            .map_err(|e| {
+                CredentialBridgeError::TokenRefreshFailed(format!("加载 Qwen 凭证失败: {}", e))
+            })?;
+
+        let token = provider.ensure_valid_token().await.map_err(|e| {
+            CredentialBridgeError::TokenRefreshFailed(format!("获取 Qwen token 失败: {}", e))
+        })?;
+
+        Ok((token, provider.get_base_url()))
+    }
+
     /// 记录凭证使用
     pub fn record_usage(&self, db: &DbConnection, uuid: &str) -> Result<(), CredentialBridgeError> {
         self.pool_service
@@ -402,6 +471,7 @@ pub fn map_pool_type_to_aster(pool_type: &PoolProviderType) -> &'static str {
         PoolProviderType::AzureOpenai => "azure",
         PoolProviderType::AwsBedrock => "bedrock",
         PoolProviderType::Ollama => "ollama",
+        PoolProviderType::Mock => "mock",
     }
 }
 
@@ -7,6 +7,7 @@ use tokio::sync::RwLock;
 
 use crate::agent::AsterAgentState;
 use crate::commands::api_key_provider_cmd::ApiKeyProviderServiceState;
+use crate::commands::client_api_key_cmd::ClientApiKeyServiceState;
 use crate::commands::connect_cmd::ConnectStateWrapper;
 use crate::commands::context_memory::ContextMemoryServiceState;
 use crate::commands::flow_monitor_cmd::{
@@ -37,8 +38,10 @@ use crate::logger;
 use crate::plugin;
 use crate::server;
 use crate::services::api_key_provider_service::ApiKeyProviderService;
+use crate::services::client_api_key_service::ClientApiKeyService;
 use crate::services::context_memory_service::{ContextMemoryConfig, ContextMemoryService};
 use crate::services::provider_pool_service::ProviderPoolService;
+use crate::services::secret_store;
 use crate::services::skill_service::SkillService;
 use crate::services::token_cache_service::TokenCacheService;
 use crate::services::tool_hooks_service::ToolHooksService;
@@ -55,7 +58,7 @@ pub enum ConfigError {
     SaveFailed(String),
     InvalidHost,
     DefaultApiKeyWithNonLocalBind,
-    TlsNotSupported,
+    InvalidTls(String),
     RemoteManagementNotSupported,
 }
 
@@ -74,7 +77,7 @@ impl std::fmt::Display for ConfigError {
                 f,
                 "监听所有网络接口 (0.0.0.0 或 ::) 时，必须设置非默认的 API Key"
             ),
-            ConfigError::TlsNotSupported => write!(f, "当前版本尚未支持 TLS"),
+            ConfigError::InvalidTls(e) => write!(f, "TLS 配置无效: {}", e),
             ConfigError::RemoteManagementNotSupported => {
                 write!(f, "远程管理需要 TLS 支持，当前版本未启用")
             }
@@ -100,9 +103,8 @@ pub fn load_and_validate_config() -> Result<Config, ConfigError> {
     }
 
     // 检查 TLS 配置
-    if config.server.tls.enable {
-        return Err(ConfigError::TlsNotSupported);
-    }
+    crate::server::tls::validate_tls_config(&config.server.tls, &config.server.host)
+        .map_err(ConfigError::InvalidTls)?;
 
     // 检查远程管理配置
     if config.remote_management.allow_remote {
@@ -112,6 +114,38 @@ pub fn load_and_validate_config() -> Result<Config, ConfigError> {
     Ok(config)
 }
 
+/// 初始化全局 tracing subscriber（每个进程只调用一次）
+///
+/// 在同一个 registry 上组合标准错误日志输出（`log_to_stderr`）和 OTLP 链路追踪
+/// （`otlp.enabled`），两者都未启用时不设置 subscriber。
+pub fn init_tracing(config: &Config, log_to_stderr: bool) {
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let fmt = log_to_stderr.then(|| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_target(false)
+    });
+    let otlp = telemetry::otlp_layer(&config.otlp);
+    if fmt.is_none() && otlp.is_none() {
+        return;
+    }
+
+    let otlp_enabled = otlp.is_some();
+    if let Err(e) = tracing_subscriber::registry()
+        .with(fmt)
+        .with(otlp)
+        .try_init()
+    {
+        eprintln!("[TRACING] 初始化失败: {}", e);
+        return;
+    }
+    if otlp_enabled {
+        tracing::info!("[OTLP] 链路追踪已启用，导出到 {}", config.otlp.traces_url());
+    }
+}
+
 /// 应用状态集合
 pub struct AppStates {
     pub state: AppState,
@@ -120,6 +154,7 @@ pub struct AppStates {
     pub skill_service: SkillServiceState,
     pub provider_pool_service: ProviderPoolServiceState,
     pub api_key_provider_service: ApiKeyProviderServiceState,
+    pub client_api_key_service: ClientApiKeyServiceState,
     pub credential_sync_service: CredentialSyncServiceState,
     pub token_cache_service: TokenCacheServiceState,
     pub machine_id_service: MachineIdState,
@@ -162,6 +197,14 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
     let state: AppState = Arc::new(RwLock::new(server::ServerState::new(config.clone())));
     let logs: LogState = Arc::new(RwLock::new(logger::LogStore::with_config(&config.logging)));
 
+    // 凭证加密主密钥需在读取凭证池之前加载
+    if let Err(e) = secret_store::init(&config.secrets) {
+        tracing::warn!(
+            "[SECRETS] 凭证加密初始化失败，新写入的凭证将保持明文: {}",
+            e
+        );
+    }
+
     // 数据库
     let db = database::init_database().map_err(|e| format!("数据库初始化失败: {}", e))?;
 
@@ -177,6 +220,9 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
     let api_key_provider_service_state =
         ApiKeyProviderServiceState(Arc::new(api_key_provider_service));
 
+    let client_api_key_service_state =
+        ClientApiKeyServiceState(Arc::new(ClientApiKeyService::new()));
+
     let credential_sync_service_state = CredentialSyncServiceState(None);
 
     let token_cache_service = TokenCacheService::new();
@@ -272,6 +318,7 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
         skill_service: skill_service_state,
         provider_pool_service: provider_pool_service_state,
         api_key_provider_service: api_key_provider_service_state,
+        client_api_key_service: client_api_key_service_state,
         credential_sync_service: credential_sync_service_state,
         token_cache_service: token_cache_service_state,
         machine_id_service: machine_id_service_state,
@@ -85,6 +85,10 @@ pub async fn check_api_compatibility(
             ("gemini-2.5-flash", "tool_call"),
         ],
         ProviderType::Codex => vec![("gpt-4.1", "basic"), ("gpt-4.1", "tool_call")],
+        ProviderType::Qwen => vec![
+            ("qwen3-coder-plus", "basic"),
+            ("qwen3-coder-plus", "tool_call"),
+        ],
         ProviderType::ClaudeOAuth => vec![
             ("claude-sonnet-4-5", "basic"),
             ("claude-sonnet-4-5", "tool_call"),
@@ -96,7 +100,8 @@ pub async fn check_api_compatibility(
         ProviderType::Anthropic
         | ProviderType::AzureOpenai
         | ProviderType::AwsBedrock
-        | ProviderType::Ollama => vec![],
+        | ProviderType::Ollama
+        | ProviderType::Mock => vec![],
     };
 
     for (model, test_type) in test_cases {
@@ -140,6 +145,7 @@ pub async fn check_api_compatibility(
                     }]),
                     tool_choice: None,
                     reasoning_effort: None,
+                    extra: Default::default(),
                 }
             }
             _ => {
@@ -162,6 +168,7 @@ pub async fn check_api_compatibility(
                     tools: None,
                     tool_choice: None,
                     reasoning_effort: None,
+                    extra: Default::default(),
                 }
             }
         };
@@ -310,14 +317,18 @@ pub async fn test_api(
     let s = state.read().await;
     // 使用 status() 获取实际监听的地址（可能与配置不同）
     let status = s.status();
-    let base_url = format!("http://{}:{}", status.host, status.port);
+    let tls_enabled = s.running && s.config.server.tls.enable;
+    let scheme = if tls_enabled { "https" } else { "http" };
+    let base_url = format!("{}://{}:{}", scheme, status.host, status.port);
     let api_key = s
         .running_api_key
         .as_ref()
         .unwrap_or(&s.config.server.api_key);
 
+    // 本地服务器可能使用自签名证书
     let client = reqwest::Client::builder()
         .no_proxy()
+        .danger_accept_invalid_certs(tls_enabled)
         .build()
         .map_err(|e| e.to_string())?;
 
@@ -61,6 +61,47 @@ pub async fn save_config(
     }
 }
 
+/// 获取配置 Profile 列表和当前 Profile
+#[tauri::command]
+pub async fn get_config_profiles(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let s = state.read().await;
+    Ok(serde_json::json!({
+        "active": s.config.active_profile.clone(),
+        "profiles": s.config.profiles.keys().cloned().collect::<Vec<_>>(),
+    }))
+}
+
+/// 切换配置 Profile（`None` 表示仅使用基础配置），无需重启
+///
+/// 写入配置文件后由文件监控触发运行中服务器的热重载
+#[tauri::command]
+pub async fn switch_config_profile(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+    config_manager: tauri::State<'_, GlobalConfigManagerState>,
+    profile: Option<String>,
+) -> Result<config::Config, String> {
+    let config_path = config::ConfigManager::default_config_path();
+    let new_config =
+        config::switch_profile(&config_path, profile.as_deref()).map_err(|e| e.to_string())?;
+
+    state.write().await.config = new_config.clone();
+    config_manager
+        .0
+        .update_config(new_config.clone(), ConfigChangeSource::FrontendUI)
+        .await;
+
+    let profile_display = profile.as_deref().unwrap_or("基础配置");
+    logs.write().await.add(
+        "info",
+        &format!("配置 Profile 已切换为: {}", profile_display),
+    );
+    tracing::info!("[CONFIG] 配置 Profile 已切换: {}", profile_display);
+    Ok(new_config)
+}
+
 /// 获取默认 Provider
 #[tauri::command]
 pub async fn get_default_provider(state: tauri::State<'_, AppState>) -> Result<String, String> {
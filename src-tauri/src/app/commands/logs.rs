@@ -1,9 +1,11 @@
 //! 日志命令
 //!
-//! 包含日志查询和清理命令。
+//! 包含日志查询、订阅和清理命令。
 
 use crate::app::types::LogState;
 use crate::logger;
+use tauri::ipc::Channel;
+use tokio::sync::broadcast::error::RecvError;
 
 /// 获取日志
 #[tauri::command]
@@ -11,6 +13,58 @@ pub async fn get_logs(logs: tauri::State<'_, LogState>) -> Result<Vec<logger::Lo
     Ok(logs.read().await.get_logs())
 }
 
+/// 按级别、关键词（子串或正则）过滤并分页查询日志
+#[tauri::command]
+pub async fn query_logs(
+    logs: tauri::State<'_, LogState>,
+    query: Option<logger::LogQuery>,
+) -> Result<logger::LogPage, String> {
+    logs.read().await.query(&query.unwrap_or_default())
+}
+
+/// 获取内存日志统计
+#[tauri::command]
+pub async fn get_log_stats(logs: tauri::State<'_, LogState>) -> Result<logger::LogStats, String> {
+    Ok(logs.read().await.stats())
+}
+
+/// 将日志导出为 NDJSON 文件（每行一个 JSON 对象，带 request_id 关联字段），返回导出的条数
+#[tauri::command]
+pub async fn export_logs(
+    logs: tauri::State<'_, LogState>,
+    path: String,
+    query: Option<logger::LogQuery>,
+) -> Result<usize, String> {
+    let path = crate::config::expand_tilde(&path);
+    logs.read()
+        .await
+        .export_ndjson(&path, &query.unwrap_or_default())
+}
+
+/// 订阅新日志，新日志通过 Channel 推送给前端（实时日志视图），推送失败时停止
+#[tauri::command]
+pub async fn subscribe_logs(
+    logs: tauri::State<'_, LogState>,
+    channel: Channel<logger::LogEntry>,
+) -> Result<(), String> {
+    let mut receiver = logs.read().await.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(entry) => {
+                    if channel.send(entry).is_err() {
+                        break;
+                    }
+                }
+                // 前端处理不及时时跳过积压的日志
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+    Ok(())
+}
+
 /// 清除日志
 #[tauri::command]
 pub async fn clear_logs(logs: tauri::State<'_, LogState>) -> Result<(), String> {
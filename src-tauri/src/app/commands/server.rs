@@ -50,6 +50,24 @@ pub async fn stop_server(
     Ok("Server stopped".to_string())
 }
 
+/// 重启服务器（应用最新配置，监听地址不变时不中断连接）
+#[tauri::command]
+pub async fn restart_server(
+    state: tauri::State<'_, AppState>,
+    logs: tauri::State<'_, LogState>,
+) -> Result<String, String> {
+    let mut s = state.write().await;
+    logs.write().await.add("info", "Restarting server...");
+    s.restart().await.map_err(|e| e.to_string())?;
+
+    let status = s.status();
+    logs.write().await.add(
+        "info",
+        &format!("Server restarted on {}:{}", status.host, status.port),
+    );
+    Ok("Server restarted".to_string())
+}
+
 /// 获取服务器状态
 #[tauri::command]
 pub async fn get_server_status(
@@ -0,0 +1,256 @@
+//! 无界面模式
+//!
+//! `proxycast serve [--config <path>]` 不启动 Tauri 窗口，只运行代理服务器、配置热重载、
+//! 遥测和管理 API，适合部署在没有图形界面的服务器上。收到 Ctrl+C / SIGTERM 时排空
+//! 进行中的请求后退出。
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::config::{Config, ConfigManager};
+use crate::database;
+use crate::logger;
+use crate::server;
+use crate::services::provider_pool_service::ProviderPoolService;
+use crate::services::secret_store;
+use crate::services::token_cache_service::TokenCacheService;
+
+use super::bootstrap;
+use super::server_runner::{start_server, ServerServices};
+use super::types::{AppState, LogState};
+use super::utils::is_non_local_bind;
+
+/// `serve` 子命令的用法说明
+pub const SERVE_USAGE: &str = "\
+用法: proxycast serve [选项]
+
+以无界面模式运行代理服务器
+
+选项:
+  -c, --config <PATH>  配置文件路径（默认使用系统配置目录下的 proxycast/config.yaml）
+  -h, --help           显示帮助信息";
+
+/// `serve` 子命令参数
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ServeArgs {
+    /// 配置文件路径，未指定时使用默认路径
+    pub config_path: Option<PathBuf>,
+    /// 仅显示帮助信息
+    pub help: bool,
+}
+
+impl ServeArgs {
+    /// 解析 `serve` 之后的命令行参数
+    pub fn parse<I, S>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().map(Into::into);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "-c" | "--config" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| format!("{} 需要指定配置文件路径", arg))?;
+                    parsed.config_path = Some(PathBuf::from(path));
+                }
+                _ => match arg.strip_prefix("--config=") {
+                    Some(path) if !path.is_empty() => {
+                        parsed.config_path = Some(PathBuf::from(path));
+                    }
+                    _ => return Err(format!("未知参数: {}", arg)),
+                },
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// 运行 `serve` 子命令，返回进程退出码
+pub fn run_cli<I, S>(args: I) -> i32
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let args = match ServeArgs::parse(args) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, SERVE_USAGE);
+            return 2;
+        }
+    };
+    if args.help {
+        println!("{}", SERVE_USAGE);
+        return 0;
+    }
+
+    match serve(args) {
+        Ok(()) => 0,
+        Err(err) => {
+            tracing::error!("{}", err);
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+/// 以无界面模式运行代理服务器，直到收到退出信号
+pub fn serve(args: ServeArgs) -> Result<(), String> {
+    if let Some(path) = args.config_path {
+        let path = crate::config::expand_tilde(&path);
+        if !path.is_file() {
+            return Err(format!("配置文件不存在: {}", path.display()));
+        }
+        let path = path
+            .canonicalize()
+            .map_err(|e| format!("无法解析配置文件路径 {}: {}", path.display(), e))?;
+        let _ = ConfigManager::set_config_path(path);
+    }
+
+    let config = bootstrap::load_and_validate_config().map_err(|e| e.to_string())?;
+    bootstrap::init_tracing(&config, true);
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("创建异步运行时失败: {}", e))?;
+    let services = {
+        let _guard = runtime.enter();
+        init_services(&config)?
+    };
+    runtime.block_on(run_server(config, services))
+}
+
+/// 初始化代理服务器需要的状态，不创建任何界面相关的服务
+fn init_services(config: &Config) -> Result<ServerServices, String> {
+    let state: AppState = Arc::new(RwLock::new(server::ServerState::new(config.clone())));
+    let logs: LogState = Arc::new(RwLock::new(logger::LogStore::with_config(&config.logging)));
+
+    // 凭证加密主密钥需在读取凭证池之前加载
+    if let Err(e) = secret_store::init(&config.secrets) {
+        tracing::warn!(
+            "[SECRETS] 凭证加密初始化失败，新写入的凭证将保持明文: {}",
+            e
+        );
+    }
+
+    let db = database::init_database().map_err(|e| format!("数据库初始化失败: {}", e))?;
+    let telemetry = super::state::init_telemetry_states(config);
+
+    Ok(ServerServices {
+        state,
+        logs,
+        db,
+        pool_service: Arc::new(ProviderPoolService::new()),
+        token_cache: Arc::new(TokenCacheService::new()),
+        shared_stats: telemetry.stats,
+        shared_tokens: telemetry.tokens,
+        shared_logger: telemetry.logger,
+        // Flow 监控依赖桌面端插件和界面查看，无界面模式不启用
+        flow_monitor: None,
+        flow_interceptor: None,
+    })
+}
+
+/// 启动代理服务器，收到退出信号后排空请求并停止
+async fn run_server(config: Config, services: ServerServices) -> Result<(), String> {
+    tracing::info!(
+        "[HEADLESS] 使用配置文件: {}",
+        ConfigManager::default_config_path().display()
+    );
+    if is_non_local_bind(&config.server.host) {
+        tracing::warn!(
+            "[HEADLESS] 服务器监听 {}，请确认已通过防火墙或 TLS 保护端口 {}",
+            config.server.host,
+            config.server.port
+        );
+    }
+
+    forward_logs(&services.logs).await;
+
+    let state = services.state.clone();
+    start_server(services, None).await?;
+
+    wait_for_shutdown_signal().await;
+    tracing::info!("[HEADLESS] 收到退出信号，正在停止服务器...");
+    state.write().await.stop().await;
+    tracing::info!("[HEADLESS] 服务器已停止");
+    Ok(())
+}
+
+/// 将应用日志输出到标准输出，便于 systemd / Docker 收集
+async fn forward_logs(logs: &LogState) {
+    let mut receiver = logs.read().await.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(entry) => println!(
+                    "{} [{}] {}",
+                    entry.timestamp,
+                    entry.level.to_uppercase(),
+                    entry.message
+                ),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    println!("[HEADLESS] 日志输出过慢，跳过 {} 条", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// 等待 Ctrl+C 或 SIGTERM
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("[HEADLESS] 注册 SIGTERM 处理失败: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_serve_args() {
+        assert_eq!(
+            ServeArgs::parse(Vec::<String>::new()).unwrap(),
+            ServeArgs::default()
+        );
+        assert_eq!(
+            ServeArgs::parse(["--config", "/etc/proxycast.yaml"])
+                .unwrap()
+                .config_path,
+            Some(PathBuf::from("/etc/proxycast.yaml"))
+        );
+        assert_eq!(
+            ServeArgs::parse(["--config=conf.yaml"])
+                .unwrap()
+                .config_path,
+            Some(PathBuf::from("conf.yaml"))
+        );
+        assert_eq!(
+            ServeArgs::parse(["-c", "conf.yaml"]).unwrap().config_path,
+            Some(PathBuf::from("conf.yaml"))
+        );
+        assert!(ServeArgs::parse(["--help"]).unwrap().help);
+        assert!(ServeArgs::parse(["--config"]).is_err());
+        assert!(ServeArgs::parse(["--config="]).is_err());
+        assert!(ServeArgs::parse(["--port", "8999"]).is_err());
+    }
+}
@@ -10,10 +10,14 @@
 //! - `utils` - 辅助函数
 //! - `bootstrap` - 应用启动引导（配置验证、状态初始化）
 //! - `runner` - 应用运行器（Tauri Builder 配置和命令注册）
+//! - `server_runner` - 代理服务器启动（桌面应用与无界面模式共用）
+//! - `headless` - 无界面模式（`proxycast serve`）
 
 pub mod bootstrap;
 pub mod commands;
+pub mod headless;
 pub mod runner;
+pub mod server_runner;
 mod setup;
 mod state;
 mod types;
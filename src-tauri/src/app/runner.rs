@@ -13,6 +13,7 @@ use crate::tray::{TrayIconStatus, TrayManager, TrayStateSnapshot};
 
 use super::bootstrap::{self, AppStates};
 use super::commands as app_commands;
+use super::server_runner::{start_server, ServerServices};
 use super::types::{AppState, TrayManagerState};
 
 /// 运行 Tauri 应用
@@ -35,6 +36,9 @@ pub fn run() {
         }
     };
 
+    // 初始化 tracing（启用 OTLP 时导出链路追踪）
+    bootstrap::init_tracing(&config, false);
+
     // 初始化所有应用状态
     let states = match bootstrap::init_states(&config) {
         Ok(s) => s,
@@ -53,6 +57,7 @@ pub fn run() {
         skill_service: skill_service_state,
         provider_pool_service: provider_pool_service_state,
         api_key_provider_service: api_key_provider_service_state,
+        client_api_key_service: client_api_key_service_state,
         credential_sync_service: credential_sync_service_state,
         token_cache_service: token_cache_service_state,
         machine_id_service: machine_id_service_state,
@@ -136,6 +141,7 @@ pub fn run() {
         .manage(skill_service_state)
         .manage(provider_pool_service_state)
         .manage(api_key_provider_service_state)
+        .manage(client_api_key_service_state)
         .manage(credential_sync_service_state)
         .manage(token_cache_service_state)
         .manage(machine_id_service_state)
@@ -165,6 +171,14 @@ pub fn run() {
         .manage(context_memory_service)
         .manage(tool_hooks_service)
         .on_window_event(move |window, event| {
+            // 拖放到窗口的文件授权给终端上传（zmodem）
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                let app_handle = window.app_handle();
+                if let Some(grants) = app_handle.try_state::<Arc<crate::terminal::UploadGrants>>() {
+                    grants.grant(paths.iter().cloned());
+                }
+            }
+
             // 处理窗口关闭事件
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
                 // 获取配置，检查是否启用最小化到托盘
@@ -329,6 +343,75 @@ pub fn run() {
                 });
             }
 
+            // 初始化终端命令历史存储
+            // 建表失败时仍注册状态，命令历史相关命令返回数据库错误
+            {
+                let db = app.state::<crate::database::DbConnection>().inner().clone();
+                let store = crate::terminal::CommandHistoryStore::new(db);
+                match store.init_tables() {
+                    Ok(()) => tracing::info!("[启动] 终端命令历史存储初始化成功"),
+                    Err(e) => tracing::error!("[启动] 终端命令历史存储初始化失败: {}", e),
+                }
+                app.manage(Arc::new(store));
+            }
+
+            // 初始化终端启动配置存储
+            {
+                let db = app.state::<crate::database::DbConnection>().inner().clone();
+                let store = crate::terminal::LaunchProfileStore::new(db);
+                match store.init_tables() {
+                    Ok(()) => tracing::info!("[启动] 终端启动配置存储初始化成功"),
+                    Err(e) => tracing::error!("[启动] 终端启动配置存储初始化失败: {}", e),
+                }
+                app.manage(Arc::new(store));
+            }
+
+            // 初始化终端剪贴板策略存储和 OSC 52 剪贴板桥接
+            {
+                let db = app.state::<crate::database::DbConnection>().inner().clone();
+                let store = crate::terminal::ClipboardPolicyStore::new(db);
+                match store.init_tables() {
+                    Ok(()) => tracing::info!("[启动] 终端剪贴板策略存储初始化成功"),
+                    Err(e) => tracing::error!("[启动] 终端剪贴板策略存储初始化失败: {}", e),
+                }
+                let store = Arc::new(store);
+                let bridge = crate::terminal::ClipboardBridge::new(store.clone(), app.handle().clone());
+                app.manage(store);
+                app.manage(Arc::new(bridge));
+            }
+
+            // 初始化终端内联图片缓存
+            // 无法使用默认目录时回退到临时目录，仍失败时不注册（图片只显示不缓存）
+            {
+                let limits = crate::terminal::ImageCacheLimits::default();
+                let cache = crate::terminal::ImageCache::default_dir()
+                    .and_then(|dir| crate::terminal::ImageCache::new(dir, limits))
+                    .or_else(|e| {
+                        tracing::warn!("[启动] 终端图片缓存目录不可用，使用临时目录: {}", e);
+                        crate::terminal::ImageCache::new(
+                            std::env::temp_dir().join("proxycast_terminal_images"),
+                            limits,
+                        )
+                    });
+                match cache {
+                    Ok(cache) => {
+                        tracing::info!("[启动] 终端图片缓存初始化成功");
+                        app.manage(Arc::new(cache));
+                    }
+                    Err(e) => tracing::error!("[启动] 终端图片缓存初始化失败: {}", e),
+                }
+            }
+
+            // 注册终端上传授权（只允许读取拖放到窗口或在文件对话框中选择的文件）
+            app.manage(Arc::new(crate::terminal::UploadGrants::new()));
+
+            // 注册 SSH 连接注册表（端口转发等命令按连接名称查找 SSHConn，终端块通过它复用连接）
+            {
+                let registry = crate::terminal::connections::SSHConnRegistry::new();
+                registry.set_app_handle(app.handle().clone());
+                app.manage(Arc::new(registry));
+            }
+
             // 初始化终端会话管理器
             {
                 let app_handle = app.handle().clone();
@@ -433,114 +516,23 @@ pub fn run() {
             }
 
             // 自动启动服务器
-            let state = state_clone.clone();
-            let logs = logs_clone.clone();
-            let db = db_clone.clone();
-            let pool_service = pool_service_clone.clone();
-            let token_cache = token_cache_clone.clone();
-            let shared_stats = shared_stats_clone.clone();
-            let shared_tokens = shared_tokens_clone.clone();
-            let shared_logger = shared_logger_clone.clone();
-            let shared_flow_monitor = flow_monitor_clone.clone();
+            let services = ServerServices {
+                state: state_clone.clone(),
+                logs: logs_clone.clone(),
+                db: db_clone.clone(),
+                pool_service: pool_service_clone.clone(),
+                token_cache: token_cache_clone.clone(),
+                shared_stats: shared_stats_clone.clone(),
+                shared_tokens: shared_tokens_clone.clone(),
+                shared_logger: shared_logger_clone.clone(),
+                flow_monitor: Some(flow_monitor_clone.clone()),
+                flow_interceptor: Some(flow_interceptor_clone.clone()),
+            };
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                // 先加载凭证池中的凭证
-                {
-                    logs.write().await.add("info", "[启动] 正在加载凭证池...");
-
-                    // 获取凭证池概览信息
-                    match pool_service.get_overview(&db) {
-                        Ok(overview) => {
-                            let mut loaded_types = Vec::new();
-                            let mut total_credentials = 0;
-
-                            for provider_overview in overview {
-                                let count = provider_overview.stats.total_count;
-                                if count > 0 {
-                                    total_credentials += count;
-                                    let provider_name =
-                                        match provider_overview.provider_type.as_str() {
-                                            "kiro" => "Kiro",
-                                            "gemini" => "Gemini",
-                                            "antigravity" => "Antigravity",
-                                            "openai" => "OpenAI",
-                                            "claude" => "Claude",
-                                            "codex" => "Codex",
-                                            "claude_oauth" => "Claude OAuth",
-                                            _ => &provider_overview.provider_type,
-                                        };
-                                    loaded_types.push(format!("{} ({} 个)", provider_name, count));
-                                }
-                            }
-
-                            if loaded_types.is_empty() {
-                                logs.write().await.add("warn", "[启动] 未找到任何可用凭证");
-                            } else {
-                                let message = format!(
-                                    "[启动] 凭证已加载: {} (共 {} 个)",
-                                    loaded_types.join(", "),
-                                    total_credentials
-                                );
-                                logs.write().await.add("info", &message);
-                            }
-                        }
-                        Err(e) => {
-                            logs.write()
-                                .await
-                                .add("warn", &format!("[启动] 获取凭证池信息失败: {}", e));
-                        }
-                    }
-
-                    // 兼容性：仍然尝试加载旧的 Kiro 凭证（如果存在）
-                    let mut s = state.write().await;
-                    if let Err(e) = s.kiro_provider.load_credentials().await {
-                        logs.write()
-                            .await
-                            .add("debug", &format!("[启动] 旧版 Kiro 凭证加载失败: {e}"));
-                    }
-                }
-                // 启动服务器（使用共享的遥测实例和 Flow Monitor）
-                let server_started;
-                let server_address;
-                {
-                    let mut s = state.write().await;
-                    logs.write()
-                        .await
-                        .add("info", "[启动] 正在自动启动服务器...");
-                    match s
-                        .start_with_telemetry_and_flow_monitor(
-                            logs.clone(),
-                            pool_service,
-                            token_cache,
-                            Some(db),
-                            Some(shared_stats),
-                            Some(shared_tokens),
-                            Some(shared_logger),
-                            Some(shared_flow_monitor),
-                            Some(flow_interceptor_clone),
-                        )
-                        .await
-                    {
-                        Ok(_) => {
-                            // 使用 status() 获取实际使用的地址（可能已经自动切换到有效的 IP）
-                            let status = s.status();
-                            let host = status.host;
-                            let port = status.port;
-                            logs.write()
-                                .await
-                                .add("info", &format!("[启动] 服务器已启动: {host}:{port}"));
-                            server_started = true;
-                            server_address = format!("{}:{}", host, port);
-                        }
-                        Err(e) => {
-                            logs.write()
-                                .await
-                                .add("error", &format!("[启动] 服务器启动失败: {e}"));
-                            server_started = false;
-                            server_address = String::new();
-                        }
-                    }
-                }
+                let result = start_server(services, Some(app_handle.clone())).await;
+                let server_started = result.is_ok();
+                let server_address = result.unwrap_or_default();
 
                 // 更新托盘状态
                 // Requirements 7.1: API 服务器状态变化时更新托盘图标
@@ -627,10 +619,13 @@ pub fn run() {
             // Server commands (from app::commands)
             app_commands::start_server,
             app_commands::stop_server,
+            app_commands::restart_server,
             app_commands::get_server_status,
             // Config commands (from app::commands)
             app_commands::get_config,
             app_commands::save_config,
+            app_commands::get_config_profiles,
+            app_commands::switch_config_profile,
             app_commands::get_default_provider,
             app_commands::set_default_provider,
             app_commands::get_endpoint_providers,
@@ -666,6 +661,10 @@ pub fn run() {
             app_commands::set_claude_custom_config,
             // Log commands (from app::commands)
             app_commands::get_logs,
+            app_commands::query_logs,
+            app_commands::get_log_stats,
+            app_commands::subscribe_logs,
+            app_commands::export_logs,
             app_commands::clear_logs,
             // API test commands (from app::commands)
             app_commands::test_api,
@@ -754,8 +753,18 @@ pub fn run() {
             commands::provider_pool_cmd::add_gemini_api_key_credential,
             commands::provider_pool_cmd::add_codex_oauth_credential,
             commands::provider_pool_cmd::add_claude_oauth_credential,
+            commands::provider_pool_cmd::add_qwen_oauth_credential,
+            commands::provider_pool_cmd::add_vertex_service_account_credential,
+            commands::provider_pool_cmd::add_aws_bedrock_credential,
+            commands::provider_pool_cmd::add_azure_openai_credential,
+            commands::provider_pool_cmd::add_ollama_credential,
+            commands::provider_pool_cmd::add_mock_credential,
+            commands::provider_pool_cmd::detect_local_model_servers,
+            commands::provider_pool_cmd::scan_importable_credentials,
+            commands::provider_pool_cmd::import_detected_credentials,
             commands::provider_pool_cmd::refresh_pool_credential_token,
             commands::provider_pool_cmd::get_pool_credential_oauth_status,
+            commands::provider_pool_cmd::set_credential_encryption,
             commands::provider_pool_cmd::debug_kiro_credentials,
             commands::provider_pool_cmd::test_user_credentials,
             commands::provider_pool_cmd::migrate_private_config_to_pool,
@@ -788,6 +797,11 @@ pub fn run() {
             commands::provider_pool_cmd::install_playwright,
             commands::provider_pool_cmd::start_kiro_playwright_login,
             commands::provider_pool_cmd::cancel_kiro_playwright_login,
+            // Client API Key commands
+            commands::client_api_key_cmd::get_client_api_keys,
+            commands::client_api_key_cmd::create_client_api_key,
+            commands::client_api_key_cmd::update_client_api_key,
+            commands::client_api_key_cmd::delete_client_api_key,
             // API Key Provider commands
             commands::api_key_provider_cmd::get_api_key_providers,
             commands::api_key_provider_cmd::get_api_key_provider,
@@ -826,14 +840,18 @@ pub fn run() {
             // Telemetry commands
             commands::telemetry_cmd::get_request_logs,
             commands::telemetry_cmd::get_request_log_detail,
+            commands::telemetry_cmd::search_request_logs,
             commands::telemetry_cmd::clear_request_logs,
             commands::telemetry_cmd::get_stats_summary,
             commands::telemetry_cmd::get_stats_by_provider,
             commands::telemetry_cmd::get_stats_by_model,
+            commands::telemetry_cmd::get_latency_percentiles,
             commands::telemetry_cmd::get_token_summary,
             commands::telemetry_cmd::get_token_stats_by_provider,
             commands::telemetry_cmd::get_token_stats_by_model,
             commands::telemetry_cmd::get_token_stats_by_day,
+            commands::telemetry_cmd::get_cost_report,
+            commands::telemetry_cmd::generate_usage_report,
             // Injection commands
             commands::injection_cmd::get_injection_config,
             commands::injection_cmd::set_injection_enabled,
@@ -1126,10 +1144,60 @@ pub fn run() {
             // Terminal commands
             commands::terminal_cmd::terminal_create_session,
             commands::terminal_cmd::terminal_write,
+            commands::terminal_cmd::terminal_interrupt_session,
+            commands::terminal_cmd::terminal_send_key,
+            commands::terminal_cmd::terminal_keymap_get,
+            commands::terminal_cmd::terminal_keymap_save,
+            commands::terminal_cmd::terminal_paste,
+            commands::terminal_cmd::terminal_paste_respond,
             commands::terminal_cmd::terminal_resize,
             commands::terminal_cmd::terminal_close,
             commands::terminal_cmd::terminal_list_sessions,
             commands::terminal_cmd::terminal_get_session,
+            commands::terminal_cmd::terminal_list_detached_sessions,
+            commands::terminal_cmd::terminal_restore_session,
+            commands::terminal_cmd::terminal_attach_session,
+            commands::terminal_cmd::terminal_detach_session,
+            commands::terminal_cmd::terminal_replay_start,
+            commands::terminal_cmd::terminal_replay_control,
+            commands::terminal_cmd::terminal_replay_stop,
+            commands::terminal_cmd::terminal_history_query,
+            commands::terminal_cmd::terminal_history_hosts,
+            commands::terminal_cmd::terminal_history_delete,
+            commands::terminal_cmd::terminal_history_clear,
+            commands::terminal_cmd::terminal_command_blocks,
+            commands::terminal_cmd::terminal_command_output,
+            commands::terminal_cmd::terminal_prompt_marks,
+            commands::terminal_cmd::terminal_prompt_rerun,
+            commands::terminal_cmd::terminal_session_metrics,
+            commands::terminal_cmd::terminal_save_file,
+            commands::terminal_cmd::terminal_upload_files,
+            commands::terminal_cmd::terminal_upload_pick_files,
+            commands::terminal_cmd::terminal_upload_read,
+            commands::terminal_cmd::terminal_profile_list,
+            commands::terminal_cmd::terminal_profile_save,
+            commands::terminal_cmd::terminal_profile_delete,
+            commands::terminal_cmd::terminal_clipboard_respond,
+            commands::terminal_cmd::terminal_clipboard_policy_get,
+            commands::terminal_cmd::terminal_clipboard_policy_list,
+            commands::terminal_cmd::terminal_clipboard_policy_save,
+            commands::terminal_cmd::terminal_clipboard_policy_delete,
+            commands::terminal_cmd::terminal_image_get,
+            commands::terminal_cmd::terminal_image_cache_clear,
+            commands::terminal_cmd::terminal_storage_settings_get,
+            commands::terminal_cmd::terminal_storage_settings_save,
+            commands::terminal_cmd::terminal_storage_usage,
+            commands::terminal_cmd::terminal_storage_prune,
+            commands::terminal_cmd::terminal_share_create,
+            commands::terminal_cmd::terminal_share_list,
+            commands::terminal_cmd::terminal_share_revoke,
+            commands::terminal_cmd::terminal_workspace_list,
+            commands::terminal_cmd::terminal_workspace_create,
+            commands::terminal_cmd::terminal_workspace_rename,
+            commands::terminal_cmd::terminal_workspace_set_layout,
+            commands::terminal_cmd::terminal_workspace_reorder,
+            commands::terminal_cmd::terminal_workspace_delete,
+            commands::terminal_cmd::terminal_workspace_place_session,
             // Connection commands
             commands::connection_cmd::connection_list,
             commands::connection_cmd::connection_add,
@@ -1141,6 +1209,10 @@ pub fn run() {
             commands::connection_cmd::connection_save_raw_config,
             commands::connection_cmd::connection_test,
             commands::connection_cmd::connection_import_ssh_host,
+            commands::connection_cmd::connection_list_serial_ports,
+            commands::connection_cmd::connection_forward_list,
+            commands::connection_cmd::connection_forward_add,
+            commands::connection_cmd::connection_forward_remove,
             // Sysinfo commands
             crate::services::sysinfo_service::get_sysinfo,
             crate::services::sysinfo_service::subscribe_sysinfo,
@@ -0,0 +1,280 @@
+//! 代理服务器启动
+//!
+//! 加载凭证池、启动后台任务（Token 续期、用量报告、异常告警）并启动代理服务器。
+//! 不依赖 Tauri 窗口，桌面应用和无界面模式（`proxycast serve`）共用。
+
+use std::sync::Arc;
+use tauri::Emitter;
+
+use crate::database;
+use crate::flow_monitor::{FlowInterceptor, FlowMonitor};
+use crate::services::alert_service::AlertService;
+use crate::services::provider_pool_service::ProviderPoolService;
+use crate::services::token_cache_service::{self, TokenCacheService};
+use crate::telemetry;
+
+use super::types::{AppState, LogState};
+
+/// 代理服务器运行所需的共享状态
+#[derive(Clone)]
+pub struct ServerServices {
+    pub state: AppState,
+    pub logs: LogState,
+    pub db: database::DbConnection,
+    pub pool_service: Arc<ProviderPoolService>,
+    pub token_cache: Arc<TokenCacheService>,
+    pub shared_stats: Arc<parking_lot::RwLock<telemetry::StatsAggregator>>,
+    pub shared_tokens: Arc<parking_lot::RwLock<telemetry::TokenTracker>>,
+    pub shared_logger: Arc<telemetry::RequestLogger>,
+    pub flow_monitor: Option<Arc<FlowMonitor>>,
+    pub flow_interceptor: Option<Arc<FlowInterceptor>>,
+}
+
+/// 加载凭证池、启动后台任务并启动代理服务器
+///
+/// 需在 Tokio 运行时中调用。`app_handle` 为 `None`（无界面模式）时告警只写入日志和 Webhook。
+/// 成功时返回服务器实际监听的地址。
+pub async fn start_server(
+    services: ServerServices,
+    app_handle: Option<tauri::AppHandle>,
+) -> Result<String, String> {
+    let ServerServices {
+        state,
+        logs,
+        db,
+        pool_service,
+        token_cache,
+        shared_stats,
+        shared_tokens,
+        shared_logger,
+        flow_monitor,
+        flow_interceptor,
+    } = services;
+
+    // 先加载凭证池中的凭证
+    log_credential_overview(&logs, &pool_service, &db).await;
+
+    // 启动后台 Token 续期任务，在过期前提前刷新凭证池中的 OAuth Token
+    token_cache.clone().start_background_refresh(
+        db.clone(),
+        token_cache_service::BACKGROUND_REFRESH_INTERVAL_SECS,
+        token_cache_service::BACKGROUND_REFRESH_LEAD_MINUTES,
+    );
+
+    // 启动用量报告定期写入任务
+    start_report_task(state.clone(), shared_stats.clone(), shared_tokens.clone());
+
+    // 启动异常告警检测任务
+    start_alert_task(
+        state.clone(),
+        logs.clone(),
+        db.clone(),
+        pool_service.clone(),
+        shared_stats.clone(),
+        shared_tokens.clone(),
+        app_handle,
+    );
+
+    let mut s = state.write().await;
+
+    // 兼容性：仍然尝试加载旧的 Kiro 凭证（如果存在）
+    if let Err(e) = s.kiro_provider.load_credentials().await {
+        logs.write()
+            .await
+            .add("debug", &format!("[启动] 旧版 Kiro 凭证加载失败: {e}"));
+    }
+
+    // 启动服务器（使用共享的遥测实例和 Flow Monitor）
+    logs.write()
+        .await
+        .add("info", "[启动] 正在自动启动服务器...");
+    match s
+        .start_with_telemetry_and_flow_monitor(
+            logs.clone(),
+            pool_service,
+            token_cache,
+            Some(db),
+            Some(shared_stats),
+            Some(shared_tokens),
+            Some(shared_logger),
+            flow_monitor,
+            flow_interceptor,
+        )
+        .await
+    {
+        Ok(_) => {
+            // 使用 status() 获取实际使用的地址（可能已经自动切换到有效的 IP）
+            let status = s.status();
+            let address = format!("{}:{}", status.host, status.port);
+            logs.write()
+                .await
+                .add("info", &format!("[启动] 服务器已启动: {address}"));
+            Ok(address)
+        }
+        Err(e) => {
+            let message = format!("[启动] 服务器启动失败: {e}");
+            logs.write().await.add("error", &message);
+            Err(message)
+        }
+    }
+}
+
+/// 记录凭证池中已加载的凭证数量
+async fn log_credential_overview(
+    logs: &LogState,
+    pool_service: &ProviderPoolService,
+    db: &database::DbConnection,
+) {
+    logs.write().await.add("info", "[启动] 正在加载凭证池...");
+
+    match pool_service.get_overview(db) {
+        Ok(overview) => {
+            let mut loaded_types = Vec::new();
+            let mut total_credentials = 0;
+
+            for provider_overview in overview {
+                let count = provider_overview.stats.total_count;
+                if count > 0 {
+                    total_credentials += count;
+                    let provider_name = match provider_overview.provider_type.as_str() {
+                        "kiro" => "Kiro",
+                        "gemini" => "Gemini",
+                        "qwen" => "通义千问",
+                        "antigravity" => "Antigravity",
+                        "openai" => "OpenAI",
+                        "claude" => "Claude",
+                        "codex" => "Codex",
+                        "claude_oauth" => "Claude OAuth",
+                        "iflow" => "iFlow",
+                        _ => &provider_overview.provider_type,
+                    };
+                    loaded_types.push(format!("{} ({} 个)", provider_name, count));
+                }
+            }
+
+            if loaded_types.is_empty() {
+                logs.write().await.add("warn", "[启动] 未找到任何可用凭证");
+            } else {
+                let message = format!(
+                    "[启动] 凭证已加载: {} (共 {} 个)",
+                    loaded_types.join(", "),
+                    total_credentials
+                );
+                logs.write().await.add("info", &message);
+            }
+        }
+        Err(e) => {
+            logs.write()
+                .await
+                .add("warn", &format!("[启动] 获取凭证池信息失败: {}", e));
+        }
+    }
+}
+
+/// 用量报告检查间隔（秒）
+const REPORT_CHECK_INTERVAL_SECS: u64 = 3600;
+
+/// 启动用量报告定期写入任务
+///
+/// 每小时检查一次，`reports.enabled` 开启时将最近一个已结束周期的日报 / 周报写入报告目录，
+/// 每次检查时重新读取配置，配置修改无需重启。
+fn start_report_task(
+    state: AppState,
+    shared_stats: Arc<parking_lot::RwLock<telemetry::StatsAggregator>>,
+    shared_tokens: Arc<parking_lot::RwLock<telemetry::TokenTracker>>,
+) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(REPORT_CHECK_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            let (reports, pricing) = {
+                let s = state.read().await;
+                (s.config.reports.clone(), s.config.pricing.clone())
+            };
+            if !reports.enabled {
+                continue;
+            }
+
+            let dir = crate::config::expand_tilde(&reports.directory);
+            let logs = shared_stats.read().get_all();
+            let tokens = shared_tokens.read().get_all();
+            match telemetry::write_due_reports(
+                &reports,
+                &dir,
+                chrono::Utc::now(),
+                &logs,
+                &tokens,
+                &telemetry::PricingTable::new(&pricing),
+            ) {
+                Ok(written) => {
+                    for path in written {
+                        tracing::info!("[REPORT] 用量报告已写入: {}", path.display());
+                    }
+                }
+                Err(e) => tracing::warn!("[REPORT] 写入用量报告失败 ({}): {}", dir.display(), e),
+            }
+        }
+    });
+}
+
+/// 告警检测间隔（秒）
+const ALERT_CHECK_INTERVAL_SECS: u64 = 60;
+
+/// 启动异常告警检测任务
+///
+/// 每分钟按 `alerts` 配置检测一次，新触发的告警写入日志、推送 `telemetry-alert` 事件，
+/// 配置了 `webhook_url` 时同时 POST 到 Webhook。每次检测时重新读取配置，修改无需重启。
+fn start_alert_task(
+    state: AppState,
+    logs: LogState,
+    db: database::DbConnection,
+    pool_service: Arc<ProviderPoolService>,
+    shared_stats: Arc<parking_lot::RwLock<telemetry::StatsAggregator>>,
+    shared_tokens: Arc<parking_lot::RwLock<telemetry::TokenTracker>>,
+    app_handle: Option<tauri::AppHandle>,
+) {
+    let service = AlertService::new();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(ALERT_CHECK_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            let (config, pricing) = {
+                let s = state.read().await;
+                (s.config.alerts.clone(), s.config.pricing.clone())
+            };
+            // 未启用时也要检测，以便持续记录凭证开始不健康的时间
+            let credentials = AlertService::credential_statuses(&pool_service, &db);
+            let alerts = {
+                let request_logs = shared_stats.read().get_all();
+                let tokens = shared_tokens.read().get_all();
+                service.check(
+                    &config,
+                    &request_logs,
+                    &tokens,
+                    &telemetry::PricingTable::new(&pricing),
+                    &credentials,
+                )
+            };
+
+            for alert in alerts {
+                logs.write()
+                    .await
+                    .add("warn", &format!("[ALERT] {}", alert.message));
+                if let Some(app_handle) = &app_handle {
+                    if let Err(e) = app_handle.emit("telemetry-alert", &alert) {
+                        tracing::warn!("[ALERT] 推送告警事件失败: {}", e);
+                    }
+                }
+                if let Some(url) = &config.webhook_url {
+                    if let Err(e) = service.send_webhook(url, &alert).await {
+                        tracing::warn!("[ALERT] 发送告警 Webhook 失败: {}", e);
+                    }
+                }
+            }
+        }
+    });
+}
@@ -14,6 +14,7 @@ use crate::services::token_cache_service::TokenCacheService;
 use crate::telemetry;
 use crate::tray::{TrayIconStatus, TrayManager, TrayStateSnapshot};
 
+use super::server_runner::{start_server, ServerServices};
 use super::types::{AppState, LogState, TrayManagerState};
 
 /// Tauri setup hook
@@ -103,104 +104,21 @@ async fn start_server_async(
     flow_interceptor: Arc<FlowInterceptor>,
     app_handle: tauri::AppHandle,
 ) {
-    // 先加载凭证池中的凭证
-    {
-        logs.write().await.add("info", "[启动] 正在加载凭证池...");
-
-        match pool_service.get_overview(&db) {
-            Ok(overview) => {
-                let mut loaded_types = Vec::new();
-                let mut total_credentials = 0;
-
-                for provider_overview in overview {
-                    let count = provider_overview.stats.total_count;
-                    if count > 0 {
-                        total_credentials += count;
-                        let provider_name = match provider_overview.provider_type.as_str() {
-                            "kiro" => "Kiro",
-                            "gemini" => "Gemini",
-                            "qwen" => "通义千问",
-                            "antigravity" => "Antigravity",
-                            "openai" => "OpenAI",
-                            "claude" => "Claude",
-                            "codex" => "Codex",
-                            "claude_oauth" => "Claude OAuth",
-                            "iflow" => "iFlow",
-                            _ => &provider_overview.provider_type,
-                        };
-                        loaded_types.push(format!("{} ({} 个)", provider_name, count));
-                    }
-                }
-
-                if loaded_types.is_empty() {
-                    logs.write().await.add("warn", "[启动] 未找到任何可用凭证");
-                } else {
-                    let message = format!(
-                        "[启动] 凭证已加载: {} (共 {} 个)",
-                        loaded_types.join(", "),
-                        total_credentials
-                    );
-                    logs.write().await.add("info", &message);
-                }
-            }
-            Err(e) => {
-                logs.write()
-                    .await
-                    .add("warn", &format!("[启动] 获取凭证池信息失败: {}", e));
-            }
-        }
-
-        // 兼容性：仍然尝试加载旧的 Kiro 凭证（如果存在）
-        let mut s = state.write().await;
-        if let Err(e) = s.kiro_provider.load_credentials().await {
-            logs.write()
-                .await
-                .add("debug", &format!("[启动] 旧版 Kiro 凭证加载失败: {e}"));
-        }
-    }
-
-    // 启动服务器
-    let server_started;
-    let server_address;
-    {
-        let mut s = state.write().await;
-        logs.write()
-            .await
-            .add("info", "[启动] 正在自动启动服务器...");
-        match s
-            .start_with_telemetry_and_flow_monitor(
-                logs.clone(),
-                pool_service,
-                token_cache,
-                Some(db),
-                Some(shared_stats),
-                Some(shared_tokens),
-                Some(shared_logger),
-                Some(shared_flow_monitor),
-                Some(flow_interceptor),
-            )
-            .await
-        {
-            Ok(_) => {
-                // 获取服务器实际使用的地址（可能已经自动切换到有效的 IP）
-                let status = s.status();
-                let host = status.host;
-                let port = status.port;
-                logs.write()
-                    .await
-                    .add("info", &format!("[启动] 服务器已启动: {host}:{port}"));
-                server_started = true;
-                server_address = format!("{}:{}", host, port);
-            }
-            Err(e) => {
-                logs.write()
-                    .await
-                    .add("error", &format!("[启动] 服务器启动失败: {e}"));
-                server_started = false;
-                server_address = String::new();
-            }
-        }
-    }
+    let services = ServerServices {
+        state,
+        logs,
+        db,
+        pool_service,
+        token_cache,
+        shared_stats,
+        shared_tokens,
+        shared_logger,
+        flow_monitor: Some(shared_flow_monitor),
+        flow_interceptor: Some(flow_interceptor),
+    };
+    let result = start_server(services, Some(app_handle.clone())).await;
+    let server_started = result.is_ok();
+    let server_address = result.unwrap_or_default();
 
     // 更新托盘状态
     if let Some(tray_state) = app_handle.try_state::<TrayManagerState<tauri::Wry>>() {
@@ -0,0 +1,54 @@
+//! 客户端 API Key Tauri 命令
+//!
+//! 提供客户端 API Key（访问代理服务的多个 Key 及其作用域）管理的前端调用接口。
+
+use crate::database::dao::client_api_keys::ClientApiKey;
+use crate::database::DbConnection;
+use crate::services::client_api_key_service::{
+    ClientApiKeyService, CreateClientApiKeyRequest, CreatedClientApiKey, UpdateClientApiKeyRequest,
+};
+use std::sync::Arc;
+use tauri::State;
+
+/// 客户端 API Key 服务状态封装
+pub struct ClientApiKeyServiceState(pub Arc<ClientApiKeyService>);
+
+/// 获取所有客户端 API Key
+#[tauri::command]
+pub fn get_client_api_keys(
+    db: State<'_, DbConnection>,
+    service: State<'_, ClientApiKeyServiceState>,
+) -> Result<Vec<ClientApiKey>, String> {
+    service.0.list(&db)
+}
+
+/// 创建客户端 API Key，明文 Key 仅在返回值中出现一次
+#[tauri::command]
+pub fn create_client_api_key(
+    db: State<'_, DbConnection>,
+    service: State<'_, ClientApiKeyServiceState>,
+    request: CreateClientApiKeyRequest,
+) -> Result<CreatedClientApiKey, String> {
+    service.0.create(&db, request)
+}
+
+/// 更新客户端 API Key
+#[tauri::command]
+pub fn update_client_api_key(
+    db: State<'_, DbConnection>,
+    service: State<'_, ClientApiKeyServiceState>,
+    id: String,
+    request: UpdateClientApiKeyRequest,
+) -> Result<ClientApiKey, String> {
+    service.0.update(&db, &id, request)
+}
+
+/// 删除客户端 API Key
+#[tauri::command]
+pub fn delete_client_api_key(
+    db: State<'_, DbConnection>,
+    service: State<'_, ClientApiKeyServiceState>,
+    id: String,
+) -> Result<bool, String> {
+    service.0.delete(&db, &id)
+}
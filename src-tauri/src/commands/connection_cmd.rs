@@ -10,11 +10,21 @@
 //! - `connection_get_config_path` - 获取配置文件路径
 //! - `connection_get_raw_config` - 获取原始配置内容
 //! - `connection_save_raw_config` - 保存原始配置内容
+//! - `connection_forward_list` - 获取 SSH 连接的端口转发
+//! - `connection_forward_add` - 添加 SSH 端口转发
+//! - `connection_forward_remove` - 移除 SSH 端口转发
+//! - `connection_list_serial_ports` - 列出本机串口
+
+use std::path::Path;
+use std::sync::Arc;
 
 use crate::terminal::connections::{
-    ConnectionConfig, ConnectionConfigManager, ConnectionConfigType, ConnectionListEntry,
+    find_kubectl, find_mosh_client, list_serial_ports, ConnectionConfig, ConnectionConfigManager,
+    ConnectionConfigType, ConnectionListEntry, DockerConn, ForwardKind, ForwardSpec, ForwardStatus,
+    MoshPredict, SSHConn, SSHConnRegistry,
 };
 use serde::{Deserialize, Serialize};
+use tauri::State;
 
 /// 添加连接的请求参数
 #[derive(Debug, Deserialize)]
@@ -25,7 +35,7 @@ pub struct AddConnectionRequest {
     /// 连接类型
     #[serde(rename = "type")]
     pub conn_type: ConnectionConfigType,
-    /// 用户名（SSH）
+    /// 用户名（SSH，Docker 为容器内执行用户）
     pub user: Option<String>,
     /// 主机名（SSH）
     pub host: Option<String>,
@@ -37,6 +47,22 @@ pub struct AddConnectionRequest {
     pub proxy_jump: Option<String>,
     /// WSL 发行版
     pub wsl_distro: Option<String>,
+    /// Mosh 预测回显模式
+    pub mosh_predict: Option<MoshPredict>,
+    /// 是否为原始 TCP（Telnet）
+    pub telnet_raw: Option<bool>,
+    /// 串口设备路径
+    pub serial_port: Option<String>,
+    /// 串口波特率
+    pub baud_rate: Option<u32>,
+    /// 容器名称（Docker / Kubernetes）
+    pub container: Option<String>,
+    /// Kubernetes 命名空间
+    pub namespace: Option<String>,
+    /// Kubernetes Pod 名称
+    pub pod: Option<String>,
+    /// kubeconfig 上下文
+    pub kube_context: Option<String>,
 }
 
 /// 更新连接的请求参数
@@ -111,6 +137,14 @@ pub fn connection_add(request: AddConnectionRequest) -> ConnectionResponse {
         display_order: None,
         hidden: None,
         wsl_distro: request.wsl_distro,
+        mosh_predict: request.mosh_predict,
+        telnet_raw: request.telnet_raw,
+        serial_port: request.serial_port,
+        baud_rate: request.baud_rate,
+        container: request.container,
+        namespace: request.namespace,
+        pod: request.pod,
+        kube_context: request.kube_context,
     };
 
     // 添加并保存
@@ -243,12 +277,15 @@ pub async fn connection_test(name: String) -> ConnectionResponse {
             // 本地连接总是可用
             ConnectionResponse::ok()
         }
-        ConnectionConfigType::Ssh => {
-            // SSH 连接测试
+        ConnectionConfigType::Ssh | ConnectionConfigType::Mosh => {
+            // SSH 连接测试（Mosh 也先通过 SSH 登录）
             let host = match &conn.host {
                 Some(h) => h,
                 None => return ConnectionResponse::err("SSH 连接缺少主机名"),
             };
+            if conn.conn_type == ConnectionConfigType::Mosh && find_mosh_client().is_none() {
+                return ConnectionResponse::err("未找到 mosh 客户端，请先安装 mosh");
+            }
 
             let port = conn.port.unwrap_or(22);
 
@@ -264,6 +301,85 @@ pub async fn connection_test(name: String) -> ConnectionResponse {
                 }
             }
         }
+        ConnectionConfigType::Telnet => {
+            let Some(opts) = conn.to_telnet_opts() else {
+                return ConnectionResponse::err("Telnet 连接缺少主机名或端口");
+            };
+
+            match tokio::net::TcpStream::connect((opts.host.as_str(), opts.port)).await {
+                Ok(_) => {
+                    tracing::info!("[Connection] Telnet 连接测试成功: {}", opts);
+                    ConnectionResponse::ok()
+                }
+                Err(e) => {
+                    tracing::warn!("[Connection] Telnet 连接测试失败: {} - {}", opts, e);
+                    ConnectionResponse::err(format!("连接失败: {}", e))
+                }
+            }
+        }
+        ConnectionConfigType::Serial => {
+            let Some(opts) = conn.to_serial_opts() else {
+                return ConnectionResponse::err("串口连接缺少设备路径");
+            };
+
+            // 只检查串口是否存在，不打开设备（避免打断正在使用的会话）
+            if list_serial_ports().contains(&opts.path) || Path::new(&opts.path).exists() {
+                ConnectionResponse::ok()
+            } else {
+                ConnectionResponse::err(format!("串口不存在: {}", opts.path))
+            }
+        }
+        ConnectionConfigType::Docker => {
+            let Some(opts) = conn.to_docker_opts() else {
+                return ConnectionResponse::err("Docker 连接缺少容器名称");
+            };
+
+            // 检查 Docker Engine 是否可用
+            let result = tokio::task::spawn_blocking(|| DockerConn::from_env()?.ping()).await;
+            match result {
+                Ok(Ok(())) => {
+                    tracing::info!("[Connection] Docker 连接测试成功: {}", opts);
+                    ConnectionResponse::ok()
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!("[Connection] Docker 连接测试失败: {} - {}", opts, e);
+                    ConnectionResponse::err(e.to_string())
+                }
+                Err(e) => ConnectionResponse::err(e.to_string()),
+            }
+        }
+        ConnectionConfigType::Kubernetes => {
+            let Some(opts) = conn.to_k8s_opts() else {
+                return ConnectionResponse::err("Kubernetes 连接缺少 Pod 名称");
+            };
+            let Some(kubectl) = find_kubectl() else {
+                return ConnectionResponse::err("未找到 kubectl，请先安装");
+            };
+
+            // 通过 kubectl get pod 检查 Pod 是否存在
+            let mut cmd = std::process::Command::new(kubectl);
+            cmd.args(["get", "pod", &opts.pod, "--namespace", &opts.namespace]);
+            if let Some(ref context) = opts.context {
+                cmd.arg(format!("--context={}", context));
+            }
+            match tokio::task::spawn_blocking(move || cmd.output()).await {
+                Ok(Ok(output)) if output.status.success() => {
+                    tracing::info!("[Connection] Kubernetes 连接测试成功: {}", opts);
+                    ConnectionResponse::ok()
+                }
+                Ok(Ok(output)) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    tracing::warn!(
+                        "[Connection] Kubernetes 连接测试失败: {} - {}",
+                        opts,
+                        stderr
+                    );
+                    ConnectionResponse::err(stderr.trim().to_string())
+                }
+                Ok(Err(e)) => ConnectionResponse::err(format!("kubectl 执行失败: {}", e)),
+                Err(e) => ConnectionResponse::err(e.to_string()),
+            }
+        }
         ConnectionConfigType::Wsl => {
             // WSL 连接测试
             #[cfg(target_os = "windows")]
@@ -292,6 +408,12 @@ pub async fn connection_test(name: String) -> ConnectionResponse {
     }
 }
 
+/// 列出本机可用串口
+#[tauri::command]
+pub fn connection_list_serial_ports() -> Vec<String> {
+    list_serial_ports()
+}
+
 /// 导入 SSH 配置中的 Host 到用户配置
 #[tauri::command]
 pub fn connection_import_ssh_host(host_name: String) -> ConnectionResponse {
@@ -329,6 +451,14 @@ pub fn connection_import_ssh_host(host_name: String) -> ConnectionResponse {
         display_order: None,
         hidden: None,
         wsl_distro: None,
+        mosh_predict: None,
+        telnet_raw: None,
+        serial_port: None,
+        baud_rate: None,
+        container: None,
+        namespace: None,
+        pod: None,
+        kube_context: None,
     };
 
     // 添加并保存
@@ -342,3 +472,53 @@ pub fn connection_import_ssh_host(host_name: String) -> ConnectionResponse {
         Err(e) => ConnectionResponse::err(e),
     }
 }
+
+/// 按连接名称查找已建立的 SSH 连接
+fn find_ssh_conn(registry: &SSHConnRegistry, connection: &str) -> Result<Arc<SSHConn>, String> {
+    registry
+        .get(connection)
+        .ok_or_else(|| format!("SSH 连接 '{}' 未建立", connection))
+}
+
+/// 获取 SSH 连接的端口转发
+///
+/// 包括 ssh_config 中的 LocalForward/RemoteForward/DynamicForward 和运行时添加的转发。
+#[tauri::command]
+pub async fn connection_forward_list(
+    registry: State<'_, Arc<SSHConnRegistry>>,
+    connection: String,
+) -> Result<Vec<ForwardStatus>, String> {
+    let conn = find_ssh_conn(&registry, &connection)?;
+    Ok(conn.list_forwards())
+}
+
+/// 添加 SSH 端口转发
+///
+/// # 参数
+/// - `connection`: 连接名称
+/// - `kind`: 转发类型（`local` / `remote` / `dynamic`）
+/// - `spec`: 转发配置，如 `8080:localhost:80`、`127.0.0.1:1080`
+#[tauri::command]
+pub async fn connection_forward_add(
+    registry: State<'_, Arc<SSHConnRegistry>>,
+    connection: String,
+    kind: ForwardKind,
+    spec: String,
+) -> Result<ForwardStatus, String> {
+    let conn = find_ssh_conn(&registry, &connection)?;
+    let spec = ForwardSpec::parse(kind, &spec).map_err(|e| e.to_string())?;
+    conn.add_forward(spec).map_err(|e| e.to_string())
+}
+
+/// 移除 SSH 端口转发
+///
+/// 停止监听并关闭已建立的转发连接。
+#[tauri::command]
+pub async fn connection_forward_remove(
+    registry: State<'_, Arc<SSHConnRegistry>>,
+    connection: String,
+    forward_id: String,
+) -> Result<(), String> {
+    let conn = find_ssh_conn(&registry, &connection)?;
+    conn.remove_forward(&forward_id).map_err(|e| e.to_string())
+}
@@ -1,9 +1,10 @@
 //! 参数注入相关命令
 
 use crate::config::{save_config, InjectionRuleConfig, InjectionSettings};
-use crate::injection::{InjectionMode, InjectionRule};
+use crate::injection::{Condition, InjectionMode, InjectionRule, SystemPromptInjection};
 use crate::AppState;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -23,7 +24,16 @@ pub struct InjectionConfigResponse {
 pub struct InjectionRuleResponse {
     pub id: String,
     pub pattern: String,
+    #[serde(default)]
     pub parameters: serde_json::Value,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub system_prompt: Option<SystemPromptInjection>,
+    #[serde(default)]
+    pub providers: Vec<String>,
+    #[serde(default)]
+    pub condition: Option<String>,
     pub mode: InjectionMode,
     pub priority: i32,
     pub enabled: bool,
@@ -35,6 +45,10 @@ impl From<&InjectionRuleConfig> for InjectionRuleResponse {
             id: config.id.clone(),
             pattern: config.pattern.clone(),
             parameters: config.parameters.clone(),
+            headers: config.headers.clone(),
+            system_prompt: config.system_prompt.clone(),
+            providers: config.providers.clone(),
+            condition: config.condition.clone(),
             mode: config.mode,
             priority: config.priority,
             enabled: config.enabled,
@@ -48,6 +62,10 @@ impl From<&InjectionRule> for InjectionRuleResponse {
             id: rule.id.clone(),
             pattern: rule.pattern.clone(),
             parameters: rule.parameters.clone(),
+            headers: rule.headers.clone(),
+            system_prompt: rule.system_prompt.clone(),
+            providers: rule.providers.clone(),
+            condition: rule.condition.clone(),
             mode: rule.mode,
             priority: rule.priority,
             enabled: rule.enabled,
@@ -105,6 +123,10 @@ pub async fn add_injection_rule(
     state: tauri::State<'_, AppState>,
     rule: InjectionRuleResponse,
 ) -> Result<(), String> {
+    if let Some(condition) = &rule.condition {
+        Condition::parse(condition)?;
+    }
+
     let mut s = state.write().await;
 
     // 检查是否已存在相同 ID 的规则
@@ -116,6 +138,10 @@ pub async fn add_injection_rule(
         id: rule.id,
         pattern: rule.pattern,
         parameters: rule.parameters,
+        headers: rule.headers,
+        system_prompt: rule.system_prompt,
+        providers: rule.providers,
+        condition: rule.condition,
         mode: rule.mode,
         priority: rule.priority,
         enabled: rule.enabled,
@@ -154,6 +180,10 @@ pub async fn update_injection_rule(
     id: String,
     rule: InjectionRuleResponse,
 ) -> Result<(), String> {
+    if let Some(condition) = &rule.condition {
+        Condition::parse(condition)?;
+    }
+
     let mut s = state.write().await;
 
     let pos = s
@@ -168,6 +198,10 @@ pub async fn update_injection_rule(
         id: rule.id,
         pattern: rule.pattern,
         parameters: rule.parameters,
+        headers: rule.headers,
+        system_prompt: rule.system_prompt,
+        providers: rule.providers,
+        condition: rule.condition,
         mode: rule.mode,
         priority: rule.priority,
         enabled: rule.enabled,
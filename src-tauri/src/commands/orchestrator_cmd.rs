@@ -159,6 +159,25 @@ fn extract_supported_models(
         CredentialData::CodexOAuth { .. } => {
             vec!["codex-mini-latest".to_string()]
         }
+        CredentialData::QwenOAuth { .. } => crate::providers::qwen::QWEN_MODELS
+            .iter()
+            .map(|m| m.to_string())
+            .collect(),
+        CredentialData::AzureOpenaiKey {
+            model_deployments, ..
+        } => model_deployments
+            .keys()
+            .filter(|m| !m.contains('*'))
+            .cloned()
+            .collect(),
+        CredentialData::AwsBedrock { .. } => crate::providers::bedrock::BEDROCK_MODELS
+            .iter()
+            .map(|m| m.to_string())
+            .collect(),
+        // 本地模型以实际安装为准，这里只给出默认检查模型
+        CredentialData::Ollama { .. } => vec!["llama3.2".to_string()],
+        // Mock 接受任意模型名
+        CredentialData::Mock { .. } => vec!["mock-model".to_string()],
         CredentialData::AntigravityOAuth { .. } => {
             vec![
                 // Max 等级
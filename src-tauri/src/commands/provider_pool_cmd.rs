@@ -2,15 +2,22 @@
 
 #![allow(dead_code)]
 
+use crate::commands::api_key_provider_cmd::ApiKeyProviderServiceState;
+use crate::config::save_config;
+use crate::credential::importer::{self, DetectedCredential, ImportSource};
 use crate::credential::CredentialSyncService;
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::models::provider_pool_model::{
-    AddCredentialRequest, CredentialData, CredentialDisplay, HealthCheckResult, OAuthStatus,
-    PoolProviderType, ProviderCredential, ProviderPoolOverview, UpdateCredentialRequest,
+    AddCredentialRequest, CredentialData, CredentialDisplay, CredentialSource, HealthCheckResult,
+    OAuthStatus, PoolProviderType, ProviderCredential, ProviderPoolOverview,
+    UpdateCredentialRequest,
 };
+use crate::providers::ollama::LocalModelServer;
 use crate::services::provider_pool_service::ProviderPoolService;
+use crate::services::secret_store;
 use chrono::Utc;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -316,6 +323,7 @@ pub fn update_provider_pool_credential(
         request.check_model_name,
         request.not_supported_models
     );
+    let weight = request.weight;
     // 如果需要重新上传文件，先处理文件上传
     let credential = if let Some(new_file_path) = request.new_creds_file_path {
         // 获取当前凭证以确定类型
@@ -493,6 +501,12 @@ pub fn update_provider_pool_credential(
         )?
     };
 
+    // 更新负载均衡权重
+    let credential = match weight {
+        Some(weight) => pool_service.0.update_weight(&db, &uuid, weight)?,
+        None => credential,
+    };
+
     // 同步到 YAML 配置（如果同步服务可用）
     if let Some(ref sync) = sync_service.0 {
         if let Err(e) = sync.update_credential(&credential) {
@@ -1001,6 +1015,312 @@ pub fn add_claude_oauth_credential(
     )
 }
 
+/// 添加 Qwen OAuth 凭证（通过文件路径）
+#[tauri::command]
+pub fn add_qwen_oauth_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    creds_file_path: String,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    // 复制并重命名文件到应用存储目录
+    let stored_file_path = copy_and_rename_credential_file(&creds_file_path, "qwen")?;
+
+    pool_service.0.add_credential(
+        &db,
+        "qwen",
+        CredentialData::QwenOAuth {
+            creds_file_path: stored_file_path,
+        },
+        name,
+        Some(true),
+        None,
+    )
+}
+
+/// 添加 Vertex AI 服务账号凭证（通过服务账号 JSON 文件路径）
+#[tauri::command]
+pub fn add_vertex_service_account_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    creds_file_path: String,
+    project_id: Option<String>,
+    region: Option<String>,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    let non_empty = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let project_id = non_empty(project_id);
+
+    // 先校验服务账号文件，避免添加无法使用的凭证
+    let key = crate::providers::vertex_service_account::ServiceAccountKey::load(&expand_tilde(
+        &creds_file_path,
+    ))?;
+    if project_id.is_none() && key.project_id.is_none() {
+        return Err("服务账号文件中没有 project_id，请手动指定项目".to_string());
+    }
+
+    // 复制并重命名文件到应用存储目录
+    let stored_file_path = copy_and_rename_credential_file(&creds_file_path, "vertex")?;
+
+    pool_service.0.add_credential(
+        &db,
+        "vertex",
+        CredentialData::VertexServiceAccount {
+            creds_file_path: stored_file_path,
+            project_id,
+            region: non_empty(region),
+        },
+        name,
+        Some(true),
+        None,
+    )
+}
+
+/// 添加 AWS Bedrock 凭证
+///
+/// `access_key_id` / `secret_access_key` 与 `profile` 均为空时，使用环境变量或 default profile
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn add_aws_bedrock_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    region: String,
+    profile: Option<String>,
+    role_arn: Option<String>,
+    access_key_id: Option<String>,
+    secret_access_key: Option<String>,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+    let access_key_id = non_empty(access_key_id);
+    let secret_access_key = non_empty(secret_access_key);
+    if access_key_id.is_some() != secret_access_key.is_some() {
+        return Err("Access Key ID 和 Secret Access Key 需要同时填写".to_string());
+    }
+
+    pool_service.0.add_credential(
+        &db,
+        "aws_bedrock",
+        CredentialData::AwsBedrock {
+            region: region.trim().to_string(),
+            profile: non_empty(profile),
+            role_arn: non_empty(role_arn),
+            access_key_id,
+            secret_access_key,
+        },
+        name,
+        Some(true),
+        None,
+    )
+}
+
+/// 添加 Azure OpenAI 凭证
+///
+/// `model_deployments` 为模型名到部署名的映射，未命中映射的模型使用 `deployment`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn add_azure_openai_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    api_key: String,
+    resource: String,
+    deployment: Option<String>,
+    api_version: Option<String>,
+    model_deployments: Option<HashMap<String, String>>,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+    let deployment = non_empty(deployment);
+    let model_deployments: HashMap<String, String> = model_deployments
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(model, deployment)| (model.trim().to_string(), deployment.trim().to_string()))
+        .filter(|(model, deployment)| !model.is_empty() && !deployment.is_empty())
+        .collect();
+    if deployment.is_none() && model_deployments.is_empty() {
+        return Err("请至少配置一个部署名称".to_string());
+    }
+
+    pool_service.0.add_credential(
+        &db,
+        "azure_openai",
+        CredentialData::AzureOpenaiKey {
+            api_key,
+            resource: resource.trim().to_string(),
+            deployment,
+            api_version: non_empty(api_version),
+            model_deployments,
+        },
+        name,
+        Some(true),
+        None,
+    )
+}
+
+/// 添加本地模型服务凭证（Ollama / LM Studio / llama.cpp）
+#[tauri::command]
+pub fn add_ollama_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    base_url: Option<String>,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    pool_service.0.add_credential(
+        &db,
+        "ollama",
+        CredentialData::Ollama {
+            base_url: base_url
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty()),
+        },
+        name,
+        Some(true),
+        None,
+    )
+}
+
+/// 添加 Mock 凭证（离线开发和测试）
+#[tauri::command]
+pub fn add_mock_credential(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    response: Option<String>,
+    latency_ms: Option<u64>,
+    error_rate: Option<f64>,
+    error_status: Option<u16>,
+    name: Option<String>,
+) -> Result<ProviderCredential, String> {
+    if let Some(rate) = error_rate {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err("错误注入概率必须在 0 到 1 之间".to_string());
+        }
+    }
+    if let Some(status) = error_status {
+        if !(400..600).contains(&status) {
+            return Err("注入错误的状态码必须在 400 到 599 之间".to_string());
+        }
+    }
+    pool_service.0.add_credential(
+        &db,
+        "mock",
+        CredentialData::Mock {
+            response: response.filter(|r| !r.trim().is_empty()),
+            latency_ms,
+            error_rate,
+            error_status,
+        },
+        name,
+        Some(true),
+        None,
+    )
+}
+
+/// 探测本机正在运行的本地模型服务
+#[tauri::command]
+pub async fn detect_local_model_servers() -> Result<Vec<LocalModelServer>, String> {
+    Ok(crate::providers::ollama::discover_local_servers().await)
+}
+
+/// 收集凭证池中已有 OAuth 凭证的 refresh token
+fn pool_refresh_tokens(db: &DbConnection) -> Result<HashSet<String>, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+    Ok(credentials
+        .iter()
+        .filter_map(|cred| match &cred.credential {
+            CredentialData::KiroOAuth { creds_file_path }
+            | CredentialData::GeminiOAuth {
+                creds_file_path, ..
+            }
+            | CredentialData::AntigravityOAuth {
+                creds_file_path, ..
+            }
+            | CredentialData::CodexOAuth {
+                creds_file_path, ..
+            }
+            | CredentialData::ClaudeOAuth { creds_file_path }
+            | CredentialData::QwenOAuth { creds_file_path } => {
+                importer::read_refresh_token(Path::new(creds_file_path))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// 扫描其他工具（Claude Code、gemini-cli、qwen-code、Codex CLI、Kiro）保存在本机的凭证
+#[tauri::command]
+pub fn scan_importable_credentials(
+    db: State<'_, DbConnection>,
+) -> Result<Vec<DetectedCredential>, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
+    Ok(importer::scan(&home, &pool_refresh_tokens(&db)?))
+}
+
+/// 导入扫描到的凭证
+///
+/// `paths` 为 `scan_importable_credentials` 返回的凭证文件路径，
+/// 凭证文件会转换后复制到应用存储目录，并同步到 YAML 配置
+#[tauri::command]
+pub fn import_detected_credentials(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    sync_service: State<'_, CredentialSyncServiceState>,
+    paths: Vec<String>,
+) -> Result<Vec<ProviderCredential>, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
+    let detected = importer::scan(&home, &pool_refresh_tokens(&db)?);
+
+    let mut imported = Vec::new();
+    for path in &paths {
+        let found = detected
+            .iter()
+            .find(|d| &d.path == path)
+            .ok_or_else(|| format!("未找到可导入的凭证: {}", path))?;
+        if found.already_imported {
+            tracing::info!("[IMPORT] 跳过已导入的凭证: {}", path);
+            continue;
+        }
+
+        let stored_file_path = match found.source {
+            ImportSource::Kiro => copy_and_rename_credential_file(path, "kiro")?,
+            _ => importer::stage(found, &get_credentials_dir()?)?
+                .to_string_lossy()
+                .to_string(),
+        };
+        let name = match &found.account {
+            Some(account) => format!("{} ({})", found.source.label(), account),
+            None => found.source.label().to_string(),
+        };
+
+        let credential = pool_service.0.add_credential_with_source(
+            &db,
+            &found.provider_type,
+            importer::credential_data(found.source, stored_file_path),
+            Some(name),
+            Some(true),
+            None,
+            CredentialSource::Imported,
+        )?;
+        if let Some(ref sync) = sync_service.0 {
+            if let Err(e) = sync.add_credential(&credential) {
+                tracing::warn!("同步凭证到 YAML 失败: {}", e);
+            }
+        }
+        tracing::info!(
+            "[IMPORT] 已从 {} 导入 {} 凭证",
+            found.source.label(),
+            found.provider_type
+        );
+        imported.push(credential);
+    }
+
+    Ok(imported)
+}
+
 /// 刷新凭证的 OAuth Token
 #[tauri::command]
 pub async fn refresh_pool_credential_token(
@@ -1027,6 +1347,44 @@ pub fn get_pool_credential_oauth_status(
     pool_service.0.get_credential_oauth_status(&db, &uuid)
 }
 
+/// 启用或禁用凭证静态加密，并迁移已有凭证
+///
+/// 同时迁移 API Key Provider 的密钥和配置文件中的敏感字段（保存配置时重写），
+/// 返回重写的凭证和 API Key 数量
+#[tauri::command]
+pub async fn set_credential_encryption(
+    state: State<'_, crate::AppState>,
+    db: State<'_, DbConnection>,
+    api_key_service: State<'_, ApiKeyProviderServiceState>,
+    enabled: bool,
+) -> Result<usize, String> {
+    let mut s = state.write().await;
+    if enabled {
+        let mut secrets = s.config.secrets.clone();
+        secrets.enabled = true;
+        secret_store::init(&secrets).map_err(|e| e.to_string())?;
+        s.config.secrets = secrets;
+    } else {
+        // 保留已加载的主密钥，用于解密已有数据
+        secret_store::set_encrypt_writes(false).map_err(|e| e.to_string())?;
+        s.config.secrets.enabled = false;
+    }
+
+    let rewritten = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::rewrite_secrets(&conn).map_err(|e| e.to_string())?
+    };
+    let rewritten_keys = api_key_service.0.rewrite_secrets(&db)?;
+    save_config(&s.config).map_err(|e| e.to_string())?;
+    tracing::info!(
+        "[SECRETS] 凭证加密已{}，重写 {} 条凭证、{} 个 API Key",
+        if enabled { "启用" } else { "禁用" },
+        rewritten,
+        rewritten_keys
+    );
+    Ok(rewritten + rewritten_keys)
+}
+
 /// 调试 Kiro 凭证加载（从默认路径）
 /// P0 安全修复：仅在 debug 构建中可用
 #[cfg(debug_assertions)]
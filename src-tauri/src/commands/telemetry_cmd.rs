@@ -1,12 +1,16 @@
 //! 遥测命令模块
 //!
-//! 提供请求日志、统计数据和 Token 追踪的 Tauri 命令
+//! 提供请求日志（含持久化检索）、统计数据、Token 追踪、费用估算和用量报告的 Tauri 命令
 
+use crate::database::dao::request_logs::{RequestLogDao, RequestLogPage, RequestLogQuery};
+use crate::database::DbConnection;
 use crate::telemetry::{
-    ModelStats, ModelTokenStats, ProviderStats, ProviderTokenStats, RequestLog, RequestLogger,
-    RequestStatus, StatsAggregator, StatsSummary, TimeRange, TokenStatsSummary, TokenTracker,
+    CostReport, LatencyGroupBy, LatencyReport, ModelStats, ModelTokenStats, PricingTable,
+    ProviderStats, ProviderTokenStats, ReportFormat, ReportGenerator, ReportPeriod, RequestLog,
+    RequestLogger, RequestStatus, StatsAggregator, StatsSummary, TimeRange, TokenStatsSummary,
+    TokenTracker,
 };
-use crate::ProviderType;
+use crate::{AppState, ProviderType};
 use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
@@ -100,6 +104,7 @@ pub async fn get_request_logs(
             "success" => RequestStatus::Success,
             "failed" => RequestStatus::Failed,
             "timeout" => RequestStatus::Timeout,
+            "rate_limited" => RequestStatus::RateLimited,
             "retrying" => RequestStatus::Retrying,
             "cancelled" => RequestStatus::Cancelled,
             _ => return Err(format!("Invalid status: {}", s)),
@@ -119,12 +124,31 @@ pub async fn get_request_logs(
 }
 
 /// 获取单个请求日志详情
+///
+/// 内存中已轮转掉的日志从数据库中查找
 #[tauri::command]
 pub async fn get_request_log_detail(
     state: tauri::State<'_, TelemetryState>,
+    db: tauri::State<'_, DbConnection>,
     id: String,
 ) -> Result<Option<RequestLog>, String> {
-    Ok(state.logger.get_by_id(&id))
+    if let Some(log) = state.logger.get_by_id(&id) {
+        return Ok(Some(log));
+    }
+    db.run(move |conn| RequestLogDao::get(conn, &id)).await
+}
+
+/// 检索持久化的请求日志
+///
+/// 支持按 Provider / 模型 / 状态 / 凭证 / 客户端 Key / 时间过滤、错误信息全文搜索和分页
+#[tauri::command]
+pub async fn search_request_logs(
+    db: tauri::State<'_, DbConnection>,
+    query: Option<RequestLogQuery>,
+) -> Result<RequestLogPage, String> {
+    let query = query.unwrap_or_default();
+    db.run(move |conn| RequestLogDao::search(conn, &query))
+        .await
 }
 
 /// 清空请求日志
@@ -211,6 +235,20 @@ pub async fn get_stats_by_model(
     Ok(stats.by_model(range))
 }
 
+/// 获取延迟分位数（p50 / p95 / p99）
+///
+/// 包含端到端延迟和流式请求的首字节延迟，`group_by` 默认按 Provider 分组
+#[tauri::command]
+pub async fn get_latency_percentiles(
+    state: tauri::State<'_, TelemetryState>,
+    time_range: Option<TimeRangeParam>,
+    group_by: Option<LatencyGroupBy>,
+) -> Result<LatencyReport, String> {
+    let range = time_range.map(|r| r.to_time_range()).transpose()?.flatten();
+    let stats = state.stats.read();
+    Ok(stats.latency_percentiles(range, group_by.unwrap_or_default()))
+}
+
 // ========== Token 统计命令 ==========
 
 /// 获取 Token 统计摘要
@@ -284,3 +322,50 @@ pub async fn get_token_stats_by_day(
     let tokens = state.tokens.read();
     Ok(tokens.by_day(days.unwrap_or(7)))
 }
+
+// ========== 费用估算命令 ==========
+
+/// 按配置的单价表估算费用，按 Provider / 凭证 / 模型 / 天汇总
+#[tauri::command]
+pub async fn get_cost_report(
+    state: tauri::State<'_, TelemetryState>,
+    app_state: tauri::State<'_, AppState>,
+    time_range: Option<TimeRangeParam>,
+) -> Result<CostReport, String> {
+    let (start, end) = match time_range.map(|r| r.to_time_range()).transpose()?.flatten() {
+        Some(tr) => (Some(tr.start), Some(tr.end)),
+        None => (None, None),
+    };
+    let pricing = PricingTable::new(&app_state.read().await.config.pricing);
+    let tokens = state.tokens.read();
+    Ok(tokens.cost_report(&pricing, start, end))
+}
+
+// ========== 用量报告命令 ==========
+
+/// 生成日报 / 周报并渲染为指定格式
+///
+/// - `date`: 报告周期内的任意日期（YYYY-MM-DD，UTC），不传则为最近一个已结束的周期
+#[tauri::command]
+pub async fn generate_usage_report(
+    state: tauri::State<'_, TelemetryState>,
+    app_state: tauri::State<'_, AppState>,
+    period: ReportPeriod,
+    format: ReportFormat,
+    date: Option<String>,
+) -> Result<String, String> {
+    let date = match date {
+        Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date: {}", e))?,
+        None => period.last_complete(Utc::now()),
+    };
+    let (pricing, top_n) = {
+        let s = app_state.read().await;
+        (PricingTable::new(&s.config.pricing), s.config.reports.top_n)
+    };
+
+    let logs = state.stats.read().get_all();
+    let tokens = state.tokens.read().get_all();
+    let report = ReportGenerator::new(top_n).generate(period, date, &logs, &tokens, &pricing);
+    Ok(report.render(format))
+}
@@ -8,26 +8,101 @@
 //! ## 命令列表
 //! - `terminal_create_session` - 创建终端会话（使用默认大小）
 //! - `terminal_write` - 向终端发送输入
+//! - `terminal_interrupt_session` - 中断前台进程（输出洪泛时的快捷中断）
+//! - `terminal_send_key` - 发送按键（按 TERM、键盘模式和自定义绑定编码）
+//! - `terminal_keymap_get` - 获取自定义按键绑定
+//! - `terminal_keymap_save` - 保存自定义按键绑定
+//! - `terminal_paste` - 粘贴文本（括号粘贴，包含换行或控制字符时需确认）
+//! - `terminal_paste_respond` - 响应需确认的粘贴
 //! - `terminal_resize` - 调整终端大小
 //! - `terminal_close` - 关闭终端会话
 //! - `terminal_list_sessions` - 获取所有会话列表
+//! - `terminal_list_detached_sessions` - 获取可恢复的后台会话（应用重启后）
+//! - `terminal_restore_session` - 恢复会话
+//! - `terminal_attach_session` - 连接恢复的后台会话（回填滚动历史）
+//! - `terminal_detach_session` - 断开后台会话（Shell 继续运行）
+//! - `terminal_replay_start` - 开始回放块文件中录制的会话
+//! - `terminal_replay_control` - 控制回放（播放、暂停、调速、跳转）
+//! - `terminal_replay_stop` - 停止回放
+//! - `terminal_history_query` - 查询命令历史（前缀搜索、按主机过滤、排序）
+//! - `terminal_history_hosts` - 获取有命令历史的主机列表
+//! - `terminal_history_delete` - 删除单条命令历史
+//! - `terminal_history_clear` - 清空命令历史
+//! - `terminal_command_blocks` - 查询会话的命令块（命令、退出码、耗时、目录、Git 分支）
+//! - `terminal_command_output` - 获取单条命令的输出（纯文本或保留 ANSI 序列）
+//! - `terminal_prompt_marks` - 获取会话的提示符标记（OSC 133，含历史输出，用于跳转到提示符）
+//! - `terminal_prompt_rerun` - 重新执行提示符标记处的命令
+//! - `terminal_session_metrics` - 获取会话指标（字节数、输出速率、回显延迟估算、调整大小次数）
+//! - `terminal_save_file` - 保存终端内传输的文件（OSC 1337 File=），由后端弹出保存对话框
+//! - `terminal_upload_files` - 获取拖放到窗口的可上传文件（zmodem 上传）
+//! - `terminal_upload_pick_files` - 弹出文件对话框选择要上传的文件
+//! - `terminal_upload_read` - 读取待上传文件的一段内容
+//! - `terminal_profile_list` - 获取终端启动配置列表
+//! - `terminal_profile_save` - 新建或更新终端启动配置
+//! - `terminal_profile_delete` - 删除终端启动配置
+//! - `terminal_clipboard_respond` - 响应需确认的 OSC 52 剪贴板请求
+//! - `terminal_clipboard_policy_get` - 获取主机生效的剪贴板策略
+//! - `terminal_clipboard_policy_list` - 获取保存的剪贴板策略列表
+//! - `terminal_clipboard_policy_save` - 保存主机的剪贴板策略
+//! - `terminal_clipboard_policy_delete` - 删除主机的剪贴板策略（恢复默认策略）
+//! - `terminal_image_get` - 从缓存读取内联图片（sixel、iTerm2 OSC 1337）
+//! - `terminal_image_cache_clear` - 清空内联图片缓存
+//! - `terminal_storage_settings_get` - 获取输出历史存储设置（回滚缓冲大小、冷段压缩、清理策略）
+//! - `terminal_storage_settings_save` - 保存输出历史存储设置
+//! - `terminal_storage_usage` - 获取每个会话输出历史的磁盘占用
+//! - `terminal_storage_prune` - 按清理策略清理已结束会话的输出历史
+//! - `terminal_share_create` - 分享会话（生成一次性令牌，只读观看或协作）
+//! - `terminal_share_list` - 获取会话分享列表
+//! - `terminal_share_revoke` - 撤销会话分享（断开已连接的观看者）
+//! - `terminal_workspace_list` - 获取工作区列表（标签页与分屏布局）
+//! - `terminal_workspace_create` - 创建工作区
+//! - `terminal_workspace_rename` - 重命名工作区
+//! - `terminal_workspace_set_layout` - 保存工作区的分屏布局
+//! - `terminal_workspace_reorder` - 调整工作区顺序
+//! - `terminal_workspace_delete` - 删除工作区（关闭其中的会话）
+//! - `terminal_workspace_place_session` - 把会话放入工作区（在指定窗格旁分屏）
 
 use std::sync::Arc;
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::Serialize;
-use tauri::State;
-use tokio::sync::RwLock;
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::{oneshot, RwLock};
 
-use crate::terminal::{SessionMetadata, TerminalSessionManager};
+use crate::telemetry::TerminalMetricsSnapshot;
+use crate::terminal::integration::{decode_file_data, sanitize_file_name};
+use crate::terminal::{
+    BlockPruneReport, BlockStorageSettings, BlockUsage, ClipboardBridge, ClipboardPolicy,
+    ClipboardPolicyStore, CommandBlock, CommandBlockQuery, CommandHistoryEntry,
+    CommandHistoryQuery, CommandHistoryStore, CommandOutput, CommandOutputFormat, ImageCache,
+    KeyChord, KeymapSettings, LaunchProfile, LaunchProfileStore, PaneLayout, PanePlacement,
+    PasteOutcome, PromptMarks, ReplayCommand, ReplayInfo, SessionMetadata, ShareMode,
+    TerminalSessionManager, TerminalShareInfo, TerminalShareTicket, TerminalWorkspace,
+    UploadFileInfo, UploadGrants,
+};
 
 /// 终端会话管理器状态包装
 pub struct TerminalManagerState(pub Arc<RwLock<Option<TerminalSessionManager>>>);
 
+/// 缓存的内联图片响应
+#[derive(Debug, Serialize)]
+pub struct TerminalImageResponse {
+    /// MIME 类型
+    pub mime: String,
+    /// 图片数据（Base64 编码）
+    pub data: String,
+}
+
 /// 创建终端会话响应
 #[derive(Debug, Serialize)]
 pub struct CreateSessionResponse {
     /// 会话 ID
     pub session_id: String,
+    /// 是否为后台会话（请求后台会话但 tmux 不可用时为 false）
+    pub detached: bool,
+    /// 会话所在的工作区（指定 `placement` 时）
+    pub workspace: Option<TerminalWorkspace>,
 }
 
 /// 创建终端会话（使用默认大小）
@@ -36,26 +111,78 @@ pub struct CreateSessionResponse {
 ///
 /// # 参数
 /// - `cwd`: 工作目录（可选）
+/// - `detached`: 是否创建后台会话（可选，默认 false），后台会话在应用重启后可恢复
+/// - `connection`: 连接名称（可选，如 `mosh://…`、`telnet://…`、`serial://…`、`docker://…`、`k8s://…`）
+/// - `profile_id`: 启动配置 ID（可选），指定时按启动配置创建会话，传入的 `cwd`、`connection` 覆盖配置中的值
+/// - `scrollback_bytes`: 回滚缓冲大小（可选，字节），为空时使用存储设置中的大小
+/// - `placement`: 放入的工作区和分屏位置（可选），放入失败时关闭会话并返回错误
 ///
 /// # 返回
 /// - `Ok(CreateSessionResponse)`: 包含会话 ID
 /// - `Err(String)`: 错误信息
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn terminal_create_session(
     state: State<'_, TerminalManagerState>,
+    profiles: State<'_, Arc<LaunchProfileStore>>,
     cwd: Option<String>,
+    detached: Option<bool>,
+    connection: Option<String>,
+    profile_id: Option<String>,
+    scrollback_bytes: Option<usize>,
+    placement: Option<PanePlacement>,
 ) -> Result<CreateSessionResponse, String> {
+    let profile = match profile_id {
+        Some(id) => {
+            let mut profile = profiles
+                .get(&id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("启动配置不存在: {}", id))?;
+            profile.cwd = cwd.or(profile.cwd);
+            profile.connection = connection.or(profile.connection);
+            Some(profile)
+        }
+        None => None,
+    };
+
     let guard = state.inner().0.read().await;
     let manager = guard
         .as_ref()
         .ok_or_else(|| "终端管理器未初始化".to_string())?;
 
-    let session_id = manager
-        .create_session_with_options(24, 80, cwd)
-        .await
-        .map_err(|e| e.to_string())?;
+    let detached = detached.unwrap_or(false);
+    let metadata = match profile {
+        Some(profile) => {
+            manager
+                .create_session_with_profile(24, 80, &profile, detached, scrollback_bytes)
+                .await
+        }
+        None => {
+            manager
+                .create_session_with_options(24, 80, cwd, detached, connection, scrollback_bytes)
+                .await
+        }
+    }
+    .map_err(|e| e.to_string())?;
+
+    let workspace = match placement {
+        Some(placement) => match manager.place_session(&metadata.id, &placement).await {
+            Ok(workspace) => Some(workspace),
+            Err(e) => {
+                if let Err(close_err) = manager.close_session(&metadata.id).await {
+                    tracing::warn!("[终端] 关闭会话 {} 失败: {}", metadata.id, close_err);
+                }
+                return Err(e.to_string());
+            }
+        },
+        None => None,
+    };
 
-    Ok(CreateSessionResponse { session_id })
+    Ok(CreateSessionResponse {
+        session_id: metadata.id,
+        detached: metadata.detached,
+        workspace,
+    })
 }
 
 /// 向终端发送输入
@@ -80,6 +207,138 @@ pub async fn terminal_write(
         .map_err(|e| e.to_string())
 }
 
+/// 中断终端的前台进程
+///
+/// 收到 `terminal:output-flood` 事件后，前端可通过该命令快速中断持续大量输出的进程。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+#[tauri::command]
+pub async fn terminal_interrupt_session(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .interrupt_session(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 向终端发送按键
+///
+/// 前端只上报按键组合，由后端按会话的 TERM、终端程序启用的键盘模式
+/// （应用光标键、modifyOtherKeys、kitty 键盘协议）和自定义按键绑定编码。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `chord`: 按键组合（按键名称使用 `KeyboardEvent.key`）
+///
+/// # 返回
+/// 是否已写入（无法编码的按键返回 `false`，前端按普通输入处理）
+#[tauri::command]
+pub async fn terminal_send_key(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    chord: KeyChord,
+) -> Result<bool, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .send_key(&session_id, &chord)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取自定义按键绑定
+#[tauri::command]
+pub async fn terminal_keymap_get(
+    state: State<'_, TerminalManagerState>,
+) -> Result<KeymapSettings, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    Ok(manager.keymap_settings().await)
+}
+
+/// 保存自定义按键绑定
+///
+/// # 参数
+/// - `settings`: 按键绑定设置（按键组合如 `ctrl+shift+ArrowLeft`，写入内容为转义文本如 `\e[1;5D`）
+#[tauri::command]
+pub async fn terminal_keymap_save(
+    state: State<'_, TerminalManagerState>,
+    settings: KeymapSettings,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .save_keymap_settings(settings)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 粘贴文本到终端
+///
+/// 终端程序启用括号粘贴模式时包裹粘贴内容；内容包含换行或可疑控制字符且未确认时，
+/// 发送 `terminal:paste-warning` 事件并返回等待确认。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `text`: 粘贴的文本
+/// - `confirmed`: 是否已确认（为 true 时直接写入）
+#[tauri::command]
+pub async fn terminal_paste(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    text: String,
+    confirmed: Option<bool>,
+) -> Result<PasteOutcome, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .paste_to_session(&session_id, &text, confirmed.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 响应需确认的粘贴
+///
+/// # 参数
+/// - `paste_id`: 粘贴 ID（`terminal:paste-warning` 事件中的 `paste_id`）
+/// - `allow`: 是否允许写入
+#[tauri::command]
+pub async fn terminal_paste_respond(
+    state: State<'_, TerminalManagerState>,
+    paste_id: String,
+    allow: bool,
+) -> Result<PasteOutcome, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .respond_paste(&paste_id, allow)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// 调整终端大小
 ///
 /// # 参数
@@ -153,3 +412,827 @@ pub async fn terminal_get_session(
 
     Ok(manager.get_session(&session_id).await)
 }
+
+/// 获取可恢复的后台会话
+///
+/// 应用重启后调用，返回 tmux 中仍在运行、尚未恢复的会话。tmux 不可用时返回空列表。
+#[tauri::command]
+pub async fn terminal_list_detached_sessions(
+    state: State<'_, TerminalManagerState>,
+) -> Result<Vec<SessionMetadata>, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .list_detached_sessions()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 恢复会话
+///
+/// 后台会话恢复后还需调用 `terminal_attach_session` 连接。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+#[tauri::command]
+pub async fn terminal_restore_session(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+) -> Result<SessionMetadata, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .restore_session(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 连接恢复的后台会话
+///
+/// 先通过 `terminal:output` 发送滚动历史，再连接 tmux。前端应在订阅输出事件后调用。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+#[tauri::command]
+pub async fn terminal_attach_session(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .attach_session(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 断开后台会话
+///
+/// 关闭本地连接，Shell 在后台继续运行。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+#[tauri::command]
+pub async fn terminal_detach_session(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .detach_session(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 开始回放块文件中录制的会话
+///
+/// 回放创建后处于暂停状态，前端订阅 `terminal:replay-output` 后通过
+/// `terminal_replay_control` 发送 `play` 开始播放。
+///
+/// # 参数
+/// - `block_id`: 块 ID（会话 ID）
+/// - `speed`: 初始播放速度（可选，默认 1.0）
+/// - `max_idle_ms`: 最大空闲间隔（可选，默认 2000 毫秒）
+#[tauri::command]
+pub async fn terminal_replay_start(
+    state: State<'_, TerminalManagerState>,
+    block_id: String,
+    speed: Option<f64>,
+    max_idle_ms: Option<u64>,
+) -> Result<ReplayInfo, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .start_replay(&block_id, speed, max_idle_ms)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 控制回放
+///
+/// # 参数
+/// - `replay_id`: 回放 ID
+/// - `command`: 控制指令，如 `{ "action": "seek_to_command", "index": 2 }`
+#[tauri::command]
+pub async fn terminal_replay_control(
+    state: State<'_, TerminalManagerState>,
+    replay_id: String,
+    command: ReplayCommand,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .control_replay(&replay_id, command)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 停止回放
+///
+/// # 参数
+/// - `replay_id`: 回放 ID
+#[tauri::command]
+pub async fn terminal_replay_stop(
+    state: State<'_, TerminalManagerState>,
+    replay_id: String,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .stop_replay(&replay_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 查询命令历史
+///
+/// # 参数
+/// - `query`: 查询条件，如 `{ "prefix": "git", "host": "local", "sort": "frequency" }`
+#[tauri::command]
+pub async fn terminal_history_query(
+    store: State<'_, Arc<CommandHistoryStore>>,
+    query: CommandHistoryQuery,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    store.query(&query).map_err(|e| e.to_string())
+}
+
+/// 获取有命令历史的主机列表
+#[tauri::command]
+pub async fn terminal_history_hosts(
+    store: State<'_, Arc<CommandHistoryStore>>,
+) -> Result<Vec<String>, String> {
+    store.hosts().map_err(|e| e.to_string())
+}
+
+/// 删除单条命令历史
+///
+/// # 参数
+/// - `id`: 记录 ID
+#[tauri::command]
+pub async fn terminal_history_delete(
+    store: State<'_, Arc<CommandHistoryStore>>,
+    id: i64,
+) -> Result<(), String> {
+    store.delete(id).map_err(|e| e.to_string())
+}
+
+/// 清空命令历史
+///
+/// # 参数
+/// - `host`: 主机（可选，不指定时清空所有主机）
+///
+/// # 返回
+/// 删除的记录数
+#[tauri::command]
+pub async fn terminal_history_clear(
+    store: State<'_, Arc<CommandHistoryStore>>,
+    host: Option<String>,
+) -> Result<usize, String> {
+    store.clear(host.as_deref()).map_err(|e| e.to_string())
+}
+
+/// 查询会话的命令块
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `query`: 查询条件，如 `{ "failed_only": true, "limit": 20 }`
+#[tauri::command]
+pub async fn terminal_command_blocks(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    query: Option<CommandBlockQuery>,
+) -> Result<Vec<CommandBlock>, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .command_blocks(&session_id, &query.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取单条命令的输出
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `index`: 命令块序号
+/// - `format`: 输出格式（默认纯文本）
+#[tauri::command]
+pub async fn terminal_command_output(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    index: u64,
+    format: Option<CommandOutputFormat>,
+) -> Result<CommandOutput, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .command_output(&session_id, index, format.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取会话的提示符标记
+///
+/// 前端按 `line_count - line` 计算标记距输出末尾的行数，实现跳转到上一个 / 下一个提示符。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+#[tauri::command]
+pub async fn terminal_prompt_marks(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+) -> Result<PromptMarks, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .prompt_marks(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 重新执行提示符标记处的命令
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `index`: 提示符标记序号
+#[tauri::command]
+pub async fn terminal_prompt_rerun(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    index: u64,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .rerun_prompt_command(&session_id, index)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取会话指标
+///
+/// 用于诊断终端卡顿：输入 / 输出字节数、最近 10 秒输出速率、输入到回显的延迟估算和调整大小次数。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+#[tauri::command]
+pub async fn terminal_session_metrics(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+) -> Result<TerminalMetricsSnapshot, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .session_metrics(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 保存终端内传输的文件
+///
+/// 远程程序通过 OSC 1337 File= 序列发送文件，前端解析后调用此命令。
+/// 保存路径由后端弹出的保存对话框选择，前端只能提供默认文件名。
+///
+/// # 参数
+/// - `name`: 远程声明的文件名（去除路径部分后作为默认文件名）
+/// - `data`: Base64 编码的文件内容（允许包含换行等空白字符）
+///
+/// # 返回
+/// 保存路径，用户取消时返回 None
+#[tauri::command]
+pub async fn terminal_save_file(
+    app: AppHandle,
+    name: String,
+    data: String,
+) -> Result<Option<String>, String> {
+    let bytes = decode_file_data(&data)?;
+
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("保存终端传输的文件")
+        .set_file_name(sanitize_file_name(&name))
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let Some(path) = rx.await.map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let path = path.into_path().map_err(|e| e.to_string())?;
+
+    tokio::fs::write(&path, &bytes)
+        .await
+        .map_err(|e| format!("写入文件失败: {}", e))?;
+
+    tracing::info!(
+        "[终端] 已保存传输文件: {} ({} 字节)",
+        path.display(),
+        bytes.len()
+    );
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+/// 获取可上传文件的信息
+///
+/// 只返回用户拖放到窗口的文件（由窗口拖放事件授权），用于 zmodem 上传。
+///
+/// # 参数
+/// - `paths`: 拖放事件中的文件路径
+#[tauri::command]
+pub async fn terminal_upload_files(
+    grants: State<'_, Arc<UploadGrants>>,
+    paths: Vec<String>,
+) -> Result<Vec<UploadFileInfo>, String> {
+    Ok(grants.files(&paths))
+}
+
+/// 弹出文件对话框选择要上传的文件
+///
+/// # 返回
+/// 选择的文件，用户取消时为空
+#[tauri::command]
+pub async fn terminal_upload_pick_files(
+    app: AppHandle,
+    grants: State<'_, Arc<UploadGrants>>,
+) -> Result<Vec<UploadFileInfo>, String> {
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("选择要上传的文件")
+        .pick_files(move |paths| {
+            let _ = tx.send(paths);
+        });
+    let paths = rx.await.map_err(|e| e.to_string())?.unwrap_or_default();
+    let paths = paths.into_iter().filter_map(|path| path.into_path().ok());
+    Ok(grants.grant(paths))
+}
+
+/// 读取待上传文件的一段内容
+///
+/// # 参数
+/// - `path`: 已授权上传的文件路径
+/// - `offset`: 起始偏移（字节）
+/// - `length`: 读取长度（最多 1 MiB）
+///
+/// # 返回
+/// Base64 编码的文件内容，到达文件末尾时为空
+#[tauri::command]
+pub async fn terminal_upload_read(
+    grants: State<'_, Arc<UploadGrants>>,
+    path: String,
+    offset: u64,
+    length: usize,
+) -> Result<String, String> {
+    let grants = grants.inner().clone();
+    let bytes = tokio::task::spawn_blocking(move || grants.read(&path, offset, length))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(BASE64.encode(bytes))
+}
+
+/// 获取终端启动配置列表（按名称排序）
+#[tauri::command]
+pub async fn terminal_profile_list(
+    store: State<'_, Arc<LaunchProfileStore>>,
+) -> Result<Vec<LaunchProfile>, String> {
+    store.list().map_err(|e| e.to_string())
+}
+
+/// 新建或更新终端启动配置
+///
+/// # 参数
+/// - `profile`: 启动配置（`id` 为空时新建）
+///
+/// # 返回
+/// 保存后的启动配置
+#[tauri::command]
+pub async fn terminal_profile_save(
+    store: State<'_, Arc<LaunchProfileStore>>,
+    profile: LaunchProfile,
+) -> Result<LaunchProfile, String> {
+    store.save(&profile).map_err(|e| e.to_string())
+}
+
+/// 删除终端启动配置
+///
+/// # 参数
+/// - `id`: 启动配置 ID
+///
+/// # 返回
+/// 是否删除了配置
+#[tauri::command]
+pub async fn terminal_profile_delete(
+    store: State<'_, Arc<LaunchProfileStore>>,
+    id: String,
+) -> Result<bool, String> {
+    store.delete(&id).map_err(|e| e.to_string())
+}
+
+/// 响应需确认的 OSC 52 剪贴板请求
+///
+/// # 参数
+/// - `request_id`: 请求 ID（来自 `terminal:clipboard-request` 事件）
+/// - `allow`: 是否允许
+/// - `remember`: 是否记住决定（更新该主机的剪贴板策略）
+#[tauri::command]
+pub async fn terminal_clipboard_respond(
+    bridge: State<'_, Arc<ClipboardBridge>>,
+    request_id: String,
+    allow: bool,
+    remember: Option<bool>,
+) -> Result<(), String> {
+    bridge
+        .respond(&request_id, allow, remember.unwrap_or(false))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// 获取主机生效的剪贴板策略（没有保存策略时返回默认策略）
+///
+/// # 参数
+/// - `host`: 主机（连接名称，本地为 `local`）
+#[tauri::command]
+pub async fn terminal_clipboard_policy_get(
+    store: State<'_, Arc<ClipboardPolicyStore>>,
+    host: String,
+) -> Result<ClipboardPolicy, String> {
+    Ok(store.resolve(&host))
+}
+
+/// 获取保存的剪贴板策略列表（按主机排序）
+#[tauri::command]
+pub async fn terminal_clipboard_policy_list(
+    store: State<'_, Arc<ClipboardPolicyStore>>,
+) -> Result<Vec<ClipboardPolicy>, String> {
+    store.list().map_err(|e| e.to_string())
+}
+
+/// 保存主机的剪贴板策略
+///
+/// # 参数
+/// - `policy`: 剪贴板策略
+///
+/// # 返回
+/// 保存后的剪贴板策略
+#[tauri::command]
+pub async fn terminal_clipboard_policy_save(
+    store: State<'_, Arc<ClipboardPolicyStore>>,
+    policy: ClipboardPolicy,
+) -> Result<ClipboardPolicy, String> {
+    store.save(&policy).map_err(|e| e.to_string())
+}
+
+/// 删除主机的剪贴板策略（之后使用默认策略）
+///
+/// # 参数
+/// - `host`: 主机
+///
+/// # 返回
+/// 是否删除了策略
+#[tauri::command]
+pub async fn terminal_clipboard_policy_delete(
+    store: State<'_, Arc<ClipboardPolicyStore>>,
+    host: String,
+) -> Result<bool, String> {
+    store.delete(&host).map_err(|e| e.to_string())
+}
+
+/// 从缓存读取内联图片
+///
+/// # 参数
+/// - `image_id`: 图片 ID（`terminal:image` 事件中的 `image_id`）
+///
+/// # 返回
+/// 缓存的图片，已被淘汰时返回 None
+#[tauri::command]
+pub async fn terminal_image_get(
+    cache: State<'_, Arc<ImageCache>>,
+    image_id: String,
+) -> Result<Option<TerminalImageResponse>, String> {
+    let image = cache.get(&image_id).map_err(|e| e.to_string())?;
+    Ok(image.map(|image| TerminalImageResponse {
+        mime: image.mime,
+        data: BASE64.encode(&image.data),
+    }))
+}
+
+/// 清空内联图片缓存
+///
+/// # 返回
+/// 删除的图片数量
+#[tauri::command]
+pub async fn terminal_image_cache_clear(
+    cache: State<'_, Arc<ImageCache>>,
+) -> Result<usize, String> {
+    cache.clear().map_err(|e| e.to_string())
+}
+
+/// 获取输出历史存储设置
+#[tauri::command]
+pub async fn terminal_storage_settings_get(
+    state: State<'_, TerminalManagerState>,
+) -> Result<BlockStorageSettings, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    Ok(manager.storage_settings().await)
+}
+
+/// 保存输出历史存储设置
+///
+/// # 参数
+/// - `settings`: 存储设置（回滚缓冲大小、冷段压缩、清理策略），对之后创建的会话生效
+#[tauri::command]
+pub async fn terminal_storage_settings_save(
+    state: State<'_, TerminalManagerState>,
+    settings: BlockStorageSettings,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .save_storage_settings(settings)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取每个会话输出历史的磁盘占用
+///
+/// # 返回
+/// 按最后写入时间从新到旧排序的占用记录
+#[tauri::command]
+pub async fn terminal_storage_usage(
+    state: State<'_, TerminalManagerState>,
+) -> Result<Vec<BlockUsage>, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager.storage_usage().await.map_err(|e| e.to_string())
+}
+
+/// 按清理策略清理已结束会话的输出历史
+///
+/// # 返回
+/// 被清理的会话和释放的字节数
+#[tauri::command]
+pub async fn terminal_storage_prune(
+    state: State<'_, TerminalManagerState>,
+) -> Result<BlockPruneReport, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager.prune_block_files().await.map_err(|e| e.to_string())
+}
+
+/// 分享会话
+///
+/// 观看者在令牌有效期内通过代理服务器的 WebSocket（返回的 `path`）连接，令牌只能使用一次。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `mode`: 分享模式（默认只读）
+/// - `ttl_secs`: 令牌有效期（秒，默认 600，最长 24 小时）
+///
+/// # 返回
+/// 分享信息、一次性令牌和 WebSocket 路径
+#[tauri::command]
+pub async fn terminal_share_create(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    mode: Option<ShareMode>,
+    ttl_secs: Option<u64>,
+) -> Result<TerminalShareTicket, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .create_share(&session_id, mode.unwrap_or_default(), ttl_secs)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取会话分享列表
+///
+/// # 参数
+/// - `session_id`: 只列出该会话的分享（可选）
+#[tauri::command]
+pub async fn terminal_share_list(
+    state: State<'_, TerminalManagerState>,
+    session_id: Option<String>,
+) -> Result<Vec<TerminalShareInfo>, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    Ok(manager.list_shares(session_id.as_deref()))
+}
+
+/// 撤销会话分享
+///
+/// # 参数
+/// - `share_id`: 分享 ID
+#[tauri::command]
+pub async fn terminal_share_revoke(
+    state: State<'_, TerminalManagerState>,
+    share_id: String,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager.revoke_share(&share_id).map_err(|e| e.to_string())
+}
+
+/// 获取工作区列表（按标签页顺序）
+#[tauri::command]
+pub async fn terminal_workspace_list(
+    state: State<'_, TerminalManagerState>,
+) -> Result<Vec<TerminalWorkspace>, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    Ok(manager.list_workspaces().await)
+}
+
+/// 创建工作区
+///
+/// # 参数
+/// - `name`: 名称
+#[tauri::command]
+pub async fn terminal_workspace_create(
+    state: State<'_, TerminalManagerState>,
+    name: String,
+) -> Result<TerminalWorkspace, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .create_workspace(&name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 重命名工作区
+///
+/// # 参数
+/// - `workspace_id`: 工作区 ID
+/// - `name`: 新名称
+#[tauri::command]
+pub async fn terminal_workspace_rename(
+    state: State<'_, TerminalManagerState>,
+    workspace_id: String,
+    name: String,
+) -> Result<TerminalWorkspace, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .rename_workspace(&workspace_id, &name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 保存工作区的分屏布局
+///
+/// 布局中的会话从其他工作区移除。
+///
+/// # 参数
+/// - `workspace_id`: 工作区 ID
+/// - `layout`: 分屏布局（为空时清空工作区）
+/// - `active_session_id`: 当前聚焦的会话（可选）
+#[tauri::command]
+pub async fn terminal_workspace_set_layout(
+    state: State<'_, TerminalManagerState>,
+    workspace_id: String,
+    layout: Option<PaneLayout>,
+    active_session_id: Option<String>,
+) -> Result<TerminalWorkspace, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .set_workspace_layout(&workspace_id, layout, active_session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 调整工作区顺序
+///
+/// # 参数
+/// - `workspace_ids`: 工作区 ID（按新顺序）
+#[tauri::command]
+pub async fn terminal_workspace_reorder(
+    state: State<'_, TerminalManagerState>,
+    workspace_ids: Vec<String>,
+) -> Result<Vec<TerminalWorkspace>, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .reorder_workspaces(&workspace_ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 删除工作区并关闭其中的会话
+///
+/// # 参数
+/// - `workspace_id`: 工作区 ID
+#[tauri::command]
+pub async fn terminal_workspace_delete(
+    state: State<'_, TerminalManagerState>,
+    workspace_id: String,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .delete_workspace(&workspace_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 把会话放入工作区
+///
+/// 会话在目标窗格旁分屏并设为当前会话，同时从原来所在的工作区移除。
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `placement`: 目标工作区和分屏位置
+#[tauri::command]
+pub async fn terminal_workspace_place_session(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    placement: PanePlacement,
+) -> Result<TerminalWorkspace, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .place_session(&session_id, &placement)
+        .await
+        .map_err(|e| e.to_string())
+}
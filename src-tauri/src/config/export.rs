@@ -301,6 +301,18 @@ impl ExportService {
         // 脱敏凭证池中的 API Key
         redacted.credential_pool = Self::redact_credential_pool(&config.credential_pool);
 
+        // 脱敏 OTLP 导出请求头（通常包含认证信息）
+        for value in redacted.otlp.headers.values_mut() {
+            *value = REDACTED_PLACEHOLDER.to_string();
+        }
+
+        // 脱敏选择器端点的专用 API Key
+        for endpoint in redacted.selector_endpoints.values_mut() {
+            if endpoint.api_key.is_some() {
+                endpoint.api_key = Some(REDACTED_PLACEHOLDER.to_string());
+            }
+        }
+
         redacted
     }
 
@@ -371,6 +383,15 @@ impl ExportService {
             }
         }
 
+        // 检查选择器端点的专用 API Key
+        for endpoint in config.selector_endpoints.values() {
+            if let Some(ref key) = endpoint.api_key {
+                if !key.is_empty() && key != REDACTED_PLACEHOLDER {
+                    return true;
+                }
+            }
+        }
+
         false
     }
 
@@ -676,6 +697,27 @@ mod unit_tests {
         assert!(ExportService::contains_secrets(&config));
     }
 
+    #[test]
+    fn test_redact_selector_endpoint_keys() {
+        let mut config = Config::default();
+        config.server.api_key = REDACTED_PLACEHOLDER.to_string();
+        config.selector_endpoints.insert(
+            "team-a".to_string(),
+            crate::config::SelectorEndpointConfig {
+                api_key: Some("team-a-key".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(ExportService::contains_secrets(&config));
+
+        let redacted = ExportService::redact_config(&config);
+        assert_eq!(
+            redacted.selector_endpoints["team-a"].api_key.as_deref(),
+            Some(REDACTED_PLACEHOLDER)
+        );
+        assert!(!ExportService::contains_secrets(&redacted));
+    }
+
     #[test]
     fn test_export_config_only() {
         let config = Config::default();
@@ -7,8 +7,8 @@
 #![allow(dead_code)]
 //! - 失败时自动回滚到之前的配置
 
+use super::schema::{self, ConfigDiagnostic};
 use super::types::{is_default_api_key, Config};
-use super::yaml::ConfigManager;
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use parking_lot::RwLock;
 use std::path::{Path, PathBuf};
@@ -55,11 +55,15 @@ pub enum ReloadResult {
     Success {
         /// 重载时间戳
         timestamp: Instant,
+        /// 警告级别的诊断（如未知字段）
+        warnings: Vec<ConfigDiagnostic>,
     },
     /// 重载失败，已回滚
     RolledBack {
-        /// 错误信息
+        /// 错误信息（诊断摘要）
         error: String,
+        /// 结构化诊断（含路径和修改建议）
+        diagnostics: Vec<ConfigDiagnostic>,
         /// 回滚时间戳
         timestamp: Instant,
     },
@@ -311,26 +315,37 @@ impl HotReloadManager {
         }
 
         // 2. 尝试加载新配置
-        let new_config = match self.load_config_from_file() {
-            Ok(config) => config,
-            Err(e) => {
+        let (new_config, mut diagnostics) = match self.load_config_from_file() {
+            Ok(loaded) => loaded,
+            Err(diagnostic) => {
                 // 加载失败，清除备份（无需回滚，因为当前配置未变）
                 let mut backup = self.backup_config.write();
                 *backup = None;
                 return ReloadResult::RolledBack {
-                    error: e.to_string(),
+                    error: diagnostic.to_string(),
+                    diagnostics: vec![diagnostic],
                     timestamp: now,
                 };
             }
         };
 
         // 3. 验证新配置
-        if let Err(e) = self.validate_config(&new_config) {
+        diagnostics.extend(self.validate_config(&new_config));
+        let (errors, warnings): (Vec<_>, Vec<_>) = diagnostics
+            .into_iter()
+            .partition(ConfigDiagnostic::is_error);
+        if !errors.is_empty() {
             // 验证失败，清除备份
             let mut backup = self.backup_config.write();
             *backup = None;
+            let error = errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ");
             return ReloadResult::RolledBack {
-                error: e.to_string(),
+                error,
+                diagnostics: errors.into_iter().chain(warnings).collect(),
                 timestamp: now,
             };
         }
@@ -354,83 +369,99 @@ impl HotReloadManager {
         }
 
         tracing::info!("配置热重载成功");
-        ReloadResult::Success { timestamp: now }
+        ReloadResult::Success {
+            timestamp: now,
+            warnings,
+        }
     }
 
-    /// 从文件加载配置
-    fn load_config_from_file(&self) -> Result<Config, HotReloadError> {
+    /// 从文件加载配置，同时返回解析阶段的诊断
+    fn load_config_from_file(&self) -> Result<(Config, Vec<ConfigDiagnostic>), ConfigDiagnostic> {
         if !self.config_path.exists() {
-            return Err(HotReloadError::LoadError(format!(
-                "配置文件不存在: {:?}",
-                self.config_path
-            )));
+            return Err(ConfigDiagnostic::error(
+                "",
+                format!("配置文件不存在: {:?}", self.config_path),
+            ));
         }
 
         let content = std::fs::read_to_string(&self.config_path)
-            .map_err(|e| HotReloadError::LoadError(e.to_string()))?;
+            .map_err(|e| ConfigDiagnostic::error("", format!("配置读取错误: {}", e)))?;
 
-        ConfigManager::parse_yaml(&content).map_err(|e| HotReloadError::LoadError(e.to_string()))
+        schema::parse_with_diagnostics(&content)
     }
 
     /// 验证配置
-    fn validate_config(&self, config: &Config) -> Result<(), HotReloadError> {
-        let is_localhost = is_localhost_host(&config.server.host);
-        let is_valid_host = is_valid_bind_host(&config.server.host);
-        let is_non_local = is_non_local_bind(&config.server.host);
+    fn validate_config(&self, config: &Config) -> Vec<ConfigDiagnostic> {
+        let mut diagnostics = Vec::new();
 
         // 验证端口范围
         if config.server.port == 0 {
-            return Err(HotReloadError::ValidationError(
-                "端口号不能为 0".to_string(),
-            ));
+            diagnostics.push(
+                ConfigDiagnostic::error("server.port", "端口号不能为 0")
+                    .with_suggestion("使用 1-65535 之间的端口，默认为 8999"),
+            );
         }
 
         // 验证绑定地址
-        if !is_valid_host {
-            return Err(HotReloadError::ValidationError(
-                "无效的监听地址。允许的地址：127.0.0.1、localhost、::1、0.0.0.0、::".to_string(),
-            ));
+        if !is_valid_bind_host(&config.server.host) {
+            diagnostics.push(
+                ConfigDiagnostic::error("server.host", "无效的监听地址").with_suggestion(
+                    "允许的地址：127.0.0.1、localhost、::1、0.0.0.0、:: 或局域网 IP",
+                ),
+            );
         }
 
         // 验证重试配置
         if config.retry.max_retries > 100 {
-            return Err(HotReloadError::ValidationError(
-                "最大重试次数不能超过 100".to_string(),
-            ));
+            diagnostics.push(
+                ConfigDiagnostic::error("retry.max_retries", "最大重试次数不能超过 100")
+                    .with_suggestion("通常设置为 3"),
+            );
         }
 
         if config.retry.base_delay_ms == 0 {
-            return Err(HotReloadError::ValidationError(
-                "基础延迟不能为 0".to_string(),
-            ));
+            diagnostics.push(
+                ConfigDiagnostic::error("retry.base_delay_ms", "基础延迟不能为 0")
+                    .with_suggestion("默认为 1000"),
+            );
         }
 
         // 验证日志保留天数
         if config.logging.retention_days == 0 {
-            return Err(HotReloadError::ValidationError(
-                "日志保留天数不能为 0".to_string(),
-            ));
+            diagnostics.push(
+                ConfigDiagnostic::error("logging.retention_days", "日志保留天数不能为 0")
+                    .with_suggestion("默认为 7"),
+            );
         }
 
         if config.server.api_key.trim().is_empty() {
-            return Err(HotReloadError::ValidationError(
-                "API Key 不能为空".to_string(),
-            ));
+            diagnostics.push(
+                ConfigDiagnostic::error("server.api_key", "API Key 不能为空")
+                    .with_suggestion("删除该字段后重启，将自动生成强随机 Key"),
+            );
         }
 
-        if config.server.tls.enable {
-            return Err(HotReloadError::ValidationError(
-                "当前版本暂不支持 TLS，请关闭 TLS 配置".to_string(),
-            ));
+        if let Err(e) =
+            crate::server::tls::validate_tls_config(&config.server.tls, &config.server.host)
+        {
+            diagnostics.push(ConfigDiagnostic::error("server.tls", e));
+        }
+
+        if let Err(e) = crate::server::bind_guard::check_bind_exposure(&config.server) {
+            diagnostics.push(ConfigDiagnostic::error("server.host", e));
         }
 
         if config.remote_management.allow_remote {
-            return Err(HotReloadError::ValidationError(
-                "当前版本未启用 TLS，禁止开启远程管理".to_string(),
-            ));
+            diagnostics.push(
+                ConfigDiagnostic::error(
+                    "remote_management.allow_remote",
+                    "当前版本未启用 TLS，禁止开启远程管理",
+                )
+                .with_suggestion("设置为 false"),
+            );
         }
 
-        Ok(())
+        diagnostics
     }
 
     /// 手动回滚到备份配置
@@ -663,6 +694,35 @@ server:
         }
     }
 
+    #[test]
+    fn test_hot_reload_manager_diagnostics() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let yaml_content = r#"
+server:
+  host: "127.0.0.1"
+  port: 0
+  api_key: "test-key"
+  hots: "0.0.0.0"
+"#;
+        temp_file.write_all(yaml_content.as_bytes()).unwrap();
+
+        let manager = HotReloadManager::new(Config::default(), temp_file.path().to_path_buf());
+        match manager.reload() {
+            ReloadResult::RolledBack { diagnostics, .. } => {
+                assert!(diagnostics
+                    .iter()
+                    .any(|d| d.path == "server.port" && d.is_error()));
+                let unknown = diagnostics
+                    .iter()
+                    .find(|d| d.path == "server.hots")
+                    .unwrap();
+                assert!(!unknown.is_error());
+                assert_eq!(unknown.suggestion.as_deref(), Some("是否为 'host'？"));
+            }
+            _ => panic!("Expected RolledBack result"),
+        }
+    }
+
     #[test]
     fn test_config_change_kind_eq() {
         assert_eq!(ConfigChangeKind::Modified, ConfigChangeKind::Modified);
@@ -342,9 +342,16 @@ impl ImportService {
 
         // 合并重试配置
         merged.retry = imported.retry.clone();
+        merged.failover = imported.failover.clone();
+        merged.load_balance_strategy = imported.load_balance_strategy;
+        merged.session_affinity = imported.session_affinity.clone();
+        merged.context_fallback = imported.context_fallback.clone();
+        merged.chaos = imported.chaos.clone();
+        merged.selector_endpoints = imported.selector_endpoints.clone();
 
         // 合并日志配置
         merged.logging = imported.logging.clone();
+        merged.otlp = imported.otlp.clone();
 
         // 合并注入配置
         merged.injection = imported.injection.clone();
@@ -496,6 +503,11 @@ impl ImportService {
             config.providers.claude.api_key = None;
         }
 
+        // 移除专用 API Key 被脱敏的选择器端点（保留占位符会让任何人都能用占位符认证）
+        config
+            .selector_endpoints
+            .retain(|_, e| e.api_key.as_deref() != Some(REDACTED_PLACEHOLDER));
+
         // 清理服务器 API 密钥（如果是脱敏的，清空并提示手动设置）
         if config.server.api_key == REDACTED_PLACEHOLDER {
             config.server.api_key = String::new();
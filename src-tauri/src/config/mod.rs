@@ -10,6 +10,9 @@ mod hot_reload;
 mod import;
 pub mod observer;
 mod path_utils;
+mod profiles;
+mod schema;
+mod secret_fields;
 mod types;
 mod yaml;
 
@@ -20,14 +23,19 @@ pub use hot_reload::{
 };
 pub use import::{ImportOptions, ImportService, ValidationResult};
 pub use path_utils::{collapse_tilde, contains_tilde, expand_tilde};
+pub use profiles::switch_profile;
+pub use schema::{parse_with_diagnostics, ConfigDiagnostic, DiagnosticSeverity};
 pub use types::{
-    generate_secure_api_key, AmpConfig, AmpModelMapping, ApiKeyEntry, Config, CredentialEntry,
-    CredentialPoolConfig, CustomProviderConfig, EndpointProvidersConfig, ExperimentalFeatures,
-    GeminiApiKeyEntry, InjectionRuleConfig, InjectionSettings, LoggingConfig, ModelInfo,
-    ModelsConfig, NativeAgentConfig, ProviderConfig, ProviderModelsConfig, ProvidersConfig,
-    QuotaExceededConfig, RemoteManagementConfig, RetrySettings, RoutingConfig,
-    ScreenshotChatConfig, ServerConfig, TlsConfig, VertexApiKeyEntry, VertexModelAlias,
-    DEFAULT_API_KEY,
+    generate_secure_api_key, AliasMatchType, AmpConfig, AmpModelMapping, ApiKeyEntry,
+    AuditLogConfig, AuditRedactionRule, CompressionConfig, Config, ContextFallbackConfig,
+    CredentialEntry, CredentialPoolConfig, CustomProviderConfig, EndpointProvidersConfig,
+    ExperimentalFeatures, FailoverSettings, GeminiApiKeyEntry, HttpClientConfig,
+    InjectionRuleConfig, InjectionSettings, LoadBalanceStrategy, LoggingConfig, ModelAliasRule,
+    ModelInfo, ModelsConfig, NativeAgentConfig, ProviderConfig, ProviderModelsConfig,
+    ProviderTimeoutConfig, ProvidersConfig, QuotaExceededConfig, RemoteManagementConfig,
+    RequestLogStoreConfig, ResponseCacheConfig, RetrySettings, RoutingConfig, ScreenshotChatConfig,
+    SecretKeySource, SecretsConfig, SelectorEndpointConfig, ServerConfig, SessionAffinityConfig,
+    StorageBackend, StorageConfig, TlsConfig, VertexApiKeyEntry, VertexModelAlias, DEFAULT_API_KEY,
 };
 pub use yaml::{load_config, save_config, ConfigError, ConfigManager, YamlService};
 
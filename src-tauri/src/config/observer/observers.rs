@@ -65,13 +65,13 @@ impl ConfigObserver for RouterObserver {
         // 更新模型别名
         {
             let mut mapper = self.mapper.write().await;
-            mapper.clear();
-            for (alias, model) in &config.routing.model_aliases {
-                mapper.add_alias(alias, model);
+            for error in mapper.load_config(&config.routing) {
+                tracing::warn!("[RouterObserver] 跳过别名规则: {}", error);
             }
             tracing::debug!(
-                "[RouterObserver] 更新模型别名: {} 个",
-                config.routing.model_aliases.len()
+                "[RouterObserver] 更新模型别名: {} 个别名, {} 条规则",
+                config.routing.model_aliases.len(),
+                config.routing.alias_rules.len()
             );
         }
 
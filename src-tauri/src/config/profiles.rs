@@ -0,0 +1,398 @@
+//! 配置 Profile 与环境变量替换
+//!
+//! 配置文件可定义多个命名 Profile，`active_profile` 指定的 Profile 会深度合并到基础配置之上：
+//! 映射逐键合并，其余值（标量、列表）整体覆盖。
+//!
+//! 字符串值支持 `${VAR}` 和 `${VAR:-default}` 形式的环境变量替换，`$${` 表示字面量 `${`。
+//! `profiles` 下的内容保持原样，仅在合并时替换。
+//!
+//! 保存配置时只把相对于当前生效配置发生变化的字段写回原文件：
+//! 未修改的字段保留占位符，已被当前 Profile 覆盖的字段写回该 Profile。
+//! 启用凭证加密时，写回的敏感字段经 [`secret_fields`] 加密。
+
+use super::secret_fields;
+use super::types::Config;
+use super::yaml::ConfigError;
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+
+const PROFILES_KEY: &str = "profiles";
+const ACTIVE_PROFILE_KEY: &str = "active_profile";
+
+/// 合并当前 Profile 并替换环境变量，返回生效的配置值
+pub fn resolve(mut raw: Value) -> Value {
+    let Value::Mapping(root) = &mut raw else {
+        return raw;
+    };
+
+    let active = root
+        .get(ACTIVE_PROFILE_KEY)
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let overlay =
+        active.as_deref().and_then(
+            |name| match root.get(PROFILES_KEY).and_then(|p| p.get(name)) {
+                Some(overlay) => Some(overlay.clone()),
+                None => {
+                    tracing::warn!("[CONFIG] Profile '{}' 不存在，使用基础配置", name);
+                    None
+                }
+            },
+        );
+
+    for (key, value) in root.iter_mut() {
+        if key.as_str() != Some(PROFILES_KEY) {
+            substitute_env(value);
+        }
+    }
+
+    match overlay {
+        Some(Value::Mapping(mut overlay)) => {
+            overlay.remove(PROFILES_KEY);
+            overlay.remove(ACTIVE_PROFILE_KEY);
+            let mut overlay = Value::Mapping(overlay);
+            substitute_env(&mut overlay);
+            merge(&mut raw, &overlay);
+        }
+        Some(Value::Null) | None => {}
+        Some(_) => tracing::warn!(
+            "[CONFIG] Profile '{}' 不是映射，已忽略",
+            active.as_deref().unwrap_or_default()
+        ),
+    }
+
+    raw
+}
+
+/// 生成要写回配置文件的 YAML
+///
+/// `original` 为配置文件当前内容，无法解析时直接序列化整个配置
+pub fn render_for_save(original: Option<&str>, config: &Config) -> Result<String, ConfigError> {
+    let mut updated =
+        serde_yaml::to_value(config).map_err(|e| ConfigError::SerializeError(e.to_string()))?;
+    let Some(mut raw) = original
+        .and_then(|content| serde_yaml::from_str::<Value>(content).ok())
+        .filter(Value::is_mapping)
+    else {
+        secret_fields::seal_value(&[], &mut updated);
+        return serde_yaml::to_string(&updated)
+            .map_err(|e| ConfigError::SerializeError(e.to_string()));
+    };
+
+    // 生效值来自保存前的 Profile，变更按该 Profile 路由
+    let active = raw
+        .get(ACTIVE_PROFILE_KEY)
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let mut effective = resolve(raw.clone());
+    // 按明文比较敏感字段，避免每次保存都因随机 nonce 重写密文
+    if let Err(e) = secret_fields::open_values(&mut effective) {
+        tracing::warn!("[SECRETS] 配置文件中的敏感字段解密失败: {}", e);
+    }
+    let resealed = secret_fields::reseal_file_values(&mut raw);
+    if resealed > 0 {
+        tracing::info!(
+            "[SECRETS] 已按当前加密状态重写配置文件中的 {} 个敏感字段",
+            resealed
+        );
+    }
+
+    // Profile 定义和当前 Profile 只通过配置文件或 switch_profile 修改，内存中的副本可能已过期
+    for value in [&mut effective, &mut updated] {
+        if let Value::Mapping(map) = value {
+            map.remove(PROFILES_KEY);
+            map.remove(ACTIVE_PROFILE_KEY);
+        }
+    }
+
+    let mut changes = Vec::new();
+    diff(&mut Vec::new(), &effective, &updated, &mut changes);
+    for (path, value) in changes {
+        let overlay_path: Option<Vec<Value>> = active.as_deref().map(|name| {
+            [Value::from(PROFILES_KEY), Value::from(name)]
+                .into_iter()
+                .chain(path.iter().cloned())
+                .collect()
+        });
+        match value {
+            Some(mut value) => {
+                secret_fields::seal_value(&path, &mut value);
+                let target = match overlay_path {
+                    Some(overlay_path) if get_at(&raw, &overlay_path).is_some() => overlay_path,
+                    _ => path,
+                };
+                set_at(&mut raw, &target, value);
+            }
+            None => {
+                remove_at(&mut raw, &path);
+                if let Some(overlay_path) = overlay_path {
+                    remove_at(&mut raw, &overlay_path);
+                }
+            }
+        }
+    }
+
+    serde_yaml::to_string(&raw).map_err(|e| ConfigError::SerializeError(e.to_string()))
+}
+
+/// 切换配置文件中的当前 Profile（`None` 表示仅使用基础配置），返回切换后生效的配置
+pub fn switch_profile(path: &Path, profile: Option<&str>) -> Result<Config, ConfigError> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| ConfigError::ReadError(e.to_string()))?;
+    let mut raw: Value =
+        serde_yaml::from_str(&content).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+    let Value::Mapping(root) = &mut raw else {
+        return Err(ConfigError::ParseError(
+            "配置文件根节点不是映射".to_string(),
+        ));
+    };
+
+    match profile {
+        Some(name) => {
+            if root.get(PROFILES_KEY).and_then(|p| p.get(name)).is_none() {
+                return Err(ConfigError::ValidationError(format!(
+                    "Profile '{}' 不存在",
+                    name
+                )));
+            }
+            root.insert(Value::from(ACTIVE_PROFILE_KEY), Value::from(name));
+        }
+        None => {
+            root.remove(ACTIVE_PROFILE_KEY);
+        }
+    }
+
+    let content =
+        serde_yaml::to_string(&raw).map_err(|e| ConfigError::SerializeError(e.to_string()))?;
+    let config: Config =
+        serde_yaml::from_value(resolve(raw)).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+
+    let backup_path = path.with_extension("yaml.backup");
+    let _ = std::fs::copy(path, backup_path);
+    std::fs::write(path, content).map_err(|e| ConfigError::WriteError(e.to_string()))?;
+    Ok(config)
+}
+
+/// 递归替换字符串中的环境变量
+fn substitute_env(value: &mut Value) {
+    match value {
+        Value::String(s) if s.contains('$') => *s = substitute_str(s),
+        Value::Sequence(items) => items.iter_mut().for_each(substitute_env),
+        Value::Mapping(map) => map.iter_mut().for_each(|(_, v)| substitute_env(v)),
+        Value::Tagged(tagged) => substitute_env(&mut tagged.value),
+        _ => {}
+    }
+}
+
+fn substitute_str(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        if let Some(escaped) = after.strip_prefix("${") {
+            output.push_str("${");
+            rest = escaped;
+        } else if let Some(body) = after.strip_prefix('{') {
+            let Some(end) = body.find('}') else {
+                output.push_str(&rest[pos..]);
+                rest = "";
+                break;
+            };
+            let (name, default) = match body[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&body[..end], None),
+            };
+            // `:-` 在变量未设置或为空时使用默认值
+            let value = std::env::var(name)
+                .ok()
+                .filter(|v| !v.is_empty() || default.is_none());
+            match (value, default) {
+                (Some(value), _) => output.push_str(&value),
+                (None, Some(default)) => output.push_str(default),
+                (None, None) => {
+                    tracing::warn!("[CONFIG] 环境变量 {} 未设置，保留占位符", name);
+                    output.push_str(&rest[pos..pos + end + 3]);
+                }
+            }
+            rest = &body[end + 1..];
+        } else {
+            output.push('$');
+            rest = after;
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// 深度合并：映射逐键合并，其余值整体覆盖
+fn merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// 收集叶子级变更，`None` 表示字段被删除
+fn diff(
+    path: &mut Vec<Value>,
+    old: &Value,
+    new: &Value,
+    changes: &mut Vec<(Vec<Value>, Option<Value>)>,
+) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Mapping(old), Value::Mapping(new)) => {
+            for (key, old_value) in old {
+                path.push(key.clone());
+                match new.get(key) {
+                    Some(new_value) => diff(path, old_value, new_value, changes),
+                    None => changes.push((path.clone(), None)),
+                }
+                path.pop();
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    let mut key_path = path.clone();
+                    key_path.push(key.clone());
+                    changes.push((key_path, Some(new_value.clone())));
+                }
+            }
+        }
+        _ => changes.push((path.clone(), Some(new.clone()))),
+    }
+}
+
+fn get_at<'a>(root: &'a Value, path: &[Value]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(root, |current, key| current.as_mapping()?.get(key))
+}
+
+fn set_at(root: &mut Value, path: &[Value], value: Value) {
+    let mut current = root;
+    for key in path {
+        if !current.is_mapping() {
+            *current = Value::Mapping(Mapping::new());
+        }
+        let Value::Mapping(map) = current else {
+            unreachable!();
+        };
+        current = map.entry(key.clone()).or_insert(Value::Null);
+    }
+    *current = value;
+}
+
+fn remove_at(root: &mut Value, path: &[Value]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let parent = parents
+        .iter()
+        .try_fold(root, |current, key| current.as_mapping_mut()?.get_mut(key));
+    if let Some(Value::Mapping(map)) = parent {
+        map.remove(last);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::config::ConfigManager;
+
+    const PROFILE_YAML: &str = r#"
+server:
+  host: "127.0.0.1"
+  port: 8999
+  api_key: "${PROXYCAST_TEST_PROFILE_KEY:-base-key}"
+routing:
+  default_provider: "kiro"
+active_profile: "work"
+profiles:
+  work:
+    server:
+      port: 9100
+    routing:
+      default_provider: "${PROXYCAST_TEST_PROFILE_PROVIDER:-claude}"
+  personal:
+    routing:
+      default_provider: "gemini"
+"#;
+
+    #[test]
+    fn test_resolve_active_profile() {
+        let config = ConfigManager::parse_yaml(PROFILE_YAML).unwrap();
+        assert_eq!(config.active_profile.as_deref(), Some("work"));
+        assert_eq!(config.server.host, "127.0.0.1");
+        assert_eq!(config.server.port, 9100);
+        assert_eq!(config.server.api_key, "base-key");
+        assert_eq!(config.routing.default_provider, "claude");
+        assert_eq!(config.profiles.len(), 2);
+
+        let base = ConfigManager::parse_yaml(&PROFILE_YAML.replace("\"work\"", "null")).unwrap();
+        assert_eq!(base.server.port, 8999);
+        assert_eq!(base.routing.default_provider, "kiro");
+    }
+
+    #[test]
+    fn test_substitute_str() {
+        std::env::set_var("PROXYCAST_TEST_SUBSTITUTE", "secret");
+        assert_eq!(
+            substitute_str("Bearer ${PROXYCAST_TEST_SUBSTITUTE}"),
+            "Bearer secret"
+        );
+        assert_eq!(
+            substitute_str("${PROXYCAST_TEST_UNSET:-fallback}"),
+            "fallback"
+        );
+        assert_eq!(
+            substitute_str("${PROXYCAST_TEST_UNSET}"),
+            "${PROXYCAST_TEST_UNSET}"
+        );
+        assert_eq!(substitute_str("$${literal} $.tools"), "${literal} $.tools");
+    }
+
+    #[test]
+    fn test_render_for_save_keeps_placeholders_and_profile() {
+        let mut config = ConfigManager::parse_yaml(PROFILE_YAML).unwrap();
+        config.server.port = 9200;
+        config.server.host = "0.0.0.0".to_string();
+
+        let saved = render_for_save(Some(PROFILE_YAML), &config).unwrap();
+        let raw: Value = serde_yaml::from_str(&saved).unwrap();
+
+        // 未修改的字段保留占位符
+        assert_eq!(
+            raw["server"]["api_key"].as_str(),
+            Some("${PROXYCAST_TEST_PROFILE_KEY:-base-key}")
+        );
+        // Profile 覆盖的字段写回 Profile，其余写回基础配置
+        assert_eq!(raw["server"]["port"].as_u64(), Some(8999));
+        assert_eq!(
+            raw["profiles"]["work"]["server"]["port"].as_u64(),
+            Some(9200)
+        );
+        assert_eq!(raw["server"]["host"].as_str(), Some("0.0.0.0"));
+
+        let reloaded = ConfigManager::parse_yaml(&saved).unwrap();
+        assert_eq!(reloaded.server, config.server);
+        assert_eq!(reloaded.routing, config.routing);
+
+        // 内存中过期的 Profile 定义不会覆盖文件
+        let saved_again = render_for_save(Some(&saved), &config).unwrap();
+        assert_eq!(saved_again, saved);
+    }
+}
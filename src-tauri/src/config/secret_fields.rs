@@ -0,0 +1,180 @@
+//! 配置文件中的敏感字段加密
+//!
+//! 启用凭证加密（`secrets.enabled`）时，主 API Key、管理密钥和 Provider API Key
+//! 经 `secret_store` 以 `enc:v1:` 密文写入配置文件，解析配置时透明解密。
+//! 使用 `${VAR}` 占位符的字段不在文件中保存密钥，保持原样。
+
+use super::types::{Config, SecretsConfig};
+use super::yaml::ConfigError;
+use crate::services::secret_store::{self, SecretError};
+use serde_yaml::Value;
+
+/// 敏感字段路径，`*` 匹配列表元素或映射的任意键
+const SECRET_PATHS: &[&[&str]] = &[
+    &["server", "api_key"],
+    &["remote_management", "secret_key"],
+    &["providers", "openai", "api_key"],
+    &["providers", "claude", "api_key"],
+    &["credential_pool", "openai", "*", "api_key"],
+    &["credential_pool", "claude", "*", "api_key"],
+    &["credential_pool", "gemini_api_keys", "*", "api_key"],
+    &["credential_pool", "vertex_api_keys", "*", "api_key"],
+    &["selector_endpoints", "*", "api_key"],
+];
+
+const PROFILES_KEY: &str = "profiles";
+
+fn visit(value: &mut Value, pattern: &[&str], f: &mut dyn FnMut(&mut String)) {
+    let Some((segment, rest)) = pattern.split_first() else {
+        if let Value::String(s) = value {
+            f(s);
+        }
+        return;
+    };
+    match (value, *segment) {
+        (Value::Sequence(items), "*") => items.iter_mut().for_each(|v| visit(v, rest, f)),
+        (Value::Mapping(map), "*") => map.iter_mut().for_each(|(_, v)| visit(v, rest, f)),
+        (Value::Mapping(map), key) => {
+            if let Some(v) = map.get_mut(key) {
+                visit(v, rest, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 对 `value`（位于 `path`）中的所有敏感字段执行 `f`
+fn for_each_secret(path: &[Value], value: &mut Value, f: &mut dyn FnMut(&mut String)) {
+    for pattern in SECRET_PATHS {
+        let matches = pattern.len() >= path.len()
+            && path
+                .iter()
+                .zip(pattern.iter())
+                .all(|(key, segment)| *segment == "*" || key.as_str() == Some(*segment));
+        if matches {
+            visit(value, &pattern[path.len()..], f);
+        }
+    }
+}
+
+/// 解密配置值中的敏感字段
+///
+/// 存在密文但尚未加载主密钥时，按配置中的 `secrets` 加载
+pub fn open_values(root: &mut Value) -> Result<(), SecretError> {
+    let mut sealed = false;
+    for_each_secret(&[], root, &mut |s| sealed |= secret_store::is_sealed(s));
+    if !sealed {
+        return Ok(());
+    }
+    if !secret_store::is_loaded() {
+        let secrets: SecretsConfig = root
+            .get("secrets")
+            .cloned()
+            .and_then(|v| serde_yaml::from_value(v).ok())
+            .unwrap_or_default();
+        secret_store::init(&secrets)?;
+    }
+
+    let mut result = Ok(());
+    for_each_secret(&[], root, &mut |s| match secret_store::open(s) {
+        Ok(plaintext) => *s = plaintext,
+        Err(e) => result = Err(e),
+    });
+    result
+}
+
+/// 加密写回配置文件的变更值（`path` 为该值在配置中的路径）
+pub fn seal_value(path: &[Value], value: &mut Value) {
+    for_each_secret(path, value, &mut |s| {
+        if !s.contains("${") && !secret_store::is_sealed(s) {
+            *s = secret_store::seal(s);
+        }
+    });
+}
+
+/// 按当前加密状态重写配置文件（含各 Profile）中的字面量敏感字段，返回重写数量
+///
+/// 启用加密时加密明文，禁用时解密密文；无法解密的值保持原样
+pub fn reseal_file_values(raw: &mut Value) -> usize {
+    let encrypt = secret_store::is_enabled();
+    let mut rewritten = 0;
+    let mut reseal = |s: &mut String| {
+        if s.contains("${") || secret_store::is_sealed(s) == encrypt {
+            return;
+        }
+        match secret_store::open(s) {
+            Ok(plaintext) => {
+                *s = secret_store::seal(&plaintext);
+                rewritten += 1;
+            }
+            Err(e) => tracing::warn!("[SECRETS] 配置文件中的敏感字段解密失败: {}", e),
+        }
+    };
+
+    for_each_secret(&[], raw, &mut reseal);
+    if let Some(Value::Mapping(profiles)) = raw.get_mut(PROFILES_KEY) {
+        for (_, profile) in profiles.iter_mut() {
+            for_each_secret(&[], profile, &mut reseal);
+        }
+    }
+    rewritten
+}
+
+/// 返回敏感字段已加密的配置副本（用于不经过 Profile 合并直接序列化的保存路径）
+pub fn sealed_copy(config: &Config) -> Result<Config, ConfigError> {
+    let mut value =
+        serde_yaml::to_value(config).map_err(|e| ConfigError::SerializeError(e.to_string()))?;
+    seal_value(&[], &mut value);
+    serde_yaml::from_value(value).map_err(|e| ConfigError::SerializeError(e.to_string()))
+}
+
+/// 解密已反序列化配置中的敏感字段
+pub fn open_config(config: &mut Config) -> Result<(), ConfigError> {
+    let mut value =
+        serde_yaml::to_value(&*config).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+    open_values(&mut value).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+    *config = serde_yaml::from_value(value).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_paths_match_nested_values() {
+        let mut value: Value = serde_yaml::from_str(
+            "server:\n  api_key: pc_main\n  host: 0.0.0.0\n\
+             credential_pool:\n  openai:\n    - id: a\n      api_key: sk-a\n\
+             selector_endpoints:\n  team:\n    api_key: sk-team\n",
+        )
+        .unwrap();
+        let mut seen = Vec::new();
+        for_each_secret(&[], &mut value, &mut |s| seen.push(s.clone()));
+        seen.sort();
+        assert_eq!(seen, vec!["pc_main", "sk-a", "sk-team"]);
+
+        // 变更路径位于敏感字段之内或之上时都能匹配
+        let mut pool: Value = serde_yaml::from_str("- id: b\n  api_key: sk-b\n").unwrap();
+        let mut seen = Vec::new();
+        let path = [Value::from("credential_pool"), Value::from("claude")];
+        for_each_secret(&path, &mut pool, &mut |s| seen.push(s.clone()));
+        assert_eq!(seen, vec!["sk-b"]);
+
+        let mut host = Value::from("0.0.0.0");
+        let path = [Value::from("server"), Value::from("host")];
+        let mut called = false;
+        for_each_secret(&path, &mut host, &mut |_| called = true);
+        assert!(!called);
+    }
+
+    #[test]
+    fn test_plaintext_values_unchanged_when_disabled() {
+        let mut value: Value =
+            serde_yaml::from_str("server:\n  api_key: \"${PROXY_KEY}\"\n").unwrap();
+        seal_value(&[], &mut value);
+        assert_eq!(value["server"]["api_key"], Value::from("${PROXY_KEY}"));
+        open_values(&mut value).unwrap();
+        assert_eq!(value["server"]["api_key"], Value::from("${PROXY_KEY}"));
+    }
+}
@@ -3,9 +3,10 @@
 //! 使用 proptest 进行属性测试
 
 use crate::config::{
-    collapse_tilde, contains_tilde, expand_tilde, Config, ConfigManager, CustomProviderConfig,
-    HotReloadManager, InjectionSettings, LoggingConfig, ProviderConfig, ProvidersConfig,
-    ReloadResult, RetrySettings, RoutingConfig, ServerConfig, YamlService,
+    collapse_tilde, contains_tilde, expand_tilde, AuditLogConfig, Config, ConfigManager,
+    CustomProviderConfig, FailoverSettings, HotReloadManager, InjectionSettings, LoggingConfig,
+    ProviderConfig, ProvidersConfig, ReloadResult, RequestLogStoreConfig, RetrySettings,
+    RoutingConfig, ServerConfig, SessionAffinityConfig, YamlService,
 };
 use proptest::prelude::*;
 use std::io::Write;
@@ -38,6 +39,9 @@ fn arb_server_config() -> impl Strategy<Value = ServerConfig> {
         port,
         api_key,
         tls: crate::config::TlsConfig::default(),
+        shutdown_timeout_secs: 30,
+        allowed_ips: Vec::new(),
+        compression: crate::config::CompressionConfig::default(),
     })
 }
 
@@ -116,6 +120,7 @@ fn arb_routing_config() -> impl Strategy<Value = RoutingConfig> {
         .prop_map(|(default_provider, model_aliases)| RoutingConfig {
             default_provider,
             model_aliases,
+            alias_rules: Vec::new(),
         })
 }
 
@@ -156,6 +161,8 @@ fn arb_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                audit: AuditLogConfig::default(),
+                request_logs: RequestLogStoreConfig::default(),
             },
         )
 }
@@ -176,19 +183,39 @@ fn arb_config() -> impl Strategy<Value = Config> {
             routing,
             retry,
             logging,
+            failover: FailoverSettings::default(),
+            concurrency: proxycast_infra::ConcurrencyConfig::default(),
+            load_balance_strategy: Default::default(),
+            session_affinity: SessionAffinityConfig::default(),
+            context_fallback: Default::default(),
+            chaos: Default::default(),
+            otlp: proxycast_infra::OtlpConfig::default(),
+            pricing: proxycast_infra::PricingConfig::default(),
+            reports: proxycast_infra::ReportConfig::default(),
+            alerts: proxycast_infra::AlertConfig::default(),
             injection: InjectionSettings::default(),
+            transforms: proxycast_infra::TransformConfig::default(),
+            reasoning: Default::default(),
+            response_cache: crate::config::ResponseCacheConfig::default(),
+            secrets: crate::config::SecretsConfig::default(),
+            storage: crate::config::StorageConfig::default(),
             auth_dir: "~/.proxycast/auth".to_string(),
             credential_pool: crate::config::CredentialPoolConfig::default(),
             remote_management: crate::config::RemoteManagementConfig::default(),
             quota_exceeded: crate::config::QuotaExceededConfig::default(),
             proxy_url: None,
+            provider_proxies: std::collections::HashMap::new(),
+            http_client: crate::config::HttpClientConfig::default(),
             ampcode: crate::config::AmpConfig::default(),
             endpoint_providers: crate::config::EndpointProvidersConfig::default(),
+            selector_endpoints: Default::default(),
             minimize_to_tray: true,
             models: crate::config::ModelsConfig::default(),
             agent: crate::config::NativeAgentConfig::default(),
             language: "zh".to_string(),
             experimental: crate::config::ExperimentalFeatures::default(),
+            active_profile: None,
+            profiles: Default::default(),
         })
 }
 
@@ -354,6 +381,9 @@ fn arb_valid_server_config() -> impl Strategy<Value = ServerConfig> {
         port,
         api_key,
         tls: crate::config::TlsConfig::default(),
+        shutdown_timeout_secs: 30,
+        allowed_ips: Vec::new(),
+        compression: crate::config::CompressionConfig::default(),
     })
 }
 
@@ -394,6 +424,8 @@ fn arb_valid_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                audit: AuditLogConfig::default(),
+                request_logs: RequestLogStoreConfig::default(),
             },
         )
 }
@@ -414,19 +446,39 @@ fn arb_valid_config() -> impl Strategy<Value = Config> {
             routing,
             retry,
             logging,
+            failover: FailoverSettings::default(),
+            concurrency: proxycast_infra::ConcurrencyConfig::default(),
+            load_balance_strategy: Default::default(),
+            session_affinity: SessionAffinityConfig::default(),
+            context_fallback: Default::default(),
+            chaos: Default::default(),
+            otlp: proxycast_infra::OtlpConfig::default(),
+            pricing: proxycast_infra::PricingConfig::default(),
+            reports: proxycast_infra::ReportConfig::default(),
+            alerts: proxycast_infra::AlertConfig::default(),
             injection: InjectionSettings::default(),
+            transforms: proxycast_infra::TransformConfig::default(),
+            reasoning: Default::default(),
+            response_cache: crate::config::ResponseCacheConfig::default(),
+            secrets: crate::config::SecretsConfig::default(),
+            storage: crate::config::StorageConfig::default(),
             auth_dir: "~/.proxycast/auth".to_string(),
             credential_pool: crate::config::CredentialPoolConfig::default(),
             remote_management: crate::config::RemoteManagementConfig::default(),
             quota_exceeded: crate::config::QuotaExceededConfig::default(),
             proxy_url: None,
+            provider_proxies: std::collections::HashMap::new(),
+            http_client: crate::config::HttpClientConfig::default(),
             ampcode: crate::config::AmpConfig::default(),
             endpoint_providers: crate::config::EndpointProvidersConfig::default(),
+            selector_endpoints: Default::default(),
             minimize_to_tray: true,
             models: crate::config::ModelsConfig::default(),
             agent: crate::config::NativeAgentConfig::default(),
             language: "zh".to_string(),
             experimental: crate::config::ExperimentalFeatures::default(),
+            active_profile: None,
+            profiles: Default::default(),
         })
 }
 
@@ -463,19 +515,39 @@ fn arb_invalid_config() -> impl Strategy<Value = Config> {
                     routing,
                     retry,
                     logging,
+                    failover: FailoverSettings::default(),
+                    concurrency: proxycast_infra::ConcurrencyConfig::default(),
+                    load_balance_strategy: Default::default(),
+                    session_affinity: SessionAffinityConfig::default(),
+                    context_fallback: Default::default(),
+                    chaos: Default::default(),
+                    otlp: proxycast_infra::OtlpConfig::default(),
+                    pricing: proxycast_infra::PricingConfig::default(),
+                    reports: proxycast_infra::ReportConfig::default(),
+                    alerts: proxycast_infra::AlertConfig::default(),
                     injection: InjectionSettings::default(),
+                    transforms: proxycast_infra::TransformConfig::default(),
+                    reasoning: Default::default(),
+                    response_cache: crate::config::ResponseCacheConfig::default(),
+                    secrets: crate::config::SecretsConfig::default(),
+                    storage: crate::config::StorageConfig::default(),
                     auth_dir: "~/.proxycast/auth".to_string(),
                     credential_pool: crate::config::CredentialPoolConfig::default(),
                     remote_management: crate::config::RemoteManagementConfig::default(),
                     quota_exceeded: crate::config::QuotaExceededConfig::default(),
                     proxy_url: None,
+                    provider_proxies: std::collections::HashMap::new(),
+                    http_client: crate::config::HttpClientConfig::default(),
                     ampcode: crate::config::AmpConfig::default(),
                     endpoint_providers: crate::config::EndpointProvidersConfig::default(),
+                    selector_endpoints: Default::default(),
                     minimize_to_tray: true,
                     models: crate::config::ModelsConfig::default(),
                     agent: crate::config::NativeAgentConfig::default(),
                     language: "zh".to_string(),
                     experimental: crate::config::ExperimentalFeatures::default(),
+                    active_profile: None,
+                    profiles: Default::default(),
                 };
                 // 根据类型使配置无效
                 match invalid_type {
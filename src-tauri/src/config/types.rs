@@ -3,9 +3,9 @@
 //! 定义 ProxyCast 的配置结构，支持 YAML 和 JSON 序列化/反序列化
 //! 保持与旧版 JSON 配置的向后兼容性
 
-use crate::injection::{InjectionMode, InjectionRule};
+use crate::injection::{InjectionMode, InjectionRule, SystemPromptInjection};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 // ============ 凭证池配置类型 ============
 
@@ -253,12 +253,57 @@ pub struct Config {
     /// 重试配置
     #[serde(default)]
     pub retry: RetrySettings,
+    /// 凭证故障转移配置
+    #[serde(default)]
+    pub failover: FailoverSettings,
+    /// 并发限制配置（全局 / Provider / 凭证）
+    #[serde(default)]
+    pub concurrency: proxycast_infra::ConcurrencyConfig,
+    /// 凭证池负载均衡策略
+    #[serde(default)]
+    pub load_balance_strategy: LoadBalanceStrategy,
+    /// 会话粘性路由配置
+    #[serde(default)]
+    pub session_affinity: SessionAffinityConfig,
+    /// 长上下文降级配置
+    #[serde(default)]
+    pub context_fallback: ContextFallbackConfig,
+    /// 故障注入配置（仅用于测试容错逻辑）
+    #[serde(default)]
+    pub chaos: proxycast_infra::ChaosConfig,
     /// 日志配置
     #[serde(default)]
     pub logging: LoggingConfig,
+    /// OpenTelemetry 链路追踪导出配置
+    #[serde(default)]
+    pub otlp: proxycast_infra::OtlpConfig,
+    /// 费用估算单价表
+    #[serde(default)]
+    pub pricing: proxycast_infra::PricingConfig,
+    /// 用量报告配置
+    #[serde(default)]
+    pub reports: proxycast_infra::ReportConfig,
+    /// 异常告警配置
+    #[serde(default)]
+    pub alerts: proxycast_infra::AlertConfig,
     /// 参数注入配置
     #[serde(default)]
     pub injection: InjectionSettings,
+    /// 请求转换管道配置
+    #[serde(default)]
+    pub transforms: proxycast_infra::TransformConfig,
+    /// 跨格式转换时推理内容（thinking / reasoning_content）的处理方式
+    #[serde(default)]
+    pub reasoning: crate::converter::reasoning::ReasoningMode,
+    /// 响应缓存配置
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+    /// 凭证静态加密配置
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+    /// 共享存储配置（多实例共享凭证池、请求日志和用量统计）
+    #[serde(default)]
+    pub storage: StorageConfig,
     /// 认证目录路径（存储 OAuth Token 文件，支持 ~ 展开）
     #[serde(default = "default_auth_dir")]
     pub auth_dir: String,
@@ -274,6 +319,15 @@ pub struct Config {
     /// 全局代理 URL
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub proxy_url: Option<String>,
+    /// Per-Provider 出站代理（键为 Provider 类型，如 `claude`、`openai`）
+    ///
+    /// 优先级：凭证 `proxy_url` > Per-Provider 代理 > 全局 `proxy_url`，
+    /// 值为 `direct` 时该 Provider 不使用代理
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub provider_proxies: HashMap<String, String>,
+    /// 上游 HTTP 客户端（连接池、Keep-Alive、HTTP/2）配置
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
     /// Amp CLI 配置
     #[serde(default)]
     pub ampcode: AmpConfig,
@@ -281,6 +335,9 @@ pub struct Config {
     /// 允许为不同的客户端端点（CC/Codex）配置不同的 Provider
     #[serde(default)]
     pub endpoint_providers: EndpointProvidersConfig,
+    /// 选择器端点配置（键为 `/{selector}/v1/*` 中的选择器）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub selector_endpoints: HashMap<String, SelectorEndpointConfig>,
     /// 关闭时最小化到托盘（而不是退出应用）
     #[serde(default = "default_minimize_to_tray")]
     pub minimize_to_tray: bool,
@@ -296,6 +353,12 @@ pub struct Config {
     /// 实验室功能配置
     #[serde(default)]
     pub experimental: ExperimentalFeatures,
+    /// 当前生效的配置 Profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    /// 命名配置 Profile（原始 YAML，生效时深度合并到基础配置之上）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, serde_yaml::Value>,
 }
 
 // ============ Native Agent 配置类型 ============
@@ -501,11 +564,21 @@ pub struct ServerConfig {
     /// TLS 配置
     #[serde(default)]
     pub tls: TlsConfig,
+    /// 停机时等待进行中请求完成的超时时间（秒）
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// 允许访问的客户端 IP / CIDR 列表（为空时不限制，回环地址始终允许）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_ips: Vec<String>,
+    /// HTTP 压缩配置
+    #[serde(default)]
+    pub compression: CompressionConfig,
 }
 
 /// TLS 配置
 ///
-/// 用于启用 HTTPS 支持
+/// 用于启用 HTTPS 支持。未配置证书路径时自动生成自签名证书，
+/// 仅允许在本地监听地址上使用。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct TlsConfig {
     /// 是否启用 TLS
@@ -519,6 +592,42 @@ pub struct TlsConfig {
     pub key_path: Option<String>,
 }
 
+/// HTTP 压缩配置
+///
+/// 响应按客户端的 `Accept-Encoding` 使用 gzip / brotli 压缩（SSE 流式响应、图片和音频除外），
+/// 带 `Content-Encoding: gzip / br / deflate` 的请求体自动解压后再计入请求体大小限制。
+/// 启用时 WebSocket 连接可协商 `proxycast.deflate` 子协议压缩消息。修改后需重启服务生效。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompressionConfig {
+    /// 是否压缩响应（及允许 WebSocket 协商消息压缩）
+    #[serde(default = "default_compression_enabled")]
+    pub enable: bool,
+    /// 响应体或 WebSocket 消息达到该字节数时才压缩
+    #[serde(default = "default_compression_min_size")]
+    pub min_size: u16,
+    /// 是否解压客户端压缩的请求体
+    #[serde(default = "default_compression_enabled")]
+    pub decompress_requests: bool,
+}
+
+fn default_compression_enabled() -> bool {
+    true
+}
+
+fn default_compression_min_size() -> u16 {
+    1024
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enable: default_compression_enabled(),
+            min_size: default_compression_min_size(),
+            decompress_requests: default_compression_enabled(),
+        }
+    }
+}
+
 /// 远程管理配置
 ///
 /// 用于配置远程管理 API 的访问控制
@@ -606,6 +715,10 @@ fn default_port() -> u16 {
     8999
 }
 
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
 pub const DEFAULT_API_KEY: &str = "proxy_cast";
 
 fn default_api_key() -> String {
@@ -637,6 +750,9 @@ impl Default for ServerConfig {
             port: default_port(),
             api_key: default_api_key(),
             tls: TlsConfig::default(),
+            shutdown_timeout_secs: default_shutdown_timeout_secs(),
+            allowed_ips: Vec::new(),
+            compression: CompressionConfig::default(),
         }
     }
 }
@@ -736,6 +852,9 @@ pub struct RoutingConfig {
     /// 模型别名映射
     #[serde(default)]
     pub model_aliases: HashMap<String, String>,
+    /// 通配符 / 正则别名规则（精确别名未命中时按优先级匹配）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alias_rules: Vec<ModelAliasRule>,
 }
 
 fn default_provider() -> String {
@@ -747,10 +866,37 @@ impl Default for RoutingConfig {
         Self {
             default_provider: default_provider(),
             model_aliases: HashMap::new(),
+            alias_rules: Vec::new(),
         }
     }
 }
 
+/// 别名规则的匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AliasMatchType {
+    /// 通配符（`*` 匹配任意字符，`?` 匹配单个字符）
+    #[default]
+    Glob,
+    /// 正则表达式（整串匹配，目标模型可引用捕获组，如 `$1`）
+    Regex,
+}
+
+/// 模型别名规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelAliasRule {
+    /// 匹配模式，如 `gpt-4*`
+    pub pattern: String,
+    /// 目标模型
+    pub target: String,
+    /// 匹配方式
+    #[serde(default)]
+    pub match_type: AliasMatchType,
+    /// 优先级，数值越大越先匹配；相同优先级按配置顺序
+    #[serde(default)]
+    pub priority: i32,
+}
+
 /// 重试配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RetrySettings {
@@ -795,6 +941,158 @@ impl Default for RetrySettings {
     }
 }
 
+/// 凭证池负载均衡策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalanceStrategy {
+    /// 综合评分（健康状态、使用频率、错误率、冷却时间）
+    #[default]
+    Smart,
+    /// 轮询
+    RoundRobin,
+    /// 按凭证权重加权随机
+    Weighted,
+    /// 进行中请求数最少优先
+    LeastConnections,
+    /// 最久未出错优先
+    LeastRecentError,
+    /// 最近响应延迟 P95 最低优先
+    LatencyP95,
+}
+
+/// 凭证故障转移配置
+///
+/// 上游返回 5xx/401 时，自动换用同一 Provider 的下一个健康凭证重试，
+/// 同类凭证用尽后依次尝试 `fallback_providers` 中的 Provider。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FailoverSettings {
+    /// 是否启用凭证故障转移
+    #[serde(default = "default_failover_enabled")]
+    pub enabled: bool,
+    /// 单个请求最多尝试的凭证数（包含首次请求）
+    #[serde(default = "default_failover_max_attempts")]
+    pub max_attempts: u32,
+    /// 备用 Provider 列表（按顺序尝试）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_providers: Vec<String>,
+    /// 请求对冲：首个凭证响应过慢时并发请求另一个凭证，采用先成功的响应
+    #[serde(default)]
+    pub hedging: proxycast_infra::HedgeConfig,
+}
+
+fn default_failover_enabled() -> bool {
+    true
+}
+
+fn default_failover_max_attempts() -> u32 {
+    3
+}
+
+impl Default for FailoverSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_failover_enabled(),
+            max_attempts: default_failover_max_attempts(),
+            fallback_providers: Vec::new(),
+            hedging: proxycast_infra::HedgeConfig::default(),
+        }
+    }
+}
+
+/// 会话粘性路由配置
+///
+/// 将同一会话的请求固定到同一凭证，多轮对话持续命中同一上游账号的提示词缓存；
+/// 绑定的凭证不可用时按负载均衡策略重新选择并改绑。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionAffinityConfig {
+    /// 是否启用会话粘性路由
+    #[serde(default)]
+    pub enabled: bool,
+    /// 客户端传入会话标识的请求头
+    #[serde(default = "default_session_affinity_header")]
+    pub header: String,
+    /// 未传入会话标识时，是否以首条用户消息的哈希作为会话标识
+    #[serde(default = "default_session_affinity_hash_first_message")]
+    pub hash_first_message: bool,
+    /// 会话绑定的空闲过期时间（秒）
+    #[serde(default = "default_session_affinity_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最多保留的会话绑定数（超出时淘汰最久未使用的）
+    #[serde(default = "default_session_affinity_max_sessions")]
+    pub max_sessions: usize,
+}
+
+fn default_session_affinity_header() -> String {
+    "x-session-id".to_string()
+}
+
+fn default_session_affinity_hash_first_message() -> bool {
+    true
+}
+
+fn default_session_affinity_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_session_affinity_max_sessions() -> usize {
+    10000
+}
+
+impl Default for SessionAffinityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            header: default_session_affinity_header(),
+            hash_first_message: default_session_affinity_hash_first_message(),
+            ttl_secs: default_session_affinity_ttl_secs(),
+            max_sessions: default_session_affinity_max_sessions(),
+        }
+    }
+}
+
+/// 长上下文降级配置
+///
+/// 请求的预估 Token 数（输入 + `max_tokens`）超过目标模型的上下文窗口时，
+/// 改用配置的长上下文模型，并在响应头 `x-proxycast-model-fallback` 中注明。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ContextFallbackConfig {
+    /// 是否启用长上下文降级
+    #[serde(default)]
+    pub enabled: bool,
+    /// 长上下文模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 长上下文模型所在的 Provider，为空时沿用原 Provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// 模型上下文窗口（Token），优先于模型注册表中的数据
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub context_windows: HashMap<String, u32>,
+}
+
+/// 选择器端点配置
+///
+/// 为 `/{selector}/v1/*` 路由配置独立的 API Key 和隔离策略，
+/// 使每个团队 / 工具拥有一个只能使用指定凭证、单独限流和统计的虚拟端点。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SelectorEndpointConfig {
+    /// 端点专用 API Key，配置后只接受该 Key（主 Key 和客户端 Key 均不可用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// 实际解析的选择器（凭证名称、凭证 UUID 或 Provider 类型），为空时使用端点名本身
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// 允许使用的凭证（名称或 UUID），为空表示不限制
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub credentials: Vec<String>,
+    /// 每分钟请求数上限，为空表示不限流
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit_per_minute: Option<u32>,
+    /// 遥测和用量报告中的客户端分组，为空时为 `endpoint:<端点名>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub telemetry_bucket: Option<String>,
+}
+
 /// 日志配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct LoggingConfig {
@@ -810,6 +1108,12 @@ pub struct LoggingConfig {
     /// 是否包含请求体
     #[serde(default)]
     pub include_request_body: bool,
+    /// 请求/响应审计日志
+    #[serde(default)]
+    pub audit: AuditLogConfig,
+    /// 请求日志持久化
+    #[serde(default)]
+    pub request_logs: RequestLogStoreConfig,
 }
 
 fn default_logging_enabled() -> bool {
@@ -831,10 +1135,384 @@ impl Default for LoggingConfig {
             level: default_log_level(),
             retention_days: default_retention_days(),
             include_request_body: false,
+            audit: AuditLogConfig::default(),
+            request_logs: RequestLogStoreConfig::default(),
+        }
+    }
+}
+
+/// 响应缓存配置
+///
+/// 按规范化后的请求哈希缓存非流式补全响应，相同请求在 TTL 内直接返回缓存结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseCacheConfig {
+    /// 是否启用响应缓存
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓存有效期（秒）
+    #[serde(default = "default_response_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 内存中最多保留的条目数（LRU 淘汰）
+    #[serde(default = "default_response_cache_max_entries")]
+    pub max_entries: usize,
+    /// 是否同时持久化到数据库（重启后仍可命中）
+    #[serde(default)]
+    pub persist: bool,
+}
+
+fn default_response_cache_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_response_cache_max_entries() -> usize {
+    1000
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_response_cache_ttl_secs(),
+            max_entries: default_response_cache_max_entries(),
+            persist: false,
         }
     }
 }
 
+/// 凭证加密主密钥来源
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretKeySource {
+    /// 系统钥匙串（Windows 凭据管理器 / macOS Keychain / libsecret）
+    #[default]
+    Keychain,
+    /// 主口令（从环境变量读取，Argon2id 派生密钥）
+    Passphrase,
+}
+
+/// 凭证静态加密配置
+///
+/// 启用后凭证池中的凭证数据和缓存的 Token 以 AES-256-GCM 加密存储，读取时透明解密
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SecretsConfig {
+    /// 是否加密存储凭证
+    #[serde(default)]
+    pub enabled: bool,
+    /// 主密钥来源
+    #[serde(default)]
+    pub key_source: SecretKeySource,
+    /// 存放主口令的环境变量名（key_source 为 passphrase 时使用）
+    #[serde(default = "default_passphrase_env")]
+    pub passphrase_env: String,
+}
+
+fn default_passphrase_env() -> String {
+    "PROXYCAST_MASTER_PASSPHRASE".to_string()
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_source: SecretKeySource::default(),
+            passphrase_env: default_passphrase_env(),
+        }
+    }
+}
+
+/// 共享存储后端
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// 仅使用本地 SQLite（单实例）
+    #[default]
+    Sqlite,
+    /// 同步到 Postgres，多个实例共享凭证池、请求日志和用量统计（需启用 `postgres` 编译特性）
+    Postgres,
+}
+
+/// 共享存储配置
+///
+/// 本地 SQLite 始终是请求路径上的工作存储；启用 Postgres 后凭证池定期与共享库双向同步，
+/// 请求日志和 Token 用量异步写入共享库，管理 API 的请求日志检索和共享用量统计从共享库读取
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StorageConfig {
+    /// 存储后端
+    #[serde(default)]
+    pub backend: StorageBackend,
+    /// Postgres 连接串（未设置时读取 `postgres_url_env` 指定的环境变量）
+    #[serde(default)]
+    pub postgres_url: Option<String>,
+    /// 存放 Postgres 连接串的环境变量名
+    #[serde(default = "default_postgres_url_env")]
+    pub postgres_url_env: String,
+    /// 连接池最大连接数
+    #[serde(default = "default_storage_max_connections")]
+    pub max_connections: u32,
+    /// 凭证池同步间隔（秒）
+    #[serde(default = "default_storage_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+    /// 共享库中请求日志和 Token 用量的保留天数
+    #[serde(default = "default_storage_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_postgres_url_env() -> String {
+    "PROXYCAST_DATABASE_URL".to_string()
+}
+
+fn default_storage_max_connections() -> u32 {
+    5
+}
+
+fn default_storage_sync_interval_secs() -> u64 {
+    30
+}
+
+fn default_storage_retention_days() -> u32 {
+    30
+}
+
+impl StorageConfig {
+    /// 解析 Postgres 连接串：优先使用配置值，其次读取环境变量
+    pub fn resolve_postgres_url(&self) -> Option<String> {
+        self.postgres_url
+            .clone()
+            .filter(|url| !url.trim().is_empty())
+            .or_else(|| std::env::var(&self.postgres_url_env).ok())
+            .filter(|url| !url.trim().is_empty())
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::default(),
+            postgres_url: None,
+            postgres_url_env: default_postgres_url_env(),
+            max_connections: default_storage_max_connections(),
+            sync_interval_secs: default_storage_sync_interval_secs(),
+            retention_days: default_storage_retention_days(),
+        }
+    }
+}
+
+/// 上游 HTTP 客户端配置
+///
+/// 每个 Provider（及其出站代理）共享一个带连接池的客户端，请求之间复用连接。
+/// 时间类字段为 0 表示不启用对应设置。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HttpClientConfig {
+    /// 连接超时（秒）
+    #[serde(default = "default_http_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// 请求总超时（秒），包括读取响应体，流式响应可能很长
+    #[serde(default = "default_http_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// 首字节超时（秒）：流式请求发出后等待上游响应头的最长时间
+    #[serde(default = "default_http_first_byte_timeout_secs")]
+    pub first_byte_timeout_secs: u64,
+    /// 按 Provider 覆盖的超时设置（键为 Provider 类型，如 `claude`、`kiro`）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub provider_timeouts: HashMap<String, ProviderTimeoutConfig>,
+    /// 每个主机保留的最大空闲连接数
+    #[serde(default = "default_http_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接保留时间（秒），0 表示不回收
+    #[serde(default = "default_http_pool_idle_timeout_secs")]
+    pub pool_idle_timeout_secs: u64,
+    /// TCP Keep-Alive 间隔（秒）
+    #[serde(default = "default_http_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+    /// 是否允许 HTTP/2（TLS 握手时协商），关闭时只使用 HTTP/1.1
+    #[serde(default = "default_http2")]
+    pub http2: bool,
+    /// HTTP/2 PING 保活间隔（秒）
+    #[serde(default)]
+    pub http2_keep_alive_interval_secs: u64,
+    /// 是否启用 HTTP/2 自适应流控窗口
+    #[serde(default)]
+    pub http2_adaptive_window: bool,
+}
+
+fn default_http_connect_timeout_secs() -> u64 {
+    30
+}
+
+fn default_http_request_timeout_secs() -> u64 {
+    600
+}
+
+fn default_http_first_byte_timeout_secs() -> u64 {
+    120
+}
+
+fn default_http_pool_max_idle_per_host() -> usize {
+    32
+}
+
+fn default_http_pool_idle_timeout_secs() -> u64 {
+    90
+}
+
+fn default_http_tcp_keepalive_secs() -> u64 {
+    60
+}
+
+fn default_http2() -> bool {
+    true
+}
+
+impl HttpClientConfig {
+    /// 转换为客户端工厂的连接池设置
+    pub fn pool_settings(&self) -> proxycast_infra::ConnectionPoolSettings {
+        let secs = |value: u64| (value > 0).then(|| std::time::Duration::from_secs(value));
+        proxycast_infra::ConnectionPoolSettings {
+            max_idle_per_host: self.pool_max_idle_per_host,
+            idle_timeout: secs(self.pool_idle_timeout_secs),
+            tcp_keepalive: secs(self.tcp_keepalive_secs),
+            http2: self.http2,
+            http2_keep_alive_interval: secs(self.http2_keep_alive_interval_secs),
+            http2_adaptive_window: self.http2_adaptive_window,
+        }
+    }
+
+    /// 指定 Provider 生效的超时设置，`provider_timeouts` 中未设置的字段使用全局值
+    pub fn timeouts_for(&self, provider: &str) -> proxycast_infra::TimeoutConfig {
+        let overrides = self
+            .provider_timeouts
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(provider))
+            .map(|(_, timeouts)| timeouts.clone())
+            .unwrap_or_default();
+        let ms = |value: Option<u64>, default: u64| value.unwrap_or(default).saturating_mul(1000);
+        proxycast_infra::TimeoutConfig {
+            request_timeout_ms: ms(overrides.request_timeout_secs, self.request_timeout_secs),
+            connect_timeout_ms: ms(overrides.connect_timeout_secs, self.connect_timeout_secs),
+            first_byte_timeout_ms: ms(
+                overrides.first_byte_timeout_secs,
+                self.first_byte_timeout_secs,
+            ),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: default_http_connect_timeout_secs(),
+            request_timeout_secs: default_http_request_timeout_secs(),
+            first_byte_timeout_secs: default_http_first_byte_timeout_secs(),
+            provider_timeouts: HashMap::new(),
+            pool_max_idle_per_host: default_http_pool_max_idle_per_host(),
+            pool_idle_timeout_secs: default_http_pool_idle_timeout_secs(),
+            tcp_keepalive_secs: default_http_tcp_keepalive_secs(),
+            http2: default_http2(),
+            http2_keep_alive_interval_secs: 0,
+            http2_adaptive_window: false,
+        }
+    }
+}
+
+/// 单个 Provider 的上游超时设置（秒），未设置的字段使用 `http_client` 中的全局值，0 表示不限制
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProviderTimeoutConfig {
+    /// 连接超时
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+    /// 首字节超时
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_byte_timeout_secs: Option<u64>,
+    /// 请求总超时
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout_secs: Option<u64>,
+}
+
+/// 请求/响应审计日志配置
+///
+/// 启用后按 request_id 将完整的请求体和响应体（脱敏后）持久化到数据库
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditLogConfig {
+    /// 是否启用审计日志
+    #[serde(default)]
+    pub enabled: bool,
+    /// 单个请求体/响应体最多记录的字节数，超出部分截断
+    #[serde(default = "default_audit_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// 审计记录保留天数
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+    /// 是否启用内置脱敏规则（API Key、Token、邮箱、手机号等）
+    #[serde(default = "default_audit_default_redaction")]
+    pub default_redaction: bool,
+    /// 自定义脱敏规则
+    #[serde(default)]
+    pub redaction_rules: Vec<AuditRedactionRule>,
+}
+
+fn default_audit_max_body_bytes() -> usize {
+    256 * 1024
+}
+
+fn default_audit_default_redaction() -> bool {
+    true
+}
+
+impl Default for AuditLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_body_bytes: default_audit_max_body_bytes(),
+            retention_days: default_retention_days(),
+            default_redaction: default_audit_default_redaction(),
+            redaction_rules: Vec::new(),
+        }
+    }
+}
+
+/// 请求日志持久化配置
+///
+/// 启用后每条请求遥测日志写入数据库，可按条件检索和分页查询
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RequestLogStoreConfig {
+    /// 是否持久化请求日志
+    #[serde(default = "default_request_log_store_enabled")]
+    pub enabled: bool,
+    /// 请求日志保留天数
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_request_log_store_enabled() -> bool {
+    true
+}
+
+impl Default for RequestLogStoreConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_request_log_store_enabled(),
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+/// 审计日志脱敏规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditRedactionRule {
+    /// 规则名称
+    pub name: String,
+    /// 匹配模式（正则表达式）
+    pub pattern: String,
+    /// 替换文本
+    #[serde(default = "default_redaction_replacement")]
+    pub replacement: String,
+}
+
+fn default_redaction_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
 // ============ 模型配置类型 ============
 
 /// 模型信息
@@ -1071,6 +1749,31 @@ impl Default for ModelsConfig {
             },
         );
 
+        // Mistral
+        providers.insert(
+            "mistral".to_string(),
+            ProviderModelsConfig {
+                label: "Mistral".to_string(),
+                models: vec![
+                    ModelInfo {
+                        id: "mistral-large-latest".to_string(),
+                        name: None,
+                        enabled: true,
+                    },
+                    ModelInfo {
+                        id: "mistral-medium-latest".to_string(),
+                        name: None,
+                        enabled: true,
+                    },
+                    ModelInfo {
+                        id: "codestral-latest".to_string(),
+                        name: None,
+                        enabled: true,
+                    },
+                ],
+            },
+        );
+
         // Codex - 模型列表从别名配置动态加载
         providers.insert(
             "codex".to_string(),
@@ -1240,7 +1943,20 @@ pub struct InjectionRuleConfig {
     /// 模型匹配模式（支持通配符）
     pub pattern: String,
     /// 要注入的参数
+    #[serde(default)]
     pub parameters: serde_json::Value,
+    /// 要注入到上游请求的 HTTP 头
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// 要注入的 system prompt（不存在 system prompt 时插入）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<SystemPromptInjection>,
+    /// 请求头和 system prompt 注入限定的 Provider（为空时对所有 Provider 生效）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<String>,
+    /// 条件表达式（针对请求体求值，如 `$.tools && !$.temperature`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
     /// 注入模式
     #[serde(default)]
     pub mode: InjectionMode,
@@ -1263,6 +1979,10 @@ fn default_priority() -> i32 {
 impl From<InjectionRuleConfig> for InjectionRule {
     fn from(config: InjectionRuleConfig) -> Self {
         let mut rule = InjectionRule::new(&config.id, &config.pattern, config.parameters);
+        rule.headers = config.headers;
+        rule.system_prompt = config.system_prompt;
+        rule.condition = config.condition;
+        rule.providers = config.providers;
         rule.mode = config.mode;
         rule.priority = config.priority;
         rule.enabled = config.enabled;
@@ -1276,6 +1996,10 @@ impl From<&InjectionRule> for InjectionRuleConfig {
             id: rule.id.clone(),
             pattern: rule.pattern.clone(),
             parameters: rule.parameters.clone(),
+            headers: rule.headers.clone(),
+            system_prompt: rule.system_prompt.clone(),
+            providers: rule.providers.clone(),
+            condition: rule.condition.clone(),
             mode: rule.mode,
             priority: rule.priority,
             enabled: rule.enabled,
@@ -1291,20 +2015,40 @@ impl Default for Config {
             default_provider: default_provider(),
             routing: RoutingConfig::default(),
             retry: RetrySettings::default(),
+            failover: FailoverSettings::default(),
+            concurrency: proxycast_infra::ConcurrencyConfig::default(),
+            load_balance_strategy: LoadBalanceStrategy::default(),
+            session_affinity: SessionAffinityConfig::default(),
+            context_fallback: ContextFallbackConfig::default(),
+            chaos: proxycast_infra::ChaosConfig::default(),
             logging: LoggingConfig::default(),
+            otlp: proxycast_infra::OtlpConfig::default(),
+            pricing: proxycast_infra::PricingConfig::default(),
+            reports: proxycast_infra::ReportConfig::default(),
+            alerts: proxycast_infra::AlertConfig::default(),
             injection: InjectionSettings::default(),
+            transforms: proxycast_infra::TransformConfig::default(),
+            reasoning: Default::default(),
+            response_cache: ResponseCacheConfig::default(),
+            secrets: SecretsConfig::default(),
+            storage: StorageConfig::default(),
             auth_dir: default_auth_dir(),
             credential_pool: CredentialPoolConfig::default(),
             remote_management: RemoteManagementConfig::default(),
             quota_exceeded: QuotaExceededConfig::default(),
             proxy_url: None,
+            provider_proxies: HashMap::new(),
+            http_client: HttpClientConfig::default(),
             ampcode: AmpConfig::default(),
             endpoint_providers: EndpointProvidersConfig::default(),
+            selector_endpoints: HashMap::new(),
             minimize_to_tray: default_minimize_to_tray(),
             language: default_language(),
             models: ModelsConfig::default(),
             agent: NativeAgentConfig::default(),
             experimental: ExperimentalFeatures::default(),
+            active_profile: None,
+            profiles: BTreeMap::new(),
         }
     }
 }
@@ -1447,6 +2191,40 @@ mod unit_tests {
         assert!(config.auto_switch_provider);
     }
 
+    #[test]
+    fn test_failover_settings_default() {
+        let config = FailoverSettings::default();
+        assert!(config.enabled);
+        assert_eq!(config.max_attempts, 3);
+        assert!(config.fallback_providers.is_empty());
+        assert!(!config.hedging.enabled);
+
+        let parsed: FailoverSettings =
+            serde_yaml::from_str("fallback_providers: [openai]").unwrap();
+        assert!(parsed.enabled);
+        assert_eq!(parsed.max_attempts, 3);
+        assert_eq!(parsed.fallback_providers, vec!["openai".to_string()]);
+
+        let parsed: FailoverSettings =
+            serde_yaml::from_str("hedging:\n  enabled: true\n  models: ['claude-*']").unwrap();
+        assert!(parsed.hedging.enabled);
+        assert_eq!(parsed.hedging.delay_ms, 2000);
+        assert!(parsed.hedging.applies_to("claude-sonnet-4-5"));
+    }
+
+    #[test]
+    fn test_load_balance_strategy_serde() {
+        assert_eq!(LoadBalanceStrategy::default(), LoadBalanceStrategy::Smart);
+        let parsed: LoadBalanceStrategy = serde_yaml::from_str("latency_p95").unwrap();
+        assert_eq!(parsed, LoadBalanceStrategy::LatencyP95);
+        assert_eq!(
+            serde_yaml::to_string(&LoadBalanceStrategy::LeastConnections)
+                .unwrap()
+                .trim(),
+            "least_connections"
+        );
+    }
+
     #[test]
     fn test_logging_config_default() {
         let config = LoggingConfig::default();
@@ -1454,6 +2232,22 @@ mod unit_tests {
         assert_eq!(config.level, "info");
         assert_eq!(config.retention_days, 7);
         assert!(!config.include_request_body);
+        assert!(!config.audit.enabled);
+        assert!(config.audit.default_redaction);
+    }
+
+    #[test]
+    fn test_audit_log_config_deserialize() {
+        let yaml = r#"
+enabled: true
+redaction_rules:
+  - name: internal_id
+    pattern: "EMP-\\d+"
+"#;
+        let config: AuditLogConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.max_body_bytes, 256 * 1024);
+        assert_eq!(config.redaction_rules[0].replacement, "[REDACTED]");
     }
 
     #[test]
@@ -1625,4 +2419,26 @@ mod unit_tests {
             "CommandOrControl+Shift+S"
         );
     }
+
+    #[test]
+    fn test_http_client_timeouts_for_provider() {
+        let yaml = r#"
+first_byte_timeout_secs: 60
+provider_timeouts:
+  kiro:
+    first_byte_timeout_secs: 300
+    request_timeout_secs: 0
+"#;
+        let config: HttpClientConfig = serde_yaml::from_str(yaml).unwrap();
+
+        let claude = config.timeouts_for("claude");
+        assert_eq!(claude.connect_timeout_ms, 30_000);
+        assert_eq!(claude.first_byte_timeout_ms, 60_000);
+        assert_eq!(claude.request_timeout_ms, 600_000);
+
+        let kiro = config.timeouts_for("Kiro");
+        assert_eq!(kiro.connect_timeout_ms, 30_000);
+        assert_eq!(kiro.first_byte_timeout_ms, 300_000);
+        assert!(kiro.request_timeout().is_none());
+    }
 }
@@ -5,6 +5,8 @@
 
 #![allow(dead_code)]
 
+use super::profiles;
+use super::secret_fields;
 use super::types::Config;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -38,6 +40,9 @@ impl std::fmt::Display for ConfigError {
 
 impl std::error::Error for ConfigError {}
 
+/// 通过 [`ConfigManager::set_config_path`] 指定的配置文件路径
+static CONFIG_PATH_OVERRIDE: once_cell::sync::OnceCell<PathBuf> = once_cell::sync::OnceCell::new();
+
 /// 配置管理器
 ///
 /// 管理 YAML 配置文件的加载、保存和热重载
@@ -85,8 +90,15 @@ impl ConfigManager {
     }
 
     /// 从 YAML 字符串解析配置
+    ///
+    /// 合并当前 Profile、替换 `${VAR}` 环境变量并解密敏感字段
     pub fn parse_yaml(yaml: &str) -> Result<Config, ConfigError> {
-        serde_yaml::from_str(yaml).map_err(|e| ConfigError::ParseError(e.to_string()))
+        let raw: serde_yaml::Value =
+            serde_yaml::from_str(yaml).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        let mut resolved = profiles::resolve(raw);
+        secret_fields::open_values(&mut resolved)
+            .map_err(|e| ConfigError::ParseError(format!("敏感字段解密失败: {}", e)))?;
+        serde_yaml::from_value(resolved).map_err(|e| ConfigError::ParseError(e.to_string()))
     }
 
     /// 将配置序列化为 YAML 字符串
@@ -110,7 +122,8 @@ impl ConfigManager {
             let backup_path = path.with_extension("yaml.backup");
             let _ = std::fs::copy(path, backup_path);
         }
-        let yaml = Self::to_yaml(&self.config)?;
+        let original = std::fs::read_to_string(path).ok();
+        let yaml = profiles::render_for_save(original.as_deref(), &self.config)?;
         std::fs::write(path, yaml).map_err(|e| ConfigError::WriteError(e.to_string()))
     }
 
@@ -222,14 +235,45 @@ impl ConfigManager {
             self.config.retry = other.retry;
         }
 
+        // 合并故障转移配置
+        if other.failover != FailoverSettings::default() {
+            self.config.failover = other.failover;
+        }
+        if other.load_balance_strategy != LoadBalanceStrategy::default() {
+            self.config.load_balance_strategy = other.load_balance_strategy;
+        }
+        if other.session_affinity != SessionAffinityConfig::default() {
+            self.config.session_affinity = other.session_affinity;
+        }
+        if other.context_fallback != ContextFallbackConfig::default() {
+            self.config.context_fallback = other.context_fallback;
+        }
+        if other.chaos != proxycast_infra::ChaosConfig::default() {
+            self.config.chaos = other.chaos;
+        }
+        if !other.selector_endpoints.is_empty() {
+            self.config.selector_endpoints = other.selector_endpoints;
+        }
+
         // 合并日志配置
         if other.logging != LoggingConfig::default() {
             self.config.logging = other.logging;
         }
     }
 
+    /// 指定配置文件路径，替代默认路径
+    ///
+    /// 供命令行 `--config` 使用，需在加载配置之前调用，且只能设置一次；
+    /// 加载、保存和热重载监控都使用该路径。
+    pub fn set_config_path(path: PathBuf) -> Result<(), PathBuf> {
+        CONFIG_PATH_OVERRIDE.set(path)
+    }
+
     /// 获取默认配置文件路径
     pub fn default_config_path() -> PathBuf {
+        if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+            return path.clone();
+        }
         dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("proxycast")
@@ -237,7 +281,10 @@ impl ConfigManager {
     }
 }
 
-use super::types::{LoggingConfig, RetrySettings, ServerConfig};
+use super::types::{
+    ContextFallbackConfig, FailoverSettings, LoadBalanceStrategy, LoggingConfig, RetrySettings,
+    ServerConfig, SessionAffinityConfig,
+};
 
 impl Default for ConfigManager {
     fn default() -> Self {
@@ -281,8 +328,8 @@ impl YamlService {
             None
         };
 
-        // 序列化新配置
-        let new_yaml = ConfigManager::to_yaml(config)?;
+        // 序列化新配置（敏感字段按当前加密状态加密）
+        let new_yaml = ConfigManager::to_yaml(&secret_fields::sealed_copy(config)?)?;
 
         // 如果原文件存在，尝试保留注释
         let final_content = if let Some(original) = original_content {
@@ -663,7 +710,11 @@ pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
     // 优先尝试 YAML 配置
     if yaml_path.exists() {
         let content = std::fs::read_to_string(&yaml_path)?;
-        let mut config: Config = serde_yaml::from_str(&content)?;
+        let (mut config, diagnostics) = super::schema::parse_with_diagnostics(&content)
+            .map_err(|d| ConfigError::ParseError(d.to_string()))?;
+        for diagnostic in &diagnostics {
+            tracing::warn!("[CONFIG] {}", diagnostic);
+        }
         // 如果配置中使用默认 API Key，生成强随机 Key 并保存
         if is_default_api_key(&config.server.api_key) {
             let new_key = generate_secure_api_key();
@@ -681,6 +732,7 @@ pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
     if json_path.exists() {
         let content = std::fs::read_to_string(&json_path)?;
         let mut config: Config = serde_json::from_str(&content)?;
+        secret_fields::open_config(&mut config)?;
         // 如果配置中使用默认 API Key，生成强随机 Key 并保存
         if is_default_api_key(&config.server.api_key) {
             let new_key = generate_secure_api_key();
@@ -716,7 +768,7 @@ pub fn save_config(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    let content = serde_json::to_string_pretty(config)?;
+    let content = serde_json::to_string_pretty(&secret_fields::sealed_copy(config)?)?;
     std::fs::write(&path, content)?;
     Ok(())
 }
@@ -727,11 +779,12 @@ pub fn save_config_yaml(config: &Config) -> Result<(), Box<dyn std::error::Error
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    if path.exists() {
+    let original = std::fs::read_to_string(&path).ok();
+    if original.is_some() {
         let backup_path = path.with_extension("yaml.backup");
         let _ = std::fs::copy(&path, &backup_path);
     }
-    let content = serde_yaml::to_string(config)?;
+    let content = profiles::render_for_save(original.as_deref(), config)?;
     std::fs::write(&path, content)?;
     Ok(())
 }
@@ -1,9 +1,19 @@
 //! Anthropic 格式转换为 OpenAI 格式 (支持 Claude Code)
+//!
+//! 请求字段映射：
+//! - `system`（字符串或文本块数组）→ `system` 消息，文本块以换行拼接
+//! - `stop_sequences` → `stop`，`metadata.user_id` → `user`，`top_p` → `top_p`
+//! - `tool_choice`：`auto` → `"auto"`、`any` → `"required"`、`none` → `"none"`、
+//!   `tool` → 指定函数；`disable_parallel_tool_use` → `parallel_tool_calls: false`
+//! - `top_k` 和 `thinking` 没有通用的 OpenAI 对应字段，不转发（`reasoning_effort`
+//!   会被不支持推理的模型拒绝）
+use super::image_content::{ImageContent, OPENAI_IMAGE_LIMITS};
 use crate::models::anthropic::*;
 use crate::models::openai::*;
 use uuid::Uuid;
 
 /// 将 Anthropic MessagesRequest 转换为 OpenAI ChatCompletionRequest
+#[tracing::instrument(name = "convert.anthropic_to_openai", skip_all)]
 pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCompletionRequest {
     let mut openai_messages: Vec<ChatMessage> = Vec::new();
 
@@ -41,16 +51,71 @@ pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCo
             .collect()
     });
 
+    let mut extra = serde_json::Map::new();
+    if let Some(stop) = request
+        .extra
+        .get("stop_sequences")
+        .and_then(|s| s.as_array())
+        .filter(|s| !s.is_empty())
+    {
+        extra.insert("stop".to_string(), serde_json::Value::Array(stop.clone()));
+    }
+    if let Some(user_id) = request
+        .extra
+        .get("metadata")
+        .and_then(|m| m.get("user_id"))
+        .and_then(|u| u.as_str())
+    {
+        extra.insert("user".to_string(), serde_json::json!(user_id));
+    }
+
+    let has_tools = request.tools.as_ref().is_some_and(|t| !t.is_empty());
+    let tool_choice = request.tool_choice.as_ref().and_then(convert_tool_choice);
+    // OpenAI 只允许在提供 tools 时设置 parallel_tool_calls
+    if has_tools
+        && request
+            .tool_choice
+            .as_ref()
+            .and_then(|c| c.get("disable_parallel_tool_use"))
+            .and_then(|d| d.as_bool())
+            == Some(true)
+    {
+        extra.insert("parallel_tool_calls".to_string(), serde_json::json!(false));
+    }
+
     ChatCompletionRequest {
         model: request.model.clone(),
         messages: openai_messages,
         temperature: request.temperature,
         max_tokens: request.max_tokens,
-        top_p: None,
+        top_p: request
+            .extra
+            .get("top_p")
+            .and_then(|p| p.as_f64())
+            .map(|p| p as f32),
         stream: request.stream,
         tools,
-        tool_choice: request.tool_choice.clone(),
+        tool_choice,
         reasoning_effort: None,
+        extra,
+    }
+}
+
+/// 将 Anthropic 的 `tool_choice` 转换为 OpenAI 格式，无法识别时返回 `None`（使用上游默认值）
+fn convert_tool_choice(choice: &serde_json::Value) -> Option<serde_json::Value> {
+    // 已是 OpenAI 格式的字符串原样保留
+    if choice.is_string() {
+        return Some(choice.clone());
+    }
+    match choice.get("type").and_then(|t| t.as_str())? {
+        "auto" => Some(serde_json::json!("auto")),
+        "any" => Some(serde_json::json!("required")),
+        "none" => Some(serde_json::json!("none")),
+        "tool" => {
+            let name = choice.get("name").and_then(|n| n.as_str())?;
+            Some(serde_json::json!({"type": "function", "function": {"name": name}}))
+        }
+        _ => None,
     }
 }
 
@@ -89,8 +154,13 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
         }
         serde_json::Value::Array(parts) => {
             let mut text_parts: Vec<String> = Vec::new();
+            // 按原始顺序保留的文本和图片（user 消息含图片时使用）
+            let mut content_parts: Vec<ContentPart> = Vec::new();
+            let mut has_image = false;
             let mut tool_calls: Vec<ToolCall> = Vec::new();
             let mut tool_results: Vec<(String, String)> = Vec::new(); // (tool_use_id, content)
+                                                                      // OpenAI 的 tool 消息只支持文本，tool_result 中的图片放到随后的 user 消息
+            let mut tool_result_images: Vec<ContentPart> = Vec::new();
 
             for part in parts {
                 let part_type = part.get("type").and_then(|t| t.as_str()).unwrap_or("");
@@ -99,6 +169,15 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                     "text" => {
                         if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
                             text_parts.push(text.to_string());
+                            content_parts.push(ContentPart::Text {
+                                text: text.to_string(),
+                            });
+                        }
+                    }
+                    "image" => {
+                        if let Some(image) = convert_image_block(part) {
+                            has_image = true;
+                            content_parts.push(image);
                         }
                     }
                     "tool_use" => {
@@ -125,6 +204,7 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                             .and_then(|i| i.as_str())
                             .unwrap_or("");
                         let content = extract_tool_result_content(part.get("content"));
+                        tool_result_images.extend(extract_tool_result_images(part.get("content")));
                         tool_results.push((tool_use_id.to_string(), content));
                     }
                     _ => {}
@@ -165,11 +245,19 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                     });
                 }
 
-                // 添加文本内容
-                if !text_parts.is_empty() {
+                // 添加文本和图片内容
+                let content = if has_image || !tool_result_images.is_empty() {
+                    tool_result_images.extend(content_parts);
+                    Some(MessageContent::Parts(tool_result_images))
+                } else if !text_parts.is_empty() {
+                    Some(MessageContent::Text(text_parts.join("")))
+                } else {
+                    None
+                };
+                if content.is_some() {
                     result.push(ChatMessage {
                         role: "user".to_string(),
-                        content: Some(MessageContent::Text(text_parts.join(""))),
+                        content,
                         tool_calls: None,
                         tool_call_id: None,
                         reasoning_content: None,
@@ -202,3 +290,125 @@ fn extract_tool_result_content(content: Option<&serde_json::Value>) -> String {
         _ => String::new(),
     }
 }
+
+/// 提取 tool_result 中的图片
+fn extract_tool_result_images(content: Option<&serde_json::Value>) -> Vec<ContentPart> {
+    match content {
+        Some(serde_json::Value::Array(arr)) => arr
+            .iter()
+            .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("image"))
+            .filter_map(convert_image_block)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 转换 Anthropic 图片块，超出 OpenAI 限制时压缩，无法处理时替换为文本
+fn convert_image_block(block: &serde_json::Value) -> Option<ContentPart> {
+    let image = ImageContent::from_anthropic(block)?;
+    Some(match image.fit_or_placeholder(&OPENAI_IMAGE_LIMITS) {
+        Ok(image) => image.to_openai(),
+        Err(text) => ContentPart::Text { text },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn full_request() -> AnthropicMessagesRequest {
+        serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 2048,
+            "system": [
+                {"type": "text", "text": "You are Claude Code.", "cache_control": {"type": "ephemeral"}},
+                {"type": "text", "text": "Be concise."}
+            ],
+            "messages": [{"role": "user", "content": "List the files"}],
+            "temperature": 0.5,
+            "top_p": 0.9,
+            "top_k": 40,
+            "stream": true,
+            "stop_sequences": ["</done>"],
+            "metadata": {"user_id": "user_abc"},
+            "thinking": {"type": "enabled", "budget_tokens": 4096},
+            "tools": [{"name": "ls", "description": "List files", "input_schema": {"type": "object"}}],
+            "tool_choice": {"type": "tool", "name": "ls", "disable_parallel_tool_use": true}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_request_field_fidelity() {
+        let request = convert_anthropic_to_openai(&full_request());
+
+        assert_eq!(request.model, "claude-sonnet-4-5");
+        assert_eq!(request.max_tokens, Some(2048));
+        assert_eq!(request.temperature, Some(0.5));
+        assert_eq!(request.top_p, Some(0.9));
+        assert!(request.stream);
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[0].role, "system");
+        assert_eq!(
+            request.messages[0].get_content_text(),
+            "You are Claude Code.\nBe concise."
+        );
+        assert_eq!(request.messages[1].get_content_text(), "List the files");
+        assert_eq!(request.tools.as_ref().unwrap().len(), 1);
+        assert_eq!(
+            request.tool_choice,
+            Some(json!({"type": "function", "function": {"name": "ls"}}))
+        );
+
+        // 序列化后只包含 OpenAI 字段
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["stop"], json!(["</done>"]));
+        assert_eq!(body["user"], "user_abc");
+        assert_eq!(body["parallel_tool_calls"], false);
+        for key in ["stop_sequences", "metadata", "top_k", "thinking", "system"] {
+            assert!(body.get(key).is_none(), "{} should not be forwarded", key);
+        }
+    }
+
+    #[test]
+    fn test_minimal_request_adds_no_fields() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": "hi"}],
+            "system": "Be brief.",
+            "stop_sequences": [],
+            "tool_choice": {"type": "auto", "disable_parallel_tool_use": true}
+        }))
+        .unwrap();
+        let request = convert_anthropic_to_openai(&request);
+
+        assert_eq!(request.messages[0].get_content_text(), "Be brief.");
+        assert_eq!(request.top_p, None);
+        assert_eq!(request.tool_choice, Some(json!("auto")));
+        // 没有 tools 时不设置 parallel_tool_calls
+        assert!(request.extra.is_empty());
+    }
+
+    #[test]
+    fn test_convert_tool_choice() {
+        assert_eq!(
+            convert_tool_choice(&json!({"type": "auto"})),
+            Some(json!("auto"))
+        );
+        assert_eq!(
+            convert_tool_choice(&json!({"type": "any"})),
+            Some(json!("required"))
+        );
+        assert_eq!(
+            convert_tool_choice(&json!({"type": "none"})),
+            Some(json!("none"))
+        );
+        assert_eq!(
+            convert_tool_choice(&json!("required")),
+            Some(json!("required"))
+        );
+        assert_eq!(convert_tool_choice(&json!({"type": "tool"})), None);
+        assert_eq!(convert_tool_choice(&json!({"type": "unknown"})), None);
+    }
+}
@@ -7,10 +7,13 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 /// 将 CodeWhisperer 流式事件转换为 OpenAI 格式
+///
+/// `tool_index` 为本次工具调用在响应中的序号（从 0 开始），由调用方按工具调用出现顺序维护。
 pub fn convert_cw_event_to_openai_chunk(
     event: &CWStreamEvent,
     model: &str,
     response_id: &str,
+    tool_index: u32,
 ) -> Option<ChatCompletionChunk> {
     let created = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -31,6 +34,7 @@ pub fn convert_cw_event_to_openai_chunk(
                         role: Some("assistant".to_string()),
                         content: Some(content.clone()),
                         tool_calls: None,
+                        reasoning_content: None,
                     },
                     finish_reason: None,
                 }],
@@ -49,15 +53,18 @@ pub fn convert_cw_event_to_openai_chunk(
                     delta: StreamDelta {
                         role: Some("assistant".to_string()),
                         content: None,
-                        tool_calls: Some(vec![ToolCall {
-                            id: tool_use.tool_use_id.clone(),
-                            call_type: "function".to_string(),
-                            function: FunctionCall {
-                                name: tool_use.name.clone(),
-                                arguments: serde_json::to_string(&tool_use.input)
-                                    .unwrap_or_default(),
-                            },
+                        tool_calls: Some(vec![StreamToolCall {
+                            index: tool_index,
+                            id: Some(tool_use.tool_use_id.clone()),
+                            call_type: Some("function".to_string()),
+                            function: Some(StreamFunctionCall {
+                                name: Some(tool_use.name.clone()),
+                                arguments: Some(
+                                    serde_json::to_string(&tool_use.input).unwrap_or_default(),
+                                ),
+                            }),
                         }]),
+                        reasoning_content: None,
                     },
                     finish_reason: None,
                 }],
@@ -102,6 +109,7 @@ pub fn create_openai_response(
                     Some(content.to_string())
                 },
                 tool_calls,
+                reasoning_content: None,
             },
             finish_reason: finish_reason.to_string(),
         }],
@@ -131,6 +139,7 @@ pub fn create_stream_end_chunk(model: &str, response_id: &str) -> ChatCompletion
                 role: None,
                 content: None,
                 tool_calls: None,
+                reasoning_content: None,
             },
             finish_reason: Some("stop".to_string()),
         }],
@@ -0,0 +1,603 @@
+//! Gemini 原生协议与 OpenAI 格式之间的转换
+//!
+//! 用于 `/v1beta/models/{model}:generateContent` 端点：
+//! - 请求：Gemini `generateContent` 请求转换为 OpenAI `ChatCompletionRequest`，
+//!   复用 `/v1/chat/completions` 的凭证池路由
+//! - 响应：OpenAI 响应（JSON 或 SSE chunk）转换回 Gemini `GenerateContentResponse`
+//!
+//! Gemini 的 `functionResponse` 只有函数名，没有调用 ID，
+//! 转换时按函数名匹配最近一次同名 `functionCall` 的 ID。
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{json, Value};
+
+use crate::models::openai::{
+    ChatCompletionRequest, ChatMessage, ContentPart, FunctionCall, FunctionDef, ImageUrl,
+    MessageContent, Tool, ToolCall,
+};
+
+/// 将 Gemini generateContent 请求转换为 OpenAI ChatCompletion 请求
+pub fn convert_gemini_request_to_openai(
+    request: &Value,
+    model: &str,
+    stream: bool,
+) -> Result<ChatCompletionRequest, String> {
+    let mut messages = Vec::new();
+
+    if let Some(system) = request
+        .get("systemInstruction")
+        .or_else(|| request.get("system_instruction"))
+    {
+        let text = collect_text(system);
+        if !text.is_empty() {
+            messages.push(text_message("system", text));
+        }
+    }
+
+    let contents = request
+        .get("contents")
+        .and_then(|c| c.as_array())
+        .ok_or_else(|| "contents is required".to_string())?;
+
+    // 函数名 -> 最近一次调用的 ID，用于关联 functionResponse
+    let mut call_ids: HashMap<String, String> = HashMap::new();
+    let mut call_count = 0usize;
+
+    for content in contents {
+        let role = content
+            .get("role")
+            .and_then(|r| r.as_str())
+            .unwrap_or("user");
+        let parts = content
+            .get("parts")
+            .and_then(|p| p.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let mut content_parts = Vec::new();
+        let mut tool_calls = Vec::new();
+
+        for part in &parts {
+            if part.get("thought").and_then(|t| t.as_bool()) == Some(true) {
+                // 思考内容不回传给上游
+                continue;
+            }
+            if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                content_parts.push(ContentPart::Text {
+                    text: text.to_string(),
+                });
+            } else if let Some(inline) = part.get("inlineData").or_else(|| part.get("inline_data"))
+            {
+                let mime_type = inline
+                    .get("mimeType")
+                    .or_else(|| inline.get("mime_type"))
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("image/png");
+                let data = inline.get("data").and_then(|d| d.as_str()).unwrap_or("");
+                content_parts.push(ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: format!("data:{};base64,{}", mime_type, data),
+                        detail: None,
+                    },
+                });
+            } else if let Some(call) = part.get("functionCall") {
+                let name = call
+                    .get("name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let id = call
+                    .get("id")
+                    .and_then(|i| i.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("call_{}_{}", name, call_count));
+                call_count += 1;
+                call_ids.insert(name.clone(), id.clone());
+                tool_calls.push(ToolCall {
+                    id,
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name,
+                        arguments: call
+                            .get("args")
+                            .map(|a| a.to_string())
+                            .unwrap_or_else(|| "{}".to_string()),
+                    },
+                });
+            } else if let Some(response) = part.get("functionResponse") {
+                let name = response.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                let tool_call_id = response
+                    .get("id")
+                    .and_then(|i| i.as_str())
+                    .map(|s| s.to_string())
+                    .or_else(|| call_ids.get(name).cloned())
+                    .unwrap_or_else(|| format!("call_{}", name));
+                let output = response.get("response").cloned().unwrap_or(Value::Null);
+                messages.push(ChatMessage {
+                    role: "tool".to_string(),
+                    content: Some(MessageContent::Text(output.to_string())),
+                    tool_calls: None,
+                    tool_call_id: Some(tool_call_id),
+                    reasoning_content: None,
+                });
+            }
+        }
+
+        if content_parts.is_empty() && tool_calls.is_empty() {
+            continue;
+        }
+
+        let role = if role == "model" { "assistant" } else { "user" };
+        let content = match content_parts.as_slice() {
+            [] => None,
+            [ContentPart::Text { text }] => Some(MessageContent::Text(text.clone())),
+            _ => Some(MessageContent::Parts(content_parts)),
+        };
+        messages.push(ChatMessage {
+            role: role.to_string(),
+            content,
+            tool_calls: if tool_calls.is_empty() {
+                None
+            } else {
+                Some(tool_calls)
+            },
+            tool_call_id: None,
+            reasoning_content: None,
+        });
+    }
+
+    let generation_config = request
+        .get("generationConfig")
+        .or_else(|| request.get("generation_config"));
+    let config_value = |key: &str| generation_config.and_then(|c| c.get(key));
+
+    Ok(ChatCompletionRequest {
+        model: model.to_string(),
+        messages,
+        temperature: config_value("temperature")
+            .and_then(|v| v.as_f64())
+            .map(|v| v as f32),
+        max_tokens: config_value("maxOutputTokens")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32),
+        top_p: config_value("topP")
+            .and_then(|v| v.as_f64())
+            .map(|v| v as f32),
+        stream,
+        tools: convert_tools(request.get("tools")),
+        tool_choice: convert_tool_config(request.get("toolConfig")),
+        reasoning_effort: None,
+        extra: Default::default(),
+    })
+}
+
+fn text_message(role: &str, text: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: Some(MessageContent::Text(text)),
+        tool_calls: None,
+        tool_call_id: None,
+        reasoning_content: None,
+    }
+}
+
+/// 拼接 Content 中所有文本 part
+fn collect_text(content: &Value) -> String {
+    content
+        .get("parts")
+        .and_then(|p| p.as_array())
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default()
+}
+
+/// 转换 `tools[].functionDeclarations`
+fn convert_tools(tools: Option<&Value>) -> Option<Vec<Tool>> {
+    let tools: Vec<Tool> = tools?
+        .as_array()?
+        .iter()
+        .filter_map(|tool| {
+            tool.get("functionDeclarations")
+                .or_else(|| tool.get("function_declarations"))
+                .and_then(|d| d.as_array())
+        })
+        .flatten()
+        .map(|decl| Tool::Function {
+            function: FunctionDef {
+                name: decl
+                    .get("name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                description: decl
+                    .get("description")
+                    .and_then(|d| d.as_str())
+                    .map(|s| s.to_string()),
+                parameters: decl
+                    .get("parametersJsonSchema")
+                    .or_else(|| decl.get("parameters"))
+                    .map(normalize_schema_types),
+            },
+        })
+        .collect();
+
+    if tools.is_empty() {
+        None
+    } else {
+        Some(tools)
+    }
+}
+
+/// Gemini Schema 的类型为大写（`OBJECT`、`STRING`），JSON Schema 需要小写
+fn normalize_schema_types(schema: &Value) -> Value {
+    match schema {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = match (key.as_str(), value) {
+                        ("type", Value::String(t)) => Value::String(t.to_lowercase()),
+                        _ => normalize_schema_types(value),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(normalize_schema_types).collect()),
+        other => other.clone(),
+    }
+}
+
+/// 转换 `toolConfig.functionCallingConfig`
+fn convert_tool_config(tool_config: Option<&Value>) -> Option<Value> {
+    let config = tool_config?.get("functionCallingConfig")?;
+    let allowed = config
+        .get("allowedFunctionNames")
+        .and_then(|a| a.as_array())
+        .filter(|a| a.len() == 1)
+        .and_then(|a| a[0].as_str());
+
+    match config.get("mode").and_then(|m| m.as_str())? {
+        "NONE" => Some(json!("none")),
+        "ANY" => match allowed {
+            Some(name) => Some(json!({ "type": "function", "function": { "name": name } })),
+            None => Some(json!("required")),
+        },
+        _ => Some(json!("auto")),
+    }
+}
+
+/// OpenAI finish_reason 转换为 Gemini finishReason
+fn convert_finish_reason(reason: &str) -> &'static str {
+    match reason {
+        "length" => "MAX_TOKENS",
+        "content_filter" => "SAFETY",
+        _ => "STOP",
+    }
+}
+
+/// OpenAI usage 转换为 Gemini usageMetadata
+fn convert_usage(usage: &Value) -> Option<Value> {
+    let prompt = usage.get("prompt_tokens")?.as_u64().unwrap_or(0);
+    let completion = usage
+        .get("completion_tokens")
+        .and_then(|c| c.as_u64())
+        .unwrap_or(0);
+    let total = usage
+        .get("total_tokens")
+        .and_then(|t| t.as_u64())
+        .unwrap_or(prompt + completion);
+    Some(json!({
+        "promptTokenCount": prompt,
+        "candidatesTokenCount": completion,
+        "totalTokenCount": total
+    }))
+}
+
+/// 解析工具调用参数，无法解析时原样放入 `args`
+fn parse_arguments(arguments: &str) -> Value {
+    if arguments.trim().is_empty() {
+        return json!({});
+    }
+    serde_json::from_str(arguments).unwrap_or_else(|_| json!({ "arguments": arguments }))
+}
+
+fn function_call_part(id: &str, name: &str, arguments: &str) -> Value {
+    json!({
+        "functionCall": {
+            "id": id,
+            "name": name,
+            "args": parse_arguments(arguments)
+        }
+    })
+}
+
+/// 将 OpenAI ChatCompletion 响应转换为 Gemini generateContent 响应
+pub fn convert_openai_response_to_gemini(response: &Value, model: &str) -> Value {
+    let choice = response
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .and_then(|c| c.first());
+    let message = choice.and_then(|c| c.get("message"));
+
+    let mut parts = Vec::new();
+    if let Some(reasoning) = message
+        .and_then(|m| m.get("reasoning_content"))
+        .and_then(|r| r.as_str())
+        .filter(|r| !r.is_empty())
+    {
+        parts.push(json!({ "text": reasoning, "thought": true }));
+    }
+    if let Some(text) = message
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_str())
+        .filter(|c| !c.is_empty())
+    {
+        parts.push(json!({ "text": text }));
+    }
+    if let Some(tool_calls) = message
+        .and_then(|m| m.get("tool_calls"))
+        .and_then(|t| t.as_array())
+    {
+        for call in tool_calls {
+            let function = call.get("function");
+            parts.push(function_call_part(
+                call.get("id").and_then(|i| i.as_str()).unwrap_or(""),
+                function
+                    .and_then(|f| f.get("name"))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or(""),
+                function
+                    .and_then(|f| f.get("arguments"))
+                    .and_then(|a| a.as_str())
+                    .unwrap_or(""),
+            ));
+        }
+    }
+
+    let finish_reason = choice
+        .and_then(|c| c.get("finish_reason"))
+        .and_then(|f| f.as_str())
+        .map(convert_finish_reason)
+        .unwrap_or("STOP");
+
+    let mut result = json!({
+        "candidates": [{
+            "content": { "role": "model", "parts": parts },
+            "finishReason": finish_reason,
+            "index": 0
+        }],
+        "modelVersion": model
+    });
+    if let Some(usage) = response.get("usage").and_then(convert_usage) {
+        result["usageMetadata"] = usage;
+    }
+    result
+}
+
+/// 累积中的流式工具调用
+#[derive(Debug, Default)]
+struct PendingToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// OpenAI SSE chunk 到 Gemini 流式响应的转换器
+///
+/// Gemini 的 `functionCall` 必须一次给出完整参数，因此工具调用分片先累积，
+/// 在收到 finish_reason（或流结束）时一次性输出。
+#[derive(Debug)]
+pub struct GeminiStreamConverter {
+    model: String,
+    tool_calls: BTreeMap<u64, PendingToolCall>,
+    usage: Option<Value>,
+    finished: bool,
+}
+
+impl GeminiStreamConverter {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            tool_calls: BTreeMap::new(),
+            usage: None,
+            finished: false,
+        }
+    }
+
+    /// 转换单个 OpenAI chunk，没有需要输出的内容时返回 None
+    pub fn convert_chunk(&mut self, chunk: &Value) -> Option<Value> {
+        if let Some(usage) = chunk.get("usage").and_then(convert_usage) {
+            self.usage = Some(usage);
+        }
+
+        let choice = chunk
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())?;
+        let mut parts = Vec::new();
+
+        if let Some(delta) = choice.get("delta") {
+            if let Some(reasoning) = delta
+                .get("reasoning_content")
+                .and_then(|r| r.as_str())
+                .filter(|r| !r.is_empty())
+            {
+                parts.push(json!({ "text": reasoning, "thought": true }));
+            }
+            if let Some(text) = delta
+                .get("content")
+                .and_then(|c| c.as_str())
+                .filter(|c| !c.is_empty())
+            {
+                parts.push(json!({ "text": text }));
+            }
+            if let Some(tool_calls) = delta.get("tool_calls").and_then(|t| t.as_array()) {
+                for call in tool_calls {
+                    let index = call.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+                    let pending = self.tool_calls.entry(index).or_default();
+                    if let Some(id) = call.get("id").and_then(|i| i.as_str()) {
+                        pending.id = id.to_string();
+                    }
+                    if let Some(function) = call.get("function") {
+                        if let Some(name) = function.get("name").and_then(|n| n.as_str()) {
+                            pending.name.push_str(name);
+                        }
+                        if let Some(arguments) = function.get("arguments").and_then(|a| a.as_str())
+                        {
+                            pending.arguments.push_str(arguments);
+                        }
+                    }
+                }
+            }
+        }
+
+        let finish_reason = choice.get("finish_reason").and_then(|f| f.as_str());
+        if let Some(reason) = finish_reason {
+            parts.extend(self.take_tool_calls());
+            self.finished = true;
+            return Some(self.build_chunk(parts, Some(convert_finish_reason(reason))));
+        }
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(self.build_chunk(parts, None))
+        }
+    }
+
+    /// 流结束时输出未完成的工具调用（上游未发送 finish_reason 时）
+    pub fn finish(&mut self) -> Option<Value> {
+        if self.finished {
+            return None;
+        }
+        self.finished = true;
+        let parts = self.take_tool_calls();
+        Some(self.build_chunk(parts, Some("STOP")))
+    }
+
+    fn take_tool_calls(&mut self) -> Vec<Value> {
+        std::mem::take(&mut self.tool_calls)
+            .into_values()
+            .map(|call| function_call_part(&call.id, &call.name, &call.arguments))
+            .collect()
+    }
+
+    fn build_chunk(&self, parts: Vec<Value>, finish_reason: Option<&str>) -> Value {
+        let mut candidate = json!({
+            "content": { "role": "model", "parts": parts },
+            "index": 0
+        });
+        if let Some(reason) = finish_reason {
+            candidate["finishReason"] = json!(reason);
+        }
+        let mut chunk = json!({
+            "candidates": [candidate],
+            "modelVersion": self.model
+        });
+        if finish_reason.is_some() {
+            if let Some(usage) = &self.usage {
+                chunk["usageMetadata"] = usage.clone();
+            }
+        }
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_request() {
+        let request = json!({
+            "systemInstruction": { "parts": [{ "text": "Be brief." }] },
+            "contents": [
+                { "role": "user", "parts": [{ "text": "Weather in Paris?" }] },
+                { "role": "model", "parts": [{ "functionCall": { "name": "get_weather", "args": { "city": "Paris" } } }] },
+                { "role": "user", "parts": [{ "functionResponse": { "name": "get_weather", "response": { "temp": 20 } } }] }
+            ],
+            "tools": [{ "functionDeclarations": [{
+                "name": "get_weather",
+                "parameters": { "type": "OBJECT", "properties": { "city": { "type": "STRING" } } }
+            }] }],
+            "toolConfig": { "functionCallingConfig": { "mode": "ANY" } },
+            "generationConfig": { "temperature": 0.5, "maxOutputTokens": 128 }
+        });
+
+        let converted = convert_gemini_request_to_openai(&request, "gemini-2.5-pro", true).unwrap();
+        assert!(converted.stream);
+        assert_eq!(converted.max_tokens, Some(128));
+        assert_eq!(converted.tool_choice, Some(json!("required")));
+
+        let roles: Vec<&str> = converted.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "tool"]);
+
+        let call_id = &converted.messages[2].tool_calls.as_ref().unwrap()[0].id;
+        assert_eq!(converted.messages[3].tool_call_id.as_ref(), Some(call_id));
+
+        match &converted.tools.as_ref().unwrap()[0] {
+            Tool::Function { function } => {
+                let params = function.parameters.as_ref().unwrap();
+                assert_eq!(params["type"], "object");
+                assert_eq!(params["properties"]["city"]["type"], "string");
+            }
+            other => panic!("unexpected tool: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_convert_response() {
+        let response = json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": "Checking.",
+                    "tool_calls": [{ "id": "call_1", "type": "function", "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" } }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        });
+
+        let converted = convert_openai_response_to_gemini(&response, "gemini-2.5-pro");
+        let parts = &converted["candidates"][0]["content"]["parts"];
+        assert_eq!(parts[0]["text"], "Checking.");
+        assert_eq!(parts[1]["functionCall"]["args"]["city"], "Paris");
+        assert_eq!(converted["candidates"][0]["finishReason"], "STOP");
+        assert_eq!(converted["usageMetadata"]["totalTokenCount"], 15);
+    }
+
+    #[test]
+    fn test_stream_converter_accumulates_tool_calls() {
+        let mut converter = GeminiStreamConverter::new("gemini-2.5-pro");
+
+        let text = converter
+            .convert_chunk(
+                &json!({ "choices": [{ "delta": { "content": "Hi" }, "finish_reason": null }] }),
+            )
+            .unwrap();
+        assert_eq!(text["candidates"][0]["content"]["parts"][0]["text"], "Hi");
+
+        assert!(converter
+            .convert_chunk(&json!({ "choices": [{ "delta": { "tool_calls": [{ "index": 0, "id": "call_1", "type": "function", "function": { "name": "get_weather", "arguments": "" } }] } }] }))
+            .is_none());
+        assert!(converter
+            .convert_chunk(&json!({ "choices": [{ "delta": { "tool_calls": [{ "index": 0, "function": { "arguments": "{\"city\":\"Paris\"}" } }] } }] }))
+            .is_none());
+
+        let last = converter
+            .convert_chunk(&json!({ "choices": [{ "delta": {}, "finish_reason": "tool_calls" }] }))
+            .unwrap();
+        let call = &last["candidates"][0]["content"]["parts"][0]["functionCall"];
+        assert_eq!(call["name"], "get_weather");
+        assert_eq!(call["args"]["city"], "Paris");
+        assert_eq!(last["candidates"][0]["finishReason"], "STOP");
+        assert!(converter.finish().is_none());
+    }
+}
@@ -0,0 +1,440 @@
+//! Gemini / Antigravity 工具调用转换
+//!
+//! OpenAI 格式 -> Gemini 格式：
+//! - 工具定义：`tools[].function` -> `tools[].functionDeclarations`，
+//!   展开 Gemini 不支持的 JSON Schema 写法（`$ref`、`allOf`、`const`，OpenAPI 模式下还有 `type` 数组和可空 `anyOf`）
+//! - 工具选择：OpenAI / Anthropic 的 `tool_choice` -> `toolConfig.functionCallingConfig`
+//! - 工具结果：tool 消息内容 -> `functionResponse.response.result`
+//!
+//! Gemini 格式 -> OpenAI 格式：
+//! - 工具调用：`functionCall` -> `tool_calls`
+//!
+//! Gemini 请求到 OpenAI 请求的转换见 `gemini_to_openai`。
+
+use crate::models::openai::{FunctionCall, Tool, ToolCall};
+use serde_json::{json, Map, Value};
+
+/// Schema 方言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaDialect {
+    /// `parametersJsonSchema`：支持 `type` 数组和 `anyOf`
+    JsonSchema,
+    /// `parameters`：OpenAPI 3.0 子集，可空类型使用 `nullable`
+    OpenApi,
+}
+
+/// Gemini 不支持的 Schema 字段
+const EXCLUDED_SCHEMA_KEYS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "$defs",
+    "definitions",
+    "additionalProperties",
+    "patternProperties",
+    "minLength",
+    "maxLength",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "examples",
+    "strict",
+];
+
+/// `$ref` 最大展开深度（递归引用超过该深度时退化为 object）
+const MAX_REF_DEPTH: usize = 8;
+
+/// 转换工具定义为 `[{"functionDeclarations": [...]}]`，没有可转换的函数工具时返回 `None`
+pub fn convert_tools_to_gemini(tools: &[Tool], dialect: SchemaDialect) -> Option<Value> {
+    let declarations: Vec<Value> = tools
+        .iter()
+        .filter_map(|tool| match tool {
+            Tool::Function { function } => {
+                let schema = function
+                    .parameters
+                    .as_ref()
+                    .map(|p| to_gemini_schema(p, dialect))
+                    .unwrap_or_else(|| json!({"type": "object", "properties": {}}));
+                let schema_key = match dialect {
+                    SchemaDialect::JsonSchema => "parametersJsonSchema",
+                    SchemaDialect::OpenApi => "parameters",
+                };
+                Some(json!({
+                    "name": function.name,
+                    "description": function.description.clone().unwrap_or_default(),
+                    schema_key: schema
+                }))
+            }
+            // web_search 工具不转换
+            Tool::WebSearch | Tool::WebSearch20250305 => None,
+        })
+        .collect();
+
+    if declarations.is_empty() {
+        None
+    } else {
+        Some(json!([{ "functionDeclarations": declarations }]))
+    }
+}
+
+/// 转换工具参数 Schema，顶层保证为带 `properties` 的 object
+pub fn to_gemini_schema(schema: &Value, dialect: SchemaDialect) -> Value {
+    let mut defs = Map::new();
+    for key in ["definitions", "$defs"] {
+        if let Some(Value::Object(map)) = schema.get(key) {
+            defs.extend(map.clone());
+        }
+    }
+
+    let mut result = convert_schema(schema, &defs, dialect, 0);
+    let Value::Object(map) = &mut result else {
+        return json!({"type": "object", "properties": {}});
+    };
+    map.entry("type").or_insert_with(|| json!("object"));
+    if map.get("type") == Some(&json!("object")) {
+        map.entry("properties").or_insert_with(|| json!({}));
+    }
+    result
+}
+
+fn convert_schema(
+    schema: &Value,
+    defs: &Map<String, Value>,
+    dialect: SchemaDialect,
+    depth: usize,
+) -> Value {
+    let Value::Object(map) = schema else {
+        return schema.clone();
+    };
+
+    if let Some(reference) = map.get("$ref").and_then(|r| r.as_str()) {
+        let name = reference.rsplit('/').next().unwrap_or(reference);
+        let mut resolved = match defs.get(name) {
+            Some(target) if depth < MAX_REF_DEPTH => {
+                convert_schema(target, defs, dialect, depth + 1)
+            }
+            _ => json!({"type": "object"}),
+        };
+        // `$ref` 同级的 description 优先
+        if let (Value::Object(resolved), Some(description)) =
+            (&mut resolved, map.get("description"))
+        {
+            resolved.insert("description".to_string(), description.clone());
+        }
+        return resolved;
+    }
+
+    let mut out = Map::new();
+    let mut nullable = false;
+
+    for (key, value) in map {
+        if EXCLUDED_SCHEMA_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        match key.as_str() {
+            "type" if dialect == SchemaDialect::OpenApi && value.is_array() => {
+                let types = value.as_array().map(Vec::as_slice).unwrap_or_default();
+                nullable |= types.iter().any(|t| t == "null");
+                if let Some(t) = types.iter().find(|t| *t != "null") {
+                    out.insert("type".to_string(), t.clone());
+                }
+            }
+            "const" => {
+                out.insert("enum".to_string(), json!([value]));
+            }
+            "anyOf" | "oneOf" => {
+                let mut branches: Vec<Value> = value
+                    .as_array()
+                    .map(|items| {
+                        items
+                            .iter()
+                            .map(|b| convert_schema(b, defs, dialect, depth))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if dialect == SchemaDialect::OpenApi {
+                    let before = branches.len();
+                    branches.retain(|b| b.get("type") != Some(&json!("null")));
+                    nullable |= branches.len() < before;
+                }
+                match branches.len() {
+                    0 => {}
+                    1 => merge_schema(&mut out, branches.remove(0)),
+                    _ => {
+                        out.insert("anyOf".to_string(), Value::Array(branches));
+                    }
+                }
+            }
+            "allOf" => {
+                for branch in value.as_array().into_iter().flatten() {
+                    merge_schema(&mut out, convert_schema(branch, defs, dialect, depth));
+                }
+            }
+            "properties" => {
+                let properties: Map<String, Value> = value
+                    .as_object()
+                    .map(|props| {
+                        props
+                            .iter()
+                            .map(|(name, prop)| {
+                                (name.clone(), convert_schema(prop, defs, dialect, depth))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                merge_schema(&mut out, json!({ "properties": properties }));
+            }
+            "items" => {
+                out.insert(key.clone(), convert_schema(value, defs, dialect, depth));
+            }
+            _ => {
+                out.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    if nullable {
+        out.insert("nullable".to_string(), Value::Bool(true));
+    }
+    Value::Object(out)
+}
+
+/// 合并子 Schema：`properties` 和 `required` 取并集，其余字段保留已有值
+fn merge_schema(target: &mut Map<String, Value>, source: Value) {
+    let Value::Object(source) = source else {
+        return;
+    };
+    for (key, value) in source {
+        match (key.as_str(), target.get_mut(&key), value) {
+            ("properties", Some(Value::Object(existing)), Value::Object(props)) => {
+                existing.extend(props);
+            }
+            ("required", Some(Value::Array(existing)), Value::Array(required)) => {
+                for name in required {
+                    if !existing.contains(&name) {
+                        existing.push(name);
+                    }
+                }
+            }
+            (_, Some(_), _) => {}
+            (_, None, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+/// 转换 OpenAI / Anthropic 的 `tool_choice` 为 `toolConfig`
+///
+/// - `"none"` / `{"type": "none"}` -> `NONE`
+/// - `"required"` / `{"type": "any"}` -> `ANY`
+/// - `{"type": "function", "function": {"name"}}` / `{"type": "tool", "name"}` -> `ANY` + `allowedFunctionNames`
+/// - 其他 -> `AUTO`
+pub fn convert_tool_choice_to_gemini(tool_choice: Option<&Value>) -> Value {
+    let (mode, name) = match tool_choice {
+        Some(Value::String(choice)) => match choice.as_str() {
+            "none" => ("NONE", None),
+            "required" | "any" => ("ANY", None),
+            _ => ("AUTO", None),
+        },
+        Some(Value::Object(choice)) => match choice.get("type").and_then(|t| t.as_str()) {
+            Some("none") => ("NONE", None),
+            Some("any") => ("ANY", None),
+            Some("function") => (
+                "ANY",
+                choice
+                    .get("function")
+                    .and_then(|f| f.get("name"))
+                    .and_then(|n| n.as_str()),
+            ),
+            Some("tool") => ("ANY", choice.get("name").and_then(|n| n.as_str())),
+            _ => ("AUTO", None),
+        },
+        _ => ("AUTO", None),
+    };
+
+    let mut config = json!({ "mode": mode });
+    if let Some(name) = name {
+        config["allowedFunctionNames"] = json!([name]);
+    }
+    json!({ "functionCallingConfig": config })
+}
+
+/// 转换 tool 消息内容为 `functionResponse.response.result`（JSON 内容保留结构，其余作为字符串）
+pub fn tool_result_to_gemini(content: &str) -> Value {
+    if content.is_empty() || content == "null" {
+        return json!({});
+    }
+    serde_json::from_str(content).unwrap_or_else(|_| Value::String(content.to_string()))
+}
+
+/// 转换 Gemini `functionCall` 为 OpenAI 工具调用（没有 ID 时生成）
+pub fn function_call_to_openai(call: &Value) -> ToolCall {
+    let id = call
+        .get("id")
+        .and_then(|id| id.as_str())
+        .filter(|id| !id.is_empty())
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| format!("call_{}", &uuid::Uuid::new_v4().simple().to_string()[..8]));
+    let arguments = match call.get("args") {
+        Some(Value::String(args)) => args.clone(),
+        Some(args) => args.to_string(),
+        None => "{}".to_string(),
+    };
+    ToolCall {
+        id,
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: call
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or("")
+                .to_string(),
+            arguments,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::openai::FunctionDef;
+
+    fn schema() -> Value {
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "minLength": 1},
+                "mode": {"const": "overwrite"},
+                "limit": {"type": ["integer", "null"]},
+                "target": {"$ref": "#/$defs/Target", "description": "写入目标"},
+                "encoding": {"anyOf": [{"type": "string"}, {"type": "null"}]}
+            },
+            "required": ["path"],
+            "additionalProperties": false,
+            "$defs": {
+                "Target": {
+                    "allOf": [
+                        {"type": "object", "properties": {"host": {"type": "string"}}, "required": ["host"]},
+                        {"properties": {"port": {"type": "integer"}}, "required": ["port"]}
+                    ]
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_openapi_schema() {
+        let converted = to_gemini_schema(&schema(), SchemaDialect::OpenApi);
+        assert_eq!(
+            converted,
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string"},
+                    "mode": {"enum": ["overwrite"]},
+                    "limit": {"type": "integer", "nullable": true},
+                    "target": {
+                        "type": "object",
+                        "properties": {"host": {"type": "string"}, "port": {"type": "integer"}},
+                        "required": ["host", "port"],
+                        "description": "写入目标"
+                    },
+                    "encoding": {"type": "string", "nullable": true}
+                },
+                "required": ["path"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_schema_keeps_nullable_types() {
+        let converted = to_gemini_schema(&schema(), SchemaDialect::JsonSchema);
+        assert_eq!(
+            converted["properties"]["limit"],
+            json!({"type": ["integer", "null"]})
+        );
+        assert_eq!(
+            converted["properties"]["encoding"],
+            json!({"anyOf": [{"type": "string"}, {"type": "null"}]})
+        );
+        assert!(converted.get("$defs").is_none());
+    }
+
+    #[test]
+    fn test_recursive_ref_is_bounded() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"node": {"$ref": "#/definitions/Node"}},
+            "definitions": {
+                "Node": {"type": "object", "properties": {"child": {"$ref": "#/definitions/Node"}}}
+            }
+        });
+        let converted = to_gemini_schema(&schema, SchemaDialect::OpenApi);
+        assert_eq!(converted["properties"]["node"]["type"], "object");
+    }
+
+    #[test]
+    fn test_convert_tools() {
+        let tools = vec![
+            Tool::Function {
+                function: FunctionDef {
+                    name: "read_file".to_string(),
+                    description: Some("Read a file".to_string()),
+                    parameters: None,
+                },
+            },
+            Tool::WebSearch,
+        ];
+        assert_eq!(
+            convert_tools_to_gemini(&tools, SchemaDialect::JsonSchema).unwrap(),
+            json!([{"functionDeclarations": [{
+                "name": "read_file",
+                "description": "Read a file",
+                "parametersJsonSchema": {"type": "object", "properties": {}}
+            }]}])
+        );
+        assert!(convert_tools_to_gemini(&tools[1..], SchemaDialect::OpenApi).is_none());
+    }
+
+    #[test]
+    fn test_convert_tool_choice() {
+        let mode = |choice: Option<Value>| convert_tool_choice_to_gemini(choice.as_ref());
+        assert_eq!(mode(None)["functionCallingConfig"]["mode"], "AUTO");
+        assert_eq!(
+            mode(Some(json!("none")))["functionCallingConfig"]["mode"],
+            "NONE"
+        );
+        assert_eq!(
+            mode(Some(json!({"type": "any"})))["functionCallingConfig"]["mode"],
+            "ANY"
+        );
+        assert_eq!(
+            mode(Some(
+                json!({"type": "function", "function": {"name": "read_file"}})
+            )),
+            json!({"functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["read_file"]}})
+        );
+        assert_eq!(
+            mode(Some(json!({"type": "tool", "name": "bash"}))),
+            json!({"functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["bash"]}})
+        );
+    }
+
+    #[test]
+    fn test_tool_results_and_calls() {
+        assert_eq!(tool_result_to_gemini(""), json!({}));
+        assert_eq!(tool_result_to_gemini("{\"ok\":true}"), json!({"ok": true}));
+        assert_eq!(tool_result_to_gemini("done"), json!("done"));
+
+        let call = function_call_to_openai(&json!({"name": "bash", "args": {"cmd": "ls"}}));
+        assert!(call.id.starts_with("call_"));
+        assert_eq!(call.function.name, "bash");
+        assert_eq!(call.function.arguments, "{\"cmd\":\"ls\"}");
+
+        let call = function_call_to_openai(&json!({"id": "fc_1", "name": "bash"}));
+        assert_eq!(call.id, "fc_1");
+        assert_eq!(call.function.arguments, "{}");
+    }
+}
@@ -0,0 +1,377 @@
+//! 多模态图片内容转换
+//!
+//! 在各协议的图片表示之间转换：
+//! - Anthropic: `{"type": "image", "source": {"type": "base64" | "url", ...}}`
+//! - OpenAI: `{"type": "image_url", "image_url": {"url": "data:image/png;base64,..." | "https://..."}}`
+//! - Gemini: `{"inlineData": {"mimeType", "data"}}` / `{"fileData": {"mimeType", "fileUri"}}`
+//! - CodeWhisperer: `{"format": "png", "source": {"bytes": "..."}}`
+//!
+//! 各上游对单张图片的大小和边长限制不同，超出限制时按比例缩小并重新编码为 JPEG。
+
+use crate::models::codewhisperer::{CWImage, CWImageSource};
+use crate::models::openai::{ContentPart, ImageUrl};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader};
+use serde_json::{json, Value};
+use std::io::Cursor;
+
+/// 上游对单张图片的限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+    /// 解码后的最大字节数
+    pub max_bytes: usize,
+    /// 最长边的最大像素数
+    pub max_dimension: u32,
+}
+
+/// Anthropic：单张 5 MB，最长边 8000 px
+pub const ANTHROPIC_IMAGE_LIMITS: ImageLimits = ImageLimits {
+    max_bytes: 5 * 1024 * 1024,
+    max_dimension: 8000,
+};
+
+/// OpenAI：单张 20 MB，高清模式下上游会缩放到 2048 px 以内
+pub const OPENAI_IMAGE_LIMITS: ImageLimits = ImageLimits {
+    max_bytes: 20 * 1024 * 1024,
+    max_dimension: 2048,
+};
+
+/// Gemini：内联数据整个请求不超过 20 MB，单张按 7 MB 控制
+pub const GEMINI_IMAGE_LIMITS: ImageLimits = ImageLimits {
+    max_bytes: 7 * 1024 * 1024,
+    max_dimension: 3072,
+};
+
+/// CodeWhisperer：单张 3.75 MB，最长边 8000 px
+pub const CW_IMAGE_LIMITS: ImageLimits = ImageLimits {
+    max_bytes: 3_932_160,
+    max_dimension: 8000,
+};
+
+/// 压缩时依次尝试的 JPEG 质量
+const JPEG_QUALITIES: &[u8] = &[85, 70, 55];
+
+/// 缩小到该边长仍超出大小限制时放弃
+const MIN_DIMENSION: u32 = 64;
+
+/// 与协议无关的图片内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageContent {
+    /// 内联 base64 数据
+    Base64 { media_type: String, data: String },
+    /// 远程 URL
+    Url(String),
+}
+
+impl ImageContent {
+    /// 解析 Anthropic 图片块
+    pub fn from_anthropic(block: &Value) -> Option<Self> {
+        let source = block.get("source")?;
+        match source.get("type").and_then(|t| t.as_str()) {
+            Some("base64") => {
+                let data = source.get("data").and_then(|d| d.as_str())?;
+                if data.is_empty() {
+                    return None;
+                }
+                let media_type = source
+                    .get("media_type")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("image/jpeg");
+                Some(ImageContent::Base64 {
+                    media_type: media_type.to_string(),
+                    data: data.to_string(),
+                })
+            }
+            Some("url") => source
+                .get("url")
+                .and_then(|u| u.as_str())
+                .filter(|u| !u.is_empty())
+                .map(|u| ImageContent::Url(u.to_string())),
+            _ => None,
+        }
+    }
+
+    /// 解析 OpenAI `image_url.url`（data URL 或远程 URL）
+    pub fn from_openai_url(url: &str) -> Self {
+        match parse_data_url(url) {
+            Some((media_type, data)) => ImageContent::Base64 { media_type, data },
+            None => ImageContent::Url(url.to_string()),
+        }
+    }
+
+    /// 解析 Gemini `inlineData` / `fileData` 部分
+    pub fn from_gemini(part: &Value) -> Option<Self> {
+        if let Some(inline) = part.get("inlineData").or_else(|| part.get("inline_data")) {
+            let data = inline.get("data").and_then(|d| d.as_str())?;
+            let media_type = inline
+                .get("mimeType")
+                .or_else(|| inline.get("mime_type"))
+                .and_then(|m| m.as_str())
+                .unwrap_or("image/png");
+            return Some(ImageContent::Base64 {
+                media_type: media_type.to_string(),
+                data: data.to_string(),
+            });
+        }
+        let file = part.get("fileData").or_else(|| part.get("file_data"))?;
+        file.get("fileUri")
+            .or_else(|| file.get("file_uri"))
+            .and_then(|u| u.as_str())
+            .map(|u| ImageContent::Url(u.to_string()))
+    }
+
+    pub fn to_anthropic(&self) -> Value {
+        match self {
+            ImageContent::Base64 { media_type, data } => json!({
+                "type": "image",
+                "source": {"type": "base64", "media_type": media_type, "data": data}
+            }),
+            ImageContent::Url(url) => json!({
+                "type": "image",
+                "source": {"type": "url", "url": url}
+            }),
+        }
+    }
+
+    pub fn to_openai(&self) -> ContentPart {
+        let url = match self {
+            ImageContent::Base64 { media_type, data } => data_url(media_type, data),
+            ImageContent::Url(url) => url.clone(),
+        };
+        ContentPart::ImageUrl {
+            image_url: ImageUrl { url, detail: None },
+        }
+    }
+
+    /// 转为 Gemini 部分（远程 URL 使用 `fileData`，MIME 类型按扩展名推断）
+    pub fn to_gemini(&self) -> Value {
+        match self {
+            ImageContent::Base64 { media_type, data } => json!({
+                "inlineData": {"mimeType": media_type, "data": data}
+            }),
+            ImageContent::Url(url) => json!({
+                "fileData": {"mimeType": media_type_from_url(url), "fileUri": url}
+            }),
+        }
+    }
+
+    /// 确保图片满足上游限制，超出时缩小并重新编码为 JPEG
+    ///
+    /// 远程 URL 由上游自行下载，原样返回；无法识别格式但大小合规的图片也原样返回。
+    pub fn fit_limits(self, limits: &ImageLimits) -> Result<Self, String> {
+        let ImageContent::Base64 { data, .. } = &self else {
+            return Ok(self);
+        };
+        let bytes = BASE64
+            .decode(data.trim())
+            .map_err(|e| format!("图片 base64 解码失败: {}", e))?;
+
+        if bytes.len() <= limits.max_bytes {
+            match image_dimensions(&bytes) {
+                Some((width, height)) if width.max(height) > limits.max_dimension => {}
+                _ => return Ok(self),
+            }
+        }
+
+        let img = image::load_from_memory(&bytes).map_err(|e| format!("图片解码失败: {}", e))?;
+        let encoded = downscale(img, limits)?;
+        tracing::info!(
+            "[IMAGE] 图片超出限制，已压缩: {} -> {} 字节",
+            bytes.len(),
+            encoded.len()
+        );
+        Ok(ImageContent::Base64 {
+            media_type: "image/jpeg".to_string(),
+            data: BASE64.encode(encoded),
+        })
+    }
+
+    /// 转为满足 CodeWhisperer 限制的图片，远程 URL 和无法处理的图片返回替代文本
+    pub fn into_cw_image(self) -> Result<CWImage, String> {
+        match self.fit_or_placeholder(&CW_IMAGE_LIMITS)? {
+            ImageContent::Base64 { media_type, data } => Ok(CWImage {
+                format: image_format(&media_type),
+                source: CWImageSource { bytes: data },
+            }),
+            ImageContent::Url(url) => {
+                tracing::warn!("[IMAGE] CodeWhisperer 不支持 URL 图片，转为文本: {}", url);
+                Err(format!("[Image: {}]", url))
+            }
+        }
+    }
+
+    /// 同 [`fit_limits`](Self::fit_limits)，失败时返回说明文本，供调用方替换为文本内容
+    pub fn fit_or_placeholder(self, limits: &ImageLimits) -> Result<Self, String> {
+        self.fit_limits(limits).map_err(|e| {
+            tracing::warn!("[IMAGE] 图片无法发送到上游，已替换为文本: {}", e);
+            format!("[Image omitted: {}]", e)
+        })
+    }
+}
+
+/// 解析 data URL，返回 (media_type, base64 数据)
+pub fn parse_data_url(url: &str) -> Option<(String, String)> {
+    let (meta, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let media_type = meta.split(';').next().filter(|m| !m.is_empty());
+    Some((
+        media_type.unwrap_or("image/jpeg").to_string(),
+        data.to_string(),
+    ))
+}
+
+pub fn data_url(media_type: &str, data: &str) -> String {
+    format!("data:{};base64,{}", media_type, data)
+}
+
+/// 从 MIME 类型提取格式（`image/jpeg` -> `jpeg`，`image/jpg` -> `jpeg`）
+pub fn image_format(media_type: &str) -> String {
+    match media_type.split('/').nth(1).unwrap_or("jpeg") {
+        "jpg" => "jpeg".to_string(),
+        format => format.to_string(),
+    }
+}
+
+/// 按 URL 扩展名推断图片 MIME 类型，无法识别时视为 JPEG
+pub fn media_type_from_url(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    match path.rsplit('.').next() {
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("heic") => "image/heic",
+        _ => "image/jpeg",
+    }
+}
+
+/// 只读取图片头部获取尺寸
+fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+fn downscale(img: DynamicImage, limits: &ImageLimits) -> Result<Vec<u8>, String> {
+    let mut img = if img.width().max(img.height()) > limits.max_dimension {
+        img.resize(
+            limits.max_dimension,
+            limits.max_dimension,
+            FilterType::Triangle,
+        )
+    } else {
+        img
+    };
+
+    loop {
+        for quality in JPEG_QUALITIES {
+            let encoded = encode_jpeg(&img, *quality)?;
+            if encoded.len() <= limits.max_bytes {
+                return Ok(encoded);
+            }
+        }
+        let (width, height) = (img.width() * 3 / 4, img.height() * 3 / 4);
+        if width.max(height) < MIN_DIMENSION {
+            return Err(format!("无法压缩到 {} 字节以内", limits.max_bytes));
+        }
+        img = img.resize(width, height, FilterType::Triangle);
+    }
+}
+
+fn encode_jpeg(img: &DynamicImage, quality: u8) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::new();
+    JpegEncoder::new_with_quality(&mut buf, quality)
+        .encode_image(&img.to_rgb8())
+        .map_err(|e| format!("图片编码失败: {}", e))?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    fn png_base64(width: u32, height: u32) -> String {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x ^ y) % 256) as u8])
+        }));
+        let mut buf = Cursor::new(Vec::new());
+        img.write_to(&mut buf, ImageFormat::Png).unwrap();
+        BASE64.encode(buf.into_inner())
+    }
+
+    #[test]
+    fn test_round_trip_between_formats() {
+        let block = json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}
+        });
+        let image = ImageContent::from_anthropic(&block).unwrap();
+        let ContentPart::ImageUrl { image_url } = image.to_openai() else {
+            panic!("expected image_url part");
+        };
+        assert_eq!(image_url.url, "data:image/png;base64,AAAA");
+        assert_eq!(ImageContent::from_openai_url(&image_url.url), image);
+        assert_eq!(
+            ImageContent::from_gemini(&image.to_gemini()).unwrap(),
+            image.clone()
+        );
+        assert_eq!(image.to_anthropic(), block);
+        assert_eq!(image.into_cw_image().unwrap().format, "png");
+
+        let url = ImageContent::from_anthropic(&json!({
+            "type": "image",
+            "source": {"type": "url", "url": "https://example.com/cat.webp?x=1"}
+        }))
+        .unwrap();
+        assert_eq!(
+            url.to_gemini(),
+            json!({"fileData": {"mimeType": "image/webp", "fileUri": "https://example.com/cat.webp?x=1"}})
+        );
+        assert_eq!(
+            url.into_cw_image().unwrap_err(),
+            "[Image: https://example.com/cat.webp?x=1]"
+        );
+    }
+
+    #[test]
+    fn test_fit_limits_keeps_small_images() {
+        let image = ImageContent::Base64 {
+            media_type: "image/png".to_string(),
+            data: png_base64(32, 16),
+        };
+        assert_eq!(
+            image.clone().fit_limits(&ANTHROPIC_IMAGE_LIMITS).unwrap(),
+            image
+        );
+    }
+
+    #[test]
+    fn test_fit_limits_downscales_large_images() {
+        let image = ImageContent::Base64 {
+            media_type: "image/png".to_string(),
+            data: png_base64(400, 200),
+        };
+        let limits = ImageLimits {
+            max_bytes: 1024 * 1024,
+            max_dimension: 100,
+        };
+        let ImageContent::Base64 { media_type, data } = image.fit_limits(&limits).unwrap() else {
+            panic!("expected base64 image");
+        };
+        assert_eq!(media_type, "image/jpeg");
+        let bytes = BASE64.decode(data).unwrap();
+        assert_eq!(image_dimensions(&bytes), Some((100, 50)));
+    }
+
+    #[test]
+    fn test_fit_limits_rejects_invalid_data() {
+        let image = ImageContent::Base64 {
+            media_type: "image/png".to_string(),
+            data: "not base64!".to_string(),
+        };
+        assert!(image.fit_or_placeholder(&CW_IMAGE_LIMITS).is_err());
+    }
+}
@@ -1,16 +1,34 @@
 pub mod anthropic_to_openai;
 pub mod cw_to_openai;
+pub mod gemini_to_openai;
+pub mod gemini_tools;
+pub mod image_content;
 pub mod openai_to_antigravity;
 pub mod openai_to_cw;
+pub mod openai_to_gemini_embeddings;
 pub mod protocol_selector;
+pub mod reasoning;
+pub mod response_format;
 
 #[allow(unused_imports)]
 pub use anthropic_to_openai::*;
 #[allow(unused_imports)]
 pub use cw_to_openai::*;
 #[allow(unused_imports)]
+pub use gemini_to_openai::*;
+#[allow(unused_imports)]
+pub use gemini_tools::*;
+#[allow(unused_imports)]
+pub use image_content::*;
+#[allow(unused_imports)]
 pub use openai_to_antigravity::*;
 #[allow(unused_imports)]
 pub use openai_to_cw::*;
 #[allow(unused_imports)]
+pub use openai_to_gemini_embeddings::*;
+#[allow(unused_imports)]
 pub use protocol_selector::*;
+#[allow(unused_imports)]
+pub use reasoning::*;
+#[allow(unused_imports)]
+pub use response_format::*;
@@ -14,6 +14,12 @@
 //! ## 更新日志
 //! - 2025-12-28: 修复请求格式，对齐 CLIProxyAPI 实现
 
+use super::gemini_tools::{
+    convert_tool_choice_to_gemini, convert_tools_to_gemini, function_call_to_openai,
+    tool_result_to_gemini, SchemaDialect,
+};
+use super::image_content::{media_type_from_url, ImageContent, GEMINI_IMAGE_LIMITS};
+use super::response_format::ResponseFormat;
 use crate::models::openai::*;
 use crate::session::{get_thought_signature, SessionManager};
 use serde::{Deserialize, Serialize};
@@ -39,6 +45,8 @@ pub struct GeminiPart {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub inline_data: Option<InlineData>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_data: Option<FileData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub function_call: Option<GeminiFunctionCall>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function_response: Option<GeminiFunctionResponse>,
@@ -54,6 +62,14 @@ pub struct InlineData {
     pub data: String,
 }
 
+/// 远程文件（图片 URL）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileData {
+    pub mime_type: String,
+    pub file_uri: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeminiFunctionCall {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -133,6 +149,12 @@ pub struct GeminiGenerationConfig {
     /// 响应模态（TEXT, IMAGE）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_modalities: Option<Vec<String>>,
+    /// 结构化输出的 MIME 类型（application/json）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mime_type: Option<String>,
+    /// 结构化输出的 Schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -286,6 +308,7 @@ fn is_claude_model(model: &str) -> bool {
 /// 将 OpenAI ChatCompletionRequest 转换为 Antigravity 请求体
 ///
 /// 参考 CLIProxyAPI 的实现，确保请求格式正确。
+#[tracing::instrument(name = "convert.openai_to_antigravity", skip_all)]
 pub fn convert_openai_to_antigravity_with_context(
     request: &ChatCompletionRequest,
     project_id: &str,
@@ -344,6 +367,7 @@ pub fn convert_openai_to_antigravity_with_context(
                             parts: vec![GeminiPart {
                                 text: Some(text),
                                 inline_data: None,
+                                file_data: None,
                                 function_call: None,
                                 function_response: None,
                                 thought_signature: None,
@@ -379,6 +403,7 @@ pub fn convert_openai_to_antigravity_with_context(
                     parts.push(GeminiPart {
                         text: Some(text),
                         inline_data: None,
+                        file_data: None,
                         function_call: None,
                         function_response: None,
                         thought_signature: None,
@@ -389,18 +414,7 @@ pub fn convert_openai_to_antigravity_with_context(
                 if let Some(MessageContent::Parts(content_parts)) = &msg.content {
                     for part in content_parts {
                         if let ContentPart::ImageUrl { image_url } = part {
-                            if let Some((mime, data)) = parse_data_url(&image_url.url) {
-                                parts.push(GeminiPart {
-                                    text: None,
-                                    inline_data: Some(InlineData {
-                                        mime_type: mime,
-                                        data,
-                                    }),
-                                    function_call: None,
-                                    function_response: None,
-                                    thought_signature: None,
-                                });
-                            }
+                            parts.push(convert_image_part(image_url));
                         }
                     }
                 }
@@ -424,6 +438,7 @@ pub fn convert_openai_to_antigravity_with_context(
                         parts.push(GeminiPart {
                             text: None,
                             inline_data: None,
+                            file_data: None,
                             function_call: Some(GeminiFunctionCall {
                                 id: Some(tc.id.clone()),
                                 name: tc.function.name.clone(),
@@ -450,20 +465,12 @@ pub fn convert_openai_to_antigravity_with_context(
                         if let Some(name) = tc_id_to_name.get(fid) {
                             let resp = tool_responses.get(fid).cloned().unwrap_or_default();
 
-                            // 解析响应内容
-                            let result_value: serde_json::Value =
-                                if resp.is_empty() || resp == "null" {
-                                    serde_json::json!({})
-                                } else {
-                                    serde_json::from_str(&resp).unwrap_or_else(|_| {
-                                        // 非 JSON 内容，作为字符串
-                                        serde_json::Value::String(resp.clone())
-                                    })
-                                };
+                            let result_value = tool_result_to_gemini(&resp);
 
                             tool_parts.push(GeminiPart {
                                 text: None,
                                 inline_data: None,
+                                file_data: None,
                                 function_call: None,
                                 function_response: Some(GeminiFunctionResponse {
                                     id: Some(fid.clone()),
@@ -509,17 +516,12 @@ pub fn convert_openai_to_antigravity_with_context(
                     let content = msg.get_content_text();
                     let function_name = tc_id_to_name.get(&tool_id).cloned().unwrap_or_default();
 
-                    let result_value: serde_json::Value = if content.is_empty() || content == "null"
-                    {
-                        serde_json::json!({})
-                    } else {
-                        serde_json::from_str(&content)
-                            .unwrap_or_else(|_| serde_json::Value::String(content.clone()))
-                    };
+                    let result_value = tool_result_to_gemini(&content);
 
                     let function_response = GeminiPart {
                         text: None,
                         inline_data: None,
+                        file_data: None,
                         function_call: None,
                         function_response: Some(GeminiFunctionResponse {
                             id: Some(tool_id),
@@ -566,6 +568,8 @@ pub fn convert_openai_to_antigravity_with_context(
         candidate_count: None,
         thinking_config: None,
         response_modalities: None,
+        response_mime_type: None,
+        response_schema: None,
     };
 
     // 为图片生成模型设置 response_modalities
@@ -622,71 +626,31 @@ pub fn convert_openai_to_antigravity_with_context(
 
     // 转换工具定义
     // 注意：Antigravity API 统一使用 functionDeclarations 格式
-    // Claude 和 Gemini 模型都使用相同的结构，但字段名可能不同
-    let tools: Option<serde_json::Value> = request.tools.as_ref().and_then(|tools| {
-        let is_claude = is_claude_model(actual_model);
-        let mut function_declarations: Vec<serde_json::Value> = Vec::new();
-
-        for t in tools {
-            match t {
-                Tool::Function { function } => {
-                    let params_schema = function
-                        .parameters
-                        .as_ref()
-                        .map(|p| {
-                            let mut schema = clean_parameters(Some(p.clone())).unwrap_or_default();
-                            // 确保有 type 和 properties
-                            if schema.get("type").is_none() {
-                                schema["type"] = serde_json::json!("object");
-                            }
-                            if schema.get("properties").is_none() {
-                                schema["properties"] = serde_json::json!({});
-                            }
-                            schema
-                        })
-                        .unwrap_or_else(|| serde_json::json!({"type": "object", "properties": {}}));
-
-                    if is_claude {
-                        // Claude 模型使用 parameters 字段（标准 Gemini 格式）
-                        function_declarations.push(serde_json::json!({
-                            "name": function.name,
-                            "description": function.description.clone().unwrap_or_default(),
-                            "parameters": params_schema
-                        }));
-                    } else {
-                        // Gemini 模型使用 parametersJsonSchema 字段
-                        function_declarations.push(serde_json::json!({
-                            "name": function.name,
-                            "description": function.description.clone().unwrap_or_default(),
-                            "parametersJsonSchema": params_schema
-                        }));
-                    }
-                }
-                Tool::WebSearch | Tool::WebSearch20250305 => {
-                    // web_search 工具不转换
-                }
-            }
-        }
-
-        if function_declarations.is_empty() {
-            None
-        } else {
-            Some(serde_json::json!([{
-                "functionDeclarations": function_declarations
-            }]))
-        }
-    });
-
-    // 构建 toolConfig（如果有工具定义）
-    let tool_config: Option<serde_json::Value> = if tools.is_some() {
-        Some(serde_json::json!({
-            "functionCallingConfig": {
-                "mode": "AUTO"
-            }
-        }))
+    // Claude 模型使用 parameters 字段（OpenAPI Schema），Gemini 模型使用 parametersJsonSchema 字段
+    let dialect = if is_claude_model(actual_model) {
+        SchemaDialect::OpenApi
     } else {
-        None
+        SchemaDialect::JsonSchema
     };
+    let tools: Option<serde_json::Value> = request
+        .tools
+        .as_ref()
+        .and_then(|tools| convert_tools_to_gemini(tools, dialect));
+
+    // 构建 toolConfig（如果有工具定义），按 tool_choice 设置调用模式
+    let tool_config: Option<serde_json::Value> = tools
+        .as_ref()
+        .map(|_| convert_tool_choice_to_gemini(request.tool_choice.as_ref()));
+
+    // 结构化输出（Gemini 不支持函数调用与 JSON 模式同时使用，有工具定义时忽略）
+    if let Some(format) = ResponseFormat::from_request(request) {
+        if tools.is_none() {
+            generation_config.response_mime_type = Some("application/json".to_string());
+            generation_config.response_schema = format.gemini_response_schema();
+        } else {
+            tracing::warn!("[CONVERT] 请求包含工具定义，忽略 response_format");
+        }
+    }
 
     // 使用 SessionManager 生成稳定的会话 ID
     let session_id = SessionManager::extract_session_id(request);
@@ -735,44 +699,23 @@ pub fn convert_openai_to_antigravity_with_context(
 // 辅助转换函数
 // ============================================================================
 
-/// 清理参数中不需要的字段
-fn clean_parameters(params: Option<serde_json::Value>) -> Option<serde_json::Value> {
-    params.map(clean_value)
-}
-
-fn clean_value(value: serde_json::Value) -> serde_json::Value {
-    const EXCLUDED_KEYS: &[&str] = &[
-        "$schema",
-        "additionalProperties",
-        "minLength",
-        "maxLength",
-        "minItems",
-        "maxItems",
-        "uniqueItems",
-        "strict", // Gemini 不支持 strict
-    ];
-
-    match value {
-        serde_json::Value::Object(map) => {
-            let cleaned: serde_json::Map<String, serde_json::Value> = map
-                .into_iter()
-                .filter(|(k, _)| !EXCLUDED_KEYS.contains(&k.as_str()))
-                .map(|(k, v)| (k, clean_value(v)))
-                .collect();
-            serde_json::Value::Object(cleaned)
-        }
-        serde_json::Value::Array(arr) => {
-            serde_json::Value::Array(arr.into_iter().map(clean_value).collect())
-        }
-        other => other,
-    }
-}
-
 /// 兼容旧接口
 pub fn convert_openai_to_antigravity(request: &ChatCompletionRequest) -> serde_json::Value {
     convert_openai_to_antigravity_with_context(request, "")
 }
 
+/// 将 OpenAI 请求转换为 Gemini API（generateContent）请求体
+///
+/// 与 Antigravity 共用转换逻辑，只取内层 request 并去掉 Antigravity 专用的 sessionId。
+pub fn convert_openai_to_gemini(request: &ChatCompletionRequest) -> serde_json::Value {
+    let mut wrapped = convert_openai_to_antigravity_with_context(request, "");
+    let mut body = wrapped["request"].take();
+    if let Some(obj) = body.as_object_mut() {
+        obj.remove("sessionId");
+    }
+    body
+}
+
 /// 转换用户消息内容
 fn convert_user_content(msg: &ChatMessage) -> Vec<GeminiPart> {
     let mut parts = Vec::new();
@@ -782,6 +725,7 @@ fn convert_user_content(msg: &ChatMessage) -> Vec<GeminiPart> {
             parts.push(GeminiPart {
                 text: Some(text.clone()),
                 inline_data: None,
+                file_data: None,
                 function_call: None,
                 function_response: None,
                 thought_signature: None,
@@ -794,25 +738,14 @@ fn convert_user_content(msg: &ChatMessage) -> Vec<GeminiPart> {
                         parts.push(GeminiPart {
                             text: Some(text.clone()),
                             inline_data: None,
+                            file_data: None,
                             function_call: None,
                             function_response: None,
                             thought_signature: None,
                         });
                     }
                     ContentPart::ImageUrl { image_url } => {
-                        // 处理 base64 图片
-                        if let Some((mime, data)) = parse_data_url(&image_url.url) {
-                            parts.push(GeminiPart {
-                                text: None,
-                                inline_data: Some(InlineData {
-                                    mime_type: mime,
-                                    data,
-                                }),
-                                function_call: None,
-                                function_response: None,
-                                thought_signature: None,
-                            });
-                        }
+                        parts.push(convert_image_part(image_url));
                     }
                 }
             }
@@ -823,18 +756,32 @@ fn convert_user_content(msg: &ChatMessage) -> Vec<GeminiPart> {
     parts
 }
 
-/// 解析 data URL
-fn parse_data_url(url: &str) -> Option<(String, String)> {
-    if url.starts_with("data:") {
-        let parts: Vec<&str> = url.splitn(2, ',').collect();
-        if parts.len() == 2 {
-            let meta = parts[0].strip_prefix("data:")?;
-            let mime = meta.split(';').next()?.to_string();
-            let data = parts[1].to_string();
-            return Some((mime, data));
+/// 转换图片：base64 使用 inlineData，远程 URL 使用 fileData，无法处理时替换为文本
+fn convert_image_part(image_url: &ImageUrl) -> GeminiPart {
+    let mut part = GeminiPart {
+        text: None,
+        inline_data: None,
+        file_data: None,
+        function_call: None,
+        function_response: None,
+        thought_signature: None,
+    };
+    match ImageContent::from_openai_url(&image_url.url).fit_or_placeholder(&GEMINI_IMAGE_LIMITS) {
+        Ok(ImageContent::Base64 { media_type, data }) => {
+            part.inline_data = Some(InlineData {
+                mime_type: media_type,
+                data,
+            });
+        }
+        Ok(ImageContent::Url(url)) => {
+            part.file_data = Some(FileData {
+                mime_type: media_type_from_url(&url).to_string(),
+                file_uri: url,
+            });
         }
+        Err(text) => part.text = Some(text),
     }
-    None
+    part
 }
 
 // ============================================================================
@@ -912,30 +859,9 @@ pub fn convert_antigravity_to_openai_response(
 
                     if let Some(fc) = part.get("functionCall") {
                         // 优先使用响应中的 id，否则生成新的
-                        let call_id = fc
-                            .get("id")
-                            .and_then(|id| id.as_str())
-                            .map(|s| s.to_string())
-                            .unwrap_or_else(|| {
-                                format!("call_{}", &uuid::Uuid::new_v4().to_string()[..8])
-                            });
-
-                        let default_args = serde_json::json!({});
-                        let args = fc.get("args").unwrap_or(&default_args);
-                        let args_str = if args.is_string() {
-                            args.as_str().unwrap_or("{}").to_string()
-                        } else {
-                            serde_json::to_string(args).unwrap_or_default()
-                        };
-
-                        tool_calls.push(serde_json::json!({
-                            "id": call_id,
-                            "type": "function",
-                            "function": {
-                                "name": fc.get("name").and_then(|n| n.as_str()).unwrap_or(""),
-                                "arguments": args_str
-                            }
-                        }));
+                        tool_calls.push(
+                            serde_json::to_value(function_call_to_openai(fc)).unwrap_or_default(),
+                        );
                     }
 
                     // 处理图片输出
@@ -964,6 +890,8 @@ pub fn convert_antigravity_to_openai_response(
                 .get("finishReason")
                 .and_then(|r| r.as_str())
                 .map(|r| match r.to_uppercase().as_str() {
+                    // Gemini 发起工具调用时 finishReason 仍为 STOP
+                    "STOP" if !tool_calls.is_empty() => "tool_calls",
                     "STOP" => "stop",
                     "MAX_TOKENS" => "length",
                     "SAFETY" => "content_filter",
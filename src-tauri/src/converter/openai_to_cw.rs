@@ -8,6 +8,7 @@
 
 #![allow(dead_code)]
 
+use super::image_content::ImageContent;
 use crate::models::codewhisperer::*;
 use crate::models::openai::*;
 use std::collections::HashMap;
@@ -91,7 +92,8 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
             }
             "user" => {
                 // 如果有待处理的 tool results，合并到这个 user 消息
-                let content = msg.get_content_text();
+                let mut content = msg.get_content_text();
+                let images = extract_images(msg, &mut content);
                 let mut tool_results = pending_tool_results.clone();
                 pending_tool_results.clear();
 
@@ -108,6 +110,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
                     } else {
                         Some(tool_results)
                     },
+                    images,
                 });
             }
             "assistant" => {
@@ -125,6 +128,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
                         content: "Tool results provided.".to_string(),
                         tool_calls: None,
                         tool_results: Some(tool_results),
+                        images: None,
                     });
                 }
 
@@ -146,6 +150,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
                     content,
                     tool_calls,
                     tool_results: None,
+                    images: None,
                 });
             }
             _ => {}
@@ -163,21 +168,50 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
             content: "Tool results provided.".to_string(),
             tool_calls: None,
             tool_results: Some(tool_results),
+            images: None,
         });
     }
 
     result
 }
 
+/// 提取 user 消息中的图片，URL 图片和无法压缩的图片以文本形式追加到内容
+fn extract_images(msg: &ChatMessage, content: &mut String) -> Option<Vec<CWImage>> {
+    let Some(MessageContent::Parts(parts)) = &msg.content else {
+        return None;
+    };
+    let mut images = Vec::new();
+    for part in parts {
+        if let ContentPart::ImageUrl { image_url } = part {
+            match ImageContent::from_openai_url(&image_url.url).into_cw_image() {
+                Ok(image) => images.push(image),
+                Err(text) => {
+                    if !content.is_empty() {
+                        content.push('\n');
+                    }
+                    content.push_str(&text);
+                }
+            }
+        }
+    }
+    if images.is_empty() {
+        None
+    } else {
+        Some(images)
+    }
+}
+
 #[derive(Debug, Clone)]
 struct ProcessedMessage {
     role: String,
     content: String,
     tool_calls: Option<Vec<CWToolUse>>,
     tool_results: Option<Vec<CWToolResult>>,
+    images: Option<Vec<CWImage>>,
 }
 
 /// 将 OpenAI ChatCompletionRequest 转换为 CodeWhisperer 请求
+#[tracing::instrument(name = "convert.openai_to_codewhisperer", skip_all)]
 pub fn convert_openai_to_codewhisperer(
     request: &ChatCompletionRequest,
     profile_arn: Option<String>,
@@ -218,7 +252,7 @@ pub fn convert_openai_to_codewhisperer(
             content: combined,
             model_id: cw_model.clone(),
             origin: "AI_EDITOR".to_string(),
-            images: None,
+            images: messages[0].images.clone(),
             user_input_message_context: None,
         };
 
@@ -258,7 +292,7 @@ pub fn convert_openai_to_codewhisperer(
                     content,
                     model_id: cw_model.clone(),
                     origin: "AI_EDITOR".to_string(),
-                    images: None,
+                    images: msg.images.clone(),
                     user_input_message_context: None,
                 };
 
@@ -295,24 +329,29 @@ pub fn convert_openai_to_codewhisperer(
     let history = fix_history_alternation(history, &cw_model);
 
     // 构建当前消息
-    let (current_content, current_tool_results) = if let Some(last_msg) = messages.last() {
-        if last_msg.role == "assistant" {
-            ("Continue".to_string(), None)
-        } else {
-            let content = if last_msg.content.is_empty() {
-                if last_msg.tool_results.is_some() {
-                    "Tool results provided.".to_string()
-                } else {
-                    "Continue".to_string()
-                }
+    let (current_content, current_tool_results, current_images) =
+        if let Some(last_msg) = messages.last() {
+            if last_msg.role == "assistant" {
+                ("Continue".to_string(), None, None)
             } else {
-                last_msg.content.clone()
-            };
-            (content, last_msg.tool_results.clone())
-        }
-    } else {
-        ("Continue".to_string(), None)
-    };
+                let content = if last_msg.content.is_empty() {
+                    if last_msg.tool_results.is_some() {
+                        "Tool results provided.".to_string()
+                    } else {
+                        "Continue".to_string()
+                    }
+                } else {
+                    last_msg.content.clone()
+                };
+                (
+                    content,
+                    last_msg.tool_results.clone(),
+                    last_msg.images.clone(),
+                )
+            }
+        } else {
+            ("Continue".to_string(), None, None)
+        };
 
     // 构建 tools
     let tools = request.tools.as_ref().map(|tools| {
@@ -391,7 +430,7 @@ pub fn convert_openai_to_codewhisperer(
                     content: current_content,
                     model_id: cw_model,
                     origin: "AI_EDITOR".to_string(),
-                    images: None,
+                    images: current_images,
                     user_input_message_context,
                 },
             },
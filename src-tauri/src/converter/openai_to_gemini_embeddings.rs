@@ -0,0 +1,153 @@
+//! OpenAI Embeddings 与 Gemini batchEmbedContents 之间的格式转换
+//!
+//! - 请求：`input` 中的每条文本转换为一个 `EmbedContentRequest`
+//! - 响应：`embeddings[].values` 按顺序转换为 OpenAI 的 `data[]`
+//!
+//! Gemini 不返回 Token 用量，由调用方按输入长度估算。
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+
+use crate::models::openai::{
+    EmbeddingData, EmbeddingRequest, EmbeddingResponse, EmbeddingUsage, EmbeddingVector,
+};
+
+/// 将 OpenAI Embeddings 请求转换为 Gemini batchEmbedContents 请求
+///
+/// Gemini 只支持文本输入，token 数组输入返回错误。
+pub fn convert_embedding_request_to_gemini(request: &EmbeddingRequest) -> Result<Value, String> {
+    let texts = request
+        .input
+        .texts()
+        .ok_or_else(|| "Token array input is not supported by Gemini embeddings".to_string())?;
+
+    let model = format!("models/{}", request.model);
+    let requests: Vec<Value> = texts
+        .into_iter()
+        .map(|text| {
+            let mut item = json!({
+                "model": model,
+                "content": { "parts": [{ "text": text }] }
+            });
+            if let Some(dimensions) = request.dimensions {
+                item["outputDimensionality"] = json!(dimensions);
+            }
+            item
+        })
+        .collect();
+
+    Ok(json!({ "requests": requests }))
+}
+
+/// 将 Gemini batchEmbedContents 响应转换为 OpenAI Embeddings 响应
+pub fn convert_gemini_embedding_response(
+    response: &Value,
+    model: &str,
+    encoding_format: Option<&str>,
+    prompt_tokens: u32,
+) -> Result<EmbeddingResponse, String> {
+    let embeddings = response
+        .get("embeddings")
+        .and_then(|e| e.as_array())
+        .ok_or_else(|| "Gemini response missing embeddings".to_string())?;
+
+    let base64 = encoding_format == Some("base64");
+    let data = embeddings
+        .iter()
+        .enumerate()
+        .map(|(index, embedding)| {
+            let values: Vec<f32> = embedding
+                .get("values")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| format!("Gemini embedding {} missing values", index))?
+                .iter()
+                .map(|v| v.as_f64().unwrap_or_default() as f32)
+                .collect();
+
+            let embedding = if base64 {
+                let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+                EmbeddingVector::Base64(BASE64.encode(bytes))
+            } else {
+                EmbeddingVector::Float(values)
+            };
+
+            Ok(EmbeddingData {
+                object: "embedding".to_string(),
+                index: index as u32,
+                embedding,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(EmbeddingResponse {
+        object: "list".to_string(),
+        data,
+        model: model.to_string(),
+        usage: EmbeddingUsage {
+            prompt_tokens,
+            total_tokens: prompt_tokens,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::openai::EmbeddingInput;
+
+    fn request(input: EmbeddingInput) -> EmbeddingRequest {
+        EmbeddingRequest {
+            model: "text-embedding-004".to_string(),
+            input,
+            encoding_format: None,
+            dimensions: Some(256),
+            user: None,
+        }
+    }
+
+    #[test]
+    fn test_convert_request() {
+        let req = request(EmbeddingInput::TextArray(vec![
+            "hello".to_string(),
+            "world".to_string(),
+        ]));
+        let body = convert_embedding_request_to_gemini(&req).unwrap();
+        let requests = body["requests"].as_array().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["model"], "models/text-embedding-004");
+        assert_eq!(requests[1]["content"]["parts"][0]["text"], "world");
+        assert_eq!(requests[0]["outputDimensionality"], 256);
+    }
+
+    #[test]
+    fn test_token_input_rejected() {
+        let req = request(EmbeddingInput::Tokens(vec![1, 2, 3]));
+        assert!(convert_embedding_request_to_gemini(&req).is_err());
+    }
+
+    #[test]
+    fn test_convert_response() {
+        let resp = json!({
+            "embeddings": [{ "values": [0.5, -1.0] }, { "values": [0.25] }]
+        });
+        let converted =
+            convert_gemini_embedding_response(&resp, "text-embedding-004", None, 3).unwrap();
+        assert_eq!(converted.object, "list");
+        assert_eq!(converted.data.len(), 2);
+        assert_eq!(converted.data[1].index, 1);
+        assert!(
+            matches!(&converted.data[0].embedding, EmbeddingVector::Float(v) if v == &vec![0.5, -1.0])
+        );
+        assert_eq!(converted.usage.total_tokens, 3);
+
+        let encoded =
+            convert_gemini_embedding_response(&resp, "text-embedding-004", Some("base64"), 3)
+                .unwrap();
+        match &encoded.data[1].embedding {
+            EmbeddingVector::Base64(s) => {
+                assert_eq!(BASE64.decode(s).unwrap(), 0.25f32.to_le_bytes().to_vec())
+            }
+            other => panic!("unexpected embedding: {:?}", other),
+        }
+    }
+}
@@ -64,11 +64,13 @@ impl ProtocolSelector {
             PoolProviderType::Vertex => Protocol::Gemini, // Vertex AI uses Gemini protocol
             PoolProviderType::GeminiApiKey => Protocol::Gemini, // Gemini API Key uses Gemini protocol
             PoolProviderType::Codex => Protocol::OpenAI,        // Codex uses OpenAI protocol
+            PoolProviderType::Qwen => Protocol::OpenAI,         // Qwen uses OpenAI protocol
             // API Key Provider 类型
             PoolProviderType::Anthropic => Protocol::Anthropic,
             PoolProviderType::AzureOpenai => Protocol::OpenAI,
             PoolProviderType::AwsBedrock => Protocol::Anthropic,
             PoolProviderType::Ollama => Protocol::OpenAI,
+            PoolProviderType::Mock => Protocol::OpenAI,
         }
     }
 
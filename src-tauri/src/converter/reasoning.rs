@@ -0,0 +1,157 @@
+//! 推理内容（思维链）跨格式处理
+//!
+//! 不同格式的推理内容字段：
+//! - OpenAI 兼容（DeepSeek、Qwen 等）：`message.reasoning_content` / `delta.reasoning_content`
+//! - Anthropic：`thinking` 内容块 / `thinking_delta`
+//! - Gemini / Antigravity：`thought: true` 的 part
+//!
+//! 格式转换时按 [`ReasoningMode`] 处理推理内容；源格式与目标格式相同时原样透传，不受影响。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 推理内容处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningMode {
+    /// 丢弃推理内容
+    Strip,
+    /// 以 `<thinking>` 标签包裹后放在正文前面（适用于不支持推理字段的客户端）
+    Passthrough,
+    /// 映射到目标格式的推理字段
+    #[default]
+    Map,
+}
+
+impl ReasoningMode {
+    /// 按模式处理推理内容
+    ///
+    /// 返回（目标格式推理字段的内容, 正文）
+    pub fn apply(self, reasoning: Option<&str>, content: &str) -> (Option<String>, String) {
+        let reasoning = reasoning.filter(|r| !r.is_empty());
+        match (self, reasoning) {
+            (ReasoningMode::Map, Some(reasoning)) => {
+                (Some(reasoning.to_string()), content.to_string())
+            }
+            (ReasoningMode::Passthrough, Some(reasoning)) => {
+                (None, format!("{}{}", wrap_thinking(reasoning), content))
+            }
+            _ => (None, content.to_string()),
+        }
+    }
+}
+
+/// 用 `<thinking>` 标签包裹推理内容
+pub fn wrap_thinking(reasoning: &str) -> String {
+    format!("<thinking>{}</thinking>\n\n", reasoning)
+}
+
+/// 按模式处理 OpenAI 格式响应中的 `reasoning_content`（非流式）
+pub fn apply_reasoning_to_openai_response(response: &mut Value, mode: ReasoningMode) {
+    let Some(choices) = response.get_mut("choices").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for choice in choices {
+        let Some(message) = choice.get_mut("message").and_then(Value::as_object_mut) else {
+            continue;
+        };
+        let Some(reasoning) = message.remove("reasoning_content") else {
+            continue;
+        };
+        let content = message
+            .get("content")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let (reasoning, content) = mode.apply(reasoning.as_str(), content);
+        if !content.is_empty() {
+            message.insert("content".to_string(), Value::String(content));
+        }
+        if let Some(reasoning) = reasoning {
+            message.insert("reasoning_content".to_string(), Value::String(reasoning));
+        }
+    }
+}
+
+/// 从 Anthropic 内容块中提取正文和推理内容
+///
+/// 返回（拼接后的 text 块内容, 拼接后的 thinking 块内容）
+pub fn split_anthropic_content(blocks: &[Value]) -> (String, Option<String>) {
+    let mut text = String::new();
+    let mut thinking = String::new();
+    for block in blocks {
+        match block.get("type").and_then(Value::as_str) {
+            Some("text") => text.push_str(block["text"].as_str().unwrap_or_default()),
+            Some("thinking") => thinking.push_str(block["thinking"].as_str().unwrap_or_default()),
+            _ => {}
+        }
+    }
+    (text, (!thinking.is_empty()).then_some(thinking))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_modes() {
+        assert_eq!(
+            ReasoningMode::Map.apply(Some("think"), "answer"),
+            (Some("think".to_string()), "answer".to_string())
+        );
+        assert_eq!(
+            ReasoningMode::Passthrough.apply(Some("think"), "answer"),
+            (None, "<thinking>think</thinking>\n\nanswer".to_string())
+        );
+        assert_eq!(
+            ReasoningMode::Strip.apply(Some("think"), "answer"),
+            (None, "answer".to_string())
+        );
+        assert_eq!(
+            ReasoningMode::Passthrough.apply(Some(""), "answer"),
+            (None, "answer".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply_reasoning_to_openai_response() {
+        let response = json!({
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "42", "reasoning_content": "6*7"}
+            }]
+        });
+
+        let mut mapped = response.clone();
+        apply_reasoning_to_openai_response(&mut mapped, ReasoningMode::Map);
+        assert_eq!(mapped, response);
+
+        let mut inline = response.clone();
+        apply_reasoning_to_openai_response(&mut inline, ReasoningMode::Passthrough);
+        assert_eq!(
+            inline["choices"][0]["message"],
+            json!({"role": "assistant", "content": "<thinking>6*7</thinking>\n\n42"})
+        );
+
+        let mut stripped = response;
+        apply_reasoning_to_openai_response(&mut stripped, ReasoningMode::Strip);
+        assert_eq!(
+            stripped["choices"][0]["message"],
+            json!({"role": "assistant", "content": "42"})
+        );
+    }
+
+    #[test]
+    fn test_split_anthropic_content() {
+        let blocks = vec![
+            json!({"type": "thinking", "thinking": "plan", "signature": "sig"}),
+            json!({"type": "text", "text": "Hello "}),
+            json!({"type": "tool_use", "id": "t1", "name": "f", "input": {}}),
+            json!({"type": "text", "text": "world"}),
+        ];
+        assert_eq!(
+            split_anthropic_content(&blocks),
+            ("Hello world".to_string(), Some("plan".to_string()))
+        );
+    }
+}
@@ -0,0 +1,370 @@
+//! 结构化输出（`response_format`）跨格式转换
+//!
+//! OpenAI 的 `response_format: {type: json_object | json_schema}` 转换为各上游的等价方式：
+//! - Anthropic：没有原生 JSON 模式，强制调用 `json_response` 工具，工具参数即为 JSON 输出；
+//!   请求自带工具时无法强制调用，改为在 system prompt 中追加 JSON 输出要求
+//! - Gemini / Antigravity：`generationConfig.responseMimeType` + `responseSchema`
+//!
+//! 上游返回后按 schema 校验输出（类型、必填字段、枚举值），`json_schema.strict` 为 true 时由调用方重试一次。
+
+use crate::converter::gemini_tools::{to_gemini_schema, SchemaDialect};
+use crate::models::openai::{ChatCompletionRequest, Tool};
+use serde_json::{json, Value};
+
+/// Anthropic 强制调用的结构化输出工具名
+pub const JSON_RESPONSE_TOOL: &str = "json_response";
+
+/// 请求要求的结构化输出格式
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseFormat {
+    /// 任意 JSON 对象
+    JsonObject,
+    /// 符合指定 schema 的 JSON
+    JsonSchema {
+        name: String,
+        schema: Value,
+        strict: bool,
+    },
+}
+
+impl ResponseFormat {
+    /// 从 OpenAI 请求中读取 `response_format`，`text` 或未设置时返回 `None`
+    pub fn from_request(request: &ChatCompletionRequest) -> Option<Self> {
+        let format = request.extra.get("response_format")?;
+        match format.get("type").and_then(Value::as_str)? {
+            "json_object" => Some(Self::JsonObject),
+            "json_schema" => {
+                let spec = &format["json_schema"];
+                Some(Self::JsonSchema {
+                    name: spec["name"].as_str().unwrap_or("response").to_string(),
+                    schema: spec
+                        .get("schema")
+                        .cloned()
+                        .unwrap_or_else(|| json!({"type": "object"})),
+                    strict: spec["strict"].as_bool().unwrap_or(false),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// 是否为 strict 模式（输出无效时重试）
+    pub fn is_strict(&self) -> bool {
+        matches!(self, Self::JsonSchema { strict: true, .. })
+    }
+
+    fn schema(&self) -> Value {
+        match self {
+            Self::JsonObject => json!({"type": "object"}),
+            Self::JsonSchema { schema, .. } => schema.clone(),
+        }
+    }
+
+    fn instruction(&self) -> String {
+        match self {
+            Self::JsonObject => "Respond only with a valid JSON object.".to_string(),
+            Self::JsonSchema { schema, .. } => format!(
+                "Respond only with a valid JSON object that conforms to this JSON schema:\n{}",
+                schema
+            ),
+        }
+    }
+
+    /// 写入 Anthropic 请求体
+    ///
+    /// `force_tool` 为 true 时添加并强制调用 `json_response` 工具，否则在 system prompt 中追加要求
+    pub fn apply_to_anthropic(&self, body: &mut Value, force_tool: bool) {
+        if !force_tool {
+            let instruction = self.instruction();
+            match body.get_mut("system") {
+                Some(Value::String(system)) if !system.is_empty() => {
+                    system.push_str("\n\n");
+                    system.push_str(&instruction);
+                }
+                Some(Value::Array(blocks)) => {
+                    blocks.push(json!({"type": "text", "text": instruction}));
+                }
+                _ => body["system"] = json!(instruction),
+            }
+            return;
+        }
+
+        let description = match self {
+            Self::JsonObject => "Return the response as a JSON object.".to_string(),
+            Self::JsonSchema { name, .. } => format!("Return the {} as structured JSON.", name),
+        };
+        body["tools"] = json!([{
+            "name": JSON_RESPONSE_TOOL,
+            "description": description,
+            "input_schema": self.schema()
+        }]);
+        body["tool_choice"] = json!({"type": "tool", "name": JSON_RESPONSE_TOOL});
+    }
+
+    /// Gemini `responseSchema`（OpenAPI Schema 子集），`json_object` 时返回 `None`
+    pub fn gemini_response_schema(&self) -> Option<Value> {
+        match self {
+            Self::JsonObject => None,
+            Self::JsonSchema { schema, .. } => {
+                Some(to_gemini_schema(schema, SchemaDialect::OpenApi))
+            }
+        }
+    }
+
+    /// 校验模型输出的文本
+    pub fn validate(&self, content: &str) -> Result<(), String> {
+        let value: Value = serde_json::from_str(strip_code_fence(content))
+            .map_err(|e| format!("输出不是有效的 JSON: {}", e))?;
+        validate_schema(&value, &self.schema(), "$")
+    }
+
+    /// 校验 OpenAI 格式响应中第一个 choice 的内容
+    pub fn validate_openai_response(&self, response: &Value) -> Result<(), String> {
+        let content = response["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or_default();
+        self.validate(content)
+    }
+}
+
+/// Anthropic 请求是否强制调用 `json_response` 工具（请求自带函数工具时不强制）
+pub fn forces_json_tool(request: &ChatCompletionRequest) -> bool {
+    ResponseFormat::from_request(request).is_some()
+        && !request
+            .tools
+            .iter()
+            .flatten()
+            .any(|tool| matches!(tool, Tool::Function { .. }))
+}
+
+/// 从 Anthropic 内容块中提取 `json_response` 工具的参数作为 JSON 文本
+pub fn json_from_anthropic_content(blocks: &[Value]) -> Option<String> {
+    blocks
+        .iter()
+        .find(|block| {
+            block["type"].as_str() == Some("tool_use")
+                && block["name"].as_str() == Some(JSON_RESPONSE_TOOL)
+        })
+        .map(|block| block["input"].to_string())
+}
+
+/// 去掉模型常见的 Markdown 代码块包裹
+fn strip_code_fence(content: &str) -> &str {
+    let trimmed = content.trim();
+    trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(str::trim)
+        .unwrap_or(trimmed)
+}
+
+/// 按 JSON Schema 常用关键字校验（type、enum、required、properties、items、anyOf）
+fn validate_schema(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    if let Some(variants) = schema
+        .get("anyOf")
+        .or_else(|| schema.get("oneOf"))
+        .and_then(Value::as_array)
+    {
+        if !variants
+            .iter()
+            .any(|variant| validate_schema(value, variant, path).is_ok())
+        {
+            return Err(format!("{} 不匹配任何候选 schema", path));
+        }
+    }
+
+    if let Some(types) = schema.get("type") {
+        let matches = match types {
+            Value::String(t) => type_matches(value, t),
+            Value::Array(ts) => ts
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|t| type_matches(value, t)),
+            _ => true,
+        };
+        if !matches {
+            return Err(format!("{} 类型应为 {}", path, types));
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            return Err(format!("{} 不在枚举值中", path));
+        }
+    }
+
+    if let Value::Object(object) = value {
+        for key in schema["required"].as_array().into_iter().flatten() {
+            if let Some(key) = key.as_str() {
+                if !object.contains_key(key) {
+                    return Err(format!("{} 缺少必填字段 {}", path, key));
+                }
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (key, property) in properties {
+                if let Some(field) = object.get(key) {
+                    validate_schema(field, property, &format!("{}.{}", path, key))?;
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            validate_schema(item, item_schema, &format!("{}[{}]", path, index))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn type_matches(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: Value) -> ChatCompletionRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn schema_request() -> ChatCompletionRequest {
+        request(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{"role": "user", "content": "weather?"}],
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "weather",
+                    "strict": true,
+                    "schema": {
+                        "type": "object",
+                        "properties": {
+                            "city": {"type": "string"},
+                            "unit": {"type": "string", "enum": ["c", "f"]}
+                        },
+                        "required": ["city", "unit"]
+                    }
+                }
+            }
+        }))
+    }
+
+    #[test]
+    fn test_from_request() {
+        let format = ResponseFormat::from_request(&schema_request()).unwrap();
+        assert!(format.is_strict());
+        assert!(matches!(format, ResponseFormat::JsonSchema { ref name, .. } if name == "weather"));
+
+        let json_object = request(json!({
+            "model": "gpt-4o",
+            "messages": [],
+            "response_format": {"type": "json_object"}
+        }));
+        assert_eq!(
+            ResponseFormat::from_request(&json_object),
+            Some(ResponseFormat::JsonObject)
+        );
+
+        let text = request(json!({
+            "model": "gpt-4o",
+            "messages": [],
+            "response_format": {"type": "text"}
+        }));
+        assert_eq!(ResponseFormat::from_request(&text), None);
+    }
+
+    #[test]
+    fn test_apply_to_anthropic() {
+        let format = ResponseFormat::from_request(&schema_request()).unwrap();
+
+        let mut forced = json!({"model": "claude-sonnet-4-5", "messages": []});
+        format.apply_to_anthropic(&mut forced, true);
+        assert_eq!(forced["tools"][0]["name"], JSON_RESPONSE_TOOL);
+        assert_eq!(forced["tools"][0]["input_schema"]["required"][0], "city");
+        assert_eq!(
+            forced["tool_choice"],
+            json!({"type": "tool", "name": JSON_RESPONSE_TOOL})
+        );
+
+        let mut prompted = json!({"system": "Be brief.", "tools": [{"name": "lookup"}]});
+        format.apply_to_anthropic(&mut prompted, false);
+        assert_eq!(prompted["tools"].as_array().unwrap().len(), 1);
+        assert!(prompted["system"]
+            .as_str()
+            .unwrap()
+            .starts_with("Be brief.\n\nRespond only with a valid JSON object"));
+    }
+
+    #[test]
+    fn test_gemini_generation_config() {
+        let body =
+            crate::converter::openai_to_antigravity::convert_openai_to_gemini(&schema_request());
+        let config = &body["generationConfig"];
+        assert_eq!(config["responseMimeType"], "application/json");
+        assert_eq!(config["responseSchema"]["type"], "object");
+        assert_eq!(
+            config["responseSchema"]["required"],
+            json!(["city", "unit"])
+        );
+    }
+
+    #[test]
+    fn test_forces_json_tool() {
+        assert!(forces_json_tool(&schema_request()));
+
+        let mut with_tools = schema_request();
+        with_tools.tools = Some(vec![serde_json::from_value(json!({
+            "type": "function",
+            "function": {"name": "lookup", "parameters": {"type": "object"}}
+        }))
+        .unwrap()]);
+        assert!(!forces_json_tool(&with_tools));
+    }
+
+    #[test]
+    fn test_json_from_anthropic_content() {
+        let blocks = vec![json!({
+            "type": "tool_use",
+            "id": "toolu_1",
+            "name": JSON_RESPONSE_TOOL,
+            "input": {"city": "Paris", "unit": "c"}
+        })];
+        let content = json_from_anthropic_content(&blocks).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&content).unwrap(),
+            json!({"city": "Paris", "unit": "c"})
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let format = ResponseFormat::from_request(&schema_request()).unwrap();
+        assert!(format.validate(r#"{"city": "Paris", "unit": "c"}"#).is_ok());
+        assert!(format
+            .validate("```json\n{\"city\": \"Paris\", \"unit\": \"f\"}\n```")
+            .is_ok());
+        assert!(format.validate(r#"{"city": "Paris"}"#).is_err());
+        assert!(format
+            .validate(r#"{"city": "Paris", "unit": "k"}"#)
+            .is_err());
+        assert!(format.validate(r#"{"city": 1, "unit": "c"}"#).is_err());
+        assert!(format.validate("Paris, 20C").is_err());
+
+        assert!(ResponseFormat::JsonObject.validate("[1, 2]").is_err());
+        assert!(ResponseFormat::JsonObject.validate("{}").is_ok());
+    }
+}
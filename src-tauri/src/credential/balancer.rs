@@ -5,12 +5,13 @@
 use super::health::{HealthCheckConfig, HealthChecker};
 use super::pool::{CredentialPool, PoolError};
 use super::types::Credential;
-use crate::proxy::ProxyClientFactory;
+use crate::proxy::{redact_proxy_url, ProxyClientFactory};
 use crate::ProviderType;
 use chrono::{DateTime, Duration, Utc};
 use dashmap::DashMap;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
@@ -98,6 +99,12 @@ impl LoadBalancer {
         self.proxy_factory = ProxyClientFactory::new().with_global_proxy(proxy_url);
     }
 
+    /// 设置 Per-Provider 代理（键为 Provider 类型）
+    pub fn with_provider_proxies(mut self, provider_proxies: HashMap<String, String>) -> Self {
+        self.proxy_factory = self.proxy_factory.with_provider_proxies(provider_proxies);
+        self
+    }
+
     /// 获取代理客户端工厂
     pub fn proxy_factory(&self) -> &ProxyClientFactory {
         &self.proxy_factory
@@ -164,8 +171,9 @@ impl LoadBalancer {
     ///
     /// 代理选择逻辑：
     /// 1. 如果凭证有 proxy_url，使用 Per-Key 代理
-    /// 2. 否则，使用全局代理（如果配置了）
-    /// 3. 否则，不使用代理
+    /// 2. 否则，使用该 Provider 的代理（如果配置了）
+    /// 3. 否则，使用全局代理（如果配置了）
+    /// 4. 否则，不使用代理
     ///
     /// # 错误
     /// - 如果 Provider 未注册，返回 `PoolError::EmptyPool`
@@ -177,10 +185,10 @@ impl LoadBalancer {
     ) -> Result<CredentialSelection, PoolError> {
         let credential = self.select(provider)?;
 
-        // 使用凭证的 proxy_url 或回退到全局代理
+        // 使用凭证的 proxy_url，依次回退到 Provider 代理和全局代理
         let client = self
             .proxy_factory
-            .create_client(credential.proxy_url())
+            .create_client_for(&provider.to_string(), credential.proxy_url())
             .map_err(|e| PoolError::CredentialNotFound(format!("代理配置错误: {}", e)))?;
 
         Ok(CredentialSelection { credential, client })
@@ -199,7 +207,7 @@ impl LoadBalancer {
         credential: &Credential,
     ) -> Result<Client, PoolError> {
         self.proxy_factory
-            .create_client(credential.proxy_url())
+            .create_client_for(&credential.provider.to_string(), credential.proxy_url())
             .map_err(|e| PoolError::CredentialNotFound(format!("代理配置错误: {}", e)))
     }
 
@@ -249,7 +257,10 @@ impl LoadBalancer {
             tried_ids.insert(credential.id.clone());
 
             // 尝试创建客户端
-            match self.proxy_factory.create_client(credential.proxy_url()) {
+            match self
+                .proxy_factory
+                .create_client_for(&provider.to_string(), credential.proxy_url())
+            {
                 Ok(client) => {
                     return Ok(CredentialSelection { credential, client });
                 }
@@ -257,7 +268,7 @@ impl LoadBalancer {
                     // 记录警告并继续尝试下一个凭证
                     tracing::warn!(
                         credential_id = %credential.id,
-                        proxy_url = ?credential.proxy_url(),
+                        proxy_url = ?credential.proxy_url().map(redact_proxy_url),
                         error = %e,
                         "代理连接失败，尝试下一个凭证"
                     );
@@ -0,0 +1,327 @@
+//! 外部工具凭证导入
+//!
+//! 扫描其他 CLI 工具在本机保存的 OAuth 凭证，转换为凭证池可用的格式：
+//! - Claude Code: `~/.claude/.credentials.json`
+//! - gemini-cli: `~/.gemini/oauth_creds.json`
+//! - qwen-code: `~/.qwen/oauth_creds.json`
+//! - Codex CLI: `~/.codex/auth.json`
+//! - Kiro: `~/.aws/sso/cache/kiro-auth-token*.json`
+//!
+//! Cline 的凭证保存在 VS Code SecretStorage 中，无法通过文件扫描获取。
+
+use crate::models::provider_pool_model::CredentialData;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 凭证来源工具
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    ClaudeCode,
+    GeminiCli,
+    QwenCode,
+    CodexCli,
+    Kiro,
+}
+
+impl ImportSource {
+    /// 对应的凭证池 Provider 类型
+    pub fn provider_type(&self) -> &'static str {
+        match self {
+            ImportSource::ClaudeCode => "claude_oauth",
+            ImportSource::GeminiCli => "gemini",
+            ImportSource::QwenCode => "qwen",
+            ImportSource::CodexCli => "codex",
+            ImportSource::Kiro => "kiro",
+        }
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportSource::ClaudeCode => "Claude Code",
+            ImportSource::GeminiCli => "Gemini CLI",
+            ImportSource::QwenCode => "Qwen Code",
+            ImportSource::CodexCli => "Codex CLI",
+            ImportSource::Kiro => "Kiro",
+        }
+    }
+}
+
+/// 扫描到的凭证文件
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedCredential {
+    /// 来源工具
+    pub source: ImportSource,
+    /// 凭证池 Provider 类型
+    pub provider_type: String,
+    /// 凭证文件路径
+    pub path: String,
+    /// 账号标识（邮箱等，可能为空）
+    pub account: Option<String>,
+    /// 凭证池中是否已存在相同的 refresh token
+    pub already_imported: bool,
+}
+
+/// 扫描主目录下的已知凭证位置
+///
+/// `known_refresh_tokens` 为凭证池中已有凭证的 refresh token，用于标记已导入的凭证
+pub fn scan(home: &Path, known_refresh_tokens: &HashSet<String>) -> Vec<DetectedCredential> {
+    let mut candidates = vec![
+        (
+            ImportSource::ClaudeCode,
+            home.join(".claude").join(".credentials.json"),
+        ),
+        (
+            ImportSource::GeminiCli,
+            home.join(".gemini").join("oauth_creds.json"),
+        ),
+        (
+            ImportSource::QwenCode,
+            home.join(".qwen").join("oauth_creds.json"),
+        ),
+        (
+            ImportSource::CodexCli,
+            home.join(".codex").join("auth.json"),
+        ),
+    ];
+    candidates.extend(
+        kiro_token_files(&home.join(".aws").join("sso").join("cache"))
+            .into_iter()
+            .map(|path| (ImportSource::Kiro, path)),
+    );
+
+    candidates
+        .into_iter()
+        .filter_map(|(source, path)| {
+            let content = fs::read_to_string(&path).ok()?;
+            let value: Value = serde_json::from_str(&content).ok()?;
+            let normalized = normalize(source, &value)?;
+            let already_imported = refresh_token(&normalized)
+                .map(|token| known_refresh_tokens.contains(token))
+                .unwrap_or(false);
+            Some(DetectedCredential {
+                source,
+                provider_type: source.provider_type().to_string(),
+                path: path.to_string_lossy().to_string(),
+                account: account(&normalized),
+                already_imported,
+            })
+        })
+        .collect()
+}
+
+/// 读取凭证文件中的 refresh token（兼容 snake_case 和 camelCase）
+pub fn read_refresh_token(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let value: Value = serde_json::from_str(&content).ok()?;
+    refresh_token(&value).map(str::to_string)
+}
+
+/// 将需要转换格式的凭证写入凭证存储目录
+///
+/// Kiro 凭证需要合并 clientIdHash 文件，由调用方单独处理；其余来源转换为
+/// 对应 Provider 可直接读取的格式，返回新文件路径
+pub fn stage(detected: &DetectedCredential, target_dir: &Path) -> Result<PathBuf, String> {
+    let content =
+        fs::read_to_string(&detected.path).map_err(|e| format!("读取凭证文件失败: {}", e))?;
+    let value: Value =
+        serde_json::from_str(&content).map_err(|e| format!("解析凭证文件失败: {}", e))?;
+    let normalized = normalize(detected.source, &value)
+        .ok_or_else(|| format!("无法识别的凭证格式: {}", detected.path))?;
+
+    let uuid = uuid::Uuid::new_v4().to_string();
+    let file_name = format!(
+        "{}_{}_{}_imported.json",
+        detected.provider_type,
+        &uuid[..8],
+        chrono::Utc::now().timestamp()
+    );
+    let target = target_dir.join(file_name);
+    let serialized =
+        serde_json::to_string_pretty(&normalized).map_err(|e| format!("序列化凭证失败: {}", e))?;
+    fs::write(&target, serialized).map_err(|e| format!("写入凭证文件失败: {}", e))?;
+    Ok(target)
+}
+
+/// 构造凭证池的凭证数据
+pub fn credential_data(source: ImportSource, creds_file_path: String) -> CredentialData {
+    match source {
+        ImportSource::ClaudeCode => CredentialData::ClaudeOAuth { creds_file_path },
+        ImportSource::GeminiCli => CredentialData::GeminiOAuth {
+            creds_file_path,
+            project_id: None,
+        },
+        ImportSource::QwenCode => CredentialData::QwenOAuth { creds_file_path },
+        ImportSource::CodexCli => CredentialData::CodexOAuth {
+            creds_file_path,
+            api_base_url: None,
+        },
+        ImportSource::Kiro => CredentialData::KiroOAuth { creds_file_path },
+    }
+}
+
+fn kiro_token_files(cache_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            name.starts_with("kiro-auth-token") && name.ends_with(".json")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// 校验并转换为凭证池格式，不是有效凭证时返回 None
+fn normalize(source: ImportSource, value: &Value) -> Option<Value> {
+    match source {
+        // Claude Code: {"claudeAiOauth": {"accessToken", "refreshToken", "expiresAt"(毫秒)}}
+        ImportSource::ClaudeCode => {
+            let oauth = value.get("claudeAiOauth")?;
+            let refresh_token = oauth.get("refreshToken")?.as_str()?;
+            let expire = oauth
+                .get("expiresAt")
+                .and_then(Value::as_i64)
+                .and_then(chrono::DateTime::from_timestamp_millis)
+                .map(|t| t.to_rfc3339());
+            Some(json!({
+                "access_token": oauth.get("accessToken"),
+                "refresh_token": refresh_token,
+                "expire": expire,
+                "type": "claude_oauth",
+            }))
+        }
+        // Codex CLI: {"OPENAI_API_KEY", "tokens": {...}, "last_refresh"}
+        ImportSource::CodexCli => {
+            let tokens = value.get("tokens").filter(|t| t.is_object());
+            let api_key = value.get("OPENAI_API_KEY").and_then(Value::as_str);
+            match (tokens, api_key) {
+                (Some(tokens), _) => {
+                    tokens.get("refresh_token")?.as_str()?;
+                    let mut flattened = tokens.clone();
+                    if let Some(last_refresh) = value.get("last_refresh") {
+                        flattened["last_refresh"] = last_refresh.clone();
+                    }
+                    Some(flattened)
+                }
+                (None, Some(api_key)) => Some(json!({ "api_key": api_key })),
+                (None, None) => None,
+            }
+        }
+        // gemini-cli / qwen-code 的格式与对应 Provider 一致
+        ImportSource::GeminiCli | ImportSource::QwenCode => {
+            value.get("refresh_token")?.as_str()?;
+            Some(value.clone())
+        }
+        ImportSource::Kiro => {
+            value.get("refreshToken")?.as_str()?;
+            Some(value.clone())
+        }
+    }
+}
+
+fn refresh_token(value: &Value) -> Option<&str> {
+    value
+        .get("refresh_token")
+        .or_else(|| value.get("refreshToken"))
+        .and_then(Value::as_str)
+}
+
+fn account(value: &Value) -> Option<String> {
+    value
+        .get("email")
+        .or_else(|| value.get("account_id"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    fn write(path: &Path, value: Value) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, value.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_scan_detects_known_locations() {
+        let home = tempfile::tempdir().unwrap();
+        write(
+            &home.path().join(".claude/.credentials.json"),
+            json!({"claudeAiOauth": {"accessToken": "a", "refreshToken": "claude-rt", "expiresAt": 1700000000000i64}}),
+        );
+        write(
+            &home.path().join(".gemini/oauth_creds.json"),
+            json!({"access_token": "a", "refresh_token": "gemini-rt"}),
+        );
+        write(
+            &home.path().join(".aws/sso/cache/kiro-auth-token.json"),
+            json!({"accessToken": "a", "refreshToken": "kiro-rt"}),
+        );
+        // 缺少 refresh token 的文件不应被识别
+        write(
+            &home.path().join(".qwen/oauth_creds.json"),
+            json!({"access_token": "a"}),
+        );
+
+        let known = HashSet::from(["gemini-rt".to_string()]);
+        let detected = scan(home.path(), &known);
+
+        let sources: Vec<ImportSource> = detected.iter().map(|d| d.source).collect();
+        assert_eq!(
+            sources,
+            vec![
+                ImportSource::ClaudeCode,
+                ImportSource::GeminiCli,
+                ImportSource::Kiro
+            ]
+        );
+        assert!(detected[1].already_imported);
+        assert!(!detected[0].already_imported);
+        assert_eq!(detected[2].provider_type, "kiro");
+    }
+
+    #[test]
+    fn test_stage_converts_claude_code_credentials() {
+        let home = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        write(
+            &home.path().join(".claude/.credentials.json"),
+            json!({"claudeAiOauth": {"accessToken": "at", "refreshToken": "rt", "expiresAt": 1700000000000i64}}),
+        );
+
+        let detected = scan(home.path(), &HashSet::new()).remove(0);
+        let staged = stage(&detected, target.path()).unwrap();
+
+        let creds: crate::providers::claude_oauth::ClaudeOAuthCredentials =
+            serde_json::from_str(&fs::read_to_string(&staged).unwrap()).unwrap();
+        assert_eq!(creds.access_token.as_deref(), Some("at"));
+        assert_eq!(creds.refresh_token.as_deref(), Some("rt"));
+        assert!(creds.expire.unwrap().starts_with("2023-11-14"));
+        assert_eq!(read_refresh_token(&staged).as_deref(), Some("rt"));
+    }
+
+    #[test]
+    fn test_codex_tokens_are_flattened() {
+        let value = json!({
+            "OPENAI_API_KEY": null,
+            "tokens": {"id_token": "id", "access_token": "at", "refresh_token": "rt", "account_id": "acc"},
+            "last_refresh": "2025-01-01T00:00:00Z"
+        });
+        let normalized = normalize(ImportSource::CodexCli, &value).unwrap();
+        let creds: crate::providers::codex::CodexCredentials =
+            serde_json::from_value(normalized.clone()).unwrap();
+        assert_eq!(creds.refresh_token.as_deref(), Some("rt"));
+        assert_eq!(creds.last_refresh.as_deref(), Some("2025-01-01T00:00:00Z"));
+        assert_eq!(account(&normalized).as_deref(), Some("acc"));
+    }
+}
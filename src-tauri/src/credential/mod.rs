@@ -11,9 +11,11 @@
 //! - `quota` - 配额管理
 //! - `sync` - 数据库同步
 //! - `risk` - 风控模块（限流检测、冷却期管理）
+//! - `importer` - 从其他工具导入本机凭证
 
 mod balancer;
 mod health;
+pub mod importer;
 mod pool;
 mod quota;
 pub mod risk;
@@ -214,6 +214,17 @@ impl CredentialSyncService {
                     "Claude OAuth 凭证暂不支持同步到配置".to_string(),
                 ));
             }
+            CredentialData::QwenOAuth { creds_file_path } => {
+                let token_file =
+                    self.save_oauth_token_file(creds_file_path, &credential.uuid, "qwen")?;
+                let entry = CredentialEntry {
+                    id: credential.uuid.clone(),
+                    token_file,
+                    disabled: credential.is_disabled,
+                    proxy_url: None,
+                };
+                config.credential_pool.qwen.push(entry);
+            }
             CredentialData::AnthropicKey { api_key, base_url } => {
                 // Anthropic API Key 保存到 claude 配置（使用相同的 API 格式）
                 let entry = ApiKeyEntry {
@@ -227,6 +238,36 @@ impl CredentialSyncService {
                 // 目前暂时保存到 claude 配置中
                 config.credential_pool.claude.push(entry);
             }
+            CredentialData::AzureOpenaiKey { .. } => {
+                // Azure OpenAI 暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(
+                    "Azure OpenAI 凭证暂不支持同步到配置".to_string(),
+                ));
+            }
+            CredentialData::AwsBedrock { .. } => {
+                // AWS Bedrock 暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(
+                    "AWS Bedrock 凭证暂不支持同步到配置".to_string(),
+                ));
+            }
+            CredentialData::VertexServiceAccount { .. } => {
+                // Vertex AI 服务账号暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(
+                    "Vertex AI 服务账号凭证暂不支持同步到配置".to_string(),
+                ));
+            }
+            CredentialData::Ollama { .. } => {
+                // 本地模型服务暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(
+                    "本地模型凭证暂不支持同步到配置".to_string(),
+                ));
+            }
+            CredentialData::Mock { .. } => {
+                // Mock 凭证仅存在于凭证池
+                return Err(SyncError::InvalidCredentialType(
+                    "Mock 凭证不支持同步到配置".to_string(),
+                ));
+            }
         }
 
         self.update_config(config)
@@ -368,6 +409,18 @@ impl CredentialSyncService {
                     "Claude OAuth 凭证暂不支持同步到配置".to_string(),
                 ));
             }
+            PoolProviderType::Qwen => {
+                if let Some(pos) = config
+                    .credential_pool
+                    .qwen
+                    .iter()
+                    .position(|e| e.id == credential_id)
+                {
+                    let entry = config.credential_pool.qwen.remove(pos);
+                    self.delete_oauth_token_file(&entry.token_file)?;
+                    found = true;
+                }
+            }
             // Anthropic 兼容格式 - 不支持同步到配置
             PoolProviderType::AnthropicCompatible => {
                 return Err(SyncError::InvalidCredentialType(
@@ -378,7 +431,8 @@ impl CredentialSyncService {
             PoolProviderType::Anthropic
             | PoolProviderType::AzureOpenai
             | PoolProviderType::AwsBedrock
-            | PoolProviderType::Ollama => {
+            | PoolProviderType::Ollama
+            | PoolProviderType::Mock => {
                 return Err(SyncError::InvalidCredentialType(
                     "API Key Provider 凭证不支持同步到配置".to_string(),
                 ));
@@ -532,6 +586,20 @@ impl CredentialSyncService {
                     "Claude OAuth 凭证暂不支持同步到配置".to_string(),
                 ));
             }
+            CredentialData::QwenOAuth { creds_file_path } => {
+                if let Some(entry) = config
+                    .credential_pool
+                    .qwen
+                    .iter_mut()
+                    .find(|e| e.id == credential.uuid)
+                {
+                    entry.disabled = credential.is_disabled;
+                    let new_token_file =
+                        self.save_oauth_token_file(creds_file_path, &credential.uuid, "qwen")?;
+                    entry.token_file = new_token_file;
+                    found = true;
+                }
+            }
             CredentialData::AnthropicKey { api_key, base_url } => {
                 // Anthropic API Key 更新到 claude 配置
                 if let Some(entry) = config
@@ -546,6 +614,36 @@ impl CredentialSyncService {
                     found = true;
                 }
             }
+            CredentialData::AzureOpenaiKey { .. } => {
+                // Azure OpenAI 暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(
+                    "Azure OpenAI 凭证暂不支持同步到配置".to_string(),
+                ));
+            }
+            CredentialData::AwsBedrock { .. } => {
+                // AWS Bedrock 暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(
+                    "AWS Bedrock 凭证暂不支持同步到配置".to_string(),
+                ));
+            }
+            CredentialData::VertexServiceAccount { .. } => {
+                // Vertex AI 服务账号暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(
+                    "Vertex AI 服务账号凭证暂不支持同步到配置".to_string(),
+                ));
+            }
+            CredentialData::Ollama { .. } => {
+                // 本地模型服务暂不支持同步到配置
+                return Err(SyncError::InvalidCredentialType(
+                    "本地模型凭证暂不支持同步到配置".to_string(),
+                ));
+            }
+            CredentialData::Mock { .. } => {
+                // Mock 凭证仅存在于凭证池
+                return Err(SyncError::InvalidCredentialType(
+                    "Mock 凭证不支持同步到配置".to_string(),
+                ));
+            }
         }
 
         if !found {
@@ -598,6 +696,21 @@ impl CredentialSyncService {
             credentials.push(cred);
         }
 
+        // 加载 Qwen 凭证
+        for entry in &config.credential_pool.qwen {
+            let token_path = auth_dir.join(&entry.token_file);
+            let cred = ProviderCredential::new(
+                PoolProviderType::Qwen,
+                CredentialData::QwenOAuth {
+                    creds_file_path: token_path.to_string_lossy().to_string(),
+                },
+            );
+            let mut cred = cred;
+            cred.uuid = entry.id.clone();
+            cred.is_disabled = entry.disabled;
+            credentials.push(cred);
+        }
+
         // 加载 OpenAI 凭证
         for entry in &config.credential_pool.openai {
             let cred = ProviderCredential::new(
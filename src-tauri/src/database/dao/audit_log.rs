@@ -0,0 +1,182 @@
+//! 审计日志数据访问对象
+//!
+//! 按 request_id 存储脱敏后的完整请求体/响应体，用于排查协议转换问题。
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 审计日志条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub request_id: String,
+    pub created_at: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    /// 请求中的模型名称（如果可解析）
+    pub model: Option<String>,
+    pub status_code: Option<u16>,
+    pub duration_ms: Option<u64>,
+    /// 脱敏后的请求头（JSON 对象）
+    pub request_headers: Option<String>,
+    /// 脱敏后的请求体（列表查询时为空）
+    pub request_body: Option<String>,
+    /// 脱敏后的响应体（列表查询时为空）
+    pub response_body: Option<String>,
+    /// 请求体是否被截断
+    pub request_truncated: bool,
+    /// 响应体是否被截断
+    pub response_truncated: bool,
+}
+
+pub struct AuditLogDao;
+
+impl AuditLogDao {
+    /// 插入审计日志（相同 request_id 覆盖）
+    pub fn insert(conn: &Connection, entry: &AuditLogEntry) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT OR REPLACE INTO audit_logs
+             (request_id, created_at, method, path, model, status_code, duration_ms,
+              request_headers, request_body, response_body, request_truncated,
+              response_truncated)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                entry.request_id,
+                entry.created_at.to_rfc3339(),
+                entry.method,
+                entry.path,
+                entry.model,
+                entry.status_code,
+                entry.duration_ms.map(|d| d as i64),
+                entry.request_headers,
+                entry.request_body,
+                entry.response_body,
+                entry.request_truncated,
+                entry.response_truncated,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 根据 request_id 获取完整审计日志
+    pub fn get(
+        conn: &Connection,
+        request_id: &str,
+    ) -> Result<Option<AuditLogEntry>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT request_id, created_at, method, path, model, status_code, duration_ms,
+                    request_headers, request_body, response_body, request_truncated,
+                    response_truncated
+             FROM audit_logs WHERE request_id = ?1",
+        )?;
+
+        let mut rows = stmt.query([request_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_entry(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 按时间倒序列出审计日志（不包含请求体/响应体）
+    pub fn list(
+        conn: &Connection,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<AuditLogEntry>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT request_id, created_at, method, path, model, status_code, duration_ms,
+                    request_headers, NULL, NULL, request_truncated, response_truncated
+             FROM audit_logs
+             ORDER BY created_at DESC
+             LIMIT ?1 OFFSET ?2",
+        )?;
+
+        let entries = stmt.query_map(params![limit as i64, offset as i64], Self::row_to_entry)?;
+        entries.collect()
+    }
+
+    /// 删除指定时间之前的审计日志
+    pub fn delete_before(
+        conn: &Connection,
+        before: DateTime<Utc>,
+    ) -> Result<usize, rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM audit_logs WHERE created_at < ?1",
+            [before.to_rfc3339()],
+        )
+    }
+
+    /// 清空审计日志
+    pub fn clear(conn: &Connection) -> Result<usize, rusqlite::Error> {
+        conn.execute("DELETE FROM audit_logs", [])
+    }
+
+    fn row_to_entry(row: &rusqlite::Row) -> Result<AuditLogEntry, rusqlite::Error> {
+        let created_at_str: String = row.get(1)?;
+        let created_at = DateTime::parse_from_rfc3339(&created_at_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        Ok(AuditLogEntry {
+            request_id: row.get(0)?,
+            created_at,
+            method: row.get(2)?,
+            path: row.get(3)?,
+            model: row.get(4)?,
+            status_code: row.get(5)?,
+            duration_ms: row.get::<_, Option<i64>>(6)?.map(|d| d as u64),
+            request_headers: row.get(7)?,
+            request_body: row.get(8)?,
+            response_body: row.get(9)?,
+            request_truncated: row.get(10)?,
+            response_truncated: row.get(11)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(request_id: &str, created_at: DateTime<Utc>) -> AuditLogEntry {
+        AuditLogEntry {
+            request_id: request_id.to_string(),
+            created_at,
+            method: "POST".to_string(),
+            path: "/v1/messages".to_string(),
+            model: Some("claude-sonnet-4-5".to_string()),
+            status_code: Some(200),
+            duration_ms: Some(120),
+            request_headers: Some("{}".to_string()),
+            request_body: Some(r#"{"model":"claude-sonnet-4-5"}"#.to_string()),
+            response_body: Some("data: {}".to_string()),
+            request_truncated: false,
+            response_truncated: true,
+        }
+    }
+
+    #[test]
+    fn test_insert_get_list_cleanup() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+
+        let old = Utc::now() - chrono::Duration::days(10);
+        AuditLogDao::insert(&conn, &entry("old", old)).unwrap();
+        AuditLogDao::insert(&conn, &entry("new", Utc::now())).unwrap();
+
+        let full = AuditLogDao::get(&conn, "new").unwrap().unwrap();
+        assert_eq!(full.response_body.as_deref(), Some("data: {}"));
+        assert!(full.response_truncated);
+
+        let listed = AuditLogDao::list(&conn, 10, 0).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].request_id, "new");
+        assert!(listed[0].request_body.is_none());
+
+        let removed =
+            AuditLogDao::delete_before(&conn, Utc::now() - chrono::Duration::days(7)).unwrap();
+        assert_eq!(removed, 1);
+        assert!(AuditLogDao::get(&conn, "old").unwrap().is_none());
+    }
+}
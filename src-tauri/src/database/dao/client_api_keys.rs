@@ -0,0 +1,288 @@
+//! 客户端 API Key 数据访问对象
+//!
+//! 存储访问代理服务的客户端 Key 及其作用域（允许的模型、Provider、限流）。
+//! 数据库中只保存 Key 的 SHA-256 哈希和用于展示的前缀。
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// 数据模型
+// ============================================================================
+
+/// 客户端 API Key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientApiKey {
+    pub id: String,
+    pub name: String,
+    /// Key 的 SHA-256 哈希（不返回给前端）
+    #[serde(skip_serializing, default)]
+    pub key_hash: String,
+    /// Key 前缀（用于展示识别）
+    pub key_prefix: String,
+    pub enabled: bool,
+    /// 允许的模型（为空表示不限制，支持 `*` 后缀通配）
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    /// 允许的 Provider（为空表示不限制）
+    #[serde(default)]
+    pub allowed_providers: Vec<String>,
+    /// 每分钟请求数上限（为空表示不限制）
+    pub rate_limit_per_minute: Option<u32>,
+    pub usage_count: i64,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ClientApiKey {
+    /// 检查是否允许访问指定模型
+    pub fn allows_model(&self, model: &str) -> bool {
+        self.allowed_models.is_empty()
+            || self
+                .allowed_models
+                .iter()
+                .any(|pattern| scope_matches(pattern, model))
+    }
+
+    /// 检查是否允许使用指定 Provider
+    pub fn allows_provider(&self, provider: &str) -> bool {
+        self.allowed_providers.is_empty()
+            || self
+                .allowed_providers
+                .iter()
+                .any(|pattern| scope_matches(pattern, provider))
+    }
+}
+
+/// 作用域匹配：忽略大小写，支持 `*` 后缀通配
+fn scope_matches(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    let value = value.to_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => pattern == value,
+    }
+}
+
+// ============================================================================
+// DAO 实现
+// ============================================================================
+
+pub struct ClientApiKeyDao;
+
+impl ClientApiKeyDao {
+    /// 获取所有客户端 Key
+    pub fn get_all(conn: &Connection) -> Result<Vec<ClientApiKey>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, key_hash, key_prefix, enabled, allowed_models,
+                    allowed_providers, rate_limit_per_minute, usage_count,
+                    last_used_at, created_at
+             FROM client_api_keys
+             ORDER BY created_at",
+        )?;
+
+        let keys = stmt.query_map([], Self::row_to_key)?;
+        keys.collect()
+    }
+
+    /// 根据 ID 获取客户端 Key
+    pub fn get_by_id(conn: &Connection, id: &str) -> Result<Option<ClientApiKey>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, key_hash, key_prefix, enabled, allowed_models,
+                    allowed_providers, rate_limit_per_minute, usage_count,
+                    last_used_at, created_at
+             FROM client_api_keys
+             WHERE id = ?1",
+        )?;
+
+        let mut rows = stmt.query([id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_key(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 根据 Key 哈希获取客户端 Key
+    pub fn get_by_hash(
+        conn: &Connection,
+        key_hash: &str,
+    ) -> Result<Option<ClientApiKey>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, key_hash, key_prefix, enabled, allowed_models,
+                    allowed_providers, rate_limit_per_minute, usage_count,
+                    last_used_at, created_at
+             FROM client_api_keys
+             WHERE key_hash = ?1",
+        )?;
+
+        let mut rows = stmt.query([key_hash])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_key(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 插入客户端 Key
+    pub fn insert(conn: &Connection, key: &ClientApiKey) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO client_api_keys
+             (id, name, key_hash, key_prefix, enabled, allowed_models,
+              allowed_providers, rate_limit_per_minute, usage_count,
+              last_used_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                key.id,
+                key.name,
+                key.key_hash,
+                key.key_prefix,
+                key.enabled,
+                serde_json::to_string(&key.allowed_models).unwrap_or_default(),
+                serde_json::to_string(&key.allowed_providers).unwrap_or_default(),
+                key.rate_limit_per_minute,
+                key.usage_count,
+                key.last_used_at.map(|t| t.to_rfc3339()),
+                key.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 更新客户端 Key 的名称、状态和作用域
+    pub fn update(conn: &Connection, key: &ClientApiKey) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE client_api_keys SET
+             name = ?2, enabled = ?3, allowed_models = ?4,
+             allowed_providers = ?5, rate_limit_per_minute = ?6
+             WHERE id = ?1",
+            params![
+                key.id,
+                key.name,
+                key.enabled,
+                serde_json::to_string(&key.allowed_models).unwrap_or_default(),
+                serde_json::to_string(&key.allowed_providers).unwrap_or_default(),
+                key.rate_limit_per_minute,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 删除客户端 Key
+    pub fn delete(conn: &Connection, id: &str) -> Result<bool, rusqlite::Error> {
+        let affected = conn.execute("DELETE FROM client_api_keys WHERE id = ?1", [id])?;
+        Ok(affected > 0)
+    }
+
+    /// 记录一次使用
+    pub fn record_usage(
+        conn: &Connection,
+        id: &str,
+        used_at: DateTime<Utc>,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE client_api_keys SET usage_count = usage_count + 1, last_used_at = ?2
+             WHERE id = ?1",
+            params![id, used_at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// 从数据库行转换为 ClientApiKey
+    fn row_to_key(row: &rusqlite::Row) -> Result<ClientApiKey, rusqlite::Error> {
+        let allowed_models: Option<String> = row.get(5)?;
+        let allowed_providers: Option<String> = row.get(6)?;
+        let last_used_at_str: Option<String> = row.get(9)?;
+        let created_at_str: String = row.get(10)?;
+
+        let parse_list = |s: Option<String>| -> Vec<String> {
+            s.and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default()
+        };
+        let last_used_at = last_used_at_str.and_then(|s| {
+            DateTime::parse_from_rfc3339(&s)
+                .ok()
+                .map(|dt| dt.with_timezone(&Utc))
+        });
+        let created_at = DateTime::parse_from_rfc3339(&created_at_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        Ok(ClientApiKey {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            key_hash: row.get(2)?,
+            key_prefix: row.get(3)?,
+            enabled: row.get(4)?,
+            allowed_models: parse_list(allowed_models),
+            allowed_providers: parse_list(allowed_providers),
+            rate_limit_per_minute: row.get(7)?,
+            usage_count: row.get(8)?,
+            last_used_at,
+            created_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_with_scopes(models: &[&str], providers: &[&str]) -> ClientApiKey {
+        ClientApiKey {
+            id: "k1".to_string(),
+            name: "test".to_string(),
+            key_hash: "hash".to_string(),
+            key_prefix: "pc-abcd".to_string(),
+            enabled: true,
+            allowed_models: models.iter().map(|s| s.to_string()).collect(),
+            allowed_providers: providers.iter().map(|s| s.to_string()).collect(),
+            rate_limit_per_minute: None,
+            usage_count: 0,
+            last_used_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_empty_scopes_allow_all() {
+        let key = key_with_scopes(&[], &[]);
+        assert!(key.allows_model("gpt-4o"));
+        assert!(key.allows_provider("kiro"));
+    }
+
+    #[test]
+    fn test_scope_wildcard_and_case() {
+        let key = key_with_scopes(&["claude-*", "gpt-4o"], &["Kiro"]);
+        assert!(key.allows_model("claude-sonnet-4-5"));
+        assert!(key.allows_model("GPT-4o"));
+        assert!(!key.allows_model("gpt-4o-mini"));
+        assert!(key.allows_provider("kiro"));
+        assert!(!key.allows_provider("openai"));
+    }
+
+    #[test]
+    fn test_crud_roundtrip() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+
+        let mut key = key_with_scopes(&["claude-*"], &["kiro"]);
+        key.rate_limit_per_minute = Some(10);
+        ClientApiKeyDao::insert(&conn, &key).unwrap();
+
+        let loaded = ClientApiKeyDao::get_by_hash(&conn, "hash")
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.allowed_models, vec!["claude-*".to_string()]);
+        assert_eq!(loaded.rate_limit_per_minute, Some(10));
+
+        ClientApiKeyDao::record_usage(&conn, &key.id, Utc::now()).unwrap();
+        let loaded = ClientApiKeyDao::get_by_id(&conn, &key.id).unwrap().unwrap();
+        assert_eq!(loaded.usage_count, 1);
+        assert!(loaded.last_used_at.is_some());
+
+        assert!(ClientApiKeyDao::delete(&conn, &key.id).unwrap());
+        assert!(ClientApiKeyDao::get_all(&conn).unwrap().is_empty());
+    }
+}
@@ -1,5 +1,7 @@
 pub mod agent;
 pub mod api_key_provider;
+pub mod audit_log;
+pub mod client_api_keys;
 pub mod general_chat;
 pub mod installed_plugins;
 pub mod mcp;
@@ -7,4 +9,7 @@ pub mod orchestrator;
 pub mod prompts;
 pub mod provider_pool;
 pub mod providers;
+pub mod request_logs;
+pub mod response_cache;
 pub mod skills;
+pub mod token_usage;
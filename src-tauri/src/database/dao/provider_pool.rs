@@ -6,6 +6,7 @@ use crate::models::provider_pool_model::{
     CachedTokenInfo, CredentialData, CredentialSource, PoolProviderType, ProviderCredential,
     ProviderPools,
 };
+use crate::services::secret_store;
 use chrono::{DateTime, TimeZone, Utc};
 use rusqlite::{params, Connection};
 
@@ -18,7 +19,7 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, weight
              FROM provider_pool_credentials
              ORDER BY provider_type, created_at ASC",
         )?;
@@ -41,7 +42,7 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, weight
              FROM provider_pool_credentials
              WHERE provider_type = ?1
              ORDER BY created_at ASC",
@@ -67,7 +68,7 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, weight
              FROM provider_pool_credentials
              WHERE uuid = ?1",
         )?;
@@ -89,7 +90,7 @@ impl ProviderPoolDao {
             "SELECT uuid, provider_type, credential_data, name, is_healthy, is_disabled,
                     check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
                     last_used, last_error_time, last_error_message, last_health_check_time,
-                    last_health_check_model, created_at, updated_at, source, proxy_url
+                    last_health_check_model, created_at, updated_at, source, proxy_url, weight
              FROM provider_pool_credentials
              WHERE name = ?1",
         )?;
@@ -116,8 +117,9 @@ impl ProviderPoolDao {
 
     /// 插入新凭证
     pub fn insert(conn: &Connection, cred: &ProviderCredential) -> Result<(), rusqlite::Error> {
-        let credential_json =
-            serde_json::to_string(&cred.credential).unwrap_or_else(|_| "{}".to_string());
+        let credential_json = secret_store::seal(
+            &serde_json::to_string(&cred.credential).unwrap_or_else(|_| "{}".to_string()),
+        );
         let not_supported_models_json =
             serde_json::to_string(&cred.not_supported_models).unwrap_or_else(|_| "[]".to_string());
         let supported_models_json =
@@ -133,8 +135,8 @@ impl ProviderPoolDao {
              (uuid, provider_type, credential_data, name, is_healthy, is_disabled,
               check_health, check_model_name, not_supported_models, supported_models, usage_count, error_count,
               last_used, last_error_time, last_error_message, last_health_check_time,
-              last_health_check_model, created_at, updated_at, source, proxy_url)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+              last_health_check_model, created_at, updated_at, source, proxy_url, weight)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
             params![
                 cred.uuid,
                 cred.provider_type.to_string(),
@@ -157,6 +159,7 @@ impl ProviderPoolDao {
                 cred.updated_at.timestamp(),
                 source_str,
                 cred.proxy_url,
+                cred.weight,
             ],
         )?;
         Ok(())
@@ -164,8 +167,9 @@ impl ProviderPoolDao {
 
     /// 更新凭证
     pub fn update(conn: &Connection, cred: &ProviderCredential) -> Result<(), rusqlite::Error> {
-        let credential_json =
-            serde_json::to_string(&cred.credential).unwrap_or_else(|_| "{}".to_string());
+        let credential_json = secret_store::seal(
+            &serde_json::to_string(&cred.credential).unwrap_or_else(|_| "{}".to_string()),
+        );
         let not_supported_models_json =
             serde_json::to_string(&cred.not_supported_models).unwrap_or_else(|_| "[]".to_string());
         let supported_models_json =
@@ -177,7 +181,8 @@ impl ProviderPoolDao {
              is_disabled = ?6, check_health = ?7, check_model_name = ?8,
              not_supported_models = ?9, supported_models = ?10, usage_count = ?11, error_count = ?12,
              last_used = ?13, last_error_time = ?14, last_error_message = ?15,
-             last_health_check_time = ?16, last_health_check_model = ?17, updated_at = ?18, proxy_url = ?19
+             last_health_check_time = ?16, last_health_check_model = ?17, updated_at = ?18, proxy_url = ?19,
+             weight = ?20
              WHERE uuid = ?1",
             params![
                 cred.uuid,
@@ -199,6 +204,7 @@ impl ProviderPoolDao {
                 cred.last_health_check_model,
                 cred.updated_at.timestamp(),
                 cred.proxy_url,
+                cred.weight,
             ],
         )?;
         Ok(())
@@ -265,6 +271,62 @@ impl ProviderPoolDao {
         Ok(())
     }
 
+    /// 使用次数加一，返回是否找到凭证
+    ///
+    /// 在单条 SQL 中完成自增，多个连接并发记录时不会丢失计数
+    pub fn increment_usage(
+        conn: &Connection,
+        uuid: &str,
+        last_used: DateTime<Utc>,
+    ) -> Result<bool, rusqlite::Error> {
+        let affected = conn.execute(
+            "UPDATE provider_pool_credentials SET
+             usage_count = usage_count + 1, last_used = ?2, updated_at = ?3
+             WHERE uuid = ?1",
+            params![uuid, last_used.timestamp(), Utc::now().timestamp()],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// 错误次数加一并更新健康状态，返回是否找到凭证
+    ///
+    /// 在单条 SQL 中完成自增，多个连接并发记录时不会丢失计数
+    pub fn record_error(
+        conn: &Connection,
+        uuid: &str,
+        is_healthy: bool,
+        last_error_time: DateTime<Utc>,
+        last_error_message: Option<&str>,
+    ) -> Result<bool, rusqlite::Error> {
+        let affected = conn.execute(
+            "UPDATE provider_pool_credentials SET
+             is_healthy = ?2, error_count = error_count + 1, last_error_time = ?3,
+             last_error_message = ?4, updated_at = ?5
+             WHERE uuid = ?1",
+            params![
+                uuid,
+                is_healthy,
+                last_error_time.timestamp(),
+                last_error_message,
+                Utc::now().timestamp()
+            ],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// 更新负载均衡权重
+    pub fn update_weight(
+        conn: &Connection,
+        uuid: &str,
+        weight: u32,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "UPDATE provider_pool_credentials SET weight = ?2, updated_at = ?3 WHERE uuid = ?1",
+            params![uuid, weight, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
     /// 重置凭证计数器
     pub fn reset_counters(conn: &Connection, uuid: &str) -> Result<(), rusqlite::Error> {
         conn.execute(
@@ -315,10 +377,20 @@ impl ProviderPoolDao {
         let updated_at_ts: i64 = row.get(18)?;
         let source_str: Option<String> = row.get(19).ok();
         let proxy_url: Option<String> = row.get(20).ok();
+        let weight: u32 = row
+            .get::<_, Option<i64>>(21)
+            .ok()
+            .flatten()
+            .map(|w| w.clamp(0, u32::MAX as i64) as u32)
+            .unwrap_or(1);
 
         let provider_type: PoolProviderType =
             provider_type_str.parse().unwrap_or(PoolProviderType::Kiro);
 
+        let credential_json = secret_store::open(&credential_json).map_err(|e| {
+            tracing::warn!("[SECRETS] 凭证 {} 解密失败: {}", uuid, e);
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+        })?;
         let credential: CredentialData = serde_json::from_str(&credential_json).map_err(|e| {
             rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
         })?;
@@ -367,6 +439,7 @@ impl ProviderPoolDao {
             cached_token: None, // 从 get_token_cache 单独获取
             source,
             proxy_url,
+            weight,
         })
     }
 
@@ -386,8 +459,8 @@ impl ProviderPoolDao {
 
         let mut rows = stmt.query([uuid])?;
         if let Some(row) = rows.next()? {
-            let access_token: Option<String> = row.get(0)?;
-            let refresh_token: Option<String> = row.get(1)?;
+            let access_token = Self::open_token(uuid, row.get(0)?)?;
+            let refresh_token = Self::open_token(uuid, row.get(1)?)?;
             let expiry_time_str: Option<String> = row.get(2)?;
             let last_refresh_str: Option<String> = row.get(3)?;
             let refresh_error_count: i32 = row.get::<_, Option<i32>>(4)?.unwrap_or(0);
@@ -437,8 +510,8 @@ impl ProviderPoolDao {
              WHERE uuid = ?1",
             params![
                 uuid,
-                token_info.access_token,
-                token_info.refresh_token,
+                token_info.access_token.as_deref().map(secret_store::seal),
+                token_info.refresh_token.as_deref().map(secret_store::seal),
                 token_info.expiry_time.map(|t| t.to_rfc3339()),
                 token_info.last_refresh.map(|t| t.to_rfc3339()),
                 token_info.refresh_error_count as i32,
@@ -449,6 +522,76 @@ impl ProviderPoolDao {
         Ok(())
     }
 
+    /// 解密缓存的 token
+    fn open_token(uuid: &str, token: Option<String>) -> Result<Option<String>, rusqlite::Error> {
+        token
+            .map(|t| secret_store::open(&t))
+            .transpose()
+            .map_err(|e| {
+                tracing::warn!("[SECRETS] 凭证 {} 的 Token 缓存解密失败: {}", uuid, e);
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })
+    }
+
+    /// 按当前加密状态重写所有凭证数据和 Token 缓存
+    ///
+    /// 启用加密时将明文加密，禁用时将密文解密，返回重写的行数
+    pub fn rewrite_secrets(conn: &Connection) -> Result<usize, rusqlite::Error> {
+        let rows: Vec<(String, String, Option<String>, Option<String>)> = {
+            let mut stmt = conn.prepare(
+                "SELECT uuid, credential_data, cached_access_token, cached_refresh_token
+                 FROM provider_pool_credentials",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?;
+            rows.collect::<Result<_, _>>()?
+        };
+
+        let encrypt = secret_store::is_enabled();
+        let needs_rewrite = |value: &str| secret_store::is_sealed(value) != encrypt;
+        let rewrite = |value: &str| -> Result<String, rusqlite::Error> {
+            let plaintext = secret_store::open(value).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?;
+            Ok(secret_store::seal(&plaintext))
+        };
+
+        let mut rewritten = 0;
+        for (uuid, credential_data, access_token, refresh_token) in rows {
+            let changed = needs_rewrite(&credential_data)
+                || access_token.as_deref().is_some_and(needs_rewrite)
+                || refresh_token.as_deref().is_some_and(needs_rewrite);
+            if !changed {
+                continue;
+            }
+
+            conn.execute(
+                "UPDATE provider_pool_credentials SET
+                 credential_data = ?2,
+                 cached_access_token = ?3,
+                 cached_refresh_token = ?4
+                 WHERE uuid = ?1",
+                params![
+                    uuid,
+                    rewrite(&credential_data)?,
+                    access_token.as_deref().map(rewrite).transpose()?,
+                    refresh_token.as_deref().map(rewrite).transpose()?,
+                ],
+            )?;
+            rewritten += 1;
+        }
+        Ok(rewritten)
+    }
+
     /// 清除凭证的 Token 缓存
     pub fn clear_token_cache(conn: &Connection, uuid: &str) -> Result<(), rusqlite::Error> {
         conn.execute(
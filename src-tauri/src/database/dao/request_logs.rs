@@ -0,0 +1,477 @@
+//! 请求日志数据访问对象
+//!
+//! 持久化请求遥测日志，支持按 Provider / 模型 / 状态 / 时间过滤、分页，
+//! 以及基于 FTS5 的错误信息全文搜索。
+
+use crate::telemetry::{RequestLog, RequestStatus};
+use crate::ProviderType;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 单页最多返回的日志条数
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// 请求日志查询条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestLogQuery {
+    #[serde(default)]
+    pub provider: Option<ProviderType>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub status: Option<RequestStatus>,
+    #[serde(default)]
+    pub credential_id: Option<String>,
+    #[serde(default)]
+    pub client_key_id: Option<String>,
+    /// 调用方工具名称
+    #[serde(default)]
+    pub client_name: Option<String>,
+    /// 开始时间（包含）
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    /// 结束时间（包含）
+    #[serde(default)]
+    pub end: Option<DateTime<Utc>>,
+    /// 错误信息全文搜索关键词，多个关键词以空格分隔且需同时匹配
+    #[serde(default)]
+    pub search: Option<String>,
+    #[serde(default = "default_page_size")]
+    pub limit: usize,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+fn default_page_size() -> usize {
+    50
+}
+
+impl Default for RequestLogQuery {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            model: None,
+            status: None,
+            credential_id: None,
+            client_key_id: None,
+            client_name: None,
+            start: None,
+            end: None,
+            search: None,
+            limit: default_page_size(),
+            offset: 0,
+        }
+    }
+}
+
+/// 分页查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestLogPage {
+    /// 符合条件的总条数
+    pub total: u64,
+    pub limit: usize,
+    pub offset: usize,
+    /// 当前页日志（按时间倒序）
+    pub logs: Vec<RequestLog>,
+}
+
+const SELECT_COLUMNS: &str = "id, timestamp, provider, model, duration_ms, ttfb_ms, status,
+    http_status, input_tokens, output_tokens, total_tokens, error_message, is_streaming,
+    credential_id, client_key_id, retry_count, client_name, fallback_from_model";
+
+pub struct RequestLogDao;
+
+impl RequestLogDao {
+    /// 插入请求日志（相同 ID 覆盖），同时更新错误信息全文索引
+    pub fn upsert(conn: &Connection, log: &RequestLog) -> Result<(), rusqlite::Error> {
+        let replaced = conn.execute("DELETE FROM request_logs WHERE id = ?1", [&log.id])? > 0;
+        if replaced {
+            conn.execute("DELETE FROM request_logs_fts WHERE id = ?1", [&log.id])?;
+        }
+        conn.execute(
+            "INSERT INTO request_logs
+             (id, timestamp, provider, model, duration_ms, ttfb_ms, status, http_status,
+              input_tokens, output_tokens, total_tokens, error_message, is_streaming,
+              credential_id, client_key_id, retry_count, client_name, fallback_from_model)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                     ?17, ?18)",
+            params![
+                log.id,
+                format_timestamp(&log.timestamp),
+                log.provider.to_string(),
+                log.model,
+                log.duration_ms as i64,
+                log.ttfb_ms.map(|t| t as i64),
+                log.status.to_string(),
+                log.http_status,
+                log.input_tokens,
+                log.output_tokens,
+                log.total_tokens,
+                log.error_message,
+                log.is_streaming,
+                log.credential_id,
+                log.client_key_id,
+                log.retry_count,
+                log.client_name,
+                log.fallback_from_model,
+            ],
+        )?;
+
+        if let Some(message) = log.error_message.as_deref().filter(|m| !m.is_empty()) {
+            conn.execute(
+                "INSERT INTO request_logs_fts (id, error_message) VALUES (?1, ?2)",
+                params![log.id, message],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 更新请求持续时间（流式响应结束时补记），返回是否找到对应日志
+    pub fn update_duration(
+        conn: &Connection,
+        id: &str,
+        duration_ms: u64,
+    ) -> Result<bool, rusqlite::Error> {
+        let updated = conn.execute(
+            "UPDATE request_logs SET duration_ms = ?1 WHERE id = ?2",
+            params![duration_ms as i64, id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// 根据 ID 获取请求日志
+    pub fn get(conn: &Connection, id: &str) -> Result<Option<RequestLog>, rusqlite::Error> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM request_logs WHERE id = ?1"
+        ))?;
+
+        let mut rows = stmt.query([id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_log(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 按条件分页查询请求日志（按时间倒序）
+    pub fn search(
+        conn: &Connection,
+        query: &RequestLogQuery,
+    ) -> Result<RequestLogPage, rusqlite::Error> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+
+        if let Some(provider) = query.provider {
+            conditions.push("provider = ?");
+            values.push(Value::Text(provider.to_string()));
+        }
+        if let Some(model) = &query.model {
+            conditions.push("model = ?");
+            values.push(Value::Text(model.clone()));
+        }
+        if let Some(status) = query.status {
+            conditions.push("status = ?");
+            values.push(Value::Text(status.to_string()));
+        }
+        if let Some(credential_id) = &query.credential_id {
+            conditions.push("credential_id = ?");
+            values.push(Value::Text(credential_id.clone()));
+        }
+        if let Some(client_key_id) = &query.client_key_id {
+            conditions.push("client_key_id = ?");
+            values.push(Value::Text(client_key_id.clone()));
+        }
+        if let Some(client_name) = &query.client_name {
+            conditions.push("client_name = ?");
+            values.push(Value::Text(client_name.clone()));
+        }
+        if let Some(start) = &query.start {
+            conditions.push("timestamp >= ?");
+            values.push(Value::Text(format_timestamp(start)));
+        }
+        if let Some(end) = &query.end {
+            conditions.push("timestamp <= ?");
+            values.push(Value::Text(format_timestamp(end)));
+        }
+        if let Some(fts_query) = query.search.as_deref().and_then(build_fts_query) {
+            conditions
+                .push("id IN (SELECT id FROM request_logs_fts WHERE request_logs_fts MATCH ?)");
+            values.push(Value::Text(fts_query));
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM request_logs {where_clause}"),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let limit = query.limit.min(MAX_PAGE_SIZE);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM request_logs {where_clause}
+             ORDER BY timestamp DESC LIMIT ? OFFSET ?"
+        ))?;
+        values.push(Value::Integer(limit as i64));
+        values.push(Value::Integer(query.offset as i64));
+        let logs = stmt
+            .query_map(params_from_iter(values.iter()), Self::row_to_log)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RequestLogPage {
+            total: total as u64,
+            limit,
+            offset: query.offset,
+            logs,
+        })
+    }
+
+    /// 删除指定时间之前的请求日志
+    pub fn delete_before(
+        conn: &Connection,
+        before: DateTime<Utc>,
+    ) -> Result<usize, rusqlite::Error> {
+        let before = format_timestamp(&before);
+        conn.execute(
+            "DELETE FROM request_logs_fts
+             WHERE id IN (SELECT id FROM request_logs WHERE timestamp < ?1)",
+            [&before],
+        )?;
+        conn.execute("DELETE FROM request_logs WHERE timestamp < ?1", [&before])
+    }
+
+    /// 清空请求日志
+    pub fn clear(conn: &Connection) -> Result<usize, rusqlite::Error> {
+        conn.execute("DELETE FROM request_logs_fts", [])?;
+        conn.execute("DELETE FROM request_logs", [])
+    }
+
+    fn row_to_log(row: &rusqlite::Row) -> Result<RequestLog, rusqlite::Error> {
+        let timestamp_str: String = row.get(1)?;
+        let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+        let provider_str: String = row.get(2)?;
+        let status_str: String = row.get(6)?;
+
+        Ok(RequestLog {
+            id: row.get(0)?,
+            timestamp,
+            provider: provider_str.parse().unwrap_or(ProviderType::Kiro),
+            model: row.get(3)?,
+            duration_ms: row.get::<_, i64>(4)? as u64,
+            ttfb_ms: row.get::<_, Option<i64>>(5)?.map(|t| t as u64),
+            status: parse_status(&status_str),
+            http_status: row.get(7)?,
+            input_tokens: row.get(8)?,
+            output_tokens: row.get(9)?,
+            total_tokens: row.get(10)?,
+            error_message: row.get(11)?,
+            is_streaming: row.get(12)?,
+            credential_id: row.get(13)?,
+            client_key_id: row.get(14)?,
+            retry_count: row.get(15)?,
+            client_name: row.get(16)?,
+            fallback_from_model: row.get(17)?,
+        })
+    }
+}
+
+/// 统一为毫秒精度的 UTC 时间字符串，保证按字符串比较即按时间比较
+fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn parse_status(status: &str) -> RequestStatus {
+    serde_json::from_value(serde_json::Value::String(status.to_string()))
+        .unwrap_or(RequestStatus::Failed)
+}
+
+/// 将用户输入转换为 FTS5 查询：每个关键词按短语转义，关键词之间为 AND 关系，
+/// 不含字母数字的关键词会被忽略
+fn build_fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .filter(|term| term.chars().any(char::is_alphanumeric))
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(id: &str, provider: ProviderType, model: &str, error: Option<&str>) -> RequestLog {
+        let mut log = RequestLog::new(id.to_string(), provider, model.to_string(), false);
+        match error {
+            Some(message) => log.mark_failed(300, Some(500), message.to_string()),
+            None => log.mark_success(120, 200),
+        }
+        log.set_credential_id("cred-1".to_string());
+        log
+    }
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_upsert_get_roundtrip() {
+        let conn = setup();
+        let mut entry = log("req-1", ProviderType::ClaudeOAuth, "claude-sonnet-4", None);
+        entry.set_ttfb(80);
+        entry.set_client_name("claude-code".to_string());
+        entry.set_fallback_from_model("gpt-4o".to_string());
+        RequestLogDao::upsert(&conn, &entry).unwrap();
+
+        let loaded = RequestLogDao::get(&conn, "req-1").unwrap().unwrap();
+        assert_eq!(loaded.provider, ProviderType::ClaudeOAuth);
+        assert_eq!(loaded.status, RequestStatus::Success);
+        assert_eq!(loaded.ttfb_ms, Some(80));
+        assert_eq!(loaded.credential_id.as_deref(), Some("cred-1"));
+        assert_eq!(loaded.client_name.as_deref(), Some("claude-code"));
+        assert_eq!(loaded.fallback_from_model.as_deref(), Some("gpt-4o"));
+
+        assert!(RequestLogDao::update_duration(&conn, "req-1", 4000).unwrap());
+        assert!(!RequestLogDao::update_duration(&conn, "missing", 4000).unwrap());
+        let loaded = RequestLogDao::get(&conn, "req-1").unwrap().unwrap();
+        assert_eq!(loaded.duration_ms, 4000);
+    }
+
+    #[test]
+    fn test_search_filters_and_pagination() {
+        let conn = setup();
+        for i in 0..5 {
+            RequestLogDao::upsert(
+                &conn,
+                &log(
+                    &format!("ok-{i}"),
+                    ProviderType::Claude,
+                    "claude-sonnet-4",
+                    None,
+                ),
+            )
+            .unwrap();
+        }
+        RequestLogDao::upsert(
+            &conn,
+            &log(
+                "err-1",
+                ProviderType::OpenAI,
+                "gpt-4o",
+                Some("upstream connection reset"),
+            ),
+        )
+        .unwrap();
+        RequestLogDao::upsert(
+            &conn,
+            &log(
+                "err-2",
+                ProviderType::OpenAI,
+                "gpt-4o",
+                Some("invalid api key"),
+            ),
+        )
+        .unwrap();
+
+        let page = RequestLogDao::search(
+            &conn,
+            &RequestLogQuery {
+                provider: Some(ProviderType::Claude),
+                limit: 2,
+                offset: 4,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 5);
+        assert_eq!(page.logs.len(), 1);
+
+        let page = RequestLogDao::search(
+            &conn,
+            &RequestLogQuery {
+                status: Some(RequestStatus::Failed),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 2);
+
+        let page = RequestLogDao::search(
+            &conn,
+            &RequestLogQuery {
+                client_name: Some("cursor".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 0);
+
+        let page = RequestLogDao::search(
+            &conn,
+            &RequestLogQuery {
+                search: Some("connection reset".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.logs[0].id, "err-1");
+
+        // 特殊字符按普通文本处理，不会导致 FTS 语法错误
+        let page = RequestLogDao::search(
+            &conn,
+            &RequestLogQuery {
+                search: Some("\"api key (".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.logs[0].id, "err-2");
+
+        let page = RequestLogDao::search(
+            &conn,
+            &RequestLogQuery {
+                start: Some(Utc::now() + chrono::Duration::hours(1)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 0);
+    }
+
+    #[test]
+    fn test_delete_before_and_clear() {
+        let conn = setup();
+        let mut old = log("old", ProviderType::Claude, "m", Some("timeout"));
+        old.timestamp = Utc::now() - chrono::Duration::days(10);
+        RequestLogDao::upsert(&conn, &old).unwrap();
+        RequestLogDao::upsert(&conn, &log("new", ProviderType::Claude, "m", None)).unwrap();
+
+        let removed =
+            RequestLogDao::delete_before(&conn, Utc::now() - chrono::Duration::days(7)).unwrap();
+        assert_eq!(removed, 1);
+        assert!(RequestLogDao::get(&conn, "old").unwrap().is_none());
+        let fts_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM request_logs_fts", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(fts_rows, 0);
+
+        assert_eq!(RequestLogDao::clear(&conn).unwrap(), 1);
+    }
+}
@@ -0,0 +1,118 @@
+//! 响应缓存数据访问对象
+//!
+//! 按请求哈希持久化非流式补全响应，时间字段均为 Unix 秒。
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// 持久化的缓存条目
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseCacheRow {
+    pub cache_key: String,
+    pub model: String,
+    pub content_type: String,
+    pub body: Vec<u8>,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+pub struct ResponseCacheDao;
+
+impl ResponseCacheDao {
+    /// 写入缓存条目（相同 key 覆盖）
+    pub fn upsert(conn: &Connection, row: &ResponseCacheRow) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT OR REPLACE INTO response_cache
+             (cache_key, model, content_type, body, created_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                row.cache_key,
+                row.model,
+                row.content_type,
+                row.body,
+                row.created_at,
+                row.expires_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 获取未过期的缓存条目
+    pub fn get(
+        conn: &Connection,
+        cache_key: &str,
+        now: i64,
+    ) -> Result<Option<ResponseCacheRow>, rusqlite::Error> {
+        conn.query_row(
+            "SELECT cache_key, model, content_type, body, created_at, expires_at
+             FROM response_cache WHERE cache_key = ?1 AND expires_at > ?2",
+            params![cache_key, now],
+            |row| {
+                Ok(ResponseCacheRow {
+                    cache_key: row.get(0)?,
+                    model: row.get(1)?,
+                    content_type: row.get(2)?,
+                    body: row.get(3)?,
+                    created_at: row.get(4)?,
+                    expires_at: row.get(5)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    /// 删除已过期的缓存条目
+    pub fn delete_expired(conn: &Connection, now: i64) -> Result<usize, rusqlite::Error> {
+        conn.execute("DELETE FROM response_cache WHERE expires_at <= ?1", [now])
+    }
+
+    /// 统计未过期的缓存条目数
+    pub fn count(conn: &Connection, now: i64) -> Result<usize, rusqlite::Error> {
+        conn.query_row(
+            "SELECT COUNT(*) FROM response_cache WHERE expires_at > ?1",
+            [now],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|count| count as usize)
+    }
+
+    /// 清空缓存
+    pub fn clear(conn: &Connection) -> Result<usize, rusqlite::Error> {
+        conn.execute("DELETE FROM response_cache", [])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cache_key: &str, expires_at: i64) -> ResponseCacheRow {
+        ResponseCacheRow {
+            cache_key: cache_key.to_string(),
+            model: "gpt-4o".to_string(),
+            content_type: "application/json".to_string(),
+            body: br#"{"id":"chatcmpl-1"}"#.to_vec(),
+            created_at: 100,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_upsert_get_expire() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+
+        ResponseCacheDao::upsert(&conn, &row("live", 200)).unwrap();
+        ResponseCacheDao::upsert(&conn, &row("stale", 150)).unwrap();
+
+        let cached = ResponseCacheDao::get(&conn, "live", 160).unwrap().unwrap();
+        assert_eq!(cached, row("live", 200));
+        assert!(ResponseCacheDao::get(&conn, "stale", 160)
+            .unwrap()
+            .is_none());
+        assert_eq!(ResponseCacheDao::count(&conn, 160).unwrap(), 1);
+
+        assert_eq!(ResponseCacheDao::delete_expired(&conn, 160).unwrap(), 1);
+        assert_eq!(ResponseCacheDao::clear(&conn).unwrap(), 1);
+        assert!(ResponseCacheDao::get(&conn, "live", 160).unwrap().is_none());
+    }
+}
@@ -0,0 +1,101 @@
+//! Token 用量记录数据访问对象
+//!
+//! 以 JSON 保存 Token 用量记录，按时间范围读取用于跨实例用量统计。
+
+use crate::telemetry::TokenUsageRecord;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection};
+
+pub struct TokenUsageDao;
+
+impl TokenUsageDao {
+    /// 插入用量记录（相同 ID 覆盖）
+    pub fn insert(conn: &Connection, record: &TokenUsageRecord) -> Result<(), rusqlite::Error> {
+        let data = serde_json::to_string(record)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO token_usage_records (id, timestamp, data)
+             VALUES (?1, ?2, ?3)",
+            params![record.id, format_timestamp(&record.timestamp), data],
+        )?;
+        Ok(())
+    }
+
+    /// 获取指定时间之后（包含）的用量记录，按时间正序
+    pub fn get_since(
+        conn: &Connection,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<TokenUsageRecord>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT data FROM token_usage_records
+             WHERE timestamp >= ?1
+             ORDER BY timestamp ASC",
+        )?;
+        let rows = stmt.query_map([format_timestamp(&since)], |row| row.get::<_, String>(0))?;
+
+        let mut records = Vec::new();
+        for data in rows.flatten() {
+            match serde_json::from_str(&data) {
+                Ok(record) => records.push(record),
+                Err(e) => tracing::warn!("[TOKEN] 跳过无法解析的用量记录: {}", e),
+            }
+        }
+        Ok(records)
+    }
+
+    /// 删除指定时间之前的用量记录
+    pub fn delete_before(
+        conn: &Connection,
+        before: DateTime<Utc>,
+    ) -> Result<usize, rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM token_usage_records WHERE timestamp < ?1",
+            [format_timestamp(&before)],
+        )
+    }
+}
+
+/// 统一为毫秒精度的 UTC 时间字符串，保证按字符串比较即按时间比较
+fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::TokenSource;
+    use crate::ProviderType;
+
+    fn record(id: &str, hours_ago: i64) -> TokenUsageRecord {
+        let mut record = TokenUsageRecord::new(
+            id.to_string(),
+            ProviderType::Claude,
+            "claude-sonnet-4".to_string(),
+            100,
+            50,
+            TokenSource::Actual,
+        );
+        record.timestamp = Utc::now() - chrono::Duration::hours(hours_ago);
+        record
+    }
+
+    #[test]
+    fn test_insert_get_since_and_delete_before() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+
+        TokenUsageDao::insert(&conn, &record("old", 48)).unwrap();
+        TokenUsageDao::insert(&conn, &record("new", 1)).unwrap();
+        TokenUsageDao::insert(&conn, &record("new", 1)).unwrap();
+
+        let since = Utc::now() - chrono::Duration::hours(24);
+        let records = TokenUsageDao::get_since(&conn, since).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, "new");
+        assert_eq!(records[0].total_tokens, 150);
+
+        assert_eq!(TokenUsageDao::delete_before(&conn, since).unwrap(), 1);
+        let all = TokenUsageDao::get_since(&conn, since - chrono::Duration::days(30)).unwrap();
+        assert_eq!(all.len(), 1);
+    }
+}
@@ -1,13 +1,17 @@
 pub mod dao;
 pub mod migration;
+pub mod pool;
 pub mod schema;
+pub mod storage;
 pub mod system_providers;
 
-use rusqlite::Connection;
+pub use pool::{DbPool, DbPoolError, PooledConnection};
+
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-pub type DbConnection = Arc<Mutex<Connection>>;
+/// 共享的数据库连接池
+pub type DbConnection = Arc<DbPool>;
 
 /// 获取数据库文件路径
 pub fn get_db_path() -> Result<PathBuf, String> {
@@ -21,11 +25,8 @@ pub fn get_db_path() -> Result<PathBuf, String> {
 /// 初始化数据库连接
 pub fn init_database() -> Result<DbConnection, String> {
     let db_path = get_db_path()?;
-    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-
-    // 设置 busy_timeout 为 5 秒，避免 "database is locked" 错误
-    conn.busy_timeout(std::time::Duration::from_secs(5))
-        .map_err(|e| format!("设置 busy_timeout 失败: {}", e))?;
+    let pool = DbPool::open(&db_path, pool::DEFAULT_POOL_SIZE).map_err(|e| e.to_string())?;
+    let conn = pool.lock().map_err(|e| e.to_string())?;
 
     // 创建表结构
     schema::create_tables(&conn).map_err(|e| e.to_string())?;
@@ -72,5 +73,6 @@ pub fn init_database() -> Result<DbConnection, String> {
         }
     }
 
-    Ok(Arc::new(Mutex::new(conn)))
+    drop(conn);
+    Ok(Arc::new(pool))
 }
@@ -0,0 +1,249 @@
+//! SQLite 连接池
+//!
+//! 以 WAL 模式打开多个连接，读操作可以并发执行，写操作由 SQLite 自身串行化
+//! （`busy_timeout` 内等待写锁），不再所有数据库操作都排队在同一个连接上。
+//!
+//! `lock()` 保持原先 `Mutex<Connection>` 的用法，取出的连接在 guard 释放时归还连接池；
+//! 异步上下文中可使用 `run()` 在阻塞线程池中执行数据库操作，避免占用异步工作线程。
+
+use rusqlite::Connection;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// 默认最大连接数
+pub const DEFAULT_POOL_SIZE: usize = 8;
+
+/// 获取连接的最长等待时间
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 等待 SQLite 写锁的超时时间，避免 "database is locked" 错误
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 连接池错误
+#[derive(Debug, thiserror::Error)]
+pub enum DbPoolError {
+    #[error("获取数据库连接超时（{0:?} 内无空闲连接）")]
+    Timeout(Duration),
+    #[error("打开数据库连接失败: {0}")]
+    Open(#[from] rusqlite::Error),
+}
+
+struct PoolState {
+    idle: Vec<Connection>,
+    /// 已创建的连接数（含使用中的连接）
+    size: usize,
+}
+
+/// SQLite 连接池
+pub struct DbPool {
+    /// 数据库文件路径，为空时连接池只包含创建时传入的连接
+    path: Option<PathBuf>,
+    max_size: usize,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+impl DbPool {
+    /// 打开数据库文件，最多按需创建 `max_size` 个连接
+    ///
+    /// 立即打开第一个连接并切换到 WAL 模式，以便尽早暴露路径或权限错误
+    pub fn open(path: impl Into<PathBuf>, max_size: usize) -> Result<Self, DbPoolError> {
+        let path = path.into();
+        let conn = open_connection(&path)?;
+        Ok(Self {
+            path: Some(path),
+            max_size: max_size.max(1),
+            state: Mutex::new(PoolState {
+                idle: vec![conn],
+                size: 1,
+            }),
+            available: Condvar::new(),
+        })
+    }
+
+    /// 使用已有连接创建单连接的连接池（内存数据库、测试）
+    pub fn from_connection(conn: Connection) -> Self {
+        Self {
+            path: None,
+            max_size: 1,
+            state: Mutex::new(PoolState {
+                idle: vec![conn],
+                size: 1,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// 取出一个连接，没有空闲连接且已达上限时阻塞等待
+    pub fn lock(&self) -> Result<PooledConnection<'_>, DbPoolError> {
+        let deadline = Instant::now() + ACQUIRE_TIMEOUT;
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if let Some(conn) = state.idle.pop() {
+                return Ok(PooledConnection {
+                    pool: self,
+                    conn: Some(conn),
+                });
+            }
+
+            if let (Some(path), true) = (&self.path, state.size < self.max_size) {
+                // 打开连接期间不持有连接池锁
+                state.size += 1;
+                drop(state);
+                return match open_connection(path) {
+                    Ok(conn) => Ok(PooledConnection {
+                        pool: self,
+                        conn: Some(conn),
+                    }),
+                    Err(e) => {
+                        self.state
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .size -= 1;
+                        self.available.notify_one();
+                        Err(e.into())
+                    }
+                };
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(DbPoolError::Timeout(ACQUIRE_TIMEOUT));
+            }
+            state = self
+                .available
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// 在阻塞线程池中取出连接执行数据库操作
+    pub async fn run<T, F>(self: &Arc<Self>, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&Connection) -> Result<T, rusqlite::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.lock().map_err(|e| e.to_string())?;
+            f(&conn).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("数据库任务执行失败: {}", e))?
+    }
+
+    /// 已创建的连接数和空闲连接数
+    pub fn status(&self) -> (usize, usize) {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        (state.size, state.idle.len())
+    }
+
+    fn release(&self, conn: Connection) {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .idle
+            .push(conn);
+        self.available.notify_one();
+    }
+}
+
+fn open_connection(path: &Path) -> Result<Connection, rusqlite::Error> {
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    Ok(conn)
+}
+
+/// 从连接池取出的连接，释放时归还连接池
+pub struct PooledConnection<'a> {
+    pool: &'a DbPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("连接在归还前始终存在")
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("连接在归还前始终存在")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.release(conn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_connections_share_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = DbPool::open(dir.path().join("test.db"), 2).unwrap();
+
+        let first = pool.lock().unwrap();
+        first
+            .execute("CREATE TABLE items (id INTEGER PRIMARY KEY)", [])
+            .unwrap();
+        first
+            .execute("INSERT INTO items (id) VALUES (1)", [])
+            .unwrap();
+
+        // 第一个连接未归还时可取出第二个连接，并读到已提交的数据
+        let second = pool.lock().unwrap();
+        let count: i64 = second
+            .query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(pool.status(), (2, 0));
+
+        drop(first);
+        drop(second);
+        assert_eq!(pool.status(), (2, 2));
+    }
+
+    #[test]
+    fn test_waits_for_released_connection_when_exhausted() {
+        let pool = Arc::new(DbPool::from_connection(
+            Connection::open_in_memory().unwrap(),
+        ));
+        let held = pool.lock().unwrap();
+
+        let waiter = {
+            let pool = pool.clone();
+            std::thread::spawn(move || pool.lock().map(|conn| conn.is_autocommit()))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        drop(held);
+
+        assert!(waiter.join().unwrap().unwrap());
+        assert_eq!(pool.status(), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_run_executes_on_blocking_pool() {
+        let pool = Arc::new(DbPool::from_connection(
+            Connection::open_in_memory().unwrap(),
+        ));
+        let value: i64 = pool
+            .run(|conn| conn.query_row("SELECT 41 + 1", [], |row| row.get(0)))
+            .await
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+}
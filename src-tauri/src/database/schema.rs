@@ -60,6 +60,141 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     )?;
 
+    // 客户端 API Key 表（访问代理服务的多个 Key 及其作用域）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS client_api_keys (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            key_hash TEXT NOT NULL UNIQUE,
+            key_prefix TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            allowed_models TEXT,
+            allowed_providers TEXT,
+            rate_limit_per_minute INTEGER,
+            usage_count INTEGER NOT NULL DEFAULT 0,
+            last_used_at TEXT,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // 请求/响应审计日志表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audit_logs (
+            request_id TEXT PRIMARY KEY,
+            created_at TEXT NOT NULL,
+            method TEXT NOT NULL,
+            path TEXT NOT NULL,
+            model TEXT,
+            status_code INTEGER,
+            duration_ms INTEGER,
+            request_headers TEXT,
+            request_body TEXT,
+            response_body TEXT,
+            request_truncated INTEGER NOT NULL DEFAULT 0,
+            response_truncated INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_audit_logs_created_at ON audit_logs(created_at)",
+        [],
+    )?;
+
+    // 请求日志表（遥测请求日志持久化与检索）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS request_logs (
+            id TEXT PRIMARY KEY,
+            timestamp TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            ttfb_ms INTEGER,
+            status TEXT NOT NULL,
+            http_status INTEGER,
+            input_tokens INTEGER,
+            output_tokens INTEGER,
+            total_tokens INTEGER,
+            error_message TEXT,
+            is_streaming INTEGER NOT NULL DEFAULT 0,
+            credential_id TEXT,
+            client_key_id TEXT,
+            retry_count INTEGER NOT NULL DEFAULT 0,
+            client_name TEXT,
+            fallback_from_model TEXT
+        )",
+        [],
+    )?;
+
+    // Migration: 添加调用方工具名称字段
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN client_name TEXT", []);
+    // Migration: 添加长上下文降级前模型字段
+    let _ = conn.execute(
+        "ALTER TABLE request_logs ADD COLUMN fallback_from_model TEXT",
+        [],
+    );
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_request_logs_timestamp ON request_logs(timestamp)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_request_logs_provider ON request_logs(provider, timestamp)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_request_logs_model ON request_logs(model, timestamp)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_request_logs_status ON request_logs(status, timestamp)",
+        [],
+    )?;
+
+    // 请求日志错误信息全文索引
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS request_logs_fts USING fts5(
+            id UNINDEXED,
+            error_message
+        )",
+        [],
+    )?;
+
+    // Token 用量记录表（共享存储同步使用，记录以 JSON 保存）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS token_usage_records (
+            id TEXT PRIMARY KEY,
+            timestamp TEXT NOT NULL,
+            data TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_token_usage_records_timestamp
+         ON token_usage_records(timestamp)",
+        [],
+    )?;
+
+    // 响应缓存表（响应缓存启用持久化时使用）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS response_cache (
+            cache_key TEXT PRIMARY KEY,
+            model TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            body BLOB NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_response_cache_expires_at ON response_cache(expires_at)",
+        [],
+    )?;
+
     // Provider UI 状态表
     // _Requirements: 8.4_
     conn.execute(
@@ -237,6 +372,12 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     );
 
+    // Migration: 添加负载均衡权重字段
+    let _ = conn.execute(
+        "ALTER TABLE provider_pool_credentials ADD COLUMN weight INTEGER DEFAULT 1",
+        [],
+    );
+
     // 已安装插件表
     // _需求: 1.2, 1.3_
     conn.execute(
@@ -0,0 +1,323 @@
+//! 存储后端抽象
+//!
+//! 将凭证池、请求日志和 Token 用量的读写抽象为 [`Storage`] trait：
+//! - [`SqliteStorage`]：本地 SQLite，委托给现有 DAO，是请求路径上的工作存储
+//! - `PostgresStorage`：共享 Postgres（需启用 `postgres` 编译特性），多个实例共用
+//!
+//! 凭证池通过 [`sync_credentials`] 在本地与共享后端之间做三方合并同步。
+
+#[cfg(feature = "postgres")]
+mod postgres;
+mod sqlite;
+
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresStorage;
+pub use sqlite::SqliteStorage;
+
+use crate::config::{StorageBackend, StorageConfig};
+use crate::database::dao::request_logs::{RequestLogPage, RequestLogQuery};
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::telemetry::{RequestLog, TokenUsageRecord};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// 存储后端
+///
+/// 错误统一以字符串返回，与服务层的错误处理方式一致
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// 后端名称（用于日志）
+    fn name(&self) -> &'static str;
+
+    /// 获取所有凭证
+    async fn list_credentials(&self) -> Result<Vec<ProviderCredential>, String>;
+
+    /// 插入或整体覆盖凭证（不含 Token 缓存）
+    async fn upsert_credential(&self, credential: &ProviderCredential) -> Result<(), String>;
+
+    /// 删除凭证，返回是否存在
+    async fn delete_credential(&self, uuid: &str) -> Result<bool, String>;
+
+    /// 插入请求日志（相同 ID 覆盖）
+    async fn upsert_request_log(&self, log: &RequestLog) -> Result<(), String>;
+
+    /// 更新请求持续时间，返回是否找到对应日志
+    async fn update_request_log_duration(&self, id: &str, duration_ms: u64)
+        -> Result<bool, String>;
+
+    /// 根据 ID 获取请求日志
+    async fn get_request_log(&self, id: &str) -> Result<Option<RequestLog>, String>;
+
+    /// 按条件分页检索请求日志（按时间倒序）
+    async fn search_request_logs(&self, query: &RequestLogQuery) -> Result<RequestLogPage, String>;
+
+    /// 删除指定时间之前的请求日志
+    async fn delete_request_logs_before(&self, before: DateTime<Utc>) -> Result<usize, String>;
+
+    /// 清空请求日志
+    async fn clear_request_logs(&self) -> Result<usize, String>;
+
+    /// 保存 Token 用量记录（相同 ID 覆盖）
+    async fn record_token_usage(&self, record: &TokenUsageRecord) -> Result<(), String>;
+
+    /// 获取指定时间之后（包含）的 Token 用量记录
+    async fn token_usage_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<TokenUsageRecord>, String>;
+
+    /// 删除指定时间之前的 Token 用量记录
+    async fn delete_token_usage_before(&self, before: DateTime<Utc>) -> Result<usize, String>;
+}
+
+/// 按配置连接共享存储
+///
+/// `sqlite` 后端没有共享存储，返回 `None`
+pub async fn connect_shared(config: &StorageConfig) -> Result<Option<Arc<dyn Storage>>, String> {
+    match config.backend {
+        StorageBackend::Sqlite => Ok(None),
+        #[cfg(feature = "postgres")]
+        StorageBackend::Postgres => {
+            let url = config.resolve_postgres_url().ok_or_else(|| {
+                format!(
+                    "未配置 Postgres 连接串（storage.postgres_url 或环境变量 {}）",
+                    config.postgres_url_env
+                )
+            })?;
+            let storage = PostgresStorage::connect(&url, config.max_connections).await?;
+            Ok(Some(Arc::new(storage)))
+        }
+        #[cfg(not(feature = "postgres"))]
+        StorageBackend::Postgres => {
+            Err("当前构建未启用 postgres 特性，无法使用 Postgres 存储后端".to_string())
+        }
+    }
+}
+
+/// 凭证池同步计划
+#[derive(Debug, Default)]
+pub struct CredentialSyncPlan {
+    /// 需要写入共享后端的凭证
+    pub push: Vec<ProviderCredential>,
+    /// 需要写入本地的凭证
+    pub pull: Vec<ProviderCredential>,
+    /// 已在其他实例删除、需要从本地删除的凭证
+    pub delete_local: Vec<String>,
+    /// 已在本地删除、需要从共享后端删除的凭证
+    pub delete_remote: Vec<String>,
+    /// 同步完成后两端都存在的凭证 UUID，作为下一轮同步的已知集合
+    pub known: HashSet<String>,
+}
+
+impl CredentialSyncPlan {
+    /// 是否无需任何变更
+    pub fn is_empty(&self) -> bool {
+        self.push.is_empty()
+            && self.pull.is_empty()
+            && self.delete_local.is_empty()
+            && self.delete_remote.is_empty()
+    }
+}
+
+/// 计算凭证池三方合并同步计划
+///
+/// `known` 为上一轮同步后两端都存在的凭证，用于区分「新增」与「已被删除」：
+/// - 两端都有：按 `updated_at`（秒级，SQLite 只保存到秒）较新的一方覆盖另一方
+/// - 仅本地有：上一轮已知则说明被其他实例删除，否则为本地新增
+/// - 仅共享端有：上一轮已知则说明被本地删除，否则为其他实例新增
+pub fn plan_credential_sync(
+    local: Vec<ProviderCredential>,
+    remote: Vec<ProviderCredential>,
+    known: &HashSet<String>,
+) -> CredentialSyncPlan {
+    let mut plan = CredentialSyncPlan::default();
+    let mut remote: HashMap<String, ProviderCredential> = remote
+        .into_iter()
+        .map(|cred| (cred.uuid.clone(), cred))
+        .collect();
+
+    for local_cred in local {
+        match remote.remove(&local_cred.uuid) {
+            Some(remote_cred) => {
+                let local_ts = local_cred.updated_at.timestamp();
+                let remote_ts = remote_cred.updated_at.timestamp();
+                plan.known.insert(local_cred.uuid.clone());
+                if local_ts > remote_ts {
+                    plan.push.push(local_cred);
+                } else if remote_ts > local_ts {
+                    plan.pull.push(remote_cred);
+                }
+            }
+            None if known.contains(&local_cred.uuid) => plan.delete_local.push(local_cred.uuid),
+            None => {
+                plan.known.insert(local_cred.uuid.clone());
+                plan.push.push(local_cred);
+            }
+        }
+    }
+
+    for (uuid, remote_cred) in remote {
+        if known.contains(&uuid) {
+            plan.delete_remote.push(uuid);
+        } else {
+            plan.known.insert(uuid);
+            plan.pull.push(remote_cred);
+        }
+    }
+
+    plan
+}
+
+/// 在本地与共享后端之间同步凭证池，返回已执行的同步计划
+///
+/// Token 缓存属于各实例自身的刷新状态，不写入共享后端
+pub async fn sync_credentials(
+    local: &dyn Storage,
+    remote: &dyn Storage,
+    known: &HashSet<String>,
+) -> Result<CredentialSyncPlan, String> {
+    let plan = plan_credential_sync(
+        local.list_credentials().await?,
+        remote.list_credentials().await?,
+        known,
+    );
+
+    for cred in &plan.push {
+        let mut cred = cred.clone();
+        cred.cached_token = None;
+        remote.upsert_credential(&cred).await?;
+    }
+    for cred in &plan.pull {
+        local.upsert_credential(cred).await?;
+    }
+    for uuid in &plan.delete_local {
+        local.delete_credential(uuid).await?;
+    }
+    for uuid in &plan.delete_remote {
+        remote.delete_credential(uuid).await?;
+    }
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::{CredentialData, PoolProviderType};
+    use chrono::TimeZone;
+    use rusqlite::Connection;
+
+    fn storage() -> SqliteStorage {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        SqliteStorage::new(Arc::new(crate::database::DbPool::from_connection(conn)))
+    }
+
+    fn credential(api_key: &str) -> ProviderCredential {
+        ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: api_key.to_string(),
+                base_url: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_plan_uses_known_set_to_detect_deletions() {
+        let kept = credential("sk-kept");
+        let deleted_remotely = credential("sk-deleted-remotely");
+        let deleted_locally = credential("sk-deleted-locally");
+        let added_locally = credential("sk-added-locally");
+        let added_remotely = credential("sk-added-remotely");
+        let known: HashSet<String> = [&kept, &deleted_remotely, &deleted_locally]
+            .iter()
+            .map(|c| c.uuid.clone())
+            .collect();
+
+        let plan = plan_credential_sync(
+            vec![
+                kept.clone(),
+                deleted_remotely.clone(),
+                added_locally.clone(),
+            ],
+            vec![
+                kept.clone(),
+                deleted_locally.clone(),
+                added_remotely.clone(),
+            ],
+            &known,
+        );
+
+        assert_eq!(plan.delete_local, vec![deleted_remotely.uuid.clone()]);
+        assert_eq!(plan.delete_remote, vec![deleted_locally.uuid.clone()]);
+        assert_eq!(plan.push.len(), 1);
+        assert_eq!(plan.push[0].uuid, added_locally.uuid);
+        assert_eq!(plan.pull.len(), 1);
+        assert_eq!(plan.pull[0].uuid, added_remotely.uuid);
+        assert_eq!(plan.known.len(), 3);
+        assert!(!plan.known.contains(&deleted_remotely.uuid));
+    }
+
+    #[test]
+    fn test_plan_newer_side_wins() {
+        let base = credential("sk-1");
+        let mut newer = base.clone();
+        newer.updated_at = base.updated_at + chrono::Duration::seconds(5);
+        newer.is_disabled = true;
+        let known = HashSet::from([base.uuid.clone()]);
+
+        let plan = plan_credential_sync(vec![base.clone()], vec![newer.clone()], &known);
+        assert!(plan.push.is_empty());
+        assert_eq!(plan.pull.len(), 1);
+        assert!(plan.pull[0].is_disabled);
+
+        let plan = plan_credential_sync(vec![newer], vec![base.clone()], &known);
+        assert_eq!(plan.push.len(), 1);
+        assert!(plan.pull.is_empty());
+
+        // 秒级以内的差异视为相同，避免 SQLite 截断精度导致反复同步
+        let mut truncated = base.clone();
+        truncated.updated_at = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let mut precise = base.clone();
+        precise.updated_at = truncated.updated_at + chrono::Duration::milliseconds(500);
+        let plan = plan_credential_sync(vec![truncated], vec![precise], &known);
+        assert!(plan.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sync_credentials_between_instances() {
+        let shared = storage();
+        let instance_a = storage();
+        let instance_b = storage();
+
+        let cred = credential("sk-shared");
+        instance_a.upsert_credential(&cred).await.unwrap();
+
+        let known_a = sync_credentials(&instance_a, &shared, &HashSet::new())
+            .await
+            .unwrap()
+            .known;
+        let known_b = sync_credentials(&instance_b, &shared, &HashSet::new())
+            .await
+            .unwrap()
+            .known;
+        let on_b = instance_b.list_credentials().await.unwrap();
+        assert_eq!(on_b.len(), 1);
+        assert_eq!(on_b[0].uuid, cred.uuid);
+
+        // 实例 B 删除后，删除经共享后端传播到实例 A
+        instance_b.delete_credential(&cred.uuid).await.unwrap();
+        let plan = sync_credentials(&instance_b, &shared, &known_b)
+            .await
+            .unwrap();
+        assert_eq!(plan.delete_remote, vec![cred.uuid.clone()]);
+        let plan = sync_credentials(&instance_a, &shared, &known_a)
+            .await
+            .unwrap();
+        assert_eq!(plan.delete_local, vec![cred.uuid.clone()]);
+        assert!(instance_a.list_credentials().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,388 @@
+//! Postgres 存储后端
+//!
+//! 多个 ProxyCast 实例共用的存储。凭证、请求日志和用量记录整体以 JSONB 保存，
+//! 请求日志额外展开检索所需的列；错误信息搜索使用 `ILIKE`（每个关键词都需匹配）。
+//! 启用凭证加密时，凭证整体经 `secret_store` 加密后以 JSON 字符串保存，读取时解密。
+
+use super::Storage;
+use crate::database::dao::request_logs::{
+    RequestLogPage, RequestLogQuery, MAX_PAGE_SIZE as MAX_REQUEST_LOG_PAGE_SIZE,
+};
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::services::secret_store;
+use crate::telemetry::{RequestLog, TokenUsageRecord};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::types::Json;
+use sqlx::{Postgres, QueryBuilder};
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS provider_pool_credentials (
+        uuid TEXT PRIMARY KEY,
+        provider_type TEXT NOT NULL,
+        data JSONB NOT NULL,
+        updated_at TIMESTAMPTZ NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS request_logs (
+        id TEXT PRIMARY KEY,
+        timestamp TIMESTAMPTZ NOT NULL,
+        provider TEXT NOT NULL,
+        model TEXT NOT NULL,
+        status TEXT NOT NULL,
+        credential_id TEXT,
+        client_key_id TEXT,
+        client_name TEXT,
+        error_message TEXT,
+        data JSONB NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_request_logs_timestamp ON request_logs(timestamp)",
+    "CREATE TABLE IF NOT EXISTS token_usage_records (
+        id TEXT PRIMARY KEY,
+        timestamp TIMESTAMPTZ NOT NULL,
+        data JSONB NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_token_usage_records_timestamp
+     ON token_usage_records(timestamp)",
+];
+
+/// Postgres 共享存储
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    /// 连接数据库并创建表结构
+    pub async fn connect(url: &str, max_connections: u32) -> Result<Self, String> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections.max(1))
+            .connect(url)
+            .await
+            .map_err(|e| format!("连接 Postgres 失败: {}", e))?;
+        for statement in SCHEMA {
+            sqlx::query(statement)
+                .execute(&pool)
+                .await
+                .map_err(|e| format!("创建 Postgres 表结构失败: {}", e))?;
+        }
+        Ok(Self { pool })
+    }
+}
+
+fn push_condition(qb: &mut QueryBuilder<'_, Postgres>, first: &mut bool, sql: &str) {
+    qb.push(if *first { " WHERE " } else { " AND " });
+    qb.push(sql);
+    *first = false;
+}
+
+/// 追加请求日志查询条件，与 SQLite 版本的过滤语义保持一致
+fn push_filters(qb: &mut QueryBuilder<'_, Postgres>, query: &RequestLogQuery) {
+    let mut first = true;
+    if let Some(provider) = query.provider {
+        push_condition(qb, &mut first, "provider = ");
+        qb.push_bind(provider.to_string());
+    }
+    if let Some(model) = &query.model {
+        push_condition(qb, &mut first, "model = ");
+        qb.push_bind(model.clone());
+    }
+    if let Some(status) = query.status {
+        push_condition(qb, &mut first, "status = ");
+        qb.push_bind(status.to_string());
+    }
+    if let Some(credential_id) = &query.credential_id {
+        push_condition(qb, &mut first, "credential_id = ");
+        qb.push_bind(credential_id.clone());
+    }
+    if let Some(client_key_id) = &query.client_key_id {
+        push_condition(qb, &mut first, "client_key_id = ");
+        qb.push_bind(client_key_id.clone());
+    }
+    if let Some(client_name) = &query.client_name {
+        push_condition(qb, &mut first, "client_name = ");
+        qb.push_bind(client_name.clone());
+    }
+    if let Some(start) = query.start {
+        push_condition(qb, &mut first, "timestamp >= ");
+        qb.push_bind(start);
+    }
+    if let Some(end) = query.end {
+        push_condition(qb, &mut first, "timestamp <= ");
+        qb.push_bind(end);
+    }
+    for term in query
+        .search
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter(|term| term.chars().any(char::is_alphanumeric))
+    {
+        push_condition(qb, &mut first, "error_message ILIKE ");
+        qb.push_bind(format!("%{}%", escape_like(term)));
+        qb.push(" ESCAPE '\\'");
+    }
+}
+
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn db_error(e: sqlx::Error) -> String {
+    e.to_string()
+}
+
+/// 凭证写入前处理：启用加密时保存为密文字符串，否则保存为 JSON 对象
+fn seal_credential(credential: &ProviderCredential) -> Result<serde_json::Value, String> {
+    let json = serde_json::to_string(credential).map_err(|e| e.to_string())?;
+    let sealed = secret_store::seal(&json);
+    if secret_store::is_sealed(&sealed) {
+        Ok(serde_json::Value::String(sealed))
+    } else {
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+}
+
+/// 凭证读取后处理：密文解密，明文 JSON 对象直接解析
+fn open_credential(data: serde_json::Value) -> Result<ProviderCredential, String> {
+    match data {
+        serde_json::Value::String(sealed) => {
+            let json = secret_store::open(&sealed).map_err(|e| format!("凭证解密失败: {}", e))?;
+            serde_json::from_str(&json).map_err(|e| e.to_string())
+        }
+        data => serde_json::from_value(data).map_err(|e| e.to_string()),
+    }
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn list_credentials(&self) -> Result<Vec<ProviderCredential>, String> {
+        let rows: Vec<Json<serde_json::Value>> = sqlx::query_scalar(
+            "SELECT data FROM provider_pool_credentials ORDER BY provider_type, uuid",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_error)?;
+        rows.into_iter()
+            .map(|Json(data)| open_credential(data))
+            .collect()
+    }
+
+    async fn upsert_credential(&self, credential: &ProviderCredential) -> Result<(), String> {
+        let data = seal_credential(credential)?;
+        sqlx::query(
+            "INSERT INTO provider_pool_credentials (uuid, provider_type, data, updated_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (uuid) DO UPDATE SET
+             provider_type = EXCLUDED.provider_type, data = EXCLUDED.data,
+             updated_at = EXCLUDED.updated_at",
+        )
+        .bind(&credential.uuid)
+        .bind(credential.provider_type.to_string())
+        .bind(Json(data))
+        .bind(credential.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn delete_credential(&self, uuid: &str) -> Result<bool, String> {
+        let result = sqlx::query("DELETE FROM provider_pool_credentials WHERE uuid = $1")
+            .bind(uuid)
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn upsert_request_log(&self, log: &RequestLog) -> Result<(), String> {
+        sqlx::query(
+            "INSERT INTO request_logs
+             (id, timestamp, provider, model, status, credential_id, client_key_id,
+              client_name, error_message, data)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             ON CONFLICT (id) DO UPDATE SET
+             timestamp = EXCLUDED.timestamp, provider = EXCLUDED.provider,
+             model = EXCLUDED.model, status = EXCLUDED.status,
+             credential_id = EXCLUDED.credential_id, client_key_id = EXCLUDED.client_key_id,
+             client_name = EXCLUDED.client_name, error_message = EXCLUDED.error_message,
+             data = EXCLUDED.data",
+        )
+        .bind(&log.id)
+        .bind(log.timestamp)
+        .bind(log.provider.to_string())
+        .bind(&log.model)
+        .bind(log.status.to_string())
+        .bind(&log.credential_id)
+        .bind(&log.client_key_id)
+        .bind(&log.client_name)
+        .bind(&log.error_message)
+        .bind(Json(log))
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn update_request_log_duration(
+        &self,
+        id: &str,
+        duration_ms: u64,
+    ) -> Result<bool, String> {
+        let result = sqlx::query(
+            "UPDATE request_logs
+             SET data = jsonb_set(data, '{duration_ms}', to_jsonb($2::BIGINT))
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(duration_ms as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_request_log(&self, id: &str) -> Result<Option<RequestLog>, String> {
+        let row: Option<Json<RequestLog>> =
+            sqlx::query_scalar("SELECT data FROM request_logs WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(db_error)?;
+        Ok(row.map(|Json(log)| log))
+    }
+
+    async fn search_request_logs(&self, query: &RequestLogQuery) -> Result<RequestLogPage, String> {
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM request_logs");
+        push_filters(&mut count, query);
+        let total: i64 = count
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        let limit = query.limit.min(MAX_REQUEST_LOG_PAGE_SIZE);
+        let mut select = QueryBuilder::<Postgres>::new("SELECT data FROM request_logs");
+        push_filters(&mut select, query);
+        select.push(" ORDER BY timestamp DESC LIMIT ");
+        select.push_bind(limit as i64);
+        select.push(" OFFSET ");
+        select.push_bind(query.offset as i64);
+        let rows: Vec<Json<RequestLog>> = select
+            .build_query_scalar()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(db_error)?;
+
+        Ok(RequestLogPage {
+            total: total as u64,
+            limit,
+            offset: query.offset,
+            logs: rows.into_iter().map(|Json(log)| log).collect(),
+        })
+    }
+
+    async fn delete_request_logs_before(&self, before: DateTime<Utc>) -> Result<usize, String> {
+        let result = sqlx::query("DELETE FROM request_logs WHERE timestamp < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn clear_request_logs(&self) -> Result<usize, String> {
+        let result = sqlx::query("DELETE FROM request_logs")
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn record_token_usage(&self, record: &TokenUsageRecord) -> Result<(), String> {
+        sqlx::query(
+            "INSERT INTO token_usage_records (id, timestamp, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE SET timestamp = EXCLUDED.timestamp, data = EXCLUDED.data",
+        )
+        .bind(&record.id)
+        .bind(record.timestamp)
+        .bind(Json(record))
+        .execute(&self.pool)
+        .await
+        .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn token_usage_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<TokenUsageRecord>, String> {
+        let rows: Vec<Json<TokenUsageRecord>> = sqlx::query_scalar(
+            "SELECT data FROM token_usage_records WHERE timestamp >= $1 ORDER BY timestamp ASC",
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_error)?;
+        Ok(rows.into_iter().map(|Json(record)| record).collect())
+    }
+
+    async fn delete_token_usage_before(&self, before: DateTime<Utc>) -> Result<usize, String> {
+        let result = sqlx::query("DELETE FROM token_usage_records WHERE timestamp < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await
+            .map_err(db_error)?;
+        Ok(result.rows_affected() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::RequestStatus;
+    use crate::ProviderType;
+
+    #[test]
+    fn test_push_filters_binds_each_condition() {
+        let query = RequestLogQuery {
+            provider: Some(ProviderType::Claude),
+            status: Some(RequestStatus::Failed),
+            search: Some("rate_limit 100%".to_string()),
+            ..Default::default()
+        };
+        let mut qb = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM request_logs");
+        push_filters(&mut qb, &query);
+        assert_eq!(
+            qb.sql(),
+            "SELECT COUNT(*) FROM request_logs WHERE provider = $1 AND status = $2 \
+             AND error_message ILIKE $3 ESCAPE '\\' AND error_message ILIKE $4 ESCAPE '\\'"
+        );
+        assert_eq!(escape_like("100%_a\\b"), "100\\%\\_a\\\\b");
+    }
+    #[test]
+    fn test_plaintext_credential_round_trip() {
+        use crate::models::provider_pool_model::{CredentialData, PoolProviderType};
+
+        let credential = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+        let data = seal_credential(&credential).unwrap();
+        assert!(data.is_object());
+        assert_eq!(open_credential(data).unwrap().uuid, credential.uuid);
+
+        // 未加载主密钥时无法读取密文
+        let sealed = serde_json::Value::String(format!("{}AAAA", secret_store::SEALED_PREFIX));
+        assert!(open_credential(sealed).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! SQLite 存储后端
+//!
+//! 委托给现有 DAO，通过连接池在阻塞线程池中执行。
+
+use super::Storage;
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::dao::request_logs::{RequestLogDao, RequestLogPage, RequestLogQuery};
+use crate::database::dao::token_usage::TokenUsageDao;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::telemetry::{RequestLog, TokenUsageRecord};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// 本地 SQLite 存储
+pub struct SqliteStorage {
+    db: DbConnection,
+}
+
+impl SqliteStorage {
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn list_credentials(&self) -> Result<Vec<ProviderCredential>, String> {
+        self.db.run(ProviderPoolDao::get_all).await
+    }
+
+    async fn upsert_credential(&self, credential: &ProviderCredential) -> Result<(), String> {
+        let credential = credential.clone();
+        self.db
+            .run(move |conn| {
+                if ProviderPoolDao::get_by_uuid(conn, &credential.uuid)?.is_some() {
+                    ProviderPoolDao::update(conn, &credential)
+                } else {
+                    ProviderPoolDao::insert(conn, &credential)
+                }
+            })
+            .await
+    }
+
+    async fn delete_credential(&self, uuid: &str) -> Result<bool, String> {
+        let uuid = uuid.to_string();
+        self.db
+            .run(move |conn| ProviderPoolDao::delete(conn, &uuid))
+            .await
+    }
+
+    async fn upsert_request_log(&self, log: &RequestLog) -> Result<(), String> {
+        let log = log.clone();
+        self.db
+            .run(move |conn| RequestLogDao::upsert(conn, &log))
+            .await
+    }
+
+    async fn update_request_log_duration(
+        &self,
+        id: &str,
+        duration_ms: u64,
+    ) -> Result<bool, String> {
+        let id = id.to_string();
+        self.db
+            .run(move |conn| RequestLogDao::update_duration(conn, &id, duration_ms))
+            .await
+    }
+
+    async fn get_request_log(&self, id: &str) -> Result<Option<RequestLog>, String> {
+        let id = id.to_string();
+        self.db.run(move |conn| RequestLogDao::get(conn, &id)).await
+    }
+
+    async fn search_request_logs(&self, query: &RequestLogQuery) -> Result<RequestLogPage, String> {
+        let query = query.clone();
+        self.db
+            .run(move |conn| RequestLogDao::search(conn, &query))
+            .await
+    }
+
+    async fn delete_request_logs_before(&self, before: DateTime<Utc>) -> Result<usize, String> {
+        self.db
+            .run(move |conn| RequestLogDao::delete_before(conn, before))
+            .await
+    }
+
+    async fn clear_request_logs(&self) -> Result<usize, String> {
+        self.db.run(RequestLogDao::clear).await
+    }
+
+    async fn record_token_usage(&self, record: &TokenUsageRecord) -> Result<(), String> {
+        let record = record.clone();
+        self.db
+            .run(move |conn| TokenUsageDao::insert(conn, &record))
+            .await
+    }
+
+    async fn token_usage_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<TokenUsageRecord>, String> {
+        self.db
+            .run(move |conn| TokenUsageDao::get_since(conn, since))
+            .await
+    }
+
+    async fn delete_token_usage_before(&self, before: DateTime<Utc>) -> Result<usize, String> {
+        self.db
+            .run(move |conn| TokenUsageDao::delete_before(conn, before))
+            .await
+    }
+}
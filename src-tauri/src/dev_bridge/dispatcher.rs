@@ -71,6 +71,19 @@ pub async fn handle_command(
             Ok(serde_json::to_value(recent)?)
         }
 
+        "query_logs" => {
+            let query: crate::logger::LogQuery = args
+                .and_then(|a| a.get("query").cloned())
+                .filter(|q| !q.is_null())
+                .map(serde_json::from_value)
+                .transpose()?
+                .unwrap_or_default();
+            let page = state.logs.read().await.query(&query)?;
+            Ok(serde_json::to_value(page)?)
+        }
+
+        "get_log_stats" => Ok(serde_json::to_value(state.logs.read().await.stats())?),
+
         "clear_logs" => {
             state.logs.write().await.clear();
             Ok(serde_json::json!({ "success": true }))
@@ -18,7 +18,7 @@
 pub use proxycast_core::{LogEntry, LogStore, LogStoreConfig, SharedLogStore};
 // infra crate 的类型通过 proxycast_infra 前缀访问，避免与 core 的 InjectionMode/InjectionRule 冲突
 pub use proxycast_infra::{
-    injection, proxy, resilience, telemetry, Failover, FailoverConfig, InjectionConfig,
+    injection, proxy, resilience, telemetry, transform, Failover, FailoverConfig, InjectionConfig,
     InjectionMode, InjectionResult, InjectionRule, Injector, LogRotationConfig, LoggerError,
     ModelStats, ModelTokenStats, PeriodTokenStats, ProviderStats, ProviderTokenStats,
     ProxyClientFactory, ProxyError, ProxyProtocol, RequestLog, RequestLogger, RequestStatus,
@@ -71,4 +71,5 @@ pub use app::{AppState, LogState, ProviderType, TokenCacheServiceState, TrayMana
 pub use services::provider_pool_service::ProviderPoolService;
 
 // 重新导出 run 函数
+pub use app::headless::run_cli as run_serve_cli;
 pub use app::run;
@@ -3,45 +3,22 @@ use chrono::{Duration, Local, Utc};
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use regex::Regex;
-use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
 use std::fs::{self, OpenOptions};
-use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-
-#[derive(Debug, Clone)]
-pub struct LogStoreConfig {
-    pub max_logs: usize,
-    pub retention_days: u32,
-    pub max_file_size: u64,
-    pub enable_file_logging: bool,
-}
-
-impl Default for LogStoreConfig {
-    fn default() -> Self {
-        Self {
-            max_logs: 1000,
-            retention_days: 7,
-            max_file_size: 10 * 1024 * 1024,
-            enable_file_logging: true,
-        }
-    }
-}
+use tokio::sync::{broadcast, RwLock};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LogEntry {
-    pub timestamp: String,
-    pub level: String,
-    pub message: String,
-}
+use proxycast_core::logger::{write_ndjson, LogBuffer, LogStoreConfig};
+pub use proxycast_core::logger::{LogEntry, LogPage, LogQuery, LogStats};
 
 pub struct LogStore {
-    logs: VecDeque<LogEntry>,
-    max_logs: usize,
+    /// 按级别分开保存的内存日志（带条数和内存上限）
+    logs: LogBuffer,
     config: LogStoreConfig,
     log_file_path: Option<PathBuf>,
+    /// 新日志推送通道（WebSocket 日志订阅）
+    event_sender: broadcast::Sender<LogEntry>,
 }
 
 impl Default for LogStore {
@@ -58,12 +35,13 @@ impl Default for LogStore {
         let log_file = log_dir.join("proxycast.log");
 
         let config = LogStoreConfig::default();
+        let (event_sender, _) = broadcast::channel(1000);
 
         Self {
-            logs: VecDeque::new(),
-            max_logs: config.max_logs,
+            logs: LogBuffer::new(config.max_logs, config.max_memory_bytes),
             config,
             log_file_path: Some(log_file),
+            event_sender,
         }
     }
 }
@@ -77,7 +55,6 @@ impl LogStore {
         let mut store = Self::default();
         store.config.retention_days = logging.retention_days;
         store.config.enable_file_logging = logging.enabled;
-        store.max_logs = store.config.max_logs;
         store
     }
 
@@ -88,9 +65,13 @@ impl LogStore {
             timestamp: now.to_rfc3339(),
             level: level.to_string(),
             message: sanitized.clone(),
+            // 代理请求处理过程中记录的日志关联到请求 ID
+            request_id: crate::processor::current_request_id(),
         };
 
-        self.logs.push_back(entry.clone());
+        self.logs.push(entry.clone());
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.event_sender.send(entry.clone());
 
         // 写入日志文件
         if self.config.enable_file_logging {
@@ -105,11 +86,6 @@ impl LogStore {
                 self.prune_old_logs(path);
             }
         }
-
-        // 保持日志数量在限制内
-        if self.logs.len() > self.max_logs {
-            self.logs.pop_front();
-        }
     }
 
     /// 记录原始响应到单独的文件（用于调试）
@@ -130,8 +106,33 @@ impl LogStore {
         }
     }
 
+    /// 订阅新日志
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+        self.event_sender.subscribe()
+    }
+
     pub fn get_logs(&self) -> Vec<LogEntry> {
-        self.logs.iter().cloned().collect()
+        self.logs.entries()
+    }
+
+    /// 按级别、关键词过滤并分页查询（按时间倒序）
+    pub fn query(&self, query: &LogQuery) -> Result<LogPage, String> {
+        self.logs.query(query)
+    }
+
+    /// 内存日志统计（各级别条数、内存占用、丢弃计数）
+    pub fn stats(&self) -> LogStats {
+        self.logs.stats()
+    }
+
+    /// 将符合条件的日志按时间正序导出为 NDJSON 文件，返回导出的条数
+    pub fn export_ndjson(&self, path: &Path, query: &LogQuery) -> Result<usize, String> {
+        let entries = self.logs.matching(query)?;
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let file = fs::File::create(path).map_err(|e| e.to_string())?;
+        write_ndjson(BufWriter::new(file), entries).map_err(|e| e.to_string())
     }
 
     pub fn clear(&mut self) {
@@ -1,5 +1,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() == Some("serve") {
+        std::process::exit(proxycast_lib::run_serve_cli(args));
+    }
     proxycast_lib::run()
 }
@@ -0,0 +1,208 @@
+//! 请求/响应审计中间件
+//!
+//! 为每个请求分配 request_id（沿用客户端传入的 `x-request-id`），并通过响应头返回。
+//! 启用审计日志时，缓存请求体、在响应体传输过程中收集响应内容，
+//! 响应结束（或客户端断开）后在阻塞线程池中脱敏写入数据库。
+
+use crate::database::dao::audit_log::AuditLogEntry;
+use crate::database::DbConnection;
+use crate::processor::with_request_id;
+use crate::services::audit_log_service::AuditLogService;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// 请求 ID 头
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 审计时缓存请求体的上限（与服务器请求体大小限制一致）
+const MAX_REQUEST_BODY_BYTES: usize = 100 * 1024 * 1024;
+
+/// 审计中间件状态
+#[derive(Clone)]
+pub struct AuditState {
+    pub service: Arc<AuditLogService>,
+    pub db: Option<DbConnection>,
+}
+
+/// 审计中间件
+pub async fn audit_request(State(audit): State<AuditState>, req: Request, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let recorder = match (&audit.db, req.method() == Method::POST) {
+        (Some(db), true) if audit.service.is_enabled() => Some((db.clone(), audit.service.clone())),
+        _ => None,
+    };
+
+    let (req, recorder) = match recorder {
+        Some((db, service)) => {
+            let (parts, body) = req.into_parts();
+            let bytes = match axum::body::to_bytes(body, MAX_REQUEST_BODY_BYTES).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    return (
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        format!("Failed to read request body: {}", e),
+                    )
+                        .into_response();
+                }
+            };
+            let recorder = AuditRecorder::new(
+                service,
+                db,
+                &request_id,
+                &parts.method,
+                parts.uri.path(),
+                &parts.headers,
+                &bytes,
+            );
+            (
+                Request::from_parts(parts, Body::from(bytes)),
+                Some(recorder),
+            )
+        }
+        None => (req, None),
+    };
+
+    let mut response = with_request_id(request_id.clone(), next.run(req)).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    match recorder {
+        Some(mut recorder) => {
+            recorder.entry.status_code = Some(response.status().as_u16());
+            let (parts, body) = response.into_parts();
+            let stream = body.into_data_stream().map(move |chunk| {
+                if let Ok(bytes) = &chunk {
+                    recorder.append(bytes);
+                }
+                chunk
+            });
+            Response::from_parts(parts, Body::from_stream(stream))
+        }
+        None => response,
+    }
+}
+
+/// 客户端传入的 request_id 只接受较短的字母、数字、`-`、`_`、`.`
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// 收集响应体，释放时写入审计日志
+struct AuditRecorder {
+    service: Arc<AuditLogService>,
+    db: DbConnection,
+    entry: AuditLogEntry,
+    started_at: Instant,
+    max_body_bytes: usize,
+    response_body: Vec<u8>,
+}
+
+impl AuditRecorder {
+    fn new(
+        service: Arc<AuditLogService>,
+        db: DbConnection,
+        request_id: &str,
+        method: &Method,
+        path: &str,
+        headers: &axum::http::HeaderMap,
+        body: &Bytes,
+    ) -> Self {
+        let max_body_bytes = service.max_body_bytes();
+        let model = serde_json::from_slice::<serde_json::Value>(body)
+            .ok()
+            .and_then(|v| v.get("model")?.as_str().map(|s| s.to_string()));
+        let request_truncated = body.len() > max_body_bytes;
+        let request_body = service.redact_body(&body[..body.len().min(max_body_bytes)]);
+
+        let entry = AuditLogEntry {
+            request_id: request_id.to_string(),
+            created_at: Utc::now(),
+            method: method.to_string(),
+            path: path.to_string(),
+            model,
+            status_code: None,
+            duration_ms: None,
+            request_headers: Some(service.redact_headers(headers)),
+            request_body: Some(request_body),
+            response_body: None,
+            request_truncated,
+            response_truncated: false,
+        };
+
+        Self {
+            service,
+            db,
+            entry,
+            started_at: Instant::now(),
+            max_body_bytes,
+            response_body: Vec::new(),
+        }
+    }
+
+    fn append(&mut self, chunk: &Bytes) {
+        let remaining = self.max_body_bytes.saturating_sub(self.response_body.len());
+        if chunk.len() > remaining {
+            self.entry.response_truncated = true;
+        }
+        self.response_body
+            .extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+    }
+}
+
+impl Drop for AuditRecorder {
+    fn drop(&mut self) {
+        let request_body = self.entry.request_body.take();
+        let mut entry = self.entry.clone();
+        entry.request_body = request_body;
+        entry.duration_ms = Some(self.started_at.elapsed().as_millis() as u64);
+        let response_body = std::mem::take(&mut self.response_body);
+        let service = self.service.clone();
+        let db = self.db.clone();
+        let write = move || {
+            entry.response_body = Some(service.redact_body(&response_body));
+            service.record(&db, &entry);
+        };
+
+        // Drop 运行在 tokio 工作线程上，脱敏和 SQLite 写入（含定期清理）交给阻塞线程池
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(write);
+            }
+            Err(_) => write(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_request_id() {
+        assert!(is_valid_request_id("req_01HX-abc.1"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("bad id"));
+        assert!(!is_valid_request_id(&"a".repeat(129)));
+    }
+}
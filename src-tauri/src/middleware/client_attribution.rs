@@ -0,0 +1,15 @@
+//! 客户端归因中间件
+//!
+//! 从 `x-proxycast-client` 头（或 User-Agent）确定调用方工具名称，
+//! 在请求处理期间作用域化，创建 `RequestContext` 时自动带上，
+//! 用于按工具拆分用量与费用。
+
+use crate::processor::with_client_name;
+use crate::server::client_detector::client_name_from_headers;
+use axum::{extract::Request, middleware::Next, response::Response};
+
+/// 客户端归因中间件
+pub async fn attribute_client(req: Request, next: Next) -> Response {
+    let client_name = client_name_from_headers(req.headers());
+    with_client_name(client_name, next.run(req)).await
+}
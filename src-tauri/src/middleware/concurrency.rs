@@ -0,0 +1,60 @@
+//! 并发限制中间件
+//!
+//! 代理请求（POST）进入处理前获取全局并发名额，名额持有到响应体发送完毕；
+//! 等待队列已满或排队超时返回 429。Provider / 凭证维度的名额在选定凭证后获取。
+
+use crate::resilience::{ConcurrencyError, ConcurrencyLimiter, ConcurrencyPermit};
+use crate::server::error::ProxyApiError;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// 并发限制中间件
+pub async fn limit_concurrency(
+    State(limiter): State<Arc<RwLock<ConcurrencyLimiter>>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if req.method() != Method::POST {
+        return next.run(req).await;
+    }
+
+    let limiter = limiter.read().await.clone();
+    let permit = match limiter.acquire_global().await {
+        Ok(permit) => permit,
+        Err(e) => {
+            tracing::warn!("[CONCURRENCY] {} {}", req.uri().path(), e);
+            return concurrency_error_response(&e);
+        }
+    };
+
+    hold_permit_until_body_end(next.run(req).await, permit)
+}
+
+/// 响应体发送完毕（包括 SSE 流）后才释放名额
+pub fn hold_permit_until_body_end(response: Response, permit: ConcurrencyPermit) -> Response {
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        let _held = &permit;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 并发超限的 429 响应
+pub fn concurrency_error_response(error: &ConcurrencyError) -> Response {
+    let mut response = ProxyApiError::rate_limited(error.to_string())
+        .with_code("concurrency_limit_exceeded")
+        .into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+    response
+}
@@ -0,0 +1,185 @@
+//! IP 白名单中间件
+//!
+//! `server.allowed_ips` 非空时只允许列表中的客户端地址访问，条目可以是单个 IP（`192.168.1.20`）
+//! 或 CIDR（`10.0.0.0/8`、`fd00::/8`）。回环地址始终允许，避免把本机的桌面端挡在外面。
+//! 客户端地址取自 TCP 连接，不信任 `X-Forwarded-For`。
+
+use crate::server::error::ProxyApiError;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// IP 网段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    fn parse(entry: &str) -> Result<Self, String> {
+        let (addr, prefix) = match entry.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (entry.trim(), None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("无效的 IP 地址: {}", entry))?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max_prefix)
+                .ok_or_else(|| format!("无效的前缀长度: {}", entry))?,
+            None => max_prefix,
+        };
+        Ok(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let (network, ip, bits) = match (self.addr, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                (u32::from(network) as u128, u32::from(ip) as u128, 32)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(network), u128::from(ip), 128),
+            _ => return false,
+        };
+        if self.prefix == 0 {
+            return true;
+        }
+        let shift = bits - self.prefix as u32;
+        network >> shift == ip >> shift
+    }
+}
+
+/// 客户端 IP 白名单
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IpAllowlist {
+    networks: Vec<IpNetwork>,
+}
+
+impl IpAllowlist {
+    /// 从配置条目创建白名单，返回无法解析的条目说明
+    ///
+    /// 无效条目会被跳过（白名单只会更严格），配置校验阶段已将其报告为错误
+    pub fn from_entries(entries: &[String]) -> (Self, Vec<String>) {
+        let mut networks = Vec::new();
+        let mut errors = Vec::new();
+        for entry in entries {
+            match IpNetwork::parse(entry) {
+                Ok(network) => networks.push(network),
+                Err(e) => errors.push(e),
+            }
+        }
+        (Self { networks }, errors)
+    }
+
+    /// 校验单个配置条目
+    pub fn validate_entry(entry: &str) -> Result<(), String> {
+        IpNetwork::parse(entry).map(|_| ())
+    }
+
+    /// 是否未配置任何条目
+    pub fn is_empty(&self) -> bool {
+        self.networks.is_empty()
+    }
+
+    /// 客户端地址是否允许访问
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        // 双栈监听时 IPv4 客户端以 IPv4 映射地址（::ffff:a.b.c.d）出现
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            ip => ip,
+        };
+        self.networks.is_empty()
+            || ip.is_loopback()
+            || self.networks.iter().any(|network| network.contains(ip))
+    }
+}
+
+/// IP 白名单中间件
+///
+/// 配置了白名单但无法获取客户端地址时保守地拒绝请求
+pub async fn filter_client_ip(
+    State(allowlist): State<Arc<RwLock<IpAllowlist>>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let client_ip = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ci| ci.0.ip());
+    let allowed = {
+        let allowlist = allowlist.read().await;
+        allowlist.is_empty() || client_ip.is_some_and(|ip| allowlist.is_allowed(ip))
+    };
+    if allowed {
+        return next.run(req).await;
+    }
+
+    tracing::warn!(
+        "[ACCESS] 拒绝不在 IP 白名单中的客户端: {:?} {}",
+        client_ip,
+        req.uri().path()
+    );
+    ProxyApiError::permission("Client IP address is not allowed")
+        .with_code("ip_not_allowed")
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist(entries: &[&str]) -> IpAllowlist {
+        let entries: Vec<String> = entries.iter().map(|e| e.to_string()).collect();
+        let (allowlist, errors) = IpAllowlist::from_entries(&entries);
+        assert!(errors.is_empty(), "{:?}", errors);
+        allowlist
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cidr_matching() {
+        let list = allowlist(&["192.168.1.0/24", "10.0.0.5", "fd00::/8"]);
+        assert!(list.is_allowed(ip("192.168.1.200")));
+        assert!(!list.is_allowed(ip("192.168.2.1")));
+        assert!(list.is_allowed(ip("10.0.0.5")));
+        assert!(!list.is_allowed(ip("10.0.0.6")));
+        assert!(list.is_allowed(ip("fd12:3456::1")));
+        assert!(!list.is_allowed(ip("2001:db8::1")));
+        // IPv4 映射地址按 IPv4 匹配
+        assert!(list.is_allowed(ip("::ffff:192.168.1.7")));
+        // 回环地址始终允许
+        assert!(list.is_allowed(ip("127.0.0.1")));
+        assert!(list.is_allowed(ip("::1")));
+
+        assert!(allowlist(&["0.0.0.0/0"]).is_allowed(ip("8.8.8.8")));
+        assert!(allowlist(&[]).is_allowed(ip("8.8.8.8")));
+    }
+
+    #[test]
+    fn test_invalid_entries_are_skipped() {
+        let entries = vec![
+            "10.0.0.0/33".to_string(),
+            "not-an-ip".to_string(),
+            "10.1.0.0/16".to_string(),
+        ];
+        let (list, errors) = IpAllowlist::from_entries(&entries);
+        assert_eq!(errors.len(), 2);
+        assert!(list.is_allowed(ip("10.1.2.3")));
+        assert!(!list.is_allowed(ip("10.0.0.1")));
+
+        assert!(IpAllowlist::validate_entry(" 172.16.0.0/12 ").is_ok());
+        assert!(IpAllowlist::validate_entry("::1/129").is_err());
+    }
+}
@@ -2,9 +2,19 @@
 //!
 //! 提供 HTTP 请求处理的中间件组件
 
+pub mod audit;
+pub mod client_attribution;
+pub mod concurrency;
+pub mod ip_allowlist;
 pub mod management_auth;
+pub mod request_trace;
 
 #[cfg(test)]
 mod tests;
 
+pub use audit::{audit_request, AuditState, REQUEST_ID_HEADER};
+pub use client_attribution::attribute_client;
+pub use concurrency::{concurrency_error_response, hold_permit_until_body_end, limit_concurrency};
+pub use ip_allowlist::{filter_client_ip, IpAllowlist};
 pub use management_auth::{ManagementAuthLayer, ManagementAuthService};
+pub use request_trace::trace_request;
@@ -0,0 +1,42 @@
+//! 请求链路追踪中间件
+//!
+//! 为每个代理请求创建根 span（`proxy.request`），Provider 调用、Token 刷新、
+//! 协议转换等步骤在其下创建子 span，启用 OTLP 导出时整棵 span 树会被推送到 Collector。
+//!
+//! 未启用 OTLP 时没有全局 subscriber，span 为空操作，不产生额外开销。
+
+use crate::telemetry::ROOT_SPAN_NAME;
+use axum::{extract::Request, http::Method, middleware::Next, response::Response};
+use tracing::{field, Instrument};
+
+/// 请求追踪中间件
+///
+/// 仅追踪 POST 请求（代理 API），健康检查、模型列表等 GET 请求不产生 span。
+pub async fn trace_request(req: Request, next: Next) -> Response {
+    if req.method() != Method::POST {
+        return next.run(req).await;
+    }
+
+    let span = tracing::info_span!(
+        ROOT_SPAN_NAME,
+        otel.kind = "server",
+        http.method = %req.method(),
+        http.route = %req.uri().path(),
+        http.status_code = field::Empty,
+        request.id = field::Empty,
+        model = field::Empty,
+        provider = field::Empty,
+        credential.id = field::Empty,
+        error = field::Empty,
+    );
+
+    let response = next.run(req).instrument(span.clone()).await;
+
+    let status = response.status();
+    span.record("http.status_code", status.as_u16());
+    if status.is_client_error() || status.is_server_error() {
+        span.record("error", format!("HTTP {}", status.as_u16()));
+    }
+
+    response
+}
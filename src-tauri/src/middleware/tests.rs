@@ -418,4 +418,50 @@ mod unit_tests {
 
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_rejects_overflow() {
+        use crate::middleware::limit_concurrency;
+        use crate::resilience::{ConcurrencyConfig, ConcurrencyLimiter};
+        use std::sync::Arc;
+        use tower::ServiceExt;
+
+        let limiter = Arc::new(tokio::sync::RwLock::new(ConcurrencyLimiter::new(
+            ConcurrencyConfig {
+                enabled: true,
+                max_concurrent: 1,
+                max_queue: 0,
+                ..Default::default()
+            },
+        )));
+        let app = axum::Router::new()
+            .route(
+                "/v1/messages",
+                axum::routing::post(|| async { "ok" }).get(|| async { "ok" }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                limiter.clone(),
+                limit_concurrency,
+            ));
+        let request = |method: &str| {
+            Request::builder()
+                .method(method)
+                .uri("/v1/messages")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let held = limiter.read().await.acquire_global().await.unwrap();
+        let response = app.clone().oneshot(request("POST")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("retry-after"));
+
+        // GET 请求不受限制
+        let response = app.clone().oneshot(request("GET")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drop(held);
+        let response = app.oneshot(request("POST")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 }
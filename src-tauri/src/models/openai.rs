@@ -11,15 +11,16 @@
 //!
 //! - 2025-12-27: 添加 web_search 工具支持，修复 Issue #49
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ImageUrl {
     pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detail: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type")]
 pub enum ContentPart {
     #[serde(rename = "text")]
@@ -28,7 +29,7 @@ pub enum ContentPart {
     ImageUrl { image_url: ImageUrl },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ToolCall {
     pub id: String,
     #[serde(rename = "type")]
@@ -36,20 +37,20 @@ pub struct ToolCall {
     pub function: FunctionCall,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FunctionCall {
     pub name: String,
     pub arguments: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(untagged)]
 pub enum MessageContent {
     Text(String),
     Parts(Vec<ContentPart>),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ChatMessage {
     pub role: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -119,7 +120,7 @@ impl ChatMessage {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FunctionDef {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -134,7 +135,7 @@ pub struct FunctionDef {
 /// - `function`: 标准函数调用工具，包含 function 字段
 /// - `web_search`: 联网搜索工具，无需额外字段
 /// - `web_search_20250305`: Claude Code 的联网搜索工具类型
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type")]
 pub enum Tool {
     /// 标准函数调用工具
@@ -148,7 +149,7 @@ pub enum Tool {
     WebSearch20250305,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ChatCompletionRequest {
     pub model: String,
     pub messages: Vec<ChatMessage>,
@@ -167,32 +168,39 @@ pub struct ChatCompletionRequest {
     /// 思维链强度：none, low, medium, high
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_effort: Option<String>,
+    /// 未建模的字段（如 `stream_options`、`stop`、`response_format`），原样转发给上游
+    #[serde(flatten)]
+    #[schema(ignore)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ResponseMessage {
     pub role: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// 推理内容（DeepSeek、Qwen 等 OpenAI 兼容接口）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Choice {
     pub index: u32,
     pub message: ResponseMessage,
     pub finish_reason: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ChatCompletionResponse {
     pub id: String,
     pub object: String,
@@ -202,17 +210,43 @@ pub struct ChatCompletionResponse {
     pub usage: Usage,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 流式响应中的工具调用分片
+///
+/// 同一工具调用的多个分片通过 `index` 关联：首个分片携带 `id`、`type` 和函数名，
+/// 后续分片只携带 `function.arguments` 的增量。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StreamToolCall {
+    pub index: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub call_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<StreamFunctionCall>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StreamFunctionCall {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StreamDelta {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub role: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tool_calls: Option<Vec<ToolCall>>,
+    pub tool_calls: Option<Vec<StreamToolCall>>,
+    /// 推理内容增量
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StreamChoice {
     pub index: u32,
     pub delta: StreamDelta,
@@ -220,7 +254,7 @@ pub struct StreamChoice {
     pub finish_reason: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ChatCompletionChunk {
     pub id: String,
     pub object: String,
@@ -307,3 +341,143 @@ pub struct ImageData {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub revised_prompt: Option<String>,
 }
+
+// ============================================================================
+// Embeddings API 数据模型
+// ============================================================================
+
+/// OpenAI Embeddings 请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingRequest {
+    /// 模型名称
+    pub model: String,
+
+    /// 输入内容（字符串、字符串数组或 token 数组）
+    pub input: EmbeddingInput,
+
+    /// 向量编码格式: "float" 或 "base64" (默认: "float")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<String>,
+
+    /// 输出向量维度 (可选)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+
+    /// 用户标识 (可选)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+/// Embeddings 输入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EmbeddingInput {
+    Text(String),
+    TextArray(Vec<String>),
+    Tokens(Vec<u32>),
+    TokensArray(Vec<Vec<u32>>),
+}
+
+impl EmbeddingInput {
+    /// 获取文本输入（token 数组输入返回 None）
+    pub fn texts(&self) -> Option<Vec<&str>> {
+        match self {
+            EmbeddingInput::Text(text) => Some(vec![text.as_str()]),
+            EmbeddingInput::TextArray(texts) => Some(texts.iter().map(|s| s.as_str()).collect()),
+            EmbeddingInput::Tokens(_) | EmbeddingInput::TokensArray(_) => None,
+        }
+    }
+
+    /// 输入条数
+    pub fn len(&self) -> usize {
+        match self {
+            EmbeddingInput::Text(_) | EmbeddingInput::Tokens(_) => 1,
+            EmbeddingInput::TextArray(texts) => texts.len(),
+            EmbeddingInput::TokensArray(tokens) => tokens.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 估算输入 Token 数（文本约 4 字符 = 1 token，token 数组按实际长度）
+    pub fn estimate_tokens(&self) -> u32 {
+        match self {
+            EmbeddingInput::Text(text) => text.len().div_ceil(4) as u32,
+            EmbeddingInput::TextArray(texts) => {
+                texts.iter().map(|t| t.len().div_ceil(4) as u32).sum()
+            }
+            EmbeddingInput::Tokens(tokens) => tokens.len() as u32,
+            EmbeddingInput::TokensArray(tokens) => tokens.iter().map(|t| t.len() as u32).sum(),
+        }
+    }
+}
+
+/// OpenAI Embeddings 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingResponse {
+    /// 固定为 "list"
+    pub object: String,
+
+    /// 向量数组（与输入顺序一致）
+    pub data: Vec<EmbeddingData>,
+
+    /// 模型名称
+    pub model: String,
+
+    /// Token 使用量
+    pub usage: EmbeddingUsage,
+}
+
+/// 单个向量数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingData {
+    /// 固定为 "embedding"
+    pub object: String,
+
+    /// 输入序号
+    pub index: u32,
+
+    /// 向量（float 数组或 base64 编码的 little-endian f32）
+    pub embedding: EmbeddingVector,
+}
+
+/// 向量内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EmbeddingVector {
+    Float(Vec<f32>),
+    Base64(String),
+}
+
+/// Embeddings Token 使用量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingUsage {
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let body = json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "hi"}],
+            "stream": true,
+            "stream_options": {"include_usage": true},
+            "stop": ["\n\n"],
+            "seed": 42
+        });
+        let request: ChatCompletionRequest = serde_json::from_value(body).unwrap();
+        assert_eq!(request.extra["stream_options"]["include_usage"], true);
+
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["stream_options"], json!({"include_usage": true}));
+        assert_eq!(value["seed"], 42);
+    }
+}
@@ -5,8 +5,44 @@
 use crate::plugin::PluginContext;
 use crate::ProviderType;
 use chrono::{DateTime, Utc};
+use std::future::Future;
 use std::time::Instant;
 
+tokio::task_local! {
+    /// 入口中间件为当前请求分配的 ID
+    static SCOPED_REQUEST_ID: String;
+    /// 入口中间件识别出的调用方工具名称
+    static SCOPED_CLIENT_NAME: Option<String>;
+}
+
+/// 在指定请求 ID 的作用域内执行
+///
+/// 作用域内创建的 `RequestContext` 复用该 ID，使审计日志、遥测与响应头中的
+/// `x-request-id` 保持一致
+pub async fn with_request_id<F: Future>(request_id: String, fut: F) -> F::Output {
+    SCOPED_REQUEST_ID.scope(request_id, fut).await
+}
+
+/// 当前作用域内的请求 ID（不在请求作用域内时返回 `None`）
+pub fn current_request_id() -> Option<String> {
+    SCOPED_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// 在指定调用方工具名称的作用域内执行
+///
+/// 作用域内创建的 `RequestContext` 携带该名称，用于按工具统计用量和费用
+pub async fn with_client_name<F: Future>(client_name: Option<String>, fut: F) -> F::Output {
+    SCOPED_CLIENT_NAME.scope(client_name, fut).await
+}
+
+/// 当前作用域内的调用方工具名称
+pub fn current_client_name() -> Option<String> {
+    SCOPED_CLIENT_NAME
+        .try_with(|name| name.clone())
+        .ok()
+        .flatten()
+}
+
 /// 请求上下文
 ///
 /// 在请求处理管道中传递的上下文信息
@@ -26,6 +62,12 @@ pub struct RequestContext {
     pub provider: Option<ProviderType>,
     /// 使用的凭证 ID
     pub credential_id: Option<String>,
+    /// 发起请求的客户端 Key ID（使用主 Key 时为空）
+    pub client_key_id: Option<String>,
+    /// 调用方工具名称（如 `claude-code`，来自 `x-proxycast-client` 头或 User-Agent）
+    pub client_name: Option<String>,
+    /// 降级前请求的模型（超出上下文窗口改用长上下文模型时设置）
+    pub fallback_from_model: Option<String>,
     /// 重试次数
     pub retry_count: u32,
     /// 是否为流式请求
@@ -39,7 +81,7 @@ pub struct RequestContext {
 impl RequestContext {
     /// 创建新的请求上下文
     pub fn new(model: String) -> Self {
-        let request_id = uuid::Uuid::new_v4().to_string();
+        let request_id = current_request_id().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
         Self {
             request_id: request_id.clone(),
             start_time: Instant::now(),
@@ -48,6 +90,9 @@ impl RequestContext {
             resolved_model: model,
             provider: None,
             credential_id: None,
+            client_key_id: None,
+            client_name: current_client_name(),
+            fallback_from_model: None,
             retry_count: 0,
             is_stream: false,
             plugin_ctx: None,
@@ -71,11 +116,27 @@ impl RequestContext {
         self.credential_id = Some(credential_id);
     }
 
+    /// 设置客户端 Key ID
+    pub fn set_client_key_id(&mut self, client_key_id: String) {
+        self.client_key_id = Some(client_key_id);
+    }
+
+    /// 设置调用方工具名称
+    pub fn set_client_name(&mut self, client_name: String) {
+        self.client_name = Some(client_name);
+    }
+
     /// 设置解析后的模型名称
     pub fn set_resolved_model(&mut self, model: String) {
         self.resolved_model = model;
     }
 
+    /// 记录长上下文降级：原模型保存到 `fallback_from_model`，后续使用降级模型
+    pub fn set_context_fallback(&mut self, model: String) {
+        let previous = std::mem::replace(&mut self.resolved_model, model);
+        self.fallback_from_model.get_or_insert(previous);
+    }
+
     /// 增加重试计数
     pub fn increment_retry(&mut self) {
         self.retry_count += 1;
@@ -130,10 +191,36 @@ mod tests {
         assert_eq!(ctx.resolved_model, "claude-sonnet-4-5");
         assert!(ctx.provider.is_none());
         assert!(ctx.credential_id.is_none());
+        assert!(ctx.client_key_id.is_none());
+        assert!(ctx.client_name.is_none());
         assert_eq!(ctx.retry_count, 0);
         assert!(!ctx.is_stream);
     }
 
+    #[tokio::test]
+    async fn test_request_context_uses_scoped_request_id() {
+        let ctx = with_request_id("req-123".to_string(), async {
+            RequestContext::new("model".to_string())
+        })
+        .await;
+        assert_eq!(ctx.request_id, "req-123");
+
+        let ctx = RequestContext::new("model".to_string());
+        assert_ne!(ctx.request_id, "req-123");
+    }
+
+    #[tokio::test]
+    async fn test_request_context_uses_scoped_client_name() {
+        let ctx = with_client_name(Some("claude-code".to_string()), async {
+            RequestContext::new("model".to_string())
+        })
+        .await;
+        assert_eq!(ctx.client_name.as_deref(), Some("claude-code"));
+
+        let ctx = with_client_name(None, async { RequestContext::new("model".to_string()) }).await;
+        assert!(ctx.client_name.is_none());
+    }
+
     #[test]
     fn test_request_context_with_stream() {
         let ctx = RequestContext::new("model".to_string()).with_stream(true);
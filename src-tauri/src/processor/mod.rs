@@ -6,7 +6,7 @@
 //!
 //! 请求处理流程：
 //! 1. 认证 (AuthStep)
-//! 2. 参数注入 (InjectionStep)
+//! 2. 参数注入 (InjectionStep)，随后按配置顺序执行请求转换 (`transform_request`)
 //! 3. 路由解析 (RoutingStep)
 //! 4. 插件前置钩子 (PluginPreStep)
 //! 5. Provider 调用 (ProviderStep) - 包含重试和故障转移
@@ -17,20 +17,29 @@ mod context;
 mod error;
 mod steps;
 
-pub use context::RequestContext;
+pub use context::{
+    current_client_name, current_request_id, with_client_name, with_request_id, RequestContext,
+};
 pub use error::ProcessError;
 pub use steps::{
     AuthStep, InjectionStep, PipelineStep, PluginPostStep, PluginPreStep, ProviderStep,
     RoutingStep, TelemetryStep,
 };
 
+use crate::config::{ContextFallbackConfig, SelectorEndpointConfig};
+use crate::converter::reasoning::ReasoningMode;
 use crate::injection::Injector;
+use crate::middleware::IpAllowlist;
 use crate::plugin::PluginManager;
-use crate::resilience::{Failover, Retrier, TimeoutController};
+use crate::resilience::{
+    ChaosInjector, ConcurrencyLimiter, Failover, Hedger, Retrier, TimeoutController,
+};
 use crate::router::{ModelMapper, Router};
+use crate::transform::{PayloadFormat, TransformResult, Transformer};
 use crate::services::provider_pool_service::ProviderPoolService;
-use crate::telemetry::{StatsAggregator, TokenTracker};
+use crate::telemetry::{PricingTable, StatsAggregator, TokenTracker};
 use parking_lot::RwLock as ParkingLotRwLock;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -44,10 +53,26 @@ pub struct RequestProcessor {
     pub mapper: Arc<RwLock<ModelMapper>>,
     /// 参数注入器
     pub injector: Arc<RwLock<Injector>>,
+    /// 请求转换器（支持热重载）
+    pub transformer: Arc<RwLock<Transformer>>,
+    /// 推理内容处理方式（支持热重载）
+    pub reasoning: Arc<RwLock<ReasoningMode>>,
+    /// 长上下文降级配置（支持热重载）
+    pub context_fallback: Arc<RwLock<ContextFallbackConfig>>,
+    /// 选择器端点配置（支持热重载）
+    pub selector_endpoints: Arc<RwLock<HashMap<String, SelectorEndpointConfig>>>,
     /// 重试器
     pub retrier: Arc<Retrier>,
     /// 故障转移器
     pub failover: Arc<Failover>,
+    /// 请求对冲器（支持热重载）
+    pub hedger: Arc<RwLock<Hedger>>,
+    /// 故障注入器（支持热重载）
+    pub chaos: Arc<RwLock<ChaosInjector>>,
+    /// 并发限制器（支持热重载）
+    pub concurrency: Arc<RwLock<ConcurrencyLimiter>>,
+    /// 客户端 IP 白名单（支持热重载）
+    pub ip_allowlist: Arc<RwLock<IpAllowlist>>,
     /// 超时控制器
     pub timeout: Arc<TimeoutController>,
     /// 插件管理器
@@ -56,6 +81,8 @@ pub struct RequestProcessor {
     pub stats: Arc<ParkingLotRwLock<StatsAggregator>>,
     /// Token 追踪器（使用 parking_lot::RwLock 以支持与 TelemetryState 共享）
     pub tokens: Arc<ParkingLotRwLock<TokenTracker>>,
+    /// 费用估算单价表（支持热重载）
+    pub pricing: Arc<RwLock<PricingTable>>,
     /// 凭证池服务
     pub pool_service: Arc<ProviderPoolService>,
     /// 热重载协调锁（避免配置更新期间请求读取不一致的配置）
@@ -80,12 +107,21 @@ impl RequestProcessor {
             router,
             mapper,
             injector,
+            transformer: Arc::new(RwLock::new(Transformer::default())),
+            reasoning: Arc::new(RwLock::new(ReasoningMode::default())),
+            context_fallback: Arc::new(RwLock::new(ContextFallbackConfig::default())),
+            selector_endpoints: Arc::new(RwLock::new(HashMap::new())),
             retrier,
             failover,
+            hedger: Arc::new(RwLock::new(Hedger::default())),
+            chaos: Arc::new(RwLock::new(ChaosInjector::default())),
+            concurrency: Arc::new(RwLock::new(ConcurrencyLimiter::default())),
+            ip_allowlist: Arc::new(RwLock::new(IpAllowlist::default())),
             timeout,
             plugins,
             stats,
             tokens,
+            pricing: Arc::new(RwLock::new(PricingTable::default())),
             pool_service,
             reload_lock: Arc::new(RwLock::new(())),
         }
@@ -97,12 +133,21 @@ impl RequestProcessor {
             router: Arc::new(RwLock::new(Self::create_router_with_defaults())),
             mapper: Arc::new(RwLock::new(ModelMapper::new())),
             injector: Arc::new(RwLock::new(Injector::new())),
+            transformer: Arc::new(RwLock::new(Transformer::default())),
+            reasoning: Arc::new(RwLock::new(ReasoningMode::default())),
+            context_fallback: Arc::new(RwLock::new(ContextFallbackConfig::default())),
+            selector_endpoints: Arc::new(RwLock::new(HashMap::new())),
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
+            hedger: Arc::new(RwLock::new(Hedger::default())),
+            chaos: Arc::new(RwLock::new(ChaosInjector::default())),
+            concurrency: Arc::new(RwLock::new(ConcurrencyLimiter::default())),
+            ip_allowlist: Arc::new(RwLock::new(IpAllowlist::default())),
             timeout: Arc::new(TimeoutController::with_defaults()),
             plugins: Arc::new(PluginManager::with_defaults()),
             stats: Arc::new(ParkingLotRwLock::new(StatsAggregator::with_defaults())),
             tokens: Arc::new(ParkingLotRwLock::new(TokenTracker::with_defaults())),
+            pricing: Arc::new(RwLock::new(PricingTable::default())),
             pool_service,
             reload_lock: Arc::new(RwLock::new(())),
         }
@@ -135,12 +180,21 @@ impl RequestProcessor {
             router: Arc::new(RwLock::new(Self::create_router_with_defaults())),
             mapper: Arc::new(RwLock::new(ModelMapper::new())),
             injector: Arc::new(RwLock::new(Injector::new())),
+            transformer: Arc::new(RwLock::new(Transformer::default())),
+            reasoning: Arc::new(RwLock::new(ReasoningMode::default())),
+            context_fallback: Arc::new(RwLock::new(ContextFallbackConfig::default())),
+            selector_endpoints: Arc::new(RwLock::new(HashMap::new())),
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
+            hedger: Arc::new(RwLock::new(Hedger::default())),
+            chaos: Arc::new(RwLock::new(ChaosInjector::default())),
+            concurrency: Arc::new(RwLock::new(ConcurrencyLimiter::default())),
+            ip_allowlist: Arc::new(RwLock::new(IpAllowlist::default())),
             timeout: Arc::new(TimeoutController::with_defaults()),
             plugins: Arc::new(PluginManager::with_defaults()),
             stats,
             tokens,
+            pricing: Arc::new(RwLock::new(PricingTable::default())),
             pool_service,
             reload_lock: Arc::new(RwLock::new(())),
         }
@@ -226,6 +280,41 @@ impl RequestProcessor {
         provider
     }
 
+    /// 按配置顺序执行请求转换
+    ///
+    /// 变更（包括 dry-run 变更）记录到请求上下文的 `transform_result` 元数据
+    ///
+    /// # Arguments
+    /// * `ctx` - 请求上下文
+    /// * `provider` - 选定的 Provider
+    /// * `format` - 请求体格式
+    /// * `payload` - 请求体
+    pub async fn transform_request(
+        &self,
+        ctx: &mut RequestContext,
+        provider: &str,
+        format: PayloadFormat,
+        payload: &mut serde_json::Value,
+    ) -> TransformResult {
+        let transformer = self.transformer.read().await;
+        let result = transformer.apply(&ctx.resolved_model, provider, format, payload);
+
+        if result.has_changes() {
+            tracing::info!(
+                "[TRANSFORM] request_id={} provider={} changes={:?}",
+                ctx.request_id,
+                provider,
+                result.changes
+            );
+            ctx.set_metadata(
+                "transform_result",
+                serde_json::to_value(&result.changes).unwrap_or_default(),
+            );
+        }
+
+        result
+    }
+
     /// 执行完整的路由解析流程
     ///
     /// 包括模型别名解析和 Provider 选择
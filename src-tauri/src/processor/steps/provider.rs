@@ -6,13 +6,14 @@ use super::traits::{PipelineStep, StepError};
 use crate::processor::RequestContext;
 use crate::resilience::{
     Failover, FailoverConfig, FailoverManager, Retrier, RetryConfig, TimeoutConfig,
-    TimeoutController, TimeoutError,
+    TimeoutController, UpstreamError, UpstreamErrorKind,
 };
 use crate::services::provider_pool_service::ProviderPoolService;
 use crate::ProviderType;
 use async_trait::async_trait;
 use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Provider 调用结果
 #[derive(Debug, Clone)]
@@ -38,6 +39,8 @@ pub struct ProviderCallError {
     pub retryable: bool,
     /// 是否应触发故障转移
     pub should_failover: bool,
+    /// 上游建议的重试等待时间（`Retry-After`）
+    pub retry_after: Option<Duration>,
 }
 
 impl ProviderCallError {
@@ -48,6 +51,7 @@ impl ProviderCallError {
             status_code,
             retryable: true,
             should_failover: false,
+            retry_after: None,
         }
     }
 
@@ -58,6 +62,7 @@ impl ProviderCallError {
             status_code,
             retryable: false,
             should_failover: true,
+            retry_after: None,
         }
     }
 
@@ -68,6 +73,21 @@ impl ProviderCallError {
             status_code,
             retryable: false,
             should_failover: false,
+            retry_after: None,
+        }
+    }
+
+    /// 从已分类的上游错误构造
+    ///
+    /// 限流、过载、超时和服务异常可重试；限流与认证失败还会触发故障转移
+    pub fn from_upstream(error: UpstreamError) -> Self {
+        Self {
+            retryable: error.is_retryable(),
+            should_failover: error.kind.is_rate_limited()
+                || error.kind == UpstreamErrorKind::Authentication,
+            status_code: error.status,
+            retry_after: error.retry_after,
+            message: error.message,
         }
     }
 
@@ -211,11 +231,22 @@ impl ProviderStep {
                             status_code: err.status_code,
                             retryable: false,
                             should_failover,
+                            retry_after: err.retry_after,
                         });
                     }
 
-                    // 等待退避时间
-                    let delay = self.retrier.backoff_delay(attempts - 1);
+                    // 等待退避时间（优先遵循上游的 Retry-After）
+                    let provider = ctx.provider.unwrap_or(ProviderType::Kiro);
+                    let Some(delay) =
+                        self.retrier
+                            .retry_delay(provider, attempts - 1, err.retry_after)
+                    else {
+                        return Err(ProviderCallError {
+                            retryable: false,
+                            should_failover,
+                            ..err
+                        });
+                    };
                     tokio::time::sleep(delay).await;
                 }
             }
@@ -245,17 +276,11 @@ impl ProviderStep {
         match timeout_result {
             Ok(call_result) => call_result,
             Err(timeout_err) => {
-                let timeout_ms = match &timeout_err {
-                    TimeoutError::RequestTimeout { timeout_ms, .. } => *timeout_ms,
-                    TimeoutError::StreamIdleTimeout { timeout_ms, .. } => *timeout_ms,
-                    TimeoutError::Cancelled => 0,
-                };
-
                 tracing::warn!(
                     "[TIMEOUT] request_id={} error={} timeout_ms={}",
                     ctx.request_id,
                     timeout_err,
-                    timeout_ms
+                    timeout_err.timeout_ms()
                 );
 
                 Err(ProviderCallError {
@@ -263,6 +288,7 @@ impl ProviderStep {
                     status_code: Some(408),
                     retryable: true,
                     should_failover: false,
+                    retry_after: None,
                 })
             }
         }
@@ -397,11 +423,22 @@ impl ProviderStep {
                                 status_code: err.status_code,
                                 retryable: false,
                                 should_failover,
+                                retry_after: err.retry_after,
                             });
                         }
 
-                        // 等待退避时间
-                        let delay = self.retrier.backoff_delay(retry_attempts - 1);
+                        // 等待退避时间（优先遵循上游的 Retry-After）
+                        let Some(delay) = self.retrier.retry_delay(
+                            current_provider,
+                            retry_attempts - 1,
+                            err.retry_after,
+                        ) else {
+                            break Err(ProviderCallError {
+                                retryable: false,
+                                should_failover,
+                                ..err
+                            });
+                        };
                         tokio::time::sleep(delay).await;
                     }
                 }
@@ -498,7 +535,6 @@ impl PipelineStep for ProviderStep {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Duration;
 
     #[tokio::test]
     async fn test_provider_step_new() {
@@ -564,6 +600,29 @@ mod tests {
         assert!(!err.should_failover);
     }
 
+    #[test]
+    fn test_provider_call_error_from_upstream() {
+        let err = ProviderCallError::from_upstream(
+            UpstreamError::new(Some(429), "Too Many Requests")
+                .with_retry_after(Some(Duration::from_secs(2))),
+        );
+        assert!(err.retryable);
+        assert!(err.should_failover);
+        assert_eq!(err.retry_after, Some(Duration::from_secs(2)));
+
+        let err = ProviderCallError::from_upstream(UpstreamError::new(Some(503), "unavailable"));
+        assert!(err.retryable);
+        assert!(!err.should_failover);
+
+        let err = ProviderCallError::from_upstream(UpstreamError::new(Some(401), "expired"));
+        assert!(!err.retryable);
+        assert!(err.should_failover);
+
+        let err = ProviderCallError::from_upstream(UpstreamError::new(Some(400), "bad input"));
+        assert!(!err.retryable);
+        assert!(!err.should_failover);
+    }
+
     #[test]
     fn test_is_quota_exceeded_by_status() {
         let err = ProviderCallError::retryable("Error", Some(429));
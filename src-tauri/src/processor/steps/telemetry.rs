@@ -53,6 +53,9 @@ impl TelemetryStep {
                 log.mark_failed(ctx.elapsed_ms(), None, error_message.unwrap_or_default())
             }
             RequestStatus::Timeout => log.mark_timeout(ctx.elapsed_ms()),
+            RequestStatus::RateLimited => {
+                log.mark_rate_limited(ctx.elapsed_ms(), None, error_message.unwrap_or_default())
+            }
             RequestStatus::Cancelled => log.mark_cancelled(ctx.elapsed_ms()),
             RequestStatus::Retrying => {
                 log.duration_ms = ctx.elapsed_ms();
@@ -64,6 +67,11 @@ impl TelemetryStep {
             log.set_credential_id(cred_id.clone());
         }
 
+        // 设置调用方工具名称
+        if let Some(client_name) = &ctx.client_name {
+            log.set_client_name(client_name.clone());
+        }
+
         // 设置重试次数
         log.retry_count = ctx.retry_count;
 
@@ -87,7 +95,7 @@ impl TelemetryStep {
 
         // 只有当至少有一个 Token 值时才记录
         if input_tokens.is_some() || output_tokens.is_some() {
-            let record = TokenUsageRecord::new(
+            let mut record = TokenUsageRecord::new(
                 uuid::Uuid::new_v4().to_string(),
                 provider,
                 ctx.resolved_model.clone(),
@@ -96,6 +104,8 @@ impl TelemetryStep {
                 source,
             )
             .with_request_id(ctx.request_id.clone());
+            record.credential_id = ctx.credential_id.clone();
+            record.client_name = ctx.client_name.clone();
 
             // 使用 parking_lot::RwLock 的同步写锁
             let tokens = self.tokens.write();
@@ -186,6 +186,7 @@ fn arb_request_status() -> impl Strategy<Value = RequestStatus> {
         Just(RequestStatus::Success),
         Just(RequestStatus::Failed),
         Just(RequestStatus::Timeout),
+        Just(RequestStatus::RateLimited),
         Just(RequestStatus::Cancelled),
     ]
 }
@@ -239,6 +240,9 @@ fn arb_request_log() -> impl Strategy<Value = RequestLog> {
                     RequestStatus::Timeout => {
                         log.mark_timeout(duration_ms);
                     }
+                    RequestStatus::RateLimited => {
+                        log.mark_rate_limited(duration_ms, http_status, "Test error".to_string());
+                    }
                     RequestStatus::Cancelled => {
                         log.mark_cancelled(duration_ms);
                     }
@@ -5,6 +5,8 @@
 #![allow(dead_code)]
 
 use super::traits::{CredentialProvider, ProviderResult};
+use crate::injection::injected_headers;
+use crate::services::http_client_service::HttpClientService;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -372,10 +374,7 @@ impl Default for AntigravityProvider {
         Self {
             credentials: AntigravityCredentials::default(),
             project_id: None,
-            client: Client::builder()
-                .timeout(std::time::Duration::from_secs(120))
-                .build()
-                .unwrap_or_else(|_| Client::new()),
+            client: HttpClientService::provider_client("antigravity"),
             // 只使用生产环境和 daily 环境（参考 Antigravity-Manager）
             // 沙盒环境（autopush）需要特殊许可证，不适合普通用户
             base_urls: vec![
@@ -871,6 +870,7 @@ impl AntigravityProvider {
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
             .header("User-Agent", "antigravity/1.11.9 windows/amd64")
+            .headers(injected_headers())
             .json(body)
             .send()
             .await
@@ -2185,6 +2185,7 @@ impl StreamingProvider for AntigravityProvider {
                 .header("Content-Type", "application/json")
                 .header("Accept", "text/event-stream")
                 .header("User-Agent", "antigravity/1.11.9 windows/amd64")
+                .headers(injected_headers())
                 .json(&payload)
                 .send()
                 .await;
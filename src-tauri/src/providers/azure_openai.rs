@@ -0,0 +1,109 @@
+//! Azure OpenAI Provider
+//!
+//! 请求发送到 `{endpoint}/openai/deployments/{deployment}/chat/completions?api-version=...`，
+//! 使用 `api-key` 头认证。请求和响应均为 OpenAI 格式，模型到部署的映射由调用方解析。
+
+#![allow(dead_code)]
+
+use crate::injection::injected_headers;
+use crate::models::openai::ChatCompletionRequest;
+use crate::services::http_client_service::HttpClientService;
+use reqwest::Client;
+use std::error::Error;
+
+/// 未指定 api-version 时使用的版本
+pub const AZURE_OPENAI_DEFAULT_API_VERSION: &str = "2024-10-21";
+
+pub struct AzureOpenAIProvider {
+    pub api_key: String,
+    /// 资源名或完整 endpoint
+    pub resource: String,
+    pub api_version: String,
+    pub client: Client,
+}
+
+impl AzureOpenAIProvider {
+    pub fn new(api_key: String, resource: String, api_version: Option<String>) -> Self {
+        Self {
+            api_key,
+            resource,
+            api_version: api_version
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| AZURE_OPENAI_DEFAULT_API_VERSION.to_string()),
+            client: HttpClientService::provider_client("azure_openai"),
+        }
+    }
+
+    /// 资源 endpoint
+    ///
+    /// - `my-resource` -> `https://my-resource.openai.azure.com`
+    /// - `https://my-resource.openai.azure.com/` -> `https://my-resource.openai.azure.com`
+    pub fn endpoint(&self) -> String {
+        let resource = self.resource.trim().trim_end_matches('/');
+        if resource.starts_with("http://") || resource.starts_with("https://") {
+            resource.to_string()
+        } else {
+            format!("https://{}.openai.azure.com", resource)
+        }
+    }
+
+    pub fn chat_completions_url(&self, deployment: &str) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            self.endpoint(),
+            urlencoding::encode(deployment),
+            urlencoding::encode(&self.api_version)
+        )
+    }
+
+    /// 调用指定部署的 Chat Completions API，返回上游原始响应
+    pub async fn call_api(
+        &self,
+        deployment: &str,
+        request: &ChatCompletionRequest,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let url = self.chat_completions_url(deployment);
+        tracing::debug!("[AZURE_OPENAI] POST {} model={}", url, request.model);
+
+        let accept = if request.stream {
+            "text/event-stream"
+        } else {
+            "application/json"
+        };
+        let resp = self
+            .client
+            .post(&url)
+            .header("api-key", &self.api_key)
+            .header("Content-Type", "application/json")
+            .header("Accept", accept)
+            .headers(injected_headers())
+            .json(request)
+            .send()
+            .await?;
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_completions_url() {
+        let provider = AzureOpenAIProvider::new("key".to_string(), "my-res".to_string(), None);
+        assert_eq!(
+            provider.chat_completions_url("gpt 4o"),
+            "https://my-res.openai.azure.com/openai/deployments/gpt%204o/chat/completions?api-version=2024-10-21"
+        );
+
+        let provider = AzureOpenAIProvider::new(
+            "key".to_string(),
+            "https://proxy.example.com/".to_string(),
+            Some("2025-01-01-preview".to_string()),
+        );
+        assert_eq!(
+            provider.chat_completions_url("prod"),
+            "https://proxy.example.com/openai/deployments/prod/chat/completions?api-version=2025-01-01-preview"
+        );
+    }
+}
@@ -0,0 +1,1285 @@
+//! AWS Bedrock Provider
+//!
+//! 通过 Bedrock Runtime 的 Converse / ConverseStream API 调用模型，请求使用 SigV4 签名。
+//! 内部统一使用 OpenAI Chat Completions 格式，由本模块负责与 Converse 格式互转。
+//!
+//! 访问密钥来源优先级：凭证中的显式密钥 > 指定的 profile > 环境变量 > default profile。
+//! 配置了 `role_arn`（或 profile 在 `~/.aws/config` 中配置了 `role_arn` + `source_profile`）时，
+//! 再通过 STS AssumeRole 换取临时凭证，临时凭证在过期前 5 分钟内复用。
+
+#![allow(dead_code)]
+
+use super::error::{create_config_error, ProviderError};
+use crate::injection::injected_headers;
+use crate::models::openai::{
+    ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, ChatMessage, Choice,
+    ContentPart, FunctionCall, MessageContent, ResponseMessage, StreamChoice, StreamDelta,
+    StreamFunctionCall, StreamToolCall, Tool, ToolCall, Usage,
+};
+use crate::models::provider_pool_model::CredentialData;
+use crate::services::http_client_service::HttpClientService;
+use crate::streaming::event_stream::{EventStreamDecoder, EventStreamMessage};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use reqwest::{Client, Method};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 未配置区域时使用的默认区域
+pub const BEDROCK_DEFAULT_REGION: &str = "us-east-1";
+
+/// 常用模型（无法从上游获取模型列表时使用）
+pub const BEDROCK_MODELS: &[&str] = &[
+    "anthropic.claude-sonnet-4-5-20250929-v1:0",
+    "anthropic.claude-sonnet-4-20250514-v1:0",
+    "anthropic.claude-3-7-sonnet-20250219-v1:0",
+    "anthropic.claude-3-5-haiku-20241022-v1:0",
+    "amazon.nova-pro-v1:0",
+    "amazon.nova-lite-v1:0",
+    "meta.llama3-3-70b-instruct-v1:0",
+    "mistral.mistral-large-2407-v1:0",
+];
+
+const STS_API_VERSION: &str = "2011-06-15";
+const ASSUME_ROLE_SESSION_NAME: &str = "proxycast-bedrock";
+const ASSUME_ROLE_DURATION_SECS: i64 = 3600;
+/// 临时凭证提前刷新的时间（秒）
+const ASSUME_ROLE_REFRESH_MARGIN_SECS: i64 = 300;
+
+/// AssumeRole 得到的临时凭证缓存（key: role_arn + 源 access key）
+static ASSUMED_ROLE_CACHE: Lazy<Mutex<HashMap<String, AwsCredentials>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Bedrock 连接配置
+#[derive(Debug, Clone, Default)]
+pub struct BedrockConfig {
+    /// AWS 区域
+    pub region: String,
+    /// `~/.aws/credentials` / `~/.aws/config` 中的 profile 名称
+    pub profile: Option<String>,
+    /// 需要扮演的 IAM 角色
+    pub role_arn: Option<String>,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+}
+
+impl BedrockConfig {
+    /// 从凭证池的 AwsBedrock 凭证构造配置
+    pub fn from_credential(credential: &CredentialData) -> Option<Self> {
+        match credential {
+            CredentialData::AwsBedrock {
+                region,
+                profile,
+                role_arn,
+                access_key_id,
+                secret_access_key,
+            } => Some(Self {
+                region: region.clone(),
+                profile: profile.clone(),
+                role_arn: role_arn.clone(),
+                access_key_id: access_key_id.clone(),
+                secret_access_key: secret_access_key.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// AWS 访问凭证
+#[derive(Debug, Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+    /// 临时凭证的过期时间
+    pub expiration: Option<DateTime<Utc>>,
+}
+
+impl AwsCredentials {
+    fn new(access_key_id: &str, secret_access_key: &str, session_token: Option<&str>) -> Self {
+        Self {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            session_token: session_token.map(str::to_string),
+            expiration: None,
+        }
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.expiration
+            .map(|exp| (exp - Utc::now()).num_seconds() > ASSUME_ROLE_REFRESH_MARGIN_SECS)
+            .unwrap_or(true)
+    }
+}
+
+pub struct BedrockProvider {
+    pub config: BedrockConfig,
+    pub client: Client,
+}
+
+impl BedrockProvider {
+    pub fn new(config: BedrockConfig) -> Self {
+        Self {
+            config,
+            client: HttpClientService::provider_client("aws_bedrock"),
+        }
+    }
+
+    pub fn region(&self) -> &str {
+        if self.config.region.is_empty() {
+            BEDROCK_DEFAULT_REGION
+        } else {
+            &self.config.region
+        }
+    }
+
+    /// 解析本次请求使用的 AWS 凭证（必要时执行 AssumeRole）
+    pub async fn resolve_credentials(
+        &self,
+    ) -> Result<AwsCredentials, Box<dyn Error + Send + Sync>> {
+        let (base, profile_role) = self.base_credentials()?;
+        match self.config.role_arn.clone().or(profile_role) {
+            Some(role_arn) => self.assume_role(&base, &role_arn).await,
+            None => Ok(base),
+        }
+    }
+
+    /// 返回源凭证，以及 profile 中配置的待扮演角色
+    fn base_credentials(
+        &self,
+    ) -> Result<(AwsCredentials, Option<String>), Box<dyn Error + Send + Sync>> {
+        if let (Some(access_key_id), Some(secret_access_key)) =
+            (&self.config.access_key_id, &self.config.secret_access_key)
+        {
+            return Ok((
+                AwsCredentials::new(access_key_id, secret_access_key, None),
+                None,
+            ));
+        }
+
+        let credentials_file = read_aws_file("AWS_SHARED_CREDENTIALS_FILE", "credentials");
+        let config_file = read_aws_file("AWS_CONFIG_FILE", "config");
+
+        if let Some(profile) = &self.config.profile {
+            return load_profile(&credentials_file, &config_file, profile).ok_or_else(|| {
+                create_config_error(&format!("AWS profile '{}' 不存在或缺少访问密钥", profile))
+            });
+        }
+
+        if let Some(credentials) = env_credentials() {
+            return Ok((credentials, None));
+        }
+
+        let profile = std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string());
+        load_profile(&credentials_file, &config_file, &profile).ok_or_else(|| {
+            create_config_error(
+                "未找到 AWS 凭证：请配置访问密钥、profile 或 AWS_ACCESS_KEY_ID 环境变量",
+            )
+        })
+    }
+
+    /// 通过 STS AssumeRole 换取临时凭证
+    async fn assume_role(
+        &self,
+        base: &AwsCredentials,
+        role_arn: &str,
+    ) -> Result<AwsCredentials, Box<dyn Error + Send + Sync>> {
+        let cache_key = format!("{}|{}", role_arn, base.access_key_id);
+        if let Some(cached) = ASSUMED_ROLE_CACHE
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(&cache_key).cloned())
+        {
+            if cached.is_fresh() {
+                return Ok(cached);
+            }
+        }
+
+        tracing::info!("[BEDROCK] AssumeRole: {}", role_arn);
+        let host = format!("sts.{}.amazonaws.com", self.region());
+        let query = vec![
+            ("Action".to_string(), "AssumeRole".to_string()),
+            (
+                "DurationSeconds".to_string(),
+                ASSUME_ROLE_DURATION_SECS.to_string(),
+            ),
+            ("RoleArn".to_string(), role_arn.to_string()),
+            (
+                "RoleSessionName".to_string(),
+                ASSUME_ROLE_SESSION_NAME.to_string(),
+            ),
+            ("Version".to_string(), STS_API_VERSION.to_string()),
+        ];
+        let resp = self
+            .send_signed(
+                Method::GET,
+                "sts",
+                &host,
+                "/",
+                &query,
+                Vec::new(),
+                base,
+                "application/xml",
+            )
+            .await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(Box::new(ProviderError::from_http_status(
+                status.as_u16(),
+                &body,
+            )));
+        }
+
+        let (Some(access_key_id), Some(secret_access_key)) = (
+            xml_tag(&body, "AccessKeyId"),
+            xml_tag(&body, "SecretAccessKey"),
+        ) else {
+            return Err(Box::new(ProviderError::ParseError(
+                "AssumeRole 响应缺少临时凭证".to_string(),
+            )));
+        };
+        let mut credentials = AwsCredentials::new(
+            access_key_id,
+            secret_access_key,
+            xml_tag(&body, "SessionToken"),
+        );
+        credentials.expiration = xml_tag(&body, "Expiration")
+            .and_then(|exp| DateTime::parse_from_rfc3339(exp).ok())
+            .map(|exp| exp.with_timezone(&Utc));
+
+        if let Ok(mut cache) = ASSUMED_ROLE_CACHE.lock() {
+            cache.insert(cache_key, credentials.clone());
+        }
+        Ok(credentials)
+    }
+
+    /// 调用 Converse API（非流式）
+    pub async fn converse(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        self.invoke(request, "converse", "application/json").await
+    }
+
+    /// 调用 ConverseStream API，响应体为 AWS event stream 格式
+    pub async fn converse_stream(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        self.invoke(
+            request,
+            "converse-stream",
+            "application/vnd.amazon.eventstream",
+        )
+        .await
+    }
+
+    async fn invoke(
+        &self,
+        request: &ChatCompletionRequest,
+        action: &str,
+        accept: &str,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let credentials = self.resolve_credentials().await?;
+        let model_id = resolve_model_id(&request.model, self.region());
+        let path = format!("/model/{}/{}", urlencoding::encode(&model_id), action);
+        let host = format!("bedrock-runtime.{}.amazonaws.com", self.region());
+        let body = serde_json::to_vec(&convert_openai_to_converse(request))?;
+
+        tracing::debug!("[BEDROCK] {} model={}", action, model_id);
+        self.send_signed(
+            Method::POST,
+            "bedrock",
+            &host,
+            &path,
+            &[],
+            body,
+            &credentials,
+            accept,
+        )
+        .await
+    }
+
+    /// 获取可用的文本模型列表（ListFoundationModels）
+    pub async fn list_models(&self) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let credentials = self.resolve_credentials().await?;
+        let host = format!("bedrock.{}.amazonaws.com", self.region());
+        let query = vec![("byOutputModality".to_string(), "TEXT".to_string())];
+        let resp = self
+            .send_signed(
+                Method::GET,
+                "bedrock",
+                &host,
+                "/foundation-models",
+                &query,
+                Vec::new(),
+                &credentials,
+                "application/json",
+            )
+            .await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(Box::new(ProviderError::from_http_status(
+                status.as_u16(),
+                &body,
+            )));
+        }
+
+        let value: Value = serde_json::from_str(&body)?;
+        Ok(value["modelSummaries"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m["modelId"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// 发送 SigV4 签名请求
+    ///
+    /// `path` 需已按 URI 规则编码一次，签名时会再编码一次（非 S3 服务的规范 URI 规则）
+    #[allow(clippy::too_many_arguments)]
+    async fn send_signed(
+        &self,
+        method: Method,
+        service: &str,
+        host: &str,
+        path: &str,
+        query: &[(String, String)],
+        body: Vec<u8>,
+        credentials: &AwsCredentials,
+        accept: &str,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let signed_headers = sign_request(
+            &SigningRequest {
+                method: method.as_str(),
+                host,
+                path,
+                query,
+                payload: &body,
+                region: self.region(),
+                service,
+                now: Utc::now(),
+            },
+            credentials,
+        );
+
+        let mut url = format!("https://{}{}", host, path);
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&canonical_query(query));
+        }
+
+        let mut builder = self.client.request(method, &url).header("Accept", accept);
+        for (name, value) in signed_headers {
+            builder = builder.header(name, value);
+        }
+        if !body.is_empty() {
+            builder = builder
+                .header("Content-Type", "application/json")
+                .body(body);
+        }
+        Ok(builder.headers(injected_headers()).send().await?)
+    }
+}
+
+// ============================================================================
+// 凭证解析
+// ============================================================================
+
+fn read_aws_file(env_var: &str, file_name: &str) -> String {
+    let path = std::env::var(env_var)
+        .map(PathBuf::from)
+        .ok()
+        .or_else(|| dirs::home_dir().map(|home| home.join(".aws").join(file_name)));
+    path.and_then(|p| std::fs::read_to_string(p).ok())
+        .unwrap_or_default()
+}
+
+fn env_credentials() -> Option<AwsCredentials> {
+    let access_key_id = std::env::var("AWS_ACCESS_KEY_ID").ok()?;
+    let secret_access_key = std::env::var("AWS_SECRET_ACCESS_KEY").ok()?;
+    let session_token = std::env::var("AWS_SESSION_TOKEN").ok();
+    Some(AwsCredentials::new(
+        &access_key_id,
+        &secret_access_key,
+        session_token.as_deref(),
+    ))
+}
+
+/// 从 credentials / config 文件内容中加载 profile
+///
+/// profile 配置了 `role_arn` + `source_profile` 时返回源 profile 的静态密钥和待扮演的角色
+fn load_profile(
+    credentials_file: &str,
+    config_file: &str,
+    profile: &str,
+) -> Option<(AwsCredentials, Option<String>)> {
+    let config_section = if profile == "default" {
+        "default".to_string()
+    } else {
+        format!("profile {}", profile)
+    };
+    let config = parse_ini_section(config_file, &config_section).unwrap_or_default();
+
+    if let (Some(role_arn), Some(source_profile)) =
+        (config.get("role_arn"), config.get("source_profile"))
+    {
+        if source_profile != profile {
+            let (source, _) = load_profile(credentials_file, config_file, source_profile)?;
+            return Some((source, Some(role_arn.clone())));
+        }
+    }
+
+    let values = parse_ini_section(credentials_file, profile).unwrap_or_default();
+    let key = |name: &str| values.get(name).or_else(|| config.get(name));
+    Some((
+        AwsCredentials::new(
+            key("aws_access_key_id")?,
+            key("aws_secret_access_key")?,
+            key("aws_session_token").map(String::as_str),
+        ),
+        config.get("role_arn").cloned(),
+    ))
+}
+
+/// 解析 INI 文件中的指定 section
+fn parse_ini_section(content: &str, section: &str) -> Option<HashMap<String, String>> {
+    let mut values = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if values.is_some() {
+                break;
+            }
+            if name.trim() == section {
+                values = Some(HashMap::new());
+            }
+            continue;
+        }
+        if let (Some(map), Some((key, value))) = (values.as_mut(), line.split_once('=')) {
+            map.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    values
+}
+
+/// 提取 XML 中第一个指定标签的文本
+fn xml_tag<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(xml[start..end].trim())
+}
+
+// ============================================================================
+// SigV4 签名
+// ============================================================================
+
+struct SigningRequest<'a> {
+    method: &'a str,
+    host: &'a str,
+    path: &'a str,
+    query: &'a [(String, String)],
+    payload: &'a [u8],
+    region: &'a str,
+    service: &'a str,
+    now: DateTime<Utc>,
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn canonical_uri(path: &str) -> String {
+    path.split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(query: &[(String, String)]) -> String {
+    let mut pairs: Vec<(String, String)> = query
+        .iter()
+        .map(|(k, v)| {
+            (
+                urlencoding::encode(k).into_owned(),
+                urlencoding::encode(v).into_owned(),
+            )
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// 计算 SigV4 签名，返回需要附加到请求上的头部
+fn sign_request(
+    request: &SigningRequest<'_>,
+    credentials: &AwsCredentials,
+) -> Vec<(&'static str, String)> {
+    let amz_date = request.now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = request.now.format("%Y%m%d").to_string();
+
+    // 参与签名的头部（按名称排序）
+    let mut headers = vec![
+        ("host", request.host.to_string()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    let signed_header_names = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method,
+        canonical_uri(request.path),
+        canonical_query(request.query),
+        canonical_headers,
+        signed_header_names,
+        hex::encode(Sha256::digest(request.payload))
+    );
+    let scope = format!(
+        "{}/{}/{}/aws4_request",
+        date, request.region, request.service
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let signing_key = [
+        date.as_str(),
+        request.region,
+        request.service,
+        "aws4_request",
+    ]
+    .iter()
+    .fold(
+        format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+        |key, part| hmac_sha256(&key, part.as_bytes()),
+    );
+    let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+    let mut result: Vec<(&'static str, String)> = headers
+        .into_iter()
+        .filter(|(name, _)| *name != "host")
+        .collect();
+    result.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_header_names, signature
+        ),
+    ));
+    result
+}
+
+// ============================================================================
+// 请求 / 响应转换
+// ============================================================================
+
+/// 将客户端模型名映射为 Bedrock 模型 ID
+///
+/// `claude-*` 形式的 Anthropic 模型名转换为所在地区的跨区域推理配置文件 ID，
+/// 其余（Bedrock 模型 ID、推理配置文件 ID、ARN）原样使用
+pub fn resolve_model_id(model: &str, region: &str) -> String {
+    if !model.starts_with("claude-") {
+        return model.to_string();
+    }
+    let geo = match region.split('-').next() {
+        Some("eu") => "eu",
+        Some("ap") => "apac",
+        _ => "us",
+    };
+    format!("{}.anthropic.{}-v1:0", geo, model)
+}
+
+/// 将 OpenAI ChatCompletionRequest 转换为 Converse 请求体
+pub fn convert_openai_to_converse(request: &ChatCompletionRequest) -> Value {
+    let mut system = Vec::new();
+    let mut messages: Vec<Value> = Vec::new();
+
+    for message in &request.messages {
+        let (role, blocks) = match message.role.as_str() {
+            "system" | "developer" => {
+                let text = message.get_content_text();
+                if !text.is_empty() {
+                    system.push(json!({ "text": text }));
+                }
+                continue;
+            }
+            "assistant" => ("assistant", assistant_blocks(message)),
+            "tool" => (
+                "user",
+                vec![json!({
+                    "toolResult": {
+                        "toolUseId": message.tool_call_id.clone().unwrap_or_default(),
+                        "content": [{ "text": message.get_content_text() }],
+                    }
+                })],
+            ),
+            _ => ("user", user_blocks(message)),
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+
+        // Converse 要求 user / assistant 交替出现，连续的同角色消息合并
+        match messages.last_mut() {
+            Some(last) if last["role"] == role => {
+                if let Some(content) = last["content"].as_array_mut() {
+                    content.extend(blocks);
+                }
+            }
+            _ => messages.push(json!({ "role": role, "content": blocks })),
+        }
+    }
+
+    let mut body = json!({ "messages": messages });
+    if !system.is_empty() {
+        body["system"] = Value::Array(system);
+    }
+
+    let mut inference = serde_json::Map::new();
+    if let Some(max_tokens) = request.max_tokens {
+        inference.insert("maxTokens".to_string(), json!(max_tokens));
+    }
+    if let Some(temperature) = request.temperature {
+        inference.insert("temperature".to_string(), json!(temperature));
+    }
+    if let Some(top_p) = request.top_p {
+        inference.insert("topP".to_string(), json!(top_p));
+    }
+    let stop_sequences = match request.extra.get("stop") {
+        Some(Value::String(stop)) => vec![json!(stop)],
+        Some(Value::Array(stops)) => stops.clone(),
+        _ => Vec::new(),
+    };
+    if !stop_sequences.is_empty() {
+        inference.insert("stopSequences".to_string(), Value::Array(stop_sequences));
+    }
+    if !inference.is_empty() {
+        body["inferenceConfig"] = Value::Object(inference);
+    }
+
+    if let Some(tool_config) = convert_tool_config(request) {
+        body["toolConfig"] = tool_config;
+    }
+    body
+}
+
+fn user_blocks(message: &ChatMessage) -> Vec<Value> {
+    match &message.content {
+        Some(MessageContent::Text(text)) if !text.is_empty() => vec![json!({ "text": text })],
+        Some(MessageContent::Parts(parts)) => parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text } => Some(json!({ "text": text })),
+                ContentPart::ImageUrl { image_url } => image_block(&image_url.url),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Converse 只接受内联图片，远程 URL 图片会被忽略
+fn image_block(url: &str) -> Option<Value> {
+    let Some((header, data)) = url.strip_prefix("data:").and_then(|u| u.split_once(',')) else {
+        tracing::warn!("[BEDROCK] 忽略非 data URL 图片");
+        return None;
+    };
+    let format = header
+        .split(';')
+        .next()
+        .and_then(|media_type| media_type.split('/').nth(1))
+        .map(|f| if f == "jpg" { "jpeg" } else { f })
+        .unwrap_or("jpeg");
+    Some(json!({ "image": { "format": format, "source": { "bytes": data } } }))
+}
+
+fn assistant_blocks(message: &ChatMessage) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    let text = message.get_content_text();
+    if !text.is_empty() {
+        blocks.push(json!({ "text": text }));
+    }
+    for call in message.tool_calls.iter().flatten() {
+        let input: Value =
+            serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| json!({}));
+        blocks.push(json!({
+            "toolUse": {
+                "toolUseId": call.id,
+                "name": call.function.name,
+                "input": input,
+            }
+        }));
+    }
+    blocks
+}
+
+fn convert_tool_config(request: &ChatCompletionRequest) -> Option<Value> {
+    let tools: Vec<Value> = request
+        .tools
+        .as_ref()?
+        .iter()
+        .filter_map(|tool| match tool {
+            Tool::Function { function } => {
+                let mut spec = json!({
+                    "name": function.name,
+                    "inputSchema": {
+                        "json": function
+                            .parameters
+                            .clone()
+                            .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                    },
+                });
+                if let Some(description) = &function.description {
+                    spec["description"] = json!(description);
+                }
+                Some(json!({ "toolSpec": spec }))
+            }
+            // Bedrock 没有内置联网搜索工具
+            _ => None,
+        })
+        .collect();
+    if tools.is_empty() {
+        return None;
+    }
+
+    let mut config = json!({ "tools": tools });
+    match &request.tool_choice {
+        Some(Value::String(choice)) if choice == "required" => {
+            config["toolChoice"] = json!({ "any": {} });
+        }
+        Some(Value::Object(choice)) => {
+            if let Some(name) = choice
+                .get("function")
+                .and_then(|f| f.get("name"))
+                .and_then(Value::as_str)
+            {
+                config["toolChoice"] = json!({ "tool": { "name": name } });
+            }
+        }
+        _ => {}
+    }
+    Some(config)
+}
+
+fn finish_reason(stop_reason: Option<&str>) -> String {
+    match stop_reason {
+        Some("tool_use") => "tool_calls",
+        Some("max_tokens") => "length",
+        Some("guardrail_intervened") | Some("content_filtered") => "content_filter",
+        _ => "stop",
+    }
+    .to_string()
+}
+
+fn token_count(usage: &Value, key: &str) -> u32 {
+    usage[key].as_u64().unwrap_or(0) as u32
+}
+
+fn completion_id() -> String {
+    format!("chatcmpl-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// 将 Converse 响应转换为 OpenAI ChatCompletionResponse
+pub fn convert_converse_to_openai(response: &Value, model: &str) -> ChatCompletionResponse {
+    let mut text = String::new();
+    let mut reasoning = String::new();
+    let mut tool_calls = Vec::new();
+    for block in response["output"]["message"]["content"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        if let Some(t) = block["text"].as_str() {
+            text.push_str(t);
+        } else if let Some(r) = block["reasoningContent"]["reasoningText"]["text"].as_str() {
+            reasoning.push_str(r);
+        } else if let Some(tool_use) = block.get("toolUse") {
+            tool_calls.push(ToolCall {
+                id: tool_use["toolUseId"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: tool_use["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: tool_use["input"].to_string(),
+                },
+            });
+        }
+    }
+
+    let usage = &response["usage"];
+    ChatCompletionResponse {
+        id: completion_id(),
+        object: "chat.completion".to_string(),
+        created: Utc::now().timestamp() as u64,
+        model: model.to_string(),
+        choices: vec![Choice {
+            index: 0,
+            message: ResponseMessage {
+                role: "assistant".to_string(),
+                content: if text.is_empty() && !tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(text)
+                },
+                tool_calls: if tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(tool_calls)
+                },
+                reasoning_content: (!reasoning.is_empty()).then_some(reasoning),
+            },
+            finish_reason: finish_reason(response["stopReason"].as_str()),
+        }],
+        usage: Usage {
+            prompt_tokens: token_count(usage, "inputTokens"),
+            completion_tokens: token_count(usage, "outputTokens"),
+            total_tokens: token_count(usage, "totalTokens"),
+        },
+    }
+}
+
+// ============================================================================
+// 流式响应
+// ============================================================================
+
+/// ConverseStream 事件到 OpenAI SSE 的转换器
+pub struct ConverseStreamConverter {
+    id: String,
+    created: u64,
+    model: String,
+    /// 是否输出 usage 分片（`stream_options.include_usage`）
+    include_usage: bool,
+    /// Bedrock contentBlockIndex -> OpenAI tool_calls index
+    tool_indices: HashMap<u64, u32>,
+}
+
+impl ConverseStreamConverter {
+    pub fn new(model: &str, include_usage: bool) -> Self {
+        Self {
+            id: completion_id(),
+            created: Utc::now().timestamp() as u64,
+            model: model.to_string(),
+            include_usage,
+            tool_indices: HashMap::new(),
+        }
+    }
+
+    fn sse(&self, delta: StreamDelta, finish_reason: Option<String>) -> String {
+        let chunk = ChatCompletionChunk {
+            id: self.id.clone(),
+            object: "chat.completion.chunk".to_string(),
+            created: self.created,
+            model: self.model.clone(),
+            choices: vec![StreamChoice {
+                index: 0,
+                delta,
+                finish_reason,
+            }],
+        };
+        format!(
+            "data: {}\n\n",
+            serde_json::to_string(&chunk).unwrap_or_default()
+        )
+    }
+
+    fn tool_call_sse(&self, call: StreamToolCall) -> String {
+        self.sse(
+            StreamDelta {
+                role: None,
+                content: None,
+                tool_calls: Some(vec![call]),
+                reasoning_content: None,
+            },
+            None,
+        )
+    }
+
+    /// 转换一条 event stream 消息，返回 SSE 数据块
+    pub fn convert(&mut self, message: &EventStreamMessage) -> Vec<String> {
+        let payload: Value = serde_json::from_slice(&message.payload).unwrap_or(Value::Null);
+
+        if message.header(":message-type") == Some("exception") {
+            let error_type = message.header(":exception-type").unwrap_or("exception");
+            let error_message = payload["message"].as_str().unwrap_or(error_type);
+            tracing::error!("[BEDROCK] 流式响应异常: {} {}", error_type, error_message);
+            return vec![format!(
+                "data: {}\n\n",
+                json!({ "error": { "message": error_message, "type": error_type } })
+            )];
+        }
+
+        let block_index = payload["contentBlockIndex"].as_u64().unwrap_or(0);
+        match message.header(":event-type").unwrap_or_default() {
+            "messageStart" => vec![self.sse(
+                StreamDelta {
+                    role: Some("assistant".to_string()),
+                    content: None,
+                    tool_calls: None,
+                    reasoning_content: None,
+                },
+                None,
+            )],
+            "contentBlockStart" => {
+                let Some(tool_use) = payload["start"].get("toolUse") else {
+                    return Vec::new();
+                };
+                let index = self.tool_indices.len() as u32;
+                self.tool_indices.insert(block_index, index);
+                vec![self.tool_call_sse(StreamToolCall {
+                    index,
+                    id: tool_use["toolUseId"].as_str().map(str::to_string),
+                    call_type: Some("function".to_string()),
+                    function: Some(StreamFunctionCall {
+                        name: tool_use["name"].as_str().map(str::to_string),
+                        arguments: Some(String::new()),
+                    }),
+                })]
+            }
+            "contentBlockDelta" => {
+                let delta = &payload["delta"];
+                if let Some(text) = delta["text"].as_str() {
+                    vec![self.sse(
+                        StreamDelta {
+                            role: None,
+                            content: Some(text.to_string()),
+                            tool_calls: None,
+                            reasoning_content: None,
+                        },
+                        None,
+                    )]
+                } else if let Some(reasoning) = delta["reasoningContent"]["text"].as_str() {
+                    vec![self.sse(
+                        StreamDelta {
+                            role: None,
+                            content: None,
+                            tool_calls: None,
+                            reasoning_content: Some(reasoning.to_string()),
+                        },
+                        None,
+                    )]
+                } else if let (Some(input), Some(&index)) = (
+                    delta["toolUse"]["input"].as_str(),
+                    self.tool_indices.get(&block_index),
+                ) {
+                    vec![self.tool_call_sse(StreamToolCall {
+                        index,
+                        id: None,
+                        call_type: None,
+                        function: Some(StreamFunctionCall {
+                            name: None,
+                            arguments: Some(input.to_string()),
+                        }),
+                    })]
+                } else {
+                    Vec::new()
+                }
+            }
+            "messageStop" => vec![self.sse(
+                StreamDelta {
+                    role: None,
+                    content: None,
+                    tool_calls: None,
+                    reasoning_content: None,
+                },
+                Some(finish_reason(payload["stopReason"].as_str())),
+            )],
+            "metadata" if self.include_usage => {
+                let usage = &payload["usage"];
+                let chunk = json!({
+                    "id": self.id,
+                    "object": "chat.completion.chunk",
+                    "created": self.created,
+                    "model": self.model,
+                    "choices": [],
+                    "usage": {
+                        "prompt_tokens": token_count(usage, "inputTokens"),
+                        "completion_tokens": token_count(usage, "outputTokens"),
+                        "total_tokens": token_count(usage, "totalTokens"),
+                    },
+                });
+                vec![format!("data: {}\n\n", chunk)]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// 将 ConverseStream 响应转换为 OpenAI SSE 字节流（以 `data: [DONE]` 结尾）
+pub fn converse_stream_to_openai_sse(
+    resp: reqwest::Response,
+    model: String,
+    include_usage: bool,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send {
+    async_stream::stream! {
+        let mut decoder = EventStreamDecoder::new();
+        let mut converter = ConverseStreamConverter::new(&model, include_usage);
+        let mut body = resp.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    tracing::error!("[BEDROCK] 读取流式响应失败: {}", e);
+                    yield Err(std::io::Error::other(e.to_string()));
+                    return;
+                }
+            };
+            match decoder.push(&chunk) {
+                Ok(messages) => {
+                    for message in &messages {
+                        for sse in converter.convert(message) {
+                            yield Ok(Bytes::from(sse));
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("[BEDROCK] 解析 event stream 失败: {}", e);
+                    yield Err(std::io::Error::other(e));
+                    return;
+                }
+            }
+        }
+        yield Ok(Bytes::from_static(b"data: [DONE]\n\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::event_stream::encode_message;
+
+    #[test]
+    fn test_sigv4_matches_aws_test_suite() {
+        // AWS SigV4 测试套件 get-vanilla 用例
+        let credentials = AwsCredentials::new(
+            "AKIDEXAMPLE",
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            None,
+        );
+        let headers = sign_request(
+            &SigningRequest {
+                method: "GET",
+                host: "example.amazonaws.com",
+                path: "/",
+                query: &[],
+                payload: b"",
+                region: "us-east-1",
+                service: "service",
+                now: DateTime::parse_from_rfc3339("2015-08-30T12:36:00Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+            },
+            &credentials,
+        );
+
+        assert_eq!(headers[0], ("x-amz-date", "20150830T123600Z".to_string()));
+        assert_eq!(
+            headers[1].1,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_model_id_path_is_double_encoded_for_signing() {
+        let path = format!(
+            "/model/{}/converse",
+            urlencoding::encode("anthropic.claude-3-5-haiku-20241022-v1:0")
+        );
+        assert_eq!(
+            canonical_uri(&path),
+            "/model/anthropic.claude-3-5-haiku-20241022-v1%253A0/converse"
+        );
+        assert_eq!(
+            resolve_model_id("claude-sonnet-4-5-20250929", "eu-west-1"),
+            "eu.anthropic.claude-sonnet-4-5-20250929-v1:0"
+        );
+        assert_eq!(
+            resolve_model_id("amazon.nova-pro-v1:0", "us-east-1"),
+            "amazon.nova-pro-v1:0"
+        );
+    }
+
+    #[test]
+    fn test_load_profile_with_source_profile_role() {
+        let credentials = "[default]\naws_access_key_id = AKIA1\naws_secret_access_key = secret1\n\n[dev]\naws_access_key_id=AKIA2\naws_secret_access_key=secret2\n";
+        let config = "[profile admin]\nrole_arn = arn:aws:iam::123456789012:role/Admin\nsource_profile = dev\n";
+
+        let (creds, role) = load_profile(credentials, config, "default").unwrap();
+        assert_eq!(creds.access_key_id, "AKIA1");
+        assert!(role.is_none());
+
+        let (creds, role) = load_profile(credentials, config, "admin").unwrap();
+        assert_eq!(creds.access_key_id, "AKIA2");
+        assert_eq!(
+            role.as_deref(),
+            Some("arn:aws:iam::123456789012:role/Admin")
+        );
+
+        assert!(load_profile(credentials, config, "missing").is_none());
+    }
+
+    #[test]
+    fn test_convert_openai_to_converse() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5-20250929",
+            "max_tokens": 256,
+            "stop": "END",
+            "messages": [
+                {"role": "system", "content": "be brief"},
+                {"role": "user", "content": "weather?"},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "sunny"},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "and this?"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}
+                ]}
+            ],
+            "tools": [{"type": "function", "function": {"name": "get_weather", "parameters": {"type": "object"}}}],
+            "tool_choice": "required"
+        }))
+        .unwrap();
+
+        let body = convert_openai_to_converse(&request);
+        assert_eq!(body["system"], json!([{"text": "be brief"}]));
+        assert_eq!(
+            body["inferenceConfig"],
+            json!({"maxTokens": 256, "stopSequences": ["END"]})
+        );
+        assert_eq!(body["toolConfig"]["toolChoice"], json!({"any": {}}));
+        assert_eq!(
+            body["toolConfig"]["tools"][0]["toolSpec"]["inputSchema"]["json"],
+            json!({"type": "object"})
+        );
+
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[1]["content"][0]["toolUse"],
+            json!({"toolUseId": "call_1", "name": "get_weather", "input": {"city": "Paris"}})
+        );
+        // 工具结果与后续用户消息合并为同一条 user 消息
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(
+            messages[2]["content"][0]["toolResult"]["toolUseId"],
+            "call_1"
+        );
+        assert_eq!(
+            messages[2]["content"][2]["image"],
+            json!({"format": "png", "source": {"bytes": "AAAA"}})
+        );
+    }
+
+    #[test]
+    fn test_convert_converse_response() {
+        let response = json!({
+            "output": {"message": {"role": "assistant", "content": [
+                {"text": "checking"},
+                {"toolUse": {"toolUseId": "t1", "name": "get_weather", "input": {"city": "Paris"}}}
+            ]}},
+            "stopReason": "tool_use",
+            "usage": {"inputTokens": 10, "outputTokens": 5, "totalTokens": 15}
+        });
+        let resp = convert_converse_to_openai(&response, "claude-sonnet-4-5-20250929");
+        let choice = &resp.choices[0];
+        assert_eq!(choice.finish_reason, "tool_calls");
+        assert_eq!(choice.message.content.as_deref(), Some("checking"));
+        let call = &choice.message.tool_calls.as_ref().unwrap()[0];
+        assert_eq!(call.id, "t1");
+        assert_eq!(call.function.arguments, r#"{"city":"Paris"}"#);
+        assert_eq!(resp.usage.total_tokens, 15);
+    }
+
+    #[test]
+    fn test_event_stream_decoding_across_chunks() {
+        let mut data = encode_message(
+            &[
+                (":event-type", "contentBlockDelta"),
+                (":message-type", "event"),
+            ],
+            br#"{"contentBlockIndex":0,"delta":{"text":"Hi"}}"#,
+        );
+        data.extend(encode_message(
+            &[(":event-type", "messageStop"), (":message-type", "event")],
+            br#"{"stopReason":"end_turn"}"#,
+        ));
+
+        let mut decoder = EventStreamDecoder::new();
+        let (first, rest) = data.split_at(20);
+        assert!(decoder.push(first).unwrap().is_empty());
+        let messages = decoder.push(rest).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].header(":event-type"), Some("contentBlockDelta"));
+
+        let mut converter = ConverseStreamConverter::new("model", false);
+        let text = converter.convert(&messages[0]);
+        assert!(text[0].contains(r#""content":"Hi""#));
+        let stop = converter.convert(&messages[1]);
+        assert!(stop[0].contains(r#""finish_reason":"stop""#));
+    }
+
+    #[test]
+    fn test_stream_tool_use_events() {
+        let mut converter = ConverseStreamConverter::new("model", true);
+        let start = EventStreamMessage {
+            headers: HashMap::from([(":event-type".to_string(), "contentBlockStart".to_string())]),
+            payload:
+                br#"{"contentBlockIndex":1,"start":{"toolUse":{"toolUseId":"t1","name":"f"}}}"#
+                    .to_vec(),
+        };
+        let delta = EventStreamMessage {
+            headers: HashMap::from([(":event-type".to_string(), "contentBlockDelta".to_string())]),
+            payload: br#"{"contentBlockIndex":1,"delta":{"toolUse":{"input":"{\"a\":"}}}"#.to_vec(),
+        };
+        let metadata = EventStreamMessage {
+            headers: HashMap::from([(":event-type".to_string(), "metadata".to_string())]),
+            payload: br#"{"usage":{"inputTokens":3,"outputTokens":2,"totalTokens":5}}"#.to_vec(),
+        };
+
+        assert!(converter.convert(&start)[0].contains(r#""tool_calls":[{"index":0,"id":"t1""#));
+        assert!(converter.convert(&delta)[0].contains(r#""arguments":"{\"a\":""#));
+        assert!(converter.convert(&metadata)[0].contains(r#""total_tokens":5"#));
+    }
+}
@@ -1,10 +1,16 @@
 //! Claude Custom Provider (自定义 Claude API)
+use crate::converter::reasoning::split_anthropic_content;
+use crate::converter::response_format::{
+    forces_json_tool, json_from_anthropic_content, ResponseFormat,
+};
+use crate::injection::injected_headers;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::{ChatCompletionRequest, ContentPart, MessageContent};
+use crate::services::http_client_service::HttpClientService;
+use reqwest::header::HeaderMap;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ClaudeCustomConfig {
@@ -18,29 +24,11 @@ pub struct ClaudeCustomProvider {
     pub client: Client,
 }
 
-/// 创建配置好的 HTTP 客户端
-///
-/// 配置说明：
-/// - connect_timeout: 连接超时 30 秒
-/// - timeout: 总超时 10 分钟（流式响应可能很长）
-/// - 不设置 pool_idle_timeout 以保持连接活跃
-fn create_http_client() -> Client {
-    Client::builder()
-        .connect_timeout(Duration::from_secs(30))
-        .timeout(Duration::from_secs(600)) // 10 分钟总超时，支持长时间流式响应
-        .tcp_keepalive(Duration::from_secs(60)) // TCP keepalive 保持连接活跃
-        .gzip(true) // 自动解压 gzip 响应
-        .brotli(true) // 自动解压 brotli 响应
-        .deflate(true) // 自动解压 deflate 响应
-        .build()
-        .unwrap_or_else(|_| Client::new())
-}
-
 impl Default for ClaudeCustomProvider {
     fn default() -> Self {
         Self {
             config: ClaudeCustomConfig::default(),
-            client: create_http_client(),
+            client: HttpClientService::provider_client("claude"),
         }
     }
 }
@@ -58,7 +46,7 @@ impl ClaudeCustomProvider {
                 base_url,
                 enabled: true,
             },
-            client: create_http_client(),
+            client: HttpClientService::provider_client("claude"),
         }
     }
 
@@ -135,6 +123,18 @@ impl ClaudeCustomProvider {
     pub async fn call_api(
         &self,
         request: &AnthropicMessagesRequest,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        self.call_api_with_headers(request, HeaderMap::new()).await
+    }
+
+    /// 调用 Anthropic API（原生格式），附带客户端透传的请求头
+    ///
+    /// `client_headers` 中的 `anthropic-version`、`anthropic-beta` 会覆盖默认值，
+    /// 使 fine-grained tool streaming 等 beta 功能在上游生效
+    pub async fn call_api_with_headers(
+        &self,
+        request: &AnthropicMessagesRequest,
+        client_headers: HeaderMap,
     ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
         let api_key = self
             .config
@@ -158,6 +158,8 @@ impl ClaudeCustomProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
+            .headers(client_headers)
+            .headers(injected_headers())
             .json(request)
             .send()
             .await?;
@@ -245,6 +247,11 @@ impl ClaudeCustomProvider {
             anthropic_body["system"] = serde_json::json!(sys);
         }
 
+        // 结构化输出：强制调用 json_response 工具
+        if let Some(format) = ResponseFormat::from_request(request) {
+            format.apply_to_anthropic(&mut anthropic_body, forces_json_tool(request));
+        }
+
         let api_key = self
             .config
             .api_key
@@ -267,6 +274,7 @@ impl ClaudeCustomProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
+            .headers(injected_headers())
             .json(&anthropic_body)
             .send()
             .await?;
@@ -297,12 +305,20 @@ impl ClaudeCustomProvider {
 
         let anthropic_resp: serde_json::Value = resp.json().await?;
 
-        // 转换回 OpenAI 格式
-        let content = anthropic_resp["content"]
+        // 转换回 OpenAI 格式，thinking 块映射为 reasoning_content，json_response 工具参数作为正文
+        let blocks = anthropic_resp["content"]
             .as_array()
-            .and_then(|arr| arr.first())
-            .and_then(|block| block["text"].as_str())
-            .unwrap_or("");
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let (text, reasoning) = split_anthropic_content(blocks);
+        let content = json_from_anthropic_content(blocks).unwrap_or(text);
+        let mut message = serde_json::json!({
+            "role": "assistant",
+            "content": content
+        });
+        if let Some(reasoning) = reasoning {
+            message["reasoning_content"] = serde_json::json!(reasoning);
+        }
 
         Ok(serde_json::json!({
             "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
@@ -314,10 +330,7 @@ impl ClaudeCustomProvider {
             "model": request.model,
             "choices": [{
                 "index": 0,
-                "message": {
-                    "role": "assistant",
-                    "content": content
-                },
+                "message": message,
                 "finish_reason": "stop"
             }],
             "usage": {
@@ -362,6 +375,7 @@ impl ClaudeCustomProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
+            .headers(injected_headers())
             .json(request)
             .send()
             .await?;
@@ -394,6 +408,7 @@ impl ClaudeCustomProvider {
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
+            .headers(injected_headers())
             .json(request)
             .send()
             .await?;
@@ -624,6 +639,11 @@ impl StreamingProvider for ClaudeCustomProvider {
             }
         }
 
+        // 结构化输出：无其他工具时强制调用 json_response 工具
+        if let Some(format) = ResponseFormat::from_request(request) {
+            format.apply_to_anthropic(&mut anthropic_body, forces_json_tool(request));
+        }
+
         let url = self.build_url("messages");
 
         tracing::info!(
@@ -639,6 +659,7 @@ impl StreamingProvider for ClaudeCustomProvider {
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
             .header("Accept", "text/event-stream")
+            .headers(injected_headers())
             .json(&anthropic_body)
             .send()
             .await
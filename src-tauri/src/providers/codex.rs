@@ -8,6 +8,7 @@
 use super::error::{
     create_auth_error, create_config_error, create_token_refresh_error, ProviderError,
 };
+use crate::injection::injected_headers;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
@@ -1141,7 +1142,7 @@ impl CodexProvider {
             }
         }
 
-        let resp = req.send().await?;
+        let resp = req.headers(injected_headers()).send().await?;
 
         Ok(resp)
     }
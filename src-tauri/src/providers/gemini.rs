@@ -9,6 +9,7 @@ use super::error::{
     create_auth_error, create_config_error, create_token_refresh_error, ProviderError,
 };
 use super::traits::{CredentialProvider, ProviderResult};
+use crate::injection::injected_headers;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -394,6 +395,7 @@ impl GeminiProvider {
             .post(&url)
             .header("Authorization", format!("Bearer {token}"))
             .header("Content-Type", "application/json")
+            .headers(injected_headers())
             .json(body)
             .send()
             .await?;
@@ -607,6 +609,7 @@ impl GeminiApiKeyProvider {
             .post(&url)
             .header("x-goog-api-key", &credential.api_key)
             .header("Content-Type", "application/json")
+            .headers(injected_headers())
             .json(body)
             .send()
             .await?;
@@ -638,6 +641,7 @@ impl GeminiApiKeyProvider {
             .post(&url)
             .header("x-goog-api-key", &credential.api_key)
             .header("Content-Type", "application/json")
+            .headers(injected_headers())
             .json(body)
             .send()
             .await?;
@@ -651,6 +655,34 @@ impl GeminiApiKeyProvider {
         Ok(resp)
     }
 
+    /// Make a batchEmbedContents request using the given credential
+    pub async fn batch_embed_contents(
+        &self,
+        credential: &GeminiApiKeyCredential,
+        model: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
+        let url = credential.build_api_url(model, "batchEmbedContents");
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("x-goog-api-key", &credential.api_key)
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("Gemini API embed call failed: {status} - {body}").into());
+        }
+
+        let data: serde_json::Value = resp.json().await?;
+        Ok(data)
+    }
+
     /// List available models using the given credential
     pub async fn list_models(
         &self,
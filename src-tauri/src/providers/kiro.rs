@@ -3,9 +3,11 @@
 #![allow(dead_code)]
 
 // 使用新的 translator 模块替代旧的 converter
+use crate::injection::injected_headers;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::*;
 use crate::providers::traits::{CredentialProvider, ProviderResult};
+use crate::services::http_client_service::HttpClientService;
 use crate::translator::kiro::anthropic::request::convert_anthropic_to_codewhisperer;
 use crate::translator::kiro::openai::request::convert_openai_to_codewhisperer;
 use async_trait::async_trait;
@@ -253,17 +255,9 @@ pub struct KiroProvider {
 
 impl Default for KiroProvider {
     fn default() -> Self {
-        // 创建带超时配置的 HTTP 客户端
-        // 参考 AIClient-2-API: AXIOS_TIMEOUT: 300000 (5分钟)
-        let client = Client::builder()
-            .connect_timeout(std::time::Duration::from_secs(30)) // 连接超时 30 秒
-            .timeout(std::time::Duration::from_secs(300)) // 总超时 5 分钟
-            .build()
-            .unwrap_or_else(|_| Client::new());
-
         Self {
             credentials: KiroCredentials::default(),
-            client,
+            client: HttpClientService::provider_client("kiro"),
             creds_path: None,
         }
     }
@@ -1039,6 +1033,7 @@ impl KiroProvider {
             )
             // 添加 Connection: close 避免连接复用被检测
             .header("Connection", "close")
+            .headers(injected_headers())
             .json(&cw_request)
             .send()
             .await?;
@@ -1191,6 +1186,7 @@ impl StreamingProvider for KiroProvider {
                 ),
             )
             // 注意：不要设置 Connection: close，否则会导致流式响应无法工作
+            .headers(injected_headers())
             .json(&cw_request)
             .send()
             .await
@@ -1290,6 +1286,7 @@ impl KiroProvider {
                     "aws-sdk-js/1.0.0 ua/2.1 os/{os_name} lang/js md/nodejs#{node_version} api/codewhispererruntime#1.0.0 m/E KiroIDE-{kiro_version}-{machine_id}"
                 ),
             )
+            .headers(injected_headers())
             .json(&cw_request)
             .send()
             .await
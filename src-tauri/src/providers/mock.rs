@@ -0,0 +1,274 @@
+//! Mock Provider
+//!
+//! 不访问任何上游，按凭证配置返回固定或模板化的响应，可附加人为延迟并按概率注入错误，
+//! 让客户端集成和 CI 无需真实凭证即可走通完整的代理链路。
+//!
+//! 响应模板支持以下占位符：
+//! - `{{model}}`：请求的模型
+//! - `{{last_user_message}}`：最后一条用户消息的文本
+//! - `{{message_count}}`：请求中的消息数
+
+use crate::models::openai::{
+    ChatCompletionRequest, ChatCompletionResponse, Choice, ResponseMessage, Usage,
+};
+use crate::models::provider_pool_model::CredentialData;
+use rand::Rng;
+use std::time::Duration;
+
+/// 未配置响应模板时使用的默认响应
+pub const DEFAULT_MOCK_RESPONSE: &str = "This is a mock response from {{model}}.";
+
+/// 注入错误时默认返回的状态码
+pub const DEFAULT_MOCK_ERROR_STATUS: u16 = 500;
+
+/// Mock 凭证的运行参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockProvider {
+    /// 响应模板
+    pub response: String,
+    /// 每次请求的人为延迟
+    pub latency: Duration,
+    /// 注入错误的概率（0.0 ~ 1.0）
+    pub error_rate: f64,
+    /// 注入错误时返回的状态码
+    pub error_status: u16,
+}
+
+/// 注入的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self {
+            response: DEFAULT_MOCK_RESPONSE.to_string(),
+            latency: Duration::ZERO,
+            error_rate: 0.0,
+            error_status: DEFAULT_MOCK_ERROR_STATUS,
+        }
+    }
+}
+
+impl MockProvider {
+    /// 从 Mock 凭证构造，其他凭证类型返回 `None`
+    pub fn from_credential(credential: &CredentialData) -> Option<Self> {
+        let CredentialData::Mock {
+            response,
+            latency_ms,
+            error_rate,
+            error_status,
+        } = credential
+        else {
+            return None;
+        };
+        let defaults = Self::default();
+        Some(Self {
+            response: response
+                .clone()
+                .filter(|r| !r.is_empty())
+                .unwrap_or(defaults.response),
+            latency: Duration::from_millis(latency_ms.unwrap_or(0)),
+            error_rate: error_rate.unwrap_or(0.0).clamp(0.0, 1.0),
+            error_status: error_status
+                .filter(|s| (400..600).contains(s))
+                .unwrap_or(defaults.error_status),
+        })
+    }
+
+    /// 等待配置的延迟，然后按概率注入错误
+    pub async fn simulate(&self) -> Result<(), MockError> {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+        if self.error_rate > 0.0 && rand::thread_rng().gen_bool(self.error_rate) {
+            return Err(MockError {
+                status: self.error_status,
+                message: format!(
+                    "Mock provider injected error (status {})",
+                    self.error_status
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// 按请求渲染响应模板
+    pub fn render(&self, request: &ChatCompletionRequest) -> String {
+        let last_user_message = request
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .map(|m| m.get_content_text())
+            .unwrap_or_default();
+        self.response
+            .replace("{{model}}", &request.model)
+            .replace("{{last_user_message}}", &last_user_message)
+            .replace("{{message_count}}", &request.messages.len().to_string())
+    }
+
+    /// 生成 OpenAI 格式的完整响应，`usage` 由调用方按实际分词结果填写
+    pub fn chat_completion(&self, request: &ChatCompletionRequest) -> ChatCompletionResponse {
+        ChatCompletionResponse {
+            id: format!("chatcmpl-mock-{}", uuid::Uuid::new_v4()),
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp() as u64,
+            model: request.model.clone(),
+            choices: vec![Choice {
+                index: 0,
+                message: ResponseMessage {
+                    role: "assistant".to_string(),
+                    content: Some(self.render(request)),
+                    tool_calls: None,
+                    reasoning_content: None,
+                },
+                finish_reason: "stop".to_string(),
+            }],
+            usage: Usage {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+            },
+        }
+    }
+}
+
+/// 将完整响应转换为 OpenAI SSE，内容按单词拆分成多个 chunk，模拟逐字输出
+pub fn to_openai_sse(response: &ChatCompletionResponse, include_usage: bool) -> String {
+    let content = response
+        .choices
+        .first()
+        .and_then(|c| c.message.content.as_deref())
+        .unwrap_or_default();
+    let chunk = |delta: serde_json::Value, finish_reason: Option<&str>| {
+        let chunk = serde_json::json!({
+            "id": &response.id,
+            "object": "chat.completion.chunk",
+            "created": response.created,
+            "model": &response.model,
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason
+            }]
+        });
+        format!("data: {}\n\n", chunk)
+    };
+
+    let mut sse = chunk(serde_json::json!({ "role": "assistant" }), None);
+    for piece in content.split_inclusive(' ') {
+        sse.push_str(&chunk(serde_json::json!({ "content": piece }), None));
+    }
+    sse.push_str(&chunk(serde_json::json!({}), Some("stop")));
+    if include_usage {
+        let usage_chunk = serde_json::json!({
+            "id": &response.id,
+            "object": "chat.completion.chunk",
+            "created": response.created,
+            "model": &response.model,
+            "choices": [],
+            "usage": &response.usage
+        });
+        sse.push_str(&format!("data: {}\n\n", usage_chunk));
+    }
+    sse.push_str("data: [DONE]\n\n");
+    sse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(messages: serde_json::Value) -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "mock-model",
+            "messages": messages
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_from_credential_applies_defaults() {
+        let provider = MockProvider::from_credential(&CredentialData::Mock {
+            response: None,
+            latency_ms: None,
+            error_rate: Some(2.0),
+            error_status: Some(200),
+        })
+        .unwrap();
+        assert_eq!(provider.response, DEFAULT_MOCK_RESPONSE);
+        assert_eq!(provider.latency, Duration::ZERO);
+        assert_eq!(provider.error_rate, 1.0);
+        assert_eq!(provider.error_status, DEFAULT_MOCK_ERROR_STATUS);
+
+        assert!(
+            MockProvider::from_credential(&CredentialData::Ollama { base_url: None }).is_none()
+        );
+    }
+
+    #[test]
+    fn test_render_template() {
+        let provider = MockProvider {
+            response: "[{{model}}] {{message_count}}: {{last_user_message}}".to_string(),
+            ..MockProvider::default()
+        };
+        let request = request(serde_json::json!([
+            { "role": "system", "content": "be brief" },
+            { "role": "user", "content": "first" },
+            { "role": "assistant", "content": "ok" },
+            { "role": "user", "content": "second" }
+        ]));
+        assert_eq!(provider.render(&request), "[mock-model] 4: second");
+
+        let response = provider.chat_completion(&request);
+        assert_eq!(response.model, "mock-model");
+        assert_eq!(
+            response.choices[0].message.content.as_deref(),
+            Some("[mock-model] 4: second")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simulate_injects_errors() {
+        let always = MockProvider {
+            error_rate: 1.0,
+            error_status: 503,
+            ..MockProvider::default()
+        };
+        assert_eq!(always.simulate().await.unwrap_err().status, 503);
+        assert!(MockProvider::default().simulate().await.is_ok());
+    }
+
+    #[test]
+    fn test_to_openai_sse_streams_words() {
+        let provider = MockProvider {
+            response: "hello mock world".to_string(),
+            ..MockProvider::default()
+        };
+        let response = provider.chat_completion(&request(serde_json::json!([
+            { "role": "user", "content": "hi" }
+        ])));
+        let sse = to_openai_sse(&response, true);
+        let events: Vec<&str> = sse
+            .split("\n\n")
+            .filter_map(|e| e.strip_prefix("data: "))
+            .collect();
+        // role + 3 个单词 + 结束 + usage + [DONE]
+        assert_eq!(events.len(), 7);
+        assert_eq!(events.last(), Some(&"[DONE]"));
+
+        let text: String = events
+            .iter()
+            .filter_map(|e| serde_json::from_str::<serde_json::Value>(e).ok())
+            .filter_map(|v| {
+                v["choices"][0]["delta"]["content"]
+                    .as_str()
+                    .map(String::from)
+            })
+            .collect();
+        assert_eq!(text, "hello mock world");
+    }
+}
@@ -1,13 +1,20 @@
 pub mod antigravity;
+pub mod azure_openai;
+pub mod bedrock;
 pub mod claude_custom;
 pub mod claude_oauth;
 pub mod codex;
 pub mod error;
 pub mod gemini;
 pub mod kiro;
+pub mod mock;
+pub mod ollama;
 pub mod openai_custom;
+pub mod openai_presets;
+pub mod qwen;
 pub mod traits;
 pub mod vertex;
+pub mod vertex_service_account;
 
 #[cfg(test)]
 mod tests;
@@ -23,6 +30,10 @@ pub use antigravity::AntigravityProvider;
 #[allow(unused_imports)]
 pub use antigravity::ANTIGRAVITY_MODELS_FALLBACK;
 #[allow(unused_imports)]
+pub use azure_openai::AzureOpenAIProvider;
+#[allow(unused_imports)]
+pub use bedrock::BedrockProvider;
+#[allow(unused_imports)]
 pub use claude_custom::ClaudeCustomProvider;
 #[allow(unused_imports)]
 pub use claude_oauth::ClaudeOAuthProvider;
@@ -35,6 +46,14 @@ pub use gemini::{GeminiApiKeyCredential, GeminiApiKeyProvider, GeminiProvider};
 #[allow(unused_imports)]
 pub use kiro::KiroProvider;
 #[allow(unused_imports)]
+pub use mock::MockProvider;
+#[allow(unused_imports)]
+pub use ollama::OllamaProvider;
+#[allow(unused_imports)]
 pub use openai_custom::OpenAICustomProvider;
 #[allow(unused_imports)]
+pub use qwen::QwenProvider;
+#[allow(unused_imports)]
 pub use vertex::VertexProvider;
+#[allow(unused_imports)]
+pub use vertex_service_account::VertexServiceAccountProvider;
@@ -0,0 +1,220 @@
+//! 本地模型 Provider（Ollama / LM Studio / llama.cpp）
+//!
+//! 三者都提供 OpenAI 兼容的 `/v1/chat/completions`，不需要 API Key。
+//! 模型列表优先读取 Ollama 的 `/api/tags`，不可用时回退到 OpenAI 兼容的 `/v1/models`。
+
+#![allow(dead_code)]
+
+use crate::injection::injected_headers;
+use crate::models::openai::ChatCompletionRequest;
+use crate::services::http_client_service::HttpClientService;
+use futures::future::join_all;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::time::Duration;
+
+/// Ollama 默认监听地址
+pub const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+/// 自动发现时探测的本地服务（名称, 默认地址）
+pub const KNOWN_LOCAL_SERVERS: &[(&str, &str)] = &[
+    ("Ollama", DEFAULT_OLLAMA_BASE_URL),
+    ("LM Studio", "http://localhost:1234"),
+    ("llama.cpp", "http://localhost:8080"),
+];
+
+/// 探测本地服务时的超时时间（本地服务未启动时应尽快失败）
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub struct OllamaProvider {
+    pub base_url: String,
+    pub client: Client,
+}
+
+/// 自动发现到的本地模型服务
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalModelServer {
+    pub name: String,
+    pub base_url: String,
+    pub models: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<TagModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagModel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelsResponse {
+    #[serde(default)]
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: Option<String>) -> Self {
+        Self {
+            base_url: base_url
+                .filter(|url| !url.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_OLLAMA_BASE_URL.to_string()),
+            client: HttpClientService::provider_client("ollama"),
+        }
+    }
+
+    /// 服务根地址（去掉末尾的 `/` 和 `/v1`）
+    ///
+    /// - `http://localhost:11434` -> `http://localhost:11434`
+    /// - `http://localhost:1234/v1/` -> `http://localhost:1234`
+    pub fn root_url(&self) -> String {
+        let base = self.base_url.trim().trim_end_matches('/');
+        base.strip_suffix("/v1").unwrap_or(base).to_string()
+    }
+
+    pub fn chat_completions_url(&self) -> String {
+        format!("{}/v1/chat/completions", self.root_url())
+    }
+
+    /// 调用 OpenAI 兼容的 Chat Completions API，返回上游原始响应
+    pub async fn call_api(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let url = self.chat_completions_url();
+        tracing::debug!("[OLLAMA] POST {} model={}", url, request.model);
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .headers(injected_headers())
+            .json(request)
+            .send()
+            .await?;
+        Ok(resp)
+    }
+
+    /// 获取本地已安装的模型
+    ///
+    /// 先请求 Ollama 的 `/api/tags`，失败时回退到 `/v1/models`（LM Studio / llama.cpp）
+    pub async fn list_models(&self) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let root = self.root_url();
+
+        match self.fetch_tags(&root).await {
+            Ok(models) => return Ok(models),
+            Err(e) => {
+                tracing::debug!("[OLLAMA] /api/tags 不可用，回退到 /v1/models: {}", e);
+            }
+        }
+
+        let resp = self
+            .client
+            .get(format!("{}/v1/models", root))
+            .timeout(PROBE_TIMEOUT)
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(format!(
+                "HTTP {} - {}",
+                status,
+                resp.text().await.unwrap_or_default()
+            )
+            .into());
+        }
+        let parsed: ModelsResponse = resp.json().await?;
+        Ok(parsed.data.into_iter().map(|m| m.id).collect())
+    }
+
+    async fn fetch_tags(&self, root: &str) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let resp = self
+            .client
+            .get(format!("{}/api/tags", root))
+            .timeout(PROBE_TIMEOUT)
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(format!("HTTP {}", status).into());
+        }
+        let parsed: TagsResponse = resp.json().await?;
+        Ok(parsed.models.into_iter().map(|m| m.name).collect())
+    }
+
+    /// 探测本地服务是否可用（能列出模型即视为可用）
+    pub async fn probe(&self) -> Result<(), String> {
+        self.list_models()
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("本地服务不可用 ({}): {}", self.root_url(), e))
+    }
+}
+
+/// 探测常见端口上正在运行的本地模型服务
+pub async fn discover_local_servers() -> Vec<LocalModelServer> {
+    let probes = KNOWN_LOCAL_SERVERS
+        .iter()
+        .map(|(name, base_url)| async move {
+            let provider = OllamaProvider::new(Some(base_url.to_string()));
+            match provider.list_models().await {
+                Ok(models) => Some(LocalModelServer {
+                    name: name.to_string(),
+                    base_url: base_url.to_string(),
+                    models,
+                }),
+                Err(e) => {
+                    tracing::debug!("[OLLAMA] {} ({}) 未运行: {}", name, base_url, e);
+                    None
+                }
+            }
+        });
+    join_all(probes).await.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        let provider = OllamaProvider::new(None);
+        assert_eq!(provider.root_url(), "http://localhost:11434");
+        assert_eq!(
+            provider.chat_completions_url(),
+            "http://localhost:11434/v1/chat/completions"
+        );
+
+        let provider = OllamaProvider::new(Some("http://127.0.0.1:1234/v1/".to_string()));
+        assert_eq!(provider.root_url(), "http://127.0.0.1:1234");
+        assert_eq!(
+            provider.chat_completions_url(),
+            "http://127.0.0.1:1234/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_parse_model_lists() {
+        let tags: TagsResponse = serde_json::from_str(
+            r#"{"models":[{"name":"llama3.2:latest","size":2019393189},{"name":"qwen2.5-coder:7b"}]}"#,
+        )
+        .unwrap();
+        let names: Vec<String> = tags.models.into_iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["llama3.2:latest", "qwen2.5-coder:7b"]);
+
+        let models: ModelsResponse = serde_json::from_str(
+            r#"{"object":"list","data":[{"id":"local-model","object":"model"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(models.data[0].id, "local-model");
+    }
+}
@@ -1,10 +1,14 @@
 //! OpenAI Custom Provider (自定义 OpenAI 兼容 API)
-use crate::models::openai::ChatCompletionRequest;
+use crate::injection::injected_headers;
+use crate::models::openai::{ChatCompletionRequest, EmbeddingRequest};
+use crate::providers::openai_presets::{
+    find_preset_for_base_url, AuthHeaderStyle, OpenAICompatPreset,
+};
+use crate::services::http_client_service::HttpClientService;
 use reqwest::Client;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::time::Duration;
 use url::Url;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -19,24 +23,11 @@ pub struct OpenAICustomProvider {
     pub client: Client,
 }
 
-/// 创建配置好的 HTTP 客户端
-fn create_http_client() -> Client {
-    Client::builder()
-        .connect_timeout(Duration::from_secs(30))
-        .timeout(Duration::from_secs(600)) // 10 分钟总超时
-        .tcp_keepalive(Duration::from_secs(60))
-        .gzip(true) // 自动解压 gzip 响应
-        .brotli(true) // 自动解压 brotli 响应
-        .deflate(true) // 自动解压 deflate 响应
-        .build()
-        .unwrap_or_else(|_| Client::new())
-}
-
 impl Default for OpenAICustomProvider {
     fn default() -> Self {
         Self {
             config: OpenAICustomConfig::default(),
-            client: create_http_client(),
+            client: HttpClientService::provider_client("openai"),
         }
     }
 }
@@ -54,7 +45,7 @@ impl OpenAICustomProvider {
                 base_url,
                 enabled: true,
             },
-            client: create_http_client(),
+            client: HttpClientService::provider_client("openai"),
         }
     }
 
@@ -69,6 +60,34 @@ impl OpenAICustomProvider {
         self.config.api_key.is_some() && self.config.enabled
     }
 
+    /// 按 base_url 匹配的内置预设（Mistral、DeepSeek 等）
+    pub fn preset(&self) -> Option<&'static OpenAICompatPreset> {
+        self.config
+            .base_url
+            .as_deref()
+            .and_then(find_preset_for_base_url)
+    }
+
+    /// 认证头（未匹配预设时使用 Bearer）
+    fn auth_header(&self, api_key: &str) -> (&'static str, String) {
+        self.preset()
+            .map(|p| p.auth_header)
+            .unwrap_or(AuthHeaderStyle::Bearer)
+            .header(api_key)
+    }
+
+    /// 序列化请求体，并按预设修正上游不支持的参数
+    fn prepare_body(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<serde_json::Value, serde_json::Error> {
+        let mut body = serde_json::to_value(request)?;
+        if let Some(preset) = self.preset() {
+            preset.apply_quirks(&mut body);
+        }
+        Ok(body)
+    }
+
     /// 构建完整的 API URL
     /// 智能处理用户输入的 base_url，支持多种 API 版本格式
     ///
@@ -202,7 +221,9 @@ impl OpenAICustomProvider {
             .api_key
             .as_ref()
             .ok_or("OpenAI API key not configured")?;
+        let (auth_name, auth_value) = self.auth_header(api_key);
 
+        let body = self.prepare_body(request)?;
         let urls = self.build_urls_with_fallbacks("chat/completions");
         let mut last_resp: Option<reqwest::Response> = None;
 
@@ -216,9 +237,10 @@ impl OpenAICustomProvider {
             let resp = self
                 .client
                 .post(url)
-                .header("Authorization", format!("Bearer {api_key}"))
+                .header(auth_name, &auth_value)
                 .header("Content-Type", "application/json")
-                .json(request)
+                .headers(injected_headers())
+                .json(&body)
                 .send()
                 .await?;
 
@@ -240,7 +262,12 @@ impl OpenAICustomProvider {
             .api_key
             .as_ref()
             .ok_or("OpenAI API key not configured")?;
+        let (auth_name, auth_value) = self.auth_header(api_key);
 
+        let mut body = request.clone();
+        if let Some(preset) = self.preset() {
+            preset.apply_quirks(&mut body);
+        }
         let url = self.build_url("chat/completions");
 
         eprintln!("[OPENAI_CUSTOM] chat_completions URL: {}", url);
@@ -252,9 +279,10 @@ impl OpenAICustomProvider {
         let resp = self
             .client
             .post(&url)
-            .header("Authorization", format!("Bearer {api_key}"))
+            .header(auth_name, &auth_value)
             .header("Content-Type", "application/json")
-            .json(request)
+            .headers(injected_headers())
+            .json(&body)
             .send()
             .await?;
 
@@ -264,9 +292,10 @@ impl OpenAICustomProvider {
                     let resp2 = self
                         .client
                         .post(&fallback_url)
-                        .header("Authorization", format!("Bearer {api_key}"))
+                        .header(auth_name, &auth_value)
                         .header("Content-Type", "application/json")
-                        .json(request)
+                        .headers(injected_headers())
+                        .json(&body)
                         .send()
                         .await?;
                     return Ok(resp2);
@@ -277,12 +306,118 @@ impl OpenAICustomProvider {
         Ok(resp)
     }
 
+    /// 调用 Embeddings API
+    pub async fn embeddings(
+        &self,
+        request: &EmbeddingRequest,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or("OpenAI API key not configured")?;
+        let (auth_name, auth_value) = self.auth_header(api_key);
+
+        let urls = self.build_urls_with_fallbacks("embeddings");
+        let mut last_resp: Option<reqwest::Response> = None;
+
+        for url in &urls {
+            let resp = self
+                .client
+                .post(url)
+                .header(auth_name, &auth_value)
+                .header("Content-Type", "application/json")
+                .json(request)
+                .send()
+                .await?;
+
+            if resp.status() != StatusCode::NOT_FOUND {
+                return Ok(resp);
+            }
+            last_resp = Some(resp);
+        }
+
+        Ok(last_resp.ok_or("Request failed")?)
+    }
+
+    /// 调用语音转写 API（multipart 请求）
+    ///
+    /// `form` 每次尝试 URL 时重新构建，multipart 请求体只能发送一次
+    pub async fn audio_transcriptions(
+        &self,
+        form: impl Fn() -> reqwest::multipart::Form,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or("OpenAI API key not configured")?;
+        let (auth_name, auth_value) = self.auth_header(api_key);
+
+        let urls = self.build_urls_with_fallbacks("audio/transcriptions");
+        let mut last_resp: Option<reqwest::Response> = None;
+
+        for url in &urls {
+            let resp = self
+                .client
+                .post(url)
+                .header(auth_name, &auth_value)
+                .headers(injected_headers())
+                .multipart(form())
+                .send()
+                .await?;
+
+            if resp.status() != StatusCode::NOT_FOUND {
+                return Ok(resp);
+            }
+            last_resp = Some(resp);
+        }
+
+        Ok(last_resp.ok_or("Request failed")?)
+    }
+
+    /// 调用语音合成 API，响应体为音频字节
+    pub async fn audio_speech(
+        &self,
+        request: &serde_json::Value,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or("OpenAI API key not configured")?;
+        let (auth_name, auth_value) = self.auth_header(api_key);
+
+        let urls = self.build_urls_with_fallbacks("audio/speech");
+        let mut last_resp: Option<reqwest::Response> = None;
+
+        for url in &urls {
+            let resp = self
+                .client
+                .post(url)
+                .header(auth_name, &auth_value)
+                .header("Content-Type", "application/json")
+                .headers(injected_headers())
+                .json(request)
+                .send()
+                .await?;
+
+            if resp.status() != StatusCode::NOT_FOUND {
+                return Ok(resp);
+            }
+            last_resp = Some(resp);
+        }
+
+        Ok(last_resp.ok_or("Request failed")?)
+    }
+
     pub async fn list_models(&self) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
         let api_key = self
             .config
             .api_key
             .as_ref()
             .ok_or("OpenAI API key not configured")?;
+        let (auth_name, auth_value) = self.auth_header(api_key);
 
         let urls = self.build_urls_with_fallbacks("models");
         let mut tried_urls: Vec<String> = Vec::new();
@@ -294,7 +429,7 @@ impl OpenAICustomProvider {
             let r = self
                 .client
                 .get(&url)
-                .header("Authorization", format!("Bearer {api_key}"))
+                .header(auth_name, &auth_value)
                 .send()
                 .await?;
             if r.status() != StatusCode::NOT_FOUND {
@@ -348,11 +483,15 @@ impl StreamingProvider for OpenAICustomProvider {
         let api_key = self.config.api_key.as_ref().ok_or_else(|| {
             ProviderError::ConfigurationError("OpenAI API key not configured".to_string())
         })?;
+        let (auth_name, auth_value) = self.auth_header(api_key);
 
         // 确保请求启用流式
         let mut stream_request = request.clone();
         stream_request.stream = true;
 
+        let body = self
+            .prepare_body(&stream_request)
+            .map_err(|e| ProviderError::ConfigurationError(e.to_string()))?;
         let url = self.build_url("chat/completions");
 
         tracing::info!(
@@ -364,10 +503,11 @@ impl StreamingProvider for OpenAICustomProvider {
         let resp = self
             .client
             .post(&url)
-            .header("Authorization", format!("Bearer {api_key}"))
+            .header(auth_name, &auth_value)
             .header("Content-Type", "application/json")
             .header("Accept", "text/event-stream")
-            .json(&stream_request)
+            .headers(injected_headers())
+            .json(&body)
             .send()
             .await
             .map_err(|e| ProviderError::from_reqwest_error(&e))?;
@@ -377,10 +517,11 @@ impl StreamingProvider for OpenAICustomProvider {
                 if fallback_url != url {
                     self.client
                         .post(&fallback_url)
-                        .header("Authorization", format!("Bearer {api_key}"))
+                        .header(auth_name, &auth_value)
                         .header("Content-Type", "application/json")
                         .header("Accept", "text/event-stream")
-                        .json(&stream_request)
+                        .headers(injected_headers())
+                        .json(&body)
                         .send()
                         .await
                         .map_err(|e| ProviderError::from_reqwest_error(&e))?
@@ -0,0 +1,279 @@
+//! OpenAI 兼容 Provider 预设
+//!
+//! 为常见的 OpenAI 兼容服务（Mistral、DeepSeek）内置 base URL、认证头、已知模型和参数差异，
+//! `OpenAICustomProvider` 按 base URL 的主机名匹配预设，发送前自动修正请求体：
+//! - 删除上游不支持或会报错的参数
+//! - 重命名参数（如 Mistral 的 `seed` -> `random_seed`）
+//! - 限制 `max_tokens` 上限
+
+#![allow(dead_code)]
+
+use crate::models::provider_pool_model::pattern_matches;
+use serde_json::{Map, Value};
+use url::Url;
+
+/// 认证头格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthHeaderStyle {
+    /// `Authorization: Bearer <key>`
+    Bearer,
+    /// 自定义头，值为原始 key
+    Header(&'static str),
+}
+
+impl AuthHeaderStyle {
+    /// 返回（头名称, 头的值）
+    pub fn header(&self, api_key: &str) -> (&'static str, String) {
+        match self {
+            AuthHeaderStyle::Bearer => ("Authorization", format!("Bearer {api_key}")),
+            AuthHeaderStyle::Header(name) => (*name, api_key.to_string()),
+        }
+    }
+}
+
+/// 针对部分模型的参数差异
+#[derive(Debug, Clone, Copy)]
+pub struct ParamQuirks {
+    /// 适用的模型（支持 `*` 通配符）
+    pub models: &'static str,
+    /// 发送前删除的参数
+    pub drop: &'static [&'static str],
+    /// 参数重命名（原名, 上游名）
+    pub rename: &'static [(&'static str, &'static str)],
+    /// `max_tokens` 上限
+    pub max_tokens_limit: Option<u64>,
+    /// `tool_choice: "required"` 在上游的写法
+    pub tool_choice_required: Option<&'static str>,
+}
+
+/// OpenAI 兼容 Provider 预设
+#[derive(Debug, Clone, Copy)]
+pub struct OpenAICompatPreset {
+    /// 与系统 Provider ID 一致
+    pub id: &'static str,
+    pub name: &'static str,
+    pub base_url: &'static str,
+    pub auth_header: AuthHeaderStyle,
+    /// 已知模型（上游模型列表不可用时使用）
+    pub models: &'static [&'static str],
+    /// 按顺序应用所有匹配的规则
+    pub quirks: &'static [ParamQuirks],
+}
+
+/// Mistral 对未知字段返回 422，需要删除 OpenAI 特有的参数
+pub const MISTRAL: OpenAICompatPreset = OpenAICompatPreset {
+    id: "mistral",
+    name: "Mistral",
+    base_url: "https://api.mistral.ai/v1",
+    auth_header: AuthHeaderStyle::Bearer,
+    models: &[
+        "mistral-large-latest",
+        "mistral-medium-latest",
+        "mistral-small-latest",
+        "magistral-medium-latest",
+        "magistral-small-latest",
+        "codestral-latest",
+        "devstral-medium-latest",
+        "pixtral-large-latest",
+        "ministral-8b-latest",
+        "ministral-3b-latest",
+    ],
+    quirks: &[ParamQuirks {
+        models: "*",
+        drop: &[
+            "logit_bias",
+            "logprobs",
+            "top_logprobs",
+            "user",
+            "store",
+            "service_tier",
+            "stream_options",
+            "reasoning_effort",
+            "metadata",
+        ],
+        rename: &[
+            ("seed", "random_seed"),
+            ("max_completion_tokens", "max_tokens"),
+        ],
+        max_tokens_limit: None,
+        tool_choice_required: Some("any"),
+    }],
+};
+
+/// DeepSeek 推理模型不支持采样参数，`logprobs` 会直接报错
+pub const DEEPSEEK: OpenAICompatPreset = OpenAICompatPreset {
+    id: "deepseek",
+    name: "DeepSeek",
+    base_url: "https://api.deepseek.com",
+    auth_header: AuthHeaderStyle::Bearer,
+    models: &["deepseek-chat", "deepseek-reasoner"],
+    quirks: &[
+        ParamQuirks {
+            models: "*",
+            drop: &["reasoning_effort", "store", "service_tier", "metadata"],
+            rename: &[("max_completion_tokens", "max_tokens")],
+            max_tokens_limit: None,
+            tool_choice_required: None,
+        },
+        ParamQuirks {
+            models: "deepseek-chat",
+            drop: &[],
+            rename: &[],
+            max_tokens_limit: Some(8192),
+            tool_choice_required: None,
+        },
+        ParamQuirks {
+            models: "deepseek-reasoner",
+            drop: &[
+                "temperature",
+                "top_p",
+                "presence_penalty",
+                "frequency_penalty",
+                "logprobs",
+                "top_logprobs",
+            ],
+            rename: &[],
+            max_tokens_limit: Some(65536),
+            tool_choice_required: None,
+        },
+    ],
+};
+
+/// 所有内置预设
+pub const OPENAI_COMPAT_PRESETS: &[OpenAICompatPreset] = &[MISTRAL, DEEPSEEK];
+
+/// 按系统 Provider ID 查找预设
+pub fn find_preset(id: &str) -> Option<&'static OpenAICompatPreset> {
+    OPENAI_COMPAT_PRESETS.iter().find(|p| p.id == id)
+}
+
+/// 按 base URL 的主机名查找预设
+pub fn find_preset_for_base_url(base_url: &str) -> Option<&'static OpenAICompatPreset> {
+    let host = url_host(base_url)?;
+    OPENAI_COMPAT_PRESETS
+        .iter()
+        .find(|p| url_host(p.base_url).as_deref() == Some(host.as_str()))
+}
+
+fn url_host(url: &str) -> Option<String> {
+    let url = url.trim();
+    Url::parse(url)
+        .or_else(|_| Url::parse(&format!("https://{}", url)))
+        .ok()?
+        .host_str()
+        .map(|h| h.to_ascii_lowercase())
+}
+
+impl OpenAICompatPreset {
+    /// 按模型修正请求体（非对象请求体保持不变）
+    pub fn apply_quirks(&self, body: &mut Value) {
+        let Some(obj) = body.as_object_mut() else {
+            return;
+        };
+        let model = obj
+            .get("model")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        for quirks in self
+            .quirks
+            .iter()
+            .filter(|q| pattern_matches(q.models, &model))
+        {
+            apply(quirks, obj);
+        }
+    }
+}
+
+fn apply(quirks: &ParamQuirks, obj: &mut Map<String, Value>) {
+    for key in quirks.drop {
+        obj.remove(*key);
+    }
+    for (from, to) in quirks.rename {
+        if let Some(value) = obj.remove(*from) {
+            // 两个字段同时存在时保留上游原生字段
+            obj.entry(to.to_string()).or_insert(value);
+        }
+    }
+    if let Some(limit) = quirks.max_tokens_limit {
+        if let Some(max_tokens) = obj.get_mut("max_tokens") {
+            if max_tokens.as_u64().is_some_and(|v| v > limit) {
+                *max_tokens = Value::from(limit);
+            }
+        }
+    }
+    if let Some(required) = quirks.tool_choice_required {
+        if let Some(tool_choice) = obj.get_mut("tool_choice") {
+            if tool_choice.as_str() == Some("required") {
+                *tool_choice = Value::from(required);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_preset_for_base_url() {
+        assert_eq!(
+            find_preset_for_base_url("https://api.mistral.ai").map(|p| p.id),
+            Some("mistral")
+        );
+        assert_eq!(
+            find_preset_for_base_url("api.deepseek.com/v1").map(|p| p.id),
+            Some("deepseek")
+        );
+        assert!(find_preset_for_base_url("https://api.openai.com/v1").is_none());
+        assert!(find_preset_for_base_url("https://deepseek.alayanew.com").is_none());
+    }
+
+    #[test]
+    fn test_deepseek_reasoner_quirks() {
+        let mut body = json!({
+            "model": "deepseek-reasoner",
+            "messages": [],
+            "temperature": 0.7,
+            "presence_penalty": 0.5,
+            "max_completion_tokens": 100000,
+            "reasoning_effort": "high",
+            "stream_options": {"include_usage": true}
+        });
+        DEEPSEEK.apply_quirks(&mut body);
+        assert_eq!(
+            body,
+            json!({
+                "model": "deepseek-reasoner",
+                "messages": [],
+                "max_tokens": 65536,
+                "stream_options": {"include_usage": true}
+            })
+        );
+
+        let mut body = json!({"model": "deepseek-chat", "temperature": 0.7, "max_tokens": 9000});
+        DEEPSEEK.apply_quirks(&mut body);
+        assert_eq!(
+            body,
+            json!({"model": "deepseek-chat", "temperature": 0.7, "max_tokens": 8192})
+        );
+    }
+
+    #[test]
+    fn test_mistral_quirks() {
+        let mut body = json!({
+            "model": "mistral-large-latest",
+            "seed": 42,
+            "user": "u1",
+            "tool_choice": "required",
+            "stream_options": {"include_usage": true}
+        });
+        MISTRAL.apply_quirks(&mut body);
+        assert_eq!(
+            body,
+            json!({"model": "mistral-large-latest", "random_seed": 42, "tool_choice": "any"})
+        );
+    }
+}
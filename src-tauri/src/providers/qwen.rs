@@ -0,0 +1,408 @@
+//! Qwen Code OAuth Provider
+//!
+//! 实现通义千问 Qwen Code OAuth 凭证的加载、刷新与调用。
+//! 凭证文件格式与 qwen-code CLI 的 `~/.qwen/oauth_creds.json` 兼容，
+//! 上游接口为 OpenAI 兼容格式（`/chat/completions`）。
+
+use super::error::{
+    create_auth_error, create_config_error, create_token_refresh_error, ProviderError,
+};
+use super::traits::{CredentialProvider, ProviderResult};
+use crate::injection::injected_headers;
+use crate::models::openai::ChatCompletionRequest;
+use crate::services::http_client_service::HttpClientService;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::PathBuf;
+
+// Constants - 与 qwen-code CLI 对齐
+const CREDENTIALS_DIR: &str = ".qwen";
+const CREDENTIALS_FILE: &str = "oauth_creds.json";
+
+// OAuth 端点
+const QWEN_TOKEN_URL: &str = "https://chat.qwen.ai/api/v1/oauth2/token";
+const DEFAULT_QWEN_OAUTH_CLIENT_ID: &str = "f0304373b74a44d2b584a3fb70ca9e56";
+
+/// 凭证未返回 resource_url 时使用的默认 API 地址
+pub const QWEN_DEFAULT_BASE_URL: &str = "https://dashscope.aliyuncs.com/compatible-mode/v1";
+
+const QWEN_USER_AGENT: &str = "QwenCode/0.0.14 (linux; x64)";
+
+// OAuth Client ID - 优先从环境变量读取，否则使用 qwen-code 默认值
+fn get_oauth_client_id() -> String {
+    std::env::var("QWEN_OAUTH_CLIENT_ID")
+        .unwrap_or_else(|_| DEFAULT_QWEN_OAUTH_CLIENT_ID.to_string())
+}
+
+/// 默认模型列表
+pub const QWEN_MODELS: &[&str] = &["qwen3-coder-plus", "qwen3-coder-flash", "vision-model"];
+
+/// Qwen OAuth 凭证存储
+///
+/// 与 qwen-code CLI 的 `oauth_creds.json` 格式兼容
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct QwenCredentials {
+    /// 访问令牌
+    pub access_token: Option<String>,
+    /// 刷新令牌
+    pub refresh_token: Option<String>,
+    /// 令牌类型
+    pub token_type: Option<String>,
+    /// API 资源地址（如 `portal.qwen.ai`）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_url: Option<String>,
+    /// 过期时间戳（毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry_date: Option<i64>,
+    /// 最后刷新时间（RFC3339 格式）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_refresh: Option<String>,
+}
+
+pub struct QwenProvider {
+    pub credentials: QwenCredentials,
+    pub client: Client,
+    /// 凭证文件路径（刷新后写回原文件）
+    pub creds_path: Option<PathBuf>,
+}
+
+impl Default for QwenProvider {
+    fn default() -> Self {
+        Self {
+            credentials: QwenCredentials::default(),
+            client: HttpClientService::provider_client("qwen"),
+            creds_path: None,
+        }
+    }
+}
+
+impl QwenProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn default_creds_path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(CREDENTIALS_DIR)
+            .join(CREDENTIALS_FILE)
+    }
+
+    pub async fn load_credentials_from_path(
+        &mut self,
+        path: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let content = tokio::fs::read_to_string(path).await?;
+        let creds: QwenCredentials = serde_json::from_str(&content)?;
+        self.credentials = creds;
+        self.creds_path = Some(PathBuf::from(path));
+        Ok(())
+    }
+
+    pub async fn save_credentials(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let path = self
+            .creds_path
+            .clone()
+            .unwrap_or_else(Self::default_creds_path);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(&self.credentials)?;
+        tokio::fs::write(&path, content).await?;
+        Ok(())
+    }
+
+    /// 检查 Token 是否有效（有效期需超过 5 分钟）
+    pub fn is_token_valid(&self) -> bool {
+        if self.credentials.access_token.is_none() {
+            return false;
+        }
+
+        if let Some(expiry) = self.credentials.expiry_date {
+            let now = chrono::Utc::now().timestamp_millis();
+            return expiry > now + 300_000;
+        }
+
+        true
+    }
+
+    /// 获取 API Base URL
+    ///
+    /// `resource_url` 可能不带协议和版本号（如 `portal.qwen.ai`），统一补全为
+    /// `https://portal.qwen.ai/v1`
+    pub fn get_base_url(&self) -> String {
+        let resource = match self.credentials.resource_url.as_deref() {
+            Some(url) if !url.trim().is_empty() => url.trim(),
+            _ => return QWEN_DEFAULT_BASE_URL.to_string(),
+        };
+
+        let with_scheme = if resource.starts_with("http://") || resource.starts_with("https://") {
+            resource.to_string()
+        } else {
+            format!("https://{resource}")
+        };
+        let base = with_scheme.trim_end_matches('/');
+
+        if base.ends_with("/v1") {
+            base.to_string()
+        } else {
+            format!("{base}/v1")
+        }
+    }
+
+    /// 刷新 Token
+    pub async fn refresh_token(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let refresh_token = self
+            .credentials
+            .refresh_token
+            .clone()
+            .ok_or_else(|| create_config_error("没有可用的 refresh_token"))?;
+
+        let client_id = get_oauth_client_id();
+
+        tracing::info!("[QWEN] 正在刷新 Token");
+
+        let params = [
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token.as_str()),
+            ("client_id", client_id.as_str()),
+        ];
+
+        let resp = self
+            .client
+            .post(QWEN_TOKEN_URL)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Accept", "application/json")
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| Box::new(ProviderError::from(e)) as Box<dyn Error + Send + Sync>)?;
+
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let body = resp.text().await.unwrap_or_default();
+            tracing::error!("[QWEN] Token 刷新失败: {} - {}", status, body);
+            return Err(create_token_refresh_error(status, &body, "QWEN"));
+        }
+
+        let data: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| Box::new(ProviderError::from(e)) as Box<dyn Error + Send + Sync>)?;
+
+        let new_token = data["access_token"]
+            .as_str()
+            .ok_or_else(|| create_auth_error("响应中没有 access_token"))?;
+
+        self.credentials.access_token = Some(new_token.to_string());
+
+        // refresh_token 可能被轮换
+        if let Some(new_refresh) = data["refresh_token"].as_str() {
+            self.credentials.refresh_token = Some(new_refresh.to_string());
+        }
+        if let Some(token_type) = data["token_type"].as_str() {
+            self.credentials.token_type = Some(token_type.to_string());
+        }
+        if let Some(resource_url) = data["resource_url"].as_str() {
+            self.credentials.resource_url = Some(resource_url.to_string());
+        }
+        if let Some(expires_in) = data["expires_in"].as_i64() {
+            let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expires_in);
+            self.credentials.expiry_date = Some(expires_at.timestamp_millis());
+        }
+
+        self.credentials.last_refresh = Some(chrono::Utc::now().to_rfc3339());
+
+        // 保存刷新后的凭证
+        self.save_credentials().await?;
+
+        tracing::info!("[QWEN] Token 刷新成功");
+        Ok(new_token.to_string())
+    }
+
+    /// 带重试机制的 Token 刷新
+    ///
+    /// 最多重试 `max_retries` 次，使用指数退避策略
+    pub async fn refresh_token_with_retry(
+        &mut self,
+        max_retries: u32,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut last_error = None;
+
+        for attempt in 0..max_retries {
+            if attempt > 0 {
+                let delay = std::time::Duration::from_secs(1 << attempt);
+                tracing::info!("[QWEN] 第 {} 次重试，等待 {:?}", attempt + 1, delay);
+                tokio::time::sleep(delay).await;
+            }
+
+            match self.refresh_token().await {
+                Ok(token) => return Ok(token),
+                Err(e) => {
+                    tracing::warn!("[QWEN] Token 刷新第 {} 次尝试失败: {}", attempt + 1, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        tracing::error!("[QWEN] Token 刷新在 {} 次尝试后失败", max_retries);
+        Err(last_error.unwrap_or_else(|| create_auth_error("Token 刷新失败，请重新登录")))
+    }
+
+    /// 确保 Token 有效，必要时自动刷新
+    pub async fn ensure_valid_token(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        if !self.is_token_valid() {
+            tracing::info!("[QWEN] Token 需要刷新");
+            self.refresh_token_with_retry(3).await
+        } else {
+            self.credentials
+                .access_token
+                .clone()
+                .ok_or_else(|| "没有可用的 access_token".into())
+        }
+    }
+
+    /// 调用 Chat Completions API
+    ///
+    /// 返回原始响应，由调用方根据状态码决定是否刷新 Token 后重试
+    pub async fn call_api(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let token = self
+            .credentials
+            .access_token
+            .as_ref()
+            .ok_or("No access token")?;
+
+        let url = format!("{}/chat/completions", self.get_base_url());
+        let accept = if request.stream {
+            "text/event-stream"
+        } else {
+            "application/json"
+        };
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Content-Type", "application/json")
+            .header("Accept", accept)
+            .header("User-Agent", QWEN_USER_AGENT)
+            .header("X-DashScope-UserAgent", QWEN_USER_AGENT)
+            .header("X-DashScope-AuthType", "qwen-oauth")
+            .header("X-DashScope-CacheControl", "enable")
+            .headers(injected_headers())
+            .json(request)
+            .send()
+            .await?;
+
+        Ok(resp)
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for QwenProvider {
+    async fn load_credentials_from_path(&mut self, path: &str) -> ProviderResult<()> {
+        QwenProvider::load_credentials_from_path(self, path).await
+    }
+
+    async fn save_credentials(&self) -> ProviderResult<()> {
+        QwenProvider::save_credentials(self).await
+    }
+
+    fn is_token_valid(&self) -> bool {
+        QwenProvider::is_token_valid(self)
+    }
+
+    fn is_token_expiring_soon(&self) -> bool {
+        if self.credentials.access_token.is_none() {
+            return true;
+        }
+
+        if let Some(expiry) = self.credentials.expiry_date {
+            let now = chrono::Utc::now().timestamp_millis();
+            return expiry <= now + 600_000; // 10 分钟
+        }
+
+        false
+    }
+
+    async fn refresh_token(&mut self) -> ProviderResult<String> {
+        QwenProvider::refresh_token(self).await
+    }
+
+    fn get_access_token(&self) -> Option<&str> {
+        self.credentials.access_token.as_deref()
+    }
+
+    fn provider_type(&self) -> &'static str {
+        "qwen"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider_with_resource(resource_url: Option<&str>) -> QwenProvider {
+        let mut provider = QwenProvider::new();
+        provider.credentials.resource_url = resource_url.map(|s| s.to_string());
+        provider
+    }
+
+    #[test]
+    fn test_base_url_defaults_to_dashscope() {
+        assert_eq!(
+            provider_with_resource(None).get_base_url(),
+            QWEN_DEFAULT_BASE_URL
+        );
+        assert_eq!(
+            provider_with_resource(Some("  ")).get_base_url(),
+            QWEN_DEFAULT_BASE_URL
+        );
+    }
+
+    #[test]
+    fn test_base_url_from_resource_url() {
+        assert_eq!(
+            provider_with_resource(Some("portal.qwen.ai")).get_base_url(),
+            "https://portal.qwen.ai/v1"
+        );
+        assert_eq!(
+            provider_with_resource(Some("https://portal.qwen.ai/v1/")).get_base_url(),
+            "https://portal.qwen.ai/v1"
+        );
+    }
+
+    #[test]
+    fn test_token_validity() {
+        let mut provider = QwenProvider::new();
+        assert!(!provider.is_token_valid());
+
+        provider.credentials.access_token = Some("token".to_string());
+        provider.credentials.expiry_date =
+            Some(chrono::Utc::now().timestamp_millis() + 60 * 60 * 1000);
+        assert!(provider.is_token_valid());
+
+        provider.credentials.expiry_date = Some(chrono::Utc::now().timestamp_millis() + 60_000);
+        assert!(!provider.is_token_valid());
+    }
+
+    #[test]
+    fn test_credentials_file_compat() {
+        let json = r#"{
+            "access_token": "at",
+            "refresh_token": "rt",
+            "token_type": "Bearer",
+            "resource_url": "portal.qwen.ai",
+            "expiry_date": 1760000000000
+        }"#;
+        let creds: QwenCredentials = serde_json::from_str(json).unwrap();
+        assert_eq!(creds.access_token.as_deref(), Some("at"));
+        assert_eq!(creds.resource_url.as_deref(), Some("portal.qwen.ai"));
+        assert_eq!(creds.expiry_date, Some(1760000000000));
+    }
+}
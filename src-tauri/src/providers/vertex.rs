@@ -6,6 +6,7 @@
 #![allow(dead_code)]
 
 use crate::config::VertexApiKeyEntry;
+use crate::injection::injected_headers;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -191,6 +192,7 @@ impl VertexProvider {
             .post(&url)
             .header("x-goog-api-key", api_key)
             .header("Content-Type", "application/json")
+            .headers(injected_headers())
             .json(&request)
             .send()
             .await?;
@@ -230,6 +232,7 @@ impl VertexProvider {
             .post(&url)
             .header("x-goog-api-key", api_key)
             .header("Content-Type", "application/json")
+            .headers(injected_headers())
             .json(&request)
             .send()
             .await?;
@@ -1,7 +1,11 @@
 //! 模型映射器
 //!
-//! 提供模型别名映射和解析功能
+//! 提供模型别名映射和解析功能。解析顺序：
+//! 1. 精确别名（`routing.model_aliases`）
+//! 2. 通配符 / 正则规则（`routing.alias_rules`），按优先级从高到低，相同优先级按配置顺序
 
+use crate::config::{AliasMatchType, ModelAliasRule, RoutingConfig};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -16,11 +20,76 @@ pub struct ModelInfo {
     pub actual_model: Option<String>,
 }
 
+/// 当前生效的别名条目（按匹配顺序）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AliasEntry {
+    /// 别名或匹配模式
+    pub pattern: String,
+    /// 目标模型
+    pub target: String,
+    /// 匹配方式：`exact` / `glob` / `regex`
+    pub match_type: String,
+    /// 优先级（精确别名为 `None`，总是最先匹配）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+}
+
+/// 编译后的别名规则
+#[derive(Debug, Clone)]
+struct CompiledAliasRule {
+    rule: ModelAliasRule,
+    matcher: AliasMatcher,
+}
+
+#[derive(Debug, Clone)]
+enum AliasMatcher {
+    Glob(glob::Pattern),
+    Regex(Regex),
+}
+
+impl CompiledAliasRule {
+    fn compile(rule: &ModelAliasRule) -> Result<Self, String> {
+        let matcher = match rule.match_type {
+            AliasMatchType::Glob => glob::Pattern::new(&rule.pattern)
+                .map(AliasMatcher::Glob)
+                .map_err(|e| format!("无效的别名通配符 '{}': {}", rule.pattern, e))?,
+            AliasMatchType::Regex => Regex::new(&format!("^(?:{})$", rule.pattern))
+                .map(AliasMatcher::Regex)
+                .map_err(|e| format!("无效的别名正则 '{}': {}", rule.pattern, e))?,
+        };
+        Ok(Self {
+            rule: rule.clone(),
+            matcher,
+        })
+    }
+
+    /// 匹配成功时返回目标模型（正则规则展开捕获组）
+    fn apply(&self, model: &str) -> Option<String> {
+        match &self.matcher {
+            AliasMatcher::Glob(pattern) => pattern.matches(model).then(|| self.rule.target.clone()),
+            AliasMatcher::Regex(regex) => regex.captures(model).map(|caps| {
+                let mut target = String::new();
+                caps.expand(&self.rule.target, &mut target);
+                target
+            }),
+        }
+    }
+}
+
+/// 校验别名规则能否编译
+pub fn validate_alias_rules(rules: &[ModelAliasRule]) -> Result<(), String> {
+    rules
+        .iter()
+        .try_for_each(|rule| CompiledAliasRule::compile(rule).map(|_| ()))
+}
+
 /// 模型映射器 - 管理模型别名映射
 #[derive(Debug, Clone, Default)]
 pub struct ModelMapper {
     /// 别名到实际模型的映射 (alias -> actual)
     aliases: HashMap<String, String>,
+    /// 通配符 / 正则规则（已按优先级排序）
+    rules: Vec<CompiledAliasRule>,
 }
 
 impl ModelMapper {
@@ -28,24 +97,87 @@ impl ModelMapper {
     pub fn new() -> Self {
         Self {
             aliases: HashMap::new(),
+            rules: Vec::new(),
         }
     }
 
     /// 从别名映射创建模型映射器
     pub fn from_aliases(aliases: HashMap<String, String>) -> Self {
-        Self { aliases }
+        Self {
+            aliases,
+            rules: Vec::new(),
+        }
+    }
+
+    /// 用路由配置替换全部别名和规则
+    ///
+    /// 无法编译的规则会被跳过，返回对应的错误信息
+    pub fn load_config(&mut self, routing: &RoutingConfig) -> Vec<String> {
+        self.clear();
+        for (alias, model) in &routing.model_aliases {
+            self.add_alias(alias, model);
+        }
+        routing
+            .alias_rules
+            .iter()
+            .filter_map(|rule| self.add_rule(rule).err())
+            .collect()
     }
 
     /// 解析模型名（别名 -> 实际名）
     ///
-    /// 如果模型名是别名，返回实际模型名；否则返回原模型名
+    /// 精确别名优先，其次按优先级匹配规则；均未命中时返回原模型名
     pub fn resolve(&self, model: &str) -> String {
-        self.aliases
-            .get(model)
-            .cloned()
+        if let Some(actual) = self.aliases.get(model) {
+            return actual.clone();
+        }
+        self.rules
+            .iter()
+            .find_map(|rule| rule.apply(model))
             .unwrap_or_else(|| model.to_string())
     }
 
+    /// 添加通配符 / 正则规则
+    pub fn add_rule(&mut self, rule: &ModelAliasRule) -> Result<(), String> {
+        let compiled = CompiledAliasRule::compile(rule)?;
+        // 稳定插入：排在所有优先级不低于它的规则之后
+        let index = self
+            .rules
+            .iter()
+            .position(|r| r.rule.priority < rule.priority)
+            .unwrap_or(self.rules.len());
+        self.rules.insert(index, compiled);
+        Ok(())
+    }
+
+    /// 获取所有规则（按匹配顺序）
+    pub fn rules(&self) -> Vec<ModelAliasRule> {
+        self.rules.iter().map(|r| r.rule.clone()).collect()
+    }
+
+    /// 当前生效的别名映射（按匹配顺序：精确别名按名称排序在前，规则在后）
+    pub fn effective_mapping(&self) -> Vec<AliasEntry> {
+        let mut exact: Vec<_> = self.aliases.iter().collect();
+        exact.sort();
+        let exact = exact.into_iter().map(|(alias, actual)| AliasEntry {
+            pattern: alias.clone(),
+            target: actual.clone(),
+            match_type: "exact".to_string(),
+            priority: None,
+        });
+        let rules = self.rules.iter().map(|r| AliasEntry {
+            pattern: r.rule.pattern.clone(),
+            target: r.rule.target.clone(),
+            match_type: match r.rule.match_type {
+                AliasMatchType::Glob => "glob",
+                AliasMatchType::Regex => "regex",
+            }
+            .to_string(),
+            priority: Some(r.rule.priority),
+        });
+        exact.chain(rules).collect()
+    }
+
     /// 添加别名映射
     pub fn add_alias(&mut self, alias: &str, actual: &str) {
         self.aliases.insert(alias.to_string(), actual.to_string());
@@ -71,14 +203,14 @@ impl ModelMapper {
         &self.aliases
     }
 
-    /// 获取别名数量
+    /// 获取别名数量（不含规则）
     pub fn len(&self) -> usize {
         self.aliases.len()
     }
 
-    /// 检查是否为空
+    /// 检查是否为空（别名和规则均为空）
     pub fn is_empty(&self) -> bool {
-        self.aliases.is_empty()
+        self.aliases.is_empty() && self.rules.is_empty()
     }
 
     /// 获取所有可用模型（包含别名）
@@ -108,9 +240,10 @@ impl ModelMapper {
         models
     }
 
-    /// 清空所有别名
+    /// 清空所有别名和规则
     pub fn clear(&mut self) {
         self.aliases.clear();
+        self.rules.clear();
     }
 }
 
@@ -179,4 +312,97 @@ mod mapper_tests {
             Some("claude-sonnet-4-5-20250514".to_string())
         );
     }
+
+    fn rule(
+        pattern: &str,
+        target: &str,
+        match_type: AliasMatchType,
+        priority: i32,
+    ) -> ModelAliasRule {
+        ModelAliasRule {
+            pattern: pattern.to_string(),
+            target: target.to_string(),
+            match_type,
+            priority,
+        }
+    }
+
+    #[test]
+    fn test_glob_and_regex_rules() {
+        let mut mapper = ModelMapper::new();
+        mapper.add_alias("gpt-4", "claude-opus-4");
+        mapper
+            .add_rule(&rule(
+                "gpt-4*",
+                "claude-sonnet-4-5",
+                AliasMatchType::Glob,
+                0,
+            ))
+            .unwrap();
+        mapper
+            .add_rule(&rule(
+                r"gemini-(\d\.\d)-.*",
+                "gemini-$1-pro",
+                AliasMatchType::Regex,
+                0,
+            ))
+            .unwrap();
+
+        // 精确别名优先于规则
+        assert_eq!(mapper.resolve("gpt-4"), "claude-opus-4");
+        assert_eq!(mapper.resolve("gpt-4o-mini"), "claude-sonnet-4-5");
+        assert_eq!(mapper.resolve("gemini-2.5-flash"), "gemini-2.5-pro");
+        // 正则为整串匹配
+        assert_eq!(mapper.resolve("my-gemini-2.5-flash"), "my-gemini-2.5-flash");
+        assert_eq!(mapper.resolve("claude-haiku"), "claude-haiku");
+    }
+
+    #[test]
+    fn test_rule_priority() {
+        let mut mapper = ModelMapper::new();
+        mapper
+            .add_rule(&rule("gpt-*", "low", AliasMatchType::Glob, 0))
+            .unwrap();
+        mapper
+            .add_rule(&rule("gpt-4*", "high", AliasMatchType::Glob, 10))
+            .unwrap();
+        mapper
+            .add_rule(&rule(
+                "gpt-4o*",
+                "same-priority-later",
+                AliasMatchType::Glob,
+                10,
+            ))
+            .unwrap();
+
+        assert_eq!(mapper.resolve("gpt-4o"), "high");
+        assert_eq!(mapper.resolve("gpt-3.5-turbo"), "low");
+
+        let order: Vec<_> = mapper
+            .effective_mapping()
+            .into_iter()
+            .map(|e| e.target)
+            .collect();
+        assert_eq!(order, vec!["high", "same-priority-later", "low"]);
+    }
+
+    #[test]
+    fn test_load_config_skips_invalid_rules() {
+        let routing = RoutingConfig {
+            model_aliases: HashMap::from([("a".to_string(), "b".to_string())]),
+            alias_rules: vec![
+                rule("(", "x", AliasMatchType::Regex, 0),
+                rule("c*", "d", AliasMatchType::Glob, 0),
+            ],
+            ..Default::default()
+        };
+        let mut mapper = ModelMapper::new();
+        let errors = mapper.load_config(&routing);
+
+        assert_eq!(errors.len(), 1);
+        assert!(validate_alias_rules(&routing.alias_rules).is_err());
+        assert_eq!(mapper.resolve("a"), "b");
+        assert_eq!(mapper.resolve("cat"), "d");
+        assert_eq!(mapper.effective_mapping().len(), 2);
+    }
 }
@@ -10,6 +10,7 @@
 //!
 //! 模型映射：
 //! - 支持模型别名映射（如 `gpt-4` -> `claude-sonnet-4-5-20250514`）
+//! - 支持通配符 / 正则别名规则（如 `gpt-4*` -> `claude-sonnet-4-5`），按优先级匹配
 
 mod amp_router;
 mod mapper;
@@ -18,7 +19,7 @@ mod route_registry;
 mod rules;
 
 pub use amp_router::{AmpRouteMatch, AmpRouter};
-pub use mapper::{ModelInfo, ModelMapper};
+pub use mapper::{validate_alias_rules, AliasEntry, ModelInfo, ModelMapper};
 pub use provider_router::ProviderRouter;
 pub use route_registry::{RegisteredRoute, RouteRegistry, RouteType};
 pub use rules::{RouteResult, Router};
@@ -0,0 +1,71 @@
+//! 监听地址暴露检查
+//!
+//! 监听非回环地址（`0.0.0.0`、局域网 IP 等）时代理可被其他机器访问：
+//! - 未设置 API Key（为空或仍为默认值）时拒绝启动
+//! - 未配置 `server.allowed_ips` 时启动但给出警告
+
+use crate::app::is_loopback_host;
+use crate::config::{ServerConfig, DEFAULT_API_KEY};
+
+/// 检查监听地址是否会在未受保护的情况下暴露代理
+///
+/// 拒绝启动时返回 `Err`，需要提示用户时返回 `Ok(Some(警告))`
+pub fn check_bind_exposure(server: &ServerConfig) -> Result<Option<String>, String> {
+    if is_loopback_host(&server.host) {
+        return Ok(None);
+    }
+    if server.api_key.trim().is_empty() || server.api_key == DEFAULT_API_KEY {
+        return Err(format!(
+            "监听地址 {} 可被其他机器访问，但未设置 API Key，已拒绝启动。请设置 server.api_key 或改为监听 127.0.0.1",
+            server.host
+        ));
+    }
+    if server.allowed_ips.is_empty() {
+        return Ok(Some(format!(
+            "监听地址 {} 可被其他机器访问且未配置 IP 白名单，建议通过 server.allowed_ips 限制可访问的客户端",
+            server.host
+        )));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(host: &str, api_key: &str, allowed_ips: &[&str]) -> ServerConfig {
+        ServerConfig {
+            host: host.to_string(),
+            api_key: api_key.to_string(),
+            allowed_ips: allowed_ips.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_loopback_is_always_allowed() {
+        assert_eq!(check_bind_exposure(&server("127.0.0.1", "", &[])), Ok(None));
+        assert_eq!(
+            check_bind_exposure(&server("localhost", "proxy_cast", &[])),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_public_bind_requires_api_key() {
+        assert!(check_bind_exposure(&server("0.0.0.0", "", &[])).is_err());
+        assert!(check_bind_exposure(&server("0.0.0.0", "proxy_cast", &[])).is_err());
+        assert!(check_bind_exposure(&server("192.168.1.10", "  ", &["192.168.1.0/24"])).is_err());
+    }
+
+    #[test]
+    fn test_public_bind_without_allowlist_warns() {
+        assert!(check_bind_exposure(&server("0.0.0.0", "pc_secret", &[]))
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            check_bind_exposure(&server("::", "pc_secret", &["10.0.0.0/8"])),
+            Ok(None)
+        );
+    }
+}
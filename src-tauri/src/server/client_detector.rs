@@ -1,11 +1,19 @@
 //! 客户端类型检测模块
 //!
-//! 通过解析 HTTP 请求的 User-Agent 头来识别客户端类型。
+//! 通过解析 HTTP 请求的 User-Agent 头来识别客户端类型，
+//! 并为遥测确定调用方工具名称（优先使用 `x-proxycast-client` 头）。
 
 #![allow(dead_code)]
 
+use axum::http::HeaderMap;
 use serde::{Deserialize, Serialize};
 
+/// 客户端自报工具名称的请求头
+pub const CLIENT_NAME_HEADER: &str = "x-proxycast-client";
+
+/// 工具名称最大长度
+const MAX_CLIENT_NAME_LEN: usize = 64;
+
 /// 客户端类型枚举
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -48,7 +56,10 @@ impl ClientType {
 
         if ua_lower.contains("cursor") {
             ClientType::Cursor
-        } else if ua_lower.contains("claude-code") || ua_lower.contains("claude_code") {
+        } else if ua_lower.contains("claude-code")
+            || ua_lower.contains("claude_code")
+            || ua_lower.contains("claude-cli")
+        {
             ClientType::ClaudeCode
         } else if ua_lower.contains("codex") {
             ClientType::Codex
@@ -78,6 +89,20 @@ impl ClientType {
         }
     }
 
+    /// 获取用于遥测归因的工具名称
+    ///
+    /// 未识别的客户端返回 None
+    pub fn client_name(&self) -> Option<&'static str> {
+        match self {
+            ClientType::Cursor => Some("cursor"),
+            ClientType::ClaudeCode => Some("claude-code"),
+            ClientType::Codex => Some("codex"),
+            ClientType::Windsurf => Some("windsurf"),
+            ClientType::Kiro => Some("kiro"),
+            ClientType::Other => None,
+        }
+    }
+
     /// 获取所有客户端类型
     ///
     /// 返回所有支持的客户端类型列表。
@@ -118,6 +143,41 @@ impl std::fmt::Display for ClientType {
     }
 }
 
+/// 确定请求的调用方工具名称
+///
+/// 优先使用 `x-proxycast-client` 头（转为小写，空白替换为 `-`，
+/// 只接受不超过 64 个字符的字母、数字和 `-`、`_`、`.`、`/`），
+/// 否则从 User-Agent 推断；都无法确定时返回 None。
+pub fn client_name_from_headers(headers: &HeaderMap) -> Option<String> {
+    let declared = headers
+        .get(CLIENT_NAME_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(normalize_client_name);
+    if declared.is_some() {
+        return declared;
+    }
+
+    let user_agent = headers.get("user-agent").and_then(|v| v.to_str().ok())?;
+    ClientType::from_user_agent(user_agent)
+        .client_name()
+        .map(str::to_string)
+}
+
+fn normalize_client_name(value: &str) -> Option<String> {
+    let name = value
+        .trim()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    let valid = !name.is_empty()
+        && name.len() <= MAX_CLIENT_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+    valid.then_some(name)
+}
+
 /// 根据客户端类型和端点配置选择 Provider
 ///
 /// **Validates: Requirements 1.3, 1.4, 3.4**
@@ -182,6 +242,10 @@ mod tests {
             ClientType::from_user_agent("CLAUDE_CODE"),
             ClientType::ClaudeCode
         );
+        assert_eq!(
+            ClientType::from_user_agent("claude-cli/1.0.83 (external, cli)"),
+            ClientType::ClaudeCode
+        );
     }
 
     #[test]
@@ -297,6 +361,61 @@ mod tests {
         let claude_code: ClientType = serde_json::from_str("\"claude_code\"").unwrap();
         assert_eq!(claude_code, ClientType::ClaudeCode);
     }
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(
+                axum::http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        headers
+    }
+
+    #[test]
+    fn test_client_name_from_header() {
+        let h = headers(&[
+            (CLIENT_NAME_HEADER, " Claude Code "),
+            ("user-agent", "Cursor/1.0"),
+        ]);
+        assert_eq!(
+            client_name_from_headers(&h),
+            Some("claude-code".to_string())
+        );
+
+        let h = headers(&[(CLIENT_NAME_HEADER, "my_tool/2.1")]);
+        assert_eq!(
+            client_name_from_headers(&h),
+            Some("my_tool/2.1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_client_name_invalid_header_falls_back_to_user_agent() {
+        let h = headers(&[
+            (CLIENT_NAME_HEADER, "bad<name>"),
+            ("user-agent", "Codex/1.0"),
+        ]);
+        assert_eq!(client_name_from_headers(&h), Some("codex".to_string()));
+
+        let long = "a".repeat(MAX_CLIENT_NAME_LEN + 1);
+        let h = headers(&[(CLIENT_NAME_HEADER, long.as_str())]);
+        assert_eq!(client_name_from_headers(&h), None);
+    }
+
+    #[test]
+    fn test_client_name_from_user_agent() {
+        let h = headers(&[("user-agent", "claude-cli/1.0.83 (external, cli)")]);
+        assert_eq!(
+            client_name_from_headers(&h),
+            Some("claude-code".to_string())
+        );
+
+        let h = headers(&[("user-agent", "python-requests/2.31")]);
+        assert_eq!(client_name_from_headers(&h), None);
+        assert_eq!(client_name_from_headers(&HeaderMap::new()), None);
+    }
 }
 
 // ============================================================================
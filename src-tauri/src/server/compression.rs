@@ -0,0 +1,146 @@
+//! HTTP 压缩
+//!
+//! 大上下文请求（如 Claude Code 的 `/compact`）可达数十 MB，客户端可以用
+//! `Content-Encoding: gzip / br / deflate` 压缩请求体；响应按 `Accept-Encoding` 压缩。
+//! SSE 流式响应不压缩，否则压缩器的缓冲会打断逐块推送；图片和音频本身已压缩，也跳过。
+//!
+//! WebSocket 握手响应没有响应体，不会被压缩。axum 使用的 tungstenite 不支持
+//! permessage-deflate 扩展，消息压缩改由 `proxycast.deflate` 子协议协商：握手时
+//! `Sec-WebSocket-Protocol` 包含该子协议的连接，达到 `min_size` 的消息以二进制帧发送
+//! 原始 DEFLATE（RFC 1951）压缩的 JSON，较小的消息仍为文本帧；客户端也可以用同样的
+//! 二进制帧发送消息。每条消息独立压缩，不共享压缩上下文。
+
+use std::io::{Read, Write};
+
+use crate::config::CompressionConfig;
+use axum::extract::ws::Message as WsMessage;
+use axum::Router;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::decompression::RequestDecompressionLayer;
+
+/// 响应压缩条件
+fn compress_predicate(min_size: u16) -> impl Predicate {
+    // 最小为 1 字节，避免压缩空响应体（如 WebSocket 握手的 101 响应）
+    SizeAbove::new(min_size.max(1))
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE)
+        .and(NotForContentType::const_new("audio/"))
+}
+
+/// 按配置为路由添加请求解压和响应压缩
+///
+/// 需在请求体大小限制之外调用，使限制作用于解压后的大小
+pub fn apply_compression<S>(router: Router<S>, config: &CompressionConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let router = if config.decompress_requests {
+        router.layer(RequestDecompressionLayer::new())
+    } else {
+        router
+    };
+    if config.enable {
+        router.layer(CompressionLayer::new().compress_when(compress_predicate(config.min_size)))
+    } else {
+        router
+    }
+}
+
+/// WebSocket 消息压缩的子协议名
+pub const WS_DEFLATE_PROTOCOL: &str = "proxycast.deflate";
+
+/// WebSocket 消息压缩（`proxycast.deflate` 子协议）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WsDeflate {
+    /// 消息达到该字节数时才压缩
+    min_size: usize,
+    /// 客户端消息解压后的最大字节数
+    max_size: usize,
+}
+
+impl WsDeflate {
+    pub fn new(min_size: usize, max_size: usize) -> Self {
+        Self { min_size, max_size }
+    }
+
+    /// 编码发送的 JSON 消息，达到阈值时压缩为二进制帧
+    pub fn encode(&self, text: String) -> WsMessage {
+        if text.len() < self.min_size.max(1) {
+            return WsMessage::Text(text);
+        }
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::fast());
+        match encoder
+            .write_all(text.as_bytes())
+            .and_then(|_| encoder.finish())
+        {
+            Ok(data) => WsMessage::Binary(data),
+            Err(e) => {
+                tracing::warn!("[WS] 消息压缩失败，按文本帧发送: {}", e);
+                WsMessage::Text(text)
+            }
+        }
+    }
+
+    /// 解压客户端发送的二进制帧，返回 JSON 文本
+    pub fn decode(&self, data: &[u8]) -> Result<String, String> {
+        let mut text = Vec::new();
+        DeflateDecoder::new(data)
+            .take(self.max_size as u64 + 1)
+            .read_to_end(&mut text)
+            .map_err(|e| format!("Failed to inflate message: {}", e))?;
+        if text.len() > self.max_size {
+            return Err(format!("Inflated message exceeds {} bytes", self.max_size));
+        }
+        String::from_utf8(text).map_err(|_| "Inflated message is not valid UTF-8".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Response};
+
+    fn response(content_type: &str, len: usize) -> Response<Body> {
+        Response::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(vec![b'a'; len]))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_compress_predicate() {
+        let predicate = compress_predicate(1024);
+        assert!(predicate.should_compress(&response("application/json", 4096)));
+        assert!(!predicate.should_compress(&response("application/json", 100)));
+        assert!(!predicate.should_compress(&response("text/event-stream", 4096)));
+        assert!(!predicate.should_compress(&response("audio/mpeg", 4096)));
+        assert!(!predicate.should_compress(&response("image/png", 4096)));
+
+        // 最小阈值为 0 时仍不压缩空响应体
+        assert!(!compress_predicate(0).should_compress(&response("application/json", 0)));
+    }
+
+    #[test]
+    fn test_ws_deflate_round_trip() {
+        let deflate = WsDeflate::new(64, 4096);
+        let small = r#"{"type":"pong"}"#.to_string();
+        assert_eq!(deflate.encode(small.clone()), WsMessage::Text(small));
+
+        let large = format!(r#"{{"type":"stream_chunk","data":"{}"}}"#, "a".repeat(1000));
+        let WsMessage::Binary(data) = deflate.encode(large.clone()) else {
+            panic!("large message should be compressed");
+        };
+        assert!(data.len() < large.len());
+        assert_eq!(deflate.decode(&data).unwrap(), large);
+
+        // 解压后超过大小限制或不是合法数据时拒绝
+        assert!(WsDeflate::new(64, 512).decode(&data).is_err());
+        assert!(deflate.decode(b"not deflate").is_err());
+
+    }
+}
@@ -0,0 +1,143 @@
+//! 长上下文降级
+//!
+//! 请求的预估 Token 数（输入 + `max_tokens`）超过目标模型的上下文窗口时，改用配置的长上下文模型。
+//! 上下文窗口优先取 `context_fallback.context_windows`，其次取模型注册表；两者都没有时不降级。
+
+use axum::http::HeaderValue;
+use axum::response::Response;
+
+use crate::config::ContextFallbackConfig;
+use crate::database::DbConnection;
+use crate::services::model_registry_service::ModelRegistryService;
+
+/// 注明模型降级的响应头，值形如 `<原模型> -> <降级模型>`
+pub const MODEL_FALLBACK_HEADER: &str = "x-proxycast-model-fallback";
+
+/// 一次长上下文降级
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextFallback {
+    /// 原请求的模型
+    pub from_model: String,
+    /// 降级后的长上下文模型
+    pub model: String,
+    /// 长上下文模型所在的 Provider，`None` 时沿用原 Provider
+    pub provider: Option<String>,
+    /// 预估 Token 数（输入 + `max_tokens`）
+    pub estimated_tokens: u32,
+    /// 原模型的上下文窗口
+    pub context_window: u32,
+}
+
+/// 获取模型的上下文窗口（Token）
+pub fn context_window(
+    config: &ContextFallbackConfig,
+    db: Option<&DbConnection>,
+    model: &str,
+) -> Option<u32> {
+    if let Some(window) = config.context_windows.get(model) {
+        return Some(*window);
+    }
+    let conn = db?.lock().ok()?;
+    ModelRegistryService::context_length_from_db(&conn, model)
+}
+
+/// 判断请求是否需要改用长上下文模型
+///
+/// `count_input` 只在启用降级且原模型的上下文窗口已知时调用，避免对每个请求分词。
+pub fn check(
+    config: &ContextFallbackConfig,
+    db: Option<&DbConnection>,
+    model: &str,
+    max_tokens: Option<u32>,
+    count_input: impl FnOnce() -> u32,
+) -> Option<ContextFallback> {
+    if !config.enabled {
+        return None;
+    }
+    let fallback_model = config.model.as_deref().filter(|m| *m != model)?;
+    let context_window = context_window(config, db, model)?;
+    let estimated_tokens = count_input().saturating_add(max_tokens.unwrap_or(0));
+    if estimated_tokens <= context_window {
+        return None;
+    }
+    Some(ContextFallback {
+        from_model: model.to_string(),
+        model: fallback_model.to_string(),
+        provider: config.provider.clone(),
+        estimated_tokens,
+        context_window,
+    })
+}
+
+/// 在响应头中注明模型降级
+pub fn annotate(mut response: Response, fallback: Option<&ContextFallback>) -> Response {
+    let Some(fallback) = fallback else {
+        return response;
+    };
+    let value = format!("{} -> {}", fallback.from_model, fallback.model);
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        response.headers_mut().insert(MODEL_FALLBACK_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+    use std::collections::HashMap;
+
+    fn config() -> ContextFallbackConfig {
+        ContextFallbackConfig {
+            enabled: true,
+            model: Some("gemini-2.5-pro".to_string()),
+            provider: Some("gemini".to_string()),
+            context_windows: HashMap::from([("gpt-4o".to_string(), 128_000)]),
+        }
+    }
+
+    #[test]
+    fn test_check_falls_back_when_exceeding_window() {
+        let config = config();
+        assert_eq!(check(&config, None, "gpt-4o", Some(4096), || 100_000), None);
+
+        let fallback = check(&config, None, "gpt-4o", Some(4096), || 125_000).unwrap();
+        assert_eq!(fallback.from_model, "gpt-4o");
+        assert_eq!(fallback.model, "gemini-2.5-pro");
+        assert_eq!(fallback.provider.as_deref(), Some("gemini"));
+        assert_eq!(fallback.estimated_tokens, 129_096);
+        assert_eq!(fallback.context_window, 128_000);
+    }
+
+    #[test]
+    fn test_check_skips_unknown_window_without_counting() {
+        let config = config();
+        let counted = std::cell::Cell::new(false);
+        let count = || {
+            counted.set(true);
+            u32::MAX
+        };
+        assert_eq!(check(&config, None, "unknown-model", None, count), None);
+        assert!(!counted.get());
+
+        let disabled = ContextFallbackConfig {
+            enabled: false,
+            ..config
+        };
+        assert_eq!(check(&disabled, None, "gpt-4o", None, || u32::MAX), None);
+    }
+
+    #[test]
+    fn test_annotate_sets_header() {
+        let fallback = check(&config(), None, "gpt-4o", None, || 200_000).unwrap();
+        let response = annotate("ok".into_response(), Some(&fallback));
+        assert_eq!(
+            response.headers()[MODEL_FALLBACK_HEADER],
+            "gpt-4o -> gemini-2.5-pro"
+        );
+        assert!(annotate("ok".into_response(), None)
+            .headers()
+            .get(MODEL_FALLBACK_HEADER)
+            .is_none());
+    }
+}
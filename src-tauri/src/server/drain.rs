@@ -0,0 +1,218 @@
+//! 优雅停机
+//!
+//! 跟踪进行中的 HTTP 请求，停机时通知 WebSocket 连接关闭，
+//! 并在超时前等待所有请求完成，进度写入 LogStore。
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use futures::StreamExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, RwLock};
+
+use crate::logger::LogStore;
+use crate::websocket::WsConnectionManager;
+
+/// 进度日志间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+/// 排空状态轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 进行中请求跟踪器
+pub struct DrainTracker {
+    in_flight: AtomicUsize,
+    shutdown_tx: watch::Sender<bool>,
+}
+
+impl Default for DrainTracker {
+    fn default() -> Self {
+        Self {
+            in_flight: AtomicUsize::new(0),
+            shutdown_tx: watch::channel(false).0,
+        }
+    }
+}
+
+impl DrainTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记一个进行中的请求，守卫释放时计数减一
+    pub fn begin(self: &Arc<Self>) -> DrainGuard {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        DrainGuard(self.clone())
+    }
+
+    /// 进行中的 HTTP 请求数
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// 标记进入停机状态并通知所有订阅者
+    pub fn signal_shutdown(&self) {
+        self.shutdown_tx.send_replace(true);
+    }
+
+    /// 是否已进入停机状态
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutdown_tx.borrow()
+    }
+
+    /// 订阅停机信号（WebSocket 连接据此主动关闭）
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.shutdown_tx.subscribe()
+    }
+}
+
+/// 进行中请求守卫
+pub struct DrainGuard(Arc<DrainTracker>);
+
+impl Drop for DrainGuard {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 请求计数中间件
+///
+/// 守卫持有到响应体（包括 SSE 流）发送完毕
+pub async fn track_in_flight(
+    State(tracker): State<Arc<DrainTracker>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let guard = tracker.begin();
+    let response = next.run(request).await;
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        let _held = &guard;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 等待进行中的 HTTP 请求和 WebSocket 连接结束
+///
+/// `server_done` 返回 HTTP 服务是否已退出（空闲连接全部关闭）。
+/// 全部排空返回 `true`，超时返回 `false`。
+pub async fn wait_for_drain(
+    tracker: &DrainTracker,
+    ws_manager: &WsConnectionManager,
+    logs: &RwLock<LogStore>,
+    timeout: Duration,
+    server_done: impl Fn() -> bool,
+) -> bool {
+    let started = Instant::now();
+    let mut last_progress: Option<Instant> = None;
+
+    loop {
+        let http = tracker.in_flight();
+        let ws = ws_manager.active_count();
+        if http == 0 && ws == 0 && server_done() {
+            logs.write().await.add(
+                "info",
+                &format!(
+                    "[SERVER] 所有请求已完成，停机耗时 {}ms",
+                    started.elapsed().as_millis()
+                ),
+            );
+            return true;
+        }
+
+        let elapsed = started.elapsed();
+        if elapsed >= timeout {
+            logs.write().await.add(
+                "warn",
+                &format!(
+                    "[SERVER] 等待超时（{}s），强制停机：{} 个 HTTP 请求、{} 个 WebSocket 连接未完成",
+                    timeout.as_secs(),
+                    http,
+                    ws
+                ),
+            );
+            return false;
+        }
+
+        if last_progress.map_or(true, |t| t.elapsed() >= PROGRESS_INTERVAL) {
+            last_progress = Some(Instant::now());
+            logs.write().await.add(
+                "info",
+                &format!(
+                    "[SERVER] 正在等待 {} 个 HTTP 请求、{} 个 WebSocket 连接完成（剩余 {}s）",
+                    http,
+                    ws,
+                    (timeout - elapsed).as_secs()
+                ),
+            );
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::WsConfig;
+
+    #[test]
+    fn test_guard_tracks_in_flight() {
+        let tracker = Arc::new(DrainTracker::new());
+        let first = tracker.begin();
+        let second = tracker.begin();
+        assert_eq!(tracker.in_flight(), 2);
+        drop(first);
+        assert_eq!(tracker.in_flight(), 1);
+        drop(second);
+        assert_eq!(tracker.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_signal_shutdown_notifies_subscribers() {
+        let tracker = DrainTracker::new();
+        let rx = tracker.subscribe();
+        assert!(!tracker.is_shutting_down());
+        tracker.signal_shutdown();
+        assert!(tracker.is_shutting_down());
+        assert!(*rx.borrow());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_drain() {
+        let tracker = Arc::new(DrainTracker::new());
+        let ws_manager = WsConnectionManager::new(WsConfig::default());
+        let logs = RwLock::new(LogStore::with_config(&crate::config::LoggingConfig {
+            enabled: false,
+            ..Default::default()
+        }));
+
+        let guard = tracker.begin();
+        assert!(
+            !wait_for_drain(
+                &tracker,
+                &ws_manager,
+                &logs,
+                Duration::from_millis(150),
+                || true
+            )
+            .await
+        );
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+        assert!(
+            wait_for_drain(&tracker, &ws_manager, &logs, Duration::from_secs(5), || {
+                true
+            })
+            .await
+        );
+    }
+}
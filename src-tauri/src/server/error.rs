@@ -0,0 +1,406 @@
+//! 统一的 API 错误类型
+//!
+//! 所有代理端点的错误都通过 `ProxyApiError` 返回，响应体结构一致：
+//!
+//! ```json
+//! {"error": {"message": "...", "type": "rate_limit_error", "code": "rate_limited",
+//!            "request_id": "...", "upstream_status": 429}}
+//! ```
+//!
+//! Anthropic 格式的端点额外带有顶层 `"type": "error"`。
+//! `request_id` 未显式设置时取自审计中间件分配的请求 ID。
+//! 上游的限流 / 过载错误会被归类为 `rate_limit_error` / `overloaded_error`，
+//! 上游给出重试等待时间时通过 `Retry-After` 响应头转发给客户端。
+
+use std::time::Duration;
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Map, Value};
+
+use crate::processor::{current_request_id, ProcessError};
+use crate::resilience::{UpstreamError, UpstreamErrorKind};
+
+/// Anthropic 的上游过载状态码（非标准）
+const OVERLOADED_STATUS: u16 = 529;
+
+/// 错误响应的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApiErrorFormat {
+    #[default]
+    OpenAI,
+    Anthropic,
+}
+
+/// 错误分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyErrorKind {
+    /// 请求参数错误
+    InvalidRequest,
+    /// 认证失败
+    Authentication,
+    /// 无权访问（作用域限制等）
+    Permission,
+    /// 资源不存在
+    NotFound,
+    /// 请求体过大
+    RequestTooLarge,
+    /// 请求过多（本地限流或上游限流）
+    RateLimit,
+    /// 上游过载
+    Overloaded,
+    /// 功能未实现
+    NotImplemented,
+    /// 上游返回错误
+    Upstream,
+    /// 暂无可用服务（如没有可用凭证）
+    Unavailable,
+    /// 上游超时
+    Timeout,
+    /// 内部错误
+    Internal,
+}
+
+impl ProxyErrorKind {
+    /// 默认 HTTP 状态码
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::InvalidRequest => StatusCode::BAD_REQUEST,
+            Self::Authentication => StatusCode::UNAUTHORIZED,
+            Self::Permission => StatusCode::FORBIDDEN,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::RequestTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::RateLimit => StatusCode::TOO_MANY_REQUESTS,
+            Self::Overloaded => {
+                StatusCode::from_u16(OVERLOADED_STATUS).unwrap_or(StatusCode::SERVICE_UNAVAILABLE)
+            }
+            Self::NotImplemented => StatusCode::NOT_IMPLEMENTED,
+            Self::Upstream => StatusCode::BAD_GATEWAY,
+            Self::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// `error.type` 字段
+    pub fn error_type(self) -> &'static str {
+        match self {
+            Self::InvalidRequest => "invalid_request_error",
+            Self::Authentication => "authentication_error",
+            Self::Permission => "permission_error",
+            Self::NotFound => "not_found_error",
+            Self::RequestTooLarge => "request_too_large",
+            Self::RateLimit => "rate_limit_error",
+            Self::Overloaded => "overloaded_error",
+            Self::NotImplemented => "not_implemented_error",
+            Self::Upstream => "upstream_error",
+            Self::Unavailable => "service_unavailable_error",
+            Self::Timeout => "timeout_error",
+            Self::Internal => "api_error",
+        }
+    }
+
+    /// 默认的 `error.code` 字段
+    pub fn default_code(self) -> &'static str {
+        match self {
+            Self::InvalidRequest => "invalid_request",
+            Self::Authentication => "invalid_api_key",
+            Self::Permission => "permission_denied",
+            Self::NotFound => "not_found",
+            Self::RequestTooLarge => "request_too_large",
+            Self::RateLimit => "rate_limited",
+            Self::Overloaded => "overloaded",
+            Self::NotImplemented => "not_implemented",
+            Self::Upstream => "upstream_error",
+            Self::Unavailable => "service_unavailable",
+            Self::Timeout => "timeout",
+            Self::Internal => "internal_error",
+        }
+    }
+
+    /// 根据 HTTP 状态码推断错误分类
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Authentication,
+            StatusCode::FORBIDDEN => Self::Permission,
+            StatusCode::NOT_FOUND => Self::NotFound,
+            StatusCode::PAYLOAD_TOO_LARGE => Self::RequestTooLarge,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimit,
+            s if s.as_u16() == OVERLOADED_STATUS => Self::Overloaded,
+            StatusCode::NOT_IMPLEMENTED => Self::NotImplemented,
+            StatusCode::BAD_GATEWAY => Self::Upstream,
+            StatusCode::SERVICE_UNAVAILABLE => Self::Unavailable,
+            StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => Self::Timeout,
+            s if s.is_client_error() => Self::InvalidRequest,
+            _ => Self::Internal,
+        }
+    }
+}
+
+/// 代理 API 错误
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProxyApiError {
+    pub kind: ProxyErrorKind,
+    pub status: StatusCode,
+    pub message: String,
+    pub code: Option<String>,
+    pub request_id: Option<String>,
+    pub upstream_status: Option<u16>,
+    /// 建议客户端的重试等待时间（`Retry-After` 响应头）
+    pub retry_after: Option<Duration>,
+    pub format: ApiErrorFormat,
+}
+
+impl ProxyApiError {
+    pub fn new(kind: ProxyErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            status: kind.status(),
+            message: message.into(),
+            code: None,
+            request_id: None,
+            upstream_status: None,
+            retry_after: None,
+            format: ApiErrorFormat::OpenAI,
+        }
+    }
+
+    /// 按状态码构造，分类由状态码推断，状态码原样返回
+    pub fn from_status(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            ..Self::new(ProxyErrorKind::from_status(status), message)
+        }
+    }
+
+    /// 上游返回的错误，透传状态码并记录 `upstream_status`
+    ///
+    /// 错误消息中的限流特征（如 `ThrottlingException`）会被归类为 429，
+    /// Google 风格响应体中的 `retryDelay` 会作为 `Retry-After` 返回。
+    pub fn upstream(status: u16, message: impl Into<String>) -> Self {
+        Self::from_upstream_error(UpstreamError::new(Some(status), message))
+    }
+
+    /// 从已分类的上游错误构造
+    pub fn from_upstream_error(error: UpstreamError) -> Self {
+        let upstream_status = error.status.unwrap_or(StatusCode::BAD_GATEWAY.as_u16());
+        let status_code = StatusCode::from_u16(upstream_status).unwrap_or(StatusCode::BAD_GATEWAY);
+        let (kind, status_code) = match error.kind {
+            UpstreamErrorKind::RateLimited => {
+                (ProxyErrorKind::RateLimit, StatusCode::TOO_MANY_REQUESTS)
+            }
+            UpstreamErrorKind::Overloaded => (ProxyErrorKind::Overloaded, status_code),
+            _ if status_code.is_server_error() => (ProxyErrorKind::Upstream, status_code),
+            _ => (ProxyErrorKind::from_status(status_code), status_code),
+        };
+        Self {
+            status: status_code,
+            upstream_status: error.status,
+            retry_after: error.retry_after,
+            ..Self::new(kind, error.message)
+        }
+    }
+
+    /// 上游返回的错误，额外从响应头读取 `Retry-After` / `retry-after-ms`
+    pub fn upstream_with_headers(
+        status: u16,
+        headers: &reqwest::header::HeaderMap,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::from_upstream_error(UpstreamError::from_response(status, headers, message))
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(ProxyErrorKind::InvalidRequest, message)
+    }
+
+    pub fn authentication(message: impl Into<String>) -> Self {
+        Self::new(ProxyErrorKind::Authentication, message)
+    }
+
+    pub fn permission(message: impl Into<String>) -> Self {
+        Self::new(ProxyErrorKind::Permission, message)
+    }
+
+    pub fn rate_limited(message: impl Into<String>) -> Self {
+        Self::new(ProxyErrorKind::RateLimit, message)
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(ProxyErrorKind::Unavailable, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ProxyErrorKind::Internal, message)
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    pub fn with_format(mut self, format: ApiErrorFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_retry_after(mut self, retry_after: Option<Duration>) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// 构造响应体
+    pub fn to_json(&self) -> Value {
+        let mut error = Map::new();
+        error.insert("message".to_string(), json!(self.message));
+        error.insert("type".to_string(), json!(self.kind.error_type()));
+        error.insert(
+            "code".to_string(),
+            json!(self.code.as_deref().unwrap_or(self.kind.default_code())),
+        );
+        if let Some(request_id) = self.request_id.clone().or_else(current_request_id) {
+            error.insert("request_id".to_string(), json!(request_id));
+        }
+        if let Some(upstream_status) = self.upstream_status {
+            error.insert("upstream_status".to_string(), json!(upstream_status));
+        }
+
+        match self.format {
+            ApiErrorFormat::OpenAI => json!({ "error": error }),
+            ApiErrorFormat::Anthropic => json!({ "type": "error", "error": error }),
+        }
+    }
+}
+
+impl std::fmt::Display for ProxyApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.status.as_u16())
+    }
+}
+
+impl std::error::Error for ProxyApiError {}
+
+impl IntoResponse for ProxyApiError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, Json(self.to_json())).into_response();
+        if let Some(retry_after) = self.retry_after {
+            // Retry-After 只支持整秒，向上取整
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            if let Ok(value) = HeaderValue::from_str(&seconds.to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+        }
+        response
+    }
+}
+
+impl From<ProcessError> for ProxyApiError {
+    fn from(error: ProcessError) -> Self {
+        let status =
+            StatusCode::from_u16(error.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        Self::from_status(status, error.to_string()).with_code(error.error_type())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_body_schema() {
+        let body = ProxyApiError::unavailable("no credentials")
+            .with_code("no_credentials")
+            .with_request_id("req-1")
+            .to_json();
+        assert_eq!(
+            body,
+            json!({"error": {
+                "message": "no credentials",
+                "type": "service_unavailable_error",
+                "code": "no_credentials",
+                "request_id": "req-1"
+            }})
+        );
+
+        let body = ProxyApiError::authentication("Invalid API key")
+            .with_format(ApiErrorFormat::Anthropic)
+            .to_json();
+        assert_eq!(body["type"], "error");
+        assert_eq!(body["error"]["type"], "authentication_error");
+        assert_eq!(body["error"]["code"], "invalid_api_key");
+        assert!(body["error"].get("request_id").is_none());
+    }
+
+    #[test]
+    fn test_upstream_error_keeps_status() {
+        let error = ProxyApiError::upstream(429, "slow down");
+        assert_eq!(error.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(error.kind, ProxyErrorKind::RateLimit);
+        assert_eq!(error.to_json()["error"]["upstream_status"], 429);
+
+        let error = ProxyApiError::upstream(500, "boom");
+        assert_eq!(error.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.kind, ProxyErrorKind::Upstream);
+    }
+
+    #[test]
+    fn test_upstream_rate_limit_classification() {
+        // AWS 以 400 返回限流
+        let error = ProxyApiError::upstream(
+            400,
+            r#"{"__type":"ThrottlingException","message":"Rate exceeded"}"#,
+        );
+        assert_eq!(error.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(error.kind, ProxyErrorKind::RateLimit);
+        assert_eq!(error.upstream_status, Some(400));
+
+        let error =
+            ProxyApiError::upstream(529, "Overloaded").with_format(ApiErrorFormat::Anthropic);
+        assert_eq!(error.status.as_u16(), 529);
+        assert_eq!(error.to_json()["error"]["type"], "overloaded_error");
+
+        let error = ProxyApiError::upstream(400, "prompt is too long");
+        assert_eq!(error.kind, ProxyErrorKind::InvalidRequest);
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_retry_after_header() {
+        let body = r#"{"error":{"code":429,"details":[{"retryDelay":"2.5s"}]}}"#;
+        let response = ProxyApiError::upstream(429, body).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "3");
+
+        let response = ProxyApiError::rate_limited("slow down").into_response();
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+    }
+
+    #[test]
+    fn test_from_status() {
+        let error = ProxyApiError::from_status(StatusCode::UNPROCESSABLE_ENTITY, "bad");
+        assert_eq!(error.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error.kind, ProxyErrorKind::InvalidRequest);
+
+        let error: ProxyApiError = ProcessError::Timeout { timeout_ms: 100 }.into();
+        assert_eq!(error.status, StatusCode::REQUEST_TIMEOUT);
+        assert_eq!(error.kind, ProxyErrorKind::Timeout);
+        assert_eq!(error.to_json()["error"]["code"], "timeout_error");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_from_scope() {
+        let body = crate::processor::with_request_id("req-scoped".to_string(), async {
+            ProxyApiError::internal("oops").to_json()
+        })
+        .await;
+        assert_eq!(body["error"]["request_id"], "req-scoped");
+    }
+}
@@ -17,7 +17,7 @@
 
 use axum::{
     body::Body,
-    extract::State,
+    extract::{Query, State},
     http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
@@ -26,7 +26,9 @@ use chrono::Utc;
 use serde_json::json;
 use std::collections::HashMap;
 
+use crate::config::SelectorEndpointConfig;
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use crate::database::dao::client_api_keys::ClientApiKey;
 use crate::flow_monitor::{
     ClientInfo, FlowError, FlowErrorType, FlowMetadata, FlowType, InterceptAction, InterceptType,
     LLMFlow, LLMRequest, LLMResponse, Message, MessageContent, MessageRole, RequestParameters,
@@ -36,15 +38,27 @@ use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
 use crate::processor::RequestContext;
 use crate::server::client_detector::ClientType;
+use crate::server::context_fallback::{self, ContextFallback};
+pub use crate::server::error::ApiErrorFormat;
+use crate::server::error::ProxyApiError;
+use crate::server::token_count::{count_anthropic_input_tokens, count_openai_input_tokens};
+use crate::server::token_usage::{
+    estimate_anthropic_input_tokens, estimate_openai_input_tokens, track_token_usage, UsageTracker,
+};
 use crate::server::{record_request_telemetry, record_token_usage, AppState};
 use crate::server_utils::{
-    build_anthropic_response, build_anthropic_stream_response, message_content_len,
-    parse_cw_response, safe_truncate,
+    build_anthropic_response, build_anthropic_stream_response, parse_cw_response, safe_truncate,
 };
+use crate::services::client_api_key_service::ClientKeyError;
 use crate::streaming::StreamFormat as StreamingFormat;
+use crate::telemetry::{CacheTokens, TokenSource};
+use crate::transform::PayloadFormat;
 use crate::ProviderType;
 
-use super::{call_provider_anthropic, call_provider_openai};
+use super::{
+    call_provider_anthropic_with_failover, call_provider_openai_with_failover,
+    with_client_anthropic_headers, CacheLookup, FailoverRoute,
+};
 
 // ============================================================================
 // Flow 捕获辅助函数
@@ -546,80 +560,420 @@ async fn check_response_intercept(
 // API Key 验证
 // ============================================================================
 
-/// OpenAI 格式的 API key 验证
-pub async fn verify_api_key(
+/// 从请求头提取 API key
+/// 检查请求是否超出模型上下文窗口，需要时记录改用的长上下文模型
+async fn check_context_fallback(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    model: &str,
+    max_tokens: Option<u32>,
+    count_input: impl FnOnce() -> u32,
+) -> Option<ContextFallback> {
+    let config = state.processor.context_fallback.read().await.clone();
+    let fallback =
+        context_fallback::check(&config, state.db.as_ref(), model, max_tokens, count_input)?;
+    ctx.set_context_fallback(fallback.model.clone());
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[CONTEXT_FALLBACK] request_id={} model={} -> {} estimated_tokens={} context_window={}",
+            ctx.request_id,
+            fallback.from_model,
+            fallback.model,
+            fallback.estimated_tokens,
+            fallback.context_window
+        ),
+    );
+    Some(fallback)
+}
+
+/// 提取 OpenAI 格式请求的会话标识（会话粘性路由）
+fn openai_session_key(
+    state: &AppState,
     headers: &HeaderMap,
-    expected_key: &str,
-) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    request: &ChatCompletionRequest,
+) -> Option<String> {
+    let first_user_message = request
+        .messages
+        .iter()
+        .find(|m| m.role == "user")
+        .map(|m| m.get_content_text());
+    state
+        .pool_service
+        .session_affinity()
+        .session_key(headers, first_user_message.as_deref())
+}
+
+/// 提取 Anthropic 格式请求的会话标识（会话粘性路由）
+///
+/// 只取首条用户消息的文本，`cache_control` 等标记在多轮对话中会移动，不参与计算。
+fn anthropic_session_key(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &AnthropicMessagesRequest,
+) -> Option<String> {
+    let first_user_message =
+        request
+            .messages
+            .iter()
+            .find(|m| m.role == "user")
+            .map(|m| match &m.content {
+                serde_json::Value::String(text) => text.clone(),
+                serde_json::Value::Array(blocks) => blocks
+                    .iter()
+                    .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(""),
+                _ => String::new(),
+            });
+    state
+        .pool_service
+        .session_affinity()
+        .session_key(headers, first_user_message.as_deref())
+}
+
+fn extract_api_key(headers: &HeaderMap, format: ApiErrorFormat) -> Option<&str> {
+    let (primary, secondary) = match format {
+        ApiErrorFormat::OpenAI => ("authorization", "x-api-key"),
+        ApiErrorFormat::Anthropic => ("x-api-key", "authorization"),
+    };
     let auth = headers
-        .get("authorization")
-        .or_else(|| headers.get("x-api-key"))
-        .and_then(|v| v.to_str().ok());
+        .get(primary)
+        .or_else(|| headers.get(secondary))
+        .and_then(|v| v.to_str().ok())?;
+    Some(auth.strip_prefix("Bearer ").unwrap_or(auth))
+}
 
-    let key = match auth {
-        Some(s) if s.starts_with("Bearer ") => &s[7..],
-        Some(s) => s,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({"error": {"message": "No API key provided"}})),
-            ))
-        }
+fn missing_api_key(format: ApiErrorFormat) -> ProxyApiError {
+    let message = match format {
+        ApiErrorFormat::OpenAI => "No API key provided",
+        ApiErrorFormat::Anthropic => "No API key provided. Please set the x-api-key header.",
     };
+    ProxyApiError::authentication(message)
+        .with_code("missing_api_key")
+        .with_format(format)
+}
 
-    if key != expected_key {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({"error": {"message": "Invalid API key"}})),
-        ));
+/// 校验请求头中的 API key
+async fn authenticate_api_key(
+    headers: &HeaderMap,
+    state: &AppState,
+    format: ApiErrorFormat,
+) -> Result<Option<ClientApiKey>, ProxyApiError> {
+    let Some(key) = extract_api_key(headers, format) else {
+        return Err(missing_api_key(format));
+    };
+    authenticate_key(key, state, format).await
+}
+
+/// 校验 API key
+///
+/// 先匹配服务器主 Key，再匹配客户端 Key。
+/// 返回 `Ok(None)` 表示主 Key（不受作用域限制），`Ok(Some(key))` 表示客户端 Key。
+pub(crate) async fn authenticate_key(
+    key: &str,
+    state: &AppState,
+    format: ApiErrorFormat,
+) -> Result<Option<ClientApiKey>, ProxyApiError> {
+    if key == state.api_key {
+        return Ok(None);
     }
 
-    Ok(())
+    let invalid = || ProxyApiError::authentication("Invalid API key").with_format(format);
+    let Some(db) = &state.db else {
+        return Err(invalid());
+    };
+
+    match state.client_keys.authenticate(db, key) {
+        Ok(Some(client_key)) => Ok(Some(client_key)),
+        Ok(None) => Err(invalid()),
+        Err(ClientKeyError::Disabled) => Err(ProxyApiError::authentication("API key is disabled")
+            .with_code("api_key_disabled")
+            .with_format(format)),
+        Err(e @ ClientKeyError::RateLimited { .. }) => {
+            state
+                .logs
+                .write()
+                .await
+                .add("warn", &format!("[CLIENT_KEY] {}", e));
+            Err(ProxyApiError::rate_limited(e.to_string()).with_format(format))
+        }
+        Err(e @ ClientKeyError::Storage(_)) => {
+            tracing::error!("[CLIENT_KEY] 认证失败: {}", e);
+            Err(ProxyApiError::internal("Failed to verify API key").with_format(format))
+        }
+    }
+}
+
+/// OpenAI 格式的 API key 验证
+pub async fn verify_api_key(
+    headers: &HeaderMap,
+    state: &AppState,
+) -> Result<Option<ClientApiKey>, ProxyApiError> {
+    authenticate_api_key(headers, state, ApiErrorFormat::OpenAI).await
 }
 
 /// Anthropic 格式的 API key 验证
 pub async fn verify_api_key_anthropic(
     headers: &HeaderMap,
-    expected_key: &str,
-) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
-    let auth = headers
-        .get("x-api-key")
-        .or_else(|| headers.get("authorization"))
-        .and_then(|v| v.to_str().ok());
+    state: &AppState,
+) -> Result<Option<ClientApiKey>, ProxyApiError> {
+    authenticate_api_key(headers, state, ApiErrorFormat::Anthropic).await
+}
 
-    let key = match auth {
-        Some(s) if s.starts_with("Bearer ") => &s[7..],
-        Some(s) => s,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({
-                    "type": "error",
-                    "error": {
-                        "type": "authentication_error",
-                        "message": "No API key provided. Please set the x-api-key header."
-                    }
-                })),
+/// 选择器端点的 API key 验证
+///
+/// 端点配置了专用 Key 时只接受该 Key（返回 `Ok(None)`，不受客户端 Key 作用域限制），
+/// 否则按全局规则认证。认证通过后计入端点的限流窗口。
+pub async fn verify_selector_api_key(
+    headers: &HeaderMap,
+    state: &AppState,
+    selector: &str,
+    endpoint: Option<&SelectorEndpointConfig>,
+    format: ApiErrorFormat,
+) -> Result<Option<ClientApiKey>, ProxyApiError> {
+    let client_key = match endpoint.and_then(|e| e.api_key.as_deref()) {
+        Some(expected) => match extract_api_key(headers, format) {
+            Some(key) if key == expected => None,
+            Some(_) => {
+                return Err(ProxyApiError::authentication("Invalid API key").with_format(format))
+            }
+            None => return Err(missing_api_key(format)),
+        },
+        None => authenticate_api_key(headers, state, format).await?,
+    };
+
+    if let Some(limit) = endpoint.and_then(|e| e.rate_limit_per_minute) {
+        if let Err(e) = state.client_keys.check_selector_rate_limit(selector, limit) {
+            state
+                .logs
+                .write()
+                .await
+                .add("warn", &format!("[SELECTOR] /{}: {}", selector, e));
+            return Err(ProxyApiError::rate_limited(e.to_string()).with_format(format));
+        }
+    }
+    Ok(client_key)
+}
+
+/// 校验客户端 Key 的模型和 Provider 作用域
+///
+/// 主 Key（`client_key` 为 `None`）不受限制。
+pub fn verify_client_scope(
+    client_key: Option<&ClientApiKey>,
+    model: Option<&str>,
+    provider: Option<&str>,
+    format: ApiErrorFormat,
+) -> Result<(), ProxyApiError> {
+    let Some(key) = client_key else {
+        return Ok(());
+    };
+
+    if let Some(model) = model.filter(|m| !key.allows_model(m)) {
+        return Err(ProxyApiError::permission(format!(
+            "API key '{}' is not allowed to use model '{}'",
+            key.name, model
+        ))
+        .with_code("model_not_allowed")
+        .with_format(format));
+    }
+    if let Some(provider) = provider.filter(|p| !key.allows_provider(p)) {
+        return Err(ProxyApiError::permission(format!(
+            "API key '{}' is not allowed to use provider '{}'",
+            key.name, provider
+        ))
+        .with_code("provider_not_allowed")
+        .with_format(format));
+    }
+    Ok(())
+}
+
+/// `/v1/models` 查询参数
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ListModelsQuery {
+    /// 忽略缓存，重新从上游获取
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+/// 获取可用模型列表
+///
+/// 汇总所有启用凭证的模型；携带客户端 Key 时只返回其作用域内的模型。
+/// 数据库不可用或没有任何凭证时返回内置的静态列表。
+pub async fn list_models(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListModelsQuery>,
+) -> Response {
+    // 该端点不强制认证，仅用于按客户端 Key 作用域过滤
+    let client_key = verify_api_key(&headers, &state).await.ok().flatten();
+
+    let Some(db) = &state.db else {
+        return crate::server_utils::models().await.into_response();
+    };
+    if query.refresh {
+        state.model_discovery.invalidate();
+    }
+
+    let models = match state.model_discovery.list_models(db).await {
+        Ok(models) if !models.is_empty() => models,
+        Ok(_) => return crate::server_utils::models().await.into_response(),
+        Err(e) => {
+            tracing::warn!("[MODELS] 获取模型列表失败: {}", e);
+            return crate::server_utils::models().await.into_response();
+        }
+    };
+
+    let data: Vec<_> = models
+        .into_iter()
+        .filter(|m| {
+            client_key.as_ref().is_none_or(|key| {
+                key.allows_model(&m.id) && m.providers.iter().any(|p| key.allows_provider(p))
+            })
+        })
+        .collect();
+
+    Json(json!({
+        "object": "list",
+        "data": data
+    }))
+    .into_response()
+}
+
+/// `/v1/aliases` 查询参数
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ListAliasesQuery {
+    /// 同时返回该模型名的解析结果
+    pub model: Option<String>,
+}
+
+/// 列出当前生效的模型别名映射
+///
+/// GET /v1/aliases（仅主 API Key 可访问）
+pub async fn list_aliases(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListAliasesQuery>,
+) -> Response {
+    match verify_api_key(&headers, &state).await {
+        Ok(None) => {}
+        Ok(Some(key)) => {
+            return ProxyApiError::permission(format!(
+                "API key '{}' is not allowed to access admin endpoints",
+                key.name
+            ))
+            .into_response();
+        }
+        Err(e) => return e.into_response(),
+    }
+
+    let mapper = state.processor.mapper.read().await;
+    let mut body = json!({
+        "object": "list",
+        "data": mapper.effective_mapping()
+    });
+    if let Some(model) = query.model {
+        body["resolved"] = json!({
+            "model": model,
+            "target": mapper.resolve(&model)
+        });
+    }
+    Json(body).into_response()
+}
+
+/// `/v1/costs` 查询参数
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ListCostsQuery {
+    /// 统计最近 N 天（不传则统计全部保留的记录）
+    pub days: Option<i64>,
+    /// 统计共享存储中所有实例的用量（需配置共享存储）
+    #[serde(default)]
+    pub shared: bool,
+}
+
+/// 按单价表估算费用，按 Provider / 凭证 / 模型 / 天汇总
+///
+/// GET /v1/costs（仅主 API Key 可访问）
+pub async fn list_costs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListCostsQuery>,
+) -> Response {
+    match verify_api_key(&headers, &state).await {
+        Ok(None) => {}
+        Ok(Some(key)) => {
+            return ProxyApiError::permission(format!(
+                "API key '{}' is not allowed to access admin endpoints",
+                key.name
+            ))
+            .into_response();
+        }
+        Err(e) => return e.into_response(),
+    }
+
+    let (start, end) = match query.days {
+        Some(days) if days > 0 => {
+            // Token 记录最多保留 30 天，限制上限避免时间计算溢出
+            let range = crate::telemetry::TimeRange::last_days(days.min(366));
+            (Some(range.start), Some(range.end))
+        }
+        Some(days) => {
+            return ProxyApiError::invalid_request(format!(
+                "days must be a positive integer, got {}",
+                days
             ))
+            .into_response();
         }
+        None => (None, None),
     };
 
-    if key != expected_key {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({
-                "type": "error",
-                "error": {
-                    "type": "authentication_error",
-                    "message": "Invalid API key"
-                }
-            })),
-        ));
+    if query.shared {
+        let Some(shared) = state.shared_storage.backend() else {
+            return ProxyApiError::invalid_request("Shared storage is not configured")
+                .into_response();
+        };
+        // 不传 days 时统计共享库保留的全部记录
+        let since = start.unwrap_or_else(|| {
+            chrono::Utc::now()
+                - chrono::Duration::days(state.shared_storage.retention_days() as i64)
+        });
+        let records = match shared.token_usage_since(since).await {
+            Ok(records) => records,
+            Err(e) => return ProxyApiError::internal(e).into_response(),
+        };
+        let pricing = state.processor.pricing.read().await;
+        let report =
+            crate::telemetry::CostReport::from_records(&records, &pricing).with_period(start, end);
+        return Json(report).into_response();
     }
 
-    Ok(())
+    let pricing = state.processor.pricing.read().await;
+    let report = state
+        .processor
+        .tokens
+        .read()
+        .cost_report(&pricing, start, end);
+    Json(report).into_response()
 }
 
+/// POST /v1/chat/completions - OpenAI 兼容的聊天补全
+#[utoipa::path(
+    post,
+    path = "/v1/chat/completions",
+    tag = "openai",
+    request_body = ChatCompletionRequest,
+    responses(
+        (status = 200, description = "`stream: true` 时以 SSE 返回 `ChatCompletionChunk`", content(
+            (crate::models::openai::ChatCompletionResponse = "application/json"),
+            (crate::models::openai::ChatCompletionChunk = "text/event-stream"),
+        )),
+        (status = 400, description = "请求参数错误", body = crate::server::openapi::ApiErrorBody),
+        (status = 401, description = "API Key 无效", body = crate::server::openapi::ApiErrorBody),
+        (status = 429, description = "触发限流", body = crate::server::openapi::ApiErrorBody),
+        (status = 503, description = "没有可用凭证", body = crate::server::openapi::ApiErrorBody),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn chat_completions(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -632,19 +986,25 @@ pub async fn chat_completions(
     eprintln!("[CHAT_COMPLETIONS] 流式: {}", request.stream);
     eprintln!("[CHAT_COMPLETIONS] 消息数量: {}", request.messages.len());
 
-    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
-        eprintln!("[CHAT_COMPLETIONS] 认证失败!");
-        state
-            .logs
-            .write()
-            .await
-            .add("warn", "Unauthorized request to /v1/chat/completions");
-        return e.into_response();
-    }
+    let client_key = match verify_api_key(&headers, &state).await {
+        Ok(client_key) => client_key,
+        Err(e) => {
+            eprintln!("[CHAT_COMPLETIONS] 认证失败!");
+            state
+                .logs
+                .write()
+                .await
+                .add("warn", "Unauthorized request to /v1/chat/completions");
+            return e.into_response();
+        }
+    };
     eprintln!("[CHAT_COMPLETIONS] 认证成功");
 
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    if let Some(key) = &client_key {
+        ctx.set_client_key_id(key.id.clone());
+    }
     eprintln!("[CHAT_COMPLETIONS] 请求ID: {}", ctx.request_id);
 
     state.logs.write().await.add(
@@ -676,6 +1036,26 @@ pub async fn chat_completions(
         );
     }
 
+    // 请求超出模型上下文窗口时改用长上下文模型
+    let context_fallback =
+        check_context_fallback(&state, &mut ctx, &request.model, request.max_tokens, || {
+            count_openai_input_tokens(&request)
+        })
+        .await;
+    if let Some(fallback) = &context_fallback {
+        request.model = fallback.model.clone();
+    }
+
+    // 校验客户端 Key 的模型作用域
+    if let Err(e) = verify_client_scope(
+        client_key.as_ref(),
+        Some(&request.model),
+        None,
+        ApiErrorFormat::OpenAI,
+    ) {
+        return e.into_response();
+    }
+
     // 应用参数注入
     let injection_enabled = *state.injection_enabled.read().await;
     if injection_enabled {
@@ -700,6 +1080,11 @@ pub async fn chat_completions(
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    // 长上下文模型配置了 Provider 时改用该 Provider
+    let selected_provider = context_fallback
+        .as_ref()
+        .and_then(|f| f.provider.clone())
+        .unwrap_or(selected_provider);
     eprintln!(
         "[CHAT_COMPLETIONS] 客户端类型: {}, 选择的Provider: {}",
         client_type, selected_provider
@@ -729,6 +1114,99 @@ pub async fn chat_completions(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_lowercase());
 
+    // 校验客户端 Key 的 Provider 作用域
+    if let Err(e) = verify_client_scope(
+        client_key.as_ref(),
+        None,
+        Some(provider_id_header.as_deref().unwrap_or(&selected_provider)),
+        ApiErrorFormat::OpenAI,
+    ) {
+        return e.into_response();
+    }
+
+    // 应用请求转换（在缓存查询之前，缓存键基于转换后的请求）
+    if state.processor.transformer.read().await.is_active() {
+        let mut payload = serde_json::to_value(&request).unwrap_or_default();
+        let result = state
+            .processor
+            .transform_request(
+                &mut ctx,
+                provider_id_header.as_deref().unwrap_or(&selected_provider),
+                PayloadFormat::OpenAI,
+                &mut payload,
+            )
+            .await;
+        if result.has_changes() {
+            let changes: Vec<String> = result.changes.iter().map(|c| c.to_string()).collect();
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[TRANSFORM] request_id={} changes=[{}]",
+                    ctx.request_id,
+                    changes.join("; ")
+                ),
+            );
+        }
+        if result.modified() {
+            if let Ok(updated) = serde_json::from_value(payload) {
+                request = updated;
+            }
+        }
+    }
+
+    // 注入 system prompt（需要已选定的 Provider；在请求转换之后执行，避免被改写规则覆盖）
+    if injection_enabled {
+        let mut payload = serde_json::to_value(&request).unwrap_or_default();
+        let result = state.processor.injector.read().await.inject_system_prompt(
+            &request.model,
+            provider_id_header.as_deref().unwrap_or(&selected_provider),
+            PayloadFormat::OpenAI,
+            &mut payload,
+        );
+        if result.has_injections() {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[INJECT] request_id={} applied_rules={:?} injected_params={:?}",
+                    ctx.request_id, result.applied_rules, result.injected_params
+                ),
+            );
+            if let Ok(updated) = serde_json::from_value(payload) {
+                request = updated;
+            }
+        }
+    }
+
+    // 查询响应缓存（仅非流式请求）
+    let cache = CacheLookup::new(
+        &state,
+        &headers,
+        "openai",
+        &format!(
+            "{}:{}",
+            provider_id_header.as_deref().unwrap_or(&selected_provider),
+            client_key
+                .as_ref()
+                .map(|k| k.id.as_str())
+                .unwrap_or_default()
+        ),
+        &serde_json::to_value(&request).unwrap_or_default(),
+        request.stream,
+    );
+    if let Some(cached) = cache.as_ref().and_then(|c| c.lookup(&state)) {
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[CACHE] request_id={} model={} hit",
+                ctx.request_id, request.model
+            ),
+        );
+        return context_fallback::annotate(cached, context_fallback.as_ref());
+    }
+
+    // 会话粘性路由：同一会话优先使用上次的凭证
+    let session_key = openai_session_key(&state, &headers, &request);
+
     // 尝试从凭证池中选择凭证（带客户端兼容性检查）
     // 如果指定了 X-Provider-Id，优先使用它（不降级）
     // 否则使用 selected_provider
@@ -743,11 +1221,13 @@ pub async fn chat_completions(
                 );
                 let cred = state
                     .pool_service
-                    .select_credential_with_client_check(
+                    .select_credential_for_session(
                         db,
                         explicit_provider_id,
                         Some(&request.model),
                         Some(&client_type),
+                        &[],
+                        session_key.as_deref(),
                     )
                     .ok()
                     .flatten();
@@ -765,17 +1245,15 @@ pub async fn chat_completions(
                         ),
                     );
                     // 返回错误，不降级
-                    return (
+                    return ProxyApiError::from_status(
                         StatusCode::SERVICE_UNAVAILABLE,
-                        Json(json!({
-                            "error": {
-                                "message": format!("No available credentials for provider '{}'", explicit_provider_id),
-                                "type": "provider_unavailable",
-                                "code": "no_credentials"
-                            }
-                        })),
+                        format!(
+                            "No available credentials for provider '{}'",
+                            explicit_provider_id
+                        ),
                     )
-                        .into_response();
+                    .with_code("no_credentials")
+                    .into_response();
                 }
                 cred
             } else {
@@ -786,11 +1264,13 @@ pub async fn chat_completions(
                 );
                 let cred = state
                     .pool_service
-                    .select_credential_with_client_check(
+                    .select_credential_for_session(
                         db,
                         &selected_provider,
                         Some(&request.model),
                         Some(&client_type),
+                        &[],
+                        session_key.as_deref(),
                     )
                     .ok()
                     .flatten();
@@ -1035,19 +1515,40 @@ pub async fn chat_completions(
                     // 请求被取消，标记 Flow 失败并返回错误
                     let error = FlowError::new(FlowErrorType::Cancelled, "请求被用户取消");
                     state.flow_monitor.fail_flow(fid, error).await;
-                    return (
+                    return ProxyApiError::from_status(
                         StatusCode::BAD_REQUEST,
-                        Json(
-                            serde_json::json!({"error": {"message": "Request cancelled by user"}}),
-                        ),
+                        "Request cancelled by user",
                     )
-                        .into_response();
+                    .into_response();
                 }
             }
         }
 
         eprintln!("[CHAT_COMPLETIONS] 调用 Provider: {}", cred.provider_type);
-        let response = call_provider_openai(&state, &cred, &request, flow_id.as_deref()).await;
+        let route = FailoverRoute {
+            provider: provider_id_header.as_deref().unwrap_or(&selected_provider),
+            // 限定了 Provider 的客户端 Key 不降级到其他 Provider
+            allow_fallback_providers: provider_id_header.is_none()
+                && client_key
+                    .as_ref()
+                    .is_none_or(|k| k.allowed_providers.is_empty()),
+            client_type: Some(&client_type),
+            session_key: session_key.as_deref(),
+        };
+        let response = call_provider_openai_with_failover(
+            &state,
+            &mut ctx,
+            cred,
+            route,
+            &request,
+            flow_id.as_deref(),
+        )
+        .await;
+        let response = match &cache {
+            Some(cache) => cache.store(&state, &request.model, response).await,
+            None => response,
+        };
+        let response = context_fallback::annotate(response, context_fallback.as_ref());
         eprintln!(
             "[CHAT_COMPLETIONS] Provider 响应状态: {}",
             response.status()
@@ -1056,11 +1557,7 @@ pub async fn chat_completions(
         // 记录请求统计
         let is_success = response.status().is_success();
         let status_code = response.status().as_u16();
-        let status = if is_success {
-            crate::telemetry::RequestStatus::Success
-        } else {
-            crate::telemetry::RequestStatus::Failed
-        };
+        let status = crate::telemetry::RequestStatus::from_http_status(status_code);
         record_request_telemetry(&state, &ctx, status, None);
 
         // 如果成功且需要 Flow 捕获，提取响应体内容和响应头
@@ -1086,11 +1583,11 @@ pub async fn chat_completions(
                         let error = FlowError::new(FlowErrorType::Network, &e.to_string());
                         state.flow_monitor.fail_flow(&fid, error).await;
                     }
-                    return (
+                    return ProxyApiError::from_status(
                         StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": format!("Failed to read response body: {}", e)}})),
+                        format!("Failed to read response body: {}", e),
                     )
-                        .into_response();
+                    .into_response();
                 }
             };
 
@@ -1135,18 +1632,25 @@ pub async fn chat_completions(
                 }
             }
 
-            let input_tokens = response_json["usage"]["prompt_tokens"]
-                .as_u64()
-                .unwrap_or(0) as u32;
-            let output_tokens = response_json["usage"]["completion_tokens"]
-                .as_u64()
-                .unwrap_or(0) as u32;
+            // 优先使用上游返回的 usage，缺失时估算
+            let mut usage_tracker = UsageTracker::new()
+                .with_source_hint(parts.extensions.get::<TokenSource>().copied());
+            usage_tracker.observe_json(&response_json);
+            let (input_tokens, output_tokens, token_source) =
+                usage_tracker.finish(estimate_openai_input_tokens(&request));
 
-            eprintln!("[CHAT_COMPLETIONS] 提取响应内容: content_len={}, input_tokens={}, output_tokens={}", 
-                content.len(), input_tokens, output_tokens);
+            eprintln!("[CHAT_COMPLETIONS] 提取响应内容: content_len={}, input_tokens={}, output_tokens={}, source={}", 
+                content.len(), input_tokens, output_tokens, token_source);
 
             // 记录 Token 使用量
-            record_token_usage(&state, &ctx, Some(input_tokens), Some(output_tokens));
+            record_token_usage(
+                &state,
+                &ctx,
+                Some(input_tokens),
+                Some(output_tokens),
+                usage_tracker.cache_tokens(),
+                token_source,
+            );
 
             // 完成 Flow 捕获并检查响应拦截
             // **Validates: Requirements 2.1, 2.5**
@@ -1225,28 +1729,6 @@ pub async fn chat_completions(
             return response;
         } else {
             // 流式响应或没有 Flow 捕获，直接返回
-            // 估算 Token 使用量（用于统计）
-            let estimated_input_tokens = request
-                .messages
-                .iter()
-                .map(|m| {
-                    let content_len = match &m.content {
-                        Some(c) => message_content_len(c),
-                        None => 0,
-                    };
-                    content_len / 4
-                })
-                .sum::<usize>() as u32;
-            let estimated_output_tokens = if is_success { 100u32 } else { 0u32 };
-
-            if is_success {
-                record_token_usage(
-                    &state,
-                    &ctx,
-                    Some(estimated_input_tokens),
-                    Some(estimated_output_tokens),
-                );
-            }
 
             // 如果失败，标记 Flow 失败
             if let Some(fid) = flow_id {
@@ -1260,7 +1742,14 @@ pub async fn chat_completions(
                 }
             }
 
-            return response;
+            // 在响应传输过程中解析上游 usage 并记录 Token 使用量
+            return track_token_usage(
+                &state,
+                &ctx,
+                response,
+                estimate_openai_input_tokens(&request),
+            )
+            .await;
         }
     }
 
@@ -1275,17 +1764,15 @@ pub async fn chat_completions(
                 selected_provider, client_type
             ),
         );
-        return (
+        return ProxyApiError::from_status(
             StatusCode::SERVICE_UNAVAILABLE,
-            Json(serde_json::json!({
-                "error": {
-                    "message": format!("没有找到可用的 '{}' 凭证。请在凭证池中添加对应的凭证。", selected_provider),
-                    "type": "no_credential_error",
-                    "code": "no_credential"
-                }
-            })),
+            format!(
+                "没有找到可用的 '{}' 凭证。请在凭证池中添加对应的凭证。",
+                selected_provider
+            ),
         )
-            .into_response();
+        .with_code("no_credentials")
+        .into_response();
     }
 
     state.logs.write().await.add(
@@ -1333,11 +1820,11 @@ pub async fn chat_completions(
                 // 请求被取消，标记 Flow 失败并返回错误
                 let error = FlowError::new(FlowErrorType::Cancelled, "请求被用户取消");
                 state.flow_monitor.fail_flow(fid, error).await;
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({"error": {"message": "Request cancelled by user"}})),
+                    "Request cancelled by user",
                 )
-                    .into_response();
+                .into_response();
             }
         }
     }
@@ -1363,10 +1850,11 @@ pub async fn chat_completions(
                     );
                     state.flow_monitor.fail_flow(fid, error).await;
                 }
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
-                ).into_response();
+                    format!("Token refresh failed: {e}"),
+                )
+                .into_response();
             }
         }
     }
@@ -1377,7 +1865,7 @@ pub async fn chat_completions(
         Ok(resp) => {
             let status = resp.status();
             if status.is_success() {
-                match resp.text().await {
+                match resp.bytes().await {
                     Ok(body) => {
                         let parsed = parse_cw_response(&body);
                         let has_tool_calls = !parsed.tool_calls.is_empty();
@@ -1414,21 +1902,16 @@ pub async fn chat_completions(
                             })
                         };
 
-                        // 估算 Token 数量（基于字符数，约 4 字符 = 1 token）
-                        let estimated_output_tokens = (parsed.content.len() / 4) as u32;
-                        // 估算输入 Token（基于请求消息）
-                        let estimated_input_tokens = request
-                            .messages
-                            .iter()
-                            .map(|m| {
-                                let content_len = match &m.content {
-                                    Some(c) => message_content_len(c),
-                                    None => 0,
-                                };
-                                content_len / 4
-                            })
-                            .sum::<usize>()
-                            as u32;
+                        // 优先使用上游返回的实际 Token 数，否则基于字符数估算（约 4 字符 = 1 token）
+                        let (estimated_input_tokens, estimated_output_tokens, token_source) =
+                            match parsed.token_usage() {
+                                (input, output, TokenSource::Actual) => {
+                                    (input, output, TokenSource::Actual)
+                                }
+                                (_, output, source) => {
+                                    (estimate_openai_input_tokens(&request), output, source)
+                                }
+                            };
 
                         let response = serde_json::json!({
                             "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
@@ -1462,6 +1945,8 @@ pub async fn chat_completions(
                             &ctx,
                             Some(estimated_input_tokens),
                             Some(estimated_output_tokens),
+                            CacheTokens::default(),
+                            token_source,
                         );
                         // 完成 Flow 捕获并检查响应拦截
                         // **Validates: Requirements 2.1, 2.5**
@@ -1543,10 +2028,7 @@ pub async fn chat_completions(
                             let error = FlowError::new(FlowErrorType::Network, &e.to_string());
                             state.flow_monitor.fail_flow(fid, error).await;
                         }
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
+                        ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                             .into_response()
                     }
                 }
@@ -1584,7 +2066,7 @@ pub async fn chat_completions(
                         match kiro.call_api(&request).await {
                             Ok(retry_resp) => {
                                 if retry_resp.status().is_success() {
-                                    match retry_resp.text().await {
+                                    match retry_resp.bytes().await {
                                         Ok(body) => {
                                             let parsed = parse_cw_response(&body);
                                             let has_tool_calls = !parsed.tool_calls.is_empty();
@@ -1716,10 +2198,11 @@ pub async fn chat_completions(
                                                 );
                                                 state.flow_monitor.fail_flow(fid, error).await;
                                             }
-                                            return (
-                                            StatusCode::INTERNAL_SERVER_ERROR,
-                                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                                        ).into_response();
+                                            return ProxyApiError::from_status(
+                                                StatusCode::INTERNAL_SERVER_ERROR,
+                                                e.to_string(),
+                                            )
+                                            .into_response();
                                         }
                                     }
                                 }
@@ -1732,10 +2215,11 @@ pub async fn chat_completions(
                                     );
                                     state.flow_monitor.fail_flow(fid, error).await;
                                 }
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": format!("Retry failed: {}", body)}})),
-                                ).into_response()
+                                    format!("Retry failed: {}", body),
+                                )
+                                .into_response()
                             }
                             Err(e) => {
                                 // 标记 Flow 失败
@@ -1744,11 +2228,11 @@ pub async fn chat_completions(
                                         FlowError::new(FlowErrorType::Network, &e.to_string());
                                     state.flow_monitor.fail_flow(fid, error).await;
                                 }
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                                    e.to_string(),
                                 )
-                                    .into_response()
+                                .into_response()
                             }
                         }
                     }
@@ -1766,11 +2250,11 @@ pub async fn chat_completions(
                             );
                             state.flow_monitor.fail_flow(fid, error).await;
                         }
-                        (
+                        ProxyApiError::from_status(
                             StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
+                            format!("Token refresh failed: {e}"),
                         )
-                            .into_response()
+                        .into_response()
                     }
                 }
             } else {
@@ -1786,10 +2270,8 @@ pub async fn chat_completions(
                             .with_status_code(status.as_u16());
                     state.flow_monitor.fail_flow(fid, error).await;
                 }
-                (
-                    StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                    Json(serde_json::json!({"error": {"message": format!("Upstream error: {}", body)}}))
-                ).into_response()
+                ProxyApiError::upstream(status.as_u16(), format!("Upstream error: {}", body))
+                    .into_response()
             }
         }
         Err(e) => {
@@ -1803,32 +2285,53 @@ pub async fn chat_completions(
                 let error = FlowError::new(FlowErrorType::Network, &e.to_string());
                 state.flow_monitor.fail_flow(fid, error).await;
             }
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": {"message": e.to_string()}})),
-            )
+            ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                 .into_response()
         }
     }
 }
 
+/// POST /v1/messages - Anthropic 兼容的 Messages API
+#[utoipa::path(
+    post,
+    path = "/v1/messages",
+    tag = "anthropic",
+    request_body = AnthropicMessagesRequest,
+    responses(
+        (status = 200, description = "`stream: true` 时以 SSE 返回 `AnthropicStreamEvent`", content(
+            (crate::models::anthropic::AnthropicMessagesResponse = "application/json"),
+            (crate::models::anthropic::AnthropicStreamEvent = "text/event-stream"),
+        )),
+        (status = 400, description = "请求参数错误", body = crate::server::openapi::ApiErrorBody),
+        (status = 401, description = "API Key 无效", body = crate::server::openapi::ApiErrorBody),
+        (status = 429, description = "触发限流", body = crate::server::openapi::ApiErrorBody),
+        (status = 503, description = "没有可用凭证", body = crate::server::openapi::ApiErrorBody),
+    ),
+    security(("x_api_key" = []), ("bearer" = []))
+)]
 pub async fn anthropic_messages(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(mut request): Json<AnthropicMessagesRequest>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证（优先检查 x-api-key）
-    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
-        state
-            .logs
-            .write()
-            .await
-            .add("warn", "Unauthorized request to /v1/messages");
-        return e.into_response();
-    }
+    let client_key = match verify_api_key_anthropic(&headers, &state).await {
+        Ok(client_key) => client_key,
+        Err(e) => {
+            state
+                .logs
+                .write()
+                .await
+                .add("warn", "Unauthorized request to /v1/messages");
+            return e.into_response();
+        }
+    };
 
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    if let Some(key) = &client_key {
+        ctx.set_client_key_id(key.id.clone());
+    }
 
     // 详细记录请求信息
     let msg_count = request.messages.len();
@@ -1858,6 +2361,26 @@ pub async fn anthropic_messages(
         );
     }
 
+    // 请求超出模型上下文窗口时改用长上下文模型
+    let context_fallback =
+        check_context_fallback(&state, &mut ctx, &request.model, request.max_tokens, || {
+            count_anthropic_input_tokens(&request)
+        })
+        .await;
+    if let Some(fallback) = &context_fallback {
+        request.model = fallback.model.clone();
+    }
+
+    // 校验客户端 Key 的模型作用域
+    if let Err(e) = verify_client_scope(
+        client_key.as_ref(),
+        Some(&request.model),
+        None,
+        ApiErrorFormat::Anthropic,
+    ) {
+        return e.into_response();
+    }
+
     // 记录最后一条消息的角色和内容预览
     if let Some(last_msg) = request.messages.last() {
         let content_preview = match &last_msg.content {
@@ -1908,6 +2431,11 @@ pub async fn anthropic_messages(
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    // 长上下文模型配置了 Provider 时改用该 Provider
+    let selected_provider = context_fallback
+        .as_ref()
+        .and_then(|f| f.provider.clone())
+        .unwrap_or(selected_provider);
 
     // 记录客户端检测和 Provider 选择结果
     state.logs.write().await.add(
@@ -1933,6 +2461,99 @@ pub async fn anthropic_messages(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_lowercase());
 
+    // 校验客户端 Key 的 Provider 作用域
+    if let Err(e) = verify_client_scope(
+        client_key.as_ref(),
+        None,
+        Some(provider_id_header.as_deref().unwrap_or(&selected_provider)),
+        ApiErrorFormat::Anthropic,
+    ) {
+        return e.into_response();
+    }
+
+    // 应用请求转换（在缓存查询之前，缓存键基于转换后的请求）
+    if state.processor.transformer.read().await.is_active() {
+        let mut payload = serde_json::to_value(&request).unwrap_or_default();
+        let result = state
+            .processor
+            .transform_request(
+                &mut ctx,
+                provider_id_header.as_deref().unwrap_or(&selected_provider),
+                PayloadFormat::Anthropic,
+                &mut payload,
+            )
+            .await;
+        if result.has_changes() {
+            let changes: Vec<String> = result.changes.iter().map(|c| c.to_string()).collect();
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[TRANSFORM] request_id={} changes=[{}]",
+                    ctx.request_id,
+                    changes.join("; ")
+                ),
+            );
+        }
+        if result.modified() {
+            if let Ok(updated) = serde_json::from_value(payload) {
+                request = updated;
+            }
+        }
+    }
+
+    // 注入 system prompt（需要已选定的 Provider；在请求转换之后执行，避免被改写规则覆盖）
+    if injection_enabled {
+        let mut payload = serde_json::to_value(&request).unwrap_or_default();
+        let result = state.processor.injector.read().await.inject_system_prompt(
+            &request.model,
+            provider_id_header.as_deref().unwrap_or(&selected_provider),
+            PayloadFormat::Anthropic,
+            &mut payload,
+        );
+        if result.has_injections() {
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[INJECT] request_id={} applied_rules={:?} injected_params={:?}",
+                    ctx.request_id, result.applied_rules, result.injected_params
+                ),
+            );
+            if let Ok(updated) = serde_json::from_value(payload) {
+                request = updated;
+            }
+        }
+    }
+
+    // 查询响应缓存（仅非流式请求）
+    let cache = CacheLookup::new(
+        &state,
+        &headers,
+        "anthropic",
+        &format!(
+            "{}:{}",
+            provider_id_header.as_deref().unwrap_or(&selected_provider),
+            client_key
+                .as_ref()
+                .map(|k| k.id.as_str())
+                .unwrap_or_default()
+        ),
+        &serde_json::to_value(&request).unwrap_or_default(),
+        request.stream,
+    );
+    if let Some(cached) = cache.as_ref().and_then(|c| c.lookup(&state)) {
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[CACHE] request_id={} model={} hit",
+                ctx.request_id, request.model
+            ),
+        );
+        return context_fallback::annotate(cached, context_fallback.as_ref());
+    }
+
+    // 会话粘性路由：同一会话优先使用上次的凭证
+    let session_key = anthropic_session_key(&state, &headers, &request);
+
     // 尝试从凭证池中选择凭证（带客户端兼容性检查）
     // 如果指定了 X-Provider-Id，优先使用它（不降级）
     // 否则使用 selected_provider
@@ -1946,11 +2567,13 @@ pub async fn anthropic_messages(
                 );
                 let cred = state
                     .pool_service
-                    .select_credential_with_client_check(
+                    .select_credential_for_session(
                         db,
                         explicit_provider_id,
                         Some(&request.model),
                         Some(&client_type),
+                        &[],
+                        session_key.as_deref(),
                     )
                     .ok()
                     .flatten();
@@ -1968,16 +2591,15 @@ pub async fn anthropic_messages(
                         ),
                     );
                     // 返回错误，不降级
-                    return (
+                    return ProxyApiError::from_status(
                         StatusCode::SERVICE_UNAVAILABLE,
-                        Json(json!({
-                            "error": {
-                                "type": "provider_unavailable",
-                                "message": format!("No available credentials for provider '{}'", explicit_provider_id)
-                            }
-                        })),
+                        format!(
+                            "No available credentials for provider '{}'",
+                            explicit_provider_id
+                        ),
                     )
-                        .into_response();
+                    .with_code("no_credentials")
+                    .into_response();
                 }
                 cred
             } else {
@@ -1988,11 +2610,13 @@ pub async fn anthropic_messages(
                 );
                 let cred = state
                     .pool_service
-                    .select_credential_with_client_check(
+                    .select_credential_for_session(
                         db,
                         &selected_provider,
                         Some(&request.model),
                         Some(&client_type),
+                        &[],
+                        session_key.as_deref(),
                     )
                     .ok()
                     .flatten();
@@ -2126,60 +2750,55 @@ pub async fn anthropic_messages(
                     // 请求被取消，标记 Flow 失败并返回错误
                     let error = FlowError::new(FlowErrorType::Cancelled, "请求被用户取消");
                     state.flow_monitor.fail_flow(fid, error).await;
-                    return (
+                    return ProxyApiError::from_status(
                         StatusCode::BAD_REQUEST,
-                        Json(serde_json::json!({
-                            "type": "error",
-                            "error": {
-                                "type": "request_cancelled",
-                                "message": "Request cancelled by user"
-                            }
-                        })),
+                        "Request cancelled by user",
                     )
-                        .into_response();
+                    .with_code("request_cancelled")
+                    .with_format(ApiErrorFormat::Anthropic)
+                    .into_response();
                 }
             }
         }
 
-        let response = call_provider_anthropic(&state, &cred, &request, flow_id.as_deref()).await;
+        let route = FailoverRoute {
+            provider: provider_id_header.as_deref().unwrap_or(&selected_provider),
+            // 限定了 Provider 的客户端 Key 不降级到其他 Provider
+            allow_fallback_providers: provider_id_header.is_none()
+                && client_key
+                    .as_ref()
+                    .is_none_or(|k| k.allowed_providers.is_empty()),
+            client_type: Some(&client_type),
+            session_key: session_key.as_deref(),
+        };
+        // 透传客户端的 anthropic-beta 等请求头，原生 Anthropic 凭证可直接使用 beta 功能
+        let response = with_client_anthropic_headers(
+            &headers,
+            call_provider_anthropic_with_failover(
+                &state,
+                &mut ctx,
+                cred,
+                route,
+                &request,
+                flow_id.as_deref(),
+            ),
+        )
+        .await;
+        let response = match &cache {
+            Some(cache) => cache.store(&state, &request.model, response).await,
+            None => response,
+        };
+        let response = context_fallback::annotate(response, context_fallback.as_ref());
 
         // 记录请求统计
         let is_success = response.status().is_success();
-        let status = if is_success {
-            crate::telemetry::RequestStatus::Success
-        } else {
-            crate::telemetry::RequestStatus::Failed
-        };
+        let status = crate::telemetry::RequestStatus::from_http_status(response.status().as_u16());
         record_request_telemetry(&state, &ctx, status, None);
 
-        // 估算 Token 使用量
-        let estimated_input_tokens = request
-            .messages
-            .iter()
-            .map(|m| {
-                let content_len = match &m.content {
-                    serde_json::Value::String(s) => s.len(),
-                    serde_json::Value::Array(arr) => arr
-                        .iter()
-                        .filter_map(|v| v.get("text").and_then(|t| t.as_str()))
-                        .map(|s| s.len())
-                        .sum(),
-                    _ => 0,
-                };
-                content_len / 4
-            })
-            .sum::<usize>() as u32;
+        // 估算 Token 使用量（用于 Flow 捕获，统计以上游 usage 为准）
+        let estimated_input_tokens = estimate_anthropic_input_tokens(&request);
         let estimated_output_tokens = if is_success { 100u32 } else { 0u32 };
 
-        if is_success {
-            record_token_usage(
-                &state,
-                &ctx,
-                Some(estimated_input_tokens),
-                Some(estimated_output_tokens),
-            );
-        }
-
         // 完成 Flow 捕获并检查响应拦截
         // **Validates: Requirements 2.1, 2.5**
         if let Some(fid) = flow_id {
@@ -2250,7 +2869,8 @@ pub async fn anthropic_messages(
             }
         }
 
-        return response;
+        // 在响应传输过程中解析上游 usage 并记录 Token 使用量
+        return track_token_usage(&state, &ctx, response, estimated_input_tokens).await;
     }
 
     // 回退到旧的单凭证模式（仅当选择的 Provider 是 Kiro 时）
@@ -2264,17 +2884,16 @@ pub async fn anthropic_messages(
                 selected_provider, client_type
             ),
         );
-        return (
+        return ProxyApiError::from_status(
             StatusCode::SERVICE_UNAVAILABLE,
-            Json(serde_json::json!({
-                "type": "error",
-                "error": {
-                    "type": "no_credential_error",
-                    "message": format!("没有找到可用的 '{}' 凭证。请在凭证池中添加对应的凭证。", selected_provider)
-                }
-            })),
+            format!(
+                "没有找到可用的 '{}' 凭证。请在凭证池中添加对应的凭证。",
+                selected_provider
+            ),
         )
-            .into_response();
+        .with_code("no_credentials")
+        .with_format(ApiErrorFormat::Anthropic)
+        .into_response();
     }
 
     state.logs.write().await.add(
@@ -2322,17 +2941,13 @@ pub async fn anthropic_messages(
                 // 请求被取消，标记 Flow 失败并返回错误
                 let error = FlowError::new(FlowErrorType::Cancelled, "请求被用户取消");
                 state.flow_monitor.fail_flow(fid, error).await;
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({
-                        "type": "error",
-                        "error": {
-                            "type": "request_cancelled",
-                            "message": "Request cancelled by user"
-                        }
-                    })),
+                    "Request cancelled by user",
                 )
-                    .into_response();
+                .with_code("request_cancelled")
+                .with_format(ApiErrorFormat::Anthropic)
+                .into_response();
             }
         }
     }
@@ -2362,11 +2977,11 @@ pub async fn anthropic_messages(
                     );
                     state.flow_monitor.fail_flow(fid, error).await;
                 }
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
+                    format!("Token refresh failed: {e}"),
                 )
-                    .into_response();
+                .into_response();
             }
             state
                 .logs
@@ -2430,7 +3045,7 @@ pub async fn anthropic_messages(
                             .await
                             .add("debug", &format!("[RESP] Body preview: {preview}"));
 
-                        let parsed = parse_cw_response(&body);
+                        let parsed = parse_cw_response(&bytes);
 
                         // 详细记录解析结果
                         state.logs.write().await.add(
@@ -2595,10 +3210,7 @@ pub async fn anthropic_messages(
                             let error = FlowError::new(FlowErrorType::Network, &e.to_string());
                             state.flow_monitor.fail_flow(fid, error).await;
                         }
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
+                        ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                             .into_response()
                     }
                 }
@@ -2641,8 +3253,7 @@ pub async fn anthropic_messages(
                                 if retry_resp.status().is_success() {
                                     match retry_resp.bytes().await {
                                         Ok(bytes) => {
-                                            let body = String::from_utf8_lossy(&bytes).to_string();
-                                            let parsed = parse_cw_response(&body);
+                                            let parsed = parse_cw_response(&bytes);
                                             state.logs.write().await.add(
                                                 "info",
                                                 &format!(
@@ -2762,11 +3373,11 @@ pub async fn anthropic_messages(
                                                 );
                                                 state.flow_monitor.fail_flow(fid, error).await;
                                             }
-                                            return (
+                                            return ProxyApiError::from_status(
                                                 StatusCode::INTERNAL_SERVER_ERROR,
-                                                Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                                                e.to_string(),
                                             )
-                                                .into_response();
+                                            .into_response();
                                         }
                                     }
                                 }
@@ -2790,11 +3401,11 @@ pub async fn anthropic_messages(
                                     );
                                     state.flow_monitor.fail_flow(fid, error).await;
                                 }
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": format!("Retry failed: {}", body)}})),
+                                    format!("Retry failed: {}", body),
                                 )
-                                    .into_response()
+                                .into_response()
                             }
                             Err(e) => {
                                 state
@@ -2808,11 +3419,11 @@ pub async fn anthropic_messages(
                                         FlowError::new(FlowErrorType::Network, &e.to_string());
                                     state.flow_monitor.fail_flow(fid, error).await;
                                 }
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                                    e.to_string(),
                                 )
-                                    .into_response()
+                                .into_response()
                             }
                         }
                     }
@@ -2830,11 +3441,11 @@ pub async fn anthropic_messages(
                             );
                             state.flow_monitor.fail_flow(fid, error).await;
                         }
-                        (
+                        ProxyApiError::from_status(
                             StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
+                            format!("Token refresh failed: {e}"),
                         )
-                            .into_response()
+                        .into_response()
                     }
                 }
             } else {
@@ -2854,13 +3465,7 @@ pub async fn anthropic_messages(
                             .with_status_code(status.as_u16());
                     state.flow_monitor.fail_flow(fid, error).await;
                 }
-                (
-                    StatusCode::from_u16(status.as_u16())
-                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                    Json(
-                        serde_json::json!({"error": {"message": format!("Upstream error: {}", body)}}),
-                    ),
-                )
+                ProxyApiError::upstream(status.as_u16(), format!("Upstream error: {}", body))
                     .into_response()
             }
         }
@@ -2881,10 +3486,7 @@ pub async fn anthropic_messages(
                 let error = FlowError::new(FlowErrorType::Network, &e.to_string());
                 state.flow_monitor.fail_flow(fid, error).await;
             }
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": {"message": e.to_string()}})),
-            )
+            ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                 .into_response()
         }
     }
@@ -3013,11 +3615,11 @@ fn build_stream_error_response(
         .header(header::CONNECTION, "keep-alive")
         .body(Body::from(error_event))
         .unwrap_or_else(|_| {
-            (
+            ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": {"message": "Failed to build error response"}})),
+                "Failed to build error response",
             )
-                .into_response()
+            .into_response()
         })
 }
 
@@ -3109,23 +3711,17 @@ fn convert_openai_to_anthropic(request: &ChatCompletionRequest) -> serde_json::V
                 });
             }
         } else {
-            // 转换其他消息
+            // 转换其他消息（含图片时转为内容块数组）
             let content = match &msg.content {
                 Some(c) => match c {
-                    crate::models::openai::MessageContent::Text(s) => s.clone(),
-                    crate::models::openai::MessageContent::Parts(parts) => parts
-                        .iter()
-                        .filter_map(|p| {
-                            if let crate::models::openai::ContentPart::Text { text } = p {
-                                Some(text.clone())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n"),
+                    crate::models::openai::MessageContent::Text(s) => {
+                        serde_json::Value::String(s.clone())
+                    }
+                    crate::models::openai::MessageContent::Parts(parts) => {
+                        convert_openai_parts_to_anthropic(parts)
+                    }
                 },
-                None => String::new(),
+                None => serde_json::Value::String(String::new()),
             };
 
             messages.push(serde_json::json!({
@@ -3155,6 +3751,46 @@ fn convert_openai_to_anthropic(request: &ChatCompletionRequest) -> serde_json::V
     result
 }
 
+/// 转换 OpenAI 多模态内容，纯文本时保持字符串格式
+fn convert_openai_parts_to_anthropic(
+    parts: &[crate::models::openai::ContentPart],
+) -> serde_json::Value {
+    use crate::converter::image_content::{ImageContent, ANTHROPIC_IMAGE_LIMITS};
+    use crate::models::openai::ContentPart;
+
+    if !parts
+        .iter()
+        .any(|p| matches!(p, ContentPart::ImageUrl { .. }))
+    {
+        return serde_json::Value::String(
+            parts
+                .iter()
+                .filter_map(|p| match p {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    ContentPart::ImageUrl { .. } => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+
+    let blocks = parts
+        .iter()
+        .map(|p| match p {
+            ContentPart::Text { text } => serde_json::json!({"type": "text", "text": text}),
+            ContentPart::ImageUrl { image_url } => {
+                match ImageContent::from_openai_url(&image_url.url)
+                    .fit_or_placeholder(&ANTHROPIC_IMAGE_LIMITS)
+                {
+                    Ok(image) => image.to_anthropic(),
+                    Err(text) => serde_json::json!({"type": "text", "text": text}),
+                }
+            }
+        })
+        .collect();
+    serde_json::Value::Array(blocks)
+}
+
 /// 将 Anthropic 响应转换为 OpenAI 格式
 fn convert_anthropic_response_to_openai(anthropic_resp: &serde_json::Value, model: &str) -> String {
     let content = anthropic_resp["content"]
@@ -0,0 +1,605 @@
+//! 语音 API 处理器
+//!
+//! 实现 OpenAI 兼容的 `/v1/audio/transcriptions`（语音转写）和 `/v1/audio/speech`（语音合成）端点，
+//! 请求原样转发到 OpenAI 兼容凭证（凭证池中没有时回退到 API Key Provider），
+//! 指定 `X-Provider-Id` 时使用该 Provider 的凭证。
+//!
+//! - 转写请求为 multipart 表单，所有字段合计不超过 [`MAX_AUDIO_UPLOAD_BYTES`]，`model` 字段按别名解析后转发
+//! - 合成请求的 `input` 不超过 [`MAX_SPEECH_INPUT_CHARS`] 个字符，音频字节边接收边返回给客户端
+//! - 用量：转写优先使用上游返回的 `usage`，否则按转写文本估算输出 Token；合成按 `input` 估算输入 Token
+
+use axum::{
+    body::Body,
+    extract::{multipart::MultipartRejection, Multipart, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::{CredentialData, PoolProviderType, ProviderCredential};
+use crate::processor::RequestContext;
+use crate::providers::OpenAICustomProvider;
+use crate::server::error::ProxyApiError;
+use crate::server::handlers::embeddings_handler::is_credential_failure;
+use crate::server::handlers::{
+    use_shared_client, verify_api_key, verify_client_scope, ApiErrorFormat,
+};
+use crate::server::token_count::count_text_tokens;
+use crate::server::{record_request_telemetry, record_token_usage, AppState};
+use crate::telemetry::{CacheTokens, RequestStatus, TokenSource};
+
+/// 转写请求的上传大小上限（与 OpenAI 一致）
+pub const MAX_AUDIO_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+/// 合成请求 `input` 的最大字符数
+pub const MAX_SPEECH_INPUT_CHARS: usize = 4096;
+
+/// 合成响应未返回 Content-Type 时使用的类型
+const DEFAULT_SPEECH_CONTENT_TYPE: &str = "audio/mpeg";
+
+/// multipart 表单中的一个字段
+#[derive(Debug, Clone)]
+struct FormPart {
+    name: String,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    data: Bytes,
+}
+
+/// 转写请求的 multipart 表单
+#[derive(Debug, Clone, Default)]
+struct AudioForm {
+    parts: Vec<FormPart>,
+}
+
+impl AudioForm {
+    /// 读取表单，字段合计超过 `max_bytes` 时返回 413
+    async fn read(mut multipart: Multipart, max_bytes: usize) -> Result<Self, ProxyApiError> {
+        let mut form = Self::default();
+        let mut total = 0usize;
+        while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+            let name = field.name().unwrap_or_default().to_string();
+            let file_name = field.file_name().map(str::to_string);
+            let content_type = field.content_type().map(str::to_string);
+            let mut data = BytesMut::new();
+            while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+                total += chunk.len();
+                if total > max_bytes {
+                    return Err(too_large(max_bytes));
+                }
+                data.extend_from_slice(&chunk);
+            }
+            form.parts.push(FormPart {
+                name,
+                file_name,
+                content_type,
+                data: data.freeze(),
+            });
+        }
+        Ok(form)
+    }
+
+    /// 获取文本字段
+    fn text(&self, name: &str) -> Option<&str> {
+        self.parts
+            .iter()
+            .find(|p| p.name == name && p.file_name.is_none())
+            .and_then(|p| std::str::from_utf8(&p.data).ok())
+    }
+
+    /// 设置文本字段（已存在时替换）
+    fn set_text(&mut self, name: &str, value: &str) {
+        let data = Bytes::copy_from_slice(value.as_bytes());
+        match self
+            .parts
+            .iter_mut()
+            .find(|p| p.name == name && p.file_name.is_none())
+        {
+            Some(part) => part.data = data,
+            None => self.parts.push(FormPart {
+                name: name.to_string(),
+                file_name: None,
+                content_type: None,
+                data,
+            }),
+        }
+    }
+
+    /// 是否包含上传的音频文件
+    fn has_file(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|p| p.name == "file" && p.file_name.is_some())
+    }
+
+    /// 构建转发给上游的表单
+    fn to_reqwest(&self) -> reqwest::multipart::Form {
+        self.parts
+            .iter()
+            .fold(reqwest::multipart::Form::new(), |form, p| {
+                let mut part = reqwest::multipart::Part::stream(p.data.clone());
+                if let Some(file_name) = &p.file_name {
+                    part = part.file_name(file_name.clone());
+                }
+                if let Some(content_type) = &p.content_type {
+                    part = match part.mime_str(content_type) {
+                        Ok(part) => part,
+                        Err(_) => reqwest::multipart::Part::stream(p.data.clone())
+                            .file_name(p.file_name.clone().unwrap_or_default()),
+                    };
+                }
+                form.part(p.name.clone(), part)
+            })
+    }
+}
+
+fn multipart_error(e: axum::extract::multipart::MultipartError) -> ProxyApiError {
+    ProxyApiError::from_status(e.status(), e.body_text()).with_code("invalid_multipart")
+}
+
+fn too_large(max_bytes: usize) -> ProxyApiError {
+    ProxyApiError::from_status(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!(
+            "Audio upload exceeds the {} MB limit",
+            max_bytes / 1024 / 1024
+        ),
+    )
+    .with_code("file_too_large")
+}
+
+/// 从转写响应中提取用量
+///
+/// 返回 `(输入 Token, 输出 Token, 来源)`；上游未返回 Token 用量时按转写文本估算输出 Token。
+/// `text`、`srt`、`vtt` 等非 JSON 格式的响应体本身即为转写文本。
+fn transcription_usage(
+    content_type: &str,
+    body: &[u8],
+    model: &str,
+) -> (Option<u32>, Option<u32>, TokenSource) {
+    let text = if content_type.starts_with("application/json") {
+        let Ok(json) = serde_json::from_slice::<serde_json::Value>(body) else {
+            return (None, None, TokenSource::Estimated);
+        };
+        let usage = &json["usage"];
+        if let (Some(input), Some(output)) = (
+            usage["input_tokens"].as_u64(),
+            usage["output_tokens"].as_u64(),
+        ) {
+            return (Some(input as u32), Some(output as u32), TokenSource::Actual);
+        }
+        json["text"].as_str().unwrap_or_default().to_string()
+    } else {
+        String::from_utf8_lossy(body).into_owned()
+    };
+    (
+        None,
+        Some(count_text_tokens(&text, model)),
+        TokenSource::Estimated,
+    )
+}
+
+/// 请求的目标 Provider（`X-Provider-Id` 头，默认 OpenAI）
+fn provider_id(headers: &HeaderMap) -> String {
+    headers
+        .get("x-provider-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_else(|| PoolProviderType::OpenAI.to_string())
+}
+
+/// 选择 OpenAI 兼容凭证，凭证池中没有时回退到 API Key Provider
+async fn select_credential(
+    state: &AppState,
+    db: &DbConnection,
+    provider_id: &str,
+    model: &str,
+) -> Result<ProviderCredential, ProxyApiError> {
+    let mut credential = state
+        .pool_service
+        .select_credential(db, provider_id, Some(model))
+        .map_err(|e| ProxyApiError::internal(format!("Failed to get credentials: {}", e)))?;
+    if credential.is_none()
+        && provider_id.parse::<PoolProviderType>() == Ok(PoolProviderType::OpenAI)
+    {
+        credential = state
+            .api_key_service
+            .get_fallback_credential(db, &PoolProviderType::OpenAI, Some(provider_id), None)
+            .await
+            .ok()
+            .flatten();
+    }
+    credential.ok_or_else(|| {
+        ProxyApiError::unavailable(format!(
+            "No available credentials for provider '{}'",
+            provider_id
+        ))
+        .with_code("no_credentials")
+    })
+}
+
+/// 为凭证创建 OpenAI 兼容 Provider，其他凭证类型不支持语音 API
+fn openai_provider(
+    state: &AppState,
+    credential: &ProviderCredential,
+) -> Result<OpenAICustomProvider, ProxyApiError> {
+    let CredentialData::OpenAIKey { api_key, base_url } = &credential.credential else {
+        return Err(ProxyApiError::invalid_request(format!(
+            "Credential type {} does not support audio endpoints",
+            credential.credential.provider_type()
+        )));
+    };
+    let mut provider = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+    use_shared_client(state, credential, &mut provider.client);
+    Ok(provider)
+}
+
+/// 处理上游调用失败：认证失败和服务端错误计入凭证健康状态
+async fn upstream_failure(
+    state: &AppState,
+    db: &DbConnection,
+    ctx: &RequestContext,
+    credential: &ProviderCredential,
+    status: StatusCode,
+    message: String,
+) -> Response {
+    if is_credential_failure(status) {
+        let _ = state
+            .pool_service
+            .mark_unhealthy(db, &credential.uuid, Some(&message));
+    }
+    state.logs.write().await.add(
+        "error",
+        &format!(
+            "[AUDIO] request_id={} 调用失败: {}",
+            ctx.request_id, message
+        ),
+    );
+    record_request_telemetry(state, ctx, RequestStatus::Failed, Some(message.clone()));
+    ProxyApiError::upstream(status.as_u16(), message).into_response()
+}
+
+/// 读取上游错误响应
+async fn error_body(resp: reqwest::Response) -> (StatusCode, String) {
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    (status, resp.text().await.unwrap_or_default())
+}
+
+/// 边接收边返回上游响应体，传输结束前保持凭证的进行中计数
+fn stream_response<G: Send + 'static>(
+    resp: reqwest::Response,
+    default_content_type: &str,
+    guard: G,
+) -> Response {
+    let content_type = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_str(default_content_type).unwrap());
+    let stream = resp.bytes_stream().map(move |chunk| {
+        let _held = &guard;
+        chunk
+    });
+    let mut response = Body::from_stream(stream).into_response();
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, content_type);
+    response
+}
+
+/// 处理语音转写请求
+///
+/// POST /v1/audio/transcriptions
+pub async fn handle_audio_transcriptions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    multipart: Result<Multipart, MultipartRejection>,
+) -> Response {
+    let client_key = match verify_api_key(&headers, &state).await {
+        Ok(client_key) => client_key,
+        Err(e) => return e.into_response(),
+    };
+
+    let multipart = match multipart {
+        Ok(multipart) => multipart,
+        Err(e) => {
+            return ProxyApiError::from_status(e.status(), e.body_text())
+                .with_code("invalid_multipart")
+                .into_response()
+        }
+    };
+    let mut form = match AudioForm::read(multipart, MAX_AUDIO_UPLOAD_BYTES).await {
+        Ok(form) => form,
+        Err(e) => return e.into_response(),
+    };
+    if !form.has_file() {
+        return ProxyApiError::invalid_request("file is required").into_response();
+    }
+    let Some(model) = form.text("model").map(str::to_string) else {
+        return ProxyApiError::invalid_request("model is required").into_response();
+    };
+
+    let mut ctx = RequestContext::new(model.clone());
+    if let Some(key) = &client_key {
+        ctx.set_client_key_id(key.id.clone());
+    }
+    let resolved_model = state.processor.resolve_model(&model).await;
+    ctx.set_resolved_model(resolved_model.clone());
+    form.set_text("model", &resolved_model);
+
+    let provider_id = provider_id(&headers);
+    if let Err(e) = verify_client_scope(
+        client_key.as_ref(),
+        Some(&resolved_model),
+        Some(&provider_id),
+        ApiErrorFormat::OpenAI,
+    ) {
+        return e.into_response();
+    }
+
+    let upload_bytes: usize = form.parts.iter().map(|p| p.data.len()).sum();
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[AUDIO] request_id={} transcriptions model={} provider={} bytes={}",
+            ctx.request_id, resolved_model, provider_id, upload_bytes
+        ),
+    );
+
+    let Some(db) = &state.db else {
+        return ProxyApiError::internal("Database not available").into_response();
+    };
+    let credential = match select_credential(&state, db, &provider_id, &resolved_model).await {
+        Ok(credential) => credential,
+        Err(e) => return e.into_response(),
+    };
+    let provider = match openai_provider(&state, &credential) {
+        Ok(provider) => provider,
+        Err(e) => return e.into_response(),
+    };
+    ctx.set_provider(credential.provider_type);
+    ctx.set_credential_id(credential.uuid.clone());
+    let in_flight = state.pool_service.begin_request(&credential.uuid);
+
+    let resp = match provider.audio_transcriptions(|| form.to_reqwest()).await {
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) => {
+            let (status, message) = error_body(resp).await;
+            return upstream_failure(&state, db, &ctx, &credential, status, message).await;
+        }
+        Err(e) => {
+            return upstream_failure(
+                &state,
+                db,
+                &ctx,
+                &credential,
+                StatusCode::BAD_GATEWAY,
+                e.to_string(),
+            )
+            .await
+        }
+    };
+
+    let _ = state
+        .pool_service
+        .mark_healthy(db, &credential.uuid, Some(&resolved_model));
+    let _ = state.pool_service.record_usage(db, &credential.uuid);
+
+    let content_type = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/json")
+        .to_string();
+    // `stream=true` 时上游以 SSE 返回增量转写文本，直接透传
+    if content_type.starts_with("text/event-stream") {
+        ctx.is_stream = true;
+        record_request_telemetry(&state, &ctx, RequestStatus::Success, None);
+        return stream_response(resp, &content_type, in_flight);
+    }
+
+    let body = match resp.bytes().await {
+        Ok(body) => body,
+        Err(e) => {
+            return upstream_failure(
+                &state,
+                db,
+                &ctx,
+                &credential,
+                StatusCode::BAD_GATEWAY,
+                e.to_string(),
+            )
+            .await
+        }
+    };
+    record_request_telemetry(&state, &ctx, RequestStatus::Success, None);
+    let (input_tokens, output_tokens, source) =
+        transcription_usage(&content_type, &body, &resolved_model);
+    record_token_usage(
+        &state,
+        &ctx,
+        input_tokens,
+        output_tokens,
+        CacheTokens::default(),
+        source,
+    );
+    ([(header::CONTENT_TYPE, content_type)], body).into_response()
+}
+
+/// 处理语音合成请求
+///
+/// POST /v1/audio/speech
+pub async fn handle_audio_speech(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut request): Json<serde_json::Value>,
+) -> Response {
+    let client_key = match verify_api_key(&headers, &state).await {
+        Ok(client_key) => client_key,
+        Err(e) => return e.into_response(),
+    };
+
+    let Some(model) = request["model"].as_str().map(str::to_string) else {
+        return ProxyApiError::invalid_request("model is required").into_response();
+    };
+    let input = request["input"].as_str().unwrap_or_default().to_string();
+    if input.is_empty() {
+        return ProxyApiError::invalid_request("input is required and cannot be empty")
+            .into_response();
+    }
+    if input.chars().count() > MAX_SPEECH_INPUT_CHARS {
+        return ProxyApiError::invalid_request(format!(
+            "input exceeds the {} character limit",
+            MAX_SPEECH_INPUT_CHARS
+        ))
+        .with_code("input_too_long")
+        .into_response();
+    }
+
+    let mut ctx = RequestContext::new(model.clone());
+    if let Some(key) = &client_key {
+        ctx.set_client_key_id(key.id.clone());
+    }
+    let resolved_model = state.processor.resolve_model(&model).await;
+    ctx.set_resolved_model(resolved_model.clone());
+    request["model"] = serde_json::Value::String(resolved_model.clone());
+
+    let provider_id = provider_id(&headers);
+    if let Err(e) = verify_client_scope(
+        client_key.as_ref(),
+        Some(&resolved_model),
+        Some(&provider_id),
+        ApiErrorFormat::OpenAI,
+    ) {
+        return e.into_response();
+    }
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[AUDIO] request_id={} speech model={} provider={} chars={}",
+            ctx.request_id,
+            resolved_model,
+            provider_id,
+            input.chars().count()
+        ),
+    );
+
+    let Some(db) = &state.db else {
+        return ProxyApiError::internal("Database not available").into_response();
+    };
+    let credential = match select_credential(&state, db, &provider_id, &resolved_model).await {
+        Ok(credential) => credential,
+        Err(e) => return e.into_response(),
+    };
+    let provider = match openai_provider(&state, &credential) {
+        Ok(provider) => provider,
+        Err(e) => return e.into_response(),
+    };
+    ctx.set_provider(credential.provider_type);
+    ctx.set_credential_id(credential.uuid.clone());
+    let in_flight = state.pool_service.begin_request(&credential.uuid);
+
+    let resp = match provider.audio_speech(&request).await {
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) => {
+            let (status, message) = error_body(resp).await;
+            return upstream_failure(&state, db, &ctx, &credential, status, message).await;
+        }
+        Err(e) => {
+            return upstream_failure(
+                &state,
+                db,
+                &ctx,
+                &credential,
+                StatusCode::BAD_GATEWAY,
+                e.to_string(),
+            )
+            .await
+        }
+    };
+
+    let _ = state
+        .pool_service
+        .mark_healthy(db, &credential.uuid, Some(&resolved_model));
+    let _ = state.pool_service.record_usage(db, &credential.uuid);
+    record_request_telemetry(&state, &ctx, RequestStatus::Success, None);
+    record_token_usage(
+        &state,
+        &ctx,
+        Some(count_text_tokens(&input, &resolved_model)),
+        Some(0),
+        CacheTokens::default(),
+        TokenSource::Estimated,
+    );
+    stream_response(resp, DEFAULT_SPEECH_CONTENT_TYPE, in_flight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form() -> AudioForm {
+        AudioForm {
+            parts: vec![
+                FormPart {
+                    name: "file".to_string(),
+                    file_name: Some("speech.mp3".to_string()),
+                    content_type: Some("audio/mpeg".to_string()),
+                    data: Bytes::from_static(b"ID3"),
+                },
+                FormPart {
+                    name: "model".to_string(),
+                    file_name: None,
+                    content_type: None,
+                    data: Bytes::from_static(b"whisper"),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_form_fields() {
+        let mut form = form();
+        assert!(form.has_file());
+        assert_eq!(form.text("model"), Some("whisper"));
+        assert_eq!(form.text("file"), None);
+
+        form.set_text("model", "whisper-1");
+        form.set_text("language", "zh");
+        assert_eq!(form.text("model"), Some("whisper-1"));
+        assert_eq!(form.text("language"), Some("zh"));
+        assert_eq!(form.parts.len(), 3);
+
+        assert!(!AudioForm::default().has_file());
+    }
+
+    #[test]
+    fn test_transcription_usage() {
+        let actual =
+            br#"{"text":"hi","usage":{"type":"tokens","input_tokens":120,"output_tokens":8}}"#;
+        let (input, output, source) =
+            transcription_usage("application/json", actual, "gpt-4o-transcribe");
+        assert_eq!((input, output), (Some(120), Some(8)));
+        assert_eq!(source, TokenSource::Actual);
+
+        let (input, output, source) = transcription_usage(
+            "application/json",
+            br#"{"text":"hello world"}"#,
+            "whisper-1",
+        );
+        assert_eq!(input, None);
+        assert!(output.unwrap() > 0);
+        assert_eq!(source, TokenSource::Estimated);
+
+        let (_, srt, _) = transcription_usage(
+            "text/plain; charset=utf-8",
+            b"1\n00:00:00,000 --> 00:00:01,000\nhello world\n",
+            "whisper-1",
+        );
+        assert!(srt.unwrap() > 0);
+    }
+}
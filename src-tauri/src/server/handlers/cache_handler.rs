@@ -0,0 +1,177 @@
+//! 响应缓存处理
+//!
+//! - 补全端点的缓存查询/写入辅助函数
+//! - `GET /v1/cache/stats`：缓存命中统计
+//!
+//! 客户端可通过 `Cache-Control` 请求头控制缓存：
+//! - `no-cache`：跳过缓存查询，但仍写入新的响应
+//! - `no-store`：既不查询也不写入
+//!
+//! 响应携带 `X-Cache: HIT|MISS|BYPASS`，命中时附带 `Age`。
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::server::error::ProxyApiError;
+use crate::server::handlers::verify_api_key;
+use crate::server::AppState;
+use crate::services::response_cache_service::ResponseCacheService;
+
+/// 缓存状态响应头
+const CACHE_STATUS_HEADER: &str = "x-cache";
+
+/// 单次请求的缓存策略
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheLookup {
+    /// 缓存 key
+    pub key: String,
+    /// 是否查询缓存
+    pub read: bool,
+    /// 是否写入缓存
+    pub write: bool,
+}
+
+impl CacheLookup {
+    /// 根据请求构建缓存策略，缓存未启用或请求为流式时返回 `None`
+    pub fn new(
+        state: &AppState,
+        headers: &HeaderMap,
+        namespace: &str,
+        scope: &str,
+        request: &serde_json::Value,
+        stream: bool,
+    ) -> Option<Self> {
+        if stream || !state.response_cache.is_enabled() {
+            return None;
+        }
+        let (read, write) = parse_cache_control(headers);
+        Some(Self {
+            key: ResponseCacheService::cache_key(namespace, scope, request),
+            read,
+            write,
+        })
+    }
+
+    /// 查询缓存，命中时返回缓存的响应
+    pub fn lookup(&self, state: &AppState) -> Option<Response> {
+        if !self.read {
+            return None;
+        }
+        let cached = state.response_cache.get(state.db.as_ref(), &self.key)?;
+        let age = cached.age_secs();
+        let mut response = Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, cached.content_type)
+            .header(header::AGE, age)
+            .header(CACHE_STATUS_HEADER, "HIT")
+            .body(Body::from(cached.body))
+            .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        set_cache_control(&mut response, state.response_cache.ttl_secs());
+        Some(response)
+    }
+
+    /// 写入成功的响应并返回重新组装的响应
+    ///
+    /// 非 2xx 响应和 SSE 响应不缓存，只添加 `X-Cache` 头
+    pub async fn store(&self, state: &AppState, model: &str, response: Response) -> Response {
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/json")
+            .to_string();
+        let cacheable = self.write
+            && response.status().is_success()
+            && !content_type.starts_with("text/event-stream");
+        if !cacheable {
+            return with_cache_status(response, if self.read { "MISS" } else { "BYPASS" });
+        }
+
+        let (parts, body) = response.into_parts();
+        let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!("[CACHE] 读取响应体失败，跳过缓存: {}", e);
+                return ProxyApiError::from_status(
+                    StatusCode::BAD_GATEWAY,
+                    format!("Failed to read response body: {}", e),
+                )
+                .into_response();
+            }
+        };
+        state.response_cache.put(
+            state.db.as_ref(),
+            &self.key,
+            model,
+            &content_type,
+            bytes.clone(),
+        );
+
+        let mut response = Response::from_parts(parts, Body::from(bytes));
+        set_cache_control(&mut response, state.response_cache.ttl_secs());
+        with_cache_status(response, if self.read { "MISS" } else { "BYPASS" })
+    }
+}
+
+/// 解析 `Cache-Control` 请求头，返回（是否查询，是否写入）
+fn parse_cache_control(headers: &HeaderMap) -> (bool, bool) {
+    let directives: Vec<String> = headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|d| d.trim().to_ascii_lowercase())
+        .collect();
+    let no_store = directives.iter().any(|d| d == "no-store");
+    let no_cache = no_store || directives.iter().any(|d| d == "no-cache");
+    (!no_cache, !no_store)
+}
+
+fn set_cache_control(response: &mut Response, ttl_secs: u64) {
+    if let Ok(value) = HeaderValue::from_str(&format!("private, max-age={}", ttl_secs)) {
+        response.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+}
+
+fn with_cache_status(mut response: Response, status: &'static str) -> Response {
+    response
+        .headers_mut()
+        .insert(CACHE_STATUS_HEADER, HeaderValue::from_static(status));
+    response
+}
+
+/// 获取响应缓存统计
+///
+/// GET /v1/cache/stats
+pub async fn response_cache_stats(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state).await {
+        return e.into_response();
+    }
+    Json(state.response_cache.stats(state.db.as_ref())).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cache_control() {
+        assert_eq!(parse_cache_control(&HeaderMap::new()), (true, true));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        assert_eq!(parse_cache_control(&headers), (false, true));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("max-age=0, No-Store"),
+        );
+        assert_eq!(parse_cache_control(&headers), (false, false));
+    }
+}
@@ -279,6 +279,7 @@ fn get_oauth_base_url(provider_type: &PoolProviderType) -> String {
         PoolProviderType::Vertex => "https://vertex-ai.googleapis.com".to_string(),
         PoolProviderType::GeminiApiKey => "https://generativelanguage.googleapis.com".to_string(),
         PoolProviderType::Codex => "https://api.openai.com/v1".to_string(),
+        PoolProviderType::Qwen => crate::providers::qwen::QWEN_DEFAULT_BASE_URL.to_string(),
         PoolProviderType::ClaudeOAuth => "https://api.anthropic.com".to_string(),
         _ => "https://api.openai.com/v1".to_string(),
     }
@@ -0,0 +1,290 @@
+//! Embeddings API 处理器
+//!
+//! 实现 OpenAI 兼容的 `/v1/embeddings` 端点。
+//!
+//! # 路由规则
+//! - 指定 `X-Provider-Id` 时使用该 Provider 的凭证
+//! - 否则 Gemini 向量模型（`text-embedding-004`、`gemini-embedding-*` 等）使用 Gemini API Key 凭证，
+//!   其他模型使用 OpenAI 兼容凭证（凭证池中没有时回退到 API Key Provider）
+//!
+//! # 支持的凭证
+//! - `OpenAIKey`：直接转发到上游 `/embeddings`
+//! - `GeminiApiKey`：转换为 `batchEmbedContents` 请求
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::converter::openai_to_gemini_embeddings::{
+    convert_embedding_request_to_gemini, convert_gemini_embedding_response,
+};
+use crate::models::openai::{EmbeddingRequest, EmbeddingResponse};
+use crate::models::provider_pool_model::{CredentialData, PoolProviderType, ProviderCredential};
+use crate::processor::RequestContext;
+use crate::providers::{GeminiApiKeyCredential, GeminiApiKeyProvider, OpenAICustomProvider};
+use crate::server::error::ProxyApiError;
+use crate::server::handlers::{
+    use_shared_client, verify_api_key, verify_client_scope, ApiErrorFormat,
+};
+use crate::server::{record_request_telemetry, record_token_usage, AppState};
+use crate::telemetry::{CacheTokens, RequestStatus, TokenSource};
+
+/// Gemini 向量模型前缀
+const GEMINI_EMBEDDING_MODEL_PREFIXES: &[&str] = &[
+    "text-embedding-004",
+    "text-embedding-005",
+    "text-multilingual-embedding",
+    "gemini-embedding",
+    "embedding-",
+];
+
+/// 判断是否为 Gemini 向量模型
+fn is_gemini_embedding_model(model: &str) -> bool {
+    GEMINI_EMBEDDING_MODEL_PREFIXES
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+}
+
+/// 上游错误是否应计入凭证健康状态（认证失败和服务端错误）
+pub(crate) fn is_credential_failure(status: StatusCode) -> bool {
+    status == StatusCode::UNAUTHORIZED
+        || status == StatusCode::FORBIDDEN
+        || status.is_server_error()
+}
+
+/// 处理 Embeddings 请求
+///
+/// POST /v1/embeddings
+pub async fn handle_embeddings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut request): Json<EmbeddingRequest>,
+) -> Response {
+    let client_key = match verify_api_key(&headers, &state).await {
+        Ok(client_key) => client_key,
+        Err(e) => return e.into_response(),
+    };
+
+    if request.input.is_empty() {
+        return ProxyApiError::invalid_request("input is required and cannot be empty")
+            .into_response();
+    }
+
+    let mut ctx = RequestContext::new(request.model.clone());
+    if let Some(key) = &client_key {
+        ctx.set_client_key_id(key.id.clone());
+    }
+
+    // 解析模型别名
+    let resolved_model = state.processor.resolve_model(&request.model).await;
+    ctx.set_resolved_model(resolved_model.clone());
+    request.model = resolved_model;
+
+    let provider_id = headers
+        .get("x-provider-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_else(|| {
+            if is_gemini_embedding_model(&request.model) {
+                PoolProviderType::GeminiApiKey.to_string()
+            } else {
+                PoolProviderType::OpenAI.to_string()
+            }
+        });
+
+    if let Err(e) = verify_client_scope(
+        client_key.as_ref(),
+        Some(&request.model),
+        Some(&provider_id),
+        ApiErrorFormat::OpenAI,
+    ) {
+        return e.into_response();
+    }
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[EMBEDDINGS] request_id={} model={} provider={} inputs={}",
+            ctx.request_id,
+            request.model,
+            provider_id,
+            request.input.len()
+        ),
+    );
+
+    let Some(db) = &state.db else {
+        return ProxyApiError::internal("Database not available").into_response();
+    };
+
+    // 从凭证池选择凭证，OpenAI 兼容 Provider 没有凭证时回退到 API Key Provider
+    let selected = state
+        .pool_service
+        .select_credential(db, &provider_id, Some(&request.model));
+    let mut credential = match selected {
+        Ok(cred) => cred,
+        Err(e) => {
+            return ProxyApiError::internal(format!("Failed to get credentials: {}", e))
+                .into_response();
+        }
+    };
+    if credential.is_none()
+        && provider_id.parse::<PoolProviderType>() == Ok(PoolProviderType::OpenAI)
+    {
+        credential = state
+            .api_key_service
+            .get_fallback_credential(db, &PoolProviderType::OpenAI, Some(&provider_id), None)
+            .await
+            .ok()
+            .flatten();
+    }
+    let Some(credential) = credential else {
+        state.logs.write().await.add(
+            "error",
+            &format!(
+                "[EMBEDDINGS] request_id={} 没有可用的 {} 凭证",
+                ctx.request_id, provider_id
+            ),
+        );
+        return ProxyApiError::unavailable(format!(
+            "No available credentials for provider '{}'",
+            provider_id
+        ))
+        .with_code("no_credentials")
+        .into_response();
+    };
+
+    ctx.set_provider(credential.provider_type);
+    ctx.set_credential_id(credential.uuid.clone());
+    let _in_flight = state.pool_service.begin_request(&credential.uuid);
+
+    let result = call_embeddings(&state, &credential, &request).await;
+    match result {
+        Ok((response, source)) => {
+            let _ = state
+                .pool_service
+                .mark_healthy(db, &credential.uuid, Some(&request.model));
+            let _ = state.pool_service.record_usage(db, &credential.uuid);
+            record_request_telemetry(&state, &ctx, RequestStatus::Success, None);
+            record_token_usage(
+                &state,
+                &ctx,
+                Some(response.usage.prompt_tokens),
+                Some(0),
+                CacheTokens::default(),
+                source,
+            );
+            Json(response).into_response()
+        }
+        Err((status, message)) => {
+            if is_credential_failure(status) {
+                let _ = state
+                    .pool_service
+                    .mark_unhealthy(db, &credential.uuid, Some(&message));
+            }
+            state.logs.write().await.add(
+                "error",
+                &format!(
+                    "[EMBEDDINGS] request_id={} 调用失败: {}",
+                    ctx.request_id, message
+                ),
+            );
+            record_request_telemetry(&state, &ctx, RequestStatus::Failed, Some(message.clone()));
+            ProxyApiError::from_status(status, message).into_response()
+        }
+    }
+}
+
+/// 根据凭证类型调用上游 Embeddings API
+async fn call_embeddings(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &EmbeddingRequest,
+) -> Result<(EmbeddingResponse, TokenSource), (StatusCode, String)> {
+    match &credential.credential {
+        CredentialData::OpenAIKey { api_key, base_url } => {
+            let mut openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut openai.client);
+            let resp = openai
+                .embeddings(request)
+                .await
+                .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+
+            let status = resp.status();
+            let body = resp
+                .text()
+                .await
+                .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+            if !status.is_success() {
+                let status =
+                    StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+                return Err((status, body));
+            }
+
+            let mut response: EmbeddingResponse = serde_json::from_str(&body).map_err(|e| {
+                (
+                    StatusCode::BAD_GATEWAY,
+                    format!("Invalid embeddings response: {}", e),
+                )
+            })?;
+            // 部分兼容服务不返回 usage，此时按输入估算
+            if response.usage.prompt_tokens == 0 {
+                let estimated = request.input.estimate_tokens();
+                response.usage.prompt_tokens = estimated;
+                response.usage.total_tokens = estimated;
+                Ok((response, TokenSource::Estimated))
+            } else {
+                Ok((response, TokenSource::Actual))
+            }
+        }
+        CredentialData::GeminiApiKey {
+            api_key,
+            base_url,
+            excluded_models,
+        } => {
+            let gemini_credential =
+                GeminiApiKeyCredential::new(credential.uuid.clone(), api_key.clone())
+                    .with_base_url(base_url.clone())
+                    .with_excluded_models(excluded_models.clone());
+            let body = convert_embedding_request_to_gemini(request)
+                .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            let mut provider = GeminiApiKeyProvider::new();
+            use_shared_client(state, credential, &mut provider.client);
+            let resp = provider
+                .batch_embed_contents(&gemini_credential, &request.model, &body)
+                .await
+                .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+
+            let response = convert_gemini_embedding_response(
+                &resp,
+                &request.model,
+                request.encoding_format.as_deref(),
+                request.input.estimate_tokens(),
+            )
+            .map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+            Ok((response, TokenSource::Estimated))
+        }
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Credential type {} does not support embeddings",
+                other.provider_type()
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_gemini_embedding_model() {
+        assert!(is_gemini_embedding_model("text-embedding-004"));
+        assert!(is_gemini_embedding_model("gemini-embedding-001"));
+        assert!(!is_gemini_embedding_model("text-embedding-3-small"));
+        assert!(!is_gemini_embedding_model("gemini-2.5-pro"));
+    }
+}
@@ -0,0 +1,317 @@
+//! Gemini 原生协议处理器
+//!
+//! 实现 Gemini API 兼容的端点：
+//! - `POST /v1beta/models/{model}:generateContent`
+//! - `POST /v1beta/models/{model}:streamGenerateContent`（`?alt=sse` 返回 SSE，否则返回 JSON 数组流）
+//!
+//! 请求转换为 OpenAI 格式后交给 `/v1/chat/completions` 的处理流程，
+//! 因此可以使用凭证池中的任意 Provider，响应再转换回 Gemini 格式。
+//!
+//! # 认证
+//! 支持 Gemini SDK 的 `x-goog-api-key` 请求头和 `?key=` 查询参数，
+//! 以及代理通用的 `Authorization` / `x-api-key` 请求头。
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde::Deserialize;
+
+use crate::converter::gemini_to_openai::{
+    convert_gemini_request_to_openai, convert_openai_response_to_gemini, GeminiStreamConverter,
+};
+use crate::server::handlers::chat_completions;
+use crate::server::AppState;
+
+/// Gemini 端点查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct GeminiQuery {
+    /// API Key（Gemini REST API 的查询参数认证方式）
+    pub key: Option<String>,
+    /// 流式响应格式，`sse` 表示 Server-Sent Events
+    pub alt: Option<String>,
+}
+
+/// 构建 Gemini 格式的错误响应
+fn gemini_error(status: StatusCode, message: impl Into<String>) -> Response {
+    let status_text = match status {
+        StatusCode::BAD_REQUEST => "INVALID_ARGUMENT",
+        StatusCode::UNAUTHORIZED => "UNAUTHENTICATED",
+        StatusCode::FORBIDDEN => "PERMISSION_DENIED",
+        StatusCode::NOT_FOUND => "NOT_FOUND",
+        StatusCode::TOO_MANY_REQUESTS => "RESOURCE_EXHAUSTED",
+        StatusCode::SERVICE_UNAVAILABLE => "UNAVAILABLE",
+        _ => "INTERNAL",
+    };
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {
+                "code": status.as_u16(),
+                "message": message.into(),
+                "status": status_text
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 将 Gemini 的认证方式转换为代理通用的 `Authorization` 请求头
+fn normalize_auth_headers(headers: &HeaderMap, query_key: Option<&str>) -> HeaderMap {
+    let mut headers = headers.clone();
+    if headers.contains_key(header::AUTHORIZATION) || headers.contains_key("x-api-key") {
+        return headers;
+    }
+    let key = headers
+        .get("x-goog-api-key")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .or_else(|| query_key.map(|s| s.to_string()));
+    if let Some(value) = key.and_then(|k| HeaderValue::from_str(&format!("Bearer {}", k)).ok()) {
+        headers.insert(header::AUTHORIZATION, value);
+    }
+    headers
+}
+
+/// 从上游 OpenAI 错误响应中提取错误信息
+fn extract_error_message(body: &[u8]) -> String {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| {
+            v.get("error")
+                .and_then(|e| e.get("message"))
+                .and_then(|m| m.as_str())
+                .map(|s| s.to_string())
+        })
+        .unwrap_or_else(|| String::from_utf8_lossy(body).to_string())
+}
+
+/// 处理 Gemini generateContent / streamGenerateContent 请求
+///
+/// POST /v1beta/models/{model}:{method}
+pub async fn handle_gemini_generate_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(model_method): Path<String>,
+    Query(query): Query<GeminiQuery>,
+    Json(request): Json<serde_json::Value>,
+) -> Response {
+    // 路径格式: {model}:{method}，例如 gemini-2.5-pro:streamGenerateContent
+    let Some((model, method)) = model_method.rsplit_once(':') else {
+        return gemini_error(
+            StatusCode::BAD_REQUEST,
+            format!("Invalid path '{}', expected model:method", model_method),
+        );
+    };
+    let model = model.strip_prefix("models/").unwrap_or(model);
+    let is_stream = match method {
+        "generateContent" => false,
+        "streamGenerateContent" => true,
+        other => {
+            return gemini_error(
+                StatusCode::NOT_FOUND,
+                format!("Method '{}' is not supported", other),
+            );
+        }
+    };
+
+    let openai_request = match convert_gemini_request_to_openai(&request, model, is_stream) {
+        Ok(req) => req,
+        Err(e) => return gemini_error(StatusCode::BAD_REQUEST, e),
+    };
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[GEMINI] POST /v1beta/models/{} stream={} messages={}",
+            model_method,
+            is_stream,
+            openai_request.messages.len()
+        ),
+    );
+
+    let headers = normalize_auth_headers(&headers, query.key.as_deref());
+    let response = chat_completions(State(state), headers, Json(openai_request)).await;
+    let (parts, body) = response.into_parts();
+
+    if !parts.status.is_success() {
+        let bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .unwrap_or_default();
+        return gemini_error(parts.status, extract_error_message(&bytes));
+    }
+
+    let is_sse = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/event-stream"));
+
+    if is_stream && is_sse {
+        let use_sse = query.alt.as_deref() == Some("sse");
+        return stream_response(body, model, use_sse);
+    }
+
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return gemini_error(
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to read response body: {}", e),
+            );
+        }
+    };
+    let openai_response: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(value) => value,
+        Err(e) => {
+            return gemini_error(
+                StatusCode::BAD_GATEWAY,
+                format!("Invalid upstream response: {}", e),
+            );
+        }
+    };
+    let gemini_response = convert_openai_response_to_gemini(&openai_response, model);
+
+    if is_stream {
+        // 上游没有返回流式响应时，按单个 chunk 输出
+        let body = if query.alt.as_deref() == Some("sse") {
+            format!("data: {}\n\n", gemini_response)
+        } else {
+            format!("[{}]", gemini_response)
+        };
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header(
+                header::CONTENT_TYPE,
+                stream_content_type(query.alt.as_deref() == Some("sse")),
+            )
+            .body(Body::from(body))
+            .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response());
+    }
+
+    Json(gemini_response).into_response()
+}
+
+fn stream_content_type(use_sse: bool) -> &'static str {
+    if use_sse {
+        "text/event-stream"
+    } else {
+        "application/json"
+    }
+}
+
+/// 编码单个 Gemini 流式 chunk
+fn stream_frame(chunk: &serde_json::Value, use_sse: bool, first: bool) -> bytes::Bytes {
+    let output = if use_sse {
+        format!("data: {}\n\n", chunk)
+    } else if first {
+        format!("[{}", chunk)
+    } else {
+        format!(",\r\n{}", chunk)
+    };
+    bytes::Bytes::from(output)
+}
+
+/// 将 OpenAI SSE 响应流转换为 Gemini 流式响应
+///
+/// - `alt=sse`：每个 chunk 输出为 `data: {...}\n\n`
+/// - 默认：输出为 JSON 数组，元素之间以 `,\r\n` 分隔
+fn stream_response(body: Body, model: &str, use_sse: bool) -> Response {
+    let mut upstream = body.into_data_stream();
+    let mut converter = GeminiStreamConverter::new(model);
+
+    let stream = async_stream::stream! {
+        let mut buffer = String::new();
+        let mut emitted = 0usize;
+
+        while let Some(result) = upstream.next().await {
+            let bytes = match result {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::error!("[GEMINI] 读取上游流失败: {}", e);
+                    break;
+                }
+            };
+            buffer.push_str(&String::from_utf8_lossy(&bytes));
+
+            while let Some(newline_pos) = buffer.find('\n') {
+                let line = buffer[..newline_pos].trim().to_string();
+                buffer.drain(..=newline_pos);
+
+                let Some(data) = line.strip_prefix("data:").map(|d| d.trim()) else {
+                    continue;
+                };
+                if data.is_empty() || data == "[DONE]" {
+                    continue;
+                }
+                let Ok(chunk) = serde_json::from_str::<serde_json::Value>(data) else {
+                    continue;
+                };
+                if let Some(converted) = converter.convert_chunk(&chunk) {
+                    yield Ok::<_, std::io::Error>(stream_frame(&converted, use_sse, emitted == 0));
+                    emitted += 1;
+                }
+            }
+        }
+
+        if let Some(converted) = converter.finish() {
+            yield Ok(stream_frame(&converted, use_sse, emitted == 0));
+            emitted += 1;
+        }
+        if !use_sse {
+            let tail = if emitted == 0 { "[]" } else { "]" };
+            yield Ok(bytes::Bytes::from(tail));
+        }
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, stream_content_type(use_sse))
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(Body::from_stream(stream))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_auth_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-goog-api-key", HeaderValue::from_static("secret"));
+        let normalized = normalize_auth_headers(&headers, None);
+        assert_eq!(
+            normalized.get(header::AUTHORIZATION).unwrap(),
+            "Bearer secret"
+        );
+
+        let normalized = normalize_auth_headers(&HeaderMap::new(), Some("query-key"));
+        assert_eq!(
+            normalized.get(header::AUTHORIZATION).unwrap(),
+            "Bearer query-key"
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer main"),
+        );
+        let normalized = normalize_auth_headers(&headers, Some("query-key"));
+        assert_eq!(
+            normalized.get(header::AUTHORIZATION).unwrap(),
+            "Bearer main"
+        );
+    }
+
+    #[test]
+    fn test_extract_error_message() {
+        let body = br#"{"error":{"message":"No API key provided","type":"authentication_error"}}"#;
+        assert_eq!(extract_error_message(body), "No API key provided");
+        assert_eq!(extract_error_message(b"plain"), "plain");
+    }
+}
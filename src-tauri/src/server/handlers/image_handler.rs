@@ -29,7 +29,10 @@ use crate::converter::openai_to_antigravity::{
 use crate::models::openai::ImageGenerationRequest;
 use crate::models::provider_pool_model::CredentialData;
 use crate::providers::AntigravityProvider;
-use crate::server::handlers::verify_api_key;
+use crate::server::error::ProxyApiError;
+use crate::server::handlers::{
+    use_shared_client, verify_api_key, verify_client_scope, ApiErrorFormat,
+};
 use crate::server::AppState;
 
 /// 处理图像生成请求
@@ -66,23 +69,29 @@ pub async fn handle_image_generation(
     Json(request): Json<ImageGenerationRequest>,
 ) -> Response {
     // 验证 API Key
-    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+    let client_key = match verify_api_key(&headers, &state).await {
+        Ok(client_key) => client_key,
+        Err(e) => return e.into_response(),
+    };
+
+    // 校验客户端 Key 的模型和 Provider 作用域（图像生成固定使用 Antigravity 凭证）
+    if let Err(e) = verify_client_scope(
+        client_key.as_ref(),
+        Some(&request.model),
+        Some("antigravity"),
+        ApiErrorFormat::OpenAI,
+    ) {
         return e.into_response();
     }
 
     // 验证请求参数
     if request.prompt.trim().is_empty() {
-        return (
+        return ProxyApiError::from_status(
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": {
-                    "message": "prompt is required and cannot be empty",
-                    "type": "invalid_request_error",
-                    "code": "invalid_prompt"
-                }
-            })),
+            "prompt is required and cannot be empty",
         )
-            .into_response();
+        .with_code("invalid_prompt")
+        .into_response();
     }
 
     // 记录请求日志
@@ -105,16 +114,11 @@ pub async fn handle_image_generation(
     let db = match &state.db {
         Some(db) => db,
         None => {
-            return (
+            return ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": "Database not available",
-                        "type": "server_error"
-                    }
-                })),
+                "Database not available",
             )
-                .into_response();
+            .into_response();
         }
     };
 
@@ -130,17 +134,12 @@ pub async fn handle_image_generation(
                 .write()
                 .await
                 .add("error", "[IMAGE] 没有可用的 Antigravity 凭证");
-            return (
+            return ProxyApiError::from_status(
                 StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": "No Antigravity credentials available for image generation",
-                        "type": "server_error",
-                        "code": "no_credentials"
-                    }
-                })),
+                "No Antigravity credentials available for image generation",
             )
-                .into_response();
+            .with_code("no_credentials")
+            .into_response();
         }
         Err(e) => {
             state
@@ -148,16 +147,11 @@ pub async fn handle_image_generation(
                 .write()
                 .await
                 .add("error", &format!("[IMAGE] 获取凭证失败: {}", e));
-            return (
+            return ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("Failed to get credentials: {}", e),
-                        "type": "server_error"
-                    }
-                })),
+                format!("Failed to get credentials: {}", e),
             )
-                .into_response();
+            .into_response();
         }
     };
 
@@ -173,21 +167,17 @@ pub async fn handle_image_generation(
                 .write()
                 .await
                 .add("error", "[IMAGE] 选中的凭证不是 Antigravity 类型");
-            return (
+            return ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": "Selected credential is not Antigravity type",
-                        "type": "server_error"
-                    }
-                })),
+                "Selected credential is not Antigravity type",
             )
-                .into_response();
+            .into_response();
         }
     };
 
     // 创建 Antigravity Provider
     let mut antigravity = AntigravityProvider::new();
+    use_shared_client(&state, &credential, &mut antigravity.client);
     if let Err(e) = antigravity
         .load_credentials_from_path(&creds_file_path)
         .await
@@ -197,16 +187,11 @@ pub async fn handle_image_generation(
             &credential.uuid,
             Some(&format!("Failed to load credentials: {}", e)),
         );
-        return (
+        return ProxyApiError::from_status(
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({
-                "error": {
-                    "message": format!("Failed to load Antigravity credentials: {}", e),
-                    "type": "server_error"
-                }
-            })),
+            format!("Failed to load Antigravity credentials: {}", e),
         )
-            .into_response();
+        .into_response();
     }
 
     // 验证并刷新 Token
@@ -228,16 +213,7 @@ pub async fn handle_image_generation(
                     refresh_error.user_message(),
                 )
             };
-            return (
-                status,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": message,
-                        "type": "authentication_error"
-                    }
-                })),
-            )
-                .into_response();
+            return ProxyApiError::from_status(status, message).into_response();
         }
     }
 
@@ -313,16 +289,8 @@ pub async fn handle_image_generation(
                         .write()
                         .await
                         .add("error", &format!("[IMAGE] 响应转换失败: {}", e));
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({
-                            "error": {
-                                "message": e,
-                                "type": "server_error",
-                                "code": "image_generation_failed"
-                            }
-                        })),
-                    )
+                    ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e)
+                        .with_code("image_generation_failed")
                         .into_response()
                 }
             }
@@ -336,17 +304,12 @@ pub async fn handle_image_generation(
                 .write()
                 .await
                 .add("error", &format!("[IMAGE] Antigravity API 调用失败: {}", e));
-            (
+            ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("Image generation failed: {}", e),
-                        "type": "server_error",
-                        "code": "api_error"
-                    }
-                })),
+                format!("Image generation failed: {}", e),
             )
-                .into_response()
+            .with_code("api_error")
+            .into_response()
         }
     }
 }
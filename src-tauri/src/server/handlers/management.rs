@@ -4,16 +4,26 @@
 
 #![allow(dead_code)]
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::dao::request_logs::RequestLogQuery;
 use crate::server::AppState;
+use crate::services::client_api_key_service::{
+    CreateClientApiKeyRequest, UpdateClientApiKeyRequest,
+};
 
 // ============ Types ============
 
 /// 管理 API 状态响应
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ManagementStatusResponse {
     /// 服务器是否运行中
     pub running: bool,
@@ -34,7 +44,7 @@ pub struct ManagementStatusResponse {
 }
 
 /// 凭证信息（用于列表显示）
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CredentialInfo {
     /// 凭证 ID
     pub id: String,
@@ -47,7 +57,7 @@ pub struct CredentialInfo {
 }
 
 /// 凭证列表响应
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CredentialsListResponse {
     /// 凭证列表
     pub credentials: Vec<CredentialInfo>,
@@ -56,7 +66,7 @@ pub struct CredentialsListResponse {
 }
 
 /// 添加凭证请求
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct AddCredentialRequest {
     /// Provider 类型
     pub provider_type: String,
@@ -77,7 +87,7 @@ pub struct AddCredentialRequest {
 }
 
 /// 添加凭证响应
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AddCredentialResponse {
     /// 是否成功
     pub success: bool,
@@ -88,7 +98,7 @@ pub struct AddCredentialResponse {
 }
 
 /// 配置响应（简化版，不包含敏感信息）
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ManagementConfigResponse {
     /// 服务器配置
     pub server: ManagementServerConfigInfo,
@@ -101,7 +111,7 @@ pub struct ManagementConfigResponse {
 }
 
 /// 服务器配置信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ManagementServerConfigInfo {
     pub host: String,
     pub port: u16,
@@ -109,14 +119,14 @@ pub struct ManagementServerConfigInfo {
 }
 
 /// 路由配置信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ManagementRoutingConfigInfo {
     pub default_provider: String,
     pub rules_count: usize,
 }
 
 /// 重试配置信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ManagementRetryConfigInfo {
     pub max_retries: u32,
     pub base_delay_ms: u64,
@@ -124,7 +134,7 @@ pub struct ManagementRetryConfigInfo {
 }
 
 /// 远程管理配置信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ManagementRemoteInfo {
     pub allow_remote: bool,
     pub has_secret_key: bool,
@@ -132,7 +142,7 @@ pub struct ManagementRemoteInfo {
 }
 
 /// 更新配置请求
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct UpdateConfigRequest {
     /// 默认 Provider
     #[serde(default)]
@@ -143,7 +153,7 @@ pub struct UpdateConfigRequest {
 }
 
 /// 更新配置响应
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateConfigResponse {
     pub success: bool,
     pub message: String,
@@ -152,6 +162,9 @@ pub struct UpdateConfigResponse {
 // ============ Handlers ============
 
 /// GET /v0/management/status - 获取服务器状态
+#[utoipa::path(get, path = "/v0/management/status", tag = "management",
+    responses((status = 200, body = ManagementStatusResponse)),
+    security(("management_key" = []), ("bearer" = [])))]
 pub async fn management_status(State(state): State<AppState>) -> impl IntoResponse {
     let default_provider = state.default_provider.read().await.clone();
 
@@ -165,7 +178,7 @@ pub async fn management_status(State(state): State<AppState>) -> impl IntoRespon
         requests,
         uptime_secs: 0, // TODO: Track actual uptime
         version: env!("CARGO_PKG_VERSION").to_string(),
-        tls_enabled: false,
+        tls_enabled: state.base_url.starts_with("https:"),
         default_provider,
     };
 
@@ -173,6 +186,9 @@ pub async fn management_status(State(state): State<AppState>) -> impl IntoRespon
 }
 
 /// GET /v0/management/credentials - 获取凭证列表
+#[utoipa::path(get, path = "/v0/management/credentials", tag = "management",
+    responses((status = 200, body = CredentialsListResponse)),
+    security(("management_key" = []), ("bearer" = [])))]
 pub async fn management_list_credentials(State(state): State<AppState>) -> impl IntoResponse {
     let mut credentials = Vec::new();
 
@@ -197,6 +213,13 @@ pub async fn management_list_credentials(State(state): State<AppState>) -> impl
 }
 
 /// POST /v0/management/credentials - 添加凭证
+#[utoipa::path(post, path = "/v0/management/credentials", tag = "management",
+    request_body = AddCredentialRequest,
+    responses(
+        (status = 200, body = AddCredentialResponse),
+        (status = 400, body = AddCredentialResponse),
+    ),
+    security(("management_key" = []), ("bearer" = [])))]
 pub async fn management_add_credential(
     State(state): State<AppState>,
     Json(request): Json<AddCredentialRequest>,
@@ -398,6 +421,22 @@ pub async fn management_add_credential(
                 );
             }
         }
+        PoolProviderType::Qwen => {
+            if let Some(token_file) = request.token_file {
+                CredentialData::QwenOAuth {
+                    creds_file_path: token_file,
+                }
+            } else {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(AddCredentialResponse {
+                        success: false,
+                        message: "Token file is required for Qwen provider".to_string(),
+                        id: None,
+                    }),
+                );
+            }
+        }
         // Anthropic API Key Provider
         PoolProviderType::Anthropic => {
             if let Some(api_key) = request.api_key {
@@ -435,6 +474,13 @@ pub async fn management_add_credential(
                 );
             }
         }
+        // Mock 凭证使用默认响应，模板、延迟和错误注入可在凭证池中编辑
+        PoolProviderType::Mock => CredentialData::Mock {
+            response: None,
+            latency_ms: None,
+            error_rate: None,
+            error_status: None,
+        },
         // API Key Provider 类型 - 不支持通过此接口添加凭证
         PoolProviderType::AzureOpenai | PoolProviderType::AwsBedrock | PoolProviderType::Ollama => {
             return (
@@ -500,6 +546,9 @@ pub async fn management_add_credential(
 }
 
 /// GET /v0/management/config - 获取配置
+#[utoipa::path(get, path = "/v0/management/config", tag = "management",
+    responses((status = 200, body = ManagementConfigResponse)),
+    security(("management_key" = []), ("bearer" = [])))]
 pub async fn management_get_config(State(state): State<AppState>) -> impl IntoResponse {
     let default_provider = state.default_provider.read().await.clone();
 
@@ -507,7 +556,7 @@ pub async fn management_get_config(State(state): State<AppState>) -> impl IntoRe
         server: ManagementServerConfigInfo {
             host: "0.0.0.0".to_string(),
             port: 8999,
-            tls_enabled: false,
+            tls_enabled: state.base_url.starts_with("https:"),
         },
         routing: ManagementRoutingConfigInfo {
             default_provider,
@@ -529,6 +578,13 @@ pub async fn management_get_config(State(state): State<AppState>) -> impl IntoRe
 }
 
 /// PUT /v0/management/config - 更新配置
+#[utoipa::path(put, path = "/v0/management/config", tag = "management",
+    request_body = UpdateConfigRequest,
+    responses(
+        (status = 200, body = UpdateConfigResponse),
+        (status = 400, body = UpdateConfigResponse),
+    ),
+    security(("management_key" = []), ("bearer" = [])))]
 pub async fn management_update_config(
     State(state): State<AppState>,
     Json(request): Json<UpdateConfigRequest>,
@@ -572,3 +628,292 @@ pub async fn management_update_config(
         )
     }
 }
+
+// ============ 客户端 API Key ============
+
+/// 管理操作失败时的响应体
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ManagementErrorResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+/// 数据库相关管理操作失败时的响应
+fn management_error(status: StatusCode, message: String) -> Response {
+    (
+        status,
+        Json(ManagementErrorResponse {
+            success: false,
+            message,
+        }),
+    )
+        .into_response()
+}
+
+fn database_unavailable() -> Response {
+    management_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Database not available".to_string(),
+    )
+}
+
+/// GET /v0/management/api-keys - 获取客户端 API Key 列表
+#[utoipa::path(get, path = "/v0/management/api-keys", tag = "management",
+    responses(
+        (status = 200, description = "客户端 API Key 列表（不含明文 Key）", body = [Object]),
+        (status = 503, description = "数据库不可用", body = ManagementErrorResponse),
+    ),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_list_api_keys(State(state): State<AppState>) -> Response {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    match state.client_keys.list(db) {
+        Ok(keys) => Json(keys).into_response(),
+        Err(e) => management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// POST /v0/management/api-keys - 创建客户端 API Key（明文 Key 仅返回一次）
+#[utoipa::path(post, path = "/v0/management/api-keys", tag = "management",
+    request_body = CreateClientApiKeyRequest,
+    responses(
+        (status = 201, description = "创建成功，`api_key` 为明文 Key，仅返回一次", body = Object),
+        (status = 400, body = ManagementErrorResponse),
+    ),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_create_api_key(
+    State(state): State<AppState>,
+    Json(request): Json<CreateClientApiKeyRequest>,
+) -> Response {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    match state.client_keys.create(db, request) {
+        Ok(created) => {
+            tracing::info!("[MANAGEMENT] Created client API key: {}", created.key.id);
+            (StatusCode::CREATED, Json(created)).into_response()
+        }
+        Err(e) => management_error(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+/// PUT /v0/management/api-keys/{id} - 更新客户端 API Key
+#[utoipa::path(put, path = "/v0/management/api-keys/{id}", tag = "management",
+    params(("id" = String, Path, description = "客户端 API Key ID")),
+    request_body = UpdateClientApiKeyRequest,
+    responses(
+        (status = 200, description = "更新后的客户端 API Key", body = Object),
+        (status = 400, body = ManagementErrorResponse),
+    ),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_update_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateClientApiKeyRequest>,
+) -> Response {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    match state.client_keys.update(db, &id, request) {
+        Ok(key) => Json(key).into_response(),
+        Err(e) => management_error(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+/// DELETE /v0/management/api-keys/{id} - 删除客户端 API Key
+#[utoipa::path(delete, path = "/v0/management/api-keys/{id}", tag = "management",
+    params(("id" = String, Path, description = "客户端 API Key ID")),
+    responses(
+        (status = 204, description = "已删除"),
+        (status = 404, body = ManagementErrorResponse),
+    ),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_delete_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    match state.client_keys.delete(db, &id) {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => management_error(
+            StatusCode::NOT_FOUND,
+            format!("Client API key not found: {}", id),
+        ),
+        Err(e) => management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+// ============ 审计日志 ============
+
+/// 审计日志列表查询参数
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    #[serde(default = "default_audit_log_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+fn default_audit_log_limit() -> usize {
+    50
+}
+
+/// GET /v0/management/audit-logs - 获取审计日志列表（不含请求体/响应体）
+#[utoipa::path(get, path = "/v0/management/audit-logs", tag = "management",
+    params(AuditLogQuery),
+    responses((status = 200, description = "审计日志列表（不含请求体/响应体）", body = [Object])),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_list_audit_logs(
+    State(state): State<AppState>,
+    Query(query): Query<AuditLogQuery>,
+) -> Response {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    match state.audit.list(db, query.limit.min(500), query.offset) {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// GET /v0/management/audit-logs/{request_id} - 获取单个请求的审计日志
+#[utoipa::path(get, path = "/v0/management/audit-logs/{request_id}", tag = "management",
+    params(("request_id" = String, Path, description = "请求 ID")),
+    responses(
+        (status = 200, description = "审计日志详情", body = Object),
+        (status = 404, body = ManagementErrorResponse),
+    ),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_get_audit_log(
+    State(state): State<AppState>,
+    Path(request_id): Path<String>,
+) -> Response {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    match state.audit.get(db, &request_id) {
+        Ok(Some(entry)) => Json(entry).into_response(),
+        Ok(None) => management_error(
+            StatusCode::NOT_FOUND,
+            format!("Audit log not found: {}", request_id),
+        ),
+        Err(e) => management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// DELETE /v0/management/audit-logs - 清空审计日志
+#[utoipa::path(delete, path = "/v0/management/audit-logs", tag = "management",
+    responses((status = 204, description = "已清空")),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_clear_audit_logs(State(state): State<AppState>) -> Response {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    match state.audit.clear(db) {
+        Ok(removed) => {
+            tracing::info!("[MANAGEMENT] Cleared {} audit log entries", removed);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+// ============ 请求日志 ============
+
+/// GET /v0/management/request-logs - 检索持久化的请求日志
+///
+/// 支持按 provider / model / status / credential_id / client_key_id / start / end 过滤，
+/// `search` 对错误信息做全文搜索，`limit` / `offset` 分页。
+/// 已连接共享存储时检索所有实例的请求日志
+#[utoipa::path(get, path = "/v0/management/request-logs", tag = "management",
+    params(
+        ("provider" = Option<String>, Query, description = "Provider 类型"),
+        ("model" = Option<String>, Query),
+        ("status" = Option<String>, Query, description = "请求状态，如 success、failed、timeout"),
+        ("credential_id" = Option<String>, Query),
+        ("client_key_id" = Option<String>, Query),
+        ("client_name" = Option<String>, Query, description = "调用方工具名称"),
+        ("start" = Option<String>, Query, description = "开始时间（RFC 3339，包含）"),
+        ("end" = Option<String>, Query, description = "结束时间（RFC 3339，包含）"),
+        ("search" = Option<String>, Query, description = "错误信息全文搜索关键词"),
+        ("limit" = Option<usize>, Query, description = "每页条数，默认 50"),
+        ("offset" = Option<usize>, Query),
+    ),
+    responses((status = 200, description = "请求日志分页结果", body = Object)),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_search_request_logs(
+    State(state): State<AppState>,
+    Query(query): Query<RequestLogQuery>,
+) -> Response {
+    if let Some(shared) = state.shared_storage.backend() {
+        return match shared.search_request_logs(&query).await {
+            Ok(page) => Json(page).into_response(),
+            Err(e) => management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        };
+    }
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    match state.request_logs.search(db, &query).await {
+        Ok(page) => Json(page).into_response(),
+        Err(e) => management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// GET /v0/management/request-logs/{id} - 获取单条持久化的请求日志
+#[utoipa::path(get, path = "/v0/management/request-logs/{id}", tag = "management",
+    params(("id" = String, Path, description = "请求日志 ID")),
+    responses(
+        (status = 200, description = "请求日志详情", body = Object),
+        (status = 404, body = ManagementErrorResponse),
+    ),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_get_request_log(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    let result = match (state.shared_storage.backend(), &state.db) {
+        (Some(shared), _) => shared.get_request_log(&id).await,
+        (None, Some(db)) => state.request_logs.get(db, &id).await,
+        (None, None) => return database_unavailable(),
+    };
+    match result {
+        Ok(Some(log)) => Json(log).into_response(),
+        Ok(None) => management_error(
+            StatusCode::NOT_FOUND,
+            format!("Request log not found: {}", id),
+        ),
+        Err(e) => management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// DELETE /v0/management/request-logs - 清空持久化的请求日志
+///
+/// 已连接共享存储时同时清空共享库中所有实例的请求日志
+#[utoipa::path(delete, path = "/v0/management/request-logs", tag = "management",
+    responses((status = 204, description = "已清空")),
+    security(("management_key" = []), ("bearer" = [])))]
+pub async fn management_clear_request_logs(State(state): State<AppState>) -> Response {
+    let Some(db) = &state.db else {
+        return database_unavailable();
+    };
+    if let Some(shared) = state.shared_storage.backend() {
+        match shared.clear_request_logs().await {
+            Ok(removed) => {
+                tracing::info!("[MANAGEMENT] Cleared {} shared request logs", removed)
+            }
+            Err(e) => return management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        }
+    }
+    match state.request_logs.clear(db).await {
+        Ok(removed) => {
+            tracing::info!("[MANAGEMENT] Cleared {} persisted request logs", removed);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => management_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
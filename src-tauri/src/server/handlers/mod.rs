@@ -3,17 +3,27 @@
 //! 将 server 中的各类处理器拆分到独立文件
 
 pub mod api;
+pub mod audio_handler;
+pub mod cache_handler;
 pub mod credentials_api;
+pub mod embeddings_handler;
+pub mod gemini_handler;
 pub mod image_handler;
 pub mod kiro_credential;
 pub mod management;
 pub mod provider_calls;
+pub mod terminal_share;
 pub mod websocket;
 
 pub use api::*;
+pub use audio_handler::*;
+pub use cache_handler::*;
 pub use credentials_api::*;
+pub use embeddings_handler::*;
+pub use gemini_handler::*;
 pub use image_handler::*;
 pub use kiro_credential::*;
 pub use management::*;
 pub use provider_calls::*;
+pub use terminal_share::*;
 pub use websocket::*;
@@ -48,25 +48,47 @@ use axum::{
     Json,
 };
 use futures::StreamExt;
+use std::future::Future;
 
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use crate::converter::gemini_tools::function_call_to_openai;
 use crate::converter::openai_to_antigravity::{
     convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
+    convert_openai_to_gemini,
 };
+use crate::converter::reasoning::{apply_reasoning_to_openai_response, ReasoningMode};
+use crate::converter::response_format::{forces_json_tool, ResponseFormat};
 use crate::flow_monitor::models::{FlowError, FlowErrorType};
 use crate::flow_monitor::stream_rebuilder::StreamFormat;
+use crate::injection::with_injected_headers;
 use crate::models::anthropic::AnthropicMessagesRequest;
-use crate::models::openai::ChatCompletionRequest;
-use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::models::openai::{ChatCompletionRequest, ChatCompletionResponse};
+use crate::models::provider_pool_model::{
+    resolve_azure_deployment, CredentialData, ProviderCredential,
+};
+use crate::processor::{current_request_id, RequestContext};
+use crate::providers::azure_openai::AzureOpenAIProvider;
+use crate::providers::bedrock::{self, BedrockConfig};
+use crate::providers::mock::{self, MockProvider};
+use crate::providers::ollama::OllamaProvider;
+use crate::providers::vertex_service_account::VertexServiceAccountProvider;
 use crate::providers::{
-    AntigravityApiError, AntigravityProvider, ClaudeCustomProvider, CodexProvider, KiroProvider,
-    OpenAICustomProvider, VertexProvider,
+    AntigravityApiError, AntigravityProvider, ClaudeCustomProvider, CodexProvider,
+    GeminiApiKeyCredential, GeminiApiKeyProvider, KiroProvider, OpenAICustomProvider, QwenProvider,
+    VertexProvider,
+};
+use crate::resilience::{
+    parse_retry_after, ChaosFault, ConcurrencyPermit, HedgeWinner, Hedger, UpstreamErrorKind,
+    MALFORMED_SSE_FRAME,
 };
-use crate::server::AppState;
+use crate::server::client_detector::ClientType;
+use crate::server::error::{ProxyApiError, ProxyErrorKind};
+use crate::server::{record_request_telemetry, token_count, AppState};
 use crate::server_utils::{
     build_anthropic_response, build_anthropic_stream_response, build_error_response,
     build_error_response_with_status, parse_cw_response, safe_truncate, CWParsedResponse,
 };
+use crate::services::credential_load_balancer::InFlightGuard;
 use crate::session::store_thought_signature;
 use crate::stream::{PipelineConfig, StreamPipeline};
 use crate::streaming::traits::StreamingProvider;
@@ -75,6 +97,99 @@ use crate::streaming::{
     StreamResponse,
 };
 
+/// 透传给 Anthropic 上游的客户端请求头
+const FORWARDED_ANTHROPIC_HEADERS: &[&str] = &["anthropic-version", "anthropic-beta"];
+
+/// 流式透传时不转发的上游响应头（由本地连接重新协商）
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "content-length",
+    "content-encoding",
+    "keep-alive",
+    "transfer-encoding",
+];
+
+tokio::task_local! {
+    /// 当前请求中需要透传给 Anthropic 上游的客户端请求头
+    static CLIENT_ANTHROPIC_HEADERS: reqwest::header::HeaderMap;
+}
+
+/// 在透传客户端 Anthropic 请求头的作用域内执行
+pub async fn with_client_anthropic_headers<F: Future>(
+    headers: &axum::http::HeaderMap,
+    fut: F,
+) -> F::Output {
+    let mut forwarded = reqwest::header::HeaderMap::new();
+    for name in FORWARDED_ANTHROPIC_HEADERS {
+        for value in headers.get_all(*name) {
+            forwarded.append(*name, value.clone());
+        }
+    }
+    CLIENT_ANTHROPIC_HEADERS.scope(forwarded, fut).await
+}
+
+/// 当前作用域内需要透传的客户端请求头（不在作用域内时为空）
+fn client_anthropic_headers() -> reqwest::header::HeaderMap {
+    CLIENT_ANTHROPIC_HEADERS
+        .try_with(|headers| headers.clone())
+        .unwrap_or_default()
+}
+
+/// 原样透传上游 SSE 流
+///
+/// 保留上游状态码和响应头（如 `request-id`、`anthropic-ratelimit-*`），
+/// 事件帧按字节转发，不做解析和重组
+fn passthrough_sse_response(resp: reqwest::Response) -> Response {
+    let status = resp.status();
+    let mut headers = resp.headers().clone();
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(*name);
+    }
+    headers
+        .entry(header::CACHE_CONTROL)
+        .or_insert(header::HeaderValue::from_static("no-cache"));
+    // 禁用 nginx 等代理的缓冲
+    headers.insert("X-Accel-Buffering", header::HeaderValue::from_static("no"));
+
+    let mut response = Body::from_stream(resp.bytes_stream()).into_response();
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    response
+}
+
+/// 计算本次 Provider 调用需要注入的上游请求头
+async fn injected_headers_for<T: serde::Serialize>(
+    state: &AppState,
+    model: &str,
+    request: &T,
+    credential: &ProviderCredential,
+) -> reqwest::header::HeaderMap {
+    if !*state.injection_enabled.read().await {
+        return reqwest::header::HeaderMap::new();
+    }
+    let provider = credential.provider_type.to_string();
+    let payload = serde_json::to_value(request).unwrap_or_default();
+    let result = state
+        .processor
+        .injector
+        .read()
+        .await
+        .inject_headers(model, &provider, &payload);
+    if result.has_injections() {
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[INJECT] request_id={} provider={} applied_rules={:?} injected_headers={:?}",
+                current_request_id().unwrap_or_default(),
+                provider,
+                result.applied_rules,
+                result.header_names()
+            ),
+        );
+    }
+    result.headers
+}
+
 /// 根据凭证调用 Provider (Anthropic 格式)
 ///
 /// # 参数
@@ -82,11 +197,51 @@ use crate::streaming::{
 /// - `credential`: 凭证信息
 /// - `request`: Anthropic 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
+#[tracing::instrument(
+    name = "provider.call",
+    skip_all,
+    fields(
+        otel.kind = "client",
+        provider = %credential.provider_type,
+        credential.id = %credential.uuid,
+        model = %request.model,
+        stream = request.stream,
+        protocol = "anthropic",
+    )
+)]
 pub async fn call_provider_anthropic(
     state: &AppState,
     credential: &ProviderCredential,
     request: &AnthropicMessagesRequest,
     flow_id: Option<&str>,
+) -> Response {
+    let headers = injected_headers_for(state, &request.model, request, credential).await;
+    with_injected_headers(
+        headers,
+        dispatch_provider_anthropic(state, credential, request, flow_id),
+    )
+    .await
+}
+
+/// 使用该 Provider 共享的上游客户端（复用连接池，按凭证 > Provider > 全局选择出站代理）
+pub(crate) fn use_shared_client(
+    state: &AppState,
+    credential: &ProviderCredential,
+    client: &mut reqwest::Client,
+) {
+    state.http_clients.apply(
+        client,
+        &credential.provider_type.to_string(),
+        credential.proxy_url.as_deref(),
+    );
+}
+
+/// 按凭证类型分发 Anthropic 格式请求
+async fn dispatch_provider_anthropic(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &AnthropicMessagesRequest,
+    flow_id: Option<&str>,
 ) -> Response {
     // 如果是流式请求且有 flow_id，设置流式状态
     if request.stream {
@@ -115,11 +270,11 @@ pub async fn call_provider_anthropic(
             let db = match &state.db {
                 Some(db) => db,
                 None => {
-                    return (
+                    return ProxyApiError::from_status(
                         StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": "Database not available"}})),
+                        "Database not available",
                     )
-                        .into_response();
+                    .into_response();
                 }
             };
             // 获取缓存的 token
@@ -133,6 +288,7 @@ pub async fn call_provider_anthropic(
                     tracing::warn!("[POOL] Token cache miss, loading from source: {}", e);
                     // 回退到从源文件加载
                     let mut kiro = KiroProvider::new();
+                    use_shared_client(state, credential, &mut kiro.client);
                     if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
                         // 记录凭证加载失败
                         let _ = state.pool_service.mark_unhealthy(
@@ -140,11 +296,11 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&format!("Failed to load credentials: {}", e)),
                         );
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
+                            format!("Failed to load Kiro credentials: {}", e),
                         )
-                            .into_response();
+                        .into_response();
                     }
                     if let Err(e) = kiro.refresh_token().await {
                         // 记录 Token 刷新失败
@@ -153,17 +309,18 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {}", e)),
                         );
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
+                            format!("Token refresh failed: {}", e),
                         )
-                            .into_response();
+                        .into_response();
                     }
                     kiro.credentials.access_token.unwrap_or_default()
                 }
             };
             // 使用获取到的 token 创建 KiroProvider
             let mut kiro = KiroProvider::new();
+            use_shared_client(state, credential, &mut kiro.client);
             // 从源文件加载其他配置（region, profile_arn 等）
             // 注意：必须先加载凭证文件，再设置 token，因为 load_credentials_from_path 会覆盖整个 credentials
             let _ = kiro.load_credentials_from_path(creds_file_path).await;
@@ -179,19 +336,18 @@ pub async fn call_provider_anthropic(
                         &credential.uuid,
                         Some(&e.to_string()),
                     );
-                    return (
+                    return ProxyApiError::from_status(
                         StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                        e.to_string(),
                     )
-                        .into_response();
+                    .into_response();
                 }
             };
             let status = resp.status();
             if status.is_success() {
                 match resp.bytes().await {
                     Ok(bytes) => {
-                        let body = String::from_utf8_lossy(&bytes).to_string();
-                        let parsed = parse_cw_response(&body);
+                        let parsed = parse_cw_response(&bytes);
                         // 记录成功
                         let _ = state.pool_service.mark_healthy(
                             db,
@@ -208,10 +364,7 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&e.to_string()),
                         );
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
+                        ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                             .into_response()
                     }
                 }
@@ -235,11 +388,11 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {}", e)),
                         );
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
+                            format!("Token refresh failed: {}", e),
                         )
-                            .into_response();
+                        .into_response();
                     }
                 };
                 // 使用新 token 重试
@@ -249,8 +402,7 @@ pub async fn call_provider_anthropic(
                         if retry_resp.status().is_success() {
                             match retry_resp.bytes().await {
                                 Ok(bytes) => {
-                                    let body = String::from_utf8_lossy(&bytes).to_string();
-                                    let parsed = parse_cw_response(&body);
+                                    let parsed = parse_cw_response(&bytes);
                                     // 记录重试成功
                                     let _ = state.pool_service.mark_healthy(
                                         db,
@@ -267,11 +419,11 @@ pub async fn call_provider_anthropic(
                                         &credential.uuid,
                                         Some(&e.to_string()),
                                     );
-                                    (
+                                    ProxyApiError::from_status(
                                         StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                                        e.to_string(),
                                     )
-                                        .into_response()
+                                    .into_response()
                                 }
                             }
                         } else {
@@ -281,11 +433,11 @@ pub async fn call_provider_anthropic(
                                 &credential.uuid,
                                 Some(&format!("Retry failed: {}", body)),
                             );
-                            (
+                            ProxyApiError::from_status(
                                 StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": format!("Retry failed: {}", body)}})),
+                                format!("Retry failed: {}", body),
                             )
-                                .into_response()
+                            .into_response()
                         }
                     }
                     Err(e) => {
@@ -294,17 +446,19 @@ pub async fn call_provider_anthropic(
                             &credential.uuid,
                             Some(&e.to_string()),
                         );
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
+                        ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                             .into_response()
                     }
                 }
             } else {
                 let status_code = status.as_u16();
+                let headers = resp.headers().clone();
                 let body = resp.text().await.unwrap_or_default();
-                eprintln!("[PROVIDER_CALL] Kiro 请求失败: status={} body={}", status_code, &body[..body.len().min(500)]);
+                eprintln!(
+                    "[PROVIDER_CALL] Kiro 请求失败: status={} body={}",
+                    status_code,
+                    &body[..body.len().min(500)]
+                );
                 // 只有 5xx 错误才标记为不健康
                 if status_code >= 500 {
                     let _ = state
@@ -312,26 +466,23 @@ pub async fn call_provider_anthropic(
                         .mark_unhealthy(db, &credential.uuid, Some(&body));
                 }
                 // 转发上游的实际状态码
-                (
-                    StatusCode::from_u16(status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                    Json(serde_json::json!({"error": {"message": body}})),
-                )
-                    .into_response()
+                ProxyApiError::upstream_with_headers(status_code, &headers, body).into_response()
             }
         }
         CredentialData::GeminiOAuth { .. } => {
             // Gemini OAuth 路由暂不支持
-            (
+            ProxyApiError::from_status(
                 StatusCode::NOT_IMPLEMENTED,
-                Json(serde_json::json!({"error": {"message": "Gemini OAuth routing not yet implemented. Use /v1/messages with Gemini models instead."}})),
+                "Gemini OAuth routing not yet implemented. Use /v1/messages with Gemini models instead.",
             )
-                .into_response()
+            .into_response()
         }
         CredentialData::AntigravityOAuth {
             creds_file_path,
             project_id,
         } => {
             let mut antigravity = AntigravityProvider::new();
+            use_shared_client(state, credential, &mut antigravity.client);
             if let Err(e) = antigravity
                 .load_credentials_from_path(creds_file_path)
                 .await
@@ -344,11 +495,11 @@ pub async fn call_provider_anthropic(
                         Some(&format!("Failed to load credentials: {}", e)),
                     );
                 }
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Antigravity credentials: {}", e)}})),
+                    format!("Failed to load Antigravity credentials: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
 
             // 使用新的 validate_token() 方法检查 Token 状态
@@ -360,14 +511,13 @@ pub async fn call_provider_anthropic(
                 tracing::info!("[Antigravity] Token 需要刷新，开始刷新...");
                 match antigravity.refresh_token_with_retry(3).await {
                     Ok(new_token) => {
-                        tracing::info!("[Antigravity] Token 刷新成功，新 token 长度: {}", new_token.len());
+                        tracing::info!(
+                            "[Antigravity] Token 刷新成功，新 token 长度: {}",
+                            new_token.len()
+                        );
                         // 刷新成功，标记为健康
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(
-                                db,
-                                &credential.uuid,
-                                None,
-                            );
+                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, None);
                         }
                     }
                     Err(refresh_error) => {
@@ -385,14 +535,13 @@ pub async fn call_provider_anthropic(
                         let (status, message) = if refresh_error.requires_reauth() {
                             (StatusCode::UNAUTHORIZED, refresh_error.user_message())
                         } else {
-                            (StatusCode::INTERNAL_SERVER_ERROR, refresh_error.user_message())
+                            (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                refresh_error.user_message(),
+                            )
                         };
 
-                        return (
-                            status,
-                            Json(serde_json::json!({"error": {"message": message}})),
-                        )
-                            .into_response();
+                        return ProxyApiError::from_status(status, message).into_response();
                     }
                 }
             }
@@ -407,22 +556,17 @@ pub async fn call_provider_anthropic(
             let proj_id = antigravity.project_id.clone().unwrap_or_default();
             // 先转换为 OpenAI 格式，再转换为 Antigravity 格式
             let openai_request = convert_anthropic_to_openai(request);
-            let antigravity_request = convert_openai_to_antigravity_with_context(&openai_request, &proj_id);
+            let antigravity_request =
+                convert_openai_to_antigravity_with_context(&openai_request, &proj_id);
             match antigravity
                 .generate_content(&request.model, &antigravity_request)
                 .await
             {
                 Ok(resp) => {
                     // 转换为 OpenAI 格式，再构建 Anthropic 响应
-                    let content = resp["candidates"][0]["content"]["parts"][0]["text"]
-                        .as_str()
-                        .unwrap_or("");
-                    let parsed = CWParsedResponse {
-                        content: content.to_string(),
-                        tool_calls: Vec::new(),
-                        usage_credits: 0.0,
-                        context_usage_percentage: 0.0,
-                    };
+                    let openai_resp = convert_antigravity_to_openai_response(&resp, &request.model);
+                    let reasoning = *state.processor.reasoning.read().await;
+                    let parsed = parsed_from_openai_response(&openai_resp, reasoning);
                     // 记录成功
                     if let Some(db) = &state.db {
                         let _ = state.pool_service.mark_healthy(
@@ -449,12 +593,14 @@ pub async fn call_provider_anthropic(
                     }
 
                     // 直接使用 AntigravityApiError 的状态码构建响应
-                    build_error_response_with_status(api_err.status_code, &api_err.to_string())
+                    ProxyApiError::upstream(api_err.status_code, api_err.to_string())
+                        .into_response()
                 }
             }
         }
         CredentialData::OpenAIKey { api_key, base_url } => {
-            let openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+            let mut openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut openai.client);
             let openai_request = convert_anthropic_to_openai(request);
             match openai.call_api(&openai_request).await {
                 Ok(resp) => {
@@ -463,20 +609,17 @@ pub async fn call_provider_anthropic(
                         match resp.text().await {
                             Ok(body) => {
                                 // 记录原始响应以便调试
-                                eprintln!("[PROVIDER_CALL] OpenAI 响应: {}", &body[..body.len().min(500)]);
+                                eprintln!(
+                                    "[PROVIDER_CALL] OpenAI 响应: {}",
+                                    &body[..body.len().min(500)]
+                                );
 
                                 if let Ok(openai_resp) =
                                     serde_json::from_str::<serde_json::Value>(&body)
                                 {
-                                    let content = openai_resp["choices"][0]["message"]["content"]
-                                        .as_str()
-                                        .unwrap_or("");
-                                    let parsed = CWParsedResponse {
-                                        content: content.to_string(),
-                                        tool_calls: Vec::new(),
-                                        usage_credits: 0.0,
-                                        context_usage_percentage: 0.0,
-                                    };
+                                    let reasoning = *state.processor.reasoning.read().await;
+                                    let parsed =
+                                        parsed_from_openai_response(&openai_resp, reasoning);
                                     // 记录成功
                                     if let Some(db) = &state.db {
                                         let _ = state.pool_service.mark_healthy(
@@ -494,7 +637,10 @@ pub async fn call_provider_anthropic(
                                     }
                                 } else {
                                     // 记录解析失败和原始响应
-                                    eprintln!("[PROVIDER_CALL] 解析 OpenAI 响应失败，原始响应: {}", &body);
+                                    eprintln!(
+                                        "[PROVIDER_CALL] 解析 OpenAI 响应失败，原始响应: {}",
+                                        &body
+                                    );
                                     if let Some(db) = &state.db {
                                         let _ = state.pool_service.mark_unhealthy(
                                             db,
@@ -502,11 +648,14 @@ pub async fn call_provider_anthropic(
                                             Some("Failed to parse OpenAI response"),
                                         );
                                     }
-                                    (
+                                    ProxyApiError::from_status(
                                         StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": format!("Failed to parse OpenAI response. Body: {}", &body[..body.len().min(200)])}})),
+                                        format!(
+                                            "Failed to parse OpenAI response. Body: {}",
+                                            &body[..body.len().min(200)]
+                                        ),
                                     )
-                                        .into_response()
+                                    .into_response()
                                 }
                             }
                             Err(e) => {
@@ -517,17 +666,22 @@ pub async fn call_provider_anthropic(
                                         Some(&e.to_string()),
                                     );
                                 }
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                                    e.to_string(),
                                 )
-                                    .into_response()
+                                .into_response()
                             }
                         }
                     } else {
                         let status_code = status.as_u16();
+                        let headers = resp.headers().clone();
                         let body = resp.text().await.unwrap_or_default();
-                        eprintln!("[PROVIDER_CALL] OpenAI 请求失败: status={} body={}", status_code, &body[..body.len().min(500)]);
+                        eprintln!(
+                            "[PROVIDER_CALL] OpenAI 请求失败: status={} body={}",
+                            status_code,
+                            &body[..body.len().min(500)]
+                        );
                         // 只有 5xx 错误才标记为不健康，4xx 错误（如模型不支持）不应该标记凭证为不健康
                         if status_code >= 500 {
                             if let Some(db) = &state.db {
@@ -539,10 +693,7 @@ pub async fn call_provider_anthropic(
                             }
                         }
                         // 转发上游的实际状态码
-                        (
-                            StatusCode::from_u16(status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                            Json(serde_json::json!({"error": {"message": body}})),
-                        )
+                        ProxyApiError::upstream_with_headers(status_code, &headers, body)
                             .into_response()
                     }
                 }
@@ -554,10 +705,7 @@ pub async fn call_provider_anthropic(
                             Some(&e.to_string()),
                         );
                     }
-                    (
-                        StatusCode::BAD_GATEWAY,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
+                    ProxyApiError::from_status(StatusCode::BAD_GATEWAY, e.to_string())
                         .into_response()
                 }
             }
@@ -565,7 +713,8 @@ pub async fn call_provider_anthropic(
         CredentialData::ClaudeKey { api_key, base_url } => {
             // 打印 Claude 代理 URL 用于调试
             let actual_base_url = base_url.as_deref().unwrap_or("https://api.anthropic.com");
-            let claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let mut claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut claude.client);
             let request_url = claude.get_base_url();
             state.logs.write().await.add(
                 "info",
@@ -586,7 +735,10 @@ pub async fn call_provider_anthropic(
                     &request_json.chars().take(500).collect::<String>()
                 ),
             );
-            match claude.call_api(request).await {
+            match claude
+                .call_api_with_headers(request, client_anthropic_headers())
+                .await
+            {
                 Ok(resp) => {
                     let status = resp.status();
                     // 打印响应状态
@@ -594,18 +746,17 @@ pub async fn call_provider_anthropic(
                         "info",
                         &format!(
                             "[CLAUDE] 响应状态: status={} model={} stream={}",
-                            status,
-                            request.model,
-                            request.stream
+                            status, request.model, request.stream
                         ),
                     );
 
-                    // 如果是流式请求，直接透传流式响应
+                    // 如果是流式请求，按字节透传上游 SSE 响应
                     if request.stream && status.is_success() {
-                        state.logs.write().await.add(
-                            "info",
-                            "[CLAUDE] 流式请求，透传 SSE 响应",
-                        );
+                        state
+                            .logs
+                            .write()
+                            .await
+                            .add("info", "[CLAUDE] 流式请求，透传 SSE 响应");
                         // 记录成功
                         if let Some(db) = &state.db {
                             let _ = state.pool_service.mark_healthy(
@@ -615,23 +766,7 @@ pub async fn call_provider_anthropic(
                             );
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
-                        // 透传流式响应，保持 SSE 格式
-                        let stream = resp.bytes_stream();
-                        return Response::builder()
-                            .status(StatusCode::OK)
-                            .header(header::CONTENT_TYPE, "text/event-stream")
-                            .header(header::CACHE_CONTROL, "no-cache, no-store, must-revalidate")
-                            .header("Connection", "keep-alive")
-                            .header("X-Accel-Buffering", "no") // 禁用 nginx 等代理的缓冲
-                            .header("Transfer-Encoding", "chunked")
-                            .body(Body::from_stream(stream))
-                            .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": "Failed to build stream response"}})),
-                                )
-                                    .into_response()
-                            });
+                        return passthrough_sse_response(resp);
                     }
 
                     // 非流式请求，读取完整响应
@@ -660,11 +795,11 @@ pub async fn call_provider_anthropic(
                                     .header(header::CONTENT_TYPE, "application/json")
                                     .body(Body::from(body))
                                     .unwrap_or_else(|_| {
-                                        (
+                                        ProxyApiError::from_status(
                                             StatusCode::INTERNAL_SERVER_ERROR,
-                                            Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
+                                            "Failed to build response",
                                         )
-                                            .into_response()
+                                        .into_response()
                                     })
                             } else {
                                 state.logs.write().await.add(
@@ -682,19 +817,15 @@ pub async fn call_provider_anthropic(
                                         Some(&body),
                                     );
                                 }
-                                (
-                                    StatusCode::from_u16(status.as_u16())
-                                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                                    Json(serde_json::json!({"error": {"message": body}})),
-                                )
-                                    .into_response()
+                                ProxyApiError::upstream(status.as_u16(), body).into_response()
                             }
                         }
                         Err(e) => {
-                            state.logs.write().await.add(
-                                "error",
-                                &format!("[CLAUDE] 读取响应失败: {}", e),
-                            );
+                            state
+                                .logs
+                                .write()
+                                .await
+                                .add("error", &format!("[CLAUDE] 读取响应失败: {}", e));
                             if let Some(db) = &state.db {
                                 let _ = state.pool_service.mark_unhealthy(
                                     db,
@@ -702,11 +833,11 @@ pub async fn call_provider_anthropic(
                                     Some(&e.to_string()),
                                 );
                             }
-                            (
+                            ProxyApiError::from_status(
                                 StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                                e.to_string(),
                             )
-                                .into_response()
+                            .into_response()
                         }
                     }
                 }
@@ -718,26 +849,33 @@ pub async fn call_provider_anthropic(
                             Some(&e.to_string()),
                         );
                     }
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
+                    ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                         .into_response()
                 }
             }
         }
-        CredentialData::VertexKey { api_key, base_url, .. } => {
+        CredentialData::VertexKey {
+            api_key, base_url, ..
+        } => {
             // Vertex AI uses Gemini-compatible API, convert Anthropic to OpenAI format first
             let openai_request = convert_anthropic_to_openai(request);
-            let vertex = VertexProvider::with_config(api_key.clone(), base_url.clone());
-            match vertex.chat_completions(&serde_json::to_value(&openai_request).unwrap_or_default()).await {
+            let mut vertex = VertexProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut vertex.client);
+            match vertex
+                .chat_completions(&serde_json::to_value(&openai_request).unwrap_or_default())
+                .await
+            {
                 Ok(resp) => {
                     let status = resp.status();
                     match resp.text().await {
                         Ok(body) => {
                             if status.is_success() {
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
+                                    let _ = state.pool_service.mark_healthy(
+                                        db,
+                                        &credential.uuid,
+                                        Some(&request.model),
+                                    );
                                     let _ = state.pool_service.record_usage(db, &credential.uuid);
                                 }
                                 Response::builder()
@@ -745,52 +883,225 @@ pub async fn call_provider_anthropic(
                                     .header(header::CONTENT_TYPE, "application/json")
                                     .body(Body::from(body))
                                     .unwrap_or_else(|_| {
-                                        (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": "Failed to build response"}}))).into_response()
+                                        ProxyApiError::from_status(
+                                            StatusCode::INTERNAL_SERVER_ERROR,
+                                            "Failed to build response",
+                                        )
+                                        .into_response()
                                     })
                             } else {
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&body));
+                                    let _ = state.pool_service.mark_unhealthy(
+                                        db,
+                                        &credential.uuid,
+                                        Some(&body),
+                                    );
                                 }
-                                (StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR), Json(serde_json::json!({"error": {"message": body}}))).into_response()
+                                ProxyApiError::upstream(status.as_u16(), body).into_response()
                             }
                         }
                         Err(e) => {
                             if let Some(db) = &state.db {
-                                let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                                let _ = state.pool_service.mark_unhealthy(
+                                    db,
+                                    &credential.uuid,
+                                    Some(&e.to_string()),
+                                );
                             }
-                            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response()
+                            ProxyApiError::from_status(
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                e.to_string(),
+                            )
+                            .into_response()
                         }
                     }
                 }
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                        let _ = state.pool_service.mark_unhealthy(
+                            db,
+                            &credential.uuid,
+                            Some(&e.to_string()),
+                        );
                     }
-                    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response()
+                    ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                        .into_response()
                 }
             }
         }
-        // Gemini API Key credentials - not supported for Anthropic format
         CredentialData::GeminiApiKey { .. } => {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "Gemini API Key credentials do not support Anthropic format"}})),
-            )
-                .into_response()
+            // Gemini 上游为 generateContent 格式，经 OpenAI 格式中转，工具定义和调用一并转换
+            let openai_request = convert_anthropic_to_openai(request);
+            let openai_resp = match call_gemini_api_key(state, credential, &openai_request).await {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
+            let reasoning = *state.processor.reasoning.read().await;
+            let parsed = parsed_from_openai_response(&openai_resp, reasoning);
+            if request.stream {
+                build_anthropic_stream_response(&request.model, &parsed)
+            } else {
+                build_anthropic_response(&request.model, &parsed)
+            }
         }
         // 新增的凭证类型暂不支持 Anthropic 格式
-        CredentialData::CodexOAuth { .. }
-        | CredentialData::ClaudeOAuth { .. } => {
-            (
+        CredentialData::CodexOAuth { .. } | CredentialData::ClaudeOAuth { .. } => {
+            ProxyApiError::from_status(
                 StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "This credential type does not support Anthropic format yet"}})),
+                "This credential type does not support Anthropic format yet",
             )
-                .into_response()
+            .into_response()
+        }
+        CredentialData::QwenOAuth { creds_file_path } => {
+            // Qwen 上游为 OpenAI 格式，先转换请求，拿到完整响应后再转换回 Anthropic 格式
+            let mut openai_request = convert_anthropic_to_openai(request);
+            openai_request.stream = false;
+
+            let resp =
+                match call_qwen_with_refresh(state, credential, creds_file_path, &openai_request)
+                    .await
+                {
+                    Ok(resp) => resp,
+                    Err(error_response) => return error_response,
+                };
+
+            let status = resp.status();
+            let body = match resp.text().await {
+                Ok(body) => body,
+                Err(e) => {
+                    return build_error_response_with_status(
+                        StatusCode::BAD_GATEWAY.as_u16(),
+                        &format!("Failed to read Qwen response: {}", e),
+                    )
+                }
+            };
+
+            if !status.is_success() {
+                return qwen_upstream_error(state, credential, status.as_u16(), body);
+            }
+
+            let openai_resp = match serde_json::from_str::<ChatCompletionResponse>(&body) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    tracing::error!(
+                        "[QWEN] 解析响应失败: {} body={}",
+                        e,
+                        safe_truncate(&body, 500)
+                    );
+                    return build_error_response_with_status(
+                        StatusCode::BAD_GATEWAY.as_u16(),
+                        &format!("Failed to parse Qwen response: {}", e),
+                    );
+                }
+            };
+
+            if let Some(db) = &state.db {
+                let _ = state
+                    .pool_service
+                    .mark_healthy(db, &credential.uuid, Some(&request.model));
+                let _ = state.pool_service.record_usage(db, &credential.uuid);
+            }
+
+            let reasoning = *state.processor.reasoning.read().await;
+            respond_anthropic_from_openai(&openai_resp, &request.model, request.stream, reasoning)
+        }
+        CredentialData::AzureOpenaiKey { .. } => {
+            // Azure OpenAI 上游为 OpenAI 格式，先转换请求，拿到完整响应后再转换回 Anthropic 格式
+            let mut openai_request = convert_anthropic_to_openai(request);
+            openai_request.stream = false;
+
+            let resp = match call_azure_openai(state, credential, &openai_request).await {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
+            let openai_resp = match resp.json::<ChatCompletionResponse>().await {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    tracing::error!("[AZURE_OPENAI] 解析响应失败: {}", e);
+                    return build_error_response_with_status(
+                        StatusCode::BAD_GATEWAY.as_u16(),
+                        &format!("Failed to parse Azure OpenAI response: {}", e),
+                    );
+                }
+            };
+
+            let reasoning = *state.processor.reasoning.read().await;
+            respond_anthropic_from_openai(&openai_resp, &request.model, request.stream, reasoning)
+        }
+        CredentialData::VertexServiceAccount { .. } => {
+            // Vertex AI OpenAI 兼容端点，先转换请求，拿到完整响应后再转换回 Anthropic 格式
+            let mut openai_request = convert_anthropic_to_openai(request);
+            openai_request.stream = false;
+
+            let resp = match call_vertex_service_account(state, credential, &openai_request).await {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
+            let openai_resp = match resp.json::<ChatCompletionResponse>().await {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    tracing::error!("[VERTEX_SA] 解析响应失败: {}", e);
+                    return build_error_response_with_status(
+                        StatusCode::BAD_GATEWAY.as_u16(),
+                        &format!("Failed to parse Vertex AI response: {}", e),
+                    );
+                }
+            };
+
+            let reasoning = *state.processor.reasoning.read().await;
+            respond_anthropic_from_openai(&openai_resp, &request.model, request.stream, reasoning)
+        }
+        CredentialData::Ollama { .. } => {
+            // 本地服务为 OpenAI 兼容格式，先转换请求，拿到完整响应后再转换回 Anthropic 格式
+            let mut openai_request = convert_anthropic_to_openai(request);
+            openai_request.stream = false;
+
+            let resp = match call_ollama(state, credential, &openai_request).await {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
+            let openai_resp = match resp.json::<ChatCompletionResponse>().await {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    tracing::error!("[OLLAMA] 解析响应失败: {}", e);
+                    return build_error_response_with_status(
+                        StatusCode::BAD_GATEWAY.as_u16(),
+                        &format!("Failed to parse local model response: {}", e),
+                    );
+                }
+            };
+
+            let reasoning = *state.processor.reasoning.read().await;
+            respond_anthropic_from_openai(&openai_resp, &request.model, request.stream, reasoning)
+        }
+        CredentialData::Mock { .. } => {
+            let openai_request = convert_anthropic_to_openai(request);
+            let openai_resp = match call_mock(state, credential, &openai_request).await {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
+            let reasoning = *state.processor.reasoning.read().await;
+            respond_anthropic_from_openai(&openai_resp, &request.model, request.stream, reasoning)
+        }
+        CredentialData::AwsBedrock { .. } => {
+            // Bedrock 的 Converse 格式由 OpenAI 格式转换而来，拿到完整响应后再转换回 Anthropic 格式
+            let mut openai_request = convert_anthropic_to_openai(request);
+            openai_request.stream = false;
+
+            let openai_resp = match call_bedrock_converse(state, credential, &openai_request).await
+            {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
+
+            let reasoning = *state.processor.reasoning.read().await;
+            respond_anthropic_from_openai(&openai_resp, &request.model, request.stream, reasoning)
         }
         // Anthropic API Key - 根据 base_url 决定调用方式
         CredentialData::AnthropicKey { api_key, base_url } => {
             // 使用 Anthropic 原生格式调用（无论是否有自定义 base_url）
-            let claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let mut claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut claude.client);
             let request_url = claude.get_base_url();
             state.logs.write().await.add(
                 "info",
@@ -808,18 +1119,17 @@ pub async fn call_provider_anthropic(
                         "info",
                         &format!(
                             "[ANTHROPIC] 响应状态: status={} model={} stream={}",
-                            status,
-                            request.model,
-                            request.stream
+                            status, request.model, request.stream
                         ),
                     );
 
                     // 如果是流式请求，直接透传流式响应
                     if request.stream && status.is_success() {
-                        state.logs.write().await.add(
-                            "info",
-                            "[ANTHROPIC] 流式请求，透传 SSE 响应",
-                        );
+                        state
+                            .logs
+                            .write()
+                            .await
+                            .add("info", "[ANTHROPIC] 流式请求，透传 SSE 响应");
                         if let Some(db) = &state.db {
                             let _ = state.pool_service.mark_healthy(
                                 db,
@@ -838,11 +1148,11 @@ pub async fn call_provider_anthropic(
                             .header("Transfer-Encoding", "chunked")
                             .body(Body::from_stream(stream))
                             .unwrap_or_else(|_| {
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": "Failed to build stream response"}})),
+                                    "Failed to build stream response",
                                 )
-                                    .into_response()
+                                .into_response()
                             });
                     }
 
@@ -863,11 +1173,11 @@ pub async fn call_provider_anthropic(
                                     .header(header::CONTENT_TYPE, "application/json")
                                     .body(Body::from(body))
                                     .unwrap_or_else(|_| {
-                                        (
+                                        ProxyApiError::from_status(
                                             StatusCode::INTERNAL_SERVER_ERROR,
-                                            Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
+                                            "Failed to build response",
                                         )
-                                            .into_response()
+                                        .into_response()
                                     })
                             } else {
                                 state.logs.write().await.add(
@@ -885,19 +1195,14 @@ pub async fn call_provider_anthropic(
                                         Some(&format!("API error: {}", status)),
                                     );
                                 }
-                                (
-                                    StatusCode::from_u16(status.as_u16())
-                                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                                    Json(serde_json::json!({"error": {"message": body}})),
-                                )
-                                    .into_response()
+                                ProxyApiError::upstream(status.as_u16(), body).into_response()
                             }
                         }
-                        Err(e) => (
+                        Err(e) => ProxyApiError::from_status(
                             StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to read response: {}", e)}})),
+                            format!("Failed to read response: {}", e),
                         )
-                            .into_response(),
+                        .into_response(),
                     }
                 }
                 Err(e) => {
@@ -908,11 +1213,11 @@ pub async fn call_provider_anthropic(
                             Some(&format!("API call failed: {}", e)),
                         );
                     }
-                    (
+                    ProxyApiError::from_status(
                         StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": format!("Anthropic API call failed: {}", e)}})),
+                        format!("Anthropic API call failed: {}", e),
                     )
-                        .into_response()
+                    .into_response()
                 }
             }
         }
@@ -926,7 +1231,34 @@ pub async fn call_provider_anthropic(
 /// - `credential`: 凭证信息
 /// - `request`: OpenAI 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
+#[tracing::instrument(
+    name = "provider.call",
+    skip_all,
+    fields(
+        otel.kind = "client",
+        provider = %credential.provider_type,
+        credential.id = %credential.uuid,
+        model = %request.model,
+        stream = request.stream,
+        protocol = "openai",
+    )
+)]
 pub async fn call_provider_openai(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+) -> Response {
+    let headers = injected_headers_for(state, &request.model, request, credential).await;
+    with_injected_headers(
+        headers,
+        dispatch_provider_openai(state, credential, request, flow_id),
+    )
+    .await
+}
+
+/// 按凭证类型分发 OpenAI 格式请求
+async fn dispatch_provider_openai(
     state: &AppState,
     credential: &ProviderCredential,
     request: &ChatCompletionRequest,
@@ -942,7 +1274,13 @@ pub async fn call_provider_openai(
         CredentialData::GeminiOAuth { .. } => "GeminiOAuth",
         CredentialData::GeminiApiKey { .. } => "GeminiApiKey",
         CredentialData::VertexKey { .. } => "VertexKey",
+        CredentialData::VertexServiceAccount { .. } => "VertexServiceAccount",
         CredentialData::AntigravityOAuth { .. } => "AntigravityOAuth",
+        CredentialData::QwenOAuth { .. } => "QwenOAuth",
+        CredentialData::AzureOpenaiKey { .. } => "AzureOpenaiKey",
+        CredentialData::AwsBedrock { .. } => "AwsBedrock",
+        CredentialData::Ollama { .. } => "Ollama",
+        CredentialData::Mock { .. } => "Mock",
         _ => "Other",
     };
     tracing::info!(
@@ -959,11 +1297,11 @@ pub async fn call_provider_openai(
             let db = match &state.db {
                 Some(db) => db,
                 None => {
-                    return (
+                    return ProxyApiError::from_status(
                         StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": "Database not available"}})),
+                        "Database not available",
                     )
-                        .into_response();
+                    .into_response();
                 }
             };
 
@@ -978,17 +1316,18 @@ pub async fn call_provider_openai(
                     tracing::warn!("[POOL] Token cache miss, loading from source: {}", e);
                     // 降级：从源文件加载并刷新
                     let mut kiro = KiroProvider::new();
+                    use_shared_client(state, credential, &mut kiro.client);
                     if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
                         let _ = state.pool_service.mark_unhealthy(
                             db,
                             &credential.uuid,
                             Some(&format!("Failed to load credentials: {}", e)),
                         );
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
+                            format!("Failed to load Kiro credentials: {}", e),
                         )
-                            .into_response();
+                        .into_response();
                     }
                     if let Err(e) = kiro.refresh_token().await {
                         let _ = state.pool_service.mark_unhealthy(
@@ -996,11 +1335,11 @@ pub async fn call_provider_openai(
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {}", e)),
                         );
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
+                            format!("Token refresh failed: {}", e),
                         )
-                            .into_response();
+                        .into_response();
                     }
                     kiro.credentials.access_token.unwrap_or_default()
                 }
@@ -1008,13 +1347,18 @@ pub async fn call_provider_openai(
 
             // 使用获取到的 token 创建 KiroProvider
             let mut kiro = KiroProvider::new();
+            use_shared_client(state, credential, &mut kiro.client);
             // 从源文件加载其他配置（region, profile_arn 等）
             // 注意：必须先加载凭证文件，再设置 token，因为 load_credentials_from_path 会覆盖整个 credentials
             let _ = kiro.load_credentials_from_path(creds_file_path).await;
             // 使用缓存的 token 覆盖文件中的 token（缓存的 token 更新）
             kiro.credentials.access_token = Some(token);
 
-            tracing::info!("[CALL_PROVIDER_OPENAI] request.stream = {}, model = {}", request.stream, request.model);
+            tracing::info!(
+                "[CALL_PROVIDER_OPENAI] request.stream = {}, model = {}",
+                request.stream,
+                request.model
+            );
 
             // 检查是否为流式请求
             if request.stream {
@@ -1024,7 +1368,11 @@ pub async fn call_provider_openai(
                     Ok(stream_response) => {
                         // 记录成功
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
+                            let _ = state.pool_service.mark_healthy(
+                                db,
+                                &credential.uuid,
+                                Some(&request.model),
+                            );
                             let _ = state.pool_service.record_usage(db, &credential.uuid);
                         }
 
@@ -1094,12 +1442,14 @@ pub async fn call_provider_openai(
                         tracing::info!("[OPENAI_STREAM] 构建 SSE 响应");
 
                         // 转换为 Body 流
-                        let body_stream = final_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
-                            match result {
-                                Ok(event) => Ok(axum::body::Bytes::from(event)),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
-                            }
-                        });
+                        let body_stream = final_stream.map(
+                            |result| -> Result<axum::body::Bytes, std::io::Error> {
+                                match result {
+                                    Ok(event) => Ok(axum::body::Bytes::from(event)),
+                                    Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                                }
+                            },
+                        );
 
                         // 构建 SSE 响应
                         return Response::builder()
@@ -1111,25 +1461,27 @@ pub async fn call_provider_openai(
                             .header("X-Accel-Buffering", "no")
                             .body(Body::from_stream(body_stream))
                             .unwrap_or_else(|_| {
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
+                                    "Failed to build streaming response",
                                 )
-                                    .into_response()
+                                .into_response()
                             });
                     }
                     Err(e) => {
                         // 记录请求错误
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&e.to_string()),
+                            );
                         }
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                            e.to_string(),
                         )
-                            .into_response();
+                        .into_response();
                     }
                 }
             }
@@ -1141,10 +1493,14 @@ pub async fn call_provider_openai(
                     if status.is_success() {
                         // 记录成功
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
-                            let _ = state.pool_service.record_usage(db, &credential.uuid);
-                        }
-                        match resp.text().await {
+                            let _ = state.pool_service.mark_healthy(
+                                db,
+                                &credential.uuid,
+                                Some(&request.model),
+                            );
+                            let _ = state.pool_service.record_usage(db, &credential.uuid);
+                        }
+                        match resp.bytes().await {
                             Ok(body) => {
                                 let parsed = parse_cw_response(&body);
                                 let has_tool_calls = !parsed.tool_calls.is_empty();
@@ -1190,46 +1546,49 @@ pub async fn call_provider_openai(
                                 }))
                                 .into_response()
                             }
-                            Err(e) => (
+                            Err(e) => ProxyApiError::from_status(
                                 StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                                e.to_string(),
                             )
-                                .into_response(),
+                            .into_response(),
                         }
                     } else {
                         // 记录 API 调用失败
                         let body = resp.text().await.unwrap_or_default();
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&format!("HTTP {}: {}", status, safe_truncate(&body, 100))));
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&format!("HTTP {}: {}", status, safe_truncate(&body, 100))),
+                            );
                         }
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": body}})),
-                        )
+                        ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, body)
                             .into_response()
                     }
                 }
                 Err(e) => {
                     // 记录请求错误
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                        let _ = state.pool_service.mark_unhealthy(
+                            db,
+                            &credential.uuid,
+                            Some(&e.to_string()),
+                        );
                     }
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
+                    ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                         .into_response()
                 }
             }
         }
-        CredentialData::GeminiOAuth { .. } => {
-            (
-                StatusCode::NOT_IMPLEMENTED,
-                Json(serde_json::json!({"error": {"message": "Gemini OAuth routing not yet implemented."}})),
-            )
-                .into_response()
-        }
-        CredentialData::AntigravityOAuth { creds_file_path, project_id } => {
+        CredentialData::GeminiOAuth { .. } => ProxyApiError::from_status(
+            StatusCode::NOT_IMPLEMENTED,
+            "Gemini OAuth routing not yet implemented.",
+        )
+        .into_response(),
+        CredentialData::AntigravityOAuth {
+            creds_file_path,
+            project_id,
+        } => {
             eprintln!("\n========== [ANTIGRAVITY] 开始处理 Antigravity 请求 ==========");
             eprintln!("[ANTIGRAVITY] 凭证文件: {}", creds_file_path);
             eprintln!("[ANTIGRAVITY] 项目ID: {:?}", project_id);
@@ -1237,7 +1596,11 @@ pub async fn call_provider_openai(
             eprintln!("[ANTIGRAVITY] 流式: {}", request.stream);
 
             let mut antigravity = AntigravityProvider::new();
-            if let Err(e) = antigravity.load_credentials_from_path(creds_file_path).await {
+            use_shared_client(state, credential, &mut antigravity.client);
+            if let Err(e) = antigravity
+                .load_credentials_from_path(creds_file_path)
+                .await
+            {
                 eprintln!("[ANTIGRAVITY] 加载凭证失败: {}", e);
                 // 记录凭证加载失败
                 if let Some(db) = &state.db {
@@ -1247,18 +1610,21 @@ pub async fn call_provider_openai(
                         Some(&format!("Failed to load credentials: {}", e)),
                     );
                 }
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Antigravity credentials: {}", e)}})),
+                    format!("Failed to load Antigravity credentials: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
             eprintln!("[ANTIGRAVITY] 凭证加载成功");
 
             // 使用新的 validate_token() 方法检查 Token 状态
             let validation_result = antigravity.validate_token();
             eprintln!("[ANTIGRAVITY] Token 验证结果: {:?}", validation_result);
-            eprintln!("[ANTIGRAVITY] needs_refresh() = {}", validation_result.needs_refresh());
+            eprintln!(
+                "[ANTIGRAVITY] needs_refresh() = {}",
+                validation_result.needs_refresh()
+            );
             tracing::info!("[Antigravity] Token 验证结果: {:?}", validation_result);
 
             // 根据验证结果决定是否刷新
@@ -1267,15 +1633,17 @@ pub async fn call_provider_openai(
                 tracing::info!("[Antigravity] Token 需要刷新，开始刷新...");
                 match antigravity.refresh_token_with_retry(3).await {
                     Ok(new_token) => {
-                        eprintln!("[ANTIGRAVITY] Token 刷新成功，新 token 长度: {}", new_token.len());
-                        tracing::info!("[Antigravity] Token 刷新成功，新 token 长度: {}", new_token.len());
+                        eprintln!(
+                            "[ANTIGRAVITY] Token 刷新成功，新 token 长度: {}",
+                            new_token.len()
+                        );
+                        tracing::info!(
+                            "[Antigravity] Token 刷新成功，新 token 长度: {}",
+                            new_token.len()
+                        );
                         // 刷新成功，标记为健康
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(
-                                db,
-                                &credential.uuid,
-                                None,
-                            );
+                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, None);
                         }
                     }
                     Err(refresh_error) => {
@@ -1294,14 +1662,13 @@ pub async fn call_provider_openai(
                         let (status, message) = if refresh_error.requires_reauth() {
                             (StatusCode::UNAUTHORIZED, refresh_error.user_message())
                         } else {
-                            (StatusCode::INTERNAL_SERVER_ERROR, refresh_error.user_message())
+                            (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                refresh_error.user_message(),
+                            )
                         };
 
-                        return (
-                            status,
-                            Json(serde_json::json!({"error": {"message": message}})),
-                        )
-                            .into_response();
+                        return ProxyApiError::from_status(status, message).into_response();
                     }
                 }
             } else {
@@ -1315,14 +1682,21 @@ pub async fn call_provider_openai(
                 tracing::warn!("[Antigravity] Failed to discover project: {}", e);
             }
 
-            tracing::info!("[ANTIGRAVITY] request.stream = {}, model = {}, project_id = {:?}",
-                request.stream, request.model, antigravity.project_id);
+            tracing::info!(
+                "[ANTIGRAVITY] request.stream = {}, model = {}, project_id = {:?}",
+                request.stream,
+                request.model,
+                antigravity.project_id
+            );
 
             // 检查是否为流式请求
             if request.stream {
                 tracing::info!("[ANTIGRAVITY_STREAM] ========== 开始处理流式请求 ==========");
-                tracing::info!("[ANTIGRAVITY_STREAM] model={}, has_token={}",
-                    request.model, antigravity.credentials.access_token.is_some());
+                tracing::info!(
+                    "[ANTIGRAVITY_STREAM] model={}, has_token={}",
+                    request.model,
+                    antigravity.credentials.access_token.is_some()
+                );
 
                 // 检查是否是图片生成模型
                 // 注意：gemini-3-pro-image-preview 是支持图片理解的模型，不是图片生成模型
@@ -1330,7 +1704,10 @@ pub async fn call_provider_openai(
                 let is_image_generation_model = request.model == "imagen"
                     || request.model.starts_with("imagen-")
                     || request.model.contains("image-generation");
-                tracing::info!("[ANTIGRAVITY_STREAM] is_image_generation_model={}", is_image_generation_model);
+                tracing::info!(
+                    "[ANTIGRAVITY_STREAM] is_image_generation_model={}",
+                    is_image_generation_model
+                );
 
                 // 对于图片生成模型，使用非流式请求然后模拟流式返回
                 if is_image_generation_model {
@@ -1339,10 +1716,14 @@ pub async fn call_provider_openai(
                     // 获取 project_id 用于请求
                     let proj_id = antigravity.project_id.clone().unwrap_or_default();
                     // 转换请求格式 - 这已经是完整的 Antigravity 请求格式
-                    let antigravity_request = convert_openai_to_antigravity_with_context(request, &proj_id);
+                    let antigravity_request =
+                        convert_openai_to_antigravity_with_context(request, &proj_id);
 
                     // 直接调用 call_api，因为 antigravity_request 已经是完整格式
-                    match antigravity.call_api("generateContent", &antigravity_request).await {
+                    match antigravity
+                        .call_api("generateContent", &antigravity_request)
+                        .await
+                    {
                         Ok(resp) => {
                             // 保存原始响应到文件用于调试
                             let resp_str = serde_json::to_string_pretty(&resp).unwrap_or_default();
@@ -1352,20 +1733,39 @@ pub async fn call_provider_openai(
                             let _ = std::fs::create_dir_all(&debug_dir);
                             let debug_file = debug_dir.join("antigravity_image_response.json");
                             let _ = std::fs::write(&debug_file, &resp_str);
-                            tracing::info!("[ANTIGRAVITY_STREAM] 原始响应已保存到: {:?}, 大小: {} bytes", debug_file, resp_str.len());
-                            eprintln!("[ANTIGRAVITY_STREAM] 原始响应已保存到: {:?}, 大小: {} bytes", debug_file, resp_str.len());
+                            tracing::info!(
+                                "[ANTIGRAVITY_STREAM] 原始响应已保存到: {:?}, 大小: {} bytes",
+                                debug_file,
+                                resp_str.len()
+                            );
+                            eprintln!(
+                                "[ANTIGRAVITY_STREAM] 原始响应已保存到: {:?}, 大小: {} bytes",
+                                debug_file,
+                                resp_str.len()
+                            );
 
                             tracing::info!("[ANTIGRAVITY_STREAM] 图片生成完成，转换为流式响应");
 
                             // 将非流式响应转换为 OpenAI 格式
-                            let openai_response = convert_antigravity_to_openai_response(&resp, &request.model);
+                            let openai_response =
+                                convert_antigravity_to_openai_response(&resp, &request.model);
 
                             // 保存转换后的响应到文件
-                            let openai_str = serde_json::to_string_pretty(&openai_response).unwrap_or_default();
-                            let openai_debug_file = debug_dir.join("antigravity_image_openai_response.json");
+                            let openai_str =
+                                serde_json::to_string_pretty(&openai_response).unwrap_or_default();
+                            let openai_debug_file =
+                                debug_dir.join("antigravity_image_openai_response.json");
                             let _ = std::fs::write(&openai_debug_file, &openai_str);
-                            tracing::info!("[ANTIGRAVITY_STREAM] OpenAI 响应已保存到: {:?}, 大小: {} bytes", openai_debug_file, openai_str.len());
-                            eprintln!("[ANTIGRAVITY_STREAM] OpenAI 响应已保存到: {:?}, 大小: {} bytes", openai_debug_file, openai_str.len());
+                            tracing::info!(
+                                "[ANTIGRAVITY_STREAM] OpenAI 响应已保存到: {:?}, 大小: {} bytes",
+                                openai_debug_file,
+                                openai_str.len()
+                            );
+                            eprintln!(
+                                "[ANTIGRAVITY_STREAM] OpenAI 响应已保存到: {:?}, 大小: {} bytes",
+                                openai_debug_file,
+                                openai_str.len()
+                            );
 
                             // 将非流式响应转换为流式 SSE 格式
                             let model = request.model.clone();
@@ -1382,7 +1782,10 @@ pub async fn call_provider_openai(
                                 .and_then(|c| c.as_str())
                                 .unwrap_or("");
 
-                            tracing::info!("[ANTIGRAVITY_STREAM] 图片内容长度: {} 字符", content.len());
+                            tracing::info!(
+                                "[ANTIGRAVITY_STREAM] 图片内容长度: {} 字符",
+                                content.len()
+                            );
                             eprintln!("[ANTIGRAVITY_STREAM] 图片内容长度: {} 字符", content.len());
 
                             // 构建 SSE 事件
@@ -1403,7 +1806,8 @@ pub async fn call_provider_openai(
                                         "finish_reason": null
                                     }]
                                 });
-                                sse_events.push_str(&format!("data: {}\n\n", chunk_response.to_string()));
+                                sse_events
+                                    .push_str(&format!("data: {}\n\n", chunk_response.to_string()));
                             }
 
                             // 发送结束 chunk
@@ -1418,7 +1822,8 @@ pub async fn call_provider_openai(
                                     "finish_reason": "stop"
                                 }]
                             });
-                            sse_events.push_str(&format!("data: {}\n\n", done_response.to_string()));
+                            sse_events
+                                .push_str(&format!("data: {}\n\n", done_response.to_string()));
                             sse_events.push_str("data: [DONE]\n\n");
 
                             return Response::builder()
@@ -1428,17 +1833,25 @@ pub async fn call_provider_openai(
                                 .header(header::CONNECTION, "keep-alive")
                                 .body(Body::from(sse_events))
                                 .unwrap_or_else(|_| {
-                                    (
+                                    ProxyApiError::from_status(
                                         StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Failed to build streaming response"}})),
+                                        "Failed to build streaming response",
                                     )
-                                        .into_response()
+                                    .into_response()
                                 });
                         }
                         Err(api_err) => {
-                            tracing::error!("[ANTIGRAVITY_STREAM] 图片生成失败 (HTTP {}): {}", api_err.status_code, api_err.message);
+                            tracing::error!(
+                                "[ANTIGRAVITY_STREAM] 图片生成失败 (HTTP {}): {}",
+                                api_err.status_code,
+                                api_err.message
+                            );
                             // 直接使用 AntigravityApiError 的状态码构建响应
-                            return build_error_response_with_status(api_err.status_code, &api_err.to_string());
+                            return ProxyApiError::upstream(
+                                api_err.status_code,
+                                api_err.to_string(),
+                            )
+                            .into_response();
                         }
                     }
                 }
@@ -1456,6 +1869,7 @@ pub async fn call_provider_openai(
 
                         // 在后台任务中收集所有数据
                         let model_clone = model.clone();
+                        let reasoning = *state.processor.reasoning.read().await;
                         tokio::spawn(async move {
                             use futures::StreamExt;
                             let mut stream = stream_response;
@@ -1470,24 +1884,39 @@ pub async fn call_provider_openai(
                                         all_data.push_str(&text);
 
                                         if chunk_count <= 3 {
-                                            eprintln!("[ANTIGRAVITY_STREAM] 收集 chunk #{}: {} bytes", chunk_count, bytes.len());
+                                            eprintln!(
+                                                "[ANTIGRAVITY_STREAM] 收集 chunk #{}: {} bytes",
+                                                chunk_count,
+                                                bytes.len()
+                                            );
                                         } else if chunk_count % 200 == 0 {
                                             eprintln!("[ANTIGRAVITY_STREAM] 已收集 {} 个 chunk, 总大小: {} bytes", chunk_count, all_data.len());
                                         }
                                     }
                                     Err(e) => {
-                                        eprintln!("[ANTIGRAVITY_STREAM] chunk #{} 错误: {}", chunk_count, e);
+                                        eprintln!(
+                                            "[ANTIGRAVITY_STREAM] chunk #{} 错误: {}",
+                                            chunk_count, e
+                                        );
                                         let _ = tx.send(Err(e.to_string()));
                                         return;
                                     }
                                 }
                             }
 
-                            eprintln!("[ANTIGRAVITY_STREAM] 流结束，共收集 {} 个 chunk, 总大小: {} bytes", chunk_count, all_data.len());
+                            eprintln!(
+                                "[ANTIGRAVITY_STREAM] 流结束，共收集 {} 个 chunk, 总大小: {} bytes",
+                                chunk_count,
+                                all_data.len()
+                            );
 
                             // 尝试解析累积的 JSON 数据
                             // Antigravity 返回格式: { "response": { "candidates": [...] } }
-                            let result = parse_antigravity_accumulated_response(&all_data, &model_clone);
+                            let result = parse_antigravity_accumulated_response(
+                                &all_data,
+                                &model_clone,
+                                reasoning,
+                            );
                             let _ = tx.send(result);
                         });
 
@@ -1522,13 +1951,11 @@ pub async fn call_provider_openai(
                             .header("X-Accel-Buffering", "no")
                             .body(Body::from_stream(sse_stream))
                             .unwrap_or_else(|_| {
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
+                                    "Failed to build streaming response",
                                 )
-                                    .into_response()
+                                .into_response()
                             });
                     }
                     Err(provider_err) => {
@@ -1552,68 +1979,64 @@ pub async fn call_provider_openai(
             eprintln!("[ANTIGRAVITY_OPENAI] 请求格式转换完成");
 
             eprintln!("[ANTIGRAVITY_OPENAI] 调用 generate_content...");
-            match antigravity.generate_content(&request.model, &antigravity_request).await {
-                Ok(resp) => {
+            let provider = &antigravity;
+            let antigravity_request = &antigravity_request;
+            match call_with_response_format(request, move || async move {
+                provider
+                    .generate_content(&request.model, antigravity_request)
+                    .await
+                    .map(|resp| convert_antigravity_to_openai_response(&resp, &request.model))
+            })
+            .await
+            {
+                Ok(mut openai_response) => {
                     eprintln!("[ANTIGRAVITY_OPENAI] generate_content 返回成功");
-                    let openai_response = convert_antigravity_to_openai_response(&resp, &request.model);
+                    let reasoning = *state.processor.reasoning.read().await;
+                    apply_reasoning_to_openai_response(&mut openai_response, reasoning);
                     eprintln!("[ANTIGRAVITY_OPENAI] ========== 非流式请求处理完成 ==========");
                     Json(openai_response).into_response()
                 }
                 Err(api_err) => {
-                    eprintln!("[ANTIGRAVITY_OPENAI] generate_content 失败 (HTTP {}): {}", api_err.status_code, api_err.message);
+                    eprintln!(
+                        "[ANTIGRAVITY_OPENAI] generate_content 失败 (HTTP {}): {}",
+                        api_err.status_code, api_err.message
+                    );
                     eprintln!("[ANTIGRAVITY_OPENAI] ========== 非流式请求处理失败 ==========");
 
                     // 直接使用 AntigravityApiError 的状态码构建响应
-                    build_error_response_with_status(api_err.status_code, &api_err.to_string())
+                    ProxyApiError::upstream(api_err.status_code, api_err.to_string())
+                        .into_response()
                 }
             }
         }
         CredentialData::OpenAIKey { api_key, base_url } => {
-            let openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+            let mut openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut openai.client);
 
-            tracing::info!("[OPENAI_KEY] request.stream = {}, model = {}", request.stream, request.model);
+            tracing::info!(
+                "[OPENAI_KEY] request.stream = {}, model = {}",
+                request.stream,
+                request.model
+            );
 
-            // 检查是否为流式请求
+            // 流式请求：按字节透传上游 SSE，保留分块时机、末尾的 usage 块和 [DONE]
             if request.stream {
                 tracing::info!("[OPENAI_KEY_STREAM] 处理流式请求, model={}", request.model);
-                match openai.call_api_stream(request).await {
-                    Ok(stream_response) => {
-                        tracing::info!("[OPENAI_KEY_STREAM] 开始直接转发 OpenAI SSE 流");
-
-                        // OpenAI 提供商已经返回 OpenAI SSE 格式，直接转发
-                        let body_stream = stream_response.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
-                            match result {
-                                Ok(bytes) => Ok(bytes),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
-                            }
-                        });
-
-                        return Response::builder()
-                            .status(StatusCode::OK)
-                            .header(header::CONTENT_TYPE, "text/event-stream")
-                            .header(header::CACHE_CONTROL, "no-cache")
-                            .header(header::CONNECTION, "keep-alive")
-                            .header(header::TRANSFER_ENCODING, "chunked")
-                            .header("X-Accel-Buffering", "no")
-                            .body(Body::from_stream(body_stream))
-                            .unwrap_or_else(|_| {
-                                (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
-                                )
-                                    .into_response()
-                            });
+                return match openai.call_api(request).await {
+                    Ok(resp) if resp.status().is_success() => {
+                        tracing::info!("[OPENAI_KEY_STREAM] 开始透传 OpenAI SSE 流");
+                        passthrough_sse_response(resp)
                     }
-                    Err(e) => {
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                        )
-                            .into_response();
+                    Ok(resp) => {
+                        let status = resp.status().as_u16();
+                        let headers = resp.headers().clone();
+                        let body = resp.text().await.unwrap_or_default();
+                        tracing::error!("[OPENAI_KEY_STREAM] 请求失败: {} - {}", status, body);
+                        ProxyApiError::upstream_with_headers(status, &headers, body).into_response()
                     }
-                }
+                    Err(e) => ProxyApiError::from_status(StatusCode::BAD_GATEWAY, e.to_string())
+                        .into_response(),
+                };
             }
 
             // 非流式请求处理
@@ -1625,33 +2048,29 @@ pub async fn call_provider_openai(
                                 if let Ok(json) = serde_json::from_str::<serde_json::Value>(&body) {
                                     Json(json).into_response()
                                 } else {
-                                    (
+                                    ProxyApiError::from_status(
                                         StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Invalid JSON response"}})),
+                                        "Invalid JSON response",
                                     )
-                                        .into_response()
+                                    .into_response()
                                 }
                             }
-                            Err(e) => (
+                            Err(e) => ProxyApiError::from_status(
                                 StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                                e.to_string(),
                             )
-                                .into_response(),
+                            .into_response(),
                         }
                     } else {
                         let body = resp.text().await.unwrap_or_default();
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": body}})),
-                        )
+                        ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, body)
                             .into_response()
                     }
                 }
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                )
-                    .into_response(),
+                Err(e) => {
+                    ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                        .into_response()
+                }
             }
         }
         CredentialData::ClaudeKey { api_key, base_url } => {
@@ -1663,7 +2082,8 @@ pub async fn call_provider_openai(
                 &credential.uuid[..8],
                 request.stream
             );
-            let claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let mut claude = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut claude.client);
 
             // 检查是否为流式请求
             if request.stream {
@@ -1674,12 +2094,15 @@ pub async fn call_provider_openai(
                         tracing::info!("[CLAUDE_KEY_STREAM] 开始转换 Anthropic SSE 到 OpenAI SSE");
 
                         // 创建 StreamConverter 将 Anthropic SSE 转换为 OpenAI SSE
+                        let reasoning = *state.processor.reasoning.read().await;
                         let converter = std::sync::Arc::new(tokio::sync::Mutex::new(
                             crate::streaming::converter::StreamConverter::with_model(
                                 crate::streaming::converter::StreamFormat::AnthropicSse,
                                 crate::streaming::converter::StreamFormat::OpenAiSse,
                                 &request.model,
-                            ),
+                            )
+                            .with_reasoning_mode(reasoning)
+                            .with_json_response_tool(forces_json_tool(request)),
                         ));
 
                         let converter_for_stream = converter.clone();
@@ -1720,12 +2143,14 @@ pub async fn call_provider_openai(
                             }
                         };
 
-                        let body_stream = final_stream.map(|result| -> Result<axum::body::Bytes, std::io::Error> {
-                            match result {
-                                Ok(event) => Ok(axum::body::Bytes::from(event)),
-                                Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
-                            }
-                        });
+                        let body_stream = final_stream.map(
+                            |result| -> Result<axum::body::Bytes, std::io::Error> {
+                                match result {
+                                    Ok(event) => Ok(axum::body::Bytes::from(event)),
+                                    Err(e) => Ok(axum::body::Bytes::from(e.to_sse_error())),
+                                }
+                            },
+                        );
 
                         return Response::builder()
                             .status(StatusCode::OK)
@@ -1736,42 +2161,56 @@ pub async fn call_provider_openai(
                             .header("X-Accel-Buffering", "no")
                             .body(Body::from_stream(body_stream))
                             .unwrap_or_else(|_| {
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(
-                                        serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                                    ),
+                                    "Failed to build streaming response",
                                 )
-                                    .into_response()
+                                .into_response()
                             });
                     }
                     Err(e) => {
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                            e.to_string(),
                         )
-                            .into_response();
+                        .into_response();
                     }
                 }
             }
 
             // 非流式请求处理
-            match claude.call_openai_api(request).await {
-                Ok(resp) => Json(resp).into_response(),
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                )
-                    .into_response(),
+            let claude = &claude;
+            match call_with_response_format(request, move || claude.call_openai_api(request)).await
+            {
+                Ok(mut resp) => {
+                    let reasoning = *state.processor.reasoning.read().await;
+                    apply_reasoning_to_openai_response(&mut resp, reasoning);
+                    Json(resp).into_response()
+                }
+                Err(e) => {
+                    ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                        .into_response()
+                }
             }
         }
-        CredentialData::VertexKey { api_key, base_url, model_aliases } => {
+        CredentialData::VertexKey {
+            api_key,
+            base_url,
+            model_aliases,
+        } => {
             // Resolve model alias if present
-            let resolved_model = model_aliases.get(&request.model).cloned().unwrap_or_else(|| request.model.clone());
+            let resolved_model = model_aliases
+                .get(&request.model)
+                .cloned()
+                .unwrap_or_else(|| request.model.clone());
             let mut modified_request = request.clone();
             modified_request.model = resolved_model;
-            let vertex = VertexProvider::with_config(api_key.clone(), base_url.clone());
-            match vertex.chat_completions(&serde_json::to_value(&modified_request).unwrap_or_default()).await {
+            let mut vertex = VertexProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut vertex.client);
+            match vertex
+                .chat_completions(&serde_json::to_value(&modified_request).unwrap_or_default())
+                .await
+            {
                 Ok(resp) => {
                     if resp.status().is_success() {
                         match resp.text().await {
@@ -1779,32 +2218,70 @@ pub async fn call_provider_openai(
                                 if let Ok(json) = serde_json::from_str::<serde_json::Value>(&body) {
                                     Json(json).into_response()
                                 } else {
-                                    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": "Invalid JSON response"}}))).into_response()
+                                    ProxyApiError::from_status(
+                                        StatusCode::INTERNAL_SERVER_ERROR,
+                                        "Invalid JSON response",
+                                    )
+                                    .into_response()
                                 }
                             }
-                            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response(),
+                            Err(e) => ProxyApiError::from_status(
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                e.to_string(),
+                            )
+                            .into_response(),
                         }
                     } else {
                         let body = resp.text().await.unwrap_or_default();
-                        (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": body}}))).into_response()
+                        ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, body)
+                            .into_response()
                     }
                 }
-                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response(),
+                Err(e) => {
+                    ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                        .into_response()
+                }
             }
         }
-        // Gemini API Key credentials - not supported for OpenAI format yet
         CredentialData::GeminiApiKey { .. } => {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "Gemini API Key credentials do not support OpenAI format yet"}})),
-            )
-                .into_response()
+            let mut openai_resp = match call_with_response_format(request, move || {
+                call_gemini_api_key(state, credential, request)
+            })
+            .await
+            {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
+            let reasoning = *state.processor.reasoning.read().await;
+            if !request.stream {
+                apply_reasoning_to_openai_response(&mut openai_resp, reasoning);
+                return Json(openai_resp).into_response();
+            }
+            // 上游按非流式调用，再合成 OpenAI SSE（与 Antigravity 流式一致）
+            let extracted = AntigravityContent::from_openai_response(&openai_resp);
+            match build_sse_response(&extracted, &request.model, reasoning) {
+                Ok(sse) => Response::builder()
+                    .status(StatusCode::OK)
+                    .header(header::CONTENT_TYPE, "text/event-stream")
+                    .header(header::CACHE_CONTROL, "no-cache")
+                    .body(Body::from(sse))
+                    .unwrap_or_else(|_| {
+                        ProxyApiError::from_status(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "Failed to build streaming response",
+                        )
+                        .into_response()
+                    }),
+                Err(e) => ProxyApiError::from_status(StatusCode::BAD_GATEWAY, e).into_response(),
+            }
         }
         // AnthropicKey - 如果有自定义 base_url，使用 OpenAI 兼容格式调用
         CredentialData::AnthropicKey { api_key, base_url } => {
             // 如果有自定义 base_url，假设是 OpenAI 兼容的代理服务器
             if let Some(custom_url) = base_url {
-                let openai = OpenAICustomProvider::with_config(api_key.clone(), Some(custom_url.clone()));
+                let mut openai =
+                    OpenAICustomProvider::with_config(api_key.clone(), Some(custom_url.clone()));
+                use_shared_client(state, credential, &mut openai.client);
                 state.logs.write().await.add(
                     "info",
                     &format!(
@@ -1821,17 +2298,16 @@ pub async fn call_provider_openai(
                             "info",
                             &format!(
                                 "[OPENAI_COMPAT] 响应状态: status={} model={} stream={}",
-                                status,
-                                request.model,
-                                request.stream
+                                status, request.model, request.stream
                             ),
                         );
 
                         if request.stream && status.is_success() {
-                            state.logs.write().await.add(
-                                "info",
-                                "[OPENAI_COMPAT] 流式请求，透传 SSE 响应",
-                            );
+                            state
+                                .logs
+                                .write()
+                                .await
+                                .add("info", "[OPENAI_COMPAT] 流式请求，透传 SSE 响应");
                             if let Some(db) = &state.db {
                                 let _ = state.pool_service.mark_healthy(
                                     db,
@@ -1844,17 +2320,20 @@ pub async fn call_provider_openai(
                             return Response::builder()
                                 .status(StatusCode::OK)
                                 .header(header::CONTENT_TYPE, "text/event-stream")
-                                .header(header::CACHE_CONTROL, "no-cache, no-store, must-revalidate")
+                                .header(
+                                    header::CACHE_CONTROL,
+                                    "no-cache, no-store, must-revalidate",
+                                )
                                 .header("Connection", "keep-alive")
                                 .header("X-Accel-Buffering", "no") // 禁用 nginx 等代理的缓冲
                                 .header("Transfer-Encoding", "chunked")
                                 .body(Body::from_stream(stream))
                                 .unwrap_or_else(|_| {
-                                    (
+                                    ProxyApiError::from_status(
                                         StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Failed to build stream response"}})),
+                                        "Failed to build stream response",
                                     )
-                                        .into_response()
+                                    .into_response()
                                 });
                         }
 
@@ -1884,17 +2363,17 @@ pub async fn call_provider_openai(
                                 .header(header::CONTENT_TYPE, "application/json")
                                 .body(Body::from(body))
                                 .unwrap_or_else(|_| {
-                                    (
+                                    ProxyApiError::from_status(
                                         StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(serde_json::json!({"error": {"message": "Failed to build response"}})),
+                                        "Failed to build response",
                                     )
-                                        .into_response()
+                                    .into_response()
                                 }),
-                            Err(e) => (
+                            Err(e) => ProxyApiError::from_status(
                                 StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({"error": {"message": format!("Failed to read response: {}", e)}})),
+                                format!("Failed to read response: {}", e),
                             )
-                                .into_response(),
+                            .into_response(),
                         }
                     }
                     Err(e) => {
@@ -1905,20 +2384,20 @@ pub async fn call_provider_openai(
                                 Some(&format!("API call failed: {}", e)),
                             );
                         }
-                        (
+                        ProxyApiError::from_status(
                             StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({"error": {"message": format!("OpenAI compatible API call failed: {}", e)}})),
+                            format!("OpenAI compatible API call failed: {}", e),
                         )
-                            .into_response()
+                        .into_response()
                     }
                 }
             } else {
                 // 没有自定义 base_url，不支持 OpenAI 格式
-                (
+                ProxyApiError::from_status(
                     StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({"error": {"message": "AnthropicKey without custom base_url does not support OpenAI format. Use Anthropic format endpoint instead."}})),
+                    "AnthropicKey without custom base_url does not support OpenAI format. Use Anthropic format endpoint instead.",
                 )
-                    .into_response()
+                .into_response()
             }
         }
         // Codex OAuth 凭证处理
@@ -1928,12 +2407,13 @@ pub async fn call_provider_openai(
         } => {
             // 加载 Codex 凭证
             let mut codex = CodexProvider::new();
+            use_shared_client(state, credential, &mut codex.client);
             if let Err(e) = codex.load_credentials_from_path(creds_file_path).await {
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Codex credentials: {}", e)}})),
+                    format!("Failed to load Codex credentials: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
 
             // 如果配置了自定义 API Base URL，覆盖凭证文件中的配置
@@ -1945,22 +2425,22 @@ pub async fn call_provider_openai(
 
             // 确保 token 有效
             if let Err(e) = codex.ensure_valid_token().await {
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Codex token refresh failed: {}", e)}})),
+                    format!("Codex token refresh failed: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
 
             // 将 ChatCompletionRequest 转换为 serde_json::Value
             let request_json = match serde_json::to_value(request) {
                 Ok(v) => v,
                 Err(e) => {
-                    return (
+                    return ProxyApiError::from_status(
                         StatusCode::BAD_REQUEST,
-                        Json(serde_json::json!({"error": {"message": format!("Failed to serialize request: {}", e)}})),
+                        format!("Failed to serialize request: {}", e),
                     )
-                        .into_response();
+                    .into_response();
                 }
             };
 
@@ -1990,49 +2470,65 @@ pub async fn call_provider_openai(
                             buffer: String::new(),
                         }));
 
-                        let converted_stream = bytes_stream.map(move |result| {
-                            let state = Arc::clone(&state);
-                            async move {
-                                match result {
-                                    Ok(bytes) => {
-                                        let chunk = String::from_utf8_lossy(&bytes);
-                                        let mut state = state.lock().await;
-                                        state.buffer.push_str(&chunk);
-
-                                        let mut output = String::new();
-
-                                        // 处理缓冲区中的完整行
-                                        while let Some(newline_pos) = state.buffer.find('\n') {
-                                            let line = state.buffer[..newline_pos].to_string();
-                                            state.buffer = state.buffer[newline_pos + 1..].to_string();
-
-                                            if let Some(data) = line.strip_prefix("data: ") {
-                                                if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
-                                                    if let Some(converted) = convert_codex_event_to_openai_sse_with_state(
-                                                        &json,
-                                                        &mut state.convert_state,
-                                                    ) {
-                                                        output.push_str(&format!("data: {}\n\n", converted));
+                        let converted_stream = bytes_stream
+                            .map(move |result| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    match result {
+                                        Ok(bytes) => {
+                                            let chunk = String::from_utf8_lossy(&bytes);
+                                            let mut state = state.lock().await;
+                                            state.buffer.push_str(&chunk);
+
+                                            let mut output = String::new();
+
+                                            // 处理缓冲区中的完整行
+                                            while let Some(newline_pos) = state.buffer.find('\n') {
+                                                let line = state.buffer[..newline_pos].to_string();
+                                                state.buffer =
+                                                    state.buffer[newline_pos + 1..].to_string();
+
+                                                if let Some(data) = line.strip_prefix("data: ") {
+                                                    if let Ok(json) =
+                                                        serde_json::from_str::<serde_json::Value>(
+                                                            data,
+                                                        )
+                                                    {
+                                                        if let Some(converted) =
+                                                            convert_codex_event_to_openai_sse(
+                                                                &json,
+                                                                &mut state.convert_state,
+                                                            )
+                                                        {
+                                                            output.push_str(&format!(
+                                                                "data: {}\n\n",
+                                                                converted
+                                                            ));
+                                                        }
                                                     }
                                                 }
                                             }
-                                        }
 
-                                        Ok::<_, std::io::Error>(bytes::Bytes::from(output))
-                                    }
-                                    Err(e) => {
-                                        tracing::error!("[Codex] Stream error: {}", e);
-                                        Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))
+                                            Ok::<_, std::io::Error>(bytes::Bytes::from(output))
+                                        }
+                                        Err(e) => {
+                                            tracing::error!("[Codex] Stream error: {}", e);
+                                            Err(std::io::Error::new(
+                                                std::io::ErrorKind::Other,
+                                                e.to_string(),
+                                            ))
+                                        }
                                     }
                                 }
-                            }
-                        }).buffer_unordered(1).filter_map(|result| async move {
-                            match result {
-                                Ok(bytes) if !bytes.is_empty() => Some(Ok(bytes)),
-                                Ok(_) => None,
-                                Err(e) => Some(Err(e)),
-                            }
-                        });
+                            })
+                            .buffer_unordered(1)
+                            .filter_map(|result| async move {
+                                match result {
+                                    Ok(bytes) if !bytes.is_empty() => Some(Ok(bytes)),
+                                    Ok(_) => None,
+                                    Err(e) => Some(Err(e)),
+                                }
+                            });
 
                         let body = Body::from_stream(converted_stream);
                         let mut response_builder = Response::builder()
@@ -2051,7 +2547,10 @@ pub async fn call_provider_openai(
                         }
 
                         response_builder.body(body).unwrap_or_else(|_| {
-                            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response")
+                            (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                "Failed to build response",
+                            )
                                 .into_response()
                         })
                     } else {
@@ -2065,8 +2564,12 @@ pub async fn call_provider_openai(
 
                                 for line in body_str.lines() {
                                     if let Some(data) = line.strip_prefix("data: ") {
-                                        if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
-                                            if json.get("type").and_then(|t| t.as_str()) == Some("response.completed") {
+                                        if let Ok(json) =
+                                            serde_json::from_str::<serde_json::Value>(data)
+                                        {
+                                            if json.get("type").and_then(|t| t.as_str())
+                                                == Some("response.completed")
+                                            {
                                                 completed_data = Some(json);
                                                 break;
                                             }
@@ -2077,89 +2580,848 @@ pub async fn call_provider_openai(
                                 match completed_data {
                                     Some(codex_response) => {
                                         // 转换为 OpenAI Chat Completions 格式
-                                        let openai_response = convert_codex_to_openai_non_stream(&codex_response);
+                                        let openai_response =
+                                            convert_codex_to_openai_non_stream(&codex_response);
                                         Response::builder()
                                             .status(StatusCode::OK)
                                             .header(header::CONTENT_TYPE, "application/json")
                                             .body(Body::from(openai_response.to_string()))
                                             .unwrap_or_else(|_| {
-                                                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response")
+                                                (
+                                                    StatusCode::INTERNAL_SERVER_ERROR,
+                                                    "Failed to build response",
+                                                )
                                                     .into_response()
                                             })
                                     }
                                     None => {
                                         tracing::error!("[Codex] No response.completed event found in SSE stream");
-                                        (
+                                        ProxyApiError::from_status(
                                             StatusCode::INTERNAL_SERVER_ERROR,
-                                            Json(serde_json::json!({"error": {"message": "No response.completed event found in Codex response"}})),
+                                            "No response.completed event found in Codex response",
                                         )
-                                            .into_response()
+                                        .into_response()
                                     }
                                 }
                             }
                             Err(e) => {
                                 tracing::error!("[Codex] Failed to read response body: {}", e);
-                                (
+                                ProxyApiError::from_status(
                                     StatusCode::INTERNAL_SERVER_ERROR,
-                                    Json(serde_json::json!({"error": {"message": format!("Failed to read Codex response: {}", e)}})),
+                                    format!("Failed to read Codex response: {}", e),
                                 )
-                                    .into_response()
+                                .into_response()
                             }
                         }
                     }
                 }
                 Err(e) => {
                     tracing::error!("[Codex] API call failed: {}", e);
-                    (
+                    ProxyApiError::from_status(
                         StatusCode::BAD_GATEWAY,
-                        Json(serde_json::json!({"error": {"message": format!("Codex API call failed: {}", e)}})),
+                        format!("Codex API call failed: {}", e),
                     )
-                        .into_response()
+                    .into_response()
                 }
             }
         }
-        // 新增的凭证类型暂不支持 OpenAI 格式
-        CredentialData::ClaudeOAuth { .. } => {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "This credential type does not support OpenAI format yet"}})),
-            )
-                .into_response()
+        CredentialData::QwenOAuth { creds_file_path } => {
+            let resp =
+                match call_qwen_with_refresh(state, credential, creds_file_path, request).await {
+                    Ok(resp) => resp,
+                    Err(error_response) => return error_response,
+                };
+
+            let status = resp.status();
+            if !status.is_success() {
+                let body = resp.text().await.unwrap_or_default();
+                return qwen_upstream_error(state, credential, status.as_u16(), body);
+            }
+
+            if let Some(db) = &state.db {
+                let _ = state
+                    .pool_service
+                    .mark_healthy(db, &credential.uuid, Some(&request.model));
+                let _ = state.pool_service.record_usage(db, &credential.uuid);
+            }
+
+            // Qwen 返回标准 OpenAI SSE，直接透传
+            if request.stream {
+                return Response::builder()
+                    .status(StatusCode::OK)
+                    .header(header::CONTENT_TYPE, "text/event-stream")
+                    .header(header::CACHE_CONTROL, "no-cache")
+                    .header(header::CONNECTION, "keep-alive")
+                    .header("X-Accel-Buffering", "no")
+                    .body(Body::from_stream(resp.bytes_stream()))
+                    .unwrap_or_else(|_| {
+                        ProxyApiError::from_status(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "Failed to build streaming response",
+                        )
+                        .into_response()
+                    });
+            }
+
+            match resp.json::<serde_json::Value>().await {
+                Ok(json) => Json(json).into_response(),
+                Err(e) => build_error_response_with_status(
+                    StatusCode::BAD_GATEWAY.as_u16(),
+                    &format!("Invalid JSON response from Qwen: {}", e),
+                ),
+            }
         }
-    }
-}
+        CredentialData::AzureOpenaiKey { .. } => {
+            let resp = match call_azure_openai(state, credential, request).await {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
 
-// ============================================================================
-// 流式传输支持
-// ============================================================================
+            // Azure 返回标准 OpenAI SSE，直接透传
+            if request.stream {
+                return passthrough_sse_response(resp);
+            }
 
-/// 获取凭证对应的流式格式
-///
-/// 根据凭证类型返回对应的流式响应格式。
-///
-/// # 参数
-/// - `credential`: 凭证信息
-///
-/// # 返回
-/// 流式格式枚举
-pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> StreamingFormat {
-    match &credential.credential {
-        CredentialData::KiroOAuth { .. } => StreamingFormat::AwsEventStream,
-        CredentialData::ClaudeKey { .. } => StreamingFormat::AnthropicSse,
-        CredentialData::OpenAIKey { .. } => StreamingFormat::OpenAiSse,
-        // TODO: 任务 6 完成后，将这些改为 GeminiStream
-        CredentialData::AntigravityOAuth { .. } => StreamingFormat::OpenAiSse,
-        CredentialData::GeminiOAuth { .. } => StreamingFormat::OpenAiSse,
-        CredentialData::GeminiApiKey { .. } => StreamingFormat::OpenAiSse,
-        CredentialData::VertexKey { .. } => StreamingFormat::OpenAiSse,
-        _ => StreamingFormat::OpenAiSse,
-    }
-}
+            match resp.json::<serde_json::Value>().await {
+                Ok(json) => Json(json).into_response(),
+                Err(e) => build_error_response_with_status(
+                    StatusCode::BAD_GATEWAY.as_u16(),
+                    &format!("Invalid JSON response from Azure OpenAI: {}", e),
+                ),
+            }
+        }
+        CredentialData::VertexServiceAccount { .. } => {
+            let resp = match call_vertex_service_account(state, credential, request).await {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
 
-/// 处理流式响应
-///
-/// 使用 StreamManager 处理流式响应，集成 Flow Monitor。
-///
+            if request.stream {
+                return passthrough_sse_response(resp);
+            }
+
+            match resp.json::<serde_json::Value>().await {
+                Ok(json) => Json(json).into_response(),
+                Err(e) => build_error_response_with_status(
+                    StatusCode::BAD_GATEWAY.as_u16(),
+                    &format!("Invalid JSON response from Vertex AI: {}", e),
+                ),
+            }
+        }
+        CredentialData::Ollama { .. } => {
+            let resp = match call_ollama(state, credential, request).await {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
+
+            if request.stream {
+                return passthrough_sse_response(resp);
+            }
+
+            match resp.json::<serde_json::Value>().await {
+                Ok(json) => Json(json).into_response(),
+                Err(e) => build_error_response_with_status(
+                    StatusCode::BAD_GATEWAY.as_u16(),
+                    &format!("Invalid JSON response from local model server: {}", e),
+                ),
+            }
+        }
+        CredentialData::Mock { .. } => {
+            let resp = match call_mock(state, credential, request).await {
+                Ok(resp) => resp,
+                Err(error_response) => return error_response,
+            };
+            if !request.stream {
+                return Json(resp).into_response();
+            }
+
+            let include_usage = request
+                .extra
+                .get("stream_options")
+                .and_then(|o| o.get("include_usage"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "text/event-stream")
+                .header(header::CACHE_CONTROL, "no-cache")
+                .header(header::CONNECTION, "keep-alive")
+                .body(Body::from(mock::to_openai_sse(&resp, include_usage)))
+                .unwrap_or_else(|_| {
+                    ProxyApiError::from_status(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to build streaming response",
+                    )
+                    .into_response()
+                })
+        }
+        CredentialData::AwsBedrock { .. } => {
+            if !request.stream {
+                return match call_bedrock_converse(state, credential, request).await {
+                    Ok(resp) => Json(resp).into_response(),
+                    Err(error_response) => error_response,
+                };
+            }
+
+            let Some(config) = BedrockConfig::from_credential(&credential.credential) else {
+                return ProxyApiError::from_status(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Invalid Bedrock credential",
+                )
+                .into_response();
+            };
+            let resp = match bedrock::BedrockProvider::new(config)
+                .converse_stream(request)
+                .await
+            {
+                Ok(resp) => resp,
+                Err(e) => return bedrock_call_error(state, credential, e.as_ref()),
+            };
+
+            let status = resp.status();
+            if !status.is_success() {
+                let body = resp.text().await.unwrap_or_default();
+                return bedrock_upstream_error(state, credential, status.as_u16(), body);
+            }
+
+            if let Some(db) = &state.db {
+                let _ = state
+                    .pool_service
+                    .mark_healthy(db, &credential.uuid, Some(&request.model));
+                let _ = state.pool_service.record_usage(db, &credential.uuid);
+            }
+
+            let include_usage = request
+                .extra
+                .get("stream_options")
+                .and_then(|o| o.get("include_usage"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "text/event-stream")
+                .header(header::CACHE_CONTROL, "no-cache")
+                .header(header::CONNECTION, "keep-alive")
+                .header("X-Accel-Buffering", "no")
+                .body(Body::from_stream(bedrock::converse_stream_to_openai_sse(
+                    resp,
+                    request.model.clone(),
+                    include_usage,
+                )))
+                .unwrap_or_else(|_| {
+                    ProxyApiError::from_status(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to build streaming response",
+                    )
+                    .into_response()
+                })
+        }
+        // 新增的凭证类型暂不支持 OpenAI 格式
+        CredentialData::ClaudeOAuth { .. } => ProxyApiError::from_status(
+            StatusCode::BAD_REQUEST,
+            "This credential type does not support OpenAI format yet",
+        )
+        .into_response(),
+    }
+}
+
+// ============================================================================
+// 凭证故障转移
+// ============================================================================
+
+/// 故障转移路由参数
+pub struct FailoverRoute<'a> {
+    /// 首选 Provider（凭证池类型字符串）
+    pub provider: &'a str,
+    /// 是否允许降级到配置的备用 Provider
+    ///
+    /// 通过 X-Provider-Id 显式指定 Provider 时不应降级到其他 Provider
+    pub allow_fallback_providers: bool,
+    /// 客户端类型（用于凭证兼容性检查）
+    pub client_type: Option<&'a ClientType>,
+    /// 会话粘性路由的会话标识，故障转移时改绑到新凭证
+    pub session_key: Option<&'a str>,
+}
+
+/// 判断上游响应是否需要换用下一个凭证重试
+///
+/// 5xx 表示上游服务异常，401 表示凭证失效，换凭证可能恢复；
+/// 其余 4xx 多为请求本身的问题，换凭证重试没有意义。
+pub fn should_failover_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::UNAUTHORIZED
+}
+
+/// 调用 Provider (Anthropic 格式)，失败时自动切换到下一个健康凭证
+pub async fn call_provider_anthropic_with_failover(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    credential: ProviderCredential,
+    route: FailoverRoute<'_>,
+    request: &AnthropicMessagesRequest,
+    flow_id: Option<&str>,
+) -> Response {
+    call_with_failover(
+        state,
+        ctx,
+        credential,
+        route,
+        &request.model,
+        |cred| async move { call_provider_anthropic(state, &cred, request, flow_id).await },
+    )
+    .await
+}
+
+/// 调用 Provider (OpenAI 格式)，失败时自动切换到下一个健康凭证
+pub async fn call_provider_openai_with_failover(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    credential: ProviderCredential,
+    route: FailoverRoute<'_>,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+) -> Response {
+    call_with_failover(
+        state,
+        ctx,
+        credential,
+        route,
+        &request.model,
+        |cred| async move { call_provider_openai(state, &cred, request, flow_id).await },
+    )
+    .await
+}
+
+/// 故障转移循环
+///
+/// 上游返回 5xx/401 时，先在同一 Provider 中选择未尝试过的健康凭证，
+/// 用尽后按顺序尝试配置的备用 Provider，直到成功或达到最大尝试次数。
+/// 上游限流（429/529）时同样优先换用其他凭证；没有可换的凭证时，
+/// 按 `Retry-After` 或 Provider 的退避策略等待后重试同一凭证，次数受重试配置限制。
+/// 凭证的健康状态与熔断计数由 `call_provider_*` 内部更新，
+/// 这里只负责选择下一个凭证，并把每次失败的尝试记录到遥测。
+/// 模型启用了请求对冲时，首次尝试通过 [`call_hedged`] 发出。
+///
+/// 客户端在拿到响应前断开连接时，axum 丢弃处理函数的 future，进行中的上游请求随之取消，
+/// 并由 [`DisconnectGuard`] 将请求记录为已取消。
+async fn call_with_failover<F, Fut>(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    credential: ProviderCredential,
+    route: FailoverRoute<'_>,
+    model: &str,
+    call: F,
+) -> Response
+where
+    F: Fn(ProviderCredential) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let settings = state.failover.read().await.clone();
+    let hedger = state.processor.hedger.read().await.clone();
+    let retrier = state.processor.retrier.clone();
+    let max_attempts = if settings.enabled {
+        settings.max_attempts.max(1)
+    } else {
+        1
+    };
+
+    let mut providers = vec![route.provider];
+    if route.allow_fallback_providers {
+        providers.extend(settings.fallback_providers.iter().map(String::as_str));
+    }
+    let mut provider_index = 0;
+    let mut tried: Vec<String> = Vec::new();
+    let mut credential = credential;
+    let mut attempt = 1;
+    let mut rate_limit_retries = 0;
+    let mut disconnect = DisconnectGuard::new(state, ctx);
+
+    loop {
+        let current = credential.clone();
+        disconnect.track(&current);
+        let result = if attempt == 1 && hedger.config().applies_to(model) {
+            let target = HedgeTarget {
+                providers: &providers,
+                model,
+                client_type: route.client_type,
+            };
+            call_hedged(state, ctx, &hedger, target, credential, &call, &mut tried).await
+        } else {
+            call_credential(state, credential, ctx.is_stream, &call).await
+        };
+
+        ctx.set_provider(result.provider_type);
+        ctx.set_credential_id(result.uuid.clone());
+        disconnect.track_context(ctx);
+
+        let AttemptResult {
+            uuid,
+            provider_type,
+            response,
+            in_flight,
+            permit,
+        } = result;
+        let status = response.status();
+        let rate_limited = UpstreamErrorKind::classify(Some(status.as_u16()), "").is_rate_limited();
+        if !should_failover_status(status) && !rate_limited {
+            disconnect.disarm();
+            return hold_until_body_end(response, (in_flight, permit));
+        }
+        drop(in_flight);
+        drop(permit);
+
+        tried.push(uuid.clone());
+        let next = if attempt < max_attempts {
+            next_failover_credential(
+                state,
+                &providers,
+                &mut provider_index,
+                model,
+                route.client_type,
+                route.session_key,
+                &tried,
+            )
+        } else {
+            None
+        };
+
+        let telemetry_status = crate::telemetry::RequestStatus::from_http_status(status.as_u16());
+
+        if let Some(next) = next {
+            let message = format!(
+                "HTTP {}，故障转移到凭证 {}",
+                status.as_u16(),
+                &next.uuid[..8.min(next.uuid.len())]
+            );
+            record_request_telemetry(state, ctx, telemetry_status, Some(message));
+            state.logs.write().await.add(
+                "warn",
+                &format!(
+                    "[FAILOVER] request_id={} attempt={}/{} status={} credential={} -> next={} ({})",
+                    ctx.request_id,
+                    attempt,
+                    max_attempts,
+                    status.as_u16(),
+                    &uuid[..8.min(uuid.len())],
+                    &next.uuid[..8.min(next.uuid.len())],
+                    next.provider_type
+                ),
+            );
+
+            ctx.increment_retry();
+            credential = next;
+            attempt += 1;
+            continue;
+        }
+
+        // 没有可换的凭证：限流时按 Retry-After / 退避策略等待后重试同一凭证
+        if !rate_limited || rate_limit_retries >= retrier.config().max_retries {
+            disconnect.disarm();
+            return response;
+        }
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
+        let Some(delay) = retrier.retry_delay(provider_type, rate_limit_retries, retry_after)
+        else {
+            disconnect.disarm();
+            return response;
+        };
+
+        let message = format!(
+            "HTTP {}，{}ms 后重试凭证 {}",
+            status.as_u16(),
+            delay.as_millis(),
+            &current.uuid[..8.min(current.uuid.len())]
+        );
+        record_request_telemetry(state, ctx, telemetry_status, Some(message));
+        state.logs.write().await.add(
+            "warn",
+            &format!(
+                "[RATE_LIMIT] request_id={} retry={}/{} status={} provider={} credential={} retry_after={:?} delay={}ms",
+                ctx.request_id,
+                rate_limit_retries + 1,
+                retrier.config().max_retries,
+                status.as_u16(),
+                provider_type,
+                &current.uuid[..8.min(current.uuid.len())],
+                retry_after,
+                delay.as_millis()
+            ),
+        );
+
+        tokio::time::sleep(delay).await;
+        ctx.increment_retry();
+        rate_limit_retries += 1;
+        credential = current;
+    }
+}
+
+/// 客户端断开检测
+///
+/// 故障转移循环返回响应前被丢弃，说明客户端已断开、处理函数的 future 被 axum 取消，
+/// 此时将请求记录为已取消。遥测需要的上下文在每次尝试时同步一份快照。
+struct DisconnectGuard<'a> {
+    state: &'a AppState,
+    ctx: Option<RequestContext>,
+}
+
+impl<'a> DisconnectGuard<'a> {
+    fn new(state: &'a AppState, ctx: &RequestContext) -> Self {
+        Self {
+            state,
+            ctx: Some(ctx.clone()),
+        }
+    }
+
+    /// 记录即将调用的凭证
+    fn track(&mut self, credential: &ProviderCredential) {
+        if let Some(ctx) = self.ctx.as_mut() {
+            ctx.set_provider(credential.provider_type);
+            ctx.set_credential_id(credential.uuid.clone());
+        }
+    }
+
+    /// 同步调用后的上下文（Provider、凭证和重试次数）
+    fn track_context(&mut self, ctx: &RequestContext) {
+        if self.ctx.is_some() {
+            self.ctx = Some(ctx.clone());
+        }
+    }
+
+    /// 已拿到响应，不再记录取消
+    fn disarm(&mut self) {
+        self.ctx = None;
+    }
+}
+
+impl Drop for DisconnectGuard<'_> {
+    fn drop(&mut self) {
+        let Some(ctx) = self.ctx.take() else {
+            return;
+        };
+        tracing::info!(
+            "[CANCEL] request_id={} 客户端断开连接，已取消上游请求 elapsed={}ms",
+            ctx.request_id,
+            ctx.elapsed_ms()
+        );
+        record_request_telemetry(
+            self.state,
+            &ctx,
+            crate::telemetry::RequestStatus::Cancelled,
+            None,
+        );
+    }
+}
+
+/// 单个凭证的调用结果
+struct AttemptResult {
+    uuid: String,
+    provider_type: crate::ProviderType,
+    response: Response,
+    in_flight: InFlightGuard,
+    /// Provider / 凭证维度的并发名额
+    permit: ConcurrencyPermit,
+}
+
+/// 调用单个凭证，并记录进行中计数与成功请求的延迟
+///
+/// 调用前获取该 Provider 和凭证的并发名额，超限时不请求上游，直接返回 429。
+/// 调用受该 Provider 的超时设置限制：流式请求限制首字节时间，非流式请求限制总时间，
+/// 超时后取消上游请求并返回 504，由故障转移换用下一个凭证。
+/// 启用故障注入时，注入的延迟同样计入超时。
+async fn call_credential<F, Fut>(
+    state: &AppState,
+    credential: ProviderCredential,
+    streaming: bool,
+    call: &F,
+) -> AttemptResult
+where
+    F: Fn(ProviderCredential) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let uuid = credential.uuid.clone();
+    let provider_type = credential.provider_type;
+    let limiter = state.processor.concurrency.read().await.clone();
+    let permit = match limiter
+        .acquire_credential(&provider_type.to_string(), &uuid)
+        .await
+    {
+        Ok(permit) => permit,
+        Err(e) => {
+            tracing::warn!(
+                "[CONCURRENCY] credential={} {}",
+                &uuid[..8.min(uuid.len())],
+                e
+            );
+            return AttemptResult {
+                in_flight: state.pool_service.begin_request(&uuid),
+                uuid,
+                provider_type,
+                response: crate::middleware::concurrency_error_response(&e),
+                permit: ConcurrencyPermit::default(),
+            };
+        }
+    };
+    let in_flight = state.pool_service.begin_request(&uuid);
+    let started = std::time::Instant::now();
+    let timeout = state
+        .http_clients
+        .timeout_controller(&provider_type.to_string());
+    let fault = state
+        .processor
+        .chaos
+        .read()
+        .await
+        .decide(&provider_type.to_string());
+    let response = match timeout
+        .execute_until_response(call_with_chaos(state, fault, credential, call), streaming)
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::warn!(
+                "[TIMEOUT] provider={} credential={} {}",
+                provider_type,
+                &uuid[..8.min(uuid.len())],
+                e
+            );
+            ProxyApiError::new(ProxyErrorKind::Timeout, e.to_string())
+                .with_code("upstream_timeout")
+                .into_response()
+        }
+    };
+    if response.status().is_success() {
+        state.pool_service.record_latency(&uuid, started.elapsed());
+    }
+    AttemptResult {
+        uuid,
+        provider_type,
+        response,
+        in_flight,
+        permit,
+    }
+}
+
+/// 按抽取到的故障调用凭证
+///
+/// 先等待注入的延迟；注入错误时不请求上游，按上游错误返回并像真实的 5xx 一样计入熔断；
+/// 注入损坏帧时在成功的流式响应的首个分片后插入一帧截断的 JSON。
+async fn call_with_chaos<F, Fut>(
+    state: &AppState,
+    fault: ChaosFault,
+    credential: ProviderCredential,
+    call: &F,
+) -> Response
+where
+    F: Fn(ProviderCredential) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    if fault.is_none() {
+        return call(credential).await;
+    }
+    let uuid = &credential.uuid;
+    tracing::warn!(
+        "[CHAOS] provider={} credential={} 注入故障: {:?}",
+        credential.provider_type,
+        &uuid[..8.min(uuid.len())],
+        fault
+    );
+
+    if let Some(delay) = fault.delay {
+        tokio::time::sleep(delay).await;
+    }
+    if let Some(status) = fault.error_status {
+        let message = format!("Chaos: injected upstream failure (status {})", status);
+        if status >= 500 {
+            if let Some(db) = &state.db {
+                let _ = state.pool_service.mark_unhealthy(db, uuid, Some(&message));
+            }
+        }
+        return ProxyApiError::upstream(status, message)
+            .with_code("chaos_injected")
+            .into_response();
+    }
+
+    let response = call(credential).await;
+    let is_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/event-stream"));
+    if !fault.malformed_stream || !is_stream || !response.status().is_success() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let mut injected = false;
+    let stream = body.into_data_stream().flat_map(move |chunk| {
+        let mut chunks = vec![chunk];
+        if !injected {
+            injected = true;
+            chunks.push(Ok(axum::body::Bytes::from_static(MALFORMED_SSE_FRAME)));
+        }
+        futures::stream::iter(chunks)
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 对冲请求的凭证选择范围
+struct HedgeTarget<'a> {
+    providers: &'a [&'a str],
+    model: &'a str,
+    client_type: Option<&'a ClientType>,
+}
+
+/// 对冲调用
+///
+/// 首选凭证在对冲延迟内没有返回时，按故障转移的顺序选择另一个凭证并发请求，
+/// 采用先成功的响应；落败的请求被丢弃，上游连接随之取消。
+/// 未被采用的凭证计入 `tried`，避免后续故障转移重复选择。
+async fn call_hedged<F, Fut>(
+    state: &AppState,
+    ctx: &RequestContext,
+    hedger: &Hedger,
+    target: HedgeTarget<'_>,
+    credential: ProviderCredential,
+    call: &F,
+    tried: &mut Vec<String>,
+) -> AttemptResult
+where
+    F: Fn(ProviderCredential) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let primary_uuid = credential.uuid.clone();
+    let mut hedge_uuid = None;
+
+    let outcome = hedger
+        .execute(
+            call_credential(state, credential, ctx.is_stream, call),
+            || {
+                let mut provider_index = 0;
+                let next = next_failover_credential(
+                    state,
+                    target.providers,
+                    &mut provider_index,
+                    target.model,
+                    target.client_type,
+                    None,
+                    std::slice::from_ref(&primary_uuid),
+                )?;
+                hedge_uuid = Some(next.uuid.clone());
+                Some(call_credential(state, next, ctx.is_stream, call))
+            },
+            |result| result.response.status().is_success(),
+        )
+        .await;
+
+    if let Some(hedge_uuid) = hedge_uuid.filter(|_| outcome.hedged) {
+        let (winner, loser) = match outcome.winner {
+            HedgeWinner::Primary => (primary_uuid, hedge_uuid),
+            HedgeWinner::Hedge => (hedge_uuid, primary_uuid),
+        };
+        state.logs.write().await.add(
+            "info",
+            &format!(
+                "[HEDGE] request_id={} delay={}ms winner={:?} credential={} cancelled={}",
+                ctx.request_id,
+                hedger.config().delay_ms,
+                outcome.winner,
+                &winner[..8.min(winner.len())],
+                &loser[..8.min(loser.len())]
+            ),
+        );
+        tried.push(loser);
+    }
+
+    outcome.value
+}
+
+/// 在响应体传输完成（或被丢弃）前保持凭证的进行中计数和并发名额
+///
+/// 流式响应在返回响应头后仍会占用上游连接，最少连接策略需要计入这段时间
+fn hold_until_body_end(response: Response, guards: (InFlightGuard, ConcurrencyPermit)) -> Response {
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        let _held = &guards;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 选择故障转移的下一个凭证
+///
+/// 当前 Provider 没有未尝试过的可用凭证时，推进到下一个备用 Provider。
+/// 传入 `session_key` 时会话改绑到选中的凭证，后续请求不再回到失败的凭证。
+fn next_failover_credential(
+    state: &AppState,
+    providers: &[&str],
+    provider_index: &mut usize,
+    model: &str,
+    client_type: Option<&ClientType>,
+    session_key: Option<&str>,
+    tried: &[String],
+) -> Option<ProviderCredential> {
+    let db = state.db.as_ref()?;
+    while let Some(provider) = providers.get(*provider_index) {
+        match state.pool_service.select_credential_for_session(
+            db,
+            provider,
+            Some(model),
+            client_type,
+            tried,
+            session_key,
+        ) {
+            Ok(Some(cred)) => return Some(cred),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!("[FAILOVER] 选择 {} 凭证失败: {}", provider, e);
+            }
+        }
+        *provider_index += 1;
+    }
+    None
+}
+
+// ============================================================================
+// 流式传输支持
+// ============================================================================
+
+/// 获取凭证对应的流式格式
+///
+/// 根据凭证类型返回对应的流式响应格式。
+///
+/// # 参数
+/// - `credential`: 凭证信息
+///
+/// # 返回
+/// 流式格式枚举
+pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> StreamingFormat {
+    match &credential.credential {
+        CredentialData::KiroOAuth { .. } => StreamingFormat::AwsEventStream,
+        CredentialData::ClaudeKey { .. } => StreamingFormat::AnthropicSse,
+        CredentialData::OpenAIKey { .. } => StreamingFormat::OpenAiSse,
+        // TODO: 任务 6 完成后，将这些改为 GeminiStream
+        CredentialData::AntigravityOAuth { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::GeminiOAuth { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::GeminiApiKey { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::VertexKey { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::VertexServiceAccount { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::QwenOAuth { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::AzureOpenaiKey { .. } => StreamingFormat::OpenAiSse,
+        // ConverseStream 的 event stream 在 Provider 层已转换为 OpenAI SSE
+        CredentialData::AwsBedrock { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::Ollama { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::Mock { .. } => StreamingFormat::OpenAiSse,
+        _ => StreamingFormat::OpenAiSse,
+    }
+}
+
+/// 处理流式响应
+///
+/// 使用 StreamManager 处理流式响应，集成 Flow Monitor。
+///
 /// # 参数
 /// - `state`: 应用状态
 /// - `flow_id`: Flow ID（用于 Flow Monitor 集成）
@@ -2264,13 +3526,11 @@ pub async fn handle_streaming_response(
         .header("X-Accel-Buffering", "no")
         .body(managed_stream)
         .unwrap_or_else(|_| {
-            (
+            ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
+                "Failed to build streaming response",
             )
-                .into_response()
+            .into_response()
         })
 }
 
@@ -2377,13 +3637,11 @@ pub async fn handle_streaming_response_with_timeout(
         .header("X-Accel-Buffering", "no")
         .body(Body::from_stream(body_stream))
         .unwrap_or_else(|_| {
-            (
+            ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
+                "Failed to build streaming response",
             )
-                .into_response()
+            .into_response()
         })
 }
 
@@ -2544,13 +3802,11 @@ pub async fn handle_streaming_with_disconnect_detection(
         .header("X-Accel-Buffering", "no")
         .body(body_stream)
         .unwrap_or_else(|_| {
-            (
+            ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
+                "Failed to build streaming response",
             )
-                .into_response()
+            .into_response()
         })
 }
 
@@ -2673,11 +3929,11 @@ pub async fn handle_kiro_stream(
         CredentialData::KiroOAuth { creds_file_path } => creds_file_path.clone(),
         _ => {
             tracing::error!("[KIRO_STREAM] 无效的凭证类型");
-            return (
+            return ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": {"message": "Invalid credential type for Kiro stream"}})),
+                "Invalid credential type for Kiro stream",
             )
-                .into_response();
+            .into_response();
         }
     };
 
@@ -2688,11 +3944,11 @@ pub async fn handle_kiro_stream(
         Some(db) => db,
         None => {
             tracing::error!("[KIRO_STREAM] 数据库不可用");
-            return (
+            return ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": {"message": "Database not available"}})),
+                "Database not available",
             )
-                .into_response();
+            .into_response();
         }
     };
 
@@ -2710,17 +3966,18 @@ pub async fn handle_kiro_stream(
             );
             // 回退到从源文件加载
             let mut kiro = KiroProvider::new();
+            use_shared_client(state, credential, &mut kiro.client);
             if let Err(e) = kiro.load_credentials_from_path(&creds_file_path).await {
                 let _ = state.pool_service.mark_unhealthy(
                     db,
                     &credential.uuid,
                     Some(&format!("Failed to load credentials: {}", e)),
                 );
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": {"message": format!("Failed to load Kiro credentials: {}", e)}})),
+                    format!("Failed to load Kiro credentials: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
             if let Err(e) = kiro.refresh_token().await {
                 let _ = state.pool_service.mark_unhealthy(
@@ -2728,11 +3985,11 @@ pub async fn handle_kiro_stream(
                     &credential.uuid,
                     Some(&format!("Token refresh failed: {}", e)),
                 );
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {}", e)}})),
+                    format!("Token refresh failed: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
             kiro.credentials.access_token.unwrap_or_default()
         }
@@ -2740,6 +3997,7 @@ pub async fn handle_kiro_stream(
 
     // 创建 KiroProvider 并设置 token
     let mut kiro = KiroProvider::new();
+    use_shared_client(state, credential, &mut kiro.client);
     // 从源文件加载其他配置（region, profile_arn 等）
     // 注意：必须先加载凭证文件，再设置 token，因为 load_credentials_from_path 会覆盖整个 credentials
     let _ = kiro.load_credentials_from_path(&creds_file_path).await;
@@ -2783,16 +4041,11 @@ pub async fn handle_kiro_stream(
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {}", refresh_err)),
                         );
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({
-                                "error": {
-                                    "type": "authentication_error",
-                                    "message": format!("Token refresh failed: {}", refresh_err)
-                                }
-                            })),
+                            format!("Token refresh failed: {}", refresh_err),
                         )
-                            .into_response();
+                        .into_response();
                     }
                 };
 
@@ -2806,16 +4059,11 @@ pub async fn handle_kiro_stream(
                             &credential.uuid,
                             Some(&retry_err.to_string()),
                         );
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(serde_json::json!({
-                                "error": {
-                                    "type": "api_error",
-                                    "message": format!("Retry failed after token refresh: {}", retry_err)
-                                }
-                            })),
+                            format!("Retry failed after token refresh: {}", retry_err),
                         )
-                            .into_response();
+                        .into_response();
                     }
                 }
             } else {
@@ -2823,16 +4071,11 @@ pub async fn handle_kiro_stream(
                     state
                         .pool_service
                         .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "error": {
-                            "type": "api_error",
-                            "message": e.to_string()
-                        }
-                    })),
+                    e.to_string(),
                 )
-                    .into_response();
+                .into_response();
             }
         }
     };
@@ -3026,13 +4269,11 @@ pub async fn handle_kiro_stream(
         .header("X-Accel-Buffering", "no")
         .body(Body::from_stream(body_stream))
         .unwrap_or_else(|_| {
-            (
+            ProxyApiError::from_status(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(
-                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
-                ),
+                "Failed to build streaming response",
             )
-                .into_response()
+            .into_response()
         })
 }
 
@@ -3054,7 +4295,11 @@ pub async fn handle_kiro_stream(
 ///   }
 /// }
 /// ```
-fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<String, String> {
+fn parse_antigravity_accumulated_response(
+    data: &str,
+    model: &str,
+    reasoning: ReasoningMode,
+) -> Result<String, String> {
     eprintln!(
         "[ANTIGRAVITY_PARSE] 开始解析累积数据，大小: {} bytes",
         data.len()
@@ -3082,14 +4327,13 @@ fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<Str
     // 首先尝试直接解析为单个 JSON
     if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
         eprintln!("[ANTIGRAVITY_PARSE] 单个 JSON 解析成功");
-        return parse_antigravity_json(&json, model);
+        return parse_antigravity_json(&json, model, reasoning);
     }
 
     // 如果失败，尝试按行解析，找到包含 candidates 的 JSON
     eprintln!("[ANTIGRAVITY_PARSE] 单个 JSON 解析失败，尝试按行解析");
 
-    let mut all_text = String::new();
-    let mut all_images: Vec<(String, String)> = Vec::new(); // (mime_type, data)
+    let mut all_content = AntigravityContent::default();
     let mut found_any = false;
 
     for line in data.lines() {
@@ -3100,9 +4344,8 @@ fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<Str
 
         // 尝试解析每一行
         if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
-            if let Some((text, images)) = extract_content_from_json(&json) {
-                all_text.push_str(&text);
-                all_images.extend(images);
+            if let Some(content) = extract_content_from_json(&json) {
+                all_content.extend(content);
                 found_any = true;
             }
         }
@@ -3110,11 +4353,12 @@ fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<Str
 
     if found_any {
         eprintln!(
-            "[ANTIGRAVITY_PARSE] 按行解析成功，文本长度: {}, 图片数: {}",
-            all_text.len(),
-            all_images.len()
+            "[ANTIGRAVITY_PARSE] 按行解析成功，文本长度: {}, 图片数: {}, 工具调用数: {}",
+            all_content.text.len(),
+            all_content.images.len(),
+            all_content.tool_calls.len()
         );
-        return build_sse_response(&all_text, &all_images, model);
+        return build_sse_response(&all_content, model, reasoning);
     }
 
     // 如果还是失败，尝试找到 JSON 对象的边界
@@ -3127,7 +4371,7 @@ fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<Str
         // 尝试从这个位置解析 JSON
         if let Ok(json) = serde_json::from_str::<serde_json::Value>(&data[json_start..]) {
             eprintln!("[ANTIGRAVITY_PARSE] 在位置 {} 找到有效 JSON", json_start);
-            return parse_antigravity_json(&json, model);
+            return parse_antigravity_json(&json, model, reasoning);
         }
         start = json_start + 1;
         if start >= data.len() {
@@ -3138,8 +4382,46 @@ fn parse_antigravity_accumulated_response(data: &str, model: &str) -> Result<Str
     Err(format!("无法解析响应数据，请查看 {:?}", debug_file))
 }
 
+/// 从 Antigravity 响应中提取的内容
+#[derive(Debug, Default)]
+struct AntigravityContent {
+    text: String,
+    /// 推理内容（thought parts），输出时按 [`ReasoningMode`] 处理
+    reasoning: String,
+    /// (mime_type, data)
+    images: Vec<(String, String)>,
+    tool_calls: Vec<crate::models::openai::ToolCall>,
+}
+
+impl AntigravityContent {
+    fn extend(&mut self, other: AntigravityContent) {
+        self.text.push_str(&other.text);
+        self.reasoning.push_str(&other.reasoning);
+        self.images.extend(other.images);
+        self.tool_calls.extend(other.tool_calls);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+            && self.reasoning.is_empty()
+            && self.images.is_empty()
+            && self.tool_calls.is_empty()
+    }
+
+    /// 从已转换的 OpenAI 响应中提取（图片已内嵌在文本中）
+    fn from_openai_response(openai_resp: &serde_json::Value) -> Self {
+        let parsed = parsed_from_openai_response(openai_resp, ReasoningMode::Map);
+        Self {
+            text: parsed.content,
+            reasoning: parsed.thinking,
+            images: Vec::new(),
+            tool_calls: parsed.tool_calls,
+        }
+    }
+}
+
 /// 从 JSON 中提取内容
-fn extract_content_from_json(json: &serde_json::Value) -> Option<(String, Vec<(String, String)>)> {
+fn extract_content_from_json(json: &serde_json::Value) -> Option<AntigravityContent> {
     // 尝试多种路径
     let candidates = json
         .get("response")
@@ -3154,6 +4436,7 @@ fn extract_content_from_json(json: &serde_json::Value) -> Option<(String, Vec<(S
     let mut text = String::new();
     let mut thinking_text = String::new();
     let mut images = Vec::new();
+    let mut tool_calls = Vec::new();
 
     for candidate in candidates {
         if let Some(parts) = candidate
@@ -3193,12 +4476,17 @@ fn extract_content_from_json(json: &serde_json::Value) -> Option<(String, Vec<(S
 
                 let has_content = part.get("text").is_some()
                     || part.get("inlineData").is_some()
-                    || part.get("inline_data").is_some();
+                    || part.get("inline_data").is_some()
+                    || part.get("functionCall").is_some();
 
                 if has_thought_signature && !has_content {
                     continue;
                 }
 
+                if let Some(fc) = part.get("functionCall") {
+                    tool_calls.push(function_call_to_openai(fc));
+                }
+
                 if let Some(t) = part.get("text").and_then(|t| t.as_str()) {
                     if is_thought {
                         // 思维内容
@@ -3223,24 +4511,25 @@ fn extract_content_from_json(json: &serde_json::Value) -> Option<(String, Vec<(S
         }
     }
 
-    // 如果有 thinking 内容，用 <thinking> 标签包裹并放在前面
-    let mut final_text = String::new();
-    if !thinking_text.is_empty() {
-        final_text.push_str("<thinking>");
-        final_text.push_str(&thinking_text);
-        final_text.push_str("</thinking>\n\n");
-    }
-    final_text.push_str(&text);
-
-    if final_text.is_empty() && images.is_empty() {
+    let content = AntigravityContent {
+        text,
+        reasoning: thinking_text,
+        images,
+        tool_calls,
+    };
+    if content.is_empty() {
         None
     } else {
-        Some((final_text, images))
+        Some(content)
     }
 }
 
 /// 解析 Antigravity JSON 响应
-fn parse_antigravity_json(json: &serde_json::Value, model: &str) -> Result<String, String> {
+fn parse_antigravity_json(
+    json: &serde_json::Value,
+    model: &str,
+    reasoning: ReasoningMode,
+) -> Result<String, String> {
     eprintln!(
         "[ANTIGRAVITY_PARSE] 解析 JSON，顶层类型: {}",
         if json.is_object() {
@@ -3259,25 +4548,23 @@ fn parse_antigravity_json(json: &serde_json::Value, model: &str) -> Result<Strin
         );
     }
 
-    if let Some((text, images)) = extract_content_from_json(json) {
-        return build_sse_response(&text, &images, model);
+    if let Some(content) = extract_content_from_json(json) {
+        return build_sse_response(&content, model, reasoning);
     }
 
     // 如果是数组，尝试处理每个元素
     if let Some(arr) = json.as_array() {
         eprintln!("[ANTIGRAVITY_PARSE] 顶层是数组，长度: {}", arr.len());
-        let mut all_text = String::new();
-        let mut all_images = Vec::new();
+        let mut all_content = AntigravityContent::default();
 
         for item in arr {
-            if let Some((text, images)) = extract_content_from_json(item) {
-                all_text.push_str(&text);
-                all_images.extend(images);
+            if let Some(content) = extract_content_from_json(item) {
+                all_content.extend(content);
             }
         }
 
-        if !all_text.is_empty() || !all_images.is_empty() {
-            return build_sse_response(&all_text, &all_images, model);
+        if !all_content.is_empty() {
+            return build_sse_response(&all_content, model, reasoning);
         }
     }
 
@@ -3286,14 +4573,15 @@ fn parse_antigravity_json(json: &serde_json::Value, model: &str) -> Result<Strin
 
 /// 构建 SSE 响应
 fn build_sse_response(
-    text: &str,
-    images: &[(String, String)],
+    extracted: &AntigravityContent,
     model: &str,
+    reasoning: ReasoningMode,
 ) -> Result<String, String> {
-    let mut content = text.to_string();
+    let (reasoning_content, mut content) =
+        reasoning.apply(Some(&extracted.reasoning), &extracted.text);
 
     // 添加图片
-    for (mime, data) in images {
+    for (mime, data) in &extracted.images {
         let image_url = format!("data:{};base64,{}", mime, data);
         content.push_str(&format!("\n\n![Generated Image]({})", image_url));
     }
@@ -3305,6 +4593,21 @@ fn build_sse_response(
 
     let mut sse_output = String::new();
 
+    if let Some(reasoning_content) = reasoning_content {
+        let reasoning_chunk = serde_json::json!({
+            "id": &chunk_id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "delta": { "reasoning_content": reasoning_content },
+                "finish_reason": serde_json::Value::Null
+            }]
+        });
+        sse_output.push_str(&format!("data: {}\n\n", reasoning_chunk));
+    }
+
     if !content.is_empty() {
         let content_chunk = serde_json::json!({
             "id": &chunk_id,
@@ -3320,6 +4623,43 @@ fn build_sse_response(
         sse_output.push_str(&format!("data: {}\n\n", content_chunk.to_string()));
     }
 
+    // 工具调用一次性输出完整参数
+    if !extracted.tool_calls.is_empty() {
+        let tool_calls: Vec<serde_json::Value> = extracted
+            .tool_calls
+            .iter()
+            .enumerate()
+            .map(|(index, tc)| {
+                serde_json::json!({
+                    "index": index,
+                    "id": tc.id,
+                    "type": "function",
+                    "function": {
+                        "name": tc.function.name,
+                        "arguments": tc.function.arguments
+                    }
+                })
+            })
+            .collect();
+        let tool_chunk = serde_json::json!({
+            "id": &chunk_id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "delta": { "tool_calls": tool_calls },
+                "finish_reason": serde_json::Value::Null
+            }]
+        });
+        sse_output.push_str(&format!("data: {}\n\n", tool_chunk));
+    }
+
+    let finish_reason = if extracted.tool_calls.is_empty() {
+        "stop"
+    } else {
+        "tool_calls"
+    };
     let done_chunk = serde_json::json!({
         "id": &chunk_id,
         "object": "chat.completion.chunk",
@@ -3328,7 +4668,7 @@ fn build_sse_response(
         "choices": [{
             "index": 0,
             "delta": {},
-            "finish_reason": "stop"
+            "finish_reason": finish_reason
         }]
     });
     sse_output.push_str(&format!("data: {}\n\n", done_chunk.to_string()));
@@ -3441,18 +4781,685 @@ fn convert_gemini_chunk_to_openai_sse(json: &serde_json::Value, model: &str) ->
     Some(format!("data: {}\n\n", response.to_string()))
 }
 
+// ============================================================================
+// Qwen OAuth 支持
+// ============================================================================
+
+/// 调用 Qwen OAuth 上游
+///
+/// 加载凭证并确保 Token 有效；上游返回 401 时强制刷新 Token 并重试一次。
+/// 返回上游原始响应（可能为非 2xx），凭证加载、刷新或网络失败时返回错误响应。
+async fn call_qwen_with_refresh(
+    state: &AppState,
+    credential: &ProviderCredential,
+    creds_file_path: &str,
+    request: &ChatCompletionRequest,
+) -> Result<reqwest::Response, Response> {
+    let mark_unhealthy = |message: &str| {
+        if let Some(db) = &state.db {
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(message));
+        }
+    };
+
+    let mut qwen = QwenProvider::new();
+    use_shared_client(state, credential, &mut qwen.client);
+    if let Err(e) = qwen.load_credentials_from_path(creds_file_path).await {
+        let message = format!("Failed to load Qwen credentials: {}", e);
+        mark_unhealthy(&message);
+        return Err(build_error_response_with_status(
+            StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+            &message,
+        ));
+    }
+
+    if let Err(e) = qwen.ensure_valid_token().await {
+        let message = format!("Qwen token refresh failed: {}", e);
+        mark_unhealthy(&message);
+        return Err(build_error_response_with_status(
+            StatusCode::UNAUTHORIZED.as_u16(),
+            &message,
+        ));
+    }
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[QWEN] 调用上游: base_url={} model={} stream={} credential_uuid={}",
+            qwen.get_base_url(),
+            request.model,
+            request.stream,
+            &credential.uuid[..8]
+        ),
+    );
+
+    let mut resp = match qwen.call_api(request).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            let message = format!("Qwen API call failed: {}", e);
+            mark_unhealthy(&message);
+            return Err(build_error_response_with_status(
+                StatusCode::BAD_GATEWAY.as_u16(),
+                &message,
+            ));
+        }
+    };
+
+    // access_token 可能在过期时间之前被服务端吊销，刷新后重试一次
+    if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+        state
+            .logs
+            .write()
+            .await
+            .add("warn", "[QWEN] 上游返回 401，刷新 Token 后重试");
+
+        if let Err(e) = qwen.refresh_token().await {
+            let message = format!("Qwen token refresh failed: {}", e);
+            mark_unhealthy(&message);
+            return Err(build_error_response_with_status(
+                StatusCode::UNAUTHORIZED.as_u16(),
+                &message,
+            ));
+        }
+
+        resp = match qwen.call_api(request).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                let message = format!("Qwen API call failed: {}", e);
+                mark_unhealthy(&message);
+                return Err(build_error_response_with_status(
+                    StatusCode::BAD_GATEWAY.as_u16(),
+                    &message,
+                ));
+            }
+        };
+    }
+
+    Ok(resp)
+}
+
+/// 转发 Qwen 上游错误
+///
+/// 与其他 Provider 保持一致：只有 5xx 和认证失败才标记凭证为不健康
+fn qwen_upstream_error(
+    state: &AppState,
+    credential: &ProviderCredential,
+    status_code: u16,
+    body: String,
+) -> Response {
+    tracing::error!(
+        "[QWEN] 请求失败: status={} body={}",
+        status_code,
+        safe_truncate(&body, 500)
+    );
+    if status_code >= 500 || status_code == 401 {
+        if let Some(db) = &state.db {
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(&body));
+        }
+    }
+    ProxyApiError::upstream(status_code, body).into_response()
+}
+
+/// 按模型解析部署并调用 Azure OpenAI，返回状态成功的上游响应
+/// 调用 Gemini API Key 凭证（非流式 generateContent），返回 OpenAI 格式响应
+async fn call_gemini_api_key(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+) -> Result<serde_json::Value, Response> {
+    let CredentialData::GeminiApiKey {
+        api_key,
+        base_url,
+        excluded_models,
+    } = &credential.credential
+    else {
+        return Err(ProxyApiError::from_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Invalid Gemini API Key credential",
+        )
+        .into_response());
+    };
+
+    let gemini_credential = GeminiApiKeyCredential::new(credential.uuid.clone(), api_key.clone())
+        .with_base_url(base_url.clone())
+        .with_excluded_models(excluded_models.clone());
+    let body = convert_openai_to_gemini(request);
+    tracing::info!(
+        "[GEMINI_API_KEY] model={} stream={} credential_uuid={}",
+        request.model,
+        request.stream,
+        &credential.uuid[..8]
+    );
+
+    let mut provider = GeminiApiKeyProvider::new();
+    use_shared_client(state, credential, &mut provider.client);
+    match provider
+        .generate_content(&gemini_credential, &request.model, &body)
+        .await
+    {
+        Ok(resp) => {
+            if let Some(db) = &state.db {
+                let _ = state
+                    .pool_service
+                    .mark_healthy(db, &credential.uuid, Some(&request.model));
+                let _ = state.pool_service.record_usage(db, &credential.uuid);
+            }
+            Ok(convert_antigravity_to_openai_response(
+                &resp,
+                &request.model,
+            ))
+        }
+        Err(e) => {
+            tracing::error!("[GEMINI_API_KEY] 调用失败: {}", e);
+            if let Some(db) = &state.db {
+                let _ =
+                    state
+                        .pool_service
+                        .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+            }
+            Err(build_error_response(&e.to_string()))
+        }
+    }
+}
+
+async fn call_azure_openai(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+) -> Result<reqwest::Response, Response> {
+    let CredentialData::AzureOpenaiKey {
+        api_key,
+        resource,
+        deployment,
+        api_version,
+        model_deployments,
+    } = &credential.credential
+    else {
+        return Err(ProxyApiError::from_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Invalid Azure OpenAI credential",
+        )
+        .into_response());
+    };
+
+    let Some(deployment) =
+        resolve_azure_deployment(model_deployments, deployment.as_deref(), &request.model)
+    else {
+        return Err(ProxyApiError::from_status(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "No Azure OpenAI deployment mapped for model: {}",
+                request.model
+            ),
+        )
+        .into_response());
+    };
+    tracing::info!(
+        "[AZURE_OPENAI] model={} -> deployment={} credential_uuid={}",
+        request.model,
+        deployment,
+        &credential.uuid[..8]
+    );
+
+    let mut provider =
+        AzureOpenAIProvider::new(api_key.clone(), resource.clone(), api_version.clone());
+    use_shared_client(state, credential, &mut provider.client);
+    let resp = match provider.call_api(&deployment, request).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            tracing::error!("[AZURE_OPENAI] 调用失败: {}", e);
+            if let Some(db) = &state.db {
+                let _ =
+                    state
+                        .pool_service
+                        .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+            }
+            return Err(ProxyApiError::from_status(
+                StatusCode::BAD_GATEWAY,
+                format!("Azure OpenAI API call failed: {}", e),
+            )
+            .into_response());
+        }
+    };
+
+    let status = resp.status();
+    if !status.is_success() {
+        let headers = resp.headers().clone();
+        let body = resp.text().await.unwrap_or_default();
+        tracing::error!(
+            "[AZURE_OPENAI] 请求失败: status={} body={}",
+            status,
+            safe_truncate(&body, 500)
+        );
+        if status.is_server_error() || status == StatusCode::UNAUTHORIZED {
+            if let Some(db) = &state.db {
+                let _ = state
+                    .pool_service
+                    .mark_unhealthy(db, &credential.uuid, Some(&body));
+            }
+        }
+        return Err(
+            ProxyApiError::upstream_with_headers(status.as_u16(), &headers, body).into_response(),
+        );
+    }
+
+    if let Some(db) = &state.db {
+        let _ = state
+            .pool_service
+            .mark_healthy(db, &credential.uuid, Some(&request.model));
+        let _ = state.pool_service.record_usage(db, &credential.uuid);
+    }
+    Ok(resp)
+}
+
+/// 使用服务账号调用 Vertex AI OpenAI 兼容端点，返回状态成功的上游响应
+async fn call_vertex_service_account(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+) -> Result<reqwest::Response, Response> {
+    let CredentialData::VertexServiceAccount {
+        creds_file_path,
+        project_id,
+        region,
+    } = &credential.credential
+    else {
+        return Err(ProxyApiError::from_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Invalid Vertex AI service account credential",
+        )
+        .into_response());
+    };
+
+    let mark_unhealthy = |message: &str| {
+        if let Some(db) = &state.db {
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(message));
+        }
+    };
+
+    let mut provider = match VertexServiceAccountProvider::load(
+        creds_file_path,
+        project_id.clone(),
+        region.clone(),
+    ) {
+        Ok(provider) => provider,
+        Err(e) => {
+            tracing::error!("[VERTEX_SA] 加载服务账号失败: {}", e);
+            mark_unhealthy(&e);
+            return Err(
+                ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+            );
+        }
+    };
+    use_shared_client(state, credential, &mut provider.client);
+
+    let resp = match provider.call_api(request).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            tracing::error!("[VERTEX_SA] 调用失败: {}", e);
+            mark_unhealthy(&e.to_string());
+            return Err(ProxyApiError::from_status(
+                StatusCode::BAD_GATEWAY,
+                format!("Vertex AI API call failed: {}", e),
+            )
+            .into_response());
+        }
+    };
+
+    let status = resp.status();
+    if !status.is_success() {
+        let headers = resp.headers().clone();
+        let body = resp.text().await.unwrap_or_default();
+        tracing::error!(
+            "[VERTEX_SA] 请求失败: status={} body={}",
+            status,
+            safe_truncate(&body, 500)
+        );
+        if status.is_server_error()
+            || status == StatusCode::UNAUTHORIZED
+            || status == StatusCode::FORBIDDEN
+        {
+            mark_unhealthy(&body);
+        }
+        return Err(
+            ProxyApiError::upstream_with_headers(status.as_u16(), &headers, body).into_response(),
+        );
+    }
+
+    if let Some(db) = &state.db {
+        let _ = state
+            .pool_service
+            .mark_healthy(db, &credential.uuid, Some(&request.model));
+        let _ = state.pool_service.record_usage(db, &credential.uuid);
+    }
+    Ok(resp)
+}
+
+/// 调用本地 OpenAI 兼容服务，返回状态成功的上游响应
+async fn call_ollama(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+) -> Result<reqwest::Response, Response> {
+    let CredentialData::Ollama { base_url } = &credential.credential else {
+        return Err(ProxyApiError::from_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Invalid local model credential",
+        )
+        .into_response());
+    };
+
+    let mut provider = OllamaProvider::new(base_url.clone());
+    use_shared_client(state, credential, &mut provider.client);
+    let resp = match provider.call_api(request).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            // 连接失败通常是本地服务未启动
+            tracing::error!("[OLLAMA] 调用 {} 失败: {}", provider.root_url(), e);
+            if let Some(db) = &state.db {
+                let _ =
+                    state
+                        .pool_service
+                        .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+            }
+            return Err(ProxyApiError::from_status(
+                StatusCode::BAD_GATEWAY,
+                format!("Local model server unavailable: {}", e),
+            )
+            .into_response());
+        }
+    };
+
+    let status = resp.status();
+    if !status.is_success() {
+        let headers = resp.headers().clone();
+        let body = resp.text().await.unwrap_or_default();
+        tracing::error!(
+            "[OLLAMA] 请求失败: status={} body={}",
+            status,
+            safe_truncate(&body, 500)
+        );
+        if status.is_server_error() {
+            if let Some(db) = &state.db {
+                let _ = state
+                    .pool_service
+                    .mark_unhealthy(db, &credential.uuid, Some(&body));
+            }
+        }
+        return Err(
+            ProxyApiError::upstream_with_headers(status.as_u16(), &headers, body).into_response(),
+        );
+    }
+
+    if let Some(db) = &state.db {
+        let _ = state
+            .pool_service
+            .mark_healthy(db, &credential.uuid, Some(&request.model));
+        let _ = state.pool_service.record_usage(db, &credential.uuid);
+    }
+    Ok(resp)
+}
+
+/// 调用 Mock 凭证，返回按模板生成的 OpenAI 格式响应
+///
+/// 与真实上游一样记录健康状态和用量，注入的错误按上游错误返回，可用于验证故障转移。
+async fn call_mock(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+) -> Result<ChatCompletionResponse, Response> {
+    let Some(provider) = MockProvider::from_credential(&credential.credential) else {
+        return Err(ProxyApiError::from_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Invalid mock credential",
+        )
+        .into_response());
+    };
+
+    if let Err(e) = provider.simulate().await {
+        tracing::warn!("[MOCK] 注入错误: status={}", e.status);
+        if e.status >= 500 {
+            if let Some(db) = &state.db {
+                let _ = state
+                    .pool_service
+                    .mark_unhealthy(db, &credential.uuid, Some(&e.message));
+            }
+        }
+        return Err(ProxyApiError::upstream(e.status, e.message).into_response());
+    }
+
+    let mut resp = provider.chat_completion(request);
+    let content = resp.choices[0]
+        .message
+        .content
+        .as_deref()
+        .unwrap_or_default();
+    let prompt_tokens = token_count::count_openai_input_tokens(request);
+    let completion_tokens = token_count::count_text_tokens(content, &request.model);
+    resp.usage = crate::models::openai::Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+    };
+
+    if let Some(db) = &state.db {
+        let _ = state
+            .pool_service
+            .mark_healthy(db, &credential.uuid, Some(&request.model));
+        let _ = state.pool_service.record_usage(db, &credential.uuid);
+    }
+    Ok(resp)
+}
+
+/// 调用 Bedrock Converse API（非流式），返回转换后的 OpenAI 格式响应
+async fn call_bedrock_converse(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+) -> Result<ChatCompletionResponse, Response> {
+    let Some(config) = BedrockConfig::from_credential(&credential.credential) else {
+        return Err(ProxyApiError::from_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Invalid Bedrock credential",
+        )
+        .into_response());
+    };
+
+    let mut provider = bedrock::BedrockProvider::new(config);
+    use_shared_client(state, credential, &mut provider.client);
+    let resp = provider
+        .converse(request)
+        .await
+        .map_err(|e| bedrock_call_error(state, credential, e.as_ref()))?;
+
+    let status = resp.status();
+    let body = resp.text().await.map_err(|e| {
+        build_error_response_with_status(
+            StatusCode::BAD_GATEWAY.as_u16(),
+            &format!("Failed to read Bedrock response: {}", e),
+        )
+    })?;
+    if !status.is_success() {
+        return Err(bedrock_upstream_error(
+            state,
+            credential,
+            status.as_u16(),
+            body,
+        ));
+    }
+
+    let value: serde_json::Value = serde_json::from_str(&body).map_err(|e| {
+        tracing::error!(
+            "[BEDROCK] 解析响应失败: {} body={}",
+            e,
+            safe_truncate(&body, 500)
+        );
+        build_error_response_with_status(
+            StatusCode::BAD_GATEWAY.as_u16(),
+            &format!("Failed to parse Bedrock response: {}", e),
+        )
+    })?;
+
+    if let Some(db) = &state.db {
+        let _ = state
+            .pool_service
+            .mark_healthy(db, &credential.uuid, Some(&request.model));
+        let _ = state.pool_service.record_usage(db, &credential.uuid);
+    }
+    Ok(bedrock::convert_converse_to_openai(&value, &request.model))
+}
+
+/// Bedrock 凭证解析、AssumeRole 或网络失败
+fn bedrock_call_error(
+    state: &AppState,
+    credential: &ProviderCredential,
+    error: &(dyn std::error::Error + Send + Sync),
+) -> Response {
+    tracing::error!("[BEDROCK] 调用失败: {}", error);
+    if let Some(db) = &state.db {
+        let _ = state
+            .pool_service
+            .mark_unhealthy(db, &credential.uuid, Some(&error.to_string()));
+    }
+    ProxyApiError::from_status(
+        StatusCode::BAD_GATEWAY,
+        format!("Bedrock API call failed: {}", error),
+    )
+    .into_response()
+}
+
+fn bedrock_upstream_error(
+    state: &AppState,
+    credential: &ProviderCredential,
+    status_code: u16,
+    body: String,
+) -> Response {
+    tracing::error!(
+        "[BEDROCK] 请求失败: status={} body={}",
+        status_code,
+        safe_truncate(&body, 500)
+    );
+    if status_code >= 500 || status_code == 401 || status_code == 403 {
+        if let Some(db) = &state.db {
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(&body));
+        }
+    }
+    ProxyApiError::upstream(status_code, body).into_response()
+}
+
+/// 按 `response_format` 校验上游返回的 JSON 输出，strict 模式下无效时重试一次
+async fn call_with_response_format<F, Fut, E>(
+    request: &ChatCompletionRequest,
+    mut call: F,
+) -> Result<serde_json::Value, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<serde_json::Value, E>>,
+{
+    let response = call().await?;
+    let Some(format) = ResponseFormat::from_request(request) else {
+        return Ok(response);
+    };
+    match format.validate_openai_response(&response) {
+        Ok(()) => Ok(response),
+        Err(e) if format.is_strict() => {
+            tracing::warn!("[RESPONSE_FORMAT] 输出校验失败，重试一次: {}", e);
+            call().await
+        }
+        Err(e) => {
+            tracing::warn!("[RESPONSE_FORMAT] 输出校验失败: {}", e);
+            Ok(response)
+        }
+    }
+}
+
+/// 从 OpenAI 格式的 JSON 响应中提取文本、推理内容、工具调用和 usage
+fn parsed_from_openai_response(
+    openai_resp: &serde_json::Value,
+    reasoning: ReasoningMode,
+) -> CWParsedResponse {
+    let message = &openai_resp["choices"][0]["message"];
+    let (thinking, content) = reasoning.apply(
+        message["reasoning_content"].as_str(),
+        message["content"].as_str().unwrap_or(""),
+    );
+    CWParsedResponse {
+        content,
+        tool_calls: message
+            .get("tool_calls")
+            .cloned()
+            .and_then(|calls| serde_json::from_value(calls).ok())
+            .unwrap_or_default(),
+        usage_credits: 0.0,
+        context_usage_percentage: 0.0,
+        input_tokens: openai_resp["usage"]["prompt_tokens"]
+            .as_u64()
+            .map(|t| t as u32),
+        output_tokens: openai_resp["usage"]["completion_tokens"]
+            .as_u64()
+            .map(|t| t as u32),
+        thinking: thinking.unwrap_or_default(),
+    }
+}
+
+/// 将 OpenAI ChatCompletionResponse 转换为 Anthropic 响应（流式或非流式）
+fn respond_anthropic_from_openai(
+    openai_resp: &ChatCompletionResponse,
+    model: &str,
+    stream: bool,
+    reasoning: ReasoningMode,
+) -> Response {
+    if !stream {
+        return Json(convert_openai_response_to_anthropic(
+            openai_resp,
+            model,
+            reasoning,
+        ))
+        .into_response();
+    }
+
+    let message = openai_resp.choices.first().map(|c| &c.message);
+    let (thinking, content) = reasoning.apply(
+        message.and_then(|m| m.reasoning_content.as_deref()),
+        message
+            .and_then(|m| m.content.as_deref())
+            .unwrap_or_default(),
+    );
+    let parsed = CWParsedResponse {
+        content,
+        tool_calls: message
+            .and_then(|m| m.tool_calls.clone())
+            .unwrap_or_default(),
+        usage_credits: 0.0,
+        context_usage_percentage: 0.0,
+        input_tokens: Some(openai_resp.usage.prompt_tokens),
+        output_tokens: Some(openai_resp.usage.completion_tokens),
+        thinking: thinking.unwrap_or_default(),
+    };
+    build_anthropic_stream_response(model, &parsed)
+}
+
 /// 将 OpenAI ChatCompletionResponse 转换为 Anthropic MessagesResponse 格式
 fn convert_openai_response_to_anthropic(
     openai_resp: &crate::models::openai::ChatCompletionResponse,
     model: &str,
+    reasoning: ReasoningMode,
 ) -> serde_json::Value {
-    // 提取第一个 choice 的内容
-    let content = openai_resp
-        .choices
-        .first()
-        .and_then(|c| c.message.content.as_ref())
-        .cloned()
-        .unwrap_or_default();
+    // 提取第一个 choice 的内容，并按配置处理推理内容
+    let message = openai_resp.choices.first().map(|c| &c.message);
+    let (thinking, content) = reasoning.apply(
+        message.and_then(|m| m.reasoning_content.as_deref()),
+        message
+            .and_then(|m| m.content.as_deref())
+            .unwrap_or_default(),
+    );
 
     // 提取 tool_calls
     let tool_use: Vec<serde_json::Value> = openai_resp
@@ -3476,6 +5483,13 @@ fn convert_openai_response_to_anthropic(
 
     // 构建 content 数组
     let mut content_array: Vec<serde_json::Value> = Vec::new();
+    if let Some(thinking) = thinking {
+        content_array.push(serde_json::json!({
+            "type": "thinking",
+            "thinking": thinking,
+            "signature": ""
+        }));
+    }
     if !content.is_empty() {
         content_array.push(serde_json::json!({
             "type": "text",
@@ -3648,49 +5662,70 @@ fn convert_codex_to_openai_non_stream(codex_response: &serde_json::Value) -> ser
 }
 
 /// Codex SSE 转换状态
-#[derive(Default)]
 struct CodexConvertState {
     response_id: String,
     created_at: i64,
     model: String,
+    /// 当前工具调用在 `tool_calls` 中的索引（-1 表示尚无工具调用）
     function_call_index: i32,
+    /// 当前工具调用是否已通过 `output_item.added` 发出首个分片
+    tool_call_announced: bool,
+    /// 当前工具调用是否已收到参数增量
+    arguments_streamed: bool,
 }
 
-/// 将单个 Codex SSE 事件转换为 OpenAI SSE 格式（使用状态结构体）
-/// 参考 CLIProxyAPI: internal/translator/codex/openai/chat-completions/codex_openai_response.go
-fn convert_codex_event_to_openai_sse_with_state(
-    codex_event: &serde_json::Value,
-    state: &mut CodexConvertState,
-) -> Option<String> {
-    convert_codex_event_to_openai_sse(
-        codex_event,
-        &mut state.response_id,
-        &mut state.created_at,
-        &mut state.model,
-        &mut state.function_call_index,
-    )
+impl Default for CodexConvertState {
+    fn default() -> Self {
+        Self {
+            response_id: String::new(),
+            created_at: 0,
+            model: String::new(),
+            function_call_index: -1,
+            tool_call_announced: false,
+            arguments_streamed: false,
+        }
+    }
+}
+
+/// 构建只包含单个工具调用分片的 OpenAI chunk
+fn codex_tool_call_chunk(state: &CodexConvertState, tool_call: serde_json::Value) -> String {
+    serde_json::json!({
+        "id": state.response_id,
+        "object": "chat.completion.chunk",
+        "created": state.created_at,
+        "model": state.model,
+        "choices": [{
+            "index": 0,
+            "delta": {
+                "role": "assistant",
+                "tool_calls": [tool_call]
+            },
+            "finish_reason": null
+        }]
+    })
+    .to_string()
 }
 
 /// 将单个 Codex SSE 事件转换为 OpenAI SSE 格式
 /// 参考 CLIProxyAPI: internal/translator/codex/openai/chat-completions/codex_openai_response.go
+///
+/// 工具调用按 OpenAI 规范增量输出：`output_item.added` 发出带 id/name 的首个分片，
+/// 之后每个 `function_call_arguments.delta` 发出只含 arguments 的分片。
 fn convert_codex_event_to_openai_sse(
     codex_event: &serde_json::Value,
-    response_id: &mut String,
-    created_at: &mut i64,
-    model: &mut String,
-    function_call_index: &mut i32,
+    state: &mut CodexConvertState,
 ) -> Option<String> {
     let event_type = codex_event.get("type")?.as_str()?;
 
     match event_type {
         "response.created" => {
             // 保存响应元数据
-            *response_id = codex_event["response"]["id"]
+            state.response_id = codex_event["response"]["id"]
                 .as_str()
                 .unwrap_or("")
                 .to_string();
-            *created_at = codex_event["response"]["created_at"].as_i64().unwrap_or(0);
-            *model = codex_event["response"]["model"]
+            state.created_at = codex_event["response"]["created_at"].as_i64().unwrap_or(0);
+            state.model = codex_event["response"]["model"]
                 .as_str()
                 .unwrap_or("gpt-5")
                 .to_string();
@@ -3700,10 +5735,10 @@ fn convert_codex_event_to_openai_sse(
             // 文本增量
             let delta = codex_event.get("delta")?.as_str()?;
             let chunk = serde_json::json!({
-                "id": response_id,
+                "id": state.response_id,
                 "object": "chat.completion.chunk",
-                "created": created_at,
-                "model": model,
+                "created": state.created_at,
+                "model": state.model,
                 "choices": [{
                     "index": 0,
                     "delta": {
@@ -3719,10 +5754,10 @@ fn convert_codex_event_to_openai_sse(
             // 推理内容增量
             let delta = codex_event.get("delta")?.as_str()?;
             let chunk = serde_json::json!({
-                "id": response_id,
+                "id": state.response_id,
                 "object": "chat.completion.chunk",
-                "created": created_at,
-                "model": model,
+                "created": state.created_at,
+                "model": state.model,
                 "choices": [{
                     "index": 0,
                     "delta": {
@@ -3737,10 +5772,10 @@ fn convert_codex_event_to_openai_sse(
         "response.reasoning_summary_text.done" => {
             // 推理内容结束，添加换行
             let chunk = serde_json::json!({
-                "id": response_id,
+                "id": state.response_id,
                 "object": "chat.completion.chunk",
-                "created": created_at,
-                "model": model,
+                "created": state.created_at,
+                "model": state.model,
                 "choices": [{
                     "index": 0,
                     "delta": {
@@ -3752,6 +5787,60 @@ fn convert_codex_event_to_openai_sse(
             });
             Some(chunk.to_string())
         }
+        "response.output_item.added" => {
+            // 工具调用开始：发出带 id 和 name 的首个分片
+            let item = codex_event.get("item")?;
+            if item.get("type")?.as_str()? != "function_call" {
+                return None;
+            }
+
+            state.function_call_index += 1;
+            let index = state.function_call_index;
+            state.tool_call_announced = true;
+            state.arguments_streamed = false;
+
+            Some(codex_tool_call_chunk(
+                state,
+                serde_json::json!({
+                    "index": index,
+                    "id": item["call_id"].as_str().unwrap_or(""),
+                    "type": "function",
+                    "function": {
+                        "name": item["name"].as_str().unwrap_or(""),
+                        "arguments": ""
+                    }
+                }),
+            ))
+        }
+        "response.function_call_arguments.delta" => {
+            // 工具调用参数增量
+            let delta = codex_event.get("delta")?.as_str()?;
+            state.arguments_streamed = true;
+
+            Some(codex_tool_call_chunk(
+                state,
+                serde_json::json!({
+                    "index": state.function_call_index,
+                    "function": { "arguments": delta }
+                }),
+            ))
+        }
+        "response.function_call_arguments.done" => {
+            // 上游没有发送参数增量时，一次性补发完整参数
+            if !state.tool_call_announced || state.arguments_streamed {
+                return None;
+            }
+            let arguments = codex_event.get("arguments")?.as_str()?;
+            state.arguments_streamed = true;
+
+            Some(codex_tool_call_chunk(
+                state,
+                serde_json::json!({
+                    "index": state.function_call_index,
+                    "function": { "arguments": arguments }
+                }),
+            ))
+        }
         "response.output_item.done" => {
             // 处理 function_call 完成事件
             let item = codex_event.get("item")?;
@@ -3759,39 +5848,42 @@ fn convert_codex_event_to_openai_sse(
                 return None;
             }
 
-            *function_call_index += 1;
-
-            let call_id = item["call_id"].as_str().unwrap_or("").to_string();
-            let name = item["name"].as_str().unwrap_or("").to_string();
-            let arguments = item["arguments"].as_str().unwrap_or("{}").to_string();
+            // 已增量输出的工具调用只需补发缺失的参数
+            if std::mem::take(&mut state.tool_call_announced) {
+                if std::mem::take(&mut state.arguments_streamed) {
+                    return None;
+                }
+                return Some(codex_tool_call_chunk(
+                    state,
+                    serde_json::json!({
+                        "index": state.function_call_index,
+                        "function": {
+                            "arguments": item["arguments"].as_str().unwrap_or("{}")
+                        }
+                    }),
+                ));
+            }
 
-            let chunk = serde_json::json!({
-                "id": response_id,
-                "object": "chat.completion.chunk",
-                "created": created_at,
-                "model": model,
-                "choices": [{
-                    "index": 0,
-                    "delta": {
-                        "role": "assistant",
-                        "tool_calls": [{
-                            "index": function_call_index,
-                            "id": call_id,
-                            "type": "function",
-                            "function": {
-                                "name": name,
-                                "arguments": arguments
-                            }
-                        }]
-                    },
-                    "finish_reason": null
-                }]
-            });
-            Some(chunk.to_string())
+            // 未收到 output_item.added 时，一次性输出完整工具调用
+            state.function_call_index += 1;
+            let index = state.function_call_index;
+
+            Some(codex_tool_call_chunk(
+                state,
+                serde_json::json!({
+                    "index": index,
+                    "id": item["call_id"].as_str().unwrap_or(""),
+                    "type": "function",
+                    "function": {
+                        "name": item["name"].as_str().unwrap_or(""),
+                        "arguments": item["arguments"].as_str().unwrap_or("{}")
+                    }
+                }),
+            ))
         }
         "response.completed" => {
             // 响应完成
-            let finish_reason = if *function_call_index != -1 {
+            let finish_reason = if state.function_call_index != -1 {
                 "tool_calls"
             } else {
                 "stop"
@@ -3806,10 +5898,10 @@ fn convert_codex_event_to_openai_sse(
                 .unwrap_or(prompt_tokens + completion_tokens);
 
             let chunk = serde_json::json!({
-                "id": response_id,
+                "id": state.response_id,
                 "object": "chat.completion.chunk",
-                "created": created_at,
-                "model": model,
+                "created": state.created_at,
+                "model": state.model,
                 "choices": [{
                     "index": 0,
                     "delta": {},
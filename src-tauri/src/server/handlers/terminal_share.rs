@@ -0,0 +1,195 @@
+//! 终端会话分享 WebSocket 处理器
+//!
+//! 观看者用一次性令牌连接分享的终端会话，协议见 `crate::terminal::share`。
+//! 令牌即凭据，连接不需要 API Key（IP 白名单仍然生效）。
+
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{close_code, CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Path,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::terminal::{
+    SessionStatus, SessionTap, ShareClientMessage, ShareCloseReason, ShareGrant, ShareMode,
+    ShareServerMessage, TerminalShareInfo, TerminalShareRegistry, TERMINAL_RESET_SEQUENCE,
+};
+
+/// 检查会话是否结束的间隔
+const SESSION_STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 终端会话分享 WebSocket 升级处理器
+///
+/// 令牌在握手时被消耗，无效、过期或已使用的令牌返回 404。连接建立后服务端发送 `hello`
+/// 和包含输出历史的 `output`，之后持续推送终端输出；协作模式下观看者可发送 `input`。
+#[utoipa::path(
+    get,
+    path = "/v1/terminal/share/{token}/ws",
+    tag = "websocket",
+    description = "终端会话分享的 WebSocket 握手。令牌由应用内的会话分享生成，只能使用一次。",
+    params(
+        ("token" = String, Path, description = "一次性分享令牌"),
+    ),
+    responses(
+        (status = 101, description = "升级为 WebSocket 连接"),
+        (status = 404, description = "令牌无效、已过期或已使用"),
+    ),
+    security(())
+)]
+pub async fn terminal_share_ws_handler(
+    ws: WebSocketUpgrade,
+    Path(token): Path<String>,
+) -> Response {
+    let grant = match TerminalShareRegistry::global().redeem(&token) {
+        Ok(grant) => grant,
+        Err(e) => return (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    };
+
+    let share_id = grant.share.share_id.clone();
+    ws.on_failed_upgrade(move |e| {
+        tracing::warn!("[终端分享] 分享 {} 升级连接失败: {}", share_id, e);
+        TerminalShareRegistry::global().finish(&share_id);
+    })
+    .on_upgrade(move |socket| handle_share_socket(socket, grant))
+    .into_response()
+}
+
+/// 处理分享连接
+async fn handle_share_socket(mut socket: WebSocket, grant: ShareGrant) {
+    let ShareGrant {
+        share,
+        tap,
+        mut revoked,
+    } = grant;
+    let (history, mut output) = tap.subscribe();
+
+    let hello = ShareServerMessage::Hello {
+        share_id: share.share_id.clone(),
+        session_id: share.session_id.clone(),
+        mode: share.mode,
+    };
+    let mut close_reason = None;
+    if send_message(&mut socket, &hello).await && send_output(&mut socket, &history).await {
+        let mut status_tick = tokio::time::interval(SESSION_STATUS_INTERVAL);
+        close_reason = loop {
+            tokio::select! {
+                _ = revoked.changed() => break Some(ShareCloseReason::Revoked),
+                received = output.recv() => {
+                    let sent = match received {
+                        Ok(data) => send_output(&mut socket, &data).await,
+                        Err(RecvError::Lagged(skipped)) => {
+                            // 观看者跟不上输出，重置终端后发送最新的输出历史
+                            tracing::debug!(
+                                "[终端分享] 分享 {} 丢失 {} 段输出，重新同步",
+                                share.share_id,
+                                skipped
+                            );
+                            let (history, resubscribed) = tap.subscribe();
+                            output = resubscribed;
+                            let mut data = TERMINAL_RESET_SEQUENCE.to_vec();
+                            data.extend_from_slice(&history);
+                            send_output(&mut socket, &data).await
+                        }
+                        Err(RecvError::Closed) => break Some(ShareCloseReason::SessionEnded),
+                    };
+                    if !sent {
+                        break None;
+                    }
+                }
+                message = socket.recv() => match message {
+                    Some(Ok(WsMessage::Text(text))) => {
+                        if let Some(reply) = handle_client_message(&share, &tap, &text) {
+                            if !send_message(&mut socket, &reply).await {
+                                break None;
+                            }
+                        }
+                    }
+                    Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break None,
+                    Some(Ok(_)) => {}
+                },
+                _ = status_tick.tick() => {
+                    if tap.status().await != SessionStatus::Running {
+                        break Some(ShareCloseReason::SessionEnded);
+                    }
+                }
+            }
+        };
+    }
+
+    if let Some(reason) = close_reason {
+        send_message(&mut socket, &ShareServerMessage::Closed { reason }).await;
+        let _ = socket
+            .send(WsMessage::Close(Some(CloseFrame {
+                code: close_code::NORMAL,
+                reason: "".into(),
+            })))
+            .await;
+    }
+    TerminalShareRegistry::global().finish(&share.share_id);
+}
+
+/// 处理观看者的消息
+///
+/// # 返回
+/// 需要回复的消息
+fn handle_client_message(
+    share: &TerminalShareInfo,
+    tap: &SessionTap,
+    text: &str,
+) -> Option<ShareServerMessage> {
+    let message = match serde_json::from_str::<ShareClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            return Some(ShareServerMessage::Error {
+                message: format!("无效的消息: {}", e),
+            })
+        }
+    };
+
+    match message {
+        ShareClientMessage::Ping => Some(ShareServerMessage::Pong),
+        ShareClientMessage::Input { .. } if share.mode == ShareMode::ReadOnly => {
+            Some(ShareServerMessage::Error {
+                message: "只读分享不接受输入".to_string(),
+            })
+        }
+        ShareClientMessage::Input { data } => {
+            let result = BASE64
+                .decode(data)
+                .map_err(|e| format!("Base64 解码失败: {}", e))
+                .and_then(|bytes| tap.write(&bytes).map_err(|e| e.to_string()));
+            result
+                .err()
+                .map(|message| ShareServerMessage::Error { message })
+        }
+    }
+}
+
+/// 发送终端输出（空数据不发送）
+///
+/// # 返回
+/// 连接是否仍可用
+async fn send_output(socket: &mut WebSocket, data: &[u8]) -> bool {
+    if data.is_empty() {
+        return true;
+    }
+    let message = ShareServerMessage::Output {
+        data: BASE64.encode(data),
+    };
+    send_message(socket, &message).await
+}
+
+/// 发送 JSON 文本帧
+///
+/// # 返回
+/// 连接是否仍可用
+async fn send_message(socket: &mut WebSocket, message: &ShareServerMessage) -> bool {
+    let text = serde_json::to_string(message).unwrap_or_default();
+    socket.send(WsMessage::Text(text.into())).await.is_ok()
+}
@@ -5,12 +5,13 @@
 use axum::{
     body::Body,
     extract::{
-        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
-        Query, State,
+        ws::{close_code, CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
     },
     http::HeaderMap,
     response::IntoResponse,
 };
+use futures::stream::{SplitSink, SplitStream};
 use futures::{SinkExt, StreamExt as FuturesStreamExt};
 use serde::Deserialize;
 use std::sync::Arc;
@@ -20,6 +21,8 @@ use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::converter::openai_to_antigravity::{
     convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
 };
+use crate::converter::reasoning::apply_reasoning_to_openai_response;
+use crate::database::dao::client_api_keys::ClientApiKey;
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
 use crate::models::provider_pool_model::ProviderCredential;
@@ -27,12 +30,109 @@ use crate::processor::RequestContext;
 use crate::providers::{
     AntigravityProvider, ClaudeCustomProvider, KiroProvider, OpenAICustomProvider,
 };
-use crate::server::AppState;
+use crate::server::compression::{WsDeflate, WS_DEFLATE_PROTOCOL};
+use crate::server::error::ProxyApiError;
+use crate::server::handlers::{
+    authenticate_key, use_shared_client, verify_client_scope, ApiErrorFormat,
+};
+use crate::server::{selector_endpoint, AppState};
 use crate::server_utils::parse_cw_response;
 use crate::websocket::{
-    WsApiRequest, WsApiResponse, WsEndpoint, WsError, WsFlowEvent, WsMessage as WsProtoMessage,
+    StreamForwarder, WsApiRequest, WsApiResponse, WsAuth, WsEndpoint, WsError, WsFlowEvent,
+    WsMessage as WsProtoMessage, WsResume, WsServerEvent, WsStreamBuffer, WsSubscriptions,
 };
 
+/// WebSocket 连接的发送端
+///
+/// 连接协商了 `proxycast.deflate` 子协议时，达到阈值的消息压缩后以二进制帧发送
+#[derive(Clone)]
+struct WsSender {
+    sink: Arc<Mutex<SplitSink<WebSocket, WsMessage>>>,
+    deflate: Option<WsDeflate>,
+}
+
+impl WsSender {
+    fn new(sink: SplitSink<WebSocket, WsMessage>, deflate: Option<WsDeflate>) -> Self {
+        Self {
+            sink: Arc::new(Mutex::new(sink)),
+            deflate,
+        }
+    }
+
+    /// 发送协议消息
+    async fn send(&self, message: &WsProtoMessage) -> Result<(), axum::Error> {
+        let text = serde_json::to_string(message).unwrap_or_default();
+        let frame = match &self.deflate {
+            Some(deflate) => deflate.encode(text),
+            None => WsMessage::Text(text),
+        };
+        self.send_frame(frame).await
+    }
+
+    /// 发送控制帧
+    async fn send_frame(&self, frame: WsMessage) -> Result<(), axum::Error> {
+        self.sink.lock().await.send(frame).await
+    }
+
+    /// 解压客户端的二进制帧，未协商压缩的连接不接受二进制帧
+    fn inflate(&self, data: &[u8]) -> Result<String, WsError> {
+        match &self.deflate {
+            Some(deflate) => deflate.decode(data).map_err(WsError::invalid_message),
+            None => Err(WsError::invalid_message("Binary messages not supported")),
+        }
+    }
+}
+
+/// WebSocket 连接的调用方
+///
+/// 握手或第一条消息认证一次，之后连接上的每个请求都按认证得到的客户端 Key 校验作用域并计入限流
+#[derive(Debug, Clone)]
+struct WsCaller {
+    /// 客户端 Key（主 Key 或选择器端点专用 Key 时为 `None`，不受作用域限制）
+    client_key: Option<ClientApiKey>,
+    /// 连接绑定的选择器（`/{selector}/ws`）
+    selector: Option<String>,
+}
+
+impl WsCaller {
+    fn selector(&self) -> Option<&str> {
+        self.selector.as_deref()
+    }
+
+    /// 续传缓存中的调用方标识，只有相同的 Key 和选择器才能续传请求
+    fn owner(&self) -> String {
+        let key = self.client_key.as_ref().map_or("master", |k| k.id.as_str());
+        format!("{}@{}", key, self.selector().unwrap_or_default())
+    }
+
+    /// 请求计入客户端 Key 的限流窗口
+    async fn check_rate_limit(&self, state: &AppState, request_id: &str) -> Result<(), WsError> {
+        let Some(key) = &self.client_key else {
+            return Ok(());
+        };
+        state.client_keys.check_key_rate_limit(key).map_err(|e| {
+            tracing::warn!("[WS] 客户端 Key {} 超过限流: {}", key.key_prefix, e);
+            WsError::rate_limited(Some(request_id.to_string()), e.to_string())
+        })
+    }
+
+    /// 校验客户端 Key 的模型和 Provider 作用域
+    fn verify_scope(
+        &self,
+        request_id: &str,
+        model: Option<&str>,
+        provider: Option<&str>,
+    ) -> Result<(), WsError> {
+        verify_client_scope(
+            self.client_key.as_ref(),
+            model,
+            provider,
+            ApiErrorFormat::OpenAI,
+        )
+        .map_err(|e| WsError::forbidden(Some(request_id.to_string()), e.message))
+    }
+}
+
 /// WebSocket 查询参数
 #[derive(Debug, Deserialize, Default)]
 pub struct WsQueryParams {
@@ -43,12 +143,81 @@ pub struct WsQueryParams {
 }
 
 /// WebSocket 升级处理器
+///
+/// 连接建立后以 JSON 文本帧通信，`type` 字段区分消息：客户端发送 `request`（`WsApiRequest`）、
+/// `ping`、`subscribe` / `unsubscribe`（`WsSubscription`），服务端返回 `response`（`WsApiResponse`）、
+/// `stream_chunk`、`stream_end`、`error`（`WsError`）、`pong` 和 `event`。
+///
+/// `stream: true` 的 OpenAI 兼容请求逐块推送 `stream_chunk`。连接中途断开时请求继续执行，
+/// 客户端可在新连接上发送 `resume`（`WsResume`）重放缺失的消息。
+///
+/// 握手时未携带 API Key（如浏览器无法设置请求头）的连接，第一条消息必须是 `auth`（`WsAuth`），
+/// 超时未认证或认证失败时服务端发送 `error` 后以 1008 关闭连接。
+///
+/// 启用压缩（`server.compression.enable`）时，`Sec-WebSocket-Protocol` 包含 `proxycast.deflate`
+/// 的连接以二进制帧收发 DEFLATE 压缩的 JSON 消息（见 `server::compression`）。
+#[utoipa::path(
+    get,
+    path = "/v1/ws",
+    tag = "websocket",
+    description = "WebSocket 握手（`/ws` 为别名）。API Key 可通过 `Authorization` / `x-api-key` 请求头或 `api_key` / `token` 查询参数传递；均未提供时需在连接后的第一条消息中发送 `{\"type\":\"auth\",\"api_key\":\"...\"}`。`Sec-WebSocket-Protocol` 包含 `proxycast.deflate` 时协商消息压缩：达到 `server.compression.min_size` 的消息以二进制帧发送原始 DEFLATE 压缩的 JSON，客户端也可以发送同样的二进制帧。",
+    params(
+        ("api_key" = Option<String>, Query, description = "API Key"),
+        ("token" = Option<String>, Query, description = "与 api_key 等效"),
+    ),
+    responses(
+        (status = 101, description = "升级为 WebSocket 连接"),
+        (status = 401, description = "API Key 无效"),
+    ),
+    security((), ("bearer" = []), ("x_api_key" = []))
+)]
 pub async fn ws_upgrade_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Query(params): Query<WsQueryParams>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
+    upgrade_websocket(ws, state, params, headers, None).await
+}
+
+/// 带选择器的 WebSocket 升级处理器
+///
+/// 连接上的 `chat_completions` / `messages` 请求都按选择器解析凭证，与 HTTP 的
+/// `/{selector}/v1/*` 一致：不降级到其他凭证，端点配置的 API Key 和限流同样生效。
+#[utoipa::path(
+    get,
+    path = "/{selector}/ws",
+    tag = "websocket",
+    description = "带选择器的 WebSocket 握手，协议与 `/v1/ws` 相同。",
+    params(
+        ("selector" = String, Path, description = "`selector_endpoints` 中配置的端点名，或凭证名称、凭证 UUID、Provider 类型，指定后不降级到其他凭证"),
+        ("api_key" = Option<String>, Query, description = "API Key"),
+        ("token" = Option<String>, Query, description = "与 api_key 等效"),
+    ),
+    responses(
+        (status = 101, description = "升级为 WebSocket 连接"),
+        (status = 401, description = "API Key 无效"),
+    ),
+    security((), ("bearer" = []), ("x_api_key" = []))
+)]
+pub async fn ws_selector_upgrade_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(selector): Path<String>,
+    Query(params): Query<WsQueryParams>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    upgrade_websocket(ws, state, params, headers, Some(selector)).await
+}
+
+/// 校验握手参数中的 API Key 并升级连接
+async fn upgrade_websocket(
+    ws: WebSocketUpgrade,
+    state: AppState,
+    params: WsQueryParams,
+    headers: HeaderMap,
+    selector: Option<String>,
+) -> axum::response::Response {
     // 验证 API 密钥：优先从 header 获取，其次从 URL 参数获取
     let auth = headers
         .get("authorization")
@@ -64,22 +233,19 @@ pub async fn ws_upgrade_handler(
         }
     };
 
-    // 如果没有提供任何认证信息，允许连接（用于内部 Flow Monitor）
-    // 但会在日志中记录
-    let authenticated = match key {
-        Some(k) if k == state.api_key => true,
-        Some(_) => {
-            return axum::http::Response::builder()
-                .status(401)
-                .body(Body::from("Invalid API key"))
-                .unwrap()
-                .into_response();
-        }
-        None => {
-            // 允许无认证连接（仅用于本地 Flow Monitor UI）
-            tracing::debug!("[WS] Allowing unauthenticated connection for Flow Monitor");
-            false
-        }
+    // 未提供认证信息时允许升级，由第一条消息完成认证
+    let client_key = match key {
+        Some(k) => match authenticate_ws_key(&state, k, selector.as_deref()).await {
+            Ok(client_key) => Some(client_key),
+            Err(_) => {
+                return axum::http::Response::builder()
+                    .status(401)
+                    .body(Body::from("Invalid API key"))
+                    .unwrap()
+                    .into_response();
+            }
+        },
+        None => None,
     };
 
     // 获取客户端信息
@@ -88,16 +254,54 @@ pub async fn ws_upgrade_handler(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
-    ws.on_upgrade(move |socket| handle_websocket(socket, state, client_info, authenticated))
+    // 启用压缩时可协商 proxycast.deflate 子协议
+    let ws = if state.ws_manager.config().deflate_min_size.is_some() {
+        ws.protocols([WS_DEFLATE_PROTOCOL])
+    } else {
+        ws
+    };
+
+    ws.on_upgrade(move |socket| handle_websocket(socket, state, client_info, client_key, selector))
+        .into_response()
+}
+
+/// 校验 API Key，选择器端点配置了独立 API Key 时只接受该 Key
+///
+/// 返回认证得到的客户端 Key，主 Key 和端点专用 Key 返回 `None`
+async fn authenticate_ws_key(
+    state: &AppState,
+    key: &str,
+    selector: Option<&str>,
+) -> Result<Option<ClientApiKey>, ProxyApiError> {
+    let expected = match selector {
+        Some(selector) => state
+            .processor
+            .selector_endpoints
+            .read()
+            .await
+            .get(selector)
+            .and_then(|e| e.api_key.clone()),
+        None => None,
+    };
+    match expected {
+        Some(expected) if key == expected => Ok(None),
+        Some(_) => Err(ProxyApiError::authentication("Invalid API key")),
+        None => authenticate_key(key, state, ApiErrorFormat::OpenAI).await,
+    }
 }
 
 /// 处理 WebSocket 连接
+///
+/// `client_key` 为握手时认证的结果（`None` 表示握手未携带 API Key，需在第一条消息中认证）；
+/// `selector` 为连接绑定的选择器（`/{selector}/ws`），为 `None` 时从默认 Provider 的凭证池中选择
 pub async fn handle_websocket(
     socket: WebSocket,
     state: AppState,
     client_info: Option<String>,
-    authenticated: bool,
+    client_key: Option<Option<ClientApiKey>>,
+    selector: Option<String>,
 ) {
+    let authenticated = client_key.is_some();
     let conn_id = uuid::Uuid::new_v4().to_string();
 
     // 注册连接
@@ -115,15 +319,79 @@ pub async fn handle_websocket(
     state.logs.write().await.add(
         "info",
         &format!(
-            "[WS] New connection: {} (client: {:?}, authenticated: {})",
+            "[WS] New connection: {} (client: {:?}, authenticated: {}, selector: {:?})",
             &conn_id[..8],
             client_info,
-            authenticated
+            authenticated,
+            selector
         ),
     );
 
+    // 客户端选择了 proxycast.deflate 子协议时压缩消息
+    let config = state.ws_manager.config();
+    let deflate = config
+        .deflate_min_size
+        .filter(|_| socket.protocol().is_some_and(|p| p == WS_DEFLATE_PROTOCOL))
+        .map(|min_size| WsDeflate::new(min_size, config.max_message_size));
+
     let (sender, mut receiver) = socket.split();
-    let sender = Arc::new(Mutex::new(sender));
+    let sender = WsSender::new(sender, deflate);
+
+    // 握手未认证的连接需在第一条消息中认证
+    let mut caller = WsCaller {
+        client_key: client_key.flatten(),
+        selector,
+    };
+    if !authenticated {
+        match authenticate_first_message(&state, &mut receiver, &sender, caller.selector()).await {
+            Ok(Some((auth, client_key))) => {
+                caller.client_key = client_key;
+                if let Some(name) = &auth.client_name {
+                    state.ws_manager.set_client_info(&conn_id, name.clone());
+                }
+                let ack = WsProtoMessage::Response(WsApiResponse {
+                    request_id: "auth".to_string(),
+                    payload: serde_json::json!({
+                        "status": "authenticated",
+                        "client_name": auth.client_name
+                    }),
+                });
+                let _ = sender.send(&ack).await;
+                state.logs.write().await.add(
+                    "info",
+                    &format!(
+                        "[WS] Connection {} authenticated (client: {:?})",
+                        &conn_id[..8],
+                        auth.client_name
+                    ),
+                );
+            }
+            Ok(None) => {
+                state.ws_manager.unregister(&conn_id);
+                return;
+            }
+            Err(error) => {
+                state.logs.write().await.add(
+                    "warn",
+                    &format!(
+                        "[WS] Connection {} rejected: {}",
+                        &conn_id[..8],
+                        error.message
+                    ),
+                );
+                let reason = error.message.clone();
+                let _ = sender.send(&WsProtoMessage::Error(error)).await;
+                let _ = sender
+                    .send_frame(WsMessage::Close(Some(CloseFrame {
+                        code: close_code::POLICY,
+                        reason: reason.into(),
+                    })))
+                    .await;
+                state.ws_manager.unregister(&conn_id);
+                return;
+            }
+        }
+    }
 
     // Flow 事件订阅状态
     let flow_subscribed = Arc::new(std::sync::atomic::AtomicBool::new(false));
@@ -150,19 +418,12 @@ pub async fn handle_websocket(
                     let ws_event: WsFlowEvent = event.into();
                     let ws_msg = WsProtoMessage::FlowEvent(ws_event);
 
-                    if let Ok(msg_text) = serde_json::to_string(&ws_msg) {
-                        let mut sender_guard = flow_sender.lock().await;
-                        if sender_guard
-                            .send(WsMessage::Text(msg_text.into()))
-                            .await
-                            .is_err()
-                        {
-                            tracing::debug!(
-                                "[WS] Flow event send failed for connection {}",
-                                &conn_id_clone[..8]
-                            );
-                            break;
-                        }
+                    if flow_sender.send(&ws_msg).await.is_err() {
+                        tracing::debug!(
+                            "[WS] Flow event send failed for connection {}",
+                            &conn_id_clone[..8]
+                        );
+                        break;
                     }
                 }
                 Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
@@ -183,70 +444,54 @@ pub async fn handle_websocket(
         }
     });
 
-    // 消息处理循环
-    while let Some(msg) = receiver.next().await {
-        match msg {
-            Ok(WsMessage::Text(text)) => {
-                state.ws_manager.on_message();
-                state.ws_manager.increment_request_count(&conn_id);
-
-                match serde_json::from_str::<WsProtoMessage>(&text) {
-                    Ok(ws_msg) => {
-                        let response =
-                            handle_ws_message(&state, &conn_id, ws_msg, &flow_subscribed).await;
-                        if let Some(resp) = response {
-                            let resp_text = serde_json::to_string(&resp).unwrap_or_default();
-                            let mut sender_guard = sender.lock().await;
-                            if sender_guard
-                                .send(WsMessage::Text(resp_text.into()))
-                                .await
-                                .is_err()
-                            {
-                                break;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        state.ws_manager.on_error();
-                        let error = WsProtoMessage::Error(WsError::invalid_message(format!(
-                            "Failed to parse message: {}",
-                            e
-                        )));
-                        let error_text = serde_json::to_string(&error).unwrap_or_default();
-                        let mut sender_guard = sender.lock().await;
-                        if sender_guard
-                            .send(WsMessage::Text(error_text.into()))
-                            .await
-                            .is_err()
-                        {
-                            break;
-                        }
-                    }
-                }
+    // 服务端事件订阅状态及转发任务
+    let subscriptions = Arc::new(WsSubscriptions::new());
+    let event_task = tokio::spawn(forward_server_events(
+        state.clone(),
+        sender.clone(),
+        subscriptions.clone(),
+        conn_id.clone(),
+    ));
+
+    // 消息处理循环（服务器停机时主动发送 Close 帧）
+    let mut shutdown_rx = state.drain.subscribe();
+    loop {
+        let msg = tokio::select! {
+            msg = receiver.next() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = shutdown_rx.wait_for(|stopping| *stopping) => {
+                let close = WsMessage::Close(Some(CloseFrame {
+                    code: close_code::AWAY,
+                    reason: "server shutting down".into(),
+                }));
+                let _ = sender.send_frame(close).await;
+                break;
             }
-            Ok(WsMessage::Binary(_)) => {
-                state.ws_manager.on_error();
-                let error = WsProtoMessage::Error(WsError::invalid_message(
-                    "Binary messages not supported",
-                ));
-                let error_text = serde_json::to_string(&error).unwrap_or_default();
-                let mut sender_guard = sender.lock().await;
-                if sender_guard
-                    .send(WsMessage::Text(error_text.into()))
-                    .await
-                    .is_err()
-                {
-                    break;
+        };
+        // 协商压缩的连接中，二进制帧是压缩的 JSON 消息
+        let text = match msg {
+            Ok(WsMessage::Text(text)) => text,
+            Ok(WsMessage::Binary(data)) => match sender.inflate(&data) {
+                Ok(text) => text,
+                Err(error) => {
+                    state.ws_manager.on_error();
+                    if sender.send(&WsProtoMessage::Error(error)).await.is_err() {
+                        break;
+                    }
+                    continue;
                 }
-            }
+            },
             Ok(WsMessage::Ping(data)) => {
-                let mut sender_guard = sender.lock().await;
-                if sender_guard.send(WsMessage::Pong(data)).await.is_err() {
+                if sender.send_frame(WsMessage::Pong(data)).await.is_err() {
                     break;
                 }
+                continue;
             }
             Ok(WsMessage::Pong(_)) => {
                 // 收到 pong，连接正常
+                continue;
             }
             Ok(WsMessage::Close(_)) => {
                 break;
@@ -258,11 +503,42 @@ pub async fn handle_websocket(
                 );
                 break;
             }
+        };
+
+        state.ws_manager.on_message();
+        state.ws_manager.increment_request_count(&conn_id);
+
+        let response = match serde_json::from_str::<WsProtoMessage>(&text) {
+            Ok(ws_msg) => {
+                handle_ws_message(
+                    &state,
+                    &conn_id,
+                    ws_msg,
+                    &caller,
+                    &sender,
+                    &flow_subscribed,
+                    &subscriptions,
+                )
+                .await
+            }
+            Err(e) => {
+                state.ws_manager.on_error();
+                Some(WsProtoMessage::Error(WsError::invalid_message(format!(
+                    "Failed to parse message: {}",
+                    e
+                ))))
+            }
+        };
+        if let Some(resp) = response {
+            if sender.send(&resp).await.is_err() {
+                break;
+            }
         }
     }
 
-    // 取消 Flow 事件转发任务
+    // 取消事件转发任务
     flow_task.abort();
+    event_task.abort();
 
     // 清理连接
     state.ws_manager.unregister(&conn_id);
@@ -272,12 +548,101 @@ pub async fn handle_websocket(
     );
 }
 
+/// 等待并校验第一条消息中的认证信息
+///
+/// 连接在认证前关闭时返回 `Ok(None)`；跳过认证前收到的 Ping / Pong 控制帧，
+/// 协商压缩的连接可以用压缩的二进制帧发送认证消息
+async fn authenticate_first_message(
+    state: &AppState,
+    receiver: &mut SplitStream<WebSocket>,
+    sender: &WsSender,
+    selector: Option<&str>,
+) -> Result<Option<(WsAuth, Option<ClientApiKey>)>, WsError> {
+    let timeout = std::time::Duration::from_secs(state.ws_manager.config().auth_timeout_secs);
+    let first_text = async {
+        while let Some(msg) = receiver.next().await {
+            match msg {
+                Ok(WsMessage::Ping(_)) | Ok(WsMessage::Pong(_)) => continue,
+                Ok(WsMessage::Text(text)) => return Some(Ok(text.to_string())),
+                Ok(WsMessage::Close(_)) | Err(_) => return None,
+                Ok(WsMessage::Binary(data)) => {
+                    return Some(sender.inflate(&data).map_err(|_| {
+                        WsError::unauthorized("The first message must be an auth message")
+                    }))
+                }
+            }
+        }
+        None
+    };
+    let text = match tokio::time::timeout(timeout, first_text).await {
+        Ok(Some(text)) => text?,
+        Ok(None) => return Ok(None),
+        Err(_) => return Err(WsError::unauthorized("Authentication timed out")),
+    };
+
+    let auth = WsAuth::from_first_message(&text)?;
+    match authenticate_ws_key(state, &auth.api_key, selector).await {
+        Ok(client_key) => Ok(Some((auth, client_key))),
+        Err(e) => Err(WsError::unauthorized(e.message)),
+    }
+}
+
+/// 转发订阅的服务端事件（日志、请求遥测、凭证健康状态）
+async fn forward_server_events(
+    state: AppState,
+    sender: WsSender,
+    subscriptions: Arc<WsSubscriptions>,
+    conn_id: String,
+) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let mut logs = state.logs.read().await.subscribe();
+    let mut telemetry = state.telemetry_events.subscribe();
+    let mut health = state.pool_service.subscribe_health_events();
+
+    loop {
+        let event = tokio::select! {
+            result = logs.recv() => result.map(|entry| WsServerEvent::Logs { entry }),
+            result = telemetry.recv() => result.map(|request| WsServerEvent::Telemetry { request }),
+            result = health.recv() => {
+                result.map(|credential| WsServerEvent::CredentialHealth { credential })
+            }
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(RecvError::Lagged(n)) => {
+                tracing::warn!(
+                    "[WS] Server event receiver lagged by {} messages for connection {}",
+                    n,
+                    &conn_id[..8]
+                );
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if !subscriptions.is_subscribed(event.channel()) {
+            continue;
+        }
+
+        if sender.send(&WsProtoMessage::Event(event)).await.is_err() {
+            tracing::debug!(
+                "[WS] Server event send failed for connection {}",
+                &conn_id[..8]
+            );
+            break;
+        }
+    }
+}
+
 /// 处理 WebSocket 消息
 async fn handle_ws_message(
     state: &AppState,
     conn_id: &str,
     msg: WsProtoMessage,
+    caller: &WsCaller,
+    sender: &WsSender,
     flow_subscribed: &Arc<std::sync::atomic::AtomicBool>,
+    subscriptions: &WsSubscriptions,
 ) -> Option<WsProtoMessage> {
     match msg {
         WsProtoMessage::Ping { timestamp } => Some(WsProtoMessage::Pong { timestamp }),
@@ -338,9 +703,46 @@ async fn handle_ws_message(
                 ),
             );
 
-            // 处理 API 请求
-            let response = handle_ws_api_request(state, &request).await;
-            Some(response)
+            if let Err(e) = caller.check_rate_limit(state, &request.request_id).await {
+                return Some(WsProtoMessage::Error(e));
+            }
+
+            // 处理 API 请求，响应消息同时写入续传缓存
+            let streams = state.ws_manager.streams();
+            let owner = caller.owner();
+            if !streams.begin(&owner, &request.request_id) {
+                return Some(WsProtoMessage::Error(WsError::invalid_request(
+                    Some(request.request_id.clone()),
+                    "A request with the same request_id is still in progress",
+                )));
+            }
+            let mut sink = WsResponseSink::new(streams, sender, owner, &request.request_id);
+            if request.endpoint == WsEndpoint::ChatCompletions
+                && request.payload["stream"].as_bool() == Some(true)
+            {
+                match serde_json::from_value::<ChatCompletionRequest>(request.payload.clone()) {
+                    Ok(chat_request) => {
+                        stream_ws_chat_completions(state, chat_request, caller, &mut sink).await
+                    }
+                    Err(e) => {
+                        sink.emit(WsProtoMessage::Error(WsError::invalid_request(
+                            Some(request.request_id.clone()),
+                            format!("Invalid chat completion request: {}", e),
+                        )))
+                        .await
+                    }
+                }
+            } else {
+                let response = handle_ws_api_request(state, &request, caller).await;
+                sink.emit(response).await;
+            }
+            None
+        }
+        WsProtoMessage::Resume(resume) => {
+            if let Err(e) = caller.check_rate_limit(state, &resume.request_id).await {
+                return Some(WsProtoMessage::Error(e));
+            }
+            resume_ws_stream(state, conn_id, caller, resume, sender).await
         }
         WsProtoMessage::Response(_)
         | WsProtoMessage::StreamChunk(_)
@@ -375,11 +777,59 @@ async fn handle_ws_message(
                 "KiroCredentialEvent messages are server-to-client only",
             )))
         }
+        WsProtoMessage::Subscribe(subscription) => {
+            subscriptions.set(&subscription.resolved_channels(), true);
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[WS] Connection {} subscribed to {:?}",
+                    &conn_id[..8],
+                    subscription.resolved_channels()
+                ),
+            );
+            Some(WsProtoMessage::Response(WsApiResponse {
+                request_id: "subscribe".to_string(),
+                payload: serde_json::json!({
+                    "status": "subscribed",
+                    "channels": subscriptions.channels()
+                }),
+            }))
+        }
+        WsProtoMessage::Unsubscribe(subscription) => {
+            subscriptions.set(&subscription.resolved_channels(), false);
+            state.logs.write().await.add(
+                "info",
+                &format!(
+                    "[WS] Connection {} unsubscribed from {:?}",
+                    &conn_id[..8],
+                    subscription.resolved_channels()
+                ),
+            );
+            Some(WsProtoMessage::Response(WsApiResponse {
+                request_id: "unsubscribe".to_string(),
+                payload: serde_json::json!({
+                    "status": "unsubscribed",
+                    "channels": subscriptions.channels()
+                }),
+            }))
+        }
+        WsProtoMessage::Event(_) => Some(WsProtoMessage::Error(WsError::invalid_request(
+            None,
+            "Event messages are server-to-client only",
+        ))),
+        WsProtoMessage::Auth(_) => Some(WsProtoMessage::Error(WsError::invalid_request(
+            None,
+            "Connection is already authenticated",
+        ))),
     }
 }
 
 /// 处理 WebSocket API 请求
-async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsProtoMessage {
+async fn handle_ws_api_request(
+    state: &AppState,
+    request: &WsApiRequest,
+    caller: &WsCaller,
+) -> WsProtoMessage {
     match request.endpoint {
         WsEndpoint::Models => {
             // 返回模型列表
@@ -403,7 +853,8 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
             // 解析 ChatCompletionRequest
             match serde_json::from_value::<ChatCompletionRequest>(request.payload.clone()) {
                 Ok(chat_request) => {
-                    handle_ws_chat_completions(state, &request.request_id, chat_request).await
+                    handle_ws_chat_completions(state, &request.request_id, chat_request, caller)
+                        .await
                 }
                 Err(e) => WsProtoMessage::Error(WsError::invalid_request(
                     Some(request.request_id.clone()),
@@ -415,7 +866,13 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
             // 解析 AnthropicMessagesRequest
             match serde_json::from_value::<AnthropicMessagesRequest>(request.payload.clone()) {
                 Ok(messages_request) => {
-                    handle_ws_anthropic_messages(state, &request.request_id, messages_request).await
+                    handle_ws_anthropic_messages(
+                        state,
+                        &request.request_id,
+                        messages_request,
+                        caller,
+                    )
+                    .await
                 }
                 Err(e) => WsProtoMessage::Error(WsError::invalid_request(
                     Some(request.request_id.clone()),
@@ -426,12 +883,13 @@ async fn handle_ws_api_request(state: &AppState, request: &WsApiRequest) -> WsPr
     }
 }
 
-/// 处理 WebSocket chat completions 请求
-async fn handle_ws_chat_completions(
+/// 解析模型别名、校验作用域、应用参数注入并选择凭证（不降级，指定什么就用什么）
+async fn prepare_ws_chat_request(
     state: &AppState,
     request_id: &str,
-    mut request: ChatCompletionRequest,
-) -> WsProtoMessage {
+    request: &mut ChatCompletionRequest,
+    caller: &WsCaller,
+) -> Result<ProviderCredential, WsError> {
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
 
@@ -442,53 +900,302 @@ async fn handle_ws_chat_completions(
     if ctx.resolved_model != ctx.original_model {
         request.model = ctx.resolved_model.clone();
     }
+    caller.verify_scope(request_id, Some(&request.model), None)?;
 
     // 应用参数注入
     let injection_enabled = *state.injection_enabled.read().await;
     if injection_enabled {
         let injector = state.processor.injector.read().await;
-        let mut payload = serde_json::to_value(&request).unwrap_or_default();
+        let mut payload = serde_json::to_value(&*request).unwrap_or_default();
         let result = injector.inject(&request.model, &mut payload);
         if result.has_injections() {
             if let Ok(updated) = serde_json::from_value(payload) {
-                request = updated;
+                *request = updated;
             }
         }
     }
 
-    // 获取默认 provider
-    let default_provider = state.default_provider.read().await.clone();
+    select_ws_credential(state, request_id, &request.model, caller).await
+}
 
-    // 尝试从凭证池中选择凭证（不降级，指定什么就用什么）
-    let credential = match &state.db {
-        Some(db) => state
-            .pool_service
-            .select_credential(db, &default_provider, Some(&request.model))
-            .ok()
-            .flatten(),
-        None => None,
+/// 选择凭证并校验客户端 Key 的 Provider 作用域
+async fn select_ws_credential(
+    state: &AppState,
+    request_id: &str,
+    model: &str,
+    caller: &WsCaller,
+) -> Result<ProviderCredential, WsError> {
+    let credential = resolve_ws_credential(state, request_id, model, caller.selector()).await?;
+    caller.verify_scope(
+        request_id,
+        None,
+        Some(&credential.provider_type.to_string()),
+    )?;
+    Ok(credential)
+}
+
+/// 解析凭证
+///
+/// 连接绑定了选择器时按选择器解析（不降级，端点限流生效），否则从默认 Provider 的凭证池中选择
+async fn resolve_ws_credential(
+    state: &AppState,
+    request_id: &str,
+    model: &str,
+    selector: Option<&str>,
+) -> Result<ProviderCredential, WsError> {
+    let Some(selector) = selector else {
+        let default_provider = state.default_provider.read().await.clone();
+        let credential = match &state.db {
+            Some(db) => state
+                .pool_service
+                .select_credential(db, &default_provider, Some(model))
+                .ok()
+                .flatten(),
+            None => None,
+        };
+        // 不再回退到 Kiro provider，直接返回错误
+        return credential.ok_or_else(|| {
+            WsError::internal(
+                Some(request_id.to_string()),
+                format!(
+                    "No available credentials for provider '{}'. Please add credentials in the Provider Pool.",
+                    default_provider
+                ),
+            )
+        });
+    };
+
+    let endpoint = state
+        .processor
+        .selector_endpoints
+        .read()
+        .await
+        .get(selector)
+        .cloned();
+    if let Some(limit) = endpoint.as_ref().and_then(|e| e.rate_limit_per_minute) {
+        if let Err(e) = state.client_keys.check_selector_rate_limit(selector, limit) {
+            state
+                .logs
+                .write()
+                .await
+                .add("warn", &format!("[SELECTOR] /{}/ws: {}", selector, e));
+            return Err(WsError::rate_limited(
+                Some(request_id.to_string()),
+                e.to_string(),
+            ));
+        }
+    }
+
+    let credential = state.db.as_ref().and_then(|db| {
+        selector_endpoint::resolve_credential(
+            &state.pool_service,
+            db,
+            selector,
+            endpoint.as_ref(),
+            model,
+        )
+    });
+    credential.ok_or_else(|| {
+        WsError::internal(
+            Some(request_id.to_string()),
+            format!("No available credentials for selector '{}'", selector),
+        )
+    })
+}
+
+/// 处理 WebSocket chat completions 请求
+async fn handle_ws_chat_completions(
+    state: &AppState,
+    request_id: &str,
+    mut request: ChatCompletionRequest,
+    caller: &WsCaller,
+) -> WsProtoMessage {
+    let cred = match prepare_ws_chat_request(state, request_id, &mut request, caller).await {
+        Ok(cred) => cred,
+        Err(e) => return WsProtoMessage::Error(e),
     };
 
-    // 如果找到凭证，使用它调用 API
-    if let Some(cred) = credential {
-        // 简化实现：直接调用 provider 并返回结果
-        // 实际实现应该复用 call_provider_openai 的逻辑
-        match call_provider_openai_for_ws(state, &cred, &request).await {
+    // 简化实现：直接调用 provider 并返回结果
+    // 实际实现应该复用 call_provider_openai 的逻辑
+    match call_provider_openai_for_ws(state, &cred, &request).await {
+        Ok(response) => WsProtoMessage::Response(WsApiResponse {
+            request_id: request_id.to_string(),
+            payload: response,
+        }),
+        Err(e) => WsProtoMessage::Error(WsError::upstream(Some(request_id.to_string()), e)),
+    }
+}
+
+/// 处理 `stream: true` 的 WebSocket chat completions 请求
+///
+/// OpenAI 兼容凭证逐块转发上游 SSE 为 `stream_chunk`，其他凭证返回完整响应
+async fn stream_ws_chat_completions(
+    state: &AppState,
+    mut request: ChatCompletionRequest,
+    caller: &WsCaller,
+    sink: &mut WsResponseSink<'_>,
+) {
+    use crate::models::provider_pool_model::CredentialData;
+
+    let request_id = sink.request_id.clone();
+    let cred = match prepare_ws_chat_request(state, &request_id, &mut request, caller).await {
+        Ok(cred) => cred,
+        Err(e) => return sink.emit(WsProtoMessage::Error(e)).await,
+    };
+    let CredentialData::OpenAIKey { api_key, base_url } = &cred.credential else {
+        let response = match call_provider_openai_for_ws(state, &cred, &request).await {
             Ok(response) => WsProtoMessage::Response(WsApiResponse {
-                request_id: request_id.to_string(),
+                request_id: request_id.clone(),
                 payload: response,
             }),
-            Err(e) => WsProtoMessage::Error(WsError::upstream(Some(request_id.to_string()), e)),
+            Err(e) => WsProtoMessage::Error(WsError::upstream(Some(request_id.clone()), e)),
+        };
+        return sink.emit(response).await;
+    };
+
+    let mut provider = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+    use_shared_client(state, &cred, &mut provider.client);
+    let _in_flight = state.pool_service.begin_request(&cred.uuid);
+    let upstream_error = |message: String| {
+        if let Some(db) = &state.db {
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &cred.uuid, Some(&message));
+        }
+        WsProtoMessage::Error(WsError::upstream(Some(request_id.clone()), message))
+    };
+    let resp = match provider.call_api(&request).await {
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) => {
+            let body = resp.text().await.unwrap_or_default();
+            return sink
+                .emit(upstream_error(format!("Upstream error: {}", body)))
+                .await;
+        }
+        Err(e) => return sink.emit(upstream_error(e.to_string())).await,
+    };
+    if let Some(db) = &state.db {
+        let _ = state
+            .pool_service
+            .mark_healthy(db, &cred.uuid, Some(&request.model));
+        let _ = state.pool_service.record_usage(db, &cred.uuid);
+    }
+
+    // 按 UTF-8 字符边界切分字节流，避免多字节字符跨块时被破坏
+    let text_stream = Box::pin(resp.bytes_stream().scan(Vec::new(), |pending, chunk| {
+        let text = chunk.map(|bytes| {
+            pending.extend_from_slice(&bytes);
+            let valid = match std::str::from_utf8(pending) {
+                Ok(text) => text.len(),
+                Err(e) => e.valid_up_to(),
+            };
+            let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+            pending.drain(..valid);
+            text
+        });
+        futures::future::ready(Some(text))
+    }));
+    let forwarder = StreamForwarder::new(request_id.clone());
+    let (tx, mut rx) = forwarder.create_channel();
+    let (result, _) = tokio::join!(forwarder.forward_string_stream(text_stream, tx), async {
+        while let Some(message) = rx.recv().await {
+            sink.emit(message).await;
+        }
+    });
+    if let Err(e) = result {
+        sink.emit(WsProtoMessage::Error(e)).await;
+    }
+}
+
+/// 请求响应的发送端
+///
+/// 消息先写入续传缓存再发送；连接断开后请求继续执行，消息只写入缓存
+struct WsResponseSink<'a> {
+    streams: &'a WsStreamBuffer,
+    sender: &'a WsSender,
+    owner: String,
+    request_id: String,
+    connected: bool,
+}
+
+impl<'a> WsResponseSink<'a> {
+    fn new(
+        streams: &'a WsStreamBuffer,
+        sender: &'a WsSender,
+        owner: String,
+        request_id: &str,
+    ) -> Self {
+        Self {
+            streams,
+            sender,
+            owner,
+            request_id: request_id.to_string(),
+            connected: true,
+        }
+    }
+
+    async fn emit(&mut self, message: WsProtoMessage) {
+        self.streams
+            .push(&self.owner, &self.request_id, message.clone());
+        if !self.connected {
+            return;
+        }
+        if self.sender.send(&message).await.is_err() {
+            self.connected = false;
+            tracing::debug!(
+                "[WS] 请求 {} 的连接已断开，继续缓存响应以供续传",
+                self.request_id
+            );
+        }
+    }
+}
+
+/// 在当前连接上重放缓存的响应消息，请求仍在进行时持续推送直到结束
+///
+/// 只能续传同一调用方（相同的 Key 和选择器）发起的请求，其他调用方的请求按不存在处理
+async fn resume_ws_stream(
+    state: &AppState,
+    conn_id: &str,
+    caller: &WsCaller,
+    resume: WsResume,
+    sender: &WsSender,
+) -> Option<WsProtoMessage> {
+    let streams = state.ws_manager.streams();
+    let owner = caller.owner();
+    let not_found = || {
+        Some(WsProtoMessage::Error(WsError::invalid_request(
+            Some(resume.request_id.clone()),
+            "No resumable stream for this request_id (unknown or expired)",
+        )))
+    };
+    let Some(mut updates) = streams.subscribe(&owner, &resume.request_id) else {
+        return not_found();
+    };
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[WS] Connection {} resuming request {} from message {}",
+            &conn_id[..8],
+            resume.request_id,
+            resume.replay_from()
+        ),
+    );
+
+    let mut cursor = resume.replay_from();
+    loop {
+        let Some((messages, finished)) = streams.messages_from(&owner, &resume.request_id, cursor)
+        else {
+            return not_found();
+        };
+        for message in messages {
+            if sender.send(&message).await.is_err() {
+                return None;
+            }
+            cursor += 1;
+        }
+        if finished || updates.changed().await.is_err() {
+            return None;
         }
-    } else {
-        // 不再回退到 Kiro provider，直接返回错误
-        WsProtoMessage::Error(WsError::internal(
-            Some(request_id.to_string()),
-            format!(
-                "No available credentials for provider '{}'. Please add credentials in the Provider Pool.",
-                default_provider
-            ),
-        ))
     }
 }
 
@@ -497,6 +1204,7 @@ async fn handle_ws_anthropic_messages(
     state: &AppState,
     request_id: &str,
     mut request: AnthropicMessagesRequest,
+    caller: &WsCaller,
 ) -> WsProtoMessage {
     // 创建请求上下文
     let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
@@ -508,6 +1216,9 @@ async fn handle_ws_anthropic_messages(
     if ctx.resolved_model != ctx.original_model {
         request.model = ctx.resolved_model.clone();
     }
+    if let Err(e) = caller.verify_scope(request_id, Some(&request.model), None) {
+        return WsProtoMessage::Error(e);
+    }
 
     // 应用参数注入
     let injection_enabled = *state.injection_enabled.read().await;
@@ -522,37 +1233,16 @@ async fn handle_ws_anthropic_messages(
         }
     }
 
-    // 获取默认 provider
-    let default_provider = state.default_provider.read().await.clone();
-
-    // 尝试从凭证池中选择凭证（带智能降级）
-    let credential = match &state.db {
-        Some(db) => state
-            .pool_service
-            .select_credential(db, &default_provider, Some(&request.model))
-            .ok()
-            .flatten(),
-        None => None,
+    let cred = match select_ws_credential(state, request_id, &request.model, caller).await {
+        Ok(cred) => cred,
+        Err(e) => return WsProtoMessage::Error(e),
     };
-
-    // 如果找到凭证，使用它调用 API
-    if let Some(cred) = credential {
-        match call_provider_anthropic_for_ws(state, &cred, &request).await {
-            Ok(response) => WsProtoMessage::Response(WsApiResponse {
-                request_id: request_id.to_string(),
-                payload: response,
-            }),
-            Err(e) => WsProtoMessage::Error(WsError::upstream(Some(request_id.to_string()), e)),
-        }
-    } else {
-        // 不再回退到 Kiro provider，直接返回错误
-        WsProtoMessage::Error(WsError::internal(
-            Some(request_id.to_string()),
-            format!(
-                "No available credentials for provider '{}'. Please add credentials in the Provider Pool.",
-                default_provider
-            ),
-        ))
+    match call_provider_anthropic_for_ws(state, &cred, &request).await {
+        Ok(response) => WsProtoMessage::Response(WsApiResponse {
+            request_id: request_id.to_string(),
+            payload: response,
+        }),
+        Err(e) => WsProtoMessage::Error(WsError::upstream(Some(request_id.to_string()), e)),
     }
 }
 
@@ -567,6 +1257,7 @@ pub async fn call_provider_openai_for_ws(
     match &credential.credential {
         CredentialData::KiroOAuth { creds_file_path } => {
             let mut kiro = KiroProvider::new();
+            use_shared_client(state, credential, &mut kiro.client);
             if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
                 if let Some(db) = &state.db {
                     let _ = state.pool_service.mark_unhealthy(
@@ -602,7 +1293,7 @@ pub async fn call_provider_openai_for_ws(
                 }
             };
             if resp.status().is_success() {
-                let body = resp.text().await.map_err(|e| e.to_string())?;
+                let body = resp.bytes().await.map_err(|e| e.to_string())?;
                 let parsed = parse_cw_response(&body);
                 let has_tool_calls = !parsed.tool_calls.is_empty();
 
@@ -667,7 +1358,8 @@ pub async fn call_provider_openai_for_ws(
             }
         }
         CredentialData::OpenAIKey { api_key, base_url } => {
-            let provider = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+            let mut provider = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut provider.client);
             let resp = match provider.call_api(request).await {
                 Ok(r) => r,
                 Err(e) => {
@@ -711,9 +1403,12 @@ pub async fn call_provider_openai_for_ws(
                 actual_base_url,
                 &credential.uuid[..8]
             );
-            let provider = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let mut provider = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut provider.client);
             match provider.call_openai_api(request).await {
-                Ok(result) => {
+                Ok(mut result) => {
+                    let reasoning = *state.processor.reasoning.read().await;
+                    apply_reasoning_to_openai_response(&mut result, reasoning);
                     // 记录成功
                     if let Some(db) = &state.db {
                         let _ = state.pool_service.mark_healthy(
@@ -742,6 +1437,7 @@ pub async fn call_provider_openai_for_ws(
             project_id,
         } => {
             let mut antigravity = AntigravityProvider::new();
+            use_shared_client(state, credential, &mut antigravity.client);
             if let Err(e) = antigravity
                 .load_credentials_from_path(creds_file_path)
                 .await
@@ -810,10 +1506,10 @@ pub async fn call_provider_openai_for_ws(
                         );
                         let _ = state.pool_service.record_usage(db, &credential.uuid);
                     }
-                    Ok(convert_antigravity_to_openai_response(
-                        &resp,
-                        &request.model,
-                    ))
+                    let mut result = convert_antigravity_to_openai_response(&resp, &request.model);
+                    let reasoning = *state.processor.reasoning.read().await;
+                    apply_reasoning_to_openai_response(&mut result, reasoning);
+                    Ok(result)
                 }
                 Err(e) => {
                     if let Some(db) = &state.db {
@@ -852,7 +1548,8 @@ pub async fn call_provider_anthropic_for_ws(
                 actual_base_url,
                 &credential.uuid[..8]
             );
-            let provider = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            let mut provider = ClaudeCustomProvider::with_config(api_key.clone(), base_url.clone());
+            use_shared_client(state, credential, &mut provider.client);
             let resp = match provider.call_api(request).await {
                 Ok(r) => r,
                 Err(e) => {
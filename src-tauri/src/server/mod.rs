@@ -1,10 +1,20 @@
 //! HTTP API 服务器
 
+pub mod bind_guard;
 pub mod client_detector;
+pub mod compression;
+pub mod context_fallback;
+pub mod drain;
+pub mod error;
+pub mod openapi;
+pub mod selector_endpoint;
+pub mod tls;
+pub mod token_count;
+pub mod token_usage;
 
 use crate::config::{
-    Config, ConfigChangeKind, ConfigManager, EndpointProvidersConfig, FileChangeEvent, FileWatcher,
-    HotReloadManager, ReloadResult,
+    Config, ConfigChangeKind, ConfigManager, EndpointProvidersConfig, FailoverSettings,
+    FileChangeEvent, FileWatcher, HotReloadManager, ReloadResult,
 };
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::credential::CredentialSyncService;
@@ -23,10 +33,11 @@ use crate::providers::claude_custom::ClaudeCustomProvider;
 use crate::providers::gemini::GeminiProvider;
 use crate::providers::kiro::KiroProvider;
 use crate::providers::openai_custom::OpenAICustomProvider;
+use crate::server::error::ProxyApiError;
 use crate::server_utils::{
     build_anthropic_response, build_anthropic_stream_response, build_error_response,
     build_error_response_with_status, build_gemini_cli_request, build_gemini_native_request,
-    health, models, parse_cw_response,
+    health, parse_cw_response,
 };
 use crate::services::kiro_event_service::KiroEventService;
 use crate::services::provider_pool_service::ProviderPoolService;
@@ -71,17 +82,52 @@ pub fn record_request_telemetry(
             error_message.clone().unwrap_or_default(),
         ),
         crate::telemetry::RequestStatus::Timeout => log.mark_timeout(ctx.elapsed_ms()),
+        crate::telemetry::RequestStatus::RateLimited => log.mark_rate_limited(
+            ctx.elapsed_ms(),
+            None,
+            error_message.clone().unwrap_or_default(),
+        ),
         crate::telemetry::RequestStatus::Cancelled => log.mark_cancelled(ctx.elapsed_ms()),
         crate::telemetry::RequestStatus::Retrying => {
             log.duration_ms = ctx.elapsed_ms();
         }
     }
 
+    // 流式请求在收到上游响应头时记录，此时的耗时即首字节延迟；
+    // 端到端延迟在流结束时由 Token 用量追踪补记
+    if ctx.is_stream && status == crate::telemetry::RequestStatus::Success {
+        log.set_ttfb(ctx.elapsed_ms());
+    }
+
     // 设置凭证 ID
     if let Some(cred_id) = &ctx.credential_id {
         log.set_credential_id(cred_id.clone());
     }
 
+    // 设置客户端 Key ID（用于按 Key 统计用量）
+    if let Some(client_key_id) = &ctx.client_key_id {
+        log.set_client_key_id(client_key_id.clone());
+    }
+
+    // 设置调用方工具名称（用于按工具统计用量）
+    if let Some(client_name) = &ctx.client_name {
+        log.set_client_name(client_name.clone());
+    }
+
+    // 记录长上下文降级前的模型
+    if let Some(model) = &ctx.fallback_from_model {
+        log.set_fallback_from_model(model.clone());
+    }
+
+    // 补充请求链路根 span 的属性（未处于追踪 span 中时为空操作）
+    let span = tracing::Span::current();
+    span.record("request.id", ctx.request_id.as_str());
+    span.record("model", ctx.resolved_model.as_str());
+    span.record("provider", tracing::field::display(provider));
+    if let Some(cred_id) = &ctx.credential_id {
+        span.record("credential.id", cred_id.as_str());
+    }
+
     // 设置重试次数
     log.retry_count = ctx.retry_count;
 
@@ -96,6 +142,19 @@ pub fn record_request_telemetry(
         let _ = logger.record(log.clone());
     }
 
+    // 持久化到数据库（用于检索历史请求日志）
+    if let Some(db) = &state.db {
+        state.request_logs.record(db, &log);
+    }
+
+    // 写入共享存储（多实例部署时跨实例检索）
+    state.shared_storage.record_request_log(&log);
+
+    // 推送给订阅了遥测的 WebSocket 连接
+    if state.telemetry_events.receiver_count() > 0 {
+        let _ = state.telemetry_events.send(log.clone());
+    }
+
     tracing::info!(
         "[TELEMETRY] request_id={} provider={:?} model={} status={:?} duration_ms={}",
         ctx.request_id,
@@ -112,8 +171,10 @@ pub fn record_token_usage(
     ctx: &RequestContext,
     input_tokens: Option<u32>,
     output_tokens: Option<u32>,
+    cache: crate::telemetry::CacheTokens,
+    source: crate::telemetry::TokenSource,
 ) {
-    use crate::telemetry::{TokenSource, TokenUsageRecord};
+    use crate::telemetry::TokenUsageRecord;
 
     // 只有当至少有一个 Token 值时才记录
     if input_tokens.is_none() && output_tokens.is_none() {
@@ -121,15 +182,21 @@ pub fn record_token_usage(
     }
 
     let provider = ctx.provider.unwrap_or(crate::ProviderType::Kiro);
-    let record = TokenUsageRecord::new(
+    let mut record = TokenUsageRecord::new(
         uuid::Uuid::new_v4().to_string(),
         provider,
         ctx.resolved_model.clone(),
         input_tokens.unwrap_or(0),
         output_tokens.unwrap_or(0),
-        TokenSource::Actual,
+        source,
     )
-    .with_request_id(ctx.request_id.clone());
+    .with_request_id(ctx.request_id.clone())
+    .with_cache_tokens(cache);
+    record.credential_id = ctx.credential_id.clone();
+    record.client_name = ctx.client_name.clone();
+
+    // 写入共享存储（多实例部署时跨实例统计用量）
+    state.shared_storage.record_token_usage(&record);
 
     // 记录到 Token 追踪器
     {
@@ -138,10 +205,13 @@ pub fn record_token_usage(
     }
 
     tracing::debug!(
-        "[TOKEN] request_id={} input={} output={}",
+        "[TOKEN] request_id={} input={} output={} cache_creation={} cache_read={} source={}",
         ctx.request_id,
         input_tokens.unwrap_or(0),
-        output_tokens.unwrap_or(0)
+        output_tokens.unwrap_or(0),
+        cache.cache_creation_input_tokens,
+        cache.cache_read_input_tokens,
+        source
     );
 }
 
@@ -167,6 +237,12 @@ pub struct ServerState {
     /// 路由器引用（用于动态更新默认 Provider）
     pub router_ref: Option<Arc<RwLock<crate::router::Router>>>,
     shutdown_tx: Option<oneshot::Sender<()>>,
+    /// 服务器任务句柄（停机时等待其排空进行中的请求）
+    server_task: Option<tokio::task::JoinHandle<()>>,
+    /// 保留的监听 socket，重启时复用以避免拒绝连接
+    listener: Option<(std::net::SocketAddr, std::net::TcpListener)>,
+    /// 启动参数，供 `restart` 复用
+    launch: Option<ServerLaunch>,
     /// 服务器运行时使用的 API key（启动时从配置复制）
     /// 用于 test_api 命令，确保测试使用的 API key 和服务器一致
     pub running_api_key: Option<String>,
@@ -194,6 +270,9 @@ impl ServerState {
             default_provider_ref,
             router_ref: None,
             shutdown_tx: None,
+            server_task: None,
+            listener: None,
+            launch: None,
             running_api_key: None,
             running_host: None,
         }
@@ -287,8 +366,37 @@ impl ServerState {
             return Ok(());
         }
 
-        let (tx, rx) = oneshot::channel();
-        self.shutdown_tx = Some(tx);
+        let launch = ServerLaunch {
+            logs,
+            pool_service,
+            token_cache,
+            db,
+            shared_stats,
+            shared_tokens,
+            shared_logger,
+            shared_flow_monitor,
+            shared_flow_interceptor,
+        };
+        self.launch = Some(launch.clone());
+        self.spawn_server(launch).await
+    }
+
+    /// 按启动参数创建并运行服务器任务
+    async fn spawn_server(
+        &mut self,
+        launch: ServerLaunch,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let ServerLaunch {
+            logs,
+            pool_service,
+            token_cache,
+            db,
+            shared_stats,
+            shared_tokens,
+            shared_logger,
+            shared_flow_monitor,
+            shared_flow_interceptor,
+        } = launch;
 
         // 智能选择监听地址
         // - 127.0.0.1, localhost, 0.0.0.0, :: 直接使用
@@ -305,7 +413,30 @@ impl ServerState {
             );
         }
 
+        // 对外暴露且未设置 API Key 时拒绝启动
+        match bind_guard::check_bind_exposure(&self.config.server) {
+            Ok(Some(warning)) => {
+                tracing::warn!("[SERVER] {}", warning);
+                logs.write()
+                    .await
+                    .add("warn", &format!("[SERVER] {}", warning));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!("[SERVER] {}", e);
+                logs.write().await.add("error", &format!("[SERVER] {}", e));
+                return Err(e.into());
+            }
+        }
+
         let port = self.config.server.port;
+        let tls_config = tls::load_rustls_config(&self.config.server.tls, &host).await?;
+        let listener = self.bind_listener(&host, port)?;
+        let drain_timeout =
+            std::time::Duration::from_secs(self.config.server.shutdown_timeout_secs);
+        let (tx, rx) = oneshot::channel();
+        self.shutdown_tx = Some(tx);
+
         let api_key = self.config.server.api_key.clone();
         let api_key_for_state = api_key.clone(); // 用于保存到 running_api_key
         let default_provider_ref = self.default_provider_ref.clone();
@@ -376,10 +507,13 @@ impl ServerState {
         // 保存实际使用的 host（在移动到 spawn 之前克隆）
         let running_host = host.clone();
 
-        tokio::spawn(async move {
+        self.server_task = Some(tokio::spawn(async move {
             if let Err(e) = run_server(
                 &host,
                 port,
+                listener,
+                drain_timeout,
+                tls_config,
                 &api_key,
                 default_provider_ref,
                 kiro,
@@ -403,7 +537,7 @@ impl ServerState {
             {
                 tracing::error!("Server error: {}", e);
             }
-        });
+        }));
 
         self.running = true;
         self.start_time = Some(std::time::Instant::now());
@@ -414,7 +548,56 @@ impl ServerState {
         Ok(())
     }
 
+    /// 停止服务器
+    ///
+    /// 不再接受新连接，等待进行中的 HTTP/WebSocket 请求完成（超时见
+    /// `server.shutdown_timeout_secs`）后返回，并释放监听端口。
     pub async fn stop(&mut self) {
+        let server_task = self.signal_shutdown();
+        self.listener = None;
+        self.launch = None;
+        if let Some(task) = server_task {
+            let _ = task.await;
+        }
+    }
+
+    /// 重启服务器（应用最新配置）
+    ///
+    /// 监听地址未变化时复用已绑定的 socket，新服务器立即接管连接，
+    /// 旧服务器在后台排空进行中的请求；地址变化时先等待旧服务器停止再重新绑定。
+    pub async fn restart(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(launch) = self.launch.clone() else {
+            return Err("服务器未启动，无法重启".into());
+        };
+
+        let server_task = self.signal_shutdown();
+        let reuse_listener = match (
+            &self.listener,
+            parse_bind_addr(&self.config.server.host, self.config.server.port),
+        ) {
+            (Some((bound, _)), Ok(addr)) => *bound == addr,
+            _ => false,
+        };
+        if !reuse_listener {
+            self.listener = None;
+            if let Some(task) = server_task {
+                let _ = task.await;
+            }
+        }
+
+        tracing::info!(
+            "[SERVER] 正在重启服务器（{}监听 socket）",
+            if reuse_listener {
+                "复用"
+            } else {
+                "重新绑定"
+            }
+        );
+        self.spawn_server(launch).await
+    }
+
+    /// 发送停机信号并重置运行状态，返回旧服务器任务句柄
+    fn signal_shutdown(&mut self) -> Option<tokio::task::JoinHandle<()>> {
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(());
         }
@@ -423,14 +606,75 @@ impl ServerState {
         self.running_api_key = None;
         self.running_host = None;
         self.router_ref = None;
+        self.server_task.take()
     }
+
+    /// 获取监听 socket
+    ///
+    /// 地址与已保留的 socket 相同时复制其句柄，否则重新绑定
+    fn bind_listener(
+        &mut self,
+        host: &str,
+        port: u16,
+    ) -> Result<std::net::TcpListener, Box<dyn std::error::Error + Send + Sync>> {
+        let addr = parse_bind_addr(host, port)?;
+        if let Some((bound, listener)) = &self.listener {
+            if *bound == addr {
+                return Ok(listener.try_clone()?);
+            }
+        }
+
+        let bind_error = |e: std::io::Error| {
+            format!(
+                "无法绑定到 {}:{}，错误: {}。请检查地址是否有效或端口是否被占用。",
+                host, port, e
+            )
+        };
+        let socket = if addr.is_ipv4() {
+            tokio::net::TcpSocket::new_v4()
+        } else {
+            tokio::net::TcpSocket::new_v6()
+        }
+        .map_err(bind_error)?;
+        socket.set_reuseaddr(true).map_err(bind_error)?;
+        socket.bind(addr).map_err(bind_error)?;
+        let listener = socket.listen(1024).map_err(bind_error)?.into_std()?;
+
+        self.listener = Some((addr, listener.try_clone()?));
+        Ok(listener)
+    }
+}
+
+/// 服务器启动参数（与应用其他部分共享的服务实例）
+#[derive(Clone)]
+struct ServerLaunch {
+    logs: Arc<RwLock<LogStore>>,
+    pool_service: Arc<ProviderPoolService>,
+    token_cache: Arc<TokenCacheService>,
+    db: Option<DbConnection>,
+    shared_stats: Option<Arc<parking_lot::RwLock<crate::telemetry::StatsAggregator>>>,
+    shared_tokens: Option<Arc<parking_lot::RwLock<crate::telemetry::TokenTracker>>>,
+    shared_logger: Option<Arc<crate::telemetry::RequestLogger>>,
+    shared_flow_monitor: Option<Arc<FlowMonitor>>,
+    shared_flow_interceptor: Option<Arc<FlowInterceptor>>,
+}
+
+/// 解析监听地址
+fn parse_bind_addr(
+    host: &str,
+    port: u16,
+) -> Result<std::net::SocketAddr, Box<dyn std::error::Error + Send + Sync>> {
+    format!("{host}:{port}")
+        .parse()
+        .map_err(|e| format!("无效的监听地址 {}:{} - {}", host, port, e).into())
 }
 
 impl Clone for KiroProvider {
     fn clone(&self) -> Self {
         Self {
             credentials: self.credentials.clone(),
-            client: reqwest::Client::new(),
+            // reqwest::Client 内部为 Arc，克隆后共享连接池
+            client: self.client.clone(),
             creds_path: self.creds_path.clone(),
         }
     }
@@ -473,10 +717,30 @@ pub struct AppState {
     pub flow_interceptor: Arc<FlowInterceptor>,
     /// 端点 Provider 配置
     pub endpoint_providers: Arc<RwLock<EndpointProvidersConfig>>,
+    /// 凭证故障转移配置
+    pub failover: Arc<RwLock<FailoverSettings>>,
     /// Kiro 事件服务
     pub kiro_event_service: Arc<KiroEventService>,
     /// API Key Provider 服务（用于智能降级）
     pub api_key_service: Arc<crate::services::api_key_provider_service::ApiKeyProviderService>,
+    /// 客户端 API Key 服务（多 Key 认证、作用域和限流）
+    pub client_keys: Arc<crate::services::client_api_key_service::ClientApiKeyService>,
+    /// 请求/响应审计日志服务
+    pub audit: Arc<crate::services::audit_log_service::AuditLogService>,
+    /// 请求日志持久化服务
+    pub request_logs: Arc<crate::services::request_log_service::RequestLogService>,
+    /// 共享存储服务（多实例共享凭证池和统计）
+    pub shared_storage: Arc<crate::services::shared_storage_service::SharedStorageService>,
+    /// 上游 HTTP 客户端服务（按 Provider 共享连接池，出站代理）
+    pub http_clients: Arc<crate::services::http_client_service::HttpClientService>,
+    /// 模型发现服务（/v1/models 动态模型列表）
+    pub model_discovery: Arc<crate::services::model_discovery_service::ModelDiscoveryService>,
+    /// 响应缓存服务
+    pub response_cache: Arc<crate::services::response_cache_service::ResponseCacheService>,
+    /// 请求遥测推送通道（WebSocket 遥测订阅）
+    pub telemetry_events: tokio::sync::broadcast::Sender<crate::telemetry::RequestLog>,
+    /// 进行中请求跟踪与停机信号
+    pub drain: Arc<drain::DrainTracker>,
 }
 
 /// 启动配置文件监控
@@ -497,6 +761,11 @@ async fn start_config_watcher(
     logs: Arc<RwLock<LogStore>>,
     db: Option<DbConnection>,
     config_manager: Option<Arc<std::sync::RwLock<ConfigManager>>>,
+    audit: Arc<crate::services::audit_log_service::AuditLogService>,
+    request_logs: Arc<crate::services::request_log_service::RequestLogService>,
+    response_cache: Arc<crate::services::response_cache_service::ResponseCacheService>,
+    http_clients: Arc<crate::services::http_client_service::HttpClientService>,
+    failover: Arc<RwLock<FailoverSettings>>,
 ) -> Option<FileWatcher> {
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<FileChangeEvent>();
 
@@ -541,16 +810,28 @@ async fn start_config_watcher(
             if let Some(ref manager) = hot_reload_manager_clone {
                 let result = manager.reload();
                 match &result {
-                    ReloadResult::Success { .. } => {
+                    ReloadResult::Success { warnings, .. } => {
                         tracing::info!("[HOT_RELOAD] 配置热重载成功");
                         logs_clone
                             .write()
                             .await
                             .add("info", "[HOT_RELOAD] 配置热重载成功");
+                        for warning in warnings {
+                            tracing::warn!("[HOT_RELOAD] {}", warning);
+                            logs_clone
+                                .write()
+                                .await
+                                .add("warn", &format!("[HOT_RELOAD] {}", warning));
+                        }
 
                         // 更新处理器中的组件
                         let new_config = manager.config();
                         update_processor_config(&processor_clone, &new_config).await;
+                        audit.update_config(new_config.logging.audit.clone());
+                        request_logs.update_config(new_config.logging.request_logs.clone());
+                        response_cache.update_config(new_config.response_cache.clone());
+                        http_clients.update_config(&new_config);
+                        *failover.write().await = new_config.failover.clone();
 
                         // 同步凭证池
                         if let (Some(ref db), Some(ref cfg_manager)) =
@@ -582,12 +863,24 @@ async fn start_config_watcher(
                             }
                         }
                     }
-                    ReloadResult::RolledBack { error, .. } => {
-                        tracing::warn!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error);
-                        logs_clone.write().await.add(
-                            "warn",
-                            &format!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error),
-                        );
+                    ReloadResult::RolledBack { diagnostics, .. } => {
+                        tracing::warn!("[HOT_RELOAD] 配置热重载失败，已回滚");
+                        logs_clone
+                            .write()
+                            .await
+                            .add("warn", "[HOT_RELOAD] 配置热重载失败，已回滚");
+                        for diagnostic in diagnostics {
+                            let level = if diagnostic.is_error() {
+                                "error"
+                            } else {
+                                "warn"
+                            };
+                            tracing::warn!("[HOT_RELOAD] {}", diagnostic);
+                            logs_clone
+                                .write()
+                                .await
+                                .add(level, &format!("[HOT_RELOAD] {}", diagnostic));
+                        }
                     }
                     ReloadResult::Failed {
                         error,
@@ -639,6 +932,24 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
         );
     }
 
+    // 更新请求转换规则
+    {
+        *processor.transformer.write().await =
+            crate::transform::Transformer::new(config.transforms.clone());
+        tracing::debug!(
+            "[HOT_RELOAD] 请求转换规则已更新: {} 条规则",
+            config.transforms.rules.len()
+        );
+    }
+
+    // 更新推理内容处理方式、长上下文降级和选择器端点配置
+    *processor.reasoning.write().await = config.reasoning;
+    *processor.context_fallback.write().await = config.context_fallback.clone();
+    *processor.selector_endpoints.write().await = config.selector_endpoints.clone();
+
+    // 更新费用估算单价表
+    *processor.pricing.write().await = crate::telemetry::PricingTable::new(&config.pricing);
+
     // 更新路由器默认 Provider
     {
         let mut router = processor.router.write().await;
@@ -671,16 +982,45 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
     // 更新模型映射器
     {
         let mut mapper = processor.mapper.write().await;
-        mapper.clear();
-        for (alias, model) in &config.routing.model_aliases {
-            mapper.add_alias(alias, model);
+        for error in mapper.load_config(&config.routing) {
+            tracing::warn!("[HOT_RELOAD] 跳过别名规则: {}", error);
         }
         tracing::debug!(
-            "[HOT_RELOAD] 模型别名已更新: {} 个别名",
-            config.routing.model_aliases.len()
+            "[HOT_RELOAD] 模型别名已更新: {} 个别名, {} 条规则",
+            config.routing.model_aliases.len(),
+            config.routing.alias_rules.len()
         );
     }
 
+    // 更新凭证池负载均衡策略和会话粘性路由
+    processor
+        .pool_service
+        .set_load_balance_strategy(config.load_balance_strategy);
+    processor
+        .pool_service
+        .session_affinity()
+        .set_config(config.session_affinity.clone());
+
+    // 更新请求对冲和故障注入配置
+    *processor.hedger.write().await =
+        crate::resilience::Hedger::new(config.failover.hedging.clone());
+    if processor.chaos.read().await.config() != &config.chaos {
+        if config.chaos.enabled {
+            tracing::warn!("[CHAOS] 故障注入已启用: {:?}", config.chaos);
+        }
+        *processor.chaos.write().await =
+            crate::resilience::ChaosInjector::new(config.chaos.clone());
+    }
+
+    // 更新并发限制配置（进行中的请求继续持有旧限制器的名额）
+    if processor.concurrency.read().await.config() != &config.concurrency {
+        *processor.concurrency.write().await =
+            crate::resilience::ConcurrencyLimiter::new(config.concurrency.clone());
+    }
+
+    // 更新客户端 IP 白名单
+    *processor.ip_allowlist.write().await = build_ip_allowlist(&config.server.allowed_ips);
+
     // 注意：重试配置目前不支持热更新，因为 Retrier 是不可变的
     // 如果需要更新重试配置，需要重启服务器
     tracing::debug!(
@@ -692,6 +1032,17 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
     tracing::info!("[HOT_RELOAD] 处理器配置更新完成");
 }
 
+/// 按配置构建客户端 IP 白名单
+///
+/// 无效条目已由配置校验报告，这里跳过并记录警告
+fn build_ip_allowlist(entries: &[String]) -> crate::middleware::IpAllowlist {
+    let (allowlist, errors) = crate::middleware::IpAllowlist::from_entries(entries);
+    for error in errors {
+        tracing::warn!("[ACCESS] 跳过 IP 白名单条目: {}", error);
+    }
+    allowlist
+}
+
 /// 从配置同步凭证池
 ///
 /// 当配置热重载成功后，从 YAML 配置中加载凭证并同步到数据库。
@@ -747,6 +1098,9 @@ async fn sync_credential_pool_from_config(
 async fn run_server(
     host: &str,
     port: u16,
+    listener: std::net::TcpListener,
+    drain_timeout: std::time::Duration,
+    tls_config: Option<axum_server::tls_rustls::RustlsConfig>,
     api_key: &str,
     default_provider: Arc<RwLock<String>>,
     kiro: KiroProvider,
@@ -766,7 +1120,12 @@ async fn run_server(
     config_path: Option<PathBuf>,
     processor: Option<Arc<RequestProcessor>>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let base_url = format!("http://{}:{}", host, port);
+    let scheme = if tls_config.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let base_url = format!("{}://{}:{}", scheme, host, port);
 
     // 使用传入的 processor 或创建新的
     let processor = match processor {
@@ -789,6 +1148,28 @@ async fn run_server(
         }
     }
 
+    // 从配置初始化请求对冲器、故障注入器、并发限制器、请求转换器、推理内容处理方式、长上下文降级、选择器端点、IP 白名单和模型别名
+    if let Some(cfg) = &config {
+        *processor.hedger.write().await =
+            crate::resilience::Hedger::new(cfg.failover.hedging.clone());
+        if cfg.chaos.enabled {
+            tracing::warn!("[CHAOS] 故障注入已启用: {:?}", cfg.chaos);
+        }
+        *processor.chaos.write().await = crate::resilience::ChaosInjector::new(cfg.chaos.clone());
+        *processor.concurrency.write().await =
+            crate::resilience::ConcurrencyLimiter::new(cfg.concurrency.clone());
+        *processor.transformer.write().await =
+            crate::transform::Transformer::new(cfg.transforms.clone());
+        *processor.reasoning.write().await = cfg.reasoning;
+        *processor.context_fallback.write().await = cfg.context_fallback.clone();
+        *processor.selector_endpoints.write().await = cfg.selector_endpoints.clone();
+        *processor.ip_allowlist.write().await = build_ip_allowlist(&cfg.server.allowed_ips);
+        *processor.pricing.write().await = crate::telemetry::PricingTable::new(&cfg.pricing);
+        for error in processor.mapper.write().await.load_config(&cfg.routing) {
+            tracing::warn!("[SERVER] 跳过别名规则: {}", error);
+        }
+    }
+
     // 从配置初始化 Router 的默认 Provider
     if let Some(cfg) = &config {
         let default_provider_str = &cfg.routing.default_provider;
@@ -818,8 +1199,17 @@ async fn run_server(
         }
     }
 
-    // 初始化 WebSocket 管理器
-    let ws_manager = Arc::new(WsConnectionManager::new(WsConfig::default()));
+    // 初始化 WebSocket 管理器（消息压缩与 HTTP 压缩共用配置）
+    let compression_config = config
+        .as_ref()
+        .map(|c| c.server.compression.clone())
+        .unwrap_or_default();
+    let ws_manager = Arc::new(WsConnectionManager::new(WsConfig {
+        deflate_min_size: compression_config
+            .enable
+            .then_some(compression_config.min_size as usize),
+        ..WsConfig::default()
+    }));
     let ws_stats = ws_manager.stats().clone();
 
     // 初始化热重载管理器
@@ -864,6 +1254,22 @@ async fn run_server(
             .unwrap_or_default(),
     ));
 
+    // 初始化凭证池负载均衡策略和会话粘性路由
+    if let Some(cfg) = &config {
+        pool_service.set_load_balance_strategy(cfg.load_balance_strategy);
+        pool_service
+            .session_affinity()
+            .set_config(cfg.session_affinity.clone());
+    }
+
+    // 初始化凭证故障转移配置
+    let failover = Arc::new(RwLock::new(
+        config
+            .as_ref()
+            .map(|c| c.failover.clone())
+            .unwrap_or_default(),
+    ));
+
     // 创建 Kiro 事件服务
     let kiro_event_service = Arc::new(KiroEventService::new());
 
@@ -871,6 +1277,62 @@ async fn run_server(
     let api_key_service =
         Arc::new(crate::services::api_key_provider_service::ApiKeyProviderService::new());
 
+    // 创建客户端 API Key 服务
+    let client_keys = Arc::new(crate::services::client_api_key_service::ClientApiKeyService::new());
+
+    // 创建审计日志服务
+    let audit = Arc::new(crate::services::audit_log_service::AuditLogService::new(
+        config
+            .as_ref()
+            .map(|c| c.logging.audit.clone())
+            .unwrap_or_default(),
+    ));
+
+    // 创建请求日志持久化服务
+    let request_logs = Arc::new(
+        crate::services::request_log_service::RequestLogService::new(
+            config
+                .as_ref()
+                .map(|c| c.logging.request_logs.clone())
+                .unwrap_or_default(),
+        ),
+    );
+
+    // 创建共享存储服务，有本地数据库时启动凭证池同步任务
+    let shared_storage = Arc::new(
+        crate::services::shared_storage_service::SharedStorageService::new(
+            config
+                .as_ref()
+                .map(|c| c.storage.clone())
+                .unwrap_or_default(),
+        ),
+    );
+    if let Some(db) = &db {
+        shared_storage.start(db.clone());
+    }
+
+    // 创建响应缓存服务
+    let response_cache = Arc::new(
+        crate::services::response_cache_service::ResponseCacheService::new(
+            config
+                .as_ref()
+                .map(|c| c.response_cache.clone())
+                .unwrap_or_default(),
+        ),
+    );
+
+    // 上游 HTTP 客户端服务（进程内共享，请求路径外创建的 Provider 也使用同一配置）
+    let http_clients = crate::services::http_client_service::HttpClientService::shared();
+    if let Some(config) = config.as_ref() {
+        http_clients.update_config(config);
+    }
+
+    let drain_tracker = Arc::new(drain::DrainTracker::new());
+    let concurrency_limiter = processor.concurrency.clone();
+    let ip_allowlist = processor.ip_allowlist.clone();
+    let ws_manager_for_drain = ws_manager.clone();
+    let logs_for_drain = logs.clone();
+
     let state = AppState {
         api_key: api_key.to_string(),
         base_url,
@@ -893,8 +1355,20 @@ async fn run_server(
         flow_monitor,
         flow_interceptor,
         endpoint_providers,
+        failover: failover.clone(),
         kiro_event_service,
         api_key_service,
+        client_keys,
+        audit: audit.clone(),
+        request_logs: request_logs.clone(),
+        shared_storage,
+        http_clients: http_clients.clone(),
+        model_discovery: Arc::new(
+            crate::services::model_discovery_service::ModelDiscoveryService::new(),
+        ),
+        response_cache: response_cache.clone(),
+        telemetry_events: tokio::sync::broadcast::channel(1000).0,
+        drain: drain_tracker.clone(),
     };
 
     // ========== 开发模式：启动独立的 HTTP 桥接服务器 ==========
@@ -928,6 +1402,11 @@ async fn run_server(
             logs_clone,
             db_clone,
             config_manager,
+            audit.clone(),
+            request_logs,
+            response_cache,
+            http_clients,
+            failover,
         )
         .await
     } else {
@@ -961,6 +1440,32 @@ async fn run_server(
             "/v0/management/config",
             axum::routing::put(handlers::management_update_config),
         )
+        .route(
+            "/v0/management/api-keys",
+            get(handlers::management_list_api_keys).post(handlers::management_create_api_key),
+        )
+        .route(
+            "/v0/management/api-keys/{id}",
+            axum::routing::put(handlers::management_update_api_key)
+                .delete(handlers::management_delete_api_key),
+        )
+        .route(
+            "/v0/management/audit-logs",
+            get(handlers::management_list_audit_logs).delete(handlers::management_clear_audit_logs),
+        )
+        .route(
+            "/v0/management/audit-logs/{request_id}",
+            get(handlers::management_get_audit_log),
+        )
+        .route(
+            "/v0/management/request-logs",
+            get(handlers::management_search_request_logs)
+                .delete(handlers::management_clear_request_logs),
+        )
+        .route(
+            "/v0/management/request-logs/{id}",
+            get(handlers::management_get_request_log),
+        )
         .layer(crate::middleware::ManagementAuthLayer::new(
             management_config,
         ));
@@ -992,9 +1497,15 @@ async fn run_server(
             get(handlers::credentials_get_token),
         );
 
+    // 终端会话分享路由（一次性令牌即凭据，不经过 API Key 认证）
+    let terminal_share_routes = Router::new().route(
+        "/v1/terminal/share/{token}/ws",
+        get(handlers::terminal_share_ws_handler),
+    );
+
     let app = Router::new()
         .route("/health", get(health))
-        .route("/v1/models", get(models))
+        .route("/v1/models", get(handlers::list_models))
         .route("/v1/routes", get(list_routes))
         .route("/v1/chat/completions", post(
             |State(state): State<AppState>,
@@ -1016,6 +1527,26 @@ async fn run_server(
             "/v1/images/generations",
             post(handlers::handle_image_generation),
         )
+        // Embeddings API 路由
+        .route("/v1/embeddings", post(handlers::handle_embeddings))
+        // 语音 API 路由（转写请求额外预留 1MB 给 multipart 的其他字段和边界）
+        .route(
+            "/v1/audio/transcriptions",
+            post(handlers::handle_audio_transcriptions).layer(DefaultBodyLimit::max(
+                handlers::MAX_AUDIO_UPLOAD_BYTES + 1024 * 1024,
+            )),
+        )
+        .route("/v1/audio/speech", post(handlers::handle_audio_speech))
+        // Gemini 原生协议路由
+        .route(
+            "/v1beta/models/{model_method}",
+            post(handlers::handle_gemini_generate_content),
+        )
+        // 响应缓存统计
+        .route("/v1/cache/stats", get(handlers::response_cache_stats))
+        .route("/v1/aliases", get(handlers::list_aliases))
+        // 费用估算
+        .route("/v1/costs", get(handlers::list_costs))
         // WebSocket 路由
         .route("/v1/ws", get(handlers::ws_upgrade_handler))
         .route("/ws", get(handlers::ws_upgrade_handler))
@@ -1028,33 +1559,117 @@ async fn run_server(
             "/{selector}/v1/chat/completions",
             post(chat_completions_with_selector),
         )
+        .route("/{selector}/ws", get(handlers::ws_selector_upgrade_handler))
+        // 全局并发限制（仅作用于以上代理路由）
+        .layer(axum::middleware::from_fn_with_state(
+            concurrency_limiter,
+            crate::middleware::limit_concurrency,
+        ))
+        // 请求链路追踪（仅作用于以上代理路由）
+        .layer(axum::middleware::from_fn(crate::middleware::trace_request))
+        // 调用方工具归因（仅作用于以上代理路由）
+        .layer(axum::middleware::from_fn(crate::middleware::attribute_client))
+        // 请求 ID 分配与审计日志（仅作用于以上代理路由）
+        .layer(axum::middleware::from_fn_with_state(
+            crate::middleware::AuditState {
+                service: audit,
+                db: state.db.clone(),
+            },
+            crate::middleware::audit_request,
+        ))
         // 管理 API 路由
         .merge(management_routes)
         // Kiro凭证管理API路由
         .merge(kiro_api_routes)
         // 凭证 API 路由（用于 aster Agent 集成）
         .merge(credentials_api_routes)
-        .layer(DefaultBodyLimit::max(body_limit))
+        // 终端会话分享路由
+        .merge(terminal_share_routes)
+        // OpenAPI 文档
+        .route("/openapi.json", get(openapi::openapi_json))
+        .layer(DefaultBodyLimit::max(body_limit));
+
+    // HTTP 压缩（请求体解压后再计入大小限制）
+    let app = compression::apply_compression(app, &compression_config)
+        // 进行中请求计数（优雅停机）
+        .layer(axum::middleware::from_fn_with_state(
+            drain_tracker.clone(),
+            drain::track_in_flight,
+        ))
+        // 客户端 IP 白名单（作用于所有路由）
+        .layer(axum::middleware::from_fn_with_state(
+            ip_allowlist,
+            crate::middleware::filter_client_ip,
+        ))
         .with_state(state);
 
-    let addr: std::net::SocketAddr = format!("{host}:{port}")
-        .parse()
-        .map_err(|e| format!("无效的监听地址 {}:{} - {}", host, port, e))?;
+    listener.set_nonblocking(true)?;
+    tracing::info!("Server listening on {}://{}:{}", scheme, host, port);
 
-    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
-        format!(
-            "无法绑定到 {}:{}，错误: {}。请检查地址是否有效或端口是否被占用。",
-            host, port, e
-        )
-    })?;
+    // 收到停机信号后停止 accept，已建立的 HTTP 连接处理完当前请求后关闭
+    let mut shutdown_rx = drain_tracker.subscribe();
+    let shutdown_signal = async move {
+        let _ = shutdown_rx.wait_for(|stopping| *stopping).await;
+    };
+    let mut server = match tls_config {
+        Some(tls_config) => {
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown_signal.await;
+                shutdown_handle.graceful_shutdown(None);
+            });
+            tokio::spawn(async move {
+                axum_server::from_tcp_rustls(listener, tls_config)
+                    .handle(handle)
+                    .serve(app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+                    .await
+            })
+        }
+        None => {
+            let listener = tokio::net::TcpListener::from_std(listener)?;
+            tokio::spawn(async move {
+                axum::serve(
+                    listener,
+                    app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+                )
+                .with_graceful_shutdown(shutdown_signal)
+                .await
+            })
+        }
+    };
 
-    tracing::info!("Server listening on {}", addr);
+    tokio::select! {
+        result = &mut server => {
+            result??;
+            return Ok(());
+        }
+        _ = shutdown => {}
+    }
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            let _ = shutdown.await;
-        })
-        .await?;
+    // 通知 WebSocket 连接关闭，然后等待进行中的请求完成
+    logs_for_drain.write().await.add(
+        "info",
+        &format!(
+            "[SERVER] 开始停机，最多等待 {}s 以完成进行中的请求",
+            drain_timeout.as_secs()
+        ),
+    );
+    drain_tracker.signal_shutdown();
+    let drained = drain::wait_for_drain(
+        &drain_tracker,
+        &ws_manager_for_drain,
+        &logs_for_drain,
+        drain_timeout,
+        || server.is_finished(),
+    )
+    .await;
+
+    if drained {
+        server.await??;
+    } else {
+        server.abort();
+    }
 
     Ok(())
 }
@@ -1062,15 +1677,18 @@ async fn run_server(
 async fn count_tokens(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(_request): Json<serde_json::Value>,
+    Json(mut request): Json<AnthropicMessagesRequest>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state).await {
         return e.into_response();
     }
 
-    // Claude Code 需要这个端点，返回估算值
+    // 按别名解析后的实际模型选择分词方式
+    request.model = state.processor.resolve_model(&request.model).await;
+    let input_tokens = token_count::count_anthropic_input_tokens(&request);
+
     Json(serde_json::json!({
-        "input_tokens": 100
+        "input_tokens": input_tokens
     }))
     .into_response()
 }
@@ -1084,23 +1702,20 @@ async fn gemini_generate_content(
     Path(path): Path<String>,
     Json(request): Json<serde_json::Value>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
-        return e.into_response();
-    }
+    let client_key = match handlers::verify_api_key(&headers, &state).await {
+        Ok(client_key) => client_key,
+        Err(e) => return e.into_response(),
+    };
 
     // 解析路径: {model}:{method}
     // 例如: gemini-3-pro-preview:generateContent
     let parts: Vec<&str> = path.splitn(2, ':').collect();
     if parts.len() != 2 {
-        return (
+        return ProxyApiError::from_status(
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": {
-                    "message": format!("无效的路径格式: {}，期望格式: model:method", path)
-                }
-            })),
+            format!("无效的路径格式: {}，期望格式: model:method", path),
         )
-            .into_response();
+        .into_response();
     }
 
     let model = parts[0];
@@ -1116,15 +1731,11 @@ async fn gemini_generate_content(
 
     // 目前只支持 generateContent 方法
     if method != "generateContent" && method != "streamGenerateContent" {
-        return (
+        return ProxyApiError::from_status(
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": {
-                    "message": format!("不支持的方法: {}，目前只支持 generateContent", method)
-                }
-            })),
+            format!("不支持的方法: {}，目前只支持 generateContent", method),
         )
-            .into_response();
+        .into_response();
     }
 
     let is_stream = method == "streamGenerateContent";
@@ -1132,6 +1743,16 @@ async fn gemini_generate_content(
     // 获取默认 provider
     let default_provider = state.default_provider.read().await.clone();
 
+    // 校验客户端 Key 的模型和 Provider 作用域
+    if let Err(e) = handlers::verify_client_scope(
+        client_key.as_ref(),
+        Some(model),
+        Some(&default_provider),
+        handlers::ApiErrorFormat::OpenAI,
+    ) {
+        return e.into_response();
+    }
+
     // 尝试从凭证池中选择凭证（不降级，指定什么就用什么）
     let credential = match &state.db {
         Some(db) => state
@@ -1145,15 +1766,12 @@ async fn gemini_generate_content(
     let cred = match credential {
         Some(c) => c,
         None => {
-            return (
+            return ProxyApiError::from_status(
                 StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("No available credentials for provider '{}'. Please add credentials in the Provider Pool.", default_provider)
-                    }
-                })),
+                format!("No available credentials for provider '{}'. Please add credentials in the Provider Pool.", default_provider),
             )
-                .into_response();
+            .with_code("no_credentials")
+            .into_response();
         }
     };
 
@@ -1178,15 +1796,11 @@ async fn gemini_generate_content(
                 .load_credentials_from_path(creds_file_path)
                 .await
             {
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": format!("加载 Antigravity 凭证失败: {}", e)
-                        }
-                    })),
+                    format!("加载 Antigravity 凭证失败: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
 
             // 使用新的 validate_token() 方法检查 Token 状态
@@ -1219,15 +1833,7 @@ async fn gemini_generate_content(
                             )
                         };
 
-                        return (
-                            status,
-                            Json(serde_json::json!({
-                                "error": {
-                                    "message": message
-                                }
-                            })),
-                        )
-                            .into_response();
+                        return ProxyApiError::from_status(status, message).into_response();
                     }
                 }
             }
@@ -1277,15 +1883,11 @@ async fn gemini_generate_content(
 
             if is_stream {
                 // 流式响应 - 暂不支持，返回错误
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::NOT_IMPLEMENTED,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": "流式响应暂不支持，请使用 generateContent"
-                        }
-                    })),
+                    "流式响应暂不支持，请使用 generateContent",
                 )
-                    .into_response();
+                .into_response();
             }
 
             // 非流式响应
@@ -1330,15 +1932,11 @@ async fn gemini_generate_content(
             // 使用 GeminiProvider 处理 Gemini CLI OAuth 凭证
             let mut gemini = GeminiProvider::new();
             if let Err(e) = gemini.load_credentials_from_path(creds_file_path).await {
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": format!("加载 Gemini 凭证失败: {}", e)
-                        }
-                    })),
+                    format!("加载 Gemini 凭证失败: {}", e),
                 )
-                    .into_response();
+                .into_response();
             }
 
             // 检查并刷新 Token
@@ -1353,15 +1951,11 @@ async fn gemini_generate_content(
                     }
                     Err(refresh_error) => {
                         tracing::error!("[Gemini CLI] Token 刷新失败: {:?}", refresh_error);
-                        return (
+                        return ProxyApiError::from_status(
                             StatusCode::UNAUTHORIZED,
-                            Json(serde_json::json!({
-                                "error": {
-                                    "message": format!("Token 刷新失败: {}", refresh_error)
-                                }
-                            })),
+                            format!("Token 刷新失败: {}", refresh_error),
                         )
-                            .into_response();
+                        .into_response();
                     }
                 }
             }
@@ -1408,15 +2002,11 @@ async fn gemini_generate_content(
 
             if is_stream {
                 // 流式响应 - 暂不支持
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::NOT_IMPLEMENTED,
-                    Json(serde_json::json!({
-                        "error": {
-                            "message": "Gemini CLI 流式响应暂不支持，请使用 generateContent"
-                        }
-                    })),
+                    "Gemini CLI 流式响应暂不支持，请使用 generateContent",
                 )
-                    .into_response();
+                .into_response();
             }
 
             // 非流式响应
@@ -1448,15 +2038,11 @@ async fn gemini_generate_content(
                 }
             }
         }
-        _ => (
+        _ => ProxyApiError::from_status(
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": {
-                    "message": "Gemini 原生协议只支持 Antigravity 或 Gemini CLI OAuth 凭证"
-                }
-            })),
+            "Gemini 原生协议只支持 Antigravity 或 Gemini CLI OAuth 凭证",
         )
-            .into_response(),
+        .into_response(),
     }
 }
 
@@ -1549,19 +2135,78 @@ async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
     Json(response)
 }
 
+/// 根据响应状态记录 selector 路由请求的遥测
+fn record_selector_telemetry(state: &AppState, ctx: &RequestContext, response: &Response) {
+    let status = response.status();
+    if status.is_success() {
+        record_request_telemetry(state, ctx, crate::telemetry::RequestStatus::Success, None);
+    } else {
+        record_request_telemetry(
+            state,
+            ctx,
+            crate::telemetry::RequestStatus::from_http_status(status.as_u16()),
+            Some(format!("HTTP {}", status.as_u16())),
+        );
+    }
+}
+
 /// 带选择器的 Anthropic messages 处理
+#[utoipa::path(
+    post,
+    path = "/{selector}/v1/messages",
+    tag = "anthropic",
+    params(("selector" = String, Path, description = "`selector_endpoints` 中配置的端点名，或凭证名称、凭证 UUID、Provider 类型，指定后不降级到其他凭证")),
+    request_body = AnthropicMessagesRequest,
+    responses(
+        (status = 200, description = "`stream: true` 时以 SSE 返回 `AnthropicStreamEvent`", content(
+            (AnthropicMessagesResponse = "application/json"),
+            (AnthropicStreamEvent = "text/event-stream"),
+        )),
+        (status = 401, description = "API Key 无效", body = openapi::ApiErrorBody),
+        (status = 429, description = "超过端点限流", body = openapi::ApiErrorBody),
+        (status = 503, description = "选择器没有匹配的可用凭证", body = openapi::ApiErrorBody),
+    ),
+    security(("x_api_key" = []), ("bearer" = []))
+)]
 async fn anthropic_messages_with_selector(
     State(state): State<AppState>,
     Path(selector): Path<String>,
     headers: HeaderMap,
     Json(request): Json<AnthropicMessagesRequest>,
 ) -> Response {
+    let endpoint = state
+        .processor
+        .selector_endpoints
+        .read()
+        .await
+        .get(&selector)
+        .cloned();
+
     // 使用 Anthropic 格式的认证验证
-    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
-        state.logs.write().await.add(
-            "warn",
-            &format!("Unauthorized request to /{}/v1/messages", selector),
-        );
+    let client_key = match handlers::verify_selector_api_key(
+        &headers,
+        &state,
+        &selector,
+        endpoint.as_ref(),
+        handlers::ApiErrorFormat::Anthropic,
+    )
+    .await
+    {
+        Ok(client_key) => client_key,
+        Err(e) => {
+            state.logs.write().await.add(
+                "warn",
+                &format!("Unauthorized request to /{}/v1/messages", selector),
+            );
+            return e.into_response();
+        }
+    };
+    if let Err(e) = handlers::verify_client_scope(
+        client_key.as_ref(),
+        Some(&request.model),
+        None,
+        handlers::ApiErrorFormat::Anthropic,
+    ) {
         return e.into_response();
     }
 
@@ -1573,33 +2218,28 @@ async fn anthropic_messages_with_selector(
         ),
     );
 
-    // 尝试解析凭证（不降级，指定什么就用什么）
-    let credential = match &state.db {
-        Some(db) => {
-            // 首先尝试按名称查找
-            if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &selector) {
-                Some(cred)
-            }
-            // 然后尝试按 UUID 查找
-            else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &selector) {
-                Some(cred)
-            }
-            // 最后尝试按 provider 类型选择（不降级）
-            else if let Ok(Some(cred)) =
-                state
-                    .pool_service
-                    .select_credential(db, &selector, Some(&request.model))
-            {
-                Some(cred)
-            } else {
-                None
-            }
-        }
-        None => None,
-    };
+    // 尝试解析凭证（不降级，指定什么就用什么；配置了端点时只在允许的凭证中选择）
+    let credential = state.db.as_ref().and_then(|db| {
+        selector_endpoint::resolve_credential(
+            &state.pool_service,
+            db,
+            &selector,
+            endpoint.as_ref(),
+            &request.model,
+        )
+    });
 
     match credential {
         Some(cred) => {
+            if let Err(e) = handlers::verify_client_scope(
+                client_key.as_ref(),
+                None,
+                Some(&cred.provider_type.to_string()),
+                handlers::ApiErrorFormat::Anthropic,
+            ) {
+                return e.into_response();
+            }
+
             state.logs.write().await.add(
                 "info",
                 &format!(
@@ -1610,9 +2250,31 @@ async fn anthropic_messages_with_selector(
                 ),
             );
 
+            let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+            ctx.set_provider(cred.provider_type);
+            ctx.set_credential_id(cred.uuid.clone());
+            if let Some(bucket) = selector_endpoint::telemetry_bucket(&selector, endpoint.as_ref())
+            {
+                ctx.set_client_key_id(bucket);
+            } else if let Some(key) = &client_key {
+                ctx.set_client_key_id(key.id.clone());
+            }
+
             // 根据凭证类型调用相应的 Provider
             // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_anthropic(&state, &cred, &request, None).await
+            let response = handlers::with_client_anthropic_headers(
+                &headers,
+                handlers::call_provider_anthropic(&state, &cred, &request, None),
+            )
+            .await;
+            record_selector_telemetry(&state, &ctx, &response);
+            token_usage::track_token_usage(
+                &state,
+                &ctx,
+                response,
+                token_usage::estimate_anthropic_input_tokens(&request),
+            )
+            .await
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -1623,32 +2285,71 @@ async fn anthropic_messages_with_selector(
                     selector
                 ),
             );
-            (
+            ProxyApiError::from_status(
                 StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "type": "provider_unavailable",
-                        "message": format!("No available credentials for selector '{}'", selector)
-                    }
-                })),
+                format!("No available credentials for selector '{}'", selector),
             )
-                .into_response()
+            .into_response()
         }
     }
 }
 
 /// 带选择器的 OpenAI chat completions 处理
+#[utoipa::path(
+    post,
+    path = "/{selector}/v1/chat/completions",
+    tag = "openai",
+    params(("selector" = String, Path, description = "`selector_endpoints` 中配置的端点名，或凭证名称、凭证 UUID、Provider 类型，指定后不降级到其他凭证")),
+    request_body = ChatCompletionRequest,
+    responses(
+        (status = 200, description = "`stream: true` 时以 SSE 返回 `ChatCompletionChunk`", content(
+            (ChatCompletionResponse = "application/json"),
+            (ChatCompletionChunk = "text/event-stream"),
+        )),
+        (status = 401, description = "API Key 无效", body = openapi::ApiErrorBody),
+        (status = 429, description = "超过端点限流", body = openapi::ApiErrorBody),
+        (status = 503, description = "选择器没有匹配的可用凭证", body = openapi::ApiErrorBody),
+    ),
+    security(("bearer" = []))
+)]
 async fn chat_completions_with_selector(
     State(state): State<AppState>,
     Path(selector): Path<String>,
     headers: HeaderMap,
     Json(request): Json<ChatCompletionRequest>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
-        state.logs.write().await.add(
-            "warn",
-            &format!("Unauthorized request to /{}/v1/chat/completions", selector),
-        );
+    let endpoint = state
+        .processor
+        .selector_endpoints
+        .read()
+        .await
+        .get(&selector)
+        .cloned();
+
+    let client_key = match handlers::verify_selector_api_key(
+        &headers,
+        &state,
+        &selector,
+        endpoint.as_ref(),
+        handlers::ApiErrorFormat::OpenAI,
+    )
+    .await
+    {
+        Ok(client_key) => client_key,
+        Err(e) => {
+            state.logs.write().await.add(
+                "warn",
+                &format!("Unauthorized request to /{}/v1/chat/completions", selector),
+            );
+            return e.into_response();
+        }
+    };
+    if let Err(e) = handlers::verify_client_scope(
+        client_key.as_ref(),
+        Some(&request.model),
+        None,
+        handlers::ApiErrorFormat::OpenAI,
+    ) {
         return e.into_response();
     }
 
@@ -1660,28 +2361,28 @@ async fn chat_completions_with_selector(
         ),
     );
 
-    // 尝试解析凭证（不降级，指定什么就用什么）
-    let credential = match &state.db {
-        Some(db) => {
-            if let Ok(Some(cred)) = state.pool_service.get_by_name(db, &selector) {
-                Some(cred)
-            } else if let Ok(Some(cred)) = state.pool_service.get_by_uuid(db, &selector) {
-                Some(cred)
-            } else if let Ok(Some(cred)) =
-                state
-                    .pool_service
-                    .select_credential(db, &selector, Some(&request.model))
-            {
-                Some(cred)
-            } else {
-                None
-            }
-        }
-        None => None,
-    };
+    // 尝试解析凭证（不降级，指定什么就用什么；配置了端点时只在允许的凭证中选择）
+    let credential = state.db.as_ref().and_then(|db| {
+        selector_endpoint::resolve_credential(
+            &state.pool_service,
+            db,
+            &selector,
+            endpoint.as_ref(),
+            &request.model,
+        )
+    });
 
     match credential {
         Some(cred) => {
+            if let Err(e) = handlers::verify_client_scope(
+                client_key.as_ref(),
+                None,
+                Some(&cred.provider_type.to_string()),
+                handlers::ApiErrorFormat::OpenAI,
+            ) {
+                return e.into_response();
+            }
+
             state.logs.write().await.add(
                 "info",
                 &format!(
@@ -1692,8 +2393,26 @@ async fn chat_completions_with_selector(
                 ),
             );
 
+            let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+            ctx.set_provider(cred.provider_type);
+            ctx.set_credential_id(cred.uuid.clone());
+            if let Some(bucket) = selector_endpoint::telemetry_bucket(&selector, endpoint.as_ref())
+            {
+                ctx.set_client_key_id(bucket);
+            } else if let Some(key) = &client_key {
+                ctx.set_client_key_id(key.id.clone());
+            }
+
             // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_openai(&state, &cred, &request, None).await
+            let response = handlers::call_provider_openai(&state, &cred, &request, None).await;
+            record_selector_telemetry(&state, &ctx, &response);
+            token_usage::track_token_usage(
+                &state,
+                &ctx,
+                response,
+                token_usage::estimate_openai_input_tokens(&request),
+            )
+            .await
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -1704,17 +2423,12 @@ async fn chat_completions_with_selector(
                     selector
                 ),
             );
-            (
+            ProxyApiError::from_status(
                 StatusCode::SERVICE_UNAVAILABLE,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": format!("No available credentials for selector '{}'", selector),
-                        "type": "provider_unavailable",
-                        "code": "no_credentials"
-                    }
-                })),
+                format!("No available credentials for selector '{}'", selector),
             )
-                .into_response()
+            .with_code("no_credentials")
+            .into_response()
         }
     }
 }
@@ -1739,11 +2453,11 @@ async fn anthropic_messages_internal(
                     .write()
                     .await
                     .add("error", &format!("[AUTH] Token refresh failed: {e}"));
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
+                    format!("Token refresh failed: {e}"),
                 )
-                    .into_response();
+                .into_response();
             }
         }
     }
@@ -1757,33 +2471,25 @@ async fn anthropic_messages_internal(
             if status.is_success() {
                 match resp.bytes().await {
                     Ok(bytes) => {
-                        let body = String::from_utf8_lossy(&bytes).to_string();
-                        let parsed = parse_cw_response(&body);
+                        let parsed = parse_cw_response(&bytes);
                         if request.stream {
                             build_anthropic_stream_response(&request.model, &parsed)
                         } else {
                             build_anthropic_response(&request.model, &parsed)
                         }
                     }
-                    Err(e) => (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response(),
+                    Err(e) => {
+                        ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                            .into_response()
+                    }
                 }
             } else {
                 let body = resp.text().await.unwrap_or_default();
-                (
-                    StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                    Json(serde_json::json!({"error": {"message": format!("Upstream error: {}", body)}})),
-                )
+                ProxyApiError::upstream(status.as_u16(), format!("Upstream error: {}", body))
                     .into_response()
             }
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-        )
+        Err(e) => ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
             .into_response(),
     }
 }
@@ -1799,11 +2505,11 @@ async fn chat_completions_internal(state: &AppState, request: &ChatCompletionReq
             kiro.credentials.access_token.is_none() || kiro.is_token_expiring_soon();
         if needs_refresh {
             if let Err(e) = kiro.refresh_token().await {
-                return (
+                return ProxyApiError::from_status(
                     StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": {"message": format!("Token refresh failed: {e}")}})),
+                    format!("Token refresh failed: {e}"),
                 )
-                    .into_response();
+                .into_response();
             }
         }
     }
@@ -1813,7 +2519,7 @@ async fn chat_completions_internal(state: &AppState, request: &ChatCompletionReq
         Ok(resp) => {
             let status = resp.status();
             if status.is_success() {
-                match resp.text().await {
+                match resp.bytes().await {
                     Ok(body) => {
                         let parsed = parse_cw_response(&body);
                         let has_tool_calls = !parsed.tool_calls.is_empty();
@@ -1861,25 +2567,18 @@ async fn chat_completions_internal(state: &AppState, request: &ChatCompletionReq
                         });
                         Json(response).into_response()
                     }
-                    Err(e) => (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
-                    )
-                        .into_response(),
+                    Err(e) => {
+                        ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                            .into_response()
+                    }
                 }
             } else {
                 let body = resp.text().await.unwrap_or_default();
-                (
-                    StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                    Json(serde_json::json!({"error": {"message": format!("Upstream error: {}", body)}})),
-                )
+                ProxyApiError::upstream(status.as_u16(), format!("Upstream error: {}", body))
                     .into_response()
             }
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"error": {"message": e.to_string()}})),
-        )
+        Err(e) => ProxyApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
             .into_response(),
     }
 }
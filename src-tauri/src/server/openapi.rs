@@ -0,0 +1,188 @@
+//! OpenAPI 文档
+//!
+//! 由请求 / 响应模型上的 `ToSchema` 和处理器上的 `#[utoipa::path]` 生成 OpenAPI 3.1 文档，
+//! 通过 `GET /openapi.json` 提供，无需认证。
+
+use axum::Json;
+use serde::Deserialize;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::models::anthropic::*;
+use crate::models::openai::*;
+use crate::server::handlers::management::*;
+use crate::services::client_api_key_service::{
+    CreateClientApiKeyRequest, UpdateClientApiKeyRequest,
+};
+use crate::websocket::{
+    WsApiRequest, WsApiResponse, WsAuth, WsChannel, WsEndpoint, WsError, WsErrorCode, WsResume,
+    WsStreamChunk, WsStreamEnd, WsSubscription,
+};
+
+/// 代理端点的错误响应体，结构见 [`super::error::ProxyApiError`]
+#[allow(dead_code)] // 仅用于生成文档
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ApiErrorBody {
+    /// Anthropic 格式的端点固定为 `error`
+    #[serde(rename = "type")]
+    pub body_type: Option<String>,
+    pub error: ApiErrorDetail,
+}
+
+/// 错误详情
+#[allow(dead_code)] // 仅用于生成文档
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ApiErrorDetail {
+    pub message: String,
+    /// 错误分类，如 `invalid_request_error`、`rate_limit_error`
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub code: Option<String>,
+    /// 审计中间件分配的请求 ID
+    pub request_id: Option<String>,
+    /// 上游返回的 HTTP 状态码
+    pub upstream_status: Option<u16>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "ProxyCast API",
+        description = "OpenAI / Anthropic 兼容的代理 API、WebSocket 协议和管理 API"
+    ),
+    paths(
+        super::handlers::api::chat_completions,
+        super::handlers::api::anthropic_messages,
+        super::chat_completions_with_selector,
+        super::anthropic_messages_with_selector,
+        super::handlers::websocket::ws_upgrade_handler,
+        super::handlers::websocket::ws_selector_upgrade_handler,
+        super::handlers::terminal_share::terminal_share_ws_handler,
+        management_status,
+        management_list_credentials,
+        management_add_credential,
+        management_get_config,
+        management_update_config,
+        management_list_api_keys,
+        management_create_api_key,
+        management_update_api_key,
+        management_delete_api_key,
+        management_list_audit_logs,
+        management_get_audit_log,
+        management_clear_audit_logs,
+        management_search_request_logs,
+        management_get_request_log,
+        management_clear_request_logs,
+    ),
+    components(schemas(
+        ApiErrorBody,
+        ApiErrorDetail,
+        ChatCompletionChunk,
+        AnthropicStreamEvent,
+        WsApiRequest,
+        WsApiResponse,
+        WsEndpoint,
+        WsStreamChunk,
+        WsStreamEnd,
+        WsError,
+        WsErrorCode,
+        WsChannel,
+        WsSubscription,
+        WsAuth,
+        WsResume,
+        CreateClientApiKeyRequest,
+        UpdateClientApiKeyRequest,
+    )),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "openai", description = "OpenAI 兼容端点"),
+        (name = "anthropic", description = "Anthropic 兼容端点"),
+        (name = "websocket", description = "WebSocket 协议"),
+        (name = "management", description = "管理 API（需配置 remote_management.secret_key）"),
+    )
+)]
+pub struct ApiDoc;
+
+/// 注册认证方式
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "x_api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("x-api-key"))),
+        );
+        components.add_security_scheme(
+            "management_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("x-management-key"))),
+        );
+    }
+}
+
+/// 生成 OpenAPI 文档
+pub fn spec() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+/// GET /openapi.json - 获取 OpenAPI 文档
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(spec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_covers_proxy_and_management_api() {
+        let doc = serde_json::to_value(spec()).unwrap();
+        assert!(doc["openapi"].as_str().unwrap().starts_with("3.1"));
+
+        let paths = doc["paths"].as_object().unwrap();
+        for path in [
+            "/v1/chat/completions",
+            "/v1/messages",
+            "/{selector}/v1/chat/completions",
+            "/{selector}/v1/messages",
+            "/v1/ws",
+            "/{selector}/ws",
+            "/v1/terminal/share/{token}/ws",
+            "/v0/management/status",
+            "/v0/management/api-keys/{id}",
+            "/v0/management/request-logs",
+        ] {
+            assert!(paths.contains_key(path), "缺少路径 {}", path);
+        }
+
+        let schemas = &doc["components"]["schemas"];
+        for schema in [
+            "ChatCompletionRequest",
+            "ChatCompletionResponse",
+            "AnthropicMessagesRequest",
+            "AnthropicMessagesResponse",
+            "WsApiRequest",
+            "ManagementStatusResponse",
+            "ApiErrorBody",
+        ] {
+            assert!(schemas.get(schema).is_some(), "缺少模型 {}", schema);
+        }
+        // 未建模的透传字段不出现在文档中
+        assert!(schemas["ChatCompletionRequest"]["properties"]
+            .get("extra")
+            .is_none());
+    }
+
+    #[test]
+    fn test_error_body_matches_proxy_error() {
+        let body = crate::server::error::ProxyApiError::unavailable("no credentials")
+            .with_code("no_credentials")
+            .to_json();
+        let parsed: ApiErrorBody = serde_json::from_value(body).unwrap();
+        assert_eq!(parsed.error.code.as_deref(), Some("no_credentials"));
+    }
+}
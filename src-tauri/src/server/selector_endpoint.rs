@@ -0,0 +1,156 @@
+//! 选择器端点
+//!
+//! `/{selector}/v1/*` 路由可在 `selector_endpoints` 中配置专用 API Key、可用凭证、限流和遥测分组，
+//! 使每个团队 / 工具拥有一个相互隔离的虚拟端点。未配置的选择器保持原有行为：
+//! 按凭证名称、凭证 UUID、Provider 类型的顺序解析，不降级到其他凭证。
+
+use crate::config::SelectorEndpointConfig;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::ProviderCredential;
+use crate::services::provider_pool_service::ProviderPoolService;
+
+/// 端点实际解析的选择器
+pub fn target<'a>(selector: &'a str, endpoint: Option<&'a SelectorEndpointConfig>) -> &'a str {
+    endpoint
+        .and_then(|e| e.target.as_deref())
+        .filter(|t| !t.is_empty())
+        .unwrap_or(selector)
+}
+
+/// 端点是否允许使用凭证，未配置端点或凭证列表为空时不限制
+pub fn allows_credential(
+    endpoint: Option<&SelectorEndpointConfig>,
+    uuid: &str,
+    name: Option<&str>,
+) -> bool {
+    let Some(endpoint) = endpoint.filter(|e| !e.credentials.is_empty()) else {
+        return true;
+    };
+    endpoint
+        .credentials
+        .iter()
+        .any(|c| c == uuid || Some(c.as_str()) == name)
+}
+
+/// 端点请求在遥测中的客户端分组，未配置端点时返回 `None`
+pub fn telemetry_bucket(
+    selector: &str,
+    endpoint: Option<&SelectorEndpointConfig>,
+) -> Option<String> {
+    let endpoint = endpoint?;
+    Some(
+        endpoint
+            .telemetry_bucket
+            .clone()
+            .filter(|b| !b.is_empty())
+            .unwrap_or_else(|| format!("endpoint:{}", selector)),
+    )
+}
+
+/// 解析选择器对应的凭证
+///
+/// 按名称或 UUID 命中的凭证不在端点允许列表中时返回 `None`；
+/// 按 Provider 类型选择时跳过允许列表之外的凭证。
+pub fn resolve_credential(
+    pool_service: &ProviderPoolService,
+    db: &DbConnection,
+    selector: &str,
+    endpoint: Option<&SelectorEndpointConfig>,
+    model: &str,
+) -> Option<ProviderCredential> {
+    let target = target(selector, endpoint);
+    let direct = match pool_service.get_by_name(db, target) {
+        Ok(Some(cred)) => Some(cred),
+        _ => pool_service.get_by_uuid(db, target).ok().flatten(),
+    };
+    if let Some(cred) = direct {
+        if allows_credential(endpoint, &cred.uuid, cred.name.as_deref()) {
+            return Some(cred);
+        }
+        tracing::warn!(
+            "[SELECTOR] 端点 '{}' 不允许使用凭证 {}",
+            selector,
+            &cred.uuid[..8]
+        );
+        return None;
+    }
+
+    let excluded: Vec<String> = match endpoint.filter(|e| !e.credentials.is_empty()) {
+        Some(_) => pool_service
+            .get_all_credential_health(db)
+            .ok()?
+            .into_iter()
+            .filter(|c| !allows_credential(endpoint, &c.uuid, c.name.as_deref()))
+            .map(|c| c.uuid)
+            .collect(),
+        None => Vec::new(),
+    };
+    pool_service
+        .select_credential_excluding(db, target, Some(model), None, &excluded)
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint() -> SelectorEndpointConfig {
+        SelectorEndpointConfig {
+            target: Some("claude".to_string()),
+            credentials: vec!["claude-team-a".to_string(), "uuid-b".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_target_defaults_to_selector() {
+        assert_eq!(target("team-a", Some(&endpoint())), "claude");
+        assert_eq!(target("team-a", None), "team-a");
+        let blank = SelectorEndpointConfig {
+            target: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(target("team-a", Some(&blank)), "team-a");
+    }
+
+    #[test]
+    fn test_allows_credential_by_name_or_uuid() {
+        let endpoint = endpoint();
+        assert!(allows_credential(
+            Some(&endpoint),
+            "uuid-a",
+            Some("claude-team-a")
+        ));
+        assert!(allows_credential(Some(&endpoint), "uuid-b", None));
+        assert!(!allows_credential(
+            Some(&endpoint),
+            "uuid-c",
+            Some("claude-shared")
+        ));
+        // 未配置端点或凭证列表为空时不限制
+        assert!(allows_credential(None, "uuid-c", None));
+        assert!(allows_credential(
+            Some(&SelectorEndpointConfig::default()),
+            "uuid-c",
+            None
+        ));
+    }
+
+    #[test]
+    fn test_telemetry_bucket() {
+        assert_eq!(telemetry_bucket("team-a", None), None);
+        assert_eq!(
+            telemetry_bucket("team-a", Some(&endpoint())).as_deref(),
+            Some("endpoint:team-a")
+        );
+        let named = SelectorEndpointConfig {
+            telemetry_bucket: Some("team-a-ci".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            telemetry_bucket("team-a", Some(&named)).as_deref(),
+            Some("team-a-ci")
+        );
+    }
+}
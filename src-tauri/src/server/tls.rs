@@ -0,0 +1,200 @@
+//! HTTPS 支持
+//!
+//! - 从 `server.tls.cert_path` / `server.tls.key_path` 加载 PEM 证书
+//! - 未配置证书时为本地监听地址自动生成自签名证书，保存在 `~/.proxycast/tls`
+
+use axum_server::tls_rustls::RustlsConfig;
+use openssl::asn1::Asn1Time;
+use openssl::bn::{BigNum, MsbOption};
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use openssl::x509::extension::{BasicConstraints, ExtendedKeyUsage, SubjectAlternativeName};
+use openssl::x509::{X509NameBuilder, X509};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::TlsConfig;
+
+/// 自签名证书有效期（天）
+const SELF_SIGNED_VALID_DAYS: u32 = 825;
+/// 剩余有效期不足该天数时重新生成
+const SELF_SIGNED_RENEW_DAYS: u32 = 30;
+
+const SELF_SIGNED_CERT_FILE: &str = "self-signed.crt";
+const SELF_SIGNED_KEY_FILE: &str = "self-signed.key";
+
+/// 自签名证书存放目录
+pub fn self_signed_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".proxycast")
+        .join("tls")
+}
+
+/// 校验 TLS 配置
+///
+/// 证书和私钥路径需同时配置；均未配置时使用自签名证书，仅允许本地监听地址
+pub fn validate_tls_config(tls: &TlsConfig, host: &str) -> Result<(), String> {
+    if !tls.enable {
+        return Ok(());
+    }
+    match (&tls.cert_path, &tls.key_path) {
+        (Some(cert), Some(key)) => {
+            for path in [cert, key] {
+                if !Path::new(path).is_file() {
+                    return Err(format!("TLS 证书文件不存在: {}", path));
+                }
+            }
+            Ok(())
+        }
+        (None, None) if crate::app::is_loopback_host(host) => Ok(()),
+        (None, None) => Err(format!(
+            "监听地址 {} 不是本地地址，自签名证书仅用于本地访问，请配置 cert_path 和 key_path",
+            host
+        )),
+        _ => Err("TLS 证书路径和私钥路径必须同时配置".to_string()),
+    }
+}
+
+/// 加载 rustls 配置，未启用 TLS 时返回 `None`
+pub async fn load_rustls_config(
+    tls: &TlsConfig,
+    host: &str,
+) -> Result<Option<RustlsConfig>, String> {
+    if !tls.enable {
+        return Ok(None);
+    }
+    validate_tls_config(tls, host)?;
+
+    let (cert_path, key_path) = match (&tls.cert_path, &tls.key_path) {
+        (Some(cert), Some(key)) => (PathBuf::from(cert), PathBuf::from(key)),
+        _ => {
+            let paths = ensure_self_signed_cert(&self_signed_dir())?;
+            tracing::info!("[TLS] 使用自签名证书: {:?}", paths.0);
+            paths
+        }
+    };
+
+    RustlsConfig::from_pem_file(&cert_path, &key_path)
+        .await
+        .map(Some)
+        .map_err(|e| format!("加载 TLS 证书失败 ({:?}): {}", cert_path, e))
+}
+
+/// 确保目录下存在有效的自签名证书，返回（证书路径，私钥路径）
+///
+/// 已有证书且剩余有效期充足时直接复用，避免客户端反复信任新证书
+pub fn ensure_self_signed_cert(dir: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let cert_path = dir.join(SELF_SIGNED_CERT_FILE);
+    let key_path = dir.join(SELF_SIGNED_KEY_FILE);
+
+    if key_path.is_file() && is_cert_reusable(&cert_path) {
+        return Ok((cert_path, key_path));
+    }
+
+    let (cert_pem, key_pem) =
+        generate_self_signed_cert().map_err(|e| format!("生成自签名证书失败: {}", e))?;
+    fs::create_dir_all(dir).map_err(|e| format!("创建证书目录失败: {}", e))?;
+    fs::write(&cert_path, cert_pem).map_err(|e| format!("写入证书失败: {}", e))?;
+    fs::write(&key_path, key_pem).map_err(|e| format!("写入私钥失败: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600));
+    }
+
+    tracing::info!("[TLS] 已生成自签名证书: {:?}", cert_path);
+    Ok((cert_path, key_path))
+}
+
+fn is_cert_reusable(cert_path: &Path) -> bool {
+    let Ok(pem) = fs::read(cert_path) else {
+        return false;
+    };
+    let Ok(cert) = X509::from_pem(&pem) else {
+        return false;
+    };
+    let Ok(renew_before) = Asn1Time::days_from_now(SELF_SIGNED_RENEW_DAYS) else {
+        return false;
+    };
+    matches!(
+        cert.not_after().compare(&renew_before),
+        Ok(Ordering::Greater)
+    )
+}
+
+/// 生成 localhost 自签名证书（PEM 格式的证书和 PKCS#8 私钥）
+fn generate_self_signed_cert() -> Result<(Vec<u8>, Vec<u8>), ErrorStack> {
+    let key = PKey::from_rsa(Rsa::generate(2048)?)?;
+
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_text("CN", "ProxyCast Local")?;
+    let name = name.build();
+
+    let mut serial = BigNum::new()?;
+    serial.rand(128, MsbOption::MAYBE_ZERO, false)?;
+
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(&serial.to_asn1_integer()?)?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(&name)?;
+    builder.set_pubkey(&key)?;
+    builder.set_not_before(&Asn1Time::days_from_now(0)?)?;
+    builder.set_not_after(&Asn1Time::days_from_now(SELF_SIGNED_VALID_DAYS)?)?;
+    builder.append_extension(BasicConstraints::new().critical().build()?)?;
+    builder.append_extension(ExtendedKeyUsage::new().server_auth().build()?)?;
+    let san = SubjectAlternativeName::new()
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .ip("::1")
+        .build(&builder.x509v3_context(None, None))?;
+    builder.append_extension(san)?;
+    builder.sign(&key, MessageDigest::sha256())?;
+
+    Ok((builder.build().to_pem()?, key.private_key_to_pem_pkcs8()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tls(cert_path: Option<&str>, key_path: Option<&str>) -> TlsConfig {
+        TlsConfig {
+            enable: true,
+            cert_path: cert_path.map(str::to_string),
+            key_path: key_path.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_validate_tls_config() {
+        assert!(validate_tls_config(&TlsConfig::default(), "0.0.0.0").is_ok());
+        assert!(validate_tls_config(&tls(None, None), "127.0.0.1").is_ok());
+        assert!(validate_tls_config(&tls(None, None), "localhost").is_ok());
+        assert!(validate_tls_config(&tls(None, None), "0.0.0.0").is_err());
+        assert!(validate_tls_config(&tls(Some("/tmp/cert.pem"), None), "127.0.0.1").is_err());
+        assert!(validate_tls_config(
+            &tls(Some("/nonexistent/cert.pem"), Some("/nonexistent/key.pem")),
+            "0.0.0.0"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_self_signed_cert_is_generated_and_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert_path, key_path) = ensure_self_signed_cert(dir.path()).unwrap();
+        let cert = X509::from_pem(&fs::read(&cert_path).unwrap()).unwrap();
+        let san = cert.subject_alt_names().unwrap();
+        assert!(san.iter().any(|name| name.dnsname() == Some("localhost")));
+        assert!(PKey::private_key_from_pem(&fs::read(&key_path).unwrap()).is_ok());
+
+        let first = fs::read(&cert_path).unwrap();
+        ensure_self_signed_cert(dir.path()).unwrap();
+        assert_eq!(fs::read(&cert_path).unwrap(), first);
+    }
+}
@@ -0,0 +1,272 @@
+//! 请求输入 Token 计数
+//!
+//! 为 `/v1/messages/count_tokens` 和长上下文降级提供基于 BPE 分词的输入 Token 计数：
+//! - OpenAI 模型使用对应的 tiktoken 编码（cl100k_base / o200k_base）
+//! - Claude 模型没有公开分词器，使用 cl100k_base 计数后按经验系数放大，
+//!   宁可略微高估，让客户端提前压缩上下文
+//!
+//! 文本之外的内容块按 Anthropic 文档中的规则估算（图片按上限计、工具定义附加系统提示开销）。
+
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::{ChatCompletionRequest, ContentPart, MessageContent};
+use crate::telemetry::TokenEstimator;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+/// Claude 分词结果相对 cl100k_base 的经验系数
+const CLAUDE_TOKEN_RATIO: f64 = 1.1;
+/// 每条消息的格式开销（角色标记等）
+const TOKENS_PER_MESSAGE: u32 = 4;
+/// 单张图片的 Token 上限（约 1.15 MP 图片，Anthropic 文档给出的上限）
+const TOKENS_PER_IMAGE: u32 = 1600;
+/// 启用工具时的工具调用系统提示开销
+const TOOL_USE_SYSTEM_PROMPT_TOKENS: u32 = 346;
+
+/// 全局分词器（初始化失败时回退到按字符数估算）
+static ESTIMATOR: Lazy<Option<TokenEstimator>> = Lazy::new(|| match TokenEstimator::new() {
+    Ok(estimator) => Some(estimator),
+    Err(e) => {
+        tracing::warn!("[TOKEN_COUNT] 分词器初始化失败，回退到字符估算: {}", e);
+        None
+    }
+});
+
+/// 统计文本的 Token 数
+pub fn count_text_tokens(text: &str, model: &str) -> u32 {
+    if text.is_empty() {
+        return 0;
+    }
+    let tokens = match ESTIMATOR.as_ref() {
+        Some(estimator) => estimator.estimate(text, Some(model)),
+        None => text.len().div_ceil(4) as u32,
+    };
+    if is_claude_model(model) {
+        (tokens as f64 * CLAUDE_TOKEN_RATIO).ceil() as u32
+    } else {
+        tokens
+    }
+}
+
+fn is_claude_model(model: &str) -> bool {
+    model.to_lowercase().contains("claude")
+}
+
+/// 统计 Anthropic Messages 请求的输入 Token 数
+pub fn count_anthropic_input_tokens(request: &AnthropicMessagesRequest) -> u32 {
+    let model = request.model.as_str();
+    let mut total = 0;
+
+    if let Some(system) = &request.system {
+        total += count_content_tokens(system, model);
+    }
+
+    for message in &request.messages {
+        total += TOKENS_PER_MESSAGE;
+        total += count_content_tokens(&message.content, model);
+    }
+
+    if let Some(tools) = request.tools.as_ref().filter(|t| !t.is_empty()) {
+        total += TOOL_USE_SYSTEM_PROMPT_TOKENS;
+        for tool in tools {
+            total += count_text_tokens(&tool.name, model);
+            if let Some(description) = &tool.description {
+                total += count_text_tokens(description, model);
+            }
+            if let Some(schema) = &tool.input_schema {
+                total += count_text_tokens(&schema.to_string(), model);
+            }
+        }
+    }
+
+    total
+}
+
+/// 统计 OpenAI Chat Completions 请求的输入 Token 数
+pub fn count_openai_input_tokens(request: &ChatCompletionRequest) -> u32 {
+    let model = request.model.as_str();
+    let mut total = 0;
+
+    for message in &request.messages {
+        total += TOKENS_PER_MESSAGE;
+        total += match &message.content {
+            Some(MessageContent::Text(text)) => count_text_tokens(text, model),
+            Some(MessageContent::Parts(parts)) => parts
+                .iter()
+                .map(|part| match part {
+                    ContentPart::Text { text } => count_text_tokens(text, model),
+                    ContentPart::ImageUrl { .. } => TOKENS_PER_IMAGE,
+                })
+                .sum(),
+            None => 0,
+        };
+        for call in message.tool_calls.iter().flatten() {
+            total += count_text_tokens(&call.function.name, model);
+            total += count_text_tokens(&call.function.arguments, model);
+        }
+    }
+
+    if let Some(tools) = request.tools.as_ref().filter(|t| !t.is_empty()) {
+        total += TOOL_USE_SYSTEM_PROMPT_TOKENS;
+        for tool in tools {
+            let definition = serde_json::to_string(tool).unwrap_or_default();
+            total += count_text_tokens(&definition, model);
+        }
+    }
+
+    total
+}
+
+/// 统计消息内容（字符串或内容块数组）的 Token 数
+fn count_content_tokens(content: &Value, model: &str) -> u32 {
+    match content {
+        Value::String(text) => count_text_tokens(text, model),
+        Value::Array(blocks) => blocks.iter().map(|b| count_block_tokens(b, model)).sum(),
+        Value::Null => 0,
+        other => count_text_tokens(&other.to_string(), model),
+    }
+}
+
+/// 统计单个内容块的 Token 数
+fn count_block_tokens(block: &Value, model: &str) -> u32 {
+    let text_field = |key: &str| block.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+
+    match block.get("type").and_then(|t| t.as_str()) {
+        Some("text") => count_text_tokens(text_field("text"), model),
+        Some("thinking") => count_text_tokens(text_field("thinking"), model),
+        Some("redacted_thinking") => count_text_tokens(text_field("data"), model),
+        Some("tool_use") | Some("server_tool_use") => {
+            let input = block
+                .get("input")
+                .map(|i| i.to_string())
+                .unwrap_or_default();
+            count_text_tokens(text_field("name"), model) + count_text_tokens(&input, model)
+        }
+        Some("tool_result") | Some("web_search_tool_result") => block
+            .get("content")
+            .map_or(0, |c| count_content_tokens(c, model)),
+        Some("image") => TOKENS_PER_IMAGE,
+        Some("document") => {
+            let source = block.get("source");
+            match source.and_then(|s| s.get("type")).and_then(|t| t.as_str()) {
+                Some("text") => count_text_tokens(
+                    source
+                        .and_then(|s| s.get("data"))
+                        .and_then(|d| d.as_str())
+                        .unwrap_or_default(),
+                    model,
+                ),
+                Some("content") => source
+                    .and_then(|s| s.get("content"))
+                    .map_or(0, |c| count_content_tokens(c, model)),
+                // PDF 等二进制文档按单页图片估算
+                _ => TOKENS_PER_IMAGE,
+            }
+        }
+        _ => count_text_tokens(&block.to_string(), model),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::anthropic::{AnthropicMessage, AnthropicTool};
+    use serde_json::json;
+
+    fn request(model: &str, messages: Vec<AnthropicMessage>) -> AnthropicMessagesRequest {
+        AnthropicMessagesRequest {
+            model: model.to_string(),
+            messages,
+            max_tokens: None,
+            system: None,
+            temperature: None,
+            stream: false,
+            tools: None,
+            tool_choice: None,
+            extra: Default::default(),
+        }
+    }
+
+    fn message(role: &str, content: Value) -> AnthropicMessage {
+        AnthropicMessage {
+            role: role.to_string(),
+            content,
+        }
+    }
+
+    #[test]
+    fn test_count_text_tokens_uses_bpe() {
+        // cl100k_base: "hello world" = 2 tokens
+        assert_eq!(count_text_tokens("hello world", "gpt-4"), 2);
+        assert_eq!(count_text_tokens("", "gpt-4"), 0);
+        // Claude 模型按系数放大
+        assert_eq!(count_text_tokens("hello world", "claude-sonnet-4-5"), 3);
+    }
+
+    #[test]
+    fn test_count_anthropic_request() {
+        let text = "The quick brown fox jumps over the lazy dog.";
+        let mut req = request(
+            "gpt-4",
+            vec![
+                message("user", json!(text)),
+                message(
+                    "assistant",
+                    json!([{"type": "text", "text": text}, {"type": "image", "source": {}}]),
+                ),
+            ],
+        );
+        let text_tokens = count_text_tokens(text, "gpt-4");
+        assert_eq!(
+            count_anthropic_input_tokens(&req),
+            2 * TOKENS_PER_MESSAGE + 2 * text_tokens + TOKENS_PER_IMAGE
+        );
+
+        let base = count_anthropic_input_tokens(&req);
+        req.system = Some(json!([{"type": "text", "text": text}]));
+        req.tools = Some(vec![AnthropicTool {
+            name: "read_file".to_string(),
+            description: Some("Read a file".to_string()),
+            input_schema: Some(json!({"type": "object"})),
+            extra: Default::default(),
+        }]);
+        assert!(
+            count_anthropic_input_tokens(&req) > base + text_tokens + TOOL_USE_SYSTEM_PROMPT_TOKENS
+        );
+    }
+
+    #[test]
+    fn test_count_tool_blocks() {
+        let req = request(
+            "gpt-4",
+            vec![message(
+                "user",
+                json!([
+                    {"type": "tool_use", "id": "t1", "name": "search", "input": {"q": "rust"}},
+                    {"type": "tool_result", "tool_use_id": "t1", "content": "found"}
+                ]),
+            )],
+        );
+        assert!(count_anthropic_input_tokens(&req) > TOKENS_PER_MESSAGE + 2);
+    }
+
+    #[test]
+    fn test_count_openai_request() {
+        let text = "The quick brown fox jumps over the lazy dog.";
+        let req: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "gpt-4",
+            "messages": [
+                {"role": "system", "content": text},
+                {"role": "user", "content": [
+                    {"type": "text", "text": text},
+                    {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}
+                ]}
+            ]
+        }))
+        .unwrap();
+        let text_tokens = count_text_tokens(text, "gpt-4");
+        assert_eq!(
+            count_openai_input_tokens(&req),
+            2 * TOKENS_PER_MESSAGE + 2 * text_tokens + TOKENS_PER_IMAGE
+        );
+    }
+}
@@ -0,0 +1,490 @@
+//! 上游 Token 用量统计
+//!
+//! 从 OpenAI / Anthropic 格式的响应（流式与非流式）中解析上游返回的实际 usage，
+//! 解析不到时回退到基于字符数的估算（约 4 字符 = 1 token），并标记 Token 来源。
+
+use crate::models::anthropic::AnthropicMessagesRequest;
+use crate::models::openai::ChatCompletionRequest;
+use crate::processor::RequestContext;
+use crate::server::error::ProxyApiError;
+use crate::server::{record_token_usage, AppState};
+use crate::server_utils::message_content_len;
+use crate::telemetry::{CacheTokens, TokenSource};
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+
+/// 从单个 JSON 负载中解析出的 Token 数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageCounts {
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub cache_creation_input_tokens: Option<u32>,
+    pub cache_read_input_tokens: Option<u32>,
+}
+
+impl UsageCounts {
+    /// 合并后出现的值覆盖先前的值（流式响应中 usage 会逐步更新）
+    fn merge(&mut self, other: UsageCounts) {
+        if other.input_tokens.is_some() {
+            self.input_tokens = other.input_tokens;
+        }
+        if other.output_tokens.is_some() {
+            self.output_tokens = other.output_tokens;
+        }
+        if other.cache_creation_input_tokens.is_some() {
+            self.cache_creation_input_tokens = other.cache_creation_input_tokens;
+        }
+        if other.cache_read_input_tokens.is_some() {
+            self.cache_read_input_tokens = other.cache_read_input_tokens;
+        }
+    }
+}
+
+/// 从响应 JSON 中提取 usage
+///
+/// 支持的格式：
+/// - OpenAI: `{"usage": {"prompt_tokens": 10, "completion_tokens": 5}}`
+/// - Anthropic: `{"usage": {"input_tokens": 10, "output_tokens": 5}}`
+/// - Anthropic 流式 message_start: `{"message": {"usage": {...}}}`
+///
+/// Anthropic 的 `input_tokens` 不包含缓存命中/写入部分，这里将其累加为总输入；
+/// OpenAI 的 `prompt_tokens_details.cached_tokens` 已包含在 `prompt_tokens` 中，只记为缓存读取。
+pub fn extract_usage(value: &serde_json::Value) -> UsageCounts {
+    let usage = value.get("usage").filter(|u| u.is_object()).or_else(|| {
+        value
+            .get("message")
+            .and_then(|m| m.get("usage"))
+            .filter(|u| u.is_object())
+    });
+    let Some(usage) = usage else {
+        return UsageCounts::default();
+    };
+
+    let read = |key: &str| usage.get(key).and_then(|v| v.as_u64()).map(|v| v as u32);
+
+    let cache_creation_input_tokens = read("cache_creation_input_tokens");
+    let cache_read_input_tokens = read("cache_read_input_tokens").or_else(|| {
+        usage
+            .get("prompt_tokens_details")
+            .and_then(|d| d.get("cached_tokens"))
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32)
+    });
+
+    let input_tokens = read("prompt_tokens").or_else(|| {
+        read("input_tokens").map(|input| {
+            input
+                + cache_creation_input_tokens.unwrap_or(0)
+                + read("cache_read_input_tokens").unwrap_or(0)
+        })
+    });
+    let output_tokens = read("completion_tokens").or_else(|| read("output_tokens"));
+
+    UsageCounts {
+        input_tokens,
+        output_tokens,
+        cache_creation_input_tokens,
+        cache_read_input_tokens,
+    }
+}
+
+/// 估算 OpenAI 请求的输入 Token 数（约 4 字符 = 1 token）
+pub fn estimate_openai_input_tokens(request: &ChatCompletionRequest) -> u32 {
+    request
+        .messages
+        .iter()
+        .map(|m| m.content.as_ref().map_or(0, message_content_len) / 4)
+        .sum::<usize>() as u32
+}
+
+/// 估算 Anthropic 请求的输入 Token 数（约 4 字符 = 1 token）
+pub fn estimate_anthropic_input_tokens(request: &AnthropicMessagesRequest) -> u32 {
+    request
+        .messages
+        .iter()
+        .map(|m| {
+            let content_len = match &m.content {
+                serde_json::Value::String(s) => s.len(),
+                serde_json::Value::Array(arr) => arr
+                    .iter()
+                    .filter_map(|v| v.get("text").and_then(|t| t.as_str()))
+                    .map(|s| s.len())
+                    .sum(),
+                _ => 0,
+            };
+            content_len / 4
+        })
+        .sum::<usize>() as u32
+}
+
+/// 统计响应 JSON 中输出文本的字符数（用于估算）
+fn output_text_len(value: &serde_json::Value) -> usize {
+    let mut len = 0;
+
+    // OpenAI: choices[].message / choices[].delta
+    if let Some(choices) = value.get("choices").and_then(|c| c.as_array()) {
+        for choice in choices {
+            let Some(message) = choice.get("message").or_else(|| choice.get("delta")) else {
+                continue;
+            };
+            len += message
+                .get("content")
+                .and_then(|c| c.as_str())
+                .map_or(0, str::len);
+            if let Some(tool_calls) = message.get("tool_calls").and_then(|t| t.as_array()) {
+                len += tool_calls
+                    .iter()
+                    .filter_map(|tc| tc["function"]["arguments"].as_str())
+                    .map(str::len)
+                    .sum::<usize>();
+            }
+        }
+    }
+
+    // Anthropic 非流式: content[].text / content[].input
+    if let Some(blocks) = value.get("content").and_then(|c| c.as_array()) {
+        for block in blocks {
+            if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                len += text.len();
+            } else if let Some(input) = block.get("input") {
+                len += input.to_string().len();
+            }
+        }
+    }
+
+    // Anthropic 流式: content_block_delta
+    if let Some(delta) = value.get("delta") {
+        len += delta
+            .get("text")
+            .or_else(|| delta.get("partial_json"))
+            .and_then(|t| t.as_str())
+            .map_or(0, str::len);
+    }
+
+    len
+}
+
+/// Token 用量追踪器
+///
+/// 逐块接收响应数据，记录上游返回的 usage 以及输出文本长度。
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    counts: UsageCounts,
+    output_chars: usize,
+    line_buffer: Vec<u8>,
+    /// 响应构建方声明的 Token 来源（如 Kiro 响应中的 usage 本身就是估算值）
+    source_hint: Option<TokenSource>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置 Token 来源提示
+    pub fn with_source_hint(mut self, hint: Option<TokenSource>) -> Self {
+        self.source_hint = hint;
+        self
+    }
+
+    /// 处理一个 JSON 负载
+    pub fn observe_json(&mut self, value: &serde_json::Value) {
+        self.counts.merge(extract_usage(value));
+        self.output_chars += output_text_len(value);
+    }
+
+    /// 处理一段 SSE 数据（可能包含不完整的行）
+    pub fn observe_sse_chunk(&mut self, chunk: &[u8]) {
+        self.line_buffer.extend_from_slice(chunk);
+
+        while let Some(newline) = self.line_buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.line_buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data.is_empty() || data == "[DONE]" {
+                continue;
+            }
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(data) {
+                self.observe_json(&value);
+            }
+        }
+    }
+
+    /// 上游返回的 Prompt 缓存 Token 数（未返回时为 0）
+    pub fn cache_tokens(&self) -> CacheTokens {
+        CacheTokens {
+            cache_creation_input_tokens: self.counts.cache_creation_input_tokens.unwrap_or(0),
+            cache_read_input_tokens: self.counts.cache_read_input_tokens.unwrap_or(0),
+        }
+    }
+
+    /// 计算最终的 Token 用量
+    ///
+    /// 仅当上游同时返回输入与输出 Token 数时标记为 `Actual`，
+    /// 缺失的部分使用 `estimated_input_tokens` 和输出文本长度估算。
+    pub fn finish(&self, estimated_input_tokens: u32) -> (u32, u32, TokenSource) {
+        let estimated_output_tokens = (self.output_chars / 4) as u32;
+
+        // 部分转换器在流式响应中固定输出 output_tokens=0，此时视为未返回
+        let output_tokens = self
+            .counts
+            .output_tokens
+            .filter(|&tokens| tokens > 0 || self.output_chars == 0);
+
+        match (self.counts.input_tokens, output_tokens, self.source_hint) {
+            (Some(input), Some(output), None | Some(TokenSource::Actual)) => {
+                (input, output, TokenSource::Actual)
+            }
+            (input, output, _) => (
+                input
+                    .filter(|&tokens| tokens > 0)
+                    .unwrap_or(estimated_input_tokens),
+                output.unwrap_or(estimated_output_tokens),
+                TokenSource::Estimated,
+            ),
+        }
+    }
+}
+
+/// 追踪 Provider 响应的 Token 用量
+///
+/// - 流式响应（SSE）：边转发边解析，流结束（或客户端断开）时记录
+/// - 非流式响应：读取完整响应体解析后原样返回
+///
+/// 失败响应不记录 Token 用量。
+pub async fn track_token_usage(
+    state: &AppState,
+    ctx: &RequestContext,
+    response: Response,
+    estimated_input_tokens: u32,
+) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let tracker =
+        UsageTracker::new().with_source_hint(response.extensions().get::<TokenSource>().copied());
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+
+    let (parts, body) = response.into_parts();
+
+    if is_sse {
+        let mut recorder = UsageRecorder {
+            state: state.clone(),
+            ctx: ctx.clone(),
+            tracker,
+            estimated_input_tokens,
+            streaming: true,
+        };
+        let mut upstream = body.into_data_stream();
+        let stream = async_stream::stream! {
+            while let Some(chunk) = upstream.next().await {
+                if let Ok(bytes) = &chunk {
+                    recorder.tracker.observe_sse_chunk(bytes);
+                }
+                yield chunk;
+            }
+            // recorder 在流结束或被丢弃时记录用量
+            drop(recorder);
+        };
+        return Response::from_parts(parts, Body::from_stream(stream));
+    }
+
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("[TOKEN] 读取响应体失败: {}", e);
+            return ProxyApiError::from_status(
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to read response body: {}", e),
+            )
+            .into_response();
+        }
+    };
+
+    let mut recorder = UsageRecorder {
+        state: state.clone(),
+        ctx: ctx.clone(),
+        tracker,
+        estimated_input_tokens,
+        streaming: false,
+    };
+    if let Ok(value) = serde_json::from_slice::<serde_json::Value>(&bytes) {
+        recorder.tracker.observe_json(&value);
+    }
+    drop(recorder);
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// 在被丢弃时记录 Token 用量，保证客户端中途断开的流式响应也能计入统计
+///
+/// 流式响应同时补记端到端延迟
+struct UsageRecorder {
+    state: AppState,
+    ctx: RequestContext,
+    tracker: UsageTracker,
+    estimated_input_tokens: u32,
+    streaming: bool,
+}
+
+impl Drop for UsageRecorder {
+    fn drop(&mut self) {
+        let (input_tokens, output_tokens, source) =
+            self.tracker.finish(self.estimated_input_tokens);
+        record_token_usage(
+            &self.state,
+            &self.ctx,
+            Some(input_tokens),
+            Some(output_tokens),
+            self.tracker.cache_tokens(),
+            source,
+        );
+        if self.streaming {
+            let duration_ms = self.ctx.elapsed_ms();
+            let completed = self
+                .state
+                .processor
+                .stats
+                .read()
+                .complete_stream(&self.ctx.request_id, duration_ms);
+            if let (true, Some(db)) = (completed, &self.state.db) {
+                self.state
+                    .request_logs
+                    .update_duration(db, &self.ctx.request_id, duration_ms);
+            }
+            if completed {
+                self.state
+                    .shared_storage
+                    .update_request_log_duration(&self.ctx.request_id, duration_ms);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_openai_usage() {
+        let value = serde_json::json!({
+            "choices": [{"message": {"role": "assistant", "content": "hi"}}],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15}
+        });
+        assert_eq!(
+            extract_usage(&value),
+            UsageCounts {
+                input_tokens: Some(12),
+                output_tokens: Some(3),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_extract_openai_cached_tokens() {
+        let value = serde_json::json!({
+            "usage": {
+                "prompt_tokens": 2000,
+                "completion_tokens": 10,
+                "prompt_tokens_details": {"cached_tokens": 1536}
+            }
+        });
+        let counts = extract_usage(&value);
+        assert_eq!(counts.input_tokens, Some(2000));
+        assert_eq!(counts.cache_read_input_tokens, Some(1536));
+        assert_eq!(counts.cache_creation_input_tokens, None);
+    }
+
+    #[test]
+    fn test_extract_anthropic_usage_with_cache() {
+        let value = serde_json::json!({
+            "type": "message",
+            "usage": {
+                "input_tokens": 10,
+                "cache_creation_input_tokens": 100,
+                "cache_read_input_tokens": 1000,
+                "output_tokens": 7
+            }
+        });
+        let counts = extract_usage(&value);
+        assert_eq!(counts.input_tokens, Some(1110));
+        assert_eq!(counts.output_tokens, Some(7));
+        assert_eq!(counts.cache_creation_input_tokens, Some(100));
+        assert_eq!(counts.cache_read_input_tokens, Some(1000));
+    }
+
+    #[test]
+    fn test_anthropic_sse_usage() {
+        let mut tracker = UsageTracker::new();
+        // 故意在行中间切分，验证跨块缓冲
+        let sse = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":25,\"output_tokens\":1}}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello world\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":9}}\n\n",
+        );
+        let (head, tail) = sse.as_bytes().split_at(40);
+        tracker.observe_sse_chunk(head);
+        tracker.observe_sse_chunk(tail);
+
+        assert_eq!(tracker.finish(0), (25, 9, TokenSource::Actual));
+    }
+
+    #[test]
+    fn test_openai_sse_usage_chunk() {
+        let mut tracker = UsageTracker::new();
+        tracker.observe_sse_chunk(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n");
+        tracker.observe_sse_chunk(
+            b"data: {\"choices\":[],\"usage\":{\"prompt_tokens\":8,\"completion_tokens\":2}}\n\ndata: [DONE]\n\n",
+        );
+        assert_eq!(tracker.finish(100), (8, 2, TokenSource::Actual));
+    }
+
+    #[test]
+    fn test_sse_without_usage_falls_back_to_estimate() {
+        let mut tracker = UsageTracker::new();
+        tracker
+            .observe_sse_chunk(b"data: {\"choices\":[{\"delta\":{\"content\":\"12345678\"}}]}\n\n");
+        assert_eq!(tracker.finish(50), (50, 2, TokenSource::Estimated));
+    }
+
+    #[test]
+    fn test_zero_output_with_text_is_estimated() {
+        let mut tracker = UsageTracker::new();
+        tracker.observe_sse_chunk(
+            b"data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":0,\"output_tokens\":0}}}\n",
+        );
+        tracker.observe_sse_chunk(
+            b"data: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"abcdefgh\"}}\n",
+        );
+        tracker.observe_sse_chunk(
+            b"data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":0}}\n",
+        );
+        assert_eq!(tracker.finish(30), (30, 2, TokenSource::Estimated));
+    }
+
+    #[test]
+    fn test_source_hint_marks_estimated() {
+        let mut tracker = UsageTracker::new().with_source_hint(Some(TokenSource::Estimated));
+        tracker.observe_json(&serde_json::json!({
+            "content": [{"type": "text", "text": "hello"}],
+            "usage": {"input_tokens": 40, "output_tokens": 1}
+        }));
+        assert_eq!(tracker.finish(0), (40, 1, TokenSource::Estimated));
+    }
+}
@@ -3,6 +3,9 @@
 //! 包含响应解析、字符串处理、响应构建等公共工具函数。
 
 use crate::models::openai::{ContentPart, FunctionCall, MessageContent, ToolCall};
+use crate::server::error::ProxyApiError;
+use crate::streaming::event_stream;
+use crate::telemetry::TokenSource;
 use axum::{
     body::Body,
     http::{header, StatusCode},
@@ -60,15 +63,7 @@ pub fn parse_error_status_code(error_message: &str) -> StatusCode {
 /// # 返回
 /// 包含正确状态码的 HTTP 响应
 pub fn build_error_response(error_message: &str) -> Response {
-    let status_code = parse_error_status_code(error_message);
-    (
-        status_code,
-        Json(serde_json::json!({
-            "error": {
-                "message": error_message
-            }
-        })),
-    )
+    ProxyApiError::from_status(parse_error_status_code(error_message), error_message)
         .into_response()
 }
 
@@ -85,15 +80,7 @@ pub fn build_error_response(error_message: &str) -> Response {
 /// 包含指定状态码的 HTTP 响应
 pub fn build_error_response_with_status(status_code: u16, error_message: &str) -> Response {
     let status = StatusCode::from_u16(status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-    (
-        status,
-        Json(serde_json::json!({
-            "error": {
-                "message": error_message
-            }
-        })),
-    )
-        .into_response()
+    ProxyApiError::from_status(status, error_message).into_response()
 }
 
 /// CodeWhisperer 响应解析结果
@@ -103,6 +90,12 @@ pub struct CWParsedResponse {
     pub tool_calls: Vec<ToolCall>,
     pub usage_credits: f64,
     pub context_usage_percentage: f64,
+    /// 上游返回的实际输入 Token 数（如有）
+    pub input_tokens: Option<u32>,
+    /// 上游返回的实际输出 Token 数（如有）
+    pub output_tokens: Option<u32>,
+    /// 推理内容，非空时输出为 thinking 内容块
+    pub thinking: String,
 }
 
 impl CWParsedResponse {
@@ -127,6 +120,19 @@ impl CWParsedResponse {
 
         (input_tokens, output_tokens)
     }
+
+    /// 获取 Token 使用量及其来源
+    ///
+    /// 上游同时返回了输入与输出 Token 数时使用实际值，否则回退到 `estimate_tokens`。
+    pub fn token_usage(&self) -> (u32, u32, TokenSource) {
+        match (self.input_tokens, self.output_tokens) {
+            (Some(input), Some(output)) => (input, output, TokenSource::Actual),
+            _ => {
+                let (input, output) = self.estimate_tokens();
+                (input, output, TokenSource::Estimated)
+            }
+        }
+    }
 }
 
 /// 安全截断字符串到指定字符数，避免 UTF-8 边界问题
@@ -158,18 +164,47 @@ pub fn message_content_len(content: &MessageContent) -> usize {
 
 /// 解析 CodeWhisperer AWS Event Stream 响应
 ///
-/// AWS Event Stream 是二进制格式，JSON payload 嵌入在二进制头部之间
-pub fn parse_cw_response(body: &str) -> CWParsedResponse {
-    let mut result = CWParsedResponse::default();
-    // 使用 HashMap 来跟踪多个并发的 tool calls
-    // key: toolUseId, value: (name, input_accumulated)
-    let mut tool_map: HashMap<String, (String, String)> = HashMap::new();
+/// 优先按 AWS Event Stream 帧格式（prelude、头部、CRC）逐帧解码；
+/// 数据不是合法帧序列时（如已做过有损 UTF-8 转换的文本）回退到 JSON 模式扫描。
+pub fn parse_cw_response(body: impl AsRef<[u8]>) -> CWParsedResponse {
+    let bytes = body.as_ref();
+    let mut parser = CWEventParser::default();
+
+    match event_stream::decode_all(bytes) {
+        Ok(messages) => {
+            for message in messages {
+                if message.is_exception() {
+                    tracing::warn!(
+                        "[CW_PARSE] 上游返回异常事件 {}: {}",
+                        message
+                            .header(":exception-type")
+                            .or_else(|| message.event_type())
+                            .unwrap_or("unknown"),
+                        String::from_utf8_lossy(&message.payload)
+                    );
+                    continue;
+                }
+                if let Ok(value) = serde_json::from_slice::<serde_json::Value>(&message.payload) {
+                    parser.apply(&value);
+                }
+            }
+        }
+        Err(e) => {
+            if !bytes.is_empty() {
+                tracing::debug!("[CW_PARSE] 非标准 event stream 帧，回退到模式扫描: {}", e);
+            }
+            scan_cw_json_events(bytes, &mut parser);
+        }
+    }
 
-    // 将字符串转换为字节，因为 AWS Event Stream 包含二进制数据
-    let bytes = body.as_bytes();
+    parser.finish()
+}
 
+/// 在字节流中按 JSON 起始模式扫描 CodeWhisperer 事件
+///
+/// AWS Event Stream 格式: [binary headers]{"content":"..."}[binary trailer]
+fn scan_cw_json_events(bytes: &[u8], parser: &mut CWEventParser) {
     // 搜索所有 JSON 对象的模式
-    // AWS Event Stream 格式: [binary headers]{"content":"..."}[binary trailer]
     let json_patterns: &[&[u8]] = &[
         b"{\"content\":",
         b"{\"name\":",
@@ -179,6 +214,9 @@ pub fn parse_cw_response(body: &str) -> CWParsedResponse {
         b"{\"toolUseId\":",
         b"{\"unit\":",                   // meteringEvent
         b"{\"contextUsagePercentage\":", // contextUsageEvent
+        b"{\"tokenUsage\":",             // tokenUsage
+        b"{\"inputTokens\":",            // tokenUsage（扁平格式）
+        b"{\"metadataEvent\":",          // metadataEvent
     ];
 
     let mut pos = 0;
@@ -203,95 +241,140 @@ pub fn parse_cw_response(body: &str) -> CWParsedResponse {
         // 从 start 位置提取完整的 JSON 对象
         if let Some(json_str) = extract_json_from_bytes(&bytes[start..]) {
             if let Ok(value) = serde_json::from_str::<serde_json::Value>(&json_str) {
-                // 处理 content 事件
-                if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
-                    // 跳过 followupPrompt
-                    if value.get("followupPrompt").is_none() {
-                        result.content.push_str(content);
-                    }
-                }
-                // 处理 tool use 事件 (包含 toolUseId)
-                else if let Some(tool_use_id) = value.get("toolUseId").and_then(|v| v.as_str()) {
-                    let name = value
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    let input_chunk = value
-                        .get("input")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    let is_stop = value.get("stop").and_then(|v| v.as_bool()).unwrap_or(false);
-
-                    // 获取或创建 tool entry
-                    let entry = tool_map
-                        .entry(tool_use_id.to_string())
-                        .or_insert_with(|| (String::new(), String::new()));
-
-                    // 更新 name（如果有）
-                    if !name.is_empty() {
-                        entry.0 = name;
-                    }
-
-                    // 累积 input
-                    entry.1.push_str(&input_chunk);
-
-                    // 如果是 stop 事件，完成这个 tool call
-                    if is_stop {
-                        if let Some((name, input)) = tool_map.remove(tool_use_id) {
-                            if !name.is_empty() {
-                                result.tool_calls.push(ToolCall {
-                                    id: tool_use_id.to_string(),
-                                    call_type: "function".to_string(),
-                                    function: FunctionCall {
-                                        name,
-                                        arguments: input,
-                                    },
-                                });
-                            }
-                        }
-                    }
-                }
-                // 处理独立的 stop 事件（没有 toolUseId）- 这种情况不应该发生，但以防万一
-                else if value.get("stop").and_then(|v| v.as_bool()).unwrap_or(false) {
-                    // no-op
-                }
-                // 处理 meteringEvent: {"unit":"credit","unitPlural":"credits","usage":0.34}
-                else if let Some(usage) = value.get("usage").and_then(|v| v.as_f64()) {
-                    result.usage_credits = usage;
-                }
-                // 处理 contextUsageEvent: {"contextUsagePercentage":54.36}
-                else if let Some(ctx_usage) =
-                    value.get("contextUsagePercentage").and_then(|v| v.as_f64())
-                {
-                    result.context_usage_percentage = ctx_usage;
-                }
+                parser.apply(&value);
             }
             pos = start + json_str.len();
         } else {
             pos = start + 1;
         }
     }
+}
 
-    // 处理未完成的 tool calls（没有收到 stop 事件的）
-    for (id, (name, input)) in tool_map {
-        if !name.is_empty() {
-            result.tool_calls.push(ToolCall {
-                id,
-                call_type: "function".to_string(),
-                function: FunctionCall {
-                    name,
-                    arguments: input,
-                },
-            });
+/// CodeWhisperer 事件累积器
+#[derive(Default)]
+struct CWEventParser {
+    result: CWParsedResponse,
+    // 使用 HashMap 来跟踪多个并发的 tool calls
+    // key: toolUseId, value: (name, input_accumulated)
+    tool_map: HashMap<String, (String, String)>,
+}
+
+impl CWEventParser {
+    /// 处理单个事件负载
+    fn apply(&mut self, value: &serde_json::Value) {
+        let result = &mut self.result;
+        // 处理 content 事件
+        if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
+            // 跳过 followupPrompt
+            if value.get("followupPrompt").is_none() {
+                result.content.push_str(content);
+            }
+        }
+        // 处理 tool use 事件 (包含 toolUseId)
+        else if let Some(tool_use_id) = value.get("toolUseId").and_then(|v| v.as_str()) {
+            let name = value
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+            let input_chunk = value
+                .get("input")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+            let is_stop = value.get("stop").and_then(|v| v.as_bool()).unwrap_or(false);
+
+            // 获取或创建 tool entry
+            let entry = self
+                .tool_map
+                .entry(tool_use_id.to_string())
+                .or_insert_with(|| (String::new(), String::new()));
+
+            // 更新 name（如果有）
+            if !name.is_empty() {
+                entry.0 = name;
+            }
+
+            // 累积 input
+            entry.1.push_str(&input_chunk);
+
+            // 如果是 stop 事件，完成这个 tool call
+            if is_stop {
+                if let Some((name, input)) = self.tool_map.remove(tool_use_id) {
+                    if !name.is_empty() {
+                        result.tool_calls.push(ToolCall {
+                            id: tool_use_id.to_string(),
+                            call_type: "function".to_string(),
+                            function: FunctionCall {
+                                name,
+                                arguments: input,
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        // 处理独立的 stop 事件（没有 toolUseId）- 这种情况不应该发生，但以防万一
+        else if value.get("stop").and_then(|v| v.as_bool()).unwrap_or(false) {
+            // no-op
+        }
+        // 处理 meteringEvent: {"unit":"credit","unitPlural":"credits","usage":0.34}
+        else if let Some(usage) = value.get("usage").and_then(|v| v.as_f64()) {
+            result.usage_credits = usage;
+        }
+        // 处理 contextUsageEvent: {"contextUsagePercentage":54.36}
+        else if let Some(ctx_usage) = value.get("contextUsagePercentage").and_then(|v| v.as_f64())
+        {
+            result.context_usage_percentage = ctx_usage;
+        }
+
+        // 处理实际 Token 用量（meteringEvent / metadataEvent 可能携带）
+        let (input_tokens, output_tokens) = extract_cw_token_usage(value);
+        if input_tokens.is_some() {
+            result.input_tokens = input_tokens;
+        }
+        if output_tokens.is_some() {
+            result.output_tokens = output_tokens;
         }
     }
 
-    // 解析 bracket 格式的 tool calls: [Called xxx with args: {...}]
-    parse_bracket_tool_calls(&mut result);
+    fn finish(self) -> CWParsedResponse {
+        let mut result = self.result;
+        // 处理未完成的 tool calls（没有收到 stop 事件的）
+        for (id, (name, input)) in self.tool_map {
+            if !name.is_empty() {
+                result.tool_calls.push(ToolCall {
+                    id,
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name,
+                        arguments: input,
+                    },
+                });
+            }
+        }
+
+        // 解析 bracket 格式的 tool calls: [Called xxx with args: {...}]
+        parse_bracket_tool_calls(&mut result);
 
-    result
+        result
+    }
+}
+
+/// 从 CodeWhisperer 事件中提取实际 Token 数
+///
+/// 支持以下格式：
+/// - `{"inputTokens":1234,"outputTokens":56}`
+/// - `{"unit":"credit","usage":0.34,"tokenUsage":{"inputTokens":1234,"outputTokens":56}}`
+/// - `{"metadataEvent":{"tokenUsage":{...}}}`
+fn extract_cw_token_usage(value: &serde_json::Value) -> (Option<u32>, Option<u32>) {
+    let usage = value
+        .get("metadataEvent")
+        .unwrap_or(value)
+        .get("tokenUsage")
+        .unwrap_or(value);
+    let read = |key: &str| usage.get(key).and_then(|v| v.as_u64()).map(|v| v as u32);
+    (read("inputTokens"), read("outputTokens"))
 }
 
 /// 在字节数组中查找子序列
@@ -411,6 +494,14 @@ pub fn build_anthropic_response(model: &str, parsed: &CWParsedResponse) -> Respo
     let has_tool_calls = !parsed.tool_calls.is_empty();
     let mut content_array: Vec<serde_json::Value> = Vec::new();
 
+    if !parsed.thinking.is_empty() {
+        content_array.push(serde_json::json!({
+            "type": "thinking",
+            "thinking": parsed.thinking,
+            "signature": ""
+        }));
+    }
+
     if !parsed.content.is_empty() {
         content_array.push(serde_json::json!({
             "type": "text",
@@ -433,14 +524,8 @@ pub fn build_anthropic_response(model: &str, parsed: &CWParsedResponse) -> Respo
         content_array.push(serde_json::json!({"type": "text", "text": ""}));
     }
 
-    // 估算 output tokens: 基于响应内容长度 (约 4 字符 = 1 token)
-    let mut output_tokens: u32 = (parsed.content.len() / 4) as u32;
-    for tc in &parsed.tool_calls {
-        output_tokens += (tc.function.arguments.len() / 4) as u32;
-    }
-    // 从 context_usage_percentage 估算 input tokens
-    // 假设 100% = 200k tokens (Claude 的上下文窗口)
-    let input_tokens = ((parsed.context_usage_percentage / 100.0) * 200000.0) as u32;
+    // 优先使用上游返回的实际 Token 数，否则估算
+    let (input_tokens, output_tokens, token_source) = parsed.token_usage();
 
     let response = serde_json::json!({
         "id": format!("msg_{}", uuid::Uuid::new_v4()),
@@ -455,7 +540,9 @@ pub fn build_anthropic_response(model: &str, parsed: &CWParsedResponse) -> Respo
             "output_tokens": output_tokens
         }
     });
-    Json(response).into_response()
+    let mut response = Json(response).into_response();
+    response.extensions_mut().insert(token_source);
+    response
 }
 
 /// 构建 Anthropic 流式响应 (SSE)
@@ -466,13 +553,8 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
     let content = parsed.content.clone();
     let tool_calls = parsed.tool_calls.clone();
 
-    // 估算 output tokens: 基于响应内容长度 (约 4 字符 = 1 token)
-    let mut output_tokens: u32 = (parsed.content.len() / 4) as u32;
-    for tc in &parsed.tool_calls {
-        output_tokens += (tc.function.arguments.len() / 4) as u32;
-    }
-    // 从 context_usage_percentage 估算 input tokens
-    let input_tokens = ((parsed.context_usage_percentage / 100.0) * 200000.0) as u32;
+    // 优先使用上游返回的实际 Token 数，否则估算
+    let (input_tokens, output_tokens, token_source) = parsed.token_usage();
 
     // 构建 SSE 事件流
     let mut events: Vec<String> = Vec::new();
@@ -495,7 +577,36 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
 
     let mut block_index = 0;
 
-    // 2. 文本内容块 - 即使为空也要发送，Claude Code 需要至少一个 content block
+    // 2. thinking 块
+    if !parsed.thinking.is_empty() {
+        let block_start = serde_json::json!({
+            "type": "content_block_start",
+            "index": block_index,
+            "content_block": {"type": "thinking", "thinking": "", "signature": ""}
+        });
+        events.push(format!(
+            "event: content_block_start\ndata: {block_start}\n\n"
+        ));
+
+        let block_delta = serde_json::json!({
+            "type": "content_block_delta",
+            "index": block_index,
+            "delta": {"type": "thinking_delta", "thinking": parsed.thinking}
+        });
+        events.push(format!(
+            "event: content_block_delta\ndata: {block_delta}\n\n"
+        ));
+
+        let block_stop = serde_json::json!({
+            "type": "content_block_stop",
+            "index": block_index
+        });
+        events.push(format!("event: content_block_stop\ndata: {block_stop}\n\n"));
+
+        block_index += 1;
+    }
+
+    // 3. 文本内容块 - 即使为空也要发送，Claude Code 需要至少一个 content block
     // content_block_start
     let block_start = serde_json::json!({
         "type": "content_block_start",
@@ -527,7 +638,7 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
 
     block_index += 1;
 
-    // 3. Tool use 块
+    // 4. Tool use 块
     for tc in &tool_calls {
         // content_block_start
         let block_start = serde_json::json!({
@@ -572,7 +683,7 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
         block_index += 1;
     }
 
-    // 4. message_delta
+    // 5. message_delta
     let message_delta = serde_json::json!({
         "type": "message_delta",
         "delta": {
@@ -583,7 +694,7 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
     });
     events.push(format!("event: message_delta\ndata: {message_delta}\n\n"));
 
-    // 5. message_stop
+    // 6. message_stop
     let message_stop = serde_json::json!({"type": "message_stop"});
     events.push(format!("event: message_stop\ndata: {message_stop}\n\n"));
 
@@ -596,6 +707,7 @@ pub fn build_anthropic_stream_response(model: &str, parsed: &CWParsedResponse) -
         .header(header::CONTENT_TYPE, "text/event-stream")
         .header(header::CACHE_CONTROL, "no-cache")
         .header(header::CONNECTION, "keep-alive")
+        .extension(token_source)
         .body(body)
         .unwrap_or_else(|e| {
             tracing::error!("Failed to build SSE response: {}", e);
@@ -892,6 +1004,7 @@ mod property_tests {
                     tool_calls,
                     usage_credits,
                     context_usage_percentage,
+                    ..Default::default()
                 },
             )
     }
@@ -1408,4 +1521,129 @@ mod property_tests {
             );
         }
     }
+
+    #[test]
+    fn test_parse_cw_response_token_usage() {
+        let body = concat!(
+            "\x00{\"content\":\"Hello\"}\x00",
+            "\x00{\"unit\":\"credit\",\"unitPlural\":\"credits\",\"usage\":0.34,",
+            "\"tokenUsage\":{\"inputTokens\":1200,\"outputTokens\":42}}\x00"
+        );
+        let parsed = parse_cw_response(body);
+        assert_eq!(parsed.content, "Hello");
+        assert!((parsed.usage_credits - 0.34).abs() < f64::EPSILON);
+        assert_eq!(parsed.input_tokens, Some(1200));
+        assert_eq!(parsed.output_tokens, Some(42));
+        assert_eq!(parsed.token_usage(), (1200, 42, TokenSource::Actual));
+    }
+
+    #[test]
+    fn test_cw_token_usage_falls_back_to_estimate() {
+        let parsed =
+            parse_cw_response("{\"content\":\"12345678\"}{\"contextUsagePercentage\":1.0}");
+        assert_eq!(parsed.input_tokens, None);
+        assert_eq!(parsed.token_usage(), (2000, 2, TokenSource::Estimated));
+    }
+
+    fn cw_frame(event_type: &str, payload: &str) -> Vec<u8> {
+        crate::streaming::event_stream::encode_message(
+            &[
+                (":event-type", event_type),
+                (":content-type", "application/json"),
+                (":message-type", "event"),
+            ],
+            payload.as_bytes(),
+        )
+    }
+
+    #[test]
+    fn test_parse_cw_response_frames() {
+        let mut body = cw_frame("assistantResponseEvent", r#"{"content":"Let me check"}"#);
+        body.extend(cw_frame(
+            "toolUseEvent",
+            r#"{"name":"get_weather","toolUseId":"t1","input":"{\"city\":"}"#,
+        ));
+        body.extend(cw_frame(
+            "toolUseEvent",
+            r#"{"toolUseId":"t1","input":"\"Paris\"}"}"#,
+        ));
+        body.extend(cw_frame(
+            "toolUseEvent",
+            r#"{"toolUseId":"t1","stop":true}"#,
+        ));
+        body.extend(cw_frame(
+            "meteringEvent",
+            r#"{"unit":"credit","unitPlural":"credits","usage":0.5}"#,
+        ));
+        body.extend(cw_frame(
+            "contextUsageEvent",
+            r#"{"contextUsagePercentage":12.5}"#,
+        ));
+
+        let parsed = parse_cw_response(&body);
+        assert_eq!(parsed.content, "Let me check");
+        assert_eq!(parsed.tool_calls.len(), 1);
+        assert_eq!(parsed.tool_calls[0].function.name, "get_weather");
+        assert_eq!(
+            parsed.tool_calls[0].function.arguments,
+            r#"{"city":"Paris"}"#
+        );
+        assert!((parsed.usage_credits - 0.5).abs() < f64::EPSILON);
+        assert!((parsed.context_usage_percentage - 12.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_parse_cw_response_frames_with_literal_patterns() {
+        // 其他事件的负载中嵌套了事件 JSON 的字面量，模式扫描会把它误判为正文和工具调用
+        let text = r#"Use {"toolUseId":"x","name":"rm","stop":true} carefully"#;
+        let mut body = cw_frame(
+            "assistantResponseEvent",
+            &serde_json::json!({ "content": text }).to_string(),
+        );
+        body.extend(cw_frame(
+            "codeReferenceEvent",
+            r#"{"references":[{"content":"cited snippet"},{"toolUseId":"x","name":"rm","stop":true}]}"#,
+        ));
+
+        let parsed = parse_cw_response(&body);
+        assert_eq!(parsed.content, text);
+        assert!(parsed.tool_calls.is_empty());
+    }
+
+    #[test]
+    fn test_parse_cw_response_frames_with_non_utf8_prelude() {
+        // prelude 中的长度/CRC 字节经有损 UTF-8 转换后会损坏，直接按字节解析
+        let long_text = "好".repeat(100);
+        let payload = serde_json::json!({ "content": long_text }).to_string();
+        let body = cw_frame("assistantResponseEvent", &payload);
+        assert!(std::str::from_utf8(&body).is_err());
+
+        let parsed = parse_cw_response(&body);
+        assert_eq!(parsed.content, long_text);
+    }
+
+    #[test]
+    fn test_parse_cw_response_skips_exception_frames() {
+        let mut body = cw_frame("assistantResponseEvent", r#"{"content":"partial"}"#);
+        body.extend(crate::streaming::event_stream::encode_message(
+            &[
+                (":message-type", "exception"),
+                (":exception-type", "ThrottlingException"),
+            ],
+            br#"{"content":"Too many requests"}"#,
+        ));
+
+        let parsed = parse_cw_response(&body);
+        assert_eq!(parsed.content, "partial");
+    }
+
+    #[test]
+    fn test_parse_cw_response_falls_back_on_corrupt_frames() {
+        let mut body = cw_frame("assistantResponseEvent", r#"{"content":"Hello"}"#);
+        let last = body.len() - 1;
+        body[last] ^= 0xFF;
+
+        let parsed = parse_cw_response(&body);
+        assert_eq!(parsed.content, "Hello");
+    }
 }
@@ -0,0 +1,95 @@
+//! 异常告警服务
+//!
+//! 定期按告警规则检测异常（错误率、当日费用、凭证持续不健康），
+//! 并将新触发的告警以 JSON POST 到配置的 Webhook。
+
+use crate::database::DbConnection;
+use crate::services::provider_pool_service::ProviderPoolService;
+use crate::telemetry::{
+    Alert, AlertConfig, AlertMonitor, CredentialStatus, PricingTable, RequestLog, TokenUsageRecord,
+};
+use chrono::Utc;
+use reqwest::Client;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Webhook 请求超时
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 异常告警服务
+pub struct AlertService {
+    monitor: Mutex<AlertMonitor>,
+    client: Client,
+}
+
+impl AlertService {
+    pub fn new() -> Self {
+        Self {
+            monitor: Mutex::new(AlertMonitor::new()),
+            client: Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 检测异常，返回本次新触发的告警
+    pub fn check(
+        &self,
+        config: &AlertConfig,
+        logs: &[RequestLog],
+        tokens: &[TokenUsageRecord],
+        pricing: &PricingTable,
+        credentials: &[CredentialStatus],
+    ) -> Vec<Alert> {
+        self.monitor
+            .lock()
+            .map(|mut monitor| {
+                monitor.evaluate(config, Utc::now(), logs, tokens, pricing, credentials)
+            })
+            .unwrap_or_default()
+    }
+
+    /// 读取凭证池中所有凭证的健康状态
+    pub fn credential_statuses(
+        pool_service: &ProviderPoolService,
+        db: &DbConnection,
+    ) -> Vec<CredentialStatus> {
+        match pool_service.get_all_credential_health(db) {
+            Ok(health) => health
+                .into_iter()
+                .map(|h| CredentialStatus {
+                    id: h.uuid,
+                    name: h.name,
+                    healthy: h.is_healthy,
+                })
+                .collect(),
+            Err(e) => {
+                tracing::warn!("[ALERT] 读取凭证健康状态失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 将告警 POST 到 Webhook
+    pub async fn send_webhook(&self, url: &str, alert: &Alert) -> Result<(), String> {
+        let response = self
+            .client
+            .post(url)
+            .json(alert)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("HTTP {}", response.status().as_u16()))
+        }
+    }
+}
+
+impl Default for AlertService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
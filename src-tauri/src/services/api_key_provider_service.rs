@@ -12,6 +12,7 @@ use crate::database::dao::api_key_provider::{
 use crate::database::system_providers::{get_system_providers, to_api_key_provider};
 use crate::database::DbConnection;
 use crate::models::{CredentialData, CredentialSource, PoolProviderType, ProviderCredential};
+use crate::services::secret_store;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
@@ -60,6 +61,16 @@ data: [DONE]\n";
         let content = ApiKeyProviderService::parse_codex_responses_sse_content(body);
         assert_eq!(content, "hi!");
     }
+
+    #[test]
+    fn test_legacy_format_round_trip_without_encryption() {
+        let encryption = super::EncryptionService::new();
+        let stored = encryption.encrypt("sk-test");
+        assert_ne!(stored, "sk-test");
+        assert_eq!(encryption.decrypt(&stored).unwrap(), "sk-test");
+        // 未启用凭证加密时旧格式无需迁移
+        assert_eq!(encryption.reseal(&stored).unwrap(), None);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,11 +86,13 @@ pub struct ChatTestResult {
 // 加密服务
 // ============================================================================
 
-/// 简单的 API Key 加密服务
-/// 使用 XOR 加密 + Base64 编码
-/// 注意：这是一个简单的混淆方案，不是强加密
+/// API Key 加密服务
+///
+/// 启用凭证加密时通过 `secret_store` 以 AES-256-GCM 加密（`enc:v1:` 格式）；
+/// 未启用时沿用旧版的 XOR + Base64 混淆（密钥从机器 ID 派生，不是强加密）。
+/// 两种格式都可读取，由 `set_credential_encryption` 按当前加密状态迁移。
 struct EncryptionService {
-    /// 加密密钥（从机器 ID 派生）
+    /// 旧版混淆密钥（从机器 ID 派生）
     key: Vec<u8>,
 }
 
@@ -128,6 +141,14 @@ impl EncryptionService {
 
     /// 加密 API Key
     fn encrypt(&self, plaintext: &str) -> String {
+        if secret_store::is_enabled() {
+            return secret_store::seal(plaintext);
+        }
+        self.obfuscate(plaintext)
+    }
+
+    /// 旧版 XOR 混淆
+    fn obfuscate(&self, plaintext: &str) -> String {
         let encrypted: Vec<u8> = plaintext
             .as_bytes()
             .iter()
@@ -139,6 +160,9 @@ impl EncryptionService {
 
     /// 解密 API Key
     fn decrypt(&self, ciphertext: &str) -> Result<String, String> {
+        if secret_store::is_sealed(ciphertext) {
+            return secret_store::open(ciphertext).map_err(|e| e.to_string());
+        }
         let encrypted = BASE64
             .decode(ciphertext)
             .map_err(|e| format!("Base64 解码失败: {}", e))?;
@@ -150,8 +174,19 @@ impl EncryptionService {
         String::from_utf8(decrypted).map_err(|e| format!("UTF-8 解码失败: {}", e))
     }
 
+    /// 按当前加密状态重写存储值，格式已匹配时返回 `None`
+    fn reseal(&self, stored: &str) -> Result<Option<String>, String> {
+        if secret_store::is_sealed(stored) == secret_store::is_enabled() {
+            return Ok(None);
+        }
+        Ok(Some(self.encrypt(&self.decrypt(stored)?)))
+    }
+
     /// 检查是否为加密后的值（非明文）
     fn is_encrypted(&self, value: &str) -> bool {
+        if secret_store::is_sealed(value) {
+            return true;
+        }
         // 加密后的值是 Base64 编码的，通常不包含常见的 API Key 前缀
         !value.starts_with("sk-")
             && !value.starts_with("pk-")
@@ -269,6 +304,7 @@ impl ApiKeyProviderService {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            extra: Default::default(),
         };
 
         let resp = provider
@@ -695,13 +731,16 @@ impl ApiKeyProviderService {
             existing_keys.len()
         );
 
-        // 检查是否有相同的 API Key（比较加密后的值）
-        let encrypted_input = self.encryption.encrypt(api_key);
-        for existing_key in &existing_keys {
-            if existing_key.api_key_encrypted == encrypted_input {
-                return Err("该 API Key 已存在".to_string());
-            }
+        // 检查是否有相同的 API Key（加密使用随机 nonce，需比较解密后的值）
+        let duplicate = existing_keys.iter().any(|existing_key| {
+            self.encryption
+                .decrypt(&existing_key.api_key_encrypted)
+                .is_ok_and(|existing| existing == api_key)
+        });
+        if duplicate {
+            return Err("该 API Key 已存在".to_string());
         }
+        let encrypted_input = self.encryption.encrypt(api_key);
 
         let should_enable_provider = existing_keys.is_empty() && !provider.enabled;
 
@@ -979,6 +1018,22 @@ impl ApiKeyProviderService {
         self.encryption.encrypt(plaintext)
     }
 
+    /// 按当前加密状态重写所有已保存的 API Key，返回重写数量
+    pub fn rewrite_secrets(&self, db: &DbConnection) -> Result<usize, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let providers =
+            ApiKeyProviderDao::get_all_providers_with_keys(&conn).map_err(|e| e.to_string())?;
+        let mut rewritten = 0;
+        for key in providers.iter().flat_map(|p| &p.api_keys) {
+            if let Some(resealed) = self.encryption.reseal(&key.api_key_encrypted)? {
+                ApiKeyProviderDao::update_api_key_secret(&conn, &key.id, &resealed)
+                    .map_err(|e| e.to_string())?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+
     // ==================== UI 状态 ====================
 
     /// 获取 UI 状态
@@ -1221,8 +1276,12 @@ impl ApiKeyProviderService {
             // OAuth-only，无降级
             PoolProviderType::Kiro => None,
             PoolProviderType::Codex => None,
+            PoolProviderType::Qwen => None,
             PoolProviderType::ClaudeOAuth => None,
             PoolProviderType::Antigravity => None,
+
+            // 内置 Mock，无对应的 API Key Provider
+            PoolProviderType::Mock => None,
         }
     }
 
@@ -1501,6 +1560,7 @@ impl ApiKeyProviderService {
             cached_token: None,
             source: CredentialSource::Imported,
             proxy_url: None,
+            weight: 1,
         })
     }
 
@@ -1559,6 +1619,7 @@ impl ApiKeyProviderService {
             cached_token: None,
             source: CredentialSource::Imported, // 标记为导入来源
             proxy_url: None,
+            weight: 1,
         })
     }
 
@@ -0,0 +1,303 @@
+//! 请求/响应审计日志服务
+//!
+//! 对请求体、响应体和请求头做脱敏后按 request_id 持久化，并定期清理过期记录。
+//! 脱敏包括两部分：
+//! - 敏感字段名（如 `api_key`、`authorization`）的值整体替换
+//! - 正则规则（内置规则 + 配置中的自定义规则）对所有文本内容做替换
+
+use crate::config::AuditLogConfig;
+use crate::database::dao::audit_log::{AuditLogDao, AuditLogEntry};
+use crate::database::DbConnection;
+use crate::flow_monitor::{default_redaction_rules, RedactionRule, Redactor};
+use axum::http::HeaderMap;
+use chrono::Utc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 字段值整体替换的文本
+const REDACTED: &str = "[REDACTED]";
+/// 过期记录清理间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 值需要整体脱敏的字段名/请求头名（小写，忽略 `-`/`_` 差异）
+const SENSITIVE_FIELDS: &[&str] = &[
+    "apikey",
+    "xapikey",
+    "authorization",
+    "proxyauthorization",
+    "cookie",
+    "setcookie",
+    "password",
+    "secret",
+    "clientsecret",
+    "token",
+    "accesstoken",
+    "refreshtoken",
+    "idtoken",
+    "xgoogapikey",
+];
+
+fn is_sensitive_field(name: &str) -> bool {
+    let normalized: String = name
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    SENSITIVE_FIELDS.contains(&normalized.as_str())
+}
+
+/// 审计日志服务
+pub struct AuditLogService {
+    config: RwLock<AuditLogConfig>,
+    redactor: RwLock<Arc<Redactor>>,
+    last_cleanup: Mutex<Option<Instant>>,
+}
+
+impl AuditLogService {
+    pub fn new(config: AuditLogConfig) -> Self {
+        let redactor = Arc::new(Self::build_redactor(&config));
+        Self {
+            config: RwLock::new(config),
+            redactor: RwLock::new(redactor),
+            last_cleanup: Mutex::new(None),
+        }
+    }
+
+    /// 更新配置（支持热重载）
+    pub fn update_config(&self, config: AuditLogConfig) {
+        let redactor = Arc::new(Self::build_redactor(&config));
+        if let Ok(mut current) = self.redactor.write() {
+            *current = redactor;
+        }
+        if let Ok(mut current) = self.config.write() {
+            if current.enabled != config.enabled {
+                tracing::info!(
+                    "[AUDIT] 审计日志已{}",
+                    if config.enabled { "启用" } else { "停用" }
+                );
+            }
+            *current = config;
+        }
+    }
+
+    /// 是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().map(|c| c.enabled).unwrap_or(false)
+    }
+
+    /// 单个请求体/响应体最多记录的字节数
+    pub fn max_body_bytes(&self) -> usize {
+        self.config
+            .read()
+            .map(|c| c.max_body_bytes)
+            .unwrap_or_else(|_| AuditLogConfig::default().max_body_bytes)
+    }
+
+    fn build_redactor(config: &AuditLogConfig) -> Redactor {
+        let mut rules = if config.default_redaction {
+            default_redaction_rules()
+        } else {
+            Vec::new()
+        };
+        for rule in &config.redaction_rules {
+            if regex::Regex::new(&rule.pattern).is_err() {
+                tracing::warn!(
+                    "[AUDIT] 脱敏规则 {} 的正则无效，已忽略: {}",
+                    rule.name,
+                    rule.pattern
+                );
+                continue;
+            }
+            rules.push(RedactionRule::new(
+                rule.name.clone(),
+                rule.pattern.clone(),
+                rule.replacement.clone(),
+            ));
+        }
+        Redactor::new(&rules)
+    }
+
+    fn redactor(&self) -> Arc<Redactor> {
+        self.redactor
+            .read()
+            .map(|r| r.clone())
+            .unwrap_or_else(|_| Arc::new(Redactor::with_defaults()))
+    }
+
+    /// 脱敏请求/响应体
+    ///
+    /// 完整的 JSON 按字段脱敏后重新序列化，其他内容（SSE、截断的 JSON）按文本脱敏
+    pub fn redact_body(&self, body: &[u8]) -> String {
+        let redactor = self.redactor();
+        match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(value) => {
+                let redacted = Self::redact_json(&redactor, &value);
+                serde_json::to_string(&redacted).unwrap_or_default()
+            }
+            Err(_) => redactor.redact(&String::from_utf8_lossy(body)),
+        }
+    }
+
+    fn redact_json(redactor: &Redactor, value: &serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(obj) => serde_json::Value::Object(
+                obj.iter()
+                    .map(|(k, v)| {
+                        let v = if is_sensitive_field(k) && !v.is_null() {
+                            serde_json::Value::String(REDACTED.to_string())
+                        } else {
+                            Self::redact_json(redactor, v)
+                        };
+                        (k.clone(), v)
+                    })
+                    .collect(),
+            ),
+            serde_json::Value::Array(arr) => serde_json::Value::Array(
+                arr.iter().map(|v| Self::redact_json(redactor, v)).collect(),
+            ),
+            serde_json::Value::String(s) => serde_json::Value::String(redactor.redact(s)),
+            other => other.clone(),
+        }
+    }
+
+    /// 脱敏请求头，返回 JSON 对象字符串
+    pub fn redact_headers(&self, headers: &HeaderMap) -> String {
+        let redactor = self.redactor();
+        let map: serde_json::Map<String, serde_json::Value> = headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if is_sensitive_field(name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    redactor.redact(&String::from_utf8_lossy(value.as_bytes()))
+                };
+                (name.as_str().to_string(), serde_json::Value::String(value))
+            })
+            .collect();
+        serde_json::Value::Object(map).to_string()
+    }
+
+    /// 保存审计日志，并按保留天数定期清理
+    pub fn record(&self, db: &DbConnection, entry: &AuditLogEntry) {
+        let Ok(conn) = db.lock() else {
+            return;
+        };
+        if let Err(e) = AuditLogDao::insert(&conn, entry) {
+            tracing::warn!("[AUDIT] 保存审计日志失败: {}", e);
+            return;
+        }
+
+        let due = self
+            .last_cleanup
+            .lock()
+            .map(|mut last| {
+                let due = last.is_none_or(|t| t.elapsed() >= CLEANUP_INTERVAL);
+                if due {
+                    *last = Some(Instant::now());
+                }
+                due
+            })
+            .unwrap_or(false);
+        if due {
+            let retention_days = self.config.read().map(|c| c.retention_days).unwrap_or(7);
+            let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
+            match AuditLogDao::delete_before(&conn, cutoff) {
+                Ok(removed) if removed > 0 => {
+                    tracing::info!("[AUDIT] 清理过期审计日志 {} 条", removed)
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("[AUDIT] 清理过期审计日志失败: {}", e),
+            }
+        }
+    }
+
+    /// 获取审计日志详情
+    pub fn get(
+        &self,
+        db: &DbConnection,
+        request_id: &str,
+    ) -> Result<Option<AuditLogEntry>, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        AuditLogDao::get(&conn, request_id).map_err(|e| e.to_string())
+    }
+
+    /// 列出审计日志（不包含请求体/响应体）
+    pub fn list(
+        &self,
+        db: &DbConnection,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<AuditLogEntry>, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        AuditLogDao::list(&conn, limit, offset).map_err(|e| e.to_string())
+    }
+
+    /// 清空审计日志
+    pub fn clear(&self, db: &DbConnection) -> Result<usize, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        AuditLogDao::clear(&conn).map_err(|e| e.to_string())
+    }
+}
+
+impl Default for AuditLogService {
+    fn default() -> Self {
+        Self::new(AuditLogConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AuditRedactionRule;
+
+    #[test]
+    fn test_redact_json_fields_and_patterns() {
+        let service = AuditLogService::default();
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "api_key": "plain-value",
+            "messages": [{"role": "user", "content": "mail me at dev@example.com"}]
+        });
+        let redacted = service.redact_body(body.to_string().as_bytes());
+        let value: serde_json::Value = serde_json::from_str(&redacted).unwrap();
+
+        assert_eq!(value["model"], "gpt-4o");
+        assert_eq!(value["api_key"], REDACTED);
+        assert_eq!(
+            value["messages"][0]["content"],
+            "mail me at [REDACTED_EMAIL]"
+        );
+    }
+
+    #[test]
+    fn test_redact_non_json_body() {
+        let service = AuditLogService::default();
+        let body = b"data: {\"text\":\"Bearer abc.def\"}\n\ndata: {\"te";
+        let redacted = service.redact_body(body);
+        assert!(redacted.contains("Bearer [REDACTED_TOKEN]"));
+        assert!(!redacted.contains("abc.def"));
+    }
+
+    #[test]
+    fn test_custom_rules_and_headers() {
+        let service = AuditLogService::new(AuditLogConfig {
+            enabled: true,
+            default_redaction: false,
+            redaction_rules: vec![AuditRedactionRule {
+                name: "employee".to_string(),
+                pattern: r"EMP-\d+".to_string(),
+                replacement: "[EMP]".to_string(),
+            }],
+            ..Default::default()
+        });
+        assert_eq!(service.redact_body(b"id EMP-42"), "id [EMP]");
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", "pc-secret".parse().unwrap());
+        headers.insert("user-agent", "claude-cli".parse().unwrap());
+        let redacted: serde_json::Value =
+            serde_json::from_str(&service.redact_headers(&headers)).unwrap();
+        assert_eq!(redacted["x-api-key"], REDACTED);
+        assert_eq!(redacted["user-agent"], "claude-cli");
+    }
+}
@@ -0,0 +1,389 @@
+//! 客户端 API Key 管理服务
+//!
+//! 负责客户端 Key 的生成、CRUD、请求认证和每分钟限流。
+//! 明文 Key 只在创建时返回一次，数据库中仅保存 SHA-256 哈希。
+
+use crate::database::dao::client_api_keys::{ClientApiKey, ClientApiKeyDao};
+use crate::database::DbConnection;
+use chrono::Utc;
+use dashmap::DashMap;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// 客户端 Key 前缀
+const KEY_PREFIX: &str = "pc-";
+/// 展示用前缀长度（含 `pc-`）
+const DISPLAY_PREFIX_LEN: usize = 11;
+/// 限流窗口
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// 创建客户端 Key 请求
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct CreateClientApiKeyRequest {
+    pub name: String,
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    #[serde(default)]
+    pub allowed_providers: Vec<String>,
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+/// 更新客户端 Key 请求（字段为空表示不修改）
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateClientApiKeyRequest {
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+    pub allowed_models: Option<Vec<String>>,
+    pub allowed_providers: Option<Vec<String>>,
+    /// 传 0 表示取消限流
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+/// 创建结果（包含仅返回一次的明文 Key）
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedClientApiKey {
+    #[serde(flatten)]
+    pub key: ClientApiKey,
+    pub api_key: String,
+}
+
+/// 客户端 Key 认证错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ClientKeyError {
+    #[error("API key is disabled")]
+    Disabled,
+    #[error("Rate limit exceeded, retry after {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+    #[error("Key store error: {0}")]
+    Storage(String),
+}
+
+/// 单个 Key 的固定窗口计数
+#[derive(Debug, Clone, Copy)]
+struct RateWindow {
+    started_at: Instant,
+    count: u32,
+}
+
+/// 客户端 API Key 服务
+#[derive(Debug, Default)]
+pub struct ClientApiKeyService {
+    rate_windows: DashMap<String, RateWindow>,
+}
+
+impl ClientApiKeyService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 计算 Key 的哈希
+    pub fn hash_key(api_key: &str) -> String {
+        hex::encode(Sha256::digest(api_key.as_bytes()))
+    }
+
+    /// 生成新的明文 Key
+    fn generate_key() -> String {
+        let mut bytes = [0u8; 24];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        format!("{}{}", KEY_PREFIX, hex::encode(bytes))
+    }
+
+    /// 获取所有客户端 Key
+    pub fn list(&self, db: &DbConnection) -> Result<Vec<ClientApiKey>, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ClientApiKeyDao::get_all(&conn).map_err(|e| e.to_string())
+    }
+
+    /// 创建客户端 Key
+    pub fn create(
+        &self,
+        db: &DbConnection,
+        request: CreateClientApiKeyRequest,
+    ) -> Result<CreatedClientApiKey, String> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err("Key 名称不能为空".to_string());
+        }
+
+        let api_key = Self::generate_key();
+        let key = ClientApiKey {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            key_hash: Self::hash_key(&api_key),
+            key_prefix: api_key[..DISPLAY_PREFIX_LEN].to_string(),
+            enabled: true,
+            allowed_models: normalize_scopes(request.allowed_models),
+            allowed_providers: normalize_scopes(request.allowed_providers),
+            rate_limit_per_minute: request.rate_limit_per_minute.filter(|n| *n > 0),
+            usage_count: 0,
+            last_used_at: None,
+            created_at: Utc::now(),
+        };
+
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ClientApiKeyDao::insert(&conn, &key).map_err(|e| e.to_string())?;
+        tracing::info!(
+            "[CLIENT_KEY] 创建客户端 Key: {} ({})",
+            key.name,
+            key.key_prefix
+        );
+
+        Ok(CreatedClientApiKey { key, api_key })
+    }
+
+    /// 更新客户端 Key
+    pub fn update(
+        &self,
+        db: &DbConnection,
+        id: &str,
+        request: UpdateClientApiKeyRequest,
+    ) -> Result<ClientApiKey, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let mut key = ClientApiKeyDao::get_by_id(&conn, id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("客户端 Key 不存在: {}", id))?;
+
+        if let Some(name) = request.name {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err("Key 名称不能为空".to_string());
+            }
+            key.name = name.to_string();
+        }
+        if let Some(enabled) = request.enabled {
+            key.enabled = enabled;
+        }
+        if let Some(models) = request.allowed_models {
+            key.allowed_models = normalize_scopes(models);
+        }
+        if let Some(providers) = request.allowed_providers {
+            key.allowed_providers = normalize_scopes(providers);
+        }
+        if let Some(limit) = request.rate_limit_per_minute {
+            key.rate_limit_per_minute = Some(limit).filter(|n| *n > 0);
+            self.rate_windows.remove(&key.id);
+        }
+
+        ClientApiKeyDao::update(&conn, &key).map_err(|e| e.to_string())?;
+        Ok(key)
+    }
+
+    /// 删除客户端 Key
+    pub fn delete(&self, db: &DbConnection, id: &str) -> Result<bool, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let deleted = ClientApiKeyDao::delete(&conn, id).map_err(|e| e.to_string())?;
+        self.rate_windows.remove(id);
+        Ok(deleted)
+    }
+
+    /// 认证客户端 Key
+    ///
+    /// 返回 `Ok(None)` 表示 Key 不存在；认证成功时计入限流窗口并记录使用次数。
+    pub fn authenticate(
+        &self,
+        db: &DbConnection,
+        api_key: &str,
+    ) -> Result<Option<ClientApiKey>, ClientKeyError> {
+        let conn = db
+            .lock()
+            .map_err(|e| ClientKeyError::Storage(e.to_string()))?;
+        let Some(key) = ClientApiKeyDao::get_by_hash(&conn, &Self::hash_key(api_key))
+            .map_err(|e| ClientKeyError::Storage(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        if !key.enabled {
+            return Err(ClientKeyError::Disabled);
+        }
+        if let Some(limit) = key.rate_limit_per_minute {
+            self.check_rate_limit(&key.id, limit, Instant::now())?;
+        }
+
+        if let Err(e) = ClientApiKeyDao::record_usage(&conn, &key.id, Utc::now()) {
+            tracing::warn!("[CLIENT_KEY] 记录 Key 使用失败: {}", e);
+        }
+        Ok(Some(key))
+    }
+
+    /// 已认证 Key 的限流检查
+    ///
+    /// 用于认证一次、之后发送多个请求的长连接（如 WebSocket），每个请求都计入 Key 的限流窗口。
+    pub fn check_key_rate_limit(&self, key: &ClientApiKey) -> Result<(), ClientKeyError> {
+        match key.rate_limit_per_minute {
+            Some(limit) => self.check_rate_limit(&key.id, limit, Instant::now()),
+            None => Ok(()),
+        }
+    }
+
+    /// 选择器端点限流检查
+    ///
+    /// 与客户端 Key 共用固定窗口计数，按 `selector:<端点名>` 区分；`limit` 为 0 表示不限流。
+    pub fn check_selector_rate_limit(
+        &self,
+        selector: &str,
+        limit: u32,
+    ) -> Result<(), ClientKeyError> {
+        if limit == 0 {
+            return Ok(());
+        }
+        self.check_rate_limit(&format!("selector:{}", selector), limit, Instant::now())
+    }
+
+    /// 固定窗口限流检查，通过时计数加一
+    fn check_rate_limit(&self, id: &str, limit: u32, now: Instant) -> Result<(), ClientKeyError> {
+        let mut window = self
+            .rate_windows
+            .entry(id.to_string())
+            .or_insert(RateWindow {
+                started_at: now,
+                count: 0,
+            });
+
+        let elapsed = now.duration_since(window.started_at);
+        if elapsed >= RATE_LIMIT_WINDOW {
+            *window = RateWindow {
+                started_at: now,
+                count: 0,
+            };
+        } else if window.count >= limit {
+            let retry_after = RATE_LIMIT_WINDOW.saturating_sub(elapsed);
+            return Err(ClientKeyError::RateLimited {
+                retry_after_secs: retry_after.as_secs().max(1),
+            });
+        }
+
+        window.count += 1;
+        Ok(())
+    }
+}
+
+/// 去除空白项和重复项
+fn normalize_scopes(scopes: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for scope in scopes {
+        let scope = scope.trim();
+        if !scope.is_empty() && !result.iter().any(|s| s == scope) {
+            result.push(scope.to_string());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DbPool;
+    use std::sync::Arc;
+
+    fn test_db() -> DbConnection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        Arc::new(DbPool::from_connection(conn))
+    }
+
+    #[test]
+    fn test_create_and_authenticate() {
+        let db = test_db();
+        let service = ClientApiKeyService::new();
+        let created = service
+            .create(
+                &db,
+                CreateClientApiKeyRequest {
+                    name: "ci".to_string(),
+                    allowed_models: vec![" claude-* ".to_string(), String::new()],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert!(created.api_key.starts_with(KEY_PREFIX));
+        assert!(created.api_key.starts_with(&created.key.key_prefix));
+        assert_eq!(created.key.allowed_models, vec!["claude-*".to_string()]);
+
+        let key = service
+            .authenticate(&db, &created.api_key)
+            .unwrap()
+            .unwrap();
+        assert_eq!(key.id, created.key.id);
+        assert!(service.authenticate(&db, "pc-unknown").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_disabled_key_rejected() {
+        let db = test_db();
+        let service = ClientApiKeyService::new();
+        let created = service
+            .create(
+                &db,
+                CreateClientApiKeyRequest {
+                    name: "ci".to_string(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        service
+            .update(
+                &db,
+                &created.key.id,
+                UpdateClientApiKeyRequest {
+                    enabled: Some(false),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            service.authenticate(&db, &created.api_key).unwrap_err(),
+            ClientKeyError::Disabled
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_window() {
+        let service = ClientApiKeyService::new();
+        let start = Instant::now();
+        assert!(service.check_rate_limit("k", 2, start).is_ok());
+        assert!(service.check_rate_limit("k", 2, start).is_ok());
+        assert!(matches!(
+            service.check_rate_limit("k", 2, start),
+            Err(ClientKeyError::RateLimited { .. })
+        ));
+
+        // 窗口结束后重新计数
+        assert!(service
+            .check_rate_limit("k", 2, start + RATE_LIMIT_WINDOW)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_key_rate_limit_shares_auth_window() {
+        let db = test_db();
+        let service = ClientApiKeyService::new();
+        let created = service
+            .create(
+                &db,
+                CreateClientApiKeyRequest {
+                    name: "ws".to_string(),
+                    rate_limit_per_minute: Some(2),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // 认证计入一次，同一连接上的后续请求继续计数
+        let key = service
+            .authenticate(&db, &created.api_key)
+            .unwrap()
+            .unwrap();
+        assert!(service.check_key_rate_limit(&key).is_ok());
+        assert!(matches!(
+            service.check_key_rate_limit(&key),
+            Err(ClientKeyError::RateLimited { .. })
+        ));
+    }
+}
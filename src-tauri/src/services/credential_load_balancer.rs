@@ -0,0 +1,253 @@
+//! 凭证池负载均衡
+//!
+//! 维护每个凭证的运行时指标（并发请求数、响应延迟），
+//! 并根据配置的策略从候选凭证中选出一个。
+
+use crate::config::LoadBalanceStrategy;
+use crate::models::provider_pool_model::ProviderCredential;
+use dashmap::DashMap;
+use rand::Rng;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 每个凭证保留的延迟样本数
+const LATENCY_WINDOW: usize = 100;
+
+/// 单个凭证的运行时指标
+#[derive(Debug, Default)]
+struct CredentialRuntime {
+    /// 进行中的请求数
+    in_flight: AtomicUsize,
+    /// 最近的响应延迟（毫秒）
+    latencies_ms: Mutex<VecDeque<u64>>,
+}
+
+impl CredentialRuntime {
+    fn p95_latency_ms(&self) -> Option<u64> {
+        let samples = self.latencies_ms.lock().ok()?;
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<u64> = samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = ((sorted.len() as f64) * 0.95).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1).min(sorted.len() - 1)])
+    }
+}
+
+/// 进行中请求的计数守卫
+///
+/// 请求结束（守卫被释放）时自动减少凭证的并发计数
+#[derive(Debug)]
+pub struct InFlightGuard {
+    runtime: Arc<CredentialRuntime>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.runtime.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 凭证负载均衡器
+#[derive(Debug, Default)]
+pub struct CredentialLoadBalancer {
+    strategy: std::sync::RwLock<LoadBalanceStrategy>,
+    runtime: DashMap<String, Arc<CredentialRuntime>>,
+}
+
+impl CredentialLoadBalancer {
+    pub fn new(strategy: LoadBalanceStrategy) -> Self {
+        Self {
+            strategy: std::sync::RwLock::new(strategy),
+            runtime: DashMap::new(),
+        }
+    }
+
+    /// 获取当前策略
+    pub fn strategy(&self) -> LoadBalanceStrategy {
+        self.strategy.read().map(|s| *s).unwrap_or_default()
+    }
+
+    /// 设置策略（支持热重载）
+    pub fn set_strategy(&self, strategy: LoadBalanceStrategy) {
+        if let Ok(mut current) = self.strategy.write() {
+            if *current != strategy {
+                tracing::info!(
+                    "[LOAD_BALANCE] 负载均衡策略: {:?} -> {:?}",
+                    *current,
+                    strategy
+                );
+                *current = strategy;
+            }
+        }
+    }
+
+    /// 标记凭证开始处理请求
+    pub fn begin_request(&self, uuid: &str) -> InFlightGuard {
+        let runtime = self.runtime_of(uuid);
+        runtime.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard { runtime }
+    }
+
+    /// 记录凭证的一次响应延迟
+    pub fn record_latency(&self, uuid: &str, latency: Duration) {
+        let runtime = self.runtime_of(uuid);
+        if let Ok(mut samples) = runtime.latencies_ms.lock() {
+            if samples.len() >= LATENCY_WINDOW {
+                samples.pop_front();
+            }
+            samples.push_back(latency.as_millis() as u64);
+        }
+    }
+
+    /// 获取凭证当前进行中的请求数
+    pub fn in_flight(&self, uuid: &str) -> usize {
+        self.runtime
+            .get(uuid)
+            .map(|r| r.in_flight.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// 获取凭证最近响应延迟的 P95（毫秒）
+    pub fn p95_latency_ms(&self, uuid: &str) -> Option<u64> {
+        self.runtime.get(uuid).and_then(|r| r.p95_latency_ms())
+    }
+
+    /// 按权重随机选择
+    ///
+    /// 权重为 0 的凭证只在所有候选权重都为 0 时参与选择
+    pub fn pick_weighted(&self, credentials: &[ProviderCredential]) -> usize {
+        let total: u64 = credentials.iter().map(|c| c.weight as u64).sum();
+        if total == 0 {
+            return rand::thread_rng().gen_range(0..credentials.len());
+        }
+        let mut point = rand::thread_rng().gen_range(0..total);
+        for (index, cred) in credentials.iter().enumerate() {
+            let weight = cred.weight as u64;
+            if point < weight {
+                return index;
+            }
+            point -= weight;
+        }
+        credentials.len() - 1
+    }
+
+    /// 选择进行中请求数最少的凭证（相同时按权重、使用次数）
+    pub fn pick_least_connections(&self, credentials: &[ProviderCredential]) -> usize {
+        Self::min_index_by_key(credentials, |c| {
+            (
+                self.in_flight(&c.uuid),
+                std::cmp::Reverse(c.weight),
+                c.usage_count,
+            )
+        })
+    }
+
+    /// 选择最久未出错的凭证，从未出错的凭证优先
+    pub fn pick_least_recent_error(&self, credentials: &[ProviderCredential]) -> usize {
+        Self::min_index_by_key(credentials, |c| {
+            (
+                c.last_error_time.map(|t| t.timestamp()).unwrap_or(i64::MIN),
+                c.error_count,
+                c.usage_count,
+            )
+        })
+    }
+
+    /// 选择 P95 延迟最低的凭证
+    ///
+    /// 没有延迟样本的凭证优先，以便尽快采集到数据
+    pub fn pick_lowest_p95_latency(&self, credentials: &[ProviderCredential]) -> usize {
+        Self::min_index_by_key(credentials, |c| {
+            (
+                self.p95_latency_ms(&c.uuid).unwrap_or(0),
+                self.in_flight(&c.uuid),
+                c.usage_count,
+            )
+        })
+    }
+
+    fn runtime_of(&self, uuid: &str) -> Arc<CredentialRuntime> {
+        self.runtime
+            .entry(uuid.to_string())
+            .or_default()
+            .value()
+            .clone()
+    }
+
+    fn min_index_by_key<K: Ord>(
+        credentials: &[ProviderCredential],
+        key: impl Fn(&ProviderCredential) -> K,
+    ) -> usize {
+        credentials
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, c)| key(c))
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::{CredentialData, PoolProviderType};
+
+    fn cred(weight: u32) -> ProviderCredential {
+        let mut cred = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+        cred.weight = weight;
+        cred
+    }
+
+    #[test]
+    fn test_weighted_skips_zero_weight() {
+        let lb = CredentialLoadBalancer::default();
+        let creds = vec![cred(0), cred(5), cred(0)];
+        for _ in 0..50 {
+            assert_eq!(lb.pick_weighted(&creds), 1);
+        }
+    }
+
+    #[test]
+    fn test_least_connections_uses_in_flight() {
+        let lb = CredentialLoadBalancer::default();
+        let creds = vec![cred(1), cred(1)];
+        let guard = lb.begin_request(&creds[0].uuid);
+        assert_eq!(lb.in_flight(&creds[0].uuid), 1);
+        assert_eq!(lb.pick_least_connections(&creds), 1);
+
+        drop(guard);
+        assert_eq!(lb.in_flight(&creds[0].uuid), 0);
+    }
+
+    #[test]
+    fn test_least_recent_error_prefers_never_failed() {
+        let lb = CredentialLoadBalancer::default();
+        let mut failed = cred(1);
+        failed.last_error_time = Some(chrono::Utc::now());
+        let creds = vec![failed, cred(1)];
+        assert_eq!(lb.pick_least_recent_error(&creds), 1);
+    }
+
+    #[test]
+    fn test_p95_latency() {
+        let lb = CredentialLoadBalancer::default();
+        let creds = vec![cred(1), cred(1)];
+        for ms in 1..=100 {
+            lb.record_latency(&creds[0].uuid, Duration::from_millis(ms));
+            lb.record_latency(&creds[1].uuid, Duration::from_millis(ms * 2));
+        }
+        assert_eq!(lb.p95_latency_ms(&creds[0].uuid), Some(95));
+        assert_eq!(lb.p95_latency_ms(&creds[1].uuid), Some(190));
+        assert_eq!(lb.pick_lowest_p95_latency(&creds), 0);
+    }
+}
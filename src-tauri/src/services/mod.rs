@@ -1,19 +1,30 @@
+pub mod alert_service;
 pub mod api_key_provider_service;
+pub mod audit_log_service;
 pub mod backup_service;
+pub mod client_api_key_service;
 pub mod context_memory_service;
+pub mod credential_load_balancer;
 pub mod file_browser_service;
 pub mod general_chat;
+pub mod http_client_service;
 pub mod kiro_event_service;
 pub mod live_sync;
 pub mod machine_id_service;
 pub mod mcp_service;
 pub mod mcp_sync;
+pub mod model_discovery_service;
 pub mod model_registry_service;
 pub mod model_service;
 pub mod prompt_service;
 pub mod prompt_sync;
 pub mod provider_pool_service;
+pub mod request_log_service;
+pub mod response_cache_service;
+pub mod secret_store;
+pub mod session_affinity;
 pub mod session_context_service;
+pub mod shared_storage_service;
 pub mod skill_service;
 pub mod switch;
 pub mod sysinfo_service;
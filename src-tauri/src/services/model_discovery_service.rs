@@ -0,0 +1,339 @@
+//! 代理模型发现服务
+//!
+//! 汇总所有启用凭证支持的模型，供 `/v1/models` 使用：
+//! - API Key 类凭证从上游 `/models` 接口动态获取
+//! - 无法动态获取的凭证（OAuth 等）使用凭证中保存的模型列表或 Provider 默认列表
+//!
+//! 每个凭证的结果单独缓存，过期后在下次请求时刷新；上游获取失败时沿用静态列表。
+
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::model_registry::{ModelCapabilities, ModelSource};
+use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::services::model_service::ModelService;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// 单个凭证模型列表的缓存时间
+const MODEL_CACHE_TTL: Duration = Duration::from_secs(600);
+
+/// `/v1/models` 中的单个模型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredModel {
+    pub id: String,
+    /// 固定为 "model"
+    pub object: String,
+    /// 列表生成时间（Unix 秒）
+    pub created: i64,
+    /// 模型拥有者（如 "anthropic", "google", "openai"）
+    pub owned_by: String,
+    /// 提供该模型的 Provider 类型
+    pub providers: Vec<String>,
+    /// 模型列表来源（任一凭证从上游获取即为 api）
+    pub source: ModelSource,
+    /// 推断的模型能力
+    pub capabilities: ModelCapabilities,
+}
+
+/// 凭证模型列表缓存
+#[derive(Debug, Clone)]
+struct CachedModels {
+    models: Vec<String>,
+    source: ModelSource,
+    fetched_at: Instant,
+}
+
+/// 代理模型发现服务
+pub struct ModelDiscoveryService {
+    model_service: ModelService,
+    cache: RwLock<HashMap<String, CachedModels>>,
+    ttl: Duration,
+}
+
+impl Default for ModelDiscoveryService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModelDiscoveryService {
+    pub fn new() -> Self {
+        Self {
+            model_service: ModelService::new(),
+            cache: RwLock::new(HashMap::new()),
+            ttl: MODEL_CACHE_TTL,
+        }
+    }
+
+    /// 清空缓存，下次查询时重新获取
+    pub fn invalidate(&self) {
+        if let Ok(mut cache) = self.cache.write() {
+            cache.clear();
+        }
+    }
+
+    /// 汇总所有启用凭证的模型
+    pub async fn list_models(&self, db: &DbConnection) -> Result<Vec<DiscoveredModel>, String> {
+        let credentials: Vec<ProviderCredential> = {
+            let conn = db.lock().map_err(|e| e.to_string())?;
+            ProviderPoolDao::get_all(&conn)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .filter(|c| !c.is_disabled)
+                .collect()
+        };
+
+        // 清理已删除凭证的缓存
+        if let Ok(mut cache) = self.cache.write() {
+            cache.retain(|uuid, _| credentials.iter().any(|c| &c.uuid == uuid));
+        }
+
+        let results = join_all(
+            credentials
+                .iter()
+                .map(|credential| self.credential_models(credential)),
+        )
+        .await;
+
+        Ok(aggregate_models(credentials.iter().zip(results)))
+    }
+
+    /// 获取单个凭证的模型列表（优先使用缓存）
+    async fn credential_models(&self, credential: &ProviderCredential) -> CachedModels {
+        if let Some(cached) = self
+            .cache
+            .read()
+            .ok()
+            .and_then(|cache| cache.get(&credential.uuid).cloned())
+            .filter(|cached| cached.fetched_at.elapsed() < self.ttl)
+        {
+            return cached;
+        }
+
+        let fetched = if supports_upstream_listing(&credential.credential) {
+            match self
+                .model_service
+                .fetch_models_for_credential(credential)
+                .await
+            {
+                Ok(models) if !models.is_empty() => Some(models),
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::warn!(
+                        "[MODELS] 获取凭证 {} 的模型列表失败，使用静态列表: {}",
+                        credential.uuid,
+                        e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let cached = match fetched {
+            Some(models) => CachedModels {
+                models,
+                source: ModelSource::Api,
+                fetched_at: Instant::now(),
+            },
+            None => CachedModels {
+                models: self.static_models(credential),
+                source: ModelSource::Local,
+                fetched_at: Instant::now(),
+            },
+        };
+
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(credential.uuid.clone(), cached.clone());
+        }
+        cached
+    }
+
+    /// 凭证中保存的模型列表，没有时使用 Provider 默认列表
+    fn static_models(&self, credential: &ProviderCredential) -> Vec<String> {
+        if !credential.supported_models.is_empty() {
+            credential.supported_models.clone()
+        } else {
+            self.model_service
+                .get_default_models_for_provider(&credential.provider_type)
+        }
+    }
+}
+
+/// 是否可以从上游动态获取模型列表
+fn supports_upstream_listing(credential: &CredentialData) -> bool {
+    matches!(
+        credential,
+        CredentialData::OpenAIKey { .. }
+            | CredentialData::ClaudeKey { .. }
+            | CredentialData::AnthropicKey { .. }
+            | CredentialData::GeminiApiKey { .. }
+            | CredentialData::AwsBedrock { .. }
+            | CredentialData::Ollama { .. }
+    )
+}
+
+/// 按模型 ID 合并各凭证的模型列表（排除凭证黑名单中的模型）
+fn aggregate_models<'a>(
+    entries: impl Iterator<Item = (&'a ProviderCredential, CachedModels)>,
+) -> Vec<DiscoveredModel> {
+    let created = chrono::Utc::now().timestamp();
+    let mut models: BTreeMap<String, DiscoveredModel> = BTreeMap::new();
+
+    for (credential, cached) in entries {
+        let provider = credential.provider_type.to_string();
+        for id in cached.models {
+            if credential.not_supported_models.contains(&id) {
+                continue;
+            }
+            let model = models.entry(id.clone()).or_insert_with(|| DiscoveredModel {
+                owned_by: infer_owner(&id, &provider),
+                capabilities: infer_capabilities(&id),
+                id,
+                object: "model".to_string(),
+                created,
+                providers: Vec::new(),
+                source: cached.source.clone(),
+            });
+            if !model.providers.contains(&provider) {
+                model.providers.push(provider.clone());
+            }
+            if cached.source == ModelSource::Api {
+                model.source = ModelSource::Api;
+            }
+        }
+    }
+
+    models.into_values().collect()
+}
+
+/// 根据模型名推断拥有者，无法识别时使用 Provider 类型
+fn infer_owner(model: &str, provider: &str) -> String {
+    let model = model.to_lowercase();
+    let owner = if model.starts_with("claude") || model.starts_with("gemini-claude") {
+        "anthropic"
+    } else if model.starts_with("gemini")
+        || model.starts_with("text-embedding-00")
+        || model.starts_with("imagen")
+    {
+        "google"
+    } else if model.starts_with("gpt")
+        || model.starts_with("o1")
+        || model.starts_with("o3")
+        || model.starts_with("o4")
+        || model.starts_with("text-embedding")
+        || model.starts_with("codex")
+    {
+        "openai"
+    } else if model.starts_with("qwen") {
+        "alibaba"
+    } else if model.starts_with("deepseek") {
+        "deepseek"
+    } else {
+        provider
+    };
+    owner.to_string()
+}
+
+/// 根据模型名推断能力
+fn infer_capabilities(model: &str) -> ModelCapabilities {
+    let model = model.to_lowercase();
+    if model.contains("embedding") {
+        return ModelCapabilities::default();
+    }
+
+    let reasoning = model.contains("thinking")
+        || model.contains("reasoner")
+        || model.starts_with("o1")
+        || model.starts_with("o3")
+        || model.starts_with("o4")
+        || model.starts_with("gemini-2.5")
+        || model.starts_with("gemini-3")
+        || model.starts_with("claude-sonnet-4")
+        || model.starts_with("claude-opus-4")
+        || model.starts_with("claude-3-7");
+    let vision = model.starts_with("claude")
+        || model.starts_with("gemini")
+        || model.starts_with("gpt-4o")
+        || model.starts_with("gpt-4.1")
+        || model.starts_with("gpt-5")
+        || model.contains("-vl");
+
+    ModelCapabilities {
+        vision,
+        tools: true,
+        streaming: true,
+        json_mode: !model.starts_with("claude"),
+        function_calling: true,
+        reasoning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::provider_pool_model::PoolProviderType;
+
+    fn credential(provider_type: PoolProviderType, excluded: &[&str]) -> ProviderCredential {
+        let mut credential = ProviderCredential::new(
+            provider_type,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            },
+        );
+        credential.not_supported_models = excluded.iter().map(|s| s.to_string()).collect();
+        credential
+    }
+
+    fn cached(models: &[&str], source: ModelSource) -> CachedModels {
+        CachedModels {
+            models: models.iter().map(|s| s.to_string()).collect(),
+            source,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_aggregate_merges_providers_and_excludes() {
+        let openai = credential(PoolProviderType::OpenAI, &["gpt-3.5-turbo"]);
+        let kiro = credential(PoolProviderType::Kiro, &[]);
+        let models = aggregate_models(
+            vec![
+                (
+                    &openai,
+                    cached(
+                        &["gpt-4o", "gpt-3.5-turbo", "claude-sonnet-4-5"],
+                        ModelSource::Api,
+                    ),
+                ),
+                (&kiro, cached(&["claude-sonnet-4-5"], ModelSource::Local)),
+            ]
+            .into_iter(),
+        );
+
+        let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["claude-sonnet-4-5", "gpt-4o"]);
+
+        let claude = &models[0];
+        assert_eq!(claude.owned_by, "anthropic");
+        assert_eq!(
+            claude.providers,
+            vec!["openai".to_string(), "kiro".to_string()]
+        );
+        assert_eq!(claude.source, ModelSource::Api);
+        assert!(claude.capabilities.vision);
+    }
+
+    #[test]
+    fn test_infer_owner_and_capabilities() {
+        assert_eq!(infer_owner("gemini-2.5-pro", "antigravity"), "google");
+        assert_eq!(infer_owner("my-local-model", "ollama"), "ollama");
+        assert!(infer_capabilities("o3-mini").reasoning);
+        assert!(!infer_capabilities("text-embedding-3-small").tools);
+    }
+}
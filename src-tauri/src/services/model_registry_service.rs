@@ -523,6 +523,26 @@ impl ModelRegistryService {
         Ok(())
     }
 
+    /// 从数据库读取模型的上下文长度
+    ///
+    /// 供代理请求路径使用，不依赖已初始化的服务实例（无界面模式不加载模型注册表）。
+    /// 先按 ID 精确匹配，再匹配带 Provider 前缀的 ID（如 `anthropic/claude-sonnet-4.5`）。
+    pub fn context_length_from_db(conn: &rusqlite::Connection, model_id: &str) -> Option<u32> {
+        let limits_json: String = conn
+            .query_row(
+                "SELECT limits FROM model_registry
+                 WHERE id = ?1 OR id LIKE '%/' || ?1
+                 ORDER BY id = ?1 DESC
+                 LIMIT 1",
+                params![model_id],
+                |row| row.get(0),
+            )
+            .ok()?;
+        serde_json::from_str::<ModelLimits>(&limits_json)
+            .ok()?
+            .context_length
+    }
+
     /// 获取所有模型
     pub async fn get_all_models(&self) -> Vec<EnhancedModelMetadata> {
         self.models_cache.read().await.clone()
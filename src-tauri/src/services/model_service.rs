@@ -84,7 +84,8 @@ impl ModelService {
             CredentialData::KiroOAuth { .. }
             | CredentialData::GeminiOAuth { .. }
             | CredentialData::CodexOAuth { .. }
-            | CredentialData::ClaudeOAuth { .. } => {
+            | CredentialData::ClaudeOAuth { .. }
+            | CredentialData::QwenOAuth { .. } => {
                 tracing::info!("[MODEL_SERVICE] OAuth 凭证使用默认模型列表");
                 Ok(self.get_default_models_for_provider(&credential.provider_type))
             }
@@ -92,7 +93,22 @@ impl ModelService {
             // API Key 类型凭证：直接调用 Provider 的 API
             CredentialData::OpenAIKey { base_url, api_key } => {
                 tracing::info!("[MODEL_SERVICE] 使用 OpenAI API Key");
-                self.fetch_models_openai(base_url.as_deref(), api_key).await
+                let result = self.fetch_models_openai(base_url.as_deref(), api_key).await;
+                // 内置预设（Mistral、DeepSeek 等）在上游列表不可用时使用已知模型
+                let preset = base_url
+                    .as_deref()
+                    .and_then(crate::providers::openai_presets::find_preset_for_base_url);
+                match (result, preset) {
+                    (Err(e), Some(preset)) => {
+                        tracing::warn!(
+                            "[MODEL_SERVICE] 获取 {} 模型列表失败，使用预设模型: {}",
+                            preset.name,
+                            e
+                        );
+                        Ok(preset.models.iter().map(|m| m.to_string()).collect())
+                    }
+                    (result, _) => result,
+                }
             }
             CredentialData::ClaudeKey { base_url, api_key } => {
                 tracing::info!("[MODEL_SERVICE] 使用 Claude API Key");
@@ -109,11 +125,49 @@ impl ModelService {
                 tracing::info!("[MODEL_SERVICE] 使用 Gemini API Key");
                 self.fetch_models_gemini(base_url.as_deref(), api_key).await
             }
-            CredentialData::VertexKey { .. } => {
+            CredentialData::VertexKey { .. } | CredentialData::VertexServiceAccount { .. } => {
                 tracing::info!("[MODEL_SERVICE] Vertex AI 使用固定模型列表");
                 // Vertex AI 使用固定的模型列表
                 Ok(self.get_default_models_for_provider(&credential.provider_type))
             }
+            CredentialData::AzureOpenaiKey {
+                model_deployments, ..
+            } => {
+                // Azure 按部署调用，模型列表即为已映射的模型名
+                tracing::info!("[MODEL_SERVICE] Azure OpenAI 使用部署映射的模型列表");
+                let mut models: Vec<String> = model_deployments
+                    .keys()
+                    .filter(|m| !m.contains('*'))
+                    .cloned()
+                    .collect();
+                if models.is_empty() {
+                    models = self.get_default_models_for_provider(&credential.provider_type);
+                }
+                models.sort();
+                Ok(models)
+            }
+            CredentialData::AwsBedrock { .. } => {
+                tracing::info!("[MODEL_SERVICE] 使用 AWS Bedrock ListFoundationModels");
+                let config = crate::providers::bedrock::BedrockConfig::from_credential(
+                    &credential.credential,
+                )
+                .ok_or_else(|| "无效的 Bedrock 凭证".to_string())?;
+                crate::providers::bedrock::BedrockProvider::new(config)
+                    .list_models()
+                    .await
+                    .map_err(|e| format!("获取 Bedrock 模型列表失败: {}", e))
+            }
+            CredentialData::Ollama { base_url } => {
+                tracing::info!("[MODEL_SERVICE] 获取本地模型列表");
+                crate::providers::OllamaProvider::new(base_url.clone())
+                    .list_models()
+                    .await
+                    .map_err(|e| format!("获取本地模型列表失败: {}", e))
+            }
+            CredentialData::Mock { .. } => {
+                // Mock 接受任意模型名
+                Ok(self.get_default_models_for_provider(&credential.provider_type))
+            }
         }
     }
 
@@ -386,6 +440,16 @@ impl ModelService {
             PoolProviderType::GeminiApiKey => {
                 vec!["gemini-2.5-flash".to_string(), "gemini-2.5-pro".to_string()]
             }
+            PoolProviderType::Qwen => crate::providers::qwen::QWEN_MODELS
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            PoolProviderType::AzureOpenai => vec!["gpt-4o".to_string(), "gpt-4o-mini".to_string()],
+            PoolProviderType::AwsBedrock => crate::providers::bedrock::BEDROCK_MODELS
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            PoolProviderType::Mock => vec!["mock-model".to_string()],
             _ => vec![],
         }
     }
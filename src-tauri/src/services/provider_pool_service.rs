@@ -4,23 +4,28 @@
 
 #![allow(dead_code)]
 
+use crate::config::LoadBalanceStrategy;
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::models::provider_pool_model::{
-    get_default_check_model, get_oauth_creds_path, CredentialData, CredentialDisplay,
-    HealthCheckResult, OAuthStatus, PoolProviderType, PoolStats, ProviderCredential,
-    ProviderPoolOverview,
+    get_default_check_model, get_oauth_creds_path, resolve_azure_deployment, CredentialData,
+    CredentialDisplay, HealthCheckResult, OAuthStatus, PoolProviderType, PoolStats,
+    ProviderCredential, ProviderPoolOverview,
 };
 use crate::models::route_model::RouteInfo;
 use crate::providers::antigravity::TokenRefreshError;
 use crate::providers::kiro::KiroProvider;
+use crate::resilience::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
 use crate::services::api_key_provider_service::ApiKeyProviderService;
+use crate::services::credential_load_balancer::{CredentialLoadBalancer, InFlightGuard};
+use crate::services::session_affinity::SessionAffinity;
 use chrono::Utc;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::atomic::AtomicUsize;
 use std::time::Duration;
+use tokio::sync::broadcast;
 
 /// 凭证健康信息
 /// Requirements: 3.1, 3.2
@@ -44,6 +49,25 @@ pub struct CredentialHealthInfo {
     pub requires_reauth: bool,
 }
 
+impl CredentialHealthInfo {
+    fn from_credential(c: &ProviderCredential) -> Self {
+        Self {
+            uuid: c.uuid.clone(),
+            name: c.name.clone(),
+            provider_type: c.provider_type.to_string(),
+            is_healthy: c.is_healthy,
+            last_error: c.last_error_message.clone(),
+            last_error_time: c.last_error_time.map(|t| t.to_rfc3339()),
+            failure_count: c.error_count,
+            requires_reauth: c
+                .last_error_message
+                .as_ref()
+                .map(|e| e.contains("invalid_grant") || e.contains("重新授权"))
+                .unwrap_or(false),
+        }
+    }
+}
+
 /// 凭证选择错误
 /// Requirements: 3.4
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +90,14 @@ pub struct ProviderPoolService {
     max_error_count: u32,
     /// 健康检查超时时间
     health_check_timeout: Duration,
+    /// 按凭证 UUID 维护的熔断器
+    circuit_breaker: CircuitBreaker,
+    /// 负载均衡策略及凭证运行时指标
+    load_balancer: CredentialLoadBalancer,
+    /// 会话到凭证的粘性绑定
+    session_affinity: SessionAffinity,
+    /// 凭证健康状态变化推送通道
+    health_events: broadcast::Sender<CredentialHealthInfo>,
 }
 
 impl Default for ProviderPoolService {
@@ -76,17 +108,114 @@ impl Default for ProviderPoolService {
 
 impl ProviderPoolService {
     pub fn new() -> Self {
+        let max_error_count = 3;
         Self {
             client: Client::builder()
                 .timeout(Duration::from_secs(30))
                 .build()
                 .unwrap_or_default(),
             round_robin_index: std::sync::RwLock::new(HashMap::new()),
-            max_error_count: 3,
+            max_error_count,
             health_check_timeout: Duration::from_secs(30),
+            circuit_breaker: CircuitBreaker::new(CircuitBreakerConfig {
+                failure_threshold: max_error_count,
+                ..CircuitBreakerConfig::default()
+            }),
+            load_balancer: CredentialLoadBalancer::default(),
+            session_affinity: SessionAffinity::default(),
+            health_events: broadcast::channel(256).0,
         }
     }
 
+    /// 订阅凭证健康状态变化（健康 ↔ 不健康）
+    pub fn subscribe_health_events(&self) -> broadcast::Receiver<CredentialHealthInfo> {
+        self.health_events.subscribe()
+    }
+
+    /// 推送凭证当前的健康状态
+    fn notify_health_change(&self, conn: &rusqlite::Connection, uuid: &str) {
+        if self.health_events.receiver_count() == 0 {
+            return;
+        }
+        if let Ok(Some(cred)) = ProviderPoolDao::get_by_uuid(conn, uuid) {
+            let _ = self
+                .health_events
+                .send(CredentialHealthInfo::from_credential(&cred));
+        }
+    }
+
+    /// 设置负载均衡策略（支持热重载）
+    pub fn set_load_balance_strategy(&self, strategy: LoadBalanceStrategy) {
+        self.load_balancer.set_strategy(strategy);
+    }
+
+    /// 获取当前负载均衡策略
+    pub fn load_balance_strategy(&self) -> LoadBalanceStrategy {
+        self.load_balancer.strategy()
+    }
+
+    /// 获取会话粘性路由绑定表
+    pub fn session_affinity(&self) -> &SessionAffinity {
+        &self.session_affinity
+    }
+
+    /// 标记凭证开始处理请求，返回的守卫释放时结束计数
+    pub fn begin_request(&self, uuid: &str) -> InFlightGuard {
+        self.load_balancer.begin_request(uuid)
+    }
+
+    /// 记录凭证的响应延迟
+    pub fn record_latency(&self, uuid: &str, latency: Duration) {
+        self.load_balancer.record_latency(uuid, latency);
+    }
+
+    /// 获取凭证熔断器
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.circuit_breaker
+    }
+
+    /// 判断凭证当前是否可被选中
+    ///
+    /// 因连续失败被熔断的凭证在冷却期内跳过，冷却结束后允许半开试探；
+    /// 其他原因（如需要重新授权）导致的不健康凭证仍不参与选择。
+    fn is_selectable(&self, cred: &ProviderCredential) -> bool {
+        if cred.is_disabled || !self.circuit_breaker.can_attempt(&cred.uuid) {
+            return false;
+        }
+        cred.is_healthy || self.circuit_breaker.state(&cred.uuid) != CircuitState::Closed
+    }
+
+    /// 从候选凭证中选出一个并占用熔断器放行名额
+    ///
+    /// `preferred` 在候选中且熔断器放行时直接选中（会话粘性路由），否则按负载均衡策略选择；
+    /// 半开试探名额被并发请求抢占时，跳过该凭证继续选择。
+    fn pick_credential(
+        &self,
+        mut available: Vec<ProviderCredential>,
+        round_robin_key: &str,
+        preferred: Option<&str>,
+    ) -> Option<ProviderCredential> {
+        if let Some(pos) = preferred.and_then(|uuid| available.iter().position(|c| c.uuid == uuid))
+        {
+            let selected = available.remove(pos);
+            if self.circuit_breaker.allow_request(&selected.uuid) {
+                return Some(selected);
+            }
+        }
+        while !available.is_empty() {
+            let selected = if available.len() == 1 {
+                available.remove(0)
+            } else {
+                self.select_by_strategy(&available, round_robin_key)
+            };
+            if self.circuit_breaker.allow_request(&selected.uuid) {
+                return Some(selected);
+            }
+            available.retain(|c| c.uuid != selected.uuid);
+        }
+        None
+    }
+
     /// 获取所有凭证概览
     pub fn get_overview(&self, db: &DbConnection) -> Result<Vec<ProviderPoolOverview>, String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
@@ -194,8 +323,12 @@ impl ProviderPoolService {
         if let Some(models) = not_supported_models {
             cred.not_supported_models = models;
         }
-        // 处理 proxy_url：空字符串表示清除，None 表示不修改
+        // 处理 proxy_url：空字符串表示清除，None 表示不修改，`direct` 表示绕过全局代理
         if let Some(p) = proxy_url {
+            let p = p.trim().to_string();
+            if !p.is_empty() && !p.eq_ignore_ascii_case(crate::proxy::DIRECT_PROXY) {
+                crate::proxy::ProxyClientFactory::parse_proxy_url(&p).map_err(|e| e.to_string())?;
+            }
             cred.proxy_url = if p.is_empty() { None } else { Some(p) };
         }
         cred.updated_at = Utc::now();
@@ -204,6 +337,20 @@ impl ProviderPoolService {
         Ok(cred)
     }
 
+    /// 更新凭证负载均衡权重
+    pub fn update_weight(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        weight: u32,
+    ) -> Result<ProviderCredential, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::update_weight(&conn, uuid, weight).map_err(|e| e.to_string())?;
+        ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Credential not found: {}", uuid))
+    }
+
     /// 删除凭证
     pub fn delete_credential(&self, db: &DbConnection, uuid: &str) -> Result<bool, String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
@@ -235,6 +382,44 @@ impl ProviderPoolService {
         provider_type: &str,
         model: Option<&str>,
         client_type: Option<&crate::server::client_detector::ClientType>,
+    ) -> Result<Option<ProviderCredential>, String> {
+        self.select_credential_excluding(db, provider_type, model, client_type, &[])
+    }
+
+    /// 选择凭证，跳过指定 UUID 的凭证
+    ///
+    /// 用于故障转移：同一请求中已尝试失败的凭证不会被再次选中
+    pub fn select_credential_excluding(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        model: Option<&str>,
+        client_type: Option<&crate::server::client_detector::ClientType>,
+        excluded_uuids: &[String],
+    ) -> Result<Option<ProviderCredential>, String> {
+        self.select_credential_for_session(
+            db,
+            provider_type,
+            model,
+            client_type,
+            excluded_uuids,
+            None,
+        )
+    }
+
+    /// 按会话粘性路由选择凭证
+    ///
+    /// 会话已绑定的凭证仍可用时优先选中；绑定的凭证不可用（不健康、熔断或已在本次请求中失败）
+    /// 时按负载均衡策略重新选择，并将会话改绑到新凭证。`session_key` 为 `None` 时等同于
+    /// [`Self::select_credential_excluding`]。
+    pub fn select_credential_for_session(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        model: Option<&str>,
+        client_type: Option<&crate::server::client_detector::ClientType>,
+        excluded_uuids: &[String],
+        session_key: Option<&str>,
     ) -> Result<Option<ProviderCredential>, String> {
         // 对于未知的 provider_type，直接返回 None（不是错误）
         // 这样可以让 select_credential_with_fallback 继续尝试智能降级
@@ -291,17 +476,19 @@ impl ProviderPoolService {
         // 过滤可用的凭证
         let mut available: Vec<_> = credentials
             .into_iter()
+            .filter(|c| !excluded_uuids.contains(&c.uuid))
             .filter(|c| {
-                let is_avail = c.is_available();
+                let is_avail = self.is_selectable(c);
                 if !is_avail {
                     eprintln!(
-                        "[SELECT_CREDENTIAL] credential {} (type={}) is_available={} (is_healthy={}, is_disabled={}, error_count={}, last_error={:?})",
+                        "[SELECT_CREDENTIAL] credential {} (type={}) is_available={} (is_healthy={}, is_disabled={}, error_count={}, circuit={:?}, last_error={:?})",
                         c.name.as_deref().unwrap_or("unnamed"),
                         c.provider_type,
                         is_avail,
                         c.is_healthy,
                         c.is_disabled,
                         c.error_count,
+                        self.circuit_breaker.state(&c.uuid),
                         c.last_error_message
                     );
                 } else {
@@ -353,19 +540,15 @@ impl ProviderPoolService {
             available.len()
         );
 
-        if available.is_empty() {
-            return Ok(None);
-        }
-
-        // 如果只有一个可用凭证，直接返回
-        if available.len() == 1 {
-            return Ok(Some(available.into_iter().next().unwrap()));
-        }
-
         // 智能选择：基于权重分数选择最优凭证
-        let selected = self.select_best_credential_by_weight(&available);
-
-        Ok(Some(selected))
+        let round_robin_key = format!("{}:{}", provider_type, model.unwrap_or("*"));
+        let preferred =
+            session_key.and_then(|key| self.session_affinity.lookup(provider_type, key));
+        let selected = self.pick_credential(available, &round_robin_key, preferred.as_deref());
+        if let (Some(key), Some(cred)) = (session_key, &selected) {
+            self.session_affinity.bind(provider_type, key, &cred.uuid);
+        }
+        Ok(selected)
     }
 
     /// 带智能降级的凭证选择
@@ -461,6 +644,46 @@ impl ProviderPoolService {
     }
 
     /// 基于权重分数选择最优凭证
+    /// 按当前负载均衡策略从候选凭证中选择一个
+    fn select_by_strategy(
+        &self,
+        available: &[ProviderCredential],
+        round_robin_key: &str,
+    ) -> ProviderCredential {
+        let index = match self.load_balancer.strategy() {
+            LoadBalanceStrategy::Smart => {
+                return self.select_best_credential_by_weight(available);
+            }
+            LoadBalanceStrategy::RoundRobin => {
+                self.next_round_robin_index(round_robin_key) % available.len()
+            }
+            LoadBalanceStrategy::Weighted => self.load_balancer.pick_weighted(available),
+            LoadBalanceStrategy::LeastConnections => {
+                self.load_balancer.pick_least_connections(available)
+            }
+            LoadBalanceStrategy::LeastRecentError => {
+                self.load_balancer.pick_least_recent_error(available)
+            }
+            LoadBalanceStrategy::LatencyP95 => {
+                self.load_balancer.pick_lowest_p95_latency(available)
+            }
+        };
+        available[index].clone()
+    }
+
+    /// 获取并递增指定分组的轮询索引
+    fn next_round_robin_index(&self, key: &str) -> usize {
+        if let Some(index) = self.round_robin_index.read().unwrap().get(key) {
+            return index.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        self.round_robin_index
+            .write()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert_with(|| AtomicUsize::new(0))
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    }
+
     fn select_best_credential_by_weight(
         &self,
         credentials: &[ProviderCredential],
@@ -541,12 +764,12 @@ impl ProviderPoolService {
     /// 记录凭证使用
     pub fn record_usage(&self, db: &DbConnection, uuid: &str) -> Result<(), String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
-        let cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| format!("Credential not found: {}", uuid))?;
-
-        ProviderPoolDao::update_usage(&conn, uuid, cred.usage_count + 1, Utc::now())
-            .map_err(|e| e.to_string())
+        let found =
+            ProviderPoolDao::increment_usage(&conn, uuid, Utc::now()).map_err(|e| e.to_string())?;
+        if !found {
+            return Err(format!("Credential not found: {}", uuid));
+        }
+        Ok(())
     }
 
     /// 标记凭证为健康
@@ -556,7 +779,11 @@ impl ProviderPoolService {
         uuid: &str,
         check_model: Option<&str>,
     ) -> Result<(), String> {
+        self.circuit_breaker.record_success(uuid);
         let conn = db.lock().map_err(|e| e.to_string())?;
+        let was_unhealthy = ProviderPoolDao::get_by_uuid(&conn, uuid)
+            .map_err(|e| e.to_string())?
+            .is_some_and(|c| !c.is_healthy);
         ProviderPoolDao::update_health_status(
             &conn,
             uuid,
@@ -567,7 +794,11 @@ impl ProviderPoolService {
             Some(Utc::now()),
             check_model,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+        if was_unhealthy {
+            self.notify_health_change(&conn, uuid);
+        }
+        Ok(())
     }
 
     /// 标记凭证为不健康
@@ -582,24 +813,20 @@ impl ProviderPoolService {
             .map_err(|e| e.to_string())?
             .ok_or_else(|| format!("Credential not found: {}", uuid))?;
 
-        let new_error_count = cred.error_count + 1;
-        let is_healthy = new_error_count < self.max_error_count;
+        // 连续失败达到阈值时熔断，冷却期内不再被选中
+        let is_healthy = self.circuit_breaker.record_failure(uuid) != CircuitState::Open;
 
-        ProviderPoolDao::update_health_status(
-            &conn,
-            uuid,
-            is_healthy,
-            new_error_count,
-            Some(Utc::now()),
-            error_message,
-            None,
-            None,
-        )
-        .map_err(|e| e.to_string())
+        ProviderPoolDao::record_error(&conn, uuid, is_healthy, Utc::now(), error_message)
+            .map_err(|e| e.to_string())?;
+        if cred.is_healthy && !is_healthy {
+            self.notify_health_change(&conn, uuid);
+        }
+        Ok(())
     }
 
     /// 重置凭证计数器
     pub fn reset_counters(&self, db: &DbConnection, uuid: &str) -> Result<(), String> {
+        self.circuit_breaker.reset(uuid);
         let conn = db.lock().map_err(|e| e.to_string())?;
         ProviderPoolDao::reset_counters(&conn, uuid).map_err(|e| e.to_string())
     }
@@ -612,6 +839,9 @@ impl ProviderPoolService {
     ) -> Result<usize, String> {
         let pt: PoolProviderType = provider_type.parse().map_err(|e: String| e)?;
         let conn = db.lock().map_err(|e| e.to_string())?;
+        for cred in ProviderPoolDao::get_by_type(&conn, &pt).map_err(|e| e.to_string())? {
+            self.circuit_breaker.reset(&cred.uuid);
+        }
         ProviderPoolDao::reset_health_by_type(&conn, &pt).map_err(|e| e.to_string())
     }
 
@@ -625,20 +855,7 @@ impl ProviderPoolService {
         let conn = db.lock().map_err(|e| e.to_string())?;
         let cred = ProviderPoolDao::get_by_uuid(&conn, uuid).map_err(|e| e.to_string())?;
 
-        Ok(cred.map(|c| CredentialHealthInfo {
-            uuid: c.uuid.clone(),
-            name: c.name.clone(),
-            provider_type: c.provider_type.to_string(),
-            is_healthy: c.is_healthy,
-            last_error: c.last_error_message.clone(),
-            last_error_time: c.last_error_time.map(|t| t.to_rfc3339()),
-            failure_count: c.error_count,
-            requires_reauth: c
-                .last_error_message
-                .as_ref()
-                .map(|e| e.contains("invalid_grant") || e.contains("重新授权"))
-                .unwrap_or(false),
-        }))
+        Ok(cred.as_ref().map(CredentialHealthInfo::from_credential))
     }
 
     /// 获取所有凭证的健康状态
@@ -651,21 +868,8 @@ impl ProviderPoolService {
         let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
 
         Ok(credentials
-            .into_iter()
-            .map(|c| CredentialHealthInfo {
-                uuid: c.uuid.clone(),
-                name: c.name.clone(),
-                provider_type: c.provider_type.to_string(),
-                is_healthy: c.is_healthy,
-                last_error: c.last_error_message.clone(),
-                last_error_time: c.last_error_time.map(|t| t.to_rfc3339()),
-                failure_count: c.error_count,
-                requires_reauth: c
-                    .last_error_message
-                    .as_ref()
-                    .map(|e| e.contains("invalid_grant") || e.contains("重新授权"))
-                    .unwrap_or(false),
-            })
+            .iter()
+            .map(CredentialHealthInfo::from_credential)
             .collect())
     }
 
@@ -685,12 +889,12 @@ impl ProviderPoolService {
             .map_err(|e| e.to_string())?
             .ok_or_else(|| format!("Credential not found: {}", uuid))?;
 
-        let new_error_count = cred.error_count + 1;
-        // 如果需要重新授权，直接标记为不健康
+        // 如果需要重新授权，直接标记为不健康（不走熔断，冷却结束后也不会自动恢复）
         let is_healthy = if requires_reauth {
+            self.circuit_breaker.reset(uuid);
             false
         } else {
-            new_error_count < self.max_error_count
+            self.circuit_breaker.record_failure(uuid) != CircuitState::Open
         };
 
         let error_msg = if requires_reauth {
@@ -699,17 +903,12 @@ impl ProviderPoolService {
             error_message
         };
 
-        ProviderPoolDao::update_health_status(
-            &conn,
-            uuid,
-            is_healthy,
-            new_error_count,
-            Some(Utc::now()),
-            Some(&error_msg),
-            None,
-            None,
-        )
-        .map_err(|e| e.to_string())
+        ProviderPoolDao::record_error(&conn, uuid, is_healthy, Utc::now(), Some(&error_msg))
+            .map_err(|e| e.to_string())?;
+        if cred.is_healthy && !is_healthy {
+            self.notify_health_change(&conn, uuid);
+        }
+        Ok(())
     }
 
     /// 选择一个健康的凭证
@@ -735,7 +934,7 @@ impl ProviderPoolService {
         // 过滤可用的凭证（健康且未禁用）
         let mut available: Vec<_> = credentials
             .iter()
-            .filter(|c| c.is_available() && c.is_healthy)
+            .filter(|c| self.is_selectable(c))
             .collect();
 
         // 如果指定了模型，进一步过滤支持该模型的凭证
@@ -809,8 +1008,12 @@ impl ProviderPoolService {
                 .unwrap_or(0)
         };
 
-        let selected_index = index % available.len();
-        let selected = available[selected_index].clone();
+        // 从轮询位置开始，跳过半开试探名额已被占用的凭证
+        let selected = (0..available.len())
+            .map(|offset| available[(index + offset) % available.len()])
+            .find(|c| self.circuit_breaker.allow_request(&c.uuid))
+            .cloned()
+            .ok_or(SelectionError::NoCredentials)?;
 
         // 更新轮询索引
         {
@@ -1003,6 +1206,19 @@ impl ProviderPoolService {
                 self.check_vertex_health(api_key, base_url.as_deref(), model)
                     .await
             }
+            CredentialData::VertexServiceAccount {
+                creds_file_path,
+                project_id,
+                region,
+            } => {
+                self.check_vertex_service_account_health(
+                    creds_file_path,
+                    project_id.clone(),
+                    region.clone(),
+                    model,
+                )
+                .await
+            }
             CredentialData::GeminiApiKey {
                 api_key, base_url, ..
             } => {
@@ -1019,11 +1235,43 @@ impl ProviderPoolService {
             CredentialData::ClaudeOAuth { creds_file_path } => {
                 self.check_claude_oauth_health(creds_file_path, model).await
             }
+            CredentialData::QwenOAuth { creds_file_path } => {
+                self.check_qwen_health(creds_file_path, model).await
+            }
             CredentialData::AnthropicKey { api_key, base_url } => {
                 // Anthropic API Key 使用与 Claude API Key 相同的健康检查逻辑
                 self.check_claude_health(api_key, base_url.as_deref(), model)
                     .await
             }
+            CredentialData::AzureOpenaiKey {
+                api_key,
+                resource,
+                deployment,
+                api_version,
+                model_deployments,
+            } => {
+                let deployment =
+                    resolve_azure_deployment(model_deployments, deployment.as_deref(), model)
+                        .or_else(|| model_deployments.values().next().cloned())
+                        .ok_or_else(|| "Azure OpenAI 凭证未配置部署".to_string())?;
+                self.check_azure_openai_health(
+                    api_key,
+                    resource,
+                    api_version.clone(),
+                    &deployment,
+                    model,
+                )
+                .await
+            }
+            CredentialData::AwsBedrock { .. } => self.check_bedrock_health(credential, model).await,
+            // 本地服务只探测是否在线，不实际生成（避免触发模型加载）
+            CredentialData::Ollama { base_url } => {
+                crate::providers::OllamaProvider::new(base_url.clone())
+                    .probe()
+                    .await
+            }
+            // Mock 不访问上游，始终健康（错误注入只作用于实际请求）
+            CredentialData::Mock { .. } => Ok(()),
         }
     }
 
@@ -1548,6 +1796,156 @@ impl ProviderPoolService {
         }
     }
 
+    /// Qwen OAuth 健康检查
+    ///
+    /// Qwen 上游为 OpenAI 兼容接口，刷新 Token 后复用 OpenAI 健康检查
+    async fn check_qwen_health(&self, creds_path: &str, model: &str) -> Result<(), String> {
+        use crate::providers::qwen::QwenProvider;
+
+        let mut provider = QwenProvider::new();
+        provider
+            .load_credentials_from_path(creds_path)
+            .await
+            .map_err(|e| format!("加载 Qwen 凭证失败: {}", e))?;
+
+        let token = provider
+            .ensure_valid_token()
+            .await
+            .map_err(|e| format!("获取 Qwen Token 失败: {}", e))?;
+
+        let base_url = provider.get_base_url();
+        self.check_openai_health(&token, Some(&base_url), model)
+            .await
+    }
+
+    // Vertex AI 服务账号健康检查
+    async fn check_vertex_service_account_health(
+        &self,
+        creds_file_path: &str,
+        project_id: Option<String>,
+        region: Option<String>,
+        model: &str,
+    ) -> Result<(), String> {
+        use crate::providers::VertexServiceAccountProvider;
+
+        let provider = VertexServiceAccountProvider::load(creds_file_path, project_id, region)?;
+        let token = provider.access_token().await?;
+        let url = provider.chat_completions_url();
+        let request_body = serde_json::json!({
+            "model": crate::providers::vertex_service_account::upstream_model(model),
+            "messages": [{"role": "user", "content": "Say OK"}],
+            "max_tokens": 10
+        });
+
+        tracing::debug!("[HEALTH_CHECK] Vertex AI URL: {}, model: {}", url, model);
+
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&token.token)
+            .json(&request_body)
+            .timeout(self.health_check_timeout)
+            .send()
+            .await
+            .map_err(|e| format!("请求失败: {}", e))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(format!(
+                "HTTP {} - {}",
+                status,
+                body.chars().take(200).collect::<String>()
+            ))
+        }
+    }
+
+    // Azure OpenAI 健康检查
+    async fn check_azure_openai_health(
+        &self,
+        api_key: &str,
+        resource: &str,
+        api_version: Option<String>,
+        deployment: &str,
+        model: &str,
+    ) -> Result<(), String> {
+        use crate::providers::azure_openai::AzureOpenAIProvider;
+
+        let provider =
+            AzureOpenAIProvider::new(api_key.to_string(), resource.to_string(), api_version);
+        let url = provider.chat_completions_url(deployment);
+        let request_body = serde_json::json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "Say OK"}],
+            "max_tokens": 10
+        });
+
+        tracing::debug!("[HEALTH_CHECK] Azure OpenAI URL: {}, model: {}", url, model);
+
+        let response = self
+            .client
+            .post(&url)
+            .header("api-key", api_key)
+            .json(&request_body)
+            .timeout(self.health_check_timeout)
+            .send()
+            .await
+            .map_err(|e| format!("请求失败: {}", e))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(format!(
+                "HTTP {} - {}",
+                status,
+                body.chars().take(200).collect::<String>()
+            ))
+        }
+    }
+
+    // AWS Bedrock 健康检查（Converse API）
+    async fn check_bedrock_health(
+        &self,
+        credential: &CredentialData,
+        model: &str,
+    ) -> Result<(), String> {
+        use crate::providers::bedrock::{BedrockConfig, BedrockProvider};
+
+        let config = BedrockConfig::from_credential(credential)
+            .ok_or_else(|| "无效的 Bedrock 凭证".to_string())?;
+        let request: crate::models::openai::ChatCompletionRequest =
+            serde_json::from_value(serde_json::json!({
+                "model": model,
+                "messages": [{"role": "user", "content": "Say OK"}],
+                "max_tokens": 10
+            }))
+            .map_err(|e| e.to_string())?;
+
+        let response = tokio::time::timeout(
+            self.health_check_timeout,
+            BedrockProvider::new(config).converse(&request),
+        )
+        .await
+        .map_err(|_| "请求失败: 健康检查超时".to_string())?
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(format!(
+                "HTTP {} - {}",
+                status,
+                body.chars().take(200).collect::<String>()
+            ))
+        }
+    }
+
     /// 根据名称获取凭证
     pub fn get_by_name(
         &self,
@@ -2110,4 +2508,70 @@ mod tests {
         assert_eq!(deserialized.uuid, info.uuid);
         assert_eq!(deserialized.is_healthy, info.is_healthy);
     }
+
+    #[test]
+    fn test_circuit_breaker_skips_failing_credential() {
+        let service = ProviderPoolService::new();
+        let mut failing = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-failing".to_string(),
+                base_url: None,
+            },
+        );
+        let healthy = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-healthy".to_string(),
+                base_url: None,
+            },
+        );
+
+        for _ in 0..service.max_error_count {
+            service.circuit_breaker.record_failure(&failing.uuid);
+        }
+        failing.is_healthy = false;
+
+        // 熔断冷却期内跳过失败凭证，流量转移到健康凭证
+        assert!(!service.is_selectable(&failing));
+        let selected =
+            service.pick_credential(vec![failing.clone(), healthy.clone()], "openai:*", None);
+        assert_eq!(selected.map(|c| c.uuid), Some(healthy.uuid));
+
+        // 重置熔断后，不健康凭证需等待健康检查恢复才能再次被选中
+        service.circuit_breaker.reset(&failing.uuid);
+        assert!(!service.is_selectable(&failing));
+        failing.is_healthy = true;
+        assert!(service.is_selectable(&failing));
+    }
+
+    #[test]
+    fn test_pick_credential_prefers_session_binding() {
+        let service = ProviderPoolService::new();
+        let credentials: Vec<_> = ["sk-a", "sk-b", "sk-c"]
+            .iter()
+            .map(|key| {
+                ProviderCredential::new(
+                    PoolProviderType::OpenAI,
+                    CredentialData::OpenAIKey {
+                        api_key: key.to_string(),
+                        base_url: None,
+                    },
+                )
+            })
+            .collect();
+        let bound = credentials[1].uuid.clone();
+
+        for _ in 0..5 {
+            let selected = service.pick_credential(credentials.clone(), "openai:*", Some(&bound));
+            assert_eq!(selected.map(|c| c.uuid).as_deref(), Some(bound.as_str()));
+        }
+
+        // 绑定的凭证被熔断后回退到其他凭证
+        for _ in 0..service.max_error_count {
+            service.circuit_breaker.record_failure(&bound);
+        }
+        let selected = service.pick_credential(credentials, "openai:*", Some(&bound));
+        assert_ne!(selected.map(|c| c.uuid), Some(bound));
+    }
 }
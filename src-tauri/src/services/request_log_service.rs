@@ -0,0 +1,125 @@
+//! 请求日志持久化服务
+//!
+//! 将请求遥测日志写入数据库供检索，并按保留天数定期清理过期记录。
+
+use crate::config::RequestLogStoreConfig;
+use crate::database::dao::request_logs::{RequestLogDao, RequestLogPage, RequestLogQuery};
+use crate::database::DbConnection;
+use crate::telemetry::RequestLog;
+use chrono::Utc;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 过期记录清理间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 请求日志持久化服务
+pub struct RequestLogService {
+    config: RwLock<RequestLogStoreConfig>,
+    last_cleanup: Mutex<Option<Instant>>,
+}
+
+impl RequestLogService {
+    pub fn new(config: RequestLogStoreConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            last_cleanup: Mutex::new(None),
+        }
+    }
+
+    /// 更新配置（支持热重载）
+    pub fn update_config(&self, config: RequestLogStoreConfig) {
+        if let Ok(mut current) = self.config.write() {
+            if current.enabled != config.enabled {
+                tracing::info!(
+                    "[REQUEST_LOG] 请求日志持久化已{}",
+                    if config.enabled { "启用" } else { "停用" }
+                );
+            }
+            *current = config;
+        }
+    }
+
+    /// 是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().map(|c| c.enabled).unwrap_or(false)
+    }
+
+    /// 保存请求日志，并按保留天数定期清理
+    pub fn record(&self, db: &DbConnection, log: &RequestLog) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Ok(conn) = db.lock() else {
+            return;
+        };
+        if let Err(e) = RequestLogDao::upsert(&conn, log) {
+            tracing::warn!("[REQUEST_LOG] 保存请求日志失败: {}", e);
+            return;
+        }
+
+        let due = self
+            .last_cleanup
+            .lock()
+            .map(|mut last| {
+                let due = last.is_none_or(|t| t.elapsed() >= CLEANUP_INTERVAL);
+                if due {
+                    *last = Some(Instant::now());
+                }
+                due
+            })
+            .unwrap_or(false);
+        if due {
+            let retention_days = self.config.read().map(|c| c.retention_days).unwrap_or(7);
+            let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
+            match RequestLogDao::delete_before(&conn, cutoff) {
+                Ok(removed) if removed > 0 => {
+                    tracing::info!("[REQUEST_LOG] 清理过期请求日志 {} 条", removed)
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("[REQUEST_LOG] 清理过期请求日志失败: {}", e),
+            }
+        }
+    }
+
+    /// 流式响应结束时更新持久化日志的持续时间
+    pub fn update_duration(&self, db: &DbConnection, id: &str, duration_ms: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Ok(conn) = db.lock() else {
+            return;
+        };
+        if let Err(e) = RequestLogDao::update_duration(&conn, id, duration_ms) {
+            tracing::warn!("[REQUEST_LOG] 更新请求日志耗时失败: {}", e);
+        }
+    }
+
+    /// 获取单条请求日志
+    pub async fn get(&self, db: &DbConnection, id: &str) -> Result<Option<RequestLog>, String> {
+        let id = id.to_string();
+        db.run(move |conn| RequestLogDao::get(conn, &id)).await
+    }
+
+    /// 按条件分页检索请求日志
+    pub async fn search(
+        &self,
+        db: &DbConnection,
+        query: &RequestLogQuery,
+    ) -> Result<RequestLogPage, String> {
+        let query = query.clone();
+        db.run(move |conn| RequestLogDao::search(conn, &query))
+            .await
+    }
+
+    /// 清空持久化的请求日志
+    pub async fn clear(&self, db: &DbConnection) -> Result<usize, String> {
+        db.run(RequestLogDao::clear).await
+    }
+}
+
+impl Default for RequestLogService {
+    fn default() -> Self {
+        Self::new(RequestLogStoreConfig::default())
+    }
+}
@@ -0,0 +1,494 @@
+//! 响应缓存服务
+//!
+//! 按规范化后的请求哈希缓存非流式补全响应：
+//! - 内存 LRU 缓存，超过 `max_entries` 时淘汰最久未访问的条目
+//! - 可选的数据库持久化，内存未命中时回退查询，重启后仍可命中
+//!
+//! 缓存 key 包含端点、Provider 和客户端 Key，不同租户之间不共享缓存。
+//! 规范化时忽略字段顺序和流式相关字段，因此流式请求的非流式重放也能命中。
+
+use crate::config::ResponseCacheConfig;
+use crate::database::dao::response_cache::{ResponseCacheDao, ResponseCacheRow};
+use crate::database::DbConnection;
+use bytes::Bytes;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 持久化缓存中过期条目的清理间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(600);
+
+/// 计算缓存 key 时忽略的请求字段
+const IGNORED_FIELDS: &[&str] = &["stream", "stream_options"];
+
+/// 缓存的响应
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub content_type: String,
+    pub body: Bytes,
+    /// 写入缓存的时间（Unix 秒）
+    pub created_at: i64,
+}
+
+impl CachedResponse {
+    /// 缓存条目的存活时间（秒）
+    pub fn age_secs(&self) -> u64 {
+        (Utc::now().timestamp() - self.created_at).max(0) as u64
+    }
+}
+
+/// 内存缓存条目
+#[derive(Debug, Clone)]
+struct MemoryEntry {
+    response: CachedResponse,
+    expires_at: i64,
+    /// 最近访问序号，用于 LRU 淘汰
+    last_access: u64,
+}
+
+/// 缓存统计（`/v1/cache/stats`）
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseCacheStats {
+    pub enabled: bool,
+    pub persist: bool,
+    pub ttl_secs: u64,
+    pub max_entries: usize,
+    /// 内存中的条目数
+    pub entries: usize,
+    /// 数据库中未过期的条目数（未启用持久化时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub persisted_entries: Option<usize>,
+    pub hits: u64,
+    pub misses: u64,
+    pub stores: u64,
+    pub evictions: u64,
+    pub hit_rate: f64,
+}
+
+/// 响应缓存服务
+pub struct ResponseCacheService {
+    config: RwLock<ResponseCacheConfig>,
+    entries: Mutex<HashMap<String, MemoryEntry>>,
+    access_counter: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stores: AtomicU64,
+    evictions: AtomicU64,
+    last_cleanup: Mutex<Option<Instant>>,
+}
+
+impl Default for ResponseCacheService {
+    fn default() -> Self {
+        Self::new(ResponseCacheConfig::default())
+    }
+}
+
+impl ResponseCacheService {
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            entries: Mutex::new(HashMap::new()),
+            access_counter: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            last_cleanup: Mutex::new(None),
+        }
+    }
+
+    /// 更新配置（支持热重载）
+    ///
+    /// 停用时清空内存缓存；条目上限缩小时立即淘汰多余条目
+    pub fn update_config(&self, config: ResponseCacheConfig) {
+        if let Ok(mut current) = self.config.write() {
+            if current.enabled != config.enabled {
+                tracing::info!(
+                    "[CACHE] 响应缓存已{}",
+                    if config.enabled { "启用" } else { "停用" }
+                );
+            }
+            *current = config.clone();
+        }
+        if let Ok(mut entries) = self.entries.lock() {
+            if !config.enabled {
+                entries.clear();
+            } else {
+                self.evict_to(&mut entries, config.max_entries);
+            }
+        }
+    }
+
+    fn config(&self) -> ResponseCacheConfig {
+        self.config.read().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// 是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().map(|c| c.enabled).unwrap_or(false)
+    }
+
+    /// 缓存有效期（秒）
+    pub fn ttl_secs(&self) -> u64 {
+        self.config().ttl_secs
+    }
+
+    /// 计算请求的缓存 key
+    ///
+    /// `namespace` 区分端点格式（如 `openai`、`anthropic`），
+    /// `scope` 区分 Provider 和客户端 Key。
+    pub fn cache_key(namespace: &str, scope: &str, request: &Value) -> String {
+        let mut normalized = normalize(request);
+        if let Value::Object(obj) = &mut normalized {
+            for field in IGNORED_FIELDS {
+                obj.remove(*field);
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(namespace.as_bytes());
+        hasher.update([0]);
+        hasher.update(scope.as_bytes());
+        hasher.update([0]);
+        hasher.update(canonical_json(&normalized).as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// 查询缓存（内存优先，未命中时回退到数据库）
+    pub fn get(&self, db: Option<&DbConnection>, key: &str) -> Option<CachedResponse> {
+        let config = self.config();
+        if !config.enabled {
+            return None;
+        }
+        let now = Utc::now().timestamp();
+
+        if let Ok(mut entries) = self.entries.lock() {
+            match entries.get_mut(key) {
+                Some(entry) if entry.expires_at > now => {
+                    entry.last_access = self.next_access();
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Some(entry.response.clone());
+                }
+                Some(_) => {
+                    entries.remove(key);
+                }
+                None => {}
+            }
+        }
+
+        let persisted = db
+            .filter(|_| config.persist)
+            .and_then(|db| db.lock().ok())
+            .and_then(|conn| match ResponseCacheDao::get(&conn, key, now) {
+                Ok(row) => row,
+                Err(e) => {
+                    tracing::warn!("[CACHE] 读取持久化缓存失败: {}", e);
+                    None
+                }
+            });
+
+        match persisted {
+            Some(row) => {
+                let response = CachedResponse {
+                    content_type: row.content_type,
+                    body: Bytes::from(row.body),
+                    created_at: row.created_at,
+                };
+                self.insert_memory(key, response.clone(), row.expires_at, config.max_entries);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(response)
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// 写入缓存
+    pub fn put(
+        &self,
+        db: Option<&DbConnection>,
+        key: &str,
+        model: &str,
+        content_type: &str,
+        body: Bytes,
+    ) {
+        let config = self.config();
+        if !config.enabled {
+            return;
+        }
+        let now = Utc::now().timestamp();
+        let expires_at = now.saturating_add(config.ttl_secs as i64);
+        let response = CachedResponse {
+            content_type: content_type.to_string(),
+            body,
+            created_at: now,
+        };
+
+        self.insert_memory(key, response.clone(), expires_at, config.max_entries);
+        self.stores.fetch_add(1, Ordering::Relaxed);
+
+        let Some(conn) = db.filter(|_| config.persist).and_then(|db| db.lock().ok()) else {
+            return;
+        };
+        let row = ResponseCacheRow {
+            cache_key: key.to_string(),
+            model: model.to_string(),
+            content_type: response.content_type,
+            body: response.body.to_vec(),
+            created_at: now,
+            expires_at,
+        };
+        if let Err(e) = ResponseCacheDao::upsert(&conn, &row) {
+            tracing::warn!("[CACHE] 写入持久化缓存失败: {}", e);
+            return;
+        }
+
+        let due = self
+            .last_cleanup
+            .lock()
+            .map(|mut last| {
+                let due = last.is_none_or(|t| t.elapsed() >= CLEANUP_INTERVAL);
+                if due {
+                    *last = Some(Instant::now());
+                }
+                due
+            })
+            .unwrap_or(false);
+        if due {
+            match ResponseCacheDao::delete_expired(&conn, now) {
+                Ok(removed) if removed > 0 => {
+                    tracing::info!("[CACHE] 已清理 {} 条过期的持久化缓存", removed);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("[CACHE] 清理过期缓存失败: {}", e),
+            }
+        }
+    }
+
+    /// 清空缓存（包括持久化缓存），返回删除的条目数
+    pub fn clear(&self, db: Option<&DbConnection>) -> usize {
+        let mut removed = self
+            .entries
+            .lock()
+            .map(|mut entries| {
+                let count = entries.len();
+                entries.clear();
+                count
+            })
+            .unwrap_or(0);
+        if let Some(conn) = db.and_then(|db| db.lock().ok()) {
+            removed = removed.max(ResponseCacheDao::clear(&conn).unwrap_or(0));
+        }
+        removed
+    }
+
+    /// 获取缓存统计
+    pub fn stats(&self, db: Option<&DbConnection>) -> ResponseCacheStats {
+        let config = self.config();
+        let entries = self.entries.lock().map(|e| e.len()).unwrap_or(0);
+        let persisted_entries = db
+            .filter(|_| config.persist)
+            .and_then(|db| db.lock().ok())
+            .and_then(|conn| ResponseCacheDao::count(&conn, Utc::now().timestamp()).ok());
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+
+        ResponseCacheStats {
+            enabled: config.enabled,
+            persist: config.persist,
+            ttl_secs: config.ttl_secs,
+            max_entries: config.max_entries,
+            entries,
+            persisted_entries,
+            hits,
+            misses,
+            stores: self.stores.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            hit_rate: if lookups == 0 {
+                0.0
+            } else {
+                hits as f64 / lookups as f64
+            },
+        }
+    }
+
+    fn next_access(&self) -> u64 {
+        self.access_counter.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn insert_memory(
+        &self,
+        key: &str,
+        response: CachedResponse,
+        expires_at: i64,
+        max_entries: usize,
+    ) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        entries.insert(
+            key.to_string(),
+            MemoryEntry {
+                response,
+                expires_at,
+                last_access: self.next_access(),
+            },
+        );
+        self.evict_to(&mut entries, max_entries);
+    }
+
+    /// 淘汰最久未访问的条目，直到不超过 `max_entries`
+    fn evict_to(&self, entries: &mut HashMap<String, MemoryEntry>, max_entries: usize) {
+        while entries.len() > max_entries {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_access)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 递归移除 null 字段（`None` 与缺省等价）
+fn normalize(value: &Value) -> Value {
+    match value {
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k.clone(), normalize(v)))
+                .collect(),
+        ),
+        Value::Array(arr) => Value::Array(arr.iter().map(normalize).collect()),
+        other => other.clone(),
+    }
+}
+
+/// 按 key 排序序列化 JSON，保证字段顺序不同的相同请求得到相同的哈希
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(obj) => {
+            let mut keys: Vec<&String> = obj.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|k| format!("{}:{}", Value::String(k.clone()), canonical_json(&obj[k])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(arr) => {
+            let items: Vec<String> = arr.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn service(max_entries: usize, persist: bool) -> ResponseCacheService {
+        ResponseCacheService::new(ResponseCacheConfig {
+            enabled: true,
+            ttl_secs: 60,
+            max_entries,
+            persist,
+        })
+    }
+
+    #[test]
+    fn test_cache_key_normalization() {
+        let a = json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "hi"}], "stream": true});
+        let b = json!({"messages": [{"content": "hi", "role": "user"}], "model": "gpt-4o", "temperature": null});
+        let c = json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "hello"}]});
+
+        let key = ResponseCacheService::cache_key("openai", "kiro", &a);
+        assert_eq!(key, ResponseCacheService::cache_key("openai", "kiro", &b));
+        assert_ne!(key, ResponseCacheService::cache_key("openai", "kiro", &c));
+        assert_ne!(
+            key,
+            ResponseCacheService::cache_key("anthropic", "kiro", &a)
+        );
+        assert_ne!(
+            key,
+            ResponseCacheService::cache_key("openai", "kiro:key-1", &a)
+        );
+    }
+
+    #[test]
+    fn test_lru_eviction_and_stats() {
+        let cache = service(2, false);
+        cache.put(None, "a", "m", "application/json", Bytes::from_static(b"1"));
+        cache.put(None, "b", "m", "application/json", Bytes::from_static(b"2"));
+        // 访问 a，使 b 成为最久未访问的条目
+        assert!(cache.get(None, "a").is_some());
+        cache.put(None, "c", "m", "application/json", Bytes::from_static(b"3"));
+
+        assert!(cache.get(None, "b").is_none());
+        assert_eq!(cache.get(None, "c").unwrap().body, Bytes::from_static(b"3"));
+
+        let stats = cache.stats(None);
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.stores, 3);
+        assert_eq!(stats.evictions, 1);
+    }
+
+    #[test]
+    fn test_ttl_and_disabled() {
+        let cache = service(10, false);
+        cache.update_config(ResponseCacheConfig {
+            enabled: true,
+            ttl_secs: 0,
+            max_entries: 10,
+            persist: false,
+        });
+        cache.put(None, "a", "m", "application/json", Bytes::from_static(b"1"));
+        assert!(cache.get(None, "a").is_none());
+
+        cache.update_config(ResponseCacheConfig::default());
+        cache.put(None, "b", "m", "application/json", Bytes::from_static(b"2"));
+        assert!(cache.get(None, "b").is_none());
+        assert_eq!(cache.stats(None).entries, 0);
+    }
+
+    #[test]
+    fn test_persistent_fallback() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        let db: DbConnection = Arc::new(crate::database::DbPool::from_connection(conn));
+
+        let cache = service(10, true);
+        cache.put(
+            Some(&db),
+            "a",
+            "gpt-4o",
+            "application/json",
+            Bytes::from_static(b"{}"),
+        );
+
+        // 新实例（模拟重启）从数据库命中
+        let restarted = service(10, true);
+        let cached = restarted.get(Some(&db), "a").unwrap();
+        assert_eq!(cached.body, Bytes::from_static(b"{}"));
+        assert_eq!(restarted.stats(Some(&db)).persisted_entries, Some(1));
+
+        assert_eq!(restarted.clear(Some(&db)), 1);
+        assert!(restarted.get(Some(&db), "a").is_none());
+    }
+}
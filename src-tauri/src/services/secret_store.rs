@@ -0,0 +1,341 @@
+//! 凭证静态加密
+//!
+//! 凭证池中的 `credential_data` 和缓存的 access / refresh token 写入数据库前加密，
+//! 读取时由 `ProviderPoolDao` 透明解密，`ProviderPoolService` 及其调用方无需感知。
+//! 同步到共享存储的凭证同样以密文保存，此时要求主密钥可在实例间共享。
+//! API Key Provider 的密钥和配置文件中的敏感字段也使用同一主密钥加密。
+//!
+//! 主密钥来源：
+//! - 系统钥匙串（Windows 凭据管理器 / macOS Keychain / libsecret），首次使用时生成随机密钥
+//! - 主口令：从环境变量读取，使用 Argon2id 和本地保存的盐派生密钥
+//!
+//! 密文格式为 `enc:v1:` + base64(nonce || AES-256-GCM 密文)。未加密的旧数据照常读取，
+//! 通过 `set_credential_encryption` 命令批量迁移。
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::{SecretKeySource, SecretsConfig};
+
+/// 密文前缀
+pub const SEALED_PREFIX: &str = "enc:v1:";
+
+const NONCE_LEN: usize = 12;
+const KEYCHAIN_SERVICE: &str = "proxycast";
+const KEYCHAIN_USER: &str = "credential-master-key";
+/// 用于校验主口令的已知明文
+const VERIFIER_PLAINTEXT: &str = "proxycast-secret-store";
+
+/// 凭证加密错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SecretError {
+    #[error("无法获取主密钥: {0}")]
+    KeyUnavailable(String),
+    #[error("主口令错误")]
+    WrongPassphrase,
+    #[error("数据已加密，但未加载主密钥（请在配置中启用 secrets）")]
+    Locked,
+    #[error("密文格式无效")]
+    Malformed,
+    #[error("解密失败（主密钥不匹配或数据已损坏）")]
+    DecryptFailed,
+}
+
+/// AES-256-GCM 加解密器
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for SecretCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretCipher").finish_non_exhaustive()
+    }
+}
+
+impl SecretCipher {
+    /// 从 32 字节密钥创建
+    pub fn from_key(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+
+    /// 使用 Argon2id 从主口令派生密钥
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, SecretError> {
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| SecretError::KeyUnavailable(e.to_string()))?;
+        Ok(Self::from_key(&key))
+    }
+
+    /// 加密为 `enc:v1:` 格式
+    pub fn seal(&self, plaintext: &str) -> String {
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .expect("AES-GCM 加密不会失败");
+        let mut combined = nonce.to_vec();
+        combined.extend_from_slice(&ciphertext);
+        format!("{}{}", SEALED_PREFIX, STANDARD.encode(combined))
+    }
+
+    /// 解密 `enc:v1:` 格式的密文
+    pub fn open(&self, sealed: &str) -> Result<String, SecretError> {
+        let encoded = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .ok_or(SecretError::Malformed)?;
+        let combined = STANDARD
+            .decode(encoded)
+            .map_err(|_| SecretError::Malformed)?;
+        if combined.len() < NONCE_LEN {
+            return Err(SecretError::Malformed);
+        }
+        let (nonce, ciphertext) = combined.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| SecretError::DecryptFailed)?;
+        String::from_utf8(plaintext).map_err(|_| SecretError::DecryptFailed)
+    }
+}
+
+/// 检查存储值是否为密文
+pub fn is_sealed(stored: &str) -> bool {
+    stored.starts_with(SEALED_PREFIX)
+}
+
+/// 全局加密状态
+#[derive(Debug, Default)]
+struct SecretState {
+    /// 已加载的主密钥（用于解密；禁用加密后仍保留以读取残留密文）
+    cipher: Option<Arc<SecretCipher>>,
+    /// 写入时是否加密
+    encrypt_writes: bool,
+    /// 主密钥能否在多个实例间共享（共享存储同步凭证时要求）
+    shareable: bool,
+}
+
+static STATE: Lazy<RwLock<SecretState>> = Lazy::new(|| RwLock::new(SecretState::default()));
+
+/// 按配置加载主密钥
+///
+/// 未启用时不加载；启用但无法获取主密钥时返回错误，写入保持明文
+pub fn init(config: &SecretsConfig) -> Result<(), SecretError> {
+    if !config.enabled {
+        install(None, false);
+        return Ok(());
+    }
+    let cipher = load_cipher(config)?;
+    install(Some(cipher), true);
+    STATE.write().shareable = config.key_source == SecretKeySource::Passphrase;
+    tracing::info!(
+        "[SECRETS] 凭证加密已启用 (key_source={:?})",
+        config.key_source
+    );
+    Ok(())
+}
+
+/// 替换全局加密状态
+pub fn install(cipher: Option<SecretCipher>, encrypt_writes: bool) {
+    let mut state = STATE.write();
+    state.encrypt_writes = encrypt_writes && cipher.is_some();
+    state.cipher = cipher.map(Arc::new);
+    state.shareable = false;
+}
+
+/// 设置写入时是否加密（需已加载主密钥）
+pub fn set_encrypt_writes(encrypt_writes: bool) -> Result<(), SecretError> {
+    let mut state = STATE.write();
+    if encrypt_writes && state.cipher.is_none() {
+        return Err(SecretError::Locked);
+    }
+    state.encrypt_writes = encrypt_writes;
+    Ok(())
+}
+
+/// 是否已加载主密钥
+pub fn is_loaded() -> bool {
+    STATE.read().cipher.is_some()
+}
+
+/// 写入时是否加密
+pub fn is_enabled() -> bool {
+    STATE.read().encrypt_writes
+}
+
+/// 主密钥能否在多个实例间共享
+///
+/// 钥匙串中的主密钥由每台机器随机生成，其他实例无法解密；主口令在各实例使用相同口令和
+/// 盐文件（`~/.proxycast/secret_store.json`）时派生出相同密钥
+pub fn is_key_shareable() -> bool {
+    STATE.read().shareable
+}
+
+/// 写入前处理：启用加密时返回密文，否则原样返回
+pub fn seal(plaintext: &str) -> String {
+    let state = STATE.read();
+    match (&state.cipher, state.encrypt_writes) {
+        (Some(cipher), true) => cipher.seal(plaintext),
+        _ => plaintext.to_string(),
+    }
+}
+
+/// 读取后处理：密文解密，明文原样返回
+pub fn open(stored: &str) -> Result<String, SecretError> {
+    if !is_sealed(stored) {
+        return Ok(stored.to_string());
+    }
+    let cipher = STATE.read().cipher.clone().ok_or(SecretError::Locked)?;
+    cipher.open(stored)
+}
+
+fn load_cipher(config: &SecretsConfig) -> Result<SecretCipher, SecretError> {
+    match config.key_source {
+        SecretKeySource::Keychain => load_keychain_key().map(|key| SecretCipher::from_key(&key)),
+        SecretKeySource::Passphrase => {
+            let passphrase = std::env::var(&config.passphrase_env)
+                .ok()
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| {
+                    SecretError::KeyUnavailable(format!(
+                        "环境变量 {} 未设置",
+                        config.passphrase_env
+                    ))
+                })?;
+            load_passphrase_cipher(&passphrase, &passphrase_meta_path()?)
+        }
+    }
+}
+
+/// 从系统钥匙串读取主密钥，不存在时生成并保存
+fn load_keychain_key() -> Result<[u8; 32], SecretError> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER)
+        .map_err(|e| SecretError::KeyUnavailable(e.to_string()))?;
+
+    let encoded = match entry.get_password() {
+        Ok(encoded) => encoded,
+        Err(keyring::Error::NoEntry) => {
+            let encoded = STANDARD.encode(rand::random::<[u8; 32]>());
+            entry
+                .set_password(&encoded)
+                .map_err(|e| SecretError::KeyUnavailable(e.to_string()))?;
+            tracing::info!("[SECRETS] 已在系统钥匙串中生成主密钥");
+            encoded
+        }
+        Err(e) => return Err(SecretError::KeyUnavailable(e.to_string())),
+    };
+
+    STANDARD
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| SecretError::KeyUnavailable("钥匙串中的主密钥格式无效".to_string()))
+}
+
+/// 主口令的盐和校验值
+#[derive(Debug, Serialize, Deserialize)]
+struct PassphraseMeta {
+    salt: String,
+    verifier: String,
+}
+
+fn passphrase_meta_path() -> Result<PathBuf, SecretError> {
+    dirs::home_dir()
+        .map(|home| home.join(".proxycast").join("secret_store.json"))
+        .ok_or_else(|| SecretError::KeyUnavailable("无法获取主目录".to_string()))
+}
+
+/// 从主口令派生密钥；首次使用时生成盐并写入校验值，之后用校验值识别错误口令
+fn load_passphrase_cipher(
+    passphrase: &str,
+    meta_path: &PathBuf,
+) -> Result<SecretCipher, SecretError> {
+    if let Ok(content) = std::fs::read_to_string(meta_path) {
+        let meta: PassphraseMeta = serde_json::from_str(&content).map_err(|e| {
+            SecretError::KeyUnavailable(format!("读取 {:?} 失败: {}", meta_path, e))
+        })?;
+        let salt = STANDARD
+            .decode(&meta.salt)
+            .map_err(|_| SecretError::KeyUnavailable("盐格式无效".to_string()))?;
+        let cipher = SecretCipher::from_passphrase(passphrase, &salt)?;
+        return match cipher.open(&meta.verifier) {
+            Ok(plaintext) if plaintext == VERIFIER_PLAINTEXT => Ok(cipher),
+            _ => Err(SecretError::WrongPassphrase),
+        };
+    }
+
+    let salt: [u8; 16] = rand::random();
+    let cipher = SecretCipher::from_passphrase(passphrase, &salt)?;
+    let meta = PassphraseMeta {
+        salt: STANDARD.encode(salt),
+        verifier: cipher.seal(VERIFIER_PLAINTEXT),
+    };
+    if let Some(parent) = meta_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| SecretError::KeyUnavailable(e.to_string()))?;
+    }
+    let content = serde_json::to_string_pretty(&meta)
+        .map_err(|e| SecretError::KeyUnavailable(e.to_string()))?;
+    std::fs::write(meta_path, content).map_err(|e| SecretError::KeyUnavailable(e.to_string()))?;
+    Ok(cipher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let cipher = SecretCipher::from_key(&[7u8; 32]);
+        let sealed = cipher.seal(r#"{"type":"openai_key","api_key":"sk-test"}"#);
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("sk-test"));
+        // 每次加密使用随机 nonce
+        assert_ne!(
+            sealed,
+            cipher.seal(r#"{"type":"openai_key","api_key":"sk-test"}"#)
+        );
+        assert_eq!(
+            cipher.open(&sealed).unwrap(),
+            r#"{"type":"openai_key","api_key":"sk-test"}"#
+        );
+
+        let other = SecretCipher::from_key(&[8u8; 32]);
+        assert_eq!(other.open(&sealed), Err(SecretError::DecryptFailed));
+        assert_eq!(cipher.open("enc:v1:!!"), Err(SecretError::Malformed));
+    }
+
+    #[test]
+    fn test_passphrase_meta() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret_store.json");
+
+        let first = load_passphrase_cipher("correct horse", &path).unwrap();
+        let sealed = first.seal("token");
+        // 相同口令和盐派生出相同密钥
+        let second = load_passphrase_cipher("correct horse", &path).unwrap();
+        assert_eq!(second.open(&sealed).unwrap(), "token");
+
+        assert_eq!(
+            load_passphrase_cipher("wrong", &path).unwrap_err(),
+            SecretError::WrongPassphrase
+        );
+    }
+
+    #[test]
+    fn test_plaintext_passthrough() {
+        assert!(!is_sealed(r#"{"type":"kiro_oauth"}"#));
+        assert_eq!(
+            open(r#"{"type":"kiro_oauth"}"#).unwrap(),
+            r#"{"type":"kiro_oauth"}"#
+        );
+    }
+}
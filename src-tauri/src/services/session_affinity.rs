@@ -0,0 +1,227 @@
+//! 会话粘性路由
+//!
+//! 记录会话标识到凭证 UUID 的绑定，使同一会话的多轮请求持续使用同一凭证，
+//! 提高上游提示词缓存的命中率。绑定在空闲超过 TTL 后过期。
+
+use crate::config::SessionAffinityConfig;
+use axum::http::HeaderMap;
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+/// 单个会话的凭证绑定
+#[derive(Debug, Clone)]
+struct Binding {
+    uuid: String,
+    last_used: Instant,
+}
+
+/// 会话到凭证的绑定表
+#[derive(Debug, Default)]
+pub struct SessionAffinity {
+    config: std::sync::RwLock<SessionAffinityConfig>,
+    bindings: DashMap<String, Binding>,
+}
+
+impl SessionAffinity {
+    /// 获取当前配置
+    pub fn config(&self) -> SessionAffinityConfig {
+        self.config.read().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// 更新配置（支持热重载），关闭时清空已有绑定
+    pub fn set_config(&self, config: SessionAffinityConfig) {
+        if let Ok(mut current) = self.config.write() {
+            if *current == config {
+                return;
+            }
+            if current.enabled != config.enabled {
+                tracing::info!(
+                    "[SESSION_AFFINITY] 会话粘性路由: {}",
+                    if config.enabled { "启用" } else { "停用" }
+                );
+            }
+            if !config.enabled {
+                self.bindings.clear();
+            }
+            *current = config;
+        }
+    }
+
+    /// 从请求中提取会话标识，未启用或无法识别会话时返回 `None`
+    ///
+    /// 优先使用配置的请求头，否则在 `hash_first_message` 开启时使用首条用户消息的哈希。
+    pub fn session_key(
+        &self,
+        headers: &HeaderMap,
+        first_user_message: Option<&str>,
+    ) -> Option<String> {
+        let config = self.config();
+        if !config.enabled {
+            return None;
+        }
+        let header = headers
+            .get(config.header.as_str())
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        if let Some(id) = header {
+            return Some(format!("header:{}", id));
+        }
+        if !config.hash_first_message {
+            return None;
+        }
+        let message = first_user_message.filter(|m| !m.trim().is_empty())?;
+        let digest = Sha256::digest(message.as_bytes());
+        Some(format!("message:{}", hex::encode(&digest[..16])))
+    }
+
+    /// 获取会话在指定 Provider 下绑定的凭证 UUID
+    pub fn lookup(&self, provider_type: &str, session_key: &str) -> Option<String> {
+        let ttl = Duration::from_secs(self.config().ttl_secs);
+        let key = Self::binding_key(provider_type, session_key);
+        let binding = self.bindings.get(&key)?.clone();
+        if binding.last_used.elapsed() > ttl {
+            self.bindings.remove(&key);
+            return None;
+        }
+        Some(binding.uuid)
+    }
+
+    /// 将会话绑定到凭证，已绑定其他凭证时改绑
+    pub fn bind(&self, provider_type: &str, session_key: &str, uuid: &str) {
+        let config = self.config();
+        if !config.enabled {
+            return;
+        }
+        let key = Self::binding_key(provider_type, session_key);
+        let previous = self.bindings.get(&key).map(|b| b.uuid.clone());
+        if let Some(previous) = previous {
+            if previous != uuid {
+                tracing::info!(
+                    "[SESSION_AFFINITY] 会话 {} 改绑凭证: {} -> {}",
+                    key,
+                    previous,
+                    uuid
+                );
+            }
+        } else if self.bindings.len() >= config.max_sessions {
+            self.evict(&config);
+        }
+        self.bindings.insert(
+            key,
+            Binding {
+                uuid: uuid.to_string(),
+                last_used: Instant::now(),
+            },
+        );
+    }
+
+    /// 当前的会话绑定数
+    pub fn len(&self) -> usize {
+        self.bindings.len()
+    }
+
+    /// 是否没有任何会话绑定
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// 清理过期绑定，仍然超出上限时淘汰最久未使用的绑定
+    fn evict(&self, config: &SessionAffinityConfig) {
+        let ttl = Duration::from_secs(config.ttl_secs);
+        self.bindings.retain(|_, b| b.last_used.elapsed() <= ttl);
+        while !self.bindings.is_empty() && self.bindings.len() >= config.max_sessions {
+            let oldest = self
+                .bindings
+                .iter()
+                .min_by_key(|entry| entry.last_used)
+                .map(|entry| entry.key().clone());
+            match oldest {
+                Some(key) => {
+                    self.bindings.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn binding_key(provider_type: &str, session_key: &str) -> String {
+        format!("{}:{}", provider_type, session_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> SessionAffinity {
+        let affinity = SessionAffinity::default();
+        affinity.set_config(SessionAffinityConfig {
+            enabled: true,
+            max_sessions: 2,
+            ..SessionAffinityConfig::default()
+        });
+        affinity
+    }
+
+    #[test]
+    fn test_session_key_prefers_header() {
+        let affinity = enabled();
+        let mut headers = HeaderMap::new();
+        assert_eq!(affinity.session_key(&headers, None), None);
+
+        let from_message = affinity.session_key(&headers, Some("hello")).unwrap();
+        assert!(from_message.starts_with("message:"));
+        assert_eq!(
+            affinity.session_key(&headers, Some("hello")),
+            Some(from_message)
+        );
+
+        headers.insert("x-session-id", "abc".parse().unwrap());
+        assert_eq!(
+            affinity.session_key(&headers, Some("hello")),
+            Some("header:abc".to_string())
+        );
+
+        // 未启用时不识别会话
+        let disabled = SessionAffinity::default();
+        assert_eq!(disabled.session_key(&headers, Some("hello")), None);
+    }
+
+    #[test]
+    fn test_bind_and_rebind() {
+        let affinity = enabled();
+        affinity.bind("claude", "s1", "cred-a");
+        assert_eq!(affinity.lookup("claude", "s1").as_deref(), Some("cred-a"));
+        // 绑定按 Provider 隔离
+        assert_eq!(affinity.lookup("openai", "s1"), None);
+
+        affinity.bind("claude", "s1", "cred-b");
+        assert_eq!(affinity.lookup("claude", "s1").as_deref(), Some("cred-b"));
+        assert_eq!(affinity.len(), 1);
+    }
+
+    #[test]
+    fn test_evicts_oldest_binding() {
+        let affinity = enabled();
+        affinity.bind("claude", "s1", "cred-a");
+        std::thread::sleep(Duration::from_millis(2));
+        affinity.bind("claude", "s2", "cred-a");
+        std::thread::sleep(Duration::from_millis(2));
+        affinity.bind("claude", "s3", "cred-b");
+        assert_eq!(affinity.len(), 2);
+        assert_eq!(affinity.lookup("claude", "s1"), None);
+        assert_eq!(affinity.lookup("claude", "s3").as_deref(), Some("cred-b"));
+    }
+
+    #[test]
+    fn test_disable_clears_bindings() {
+        let affinity = enabled();
+        affinity.bind("claude", "s1", "cred-a");
+        affinity.set_config(SessionAffinityConfig::default());
+        assert!(affinity.is_empty());
+        affinity.bind("claude", "s1", "cred-a");
+        assert!(affinity.is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+//! 共享存储服务
+//!
+//! `storage.backend` 为 `postgres` 时连接共享库：定期与本地 SQLite 双向同步凭证池，
+//! 将请求日志和 Token 用量异步写入共享库，供多个实例共享凭证池和跨实例统计。
+
+use crate::config::{StorageBackend, StorageConfig};
+use crate::database::storage::{self, SqliteStorage, Storage};
+use crate::database::DbConnection;
+use crate::services::secret_store;
+use crate::telemetry::{RequestLog, TokenUsageRecord};
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock, Weak};
+use std::time::{Duration, Instant};
+
+/// 共享库过期记录清理间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 共享存储服务
+pub struct SharedStorageService {
+    config: StorageConfig,
+    backend: RwLock<Option<Arc<dyn Storage>>>,
+    /// 上一轮同步后两端都存在的凭证 UUID
+    known_credentials: tokio::sync::Mutex<HashSet<String>>,
+}
+
+impl SharedStorageService {
+    pub fn new(config: StorageConfig) -> Self {
+        Self {
+            config,
+            backend: RwLock::new(None),
+            known_credentials: tokio::sync::Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// 是否配置了共享存储
+    pub fn is_enabled(&self) -> bool {
+        self.config.backend != StorageBackend::Sqlite
+    }
+
+    /// 已连接的共享存储
+    pub fn backend(&self) -> Option<Arc<dyn Storage>> {
+        self.backend.read().ok().and_then(|b| b.clone())
+    }
+
+    /// 共享库中记录的保留天数
+    pub fn retention_days(&self) -> u32 {
+        self.config.retention_days
+    }
+
+    /// 连接共享存储（已连接时直接返回）
+    pub async fn connect(&self) -> Result<Option<Arc<dyn Storage>>, String> {
+        if let Some(backend) = self.backend() {
+            return Ok(Some(backend));
+        }
+        let backend = storage::connect_shared(&self.config).await?;
+        if let Some(backend) = &backend {
+            tracing::info!("[STORAGE] 已连接共享存储: {}", backend.name());
+            if let Ok(mut current) = self.backend.write() {
+                *current = Some(backend.clone());
+            }
+        }
+        Ok(backend)
+    }
+
+    /// 与共享存储同步凭证池，返回本轮变更的凭证数
+    ///
+    /// 凭证加密使用本机钥匙串密钥时拒绝同步，避免写入其他实例无法解密的凭证
+    pub async fn sync_credentials(&self, db: &DbConnection) -> Result<usize, String> {
+        let Some(remote) = self.backend() else {
+            return Ok(0);
+        };
+        if secret_store::is_enabled() && !secret_store::is_key_shareable() {
+            return Err(
+                "凭证加密使用本机钥匙串中的主密钥，其他实例无法解密；请改用主口令（secrets.key_source: passphrase）后再同步凭证池"
+                    .to_string(),
+            );
+        }
+        let local = SqliteStorage::new(db.clone());
+        let mut known = self.known_credentials.lock().await;
+        let plan = storage::sync_credentials(&local, remote.as_ref(), &known).await?;
+        let changed =
+            plan.push.len() + plan.pull.len() + plan.delete_local.len() + plan.delete_remote.len();
+        if !plan.is_empty() {
+            tracing::info!(
+                "[STORAGE] 凭证池已同步: 上传 {} 个, 拉取 {} 个, 本地删除 {} 个, 共享库删除 {} 个",
+                plan.push.len(),
+                plan.pull.len(),
+                plan.delete_local.len(),
+                plan.delete_remote.len()
+            );
+        }
+        *known = plan.known;
+        Ok(changed)
+    }
+
+    /// 异步写入请求日志到共享存储
+    pub fn record_request_log(&self, log: &RequestLog) {
+        let Some(backend) = self.backend() else {
+            return;
+        };
+        let log = log.clone();
+        tokio::spawn(async move {
+            if let Err(e) = backend.upsert_request_log(&log).await {
+                tracing::warn!("[STORAGE] 写入共享请求日志失败: {}", e);
+            }
+        });
+    }
+
+    /// 流式响应结束时更新共享请求日志的持续时间
+    pub fn update_request_log_duration(&self, id: &str, duration_ms: u64) {
+        let Some(backend) = self.backend() else {
+            return;
+        };
+        let id = id.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = backend.update_request_log_duration(&id, duration_ms).await {
+                tracing::warn!("[STORAGE] 更新共享请求日志耗时失败: {}", e);
+            }
+        });
+    }
+
+    /// 异步写入 Token 用量到共享存储
+    pub fn record_token_usage(&self, record: &TokenUsageRecord) {
+        let Some(backend) = self.backend() else {
+            return;
+        };
+        let record = record.clone();
+        tokio::spawn(async move {
+            if let Err(e) = backend.record_token_usage(&record).await {
+                tracing::warn!("[STORAGE] 写入共享 Token 用量失败: {}", e);
+            }
+        });
+    }
+
+    /// 启动后台同步任务
+    ///
+    /// 连接失败时按同步间隔重试；连接后定期同步凭证池并清理过期记录。
+    /// 服务被释放（服务器停止）后任务自动退出。
+    pub fn start(self: &Arc<Self>, db: DbConnection) {
+        if !self.is_enabled() {
+            return;
+        }
+        let service: Weak<Self> = Arc::downgrade(self);
+        let interval = Duration::from_secs(self.config.sync_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut last_cleanup: Option<Instant> = None;
+            loop {
+                ticker.tick().await;
+                let Some(service) = service.upgrade() else {
+                    break;
+                };
+
+                if service.backend().is_none() {
+                    if let Err(e) = service.connect().await {
+                        tracing::warn!("[STORAGE] 连接共享存储失败，稍后重试: {}", e);
+                        continue;
+                    }
+                }
+
+                if let Err(e) = service.sync_credentials(&db).await {
+                    tracing::warn!("[STORAGE] 同步凭证池失败: {}", e);
+                }
+
+                if last_cleanup.is_none_or(|t| t.elapsed() >= CLEANUP_INTERVAL) {
+                    last_cleanup = Some(Instant::now());
+                    service.cleanup_expired().await;
+                }
+            }
+        });
+    }
+
+    /// 清理共享库中超过保留天数的请求日志和 Token 用量
+    async fn cleanup_expired(&self) {
+        let Some(backend) = self.backend() else {
+            return;
+        };
+        let cutoff = Utc::now() - chrono::Duration::days(self.config.retention_days as i64);
+        match backend.delete_request_logs_before(cutoff).await {
+            Ok(removed) if removed > 0 => {
+                tracing::info!("[STORAGE] 清理共享库过期请求日志 {} 条", removed)
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("[STORAGE] 清理共享库过期请求日志失败: {}", e),
+        }
+        match backend.delete_token_usage_before(cutoff).await {
+            Ok(removed) if removed > 0 => {
+                tracing::info!("[STORAGE] 清理共享库过期 Token 用量 {} 条", removed)
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("[STORAGE] 清理共享库过期 Token 用量失败: {}", e),
+        }
+    }
+}
+
+impl Default for SharedStorageService {
+    fn default() -> Self {
+        Self::new(StorageConfig::default())
+    }
+}
@@ -6,6 +6,7 @@
 
 #![allow(dead_code)]
 //! - 按需刷新即将过期的 Token
+//! - 后台定期提前续期即将过期的 OAuth Token
 //! - 处理 401/403 错误时的强制刷新
 
 use crate::database::dao::provider_pool::ProviderPoolDao;
@@ -53,6 +54,15 @@ pub struct RefreshErrorClassification {
     pub should_disable_credential: bool,
 }
 
+/// 后台续期任务的默认扫描间隔（秒）
+pub const BACKGROUND_REFRESH_INTERVAL_SECS: u64 = 60;
+
+/// 后台续期的默认提前量（分钟），Token 在此时间内过期即提前刷新
+pub const BACKGROUND_REFRESH_LEAD_MINUTES: i64 = 10;
+
+/// 连续刷新失败达到此次数后后台任务不再重试，交由请求路径按需处理
+const BACKGROUND_REFRESH_MAX_ERRORS: u32 = 3;
+
 /// Token 缓存服务
 pub struct TokenCacheService {
     /// 每凭证一把锁，防止并发刷新
@@ -423,7 +433,28 @@ impl TokenCacheService {
     }
 
     /// 执行实际的 Token 刷新
+    #[tracing::instrument(
+        name = "token.refresh",
+        skip_all,
+        fields(
+            provider = %credential.provider_type,
+            credential.id = %credential.uuid,
+            error = tracing::field::Empty,
+        )
+    )]
     async fn do_refresh(&self, credential: &ProviderCredential) -> Result<CachedTokenInfo, String> {
+        let result = self.refresh_by_type(credential).await;
+        if let Err(e) = &result {
+            tracing::Span::current().record("error", e.as_str());
+        }
+        result
+    }
+
+    /// 按凭证类型刷新 Token
+    async fn refresh_by_type(
+        &self,
+        credential: &ProviderCredential,
+    ) -> Result<CachedTokenInfo, String> {
         match &credential.credential {
             CredentialData::KiroOAuth { creds_file_path } => {
                 self.refresh_kiro(creds_file_path).await
@@ -484,6 +515,9 @@ impl TokenCacheService {
             CredentialData::ClaudeOAuth { creds_file_path } => {
                 self.refresh_claude_oauth(creds_file_path).await
             }
+            CredentialData::QwenOAuth { creds_file_path } => {
+                self.refresh_qwen(creds_file_path).await
+            }
             CredentialData::AnthropicKey { api_key, .. } => {
                 // API Key 不需要刷新，直接返回
                 Ok(CachedTokenInfo {
@@ -495,9 +529,58 @@ impl TokenCacheService {
                     last_refresh_error: None,
                 })
             }
+            CredentialData::AzureOpenaiKey { api_key, .. } => {
+                // API Key 不需要刷新，直接返回
+                Ok(CachedTokenInfo {
+                    access_token: Some(api_key.clone()),
+                    refresh_token: None,
+                    expiry_time: None, // 永不过期
+                    last_refresh: Some(Utc::now()),
+                    refresh_error_count: 0,
+                    last_refresh_error: None,
+                })
+            }
+            CredentialData::VertexServiceAccount {
+                creds_file_path,
+                project_id,
+                region,
+            } => {
+                self.refresh_vertex_service_account(creds_file_path, project_id, region)
+                    .await
+            }
+            CredentialData::AwsBedrock { .. } => {
+                Err("AWS Bedrock 使用 SigV4 逐请求签名，没有可缓存的 Token".to_string())
+            }
+            CredentialData::Ollama { .. } => {
+                Err("本地模型服务无需认证，没有可缓存的 Token".to_string())
+            }
+            CredentialData::Mock { .. } => Err("Mock 凭证无需认证，没有可缓存的 Token".to_string()),
         }
     }
 
+    /// 使用服务账号 JWT 换取 Vertex AI access token
+    async fn refresh_vertex_service_account(
+        &self,
+        creds_file_path: &str,
+        project_id: &Option<String>,
+        region: &Option<String>,
+    ) -> Result<CachedTokenInfo, String> {
+        let provider = crate::providers::VertexServiceAccountProvider::load(
+            creds_file_path,
+            project_id.clone(),
+            region.clone(),
+        )?;
+        let token = provider.access_token().await?;
+        Ok(CachedTokenInfo {
+            access_token: Some(token.token),
+            refresh_token: None,
+            expiry_time: Some(token.expires_at),
+            last_refresh: Some(Utc::now()),
+            refresh_error_count: 0,
+            last_refresh_error: None,
+        })
+    }
+
     /// 刷新 Kiro Token
     async fn refresh_kiro(&self, creds_path: &str) -> Result<CachedTokenInfo, String> {
         let mut provider = KiroProvider::new();
@@ -620,6 +703,38 @@ impl TokenCacheService {
         })
     }
 
+    /// 刷新 Qwen OAuth Token
+    async fn refresh_qwen(&self, creds_path: &str) -> Result<CachedTokenInfo, String> {
+        use crate::providers::qwen::QwenProvider;
+
+        let mut provider = QwenProvider::new();
+        provider
+            .load_credentials_from_path(creds_path)
+            .await
+            .map_err(|e| format!("加载 Qwen 凭证失败: {}", e))?;
+
+        let token = provider
+            .refresh_token_with_retry(3)
+            .await
+            .map_err(|e| format!("刷新 Qwen Token 失败: {}", e))?;
+
+        // expiry_date 为毫秒时间戳
+        let expiry_time = provider
+            .credentials
+            .expiry_date
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .unwrap_or_else(|| Utc::now() + chrono::Duration::minutes(50));
+
+        Ok(CachedTokenInfo {
+            access_token: Some(token),
+            refresh_token: provider.credentials.refresh_token.clone(),
+            expiry_time: Some(expiry_time),
+            last_refresh: Some(Utc::now()),
+            refresh_error_count: 0,
+            last_refresh_error: None,
+        })
+    }
+
     /// 刷新 Claude OAuth Token
     async fn refresh_claude_oauth(&self, creds_path: &str) -> Result<CachedTokenInfo, String> {
         use crate::providers::claude_oauth::ClaudeOAuthProvider;
@@ -841,6 +956,28 @@ impl TokenCacheService {
                     last_refresh_error: None,
                 })
             }
+            CredentialData::QwenOAuth { creds_file_path } => {
+                let content = tokio::fs::read_to_string(creds_file_path)
+                    .await
+                    .map_err(|e| format!("读取 Qwen 凭证文件失败: {}", e))?;
+                let creds: serde_json::Value =
+                    serde_json::from_str(&content).map_err(|e| format!("解析凭证失败: {}", e))?;
+
+                let access_token = creds["access_token"].as_str().map(|s| s.to_string());
+                let refresh_token = creds["refresh_token"].as_str().map(|s| s.to_string());
+                let expiry_time = creds["expiry_date"]
+                    .as_i64()
+                    .and_then(chrono::DateTime::from_timestamp_millis);
+
+                Ok(CachedTokenInfo {
+                    access_token,
+                    refresh_token,
+                    expiry_time,
+                    last_refresh: None,
+                    refresh_error_count: 0,
+                    last_refresh_error: None,
+                })
+            }
             CredentialData::AnthropicKey { api_key, .. } => Ok(CachedTokenInfo {
                 access_token: Some(api_key.clone()),
                 refresh_token: None,
@@ -849,6 +986,29 @@ impl TokenCacheService {
                 refresh_error_count: 0,
                 last_refresh_error: None,
             }),
+            CredentialData::AzureOpenaiKey { api_key, .. } => Ok(CachedTokenInfo {
+                access_token: Some(api_key.clone()),
+                refresh_token: None,
+                expiry_time: None,
+                last_refresh: None,
+                refresh_error_count: 0,
+                last_refresh_error: None,
+            }),
+            CredentialData::VertexServiceAccount {
+                creds_file_path,
+                project_id,
+                region,
+            } => {
+                self.refresh_vertex_service_account(creds_file_path, project_id, region)
+                    .await
+            }
+            CredentialData::AwsBedrock { .. } => {
+                Err("AWS Bedrock 使用 SigV4 逐请求签名，没有可缓存的 Token".to_string())
+            }
+            CredentialData::Ollama { .. } => {
+                Err("本地模型服务无需认证，没有可缓存的 Token".to_string())
+            }
+            CredentialData::Mock { .. } => Err("Mock 凭证无需认证，没有可缓存的 Token".to_string()),
         }
     }
 
@@ -1060,4 +1220,177 @@ impl TokenCacheService {
         // 需要刷新（无缓存、已过期或即将过期）
         self.refresh_and_cache(db, uuid, false).await
     }
+
+    /// 启动后台 Token 续期任务
+    ///
+    /// 每隔 `interval_secs` 秒扫描一次凭证池，对 `lead_minutes` 分钟内过期的 OAuth Token
+    /// 提前刷新，使请求路径通常直接命中有效缓存，无需在处理请求时等待刷新。
+    /// 刷新与请求路径共用每凭证锁，不会重复刷新。
+    ///
+    /// # 返回
+    /// 任务句柄（abort 时停止续期任务）
+    pub fn start_background_refresh(
+        self: Arc<Self>,
+        db: DbConnection,
+        interval_secs: u64,
+        lead_minutes: i64,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(interval_secs.max(1)));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let (refreshed, failed) = self.refresh_expiring_tokens(&db, lead_minutes).await;
+                if refreshed > 0 || failed > 0 {
+                    tracing::info!(
+                        "[TOKEN_CACHE] Background refresh finished: {} refreshed, {} failed",
+                        refreshed,
+                        failed
+                    );
+                }
+            }
+        })
+    }
+
+    /// 刷新凭证池中所有即将过期的 Token
+    ///
+    /// # 返回
+    /// `(成功数, 失败数)`
+    pub async fn refresh_expiring_tokens(
+        &self,
+        db: &DbConnection,
+        lead_minutes: i64,
+    ) -> (usize, usize) {
+        let due = match self.collect_due_credentials(db, lead_minutes) {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::warn!("[TOKEN_CACHE] Background refresh scan failed: {}", e);
+                return (0, 0);
+            }
+        };
+
+        let mut refreshed = 0;
+        let mut failed = 0;
+        for uuid in due {
+            // 强制刷新：跳过按需刷新路径的随机延迟和有效性双重检查
+            match self.refresh_and_cache(db, &uuid, true).await {
+                Ok(_) => refreshed += 1,
+                Err(e) => {
+                    failed += 1;
+                    tracing::warn!(
+                        "[TOKEN_CACHE] Background refresh failed for {}: {}",
+                        &uuid[..8],
+                        e
+                    );
+                }
+            }
+        }
+        (refreshed, failed)
+    }
+
+    /// 收集需要后台续期的凭证 UUID
+    fn collect_due_credentials(
+        &self,
+        db: &DbConnection,
+        lead_minutes: i64,
+    ) -> Result<Vec<String>, String> {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+
+        let mut due = Vec::new();
+        for credential in credentials {
+            if credential.is_disabled || !Self::has_refreshable_token(&credential.credential) {
+                continue;
+            }
+            let cache = ProviderPoolDao::get_token_cache(&conn, &credential.uuid)
+                .map_err(|e| e.to_string())?;
+            if Self::is_due_for_background_refresh(cache.as_ref(), lead_minutes) {
+                due.push(credential.uuid);
+            }
+        }
+        Ok(due)
+    }
+
+    /// 凭证是否持有会过期、可刷新的 OAuth Token（API Key 类凭证永不过期）
+    fn has_refreshable_token(credential: &CredentialData) -> bool {
+        matches!(
+            credential,
+            CredentialData::KiroOAuth { .. }
+                | CredentialData::GeminiOAuth { .. }
+                | CredentialData::AntigravityOAuth { .. }
+                | CredentialData::CodexOAuth { .. }
+                | CredentialData::ClaudeOAuth { .. }
+                | CredentialData::QwenOAuth { .. }
+                | CredentialData::VertexServiceAccount { .. }
+        )
+    }
+
+    /// 判断缓存的 Token 是否需要由后台任务提前续期
+    ///
+    /// - 尚无缓存的凭证由首次请求按需加载
+    /// - 连续刷新失败的凭证不再由后台重试，避免反复请求上游
+    fn is_due_for_background_refresh(cache: Option<&CachedTokenInfo>, lead_minutes: i64) -> bool {
+        match cache {
+            Some(cache) => {
+                cache.refresh_error_count < BACKGROUND_REFRESH_MAX_ERRORS
+                    && (!cache.is_valid() || cache.is_expiring_within_minutes(lead_minutes))
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_expiring_in(minutes: i64) -> CachedTokenInfo {
+        CachedTokenInfo {
+            access_token: Some("token".to_string()),
+            refresh_token: Some("refresh".to_string()),
+            expiry_time: Some(Utc::now() + chrono::Duration::minutes(minutes)),
+            last_refresh: Some(Utc::now()),
+            refresh_error_count: 0,
+            last_refresh_error: None,
+        }
+    }
+
+    #[test]
+    fn test_background_refresh_selects_expiring_tokens() {
+        let due = |cache: &CachedTokenInfo| {
+            TokenCacheService::is_due_for_background_refresh(Some(cache), 10)
+        };
+
+        assert!(due(&cache_expiring_in(5)));
+        assert!(due(&cache_expiring_in(-1)));
+        assert!(!due(&cache_expiring_in(30)));
+
+        // 没有过期时间的 Token 视为长期有效
+        let mut no_expiry = cache_expiring_in(0);
+        no_expiry.expiry_time = None;
+        assert!(!due(&no_expiry));
+
+        // 连续失败的凭证交由请求路径处理
+        let mut failing = cache_expiring_in(5);
+        failing.refresh_error_count = BACKGROUND_REFRESH_MAX_ERRORS;
+        assert!(!due(&failing));
+
+        assert!(!TokenCacheService::is_due_for_background_refresh(None, 10));
+    }
+
+    #[test]
+    fn test_background_refresh_skips_api_keys() {
+        assert!(TokenCacheService::has_refreshable_token(
+            &CredentialData::KiroOAuth {
+                creds_file_path: "kiro.json".to_string(),
+            }
+        ));
+        assert!(!TokenCacheService::has_refreshable_token(
+            &CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: None,
+            }
+        ));
+    }
 }
@@ -177,6 +177,7 @@ mod tests {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            extra: Default::default(),
         };
 
         let sid1 = SessionManager::extract_session_id(&request);
@@ -209,6 +210,7 @@ mod tests {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            extra: Default::default(),
         };
 
         let request2 = ChatCompletionRequest {
@@ -229,6 +231,7 @@ mod tests {
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            extra: Default::default(),
         };
 
         let sid1 = SessionManager::extract_session_id(&request1);
@@ -11,6 +11,8 @@
 //! - 需求 3.3: Anthropic SSE 到 OpenAI SSE 转换
 //! - 需求 3.5: 处理工具调用参数中的部分 JSON
 
+use crate::converter::reasoning::ReasoningMode;
+use crate::converter::response_format::JSON_RESPONSE_TOOL;
 use crate::streaming::aws_parser::{AwsEvent, AwsEventStreamParser};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -62,6 +64,8 @@ struct ToolCallAccumulator {
     started: bool,
     /// 内容块索引（用于 Anthropic 格式）
     index: u32,
+    /// OpenAI `tool_calls` 中的索引（按工具调用出现顺序从 0 开始）
+    tool_index: u32,
 }
 
 /// 部分 JSON 累积器
@@ -164,6 +168,16 @@ pub struct StreamConverter {
     message_started: bool,
     /// 累积的内容（用于重建完整响应）
     accumulated_content: String,
+    /// 已发出的工具调用数量（用于 OpenAI 格式的索引和 finish_reason）
+    tool_call_count: u32,
+    /// 推理内容（thinking 块）处理方式
+    reasoning_mode: ReasoningMode,
+    /// 当前 thinking 块的索引（用于 Anthropic SSE 到 OpenAI SSE）
+    thinking_block_index: Option<u32>,
+    /// 是否将 `json_response` 工具调用还原为正文（结构化输出）
+    json_response_tool: bool,
+    /// `json_response` 工具块的索引
+    json_block_index: Option<u32>,
 }
 
 impl StreamConverter {
@@ -186,6 +200,11 @@ impl StreamConverter {
             next_content_block_index: 0,
             message_started: false,
             accumulated_content: String::new(),
+            tool_call_count: 0,
+            reasoning_mode: ReasoningMode::default(),
+            thinking_block_index: None,
+            json_response_tool: false,
+            json_block_index: None,
         }
     }
 
@@ -196,6 +215,18 @@ impl StreamConverter {
         converter
     }
 
+    /// 设置推理内容处理方式
+    pub fn with_reasoning_mode(mut self, mode: ReasoningMode) -> Self {
+        self.reasoning_mode = mode;
+        self
+    }
+
+    /// 设置是否将 `json_response` 工具调用的参数作为正文输出
+    pub fn with_json_response_tool(mut self, enabled: bool) -> Self {
+        self.json_response_tool = enabled;
+        self
+    }
+
     /// 获取当前状态
     pub fn state(&self) -> &ConverterState {
         &self.state
@@ -222,6 +253,9 @@ impl StreamConverter {
         self.next_content_block_index = 0;
         self.message_started = false;
         self.accumulated_content.clear();
+        self.tool_call_count = 0;
+        self.thinking_block_index = None;
+        self.json_block_index = None;
     }
 
     /// 转换 chunk
@@ -342,6 +376,7 @@ impl StreamConverter {
                         input: String::new(),
                         started: true,
                         index,
+                        tool_index: 0,
                     },
                 );
 
@@ -400,7 +435,9 @@ impl StreamConverter {
                 sse_events.push(self.create_openai_content_chunk(text, false));
             }
             AwsEvent::ToolUseStart { id, name } => {
-                let index = self.tool_accumulators.len() as u32;
+                // 已结束的工具调用会从累积器中移除，索引需单独计数避免重复
+                let index = self.tool_call_count;
+                self.tool_call_count += 1;
                 self.tool_accumulators.insert(
                     id.clone(),
                     ToolCallAccumulator {
@@ -409,6 +446,7 @@ impl StreamConverter {
                         input: String::new(),
                         started: true,
                         index,
+                        tool_index: index,
                     },
                 );
                 // 发送工具调用开始 chunk
@@ -491,6 +529,10 @@ impl StreamConverter {
                                         self.accumulated_content.push_str(text);
                                         sse_events
                                             .push(self.create_openai_content_chunk(text, false));
+                                    } else if let Some(thinking) =
+                                        delta.get("thinking").and_then(|t| t.as_str())
+                                    {
+                                        sse_events.extend(self.convert_thinking_delta(thinking));
                                     } else if let Some(partial_json) =
                                         delta.get("partial_json").and_then(|t| t.as_str())
                                     {
@@ -500,13 +542,24 @@ impl StreamConverter {
                                             .and_then(|i| i.as_u64())
                                             .unwrap_or(0)
                                             as u32;
+                                        if self.json_block_index == Some(index) {
+                                            // 结构化输出：工具参数即正文
+                                            self.accumulated_content.push_str(partial_json);
+                                            sse_events.push(
+                                                self.create_openai_content_chunk(
+                                                    partial_json,
+                                                    false,
+                                                ),
+                                            );
+                                            continue;
+                                        }
                                         let tool_info = self
                                             .tool_accumulators
                                             .values_mut()
                                             .find(|a| a.index == index)
                                             .map(|acc| {
                                                 acc.input.push_str(partial_json);
-                                                (acc.index, acc.id.clone(), acc.name.clone())
+                                                (acc.tool_index, acc.id.clone(), acc.name.clone())
                                             });
                                         if let Some((idx, tool_id, tool_name)) = tool_info {
                                             sse_events.push(self.create_openai_tool_call_chunk(
@@ -522,9 +575,24 @@ impl StreamConverter {
                             }
                             "content_block_start" => {
                                 if let Some(content_block) = event.get("content_block") {
-                                    if content_block.get("type").and_then(|t| t.as_str())
-                                        == Some("tool_use")
-                                    {
+                                    let block_type =
+                                        content_block.get("type").and_then(|t| t.as_str());
+                                    if block_type == Some("thinking") {
+                                        let index = event
+                                            .get("index")
+                                            .and_then(|i| i.as_u64())
+                                            .unwrap_or(0)
+                                            as u32;
+                                        self.thinking_block_index = Some(index);
+                                        if self.reasoning_mode == ReasoningMode::Passthrough {
+                                            sse_events.push(
+                                                self.create_openai_content_chunk(
+                                                    "<thinking>",
+                                                    false,
+                                                ),
+                                            );
+                                        }
+                                    } else if block_type == Some("tool_use") {
                                         let id = content_block
                                             .get("id")
                                             .and_then(|i| i.as_str())
@@ -538,6 +606,13 @@ impl StreamConverter {
                                             .and_then(|i| i.as_u64())
                                             .unwrap_or(0)
                                             as u32;
+                                        if self.json_response_tool && name == JSON_RESPONSE_TOOL {
+                                            self.json_block_index = Some(index);
+                                            continue;
+                                        }
+                                        // Anthropic 的块索引包含文本块，OpenAI 的工具索引从 0 开始
+                                        let tool_index = self.tool_call_count;
+                                        self.tool_call_count += 1;
                                         self.tool_accumulators.insert(
                                             id.to_string(),
                                             ToolCallAccumulator {
@@ -546,16 +621,37 @@ impl StreamConverter {
                                                 input: String::new(),
                                                 started: true,
                                                 index,
+                                                tool_index,
                                             },
                                         );
                                         sse_events.push(self.create_openai_tool_call_chunk(
-                                            index, id, name, "", true,
+                                            tool_index, id, name, "", true,
                                         ));
                                     }
                                 }
                             }
+                            "content_block_stop" => {
+                                let index =
+                                    event.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as u32;
+                                if self.thinking_block_index == Some(index) {
+                                    self.thinking_block_index = None;
+                                    if self.reasoning_mode == ReasoningMode::Passthrough {
+                                        sse_events.push(
+                                            self.create_openai_content_chunk(
+                                                "</thinking>\n\n",
+                                                false,
+                                            ),
+                                        );
+                                    }
+                                }
+                            }
                             "message_stop" => {
-                                sse_events.push(self.create_openai_finish_chunk("stop"));
+                                let finish_reason = if self.tool_call_count > 0 {
+                                    "tool_calls"
+                                } else {
+                                    "stop"
+                                };
+                                sse_events.push(self.create_openai_finish_chunk(finish_reason));
                                 sse_events.push("data: [DONE]\n\n".to_string());
                             }
                             _ => {}
@@ -568,6 +664,18 @@ impl StreamConverter {
         sse_events
     }
 
+    /// 按推理内容处理方式转换 thinking_delta
+    fn convert_thinking_delta(&mut self, thinking: &str) -> Vec<String> {
+        match self.reasoning_mode {
+            ReasoningMode::Strip => Vec::new(),
+            ReasoningMode::Passthrough => {
+                self.accumulated_content.push_str(thinking);
+                vec![self.create_openai_content_chunk(thinking, false)]
+            }
+            ReasoningMode::Map => vec![self.create_openai_reasoning_chunk(thinking)],
+        }
+    }
+
     /// 转换 OpenAI SSE（直通）
     fn convert_openai_sse(&mut self, chunk: &[u8]) -> Vec<String> {
         match String::from_utf8(chunk.to_vec()) {
@@ -588,10 +696,10 @@ impl StreamConverter {
                 events
             }
             StreamFormat::OpenAiSse => {
-                let finish_reason = if self.tool_accumulators.is_empty() {
-                    "stop"
-                } else {
+                let finish_reason = if self.tool_call_count > 0 {
                     "tool_calls"
+                } else {
+                    "stop"
                 };
                 vec![
                     self.create_openai_finish_chunk(finish_reason),
@@ -741,6 +849,23 @@ impl StreamConverter {
         format!("data: {}\n\n", chunk)
     }
 
+    fn create_openai_reasoning_chunk(&self, reasoning: &str) -> String {
+        let chunk = serde_json::json!({
+            "id": self.response_id,
+            "object": "chat.completion.chunk",
+            "created": self.get_created_timestamp(),
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "delta": {
+                    "reasoning_content": reasoning
+                },
+                "finish_reason": null
+            }]
+        });
+        format!("data: {}\n\n", chunk)
+    }
+
     fn create_openai_tool_call_chunk(
         &self,
         index: u32,
@@ -1026,6 +1151,56 @@ mod tests {
         assert!(has_tool_call);
     }
 
+    #[test]
+    fn test_aws_to_openai_sequential_tool_calls() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::AwsEventStream,
+            StreamFormat::OpenAiSse,
+            "test-model",
+        );
+
+        let mut events = Vec::new();
+        for id in ["tool_1", "tool_2"] {
+            events.extend(converter.convert(
+                format!("{{\"toolUseId\":\"{}\",\"name\":\"read_file\"}}", id).as_bytes(),
+            ));
+            events
+                .extend(converter.convert(
+                    format!("{{\"toolUseId\":\"{}\",\"input\":\"{{}}\"}}", id).as_bytes(),
+                ));
+            events.extend(
+                converter.convert(format!("{{\"toolUseId\":\"{}\",\"stop\":true}}", id).as_bytes()),
+            );
+        }
+        events.extend(converter.finish());
+
+        let tool_calls: Vec<serde_json::Value> = events
+            .iter()
+            .filter_map(|e| e.strip_prefix("data: "))
+            .filter_map(|data| serde_json::from_str::<serde_json::Value>(data.trim()).ok())
+            .filter_map(|chunk| {
+                chunk["choices"][0]["delta"]["tool_calls"][0]
+                    .as_object()
+                    .cloned()
+            })
+            .map(serde_json::Value::Object)
+            .collect();
+
+        // 每个工具调用：首个分片携带 id/name，后续分片只携带 arguments
+        assert_eq!(tool_calls.len(), 4);
+        assert_eq!(tool_calls[0]["index"], 0);
+        assert_eq!(tool_calls[0]["id"], "tool_1");
+        assert_eq!(tool_calls[0]["function"]["arguments"], "");
+        assert_eq!(tool_calls[1]["index"], 0);
+        assert!(tool_calls[1].get("id").is_none());
+        assert_eq!(tool_calls[2]["index"], 1);
+        assert_eq!(tool_calls[2]["id"], "tool_2");
+
+        assert!(events
+            .iter()
+            .any(|e| e.contains("\"finish_reason\":\"tool_calls\"")));
+    }
+
     #[test]
     fn test_aws_to_anthropic_content() {
         let mut converter = StreamConverter::with_model(
@@ -1084,6 +1259,74 @@ mod tests {
         assert_eq!(converter.state(), &ConverterState::Completed);
     }
 
+    #[test]
+    fn test_anthropic_to_openai_thinking_modes() {
+        let sse = concat!(
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"thinking\",\"thinking\":\"\"}}\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"plan\"}}\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"text_delta\",\"text\":\"answer\"}}\n",
+        );
+        let convert = |mode: ReasoningMode| {
+            let mut converter = StreamConverter::with_model(
+                StreamFormat::AnthropicSse,
+                StreamFormat::OpenAiSse,
+                "claude-sonnet-4-5",
+            )
+            .with_reasoning_mode(mode);
+            converter.convert(sse.as_bytes())
+        };
+
+        let mapped = convert(ReasoningMode::Map);
+        assert_eq!(mapped.len(), 2);
+        assert!(mapped[0].contains("\"reasoning_content\":\"plan\""));
+        assert_eq!(
+            extract_content_from_sse(&mapped, StreamFormat::OpenAiSse),
+            "answer"
+        );
+
+        let inline = convert(ReasoningMode::Passthrough);
+        assert_eq!(
+            extract_content_from_sse(&inline, StreamFormat::OpenAiSse),
+            "<thinking>plan</thinking>\n\nanswer"
+        );
+
+        let stripped = convert(ReasoningMode::Strip);
+        assert_eq!(stripped.len(), 1);
+        assert_eq!(
+            extract_content_from_sse(&stripped, StreamFormat::OpenAiSse),
+            "answer"
+        );
+    }
+
+    #[test]
+    fn test_anthropic_to_openai_json_response_tool() {
+        let sse = concat!(
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"json_response\",\"input\":{}}}\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"city\\\":\"}}\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"Paris\\\"}\"}}\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n",
+            "data: {\"type\":\"message_stop\"}\n",
+        );
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::AnthropicSse,
+            StreamFormat::OpenAiSse,
+            "claude-sonnet-4-5",
+        )
+        .with_json_response_tool(true);
+        let events = converter.convert(sse.as_bytes());
+
+        assert_eq!(
+            extract_content_from_sse(&events, StreamFormat::OpenAiSse),
+            "{\"city\":\"Paris\"}"
+        );
+        assert!(events.iter().all(|e| !e.contains("tool_calls")));
+        assert!(events
+            .iter()
+            .any(|e| e.contains("\"finish_reason\":\"stop\"")));
+    }
+
     #[test]
     fn test_partial_json_accumulator() {
         let mut acc = PartialJsonAccumulator::new();
@@ -0,0 +1,431 @@
+//! AWS Event Stream（`application/vnd.amazon.eventstream`）二进制帧解析
+//!
+//! Bedrock ConverseStream 与 Kiro/CodeWhisperer 的响应都使用该格式。帧结构（整数均为大端序）：
+//!
+//! ```text
+//! 总长度(4) | 头部长度(4) | prelude CRC(4) | 头部 | 负载 | 消息 CRC(4)
+//! ```
+//!
+//! prelude CRC 覆盖前 8 字节，消息 CRC 覆盖除末尾 4 字节外的整帧，均为 CRC32（IEEE）。
+
+use std::collections::HashMap;
+
+/// prelude 长度（总长度 + 头部长度 + prelude CRC）
+const PRELUDE_LEN: usize = 12;
+/// 最小帧长度（prelude + 消息 CRC）
+const MIN_FRAME_LEN: usize = PRELUDE_LEN + 4;
+/// 单帧最大长度（AWS 规范上限 16 MiB）
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+/// 头部最大长度（AWS 规范上限 128 KiB）
+const MAX_HEADERS_LEN: usize = 128 * 1024;
+
+/// AWS event stream 消息
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventStreamMessage {
+    /// 字符串类型的头部（如 `:event-type`、`:message-type`）
+    pub headers: HashMap<String, String>,
+    pub payload: Vec<u8>,
+}
+
+impl EventStreamMessage {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    /// `:event-type` 头部
+    pub fn event_type(&self) -> Option<&str> {
+        self.header(":event-type")
+    }
+
+    /// 是否为异常/错误消息（`:message-type` 为 `exception` 或 `error`）
+    pub fn is_exception(&self) -> bool {
+        matches!(self.header(":message-type"), Some("exception" | "error"))
+    }
+}
+
+/// AWS event stream 帧解码器
+///
+/// 支持跨 chunk 的增量解码；只保留字符串类型的头部，其余类型按长度跳过。
+/// prelude 与消息 CRC 不匹配时返回错误，此后解码器不再可用。
+#[derive(Debug, Default)]
+pub struct EventStreamDecoder {
+    buffer: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加数据并返回已完整接收的消息
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<EventStreamMessage>, String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut messages = Vec::new();
+        while self.buffer.len() >= PRELUDE_LEN {
+            let total_len = read_u32(&self.buffer[0..4]) as usize;
+            let headers_len = read_u32(&self.buffer[4..8]) as usize;
+            let prelude_crc = read_u32(&self.buffer[8..12]);
+            if crc32(&self.buffer[..8]) != prelude_crc {
+                return Err(format!(
+                    "event stream prelude CRC 校验失败: total={} headers={}",
+                    total_len, headers_len
+                ));
+            }
+            if total_len < MIN_FRAME_LEN + headers_len
+                || total_len > MAX_FRAME_LEN
+                || headers_len > MAX_HEADERS_LEN
+            {
+                return Err(format!(
+                    "无效的 event stream 帧: total={} headers={}",
+                    total_len, headers_len
+                ));
+            }
+            if self.buffer.len() < total_len {
+                break;
+            }
+
+            let message_crc = read_u32(&self.buffer[total_len - 4..total_len]);
+            if crc32(&self.buffer[..total_len - 4]) != message_crc {
+                return Err(format!(
+                    "event stream 消息 CRC 校验失败: total={}",
+                    total_len
+                ));
+            }
+
+            let frame: Vec<u8> = self.buffer.drain(..total_len).collect();
+            let payload_start = PRELUDE_LEN + headers_len;
+            messages.push(EventStreamMessage {
+                headers: parse_headers(&frame[PRELUDE_LEN..payload_start])?,
+                payload: frame[payload_start..total_len - 4].to_vec(),
+            });
+        }
+        Ok(messages)
+    }
+
+    /// 缓冲区中尚未组成完整帧的字节数
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// 一次性解码完整的 event stream 数据
+///
+/// 要求数据恰好由若干完整帧组成，末尾有残缺帧时返回错误。
+pub fn decode_all(data: &[u8]) -> Result<Vec<EventStreamMessage>, String> {
+    let mut decoder = EventStreamDecoder::new();
+    let messages = decoder.push(data)?;
+    if decoder.pending() > 0 {
+        return Err(format!(
+            "event stream 末尾有 {} 字节不完整的帧",
+            decoder.pending()
+        ));
+    }
+    Ok(messages)
+}
+
+/// 编码一帧只包含字符串头部的 event stream 消息
+pub fn encode_message(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+    let mut header_bytes = Vec::new();
+    for (name, value) in headers {
+        header_bytes.push(name.len() as u8);
+        header_bytes.extend_from_slice(name.as_bytes());
+        header_bytes.push(7);
+        header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        header_bytes.extend_from_slice(value.as_bytes());
+    }
+    encode_frame(&header_bytes, payload)
+}
+
+fn encode_frame(header_bytes: &[u8], payload: &[u8]) -> Vec<u8> {
+    let total_len = MIN_FRAME_LEN + header_bytes.len() + payload.len();
+    let mut frame = Vec::with_capacity(total_len);
+    frame.extend_from_slice(&(total_len as u32).to_be_bytes());
+    frame.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+    let prelude_crc = crc32(&frame);
+    frame.extend_from_slice(&prelude_crc.to_be_bytes());
+    frame.extend_from_slice(header_bytes);
+    frame.extend_from_slice(payload);
+    let message_crc = crc32(&frame);
+    frame.extend_from_slice(&message_crc.to_be_bytes());
+    frame
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn parse_headers(mut data: &[u8]) -> Result<HashMap<String, String>, String> {
+    const TRUNCATED: &str = "event stream 头部被截断";
+    let mut headers = HashMap::new();
+    while !data.is_empty() {
+        let name_len = data[0] as usize;
+        if data.len() < 2 + name_len {
+            return Err(TRUNCATED.to_string());
+        }
+        let name = String::from_utf8_lossy(&data[1..1 + name_len]).to_string();
+        let value_type = data[1 + name_len];
+        data = &data[2 + name_len..];
+
+        let value_len = match value_type {
+            // bool true / false 没有值
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            // bytes / string: 2 字节长度前缀
+            6 | 7 => {
+                if data.len() < 2 {
+                    return Err(TRUNCATED.to_string());
+                }
+                let len = u16::from_be_bytes([data[0], data[1]]) as usize;
+                data = &data[2..];
+                len
+            }
+            other => return Err(format!("未知的 event stream 头部类型: {}", other)),
+        };
+        if data.len() < value_len {
+            return Err(TRUNCATED.to_string());
+        }
+        if value_type == 7 {
+            headers.insert(
+                name,
+                String::from_utf8_lossy(&data[..value_len]).to_string(),
+            );
+        }
+        data = &data[value_len..];
+    }
+    Ok(headers)
+}
+
+/// CRC32（IEEE 802.3，多项式 0xEDB88320）查找表
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kiro assistantResponseEvent 帧，CRC 由 zlib.crc32 独立计算
+    const KIRO_CONTENT_FRAME: &str = concat!(
+        "0000007c0000005c3bddcf78",
+        "0b3a6576656e742d74797065070016617373697374616e74526573706f6e73654576656e74",
+        "0d3a636f6e74656e742d747970650700106170706c69636174696f6e2f6a736f6e",
+        "0d3a6d6573736167652d747970650700056576656e74",
+        "7b22636f6e74656e74223a224869227d",
+        "b02babdc",
+    );
+
+    fn fixture(hex_str: &str) -> Vec<u8> {
+        hex::decode(hex_str).unwrap()
+    }
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_decode_empty_message() {
+        // AWS event stream 测试套件 empty_message 用例
+        let data = fixture("000000100000000005c248eb7d98c8ff");
+        let messages = decode_all(&data).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].headers.is_empty());
+        assert!(messages[0].payload.is_empty());
+        assert_eq!(encode_message(&[], b""), data);
+    }
+
+    #[test]
+    fn test_decode_captured_kiro_frame() {
+        let data = fixture(KIRO_CONTENT_FRAME);
+        let messages = decode_all(&data).unwrap();
+        assert_eq!(messages.len(), 1);
+        let message = &messages[0];
+        assert_eq!(message.event_type(), Some("assistantResponseEvent"));
+        assert_eq!(message.header(":content-type"), Some("application/json"));
+        assert!(!message.is_exception());
+        assert_eq!(message.payload, br#"{"content":"Hi"}"#);
+
+        let encoded = encode_message(
+            &[
+                (":event-type", "assistantResponseEvent"),
+                (":content-type", "application/json"),
+                (":message-type", "event"),
+            ],
+            br#"{"content":"Hi"}"#,
+        );
+        assert_eq!(encoded, data);
+    }
+
+    #[test]
+    fn test_decode_byte_by_byte() {
+        let mut data = fixture(KIRO_CONTENT_FRAME);
+        data.extend(encode_message(
+            &[(":event-type", "meteringEvent"), (":message-type", "event")],
+            br#"{"unit":"credit","usage":0.34}"#,
+        ));
+
+        let mut decoder = EventStreamDecoder::new();
+        let mut messages = Vec::new();
+        for byte in &data {
+            messages.extend(decoder.push(std::slice::from_ref(byte)).unwrap());
+        }
+        assert_eq!(decoder.pending(), 0);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].event_type(), Some("meteringEvent"));
+    }
+
+    #[test]
+    fn test_payload_containing_frame_like_bytes() {
+        // 负载中包含看起来像 JSON 起始模式和 prelude 的字节，不应影响分帧
+        let inner = encode_message(&[(":event-type", "x")], br#"{"content":"fake"}"#);
+        let mut payload = br#"{"content":"{\"content\":\"nested\"}"}"#.to_vec();
+        payload.extend_from_slice(&inner);
+        let data = encode_message(&[(":event-type", "assistantResponseEvent")], &payload);
+
+        let messages = decode_all(&data).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].payload, payload);
+    }
+
+    #[test]
+    fn test_non_string_headers_are_skipped() {
+        let mut header_bytes = Vec::new();
+        // bool true
+        header_bytes.extend_from_slice(&[4, b'f', b'l', b'a', b'g', 0]);
+        // int32
+        header_bytes.extend_from_slice(&[3, b'n', b'u', b'm', 4, 0, 0, 0, 42]);
+        // timestamp
+        header_bytes.extend_from_slice(&[2, b't', b's', 8]);
+        header_bytes.extend_from_slice(&1_700_000_000_000i64.to_be_bytes());
+        // bytes
+        header_bytes.extend_from_slice(&[3, b'b', b'i', b'n', 6, 0, 2, 0xFF, 0x00]);
+        // uuid
+        header_bytes.extend_from_slice(&[2, b'i', b'd', 9]);
+        header_bytes.extend_from_slice(&[0xAB; 16]);
+        // string
+        header_bytes.extend_from_slice(&[11]);
+        header_bytes.extend_from_slice(b":event-type");
+        header_bytes.extend_from_slice(&[7, 0, 4]);
+        header_bytes.extend_from_slice(b"ping");
+
+        let data = encode_frame(&header_bytes, b"{}");
+        let messages = decode_all(&data).unwrap();
+        assert_eq!(messages[0].headers.len(), 1);
+        assert_eq!(messages[0].event_type(), Some("ping"));
+        assert_eq!(messages[0].payload, b"{}");
+    }
+
+    #[test]
+    fn test_exception_message() {
+        let data = encode_message(
+            &[
+                (":message-type", "exception"),
+                (":exception-type", "ThrottlingException"),
+            ],
+            br#"{"message":"Too many requests"}"#,
+        );
+        let messages = decode_all(&data).unwrap();
+        assert!(messages[0].is_exception());
+        assert_eq!(
+            messages[0].header(":exception-type"),
+            Some("ThrottlingException")
+        );
+    }
+
+    #[test]
+    fn test_prelude_crc_mismatch() {
+        let mut data = fixture(KIRO_CONTENT_FRAME);
+        data[11] ^= 0x01;
+        let err = decode_all(&data).unwrap_err();
+        assert!(err.contains("prelude CRC"), "{}", err);
+    }
+
+    #[test]
+    fn test_message_crc_mismatch() {
+        let mut data = fixture(KIRO_CONTENT_FRAME);
+        // 篡改负载中的一个字节
+        let idx = data.len() - 6;
+        data[idx] ^= 0x20;
+        let err = decode_all(&data).unwrap_err();
+        assert!(err.contains("消息 CRC"), "{}", err);
+    }
+
+    #[test]
+    fn test_invalid_lengths() {
+        // 总长度小于 prelude + 头部 + CRC
+        let mut prelude = Vec::new();
+        prelude.extend_from_slice(&16u32.to_be_bytes());
+        prelude.extend_from_slice(&8u32.to_be_bytes());
+        let crc = crc32(&prelude);
+        prelude.extend_from_slice(&crc.to_be_bytes());
+        assert!(decode_all(&prelude)
+            .unwrap_err()
+            .contains("无效的 event stream 帧"));
+
+        // 总长度超过上限
+        let mut prelude = Vec::new();
+        prelude.extend_from_slice(&(MAX_FRAME_LEN as u32 + 1).to_be_bytes());
+        prelude.extend_from_slice(&0u32.to_be_bytes());
+        let crc = crc32(&prelude);
+        prelude.extend_from_slice(&crc.to_be_bytes());
+        assert!(decode_all(&prelude).is_err());
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        let data = fixture(KIRO_CONTENT_FRAME);
+        let mut decoder = EventStreamDecoder::new();
+        assert!(decoder.push(&data[..data.len() - 1]).unwrap().is_empty());
+        assert_eq!(decoder.pending(), data.len() - 1);
+
+        let err = decode_all(&data[..data.len() - 1]).unwrap_err();
+        assert!(err.contains("不完整"), "{}", err);
+        assert!(decode_all(&data[..5]).is_err());
+    }
+
+    #[test]
+    fn test_truncated_headers() {
+        // 字符串头部声明的长度超出头部区域
+        let header_bytes = [2, b'a', b'b', 7, 0, 10, b'x'];
+        let data = encode_frame(&header_bytes, b"");
+        assert!(decode_all(&data).unwrap_err().contains("截断"));
+
+        let header_bytes = [2, b'a', b'b', 42];
+        let data = encode_frame(&header_bytes, b"");
+        assert!(decode_all(&data).unwrap_err().contains("未知"));
+    }
+
+    #[test]
+    fn test_plain_json_is_rejected() {
+        assert!(decode_all(br#"{"content":"Hello"}"#).is_err());
+    }
+}
@@ -8,6 +8,7 @@
 //! - `error`: 流式错误类型定义
 //! - `metrics`: 流式指标类型定义
 //! - `aws_parser`: AWS Event Stream 解析器（用于 Kiro/CodeWhisperer）
+//! - `event_stream`: AWS Event Stream 二进制帧解码器（prelude、头部、CRC 校验）
 //! - `anthropic_sse`: Anthropic SSE 事件生成器（将 AWS 事件转换为 Anthropic SSE 格式）
 //! - `converter`: 流式格式转换器
 //! - `traits`: StreamingProvider trait 定义
@@ -17,6 +18,7 @@ pub mod anthropic_sse;
 pub mod aws_parser;
 pub mod converter;
 pub mod error;
+pub mod event_stream;
 pub mod manager;
 pub mod metrics;
 pub mod traits;
@@ -32,6 +34,7 @@ pub use converter::{
     StreamConverter, StreamFormat,
 };
 pub use error::StreamError;
+pub use event_stream::{EventStreamDecoder, EventStreamMessage};
 pub use manager::{
     collect_stream_content, create_flow_monitor_callback, with_timeout, FlowMonitorCallback,
     ManagedStream, ManagedStreamWithCallback, StreamConfig, StreamContext, StreamEvent,
@@ -152,6 +152,8 @@ pub struct BlockMeta {
     pub term_font_size: Option<f32>,
     /// 终端滚动缓冲区大小
     pub term_scrollback: Option<i32>,
+    /// 是否向远程 Shell 注入集成脚本（默认启用）
+    pub term_shell_integration: Option<bool>,
 }
 
 impl BlockMeta {
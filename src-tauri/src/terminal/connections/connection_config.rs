@@ -1,6 +1,6 @@
 //! 连接配置管理
 //!
-//! 管理用户保存的连接配置，支持本地和 SSH 连接。
+//! 管理用户保存的连接配置，支持本地、SSH、Mosh、Telnet、串口、Docker、Kubernetes 和 WSL 连接。
 //! 配置存储在 `~/.config/proxycast/connections.json`。
 //!
 //! ## 功能
@@ -28,7 +28,9 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
-use super::SSHConfigParser;
+use super::{
+    DockerOpts, K8sOpts, MoshOpts, MoshPredict, SSHConfigParser, SSHOpts, SerialOpts, TelnetOpts,
+};
 
 /// 连接类型
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -38,6 +40,17 @@ pub enum ConnectionConfigType {
     Local,
     /// SSH 远程连接
     Ssh,
+    /// Mosh 远程连接（通过本机 mosh 客户端）
+    Mosh,
+    /// Telnet / 原始 TCP 连接
+    Telnet,
+    /// 串口连接
+    Serial,
+    /// Docker 容器连接
+    Docker,
+    /// Kubernetes Pod 连接
+    #[serde(rename = "k8s")]
+    Kubernetes,
     /// WSL 连接
     Wsl,
 }
@@ -91,6 +104,38 @@ pub struct ConnectionConfig {
     /// WSL 发行版名称
     #[serde(skip_serializing_if = "Option::is_none")]
     pub wsl_distro: Option<String>,
+
+    /// Mosh 预测回显模式
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mosh_predict: Option<MoshPredict>,
+
+    /// 是否为原始 TCP（仅 Telnet 连接，不做 Telnet 协商）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telnet_raw: Option<bool>,
+
+    /// 串口设备路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_port: Option<String>,
+
+    /// 串口波特率（默认 115200）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baud_rate: Option<u32>,
+
+    /// 容器名称（Docker 容器名称或 ID，Kubernetes 为 Pod 中的容器）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+
+    /// Kubernetes 命名空间（默认 default）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Kubernetes Pod 名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod: Option<String>,
+
+    /// kubeconfig 上下文（默认为当前上下文）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kube_context: Option<String>,
 }
 
 impl Default for ConnectionConfig {
@@ -106,6 +151,14 @@ impl Default for ConnectionConfig {
             display_order: None,
             hidden: None,
             wsl_distro: None,
+            mosh_predict: None,
+            telnet_raw: None,
+            serial_port: None,
+            baud_rate: None,
+            container: None,
+            namespace: None,
+            pod: None,
+            kube_context: None,
         }
     }
 }
@@ -128,6 +181,15 @@ impl ConnectionConfig {
         }
     }
 
+    /// 创建 Mosh 连接配置
+    pub fn mosh(host: impl Into<String>) -> Self {
+        Self {
+            conn_type: ConnectionConfigType::Mosh,
+            host: Some(host.into()),
+            ..Default::default()
+        }
+    }
+
     /// 设置用户名
     pub fn with_user(mut self, user: impl Into<String>) -> Self {
         self.user = Some(user.into());
@@ -140,6 +202,77 @@ impl ConnectionConfig {
         self
     }
 
+    /// 转换为 Mosh 连接选项（仅 Mosh 连接）
+    pub fn to_mosh_opts(&self) -> Option<MoshOpts> {
+        if self.conn_type != ConnectionConfigType::Mosh {
+            return None;
+        }
+        let mut opts = MoshOpts::new(SSHOpts {
+            ssh_host: self.host.clone()?,
+            ssh_user: self.user.clone(),
+            ssh_port: self.port,
+        });
+        opts.predict = self.mosh_predict.unwrap_or_default();
+        Some(opts)
+    }
+
+    /// 转换为 Telnet 连接选项（仅 Telnet 连接）
+    pub fn to_telnet_opts(&self) -> Option<TelnetOpts> {
+        if self.conn_type != ConnectionConfigType::Telnet {
+            return None;
+        }
+        let raw = self.telnet_raw.unwrap_or(false);
+        Some(TelnetOpts {
+            host: self.host.clone()?,
+            // 原始 TCP 没有默认端口
+            port: match self.port {
+                Some(port) => port,
+                None if !raw => super::DEFAULT_TELNET_PORT,
+                None => return None,
+            },
+            raw,
+        })
+    }
+
+    /// 转换为串口连接选项（仅串口连接）
+    pub fn to_serial_opts(&self) -> Option<SerialOpts> {
+        if self.conn_type != ConnectionConfigType::Serial {
+            return None;
+        }
+        let mut opts = SerialOpts::new(self.serial_port.clone()?);
+        if let Some(baud_rate) = self.baud_rate {
+            opts.baud_rate = baud_rate;
+        }
+        Some(opts)
+    }
+
+    /// 转换为 Docker 连接选项（仅 Docker 连接，用户名作为容器内执行用户）
+    pub fn to_docker_opts(&self) -> Option<DockerOpts> {
+        if self.conn_type != ConnectionConfigType::Docker {
+            return None;
+        }
+        let mut opts = DockerOpts::new(self.container.clone()?);
+        opts.user = self.user.clone();
+        Some(opts)
+    }
+
+    /// 转换为 Kubernetes 连接选项（仅 Kubernetes 连接）
+    pub fn to_k8s_opts(&self) -> Option<K8sOpts> {
+        if self.conn_type != ConnectionConfigType::Kubernetes {
+            return None;
+        }
+        Some(K8sOpts {
+            namespace: self
+                .namespace
+                .clone()
+                .unwrap_or_else(|| "default".to_string()),
+            pod: self.pod.clone()?,
+            container: self.container.clone(),
+            context: self.kube_context.clone(),
+            shell: None,
+        })
+    }
+
     /// 设置身份文件
     pub fn with_identity_file(mut self, path: impl Into<String>) -> Self {
         self.identity_file = Some(path.into());
@@ -395,6 +528,14 @@ impl ConnectionConfigManager {
             host: Some(local_host),
             user: Some(local_user),
             port: None,
+            mosh_predict: None,
+            telnet_raw: None,
+            serial_port: None,
+            baud_rate: None,
+            container: None,
+            namespace: None,
+            pod: None,
+            kube_context: None,
         });
 
         // 加载用户配置
@@ -415,6 +556,37 @@ impl ConnectionConfigManager {
                         format!("{}@{}:{}", user, host, port)
                     }
                 }
+                ConnectionConfigType::Mosh => {
+                    let user = conn.user.as_deref().unwrap_or("user");
+                    let host = conn.host.as_deref().unwrap_or("unknown");
+                    format!("mosh: {}@{}", user, host)
+                }
+                ConnectionConfigType::Telnet => {
+                    let host = conn.host.as_deref().unwrap_or("unknown");
+                    let scheme = if conn.telnet_raw == Some(true) {
+                        "tcp"
+                    } else {
+                        "telnet"
+                    };
+                    match conn.port {
+                        Some(port) => format!("{}: {}:{}", scheme, host, port),
+                        None => format!("{}: {}", scheme, host),
+                    }
+                }
+                ConnectionConfigType::Serial => {
+                    let port = conn.serial_port.as_deref().unwrap_or("unknown");
+                    let baud = conn.baud_rate.unwrap_or(super::DEFAULT_BAUD_RATE);
+                    format!("serial: {} @ {}", port, baud)
+                }
+                ConnectionConfigType::Docker => {
+                    let container = conn.container.as_deref().unwrap_or("unknown");
+                    format!("docker: {}", container)
+                }
+                ConnectionConfigType::Kubernetes => {
+                    let namespace = conn.namespace.as_deref().unwrap_or("default");
+                    let pod = conn.pod.as_deref().unwrap_or("unknown");
+                    format!("k8s: {}/{}", namespace, pod)
+                }
                 ConnectionConfigType::Wsl => {
                     let distro = conn.wsl_distro.as_deref().unwrap_or("default");
                     format!("WSL: {}", distro)
@@ -430,6 +602,14 @@ impl ConnectionConfigManager {
                 host: conn.host,
                 user: conn.user,
                 port: conn.port,
+                mosh_predict: conn.mosh_predict,
+                telnet_raw: conn.telnet_raw,
+                serial_port: conn.serial_port,
+                baud_rate: conn.baud_rate,
+                container: conn.container,
+                namespace: conn.namespace,
+                pod: conn.pod,
+                kube_context: conn.kube_context,
             });
         }
 
@@ -462,6 +642,14 @@ impl ConnectionConfigManager {
                 host: host.hostname,
                 user: host.user,
                 port: host.port,
+                mosh_predict: None,
+                telnet_raw: None,
+                serial_port: None,
+                baud_rate: None,
+                container: None,
+                namespace: None,
+                pod: None,
+                kube_context: None,
             });
         }
 
@@ -524,6 +712,30 @@ pub struct ConnectionListEntry {
     /// 端口
     #[serde(skip_serializing_if = "Option::is_none")]
     pub port: Option<u16>,
+    /// Mosh 预测回显模式
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mosh_predict: Option<MoshPredict>,
+    /// 是否为原始 TCP（Telnet 连接）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telnet_raw: Option<bool>,
+    /// 串口设备路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_port: Option<String>,
+    /// 串口波特率
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baud_rate: Option<u32>,
+    /// 容器名称（Docker / Kubernetes 连接）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// Kubernetes 命名空间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Kubernetes Pod 名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod: Option<String>,
+    /// kubeconfig 上下文
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kube_context: Option<String>,
 }
 
 #[cfg(test)]
@@ -542,6 +754,62 @@ mod tests {
         assert!(json.contains("\"host\":\"192.168.1.100\""));
     }
 
+    #[test]
+    fn test_mosh_connection_config() {
+        let json = r#"{"type":"mosh","user":"root","host":"example.com","port":2222,"moshPredict":"always"}"#;
+        let config: ConnectionConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.conn_type, ConnectionConfigType::Mosh);
+
+        let opts = config.to_mosh_opts().unwrap();
+        assert_eq!(opts.predict, MoshPredict::Always);
+        assert_eq!(
+            opts.to_connection_string(),
+            "mosh://root@example.com:2222?predict=always"
+        );
+        assert!(ConnectionConfig::ssh("example.com")
+            .to_mosh_opts()
+            .is_none());
+    }
+
+    #[test]
+    fn test_telnet_and_serial_connection_config() {
+        let json = r#"{"type":"telnet","host":"10.0.0.1","port":7000,"telnetRaw":true}"#;
+        let config: ConnectionConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            config.to_telnet_opts().unwrap().to_connection_string(),
+            "tcp://10.0.0.1:7000"
+        );
+
+        let json = r#"{"type":"serial","serialPort":"/dev/ttyUSB0","baudRate":9600}"#;
+        let config: ConnectionConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.conn_type, ConnectionConfigType::Serial);
+        assert_eq!(
+            config.to_serial_opts().unwrap().to_connection_string(),
+            "serial:///dev/ttyUSB0?baud=9600"
+        );
+        assert!(config.to_telnet_opts().is_none());
+    }
+
+    #[test]
+    fn test_docker_and_k8s_connection_config() {
+        let json = r#"{"type":"docker","container":"web","user":"app"}"#;
+        let config: ConnectionConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.conn_type, ConnectionConfigType::Docker);
+        assert_eq!(
+            config.to_docker_opts().unwrap().to_connection_string(),
+            "docker://web?user=app"
+        );
+
+        let json = r#"{"type":"k8s","pod":"web-0","container":"app","kubeContext":"prod"}"#;
+        let config: ConnectionConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.conn_type, ConnectionConfigType::Kubernetes);
+        assert_eq!(
+            config.to_k8s_opts().unwrap().to_connection_string(),
+            "k8s://default/web-0/app?context=prod"
+        );
+        assert!(config.to_docker_opts().is_none());
+    }
+
     #[test]
     fn test_connections_file() {
         let mut file = ConnectionsFile::new();
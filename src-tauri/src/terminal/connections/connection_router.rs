@@ -5,7 +5,7 @@
 //! ## 功能
 //! - 根据连接名称自动路由到正确的连接类型
 //! - 提供连接工厂函数创建相应的连接
-//! - 支持本地 PTY、SSH、WSL 三种连接类型
+//! - 支持本地 PTY、SSH、Mosh、Telnet / 原始 TCP、串口、Docker、Kubernetes、WSL 八种连接类型
 //!
 //! ## Requirements
 //! - 1.4: 创建 SSH 终端时使用 SSH_Connection 建立远程连接
@@ -13,7 +13,11 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::{is_local_conn_name, is_ssh_conn_name, is_wsl_conn_name};
+use super::{
+    find_kubectl, find_mosh_client, is_docker_conn_name, is_k8s_conn_name, is_local_conn_name,
+    is_mosh_conn_name, is_serial_conn_name, is_ssh_conn_name, is_telnet_conn_name,
+    is_wsl_conn_name, DockerEndpoint,
+};
 use crate::terminal::error::TerminalError;
 
 // ============================================================================
@@ -32,6 +36,17 @@ pub enum ConnectionType {
     Local,
     /// SSH 远程连接
     SSH,
+    /// Mosh 远程连接（UDP 状态同步，支持漫游）
+    Mosh,
+    /// Telnet / 原始 TCP 连接（网络设备）
+    Telnet,
+    /// 串口连接（嵌入式设备、Console 口）
+    Serial,
+    /// Docker 容器（Docker Engine API exec）
+    Docker,
+    /// Kubernetes Pod（kubectl exec）
+    #[serde(rename = "k8s")]
+    Kubernetes,
     /// WSL 连接（仅 Windows）
     WSL,
 }
@@ -47,6 +62,11 @@ impl std::fmt::Display for ConnectionType {
         match self {
             Self::Local => write!(f, "local"),
             Self::SSH => write!(f, "ssh"),
+            Self::Mosh => write!(f, "mosh"),
+            Self::Telnet => write!(f, "telnet"),
+            Self::Serial => write!(f, "serial"),
+            Self::Docker => write!(f, "docker"),
+            Self::Kubernetes => write!(f, "k8s"),
             Self::WSL => write!(f, "wsl"),
         }
     }
@@ -59,6 +79,11 @@ impl std::str::FromStr for ConnectionType {
         match s.to_lowercase().as_str() {
             "local" | "" => Ok(Self::Local),
             "ssh" => Ok(Self::SSH),
+            "mosh" => Ok(Self::Mosh),
+            "telnet" | "tcp" => Ok(Self::Telnet),
+            "serial" => Ok(Self::Serial),
+            "docker" => Ok(Self::Docker),
+            "k8s" | "kubernetes" => Ok(Self::Kubernetes),
             "wsl" => Ok(Self::WSL),
             _ => Err(TerminalError::InvalidConnectionType(s.to_string())),
         }
@@ -76,7 +101,12 @@ impl std::str::FromStr for ConnectionType {
 /// ## 路由规则
 /// 1. 空字符串或 "local" → Local
 /// 2. 以 "wsl://" 开头或等于 "wsl" → WSL
-/// 3. 以 "ssh://" 开头、包含 "@" 或其他非本地/WSL 格式 → SSH
+/// 3. 以 "mosh://" 开头 → Mosh
+/// 4. 以 "telnet://" 或 "tcp://" 开头 → Telnet
+/// 5. 以 "serial://" 开头 → Serial
+/// 6. 以 "docker://" 开头 → Docker
+/// 7. 以 "k8s://" 开头 → Kubernetes
+/// 8. 以 "ssh://" 开头、包含 "@" 或其他非本地/WSL 格式 → SSH
 ///
 /// _Requirements: 1.4, 1.5_
 pub struct ConnectionRouter;
@@ -97,6 +127,11 @@ impl ConnectionRouter {
     /// assert_eq!(ConnectionRouter::route(""), ConnectionType::Local);
     /// assert_eq!(ConnectionRouter::route("local"), ConnectionType::Local);
     /// assert_eq!(ConnectionRouter::route("wsl://Ubuntu"), ConnectionType::WSL);
+    /// assert_eq!(ConnectionRouter::route("mosh://user@host"), ConnectionType::Mosh);
+    /// assert_eq!(ConnectionRouter::route("telnet://switch01"), ConnectionType::Telnet);
+    /// assert_eq!(ConnectionRouter::route("serial:///dev/ttyUSB0"), ConnectionType::Serial);
+    /// assert_eq!(ConnectionRouter::route("docker://web"), ConnectionType::Docker);
+    /// assert_eq!(ConnectionRouter::route("k8s://default/web-0"), ConnectionType::Kubernetes);
     /// assert_eq!(ConnectionRouter::route("user@host"), ConnectionType::SSH);
     /// ```
     ///
@@ -114,12 +149,37 @@ impl ConnectionRouter {
             return ConnectionType::WSL;
         }
 
-        // 3. 检查是否为 SSH 连接
+        // 3. 检查是否为 Mosh 连接
+        if is_mosh_conn_name(conn_name) {
+            return ConnectionType::Mosh;
+        }
+
+        // 4. 检查是否为 Telnet / 原始 TCP 连接
+        if is_telnet_conn_name(conn_name) {
+            return ConnectionType::Telnet;
+        }
+
+        // 5. 检查是否为串口连接
+        if is_serial_conn_name(conn_name) {
+            return ConnectionType::Serial;
+        }
+
+        // 6. 检查是否为 Docker 连接
+        if is_docker_conn_name(conn_name) {
+            return ConnectionType::Docker;
+        }
+
+        // 7. 检查是否为 Kubernetes 连接
+        if is_k8s_conn_name(conn_name) {
+            return ConnectionType::Kubernetes;
+        }
+
+        // 8. 检查是否为 SSH 连接
         if is_ssh_conn_name(conn_name) {
             return ConnectionType::SSH;
         }
 
-        // 4. 默认为本地连接
+        // 9. 默认为本地连接
         ConnectionType::Local
     }
 
@@ -142,6 +202,36 @@ impl ConnectionRouter {
             SSHOpts::parse(conn_name)?;
         }
 
+        // 对于 Mosh 连接，验证格式
+        if conn_type == ConnectionType::Mosh {
+            use super::MoshOpts;
+            MoshOpts::parse(conn_name)?;
+        }
+
+        // 对于 Telnet 连接，验证格式
+        if conn_type == ConnectionType::Telnet {
+            use super::TelnetOpts;
+            TelnetOpts::parse(conn_name)?;
+        }
+
+        // 对于串口连接，验证格式
+        if conn_type == ConnectionType::Serial {
+            use super::SerialOpts;
+            SerialOpts::parse(conn_name)?;
+        }
+
+        // 对于 Docker 连接，验证格式
+        if conn_type == ConnectionType::Docker {
+            use super::DockerOpts;
+            DockerOpts::parse(conn_name)?;
+        }
+
+        // 对于 Kubernetes 连接，验证格式
+        if conn_type == ConnectionType::Kubernetes {
+            use super::K8sOpts;
+            K8sOpts::parse(conn_name)?;
+        }
+
         // 对于 WSL 连接，验证格式
         if conn_type == ConnectionType::WSL {
             use super::WSLOpts;
@@ -162,6 +252,11 @@ impl ConnectionRouter {
         match conn_type {
             ConnectionType::Local => true,
             ConnectionType::SSH => true, // SSH 在所有平台上可用
+            ConnectionType::Mosh => find_mosh_client().is_some(), // 需要本机安装 mosh
+            ConnectionType::Telnet => true,
+            ConnectionType::Serial => true, // 串口是否存在在打开时检查
+            ConnectionType::Docker => DockerEndpoint::from_env().is_ok(), // 是否运行在连接时检查
+            ConnectionType::Kubernetes => find_kubectl().is_some(), // 需要本机安装 kubectl
             ConnectionType::WSL => cfg!(target_os = "windows"), // WSL 仅在 Windows 上可用
         }
     }
@@ -177,6 +272,11 @@ impl ConnectionRouter {
         match conn_type {
             ConnectionType::Local => "本地终端",
             ConnectionType::SSH => "SSH 远程连接",
+            ConnectionType::Mosh => "Mosh 远程连接（支持漫游和断续网络）",
+            ConnectionType::Telnet => "Telnet / 原始 TCP 连接",
+            ConnectionType::Serial => "串口连接",
+            ConnectionType::Docker => "Docker 容器",
+            ConnectionType::Kubernetes => "Kubernetes Pod",
             ConnectionType::WSL => "Windows Subsystem for Linux",
         }
     }
@@ -244,6 +344,7 @@ mod tests {
         fn test_display() {
             assert_eq!(ConnectionType::Local.to_string(), "local");
             assert_eq!(ConnectionType::SSH.to_string(), "ssh");
+            assert_eq!(ConnectionType::Mosh.to_string(), "mosh");
             assert_eq!(ConnectionType::WSL.to_string(), "wsl");
         }
 
@@ -319,6 +420,59 @@ mod tests {
             assert_eq!(ConnectionRouter::route("192.168.1.1"), ConnectionType::SSH);
         }
 
+        #[test]
+        fn test_route_mosh() {
+            assert_eq!(
+                ConnectionRouter::route("mosh://user@host"),
+                ConnectionType::Mosh
+            );
+            assert_eq!(ConnectionRouter::route("MOSH://host"), ConnectionType::Mosh);
+            assert_eq!(
+                ConnectionRouter::validate("mosh://user@host:2222?predict=always").unwrap(),
+                ConnectionType::Mosh
+            );
+            assert!(ConnectionRouter::validate("mosh://").is_err());
+        }
+
+        #[test]
+        fn test_route_telnet_and_serial() {
+            assert_eq!(
+                ConnectionRouter::route("telnet://admin@switch01"),
+                ConnectionType::Telnet
+            );
+            assert_eq!(
+                ConnectionRouter::route("tcp://10.0.0.1:7000"),
+                ConnectionType::Telnet
+            );
+            assert_eq!(
+                ConnectionRouter::route("serial:///dev/ttyUSB0?baud=9600"),
+                ConnectionType::Serial
+            );
+            assert!(ConnectionRouter::validate("tcp://10.0.0.1").is_err());
+            assert!(ConnectionRouter::validate("serial://COM3?baud=0").is_err());
+        }
+
+        #[test]
+        fn test_route_docker_and_k8s() {
+            assert_eq!(
+                ConnectionRouter::route("docker://web"),
+                ConnectionType::Docker
+            );
+            assert_eq!(
+                ConnectionRouter::route("docker://root@web"),
+                ConnectionType::Docker
+            );
+            assert_eq!(
+                ConnectionRouter::validate("k8s://default/web-0/app").unwrap(),
+                ConnectionType::Kubernetes
+            );
+            assert!(ConnectionRouter::validate("k8s://web-0").is_err());
+            assert_eq!(
+                "k8s".parse::<ConnectionType>().unwrap(),
+                ConnectionType::Kubernetes
+            );
+        }
+
         #[test]
         fn test_route_with_whitespace() {
             assert_eq!(
@@ -0,0 +1,705 @@
+//! Docker 容器连接模块
+//!
+//! 通过 Docker Engine API 在运行中的容器里创建 exec 实例并附加 TTY，
+//! 不依赖本机 docker CLI。
+//!
+//! ## 功能
+//! - 解析 `docker://container` 连接字符串
+//! - 按 `DOCKER_HOST` 连接 Docker（`unix://` 套接字或未加密的 `tcp://`）
+//! - 创建 exec 实例，升级连接后直接作为终端字节流
+//! - 通过 exec resize 接口同步终端大小
+//!
+//! Windows 命名管道（`npipe://`）和 TLS 加密的 Docker API 暂不支持。
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::terminal::error::TerminalError;
+use crate::terminal::pty_session::{StreamParts, TerminalResizer};
+
+/// Docker 连接前缀
+pub const DOCKER_CONN_PREFIX: &str = "docker://";
+
+/// 默认 Docker 套接字
+pub const DEFAULT_DOCKER_SOCKET: &str = "/var/run/docker.sock";
+
+/// Docker Engine API 版本（Docker 20.10+）
+const DOCKER_API_VERSION: &str = "v1.41";
+
+/// API 请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 读取超时（读取线程据此检查会话是否已关闭）
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 响应头最大长度
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// 未指定 Shell 时在容器内选择 Shell：优先 bash，没有时回退到 sh
+const SHELL_PROBE: &str = "if command -v bash >/dev/null 2>&1; then exec bash; else exec sh; fi";
+
+// ============================================================================
+// Docker 连接选项
+// ============================================================================
+
+/// Docker 连接选项
+///
+/// ## 格式支持
+/// - `docker://container` - 容器名称或 ID
+/// - `docker://container?user=root&shell=/bin/zsh&cwd=/app`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DockerOpts {
+    /// 容器名称或 ID
+    pub container: String,
+    /// 执行用户（可选，默认为容器用户）
+    pub user: Option<String>,
+    /// Shell 路径（可选，默认优先 bash）
+    pub shell: Option<String>,
+    /// 工作目录（可选，默认为容器工作目录）
+    pub workdir: Option<String>,
+}
+
+impl DockerOpts {
+    /// 创建新的 Docker 选项
+    pub fn new(container: impl Into<String>) -> Self {
+        Self {
+            container: container.into(),
+            user: None,
+            shell: None,
+            workdir: None,
+        }
+    }
+
+    /// 从连接字符串解析 Docker 选项
+    ///
+    /// # 参数
+    /// - `conn_str`: 连接字符串
+    ///
+    /// # 返回
+    /// - `Ok(DockerOpts)`: 解析成功
+    /// - `Err(TerminalError)`: 解析失败
+    pub fn parse(conn_str: &str) -> Result<Self, TerminalError> {
+        let conn_str = conn_str.trim();
+        let target = conn_str
+            .get(..DOCKER_CONN_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(DOCKER_CONN_PREFIX))
+            .map(|_| &conn_str[DOCKER_CONN_PREFIX.len()..])
+            .ok_or_else(|| {
+                TerminalError::DockerConnectionFailed(format!(
+                    "无效的 Docker 连接字符串，需要以 '{}' 开头: {}",
+                    DOCKER_CONN_PREFIX, conn_str
+                ))
+            })?;
+
+        let (container, query) = match target.split_once('?') {
+            Some((container, query)) => (container, Some(query)),
+            None => (target, None),
+        };
+        let container = container.trim_end_matches('/');
+        if container.is_empty() || container.contains('/') {
+            return Err(TerminalError::DockerConnectionFailed(format!(
+                "无效的容器名称: {}",
+                container
+            )));
+        }
+
+        let mut opts = Self::new(container);
+        for pair in query.into_iter().flat_map(|q| q.split('&')) {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            if value.is_empty() {
+                continue;
+            }
+            match key {
+                "user" => opts.user = Some(value.to_string()),
+                "shell" => opts.shell = Some(value.to_string()),
+                "cwd" => opts.workdir = Some(value.to_string()),
+                _ => tracing::warn!("[Docker] 忽略未知的连接参数: {}", pair),
+            }
+        }
+        Ok(opts)
+    }
+
+    /// 转换为连接字符串
+    pub fn to_connection_string(&self) -> String {
+        let params: Vec<String> = [
+            ("user", &self.user),
+            ("shell", &self.shell),
+            ("cwd", &self.workdir),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.as_ref().map(|value| format!("{}={}", key, value)))
+        .collect();
+
+        let mut result = format!("{}{}", DOCKER_CONN_PREFIX, self.container);
+        if !params.is_empty() {
+            result.push('?');
+            result.push_str(&params.join("&"));
+        }
+        result
+    }
+}
+
+impl std::fmt::Display for DockerOpts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_connection_string())
+    }
+}
+
+impl std::str::FromStr for DockerOpts {
+    type Err = TerminalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// 容器内执行的命令
+///
+/// cmd 模式执行指定命令；否则启动指定 Shell，未指定时优先 bash。
+/// Docker 和 Kubernetes 连接共用。
+///
+/// # 参数
+/// - `shell`: Shell 路径（可选）
+/// - `cmd`: 要执行的命令（可选，cmd 模式）
+pub fn container_command(shell: Option<&str>, cmd: Option<&str>) -> Vec<String> {
+    match (cmd, shell) {
+        (Some(cmd), _) => vec!["/bin/sh".into(), "-c".into(), cmd.into()],
+        (None, Some(shell)) => vec![shell.into()],
+        (None, None) => vec!["/bin/sh".into(), "-c".into(), SHELL_PROBE.into()],
+    }
+}
+
+// ============================================================================
+// Docker Engine API 端点
+// ============================================================================
+
+/// Docker Engine API 端点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DockerEndpoint {
+    /// Unix 套接字
+    Unix(PathBuf),
+    /// TCP 地址（`host:port`，未加密）
+    Tcp(String),
+}
+
+impl DockerEndpoint {
+    /// 从 `DOCKER_HOST` 环境变量确定端点
+    pub fn from_env() -> Result<Self, TerminalError> {
+        Self::parse(std::env::var("DOCKER_HOST").ok().as_deref())
+    }
+
+    /// 解析 `DOCKER_HOST`，未设置时使用默认套接字
+    fn parse(docker_host: Option<&str>) -> Result<Self, TerminalError> {
+        let Some(host) = docker_host.map(str::trim).filter(|host| !host.is_empty()) else {
+            return if cfg!(unix) {
+                Ok(Self::Unix(PathBuf::from(DEFAULT_DOCKER_SOCKET)))
+            } else {
+                Err(TerminalError::DockerConnectionFailed(
+                    "暂不支持 Windows 命名管道，请设置 DOCKER_HOST=tcp://host:port".to_string(),
+                ))
+            };
+        };
+
+        if let Some(path) = host.strip_prefix("unix://") {
+            Ok(Self::Unix(PathBuf::from(path)))
+        } else if let Some(addr) = host.strip_prefix("tcp://") {
+            Ok(Self::Tcp(addr.trim_end_matches('/').to_string()))
+        } else {
+            Err(TerminalError::DockerConnectionFailed(format!(
+                "不支持的 DOCKER_HOST: {}",
+                host
+            )))
+        }
+    }
+
+    /// 建立连接
+    fn connect(&self) -> Result<DockerStream, TerminalError> {
+        let stream = match self {
+            Self::Unix(path) => connect_unix(path),
+            Self::Tcp(addr) => TcpStream::connect(addr).map(DockerStream::Tcp),
+        };
+        stream.map_err(|e| {
+            TerminalError::DockerConnectionFailed(format!("无法连接 Docker ({}): {}", self, e))
+        })
+    }
+}
+
+impl std::fmt::Display for DockerEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unix(path) => write!(f, "unix://{}", path.display()),
+            Self::Tcp(addr) => write!(f, "tcp://{}", addr),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect_unix(path: &std::path::Path) -> std::io::Result<DockerStream> {
+    UnixStream::connect(path).map(DockerStream::Unix)
+}
+
+#[cfg(not(unix))]
+fn connect_unix(_path: &std::path::Path) -> std::io::Result<DockerStream> {
+    Err(std::io::Error::new(
+        ErrorKind::Unsupported,
+        "当前平台不支持 Unix 套接字",
+    ))
+}
+
+/// 到 Docker 的连接
+enum DockerStream {
+    #[cfg(unix)]
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl DockerStream {
+    fn try_clone(&self) -> std::io::Result<Self> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.try_clone().map(Self::Unix),
+            Self::Tcp(stream) => stream.try_clone().map(Self::Tcp),
+        }
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.set_read_timeout(timeout),
+            Self::Tcp(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+}
+
+impl Read for DockerStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.read(buf),
+            Self::Tcp(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for DockerStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.write(buf),
+            Self::Tcp(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.flush(),
+            Self::Tcp(stream) => stream.flush(),
+        }
+    }
+}
+
+// ============================================================================
+// HTTP/1.1
+// ============================================================================
+
+/// 写入 HTTP 请求
+///
+/// `upgrade` 为 `true` 时请求把连接升级为原始字节流（exec start 使用）。
+fn write_request(
+    stream: &mut impl Write,
+    method: &str,
+    path: &str,
+    body: Option<&Value>,
+    upgrade: bool,
+) -> std::io::Result<()> {
+    let body = body.map(|body| body.to_string()).unwrap_or_default();
+    let mut request = format!(
+        "{} /{}{} HTTP/1.1\r\nHost: docker\r\nUser-Agent: proxycast\r\n",
+        method, DOCKER_API_VERSION, path
+    );
+    if upgrade {
+        request.push_str("Connection: Upgrade\r\nUpgrade: tcp\r\n");
+    } else {
+        request.push_str("Connection: close\r\n");
+    }
+    if !body.is_empty() {
+        request.push_str("Content-Type: application/json\r\n");
+    }
+    request.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+    request.push_str(&body);
+
+    stream.write_all(request.as_bytes())?;
+    stream.flush()
+}
+
+/// 读取 HTTP 响应头
+///
+/// 逐字节读取，升级后的连接不会丢失响应头之后的终端数据。
+fn read_head(stream: &mut impl Read) -> std::io::Result<(u16, Vec<(String, String)>)> {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if stream.read(&mut byte)? == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        head.push(byte[0]);
+        if head.len() > MAX_HEAD_SIZE {
+            return Err(std::io::Error::new(ErrorKind::InvalidData, "响应头过长"));
+        }
+    }
+    parse_head(&head)
+}
+
+/// 解析状态码和响应头（名称转为小写）
+fn parse_head(head: &[u8]) -> std::io::Result<(u16, Vec<(String, String)>)> {
+    let text = String::from_utf8_lossy(head);
+    let mut lines = text.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidData, "无效的 HTTP 状态行"))?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    Ok((status, headers))
+}
+
+/// 读取响应体（支持 Content-Length 和 chunked）
+fn read_body(stream: &mut impl Read, headers: &[(String, String)]) -> std::io::Result<Vec<u8>> {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let mut body = Vec::new();
+    if header("transfer-encoding").is_some_and(|value| value.eq_ignore_ascii_case("chunked")) {
+        // 请求带 Connection: close，读到 EOF 即为完整响应
+        stream.read_to_end(&mut body)?;
+        return decode_chunked(&body);
+    }
+    match header("content-length").and_then(|value| value.parse::<u64>().ok()) {
+        Some(len) => stream.by_ref().take(len).read_to_end(&mut body)?,
+        None => stream.read_to_end(&mut body)?,
+    };
+    Ok(body)
+}
+
+/// 解码 chunked 响应体
+fn decode_chunked(mut data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let invalid = || std::io::Error::new(ErrorKind::InvalidData, "无效的 chunked 响应");
+    let mut body = Vec::new();
+    loop {
+        let line_end = data
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or_else(invalid)?;
+        let size = std::str::from_utf8(&data[..line_end])
+            .ok()
+            .and_then(|line| line.split(';').next())
+            .and_then(|size| usize::from_str_radix(size.trim(), 16).ok())
+            .ok_or_else(invalid)?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(data.get(..size).ok_or_else(invalid)?);
+        data = data.get(size + 2..).unwrap_or_default();
+    }
+}
+
+/// 从 Docker 错误响应中提取信息
+fn api_error(status: u16, body: &Value) -> TerminalError {
+    let message = body["message"].as_str().unwrap_or("未知错误");
+    TerminalError::DockerConnectionFailed(format!("Docker API 返回 {}: {}", status, message))
+}
+
+// ============================================================================
+// Docker 连接
+// ============================================================================
+
+/// Docker 连接
+#[derive(Debug, Clone)]
+pub struct DockerConn {
+    endpoint: DockerEndpoint,
+}
+
+impl DockerConn {
+    /// 使用 `DOCKER_HOST`（或默认套接字）创建连接
+    pub fn from_env() -> Result<Self, TerminalError> {
+        Ok(Self {
+            endpoint: DockerEndpoint::from_env()?,
+        })
+    }
+
+    /// 发送 API 请求并读取 JSON 响应
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<(u16, Value), TerminalError> {
+        let map_err = |e: std::io::Error| {
+            TerminalError::DockerConnectionFailed(format!("{} {} - {}", method, path, e))
+        };
+
+        let mut stream = self.endpoint.connect()?;
+        stream
+            .set_read_timeout(Some(REQUEST_TIMEOUT))
+            .map_err(map_err)?;
+        write_request(&mut stream, method, path, body, false).map_err(map_err)?;
+        let (status, headers) = read_head(&mut stream).map_err(map_err)?;
+        let body = read_body(&mut stream, &headers).map_err(map_err)?;
+        Ok((status, serde_json::from_slice(&body).unwrap_or(Value::Null)))
+    }
+
+    /// 检查 Docker 是否可用
+    pub fn ping(&self) -> Result<(), TerminalError> {
+        match self.request("GET", "/_ping", None)? {
+            (200, _) => Ok(()),
+            (status, body) => Err(api_error(status, &body)),
+        }
+    }
+
+    /// 在容器中创建 exec 实例并附加 TTY
+    ///
+    /// # 参数
+    /// - `opts`: 连接选项
+    /// - `cmd`: 要执行的命令（可选，为空时启动 Shell）
+    /// - `rows`: 终端行数
+    /// - `cols`: 终端列数
+    ///
+    /// # 返回
+    /// 交给 `PtySession::with_stream` 的读取器、写入器和大小调整器
+    pub fn exec(
+        &self,
+        opts: &DockerOpts,
+        cmd: Option<&str>,
+        rows: u16,
+        cols: u16,
+    ) -> Result<StreamParts, TerminalError> {
+        let mut config = json!({
+            "AttachStdin": true,
+            "AttachStdout": true,
+            "AttachStderr": true,
+            "Tty": true,
+            "Env": ["TERM=xterm-256color", "COLORTERM=truecolor"],
+            "Cmd": container_command(opts.shell.as_deref(), cmd),
+        });
+        if let Some(ref user) = opts.user {
+            config["User"] = json!(user);
+        }
+        if let Some(ref workdir) = opts.workdir {
+            config["WorkingDir"] = json!(workdir);
+        }
+
+        let path = format!("/containers/{}/exec", urlencoding::encode(&opts.container));
+        let exec_id = match self.request("POST", &path, Some(&config))? {
+            (201, body) => body["Id"].as_str().map(str::to_string).ok_or_else(|| {
+                TerminalError::DockerConnectionFailed("exec 响应缺少 Id".to_string())
+            })?,
+            (404, _) => {
+                return Err(TerminalError::DockerConnectionFailed(format!(
+                    "容器不存在: {}",
+                    opts.container
+                )))
+            }
+            (409, _) => {
+                return Err(TerminalError::DockerConnectionFailed(format!(
+                    "容器未运行: {}",
+                    opts.container
+                )))
+            }
+            (status, body) => return Err(api_error(status, &body)),
+        };
+
+        // 启动 exec，连接升级后即为终端字节流（Tty 模式下不做多路复用）
+        let map_err = |e: std::io::Error| TerminalError::DockerConnectionFailed(e.to_string());
+        let mut stream = self.endpoint.connect()?;
+        let start = json!({ "Detach": false, "Tty": true });
+        write_request(
+            &mut stream,
+            "POST",
+            &format!("/exec/{}/start", exec_id),
+            Some(&start),
+            true,
+        )
+        .map_err(map_err)?;
+        let (status, _) = read_head(&mut stream).map_err(map_err)?;
+        if status != 101 && status != 200 {
+            return Err(TerminalError::DockerConnectionFailed(format!(
+                "启动 exec 失败，Docker API 返回 {}",
+                status
+            )));
+        }
+
+        let resizer = DockerResizer {
+            conn: self.clone(),
+            exec_id,
+        };
+        if let Err(e) = resizer.resize(rows, cols) {
+            tracing::warn!("[Docker] 设置终端大小失败: {}", e);
+        }
+
+        let reader = stream.try_clone().map_err(map_err)?;
+        reader
+            .set_read_timeout(Some(READ_TIMEOUT))
+            .map_err(map_err)?;
+
+        tracing::info!(
+            "[Docker] 已连接容器 {}（{}）",
+            opts.container,
+            self.endpoint
+        );
+
+        Ok(StreamParts {
+            reader: Box::new(reader),
+            writer: Box::new(stream),
+            resizer: Box::new(resizer),
+        })
+    }
+}
+
+/// exec 实例的终端大小调整
+struct DockerResizer {
+    conn: DockerConn,
+    exec_id: String,
+}
+
+impl TerminalResizer for DockerResizer {
+    fn resize(&self, rows: u16, cols: u16) -> Result<(), TerminalError> {
+        let path = format!("/exec/{}/resize?h={}&w={}", self.exec_id, rows, cols);
+        match self.conn.request("POST", &path, None) {
+            Ok((200 | 201, _)) => Ok(()),
+            Ok((status, body)) => Err(TerminalError::ResizeFailed(
+                api_error(status, &body).to_string(),
+            )),
+            Err(e) => Err(TerminalError::ResizeFailed(e.to_string())),
+        }
+    }
+}
+
+// ============================================================================
+// 辅助函数
+// ============================================================================
+
+/// 检查连接名称是否为 Docker 连接
+///
+/// Docker 连接名称以 "docker://" 开头。
+pub fn is_docker_conn_name(conn_name: &str) -> bool {
+    conn_name
+        .trim()
+        .to_lowercase()
+        .starts_with(DOCKER_CONN_PREFIX)
+}
+
+// ============================================================================
+// 单元测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let opts = DockerOpts::parse("docker://web").unwrap();
+        assert_eq!(opts, DockerOpts::new("web"));
+
+        let opts =
+            DockerOpts::parse("DOCKER://db?user=postgres&shell=/bin/bash&cwd=/data").unwrap();
+        assert_eq!(opts.container, "db");
+        assert_eq!(opts.user.as_deref(), Some("postgres"));
+        assert_eq!(opts.shell.as_deref(), Some("/bin/bash"));
+        assert_eq!(opts.workdir.as_deref(), Some("/data"));
+
+        assert!(DockerOpts::parse("docker://").is_err());
+        assert!(DockerOpts::parse("docker://a/b").is_err());
+        assert!(DockerOpts::parse("k8s://ns/pod").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for conn in ["docker://web", "docker://db?user=postgres&cwd=/data"] {
+            assert_eq!(
+                DockerOpts::parse(conn).unwrap().to_connection_string(),
+                conn
+            );
+        }
+    }
+
+    #[test]
+    fn test_container_command() {
+        assert_eq!(container_command(Some("/bin/zsh"), None), vec!["/bin/zsh"]);
+        assert_eq!(
+            container_command(Some("/bin/zsh"), Some("top")),
+            vec!["/bin/sh", "-c", "top"]
+        );
+        assert_eq!(container_command(None, None)[2], SHELL_PROBE);
+    }
+
+    #[test]
+    fn test_endpoint_parse() {
+        assert_eq!(
+            DockerEndpoint::parse(Some("unix:///run/user/1000/docker.sock")).unwrap(),
+            DockerEndpoint::Unix(PathBuf::from("/run/user/1000/docker.sock"))
+        );
+        assert_eq!(
+            DockerEndpoint::parse(Some("tcp://127.0.0.1:2375/")).unwrap(),
+            DockerEndpoint::Tcp("127.0.0.1:2375".to_string())
+        );
+        assert!(DockerEndpoint::parse(Some("npipe:////./pipe/docker_engine")).is_err());
+        #[cfg(unix)]
+        assert_eq!(
+            DockerEndpoint::parse(None).unwrap(),
+            DockerEndpoint::Unix(PathBuf::from(DEFAULT_DOCKER_SOCKET))
+        );
+    }
+
+    #[test]
+    fn test_read_response() {
+        let response = b"HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nContent-Length: 11\r\n\r\n{\"Id\":\"ab\"}";
+        let mut reader = &response[..];
+        let (status, headers) = read_head(&mut reader).unwrap();
+        assert_eq!(status, 201);
+        assert_eq!(
+            read_body(&mut reader, &headers).unwrap(),
+            b"{\"Id\":\"ab\"}"
+        );
+
+        let response = b"HTTP/1.1 101 UPGRADED\r\nUpgrade: tcp\r\n\r\n$ ";
+        let mut reader = &response[..];
+        assert_eq!(read_head(&mut reader).unwrap().0, 101);
+        // 响应头之后的终端数据保留在流中
+        assert_eq!(reader, b"$ ");
+    }
+
+    #[test]
+    fn test_decode_chunked() {
+        assert_eq!(
+            decode_chunked(b"4\r\nWiki\r\n5;ext=1\r\npedia\r\n0\r\n\r\n").unwrap(),
+            b"Wikipedia"
+        );
+        assert!(decode_chunked(b"zz\r\n").is_err());
+    }
+
+    #[test]
+    fn test_is_docker_conn_name() {
+        assert!(is_docker_conn_name("docker://web"));
+        assert!(is_docker_conn_name("  Docker://web"));
+        assert!(!is_docker_conn_name("web"));
+    }
+}
@@ -0,0 +1,274 @@
+//! Kubernetes Pod 连接模块
+//!
+//! 通过本机 kubectl 在 Pod 中执行 Shell。kubectl 负责 kubeconfig 认证
+//! （证书、令牌、exec 插件、OIDC 等）和 exec 子协议（`v4.channel.k8s.io`），
+//! 终端会话在 PTY 中运行 `kubectl exec -it`。
+//!
+//! ## 功能
+//! - 解析 `k8s://namespace/pod[/container]` 连接字符串
+//! - 查找本机 kubectl
+//! - 构建在 PTY 中运行的 kubectl exec 命令
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use portable_pty::CommandBuilder;
+use serde::{Deserialize, Serialize};
+
+use super::docker_connection::container_command;
+use crate::terminal::error::TerminalError;
+
+/// Kubernetes 连接前缀
+pub const K8S_CONN_PREFIX: &str = "k8s://";
+
+/// kubectl 不在 PATH 中时尝试的位置（macOS 图形应用的 PATH 不包含 Homebrew 目录）
+const KUBECTL_FALLBACK_PATHS: &[&str] = &[
+    "/opt/homebrew/bin/kubectl",
+    "/usr/local/bin/kubectl",
+    "/usr/bin/kubectl",
+];
+
+// ============================================================================
+// Kubernetes 连接选项
+// ============================================================================
+
+/// Kubernetes 连接选项
+///
+/// ## 格式支持
+/// - `k8s://namespace/pod`
+/// - `k8s://namespace/pod/container`
+/// - `k8s://namespace/pod?context=prod&shell=/bin/bash`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct K8sOpts {
+    /// 命名空间
+    pub namespace: String,
+    /// Pod 名称
+    pub pod: String,
+    /// 容器名称（可选，默认为 Pod 的默认容器）
+    pub container: Option<String>,
+    /// kubeconfig 上下文（可选，默认为当前上下文）
+    pub context: Option<String>,
+    /// Shell 路径（可选，默认优先 bash）
+    pub shell: Option<String>,
+}
+
+impl K8sOpts {
+    /// 从连接字符串解析 Kubernetes 选项
+    ///
+    /// # 参数
+    /// - `conn_str`: 连接字符串
+    ///
+    /// # 返回
+    /// - `Ok(K8sOpts)`: 解析成功
+    /// - `Err(TerminalError)`: 解析失败
+    pub fn parse(conn_str: &str) -> Result<Self, TerminalError> {
+        let conn_str = conn_str.trim();
+        let target = conn_str
+            .get(..K8S_CONN_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(K8S_CONN_PREFIX))
+            .map(|_| &conn_str[K8S_CONN_PREFIX.len()..])
+            .ok_or_else(|| {
+                TerminalError::KubernetesConnectionFailed(format!(
+                    "无效的 Kubernetes 连接字符串，需要以 '{}' 开头: {}",
+                    K8S_CONN_PREFIX, conn_str
+                ))
+            })?;
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (target, None),
+        };
+        let parts: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        let (namespace, pod, container) = match parts.as_slice() {
+            [namespace, pod] => (*namespace, *pod, None),
+            [namespace, pod, container] => (*namespace, *pod, Some(*container)),
+            _ => ("", "", None),
+        };
+        if namespace.is_empty() || pod.is_empty() || container == Some("") {
+            return Err(TerminalError::KubernetesConnectionFailed(format!(
+                "需要 k8s://namespace/pod[/container] 格式: {}",
+                conn_str
+            )));
+        }
+
+        let mut opts = Self {
+            namespace: namespace.to_string(),
+            pod: pod.to_string(),
+            container: container.map(str::to_string),
+            context: None,
+            shell: None,
+        };
+        for pair in query.into_iter().flat_map(|q| q.split('&')) {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            if value.is_empty() {
+                continue;
+            }
+            match key {
+                "context" => opts.context = Some(value.to_string()),
+                "shell" => opts.shell = Some(value.to_string()),
+                _ => tracing::warn!("[K8s] 忽略未知的连接参数: {}", pair),
+            }
+        }
+        Ok(opts)
+    }
+
+    /// 转换为连接字符串
+    pub fn to_connection_string(&self) -> String {
+        let mut result = format!("{}{}/{}", K8S_CONN_PREFIX, self.namespace, self.pod);
+        if let Some(ref container) = self.container {
+            result.push('/');
+            result.push_str(container);
+        }
+
+        let params: Vec<String> = [("context", &self.context), ("shell", &self.shell)]
+            .into_iter()
+            .filter_map(|(key, value)| value.as_ref().map(|value| format!("{}={}", key, value)))
+            .collect();
+        if !params.is_empty() {
+            result.push('?');
+            result.push_str(&params.join("&"));
+        }
+        result
+    }
+
+    /// kubectl 参数
+    ///
+    /// # 参数
+    /// - `cmd`: 要执行的命令（可选，为空时启动 Shell）
+    pub fn args(&self, cmd: Option<&str>) -> Vec<String> {
+        let mut args = vec!["exec".to_string(), "-it".to_string()];
+        if let Some(ref context) = self.context {
+            args.push(format!("--context={}", context));
+        }
+        args.push(format!("--namespace={}", self.namespace));
+        args.push(self.pod.clone());
+        if let Some(ref container) = self.container {
+            args.push(format!("--container={}", container));
+        }
+        args.push("--".to_string());
+        args.extend(container_command(self.shell.as_deref(), cmd));
+        args
+    }
+
+    /// 构建在 PTY 中运行的 kubectl 命令
+    pub fn build_command(&self, kubectl: &Path, cmd: Option<&str>) -> CommandBuilder {
+        let mut builder = CommandBuilder::new(kubectl);
+        builder.args(self.args(cmd));
+        builder.env("TERM", "xterm-256color");
+        if let Some(home) = dirs::home_dir() {
+            builder.cwd(home);
+        }
+        builder
+    }
+}
+
+impl std::fmt::Display for K8sOpts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_connection_string())
+    }
+}
+
+impl std::str::FromStr for K8sOpts {
+    type Err = TerminalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+// ============================================================================
+// 辅助函数
+// ============================================================================
+
+/// 查找可用的 kubectl
+pub fn find_kubectl() -> Option<PathBuf> {
+    std::iter::once("kubectl")
+        .chain(KUBECTL_FALLBACK_PATHS.iter().copied())
+        .map(PathBuf::from)
+        .find(|path| {
+            Command::new(path)
+                .args(["version", "--client"])
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        })
+}
+
+/// 检查连接名称是否为 Kubernetes 连接
+///
+/// Kubernetes 连接名称以 "k8s://" 开头。
+pub fn is_k8s_conn_name(conn_name: &str) -> bool {
+    conn_name.trim().to_lowercase().starts_with(K8S_CONN_PREFIX)
+}
+
+// ============================================================================
+// 单元测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let opts = K8sOpts::parse("k8s://default/web-0").unwrap();
+        assert_eq!(opts.namespace, "default");
+        assert_eq!(opts.pod, "web-0");
+        assert_eq!(opts.container, None);
+
+        let opts = K8sOpts::parse("k8s://prod/api-7d9/sidecar?context=eu&shell=/bin/bash").unwrap();
+        assert_eq!(opts.container.as_deref(), Some("sidecar"));
+        assert_eq!(opts.context.as_deref(), Some("eu"));
+        assert_eq!(opts.shell.as_deref(), Some("/bin/bash"));
+
+        assert!(K8sOpts::parse("k8s://web-0").is_err());
+        assert!(K8sOpts::parse("k8s://ns/pod/c/extra").is_err());
+        assert!(K8sOpts::parse("k8s:///pod").is_err());
+    }
+
+    #[test]
+    fn test_args() {
+        let opts = K8sOpts::parse("k8s://prod/api-7d9/app?context=eu&shell=/bin/bash").unwrap();
+        assert_eq!(
+            opts.args(None),
+            vec![
+                "exec",
+                "-it",
+                "--context=eu",
+                "--namespace=prod",
+                "api-7d9",
+                "--container=app",
+                "--",
+                "/bin/bash",
+            ]
+        );
+        assert_eq!(
+            opts.args(Some("top"))
+                .split_last()
+                .unwrap()
+                .0
+                .last()
+                .unwrap(),
+            "-c"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for conn in [
+            "k8s://default/web-0",
+            "k8s://prod/api/app?context=eu&shell=/bin/bash",
+        ] {
+            assert_eq!(K8sOpts::parse(conn).unwrap().to_connection_string(), conn);
+        }
+    }
+
+    #[test]
+    fn test_is_k8s_conn_name() {
+        assert!(is_k8s_conn_name("k8s://default/web-0"));
+        assert!(is_k8s_conn_name("K8S://default/web-0"));
+        assert!(!is_k8s_conn_name("docker://web"));
+    }
+}
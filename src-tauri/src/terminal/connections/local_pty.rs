@@ -9,6 +9,7 @@
 //! - 监控进程退出状态
 //! - 支持命令执行模式（cmd）
 //! - 支持 Shell 集成脚本加载
+//! - 块元数据的连接为 Docker / Kubernetes 时在容器中启动 Shell
 //!
 //! ## Requirements
 //! - 17.1: 管理 Shell 进程的完整生命周期
@@ -20,7 +21,7 @@
 //! - 17.9: bash 使用 --rcfile 加载集成脚本
 //! - 17.10: fish 使用 -C 参数 source 集成脚本
 
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
 use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::Arc;
 
@@ -31,13 +32,15 @@ use tauri::Emitter;
 use tauri::Manager;
 use tokio::sync::mpsc;
 
+use super::{find_kubectl, ConnectionRouter, ConnectionType, DockerConn, DockerOpts, K8sOpts};
 use crate::terminal::block_controller::{BlockInputUnion, BlockMeta};
 use crate::terminal::error::TerminalError;
 use crate::terminal::events::{
     event_names, SessionStatus, TerminalOutputEvent, TerminalStatusEvent,
 };
-use crate::terminal::integration::{ShellLaunchBuilder, ShellType};
+use crate::terminal::integration::{ShellIntegration, ShellLaunchBuilder, ShellType};
 use crate::terminal::persistence::BlockFile;
+use crate::terminal::pty_session::{StreamParts, TerminalResizer};
 
 /// Shell 进程封装
 ///
@@ -49,8 +52,8 @@ pub struct ShellProc {
     controller_type: String,
     /// PTY 写入器
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
-    /// PTY Master（用于调整大小）
-    master: Arc<Mutex<Box<dyn portable_pty::MasterPty + Send>>>,
+    /// 大小调整器（PTY Master 或 Docker exec）
+    resizer: Arc<Mutex<Box<dyn TerminalResizer>>>,
     /// 关闭标志
     shutdown_flag: Arc<AtomicBool>,
     /// 进程退出码
@@ -95,45 +98,61 @@ impl ShellProc {
             rows
         );
 
-        let pty_system = native_pty_system();
-
-        // 创建 PTY
-        let pair = pty_system
-            .openpty(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
-
-        // 构建命令（传递 app_handle 和 block_id 用于 Shell 集成）
-        let cmd = Self::build_command(&controller_type, &block_meta, &app_handle, &block_id)?;
-
-        // 启动子进程
-        let _child = pair
-            .slave
-            .spawn_command(cmd)
-            .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
-
-        // 获取写入器
-        let writer = pair
-            .master
-            .take_writer()
-            .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
-
-        // 获取读取器
-        let reader = pair
-            .master
-            .try_clone_reader()
-            .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
+        // 按连接类型建立终端字节流：Docker 通过 Engine API 附加，其余在本地 PTY 中运行命令
+        let route = block_meta
+            .connection
+            .as_deref()
+            .map(|conn| (conn, ConnectionRouter::route(conn)));
+        let StreamParts {
+            reader,
+            writer,
+            resizer,
+        } = match route {
+            Some((conn, ConnectionType::Docker)) => {
+                let opts = DockerOpts::parse(conn)?;
+                let cmd = Self::container_cmd(&controller_type, &block_meta)?;
+                tokio::task::spawn_blocking(move || {
+                    DockerConn::from_env()?.exec(&opts, cmd.as_deref(), rows, cols)
+                })
+                .await
+                .map_err(|e| TerminalError::DockerConnectionFailed(e.to_string()))??
+            }
+            Some((conn, ConnectionType::Kubernetes)) => {
+                let opts = K8sOpts::parse(conn)?;
+                let kubectl = find_kubectl().ok_or_else(|| {
+                    TerminalError::KubernetesConnectionFailed("未找到 kubectl，请先安装".into())
+                })?;
+                let cmd = Self::container_cmd(&controller_type, &block_meta)?;
+                Self::spawn_pty(rows, cols, opts.build_command(&kubectl, cmd.as_deref()))?
+            }
+            _ => {
+                // 构建命令（传递 app_handle 和 block_id 用于 Shell 集成）
+                let cmd =
+                    Self::build_command(&controller_type, &block_meta, &app_handle, &block_id)?;
+                Self::spawn_pty(rows, cols, cmd)?
+            }
+        };
 
         // 创建共享状态
         let shutdown_flag = Arc::new(AtomicBool::new(false));
         let exit_code = Arc::new(AtomicI32::new(0));
         let exited = Arc::new(AtomicBool::new(false));
         let writer = Arc::new(Mutex::new(writer));
-        let master = Arc::new(Mutex::new(pair.master));
+        let resizer = Arc::new(Mutex::new(resizer));
+
+        // Shell 集成（跟踪命令执行并写入命令历史）
+        let shell_integration = Arc::new(ShellIntegration::for_session(
+            block_id.clone(),
+            app_handle.clone(),
+            block_meta.connection.as_deref(),
+        ));
+        // OSC 52 读取请求的回复写回 PTY 输入
+        let reply_writer = writer.clone();
+        shell_integration.set_responder(Arc::new(move |data: &[u8]| {
+            let mut w = reply_writer.lock();
+            w.write_all(data)?;
+            w.flush()
+        }));
 
         // 启动输出读取任务
         Self::spawn_output_reader(
@@ -144,13 +163,14 @@ impl ShellProc {
             exit_code.clone(),
             exited.clone(),
             block_file,
+            shell_integration,
         );
 
         // 启动输入处理任务
         Self::spawn_input_handler(
             block_id.clone(),
             writer.clone(),
-            master.clone(),
+            resizer.clone(),
             input_rx,
             shutdown_flag.clone(),
         );
@@ -161,13 +181,64 @@ impl ShellProc {
             block_id,
             controller_type,
             writer,
-            master,
+            resizer,
             shutdown_flag,
             exit_code,
             exited,
         })
     }
 
+    /// 在 PTY 中启动命令
+    fn spawn_pty(rows: u16, cols: u16, cmd: CommandBuilder) -> Result<StreamParts, TerminalError> {
+        let pty_system = native_pty_system();
+
+        // 创建 PTY
+        let pair = pty_system
+            .openpty(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
+
+        // 启动子进程
+        let _child = pair
+            .slave
+            .spawn_command(cmd)
+            .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
+
+        // 获取写入器
+        let writer = pair
+            .master
+            .take_writer()
+            .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
+
+        // 获取读取器
+        let reader = pair
+            .master
+            .try_clone_reader()
+            .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
+
+        Ok(StreamParts {
+            reader,
+            writer,
+            resizer: Box::new(pair.master),
+        })
+    }
+
+    /// 容器内要执行的命令（cmd 模式），Shell 模式为 `None`
+    fn container_cmd(
+        controller_type: &str,
+        block_meta: &BlockMeta,
+    ) -> Result<Option<String>, TerminalError> {
+        if controller_type == "cmd" {
+            Self::cmd_string(block_meta).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
     /// 构建命令
     ///
     /// 根据控制器类型和块元数据构建要执行的命令。
@@ -231,17 +302,7 @@ impl ShellProc {
         let launch_config = builder.build(&shell, block_meta.cmd_env.as_ref())?;
 
         // 构建命令
-        let mut cmd = CommandBuilder::new(&launch_config.shell_path);
-
-        // 添加参数
-        for arg in &launch_config.args {
-            cmd.arg(arg);
-        }
-
-        // 设置环境变量
-        for (key, value) in &launch_config.env {
-            cmd.env(key, value);
-        }
+        let cmd = launch_config.to_command();
 
         // 检测 Shell 类型并记录
         let shell_type = ShellType::from_path(&shell);
@@ -258,25 +319,12 @@ impl ShellProc {
     ///
     /// _Requirements: 16.1, 16.2, 16.3, 17.2_
     fn build_cmd_command(block_meta: &BlockMeta) -> Result<CommandBuilder, TerminalError> {
-        let cmd_str = block_meta
-            .cmd
-            .as_ref()
-            .ok_or_else(|| TerminalError::PtyCreationFailed("cmd 模式需要指定命令".to_string()))?;
-
-        tracing::info!("[ShellProc] 执行命令: {}", cmd_str);
+        let full_cmd = Self::cmd_string(block_meta)?;
 
         // 使用 shell 执行命令
         let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
         let mut cmd = CommandBuilder::new(&shell);
         cmd.arg("-c");
-
-        // 构建完整命令字符串
-        let full_cmd = if let Some(args) = &block_meta.cmd_args {
-            format!("{} {}", cmd_str, args.join(" "))
-        } else {
-            cmd_str.clone()
-        };
-
         cmd.arg(&full_cmd);
 
         // 设置通用环境变量
@@ -293,9 +341,26 @@ impl ShellProc {
         Ok(cmd)
     }
 
+    /// 构建完整命令字符串（命令 + 参数）
+    fn cmd_string(block_meta: &BlockMeta) -> Result<String, TerminalError> {
+        let cmd_str = block_meta
+            .cmd
+            .as_ref()
+            .ok_or_else(|| TerminalError::PtyCreationFailed("cmd 模式需要指定命令".to_string()))?;
+
+        tracing::info!("[ShellProc] 执行命令: {}", cmd_str);
+
+        Ok(if let Some(args) = &block_meta.cmd_args {
+            format!("{} {}", cmd_str, args.join(" "))
+        } else {
+            cmd_str.clone()
+        })
+    }
+
     /// 启动输出读取任务
     ///
     /// 在独立线程中读取 PTY 输出，并通过 Tauri 事件发送到前端。
+    #[allow(clippy::too_many_arguments)]
     fn spawn_output_reader(
         block_id: String,
         mut reader: Box<dyn Read + Send>,
@@ -304,6 +369,7 @@ impl ShellProc {
         exit_code: Arc<AtomicI32>,
         exited: Arc<AtomicBool>,
         block_file: Option<Arc<BlockFile>>,
+        shell_integration: Arc<ShellIntegration>,
     ) {
         std::thread::spawn(move || {
             let mut buffer = [0u8; 4096];
@@ -348,6 +414,9 @@ impl ShellProc {
                             }
                         }
 
+                        // 更新 Shell 集成状态
+                        shell_integration.process_output(output_data);
+
                         // 发送输出事件
                         let data = BASE64.encode(output_data);
                         let _ = app_handle.emit(
@@ -358,6 +427,15 @@ impl ShellProc {
                             },
                         );
                     }
+                    Err(e)
+                        if matches!(
+                            e.kind(),
+                            ErrorKind::Interrupted | ErrorKind::TimedOut | ErrorKind::WouldBlock
+                        ) =>
+                    {
+                        // Docker 字节流的读取超时，回到循环开头检查关闭标志
+                        continue;
+                    }
                     Err(e) => {
                         // 检查是否是因为关闭导致的错误
                         if shutdown_flag.load(Ordering::Relaxed) {
@@ -389,7 +467,7 @@ impl ShellProc {
     fn spawn_input_handler(
         block_id: String,
         writer: Arc<Mutex<Box<dyn Write + Send>>>,
-        master: Arc<Mutex<Box<dyn portable_pty::MasterPty + Send>>>,
+        resizer: Arc<Mutex<Box<dyn TerminalResizer>>>,
         mut input_rx: mpsc::Receiver<BlockInputUnion>,
         shutdown_flag: Arc<AtomicBool>,
     ) {
@@ -418,13 +496,7 @@ impl ShellProc {
 
                 // 处理终端大小调整
                 if let Some(size) = &input.term_size {
-                    let m = master.lock();
-                    if let Err(e) = m.resize(PtySize {
-                        rows: size.rows,
-                        cols: size.cols,
-                        pixel_width: 0,
-                        pixel_height: 0,
-                    }) {
+                    if let Err(e) = resizer.lock().resize(size.rows, size.cols) {
                         tracing::error!(
                             "[ShellProc] 调整大小失败: block_id={}, error={}",
                             block_id,
@@ -489,15 +561,7 @@ impl ShellProc {
 
     /// 调整 PTY 大小
     pub fn resize(&self, rows: u16, cols: u16) -> Result<(), TerminalError> {
-        let master = self.master.lock();
-        master
-            .resize(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .map_err(|e| TerminalError::ResizeFailed(e.to_string()))?;
+        self.resizer.lock().resize(rows, cols)?;
         tracing::debug!(
             "[ShellProc] 调整大小: block_id={}, size={}x{}",
             self.block_id,
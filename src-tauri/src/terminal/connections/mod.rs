@@ -1,11 +1,20 @@
 //! 连接模块
 //!
-//! 提供不同类型的终端连接实现：本地 PTY、SSH、WSL。
+//! 提供不同类型的终端连接实现：本地 PTY、SSH、Mosh、Telnet / 原始 TCP、串口、Docker、Kubernetes、WSL。
 //!
 //! ## 模块结构
 //! - `local_pty` - 本地 PTY 连接
 //! - `ssh_connection` - SSH 远程连接
 //! - `ssh_shell_proc` - SSH 远程 Shell 进程
+//! - `ssh_shell_integration` - SSH 远程 Shell 检测与集成脚本注入
+//! - `ssh_forward` - SSH 端口转发（-L/-R/-D）
+//! - `ssh_keepalive` - SSH 保活与断线重连
+//! - `ssh_x11` - SSH X11 转发
+//! - `mosh_connection` - Mosh 连接（运行本机 mosh 客户端）
+//! - `telnet_connection` - Telnet / 原始 TCP 连接
+//! - `serial_connection` - 串口连接
+//! - `docker_connection` - Docker 容器连接（Docker Engine API exec）
+//! - `k8s_connection` - Kubernetes Pod 连接（kubectl exec）
 //! - `wsl_connection` - WSL 连接（仅 Windows）
 //! - `connection_router` - 连接类型路由
 //! - `connection_config` - 连接配置持久化
@@ -14,15 +23,32 @@
 //! - 本地 PTY 进程管理
 //! - SSH 远程连接和认证
 //! - SSH 远程 PTY 创建和数据转发
+//! - 远程 Shell 集成脚本注入（bash、zsh、fish、nushell、PowerShell）
+//! - SSH 端口转发
+//! - SSH 保活检测和断线自动重连
+//! - SSH X11 转发
+//! - Mosh 漫游连接
+//! - Telnet / 原始 TCP 网络设备连接
+//! - 串口设备连接
+//! - Docker 容器和 Kubernetes Pod 内的 Shell
 //! - WSL 发行版连接
 //! - 连接类型自动路由
 //! - 连接配置存储和管理
 
 pub mod connection_config;
 pub mod connection_router;
+pub mod docker_connection;
+pub mod k8s_connection;
 pub mod local_pty;
+pub mod mosh_connection;
+pub mod serial_connection;
 pub mod ssh_connection;
+pub mod ssh_forward;
+pub mod ssh_keepalive;
+pub mod ssh_shell_integration;
 pub mod ssh_shell_proc;
+pub mod ssh_x11;
+pub mod telnet_connection;
 pub mod wsl_connection;
 
 pub use connection_config::{
@@ -30,14 +56,35 @@ pub use connection_config::{
     ConnectionSource, ConnectionsFile, SSHHostEntry,
 };
 pub use connection_router::{ConnectionInfo, ConnectionRouter, ConnectionType};
+pub use docker_connection::{
+    container_command, is_docker_conn_name, DockerConn, DockerEndpoint, DockerOpts,
+    DEFAULT_DOCKER_SOCKET, DOCKER_CONN_PREFIX,
+};
+pub use k8s_connection::{find_kubectl, is_k8s_conn_name, K8sOpts, K8S_CONN_PREFIX};
 pub use local_pty::ShellProc;
+pub use mosh_connection::{
+    find_mosh_client, is_mosh_conn_name, MoshOpts, MoshPredict, MOSH_CONN_PREFIX,
+};
+pub use serial_connection::{
+    is_serial_conn_name, list_serial_ports, SerialConn, SerialOpts, DEFAULT_BAUD_RATE,
+    SERIAL_CONN_PREFIX,
+};
 pub use ssh_connection::{
-    build_default_auth_methods, get_default_identity_files, is_local_conn_name,
-    is_ssh_agent_available, is_ssh_conn_name, ConnKeywords, ConnStatus, ConnectionState,
-    HostKeyVerification, NoOpAuthCallback, SSHAuthCallback, SSHAuthMethod, SSHConfigEntry,
-    SSHConfigParser, SSHConn, SSHOpts, DEFAULT_SSH_PORT, MAX_PROXY_JUMP_DEPTH,
+    build_default_auth_methods, get_default_identity_files, identity_file_methods,
+    is_local_conn_name, is_ssh_agent_available, is_ssh_conn_name, ConnKeywords, ConnStatus,
+    ConnectionState, HostKeyVerification, NoOpAuthCallback, SSHAuthCallback, SSHAuthMethod,
+    SSHConfigEntry, SSHConfigParser, SSHConn, SSHConnLease, SSHConnRegistry, SSHOpts,
+    DEFAULT_SSH_IDLE_TIMEOUT, DEFAULT_SSH_PORT, MAX_PROXY_JUMP_DEPTH,
 };
+pub use ssh_forward::{ForwardKind, ForwardSpec, ForwardState, ForwardStatus, PortForwarder};
+pub use ssh_keepalive::{Backoff, KeepaliveConfig, MAX_RECONNECT_ATTEMPTS};
+pub use ssh_shell_integration::{RemoteShell, ScriptSource};
 pub use ssh_shell_proc::SSHShellProc;
+pub use ssh_x11::{LocalDisplay, X11Forwarder, X11Options};
+pub use telnet_connection::{
+    is_telnet_conn_name, TelnetConn, TelnetOpts, TelnetParser, DEFAULT_TELNET_PORT,
+    TCP_CONN_PREFIX, TELNET_CONN_PREFIX,
+};
 pub use wsl_connection::{
     is_wsl_conn_name, WSLConn, WSLDistro, WSLDistroState, WSLOpts, WSLShellProc,
     DEFAULT_WSL_DISTRO, WSL_CONN_PREFIX,
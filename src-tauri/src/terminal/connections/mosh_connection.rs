@@ -0,0 +1,313 @@
+//! Mosh 连接模块
+//!
+//! 通过本机的 mosh 客户端连接远程主机。mosh 先用 ssh 登录并启动 mosh-server，
+//! 之后改用 UDP 同步屏幕状态，因此支持本地预测回显、网络切换（漫游）和断续网络。
+//!
+//! ## 功能
+//! - 解析 `mosh://` 连接字符串
+//! - 查找本机 mosh 客户端
+//! - 构建在 PTY 中运行的 mosh 命令
+//!
+//! 状态同步协议（SSP）由 mosh 客户端实现，这里不重新实现，
+//! 终端会话只是在 PTY 中运行 mosh 客户端。
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use portable_pty::CommandBuilder;
+use serde::{Deserialize, Serialize};
+
+use super::SSHOpts;
+use crate::terminal::error::TerminalError;
+
+/// Mosh 连接前缀
+pub const MOSH_CONN_PREFIX: &str = "mosh://";
+
+/// mosh 不在 PATH 中时尝试的位置（macOS 图形应用的 PATH 不包含 Homebrew 目录）
+const MOSH_FALLBACK_PATHS: &[&str] = &[
+    "/opt/homebrew/bin/mosh",
+    "/usr/local/bin/mosh",
+    "/usr/bin/mosh",
+];
+
+// ============================================================================
+// 预测回显模式
+// ============================================================================
+
+/// 本地预测回显模式（对应 mosh `--predict`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoshPredict {
+    /// 网络延迟较高时才显示预测
+    #[default]
+    Adaptive,
+    /// 总是显示预测
+    Always,
+    /// 从不显示预测
+    Never,
+    /// 激进预测（可能出现错误回显）
+    Experimental,
+}
+
+impl MoshPredict {
+    /// mosh 命令行参数值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Adaptive => "adaptive",
+            Self::Always => "always",
+            Self::Never => "never",
+            Self::Experimental => "experimental",
+        }
+    }
+}
+
+impl std::str::FromStr for MoshPredict {
+    type Err = TerminalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "adaptive" => Ok(Self::Adaptive),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            "experimental" => Ok(Self::Experimental),
+            _ => Err(TerminalError::SSHConnectionFailed(format!(
+                "无效的 mosh 预测模式: {}",
+                s
+            ))),
+        }
+    }
+}
+
+// ============================================================================
+// Mosh 连接选项
+// ============================================================================
+
+/// Mosh 连接选项
+///
+/// ## 格式支持
+/// - `mosh://host`
+/// - `mosh://user@host:port`（port 为 ssh 端口）
+/// - `mosh://user@host?predict=always&server=/opt/bin/mosh-server&udp=60001`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoshOpts {
+    /// 用于登录和启动 mosh-server 的 SSH 目标
+    pub ssh: SSHOpts,
+    /// 预测回显模式
+    pub predict: MoshPredict,
+    /// 远程 mosh-server 路径（可选）
+    pub server: Option<String>,
+    /// 服务端 UDP 端口或范围，如 `60001` 或 `60000:60010`（可选）
+    pub udp_port: Option<String>,
+}
+
+impl MoshOpts {
+    /// 创建新的 Mosh 选项
+    pub fn new(ssh: SSHOpts) -> Self {
+        Self {
+            ssh,
+            predict: MoshPredict::default(),
+            server: None,
+            udp_port: None,
+        }
+    }
+
+    /// 从连接字符串解析 Mosh 选项
+    ///
+    /// # 参数
+    /// - `conn_str`: 连接字符串
+    ///
+    /// # 返回
+    /// - `Ok(MoshOpts)`: 解析成功
+    /// - `Err(TerminalError)`: 解析失败
+    pub fn parse(conn_str: &str) -> Result<Self, TerminalError> {
+        let conn_str = conn_str.trim();
+        let target = conn_str
+            .get(..MOSH_CONN_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(MOSH_CONN_PREFIX))
+            .map(|_| &conn_str[MOSH_CONN_PREFIX.len()..])
+            .ok_or_else(|| {
+                TerminalError::SSHConnectionFailed(format!(
+                    "无效的 Mosh 连接字符串，需要以 '{}' 开头: {}",
+                    MOSH_CONN_PREFIX, conn_str
+                ))
+            })?;
+
+        let (target, query) = match target.split_once('?') {
+            Some((target, query)) => (target, Some(query)),
+            None => (target, None),
+        };
+
+        let mut opts = Self::new(SSHOpts::parse(target)?);
+        for pair in query.into_iter().flat_map(|q| q.split('&')) {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            match key {
+                "predict" => opts.predict = value.parse()?,
+                "server" if !value.is_empty() => opts.server = Some(value.to_string()),
+                "udp" if !value.is_empty() => opts.udp_port = Some(value.to_string()),
+                _ => tracing::warn!("[Mosh] 忽略未知的连接参数: {}", pair),
+            }
+        }
+        Ok(opts)
+    }
+
+    /// 转换为连接字符串
+    pub fn to_connection_string(&self) -> String {
+        let mut params = Vec::new();
+        if self.predict != MoshPredict::default() {
+            params.push(format!("predict={}", self.predict.as_str()));
+        }
+        if let Some(ref server) = self.server {
+            params.push(format!("server={}", server));
+        }
+        if let Some(ref udp_port) = self.udp_port {
+            params.push(format!("udp={}", udp_port));
+        }
+
+        let mut result = format!("{}{}", MOSH_CONN_PREFIX, self.ssh.to_connection_string());
+        if !params.is_empty() {
+            result.push('?');
+            result.push_str(&params.join("&"));
+        }
+        result
+    }
+
+    /// mosh 客户端参数
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![format!("--predict={}", self.predict.as_str())];
+        if let Some(port) = self.ssh.ssh_port {
+            args.push(format!("--ssh=ssh -p {}", port));
+        }
+        if let Some(ref server) = self.server {
+            args.push(format!("--server={}", server));
+        }
+        if let Some(ref udp_port) = self.udp_port {
+            args.push(format!("--port={}", udp_port));
+        }
+        args.push("--".to_string());
+        args.push(match self.ssh.ssh_user {
+            Some(ref user) => format!("{}@{}", user, self.ssh.ssh_host),
+            None => self.ssh.ssh_host.clone(),
+        });
+        args
+    }
+
+    /// 构建在 PTY 中运行的 mosh 命令
+    pub fn build_command(&self, client: &Path) -> CommandBuilder {
+        let mut cmd = CommandBuilder::new(client);
+        cmd.args(self.args());
+        cmd.env("TERM", "xterm-256color");
+        if let Some(home) = dirs::home_dir() {
+            cmd.cwd(home);
+        }
+        cmd
+    }
+}
+
+impl std::fmt::Display for MoshOpts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_connection_string())
+    }
+}
+
+impl std::str::FromStr for MoshOpts {
+    type Err = TerminalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+// ============================================================================
+// 辅助函数
+// ============================================================================
+
+/// 查找可用的 mosh 客户端
+pub fn find_mosh_client() -> Option<PathBuf> {
+    std::iter::once("mosh")
+        .chain(MOSH_FALLBACK_PATHS.iter().copied())
+        .map(PathBuf::from)
+        .find(|path| {
+            Command::new(path)
+                .arg("--version")
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        })
+}
+
+/// 检查连接名称是否为 Mosh 连接
+///
+/// Mosh 连接名称以 "mosh://" 开头。
+pub fn is_mosh_conn_name(conn_name: &str) -> bool {
+    conn_name
+        .trim()
+        .to_lowercase()
+        .starts_with(MOSH_CONN_PREFIX)
+}
+
+// ============================================================================
+// 单元测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple() {
+        let opts = MoshOpts::parse("mosh://user@example.com").unwrap();
+        assert_eq!(opts.ssh.ssh_host, "example.com");
+        assert_eq!(opts.ssh.ssh_user.as_deref(), Some("user"));
+        assert_eq!(opts.predict, MoshPredict::Adaptive);
+        assert_eq!(
+            opts.args(),
+            vec!["--predict=adaptive", "--", "user@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_parse_with_options() {
+        let opts = MoshOpts::parse("mosh://root@10.0.0.1:2222?predict=always&udp=60001").unwrap();
+        assert_eq!(opts.ssh.ssh_port, Some(2222));
+        assert_eq!(opts.predict, MoshPredict::Always);
+        assert_eq!(opts.udp_port.as_deref(), Some("60001"));
+        assert_eq!(
+            opts.args(),
+            vec![
+                "--predict=always",
+                "--ssh=ssh -p 2222",
+                "--port=60001",
+                "--",
+                "root@10.0.0.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(MoshOpts::parse("user@host").is_err());
+        assert!(MoshOpts::parse("mosh://").is_err());
+        assert!(MoshOpts::parse("mosh://host?predict=sometimes").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for conn in [
+            "mosh://host",
+            "mosh://user@host:2222",
+            "mosh://user@host?predict=never&server=/opt/bin/mosh-server",
+        ] {
+            assert_eq!(MoshOpts::parse(conn).unwrap().to_connection_string(), conn);
+        }
+    }
+
+    #[test]
+    fn test_is_mosh_conn_name() {
+        assert!(is_mosh_conn_name("mosh://host"));
+        assert!(is_mosh_conn_name("  MOSH://user@host"));
+        assert!(!is_mosh_conn_name("ssh://host"));
+        assert!(!is_mosh_conn_name("user@host"));
+    }
+}
@@ -0,0 +1,334 @@
+//! 串口连接模块
+//!
+//! 通过 serialport 打开本机串口，用于嵌入式开发板、网络设备 Console 口等场景。
+//!
+//! ## 功能
+//! - 解析 `serial:///dev/ttyUSB0?baud=115200` 连接字符串（Windows 为 `serial://COM3`）
+//! - 支持波特率、数据位、校验位、停止位和流控设置
+//! - 列出本机可用串口
+//!
+//! 串口没有窗口大小的概念，调整大小会被忽略。
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serialport::{DataBits, FlowControl, Parity, StopBits};
+
+use crate::terminal::error::TerminalError;
+use crate::terminal::pty_session::{IgnoreResize, StreamParts};
+
+/// 串口连接前缀
+pub const SERIAL_CONN_PREFIX: &str = "serial://";
+
+/// 默认波特率
+pub const DEFAULT_BAUD_RATE: u32 = 115200;
+
+/// 读取超时（读取线程据此检查会话是否已关闭）
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+// ============================================================================
+// 串口连接选项
+// ============================================================================
+
+/// 串口连接选项
+///
+/// ## 格式支持
+/// - `serial:///dev/ttyUSB0` - 默认 115200 8N1，无流控
+/// - `serial:///dev/ttyUSB0?baud=9600&data=7&parity=even&stop=2&flow=hardware`
+/// - `serial://COM3?baud=9600`（Windows）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerialOpts {
+    /// 串口设备路径
+    pub path: String,
+    /// 波特率
+    pub baud_rate: u32,
+    /// 数据位（5-8）
+    pub data_bits: u8,
+    /// 校验位：none / odd / even
+    pub parity: String,
+    /// 停止位（1 或 2）
+    pub stop_bits: u8,
+    /// 流控：none / software / hardware
+    pub flow_control: String,
+}
+
+impl SerialOpts {
+    /// 创建新的串口选项（115200 8N1，无流控）
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            baud_rate: DEFAULT_BAUD_RATE,
+            data_bits: 8,
+            parity: "none".to_string(),
+            stop_bits: 1,
+            flow_control: "none".to_string(),
+        }
+    }
+
+    /// 从连接字符串解析串口选项
+    ///
+    /// # 参数
+    /// - `conn_str`: 连接字符串
+    ///
+    /// # 返回
+    /// - `Ok(SerialOpts)`: 解析成功
+    /// - `Err(TerminalError)`: 解析失败
+    pub fn parse(conn_str: &str) -> Result<Self, TerminalError> {
+        let conn_str = conn_str.trim();
+        let target = conn_str
+            .get(..SERIAL_CONN_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(SERIAL_CONN_PREFIX))
+            .map(|_| &conn_str[SERIAL_CONN_PREFIX.len()..])
+            .ok_or_else(|| {
+                TerminalError::SerialConnectionFailed(format!(
+                    "无效的串口连接字符串，需要以 '{}' 开头: {}",
+                    SERIAL_CONN_PREFIX, conn_str
+                ))
+            })?;
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (target, None),
+        };
+        if path.is_empty() {
+            return Err(TerminalError::SerialConnectionFailed(
+                "串口设备路径不能为空".to_string(),
+            ));
+        }
+
+        let invalid = |key: &str, value: &str| {
+            TerminalError::SerialConnectionFailed(format!("无效的串口参数 {}={}", key, value))
+        };
+        let mut opts = Self::new(path);
+        for pair in query.into_iter().flat_map(|q| q.split('&')) {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            match key {
+                "baud" => {
+                    opts.baud_rate = value
+                        .parse()
+                        .ok()
+                        .filter(|baud| *baud > 0)
+                        .ok_or_else(|| invalid(key, value))?
+                }
+                "data" => {
+                    opts.data_bits = value
+                        .parse()
+                        .ok()
+                        .filter(|bits| (5..=8).contains(bits))
+                        .ok_or_else(|| invalid(key, value))?
+                }
+                "parity" => match value.to_lowercase().as_str() {
+                    parity @ ("none" | "odd" | "even") => opts.parity = parity.to_string(),
+                    _ => return Err(invalid(key, value)),
+                },
+                "stop" => match value {
+                    "1" => opts.stop_bits = 1,
+                    "2" => opts.stop_bits = 2,
+                    _ => return Err(invalid(key, value)),
+                },
+                "flow" => match value.to_lowercase().as_str() {
+                    flow @ ("none" | "software" | "hardware") => {
+                        opts.flow_control = flow.to_string()
+                    }
+                    _ => return Err(invalid(key, value)),
+                },
+                _ => tracing::warn!("[Serial] 忽略未知的连接参数: {}", pair),
+            }
+        }
+        Ok(opts)
+    }
+
+    /// 转换为连接字符串（省略默认值）
+    pub fn to_connection_string(&self) -> String {
+        let defaults = Self::new(self.path.clone());
+        let mut params = Vec::new();
+        if self.baud_rate != defaults.baud_rate {
+            params.push(format!("baud={}", self.baud_rate));
+        }
+        if self.data_bits != defaults.data_bits {
+            params.push(format!("data={}", self.data_bits));
+        }
+        if self.parity != defaults.parity {
+            params.push(format!("parity={}", self.parity));
+        }
+        if self.stop_bits != defaults.stop_bits {
+            params.push(format!("stop={}", self.stop_bits));
+        }
+        if self.flow_control != defaults.flow_control {
+            params.push(format!("flow={}", self.flow_control));
+        }
+
+        let mut result = format!("{}{}", SERIAL_CONN_PREFIX, self.path);
+        if !params.is_empty() {
+            result.push('?');
+            result.push_str(&params.join("&"));
+        }
+        result
+    }
+}
+
+impl std::fmt::Display for SerialOpts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_connection_string())
+    }
+}
+
+impl std::str::FromStr for SerialOpts {
+    type Err = TerminalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+// ============================================================================
+// 串口连接
+// ============================================================================
+
+/// 串口连接
+pub struct SerialConn;
+
+impl SerialConn {
+    /// 打开串口
+    ///
+    /// # 参数
+    /// - `opts`: 串口选项
+    ///
+    /// # 返回
+    /// 交给 `PtySession::with_stream` 的读取器、写入器和大小调整器
+    pub fn open(opts: &SerialOpts) -> Result<StreamParts, TerminalError> {
+        let map_err = |e: serialport::Error| {
+            TerminalError::SerialConnectionFailed(format!("{} - {}", opts.path, e))
+        };
+
+        let data_bits = match opts.data_bits {
+            5 => DataBits::Five,
+            6 => DataBits::Six,
+            7 => DataBits::Seven,
+            _ => DataBits::Eight,
+        };
+        let parity = match opts.parity.as_str() {
+            "odd" => Parity::Odd,
+            "even" => Parity::Even,
+            _ => Parity::None,
+        };
+        let stop_bits = match opts.stop_bits {
+            2 => StopBits::Two,
+            _ => StopBits::One,
+        };
+        let flow_control = match opts.flow_control.as_str() {
+            "software" => FlowControl::Software,
+            "hardware" => FlowControl::Hardware,
+            _ => FlowControl::None,
+        };
+
+        let port = serialport::new(&opts.path, opts.baud_rate)
+            .data_bits(data_bits)
+            .parity(parity)
+            .stop_bits(stop_bits)
+            .flow_control(flow_control)
+            .timeout(READ_TIMEOUT)
+            .open()
+            .map_err(map_err)?;
+        let reader = port.try_clone().map_err(map_err)?;
+
+        tracing::info!("[Serial] 已打开串口 {}", opts);
+
+        Ok(StreamParts {
+            reader: Box::new(reader),
+            writer: Box::new(port),
+            resizer: Box::new(IgnoreResize),
+        })
+    }
+}
+
+// ============================================================================
+// 辅助函数
+// ============================================================================
+
+/// 列出本机可用串口
+pub fn list_serial_ports() -> Vec<String> {
+    match serialport::available_ports() {
+        Ok(ports) => ports.into_iter().map(|port| port.port_name).collect(),
+        Err(e) => {
+            tracing::warn!("[Serial] 列出串口失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 检查连接名称是否为串口连接
+///
+/// 串口连接名称以 "serial://" 开头。
+pub fn is_serial_conn_name(conn_name: &str) -> bool {
+    conn_name
+        .trim()
+        .to_lowercase()
+        .starts_with(SERIAL_CONN_PREFIX)
+}
+
+// ============================================================================
+// 单元测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_defaults() {
+        let opts = SerialOpts::parse("serial:///dev/ttyUSB0").unwrap();
+        assert_eq!(opts, SerialOpts::new("/dev/ttyUSB0"));
+        assert_eq!(opts.baud_rate, 115200);
+
+        let opts = SerialOpts::parse("serial://COM3?baud=9600").unwrap();
+        assert_eq!(opts.path, "COM3");
+        assert_eq!(opts.baud_rate, 9600);
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let opts = SerialOpts::parse(
+            "serial:///dev/ttyS1?baud=57600&data=7&parity=Even&stop=2&flow=hardware",
+        )
+        .unwrap();
+        assert_eq!(opts.baud_rate, 57600);
+        assert_eq!(opts.data_bits, 7);
+        assert_eq!(opts.parity, "even");
+        assert_eq!(opts.stop_bits, 2);
+        assert_eq!(opts.flow_control, "hardware");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(SerialOpts::parse("/dev/ttyUSB0").is_err());
+        assert!(SerialOpts::parse("serial://").is_err());
+        assert!(SerialOpts::parse("serial:///dev/ttyUSB0?baud=fast").is_err());
+        assert!(SerialOpts::parse("serial:///dev/ttyUSB0?data=9").is_err());
+        assert!(SerialOpts::parse("serial:///dev/ttyUSB0?parity=mark").is_err());
+        assert!(SerialOpts::parse("serial:///dev/ttyUSB0?stop=3").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for conn in [
+            "serial:///dev/ttyUSB0",
+            "serial://COM3?baud=9600&parity=odd",
+            "serial:///dev/ttyACM0?data=7&stop=2&flow=software",
+        ] {
+            assert_eq!(
+                SerialOpts::parse(conn).unwrap().to_connection_string(),
+                conn
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_serial_conn_name() {
+        assert!(is_serial_conn_name("serial:///dev/ttyUSB0"));
+        assert!(is_serial_conn_name("SERIAL://COM3"));
+        assert!(!is_serial_conn_name("telnet://host"));
+    }
+}
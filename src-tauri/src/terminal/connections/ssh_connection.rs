@@ -9,6 +9,9 @@
 //! - 远程 PTY 创建和数据转发
 //! - SSH 配置文件解析
 //! - known_hosts 验证
+//! - 端口转发（LocalForward/RemoteForward/DynamicForward，见 `ssh_forward`）
+//! - X11 转发（ForwardX11，需用户确认，见 `ssh_x11`）
+//! - Agent 转发（ForwardAgent）不支持，配置后连接失败
 //!
 //! ## Requirements
 //! - 4.1: 解析连接字符串
@@ -24,13 +27,18 @@
 use std::collections::HashMap;
 use std::fmt;
 use std::net::TcpStream;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicI32, AtomicI64, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicI64, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
 use ssh2::{KeyboardInteractivePrompt as SshKeyboardInteractivePrompt, Session};
 
+use super::ssh_forward::{bridge_direct_tcpip, ForwardSpec, ForwardStatus, PortForwarder};
+use super::ssh_keepalive::{spawn_keepalive, Backoff, KeepaliveConfig, MAX_RECONNECT_ATTEMPTS};
+use super::ssh_x11::{X11Forwarder, X11Options};
 use crate::terminal::error::TerminalError;
 
 /// 默认 SSH 端口
@@ -348,6 +356,12 @@ pub struct ConnStatus {
     pub no_wsh_reason: Option<String>,
     /// wsh 版本
     pub wsh_version: Option<String>,
+    /// 经过的跳板机（ProxyJump，按连接顺序）
+    #[serde(default)]
+    pub jump_hosts: Vec<String>,
+    /// 自动重连的当前尝试次数（0 表示未在重连）
+    #[serde(default)]
+    pub reconnect_attempt: u32,
 }
 
 impl Default for ConnStatus {
@@ -363,6 +377,8 @@ impl Default for ConnStatus {
             wsh_error: None,
             no_wsh_reason: None,
             wsh_version: None,
+            jump_hosts: Vec::new(),
+            reconnect_attempt: 0,
         }
     }
 }
@@ -419,6 +435,25 @@ pub enum SSHAuthMethod {
         /// 密钥密码（可选）
         passphrase: Option<String>,
     },
+    /// OpenSSH 证书认证
+    ///
+    /// 以证书（`-cert.pub`）作为公钥，用对应的私钥签名。
+    Certificate {
+        /// 证书文件路径
+        cert_path: PathBuf,
+        /// 私钥文件路径
+        key_path: PathBuf,
+        /// 密钥密码（可选）
+        passphrase: Option<String>,
+    },
+    /// 安全密钥认证（FIDO2 sk-ssh-ed25519 / sk-ecdsa-sha2-nistp256）
+    ///
+    /// 私钥文件只是硬件密钥的句柄，libssh2 无法直接签名，
+    /// 因此只使用 SSH Agent 中与该公钥（或证书）匹配的身份。
+    SecurityKey {
+        /// 公钥或证书文件路径
+        public_key_path: PathBuf,
+    },
     /// SSH Agent 认证
     Agent,
     /// 密码认证
@@ -442,6 +477,8 @@ pub struct ConnKeywords {
     pub port: Option<u16>,
     /// 身份文件路径列表（IdentityFile）
     pub identity_file: Option<Vec<String>>,
+    /// 证书文件路径列表（CertificateFile）
+    pub certificate_file: Option<Vec<String>>,
     /// ProxyJump 配置（跳板机）
     pub proxy_jump: Option<String>,
     /// ProxyCommand 配置
@@ -468,6 +505,10 @@ pub struct ConnKeywords {
     pub server_alive_interval: Option<u32>,
     /// 转发 Agent（ForwardAgent）
     pub forward_agent: Option<bool>,
+    /// X11 转发（ForwardX11）
+    pub forward_x11: Option<bool>,
+    /// 受信任的 X11 转发（ForwardX11Trusted）
+    pub forward_x11_trusted: Option<bool>,
     /// 压缩（Compression）
     pub compression: Option<bool>,
     /// 本地端口转发（LocalForward）
@@ -579,11 +620,36 @@ pub struct SSHConn {
     no_wsh_reason: RwLock<Option<String>>,
     /// Tauri 应用句柄（用于事件广播）
     app_handle: RwLock<Option<tauri::AppHandle>>,
+    /// 端口转发（认证成功后启动，断开时停止）
+    forwarder: PortForwarder,
+    /// X11 转发（用户确认后启用，认证成功后启动，断开时停止）
+    x11: X11Forwarder,
+    /// ProxyJump 跳板机连接（按连接顺序，断开时逆序关闭）
+    jump_hosts: RwLock<Vec<Arc<SSHConn>>>,
+    /// 跳板通道的停止标志（每次连接新建）
+    jump_stop: RwLock<Arc<AtomicBool>>,
+    /// 最近一次连接使用的配置（自动重连时复用）
+    conn_flags: RwLock<ConnKeywords>,
+    /// 最近一次认证成功的认证方式（自动重连时复用）
+    auth_methods: RwLock<Vec<SSHAuthMethod>>,
+    /// 保活线程的停止标志（每次认证成功新建）
+    keepalive_stop: RwLock<Arc<AtomicBool>>,
+    /// 自身的弱引用，启用自动重连后设置
+    self_ref: RwLock<Weak<SSHConn>>,
+    /// 是否正在自动重连
+    reconnecting: AtomicBool,
+    /// 自动重连的当前尝试次数
+    reconnect_attempt: AtomicU32,
+    /// 主动断开计数，自动重连期间变化表示用户已断开，停止重连
+    close_epoch: AtomicU64,
+    /// 认证成功次数，变化表示会话已重建
+    generation: AtomicU64,
 }
 
 impl SSHConn {
     /// 创建新的 SSH 连接管理器
     pub fn new(opts: SSHOpts) -> Self {
+        let forwarder = PortForwarder::new(opts.to_connection_string());
         Self {
             opts,
             state: RwLock::new(ConnectionState::Init),
@@ -598,6 +664,18 @@ impl SSHConn {
             wsh_error: RwLock::new(None),
             no_wsh_reason: RwLock::new(None),
             app_handle: RwLock::new(None),
+            forwarder,
+            x11: X11Forwarder::new(),
+            jump_hosts: RwLock::new(Vec::new()),
+            jump_stop: RwLock::new(Arc::new(AtomicBool::new(false))),
+            conn_flags: RwLock::new(ConnKeywords::default()),
+            auth_methods: RwLock::new(Vec::new()),
+            keepalive_stop: RwLock::new(Arc::new(AtomicBool::new(false))),
+            self_ref: RwLock::new(Weak::new()),
+            reconnecting: AtomicBool::new(false),
+            reconnect_attempt: AtomicU32::new(0),
+            close_epoch: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
         }
     }
 
@@ -606,12 +684,13 @@ impl SSHConn {
     /// 启用事件广播功能。
     pub fn with_app_handle(opts: SSHOpts, app_handle: tauri::AppHandle) -> Self {
         let conn = Self::new(opts);
-        *conn.app_handle.write() = Some(app_handle);
+        conn.set_app_handle(app_handle);
         conn
     }
 
     /// 设置 Tauri 应用句柄
     pub fn set_app_handle(&self, app_handle: tauri::AppHandle) {
+        self.forwarder.set_app_handle(app_handle.clone());
         *self.app_handle.write() = Some(app_handle);
     }
 
@@ -678,6 +757,38 @@ impl SSHConn {
         self.session.read().clone()
     }
 
+    /// 是否正在自动重连
+    pub fn is_reconnecting(&self) -> bool {
+        self.reconnecting.load(Ordering::SeqCst)
+    }
+
+    /// 会话代数，每次认证成功递增（重连后通道需要在新会话上重建）
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// 添加端口转发
+    ///
+    /// 已连接时立即启动，否则在下次认证成功后启动。
+    pub fn add_forward(&self, spec: ForwardSpec) -> Result<ForwardStatus, TerminalError> {
+        let session = self.is_connected().then(|| self.get_session()).flatten();
+        let status = self.forwarder.add(spec, session.as_ref())?;
+        tracing::info!("[SSHConn] 添加端口转发: {} ({})", status.spec, self.opts);
+        Ok(status)
+    }
+
+    /// 移除端口转发
+    pub fn remove_forward(&self, forward_id: &str) -> Result<(), TerminalError> {
+        self.forwarder.remove(forward_id)?;
+        tracing::info!("[SSHConn] 移除端口转发: {} ({})", forward_id, self.opts);
+        Ok(())
+    }
+
+    /// 获取所有端口转发状态
+    pub fn list_forwards(&self) -> Vec<ForwardStatus> {
+        self.forwarder.list()
+    }
+
     /// 派生连接状态
     ///
     /// 生成用于前端显示的连接状态详情。
@@ -695,13 +806,68 @@ impl SSHConn {
             wsh_error: self.wsh_error.read().clone(),
             no_wsh_reason: self.no_wsh_reason.read().clone(),
             wsh_version: self.wsh_version.read().clone(),
+            jump_hosts: self
+                .jump_hosts
+                .read()
+                .iter()
+                .map(|hop| hop.opts.to_connection_string())
+                .collect(),
+            reconnect_attempt: self.reconnect_attempt.load(Ordering::SeqCst),
         }
     }
 
     /// 连接到远程服务器
     ///
+    /// 配置了 ProxyJump 时跳板机使用无交互回调认证，需要交互时使用
+    /// [`SSHConn::connect_with_callback`]。
+    ///
     /// _Requirements: 4.10, 7.2_
-    pub async fn connect(&self, _conn_flags: &ConnKeywords) -> Result<(), TerminalError> {
+    pub async fn connect(&self, conn_flags: &ConnKeywords) -> Result<(), TerminalError> {
+        self.connect_with_callback(conn_flags, &NoOpAuthCallback)
+            .await
+    }
+
+    /// 连接到远程服务器，跳板机认证通过回调与用户交互
+    ///
+    /// 配置了 ProxyJump 时依次连接每个跳板机（主机密钥验证和认证都使用 `callback`），
+    /// 再通过最后一个跳板机的 direct-tcpip 通道连接目标主机。
+    ///
+    /// _Requirements: 4.7, 4.10, 7.2_
+    pub async fn connect_with_callback<C: SSHAuthCallback>(
+        &self,
+        conn_flags: &ConnKeywords,
+        callback: &C,
+    ) -> Result<(), TerminalError> {
+        self.begin_connect()?;
+        *self.conn_flags.write() = conn_flags.clone();
+
+        // 记录配置中的端口转发，认证成功后启动
+        self.forwarder.set_config_forwards(conn_flags);
+        self.configure_session_forwarding(conn_flags, callback)?;
+
+        // 建立传输层（直连或经过跳板机）
+        let tcp = match self.open_transport(conn_flags, callback).await {
+            Ok(tcp) => tcp,
+            Err(e) => {
+                tracing::error!("[SSHConn] {}", e);
+                self.close_jump_hosts();
+                self.set_state(ConnectionState::Error);
+                self.set_error(Some(e.to_string()));
+                self.broadcast_conn_change();
+                return Err(e);
+            }
+        };
+
+        // 注意：认证将在 authenticate 方法中完成
+        // 这里只完成连接建立
+        self.establish_session(tcp).map_err(|e| {
+            self.close_jump_hosts();
+            e
+        })
+    }
+
+    /// 进入连接中状态
+    fn begin_connect(&self) -> Result<(), TerminalError> {
         // 检查状态转换
         let current_state = self.state();
         if !current_state.can_transition_to(ConnectionState::Connecting) {
@@ -714,51 +880,131 @@ impl SSHConn {
         self.set_state(ConnectionState::Connecting);
         self.set_error(None);
         self.broadcast_conn_change();
+        Ok(())
+    }
+
+    /// 记录连接失败并返回错误
+    fn fail_connect(&self, error_msg: String) -> TerminalError {
+        tracing::error!("[SSHConn] {}", error_msg);
+        self.set_state(ConnectionState::Error);
+        self.set_error(Some(error_msg.clone()));
+        self.broadcast_conn_change();
+        TerminalError::SSHConnectionFailed(error_msg)
+    }
 
-        // 构建连接地址
+    /// 直接建立到本主机的 TCP 连接
+    fn connect_tcp(&self) -> Result<TcpStream, String> {
         let addr = format!("{}:{}", self.opts.ssh_host, self.opts.effective_port());
         tracing::info!("[SSHConn] 正在连接到 {}", addr);
+        TcpStream::connect(&addr).map_err(|e| format!("TCP 连接失败: {}", e))
+    }
 
-        // 建立 TCP 连接
-        let tcp = match TcpStream::connect(&addr) {
-            Ok(stream) => stream,
-            Err(e) => {
-                let error_msg = format!("TCP 连接失败: {}", e);
-                tracing::error!("[SSHConn] {}", error_msg);
-                self.set_state(ConnectionState::Error);
-                self.set_error(Some(error_msg.clone()));
-                self.broadcast_conn_change();
-                return Err(TerminalError::SSHConnectionFailed(error_msg));
+    /// 建立到目标主机的传输层
+    ///
+    /// 未配置 ProxyJump 时直连；否则逐个连接跳板机，返回经最后一跳桥接的 TCP 流。
+    async fn open_transport<C: SSHAuthCallback>(
+        &self,
+        conn_flags: &ConnKeywords,
+        callback: &C,
+    ) -> Result<TcpStream, TerminalError> {
+        let proxy_jump = conn_flags.proxy_jump.as_deref().unwrap_or_default();
+        let chain = SSHConfigParser::resolve_proxy_jump_chain(proxy_jump, 0)?;
+        if chain.is_empty() {
+            return self
+                .connect_tcp()
+                .map_err(TerminalError::SSHConnectionFailed);
+        }
+        if chain.len() > MAX_PROXY_JUMP_DEPTH {
+            return Err(TerminalError::SSHConnectionFailed(format!(
+                "ProxyJump 跳板机数量超过最大限制 {}",
+                MAX_PROXY_JUMP_DEPTH
+            )));
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        *self.jump_stop.write() = stop.clone();
+
+        let app_handle = self.app_handle.read().clone();
+        let mut prev: Option<Arc<SSHConn>> = None;
+        for (jump_opts, config) in chain {
+            let hop = Arc::new(SSHConn::new(SSHConfigParser::resolve_jump_host_opts(
+                &jump_opts, &config,
+            )));
+            if let Some(ref app_handle) = app_handle {
+                hop.set_app_handle(app_handle.clone());
             }
+            self.jump_hosts.write().push(hop.clone());
+            tracing::info!("[SSHConn] 连接跳板机 {} ({})", hop.opts, self.opts);
+
+            hop.connect_as_jump_host(prev.as_deref(), &stop, &config, callback)
+                .await
+                .map_err(|e| {
+                    TerminalError::SSHConnectionFailed(format!(
+                        "跳板机 {} 连接失败: {}",
+                        hop.opts, e
+                    ))
+                })?;
+            prev = Some(hop);
+        }
+
+        let last = prev.expect("ProxyJump 链非空");
+        tracing::info!("[SSHConn] 通过跳板机 {} 连接到 {}", last.opts, self.opts);
+        last.open_jump_channel(&self.opts.ssh_host, self.opts.effective_port(), stop)
+            .map_err(TerminalError::SSHConnectionFailed)
+    }
+
+    /// 作为跳板机完成连接：经上一跳（或直连）建立会话，验证主机密钥并认证
+    async fn connect_as_jump_host<C: SSHAuthCallback>(
+        &self,
+        prev: Option<&SSHConn>,
+        stop: &Arc<AtomicBool>,
+        config: &ConnKeywords,
+        callback: &C,
+    ) -> Result<(), TerminalError> {
+        self.begin_connect()?;
+        let tcp = match prev {
+            Some(prev) => prev.open_jump_channel(
+                &self.opts.ssh_host,
+                self.opts.effective_port(),
+                stop.clone(),
+            ),
+            None => self.connect_tcp(),
         };
+        let tcp = tcp.map_err(|e| self.fail_connect(e))?;
+        self.establish_session(tcp)?;
+        self.verify_and_authenticate(&build_default_auth_methods(config, None), callback)
+            .await
+    }
 
+    /// 在本连接上打开到 `host:port` 的 direct-tcpip 通道，并桥接为本地 TCP 流
+    fn open_jump_channel(
+        &self,
+        host: &str,
+        port: u16,
+        stop: Arc<AtomicBool>,
+    ) -> Result<TcpStream, String> {
+        let session = self
+            .get_session()
+            .ok_or_else(|| format!("跳板机 {} 未建立 SSH 会话", self.opts))?;
+        bridge_direct_tcpip(&session, host, port, stop)
+    }
+
+    /// 在已建立的 TCP 流上创建 SSH 会话并完成握手
+    fn establish_session(&self, tcp: TcpStream) -> Result<(), TerminalError> {
         // 创建 SSH 会话
-        let mut session = Session::new().map_err(|e| {
-            let error_msg = format!("创建 SSH 会话失败: {}", e);
-            self.set_state(ConnectionState::Error);
-            self.set_error(Some(error_msg.clone()));
-            self.broadcast_conn_change();
-            TerminalError::SSHConnectionFailed(error_msg)
-        })?;
+        let mut session =
+            Session::new().map_err(|e| self.fail_connect(format!("创建 SSH 会话失败: {}", e)))?;
 
         // 设置 TCP 流
-        session.set_tcp_stream(tcp.try_clone().map_err(|e| {
-            let error_msg = format!("克隆 TCP 流失败: {}", e);
-            self.set_state(ConnectionState::Error);
-            self.set_error(Some(error_msg.clone()));
-            self.broadcast_conn_change();
-            TerminalError::SSHConnectionFailed(error_msg)
-        })?);
+        session.set_tcp_stream(
+            tcp.try_clone()
+                .map_err(|e| self.fail_connect(format!("克隆 TCP 流失败: {}", e)))?,
+        );
 
         // 执行 SSH 握手
-        session.handshake().map_err(|e| {
-            let error_msg = format!("SSH 握手失败: {}", e);
-            tracing::error!("[SSHConn] {}", error_msg);
-            self.set_state(ConnectionState::Error);
-            self.set_error(Some(error_msg.clone()));
-            self.broadcast_conn_change();
-            TerminalError::SSHConnectionFailed(error_msg)
-        })?;
+        session
+            .handshake()
+            .map_err(|e| self.fail_connect(format!("SSH 握手失败: {}", e)))?;
 
         tracing::info!("[SSHConn] SSH 握手成功");
 
@@ -772,12 +1018,18 @@ impl SSHConn {
             *stream = Some(tcp);
         }
 
-        // 注意：认证将在 authenticate 方法中完成
-        // 这里只完成连接建立
-
         Ok(())
     }
 
+    /// 逆序关闭所有跳板机连接
+    fn close_jump_hosts(&self) {
+        self.jump_stop.read().store(true, Ordering::SeqCst);
+        let hops = std::mem::take(&mut *self.jump_hosts.write());
+        for hop in hops.iter().rev() {
+            hop.shutdown();
+        }
+    }
+
     /// 执行认证
     ///
     /// _Requirements: 4.3, 4.4, 4.5, 4.6_
@@ -794,12 +1046,7 @@ impl SSHConn {
             match self.try_auth(session, &username, method) {
                 Ok(()) => {
                     tracing::info!("[SSHConn] 认证成功");
-                    self.set_state(ConnectionState::Connected);
-                    self.has_connected.store(true, Ordering::SeqCst);
-                    self.last_connect_time
-                        .store(chrono::Utc::now().timestamp(), Ordering::SeqCst);
-                    self.active_conn_num.fetch_add(1, Ordering::SeqCst);
-                    self.broadcast_conn_change();
+                    self.on_authenticated(session, auth_methods);
                     return Ok(());
                 }
                 Err(e) => {
@@ -816,6 +1063,168 @@ impl SSHConn {
         Err(TerminalError::SSHAuthFailed(error_msg))
     }
 
+    /// 认证成功后更新状态，启动端口转发和保活
+    fn on_authenticated(&self, session: &Session, auth_methods: &[SSHAuthMethod]) {
+        self.set_state(ConnectionState::Connected);
+        self.has_connected.store(true, Ordering::SeqCst);
+        self.last_connect_time
+            .store(chrono::Utc::now().timestamp(), Ordering::SeqCst);
+        self.active_conn_num.fetch_add(1, Ordering::SeqCst);
+        self.reconnect_attempt.store(0, Ordering::SeqCst);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.auth_methods.write() = auth_methods.to_vec();
+        self.broadcast_conn_change();
+        self.forwarder.start_all(session);
+        self.x11.start(session);
+        self.start_keepalive(session);
+    }
+
+    /// 按配置启用 X11 转发（需用户确认），确认结果在自动重连时沿用
+    ///
+    /// libssh2 不向应用交付服务端发起的 `auth-agent@openssh.com` 通道，远程的 SSH_AUTH_SOCK
+    /// 又必须是 Unix socket，无法像 X11 一样用远程 TCP 监听代替；Agent 协议本身没有认证，
+    /// 回环端口会把本机 Agent 暴露给远程主机上的所有用户。因此配置了 ForwardAgent 时拒绝连接。
+    fn configure_session_forwarding<C: SSHAuthCallback>(
+        &self,
+        conn_flags: &ConnKeywords,
+        callback: &C,
+    ) -> Result<(), TerminalError> {
+        if conn_flags.forward_agent == Some(true) {
+            return Err(self.fail_connect(
+                "SSH 后端不支持 Agent 转发，请在 SSH 配置中关闭 ForwardAgent".to_string(),
+            ));
+        }
+
+        let host = self.opts.to_string();
+        let x11 = X11Options::from_keywords(conn_flags).filter(|options| {
+            let allowed = callback.confirm_x11_forwarding(&host, options.trusted);
+            if !allowed {
+                tracing::info!("[SSHConn] 用户拒绝 X11 转发: {}", host);
+            }
+            allowed
+        });
+        self.x11.configure(x11);
+        Ok(())
+    }
+
+    /// 远程 Shell 应使用的 DISPLAY（X11 转发运行中时）
+    pub fn x11_display(&self) -> Option<String> {
+        self.x11.remote_display()
+    }
+
+    /// 启用保活检测和断线自动重连
+    ///
+    /// 保活线程需要在判定断线时回到连接本身，因此只对 `Arc` 持有的连接启用。
+    pub fn enable_auto_reconnect(self: &Arc<Self>) {
+        *self.self_ref.write() = Arc::downgrade(self);
+        if let Some(session) = self.is_connected().then(|| self.get_session()).flatten() {
+            self.start_keepalive(&session);
+        }
+    }
+
+    /// 按 ServerAliveInterval / ServerAliveCountMax 启动保活线程
+    fn start_keepalive(&self, session: &Session) {
+        let weak = self.self_ref.read().clone();
+        if weak.strong_count() == 0 {
+            return;
+        }
+        let Some(config) = KeepaliveConfig::from_keywords(&self.conn_flags.read()) else {
+            return;
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let previous = std::mem::replace(&mut *self.keepalive_stop.write(), stop.clone());
+        previous.store(true, Ordering::SeqCst);
+
+        tracing::debug!(
+            "[SSHConn] 启动保活: {}，间隔 {:?}，最大失败 {} 次",
+            self.opts,
+            config.interval,
+            config.count_max
+        );
+        spawn_keepalive(session.clone(), config, stop, move |reason| {
+            if let Some(conn) = weak.upgrade() {
+                conn.handle_link_lost(&reason);
+            }
+        });
+    }
+
+    /// 处理连接断开：断开当前会话并在后台自动重连
+    pub fn handle_link_lost(self: &Arc<Self>, reason: &str) {
+        if self.reconnecting.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        tracing::warn!("[SSHConn] 连接已断开: {}，{}", self.opts, reason);
+
+        let epoch = self.close_epoch.load(Ordering::SeqCst);
+        self.shutdown();
+        self.set_error(Some(reason.to_string()));
+        self.broadcast_conn_change();
+
+        let conn = self.clone();
+        tauri::async_runtime::spawn(async move {
+            conn.reconnect_with_backoff(epoch).await;
+        });
+    }
+
+    /// 以指数退避自动重连，复用上次的连接配置和认证方式（无交互）
+    ///
+    /// _Requirements: 7.5_
+    async fn reconnect_with_backoff(&self, epoch: u64) {
+        let mut backoff = Backoff::default();
+        let conn_flags = self.conn_flags.read().clone();
+        let auth_methods = self.auth_methods.read().clone();
+
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            let delay = backoff.next_delay();
+            self.reconnect_attempt.store(attempt, Ordering::SeqCst);
+            self.broadcast_conn_change();
+            tracing::info!(
+                "[SSHConn] {:?} 后第 {}/{} 次重连: {}",
+                delay,
+                attempt,
+                MAX_RECONNECT_ATTEMPTS,
+                self.opts
+            );
+            tokio::time::sleep(delay).await;
+
+            if self.close_epoch.load(Ordering::SeqCst) != epoch {
+                tracing::info!("[SSHConn] 连接已被主动断开，停止重连: {}", self.opts);
+                break;
+            }
+
+            match self
+                .connect_and_authenticate(&conn_flags, &auth_methods, &NoOpAuthCallback)
+                .await
+            {
+                Ok(()) => {
+                    tracing::info!("[SSHConn] 重连成功: {}", self.opts);
+                    self.reconnecting.store(false, Ordering::SeqCst);
+                    // 重连期间被主动断开
+                    if self.close_epoch.load(Ordering::SeqCst) != epoch {
+                        self.shutdown();
+                    }
+                    return;
+                }
+                Err(e) => {
+                    tracing::warn!("[SSHConn] 第 {} 次重连失败: {}", attempt, e);
+                    self.close_jump_hosts();
+                }
+            }
+        }
+
+        self.reconnect_attempt.store(0, Ordering::SeqCst);
+        self.reconnecting.store(false, Ordering::SeqCst);
+        if self.close_epoch.load(Ordering::SeqCst) == epoch {
+            self.set_state(ConnectionState::Error);
+            self.set_error(Some(format!(
+                "自动重连 {} 次均失败",
+                MAX_RECONNECT_ATTEMPTS
+            )));
+        }
+        self.broadcast_conn_change();
+    }
+
     /// 尝试单个认证方式
     fn try_auth(
         &self,
@@ -833,30 +1242,28 @@ impl SSHConn {
                     .userauth_pubkey_file(username, None, key_path, passphrase.as_deref())
                     .map_err(|e| TerminalError::SSHAuthFailed(e.to_string()))?;
             }
+            SSHAuthMethod::Certificate {
+                cert_path,
+                key_path,
+                passphrase,
+            } => {
+                tracing::debug!("[SSHConn] 尝试证书认证: {:?}", cert_path);
+                session
+                    .userauth_pubkey_file(
+                        username,
+                        Some(cert_path.as_path()),
+                        key_path,
+                        passphrase.as_deref(),
+                    )
+                    .map_err(|e| TerminalError::SSHAuthFailed(e.to_string()))?;
+            }
+            SSHAuthMethod::SecurityKey { public_key_path } => {
+                tracing::debug!("[SSHConn] 尝试安全密钥认证: {:?}", public_key_path);
+                return userauth_security_key(session, username, public_key_path);
+            }
             SSHAuthMethod::Agent => {
                 tracing::debug!("[SSHConn] 尝试 SSH Agent 认证");
-                let mut agent = session.agent().map_err(|e| {
-                    TerminalError::SSHAuthFailed(format!("获取 SSH Agent 失败: {}", e))
-                })?;
-                agent.connect().map_err(|e| {
-                    TerminalError::SSHAuthFailed(format!("连接 SSH Agent 失败: {}", e))
-                })?;
-                agent
-                    .list_identities()
-                    .map_err(|e| TerminalError::SSHAuthFailed(format!("列出身份失败: {}", e)))?;
-
-                let identities: Vec<_> = agent.identities().map_err(|e| {
-                    TerminalError::SSHAuthFailed(format!("获取身份列表失败: {}", e))
-                })?;
-
-                for identity in identities {
-                    if agent.userauth(username, &identity).is_ok() {
-                        return Ok(());
-                    }
-                }
-                return Err(TerminalError::SSHAuthFailed(
-                    "SSH Agent 中没有有效的身份".to_string(),
-                ));
+                return userauth_agent(session, username, None);
             }
             SSHAuthMethod::Password(password) => {
                 tracing::debug!("[SSHConn] 尝试密码认证");
@@ -1065,8 +1472,21 @@ impl SSHConn {
     ///
     /// _Requirements: 4.10_
     pub async fn close(&self) -> Result<(), TerminalError> {
+        // 主动断开时停止自动重连
+        self.close_epoch.fetch_add(1, Ordering::SeqCst);
+        self.shutdown();
+        Ok(())
+    }
+
+    /// 断开连接（同步部分，跳板机连接也通过它关闭）
+    fn shutdown(&self) {
         tracing::info!("[SSHConn] 断开连接: {}", self.opts);
 
+        // 停止保活和端口转发（保留配置，重新认证后恢复）
+        self.keepalive_stop.read().store(true, Ordering::SeqCst);
+        self.forwarder.stop_all();
+        self.x11.stop();
+
         // 断开 SSH 会话
         {
             let mut session = self.session.write();
@@ -1081,11 +1501,12 @@ impl SSHConn {
             *stream = None;
         }
 
+        // 关闭跳板机连接
+        self.close_jump_hosts();
+
         self.set_state(ConnectionState::Disconnected);
         self.active_conn_num.fetch_sub(1, Ordering::SeqCst);
         self.broadcast_conn_change();
-
-        Ok(())
     }
 
     /// 重新连接
@@ -1127,13 +1548,7 @@ impl SSHConn {
             match self.try_auth_with_callback(session, &username, method, callback) {
                 Ok(()) => {
                     tracing::info!("[SSHConn] 认证成功");
-                    // 释放读锁后再修改状态
-                    self.set_state(ConnectionState::Connected);
-                    self.has_connected.store(true, Ordering::SeqCst);
-                    self.last_connect_time
-                        .store(chrono::Utc::now().timestamp(), Ordering::SeqCst);
-                    self.active_conn_num.fetch_add(1, Ordering::SeqCst);
-                    self.broadcast_conn_change();
+                    self.on_authenticated(session, auth_methods);
                     return Ok(());
                 }
                 Err(e) => {
@@ -1165,54 +1580,37 @@ impl SSHConn {
                 passphrase,
             } => {
                 tracing::debug!("[SSHConn] 尝试公钥认证: {:?}", key_path);
-
-                // 首先尝试不带密码
-                let result =
-                    session.userauth_pubkey_file(username, None, key_path, passphrase.as_deref());
-
-                match result {
-                    Ok(()) if session.authenticated() => return Ok(()),
-                    Err(e) if e.code() == ssh2::ErrorCode::Session(-16) => {
-                        // 密钥需要密码，通过回调请求
-                        tracing::debug!("[SSHConn] 密钥需要密码: {:?}", key_path);
-                        if let Some(pass) = callback.request_passphrase(key_path) {
-                            session
-                                .userauth_pubkey_file(username, None, key_path, Some(&pass))
-                                .map_err(|e| TerminalError::SSHAuthFailed(e.to_string()))?;
-                        } else {
-                            return Err(TerminalError::SSHAuthFailed(
-                                "用户取消输入密钥密码".to_string(),
-                            ));
-                        }
-                    }
-                    Err(e) => return Err(TerminalError::SSHAuthFailed(e.to_string())),
-                    Ok(()) => {}
-                }
+                userauth_key_file(
+                    session,
+                    username,
+                    None,
+                    key_path,
+                    passphrase.as_deref(),
+                    callback,
+                )?;
+            }
+            SSHAuthMethod::Certificate {
+                cert_path,
+                key_path,
+                passphrase,
+            } => {
+                tracing::debug!("[SSHConn] 尝试证书认证: {:?}", cert_path);
+                userauth_key_file(
+                    session,
+                    username,
+                    Some(cert_path.as_path()),
+                    key_path,
+                    passphrase.as_deref(),
+                    callback,
+                )?;
+            }
+            SSHAuthMethod::SecurityKey { public_key_path } => {
+                tracing::debug!("[SSHConn] 尝试安全密钥认证: {:?}", public_key_path);
+                return userauth_security_key(session, username, public_key_path);
             }
             SSHAuthMethod::Agent => {
                 tracing::debug!("[SSHConn] 尝试 SSH Agent 认证");
-                let mut agent = session.agent().map_err(|e| {
-                    TerminalError::SSHAuthFailed(format!("获取 SSH Agent 失败: {}", e))
-                })?;
-                agent.connect().map_err(|e| {
-                    TerminalError::SSHAuthFailed(format!("连接 SSH Agent 失败: {}", e))
-                })?;
-                agent
-                    .list_identities()
-                    .map_err(|e| TerminalError::SSHAuthFailed(format!("列出身份失败: {}", e)))?;
-
-                let identities: Vec<_> = agent.identities().map_err(|e| {
-                    TerminalError::SSHAuthFailed(format!("获取身份列表失败: {}", e))
-                })?;
-
-                for identity in identities {
-                    if agent.userauth(username, &identity).is_ok() && session.authenticated() {
-                        return Ok(());
-                    }
-                }
-                return Err(TerminalError::SSHAuthFailed(
-                    "SSH Agent 中没有有效的身份".to_string(),
-                ));
+                return userauth_agent(session, username, None);
             }
             SSHAuthMethod::Password(password) => {
                 tracing::debug!("[SSHConn] 尝试密码认证");
@@ -1265,10 +1663,22 @@ impl SSHConn {
         auth_methods: &[SSHAuthMethod],
         callback: &C,
     ) -> Result<(), TerminalError> {
-        // 1. 建立连接
-        self.connect(conn_flags).await?;
+        // 1. 建立连接（跳板机同样通过回调认证）
+        self.connect_with_callback(conn_flags, callback).await?;
 
-        // 2. 验证主机密钥
+        // 2. 验证主机密钥并认证
+        self.verify_and_authenticate(auth_methods, callback).await
+    }
+
+    /// 验证主机密钥并执行认证
+    ///
+    /// _Requirements: 4.3-4.9_
+    async fn verify_and_authenticate<C: SSHAuthCallback>(
+        &self,
+        auth_methods: &[SSHAuthMethod],
+        callback: &C,
+    ) -> Result<(), TerminalError> {
+        // 1. 验证主机密钥
         match self.verify_host_key()? {
             HostKeyVerification::Verified => {
                 tracing::info!("[SSHConn] 主机密钥已验证");
@@ -1305,12 +1715,20 @@ impl SSHConn {
             }
         }
 
-        // 3. 执行认证
+        // 2. 执行认证
         self.authenticate_with_callback(auth_methods, callback)
             .await
     }
 }
 
+impl Drop for SSHConn {
+    fn drop(&mut self) {
+        // 保活线程和跳板通道持有会话副本，需要显式停止
+        self.keepalive_stop.read().store(true, Ordering::SeqCst);
+        self.jump_stop.read().store(true, Ordering::SeqCst);
+    }
+}
+
 /// 带回调的键盘交互认证处理器
 struct CallbackKeyboardInteractivePrompt<'a, C: SSHAuthCallback> {
     callback: &'a C,
@@ -1336,6 +1754,277 @@ impl<'a, C: SSHAuthCallback> SshKeyboardInteractivePrompt
     }
 }
 
+// ============================================================================
+// SSH 连接注册表
+// ============================================================================
+
+/// 空闲共享连接的默认保留时间（最后一个租约释放后）
+pub const DEFAULT_SSH_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 注册表中的连接及其引用计数
+struct SharedConn {
+    conn: Arc<SSHConn>,
+    /// 持有该连接的租约数
+    refs: usize,
+    /// 空闲代数：每次引用归零或被重新引用时递增，用于判断拆除任务是否过期
+    idle_generation: u64,
+}
+
+impl SharedConn {
+    fn new(conn: Arc<SSHConn>, refs: usize) -> Self {
+        Self {
+            conn,
+            refs,
+            idle_generation: 0,
+        }
+    }
+}
+
+/// SSH 连接注册表
+///
+/// 按规范化的连接字符串保存已建立的 SSHConn，供 Tauri 命令（如端口转发）按名称查找。
+/// 同时负责连接复用（类似 OpenSSH ControlMaster）：同一主机的多个终端块通过
+/// [`SSHConnRegistry::acquire`] 共享一个已认证的连接，各自在其上打开通道；
+/// 最后一个租约释放后连接进入空闲，超过空闲时间仍无人使用则自动断开。
+pub struct SSHConnRegistry {
+    conns: RwLock<HashMap<String, SharedConn>>,
+    /// 按连接名称串行化建立连接，避免同一主机并发握手和认证
+    connect_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// 空闲连接保留时间
+    idle_timeout: Duration,
+    /// Tauri 应用句柄（新建的连接用于广播状态事件）
+    app_handle: RwLock<Option<tauri::AppHandle>>,
+}
+
+impl Default for SSHConnRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SSHConnRegistry {
+    /// 创建空注册表
+    pub fn new() -> Self {
+        Self {
+            conns: RwLock::new(HashMap::new()),
+            connect_locks: Mutex::new(HashMap::new()),
+            idle_timeout: DEFAULT_SSH_IDLE_TIMEOUT,
+            app_handle: RwLock::new(None),
+        }
+    }
+
+    /// 设置空闲连接保留时间
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// 设置 Tauri 应用句柄
+    pub fn set_app_handle(&self, app_handle: tauri::AppHandle) {
+        *self.app_handle.write() = Some(app_handle);
+    }
+
+    /// 注册连接，返回被替换的旧连接
+    ///
+    /// 直接注册的连接由调用方管理生命周期，不会被空闲拆除。
+    pub fn register(&self, conn: Arc<SSHConn>) -> Option<Arc<SSHConn>> {
+        let key = conn.opts().to_connection_string();
+        self.conns
+            .write()
+            .insert(key, SharedConn::new(conn, 0))
+            .map(|old| old.conn)
+    }
+
+    /// 按连接名称查找（支持 `ssh://` 前缀和未规范化的写法）
+    pub fn get(&self, connection: &str) -> Option<Arc<SSHConn>> {
+        let key = Self::normalize(connection);
+        self.conns
+            .read()
+            .get(&key)
+            .map(|shared| shared.conn.clone())
+    }
+
+    /// 移除连接
+    pub fn remove(&self, connection: &str) -> Option<Arc<SSHConn>> {
+        let key = Self::normalize(connection);
+        self.conns.write().remove(&key).map(|shared| shared.conn)
+    }
+
+    /// 已注册的连接名称
+    pub fn connections(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.conns.read().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// 连接当前的租约数
+    pub fn ref_count(&self, connection: &str) -> usize {
+        let key = Self::normalize(connection);
+        self.conns.read().get(&key).map_or(0, |shared| shared.refs)
+    }
+
+    /// 规范化连接名称，无法解析时原样使用
+    fn normalize(connection: &str) -> String {
+        SSHOpts::parse(connection)
+            .map(|opts| opts.to_connection_string())
+            .unwrap_or_else(|_| connection.to_string())
+    }
+
+    /// 获取到指定主机的共享连接
+    ///
+    /// 已有可用连接时直接复用（不重复握手和认证），否则新建连接并完成认证。
+    /// 返回的租约在释放前保持连接打开。
+    ///
+    /// _Requirements: 4.2, 7.1_
+    pub async fn acquire<C: SSHAuthCallback>(
+        self: &Arc<Self>,
+        opts: SSHOpts,
+        conn_flags: &ConnKeywords,
+        auth_methods: &[SSHAuthMethod],
+        callback: &C,
+    ) -> Result<SSHConnLease, TerminalError> {
+        let key = opts.to_connection_string();
+        let connect_lock = self
+            .connect_locks
+            .lock()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let _connecting = connect_lock.lock().await;
+
+        if let Some(lease) = self.try_share(&key) {
+            return Ok(lease);
+        }
+
+        let conn = Arc::new(SSHConn::new(opts));
+        if let Some(app_handle) = self.app_handle.read().clone() {
+            conn.set_app_handle(app_handle);
+        }
+        conn.enable_auto_reconnect();
+        if let Err(e) = conn
+            .connect_and_authenticate(conn_flags, auth_methods, callback)
+            .await
+        {
+            let _ = conn.close().await;
+            return Err(e);
+        }
+
+        tracing::info!("[SSHConn] 新建共享连接: {}", key);
+        let old = self
+            .conns
+            .write()
+            .insert(key.clone(), SharedConn::new(conn.clone(), 1));
+        // 旧连接已不可用；仍被租约持有时由租约释放，否则在此断开
+        if let Some(old) = old.filter(|old| old.refs == 0) {
+            let _ = old.conn.close().await;
+        }
+
+        Ok(SSHConnLease {
+            registry: self.clone(),
+            key,
+            conn,
+        })
+    }
+
+    /// 复用已连接的共享连接
+    fn try_share(self: &Arc<Self>, key: &str) -> Option<SSHConnLease> {
+        let mut conns = self.conns.write();
+        let shared = conns.get_mut(key)?;
+        if !shared.conn.is_connected() {
+            return None;
+        }
+        shared.refs += 1;
+        // 取消等待中的空闲拆除
+        shared.idle_generation += 1;
+        tracing::info!("[SSHConn] 复用共享连接: {}（{} 个租约）", key, shared.refs);
+
+        Some(SSHConnLease {
+            registry: self.clone(),
+            key: key.to_string(),
+            conn: shared.conn.clone(),
+        })
+    }
+
+    /// 释放租约，引用归零时安排空闲拆除
+    fn release(self: &Arc<Self>, key: &str, conn: &Arc<SSHConn>) {
+        let generation = {
+            let mut conns = self.conns.write();
+            let Some(shared) = conns.get_mut(key) else {
+                return;
+            };
+            // 连接已被替换，旧租约与注册表无关
+            if !Arc::ptr_eq(&shared.conn, conn) {
+                return;
+            }
+            shared.refs = shared.refs.saturating_sub(1);
+            if shared.refs > 0 {
+                return;
+            }
+            shared.idle_generation += 1;
+            shared.idle_generation
+        };
+
+        tracing::debug!(
+            "[SSHConn] 共享连接空闲: {}，{:?} 后断开",
+            key,
+            self.idle_timeout
+        );
+        let registry = self.clone();
+        let key = key.to_string();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(registry.idle_timeout).await;
+            registry.teardown_idle(&key, generation).await;
+        });
+    }
+
+    /// 断开空闲超时且期间未被重新引用的连接
+    async fn teardown_idle(&self, key: &str, generation: u64) {
+        let conn = {
+            let mut conns = self.conns.write();
+            let idle = matches!(
+                conns.get(key),
+                Some(shared) if shared.refs == 0 && shared.idle_generation == generation
+            );
+            if !idle {
+                return;
+            }
+            conns.remove(key).map(|shared| shared.conn)
+        };
+
+        if let Some(conn) = conn {
+            tracing::info!("[SSHConn] 断开空闲共享连接: {}", key);
+            let _ = conn.close().await;
+        }
+    }
+}
+
+/// 共享 SSH 连接的租约
+///
+/// 持有期间连接保持打开；释放（Drop）后减少引用计数。
+pub struct SSHConnLease {
+    registry: Arc<SSHConnRegistry>,
+    key: String,
+    conn: Arc<SSHConn>,
+}
+
+impl SSHConnLease {
+    /// 共享的连接
+    pub fn conn(&self) -> &Arc<SSHConn> {
+        &self.conn
+    }
+
+    /// 连接名称
+    pub fn connection(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Drop for SSHConnLease {
+    fn drop(&mut self) {
+        self.registry.release(&self.key, &self.conn);
+    }
+}
+
 // ============================================================================
 // SSH 配置文件解析
 // ============================================================================
@@ -1510,6 +2199,14 @@ impl SSHConfigParser {
                     keywords.identity_file = Some(vec![path]);
                 }
             }
+            "certificatefile" => {
+                let path = Self::expand_path(value);
+                if let Some(ref mut files) = keywords.certificate_file {
+                    files.push(path);
+                } else {
+                    keywords.certificate_file = Some(vec![path]);
+                }
+            }
             "proxyjump" => keywords.proxy_jump = Some(value.to_string()),
             "proxycommand" => keywords.proxy_command = Some(value.to_string()),
             "batchmode" => keywords.batch_mode = Some(Self::parse_bool(value)),
@@ -1544,6 +2241,12 @@ impl SSHConfigParser {
             "forwardagent" => {
                 keywords.forward_agent = Some(Self::parse_bool(value));
             }
+            "forwardx11" => {
+                keywords.forward_x11 = Some(Self::parse_bool(value));
+            }
+            "forwardx11trusted" => {
+                keywords.forward_x11_trusted = Some(Self::parse_bool(value));
+            }
             "compression" => {
                 keywords.compression = Some(Self::parse_bool(value));
             }
@@ -1692,6 +2395,18 @@ impl SSHConfigParser {
                 }
             }
         }
+        if target.certificate_file.is_none() {
+            target.certificate_file = source.certificate_file.clone();
+        } else if let Some(ref source_files) = source.certificate_file {
+            // CertificateFile 同样是累加的
+            if let Some(ref mut target_files) = target.certificate_file {
+                for file in source_files {
+                    if !target_files.contains(file) {
+                        target_files.push(file.clone());
+                    }
+                }
+            }
+        }
         if target.proxy_jump.is_none() {
             target.proxy_jump = source.proxy_jump.clone();
         }
@@ -1731,6 +2446,12 @@ impl SSHConfigParser {
         if target.forward_agent.is_none() {
             target.forward_agent = source.forward_agent;
         }
+        if target.forward_x11.is_none() {
+            target.forward_x11 = source.forward_x11;
+        }
+        if target.forward_x11_trusted.is_none() {
+            target.forward_x11_trusted = source.forward_x11_trusted;
+        }
         if target.compression.is_none() {
             target.compression = source.compression;
         }
@@ -1914,6 +2635,19 @@ impl SSHConfigParser {
         Ok(chain)
     }
 
+    /// 合并跳板机的连接选项与其 ssh_config 配置
+    ///
+    /// 主机名优先使用配置中的 HostName；ProxyJump 中显式写出的用户和端口优先于配置。
+    ///
+    /// _Requirements: 4.7_
+    pub fn resolve_jump_host_opts(opts: &SSHOpts, config: &ConnKeywords) -> SSHOpts {
+        SSHOpts {
+            ssh_host: config.host.clone().unwrap_or_else(|| opts.ssh_host.clone()),
+            ssh_user: opts.ssh_user.clone().or_else(|| config.user.clone()),
+            ssh_port: opts.ssh_port.or(config.port),
+        }
+    }
+
     /// 将配置转换为连接字符串
     ///
     /// 用于 Round-Trip 测试。
@@ -1953,7 +2687,14 @@ pub fn get_default_identity_files() -> Vec<PathBuf> {
         let ssh_dir = home.join(".ssh");
 
         // 按优先级排序的默认密钥文件
-        let default_keys = ["id_ed25519", "id_ecdsa", "id_rsa", "id_dsa"];
+        let default_keys = [
+            "id_ed25519",
+            "id_ed25519_sk",
+            "id_ecdsa",
+            "id_ecdsa_sk",
+            "id_rsa",
+            "id_dsa",
+        ];
 
         for key in default_keys {
             let key_path = ssh_dir.join(key);
@@ -2038,33 +2779,213 @@ pub fn build_default_auth_methods(
 
 /// 添加身份文件认证方式
 fn add_identity_file_methods(methods: &mut Vec<SSHAuthMethod>, conn_keywords: &ConnKeywords) {
-    // 优先使用配置中指定的身份文件
-    if let Some(ref identity_files) = conn_keywords.identity_file {
-        for file in identity_files {
-            let path = PathBuf::from(file);
-            if path.exists() {
-                methods.push(SSHAuthMethod::PublicKey {
-                    key_path: path,
-                    passphrase: None,
-                });
-            }
+    let mut seen: Vec<PathBuf> = Vec::new();
+
+    // 显式指定的证书文件（CertificateFile）
+    for file in conn_keywords.certificate_file.iter().flatten() {
+        let cert_path = PathBuf::from(file);
+        if !seen.contains(&cert_path) {
+            methods.extend(identity_file_methods(&cert_path));
+            seen.push(cert_path);
         }
     }
 
-    // 然后添加默认身份文件
-    for key_path in get_default_identity_files() {
+    // 优先使用配置中指定的身份文件，然后添加默认身份文件
+    let configured = conn_keywords
+        .identity_file
+        .iter()
+        .flatten()
+        .map(PathBuf::from);
+    for path in configured.chain(get_default_identity_files()) {
         // 避免重复添加
-        let already_added = methods
-            .iter()
-            .any(|m| matches!(m, SSHAuthMethod::PublicKey { key_path: p, .. } if p == &key_path));
+        if seen.contains(&path) {
+            continue;
+        }
+        let auth_methods = identity_file_methods(&path);
+        seen.push(path);
+        for method in auth_methods {
+            if !methods.contains(&method) {
+                methods.push(method);
+            }
+        }
+    }
+}
 
-        if !already_added {
-            methods.push(SSHAuthMethod::PublicKey {
-                key_path,
-                passphrase: None,
+/// 证书文件后缀
+const CERT_SUFFIX: &str = "-cert.pub";
+
+/// 根据身份文件选择认证方式
+///
+/// - `xxx-cert.pub`：OpenSSH 证书，私钥为去掉后缀的 `xxx`
+/// - `xxx.pub`：公钥，私钥存在时按私钥处理
+/// - 其他：私钥；同目录存在 `xxx-cert.pub` 时先尝试证书认证
+///
+/// 公钥类型为 `sk-*` 的安全密钥只能通过 SSH Agent 签名，返回 `SecurityKey`。
+pub fn identity_file_methods(path: &Path) -> Vec<SSHAuthMethod> {
+    if !path.exists() {
+        return Vec::new();
+    }
+    let file_name = path.to_string_lossy();
+
+    if let Some(key) = file_name.strip_suffix(CERT_SUFFIX) {
+        if is_security_key_file(path) {
+            return vec![SSHAuthMethod::SecurityKey {
+                public_key_path: path.to_path_buf(),
+            }];
+        }
+        let key_path = PathBuf::from(key);
+        if !key_path.exists() {
+            tracing::warn!("[SSHConn] 证书 {:?} 缺少对应的私钥，已忽略", path);
+            return Vec::new();
+        }
+        return vec![SSHAuthMethod::Certificate {
+            cert_path: path.to_path_buf(),
+            key_path,
+            passphrase: None,
+        }];
+    }
+
+    if let Some(key) = file_name.strip_suffix(".pub") {
+        if is_security_key_file(path) {
+            return vec![SSHAuthMethod::SecurityKey {
+                public_key_path: path.to_path_buf(),
+            }];
+        }
+        return identity_file_methods(Path::new(key));
+    }
+
+    let cert_path = PathBuf::from(format!("{}{}", file_name, CERT_SUFFIX));
+    let pub_path = PathBuf::from(format!("{}.pub", file_name));
+    if pub_path.exists() && is_security_key_file(&pub_path) {
+        let mut methods = Vec::new();
+        if cert_path.exists() {
+            methods.push(SSHAuthMethod::SecurityKey {
+                public_key_path: cert_path,
             });
         }
+        methods.push(SSHAuthMethod::SecurityKey {
+            public_key_path: pub_path,
+        });
+        return methods;
+    }
+
+    let mut methods = Vec::new();
+    if cert_path.exists() {
+        methods.push(SSHAuthMethod::Certificate {
+            cert_path,
+            key_path: path.to_path_buf(),
+            passphrase: None,
+        });
+    }
+    methods.push(SSHAuthMethod::PublicKey {
+        key_path: path.to_path_buf(),
+        passphrase: None,
+    });
+    methods
+}
+
+/// 读取 OpenSSH 公钥（或证书）文件，返回 `(密钥类型, 公钥 blob)`
+fn read_public_key(path: &Path) -> Option<(String, Vec<u8>)> {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut parts = content.split_whitespace();
+    let key_type = parts.next()?.to_string();
+    let blob = BASE64.decode(parts.next()?).ok()?;
+    Some((key_type, blob))
+}
+
+/// 是否为安全密钥（sk-ssh-ed25519@openssh.com、sk-ecdsa-sha2-nistp256@openssh.com 及其证书）
+fn is_security_key_file(path: &Path) -> bool {
+    read_public_key(path).is_some_and(|(key_type, _)| key_type.starts_with("sk-"))
+}
+
+/// 公钥文件认证，私钥需要密码时通过回调请求
+fn userauth_key_file<C: SSHAuthCallback>(
+    session: &Session,
+    username: &str,
+    pubkey: Option<&Path>,
+    key_path: &Path,
+    passphrase: Option<&str>,
+    callback: &C,
+) -> Result<(), TerminalError> {
+    // 首先尝试不带密码
+    let result = session.userauth_pubkey_file(username, pubkey, key_path, passphrase);
+
+    match result {
+        Err(e) if e.code() == ssh2::ErrorCode::Session(-16) => {
+            // 密钥需要密码，通过回调请求
+            tracing::debug!("[SSHConn] 密钥需要密码: {:?}", key_path);
+            if let Some(pass) = callback.request_passphrase(&key_path.to_path_buf()) {
+                session
+                    .userauth_pubkey_file(username, pubkey, key_path, Some(&pass))
+                    .map_err(|e| TerminalError::SSHAuthFailed(e.to_string()))
+            } else {
+                Err(TerminalError::SSHAuthFailed(
+                    "用户取消输入密钥密码".to_string(),
+                ))
+            }
+        }
+        Err(e) => Err(TerminalError::SSHAuthFailed(e.to_string())),
+        Ok(()) => Ok(()),
+    }
+}
+
+/// 安全密钥认证
+///
+/// 签名由 SSH Agent 完成（需要用户触摸密钥），这里只负责挑出匹配的身份。
+fn userauth_security_key(
+    session: &Session,
+    username: &str,
+    public_key_path: &Path,
+) -> Result<(), TerminalError> {
+    let (_, blob) = read_public_key(public_key_path).ok_or_else(|| {
+        TerminalError::SSHAuthFailed(format!("无法读取安全密钥公钥: {:?}", public_key_path))
+    })?;
+    if !is_ssh_agent_available() {
+        return Err(TerminalError::SSHAuthFailed(
+            "安全密钥需要通过 SSH Agent 认证，但 SSH Agent 不可用".to_string(),
+        ));
+    }
+    tracing::info!("[SSHConn] 请触摸安全密钥以完成认证: {:?}", public_key_path);
+    userauth_agent(session, username, Some(&blob))
+}
+
+/// SSH Agent 认证
+///
+/// 指定 `blob` 时只使用公钥与之相同的身份。
+fn userauth_agent(
+    session: &Session,
+    username: &str,
+    blob: Option<&[u8]>,
+) -> Result<(), TerminalError> {
+    let mut agent = session
+        .agent()
+        .map_err(|e| TerminalError::SSHAuthFailed(format!("获取 SSH Agent 失败: {}", e)))?;
+    agent
+        .connect()
+        .map_err(|e| TerminalError::SSHAuthFailed(format!("连接 SSH Agent 失败: {}", e)))?;
+    agent
+        .list_identities()
+        .map_err(|e| TerminalError::SSHAuthFailed(format!("列出身份失败: {}", e)))?;
+
+    let identities: Vec<_> = agent
+        .identities()
+        .map_err(|e| TerminalError::SSHAuthFailed(format!("获取身份列表失败: {}", e)))?;
+
+    for identity in identities
+        .iter()
+        .filter(|identity| blob.map_or(true, |blob| identity.blob() == blob))
+    {
+        if agent.userauth(username, identity).is_ok() && session.authenticated() {
+            return Ok(());
+        }
     }
+    Err(TerminalError::SSHAuthFailed(if blob.is_some() {
+        "SSH Agent 中没有匹配的安全密钥（请先执行 ssh-add 加载）".to_string()
+    } else {
+        "SSH Agent 中没有有效的身份".to_string()
+    }))
 }
 
 /// 检查 SSH Agent 是否可用
@@ -2109,6 +3030,12 @@ pub trait SSHAuthCallback: Send + Sync {
     ///
     /// 当主机密钥不匹配时调用，返回是否继续。
     fn warn_host_key_mismatch(&self, host: &str, key_type: &str, fingerprint: &str) -> bool;
+
+    /// 确认 X11 转发
+    ///
+    /// 配置了 ForwardX11 时调用，返回是否启用。远程主机上的程序可借此访问本机显示，
+    /// 受信任转发（`trusted`）时还可以截屏、监听键盘输入，应提醒用户仅对可信主机开启。
+    fn confirm_x11_forwarding(&self, host: &str, trusted: bool) -> bool;
 }
 
 /// 默认认证回调（无交互）
@@ -2142,6 +3069,10 @@ impl SSHAuthCallback for NoOpAuthCallback {
     fn warn_host_key_mismatch(&self, _host: &str, _key_type: &str, _fingerprint: &str) -> bool {
         false // 默认不继续
     }
+
+    fn confirm_x11_forwarding(&self, _host: &str, _trusted: bool) -> bool {
+        false // 默认不启用
+    }
 }
 
 /// 检查连接名称是否为本地连接
@@ -2153,7 +3084,13 @@ pub fn is_local_conn_name(conn_name: &str) -> bool {
 
 /// 检查连接名称是否为 SSH 连接
 pub fn is_ssh_conn_name(conn_name: &str) -> bool {
-    if is_local_conn_name(conn_name) {
+    if is_local_conn_name(conn_name)
+        || super::is_mosh_conn_name(conn_name)
+        || super::is_telnet_conn_name(conn_name)
+        || super::is_serial_conn_name(conn_name)
+        || super::is_docker_conn_name(conn_name)
+        || super::is_k8s_conn_name(conn_name)
+    {
         return false;
     }
 
@@ -2385,6 +3322,7 @@ Host target
         assert!(!is_ssh_conn_name(""));
         assert!(!is_ssh_conn_name("local"));
         assert!(!is_ssh_conn_name("wsl://Ubuntu"));
+        assert!(!is_ssh_conn_name("mosh://user@host"));
     }
 
     // ========================================================================
@@ -2432,6 +3370,85 @@ Host target
         assert!(conn.error().is_none());
     }
 
+    #[test]
+    fn test_ssh_conn_registry() {
+        let registry = SSHConnRegistry::new();
+        let conn = Arc::new(SSHConn::from_connection_string("user@example.com:2222").unwrap());
+        assert!(registry.register(conn).is_none());
+
+        assert!(registry.get("user@example.com:2222").is_some());
+        assert!(registry.get("ssh://user@example.com:2222").is_some());
+        assert!(registry.get("other@example.com").is_none());
+        assert_eq!(registry.connections(), vec!["user@example.com:2222"]);
+
+        assert!(registry.remove("ssh://user@example.com:2222").is_some());
+        assert!(registry.connections().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ssh_conn_lease_release_and_idle_teardown() {
+        let registry =
+            Arc::new(SSHConnRegistry::new().with_idle_timeout(Duration::from_millis(10)));
+        let conn = Arc::new(SSHConn::from_connection_string("user@example.com").unwrap());
+        let key = conn.opts().to_connection_string();
+        registry
+            .conns
+            .write()
+            .insert(key.clone(), SharedConn::new(conn.clone(), 2));
+        let lease = || SSHConnLease {
+            registry: registry.clone(),
+            key: key.clone(),
+            conn: conn.clone(),
+        };
+        let (first, second) = (lease(), lease());
+
+        drop(first);
+        assert_eq!(registry.ref_count("user@example.com"), 1);
+        drop(second);
+        assert_eq!(registry.ref_count("user@example.com"), 0);
+        // 空闲期内仍可查找
+        assert!(registry.get("user@example.com").is_some());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(registry.get("user@example.com").is_none());
+    }
+
+    #[test]
+    fn test_ssh_conn_lease_of_replaced_conn() {
+        let registry = Arc::new(SSHConnRegistry::new());
+        let old = Arc::new(SSHConn::from_connection_string("user@example.com").unwrap());
+        let stale = SSHConnLease {
+            registry: registry.clone(),
+            key: "user@example.com".to_string(),
+            conn: old,
+        };
+
+        let conn = Arc::new(SSHConn::from_connection_string("user@example.com").unwrap());
+        registry
+            .conns
+            .write()
+            .insert("user@example.com".to_string(), SharedConn::new(conn, 1));
+
+        // 旧连接的租约释放不影响新连接的引用计数
+        drop(stale);
+        assert_eq!(registry.ref_count("user@example.com"), 1);
+    }
+
+    #[test]
+    fn test_ssh_conn_forwards_before_connect() {
+        use super::super::ssh_forward::{ForwardKind, ForwardState};
+
+        let conn = SSHConn::from_connection_string("user@example.com").unwrap();
+        let spec = ForwardSpec::parse(ForwardKind::Dynamic, "0").unwrap();
+        let status = conn.add_forward(spec).unwrap();
+        // 未连接时只登记，认证成功后启动
+        assert_eq!(status.state, ForwardState::Stopped);
+        assert_eq!(conn.list_forwards().len(), 1);
+
+        conn.remove_forward(&status.id).unwrap();
+        assert!(conn.list_forwards().is_empty());
+    }
+
     #[test]
     fn test_ssh_conn_derive_status() {
         let opts = SSHOpts::parse("user@example.com").unwrap();
@@ -2510,6 +3527,86 @@ Host multi
         assert_eq!(identity_files.len(), 2);
     }
 
+    #[test]
+    fn test_parse_ssh_config_with_certificate_file() {
+        let config = r#"
+Host cert
+    HostName cert.example.com
+    IdentityFile ~/.ssh/id_ed25519
+    CertificateFile ~/.ssh/id_ed25519-cert.pub
+"#;
+        let hosts = SSHConfigParser::parse_config_content(config).unwrap();
+
+        let cert = hosts.get("cert").unwrap();
+        let cert_files = cert.certificate_file.as_ref().unwrap();
+        assert_eq!(cert_files.len(), 1);
+        assert!(cert_files[0].ends_with("id_ed25519-cert.pub"));
+    }
+
+    #[test]
+    fn test_identity_file_methods_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("id_ed25519");
+        let cert = dir.path().join("id_ed25519-cert.pub");
+        std::fs::write(&key, "private").unwrap();
+        std::fs::write(&cert, "ssh-ed25519-cert-v01@openssh.com AAAA user").unwrap();
+
+        let expected_cert = SSHAuthMethod::Certificate {
+            cert_path: cert.clone(),
+            key_path: key.clone(),
+            passphrase: None,
+        };
+        let expected_key = SSHAuthMethod::PublicKey {
+            key_path: key.clone(),
+            passphrase: None,
+        };
+        // 私钥旁有证书时先尝试证书，再回退到普通公钥
+        assert_eq!(
+            identity_file_methods(&key),
+            vec![expected_cert.clone(), expected_key]
+        );
+        // IdentityFile 直接指向证书
+        assert_eq!(identity_file_methods(&cert), vec![expected_cert]);
+
+        // 证书缺少私钥时忽略
+        std::fs::remove_file(&key).unwrap();
+        assert!(identity_file_methods(&cert).is_empty());
+    }
+
+    #[test]
+    fn test_identity_file_methods_security_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("id_ed25519_sk");
+        let public = dir.path().join("id_ed25519_sk.pub");
+        std::fs::write(&key, "handle").unwrap();
+        std::fs::write(&public, "sk-ssh-ed25519@openssh.com AAAA user").unwrap();
+
+        let expected = vec![SSHAuthMethod::SecurityKey {
+            public_key_path: public.clone(),
+        }];
+        assert_eq!(identity_file_methods(&key), expected);
+        assert_eq!(identity_file_methods(&public), expected);
+        assert_eq!(read_public_key(&public).unwrap().1, vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_identity_file_methods_public_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("id_rsa");
+        let public = dir.path().join("id_rsa.pub");
+        std::fs::write(&key, "private").unwrap();
+        std::fs::write(&public, "ssh-rsa AAAA user").unwrap();
+
+        let expected = vec![SSHAuthMethod::PublicKey {
+            key_path: key.clone(),
+            passphrase: None,
+        }];
+        assert_eq!(identity_file_methods(&key), expected);
+        // IdentityFile 指向 .pub 时使用对应的私钥
+        assert_eq!(identity_file_methods(&public), expected);
+        assert!(identity_file_methods(&dir.path().join("missing")).is_empty());
+    }
+
     #[test]
     fn test_parse_ssh_config_with_batch_mode() {
         let config = r#"
@@ -2725,6 +3822,8 @@ Host example
     ServerAliveCountMax 3
     Compression yes
     ForwardAgent yes
+    ForwardX11 yes
+    ForwardX11Trusted no
 "#;
         let hosts = SSHConfigParser::parse_config_content(config).unwrap();
 
@@ -2734,6 +3833,8 @@ Host example
         assert_eq!(example.server_alive_count_max, Some(3));
         assert_eq!(example.compression, Some(true));
         assert_eq!(example.forward_agent, Some(true));
+        assert_eq!(example.forward_x11, Some(true));
+        assert_eq!(example.forward_x11_trusted, Some(false));
     }
 
     #[test]
@@ -2942,6 +4043,74 @@ Host *
         assert_eq!(opts.ssh_port, Some(2222));
     }
 
+    #[test]
+    fn test_resolve_jump_host_opts() {
+        let config = ConnKeywords {
+            host: Some("10.0.0.1".to_string()),
+            user: Some("admin".to_string()),
+            port: Some(2200),
+            ..Default::default()
+        };
+
+        // 别名解析为 HostName，用户和端口取自配置
+        let opts = SSHConfigParser::parse_proxy_jump_host("bastion").unwrap();
+        let resolved = SSHConfigParser::resolve_jump_host_opts(&opts, &config);
+        assert_eq!(resolved.ssh_host, "10.0.0.1");
+        assert_eq!(resolved.ssh_user, Some("admin".to_string()));
+        assert_eq!(resolved.ssh_port, Some(2200));
+
+        // ProxyJump 中显式写出的用户和端口优先
+        let opts = SSHConfigParser::parse_proxy_jump_host("root@bastion:22").unwrap();
+        let resolved = SSHConfigParser::resolve_jump_host_opts(&opts, &config);
+        assert_eq!(resolved.ssh_user, Some("root".to_string()));
+        assert_eq!(resolved.ssh_port, Some(22));
+    }
+
+    #[test]
+    fn test_conn_status_jump_hosts_default() {
+        let conn = SSHConn::new(SSHOpts::new("example.com"));
+        assert!(conn.derive_conn_status().jump_hosts.is_empty());
+
+        // 旧版本序列化的状态没有 jump_hosts 字段
+        let status: ConnStatus = serde_json::from_str(
+            r#"{"status":"init","connected":false,"connection":"example.com",
+                "has_connected":false,"active_conn_num":0,"error":null,
+                "wsh_enabled":false,"wsh_error":null,"no_wsh_reason":null,
+                "wsh_version":null}"#,
+        )
+        .unwrap();
+        assert!(status.jump_hosts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ssh_conn_rejects_forward_agent() {
+        let conn = SSHConn::new(SSHOpts::new("example.com"));
+        let keywords = ConnKeywords {
+            forward_agent: Some(true),
+            ..Default::default()
+        };
+
+        let err = conn.connect(&keywords).await.unwrap_err();
+        assert!(matches!(err, TerminalError::SSHConnectionFailed(_)));
+        assert!(err.to_string().contains("ForwardAgent"));
+        assert_eq!(conn.state(), ConnectionState::Error);
+        assert!(conn.derive_conn_status().error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_ssh_conn_close_without_reconnect() {
+        let conn = Arc::new(SSHConn::new(SSHOpts::new("example.com")));
+        conn.enable_auto_reconnect();
+        assert!(!conn.is_reconnecting());
+        assert_eq!(conn.generation(), 0);
+        assert_eq!(conn.derive_conn_status().reconnect_attempt, 0);
+
+        // 主动断开不触发自动重连
+        conn.close().await.unwrap();
+        assert!(!conn.is_reconnecting());
+        assert_eq!(conn.state(), ConnectionState::Disconnected);
+    }
+
     // ========================================================================
     // 路径展开测试
     // ========================================================================
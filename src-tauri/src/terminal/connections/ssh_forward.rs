@@ -0,0 +1,1129 @@
+//! SSH 端口转发模块
+//!
+//! 在 SSHConn 上实现 `-L` / `-R` / `-D` 隧道，生命周期与 SSH 连接绑定。
+//!
+//! ## 功能
+//! - 解析 LocalForward / RemoteForward / DynamicForward（ssh_config 与 `-L` 命令行两种写法）
+//! - 本地转发：本地监听，经 direct-tcpip 通道连接远程目标
+//! - 远程转发：远程监听（tcpip-forward），接入的通道转发到本地目标
+//! - 动态转发：本地 SOCKS5 代理（仅支持无认证 CONNECT）
+//! - 每个隧道独立的状态和流量统计，状态变化通过 `terminal:ssh-forward-change` 广播
+//!
+//! ## 线程模型
+//! 启动转发后会话切换为非阻塞模式（与 SSHShellProc 一致），每个监听器和每条转发
+//! 连接各占一个线程，libssh2 返回 EAGAIN 时短暂休眠后重试。
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use ssh2::{Channel, Session};
+
+use super::ssh_connection::ConnKeywords;
+use crate::terminal::error::TerminalError;
+
+/// libssh2 的 EAGAIN 错误码（非阻塞模式下操作需重试）
+const LIBSSH2_ERROR_EAGAIN: i32 = -37;
+
+/// 非阻塞轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// 转发缓冲区大小
+const BUFFER_SIZE: usize = 32 * 1024;
+
+/// SOCKS 握手和本地目标连接超时
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ============================================================================
+// 转发配置
+// ============================================================================
+
+/// 转发类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardKind {
+    /// 本地转发（-L）
+    Local,
+    /// 远程转发（-R）
+    Remote,
+    /// 动态转发（-D，SOCKS5）
+    Dynamic,
+}
+
+impl ForwardKind {
+    /// 对应的 ssh 命令行参数
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Self::Local => "-L",
+            Self::Remote => "-R",
+            Self::Dynamic => "-D",
+        }
+    }
+}
+
+/// 转发配置
+///
+/// 本地/动态转发的监听地址在本机，远程转发的监听地址在远程服务器。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardSpec {
+    /// 转发类型
+    pub kind: ForwardKind,
+    /// 监听地址（None 表示仅回环地址，`*` 表示所有地址）
+    pub bind_address: Option<String>,
+    /// 监听端口（为 0 时由系统或远程服务器分配，见 `ForwardStatus::bound_port`）
+    pub bind_port: u16,
+    /// 目标主机（动态转发为 None）
+    pub target_host: Option<String>,
+    /// 目标端口（动态转发为 None）
+    pub target_port: Option<u16>,
+}
+
+impl ForwardSpec {
+    /// 解析转发配置
+    ///
+    /// 支持的写法：
+    /// - ssh_config：`8080 localhost:80`、`127.0.0.1:8080 localhost:80`、`1080`
+    /// - 命令行：`8080:localhost:80`、`*:8080:localhost:80`、`[::1]:1080`
+    pub fn parse(kind: ForwardKind, value: &str) -> Result<Self, TerminalError> {
+        let invalid = || {
+            TerminalError::SSHForwardFailed(format!("无效的 {} 转发配置: {}", kind.flag(), value))
+        };
+
+        let tokens: Vec<&str> = value.split_whitespace().collect();
+        let fields: Vec<String> = match tokens.as_slice() {
+            [single] => split_forward_fields(single).ok_or_else(invalid)?,
+            [listen, target] if kind != ForwardKind::Dynamic => {
+                let mut fields = split_forward_fields(listen).ok_or_else(invalid)?;
+                fields.extend(split_forward_fields(target).ok_or_else(invalid)?);
+                fields
+            }
+            _ => return Err(invalid()),
+        };
+
+        let parse_port = |s: &str| s.parse::<u16>().map_err(|_| invalid());
+        let bind = |s: &str| (!s.is_empty()).then(|| s.to_string());
+
+        let (bind_address, bind_port, target) = match (kind, fields.as_slice()) {
+            (ForwardKind::Dynamic, [port]) => (None, parse_port(port)?, None),
+            (ForwardKind::Dynamic, [addr, port]) => (bind(addr), parse_port(port)?, None),
+            (_, [port, host, host_port]) if kind != ForwardKind::Dynamic => (
+                None,
+                parse_port(port)?,
+                Some((host.clone(), parse_port(host_port)?)),
+            ),
+            (_, [addr, port, host, host_port]) if kind != ForwardKind::Dynamic => (
+                bind(addr),
+                parse_port(port)?,
+                Some((host.clone(), parse_port(host_port)?)),
+            ),
+            _ => return Err(invalid()),
+        };
+
+        if let Some((host, port)) = &target {
+            if host.is_empty() || *port == 0 {
+                return Err(invalid());
+            }
+        }
+
+        let (target_host, target_port) = target.unzip();
+        Ok(Self {
+            kind,
+            bind_address,
+            bind_port,
+            target_host,
+            target_port,
+        })
+    }
+
+    /// 从 ssh_config 关键字中收集所有转发配置
+    ///
+    /// 无效的配置项记录警告后跳过。
+    pub fn from_keywords(keywords: &ConnKeywords) -> Vec<Self> {
+        let groups = [
+            (ForwardKind::Local, &keywords.local_forward),
+            (ForwardKind::Remote, &keywords.remote_forward),
+            (ForwardKind::Dynamic, &keywords.dynamic_forward),
+        ];
+
+        let mut specs = Vec::new();
+        for (kind, values) in groups {
+            for value in values.iter().flatten() {
+                match Self::parse(kind, value) {
+                    Ok(spec) => specs.push(spec),
+                    Err(e) => tracing::warn!("[SSHForward] 跳过配置: {}", e),
+                }
+            }
+        }
+        specs
+    }
+
+    /// 本机监听地址（本地/动态转发）
+    fn local_bind_host(&self) -> &str {
+        match self.bind_address.as_deref() {
+            None | Some("localhost") => "127.0.0.1",
+            Some("*") => "0.0.0.0",
+            Some(addr) => addr,
+        }
+    }
+
+    /// 远程监听地址（远程转发）
+    fn remote_bind_host(&self) -> &str {
+        match self.bind_address.as_deref() {
+            None => "localhost",
+            Some("*") => "0.0.0.0",
+            Some(addr) => addr,
+        }
+    }
+}
+
+impl fmt::Display for ForwardSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.kind.flag())?;
+        if let Some(addr) = &self.bind_address {
+            write!(f, "{}:", bracket_ipv6(addr))?;
+        }
+        write!(f, "{}", self.bind_port)?;
+        if let (Some(host), Some(port)) = (&self.target_host, self.target_port) {
+            write!(f, ":{}:{}", bracket_ipv6(host), port)?;
+        }
+        Ok(())
+    }
+}
+
+/// 按冒号拆分转发字段，`[...]` 内的 IPv6 地址作为一个字段
+fn split_forward_fields(s: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if current.is_empty() => {
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    current.push(c);
+                }
+                // IPv6 地址后必须紧跟冒号或结束
+                match chars.next() {
+                    Some(':') => fields.push(std::mem::take(&mut current)),
+                    None => {}
+                    Some(_) => return None,
+                }
+            }
+            ':' => fields.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    Some(fields)
+}
+
+fn bracket_ipv6(host: &str) -> String {
+    if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+// ============================================================================
+// 转发状态
+// ============================================================================
+
+/// 隧道状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardState {
+    /// 正在建立监听
+    Starting,
+    /// 监听中
+    Active,
+    /// 已停止（连接断开或手动移除）
+    Stopped,
+    /// 监听失败
+    Error,
+}
+
+/// 隧道状态详情
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardStatus {
+    /// 隧道 ID
+    pub id: String,
+    /// 转发配置
+    pub spec: ForwardSpec,
+    /// 是否来自 ssh_config（否则为运行时添加）
+    pub from_config: bool,
+    /// 隧道状态
+    pub state: ForwardState,
+    /// 实际监听端口（`bind_port` 为 0 时由系统或远程服务器分配）
+    pub bound_port: Option<u16>,
+    /// 最近一次错误
+    pub error: Option<String>,
+    /// 当前活跃连接数
+    pub active_connections: u32,
+    /// 累计连接数
+    pub total_connections: u64,
+    /// 发往远端的字节数
+    pub bytes_sent: u64,
+    /// 从远端收到的字节数
+    pub bytes_received: u64,
+}
+
+/// 隧道状态变更广播
+struct ForwardEmitter {
+    /// 连接名称
+    connection: String,
+    /// Tauri 应用句柄
+    app_handle: RwLock<Option<tauri::AppHandle>>,
+}
+
+impl ForwardEmitter {
+    fn emit(&self, forward: ForwardStatus) {
+        use crate::terminal::events::{event_names, SSHForwardChangeEvent};
+        use tauri::Emitter;
+
+        if let Some(ref app_handle) = *self.app_handle.read() {
+            let event = SSHForwardChangeEvent {
+                connection: self.connection.clone(),
+                forward,
+            };
+            if let Err(e) = app_handle.emit(event_names::SSH_FORWARD_CHANGE, event) {
+                tracing::warn!("[SSHForward] 广播转发状态变更事件失败: {}", e);
+            }
+        }
+    }
+}
+
+/// 单个隧道（配置 + 运行状态）
+struct Tunnel {
+    id: String,
+    spec: ForwardSpec,
+    from_config: bool,
+    state: RwLock<ForwardState>,
+    error: RwLock<Option<String>>,
+    bound_port: RwLock<Option<u16>>,
+    active_connections: AtomicU32,
+    total_connections: AtomicU64,
+    traffic: Traffic,
+    /// 当前运行的停止标志（每次启动替换）
+    stop: RwLock<Arc<AtomicBool>>,
+    emitter: Arc<ForwardEmitter>,
+}
+
+impl Tunnel {
+    fn new(id: String, spec: ForwardSpec, from_config: bool, emitter: Arc<ForwardEmitter>) -> Self {
+        Self {
+            id,
+            spec,
+            from_config,
+            state: RwLock::new(ForwardState::Stopped),
+            error: RwLock::new(None),
+            bound_port: RwLock::new(None),
+            active_connections: AtomicU32::new(0),
+            total_connections: AtomicU64::new(0),
+            traffic: Traffic::default(),
+            stop: RwLock::new(Arc::new(AtomicBool::new(true))),
+            emitter,
+        }
+    }
+
+    fn status(&self) -> ForwardStatus {
+        ForwardStatus {
+            id: self.id.clone(),
+            spec: self.spec.clone(),
+            from_config: self.from_config,
+            state: *self.state.read(),
+            bound_port: *self.bound_port.read(),
+            error: self.error.read().clone(),
+            active_connections: self.active_connections.load(Ordering::SeqCst),
+            total_connections: self.total_connections.load(Ordering::SeqCst),
+            bytes_sent: self.traffic.sent.load(Ordering::SeqCst),
+            bytes_received: self.traffic.received.load(Ordering::SeqCst),
+        }
+    }
+
+    fn set_state(&self, state: ForwardState, error: Option<String>) {
+        *self.state.write() = state;
+        if error.is_some() || state == ForwardState::Active {
+            *self.error.write() = error;
+        }
+        self.emitter.emit(self.status());
+    }
+
+    /// 记录单条转发连接的错误（不影响监听状态）
+    fn record_error(&self, error: String) {
+        tracing::warn!("[SSHForward] {} {}", self.spec, error);
+        *self.error.write() = Some(error);
+    }
+
+    fn is_running(&self) -> bool {
+        !self.stop.read().load(Ordering::SeqCst)
+    }
+
+    /// 启动隧道
+    ///
+    /// 本地/动态转发同步绑定端口，绑定失败直接返回错误；远程转发在线程中请求远程监听。
+    fn start(self: &Arc<Self>, session: &Session) -> Result<(), TerminalError> {
+        if self.is_running() {
+            return Ok(());
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        *self.stop.write() = stop.clone();
+        *self.bound_port.write() = None;
+        self.set_state(ForwardState::Starting, None);
+
+        match self.spec.kind {
+            ForwardKind::Local | ForwardKind::Dynamic => {
+                let addr = (self.spec.local_bind_host(), self.spec.bind_port);
+                let listener = TcpListener::bind(addr)
+                    .and_then(|listener| {
+                        listener.set_nonblocking(true)?;
+                        Ok(listener)
+                    })
+                    .map_err(|e| {
+                        let error = format!("监听 {}:{} 失败: {}", addr.0, addr.1, e);
+                        stop.store(true, Ordering::SeqCst);
+                        self.set_state(ForwardState::Error, Some(error.clone()));
+                        TerminalError::SSHForwardFailed(error)
+                    })?;
+                *self.bound_port.write() = listener.local_addr().ok().map(|a| a.port());
+                self.set_state(ForwardState::Active, None);
+                tracing::info!("[SSHForward] 隧道已启动: {}", self.spec);
+
+                let tunnel = self.clone();
+                let session = session.clone();
+                thread::spawn(move || tunnel.run_local_listener(listener, session, stop));
+            }
+            ForwardKind::Remote => {
+                let tunnel = self.clone();
+                let session = session.clone();
+                thread::spawn(move || tunnel.run_remote_listener(session, stop));
+            }
+        }
+        Ok(())
+    }
+
+    /// 停止隧道（已建立的转发连接随之关闭）
+    fn stop(&self) {
+        let was_running = !self.stop.read().swap(true, Ordering::SeqCst);
+        if was_running {
+            tracing::info!("[SSHForward] 隧道已停止: {}", self.spec);
+            self.set_state(ForwardState::Stopped, None);
+        }
+    }
+
+    /// 本地/动态转发监听循环
+    fn run_local_listener(
+        self: Arc<Self>,
+        listener: TcpListener,
+        session: Session,
+        stop: Arc<AtomicBool>,
+    ) {
+        while !stop.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    let tunnel = self.clone();
+                    let session = session.clone();
+                    let stop = stop.clone();
+                    thread::spawn(move || {
+                        tunnel.track_connection(|| {
+                            tunnel.handle_local_connection(stream, peer, &session, &stop)
+                        })
+                    });
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(POLL_INTERVAL * 4);
+                }
+                Err(e) => {
+                    self.record_error(format!("接受连接失败: {}", e));
+                    thread::sleep(POLL_INTERVAL * 4);
+                }
+            }
+        }
+    }
+
+    /// 处理本地/动态转发的一条连接
+    fn handle_local_connection(
+        &self,
+        mut stream: TcpStream,
+        peer: std::net::SocketAddr,
+        session: &Session,
+        stop: &AtomicBool,
+    ) -> Result<(), String> {
+        let (host, port) = match self.spec.kind {
+            ForwardKind::Dynamic => {
+                stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+                stream
+                    .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+                    .map_err(|e| e.to_string())?;
+                socks5_handshake(&mut stream).map_err(|e| format!("SOCKS5 握手失败: {}", e))?
+            }
+            _ => (
+                self.spec.target_host.clone().unwrap_or_default(),
+                self.spec.target_port.unwrap_or_default(),
+            ),
+        };
+
+        let src = peer.ip().to_string();
+        let channel = retry_would_block(|| {
+            session.channel_direct_tcpip(&host, port, Some((src.as_str(), peer.port())))
+        });
+        let channel = match channel {
+            Ok(channel) => channel,
+            Err(e) => {
+                if self.spec.kind == ForwardKind::Dynamic {
+                    let _ = socks5_reply(&mut stream, SOCKS5_REPLY_REFUSED);
+                }
+                return Err(format!("打开到 {}:{} 的通道失败: {}", host, port, e));
+            }
+        };
+        if self.spec.kind == ForwardKind::Dynamic {
+            socks5_reply(&mut stream, SOCKS5_REPLY_SUCCEEDED).map_err(|e| e.to_string())?;
+        }
+
+        tracing::debug!(
+            "[SSHForward] {} 新连接 {} → {}:{}",
+            self.spec,
+            peer,
+            host,
+            port
+        );
+        pump(stream, channel, stop, &self.traffic)
+    }
+
+    /// 远程转发监听循环
+    fn run_remote_listener(self: Arc<Self>, session: Session, stop: Arc<AtomicBool>) {
+        let host = self.spec.remote_bind_host().to_string();
+        let result = retry_would_block(|| {
+            session.channel_forward_listen(self.spec.bind_port, Some(&host), None)
+        });
+        let mut listener = match result {
+            Ok((listener, bound_port)) => {
+                *self.bound_port.write() = Some(bound_port);
+                self.set_state(ForwardState::Active, None);
+                tracing::info!("[SSHForward] 隧道已启动: {}", self.spec);
+                listener
+            }
+            Err(e) => {
+                stop.store(true, Ordering::SeqCst);
+                self.set_state(
+                    ForwardState::Error,
+                    Some(format!(
+                        "请求远程监听 {}:{} 失败: {}",
+                        host, self.spec.bind_port, e
+                    )),
+                );
+                return;
+            }
+        };
+
+        while !stop.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok(channel) => {
+                    let tunnel = self.clone();
+                    let stop = stop.clone();
+                    thread::spawn(move || {
+                        tunnel.track_connection(|| tunnel.handle_remote_connection(channel, &stop))
+                    });
+                }
+                Err(ref e) if is_would_block(e) => thread::sleep(POLL_INTERVAL * 4),
+                Err(e) => {
+                    // 会话已断开，等待重新认证后由 start_all 重新启动；已手动停止时不再报告错误
+                    if stop.swap(true, Ordering::SeqCst) {
+                        return;
+                    }
+                    self.set_state(ForwardState::Error, Some(format!("远程监听中断: {}", e)));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 处理远程转发的一条连接
+    fn handle_remote_connection(
+        &self,
+        mut channel: Channel,
+        stop: &AtomicBool,
+    ) -> Result<(), String> {
+        let host = self.spec.target_host.clone().unwrap_or_default();
+        let port = self.spec.target_port.unwrap_or_default();
+
+        let stream = (host.as_str(), port)
+            .to_socket_addrs()
+            .map_err(|e| e.to_string())
+            .and_then(|mut addrs| addrs.next().ok_or_else(|| format!("无法解析 {}", host)))
+            .and_then(|addr| {
+                TcpStream::connect_timeout(&addr, HANDSHAKE_TIMEOUT).map_err(|e| e.to_string())
+            });
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = retry_would_block(|| channel.close());
+                return Err(format!("连接本地目标 {}:{} 失败: {}", host, port, e));
+            }
+        };
+
+        tracing::debug!("[SSHForward] {} 新连接 → {}:{}", self.spec, host, port);
+        pump(stream, channel, stop, &self.traffic)
+    }
+
+    /// 统计活跃连接数并记录连接错误
+    fn track_connection(&self, handle: impl FnOnce() -> Result<(), String>) {
+        self.active_connections.fetch_add(1, Ordering::SeqCst);
+        self.total_connections.fetch_add(1, Ordering::SeqCst);
+        self.emitter.emit(self.status());
+
+        if let Err(e) = handle() {
+            self.record_error(e);
+        }
+
+        self.active_connections.fetch_sub(1, Ordering::SeqCst);
+        self.emitter.emit(self.status());
+    }
+}
+
+// ============================================================================
+// 数据转发
+// ============================================================================
+
+/// 转发流量计数
+#[derive(Default)]
+pub(crate) struct Traffic {
+    /// 发往远端的字节数
+    pub sent: AtomicU64,
+    /// 从远端收到的字节数
+    pub received: AtomicU64,
+}
+
+/// 可以与 SSH 通道对接的本地流（TCP 连接或 Unix socket）
+pub(crate) trait LocalStream: Read + Write {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+    fn shutdown(&self, how: Shutdown) -> io::Result<()>;
+}
+
+impl LocalStream for TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        TcpStream::shutdown(self, how)
+    }
+}
+
+#[cfg(unix)]
+impl LocalStream for std::os::unix::net::UnixStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        std::os::unix::net::UnixStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        std::os::unix::net::UnixStream::shutdown(self, how)
+    }
+}
+
+/// 在本地流和 SSH 通道之间双向转发数据，直到两端都关闭
+pub(crate) fn pump<S: LocalStream>(
+    mut stream: S,
+    mut channel: Channel,
+    stop: &AtomicBool,
+    traffic: &Traffic,
+) -> Result<(), String> {
+    stream.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+    let mut buf = vec![0u8; BUFFER_SIZE];
+    // 待写入通道 / 待写入 TCP 的数据
+    let mut to_channel: Vec<u8> = Vec::new();
+    let mut to_stream: Vec<u8> = Vec::new();
+    let mut stream_eof = false;
+    let mut channel_eof = false;
+    let mut eof_sent = false;
+    let mut stream_shutdown = false;
+
+    let result = loop {
+        if stop.load(Ordering::SeqCst) {
+            break Ok(());
+        }
+        let mut progressed = false;
+
+        // TCP → 通道
+        if !stream_eof && to_channel.is_empty() {
+            match stream.read(&mut buf) {
+                Ok(0) => {
+                    stream_eof = true;
+                    progressed = true;
+                }
+                Ok(n) => {
+                    to_channel.extend_from_slice(&buf[..n]);
+                    progressed = true;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => break Err(e.to_string()),
+            }
+        }
+        if !to_channel.is_empty() {
+            match channel.write(&to_channel) {
+                Ok(n) => {
+                    to_channel.drain(..n);
+                    traffic.sent.fetch_add(n as u64, Ordering::Relaxed);
+                    progressed = true;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => break Err(e.to_string()),
+            }
+        }
+        if stream_eof && to_channel.is_empty() && !eof_sent {
+            match channel.send_eof() {
+                Ok(()) => eof_sent = true,
+                Err(ref e) if is_would_block(e) => {}
+                Err(e) => break Err(e.to_string()),
+            }
+        }
+
+        // 通道 → TCP
+        if !channel_eof && to_stream.is_empty() {
+            match channel.read(&mut buf) {
+                Ok(0) => {
+                    channel_eof = channel.eof();
+                }
+                Ok(n) => {
+                    to_stream.extend_from_slice(&buf[..n]);
+                    progressed = true;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    channel_eof = channel.eof();
+                }
+                Err(e) => break Err(e.to_string()),
+            }
+        }
+        if !to_stream.is_empty() {
+            match stream.write(&to_stream) {
+                Ok(n) => {
+                    to_stream.drain(..n);
+                    traffic.received.fetch_add(n as u64, Ordering::Relaxed);
+                    progressed = true;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => break Err(e.to_string()),
+            }
+        }
+        if channel_eof && to_stream.is_empty() && !stream_shutdown {
+            let _ = stream.shutdown(Shutdown::Write);
+            stream_shutdown = true;
+        }
+
+        if eof_sent && stream_shutdown {
+            break Ok(());
+        }
+        if !progressed {
+            thread::sleep(POLL_INTERVAL);
+        }
+    };
+
+    let _ = stream.shutdown(Shutdown::Both);
+    let _ = retry_would_block(|| channel.close());
+    result
+}
+
+/// 通过 direct-tcpip 通道连接到 `host:port`，返回一个桥接到该通道的本地 TCP 流
+///
+/// libssh2 只能在真实的 socket 上握手，因此 ProxyJump 需要借助回环连接：
+/// 后台线程把回环连接的一端与跳板机上的通道对接，另一端交给下一跳的会话使用。
+/// `stop` 置位后桥接线程退出。
+pub(crate) fn bridge_direct_tcpip(
+    session: &Session,
+    host: &str,
+    port: u16,
+    stop: Arc<AtomicBool>,
+) -> Result<TcpStream, String> {
+    let listener =
+        TcpListener::bind(("127.0.0.1", 0)).map_err(|e| format!("创建本地桥接失败: {}", e))?;
+    let addr = listener.local_addr().map_err(|e| e.to_string())?;
+    let client = TcpStream::connect(addr).map_err(|e| format!("连接本地桥接失败: {}", e))?;
+    let (server, peer) = listener
+        .accept()
+        .map_err(|e| format!("接受本地桥接失败: {}", e))?;
+    // 回环端口可能被其他本地进程抢先连接，只接受自己发起的连接
+    if client.local_addr().ok() != Some(peer) {
+        return Err("本地桥接被其他连接占用".to_string());
+    }
+    drop(listener);
+
+    session.set_blocking(false);
+    let channel = retry_would_block(|| session.channel_direct_tcpip(host, port, None))
+        .map_err(|e| format!("打开到 {}:{} 的通道失败: {}", host, port, e))?;
+
+    let target = format!("{}:{}", host, port);
+    thread::Builder::new()
+        .name(format!("ssh-jump-{}", target))
+        .spawn(move || {
+            if let Err(e) = pump(server, channel, &stop, &Traffic::default()) {
+                tracing::debug!("[SSHForward] 跳板通道 {} 结束: {}", target, e);
+            }
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(client)
+}
+
+// ============================================================================
+// 转发管理器
+// ============================================================================
+
+/// SSH 连接的端口转发管理器
+///
+/// 由 SSHConn 持有：连接认证成功后启动所有隧道，断开时停止。隧道配置在断开后保留，
+/// 重新连接时自动恢复；运行时添加的隧道与 ssh_config 中的隧道同样处理。
+pub struct PortForwarder {
+    emitter: Arc<ForwardEmitter>,
+    tunnels: RwLock<Vec<Arc<Tunnel>>>,
+    next_id: AtomicU64,
+}
+
+impl PortForwarder {
+    /// 创建转发管理器
+    pub fn new(connection: impl Into<String>) -> Self {
+        Self {
+            emitter: Arc::new(ForwardEmitter {
+                connection: connection.into(),
+                app_handle: RwLock::new(None),
+            }),
+            tunnels: RwLock::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 设置 Tauri 应用句柄（用于广播隧道状态）
+    pub fn set_app_handle(&self, app_handle: tauri::AppHandle) {
+        *self.emitter.app_handle.write() = Some(app_handle);
+    }
+
+    /// 替换来自 ssh_config 的隧道配置
+    ///
+    /// 旧的配置隧道会先停止；运行时添加的隧道保持不变。
+    pub fn set_config_forwards(&self, keywords: &ConnKeywords) {
+        let specs = ForwardSpec::from_keywords(keywords);
+        let mut tunnels = self.tunnels.write();
+        tunnels.retain(|tunnel| {
+            if tunnel.from_config {
+                tunnel.stop();
+            }
+            !tunnel.from_config
+        });
+        for spec in specs {
+            tunnels.push(self.new_tunnel(spec, true));
+        }
+    }
+
+    /// 添加隧道
+    ///
+    /// 提供 `session` 时立即启动。
+    pub fn add(
+        &self,
+        spec: ForwardSpec,
+        session: Option<&Session>,
+    ) -> Result<ForwardStatus, TerminalError> {
+        if let Some(existing) = self
+            .tunnels
+            .read()
+            .iter()
+            .find(|tunnel| tunnel.spec == spec)
+        {
+            return Err(TerminalError::SSHForwardFailed(format!(
+                "转发已存在: {} ({})",
+                spec, existing.id
+            )));
+        }
+
+        let tunnel = self.new_tunnel(spec, false);
+        if let Some(session) = session {
+            session.set_blocking(false);
+            tunnel.start(session)?;
+        }
+        self.tunnels.write().push(tunnel.clone());
+        Ok(tunnel.status())
+    }
+
+    /// 移除隧道（停止监听并关闭已建立的转发连接）
+    pub fn remove(&self, id: &str) -> Result<(), TerminalError> {
+        let mut tunnels = self.tunnels.write();
+        let index = tunnels
+            .iter()
+            .position(|tunnel| tunnel.id == id)
+            .ok_or_else(|| TerminalError::SSHForwardFailed(format!("转发不存在: {}", id)))?;
+        let tunnel = tunnels.remove(index);
+        tunnel.stop();
+        Ok(())
+    }
+
+    /// 启动所有隧道
+    ///
+    /// 单个隧道启动失败不影响其他隧道，错误记录在该隧道的状态中。
+    pub fn start_all(&self, session: &Session) {
+        let tunnels = self.tunnels.read().clone();
+        if tunnels.is_empty() {
+            return;
+        }
+
+        session.set_blocking(false);
+        for tunnel in tunnels {
+            if let Err(e) = tunnel.start(session) {
+                tracing::warn!("[SSHForward] {}", e);
+            }
+        }
+    }
+
+    /// 停止所有隧道（保留配置）
+    pub fn stop_all(&self) {
+        for tunnel in self.tunnels.read().iter() {
+            tunnel.stop();
+        }
+    }
+
+    /// 获取所有隧道状态
+    pub fn list(&self) -> Vec<ForwardStatus> {
+        self.tunnels
+            .read()
+            .iter()
+            .map(|tunnel| tunnel.status())
+            .collect()
+    }
+
+    fn new_tunnel(&self, spec: ForwardSpec, from_config: bool) -> Arc<Tunnel> {
+        let id = format!("fwd-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        Arc::new(Tunnel::new(id, spec, from_config, self.emitter.clone()))
+    }
+}
+
+impl Drop for PortForwarder {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+// ============================================================================
+// libssh2 非阻塞辅助
+// ============================================================================
+
+/// 是否为非阻塞模式下的 EAGAIN 错误
+pub(crate) fn is_would_block(e: &ssh2::Error) -> bool {
+    matches!(e.code(), ssh2::ErrorCode::Session(LIBSSH2_ERROR_EAGAIN))
+}
+
+/// 非阻塞模式下重试 libssh2 操作直到完成
+///
+/// 会话可能已被端口转发切换为非阻塞模式，建立通道等一次性操作需要通过它执行。
+pub(crate) fn retry_would_block<T>(
+    mut op: impl FnMut() -> Result<T, ssh2::Error>,
+) -> Result<T, ssh2::Error> {
+    loop {
+        match op() {
+            Err(ref e) if is_would_block(e) => thread::sleep(POLL_INTERVAL),
+            result => return result,
+        }
+    }
+}
+
+// ============================================================================
+// SOCKS5
+// ============================================================================
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_NO_AUTH: u8 = 0x00;
+const SOCKS5_NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const SOCKS5_CMD_CONNECT: u8 = 0x01;
+const SOCKS5_REPLY_SUCCEEDED: u8 = 0x00;
+const SOCKS5_REPLY_REFUSED: u8 = 0x05;
+const SOCKS5_REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const SOCKS5_REPLY_ADDRESS_NOT_SUPPORTED: u8 = 0x08;
+
+/// SOCKS5 握手（仅无认证 CONNECT），返回目标地址
+///
+/// 握手成功后调用方需在通道建立后发送 `socks5_reply`。
+fn socks5_handshake(stream: &mut TcpStream) -> io::Result<(String, u16)> {
+    let protocol_error = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+    // 方法协商
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header)?;
+    if header[0] != SOCKS5_VERSION {
+        return Err(protocol_error("仅支持 SOCKS5"));
+    }
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods)?;
+    if !methods.contains(&SOCKS5_NO_AUTH) {
+        stream.write_all(&[SOCKS5_VERSION, SOCKS5_NO_ACCEPTABLE_METHOD])?;
+        return Err(protocol_error("客户端不支持无认证方式"));
+    }
+    stream.write_all(&[SOCKS5_VERSION, SOCKS5_NO_AUTH])?;
+
+    // 请求
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request)?;
+    if request[0] != SOCKS5_VERSION {
+        return Err(protocol_error("无效的 SOCKS5 请求"));
+    }
+    if request[1] != SOCKS5_CMD_CONNECT {
+        socks5_reply(stream, SOCKS5_REPLY_COMMAND_NOT_SUPPORTED)?;
+        return Err(protocol_error("仅支持 CONNECT 命令"));
+    }
+
+    let host = match request[3] {
+        0x01 => {
+            let mut addr = [0u8; 4];
+            stream.read_exact(&mut addr)?;
+            std::net::Ipv4Addr::from(addr).to_string()
+        }
+        0x03 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len)?;
+            let mut domain = vec![0u8; len[0] as usize];
+            stream.read_exact(&mut domain)?;
+            String::from_utf8(domain).map_err(|_| protocol_error("无效的域名"))?
+        }
+        0x04 => {
+            let mut addr = [0u8; 16];
+            stream.read_exact(&mut addr)?;
+            std::net::Ipv6Addr::from(addr).to_string()
+        }
+        _ => {
+            socks5_reply(stream, SOCKS5_REPLY_ADDRESS_NOT_SUPPORTED)?;
+            return Err(protocol_error("不支持的地址类型"));
+        }
+    };
+
+    let mut port = [0u8; 2];
+    stream.read_exact(&mut port)?;
+    Ok((host, u16::from_be_bytes(port)))
+}
+
+/// 发送 SOCKS5 应答（绑定地址固定为 0.0.0.0:0）
+fn socks5_reply(stream: &mut TcpStream, reply: u8) -> io::Result<()> {
+    stream.write_all(&[SOCKS5_VERSION, reply, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_forward_specs() {
+        let spec = ForwardSpec::parse(ForwardKind::Local, "8080 localhost:80").unwrap();
+        assert_eq!(spec.bind_address, None);
+        assert_eq!(spec.bind_port, 8080);
+        assert_eq!(spec.target_host.as_deref(), Some("localhost"));
+        assert_eq!(spec.target_port, Some(80));
+
+        let spec = ForwardSpec::parse(ForwardKind::Local, "*:8080:db.internal:5432").unwrap();
+        assert_eq!(spec.bind_address.as_deref(), Some("*"));
+        assert_eq!(spec.local_bind_host(), "0.0.0.0");
+        assert_eq!(spec.to_string(), "-L *:8080:db.internal:5432");
+
+        let spec = ForwardSpec::parse(ForwardKind::Remote, "[::1]:9000 [fe80::1]:22").unwrap();
+        assert_eq!(spec.bind_address.as_deref(), Some("::1"));
+        assert_eq!(spec.target_host.as_deref(), Some("fe80::1"));
+        assert_eq!(spec.to_string(), "-R [::1]:9000:[fe80::1]:22");
+
+        let spec = ForwardSpec::parse(ForwardKind::Dynamic, "1080").unwrap();
+        assert_eq!(spec.bind_port, 1080);
+        assert_eq!(spec.target_host, None);
+        assert_eq!(spec.to_string(), "-D 1080");
+
+        let spec = ForwardSpec::parse(ForwardKind::Dynamic, "127.0.0.1:1080").unwrap();
+        assert_eq!(spec.bind_address.as_deref(), Some("127.0.0.1"));
+    }
+
+    #[test]
+    fn test_parse_forward_specs_invalid() {
+        assert!(ForwardSpec::parse(ForwardKind::Local, "").is_err());
+        assert!(ForwardSpec::parse(ForwardKind::Local, "8080").is_err());
+        assert!(ForwardSpec::parse(ForwardKind::Local, "8080 localhost").is_err());
+        assert!(ForwardSpec::parse(ForwardKind::Local, "99999 localhost:80").is_err());
+        assert!(ForwardSpec::parse(ForwardKind::Remote, "9000 localhost:0").is_err());
+        assert!(ForwardSpec::parse(ForwardKind::Dynamic, "1080 localhost:80").is_err());
+        assert!(ForwardSpec::parse(ForwardKind::Local, "[::1]x:8080:host:80").is_err());
+    }
+
+    #[test]
+    fn test_from_keywords_skips_invalid() {
+        let keywords = ConnKeywords {
+            local_forward: Some(vec!["8080 localhost:80".to_string(), "bad".to_string()]),
+            remote_forward: Some(vec!["9000 localhost:9000".to_string()]),
+            dynamic_forward: Some(vec!["1080".to_string()]),
+            ..Default::default()
+        };
+        let specs = ForwardSpec::from_keywords(&keywords);
+        let kinds: Vec<ForwardKind> = specs.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ForwardKind::Local,
+                ForwardKind::Remote,
+                ForwardKind::Dynamic
+            ]
+        );
+    }
+
+    #[test]
+    fn test_forwarder_add_remove_without_session() {
+        let forwarder = PortForwarder::new("user@host");
+        let spec = ForwardSpec::parse(ForwardKind::Local, "0:localhost:80").unwrap();
+
+        let status = forwarder.add(spec.clone(), None).unwrap();
+        assert_eq!(status.state, ForwardState::Stopped);
+        assert!(!status.from_config);
+        assert!(forwarder.add(spec, None).is_err());
+
+        let keywords = ConnKeywords {
+            dynamic_forward: Some(vec!["1080".to_string()]),
+            ..Default::default()
+        };
+        forwarder.set_config_forwards(&keywords);
+        forwarder.set_config_forwards(&keywords);
+        assert_eq!(forwarder.list().len(), 2);
+
+        forwarder.remove(&status.id).unwrap();
+        assert_eq!(forwarder.list().len(), 1);
+        assert!(forwarder.list()[0].from_config);
+        assert!(forwarder.remove(&status.id).is_err());
+    }
+
+    #[test]
+    fn test_socks5_handshake() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(&[0x05, 0x01, 0x00]).unwrap();
+            let mut method = [0u8; 2];
+            stream.read_exact(&mut method).unwrap();
+            assert_eq!(method, [0x05, 0x00]);
+
+            let mut request = vec![0x05, 0x01, 0x00, 0x03, 11];
+            request.extend_from_slice(b"example.com");
+            request.extend_from_slice(&443u16.to_be_bytes());
+            stream.write_all(&request).unwrap();
+
+            let mut reply = [0u8; 10];
+            stream.read_exact(&mut reply).unwrap();
+            reply[1]
+        });
+
+        let (mut stream, _) = listener.accept().unwrap();
+        let target = socks5_handshake(&mut stream).unwrap();
+        assert_eq!(target, ("example.com".to_string(), 443));
+        socks5_reply(&mut stream, SOCKS5_REPLY_SUCCEEDED).unwrap();
+
+        assert_eq!(client.join().unwrap(), SOCKS5_REPLY_SUCCEEDED);
+    }
+}
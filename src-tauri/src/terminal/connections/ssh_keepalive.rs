@@ -0,0 +1,179 @@
+//! SSH 保活与断线重连
+//!
+//! 按 ServerAliveInterval / ServerAliveCountMax 定期发送 keepalive@openssh.com 探测，
+//! 连续失败达到上限时判定连接已断开，由 SSHConn 以指数退避自动重连。
+//!
+//! ## 说明
+//! - 未配置 ServerAliveInterval 时使用 30 秒；显式配置为 0 时禁用保活
+//! - libssh2 不跟踪保活回复，探测发送失败（连接被重置、写入超时等）计为一次未响应
+//! - 会话可能处于非阻塞模式，EAGAIN 不计为失败
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ssh2::Session;
+
+use super::ssh_connection::ConnKeywords;
+use super::ssh_forward::is_would_block;
+
+/// 未配置 ServerAliveInterval 时的保活间隔
+pub const DEFAULT_SERVER_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 未配置 ServerAliveCountMax 时的最大连续失败次数（与 OpenSSH 一致）
+pub const DEFAULT_SERVER_ALIVE_COUNT_MAX: u32 = 3;
+
+/// 首次重连前的等待时间
+pub const RECONNECT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// 重连等待时间上限
+pub const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// 最大自动重连次数
+pub const MAX_RECONNECT_ATTEMPTS: u32 = 8;
+
+/// 停止标志检查间隔
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 保活配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// 探测间隔
+    pub interval: Duration,
+    /// 连续失败多少次判定连接断开
+    pub count_max: u32,
+}
+
+impl KeepaliveConfig {
+    /// 从连接配置读取，ServerAliveInterval 为 0 时返回 None
+    pub fn from_keywords(keywords: &ConnKeywords) -> Option<Self> {
+        let interval = match keywords.server_alive_interval {
+            Some(0) => return None,
+            Some(secs) => Duration::from_secs(secs as u64),
+            None => DEFAULT_SERVER_ALIVE_INTERVAL,
+        };
+        let count_max = keywords
+            .server_alive_count_max
+            .unwrap_or(DEFAULT_SERVER_ALIVE_COUNT_MAX)
+            .max(1);
+        Some(Self {
+            interval,
+            count_max,
+        })
+    }
+}
+
+/// 指数退避
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    next: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    /// 创建退避序列
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self { next: initial, max }
+    }
+
+    /// 下一次等待时间（每次翻倍，不超过上限）
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        delay
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(RECONNECT_INITIAL_DELAY, RECONNECT_MAX_DELAY)
+    }
+}
+
+/// 启动保活线程
+///
+/// `stop` 置位后线程退出；判定连接断开时以原因调用 `on_dead` 并退出。
+pub(crate) fn spawn_keepalive(
+    session: Session,
+    config: KeepaliveConfig,
+    stop: Arc<AtomicBool>,
+    on_dead: impl FnOnce(String) + Send + 'static,
+) {
+    let interval_secs = config.interval.as_secs().max(1) as u32;
+    session.set_keepalive(true, interval_secs);
+
+    let spawned = thread::Builder::new()
+        .name("ssh-keepalive".to_string())
+        .spawn(move || {
+            let mut failures = 0u32;
+            let mut next_probe = Instant::now() + config.interval;
+            loop {
+                if stop.load(Ordering::SeqCst) {
+                    return;
+                }
+                if Instant::now() < next_probe {
+                    thread::sleep(STOP_CHECK_INTERVAL);
+                    continue;
+                }
+                next_probe = Instant::now() + config.interval;
+
+                match session.keepalive_send() {
+                    Ok(_) => failures = 0,
+                    Err(ref e) if is_would_block(e) => {}
+                    Err(e) => {
+                        failures += 1;
+                        tracing::warn!(
+                            "[SSHConn] 保活探测失败 ({}/{}): {}",
+                            failures,
+                            config.count_max,
+                            e
+                        );
+                        if failures >= config.count_max {
+                            if !stop.load(Ordering::SeqCst) {
+                                on_dead(format!("保活探测连续 {} 次失败: {}", failures, e));
+                            }
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        tracing::warn!("[SSHConn] 启动保活线程失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keepalive_config_from_keywords() {
+        let config = KeepaliveConfig::from_keywords(&ConnKeywords::default()).unwrap();
+        assert_eq!(config.interval, DEFAULT_SERVER_ALIVE_INTERVAL);
+        assert_eq!(config.count_max, DEFAULT_SERVER_ALIVE_COUNT_MAX);
+
+        let keywords = ConnKeywords {
+            server_alive_interval: Some(15),
+            server_alive_count_max: Some(5),
+            ..Default::default()
+        };
+        let config = KeepaliveConfig::from_keywords(&keywords).unwrap();
+        assert_eq!(config.interval, Duration::from_secs(15));
+        assert_eq!(config.count_max, 5);
+
+        let keywords = ConnKeywords {
+            server_alive_interval: Some(0),
+            ..Default::default()
+        };
+        assert!(KeepaliveConfig::from_keywords(&keywords).is_none());
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        let delays: Vec<u64> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+    }
+}
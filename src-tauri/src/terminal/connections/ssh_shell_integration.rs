@@ -0,0 +1,314 @@
+//! SSH 远程 Shell 集成
+//!
+//! 在远程主机上检测用户的登录 Shell，上传对应的 Shell 集成脚本，
+//! 并构建加载集成脚本的启动命令，使远程 Shell 同样发送 OSC 7 / OSC 133 标记。
+//!
+//! ## 流程
+//! 1. 通过 `sh -c` 输出 `$SHELL` 和 `$HOME`（任意登录 Shell 都能解析该命令）
+//! 2. 远程脚本版本与本机不一致时，按 Shell 类型将脚本写入 `~/.proxycast/shell-integration`
+//! 3. 远程 PTY 通道执行 [`ShellScripts::remote_launch_command`] 生成的命令
+//!
+//! 脚本无法写入（如主目录只读）时，Bash、Fish、PowerShell 改为内联加载
+//! （[`ShellScripts::remote_inline_command`]）。
+//! 远程没有 `sh`（如 Windows 主机）或 Shell 不受支持时不注入，按原方式启动 Shell。
+
+use std::io::{ErrorKind, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ssh2::{Channel, Session};
+
+use crate::terminal::error::TerminalError;
+use crate::terminal::integration::{ShellScripts, ShellType, REMOTE_INTEGRATION_DIR};
+
+use super::ssh_forward::retry_would_block;
+
+/// 探测远程登录 Shell 和主目录的命令
+const PROBE_COMMAND: &str = r#"sh -c 'echo "$SHELL"; echo "$HOME"'"#;
+
+/// 上传脚本的命令（标准输入写入 `$1`，按需创建父目录）
+const UPLOAD_COMMAND: &str = r#"sh -c 'mkdir -p "${1%/*}" && cat > "$1"' sh"#;
+
+/// 读取远程脚本版本的命令（文件不存在时输出为空）
+const VERSION_COMMAND: &str = r#"sh -c 'cat "$1" 2>/dev/null; true' sh"#;
+
+/// 版本文件名（位于各 Shell 的脚本目录中）
+const VERSION_FILE: &str = ".version";
+
+/// 远程命令超时时间
+const REMOTE_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 非阻塞读写的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 集成脚本的加载方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptSource {
+    /// source 已上传到远程主机的脚本
+    Uploaded,
+    /// 脚本内联在启动命令中
+    Inline,
+}
+
+/// 远程登录 Shell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteShell {
+    /// Shell 路径（`$SHELL`）
+    pub path: String,
+    /// Shell 类型
+    pub shell_type: ShellType,
+    /// 用户主目录（`$HOME`）
+    pub home: String,
+}
+
+impl RemoteShell {
+    /// 解析探测命令的输出
+    ///
+    /// 路径须为绝对路径且不含单引号，Shell 类型须受支持。
+    pub fn parse(output: &str) -> Option<Self> {
+        let mut lines = output.lines().map(str::trim);
+        let path = lines.next()?;
+        let home = lines.next()?.trim_end_matches('/');
+        let valid = |value: &str| value.starts_with('/') && !value.contains('\'');
+        if !valid(path) || !valid(home) {
+            return None;
+        }
+
+        let shell_type = ShellType::from_path(path);
+        if shell_type == ShellType::Unknown {
+            return None;
+        }
+
+        Some(Self {
+            path: path.to_string(),
+            shell_type,
+            home: home.to_string(),
+        })
+    }
+
+    /// 检测远程登录 Shell
+    ///
+    /// # 返回
+    /// - `Ok(Some(RemoteShell))`: 检测到受支持的 Shell
+    /// - `Ok(None)`: 探测命令失败或 Shell 不受支持
+    /// - `Err(TerminalError)`: SSH 通道错误或超时
+    pub fn detect(session: &Session) -> Result<Option<Self>, TerminalError> {
+        let (code, output) = run_remote(session, PROBE_COMMAND, None)?;
+        if code != 0 {
+            tracing::debug!("[SSHShellIntegration] 探测远程 Shell 失败，退出码 {}", code);
+            return Ok(None);
+        }
+        Ok(Self::parse(&output))
+    }
+
+    /// 远程集成脚本目录
+    pub fn integration_dir(&self) -> String {
+        format!("{}/{}", self.home, REMOTE_INTEGRATION_DIR)
+    }
+
+    /// 远程版本文件路径
+    fn version_path(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.integration_dir(),
+            self.shell_type.name(),
+            VERSION_FILE
+        )
+    }
+
+    /// 检查远程主机上的集成脚本是否为当前版本
+    pub fn scripts_up_to_date(&self, session: &Session) -> Result<bool, TerminalError> {
+        let command = format!("{} '{}'", VERSION_COMMAND, self.version_path());
+        let (code, output) = run_remote(session, &command, None)?;
+        Ok(code == 0 && output.trim() == ShellScripts::scripts_version(self.shell_type))
+    }
+
+    /// 上传集成脚本到远程主机
+    ///
+    /// 远程脚本已是当前版本时跳过上传；版本文件最后写入，上传中断时下次重新上传。
+    pub fn install_scripts(&self, session: &Session) -> Result<(), TerminalError> {
+        let dir = self.integration_dir();
+        if self.scripts_up_to_date(session)? {
+            tracing::debug!("[SSHShellIntegration] 远程集成脚本已是当前版本: {}", dir);
+            return Ok(());
+        }
+
+        for (path, content) in ShellScripts::script_files(self.shell_type) {
+            let command = format!("{} '{}/{}'", UPLOAD_COMMAND, dir, path);
+            let (code, _) = run_remote(session, &command, Some(content.as_bytes()))?;
+            if code != 0 {
+                return Err(TerminalError::SSHConnectionFailed(format!(
+                    "上传集成脚本 {} 失败，退出码 {}",
+                    path, code
+                )));
+            }
+        }
+        let version = ShellScripts::scripts_version(self.shell_type);
+        let command = format!("{} '{}'", UPLOAD_COMMAND, self.version_path());
+        let (code, _) = run_remote(session, &command, Some(version.as_bytes()))?;
+        if code != 0 {
+            return Err(TerminalError::SSHConnectionFailed(format!(
+                "写入集成脚本版本失败，退出码 {}",
+                code
+            )));
+        }
+
+        tracing::debug!(
+            "[SSHShellIntegration] 集成脚本已上传: {} ({})",
+            dir,
+            self.shell_type.name()
+        );
+        Ok(())
+    }
+
+    /// 是否支持内联加载集成脚本
+    pub fn supports_inline(&self) -> bool {
+        ShellScripts::remote_inline_command(self.shell_type, &self.path, &[]).is_some()
+    }
+
+    /// 构建加载集成脚本的启动命令
+    ///
+    /// # 参数
+    /// - `source`: 集成脚本的加载方式
+    /// - `env`: 额外的环境变量（如 setenv 被拒绝时的 DISPLAY）
+    pub fn launch_command(&self, source: ScriptSource, env: &[(&str, &str)]) -> Option<String> {
+        match source {
+            ScriptSource::Uploaded => ShellScripts::remote_launch_command(
+                self.shell_type,
+                &self.path,
+                &self.integration_dir(),
+                env,
+            ),
+            ScriptSource::Inline => {
+                ShellScripts::remote_inline_command(self.shell_type, &self.path, env)
+            }
+        }
+    }
+}
+
+/// 在远程执行命令，返回退出码和标准输出
+///
+/// 会话可能处于非阻塞模式，读写遇到 WouldBlock 时轮询重试，超过超时时间返回错误。
+fn run_remote(
+    session: &Session,
+    command: &str,
+    stdin: Option<&[u8]>,
+) -> Result<(i32, String), TerminalError> {
+    let channel_error =
+        |e: ssh2::Error| TerminalError::SSHConnectionFailed(format!("远程命令执行失败: {}", e));
+    let deadline = Instant::now() + REMOTE_COMMAND_TIMEOUT;
+
+    let mut channel = retry_would_block(|| session.channel_session()).map_err(channel_error)?;
+    retry_would_block(|| channel.exec(command)).map_err(channel_error)?;
+
+    if let Some(mut input) = stdin {
+        while !input.is_empty() {
+            match channel.write(input) {
+                Ok(written) => input = &input[written..],
+                Err(e) if e.kind() == ErrorKind::WouldBlock => wait_until(deadline)?,
+                Err(e) => {
+                    return Err(TerminalError::SSHConnectionFailed(format!(
+                        "写入远程命令输入失败: {}",
+                        e
+                    )))
+                }
+            }
+        }
+    }
+    retry_would_block(|| channel.send_eof()).map_err(channel_error)?;
+
+    let output = read_to_end(&mut channel, deadline)?;
+    retry_would_block(|| channel.wait_close()).map_err(channel_error)?;
+    let code = channel.exit_status().map_err(channel_error)?;
+
+    Ok((code, String::from_utf8_lossy(&output).into_owned()))
+}
+
+/// 读取通道的全部标准输出
+fn read_to_end(channel: &mut Channel, deadline: Instant) -> Result<Vec<u8>, TerminalError> {
+    let mut output = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        match channel.read(&mut buf) {
+            Ok(0) if channel.eof() => return Ok(output),
+            Ok(0) => wait_until(deadline)?,
+            Ok(n) => output.extend_from_slice(&buf[..n]),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => wait_until(deadline)?,
+            Err(e) => {
+                return Err(TerminalError::SSHConnectionFailed(format!(
+                    "读取远程命令输出失败: {}",
+                    e
+                )))
+            }
+        }
+    }
+}
+
+/// 等待一个轮询间隔，超过截止时间时返回超时错误
+fn wait_until(deadline: Instant) -> Result<(), TerminalError> {
+    if Instant::now() >= deadline {
+        return Err(TerminalError::ConnectionTimeout);
+    }
+    thread::sleep(POLL_INTERVAL);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_shell() {
+        let shell = RemoteShell::parse("/usr/bin/zsh\n/home/dev/\n").unwrap();
+        assert_eq!(shell.shell_type, ShellType::Zsh);
+        assert_eq!(shell.path, "/usr/bin/zsh");
+        assert_eq!(
+            shell.integration_dir(),
+            "/home/dev/.proxycast/shell-integration"
+        );
+
+        let shell = RemoteShell::parse("/usr/local/bin/nu\n/root\n").unwrap();
+        assert_eq!(shell.shell_type, ShellType::Nu);
+    }
+
+    #[test]
+    fn test_parse_remote_shell_rejects_unsupported() {
+        // 不受支持的 Shell
+        assert!(RemoteShell::parse("/bin/tcsh\n/home/dev\n").is_none());
+        // $SHELL 未设置
+        assert!(RemoteShell::parse("\n/home/dev\n").is_none());
+        // 缺少主目录
+        assert!(RemoteShell::parse("/bin/bash\n").is_none());
+        // 无法安全引用的路径
+        assert!(RemoteShell::parse("/bin/bash\n/home/o'neil\n").is_none());
+    }
+
+    #[test]
+    fn test_launch_command_uses_remote_paths() {
+        let shell = RemoteShell::parse("/bin/bash\n/home/dev\n").unwrap();
+        let command = shell.launch_command(ScriptSource::Uploaded, &[]).unwrap();
+        assert!(command.starts_with("exec env _PROXYCAST_LOGIN_SHELL='1' '/bin/bash'"));
+        assert!(command.contains("'/home/dev/.proxycast/shell-integration/bash/proxycast.bash'"));
+        assert_eq!(
+            shell.version_path(),
+            "/home/dev/.proxycast/shell-integration/bash/.version"
+        );
+
+        let command = shell
+            .launch_command(ScriptSource::Inline, &[("DISPLAY", "localhost:10.0")])
+            .unwrap();
+        assert!(command.contains("DISPLAY='localhost:10.0'"));
+        assert!(command.contains("| base64 -d"));
+        assert!(!command.contains(".proxycast"));
+    }
+
+    #[test]
+    fn test_inline_support_by_shell() {
+        let supports = |output: &str| RemoteShell::parse(output).unwrap().supports_inline();
+        assert!(supports("/bin/bash\n/root\n"));
+        assert!(supports("/usr/bin/fish\n/root\n"));
+        assert!(supports("/usr/bin/pwsh\n/root\n"));
+        assert!(!supports("/bin/zsh\n/root\n"));
+        assert!(!supports("/usr/bin/nu\n/root\n"));
+    }
+}
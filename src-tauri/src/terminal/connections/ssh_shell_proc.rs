@@ -6,6 +6,7 @@
 //! - 创建和管理远程 PTY 会话
 //! - 异步读取远程 PTY 输出并通过 Tauri Event 推送
 //! - 处理远程 PTY 输入写入
+//! - 检测远程登录 Shell 并注入 Shell 集成脚本
 //! - 监控远程进程退出状态
 //! - 终端大小同步
 //!
@@ -17,6 +18,7 @@
 use std::io::{Read, Write};
 use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use parking_lot::Mutex;
@@ -29,9 +31,25 @@ use crate::terminal::error::TerminalError;
 use crate::terminal::events::{
     event_names, SessionStatus, TerminalOutputEvent, TerminalStatusEvent,
 };
+use crate::terminal::integration::{ResyncController, ShellIntegration};
 use crate::terminal::persistence::BlockFile;
 
-use super::ssh_connection::SSHConn;
+use super::ssh_connection::{SSHConn, SSHConnLease};
+use super::ssh_forward::retry_would_block;
+use super::ssh_shell_integration::{RemoteShell, ScriptSource};
+
+/// 等待共享连接重连时的轮询间隔
+const REATTACH_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 共享连接重连后重建远程 PTY 所需的信息
+struct Reattach {
+    conn: Arc<SSHConn>,
+    /// 当前通道所在会话的代数
+    generation: u64,
+    controller_type: String,
+    block_meta: BlockMeta,
+    term_size: Arc<Mutex<TermSize>>,
+}
 
 /// SSH Shell 进程封装
 ///
@@ -53,6 +71,8 @@ pub struct SSHShellProc {
     exited: Arc<AtomicBool>,
     /// 当前终端大小
     term_size: Arc<Mutex<TermSize>>,
+    /// 共享连接租约（进程销毁时释放，在 Channel 关闭之后）
+    lease: Option<SSHConnLease>,
 }
 
 impl SSHShellProc {
@@ -84,6 +104,37 @@ impl SSHShellProc {
         block_meta: BlockMeta,
         input_rx: mpsc::Receiver<BlockInputUnion>,
         block_file: Option<Arc<BlockFile>>,
+    ) -> Result<Self, TerminalError> {
+        Self::create(
+            block_id,
+            controller_type,
+            session,
+            rows,
+            cols,
+            app_handle,
+            block_meta,
+            input_rx,
+            block_file,
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// 创建远程进程，持有租约时在连接重连后自动重建远程 PTY
+    #[allow(clippy::too_many_arguments)]
+    async fn create(
+        block_id: String,
+        controller_type: String,
+        session: &Session,
+        rows: u16,
+        cols: u16,
+        app_handle: tauri::AppHandle,
+        block_meta: BlockMeta,
+        input_rx: mpsc::Receiver<BlockInputUnion>,
+        block_file: Option<Arc<BlockFile>>,
+        lease: Option<SSHConnLease>,
+        x11_display: Option<String>,
     ) -> Result<Self, TerminalError> {
         tracing::info!(
             "[SSHShellProc] 创建远程进程: block_id={}, type={}, size={}x{}",
@@ -93,35 +144,14 @@ impl SSHShellProc {
             rows
         );
 
-        // 创建 SSH Channel
-        let mut channel = session.channel_session().map_err(|e| {
-            TerminalError::SSHConnectionFailed(format!("创建 SSH Channel 失败: {}", e))
-        })?;
-
-        // 请求 PTY
-        // 使用 xterm-256color 终端类型
-        channel
-            .request_pty(
-                "xterm-256color",
-                None,
-                Some((cols as u32, rows as u32, 0, 0)),
-            )
-            .map_err(|e| TerminalError::SSHConnectionFailed(format!("请求远程 PTY 失败: {}", e)))?;
-
-        // 根据控制器类型启动 Shell 或执行命令
-        if controller_type == "cmd" {
-            // 命令执行模式
-            let cmd = Self::build_remote_command(&block_meta)?;
-            tracing::info!("[SSHShellProc] 执行远程命令: {}", cmd);
-            channel.exec(&cmd).map_err(|e| {
-                TerminalError::SSHConnectionFailed(format!("执行远程命令失败: {}", e))
-            })?;
-        } else {
-            // Shell 模式 - 启动交互式 Shell
-            channel.shell().map_err(|e| {
-                TerminalError::SSHConnectionFailed(format!("启动远程 Shell 失败: {}", e))
-            })?;
-        }
+        let channel = Self::open_channel(
+            session,
+            &controller_type,
+            &block_meta,
+            rows,
+            cols,
+            x11_display.as_deref(),
+        )?;
 
         // 设置非阻塞模式
         session.set_blocking(false);
@@ -133,6 +163,29 @@ impl SSHShellProc {
         let channel = Arc::new(Mutex::new(channel));
         let term_size = Arc::new(Mutex::new(TermSize { rows, cols }));
 
+        // Shell 集成（远程 Shell 加载了集成脚本时跟踪命令执行并写入命令历史）
+        let shell_integration = Arc::new(ShellIntegration::for_session(
+            block_id.clone(),
+            app_handle.clone(),
+            block_meta.connection.as_deref(),
+        ));
+        // OSC 52 读取请求的回复写回远程 PTY 输入
+        let reply_channel = channel.clone();
+        shell_integration.set_responder(Arc::new(move |data: &[u8]| {
+            let mut ch = reply_channel.lock();
+            ch.write_all(data)?;
+            ch.flush()
+        }));
+
+        // 共享连接重连后在新会话上重建远程 PTY
+        let reattach = lease.as_ref().map(|lease| Reattach {
+            conn: lease.conn().clone(),
+            generation: lease.conn().generation(),
+            controller_type: controller_type.clone(),
+            block_meta: block_meta.clone(),
+            term_size: term_size.clone(),
+        });
+
         // 启动输出读取任务
         Self::spawn_output_reader(
             block_id.clone(),
@@ -142,6 +195,8 @@ impl SSHShellProc {
             exit_code.clone(),
             exited.clone(),
             block_file,
+            shell_integration,
+            reattach,
         );
 
         // 启动输入处理任务
@@ -163,9 +218,123 @@ impl SSHShellProc {
             exit_code,
             exited,
             term_size,
+            lease,
         })
     }
 
+    /// 打开会话通道，请求 PTY 并启动 Shell 或命令
+    ///
+    /// 连接启用了 X11 转发时设置 DISPLAY：服务端拒绝 env 请求（sshd 默认只接受
+    /// AcceptEnv 中的变量）时改为在命令中导出。
+    fn open_channel(
+        session: &Session,
+        controller_type: &str,
+        block_meta: &BlockMeta,
+        rows: u16,
+        cols: u16,
+        x11_display: Option<&str>,
+    ) -> Result<Channel, TerminalError> {
+        // Shell 模式下检测远程登录 Shell 并上传集成脚本，失败时按原方式启动
+        let remote_shell =
+            if controller_type != "cmd" && block_meta.term_shell_integration != Some(false) {
+                Self::prepare_shell_integration(session)
+            } else {
+                None
+            };
+
+        // 创建 SSH Channel
+        // 端口转发可能已将会话切换为非阻塞模式，建立通道的操作需要重试
+        let mut channel = retry_would_block(|| session.channel_session()).map_err(|e| {
+            TerminalError::SSHConnectionFailed(format!("创建 SSH Channel 失败: {}", e))
+        })?;
+
+        // 请求 PTY
+        // 使用 xterm-256color 终端类型
+        retry_would_block(|| {
+            channel.request_pty(
+                "xterm-256color",
+                None,
+                Some((cols as u32, rows as u32, 0, 0)),
+            )
+        })
+        .map_err(|e| TerminalError::SSHConnectionFailed(format!("请求远程 PTY 失败: {}", e)))?;
+
+        // X11 转发的 DISPLAY，env 请求被拒绝时需要在命令中导出
+        let denied_display = x11_display
+            .filter(|display| retry_would_block(|| channel.setenv("DISPLAY", display)).is_err());
+        let export_display =
+            denied_display.map(|display| format!("export DISPLAY={}; ", shell_escape(display)));
+
+        // 加载集成脚本的启动命令，DISPLAY 通过 env 传入
+        let integration_cmd = remote_shell.and_then(|(shell, source)| {
+            let env: Vec<(&str, &str)> = denied_display
+                .map(|display| ("DISPLAY", display))
+                .into_iter()
+                .collect();
+            shell.launch_command(source, &env)
+        });
+
+        // 根据控制器类型启动 Shell 或执行命令
+        if controller_type == "cmd" {
+            // 命令执行模式
+            let cmd = export_display.unwrap_or_default() + &Self::build_remote_command(block_meta)?;
+            tracing::info!("[SSHShellProc] 执行远程命令: {}", cmd);
+            retry_would_block(|| channel.exec(&cmd)).map_err(|e| {
+                TerminalError::SSHConnectionFailed(format!("执行远程命令失败: {}", e))
+            })?;
+        } else if let Some(cmd) = integration_cmd {
+            // Shell 模式 - 加载集成脚本启动远程登录 Shell
+            tracing::info!("[SSHShellProc] 启动带集成脚本的远程 Shell: {}", cmd);
+            retry_would_block(|| channel.exec(&cmd)).map_err(|e| {
+                TerminalError::SSHConnectionFailed(format!("启动远程 Shell 失败: {}", e))
+            })?;
+        } else if let Some(export_display) = export_display {
+            // Shell 模式 - 导出 DISPLAY 后以登录 Shell 启动
+            let cmd = format!("{}exec \"${{SHELL:-/bin/sh}}\" -l", export_display);
+            retry_would_block(|| channel.exec(&cmd)).map_err(|e| {
+                TerminalError::SSHConnectionFailed(format!("启动远程 Shell 失败: {}", e))
+            })?;
+        } else {
+            // Shell 模式 - 启动交互式 Shell
+            retry_would_block(|| channel.shell()).map_err(|e| {
+                TerminalError::SSHConnectionFailed(format!("启动远程 Shell 失败: {}", e))
+            })?;
+        }
+
+        Ok(channel)
+    }
+
+    /// 检测远程登录 Shell 并上传集成脚本
+    ///
+    /// # 返回
+    /// 可以注入集成脚本时返回远程 Shell 和脚本加载方式，否则返回 `None`
+    fn prepare_shell_integration(session: &Session) -> Option<(RemoteShell, ScriptSource)> {
+        let shell = match RemoteShell::detect(session) {
+            Ok(Some(shell)) => shell,
+            Ok(None) => {
+                tracing::debug!("[SSHShellProc] 远程 Shell 不受支持，跳过集成脚本注入");
+                return None;
+            }
+            Err(e) => {
+                tracing::warn!("[SSHShellProc] 检测远程 Shell 失败: {}", e);
+                return None;
+            }
+        };
+
+        match shell.install_scripts(session) {
+            Ok(()) => Some((shell, ScriptSource::Uploaded)),
+            // 无法写入脚本时尝试内联加载
+            Err(e) if shell.supports_inline() => {
+                tracing::warn!("[SSHShellProc] 上传集成脚本失败，改为内联加载: {}", e);
+                Some((shell, ScriptSource::Inline))
+            }
+            Err(e) => {
+                tracing::warn!("[SSHShellProc] 上传集成脚本失败: {}", e);
+                None
+            }
+        }
+    }
+
     /// 从 SSHConn 创建 SSH Shell 进程
     ///
     /// 便捷方法，从已连接的 SSHConn 创建远程 Shell 进程。
@@ -199,7 +368,49 @@ impl SSHShellProc {
             .get_session()
             .ok_or_else(|| TerminalError::SSHConnectionFailed("SSH 会话未建立".to_string()))?;
 
-        Self::new(
+        Self::create(
+            block_id,
+            controller_type,
+            &session,
+            rows,
+            cols,
+            app_handle,
+            block_meta,
+            input_rx,
+            block_file,
+            None,
+            ssh_conn.x11_display(),
+        )
+        .await
+    }
+
+    /// 在共享连接上创建 SSH Shell 进程
+    ///
+    /// 通道建立在租约对应的连接上，进程持有租约直到销毁，
+    /// 多个终端块因此复用同一个已认证的连接。连接断开并自动重连后，
+    /// 在新会话上重建远程 PTY，并重放重同步序列（重置终端、回放历史输出）。
+    ///
+    /// # 参数
+    /// - `lease`: 通过 `SSHConnRegistry::acquire` 获取的连接租约
+    /// - 其余参数同 [`SSHShellProc::from_ssh_conn`]
+    pub async fn from_lease(
+        block_id: String,
+        controller_type: String,
+        lease: SSHConnLease,
+        rows: u16,
+        cols: u16,
+        app_handle: tauri::AppHandle,
+        block_meta: BlockMeta,
+        input_rx: mpsc::Receiver<BlockInputUnion>,
+        block_file: Option<Arc<BlockFile>>,
+    ) -> Result<Self, TerminalError> {
+        let session = lease
+            .conn()
+            .get_session()
+            .ok_or_else(|| TerminalError::SSHConnectionFailed("SSH 会话未建立".to_string()))?;
+        let x11_display = lease.conn().x11_display();
+
+        Self::create(
             block_id,
             controller_type,
             &session,
@@ -209,6 +420,8 @@ impl SSHShellProc {
             block_meta,
             input_rx,
             block_file,
+            Some(lease),
+            x11_display,
         )
         .await
     }
@@ -255,6 +468,7 @@ impl SSHShellProc {
     /// 在独立线程中读取远程 PTY 输出，并通过 Tauri 事件发送到前端。
     ///
     /// _Requirements: 4.2_
+    #[allow(clippy::too_many_arguments)]
     fn spawn_output_reader(
         block_id: String,
         channel: Arc<Mutex<Channel>>,
@@ -263,6 +477,8 @@ impl SSHShellProc {
         exit_code: Arc<AtomicI32>,
         exited: Arc<AtomicBool>,
         block_file: Option<Arc<BlockFile>>,
+        shell_integration: Arc<ShellIntegration>,
+        mut reattach: Option<Reattach>,
     ) {
         std::thread::spawn(move || {
             let mut buffer = [0u8; 4096];
@@ -279,34 +495,45 @@ impl SSHShellProc {
                 // 读取输出
                 let read_result = {
                     let mut ch = channel.lock();
+                    (!ch.eof()).then(|| ch.read(&mut buffer))
+                };
 
-                    // 检查 Channel 是否已关闭
-                    if ch.eof() {
-                        // 获取退出状态
-                        let code = ch.exit_status().unwrap_or(0);
-                        exit_code.store(code, Ordering::SeqCst);
-                        exited.store(true, Ordering::SeqCst);
+                // 检查 Channel 是否已关闭
+                let Some(read_result) = read_result else {
+                    // 共享连接断线导致的关闭：等待重连后重建
+                    if Self::reattach_after_link_loss(
+                        &mut reattach,
+                        &channel,
+                        &shutdown_flag,
+                        &app_handle,
+                        &block_id,
+                        block_file.as_deref(),
+                    ) {
+                        continue;
+                    }
 
-                        tracing::info!(
-                            "[SSHShellProc] 远程进程已退出: block_id={}, exit_code={}",
-                            block_id,
-                            code
-                        );
+                    // 获取退出状态
+                    let code = channel.lock().exit_status().unwrap_or(0);
+                    exit_code.store(code, Ordering::SeqCst);
+                    exited.store(true, Ordering::SeqCst);
 
-                        // 发送状态事件
-                        let _ = app_handle.emit(
-                            event_names::TERMINAL_STATUS,
-                            TerminalStatusEvent {
-                                session_id: block_id.clone(),
-                                status: SessionStatus::Done,
-                                exit_code: Some(code),
-                                error: None,
-                            },
-                        );
-                        break;
-                    }
+                    tracing::info!(
+                        "[SSHShellProc] 远程进程已退出: block_id={}, exit_code={}",
+                        block_id,
+                        code
+                    );
 
-                    ch.read(&mut buffer)
+                    // 发送状态事件
+                    let _ = app_handle.emit(
+                        event_names::TERMINAL_STATUS,
+                        TerminalStatusEvent {
+                            session_id: block_id.clone(),
+                            status: SessionStatus::Done,
+                            exit_code: Some(code),
+                            error: None,
+                        },
+                    );
+                    break;
                 };
 
                 match read_result {
@@ -317,6 +544,10 @@ impl SSHShellProc {
                             // 检查 Channel 状态
                             let ch = channel.lock();
                             if ch.eof() {
+                                // 交给循环开头统一处理（可能需要等待共享连接重连）
+                                if reattach.is_some() {
+                                    continue;
+                                }
                                 let code = ch.exit_status().unwrap_or(0);
                                 exit_code.store(code, Ordering::SeqCst);
                                 exited.store(true, Ordering::SeqCst);
@@ -351,6 +582,9 @@ impl SSHShellProc {
                             }
                         }
 
+                        // 更新 Shell 集成状态
+                        shell_integration.process_output(output_data);
+
                         // 发送输出事件
                         let data = BASE64.encode(output_data);
                         let _ = app_handle.emit(
@@ -372,6 +606,18 @@ impl SSHShellProc {
                             break;
                         }
 
+                        // 共享连接断线导致的错误：等待重连后重建
+                        if Self::reattach_after_link_loss(
+                            &mut reattach,
+                            &channel,
+                            &shutdown_flag,
+                            &app_handle,
+                            &block_id,
+                            block_file.as_deref(),
+                        ) {
+                            continue;
+                        }
+
                         tracing::error!(
                             "[SSHShellProc] 读取错误: block_id={}, error={}",
                             block_id,
@@ -395,6 +641,107 @@ impl SSHShellProc {
         });
     }
 
+    /// 共享连接断线后等待自动重连，在新会话上重建远程 PTY 并重放重同步序列
+    ///
+    /// 返回 true 表示已重建、读取循环继续；未持有租约、连接未断开或重连失败时返回 false。
+    fn reattach_after_link_loss(
+        reattach: &mut Option<Reattach>,
+        channel: &Mutex<Channel>,
+        shutdown_flag: &AtomicBool,
+        app_handle: &tauri::AppHandle,
+        block_id: &str,
+        block_file: Option<&BlockFile>,
+    ) -> bool {
+        let Some(ctx) = reattach.as_mut() else {
+            return false;
+        };
+        let conn = ctx.conn.clone();
+        // 连接仍在同一会话上：远程进程正常退出
+        if conn.is_connected() && conn.generation() == ctx.generation && !conn.is_reconnecting() {
+            return false;
+        }
+
+        tracing::info!("[SSHShellProc] 连接已断开，等待重连: block_id={}", block_id);
+        Self::emit_status(app_handle, block_id, SessionStatus::Connecting, None);
+        loop {
+            if shutdown_flag.load(Ordering::Relaxed) {
+                return false;
+            }
+            if conn.is_connected() && conn.generation() != ctx.generation {
+                break;
+            }
+            if !conn.is_connected() && !conn.is_reconnecting() {
+                tracing::warn!("[SSHShellProc] 重连失败: block_id={}", block_id);
+                return false;
+            }
+            std::thread::sleep(REATTACH_POLL_INTERVAL);
+        }
+
+        let generation = conn.generation();
+        let size = *ctx.term_size.lock();
+        let opened = conn
+            .get_session()
+            .ok_or_else(|| TerminalError::SSHConnectionFailed("SSH 会话未建立".to_string()))
+            .and_then(|session| {
+                let new_channel = Self::open_channel(
+                    &session,
+                    &ctx.controller_type,
+                    &ctx.block_meta,
+                    size.rows,
+                    size.cols,
+                    conn.x11_display().as_deref(),
+                )?;
+                session.set_blocking(false);
+                Ok(new_channel)
+            });
+        match opened {
+            Ok(new_channel) => {
+                *channel.lock() = new_channel;
+                ctx.generation = generation;
+            }
+            Err(e) => {
+                tracing::error!(
+                    "[SSHShellProc] 重建远程 PTY 失败: block_id={}, error={}",
+                    block_id,
+                    e
+                );
+                return false;
+            }
+        }
+
+        // 重放重同步序列：重置终端并回放历史输出
+        if let Err(e) = ResyncController::send_reset_sequence(app_handle, block_id, true) {
+            tracing::warn!("[SSHShellProc] {}: block_id={}", e, block_id);
+        }
+        if let Some(bf) = block_file {
+            if let Err(e) = ResyncController::restore_history(app_handle, block_id, bf) {
+                tracing::warn!("[SSHShellProc] {}: block_id={}", e, block_id);
+            }
+        }
+        Self::emit_status(app_handle, block_id, SessionStatus::Running, None);
+
+        tracing::info!("[SSHShellProc] 远程 PTY 已重建: block_id={}", block_id);
+        true
+    }
+
+    /// 发送会话状态事件
+    fn emit_status(
+        app_handle: &tauri::AppHandle,
+        block_id: &str,
+        status: SessionStatus,
+        error: Option<String>,
+    ) {
+        let _ = app_handle.emit(
+            event_names::TERMINAL_STATUS,
+            TerminalStatusEvent {
+                session_id: block_id.to_string(),
+                status,
+                exit_code: None,
+                error,
+            },
+        );
+    }
+
     /// 启动输入处理任务
     ///
     /// 在独立任务中处理输入数据，包括键盘输入、信号和终端大小调整。
@@ -536,6 +883,11 @@ impl SSHShellProc {
         *self.term_size.lock()
     }
 
+    /// 获取共享连接（通过 `from_lease` 创建时）
+    pub fn shared_conn(&self) -> Option<&Arc<SSHConn>> {
+        self.lease.as_ref().map(|lease| lease.conn())
+    }
+
     /// 写入数据到远程 PTY
     ///
     /// _Requirements: 4.2_
@@ -0,0 +1,637 @@
+//! SSH X11 转发
+//!
+//! libssh2 不向应用交付服务端发起的 `x11` 通道，这里按 sshd `X11UseLocalhost` 的方式实现：
+//! 在远程回环地址上监听 X11 显示端口（6000 + 显示号），远程 Shell 的 DISPLAY 指向它，
+//! 远程 X 客户端的连接经 forwarded-tcpip 通道转发到本机 X 服务器。
+//!
+//! ## 安全
+//! - 默认关闭，需配置 ForwardX11 yes 并经用户确认后启用
+//! - 远程只拿到随机生成的假 cookie；转发时校验假 cookie 并替换为本机 cookie（与 OpenSSH 一致）
+//! - 未开启 ForwardX11Trusted 时通过 `xauth generate ... untrusted` 生成受限 cookie，
+//!   X 服务器不支持 SECURITY 扩展导致生成失败时拒绝转发
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::RwLock;
+use rand::RngCore;
+use ssh2::{Channel, Session};
+
+use super::ssh_connection::ConnKeywords;
+use super::ssh_forward::{is_would_block, pump, retry_would_block, LocalStream, Traffic};
+
+/// X11 TCP 端口基数（显示号 N 对应端口 6000 + N）
+const X11_BASE_PORT: u16 = 6000;
+
+/// 远程显示号起点（与 sshd X11DisplayOffset 默认值一致）
+const X11_DISPLAY_OFFSET: u16 = 10;
+
+/// 尝试的远程显示号数量（与 sshd X11MaxDisplays 默认值一致）
+const X11_MAX_DISPLAYS: u16 = 1000;
+
+/// X11 认证协议
+const X11_AUTH_PROTO: &str = "MIT-MAGIC-COOKIE-1";
+
+/// 假 cookie 长度（字节）
+const FAKE_COOKIE_LEN: usize = 16;
+
+/// 受限 cookie 有效期（秒，与 OpenSSH ForwardX11Timeout 默认值一致）
+const UNTRUSTED_TIMEOUT_SECS: u32 = 1200;
+
+/// X11 连接初始化包头长度
+const SETUP_HEADER_LEN: usize = 12;
+
+/// X11 连接初始化包最大长度
+const MAX_SETUP_LEN: usize = 64 * 1024;
+
+/// 等待 X11 连接初始化包的超时时间
+const SETUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 非阻塞轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// X11 转发选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct X11Options {
+    /// 是否为受信任转发（ForwardX11Trusted），受信任的远程客户端拥有本机显示的全部权限
+    pub trusted: bool,
+}
+
+impl X11Options {
+    /// 从连接配置读取，未开启 ForwardX11 时返回 None
+    pub fn from_keywords(keywords: &ConnKeywords) -> Option<Self> {
+        if keywords.forward_x11 != Some(true) {
+            return None;
+        }
+        Some(Self {
+            trusted: keywords.forward_x11_trusted.unwrap_or(false),
+        })
+    }
+}
+
+/// 本机 X 显示（DISPLAY 环境变量）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalDisplay {
+    /// 原始 DISPLAY 值（用于 xauth 查询）
+    pub display: String,
+    /// TCP 主机，None 表示本地 socket
+    pub host: Option<String>,
+    /// 显示号
+    pub number: u16,
+}
+
+impl LocalDisplay {
+    /// 读取当前进程的 DISPLAY
+    pub fn from_env() -> Option<Self> {
+        std::env::var("DISPLAY")
+            .ok()
+            .and_then(|display| Self::parse(&display))
+    }
+
+    /// 解析 DISPLAY，支持 `:0`、`unix:0.0`、`localhost:10.0` 和 macOS launchd socket 路径
+    pub fn parse(display: &str) -> Option<Self> {
+        let colon = display.rfind(':')?;
+        let (host, rest) = (&display[..colon], &display[colon + 1..]);
+        let number = rest.split('.').next()?.parse().ok()?;
+        let host = match host {
+            "" | "unix" => None,
+            path if path.starts_with('/') => None,
+            host => Some(host.to_string()),
+        };
+        Some(Self {
+            display: display.to_string(),
+            host,
+            number,
+        })
+    }
+
+    /// 连接本机 X 服务器
+    fn connect(&self) -> io::Result<X11Stream> {
+        if let Some(host) = &self.host {
+            let stream = TcpStream::connect((host.as_str(), X11_BASE_PORT + self.number))?;
+            return Ok(X11Stream::Tcp(stream));
+        }
+
+        #[cfg(unix)]
+        {
+            // launchd 提供的 DISPLAY 本身就是 socket 路径
+            let path = if self.display.starts_with('/') {
+                self.display.clone()
+            } else {
+                format!("/tmp/.X11-unix/X{}", self.number)
+            };
+            let stream = std::os::unix::net::UnixStream::connect(path)?;
+            Ok(X11Stream::Unix(stream))
+        }
+        #[cfg(not(unix))]
+        {
+            let stream = TcpStream::connect(("127.0.0.1", X11_BASE_PORT + self.number))?;
+            Ok(X11Stream::Tcp(stream))
+        }
+    }
+}
+
+/// 到本机 X 服务器的连接
+enum X11Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixStream),
+}
+
+/// 本机 cookie 与远程使用的假 cookie
+struct X11Auth {
+    /// 本机 X 服务器的 cookie，None 表示 X 服务器不需要认证
+    real: Option<Vec<u8>>,
+    /// 交给远程的假 cookie
+    fake: Vec<u8>,
+}
+
+/// SSH 连接的 X11 转发管理器
+///
+/// 由 SSHConn 持有：用户确认后通过 `configure` 启用，连接认证成功后启动，断开时停止。
+pub struct X11Forwarder {
+    /// 转发选项，None 表示未启用
+    options: RwLock<Option<X11Options>>,
+    /// 远程显示号（转发运行中）
+    display: RwLock<Option<u16>>,
+    /// 停止标志
+    stop: RwLock<Arc<AtomicBool>>,
+    /// 转发流量
+    traffic: Arc<Traffic>,
+}
+
+impl X11Forwarder {
+    /// 创建未启用的转发管理器
+    pub fn new() -> Self {
+        Self {
+            options: RwLock::new(None),
+            display: RwLock::new(None),
+            stop: RwLock::new(Arc::new(AtomicBool::new(true))),
+            traffic: Arc::new(Traffic::default()),
+        }
+    }
+
+    /// 设置转发选项（None 表示禁用）
+    pub fn configure(&self, options: Option<X11Options>) {
+        *self.options.write() = options;
+    }
+
+    /// 是否已启用
+    pub fn is_enabled(&self) -> bool {
+        self.options.read().is_some()
+    }
+
+    /// 远程 Shell 应使用的 DISPLAY，转发未运行时返回 None
+    pub fn remote_display(&self) -> Option<String> {
+        self.display
+            .read()
+            .map(|number| format!("localhost:{}.0", number))
+    }
+
+    /// 在会话上启动转发，失败只记录日志，不影响连接
+    pub fn start(&self, session: &Session) {
+        let Some(options) = *self.options.read() else {
+            return;
+        };
+        self.stop();
+
+        let Some(local) = LocalDisplay::from_env() else {
+            tracing::warn!("[SSHX11] 未设置 DISPLAY，跳过 X11 转发");
+            return;
+        };
+        let auth = match local_auth(&local, options.trusted) {
+            Ok(auth) => auth,
+            Err(e) => {
+                tracing::warn!("[SSHX11] 获取本机 X11 认证失败，跳过 X11 转发: {}", e);
+                return;
+            }
+        };
+
+        session.set_blocking(false);
+        let Some((listener, number)) = listen_display(session) else {
+            tracing::warn!("[SSHX11] 远程没有可用的 X11 显示端口，跳过 X11 转发");
+            return;
+        };
+        if let Err(e) = add_remote_xauth(session, number, &auth.fake) {
+            tracing::warn!("[SSHX11] 远程 xauth 设置失败，X 客户端可能无法认证: {}", e);
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        *self.stop.write() = stop.clone();
+        *self.display.write() = Some(number);
+        tracing::info!(
+            "[SSHX11] X11 转发已启动: 远程 localhost:{}.0 → 本机 {}{}",
+            number,
+            local.display,
+            if options.trusted {
+                "（受信任）"
+            } else {
+                ""
+            }
+        );
+
+        let traffic = self.traffic.clone();
+        let auth = Arc::new(auth);
+        let spawned = thread::Builder::new()
+            .name(format!("ssh-x11-{}", number))
+            .spawn(move || run_listener(listener, local, auth, stop, traffic));
+        if let Err(e) = spawned {
+            tracing::warn!("[SSHX11] 启动 X11 监听线程失败: {}", e);
+        }
+    }
+
+    /// 停止转发（已建立的 X11 连接随之关闭）
+    pub fn stop(&self) {
+        self.stop.read().store(true, Ordering::SeqCst);
+        if self.display.write().take().is_some() {
+            tracing::info!("[SSHX11] X11 转发已停止");
+        }
+    }
+}
+
+impl Default for X11Forwarder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for X11Forwarder {
+    fn drop(&mut self) {
+        self.stop.read().store(true, Ordering::SeqCst);
+    }
+}
+
+/// 在远程回环地址上监听第一个可用的 X11 显示端口
+fn listen_display(session: &Session) -> Option<(ssh2::Listener, u16)> {
+    (X11_DISPLAY_OFFSET..X11_DISPLAY_OFFSET + X11_MAX_DISPLAYS).find_map(|number| {
+        retry_would_block(|| {
+            session.channel_forward_listen(X11_BASE_PORT + number, Some("127.0.0.1"), None)
+        })
+        .ok()
+        .map(|(listener, _)| (listener, number))
+    })
+}
+
+/// 在远程为转发的显示添加假 cookie（与 sshd 一样使用 `unix:N.0` 条目）
+fn add_remote_xauth(session: &Session, number: u16, fake: &[u8]) -> Result<(), String> {
+    let command = format!(
+        "xauth -q add unix:{}.0 {} {}",
+        number,
+        X11_AUTH_PROTO,
+        hex::encode(fake)
+    );
+    let mut channel = retry_would_block(|| session.channel_session()).map_err(|e| e.to_string())?;
+    retry_would_block(|| channel.exec(&command)).map_err(|e| e.to_string())?;
+    retry_would_block(|| channel.wait_close()).map_err(|e| e.to_string())?;
+    match channel.exit_status().map_err(|e| e.to_string())? {
+        0 => Ok(()),
+        code => Err(format!("xauth 退出码 {}", code)),
+    }
+}
+
+/// 生成假 cookie 并读取（受信任）或生成（受限）本机 cookie
+fn local_auth(local: &LocalDisplay, trusted: bool) -> Result<X11Auth, String> {
+    let mut fake = vec![0u8; FAKE_COOKIE_LEN];
+    rand::thread_rng().fill_bytes(&mut fake);
+
+    let real = if trusted {
+        match Command::new("xauth")
+            .arg("list")
+            .arg(&local.display)
+            .output()
+        {
+            Ok(output) => parse_xauth_list(&String::from_utf8_lossy(&output.stdout)),
+            // 没有 xauth 时按无需认证的 X 服务器处理
+            Err(e) => {
+                tracing::debug!("[SSHX11] 执行 xauth 失败: {}", e);
+                None
+            }
+        }
+    } else {
+        Some(generate_untrusted_cookie(local)?)
+    };
+
+    Ok(X11Auth { real, fake })
+}
+
+/// 通过 `xauth generate` 生成受限 cookie（需要 X 服务器支持 SECURITY 扩展）
+fn generate_untrusted_cookie(local: &LocalDisplay) -> Result<Vec<u8>, String> {
+    let file = std::env::temp_dir().join(format!("proxycast-xauth-{}", uuid::Uuid::new_v4()));
+    let result = (|| {
+        let output = Command::new("xauth")
+            .arg("-f")
+            .arg(&file)
+            .args([
+                "generate",
+                local.display.as_str(),
+                X11_AUTH_PROTO,
+                "untrusted",
+            ])
+            .arg("timeout")
+            .arg(UNTRUSTED_TIMEOUT_SECS.to_string())
+            .output()
+            .map_err(|e| format!("执行 xauth 失败: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "xauth generate 失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let output = Command::new("xauth")
+            .arg("-f")
+            .arg(&file)
+            .arg("list")
+            .output()
+            .map_err(|e| format!("执行 xauth 失败: {}", e))?;
+        parse_xauth_list(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| "xauth 未生成 cookie".to_string())
+    })();
+    let _ = std::fs::remove_file(&file);
+    result
+}
+
+/// 从 `xauth list` 输出中读取第一个 MIT-MAGIC-COOKIE-1
+fn parse_xauth_list(output: &str) -> Option<Vec<u8>> {
+    output.lines().find_map(|line| {
+        // 格式: <显示> <协议> <十六进制 cookie>
+        let mut fields = line.split_whitespace().skip(1);
+        if fields.next()? != X11_AUTH_PROTO {
+            return None;
+        }
+        hex::decode(fields.next()?).ok()
+    })
+}
+
+/// X11 远程监听循环
+fn run_listener(
+    mut listener: ssh2::Listener,
+    local: LocalDisplay,
+    auth: Arc<X11Auth>,
+    stop: Arc<AtomicBool>,
+    traffic: Arc<Traffic>,
+) {
+    while !stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok(channel) => {
+                let local = local.clone();
+                let auth = auth.clone();
+                let stop = stop.clone();
+                let traffic = traffic.clone();
+                thread::spawn(move || {
+                    if let Err(e) = handle_connection(channel, &local, &auth, &stop, &traffic) {
+                        tracing::warn!("[SSHX11] X11 连接结束: {}", e);
+                    }
+                });
+            }
+            Err(ref e) if is_would_block(e) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                // 会话已断开，重新认证后由 SSHConn 重新启动
+                if !stop.swap(true, Ordering::SeqCst) {
+                    tracing::debug!("[SSHX11] X11 监听中断: {}", e);
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// 处理一条 X11 连接：校验并替换 cookie 后转发到本机 X 服务器
+fn handle_connection(
+    mut channel: Channel,
+    local: &LocalDisplay,
+    auth: &X11Auth,
+    stop: &AtomicBool,
+    traffic: &Traffic,
+) -> Result<(), String> {
+    let setup = read_setup(&mut channel, stop)
+        .and_then(|setup| spoof_auth(&setup, &auth.fake, auth.real.as_deref()));
+    let setup = match setup {
+        Ok(setup) => setup,
+        Err(e) => {
+            let _ = retry_would_block(|| channel.close());
+            return Err(e);
+        }
+    };
+
+    let stream = match local.connect() {
+        Ok(stream) => stream,
+        Err(e) => {
+            let _ = retry_would_block(|| channel.close());
+            return Err(format!("连接本机 X 服务器 {} 失败: {}", local.display, e));
+        }
+    };
+    match stream {
+        X11Stream::Tcp(stream) => relay(stream, &setup, channel, stop, traffic),
+        #[cfg(unix)]
+        X11Stream::Unix(stream) => relay(stream, &setup, channel, stop, traffic),
+    }
+}
+
+/// 发送替换后的初始化包，然后双向转发
+fn relay<S: LocalStream>(
+    mut stream: S,
+    setup: &[u8],
+    channel: Channel,
+    stop: &AtomicBool,
+    traffic: &Traffic,
+) -> Result<(), String> {
+    stream.write_all(setup).map_err(|e| e.to_string())?;
+    pump(stream, channel, stop, traffic)
+}
+
+/// 从通道读取完整的 X11 连接初始化包
+fn read_setup(channel: &mut Channel, stop: &AtomicBool) -> Result<Vec<u8>, String> {
+    let deadline = Instant::now() + SETUP_TIMEOUT;
+    let mut setup = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        if let Some(len) = setup_len(&setup)? {
+            if setup.len() >= len {
+                return Ok(setup);
+            }
+        }
+        if setup.len() > MAX_SETUP_LEN {
+            return Err("X11 初始化包过大".to_string());
+        }
+        if stop.load(Ordering::SeqCst) || Instant::now() > deadline {
+            return Err("等待 X11 初始化包超时".to_string());
+        }
+        match channel.read(&mut buf) {
+            Ok(0) if channel.eof() => return Err("X11 连接在初始化前关闭".to_string()),
+            Ok(0) => thread::sleep(POLL_INTERVAL),
+            Ok(n) => setup.extend_from_slice(&buf[..n]),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// 按字节序读取 u16
+fn read_u16(order: u8, bytes: &[u8]) -> u16 {
+    let pair = [bytes[0], bytes[1]];
+    if order == b'B' {
+        u16::from_be_bytes(pair)
+    } else {
+        u16::from_le_bytes(pair)
+    }
+}
+
+/// 按字节序写入 u16
+fn write_u16(order: u8, value: u16) -> [u8; 2] {
+    if order == b'B' {
+        value.to_be_bytes()
+    } else {
+        value.to_le_bytes()
+    }
+}
+
+/// 补齐到 4 字节
+fn pad4(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// 初始化包的总长度，包头不完整时返回 None
+fn setup_len(setup: &[u8]) -> Result<Option<usize>, String> {
+    if setup.len() < SETUP_HEADER_LEN {
+        return Ok(None);
+    }
+    let order = setup[0];
+    if order != b'B' && order != b'l' {
+        return Err("无效的 X11 初始化包".to_string());
+    }
+    let name_len = read_u16(order, &setup[6..8]) as usize;
+    let data_len = read_u16(order, &setup[8..10]) as usize;
+    Ok(Some(SETUP_HEADER_LEN + pad4(name_len) + pad4(data_len)))
+}
+
+/// 校验初始化包中的假 cookie，并替换为本机 cookie
+fn spoof_auth(setup: &[u8], fake: &[u8], real: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let len = setup_len(setup)?.ok_or_else(|| "X11 初始化包不完整".to_string())?;
+    if setup.len() < len {
+        return Err("X11 初始化包不完整".to_string());
+    }
+    let order = setup[0];
+    let name_len = read_u16(order, &setup[6..8]) as usize;
+    let data_len = read_u16(order, &setup[8..10]) as usize;
+    let data_start = SETUP_HEADER_LEN + pad4(name_len);
+    let name = &setup[SETUP_HEADER_LEN..SETUP_HEADER_LEN + name_len];
+    let data = &setup[data_start..data_start + data_len];
+    if name != X11_AUTH_PROTO.as_bytes() || data != fake {
+        return Err("X11 认证数据不匹配，已拒绝连接".to_string());
+    }
+
+    let (real_name, real_data): (&[u8], &[u8]) = match real {
+        Some(cookie) => (X11_AUTH_PROTO.as_bytes(), cookie),
+        None => (&[], &[]),
+    };
+    let mut spoofed = Vec::with_capacity(len);
+    spoofed.extend_from_slice(&setup[..6]);
+    spoofed.extend_from_slice(&write_u16(order, real_name.len() as u16));
+    spoofed.extend_from_slice(&write_u16(order, real_data.len() as u16));
+    spoofed.extend_from_slice(&setup[10..SETUP_HEADER_LEN]);
+    spoofed.extend_from_slice(real_name);
+    spoofed.resize(SETUP_HEADER_LEN + pad4(real_name.len()), 0);
+    spoofed.extend_from_slice(real_data);
+    spoofed.resize(
+        SETUP_HEADER_LEN + pad4(real_name.len()) + pad4(real_data.len()),
+        0,
+    );
+    // 初始化包之后已到达的数据原样保留
+    spoofed.extend_from_slice(&setup[len..]);
+    Ok(spoofed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造小端序的初始化包
+    fn build_setup(name: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut setup = vec![b'l', 0, 11, 0, 0, 0];
+        setup.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        setup.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        setup.extend_from_slice(&[0, 0]);
+        setup.extend_from_slice(name);
+        setup.resize(SETUP_HEADER_LEN + pad4(name.len()), 0);
+        setup.extend_from_slice(data);
+        setup.resize(SETUP_HEADER_LEN + pad4(name.len()) + pad4(data.len()), 0);
+        setup
+    }
+
+    #[test]
+    fn test_parse_local_display() {
+        let display = LocalDisplay::parse(":0").unwrap();
+        assert_eq!(display.host, None);
+        assert_eq!(display.number, 0);
+
+        let display = LocalDisplay::parse("localhost:10.0").unwrap();
+        assert_eq!(display.host.as_deref(), Some("localhost"));
+        assert_eq!(display.number, 10);
+
+        let display =
+            LocalDisplay::parse("/private/tmp/com.apple.launchd.abc/org.xquartz:0").unwrap();
+        assert_eq!(display.host, None);
+        assert_eq!(display.number, 0);
+
+        assert!(LocalDisplay::parse("wayland-0").is_none());
+    }
+
+    #[test]
+    fn test_parse_xauth_list() {
+        let output = "host/unix:0  MIT-MAGIC-COOKIE-1  0a0b0c0d\n";
+        assert_eq!(parse_xauth_list(output), Some(vec![0x0a, 0x0b, 0x0c, 0x0d]));
+        assert_eq!(
+            parse_xauth_list("host/unix:0  XDM-AUTHORIZATION-1  00\n"),
+            None
+        );
+        assert_eq!(parse_xauth_list(""), None);
+    }
+
+    #[test]
+    fn test_spoof_auth() {
+        let fake = [1u8; FAKE_COOKIE_LEN];
+        let real = [2u8; 16];
+        let setup = build_setup(X11_AUTH_PROTO.as_bytes(), &fake);
+        assert_eq!(setup_len(&setup).unwrap(), Some(setup.len()));
+
+        let spoofed = spoof_auth(&setup, &fake, Some(&real)).unwrap();
+        assert_eq!(spoofed, build_setup(X11_AUTH_PROTO.as_bytes(), &real));
+
+        // 本机 X 服务器无需认证时去掉认证数据
+        let spoofed = spoof_auth(&setup, &fake, None).unwrap();
+        assert_eq!(spoofed, build_setup(b"", b""));
+
+        // 假 cookie 不匹配时拒绝
+        let wrong = build_setup(X11_AUTH_PROTO.as_bytes(), &[3u8; FAKE_COOKIE_LEN]);
+        assert!(spoof_auth(&wrong, &fake, Some(&real)).is_err());
+    }
+
+    #[test]
+    fn test_x11_options_from_keywords() {
+        assert!(X11Options::from_keywords(&ConnKeywords::default()).is_none());
+
+        let keywords = ConnKeywords {
+            forward_x11: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            X11Options::from_keywords(&keywords),
+            Some(X11Options { trusted: false })
+        );
+
+        let keywords = ConnKeywords {
+            forward_x11: Some(true),
+            forward_x11_trusted: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            X11Options::from_keywords(&keywords),
+            Some(X11Options { trusted: true })
+        );
+    }
+}
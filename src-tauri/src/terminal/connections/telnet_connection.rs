@@ -0,0 +1,639 @@
+//! Telnet / 原始 TCP 连接模块
+//!
+//! 为网络设备（交换机、路由器、控制台服务器）提供 Telnet 和原始 TCP 终端连接。
+//!
+//! ## 功能
+//! - 解析 `telnet://host[:port]` 和 `tcp://host:port` 连接字符串
+//! - Telnet 选项协商：ECHO、SGA、TTYPE（xterm-256color）、NAWS（窗口大小）
+//! - 输入按 NVT 规则转义（IAC 加倍、单独的 CR 补 NUL）
+//! - 原始 TCP 直接透传字节流
+//!
+//! 连接建立后交给 `PtySession::with_stream` 承载，输出事件与本地终端一致。
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::terminal::error::TerminalError;
+use crate::terminal::pty_session::{IgnoreResize, StreamParts, TerminalResizer};
+
+/// Telnet 连接前缀
+pub const TELNET_CONN_PREFIX: &str = "telnet://";
+
+/// 原始 TCP 连接前缀
+pub const TCP_CONN_PREFIX: &str = "tcp://";
+
+/// 默认 Telnet 端口
+pub const DEFAULT_TELNET_PORT: u16 = 23;
+
+/// 连接超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 读取超时（读取线程据此检查会话是否已关闭）
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 上报给服务端的终端类型
+const TERMINAL_TYPE: &[u8] = b"XTERM-256COLOR";
+
+// Telnet 命令（RFC 854）
+const SE: u8 = 240;
+const SB: u8 = 250;
+const WILL: u8 = 251;
+const WONT: u8 = 252;
+const DO: u8 = 253;
+const DONT: u8 = 254;
+const IAC: u8 = 255;
+
+// Telnet 选项
+const OPT_ECHO: u8 = 1;
+const OPT_SGA: u8 = 3;
+const OPT_TTYPE: u8 = 24;
+const OPT_NAWS: u8 = 31;
+
+// TTYPE 子协商（RFC 1091）
+const TTYPE_IS: u8 = 0;
+const TTYPE_SEND: u8 = 1;
+
+// ============================================================================
+// Telnet 连接选项
+// ============================================================================
+
+/// Telnet / 原始 TCP 连接选项
+///
+/// ## 格式支持
+/// - `telnet://host` - 默认端口 23
+/// - `telnet://host:2323`
+/// - `tcp://host:port` - 原始 TCP，不做 Telnet 协商，端口必填
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelnetOpts {
+    /// 主机名或 IP 地址
+    pub host: String,
+    /// 端口
+    pub port: u16,
+    /// 是否为原始 TCP（不处理 Telnet 协议）
+    pub raw: bool,
+}
+
+impl TelnetOpts {
+    /// 从连接字符串解析 Telnet 选项
+    ///
+    /// # 参数
+    /// - `conn_str`: 连接字符串
+    ///
+    /// # 返回
+    /// - `Ok(TelnetOpts)`: 解析成功
+    /// - `Err(TerminalError)`: 解析失败
+    pub fn parse(conn_str: &str) -> Result<Self, TerminalError> {
+        let conn_str = conn_str.trim();
+        let strip = |prefix: &str| {
+            conn_str
+                .get(..prefix.len())
+                .filter(|head| head.eq_ignore_ascii_case(prefix))
+                .map(|_| &conn_str[prefix.len()..])
+        };
+        let (target, raw) = match (strip(TELNET_CONN_PREFIX), strip(TCP_CONN_PREFIX)) {
+            (Some(target), _) => (target, false),
+            (None, Some(target)) => (target, true),
+            (None, None) => {
+                return Err(TerminalError::TelnetConnectionFailed(format!(
+                    "无效的连接字符串，需要以 '{}' 或 '{}' 开头: {}",
+                    TELNET_CONN_PREFIX, TCP_CONN_PREFIX, conn_str
+                )))
+            }
+        };
+
+        let (host, port) = split_host_port(target.trim_end_matches('/'))?;
+        if host.is_empty() {
+            return Err(TerminalError::TelnetConnectionFailed(
+                "主机名不能为空".to_string(),
+            ));
+        }
+        let port = match (port, raw) {
+            (Some(port), _) => port,
+            (None, false) => DEFAULT_TELNET_PORT,
+            (None, true) => {
+                return Err(TerminalError::TelnetConnectionFailed(format!(
+                    "原始 TCP 连接需要指定端口: {}",
+                    conn_str
+                )))
+            }
+        };
+
+        Ok(Self { host, port, raw })
+    }
+
+    /// 转换为连接字符串
+    pub fn to_connection_string(&self) -> String {
+        let prefix = if self.raw {
+            TCP_CONN_PREFIX
+        } else {
+            TELNET_CONN_PREFIX
+        };
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if !self.raw && self.port == DEFAULT_TELNET_PORT {
+            format!("{}{}", prefix, host)
+        } else {
+            format!("{}{}:{}", prefix, host, self.port)
+        }
+    }
+}
+
+impl std::fmt::Display for TelnetOpts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_connection_string())
+    }
+}
+
+impl std::str::FromStr for TelnetOpts {
+    type Err = TerminalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// 拆分 `host`、`host:port`、`[v6]:port`
+fn split_host_port(target: &str) -> Result<(String, Option<u16>), TerminalError> {
+    let parse_port = |port: &str| {
+        port.parse::<u16>()
+            .map_err(|_| TerminalError::TelnetConnectionFailed(format!("无效的端口号: {}", port)))
+    };
+
+    if let Some(rest) = target.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']').ok_or_else(|| {
+            TerminalError::TelnetConnectionFailed(format!("无效的 IPv6 地址格式: {}", target))
+        })?;
+        let port = match rest.strip_prefix(':') {
+            Some(port) => Some(parse_port(port)?),
+            None if rest.is_empty() => None,
+            None => {
+                return Err(TerminalError::TelnetConnectionFailed(format!(
+                    "无效的 IPv6 地址格式: {}",
+                    target
+                )))
+            }
+        };
+        return Ok((host.to_string(), port));
+    }
+
+    match target.rsplit_once(':') {
+        // 多个冒号且没有方括号，视为完整的 IPv6 地址
+        Some((host, _)) if host.contains(':') => Ok((target.to_string(), None)),
+        Some((host, port)) => Ok((host.to_string(), Some(parse_port(port)?))),
+        None => Ok((target.to_string(), None)),
+    }
+}
+
+// ============================================================================
+// Telnet 协议
+// ============================================================================
+
+/// 协议解析状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParserState {
+    /// 普通数据
+    Data,
+    /// 收到 IAC
+    Iac,
+    /// 收到 IAC WILL/WONT/DO/DONT，等待选项
+    Negotiate(u8),
+    /// 子协商内容
+    Sub,
+    /// 子协商中收到 IAC
+    SubIac,
+}
+
+/// Telnet 协议状态机
+///
+/// 从收到的数据中剥离协商命令，返回终端数据和需要回复给服务端的字节。
+#[derive(Debug)]
+pub struct TelnetParser {
+    state: ParserState,
+    /// 子协商缓冲
+    sub: Vec<u8>,
+    /// 本端已启用的选项（WILL）
+    local: [bool; 256],
+    /// 对端已启用的选项（DO）
+    remote: [bool; 256],
+}
+
+impl Default for TelnetParser {
+    fn default() -> Self {
+        Self {
+            state: ParserState::Data,
+            sub: Vec::new(),
+            local: [false; 256],
+            remote: [false; 256],
+        }
+    }
+}
+
+impl TelnetParser {
+    /// 是否已协商窗口大小（NAWS）
+    pub fn naws_enabled(&self) -> bool {
+        self.local[OPT_NAWS as usize]
+    }
+
+    /// 处理收到的数据
+    ///
+    /// # 参数
+    /// - `input`: 从服务端读取的原始字节
+    /// - `size`: 当前终端大小 `(rows, cols)`，用于回复 NAWS
+    ///
+    /// # 返回
+    /// `(终端数据, 回复数据)`
+    pub fn feed(&mut self, input: &[u8], size: (u16, u16)) -> (Vec<u8>, Vec<u8>) {
+        let mut data = Vec::with_capacity(input.len());
+        let mut reply = Vec::new();
+
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (ParserState::Data, IAC) => ParserState::Iac,
+                (ParserState::Data, _) => {
+                    data.push(byte);
+                    ParserState::Data
+                }
+                (ParserState::Iac, IAC) => {
+                    data.push(IAC);
+                    ParserState::Data
+                }
+                (ParserState::Iac, WILL | WONT | DO | DONT) => ParserState::Negotiate(byte),
+                (ParserState::Iac, SB) => {
+                    self.sub.clear();
+                    ParserState::Sub
+                }
+                // NOP、GA 等其他命令忽略
+                (ParserState::Iac, _) => ParserState::Data,
+                (ParserState::Negotiate(command), option) => {
+                    self.negotiate(command, option, size, &mut reply);
+                    ParserState::Data
+                }
+                (ParserState::Sub, IAC) => ParserState::SubIac,
+                (ParserState::Sub, _) => {
+                    self.sub.push(byte);
+                    ParserState::Sub
+                }
+                (ParserState::SubIac, SE) => {
+                    self.subnegotiate(&mut reply);
+                    ParserState::Data
+                }
+                (ParserState::SubIac, _) => {
+                    self.sub.push(byte);
+                    ParserState::Sub
+                }
+            };
+        }
+
+        (data, reply)
+    }
+
+    /// 处理选项协商，只在状态变化时回复，避免协商循环
+    fn negotiate(&mut self, command: u8, option: u8, size: (u16, u16), reply: &mut Vec<u8>) {
+        let index = option as usize;
+        match command {
+            WILL => {
+                let accept = matches!(option, OPT_ECHO | OPT_SGA);
+                if accept != self.remote[index] || !accept {
+                    self.remote[index] = accept;
+                    reply.extend_from_slice(&[IAC, if accept { DO } else { DONT }, option]);
+                }
+            }
+            WONT => {
+                if self.remote[index] {
+                    self.remote[index] = false;
+                    reply.extend_from_slice(&[IAC, DONT, option]);
+                }
+            }
+            DO => {
+                let accept = matches!(option, OPT_SGA | OPT_TTYPE | OPT_NAWS);
+                if accept && !self.local[index] {
+                    self.local[index] = true;
+                    reply.extend_from_slice(&[IAC, WILL, option]);
+                } else if !accept {
+                    reply.extend_from_slice(&[IAC, WONT, option]);
+                }
+                if option == OPT_NAWS {
+                    reply.extend(naws_payload(size.0, size.1));
+                }
+            }
+            DONT => {
+                if self.local[index] {
+                    self.local[index] = false;
+                    reply.extend_from_slice(&[IAC, WONT, option]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 处理子协商
+    fn subnegotiate(&mut self, reply: &mut Vec<u8>) {
+        if self.sub.as_slice() == [OPT_TTYPE, TTYPE_SEND] {
+            reply.extend_from_slice(&[IAC, SB, OPT_TTYPE, TTYPE_IS]);
+            reply.extend_from_slice(TERMINAL_TYPE);
+            reply.extend_from_slice(&[IAC, SE]);
+        }
+        self.sub.clear();
+    }
+}
+
+/// 窗口大小子协商（RFC 1073）
+pub fn naws_payload(rows: u16, cols: u16) -> Vec<u8> {
+    let mut payload = vec![IAC, SB, OPT_NAWS];
+    for byte in cols.to_be_bytes().into_iter().chain(rows.to_be_bytes()) {
+        payload.push(byte);
+        if byte == IAC {
+            payload.push(IAC);
+        }
+    }
+    payload.extend_from_slice(&[IAC, SE]);
+    payload
+}
+
+/// 按 NVT 规则转义输入：IAC 加倍，后面不是 LF 的 CR 补 NUL
+pub fn encode_input(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(data.len() + 1);
+    for (i, &byte) in data.iter().enumerate() {
+        encoded.push(byte);
+        match byte {
+            IAC => encoded.push(IAC),
+            b'\r' if data.get(i + 1) != Some(&b'\n') => encoded.push(0),
+            _ => {}
+        }
+    }
+    encoded
+}
+
+// ============================================================================
+// Telnet 连接
+// ============================================================================
+
+/// Telnet 读写共享状态
+struct TelnetShared {
+    /// 写入端
+    stream: Mutex<TcpStream>,
+    /// 协议状态
+    parser: Mutex<TelnetParser>,
+    /// 当前终端大小 `(rows, cols)`
+    size: Mutex<(u16, u16)>,
+}
+
+impl TelnetShared {
+    fn send(&self, data: &[u8]) -> std::io::Result<()> {
+        let mut stream = self.stream.lock();
+        stream.write_all(data)?;
+        stream.flush()
+    }
+}
+
+/// Telnet 读取器：剥离协商命令并自动回复
+struct TelnetReader {
+    stream: TcpStream,
+    shared: Arc<TelnetShared>,
+    /// 已解析但尚未返回的终端数据
+    pending: Vec<u8>,
+}
+
+impl Read for TelnetReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut raw = [0u8; 4096];
+        while self.pending.is_empty() {
+            let n = self.stream.read(&mut raw)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let size = *self.shared.size.lock();
+            let (data, reply) = self.shared.parser.lock().feed(&raw[..n], size);
+            if !reply.is_empty() {
+                self.shared.send(&reply)?;
+            }
+            self.pending = data;
+        }
+
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+/// Telnet 写入器：按 NVT 规则转义输入
+struct TelnetWriter {
+    shared: Arc<TelnetShared>,
+}
+
+impl Write for TelnetWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.shared.send(&encode_input(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Telnet 窗口大小：已协商 NAWS 时通知服务端
+struct TelnetResizer {
+    shared: Arc<TelnetShared>,
+}
+
+impl TerminalResizer for TelnetResizer {
+    fn resize(&self, rows: u16, cols: u16) -> Result<(), TerminalError> {
+        *self.shared.size.lock() = (rows, cols);
+        if self.shared.parser.lock().naws_enabled() {
+            self.shared
+                .send(&naws_payload(rows, cols))
+                .map_err(|e| TerminalError::ResizeFailed(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Telnet / 原始 TCP 连接
+pub struct TelnetConn;
+
+impl TelnetConn {
+    /// 建立连接
+    ///
+    /// # 参数
+    /// - `opts`: 连接选项
+    /// - `rows`: 终端行数
+    /// - `cols`: 终端列数
+    ///
+    /// # 返回
+    /// 交给 `PtySession::with_stream` 的读取器、写入器和大小调整器
+    pub fn connect(opts: &TelnetOpts, rows: u16, cols: u16) -> Result<StreamParts, TerminalError> {
+        let map_err = |e: std::io::Error| TerminalError::TelnetConnectionFailed(e.to_string());
+
+        let addrs: Vec<_> = (opts.host.as_str(), opts.port)
+            .to_socket_addrs()
+            .map_err(map_err)?
+            .collect();
+        let mut last_error = None;
+        let stream = addrs
+            .iter()
+            .find_map(
+                |addr| match TcpStream::connect_timeout(addr, CONNECT_TIMEOUT) {
+                    Ok(stream) => Some(stream),
+                    Err(e) => {
+                        last_error = Some(e);
+                        None
+                    }
+                },
+            )
+            .ok_or_else(|| {
+                TerminalError::TelnetConnectionFailed(match last_error {
+                    Some(e) => format!("{}:{} - {}", opts.host, opts.port, e),
+                    None => format!("无法解析主机 {}", opts.host),
+                })
+            })?;
+        stream.set_nodelay(true).map_err(map_err)?;
+        stream
+            .set_read_timeout(Some(READ_TIMEOUT))
+            .map_err(map_err)?;
+
+        tracing::info!(
+            "[Telnet] 已连接 {}:{}{}",
+            opts.host,
+            opts.port,
+            if opts.raw { "（原始 TCP）" } else { "" }
+        );
+
+        let reader = stream.try_clone().map_err(map_err)?;
+        if opts.raw {
+            return Ok(StreamParts {
+                reader: Box::new(reader),
+                writer: Box::new(stream),
+                resizer: Box::new(IgnoreResize),
+            });
+        }
+
+        let shared = Arc::new(TelnetShared {
+            stream: Mutex::new(stream),
+            parser: Mutex::new(TelnetParser::default()),
+            size: Mutex::new((rows, cols)),
+        });
+        Ok(StreamParts {
+            reader: Box::new(TelnetReader {
+                stream: reader,
+                shared: shared.clone(),
+                pending: Vec::new(),
+            }),
+            writer: Box::new(TelnetWriter {
+                shared: shared.clone(),
+            }),
+            resizer: Box::new(TelnetResizer { shared }),
+        })
+    }
+}
+
+// ============================================================================
+// 辅助函数
+// ============================================================================
+
+/// 检查连接名称是否为 Telnet / 原始 TCP 连接
+pub fn is_telnet_conn_name(conn_name: &str) -> bool {
+    let conn_name = conn_name.trim().to_lowercase();
+    conn_name.starts_with(TELNET_CONN_PREFIX) || conn_name.starts_with(TCP_CONN_PREFIX)
+}
+
+// ============================================================================
+// 单元测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let opts = TelnetOpts::parse("telnet://switch01").unwrap();
+        assert_eq!(opts.host, "switch01");
+        assert_eq!(opts.port, DEFAULT_TELNET_PORT);
+        assert!(!opts.raw);
+
+        let opts = TelnetOpts::parse("tcp://[::1]:7000").unwrap();
+        assert_eq!(opts.host, "::1");
+        assert_eq!(opts.port, 7000);
+        assert!(opts.raw);
+
+        assert!(TelnetOpts::parse("tcp://host").is_err());
+        assert!(TelnetOpts::parse("telnet://").is_err());
+        assert!(TelnetOpts::parse("telnet://host:abc").is_err());
+        assert!(TelnetOpts::parse("ssh://host").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for conn in ["telnet://host", "telnet://host:2323", "tcp://[::1]:7000"] {
+            assert_eq!(
+                TelnetOpts::parse(conn).unwrap().to_connection_string(),
+                conn
+            );
+        }
+    }
+
+    #[test]
+    fn test_parser_strips_negotiation() {
+        let mut parser = TelnetParser::default();
+        let input = [b'h', IAC, WILL, OPT_ECHO, b'i', IAC, IAC, IAC, DO, 99];
+        let (data, reply) = parser.feed(&input, (24, 80));
+        assert_eq!(data, vec![b'h', b'i', IAC]);
+        assert_eq!(reply, vec![IAC, DO, OPT_ECHO, IAC, WONT, 99]);
+
+        // 重复的 WILL ECHO 不再回复
+        let (_, reply) = parser.feed(&[IAC, WILL, OPT_ECHO], (24, 80));
+        assert!(reply.is_empty());
+    }
+
+    #[test]
+    fn test_parser_naws_and_ttype() {
+        let mut parser = TelnetParser::default();
+        let (data, reply) = parser.feed(&[IAC, DO, OPT_NAWS], (24, 80));
+        assert!(data.is_empty());
+        let mut expected = vec![IAC, WILL, OPT_NAWS];
+        expected.extend(naws_payload(24, 80));
+        assert_eq!(reply, expected);
+        assert!(parser.naws_enabled());
+
+        // 子协商可以跨多次读取
+        let (_, reply) = parser.feed(&[IAC, DO, OPT_TTYPE, IAC, SB, OPT_TTYPE], (24, 80));
+        assert_eq!(reply, vec![IAC, WILL, OPT_TTYPE]);
+        let (_, reply) = parser.feed(&[TTYPE_SEND, IAC, SE], (24, 80));
+        let mut expected = vec![IAC, SB, OPT_TTYPE, TTYPE_IS];
+        expected.extend_from_slice(TERMINAL_TYPE);
+        expected.extend_from_slice(&[IAC, SE]);
+        assert_eq!(reply, expected);
+    }
+
+    #[test]
+    fn test_naws_payload_escapes_iac() {
+        assert_eq!(
+            naws_payload(255, 80),
+            vec![IAC, SB, OPT_NAWS, 0, 80, 0, IAC, IAC, IAC, SE]
+        );
+    }
+
+    #[test]
+    fn test_encode_input() {
+        assert_eq!(encode_input(b"ls\r"), b"ls\r\0".to_vec());
+        assert_eq!(encode_input(b"a\r\nb"), b"a\r\nb".to_vec());
+        assert_eq!(encode_input(&[IAC]), vec![IAC, IAC]);
+    }
+
+    #[test]
+    fn test_is_telnet_conn_name() {
+        assert!(is_telnet_conn_name("telnet://host"));
+        assert!(is_telnet_conn_name("TCP://host:7000"));
+        assert!(!is_telnet_conn_name("user@host"));
+    }
+}
@@ -406,6 +406,8 @@ impl WSLConn {
             wsh_error: self.wsh_error.read().clone(),
             no_wsh_reason: self.no_wsh_reason.read().clone(),
             wsh_version: self.wsh_version.read().clone(),
+            jump_hosts: Vec::new(),
+            reconnect_attempt: 0,
         }
     }
 
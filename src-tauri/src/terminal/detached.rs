@@ -0,0 +1,366 @@
+//! 后台会话（Detached Session）
+//!
+//! 普通终端会话的 PTY 随应用退出而结束。后台会话把 Shell 托管在独立的 tmux 服务进程中，
+//! 应用只作为 tmux 客户端连接；应用退出时客户端断开，Shell 继续运行，重启后重新连接。
+//!
+//! ## 功能
+//! - 检测 tmux 并生成专用配置（隐藏状态栏、禁用前缀键、禁用备用屏幕）
+//! - 创建/连接后台会话
+//! - 列出存活的后台会话（应用重启后恢复）
+//! - 读取 tmux 中的滚动历史，重连时回填到前端终端
+//!
+//! ## 设计说明
+//! - 使用独立的 tmux socket（`-L proxycast`），不影响用户自己的 tmux 会话
+//! - tmux 会话名为 `proxycast-{block_id}`，会话列表即可恢复的会话列表
+//! - 禁用备用屏幕后 tmux 输出直接进入 xterm.js 的滚动缓冲区
+//! - Windows 没有 tmux，后台会话不可用，创建时回退为普通会话
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use portable_pty::CommandBuilder;
+
+use super::error::TerminalError;
+use super::integration::ShellLaunchConfig;
+
+/// tmux socket 名称
+pub const DETACHED_SOCKET_NAME: &str = "proxycast";
+/// tmux 会话名前缀
+const SESSION_PREFIX: &str = "proxycast-";
+/// tmux 配置文件名
+const CONFIG_FILE_NAME: &str = "detached-tmux.conf";
+/// 后台会话保留的滚动历史行数
+pub const DETACHED_HISTORY_LIMIT: usize = 10_000;
+
+/// tmux 不在 PATH 中时尝试的位置（macOS 图形应用的 PATH 不包含 Homebrew 目录）
+#[cfg(unix)]
+const TMUX_FALLBACK_PATHS: &[&str] = &[
+    "/opt/homebrew/bin/tmux",
+    "/usr/local/bin/tmux",
+    "/usr/bin/tmux",
+];
+
+/// 存活的后台会话
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetachedSessionInfo {
+    /// 块 ID（会话 ID）
+    pub block_id: String,
+    /// 创建时间（Unix 时间戳，毫秒）
+    pub created_at: i64,
+    /// 当前工作目录
+    pub cwd: Option<String>,
+    /// 是否有客户端连接
+    pub attached: bool,
+}
+
+/// 后台会话后端（tmux）
+#[derive(Debug, Clone)]
+pub struct DetachedSessionBackend {
+    /// tmux 可执行文件
+    tmux: PathBuf,
+    /// tmux 配置文件
+    config_path: PathBuf,
+}
+
+impl DetachedSessionBackend {
+    /// 检测 tmux 并写入配置文件
+    ///
+    /// # 参数
+    /// - `config_dir`: 配置文件目录
+    ///
+    /// # 返回
+    /// tmux 不可用或配置写入失败时返回 `None`
+    pub fn detect(config_dir: &Path) -> Option<Self> {
+        let tmux = find_tmux()?;
+        let config_path = config_dir.join(CONFIG_FILE_NAME);
+        if let Err(e) = std::fs::create_dir_all(config_dir)
+            .and_then(|_| std::fs::write(&config_path, tmux_config()))
+        {
+            tracing::warn!("[终端] 写入后台会话 tmux 配置失败: {}", e);
+            return None;
+        }
+        Some(Self { tmux, config_path })
+    }
+
+    /// 会话 ID 对应的 tmux 会话名
+    pub fn session_name(block_id: &str) -> String {
+        format!("{}{}", SESSION_PREFIX, block_id)
+    }
+
+    /// 从 tmux 会话名解析会话 ID，不是本应用创建的会话时返回 `None`
+    pub fn block_id_from_session_name(name: &str) -> Option<&str> {
+        name.strip_prefix(SESSION_PREFIX)
+            .filter(|id| !id.is_empty())
+    }
+
+    /// 创建后台会话并连接的命令（会话已存在时直接连接）
+    ///
+    /// # 参数
+    /// - `block_id`: 块 ID
+    /// - `launch`: Shell 启动配置（参数直接传给 Shell，环境变量通过 `-e` 设置到会话中）
+    /// - `cwd`: 工作目录（可选）
+    pub fn new_session_command(
+        &self,
+        block_id: &str,
+        launch: &ShellLaunchConfig,
+        cwd: Option<&Path>,
+    ) -> CommandBuilder {
+        let mut cmd = self.base_command();
+        cmd.args(["new-session", "-A", "-s"]);
+        cmd.arg(Self::session_name(block_id));
+        if let Some(dir) = cwd {
+            cmd.arg("-c");
+            cmd.arg(dir);
+            cmd.cwd(dir);
+        }
+        for (key, value) in &launch.env {
+            cmd.arg("-e");
+            cmd.arg(format!("{}={}", key, value));
+        }
+        cmd.arg(&launch.shell_path);
+        cmd.args(&launch.args);
+        cmd
+    }
+
+    /// 连接已有后台会话的命令
+    pub fn attach_command(&self, block_id: &str) -> CommandBuilder {
+        let mut cmd = self.base_command();
+        cmd.args(["attach-session", "-t"]);
+        cmd.arg(format!("={}", Self::session_name(block_id)));
+        if let Some(home) = dirs::home_dir() {
+            cmd.cwd(home);
+        }
+        cmd
+    }
+
+    /// 列出存活的后台会话
+    pub fn list_sessions(&self) -> Result<Vec<DetachedSessionInfo>, TerminalError> {
+        let output = self.run(&[
+            "list-sessions",
+            "-F",
+            "#{session_name}\t#{session_created}\t#{pane_current_path}\t#{session_attached}",
+        ])?;
+        if !output.status.success() {
+            // 没有会话时 tmux 服务未启动，返回错误码
+            return Ok(Vec::new());
+        }
+        Ok(parse_list_sessions(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    /// 后台会话是否存活
+    pub fn has_session(&self, block_id: &str) -> bool {
+        let target = format!("={}", Self::session_name(block_id));
+        self.run(&["has-session", "-t", &target])
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    /// 读取后台会话的滚动历史（不含当前屏幕，当前屏幕由连接后的 tmux 重绘）
+    ///
+    /// # 参数
+    /// - `block_id`: 块 ID
+    /// - `lines`: 最多读取的行数
+    pub fn capture_scrollback(
+        &self,
+        block_id: &str,
+        lines: usize,
+    ) -> Result<Vec<u8>, TerminalError> {
+        let target = format!("={}:", Self::session_name(block_id));
+        let start = format!("-{}", lines);
+        let output = self.run(&[
+            "capture-pane",
+            "-p",
+            "-e",
+            "-J",
+            "-S",
+            &start,
+            "-E",
+            "-1",
+            "-t",
+            &target,
+        ])?;
+        if !output.status.success() {
+            return Err(TerminalError::DetachedSessionError(format!(
+                "读取滚动历史失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(normalize_capture(&output.stdout))
+    }
+
+    /// 结束后台会话
+    pub fn kill_session(&self, block_id: &str) -> Result<(), TerminalError> {
+        let target = format!("={}", Self::session_name(block_id));
+        let output = self.run(&["kill-session", "-t", &target])?;
+        if !output.status.success() {
+            tracing::debug!(
+                "[终端] 结束后台会话失败（可能已退出）: block_id={}, {}",
+                block_id,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// 带 socket 和配置参数的 tmux 命令
+    fn base_command(&self) -> CommandBuilder {
+        let mut cmd = CommandBuilder::new(&self.tmux);
+        cmd.args(["-L", DETACHED_SOCKET_NAME, "-f"]);
+        cmd.arg(&self.config_path);
+        cmd.env("TERM", "xterm-256color");
+        cmd
+    }
+
+    /// 执行 tmux 控制命令
+    fn run(&self, args: &[&str]) -> Result<Output, TerminalError> {
+        Command::new(&self.tmux)
+            .args(["-L", DETACHED_SOCKET_NAME, "-f"])
+            .arg(&self.config_path)
+            .args(args)
+            .output()
+            .map_err(|e| TerminalError::DetachedSessionError(format!("执行 tmux 失败: {}", e)))
+    }
+}
+
+/// 查找可用的 tmux
+#[cfg(unix)]
+fn find_tmux() -> Option<PathBuf> {
+    std::iter::once("tmux")
+        .chain(TMUX_FALLBACK_PATHS.iter().copied())
+        .map(PathBuf::from)
+        .find(|path| {
+            Command::new(path)
+                .arg("-V")
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        })
+}
+
+/// 查找可用的 tmux（Windows 不支持）
+#[cfg(not(unix))]
+fn find_tmux() -> Option<PathBuf> {
+    None
+}
+
+/// 后台会话专用的 tmux 配置
+///
+/// tmux 对终端应当是透明的：不显示状态栏、不拦截按键、不切换备用屏幕。
+/// 新版本才支持的选项使用 `-q`，旧版本忽略。
+fn tmux_config() -> String {
+    format!(
+        "set -g status off\n\
+         set -g history-limit {}\n\
+         set -g escape-time 0\n\
+         set -g default-terminal \"xterm-256color\"\n\
+         set -g set-titles off\n\
+         set -g mouse off\n\
+         set -g prefix None\n\
+         set -g prefix2 None\n\
+         unbind -a -T prefix\n\
+         unbind -a -T root\n\
+         set -ga terminal-overrides \",xterm*:smcup@:rmcup@\"\n\
+         set -gq allow-passthrough on\n\
+         set -gq set-clipboard on\n\
+         setw -g aggressive-resize on\n",
+        DETACHED_HISTORY_LIMIT
+    )
+}
+
+/// 解析 `list-sessions` 输出，跳过非本应用创建的会话
+fn parse_list_sessions(output: &str) -> Vec<DetachedSessionInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let block_id = DetachedSessionBackend::block_id_from_session_name(fields.next()?)?;
+            let created_at = fields
+                .next()
+                .and_then(|s| s.parse::<i64>().ok())
+                .map_or(0, |secs| secs * 1000);
+            let cwd = fields.next().filter(|s| !s.is_empty()).map(str::to_string);
+            let attached = fields
+                .next()
+                .and_then(|s| s.parse::<u32>().ok())
+                .is_some_and(|n| n > 0);
+            Some(DetachedSessionInfo {
+                block_id: block_id.to_string(),
+                created_at,
+                cwd,
+                attached,
+            })
+        })
+        .collect()
+}
+
+/// 将 `capture-pane` 输出转换为可直接写入终端的数据
+///
+/// 去掉末尾空行，换行转换为 `\r\n`，并在末尾重置文本属性。
+fn normalize_capture(data: &[u8]) -> Vec<u8> {
+    let text = String::from_utf8_lossy(data);
+    let lines: Vec<&str> = text.trim_end_matches(['\n', ' ']).lines().collect();
+    if lines.iter().all(|line| line.trim().is_empty()) {
+        return Vec::new();
+    }
+    let mut result = lines.join("\r\n").into_bytes();
+    result.extend_from_slice(b"\x1b[0m\r\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_name_roundtrip() {
+        let name = DetachedSessionBackend::session_name("abc-123");
+        assert_eq!(name, "proxycast-abc-123");
+        assert_eq!(
+            DetachedSessionBackend::block_id_from_session_name(&name),
+            Some("abc-123")
+        );
+        assert_eq!(
+            DetachedSessionBackend::block_id_from_session_name("work"),
+            None
+        );
+        assert_eq!(
+            DetachedSessionBackend::block_id_from_session_name("proxycast-"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_list_sessions() {
+        let output = "proxycast-a\t1700000000\t/home/user\t0\n\
+                      main\t1700000001\t/tmp\t1\n\
+                      proxycast-b\t1700000002\t\t1\n";
+        assert_eq!(
+            parse_list_sessions(output),
+            vec![
+                DetachedSessionInfo {
+                    block_id: "a".to_string(),
+                    created_at: 1_700_000_000_000,
+                    cwd: Some("/home/user".to_string()),
+                    attached: false,
+                },
+                DetachedSessionInfo {
+                    block_id: "b".to_string(),
+                    created_at: 1_700_000_002_000,
+                    cwd: None,
+                    attached: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_capture() {
+        assert_eq!(
+            normalize_capture(b"$ ls\n\x1b[34ma.txt\x1b[0m\n\n\n"),
+            b"$ ls\r\n\x1b[34ma.txt\x1b[0m\x1b[0m\r\n".to_vec()
+        );
+        assert!(normalize_capture(b"\n  \n").is_empty());
+    }
+}
@@ -58,10 +58,30 @@ pub enum TerminalError {
     #[error("SSH 认证失败: {0}")]
     SSHAuthFailed(String),
 
+    /// SSH 端口转发失败
+    #[error("SSH 端口转发失败: {0}")]
+    SSHForwardFailed(String),
+
     /// WSL 连接失败
     #[error("WSL 连接失败: {0}")]
     WSLConnectionFailed(String),
 
+    /// Telnet / TCP 连接失败
+    #[error("Telnet 连接失败: {0}")]
+    TelnetConnectionFailed(String),
+
+    /// 串口连接失败
+    #[error("串口连接失败: {0}")]
+    SerialConnectionFailed(String),
+
+    /// Docker 容器连接失败
+    #[error("Docker 连接失败: {0}")]
+    DockerConnectionFailed(String),
+
+    /// Kubernetes Pod 连接失败
+    #[error("Kubernetes 连接失败: {0}")]
+    KubernetesConnectionFailed(String),
+
     /// 无效的 OSC 序列
     #[error("无效的 OSC 序列: {0}")]
     InvalidOSCSequence(String),
@@ -78,6 +98,46 @@ pub enum TerminalError {
     #[error("主机密钥验证失败: {0}")]
     HostKeyVerificationFailed(String),
 
+    /// 回放不存在
+    #[error("回放不存在: {0}")]
+    ReplayNotFound(String),
+
+    /// 命令块不存在
+    #[error("命令块不存在: {0}")]
+    CommandBlockNotFound(u64),
+
+    /// 提示符标记不存在或没有可执行的命令
+    #[error("提示符标记不存在: {0}")]
+    PromptMarkNotFound(u64),
+
+    /// 后台会话错误
+    #[error("后台会话错误: {0}")]
+    DetachedSessionError(String),
+
+    /// 图片缓存错误
+    #[error("图片缓存错误: {0}")]
+    ImageCacheError(String),
+
+    /// 工作区不存在
+    #[error("工作区不存在: {0}")]
+    WorkspaceNotFound(String),
+
+    /// 无效的分屏布局
+    #[error("无效的分屏布局: {0}")]
+    InvalidLayout(String),
+
+    /// 无效的按键绑定
+    #[error("无效的按键绑定: {0}")]
+    InvalidKeyBinding(String),
+
+    /// 文件传输错误
+    #[error("文件传输错误: {0}")]
+    FileTransferError(String),
+
+    /// 会话分享不存在或已失效
+    #[error("会话分享不存在或已失效: {0}")]
+    ShareNotFound(String),
+
     /// 内部错误
     #[error("内部错误: {0}")]
     Internal(String),
@@ -7,11 +7,24 @@
 //! - `terminal:status` - 终端状态变化
 //! - `terminal:shell-integration` - Shell 集成状态变化
 //! - `terminal:clipboard-write` - 剪贴板写入请求
+//! - `terminal:clipboard-request` - 剪贴板访问需用户确认
+//! - `terminal:links` - 输出中检测到的链接（URL、文件路径、OSC 8 超链接）
+//! - `terminal:image` - 输出中解码的内联图片（sixel、iTerm2 OSC 1337）
+//! - `terminal:paste-warning` - 粘贴内容包含换行或控制字符，需用户确认
+//! - `terminal:output-flood` - 输出速率过高（洪泛）状态变化
+//! - `terminal:command-block` - 命令块开始或结束（命令、退出码、耗时、目录、Git 分支）
 //! - `terminal:conn-change` - 连接状态变化
+//! - `terminal:ssh-forward-change` - SSH 端口转发状态变化
+//! - `terminal:replay-output` - 会话回放输出
+//! - `terminal:replay-status` - 会话回放进度
 
 use serde::{Deserialize, Serialize};
 
-use crate::terminal::connections::ConnStatus;
+use crate::terminal::connections::{ConnStatus, ForwardStatus};
+use crate::terminal::integration::{
+    ClipboardOperation, CommandBlock, DetectedLink, ImageDimension, ImageProtocol, PasteWarning,
+};
+use crate::terminal::replay::ReplayStatus;
 
 /// 会话状态
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -59,6 +72,127 @@ pub struct TerminalStatusEvent {
     pub error: Option<String>,
 }
 
+/// 终端链接事件
+///
+/// Event name: `terminal:links`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalLinksEvent {
+    /// 会话 ID
+    pub session_id: String,
+    /// 当前工作目录（用于解析相对路径）
+    pub cwd: Option<String>,
+    /// 检测到的链接
+    pub links: Vec<DetectedLink>,
+}
+
+/// 终端内联图片事件
+///
+/// Event name: `terminal:image`
+///
+/// 在图片序列之前的输出事件之后发送，前端在当前光标位置显示图片。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalImageEvent {
+    /// 会话 ID
+    pub session_id: String,
+    /// 图片 ID（可通过 `terminal_image_get` 从缓存读取）
+    pub image_id: String,
+    /// 协议
+    pub protocol: ImageProtocol,
+    /// MIME 类型
+    pub mime: String,
+    /// 像素宽度
+    pub width: u32,
+    /// 像素高度
+    pub height: u32,
+    /// 文件名
+    pub name: Option<String>,
+    /// 显示宽度
+    pub display_width: Option<ImageDimension>,
+    /// 显示高度
+    pub display_height: Option<ImageDimension>,
+    /// 是否保持宽高比
+    pub preserve_aspect_ratio: bool,
+    /// 图片数据（Base64 编码）
+    pub data: String,
+}
+
+/// 粘贴确认事件
+///
+/// Event name: `terminal:paste-warning`
+///
+/// 粘贴内容包含换行或可疑控制字符时发送，前端通过 `terminal_paste_respond` 响应。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalPasteWarningEvent {
+    /// 粘贴 ID
+    pub paste_id: String,
+    /// 会话 ID
+    pub session_id: String,
+    /// 警告
+    pub warnings: Vec<PasteWarning>,
+    /// 换行数
+    pub line_breaks: usize,
+    /// 可疑控制字符数
+    pub control_chars: usize,
+    /// 终端程序是否启用括号粘贴模式（启用时换行不会立即执行）
+    pub bracketed: bool,
+    /// 内容大小（字节）
+    pub size: usize,
+    /// 内容预览（控制字符转换为 `^X` 形式）
+    pub preview: String,
+}
+
+/// 输出洪泛事件
+///
+/// Event name: `terminal:output-flood`
+///
+/// 输出速率超过阈值时发送 `flooding: true`，回落后发送 `flooding: false`；
+/// 前端可提示用户并通过 `terminal_interrupt_session` 中断前台进程。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalOutputFloodEvent {
+    /// 会话 ID
+    pub session_id: String,
+    /// 是否处于洪泛状态
+    pub flooding: bool,
+    /// 最近一秒的输出速率（字节/秒）
+    pub bytes_per_sec: u64,
+}
+
+/// 命令块事件
+///
+/// Event name: `terminal:command-block`
+///
+/// 命令开始执行和结束时各发送一次，前端按 `index` 更新块标题。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalCommandBlockEvent {
+    /// 会话 ID
+    pub session_id: String,
+    /// 命令块
+    pub block: CommandBlock,
+}
+
+/// 剪贴板访问确认事件
+///
+/// Event name: `terminal:clipboard-request`
+///
+/// 剪贴板策略为需确认时发送，前端通过 `terminal_clipboard_respond` 响应。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalClipboardRequestEvent {
+    /// 请求 ID
+    pub request_id: String,
+    /// 会话 ID
+    pub session_id: String,
+    /// 主机（连接名称，本地为 `local`）
+    pub host: String,
+    /// 操作类型
+    pub operation: ClipboardOperation,
+    /// OSC 52 选择类型
+    pub selection: String,
+    /// 写入内容大小（字节，仅写入请求）
+    pub size: Option<usize>,
+    /// 写入内容预览（仅写入请求）
+    pub preview: Option<String>,
+}
+
 /// 连接状态变更事件
 ///
 /// Event name: `terminal:conn-change`
@@ -72,6 +206,40 @@ pub struct ConnChangeEvent {
     pub status: ConnStatus,
 }
 
+/// SSH 端口转发状态变更事件
+///
+/// Event name: `terminal:ssh-forward-change`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SSHForwardChangeEvent {
+    /// 连接名称
+    pub connection: String,
+    /// 隧道状态
+    pub forward: ForwardStatus,
+}
+
+/// 会话回放输出事件
+///
+/// Event name: `terminal:replay-output`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalReplayOutputEvent {
+    /// 回放 ID
+    pub replay_id: String,
+    /// 输出数据（Base64 编码）
+    pub data: String,
+}
+
+/// 会话回放进度事件
+///
+/// Event name: `terminal:replay-status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalReplayStatusEvent {
+    /// 回放 ID
+    pub replay_id: String,
+    /// 回放进度
+    #[serde(flatten)]
+    pub status: ReplayStatus,
+}
+
 /// 事件名称常量
 pub mod event_names {
     /// 终端输出事件名
@@ -82,6 +250,24 @@ pub mod event_names {
     pub const SHELL_INTEGRATION_STATUS: &str = "terminal:shell-integration";
     /// 剪贴板写入事件名
     pub const CLIPBOARD_WRITE: &str = "terminal:clipboard-write";
+    /// 剪贴板访问确认事件名
+    pub const CLIPBOARD_REQUEST: &str = "terminal:clipboard-request";
+    /// 终端链接事件名
+    pub const TERMINAL_LINKS: &str = "terminal:links";
+    /// 终端内联图片事件名
+    pub const TERMINAL_IMAGE: &str = "terminal:image";
+    /// 粘贴确认事件名
+    pub const PASTE_WARNING: &str = "terminal:paste-warning";
+    /// 输出洪泛事件名
+    pub const OUTPUT_FLOOD: &str = "terminal:output-flood";
+    /// 命令块事件名
+    pub const COMMAND_BLOCK: &str = "terminal:command-block";
     /// 连接状态变更事件名
     pub const CONN_CHANGE: &str = "terminal:conn-change";
+    /// SSH 端口转发状态变更事件名
+    pub const SSH_FORWARD_CHANGE: &str = "terminal:ssh-forward-change";
+    /// 会话回放输出事件名
+    pub const TERMINAL_REPLAY_OUTPUT: &str = "terminal:replay-output";
+    /// 会话回放进度事件名
+    pub const TERMINAL_REPLAY_STATUS: &str = "terminal:replay-status";
 }
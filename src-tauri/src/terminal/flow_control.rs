@@ -0,0 +1,235 @@
+//! 输出流控
+//!
+//! 防止持续大量输出的进程（如 `yes`）占满事件通道导致界面卡死。
+//!
+//! ## 功能
+//! - 输出合并：发送线程合并连续的输出，限制输出事件的频率和单个事件的大小
+//! - 背压：读取线程与发送线程之间的队列有上限（高水位），队列满时读取线程阻塞、
+//!   不再读取 PTY，进程写满内核缓冲区后被挂起（效果等同 XOFF）
+//! - 洪泛检测：输出速率超过阈值时发送 `terminal:output-flood` 事件，
+//!   前端提示用户并可通过 `terminal_interrupt_session` 中断前台进程
+
+use std::time::{Duration, Instant};
+
+/// 输出事件的最小间隔（每秒最多约 60 个输出事件）
+pub const OUTPUT_EVENT_INTERVAL: Duration = Duration::from_millis(16);
+
+/// 单个输出事件的最大字节数
+pub const MAX_OUTPUT_EVENT_BYTES: usize = 64 * 1024;
+
+/// 读取线程与发送线程之间的队列上限（按读取次数计，每次最多 4KB，约 256KB）
+pub const OUTPUT_HIGH_WATER_CHUNKS: usize = 64;
+
+/// 进入洪泛状态的输出速率（字节/秒）
+pub const FLOOD_START_BYTES_PER_SEC: u64 = 1024 * 1024;
+
+/// 退出洪泛状态的输出速率（字节/秒）
+pub const FLOOD_END_BYTES_PER_SEC: u64 = 128 * 1024;
+
+/// 洪泛检测的统计窗口
+const FLOOD_WINDOW: Duration = Duration::from_secs(1);
+
+/// 输出合并器
+///
+/// 缓存待发送的输出，距上次发送不足 [`OUTPUT_EVENT_INTERVAL`] 时继续合并，
+/// 每次最多取出 [`MAX_OUTPUT_EVENT_BYTES`]。
+#[derive(Debug, Default)]
+pub struct OutputCoalescer {
+    pending: Vec<u8>,
+    last_emit: Option<Instant>,
+}
+
+impl OutputCoalescer {
+    /// 创建合并器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加待发送的输出
+    pub fn push(&mut self, data: &[u8]) {
+        self.pending.extend_from_slice(data);
+    }
+
+    /// 待发送的字节数
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 是否没有待发送的输出
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// 是否已积累满一个事件（满时不再接收新的输出，由队列产生背压）
+    pub fn is_full(&self) -> bool {
+        self.pending.len() >= MAX_OUTPUT_EVENT_BYTES
+    }
+
+    /// 距下次可发送的等待时间
+    pub fn wait_time(&self, now: Instant) -> Duration {
+        self.last_emit.map_or(Duration::ZERO, |last| {
+            (last + OUTPUT_EVENT_INTERVAL).saturating_duration_since(now)
+        })
+    }
+
+    /// 到达发送时间时取出待发送的输出（最多一个事件的大小）
+    pub fn take_due(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if self.pending.is_empty() || !self.wait_time(now).is_zero() {
+            return None;
+        }
+        self.last_emit = Some(now);
+        let len = self.pending.len().min(MAX_OUTPUT_EVENT_BYTES);
+        Some(self.pending.drain(..len).collect())
+    }
+
+    /// 立即取出全部待发送的输出（在图片、状态事件之前发送，保证顺序）
+    pub fn take_all(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        self.last_emit = Some(now);
+        Some(std::mem::take(&mut self.pending))
+    }
+}
+
+/// 洪泛状态变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloodChange {
+    /// 是否处于洪泛状态
+    pub flooding: bool,
+    /// 最近统计窗口的输出速率（字节/秒）
+    pub bytes_per_sec: u64,
+}
+
+/// 输出洪泛检测
+///
+/// 按 1 秒窗口统计输出速率，超过 [`FLOOD_START_BYTES_PER_SEC`] 时进入洪泛状态，
+/// 回落到 [`FLOOD_END_BYTES_PER_SEC`] 以下时退出（滞后区间避免状态抖动）。
+#[derive(Debug)]
+pub struct FloodDetector {
+    window_start: Instant,
+    window_bytes: u64,
+    flooding: bool,
+}
+
+impl FloodDetector {
+    /// 创建检测器，统计窗口从 `now` 开始
+    pub fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            window_bytes: 0,
+            flooding: false,
+        }
+    }
+
+    /// 是否处于洪泛状态
+    pub fn is_flooding(&self) -> bool {
+        self.flooding
+    }
+
+    /// 记录输出
+    pub fn record(&mut self, bytes: usize, now: Instant) -> Option<FloodChange> {
+        let change = self.tick(now);
+        self.window_bytes += bytes as u64;
+        change
+    }
+
+    /// 统计窗口结束时计算速率，洪泛状态变化时返回变化
+    pub fn tick(&mut self, now: Instant) -> Option<FloodChange> {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < FLOOD_WINDOW {
+            return None;
+        }
+        let bytes_per_sec = (self.window_bytes as f64 / elapsed.as_secs_f64()) as u64;
+        self.window_start = now;
+        self.window_bytes = 0;
+
+        let flooding = if self.flooding {
+            bytes_per_sec >= FLOOD_END_BYTES_PER_SEC
+        } else {
+            bytes_per_sec >= FLOOD_START_BYTES_PER_SEC
+        };
+        if flooding == self.flooding {
+            return None;
+        }
+        self.flooding = flooding;
+        Some(FloodChange {
+            flooding,
+            bytes_per_sec,
+        })
+    }
+
+    /// 距当前统计窗口结束的时间
+    pub fn wait_time(&self, now: Instant) -> Duration {
+        (self.window_start + FLOOD_WINDOW).saturating_duration_since(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coalescer_limits_event_rate() {
+        let start = Instant::now();
+        let mut coalescer = OutputCoalescer::new();
+
+        // 第一次输出立即发送
+        coalescer.push(b"a");
+        assert_eq!(coalescer.take_due(start), Some(b"a".to_vec()));
+
+        // 间隔内的输出合并到下一个事件
+        coalescer.push(b"b");
+        coalescer.push(b"c");
+        let soon = start + Duration::from_millis(5);
+        assert_eq!(coalescer.take_due(soon), None);
+        assert_eq!(coalescer.wait_time(soon), Duration::from_millis(11));
+        assert_eq!(
+            coalescer.take_due(start + OUTPUT_EVENT_INTERVAL),
+            Some(b"bc".to_vec())
+        );
+        assert!(coalescer.is_empty());
+    }
+
+    #[test]
+    fn test_coalescer_caps_event_size() {
+        let start = Instant::now();
+        let mut coalescer = OutputCoalescer::new();
+        coalescer.push(&vec![b'y'; MAX_OUTPUT_EVENT_BYTES + 10]);
+        assert!(coalescer.is_full());
+
+        assert_eq!(
+            coalescer.take_due(start).map(|data| data.len()),
+            Some(MAX_OUTPUT_EVENT_BYTES)
+        );
+        assert_eq!(coalescer.len(), 10);
+        assert!(!coalescer.is_full());
+
+        // 图片、状态事件之前立即发送剩余输出
+        assert_eq!(coalescer.take_all(start).map(|data| data.len()), Some(10));
+        assert_eq!(coalescer.take_all(start), None);
+    }
+
+    #[test]
+    fn test_flood_detector_hysteresis() {
+        let start = Instant::now();
+        let mut detector = FloodDetector::new(start);
+        let second = |n: u64| start + Duration::from_secs(n);
+
+        // 2 MB/s 进入洪泛状态
+        assert_eq!(detector.record(2 * 1024 * 1024, start), None);
+        let change = detector.tick(second(1)).unwrap();
+        assert!(change.flooding);
+        assert_eq!(change.bytes_per_sec, 2 * 1024 * 1024);
+
+        // 速率降到阈值之间时保持洪泛状态
+        detector.record(512 * 1024, second(1));
+        assert_eq!(detector.tick(second(2)), None);
+        assert!(detector.is_flooding());
+
+        // 没有输出后退出洪泛状态
+        let change = detector.tick(second(3)).unwrap();
+        assert!(!change.flooding);
+        assert_eq!(change.bytes_per_sec, 0);
+    }
+}
@@ -0,0 +1,594 @@
+//! OSC 52 剪贴板桥接
+//!
+//! 按主机的剪贴板策略处理终端程序（tmux、vim 等）通过 OSC 52 发起的剪贴板写入和读取。
+//!
+//! ## 功能
+//! - 写入：策略允许时写入本机剪贴板
+//! - 读取：策略允许时读取本机剪贴板，以 OSC 52 序列回复给终端程序
+//! - 需确认时发送 `terminal:clipboard-request` 事件，等待前端调用 `respond`
+//! - 拒绝或内容超过大小上限时忽略请求
+//!
+//! ## 设计说明
+//! 读取请求的回复通过会话注册的 `ClipboardResponder` 写入 PTY 输入。
+//! 用户响应时可选择记住决定，记住后更新该主机的剪贴板策略。
+//! 等待确认的请求按会话限制数量，超过有效期或会话关闭后丢弃（视为拒绝），
+//! 不会长期持有会话的 PTY 回复通道。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use uuid::Uuid;
+
+use super::osc_parser::OSCParser;
+use crate::terminal::error::TerminalError;
+use crate::terminal::events::{event_names, TerminalClipboardRequestEvent};
+use crate::terminal::persistence::{ClipboardAccess, ClipboardPolicy, ClipboardPolicyStore};
+
+/// 每个会话同时等待确认的请求上限（超过时直接拒绝该会话的新请求）
+const MAX_PENDING_PER_SESSION: usize = 4;
+
+/// 等待确认的请求的有效期（超时后丢弃，按拒绝处理）
+const PENDING_TTL: Duration = Duration::from_secs(60);
+
+/// 确认请求中预览内容的最大字符数
+const PREVIEW_CHARS: usize = 200;
+
+/// 向终端程序回复数据（写入 PTY 输入）
+pub type ClipboardResponder = Arc<dyn Fn(&[u8]) -> std::io::Result<()> + Send + Sync>;
+
+/// 本机剪贴板
+pub trait HostClipboard: Send + Sync {
+    /// 写入文本
+    fn set_text(&self, text: &str) -> Result<(), String>;
+    /// 读取文本
+    fn get_text(&self) -> Result<String, String>;
+}
+
+/// 系统剪贴板
+pub struct SystemClipboard;
+
+impl HostClipboard for SystemClipboard {
+    fn set_text(&self, text: &str) -> Result<(), String> {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|e| e.to_string())
+    }
+
+    fn get_text(&self) -> Result<String, String> {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// 剪贴板操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardOperation {
+    /// 写入本机剪贴板
+    Write,
+    /// 读取本机剪贴板
+    Read,
+}
+
+/// 剪贴板请求的处理结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardOutcome {
+    /// 已完成
+    Done,
+    /// 等待用户确认（请求 ID）
+    Prompted(String),
+    /// 被策略或用户拒绝
+    Denied,
+    /// 内容超过大小上限
+    TooLarge,
+    /// 访问剪贴板或回复终端失败
+    Failed(String),
+}
+
+/// 等待确认的操作
+enum PendingAction {
+    Write { content: String },
+    Read { responder: ClipboardResponder },
+}
+
+/// 等待确认的请求
+struct PendingRequest {
+    session_id: String,
+    host: String,
+    selection: String,
+    action: PendingAction,
+    created_at: Instant,
+}
+
+/// OSC 52 剪贴板桥接
+pub struct ClipboardBridge {
+    /// 剪贴板策略存储（未配置时使用默认策略，且不能记住决定）
+    store: Option<Arc<ClipboardPolicyStore>>,
+    /// 本机剪贴板
+    clipboard: Box<dyn HostClipboard>,
+    /// Tauri 应用句柄（可选）
+    app_handle: Option<tauri::AppHandle>,
+    /// 等待确认的请求（请求 ID -> 请求）
+    pending: Arc<Mutex<HashMap<String, PendingRequest>>>,
+}
+
+impl ClipboardBridge {
+    /// 创建使用系统剪贴板的剪贴板桥接
+    ///
+    /// # 参数
+    /// - `store`: 剪贴板策略存储
+    /// - `app_handle`: Tauri 应用句柄
+    pub fn new(store: Arc<ClipboardPolicyStore>, app_handle: tauri::AppHandle) -> Self {
+        Self {
+            store: Some(store),
+            clipboard: Box::new(SystemClipboard),
+            app_handle: Some(app_handle),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 创建使用指定剪贴板的剪贴板桥接
+    ///
+    /// # 参数
+    /// - `store`: 剪贴板策略存储（可选）
+    /// - `clipboard`: 本机剪贴板
+    pub fn with_clipboard(
+        store: Option<Arc<ClipboardPolicyStore>>,
+        clipboard: Box<dyn HostClipboard>,
+    ) -> Self {
+        Self {
+            store,
+            clipboard,
+            app_handle: None,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 获取主机生效的剪贴板策略
+    pub fn policy(&self, host: &str) -> ClipboardPolicy {
+        match self.store {
+            Some(ref store) => store.resolve(host),
+            None => ClipboardPolicy::default_for(host),
+        }
+    }
+
+    /// 处理写入请求（OSC 52 携带内容）
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `host`: 主机（连接名称，本地为 `local`）
+    /// - `selection`: OSC 52 选择类型
+    /// - `content`: 解码后的内容
+    pub fn handle_write(
+        &self,
+        session_id: &str,
+        host: &str,
+        selection: &str,
+        content: String,
+    ) -> ClipboardOutcome {
+        let policy = self.policy(host);
+        if content.len() > policy.max_bytes as usize {
+            tracing::warn!(
+                "[Clipboard] 写入内容超过上限，已忽略: host={}, len={}, max_bytes={}",
+                host,
+                content.len(),
+                policy.max_bytes
+            );
+            return ClipboardOutcome::TooLarge;
+        }
+
+        let request = PendingRequest {
+            session_id: session_id.to_string(),
+            host: host.to_string(),
+            selection: selection.to_string(),
+            action: PendingAction::Write { content },
+            created_at: Instant::now(),
+        };
+        match policy.write {
+            ClipboardAccess::Allow => self.execute(request, policy.max_bytes),
+            ClipboardAccess::Prompt => self.prompt(request),
+            ClipboardAccess::Deny => {
+                tracing::info!("[Clipboard] 策略拒绝写入剪贴板: host={}", host);
+                ClipboardOutcome::Denied
+            }
+        }
+    }
+
+    /// 处理读取请求（OSC 52 内容为 `?`）
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `host`: 主机（连接名称，本地为 `local`）
+    /// - `selection`: OSC 52 选择类型
+    /// - `responder`: 回复终端程序
+    pub fn handle_read(
+        &self,
+        session_id: &str,
+        host: &str,
+        selection: &str,
+        responder: ClipboardResponder,
+    ) -> ClipboardOutcome {
+        let policy = self.policy(host);
+        let request = PendingRequest {
+            session_id: session_id.to_string(),
+            host: host.to_string(),
+            selection: selection.to_string(),
+            action: PendingAction::Read { responder },
+            created_at: Instant::now(),
+        };
+        match policy.read {
+            ClipboardAccess::Allow => self.execute(request, policy.max_bytes),
+            ClipboardAccess::Prompt => self.prompt(request),
+            ClipboardAccess::Deny => {
+                tracing::info!("[Clipboard] 策略拒绝读取剪贴板: host={}", host);
+                ClipboardOutcome::Denied
+            }
+        }
+    }
+
+    /// 响应等待确认的请求
+    ///
+    /// # 参数
+    /// - `request_id`: 请求 ID
+    /// - `allow`: 是否允许
+    /// - `remember`: 是否记住决定（更新该主机对应操作的策略）
+    pub fn respond(
+        &self,
+        request_id: &str,
+        allow: bool,
+        remember: bool,
+    ) -> Result<ClipboardOutcome, TerminalError> {
+        self.prune_expired(Instant::now());
+        let request = self.pending.lock().remove(request_id).ok_or_else(|| {
+            TerminalError::Internal(format!("剪贴板请求不存在、已处理或已过期: {}", request_id))
+        })?;
+
+        let mut policy = self.policy(&request.host);
+        if remember {
+            let access = if allow {
+                ClipboardAccess::Allow
+            } else {
+                ClipboardAccess::Deny
+            };
+            match request.action {
+                PendingAction::Write { .. } => policy.write = access,
+                PendingAction::Read { .. } => policy.read = access,
+            }
+            match self.store {
+                Some(ref store) => policy = store.save(&policy)?,
+                None => tracing::warn!("[Clipboard] 未配置策略存储，无法记住决定"),
+            }
+        }
+
+        if !allow {
+            tracing::info!("[Clipboard] 用户拒绝剪贴板请求: host={}", request.host);
+            return Ok(ClipboardOutcome::Denied);
+        }
+        Ok(self.execute(request, policy.max_bytes))
+    }
+
+    /// 丢弃会话等待确认的请求（会话关闭时调用），返回丢弃的数量
+    pub fn cancel_session(&self, session_id: &str) -> usize {
+        let mut pending = self.pending.lock();
+        let before = pending.len();
+        pending.retain(|_, request| request.session_id != session_id);
+        let removed = before - pending.len();
+        if removed > 0 {
+            tracing::debug!(
+                "[Clipboard] 会话已关闭，丢弃等待确认的请求: session_id={}, count={}",
+                session_id,
+                removed
+            );
+        }
+        removed
+    }
+
+    /// 丢弃在 `now` 时已超过有效期的请求
+    fn prune_expired(&self, now: Instant) {
+        self.pending
+            .lock()
+            .retain(|_, request| now.duration_since(request.created_at) < PENDING_TTL);
+    }
+
+    /// 有效期结束后丢弃请求（用户一直未响应时释放回复通道）
+    fn schedule_expiry(&self, request_id: String) {
+        let pending = Arc::downgrade(&self.pending);
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(PENDING_TTL).await;
+            let expired = pending
+                .upgrade()
+                .is_some_and(|pending| pending.lock().remove(&request_id).is_some());
+            if expired {
+                tracing::debug!("[Clipboard] 剪贴板请求已过期: request_id={}", request_id);
+            }
+        });
+    }
+
+    /// 发送确认请求事件
+    fn prompt(&self, request: PendingRequest) -> ClipboardOutcome {
+        self.prune_expired(Instant::now());
+        let mut pending = self.pending.lock();
+        let session_pending = pending
+            .values()
+            .filter(|p| p.session_id == request.session_id)
+            .count();
+        if session_pending >= MAX_PENDING_PER_SESSION {
+            tracing::warn!(
+                "[Clipboard] 会话等待确认的请求过多，已拒绝: session_id={}, host={}",
+                request.session_id,
+                request.host
+            );
+            return ClipboardOutcome::Denied;
+        }
+
+        let request_id = Uuid::new_v4().to_string();
+        let (operation, size, preview) = match request.action {
+            PendingAction::Write { ref content } => (
+                ClipboardOperation::Write,
+                Some(content.len()),
+                Some(content.chars().take(PREVIEW_CHARS).collect()),
+            ),
+            PendingAction::Read { .. } => (ClipboardOperation::Read, None, None),
+        };
+        let event = TerminalClipboardRequestEvent {
+            request_id: request_id.clone(),
+            session_id: request.session_id.clone(),
+            host: request.host.clone(),
+            operation,
+            selection: request.selection.clone(),
+            size,
+            preview,
+        };
+        pending.insert(request_id.clone(), request);
+        drop(pending);
+
+        if let Some(ref app_handle) = self.app_handle {
+            if let Err(e) = app_handle.emit(event_names::CLIPBOARD_REQUEST, &event) {
+                tracing::warn!("[Clipboard] 发送确认请求失败: error={}", e);
+                self.pending.lock().remove(&request_id);
+                return ClipboardOutcome::Failed(e.to_string());
+            }
+            self.schedule_expiry(request_id.clone());
+        }
+        ClipboardOutcome::Prompted(request_id)
+    }
+
+    /// 执行剪贴板操作
+    fn execute(&self, request: PendingRequest, max_bytes: u32) -> ClipboardOutcome {
+        match request.action {
+            PendingAction::Write { content } => {
+                if let Err(e) = self.clipboard.set_text(&content) {
+                    tracing::warn!("[Clipboard] 写入剪贴板失败: error={}", e);
+                    return ClipboardOutcome::Failed(e);
+                }
+                tracing::debug!(
+                    "[Clipboard] 已写入剪贴板: host={}, len={}",
+                    request.host,
+                    content.len()
+                );
+                if let Some(ref app_handle) = self.app_handle {
+                    let _ = app_handle.emit(
+                        event_names::CLIPBOARD_WRITE,
+                        serde_json::json!({
+                            "block_id": request.session_id,
+                            "selection": request.selection,
+                            "content": content,
+                        }),
+                    );
+                }
+                ClipboardOutcome::Done
+            }
+            PendingAction::Read { responder } => {
+                let content = match self.clipboard.get_text() {
+                    Ok(content) => content,
+                    Err(e) => {
+                        tracing::warn!("[Clipboard] 读取剪贴板失败: error={}", e);
+                        return ClipboardOutcome::Failed(e);
+                    }
+                };
+                if content.len() > max_bytes as usize {
+                    tracing::warn!(
+                        "[Clipboard] 剪贴板内容超过上限，未回复: host={}, len={}, max_bytes={}",
+                        request.host,
+                        content.len(),
+                        max_bytes
+                    );
+                    return ClipboardOutcome::TooLarge;
+                }
+                let reply = OSCParser::encode_clipboard(&request.selection, &content);
+                if let Err(e) = responder(&reply) {
+                    tracing::warn!("[Clipboard] 回复剪贴板内容失败: error={}", e);
+                    return ClipboardOutcome::Failed(e.to_string());
+                }
+                tracing::debug!(
+                    "[Clipboard] 已回复剪贴板内容: host={}, len={}",
+                    request.host,
+                    content.len()
+                );
+                ClipboardOutcome::Done
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DbPool;
+    use crate::terminal::persistence::command_history::LOCAL_HOST;
+    use rusqlite::Connection;
+
+    /// 内存剪贴板
+    #[derive(Clone, Default)]
+    struct MemoryClipboard(Arc<Mutex<String>>);
+
+    impl HostClipboard for MemoryClipboard {
+        fn set_text(&self, text: &str) -> Result<(), String> {
+            *self.0.lock() = text.to_string();
+            Ok(())
+        }
+
+        fn get_text(&self) -> Result<String, String> {
+            Ok(self.0.lock().clone())
+        }
+    }
+
+    fn bridge() -> (ClipboardBridge, MemoryClipboard, Arc<ClipboardPolicyStore>) {
+        let db = Arc::new(DbPool::from_connection(
+            Connection::open_in_memory().unwrap(),
+        ));
+        let store = Arc::new(ClipboardPolicyStore::new(db));
+        store.init_tables().unwrap();
+        let clipboard = MemoryClipboard::default();
+        let bridge =
+            ClipboardBridge::with_clipboard(Some(store.clone()), Box::new(clipboard.clone()));
+        (bridge, clipboard, store)
+    }
+
+    fn recorder() -> (ClipboardResponder, Arc<Mutex<Vec<u8>>>) {
+        let replies = Arc::new(Mutex::new(Vec::new()));
+        let sink = replies.clone();
+        let responder: ClipboardResponder = Arc::new(move |data: &[u8]| {
+            sink.lock().extend_from_slice(data);
+            Ok(())
+        });
+        (responder, replies)
+    }
+
+    #[test]
+    fn test_local_write_allowed() {
+        let (bridge, clipboard, _) = bridge();
+        assert_eq!(
+            bridge.handle_write("s1", LOCAL_HOST, "c", "hello".to_string()),
+            ClipboardOutcome::Done
+        );
+        assert_eq!(clipboard.get_text().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_remote_write_prompt() {
+        let (bridge, clipboard, store) = bridge();
+        let ClipboardOutcome::Prompted(id) =
+            bridge.handle_write("s1", "root@prod", "c", "secret".to_string())
+        else {
+            panic!("expected prompt");
+        };
+        assert_eq!(clipboard.get_text().unwrap(), "");
+
+        assert_eq!(
+            bridge.respond(&id, true, true).unwrap(),
+            ClipboardOutcome::Done
+        );
+        assert_eq!(clipboard.get_text().unwrap(), "secret");
+        assert!(bridge.respond(&id, true, false).is_err());
+
+        // 记住决定后不再询问
+        assert_eq!(store.resolve("root@prod").write, ClipboardAccess::Allow);
+        assert_eq!(
+            bridge.handle_write("s1", "root@prod", "c", "again".to_string()),
+            ClipboardOutcome::Done
+        );
+    }
+
+    #[test]
+    fn test_size_limit_and_deny() {
+        let (bridge, clipboard, store) = bridge();
+        store
+            .save(&ClipboardPolicy {
+                max_bytes: 4,
+                ..ClipboardPolicy::default_for(LOCAL_HOST)
+            })
+            .unwrap();
+        assert_eq!(
+            bridge.handle_write("s1", LOCAL_HOST, "c", "too long".to_string()),
+            ClipboardOutcome::TooLarge
+        );
+
+        let (responder, replies) = recorder();
+        assert_eq!(
+            bridge.handle_read("s1", "root@prod", "c", responder),
+            ClipboardOutcome::Denied
+        );
+        assert!(replies.lock().is_empty());
+        assert_eq!(clipboard.get_text().unwrap(), "");
+    }
+
+    #[test]
+    fn test_read_reply() {
+        let (bridge, clipboard, _) = bridge();
+        clipboard.set_text("copied").unwrap();
+
+        let (responder, replies) = recorder();
+        let ClipboardOutcome::Prompted(id) = bridge.handle_read("s1", LOCAL_HOST, "c", responder)
+        else {
+            panic!("expected prompt");
+        };
+        assert_eq!(
+            bridge.respond(&id, true, false).unwrap(),
+            ClipboardOutcome::Done
+        );
+        assert_eq!(*replies.lock(), OSCParser::encode_clipboard("c", "copied"));
+
+        // 拒绝时不回复
+        let (responder, replies) = recorder();
+        let ClipboardOutcome::Prompted(id) = bridge.handle_read("s1", LOCAL_HOST, "c", responder)
+        else {
+            panic!("expected prompt");
+        };
+        assert_eq!(
+            bridge.respond(&id, false, false).unwrap(),
+            ClipboardOutcome::Denied
+        );
+        assert!(replies.lock().is_empty());
+    }
+
+    #[test]
+    fn test_pending_limit_per_session() {
+        let (bridge, _, _) = bridge();
+        for _ in 0..MAX_PENDING_PER_SESSION {
+            assert!(matches!(
+                bridge.handle_write("s1", "root@prod", "c", "x".to_string()),
+                ClipboardOutcome::Prompted(_)
+            ));
+        }
+        assert_eq!(
+            bridge.handle_write("s1", "root@prod", "c", "x".to_string()),
+            ClipboardOutcome::Denied
+        );
+        // 其他会话不受影响
+        assert!(matches!(
+            bridge.handle_write("s2", "root@prod", "c", "x".to_string()),
+            ClipboardOutcome::Prompted(_)
+        ));
+    }
+
+    #[test]
+    fn test_pending_dropped_on_close_and_expiry() {
+        let (bridge, _, _) = bridge();
+        let (responder, _) = recorder();
+        let ClipboardOutcome::Prompted(read_id) =
+            bridge.handle_read("s1", LOCAL_HOST, "c", responder.clone())
+        else {
+            panic!("expected prompt");
+        };
+        assert_eq!(Arc::strong_count(&responder), 2);
+
+        // 会话关闭后丢弃请求并释放回复通道
+        assert_eq!(bridge.cancel_session("s1"), 1);
+        assert_eq!(Arc::strong_count(&responder), 1);
+        assert!(bridge.respond(&read_id, true, false).is_err());
+
+        let ClipboardOutcome::Prompted(write_id) =
+            bridge.handle_write("s2", "root@prod", "c", "x".to_string())
+        else {
+            panic!("expected prompt");
+        };
+        bridge.prune_expired(Instant::now() + PENDING_TTL);
+        assert!(bridge.respond(&write_id, true, false).is_err());
+    }
+}
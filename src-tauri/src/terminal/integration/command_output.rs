@@ -0,0 +1,209 @@
+//! 命令输出提取
+//!
+//! 把命令块对应的原始输出（块文件中 OSC 133;C 与 D 之间的数据）转换为可复制的文本。
+//!
+//! ## 功能
+//! - 保留 ANSI 控制序列（颜色等），只去掉 OSC 序列
+//! - 转换为纯文本：去掉控制序列，处理回车覆盖（进度条）和退格
+
+use serde::{Deserialize, Serialize};
+
+use super::osc_parser::strip_osc_sequences;
+use super::shell_integration::CommandBlock;
+
+/// 命令输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandOutputFormat {
+    /// 纯文本（去掉 ANSI 控制序列）
+    #[default]
+    Plain,
+    /// 保留 ANSI 控制序列
+    Ansi,
+}
+
+/// 单条命令的输出
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandOutput {
+    /// 命令块序号
+    pub index: u64,
+    /// 命令文本
+    pub command: Option<String>,
+    /// 退出码
+    pub exit_code: Option<i32>,
+    /// 工作目录
+    pub cwd: Option<String>,
+    /// 输出格式
+    pub format: CommandOutputFormat,
+    /// 输出内容
+    pub output: String,
+    /// 命令是否已结束（未结束时为截至当前的输出）
+    pub finished: bool,
+    /// 输出开头是否已被块文件循环覆盖
+    pub truncated: bool,
+}
+
+impl CommandOutput {
+    /// 由命令块和从块文件读取的原始输出构建
+    ///
+    /// # 参数
+    /// - `block`: 命令块
+    /// - `data`: 原始输出
+    /// - `format`: 输出格式
+    /// - `truncated`: 输出开头是否已被覆盖
+    pub fn new(
+        block: &CommandBlock,
+        data: &[u8],
+        format: CommandOutputFormat,
+        truncated: bool,
+    ) -> Self {
+        let output = match format {
+            CommandOutputFormat::Plain => plain_text(data),
+            CommandOutputFormat::Ansi => {
+                String::from_utf8_lossy(&strip_osc_sequences(data)).into_owned()
+            }
+        };
+        Self {
+            index: block.index,
+            command: block.command.clone(),
+            exit_code: block.exit_code,
+            cwd: block.cwd.clone(),
+            format,
+            output,
+            finished: block.is_finished(),
+            truncated,
+        }
+    }
+}
+
+/// 把终端输出转换为纯文本
+///
+/// 去掉 CSI、OSC、DCS 等控制序列和控制字符；`\r\n` 按换行处理，单独的回车之后
+/// 有新内容时丢弃当前行已有的内容（进度条重绘），退格删除前一个字符。末尾的换行被去掉。
+pub fn plain_text(data: &[u8]) -> String {
+    let mut out: Vec<u8> = Vec::with_capacity(data.len());
+    let mut line_start = 0;
+    let mut pending_cr = false;
+    let mut i = 0;
+
+    while i < data.len() {
+        let byte = data[i];
+        i += 1;
+        match byte {
+            0x1b => i = skip_escape(data, i),
+            b'\r' => pending_cr = true,
+            b'\n' => {
+                pending_cr = false;
+                out.push(b'\n');
+                line_start = out.len();
+            }
+            0x08 => {
+                // 删除当前行的最后一个 UTF-8 字符
+                while out.len() > line_start {
+                    let last = out.pop().unwrap_or(0);
+                    if last & 0xc0 != 0x80 {
+                        break;
+                    }
+                }
+            }
+            b'\t' => {
+                take_pending_cr(&mut out, line_start, &mut pending_cr);
+                out.push(byte);
+            }
+            byte if byte < 0x20 || byte == 0x7f => {}
+            byte => {
+                take_pending_cr(&mut out, line_start, &mut pending_cr);
+                out.push(byte);
+            }
+        }
+    }
+
+    let text = String::from_utf8_lossy(&out);
+    text.trim_end_matches('\n').to_string()
+}
+
+/// 单独的回车之后出现新内容时，丢弃当前行已有的内容
+fn take_pending_cr(out: &mut Vec<u8>, line_start: usize, pending_cr: &mut bool) {
+    if std::mem::take(pending_cr) {
+        out.truncate(line_start);
+    }
+}
+
+/// 跳过 ESC 之后的控制序列，返回序列之后的位置
+fn skip_escape(data: &[u8], mut i: usize) -> usize {
+    let end = match data.get(i) {
+        // CSI: 参数和中间字节之后以 0x40-0x7E 结束
+        Some(b'[') => {
+            i += 1;
+            while i < data.len() && !(0x40..=0x7e).contains(&data[i]) {
+                i += 1;
+            }
+            i + 1
+        }
+        // OSC / DCS / SOS / PM / APC: 以 BEL（仅 OSC）或 ST 结束
+        Some(&kind @ (b']' | b'P' | b'X' | b'^' | b'_')) => {
+            i += 1;
+            while i < data.len() {
+                if kind == b']' && data[i] == 0x07 {
+                    return i + 1;
+                }
+                if data[i] == 0x1b && data.get(i + 1) == Some(&b'\\') {
+                    return i + 2;
+                }
+                i += 1;
+            }
+            i
+        }
+        // 其他序列：中间字节 0x20-0x2F 之后跟一个结束字节，如 `ESC ( B`
+        Some(_) => {
+            while i < data.len() && (0x20..=0x2f).contains(&data[i]) {
+                i += 1;
+            }
+            i + 1
+        }
+        None => i,
+    };
+    end.min(data.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(
+            plain_text(b"\x1b[1;32mok\x1b[0m\r\n\x1b]8;;http://a\x07link\x1b]8;;\x07\r\n"),
+            "ok\nlink"
+        );
+        // 进度条重绘只保留最后一次，末尾的回车不丢弃内容
+        assert_eq!(plain_text(b" 10%\r 50%\r100%\r\ndone\r"), "100%\ndone");
+        assert_eq!(plain_text(b"ab\x08c\xe4\xbd\xa0\x08!\x1b(B\x07"), "ac!");
+        assert_eq!(plain_text(b"a\tb\x1bP1;2|data\x1b\\"), "a\tb");
+    }
+
+    #[test]
+    fn test_command_output() {
+        let block = CommandBlock {
+            index: 3,
+            command: Some("ls".to_string()),
+            exit_code: Some(0),
+            start_time: 1,
+            end_time: Some(2),
+            duration_ms: Some(1),
+            cwd: Some("/tmp".to_string()),
+            git_branch: None,
+            output_start: 0,
+            output_end: Some(10),
+        };
+        let data = b"\x1b[34ma\x1b[0m\r\n\x1b]7;file:///tmp\x07";
+
+        let plain = CommandOutput::new(&block, data, CommandOutputFormat::Plain, false);
+        assert_eq!(plain.output, "a");
+        assert!(plain.finished);
+
+        let ansi = CommandOutput::new(&block, data, CommandOutputFormat::Ansi, true);
+        assert_eq!(ansi.output, "\x1b[34ma\x1b[0m\r\n");
+        assert!(ansi.truncated);
+    }
+}
@@ -0,0 +1,193 @@
+//! 终端文件传输的本机文件访问
+//!
+//! OSC 1337 下载和 zmodem 上传的协议由前端处理，本模块只负责本机文件的读写授权：
+//! - 上传：前端只能读取用户拖放到窗口或在文件对话框中选择的文件
+//! - 下载：保存路径由后端弹出的保存对话框决定，前端无法指定写入位置
+//!
+//! ## 设计说明
+//! 拖放授权来自窗口的原生拖放事件（`WindowEvent::DragDrop`），不接受前端传入的路径。
+//! 授权在有效期后失效，文件按块读取，单次最多 `MAX_READ_CHUNK` 字节。
+
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::terminal::error::TerminalError;
+
+/// 上传授权的有效期
+const GRANT_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// 单次读取的最大字节数
+pub const MAX_READ_CHUNK: usize = 1024 * 1024;
+
+/// 默认文件名
+const DEFAULT_FILE_NAME: &str = "download";
+
+/// 可上传文件的信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UploadFileInfo {
+    /// 本机路径（读取时使用）
+    pub path: String,
+    /// 文件名
+    pub name: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 修改时间（Unix 秒）
+    pub modified: u64,
+}
+
+/// 允许终端上传的本机文件
+#[derive(Default)]
+pub struct UploadGrants {
+    granted: Mutex<HashMap<PathBuf, Instant>>,
+}
+
+impl UploadGrants {
+    /// 创建空的授权表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 授权上传用户拖放或选择的文件，返回其中可读取的普通文件
+    ///
+    /// 目录和无法访问的路径被忽略
+    pub fn grant<I>(&self, paths: I) -> Vec<UploadFileInfo>
+    where
+        I: IntoIterator<Item = PathBuf>,
+    {
+        let now = Instant::now();
+        let mut granted = self.granted.lock();
+        granted.retain(|_, at| now.duration_since(*at) < GRANT_TTL);
+
+        let mut files = Vec::new();
+        for path in paths {
+            match file_info(&path) {
+                Ok(info) => {
+                    granted.insert(path, now);
+                    files.push(info);
+                }
+                Err(e) => tracing::debug!("[终端] 忽略无法上传的路径 {}: {}", path.display(), e),
+            }
+        }
+        files
+    }
+
+    /// 获取已授权文件的信息，未授权或无法访问的路径被忽略
+    pub fn files(&self, paths: &[String]) -> Vec<UploadFileInfo> {
+        paths
+            .iter()
+            .filter_map(|path| self.check(path).ok())
+            .filter_map(|path| file_info(&path).ok())
+            .collect()
+    }
+
+    /// 读取已授权文件从 `offset` 开始的最多 `len` 字节
+    pub fn read(&self, path: &str, offset: u64, len: usize) -> Result<Vec<u8>, TerminalError> {
+        let path = self.check(path)?;
+        let len = len.min(MAX_READ_CHUNK);
+        let mut file = std::fs::File::open(&path).map_err(io_error)?;
+        file.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+        let mut buf = Vec::with_capacity(len);
+        file.take(len as u64)
+            .read_to_end(&mut buf)
+            .map_err(io_error)?;
+        Ok(buf)
+    }
+
+    fn check(&self, path: &str) -> Result<PathBuf, TerminalError> {
+        let path = PathBuf::from(path);
+        match self.granted.lock().get(&path) {
+            Some(at) if at.elapsed() < GRANT_TTL => Ok(path),
+            _ => Err(TerminalError::FileTransferError(format!(
+                "文件未授权上传: {}",
+                path.display()
+            ))),
+        }
+    }
+}
+
+fn io_error(e: std::io::Error) -> TerminalError {
+    TerminalError::FileTransferError(e.to_string())
+}
+
+fn file_info(path: &Path) -> Result<UploadFileInfo, TerminalError> {
+    let metadata = std::fs::metadata(path).map_err(io_error)?;
+    if !metadata.is_file() {
+        return Err(TerminalError::FileTransferError("不是普通文件".to_string()));
+    }
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    Ok(UploadFileInfo {
+        path: path.to_string_lossy().into_owned(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        size: metadata.len(),
+        modified,
+    })
+}
+
+/// 去除远程指定的文件名中的路径部分
+pub fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or("").trim();
+    if base.is_empty() || base == "." || base == ".." {
+        DEFAULT_FILE_NAME.to_string()
+    } else {
+        base.to_string()
+    }
+}
+
+/// 解码传输文件的 Base64 内容（允许包含换行等空白字符）
+pub fn decode_file_data(data: &str) -> Result<Vec<u8>, TerminalError> {
+    let compact: String = data.split_ascii_whitespace().collect();
+    BASE64
+        .decode(compact.as_bytes())
+        .map_err(|e| TerminalError::Base64DecodeFailed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_granted_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, b"hello world").unwrap();
+        let other = dir.path().join("b.txt");
+        std::fs::write(&other, b"secret").unwrap();
+
+        let grants = UploadGrants::new();
+        let files = grants.grant(vec![file.clone(), dir.path().to_path_buf()]);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "a.txt");
+        assert_eq!(files[0].size, 11);
+
+        let path = file.to_string_lossy().into_owned();
+        assert_eq!(grants.read(&path, 6, 100).unwrap(), b"world");
+        assert_eq!(grants.files(std::slice::from_ref(&path)), files);
+
+        // 未拖放或选择的文件不可读取
+        let other = other.to_string_lossy().into_owned();
+        assert!(grants.read(&other, 0, 100).is_err());
+        assert!(grants.files(&[other]).is_empty());
+    }
+
+    #[test]
+    fn test_sanitize_and_decode() {
+        assert_eq!(sanitize_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_file_name("C:\\Users\\a.txt"), "a.txt");
+        assert_eq!(sanitize_file_name(".."), DEFAULT_FILE_NAME);
+        assert_eq!(decode_file_data("aGVs\nbG8=").unwrap(), b"hello");
+        assert!(decode_file_data("!!").is_err());
+    }
+}
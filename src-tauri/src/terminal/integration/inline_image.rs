@@ -0,0 +1,801 @@
+//! 终端内联图片解码
+//!
+//! 从终端输出流中提取并解码内联图片，供前端在终端中显示。
+//!
+//! ## 支持的协议
+//! - sixel：DCS `ESC P <P1>;<P2>;<P3> q <数据> ESC \`，解码为 RGBA 后编码为 PNG
+//! - iTerm2 OSC 1337 `File=...;inline=1:<Base64>`（imgcat 等），保留原始 PNG / JPEG / GIF / WebP 数据
+//! - iTerm2 OSC 1337 `MultipartFile=` / `FilePart=` / `FileEnd` 分片传输的内联图片
+//!
+//! ## 设计说明
+//! `InlineImageDecoder` 按会话保存跨读取块的未完成序列，只缓存可能是图片的序列
+//! （`1337;` 开头的 OSC 和 sixel DCS），其他序列只跟踪结束位置。
+//! 每张图片返回其序列在当前读取块中的结束位置，调用方据此拆分输出事件，
+//! 保证前端按输出顺序放置图片。
+//!
+//! 非内联（`inline=0`）的 OSC 1337 文件由前端按文件传输处理，这里忽略。
+
+use std::io::Cursor;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::{DynamicImage, ImageFormat, ImageReader, RgbaImage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 单个图片序列的最大长度（编码后字节数），超过时丢弃该序列
+pub const MAX_IMAGE_SEQUENCE_LEN: usize = 16 * 1024 * 1024;
+
+/// sixel 图片的最大边长（像素），超出部分被裁剪
+pub const MAX_SIXEL_DIMENSION: usize = 2048;
+
+/// iTerm2 内联图片的最大边长（像素）
+pub const MAX_IMAGE_DIMENSION: u32 = 8192;
+
+/// 判断是否需要缓存 OSC 内容时检查的前缀
+const ITERM_OSC_PREFIX: &[u8] = b"1337;";
+
+/// VT340 默认调色板（RGB 百分比）
+const VT340_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+/// 内联图片协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageProtocol {
+    /// sixel 图形
+    Sixel,
+    /// iTerm2 内联图片（OSC 1337）
+    Iterm,
+}
+
+/// 图片显示尺寸（iTerm2 `width=` / `height=` 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "unit", content = "value", rename_all = "lowercase")]
+pub enum ImageDimension {
+    /// 字符单元数
+    Cells(u32),
+    /// 像素
+    Pixels(u32),
+    /// 终端宽度或高度的百分比
+    Percent(u32),
+}
+
+impl ImageDimension {
+    /// 解析尺寸参数（`auto` 或无法识别时返回 `None`）
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Some(px) = value.strip_suffix("px") {
+            px.parse().ok().map(Self::Pixels)
+        } else if let Some(percent) = value.strip_suffix('%') {
+            percent.parse().ok().map(Self::Percent)
+        } else {
+            value.parse().ok().map(Self::Cells)
+        }
+    }
+}
+
+/// 解码后的内联图片
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineImage {
+    /// 图片 ID（图片数据的 SHA-256）
+    pub id: String,
+    /// 协议
+    pub protocol: ImageProtocol,
+    /// MIME 类型
+    pub mime: String,
+    /// 像素宽度
+    pub width: u32,
+    /// 像素高度
+    pub height: u32,
+    /// 文件名（iTerm2 `name=` 参数）
+    pub name: Option<String>,
+    /// 显示宽度（未指定时按像素宽度显示）
+    pub display_width: Option<ImageDimension>,
+    /// 显示高度（未指定时按像素高度显示）
+    pub display_height: Option<ImageDimension>,
+    /// 是否保持宽高比
+    pub preserve_aspect_ratio: bool,
+    /// 图片数据
+    pub data: Vec<u8>,
+}
+
+/// 从读取块中提取的图片
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedImage {
+    /// 图片序列在读取块中的结束位置（不含）
+    pub end: usize,
+    /// 图片
+    pub image: InlineImage,
+}
+
+/// 序列解析状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// 普通输出
+    Ground,
+    /// 收到 ESC
+    Escape,
+    /// OSC 内容
+    Osc,
+    /// OSC 内容中收到 ESC
+    OscEscape,
+    /// DCS 内容
+    Dcs,
+    /// DCS 内容中收到 ESC
+    DcsEscape,
+}
+
+/// 进行中的 iTerm2 分片传输
+struct Multipart {
+    /// `MultipartFile=` 参数
+    args: String,
+    /// 已收到的 Base64 数据
+    data: String,
+}
+
+/// 内联图片解码器
+///
+/// 每个终端会话持有一个，按输出顺序调用 `feed`。
+pub struct InlineImageDecoder {
+    /// 解析状态
+    state: State,
+    /// 是否缓存当前序列的内容
+    capture: bool,
+    /// 当前序列是否已确认为图片序列（OSC 前缀或 sixel 参数已匹配）
+    matched: bool,
+    /// 当前序列的内容（不含引导符和结束符）
+    buffer: Vec<u8>,
+    /// 进行中的分片传输
+    multipart: Option<Multipart>,
+}
+
+impl Default for InlineImageDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InlineImageDecoder {
+    /// 创建解码器
+    pub fn new() -> Self {
+        Self {
+            state: State::Ground,
+            capture: false,
+            matched: false,
+            buffer: Vec::new(),
+            multipart: None,
+        }
+    }
+
+    /// 处理一块输出，返回其中结束的图片序列
+    ///
+    /// # 参数
+    /// - `data`: 输出数据
+    pub fn feed(&mut self, data: &[u8]) -> Vec<ExtractedImage> {
+        let mut images = Vec::new();
+        for (i, &byte) in data.iter().enumerate() {
+            let image = match self.state {
+                State::Ground => {
+                    if byte == 0x1b {
+                        self.state = State::Escape;
+                    }
+                    None
+                }
+                State::Escape => {
+                    self.escape(byte);
+                    None
+                }
+                State::Osc => match byte {
+                    0x07 => self.finish_osc(),
+                    0x1b => {
+                        self.state = State::OscEscape;
+                        None
+                    }
+                    0x18 | 0x1a => self.abort(),
+                    _ => {
+                        self.push(byte);
+                        None
+                    }
+                },
+                State::Dcs => match byte {
+                    0x1b => {
+                        self.state = State::DcsEscape;
+                        None
+                    }
+                    0x18 | 0x1a => self.abort(),
+                    _ => {
+                        self.push(byte);
+                        None
+                    }
+                },
+                State::OscEscape | State::DcsEscape if byte == b'\\' => {
+                    if self.state == State::OscEscape {
+                        self.finish_osc()
+                    } else {
+                        self.finish_dcs()
+                    }
+                }
+                State::OscEscape | State::DcsEscape => {
+                    // 序列被新的转义序列打断
+                    self.abort();
+                    self.escape(byte);
+                    None
+                }
+            };
+            if let Some(image) = image {
+                images.push(ExtractedImage { end: i + 1, image });
+            }
+        }
+        images
+    }
+
+    /// 重置状态（丢弃未完成的序列）
+    pub fn reset(&mut self) {
+        self.abort();
+        self.multipart = None;
+    }
+
+    /// 处理 ESC 之后的字节
+    fn escape(&mut self, byte: u8) {
+        match byte {
+            b']' => self.begin(State::Osc),
+            b'P' => self.begin(State::Dcs),
+            0x1b => self.state = State::Escape,
+            _ => self.state = State::Ground,
+        }
+    }
+
+    /// 开始 OSC 或 DCS 序列
+    fn begin(&mut self, state: State) {
+        self.state = state;
+        self.capture = true;
+        self.matched = false;
+        self.buffer.clear();
+    }
+
+    /// 放弃当前序列
+    fn abort(&mut self) -> Option<InlineImage> {
+        self.state = State::Ground;
+        self.capture = false;
+        self.matched = false;
+        self.buffer.clear();
+        None
+    }
+
+    /// 缓存序列内容，确定不是图片序列后停止缓存
+    fn push(&mut self, byte: u8) {
+        if !self.capture {
+            return;
+        }
+        self.buffer.push(byte);
+        if !self.matched {
+            let is_image = match self.state {
+                State::Osc => {
+                    let index = self.buffer.len() - 1;
+                    self.matched = index + 1 == ITERM_OSC_PREFIX.len();
+                    ITERM_OSC_PREFIX.get(index) == Some(&byte)
+                }
+                // sixel 的参数只包含数字和分号，以 `q` 结束
+                _ => {
+                    self.matched = byte == b'q';
+                    self.matched || byte.is_ascii_digit() || byte == b';'
+                }
+            };
+            if !is_image {
+                self.capture = false;
+                self.matched = false;
+                self.buffer.clear();
+            }
+        } else if self.buffer.len() > MAX_IMAGE_SEQUENCE_LEN {
+            tracing::warn!("[InlineImage] 图片序列超过大小上限，已丢弃");
+            self.capture = false;
+            self.matched = false;
+            self.buffer.clear();
+        }
+    }
+
+    /// OSC 序列结束
+    fn finish_osc(&mut self) -> Option<InlineImage> {
+        let matched = self.capture && self.matched;
+        let buffer = std::mem::take(&mut self.buffer);
+        self.abort();
+        if !matched {
+            return None;
+        }
+        let content = String::from_utf8_lossy(&buffer[ITERM_OSC_PREFIX.len()..]);
+        self.handle_iterm(&content)
+    }
+
+    /// DCS 序列结束
+    fn finish_dcs(&mut self) -> Option<InlineImage> {
+        let matched = self.capture && self.matched;
+        let buffer = std::mem::take(&mut self.buffer);
+        self.abort();
+        if !matched {
+            return None;
+        }
+        let split = buffer.iter().position(|b| *b == b'q')?;
+        let pixels = decode_sixel(&buffer[..split], &buffer[split + 1..])?;
+        let (width, height) = pixels.dimensions();
+        let mut png = Vec::new();
+        if let Err(e) =
+            DynamicImage::ImageRgba8(pixels).write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        {
+            tracing::warn!("[InlineImage] sixel 图片编码失败: {}", e);
+            return None;
+        }
+        Some(InlineImage {
+            id: image_id(&png),
+            protocol: ImageProtocol::Sixel,
+            mime: "image/png".to_string(),
+            width,
+            height,
+            name: None,
+            display_width: None,
+            display_height: None,
+            preserve_aspect_ratio: true,
+            data: png,
+        })
+    }
+
+    /// 处理 OSC 1337 内容
+    fn handle_iterm(&mut self, content: &str) -> Option<InlineImage> {
+        if let Some(file) = content.strip_prefix("File=") {
+            let (args, data) = file.split_once(':')?;
+            return decode_iterm_image(args, data);
+        }
+        if let Some(args) = content.strip_prefix("MultipartFile=") {
+            self.multipart = Some(Multipart {
+                args: args.to_string(),
+                data: String::new(),
+            });
+            return None;
+        }
+        if let Some(part) = content.strip_prefix("FilePart=") {
+            let multipart = self.multipart.as_mut()?;
+            if multipart.data.len() + part.len() > MAX_IMAGE_SEQUENCE_LEN {
+                tracing::warn!("[InlineImage] 分片传输超过大小上限，已丢弃");
+                self.multipart = None;
+                return None;
+            }
+            multipart.data.push_str(part);
+            return None;
+        }
+        if content == "FileEnd" {
+            let multipart = self.multipart.take()?;
+            return decode_iterm_image(&multipart.args, &multipart.data);
+        }
+        None
+    }
+}
+
+/// 解码 iTerm2 内联图片
+///
+/// # 参数
+/// - `args`: `key=value` 参数（以 `;` 分隔）
+/// - `data`: Base64 编码的图片数据
+///
+/// # 返回
+/// 非内联文件或无法识别的图片返回 `None`
+pub fn decode_iterm_image(args: &str, data: &str) -> Option<InlineImage> {
+    let mut inline = false;
+    let mut name = None;
+    let mut display_width = None;
+    let mut display_height = None;
+    let mut preserve_aspect_ratio = true;
+    for (key, value) in args.split(';').filter_map(|arg| arg.split_once('=')) {
+        match key {
+            "inline" => inline = value == "1",
+            "name" => {
+                name = BASE64
+                    .decode(value)
+                    .ok()
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            }
+            "width" => display_width = ImageDimension::parse(value),
+            "height" => display_height = ImageDimension::parse(value),
+            "preserveAspectRatio" => preserve_aspect_ratio = value != "0",
+            _ => {}
+        }
+    }
+    if !inline {
+        return None;
+    }
+
+    let compact: String = data.split_ascii_whitespace().collect();
+    let bytes = match BASE64.decode(compact.as_bytes()) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("[InlineImage] 内联图片 Base64 解码失败: {}", e);
+            return None;
+        }
+    };
+    let reader = ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .ok()?;
+    let mime = match reader.format()? {
+        ImageFormat::Png => "image/png",
+        ImageFormat::Jpeg => "image/jpeg",
+        ImageFormat::Gif => "image/gif",
+        ImageFormat::WebP => "image/webp",
+        format => {
+            tracing::warn!("[InlineImage] 不支持的内联图片格式: {:?}", format);
+            return None;
+        }
+    };
+    let (width, height) = match reader.into_dimensions() {
+        Ok(dimensions) => dimensions,
+        Err(e) => {
+            tracing::warn!("[InlineImage] 读取内联图片尺寸失败: {}", e);
+            return None;
+        }
+    };
+    if width == 0 || height == 0 || width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+        tracing::warn!(
+            "[InlineImage] 内联图片尺寸超出范围，已忽略: {}x{}",
+            width,
+            height
+        );
+        return None;
+    }
+
+    Some(InlineImage {
+        id: image_id(&bytes),
+        protocol: ImageProtocol::Iterm,
+        mime: mime.to_string(),
+        width,
+        height,
+        name,
+        display_width,
+        display_height,
+        preserve_aspect_ratio,
+        data: bytes,
+    })
+}
+
+/// 解码 sixel 图形
+///
+/// 未绘制的像素保持透明，超出 `MAX_SIXEL_DIMENSION` 的部分被裁剪。
+///
+/// # 参数
+/// - `params`: DCS 参数（`P1;P2;P3`，不含 `q`）
+/// - `data`: `q` 之后的 sixel 数据
+///
+/// # 返回
+/// 没有任何像素且没有声明尺寸时返回 `None`
+pub fn decode_sixel(params: &[u8], data: &[u8]) -> Option<RgbaImage> {
+    // P2（背景选择）不影响解码结果：未绘制的像素始终透明，由终端背景透出
+    let _ = params;
+
+    let mut palette = [[0u8, 0, 0, 255]; 256];
+    for (color, rgb) in palette.iter_mut().zip(VT340_PALETTE.iter()) {
+        *color = [
+            percent_to_u8(rgb[0] as u32),
+            percent_to_u8(rgb[1] as u32),
+            percent_to_u8(rgb[2] as u32),
+            255,
+        ];
+    }
+
+    let mut canvas = SixelCanvas::default();
+    let mut color = palette[0];
+    let (mut x, mut band) = (0usize, 0usize);
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        i += 1;
+        match byte {
+            b'"' => {
+                let (values, next) = parse_numbers(data, i);
+                i = next;
+                if let (Some(&w), Some(&h)) = (values.get(2), values.get(3)) {
+                    canvas.declare(w as usize, h as usize);
+                }
+            }
+            b'#' => {
+                let (values, next) = parse_numbers(data, i);
+                i = next;
+                let Some(&index) = values.first() else {
+                    continue;
+                };
+                let index = index as usize % palette.len();
+                if let [_, space, a, b, c] = values[..] {
+                    palette[index] = match space {
+                        1 => hls_to_rgba(a, b, c),
+                        _ => [percent_to_u8(a), percent_to_u8(b), percent_to_u8(c), 255],
+                    };
+                }
+                color = palette[index];
+            }
+            b'!' => {
+                let (values, next) = parse_numbers(data, i);
+                i = next;
+                let count = values.first().copied().unwrap_or(1).max(1) as usize;
+                if let Some(&sixel) = data.get(i).filter(|b| (0x3f..=0x7e).contains(*b)) {
+                    i += 1;
+                    for _ in 0..count.min(MAX_SIXEL_DIMENSION) {
+                        canvas.draw(x, band, sixel - 0x3f, color);
+                        x += 1;
+                    }
+                }
+            }
+            b'$' => x = 0,
+            b'-' => {
+                x = 0;
+                band += 1;
+            }
+            0x3f..=0x7e => {
+                canvas.draw(x, band, byte - 0x3f, color);
+                x += 1;
+            }
+            _ => {}
+        }
+    }
+    canvas.into_image()
+}
+
+/// sixel 画布（按需扩大）
+#[derive(Default)]
+struct SixelCanvas {
+    /// 画布宽度
+    width: usize,
+    /// 画布高度
+    height: usize,
+    /// 像素（RGBA，按行存储）
+    pixels: Vec<[u8; 4]>,
+}
+
+impl SixelCanvas {
+    /// 按光栅属性声明的尺寸扩大画布
+    fn declare(&mut self, width: usize, height: usize) {
+        self.grow(
+            width.min(MAX_SIXEL_DIMENSION),
+            height.min(MAX_SIXEL_DIMENSION),
+        );
+    }
+
+    /// 在第 `band` 个 6 像素高的条带中绘制一列
+    fn draw(&mut self, x: usize, band: usize, bits: u8, color: [u8; 4]) {
+        if bits == 0 || x >= MAX_SIXEL_DIMENSION {
+            return;
+        }
+        let top = band * 6;
+        for bit in 0..6 {
+            let y = top + bit;
+            if bits & (1 << bit) == 0 || y >= MAX_SIXEL_DIMENSION {
+                continue;
+            }
+            self.grow(x + 1, y + 1);
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// 扩大画布到至少指定尺寸
+    fn grow(&mut self, width: usize, height: usize) {
+        if width <= self.width && height <= self.height {
+            return;
+        }
+        let new_width = width.max(self.width);
+        let new_height = height.max(self.height);
+        let mut pixels = vec![[0u8; 4]; new_width * new_height];
+        for row in 0..self.height {
+            let src = &self.pixels[row * self.width..(row + 1) * self.width];
+            pixels[row * new_width..row * new_width + self.width].copy_from_slice(src);
+        }
+        self.width = new_width;
+        self.height = new_height;
+        self.pixels = pixels;
+    }
+
+    /// 转换为 RGBA 图片
+    fn into_image(self) -> Option<RgbaImage> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        RgbaImage::from_raw(
+            self.width as u32,
+            self.height as u32,
+            self.pixels.into_iter().flatten().collect(),
+        )
+    }
+}
+
+/// 解析以 `;` 分隔的数字参数，返回参数和下一个字节的位置
+fn parse_numbers(data: &[u8], mut i: usize) -> (Vec<u32>, usize) {
+    let mut values = Vec::new();
+    let mut current: Option<u32> = None;
+    while let Some(&byte) = data.get(i) {
+        match byte {
+            b'0'..=b'9' => {
+                let value = current.unwrap_or(0);
+                current = Some(
+                    value
+                        .saturating_mul(10)
+                        .saturating_add((byte - b'0') as u32),
+                );
+            }
+            b';' => values.push(current.take().unwrap_or(0)),
+            _ => break,
+        }
+        i += 1;
+    }
+    if let Some(value) = current {
+        values.push(value);
+    }
+    (values, i)
+}
+
+/// 百分比（0-100）转换为 0-255
+fn percent_to_u8(percent: u32) -> u8 {
+    (percent.min(100) * 255 / 100) as u8
+}
+
+/// DEC HLS 颜色转换为 RGBA
+///
+/// DEC 的色相以蓝色为 0 度（红色 120 度、绿色 240 度），与常见的 HLS 相差 240 度。
+fn hls_to_rgba(hue: u32, lightness: u32, saturation: u32) -> [u8; 4] {
+    let h = ((hue + 240) % 360) as f64 / 360.0;
+    let l = lightness.min(100) as f64 / 100.0;
+    let s = saturation.min(100) as f64 / 100.0;
+    if s == 0.0 {
+        let v = (l * 255.0).round() as u8;
+        return [v, v, v, 255];
+    }
+    let q = if l < 0.5 {
+        l * (1.0 + s)
+    } else {
+        l + s - l * s
+    };
+    let p = 2.0 * l - q;
+    let channel = |t: f64| {
+        let t = t.rem_euclid(1.0);
+        let v = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (v * 255.0).round() as u8
+    };
+    [
+        channel(h + 1.0 / 3.0),
+        channel(h),
+        channel(h - 1.0 / 3.0),
+        255,
+    ]
+}
+
+/// 计算图片 ID（SHA-256 十六进制）
+fn image_id(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1x1 红色 PNG
+    fn png_bytes() -> Vec<u8> {
+        let img = RgbaImage::from_pixel(1, 1, image::Rgba([255, 0, 0, 255]));
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(img)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn test_decode_sixel() {
+        // 2x2 红色方块（#1 定义为 RGB 100,0,0），第二条带画一个蓝色像素
+        let img = decode_sixel(b"0;1", b"\"1;1;2;2#1;2;100;0;0#1!2N-#2;1;0;50;100@").unwrap();
+        assert_eq!((img.width(), img.height()), (2, 7));
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(1, 1).0, [255, 0, 0, 255]);
+        // `N` = 0b1111 只绘制前 4 行，`@` = 0b1 绘制第二条带的第一行
+        assert_eq!(img.get_pixel(0, 4).0, [0, 0, 0, 0]);
+        assert_eq!(img.get_pixel(0, 6).0, [0, 0, 255, 255]);
+
+        assert!(decode_sixel(b"", b"").is_none());
+        // 超出最大边长的部分被裁剪
+        let wide = decode_sixel(b"", format!("!{}~", MAX_SIXEL_DIMENSION * 2).as_bytes()).unwrap();
+        assert_eq!(wide.width() as usize, MAX_SIXEL_DIMENSION);
+    }
+
+    #[test]
+    fn test_decode_iterm_image() {
+        let png = png_bytes();
+        let data = BASE64.encode(&png);
+        let args = format!(
+            "name={};width=10;height=50%;inline=1",
+            BASE64.encode("a.png")
+        );
+        let image = decode_iterm_image(&args, &data).unwrap();
+        assert_eq!(image.protocol, ImageProtocol::Iterm);
+        assert_eq!(image.mime, "image/png");
+        assert_eq!((image.width, image.height), (1, 1));
+        assert_eq!(image.name.as_deref(), Some("a.png"));
+        assert_eq!(image.display_width, Some(ImageDimension::Cells(10)));
+        assert_eq!(image.display_height, Some(ImageDimension::Percent(50)));
+        assert!(image.preserve_aspect_ratio);
+        assert_eq!(image.data, png);
+        assert_eq!(image.id.len(), 64);
+
+        // 非内联文件和无法识别的数据
+        assert!(decode_iterm_image("name=YQ==", &data).is_none());
+        assert!(decode_iterm_image("inline=1", "bm90IGFuIGltYWdl").is_none());
+        assert_eq!(ImageDimension::parse("auto"), None);
+        assert_eq!(
+            ImageDimension::parse("32px"),
+            Some(ImageDimension::Pixels(32))
+        );
+    }
+
+    #[test]
+    fn test_decoder_split_sequences() {
+        let data = BASE64.encode(png_bytes());
+        let output = format!(
+            "ls\r\n\x1b]7;file:///tmp\x07\x1b]1337;File=inline=1:{}\x07after\x1bPq#0;2;0;100;0~\x1b\\done",
+            data
+        );
+        let bytes = output.as_bytes();
+
+        // 整块输入
+        let mut decoder = InlineImageDecoder::new();
+        let images = decoder.feed(bytes);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].image.protocol, ImageProtocol::Iterm);
+        assert_eq!(&bytes[images[0].end..images[0].end + 5], b"after");
+        assert_eq!(images[1].image.protocol, ImageProtocol::Sixel);
+        assert_eq!(&bytes[images[1].end..], b"done");
+
+        // 逐字节输入
+        let mut decoder = InlineImageDecoder::new();
+        let split: Vec<_> = bytes.iter().flat_map(|b| decoder.feed(&[*b])).collect();
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0].image, images[0].image);
+        assert_eq!(split[1].image, images[1].image);
+    }
+
+    #[test]
+    fn test_decoder_multipart_and_abort() {
+        let data = BASE64.encode(png_bytes());
+        let (a, b) = data.split_at(data.len() / 2);
+        let output = format!(
+            "\x1b]1337;MultipartFile=inline=1\x07\x1b]1337;FilePart={}\x07\x1b]1337;FilePart={}\x07\x1b]1337;FileEnd\x07",
+            a, b
+        );
+        let mut decoder = InlineImageDecoder::new();
+        let images = decoder.feed(output.as_bytes());
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].end, output.len());
+
+        // 非 sixel 的 DCS、被 CAN 中止的序列和非内联文件
+        let output = format!(
+            "\x1bP$qm\x1b\\\x1bPq~~\x18\x1b]1337;File=name=YQ==:{}\x07",
+            data
+        );
+        assert!(decoder.feed(output.as_bytes()).is_empty());
+    }
+}
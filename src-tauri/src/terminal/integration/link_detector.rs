@@ -0,0 +1,503 @@
+//! 终端链接检测
+//!
+//! 从终端输出中识别可点击的链接，供前端渲染为链接（打开 URL、在指定行列打开文件）。
+//!
+//! ## 功能
+//! - OSC 8 超链接：收集链接文本，链接结束时生成链接
+//! - URL 检测：`http`、`https`、`ftp`、`file` 协议
+//! - 文件路径检测：绝对/相对路径，支持 `path:line[:col]`、`path(line,col)`
+//!   和 Python 回溯的 `"path", line N` 位置格式
+//!
+//! ## 设计说明
+//! 普通文本按行检测，未结束的行缓存到下一次输出，避免链接被 PTY 读取块截断。
+//! 超链接文本不参与普通文本检测，避免同一链接重复上报。
+
+use std::borrow::Cow;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// 缓存的未结束行最大长度（超出后丢弃，长行通常是进度条等非链接输出）
+const MAX_PENDING_LINE_LEN: usize = 4 * 1024;
+
+/// 超链接文本最大长度
+const MAX_HYPERLINK_TEXT_LEN: usize = 1024;
+
+/// URL 匹配
+static URL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?:https?|ftp|file)://[^\s<>"'`]+"#).unwrap());
+
+/// 文件路径匹配（路径 + 可选的行列号，路径只匹配 ASCII 字符，避免把前面的中文拼进路径）
+static PATH_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?P<path>(?:[A-Za-z]:[\\/]|~[\\/]|\.{1,2}[\\/]|/)?(?:[A-Za-z0-9_.\-@+]+[\\/])*[A-Za-z0-9_.\-@+]+)(?::(?P<line>[0-9]+)(?::(?P<col>[0-9]+))?|\((?P<pline>[0-9]+)(?:,\s?(?P<pcol>[0-9]+))?\)|",\s*line\s+(?P<tline>[0-9]+))?"#,
+    )
+    .unwrap()
+});
+
+/// 链接类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    /// URL（在浏览器中打开）
+    Url,
+    /// 文件路径（在编辑器中打开）
+    File,
+}
+
+/// 检测到的链接
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectedLink {
+    /// 链接类型
+    pub kind: LinkKind,
+    /// 终端中显示的文本（前端据此在缓冲区中定位链接）
+    pub text: String,
+    /// 链接目标（URL 或文件路径，文件路径不含行列号，相对路径未解析）
+    pub target: String,
+    /// 行号（从 1 开始）
+    pub line: Option<u32>,
+    /// 列号（从 1 开始）
+    pub column: Option<u32>,
+    /// 是否来自 OSC 8 超链接
+    pub hyperlink: bool,
+}
+
+/// 未结束的 OSC 8 超链接
+#[derive(Debug)]
+struct OpenHyperlink {
+    id: Option<String>,
+    uri: String,
+    text: String,
+}
+
+/// 链接检测器
+///
+/// 每个终端会话一个实例，按输出顺序调用 `feed_text` 和 `feed_hyperlink`。
+#[derive(Debug, Default)]
+pub struct LinkDetector {
+    /// 未结束的行
+    pending_line: String,
+    /// 当前打开的超链接
+    hyperlink: Option<OpenHyperlink>,
+}
+
+impl LinkDetector {
+    /// 创建链接检测器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理普通输出（不含 OSC 序列）
+    ///
+    /// # 参数
+    /// - `data`: 输出数据（可包含 CSI 等其他控制序列）
+    ///
+    /// # 返回
+    /// 本次输出中已结束的行里检测到的链接
+    pub fn feed_text(&mut self, data: &[u8]) -> Vec<DetectedLink> {
+        let text = strip_ansi(data);
+        if let Some(ref mut link) = self.hyperlink {
+            if link.text.len() + text.len() <= MAX_HYPERLINK_TEXT_LEN {
+                link.text.push_str(&text);
+            }
+            // 占位，避免超链接前后的文本被拼接成一个路径
+            self.pending_line.push(' ');
+            return Vec::new();
+        }
+
+        self.pending_line.push_str(&text);
+        let Some(split) = self.pending_line.rfind('\n') else {
+            if self.pending_line.len() > MAX_PENDING_LINE_LEN {
+                self.pending_line.clear();
+            }
+            return Vec::new();
+        };
+
+        let rest = self.pending_line.split_off(split + 1);
+        let lines = std::mem::replace(&mut self.pending_line, rest);
+        lines.lines().flat_map(detect_links).collect()
+    }
+
+    /// 处理 OSC 8 超链接序列
+    ///
+    /// # 参数
+    /// - `id`: 链接 ID
+    /// - `uri`: 链接地址（为空表示链接结束）
+    ///
+    /// # 返回
+    /// 结束的超链接（链接文本为空时不返回）
+    pub fn feed_hyperlink(&mut self, id: Option<&str>, uri: &str) -> Option<DetectedLink> {
+        // 同 ID 同地址的链接在续行时会重新打开，视为同一链接
+        if let Some(ref link) = self.hyperlink {
+            if !uri.is_empty() && link.uri == uri && link.id.is_some() && link.id.as_deref() == id {
+                return None;
+            }
+        }
+
+        let finished = self.hyperlink.take().and_then(|link| {
+            let text = link.text.trim();
+            if text.is_empty() {
+                return None;
+            }
+            Some(hyperlink_to_link(text, &link.uri))
+        });
+
+        if !uri.is_empty() {
+            self.hyperlink = Some(OpenHyperlink {
+                id: id.map(str::to_string),
+                uri: uri.to_string(),
+                text: String::new(),
+            });
+        }
+        finished
+    }
+
+    /// 重置状态
+    pub fn reset(&mut self) {
+        self.pending_line.clear();
+        self.hyperlink = None;
+    }
+}
+
+/// 从一行文本中检测链接
+///
+/// # 参数
+/// - `line`: 不含控制序列的文本
+///
+/// # 返回
+/// 按出现顺序排列的链接
+pub fn detect_links(line: &str) -> Vec<DetectedLink> {
+    let mut links = Vec::new();
+    let mut url_ranges = Vec::new();
+
+    for m in URL_RE.find_iter(line) {
+        let url = trim_url(m.as_str());
+        url_ranges.push(m.start()..m.start() + url.len());
+        links.push((
+            m.start(),
+            DetectedLink {
+                kind: LinkKind::Url,
+                text: url.to_string(),
+                target: url.to_string(),
+                line: None,
+                column: None,
+                hyperlink: false,
+            },
+        ));
+    }
+
+    for caps in PATH_RE.captures_iter(line) {
+        let whole = caps.get(0).unwrap();
+        if url_ranges
+            .iter()
+            .any(|r| whole.start() < r.end && r.start < whole.end())
+        {
+            continue;
+        }
+
+        let number = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| caps.name(name))
+                .and_then(|m| m.as_str().parse::<u32>().ok())
+        };
+        let line_no = number(&["line", "pline", "tline"]);
+        let column = number(&["col", "pcol"]);
+
+        let path = caps.name("path").unwrap().as_str();
+        let (path, text) = if line_no.is_none() {
+            // 句末的句点不属于路径
+            let trimmed = match path.strip_suffix('.') {
+                Some(p) if p.ends_with(|c: char| c.is_alphanumeric()) => p,
+                _ => path,
+            };
+            (trimmed, trimmed)
+        } else if caps.name("tline").is_some() {
+            (path, path)
+        } else {
+            (path, whole.as_str())
+        };
+
+        if !is_file_path(path, line_no.is_some()) {
+            continue;
+        }
+        links.push((
+            whole.start(),
+            DetectedLink {
+                kind: LinkKind::File,
+                text: text.to_string(),
+                target: path.to_string(),
+                line: line_no,
+                column,
+                hyperlink: false,
+            },
+        ));
+    }
+
+    links.sort_by_key(|(start, _)| *start);
+    links.into_iter().map(|(_, link)| link).collect()
+}
+
+/// 去除文本中的控制序列（CSI、OSC 及其他 ESC 序列和控制字符）
+///
+/// 回车按换行处理，制表符保留。
+pub fn strip_ansi(data: &[u8]) -> String {
+    let mut result = Vec::with_capacity(data.len());
+    let mut i = 0;
+
+    while i < data.len() {
+        match data[i] {
+            0x1b => {
+                i += 1;
+                match data.get(i) {
+                    Some(b'[') => {
+                        i += 1;
+                        while i < data.len() && !(0x40..=0x7e).contains(&data[i]) {
+                            i += 1;
+                        }
+                    }
+                    Some(b']') => {
+                        i += 1;
+                        while i < data.len() {
+                            if data[i] == 0x07 {
+                                break;
+                            }
+                            if data[i] == 0x1b && data.get(i + 1) == Some(&b'\\') {
+                                i += 1;
+                                break;
+                            }
+                            i += 1;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            b'\r' => {
+                result.push(b'\n');
+                i += 1;
+            }
+            b'\n' | b'\t' => {
+                result.push(data[i]);
+                i += 1;
+            }
+            byte if byte < 0x20 || byte == 0x7f => i += 1,
+            byte => {
+                result.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+/// 去除 URL 末尾的标点和不成对的右括号
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let Some(last) = url.chars().last() else {
+            return url;
+        };
+        let unbalanced = |open: char| url.matches(open).count() < url.matches(last).count();
+        let trim = match last {
+            '.' | ',' | ';' | ':' | '!' | '?' => true,
+            ')' => unbalanced('('),
+            ']' => unbalanced('['),
+            '}' => unbalanced('{'),
+            _ => false,
+        };
+        if !trim {
+            return url;
+        }
+        url = &url[..url.len() - last.len_utf8()];
+    }
+}
+
+/// 判断匹配的文本是否像文件路径
+///
+/// 以 `/`、`~/`、`./`、`../` 或盘符开头的路径直接认为是文件路径；其他路径的
+/// 文件名需要有扩展名，且不含目录时还需要带行号，以排除普通单词、版本号和日期。
+fn is_file_path(path: &str, has_line: bool) -> bool {
+    let anchored = path.starts_with('/')
+        || path.starts_with("~/")
+        || path.starts_with("~\\")
+        || path.starts_with("./")
+        || path.starts_with(".\\")
+        || path.starts_with("../")
+        || path.starts_with("..\\")
+        || path.get(1..3).is_some_and(|s| s == ":\\" || s == ":/");
+    if anchored {
+        return path.len() > 1;
+    }
+
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let has_extension = file_name.rsplit_once('.').is_some_and(|(stem, ext)| {
+        !stem.is_empty() && ext.starts_with(|c: char| c.is_ascii_alphabetic())
+    });
+    has_extension && (has_line || file_name.len() < path.len())
+}
+
+/// 将 OSC 8 超链接转换为链接
+fn hyperlink_to_link(text: &str, uri: &str) -> DetectedLink {
+    let file_path = uri.strip_prefix("file://").map(|rest| {
+        // 跳过主机名（file://hostname/path）
+        let path = rest.find('/').map_or(rest, |pos| &rest[pos..]);
+        urlencoding::decode(path)
+            .unwrap_or(Cow::Borrowed(path))
+            .into_owned()
+    });
+
+    match file_path {
+        Some(path) => DetectedLink {
+            kind: LinkKind::File,
+            text: text.to_string(),
+            target: path,
+            line: None,
+            column: None,
+            hyperlink: true,
+        },
+        None => DetectedLink {
+            kind: LinkKind::Url,
+            text: text.to_string(),
+            target: uri.to_string(),
+            line: None,
+            column: None,
+            hyperlink: true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(line: &str) -> Vec<(LinkKind, String, Option<u32>, Option<u32>)> {
+        detect_links(line)
+            .into_iter()
+            .map(|l| (l.kind, l.target, l.line, l.column))
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_urls() {
+        assert_eq!(
+            targets("see https://example.com/a?b=1 (or http://x.org/wiki/Foo_(bar)), ok."),
+            vec![
+                (
+                    LinkKind::Url,
+                    "https://example.com/a?b=1".to_string(),
+                    None,
+                    None
+                ),
+                (
+                    LinkKind::Url,
+                    "http://x.org/wiki/Foo_(bar)".to_string(),
+                    None,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_file_positions() {
+        assert_eq!(
+            targets("error: src/main.rs:12:5: expected `;`"),
+            vec![(LinkKind::File, "src/main.rs".to_string(), Some(12), Some(5))]
+        );
+        assert_eq!(
+            targets("C:\\code\\app.ts(3,14): error TS2322"),
+            vec![(
+                LinkKind::File,
+                "C:\\code\\app.ts".to_string(),
+                Some(3),
+                Some(14)
+            )]
+        );
+        assert_eq!(
+            targets("  File \"/usr/lib/python3/foo.py\", line 42, in bar"),
+            vec![(
+                LinkKind::File,
+                "/usr/lib/python3/foo.py".to_string(),
+                Some(42),
+                None
+            )]
+        );
+        assert_eq!(
+            targets("Saved to ~/notes/todo.md."),
+            vec![(LinkKind::File, "~/notes/todo.md".to_string(), None, None)]
+        );
+    }
+
+    #[test]
+    fn test_ignore_non_paths() {
+        for line in [
+            "Hello World",
+            "version 1.2.3 released 10/20/2024",
+            "Content-Type: application/json",
+            "at 12:30:45 and/or e.g. later",
+            "see README.md for details",
+            "保存到日志",
+        ] {
+            assert!(detect_links(line).is_empty(), "{}", line);
+        }
+        assert_eq!(
+            targets("已保存到/tmp/out.log")[0].1,
+            "/tmp/out.log".to_string()
+        );
+        assert_eq!(
+            targets("lib.rs:7")[0],
+            (LinkKind::File, "lib.rs".to_string(), Some(7), None)
+        );
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(
+            strip_ansi(b"\x1b[1;31merror\x1b[0m: \x1b]0;title\x07a.rs:1\r\n"),
+            "error: a.rs:1\n\n"
+        );
+    }
+
+    #[test]
+    fn test_feed_text_across_chunks() {
+        let mut detector = LinkDetector::new();
+        assert!(detector.feed_text(b"open https://exam").is_empty());
+        let links = detector.feed_text(b"ple.com now\r\nnext");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].text, "https://example.com");
+        assert!(detector.feed_text(b" line").is_empty());
+    }
+
+    #[test]
+    fn test_feed_hyperlink() {
+        let mut detector = LinkDetector::new();
+        assert_eq!(
+            detector.feed_hyperlink(None, "file://host/tmp/a%20b.txt"),
+            None
+        );
+        detector.feed_text(b"a b.txt");
+        let link = detector.feed_hyperlink(None, "").unwrap();
+        assert_eq!(link.kind, LinkKind::File);
+        assert_eq!(link.text, "a b.txt");
+        assert_eq!(link.target, "/tmp/a b.txt");
+        assert!(link.hyperlink);
+
+        // 同 ID 的续行链接合并为一个
+        detector.feed_hyperlink(Some("1"), "https://example.com");
+        detector.feed_text(b"exam");
+        assert_eq!(
+            detector.feed_hyperlink(Some("1"), "https://example.com"),
+            None
+        );
+        detector.feed_text(b"ple");
+        let link = detector.feed_hyperlink(None, "").unwrap();
+        assert_eq!(link.text, "example");
+        assert_eq!(link.kind, LinkKind::Url);
+
+        // 超链接文本不参与普通检测
+        assert!(detector.feed_text(b"\n").is_empty());
+    }
+}
@@ -4,28 +4,69 @@
 //!
 //! ## 模块结构
 //! - `osc_parser` - OSC 序列解析器
+//! - `clipboard` - OSC 52 剪贴板桥接（按主机策略读写本机剪贴板）
+//! - `command_output` - 命令输出提取（纯文本或保留 ANSI 控制序列）
+//! - `file_transfer` - 终端文件传输的本机文件访问（上传授权、下载保存）
+//! - `inline_image` - 内联图片解码（sixel、iTerm2 OSC 1337）
+//! - `paste` - 粘贴安全处理（括号粘贴、换行和控制字符确认）
+//! - `prompt_index` - 提示符索引（OSC 133 提示符位置，跳转和重新执行命令）
+//! - `link_detector` - 终端输出链接检测（URL、文件路径、OSC 8 超链接）
 //! - `shell_integration` - Shell 集成处理器
 //! - `shell_scripts` - Shell 集成脚本管理
 //! - `resync` - 状态重同步控制器
 //!
 //! ## 功能
-//! - OSC 序列解析（OSC 7/52/133/16162）
+//! - OSC 序列解析（OSC 7/8/52/133/1337/16162）
 //! - Shell 集成状态管理
-//! - Shell 集成脚本安装和管理
+//! - 命令块元数据（命令、退出码、耗时、目录、Git 分支）
+//! - 单条命令的输出提取
+//! - 提示符索引（含恢复会话的历史输出）
+//! - 输出链接检测
+//! - 内联图片解码
+//! - 文件传输的上传授权
+//! - 括号粘贴与粘贴确认
+//! - OSC 52 剪贴板读写
+//! - Shell 集成脚本安装和管理（含远程 Shell 启动命令）
 //! - 终端状态重同步
 
+pub mod clipboard;
+pub mod command_output;
+pub mod file_transfer;
+pub mod inline_image;
+pub mod link_detector;
 pub mod osc_parser;
+pub mod paste;
+pub mod prompt_index;
 pub mod resync;
 pub mod shell_integration;
 pub mod shell_scripts;
 
 // 重新导出常用类型
+pub use clipboard::{
+    ClipboardBridge, ClipboardOperation, ClipboardOutcome, ClipboardResponder, HostClipboard,
+    SystemClipboard,
+};
+pub use command_output::{plain_text, CommandOutput, CommandOutputFormat};
+pub use file_transfer::{decode_file_data, sanitize_file_name, UploadFileInfo, UploadGrants};
+pub use inline_image::{
+    decode_iterm_image, decode_sixel, ExtractedImage, ImageDimension, ImageProtocol, InlineImage,
+    InlineImageDecoder,
+};
+pub use link_detector::{detect_links, DetectedLink, LinkDetector, LinkKind};
 pub use osc_parser::{strip_osc_sequences, OSCParser, OSCSequence, ParsedOSC, PromptMarkType};
+pub use paste::{
+    analyze_paste, encode_paste, BracketedPasteTracker, PasteAnalysis, PasteGuard, PasteOutcome,
+    PasteWarning, PendingPaste, BRACKETED_PASTE_END, BRACKETED_PASTE_START,
+};
+pub use prompt_index::{command_text, PromptIndex, PromptMark, PromptMarks, MAX_PROMPT_MARKS};
 pub use resync::{
     resync_controller, ResyncController, ResyncOptions, ResyncResult, TERMINAL_RESET_SEQUENCE,
     TERMINAL_SOFT_RESET_SEQUENCE,
 };
 pub use shell_integration::{
-    CommandInfo, ShellIntegration, ShellIntegrationEvent, ShellIntegrationStatus, ShellType,
+    CommandBlock, CommandBlockQuery, CommandInfo, ShellIntegration, ShellIntegrationEvent,
+    ShellIntegrationStatus, ShellType, GIT_BRANCH_USER_VAR, MAX_COMMAND_BLOCKS,
+};
+pub use shell_scripts::{
+    ShellLaunchBuilder, ShellLaunchConfig, ShellScripts, TerminalEnvConfig, REMOTE_INTEGRATION_DIR,
 };
-pub use shell_scripts::{ShellLaunchBuilder, ShellLaunchConfig, ShellScripts, TerminalEnvConfig};
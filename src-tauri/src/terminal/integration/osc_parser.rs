@@ -2,8 +2,10 @@
 //!
 //! 解析终端输出中的 OSC（Operating System Command）序列，支持：
 //! - OSC 7: 当前工作目录
+//! - OSC 8: 超链接
 //! - OSC 52: 剪贴板操作
 //! - OSC 133: 命令提示符标记（Shell Integration）
+//! - OSC 1337: iTerm2 当前目录（`CurrentDir`）和用户变量（`SetUserVar`）
 //! - OSC 16162: Wave 特定命令
 //!
 //! ## 功能
@@ -13,8 +15,10 @@
 //!
 //! ## Requirements
 //! - 6.1: OSC 7 当前目录解析
+//! - OSC 8 超链接解析
 //! - 6.2: OSC 52 剪贴板解析
 //! - 6.3: OSC 133 命令提示符标记解析
+//! - OSC 1337 当前目录和用户变量解析
 //! - 6.4: OSC 16162 Wave 命令解析
 //! - 6.7: 无效序列容错处理
 
@@ -43,6 +47,15 @@ pub enum OSCSequence {
         path: String,
     },
 
+    /// OSC 8 - 超链接
+    /// 格式: OSC 8 ; params ; URI ST，URI 为空表示结束当前链接
+    Hyperlink {
+        /// 链接 ID（params 中的 `id=...`，用于关联跨行的同一链接）
+        id: Option<String>,
+        /// 链接地址（为空表示链接结束）
+        uri: String,
+    },
+
     /// OSC 52 - 剪贴板操作
     /// 格式: OSC 52 ; selection ; base64-data ST
     Clipboard {
@@ -53,10 +66,21 @@ pub enum OSCSequence {
     },
 
     /// OSC 133 - 命令提示符标记（Shell Integration）
-    /// 格式: OSC 133 ; type ST
+    /// 格式: OSC 133 ; type ST，命令结束标记为 OSC 133 ; D ; exit_code ST
     PromptMark {
         /// 标记类型
         mark_type: PromptMarkType,
+        /// 退出码（仅命令结束标记 `D` 携带）
+        exit_code: Option<i32>,
+    },
+
+    /// OSC 1337 - iTerm2 用户变量
+    /// 格式: OSC 1337 ; SetUserVar=name=base64-value ST
+    UserVar {
+        /// 变量名
+        name: String,
+        /// 解码后的变量值
+        value: String,
     },
 
     /// OSC 16162 - Wave 特定命令
@@ -241,8 +265,10 @@ impl OSCParser {
         // 根据 OSC 代码解析
         match code {
             "7" => Self::parse_osc_7(params),
+            "8" => Self::parse_osc_8(params),
             "52" => Self::parse_osc_52(params),
             "133" => Self::parse_osc_133(params),
+            "1337" => Self::parse_osc_1337(params),
             "16162" => Self::parse_osc_16162(params),
             _ => Some(OSCSequence::Unknown {
                 code: code.to_string(),
@@ -295,6 +321,23 @@ impl OSCParser {
         })
     }
 
+    /// 解析 OSC 8 - 超链接
+    ///
+    /// 格式: params;URI，params 为 `key=value` 列表，以 `:` 分隔
+    fn parse_osc_8(params: &str) -> Option<OSCSequence> {
+        let (link_params, uri) = params.split_once(';')?;
+        let id = link_params
+            .split(':')
+            .find_map(|pair| pair.strip_prefix("id="))
+            .filter(|id| !id.is_empty())
+            .map(str::to_string);
+
+        Some(OSCSequence::Hyperlink {
+            id,
+            uri: uri.to_string(),
+        })
+    }
+
     /// 解析 OSC 52 - 剪贴板操作
     ///
     /// 格式: selection;base64-data
@@ -320,8 +363,43 @@ impl OSCParser {
     fn parse_osc_133(params: &str) -> Option<OSCSequence> {
         let mark_char = params.chars().next()?;
         let mark_type = PromptMarkType::from_char(mark_char);
+        let exit_code = match mark_type {
+            PromptMarkType::CommandFinished => params
+                .split(';')
+                .nth(1)
+                .and_then(|code| code.trim().parse().ok()),
+            _ => None,
+        };
+
+        Some(OSCSequence::PromptMark {
+            mark_type,
+            exit_code,
+        })
+    }
 
-        Some(OSCSequence::PromptMark { mark_type })
+    /// 解析 OSC 1337 - iTerm2 扩展
+    ///
+    /// 支持 `CurrentDir=path` 和 `SetUserVar=name=base64-value`，其余（如内联图片
+    /// `File=`，由内联图片解码器处理）作为未知序列返回。
+    fn parse_osc_1337(params: &str) -> Option<OSCSequence> {
+        if let Some(path) = params.strip_prefix("CurrentDir=") {
+            return Some(OSCSequence::CurrentDirectory {
+                hostname: None,
+                path: path.to_string(),
+            });
+        }
+        if let Some(var) = params.strip_prefix("SetUserVar=") {
+            let (name, value) = var.split_once('=')?;
+            let value = BASE64.decode(value).ok()?;
+            return Some(OSCSequence::UserVar {
+                name: name.to_string(),
+                value: String::from_utf8(value).ok()?,
+            });
+        }
+        Some(OSCSequence::Unknown {
+            code: "1337".to_string(),
+            params: params.to_string(),
+        })
     }
 
     /// 解析 OSC 16162 - Wave 命令
@@ -402,6 +480,19 @@ impl OSCParser {
         format!("\x1b]7;file://{}{}\x07", host, path).into_bytes()
     }
 
+    /// 构建 OSC 8 序列
+    ///
+    /// # 参数
+    /// - `id`: 链接 ID（可选）
+    /// - `uri`: 链接地址（为空时构建结束序列）
+    ///
+    /// # 返回
+    /// 完整的 OSC 8 序列
+    pub fn build_osc_8(id: Option<&str>, uri: &str) -> Vec<u8> {
+        let params = id.map(|id| format!("id={}", id)).unwrap_or_default();
+        format!("\x1b]8;{};{}\x1b\\", params, uri).into_bytes()
+    }
+
     /// 构建 OSC 133 序列
     ///
     /// # 参数
@@ -494,6 +585,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_osc_8() {
+        let data = b"\x1b]8;id=42:x=y;https://example.com/a;b\x1b\\link\x1b]8;;\x1b\\";
+        let results = OSCParser::parse(data);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].sequence,
+            OSCSequence::Hyperlink {
+                id: Some("42".to_string()),
+                uri: "https://example.com/a;b".to_string(),
+            }
+        );
+        assert_eq!(
+            results[1].sequence,
+            OSCSequence::Hyperlink {
+                id: None,
+                uri: String::new(),
+            }
+        );
+        assert_eq!(&data[results[0].range.end..results[1].range.start], b"link");
+    }
+
+    #[test]
+    fn test_build_osc_8() {
+        let osc = OSCParser::build_osc_8(Some("1"), "file:///tmp/a.rs");
+        assert_eq!(
+            OSCParser::parse_single(&osc),
+            Some(OSCSequence::Hyperlink {
+                id: Some("1".to_string()),
+                uri: "file:///tmp/a.rs".to_string(),
+            })
+        );
+        assert_eq!(OSCParser::build_osc_8(None, ""), b"\x1b]8;;\x1b\\");
+    }
+
     #[test]
     fn test_parse_osc_52() {
         let data = b"\x1b]52;c;SGVsbG8gV29ybGQ=\x07";
@@ -533,7 +660,7 @@ mod tests {
             let results = OSCParser::parse(data);
             assert_eq!(results.len(), 1);
             match &results[0].sequence {
-                OSCSequence::PromptMark { mark_type } => {
+                OSCSequence::PromptMark { mark_type, .. } => {
                     assert_eq!(*mark_type, expected_type);
                 }
                 _ => panic!("Expected PromptMark"),
@@ -541,6 +668,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_osc_133_exit_code() {
+        let results = OSCParser::parse(b"\x1b]133;D;127\x1b\\\x1b]133;A\x07");
+        assert_eq!(
+            results[0].sequence,
+            OSCSequence::PromptMark {
+                mark_type: PromptMarkType::CommandFinished,
+                exit_code: Some(127),
+            }
+        );
+        assert_eq!(
+            results[1].sequence,
+            OSCSequence::PromptMark {
+                mark_type: PromptMarkType::PromptStart,
+                exit_code: None,
+            }
+        );
+    }
+
     #[test]
     fn test_parse_osc_16162() {
         let data = b"\x1b]16162;setcwd /home/user\x07";
@@ -555,6 +701,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_osc_1337() {
+        let data =
+            b"\x1b]1337;CurrentDir=/home/user\x07\x1b]1337;SetUserVar=gitBranch=bWFpbg==\x07";
+        let results = OSCParser::parse(data);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].sequence,
+            OSCSequence::CurrentDirectory {
+                hostname: None,
+                path: "/home/user".to_string(),
+            }
+        );
+        assert_eq!(
+            results[1].sequence,
+            OSCSequence::UserVar {
+                name: "gitBranch".to_string(),
+                value: "main".to_string(),
+            }
+        );
+
+        // 空值表示清除变量，无效的 Base64 丢弃
+        assert_eq!(
+            OSCParser::parse_single(b"\x1b]1337;SetUserVar=gitBranch=\x07"),
+            Some(OSCSequence::UserVar {
+                name: "gitBranch".to_string(),
+                value: String::new(),
+            })
+        );
+        assert_eq!(
+            OSCParser::parse_single(b"\x1b]1337;SetUserVar=gitBranch=!!\x07"),
+            None
+        );
+        assert!(matches!(
+            OSCParser::parse_single(b"\x1b]1337;File=inline=1:AAAA\x07"),
+            Some(OSCSequence::Unknown { .. })
+        ));
+    }
+
     #[test]
     fn test_parse_multiple_osc() {
         let data = b"Hello\x1b]7;file:///home\x07World\x1b]133;A\x07End";
@@ -652,7 +838,7 @@ mod tests {
 
         assert_eq!(results.len(), 1);
         match &results[0].sequence {
-            OSCSequence::PromptMark { mark_type } => {
+            OSCSequence::PromptMark { mark_type, .. } => {
                 assert_eq!(*mark_type, PromptMarkType::PromptStart);
             }
             _ => panic!("Expected PromptMark"),
@@ -0,0 +1,465 @@
+//! 粘贴安全处理
+//!
+//! 在后端处理用户粘贴到终端的文本，避免粘贴内容被 Shell 直接执行。
+//!
+//! ## 功能
+//! - 跟踪终端程序是否启用括号粘贴模式（DECSET 2004）
+//! - 启用时用 `ESC[200~` / `ESC[201~` 包裹粘贴内容，并移除内容中的括号粘贴标记，防止提前结束粘贴
+//! - 检查粘贴内容中的换行和可疑控制字符
+//! - 需确认时发送 `terminal:paste-warning` 事件，等待前端响应后再写入
+//!
+//! ## 设计说明
+//! 换行统一转换为 `\r`（与按回车一致）。包含换行或控制字符的粘贴总是需要确认，
+//! 事件中附带括号粘贴状态，前端据此提示是否会立即执行。
+
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use uuid::Uuid;
+
+use crate::terminal::error::TerminalError;
+use crate::terminal::events::{event_names, TerminalPasteWarningEvent};
+
+/// 括号粘贴开始标记
+pub const BRACKETED_PASTE_START: &str = "\x1b[200~";
+
+/// 括号粘贴结束标记
+pub const BRACKETED_PASTE_END: &str = "\x1b[201~";
+
+/// 括号粘贴模式的 DEC 私有模式编号
+const BRACKETED_PASTE_MODE: &[u8] = b"2004";
+
+/// CSI 参数的最大长度（超过时忽略该序列）
+const MAX_CSI_PARAMS_LEN: usize = 64;
+
+/// 同时等待确认的粘贴上限
+const MAX_PENDING_PASTES: usize = 16;
+
+/// 确认事件中预览内容的最大字符数
+const PREVIEW_CHARS: usize = 200;
+
+/// 粘贴警告
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteWarning {
+    /// 包含换行（未启用括号粘贴时会逐行执行）
+    MultiLine,
+    /// 包含控制字符（可能改变终端状态或触发快捷键）
+    ControlCharacters,
+}
+
+/// 粘贴内容检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasteAnalysis {
+    /// 换行数（`\r\n` 计为一个）
+    pub line_breaks: usize,
+    /// 可疑控制字符数（不含制表符和换行）
+    pub control_chars: usize,
+    /// 警告
+    pub warnings: Vec<PasteWarning>,
+}
+
+/// 粘贴处理结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum PasteOutcome {
+    /// 已写入终端
+    Written,
+    /// 等待用户确认
+    Pending {
+        /// 粘贴 ID
+        paste_id: String,
+    },
+    /// 用户拒绝
+    Denied,
+}
+
+/// 等待确认的粘贴
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingPaste {
+    /// 会话 ID
+    pub session_id: String,
+    /// 粘贴的文本
+    pub text: String,
+}
+
+/// 是否为可疑控制字符（C0 / C1 控制字符和 DEL，不含制表符和换行）
+fn is_suspicious_control(c: char) -> bool {
+    match c {
+        '\t' | '\n' | '\r' => false,
+        '\u{0}'..='\u{1f}' | '\u{7f}'..='\u{9f}' => true,
+        _ => false,
+    }
+}
+
+/// 检查粘贴内容
+pub fn analyze_paste(text: &str) -> PasteAnalysis {
+    let mut line_breaks = 0;
+    let mut control_chars = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                line_breaks += 1;
+            }
+            '\n' => line_breaks += 1,
+            c if is_suspicious_control(c) => control_chars += 1,
+            _ => {}
+        }
+    }
+
+    let mut warnings = Vec::new();
+    if line_breaks > 0 {
+        warnings.push(PasteWarning::MultiLine);
+    }
+    if control_chars > 0 {
+        warnings.push(PasteWarning::ControlCharacters);
+    }
+    PasteAnalysis {
+        line_breaks,
+        control_chars,
+        warnings,
+    }
+}
+
+/// 生成写入终端的粘贴数据
+///
+/// 换行转换为 `\r`；启用括号粘贴时移除内容中的括号粘贴标记并包裹内容。
+///
+/// # 参数
+/// - `text`: 粘贴的文本
+/// - `bracketed`: 终端程序是否启用括号粘贴模式
+pub fn encode_paste(text: &str, bracketed: bool) -> Vec<u8> {
+    let normalized = text.replace("\r\n", "\r").replace('\n', "\r");
+    if !bracketed {
+        return normalized.into_bytes();
+    }
+
+    let mut content = normalized;
+    // 移除后可能拼出新的标记（如 `ESC[20ESC[201~1~`），循环直到不再包含
+    while content.contains(BRACKETED_PASTE_START) || content.contains(BRACKETED_PASTE_END) {
+        content = content
+            .replace(BRACKETED_PASTE_START, "")
+            .replace(BRACKETED_PASTE_END, "");
+    }
+    format!(
+        "{}{}{}",
+        BRACKETED_PASTE_START, content, BRACKETED_PASTE_END
+    )
+    .into_bytes()
+}
+
+/// 生成预览文本（控制字符转换为 `^X` 形式）
+fn preview(text: &str) -> String {
+    let mut preview = String::new();
+    for c in text.chars().take(PREVIEW_CHARS) {
+        match c {
+            '\u{0}'..='\u{1f}' if !matches!(c, '\t' | '\n' | '\r') => {
+                preview.push('^');
+                preview.push((c as u8 + b'@') as char);
+            }
+            '\u{7f}' => preview.push_str("^?"),
+            '\u{80}'..='\u{9f}' => preview.push_str(&c.escape_unicode().to_string()),
+            _ => preview.push(c),
+        }
+    }
+    preview
+}
+
+/// 序列解析状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// 普通输出
+    Ground,
+    /// 收到 ESC
+    Escape,
+    /// CSI 序列
+    Csi,
+}
+
+/// 括号粘贴模式跟踪器
+///
+/// 扫描终端输出中的 `CSI ? Pm h` / `CSI ? Pm l`（DECSET / DECRST）和 `ESC c`（RIS），
+/// 序列可跨读取块。
+#[derive(Debug)]
+pub struct BracketedPasteTracker {
+    state: State,
+    params: Vec<u8>,
+    /// 参数过长或包含中间字节，忽略该序列
+    ignore: bool,
+    enabled: bool,
+}
+
+impl Default for BracketedPasteTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BracketedPasteTracker {
+    /// 创建跟踪器（初始未启用）
+    pub fn new() -> Self {
+        Self {
+            state: State::Ground,
+            params: Vec::new(),
+            ignore: false,
+            enabled: false,
+        }
+    }
+
+    /// 终端程序是否启用了括号粘贴模式
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 处理终端输出
+    ///
+    /// # 返回
+    /// 处理后是否启用括号粘贴模式
+    pub fn feed(&mut self, data: &[u8]) -> bool {
+        for &byte in data {
+            match self.state {
+                State::Ground => {
+                    if byte == 0x1b {
+                        self.state = State::Escape;
+                    }
+                }
+                State::Escape => match byte {
+                    b'[' => {
+                        self.state = State::Csi;
+                        self.params.clear();
+                        self.ignore = false;
+                    }
+                    b'c' => {
+                        // RIS：完全重置终端
+                        self.enabled = false;
+                        self.state = State::Ground;
+                    }
+                    0x1b => {}
+                    _ => self.state = State::Ground,
+                },
+                State::Csi => match byte {
+                    0x30..=0x3f => {
+                        if self.params.len() < MAX_CSI_PARAMS_LEN {
+                            self.params.push(byte);
+                        } else {
+                            self.ignore = true;
+                        }
+                    }
+                    0x20..=0x2f => self.ignore = true,
+                    0x40..=0x7e => {
+                        if !self.ignore {
+                            self.finish_csi(byte);
+                        }
+                        self.state = State::Ground;
+                    }
+                    0x1b => self.state = State::Escape,
+                    // CAN / SUB 取消序列
+                    0x18 | 0x1a => self.state = State::Ground,
+                    _ => {}
+                },
+            }
+        }
+        self.enabled
+    }
+
+    /// 处理完整的 CSI 序列
+    fn finish_csi(&mut self, final_byte: u8) {
+        let set = match final_byte {
+            b'h' => true,
+            b'l' => false,
+            _ => return,
+        };
+        let Some(params) = self.params.strip_prefix(b"?") else {
+            return;
+        };
+        if params
+            .split(|b| *b == b';')
+            .any(|p| p == BRACKETED_PASTE_MODE)
+        {
+            self.enabled = set;
+        }
+    }
+}
+
+/// 粘贴确认管理
+///
+/// 保存等待确认的粘贴，需确认时发送 `terminal:paste-warning` 事件。
+pub struct PasteGuard {
+    /// Tauri 应用句柄（可选）
+    app_handle: Option<tauri::AppHandle>,
+    /// 等待确认的粘贴（粘贴 ID -> 粘贴）
+    pending: Mutex<HashMap<String, PendingPaste>>,
+}
+
+impl Default for PasteGuard {
+    fn default() -> Self {
+        Self {
+            app_handle: None,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl PasteGuard {
+    /// 创建粘贴确认管理
+    pub fn new(app_handle: tauri::AppHandle) -> Self {
+        Self {
+            app_handle: Some(app_handle),
+            ..Self::default()
+        }
+    }
+
+    /// 检查粘贴内容，需要确认时保存粘贴并发送确认事件
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `text`: 粘贴的文本
+    /// - `bracketed`: 终端程序是否启用括号粘贴模式
+    ///
+    /// # 返回
+    /// 需要确认时返回粘贴 ID，可直接写入时返回 `None`
+    pub fn check(
+        &self,
+        session_id: &str,
+        text: &str,
+        bracketed: bool,
+    ) -> Result<Option<String>, TerminalError> {
+        let analysis = analyze_paste(text);
+        if analysis.warnings.is_empty() {
+            return Ok(None);
+        }
+
+        let mut pending = self.pending.lock();
+        if pending.len() >= MAX_PENDING_PASTES {
+            return Err(TerminalError::Internal(
+                "等待确认的粘贴过多，请先处理之前的粘贴".to_string(),
+            ));
+        }
+        let paste_id = Uuid::new_v4().to_string();
+        let event = TerminalPasteWarningEvent {
+            paste_id: paste_id.clone(),
+            session_id: session_id.to_string(),
+            warnings: analysis.warnings,
+            line_breaks: analysis.line_breaks,
+            control_chars: analysis.control_chars,
+            bracketed,
+            size: text.len(),
+            preview: preview(text),
+        };
+        pending.insert(
+            paste_id.clone(),
+            PendingPaste {
+                session_id: session_id.to_string(),
+                text: text.to_string(),
+            },
+        );
+        drop(pending);
+
+        if let Some(ref app_handle) = self.app_handle {
+            if let Err(e) = app_handle.emit(event_names::PASTE_WARNING, &event) {
+                self.pending.lock().remove(&paste_id);
+                return Err(TerminalError::Internal(format!(
+                    "发送粘贴确认事件失败: {}",
+                    e
+                )));
+            }
+        }
+        tracing::debug!(
+            "[Paste] 粘贴需确认: session={}, warnings={:?}, bracketed={}",
+            session_id,
+            event.warnings,
+            bracketed
+        );
+        Ok(Some(paste_id))
+    }
+
+    /// 取出等待确认的粘贴
+    ///
+    /// # 参数
+    /// - `paste_id`: 粘贴 ID
+    pub fn take(&self, paste_id: &str) -> Result<PendingPaste, TerminalError> {
+        self.pending
+            .lock()
+            .remove(paste_id)
+            .ok_or_else(|| TerminalError::Internal(format!("粘贴不存在或已处理: {}", paste_id)))
+    }
+
+    /// 丢弃会话的所有等待确认的粘贴（会话关闭时调用）
+    pub fn discard_session(&self, session_id: &str) {
+        self.pending
+            .lock()
+            .retain(|_, paste| paste.session_id != session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_paste() {
+        assert!(analyze_paste("ls -la").warnings.is_empty());
+        assert!(analyze_paste("a\tb").warnings.is_empty());
+
+        let analysis = analyze_paste("echo 1\r\necho 2\n");
+        assert_eq!(analysis.line_breaks, 2);
+        assert_eq!(analysis.warnings, vec![PasteWarning::MultiLine]);
+
+        let analysis = analyze_paste("rm\x1b[201~ -rf\x07\u{9b}");
+        assert_eq!(analysis.control_chars, 3);
+        assert_eq!(analysis.warnings, vec![PasteWarning::ControlCharacters]);
+    }
+
+    #[test]
+    fn test_encode_paste() {
+        assert_eq!(encode_paste("a\r\nb\nc", false), b"a\rb\rc");
+        assert_eq!(encode_paste("ls", true), b"\x1b[200~ls\x1b[201~");
+        assert_eq!(
+            encode_paste("x\x1b[201~; rm -rf ~\x1b[20\x1b[201~1~", true),
+            b"\x1b[200~x; rm -rf ~\x1b[201~"
+        );
+    }
+
+    #[test]
+    fn test_tracker() {
+        let mut tracker = BracketedPasteTracker::new();
+        assert!(!tracker.enabled());
+        assert!(tracker.feed(b"prompt\x1b[?2004h$ "));
+        assert!(!tracker.feed(b"\x1b[?1049;2004l"));
+
+        // 序列跨读取块
+        assert!(!tracker.feed(b"\x1b[?20"));
+        assert!(tracker.feed(b"04h"));
+
+        // 其他模式和普通 CSI 不影响
+        assert!(tracker.feed(b"\x1b[2004l\x1b[?25l\x1b[?2004;1h"));
+        assert!(!tracker.feed(b"\x1bc"));
+    }
+
+    #[test]
+    fn test_guard() {
+        let guard = PasteGuard::default();
+        assert_eq!(guard.check("s1", "ls", false).unwrap(), None);
+
+        let id = guard.check("s1", "a\nb", true).unwrap().unwrap();
+        assert_eq!(
+            guard.take(&id).unwrap(),
+            PendingPaste {
+                session_id: "s1".to_string(),
+                text: "a\nb".to_string(),
+            }
+        );
+        assert!(guard.take(&id).is_err());
+
+        let id = guard.check("s2", "\x03", false).unwrap().unwrap();
+        guard.discard_session("s2");
+        assert!(guard.take(&id).is_err());
+        assert_eq!(preview("a\x03\x7f\tb"), "a^C^?\tb");
+    }
+}
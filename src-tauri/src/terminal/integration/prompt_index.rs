@@ -0,0 +1,256 @@
+//! 提示符索引
+//!
+//! 按 OSC 133 提示符标记（`A`）为会话输出建立索引，供前端跳转到上一个 / 下一个提示符，
+//! 以及重新执行某个提示符处的命令。
+//!
+//! ## 设计说明
+//! - 标记位置使用会话输出位置（与块文件的 [`written`] 对应）和之前的换行数
+//! - 前端按 `line_count - line` 计算标记距输出末尾的行数，与自身保留了多少历史无关
+//! - 恢复的会话先扫描块文件中的历史输出（见 [`PromptIndex::feed`]），之后随输出增量更新
+//! - 命令文本优先取 Shell 通过 `setcmd` 上报的内容，没有时从 `B` 与 `C` 之间的回显中提取
+//!
+//! [`written`]: crate::terminal::persistence::BlockFile::written
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::osc_parser::{strip_osc_sequences, OSCSequence, ParsedOSC, PromptMarkType};
+
+/// 最多保留的提示符标记数
+pub const MAX_PROMPT_MARKS: usize = 10_000;
+
+/// 命令回显的最大缓存长度（字节）
+const MAX_ECHO_LEN: usize = 4096;
+
+/// 提示符标记
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptMark {
+    /// 标记序号（会话内从 0 开始递增）
+    pub index: u64,
+    /// 提示符在会话输出中的位置
+    pub offset: u64,
+    /// 提示符之前的换行数（从会话输出开头计算）
+    pub line: u64,
+    /// 在该提示符执行的命令（未执行或无法提取时为 `None`）
+    pub command: Option<String>,
+    /// 命令退出码（命令未结束或 Shell 未上报时为 `None`）
+    pub exit_code: Option<i32>,
+}
+
+/// 提示符标记列表
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptMarks {
+    /// 提示符标记（按输出顺序）
+    pub marks: Vec<PromptMark>,
+    /// 已索引的输出中的换行数
+    pub line_count: u64,
+    /// 已索引的输出结束位置
+    pub offset: u64,
+}
+
+/// 提示符索引
+#[derive(Debug, Default)]
+pub struct PromptIndex {
+    marks: VecDeque<PromptMark>,
+    next_index: u64,
+    line_count: u64,
+    offset: u64,
+    /// OSC 133;B 之后的命令回显（等待 OSC 133;C）
+    echo: Option<Vec<u8>>,
+    /// `setcmd` 上报的命令文本
+    reported_command: Option<String>,
+}
+
+impl PromptIndex {
+    /// 创建空索引
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 索引一段输出
+    ///
+    /// # 参数
+    /// - `data`: 输出数据
+    /// - `base`: `data` 在会话输出中的起始位置
+    /// - `parsed`: `data` 中解析出的 OSC 序列（见 [`super::OSCParser::parse`]）
+    pub fn feed(&mut self, data: &[u8], base: u64, parsed: &[ParsedOSC]) {
+        let mut pos = 0;
+        for osc in parsed {
+            self.feed_text(&data[pos..osc.range.start]);
+            self.handle_osc(&osc.sequence, base + osc.range.start as u64);
+            pos = osc.range.end;
+        }
+        self.feed_text(&data[pos..]);
+        self.offset = base + data.len() as u64;
+    }
+
+    /// 获取全部标记
+    pub fn snapshot(&self) -> PromptMarks {
+        PromptMarks {
+            marks: self.marks.iter().cloned().collect(),
+            line_count: self.line_count,
+            offset: self.offset,
+        }
+    }
+
+    /// 获取指定序号的标记
+    pub fn mark(&self, index: u64) -> Option<PromptMark> {
+        self.marks.iter().find(|mark| mark.index == index).cloned()
+    }
+
+    /// 清空索引
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 统计 OSC 序列之间的文本
+    fn feed_text(&mut self, text: &[u8]) {
+        self.line_count += text.iter().filter(|&&b| b == b'\n').count() as u64;
+        if let Some(echo) = self.echo.as_mut() {
+            let len = text.len().min(MAX_ECHO_LEN.saturating_sub(echo.len()));
+            echo.extend_from_slice(&text[..len]);
+        }
+    }
+
+    fn handle_osc(&mut self, sequence: &OSCSequence, offset: u64) {
+        let (mark_type, exit_code) = match sequence {
+            OSCSequence::PromptMark {
+                mark_type,
+                exit_code,
+            } => (*mark_type, *exit_code),
+            OSCSequence::WaveCommand { command } => {
+                if let Some(text) = command.strip_prefix("setcmd ") {
+                    self.reported_command = Some(text.trim_end().to_string());
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        match mark_type {
+            PromptMarkType::PromptStart => {
+                if self.marks.len() >= MAX_PROMPT_MARKS {
+                    self.marks.pop_front();
+                }
+                self.marks.push_back(PromptMark {
+                    index: self.next_index,
+                    offset,
+                    line: self.line_count,
+                    command: None,
+                    exit_code: None,
+                });
+                self.next_index += 1;
+                self.echo = None;
+                self.reported_command = None;
+            }
+            PromptMarkType::CommandStart => self.echo = Some(Vec::new()),
+            PromptMarkType::CommandExecuted => {
+                let echo = self.echo.take();
+                let command = self
+                    .reported_command
+                    .take()
+                    .filter(|text| !text.is_empty())
+                    .or_else(|| echo.and_then(|echo| command_text(&echo)));
+                if let Some(mark) = self.marks.back_mut() {
+                    mark.command = mark.command.take().or(command);
+                }
+            }
+            PromptMarkType::CommandFinished => {
+                if let Some(mark) = self.marks.back_mut() {
+                    if mark.command.is_some() && mark.exit_code.is_none() {
+                        mark.exit_code = exit_code;
+                    }
+                }
+            }
+            PromptMarkType::Unknown(_) => {}
+        }
+    }
+}
+
+/// 从命令回显中提取可读文本，去掉 OSC/CSI 序列和控制字符
+pub fn command_text(echo: &[u8]) -> Option<String> {
+    let echo = strip_osc_sequences(echo);
+    let mut text = Vec::with_capacity(echo.len());
+    let mut i = 0;
+    while i < echo.len() {
+        match echo[i] {
+            // CSI: ESC [ 参数 ... 终止字节 0x40-0x7E
+            0x1b if echo.get(i + 1) == Some(&b'[') => {
+                i += 2;
+                while i < echo.len() && !(0x40..=0x7e).contains(&echo[i]) {
+                    i += 1;
+                }
+            }
+            b if b < 0x20 || b == 0x7f => {}
+            b => text.push(b),
+        }
+        i += 1;
+    }
+    let text = String::from_utf8_lossy(&text).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::integration::OSCParser;
+
+    fn feed(index: &mut PromptIndex, data: &[u8], base: u64) {
+        index.feed(data, base, &OSCParser::parse(data));
+    }
+
+    #[test]
+    fn test_prompt_marks_with_lines_and_commands() {
+        let mut index = PromptIndex::new();
+        let data = b"motd\n\x1b]133;A\x07$ \x1b]133;B\x07ls\x1b]133;C\x07a\nb\n\x1b]133;D;1\x07\
+\x1b]133;A\x07$ \x1b]16162;setcmd make\x1b\\\x1b]133;C\x07";
+        feed(&mut index, data, 100);
+
+        let snapshot = index.snapshot();
+        assert_eq!(snapshot.line_count, 3);
+        assert_eq!(snapshot.offset, 100 + data.len() as u64);
+        assert_eq!(snapshot.marks.len(), 2);
+
+        let first = &snapshot.marks[0];
+        assert_eq!((first.index, first.offset, first.line), (0, 105, 1));
+        assert_eq!(first.command.as_deref(), Some("ls"));
+        assert_eq!(first.exit_code, Some(1));
+
+        let second = index.mark(1).unwrap();
+        assert_eq!(second.line, 3);
+        assert_eq!(second.command.as_deref(), Some("make"));
+        assert_eq!(second.exit_code, None);
+    }
+
+    #[test]
+    fn test_echo_across_chunks() {
+        let mut index = PromptIndex::new();
+        let first = b"\x1b]133;A\x07$ \x1b]133;B\x07git st";
+        feed(&mut index, first, 0);
+        let second = b"atus\x1b]133;C\x07\n";
+        feed(&mut index, second, first.len() as u64);
+
+        let mark = index.mark(0).unwrap();
+        assert_eq!(mark.command.as_deref(), Some("git status"));
+        assert_eq!(index.snapshot().line_count, 1);
+    }
+
+    #[test]
+    fn test_empty_prompt_has_no_command() {
+        let mut index = PromptIndex::new();
+        feed(
+            &mut index,
+            b"\x1b]133;A\x07$ \x1b]133;B\x07\x1b]133;C\x07\x1b]133;D;0\x07\x1b]133;A\x07",
+            0,
+        );
+        let marks = index.snapshot().marks;
+        assert_eq!(marks.len(), 2);
+        assert_eq!(marks[0].command, None);
+        assert_eq!(marks[0].exit_code, None);
+
+        index.reset();
+        assert!(index.snapshot().marks.is_empty());
+        assert_eq!(index.mark(0), None);
+    }
+}
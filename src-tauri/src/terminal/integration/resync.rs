@@ -389,7 +389,7 @@ impl ResyncController {
     /// - `full_reset`: 是否使用完全重置序列
     ///
     /// _Requirements: 2.3_
-    fn send_reset_sequence(
+    pub(crate) fn send_reset_sequence(
         app_handle: &tauri::AppHandle,
         block_id: &str,
         full_reset: bool,
@@ -434,7 +434,7 @@ impl ResyncController {
     /// 恢复的数据大小（字节）
     ///
     /// _Requirements: 2.4_
-    fn restore_history(
+    pub(crate) fn restore_history(
         app_handle: &tauri::AppHandle,
         block_id: &str,
         block_file: &BlockFile,
@@ -4,29 +4,52 @@
 //! - 当前工作目录跟踪
 //! - 命令执行状态管理
 //! - 命令时间记录
+//! - 命令历史记录
 //! - OSC 序列处理
 //!
 //! ## 功能
 //! - 处理 OSC 7 更新当前目录
-//! - 处理 OSC 52 剪贴板操作
+//! - 处理 OSC 8 超链接，检测输出中的 URL 和文件路径（发送 `terminal:links` 事件）
+//! - 处理 OSC 52 剪贴板操作（配置剪贴板桥接后按主机策略写入和读取本机剪贴板）
 //! - 处理 OSC 133 命令提示符标记
-//! - 处理 OSC 16162 Wave 命令
+//! - 处理 OSC 16162 Wave 命令（`setcmd` 上报即将执行的命令文本）
+//! - 处理 OSC 1337 `CurrentDir` 和 `SetUserVar`（`gitBranch` 用户变量上报 Git 分支）
+//! - 命令结束时写入命令历史（需通过 `with_command_history` 配置）
+//! - 记录每个命令块的元数据（命令、退出码、耗时、目录、Git 分支），发送 `terminal:command-block` 事件
+//! - 记录命令块输出在会话输出中的位置（OSC 133;C 到 D），供从块文件读取单条命令的输出
+//! - 维护提示符索引（OSC 133;A 的位置、行号和命令），供前端跳转到提示符和重新执行命令
 //!
 //! ## Requirements
 //! - 6.5: 支持 bash、zsh、fish、pwsh 四种 Shell 类型
 //! - 6.6: Shell 集成状态变更事件通知
 //! - 6.8: 命令开始和结束时间记录
 
-use std::sync::atomic::{AtomicI64, Ordering};
-use std::sync::RwLock;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
 
-use super::osc_parser::{OSCParser, OSCSequence, PromptMarkType};
+use super::clipboard::{ClipboardBridge, ClipboardResponder};
+use super::link_detector::{DetectedLink, LinkDetector};
+use super::osc_parser::{OSCParser, OSCSequence, ParsedOSC, PromptMarkType};
+use super::prompt_index::{PromptIndex, PromptMark, PromptMarks};
 use crate::terminal::error::TerminalError;
-use crate::terminal::events::event_names;
+use crate::terminal::events::{event_names, TerminalCommandBlockEvent, TerminalLinksEvent};
+use crate::terminal::persistence::command_history::LOCAL_HOST;
+use crate::terminal::persistence::{CommandExecution, CommandHistoryStore};
+
+/// 跨读取块缓存的未完成 OSC 序列最大长度（需容纳 1 MiB 剪贴板内容的 base64 编码）
+const MAX_PARTIAL_OSC_LEN: usize = 2 * 1024 * 1024;
+
+/// 每个会话保留的命令块数量上限
+pub const MAX_COMMAND_BLOCKS: usize = 1000;
+
+/// 上报 Git 分支的 OSC 1337 用户变量名
+pub const GIT_BRANCH_USER_VAR: &str = "gitBranch";
 
 /// Shell 类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -40,6 +63,8 @@ pub enum ShellType {
     Fish,
     /// PowerShell
     Pwsh,
+    /// Nushell
+    Nu,
     /// 未知 Shell
     Unknown,
 }
@@ -56,6 +81,8 @@ impl ShellType {
             Self::Fish
         } else if path_lower.contains("pwsh") || path_lower.contains("powershell") {
             Self::Pwsh
+        } else if matches!(path_lower.rsplit(['/', '\\']).next(), Some("nu" | "nu.exe")) {
+            Self::Nu
         } else {
             Self::Unknown
         }
@@ -68,6 +95,7 @@ impl ShellType {
             Self::Zsh => "zsh",
             Self::Fish => "fish",
             Self::Pwsh => "pwsh",
+            Self::Nu => "nu",
             Self::Unknown => "unknown",
         }
     }
@@ -106,6 +134,10 @@ pub struct CommandInfo {
     pub end_time: Option<i64>,
     /// 命令持续时间（毫秒）
     pub duration_ms: Option<i64>,
+    /// 命令文本（Shell 通过 `setcmd` 上报）
+    pub command: Option<String>,
+    /// 退出码
+    pub exit_code: Option<i32>,
 }
 
 impl CommandInfo {
@@ -115,6 +147,8 @@ impl CommandInfo {
             start_time: current_timestamp_ms(),
             end_time: None,
             duration_ms: None,
+            command: None,
+            exit_code: None,
         }
     }
 
@@ -124,6 +158,11 @@ impl CommandInfo {
         self.end_time = Some(end);
         self.duration_ms = Some(end - self.start_time);
     }
+
+    /// 命令是否已结束
+    pub fn is_finished(&self) -> bool {
+        self.end_time.is_some()
+    }
 }
 
 impl Default for CommandInfo {
@@ -132,6 +171,58 @@ impl Default for CommandInfo {
     }
 }
 
+/// 命令块元数据
+///
+/// 每条开始执行的命令对应一个命令块，用于渲染块标题和筛选失败的命令。
+/// 目录和 Git 分支取命令开始执行时的值。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandBlock {
+    /// 命令块序号（会话内从 0 开始递增）
+    pub index: u64,
+    /// 命令文本（Shell 通过 `setcmd` 上报）
+    pub command: Option<String>,
+    /// 退出码（命令未结束或 Shell 未上报时为 `None`）
+    pub exit_code: Option<i32>,
+    /// 命令开始时间（Unix 时间戳，毫秒）
+    pub start_time: i64,
+    /// 命令结束时间（Unix 时间戳，毫秒）
+    pub end_time: Option<i64>,
+    /// 命令持续时间（毫秒）
+    pub duration_ms: Option<i64>,
+    /// 工作目录
+    pub cwd: Option<String>,
+    /// Git 分支
+    pub git_branch: Option<String>,
+    /// 命令输出在会话输出中的起始位置（OSC 133;C 之后）
+    #[serde(skip)]
+    pub output_start: u64,
+    /// 命令输出的结束位置（OSC 133;D 或下一个提示符之前，命令未结束时为 `None`）
+    #[serde(skip)]
+    pub output_end: Option<u64>,
+}
+
+impl CommandBlock {
+    /// 命令是否已结束
+    pub fn is_finished(&self) -> bool {
+        self.end_time.is_some()
+    }
+
+    /// 命令是否执行失败（退出码非 0）
+    pub fn is_failed(&self) -> bool {
+        self.exit_code.is_some_and(|code| code != 0)
+    }
+}
+
+/// 命令块查询条件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandBlockQuery {
+    /// 只返回执行失败的命令块（退出码非 0）
+    pub failed_only: bool,
+    /// 最多返回最近的多少个命令块
+    pub limit: Option<usize>,
+}
+
 /// Shell 集成状态变更事件
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShellIntegrationEvent {
@@ -161,6 +252,30 @@ pub struct ShellIntegration {
     current_command: RwLock<Option<CommandInfo>>,
     /// 上次命令开始时间
     last_command_start: AtomicI64,
+    /// `setcmd` 上报、尚未开始执行的命令文本
+    pending_command: RwLock<Option<String>>,
+    /// 当前 Git 分支（OSC 1337 `SetUserVar=gitBranch` 上报）
+    git_branch: RwLock<Option<String>>,
+    /// 命令块列表（按开始顺序，最多保留 `MAX_COMMAND_BLOCKS` 个）
+    blocks: RwLock<VecDeque<CommandBlock>>,
+    /// 下一个命令块序号
+    next_block_index: AtomicU64,
+    /// 已处理的输出位置（与块文件的写入位置对应）
+    output_offset: AtomicU64,
+    /// 正在处理的 OSC 序列在会话输出中的位置
+    osc_range: RwLock<Range<u64>>,
+    /// 上一次输出末尾未完成的 OSC 序列
+    partial_osc: RwLock<Vec<u8>>,
+    /// 输出链接检测器
+    link_detector: RwLock<LinkDetector>,
+    /// 提示符索引
+    prompt_index: RwLock<PromptIndex>,
+    /// 命令历史存储及主机名（可选）
+    history: Option<(Arc<CommandHistoryStore>, String)>,
+    /// 剪贴板桥接及主机名（可选）
+    clipboard: Option<(Arc<ClipboardBridge>, String)>,
+    /// 向终端程序回复数据（OSC 52 读取请求使用）
+    responder: RwLock<Option<ClipboardResponder>>,
     /// Tauri 应用句柄（可选）
     app_handle: Option<tauri::AppHandle>,
 }
@@ -178,6 +293,18 @@ impl ShellIntegration {
             status: RwLock::new(ShellIntegrationStatus::Unknown),
             current_command: RwLock::new(None),
             last_command_start: AtomicI64::new(0),
+            pending_command: RwLock::new(None),
+            git_branch: RwLock::new(None),
+            blocks: RwLock::new(VecDeque::new()),
+            next_block_index: AtomicU64::new(0),
+            output_offset: AtomicU64::new(0),
+            osc_range: RwLock::new(0..0),
+            partial_osc: RwLock::new(Vec::new()),
+            link_detector: RwLock::new(LinkDetector::new()),
+            prompt_index: RwLock::new(PromptIndex::new()),
+            history: None,
+            clipboard: None,
+            responder: RwLock::new(None),
             app_handle: None,
         }
     }
@@ -195,10 +322,93 @@ impl ShellIntegration {
             status: RwLock::new(ShellIntegrationStatus::Unknown),
             current_command: RwLock::new(None),
             last_command_start: AtomicI64::new(0),
+            pending_command: RwLock::new(None),
+            git_branch: RwLock::new(None),
+            blocks: RwLock::new(VecDeque::new()),
+            next_block_index: AtomicU64::new(0),
+            output_offset: AtomicU64::new(0),
+            osc_range: RwLock::new(0..0),
+            partial_osc: RwLock::new(Vec::new()),
+            link_detector: RwLock::new(LinkDetector::new()),
+            prompt_index: RwLock::new(PromptIndex::new()),
+            history: None,
+            clipboard: None,
+            responder: RwLock::new(None),
             app_handle: Some(app_handle),
         }
     }
 
+    /// 为终端会话创建 Shell 集成处理器
+    ///
+    /// 应用注册了命令历史存储时自动写入命令历史，注册了剪贴板桥接时按主机策略处理 OSC 52。
+    ///
+    /// # 参数
+    /// - `block_id`: Block ID
+    /// - `app_handle`: Tauri 应用句柄
+    /// - `connection`: 连接名称（SSH/WSL），本地会话为 `None`
+    pub fn for_session(
+        block_id: String,
+        app_handle: tauri::AppHandle,
+        connection: Option<&str>,
+    ) -> Self {
+        let host = connection
+            .filter(|c| !c.is_empty())
+            .unwrap_or(LOCAL_HOST)
+            .to_string();
+        let store = app_handle
+            .try_state::<Arc<CommandHistoryStore>>()
+            .map(|state| state.inner().clone());
+        let bridge = app_handle
+            .try_state::<Arc<ClipboardBridge>>()
+            .map(|state| state.inner().clone());
+        let mut integration = Self::with_app_handle(block_id, app_handle);
+        if let Some(store) = store {
+            integration = integration.with_command_history(store, host.clone());
+        }
+        if let Some(bridge) = bridge {
+            integration = integration.with_clipboard(bridge, host);
+        }
+        integration
+    }
+
+    /// 配置命令历史存储
+    ///
+    /// 配置后每条带命令文本的命令结束时写入命令历史。
+    ///
+    /// # 参数
+    /// - `store`: 命令历史存储
+    /// - `host`: 主机名（连接名称，本地为 `local`）
+    pub fn with_command_history(mut self, store: Arc<CommandHistoryStore>, host: String) -> Self {
+        self.history = Some((store, host));
+        self
+    }
+
+    /// 配置剪贴板桥接
+    ///
+    /// 配置后 OSC 52 剪贴板请求按主机的剪贴板策略处理。
+    ///
+    /// # 参数
+    /// - `bridge`: 剪贴板桥接
+    /// - `host`: 主机名（连接名称，本地为 `local`）
+    pub fn with_clipboard(mut self, bridge: Arc<ClipboardBridge>, host: String) -> Self {
+        self.clipboard = Some((bridge, host));
+        self
+    }
+
+    /// 设置向终端程序回复数据的方式
+    ///
+    /// OSC 52 读取请求通过它把剪贴板内容写回终端输入，未设置时忽略读取请求。
+    pub fn set_responder(&self, responder: ClipboardResponder) {
+        *self.responder.write().unwrap() = Some(responder);
+    }
+
+    /// 设置会话输出的起始位置
+    ///
+    /// 会话输出写入块文件时传入块文件已写入的字节数，使命令块的输出位置与块文件对应。
+    pub fn set_output_offset(&self, offset: u64) {
+        self.output_offset.store(offset, Ordering::SeqCst);
+    }
+
     /// 设置 Shell 类型
     ///
     /// # 参数
@@ -236,9 +446,72 @@ impl ShellIntegration {
         self.current_command.read().unwrap().clone()
     }
 
+    /// 获取当前 Git 分支
+    pub fn get_git_branch(&self) -> Option<String> {
+        self.git_branch.read().unwrap().clone()
+    }
+
+    /// 获取指定序号的命令块
+    pub fn command_block(&self, index: u64) -> Option<CommandBlock> {
+        self.blocks
+            .read()
+            .unwrap()
+            .iter()
+            .find(|block| block.index == index)
+            .cloned()
+    }
+
+    /// 查询命令块
+    ///
+    /// # 参数
+    /// - `query`: 查询条件
+    ///
+    /// # 返回
+    /// 符合条件的命令块，按开始顺序排列；指定 `limit` 时只返回最近的若干个
+    pub fn command_blocks(&self, query: &CommandBlockQuery) -> Vec<CommandBlock> {
+        let blocks = self.blocks.read().unwrap();
+        let mut matched: Vec<CommandBlock> = blocks
+            .iter()
+            .filter(|block| !query.failed_only || block.is_failed())
+            .cloned()
+            .collect();
+        if let Some(limit) = query.limit {
+            let skip = matched.len().saturating_sub(limit);
+            matched.drain(..skip);
+        }
+        matched
+    }
+
+    /// 获取提示符标记
+    pub fn prompt_marks(&self) -> PromptMarks {
+        self.prompt_index.read().unwrap().snapshot()
+    }
+
+    /// 获取指定序号的提示符标记
+    pub fn prompt_mark(&self, index: u64) -> Option<PromptMark> {
+        self.prompt_index.read().unwrap().mark(index)
+    }
+
+    /// 索引会话的历史输出
+    ///
+    /// 恢复会话时传入块文件中保留的输出，使提示符索引包含之前的提示符；
+    /// 需在处理新的输出之前调用。
+    ///
+    /// # 参数
+    /// - `data`: 历史输出
+    /// - `start`: `data` 在会话输出中的起始位置
+    pub fn index_history(&self, data: &[u8], start: u64) {
+        let parsed = OSCParser::parse(data);
+        self.prompt_index
+            .write()
+            .unwrap()
+            .feed(data, start, &parsed);
+    }
+
     /// 处理 PTY 输出数据
     ///
-    /// 解析数据中的 OSC 序列并更新状态。
+    /// 解析数据中的 OSC 序列并更新状态，检测输出中的链接。末尾未完成的 OSC 序列
+    /// 会缓存到下一次调用，避免序列被 PTY 读取块截断时丢失。
     ///
     /// # 参数
     /// - `data`: PTY 输出数据
@@ -246,10 +519,42 @@ impl ShellIntegration {
     /// # 返回
     /// 处理的 OSC 序列数量
     pub fn process_output(&self, data: &[u8]) -> usize {
-        let parsed = OSCParser::parse(data);
+        let offset = self
+            .output_offset
+            .fetch_add(data.len() as u64, Ordering::SeqCst);
+        let (data, base) = {
+            let mut partial = self.partial_osc.write().unwrap();
+            // 拼接后的数据从缓存的未完成序列开始
+            let base = offset.saturating_sub(partial.len() as u64);
+            let mut data = if partial.is_empty() {
+                data.to_vec()
+            } else {
+                let mut joined = std::mem::take(&mut *partial);
+                joined.extend_from_slice(data);
+                joined
+            };
+            let split = partial_osc_start(&data);
+            if data.len() - split <= MAX_PARTIAL_OSC_LEN {
+                *partial = data.split_off(split);
+            }
+            (data, base)
+        };
+
+        let parsed = OSCParser::parse(&data);
         let count = parsed.len();
+        self.prompt_index
+            .write()
+            .unwrap()
+            .feed(&data, base, &parsed);
+
+        // 链接按本次输出前的目录解析（目录变更的 OSC 7 在命令输出之后）
+        let cwd = self.get_current_dir();
+        let links = self.detect_links(&data, &parsed);
+        self.send_links_event(links, cwd);
 
         for osc in parsed {
+            *self.osc_range.write().unwrap() =
+                base + osc.range.start as u64..base + osc.range.end as u64;
             if let Err(e) = self.process_osc(&osc.sequence) {
                 tracing::warn!(
                     "[ShellIntegration] 处理 OSC 序列失败: block_id={}, error={}",
@@ -273,11 +578,20 @@ impl ShellIntegration {
             OSCSequence::CurrentDirectory { hostname: _, path } => {
                 self.update_current_dir(path.clone());
             }
+            OSCSequence::Hyperlink { .. } => {
+                // 超链接需要结合链接文本，在 process_output 中处理
+            }
             OSCSequence::Clipboard { selection, data } => {
                 self.handle_clipboard(selection, data)?;
             }
-            OSCSequence::PromptMark { mark_type } => {
-                self.handle_prompt_mark(*mark_type);
+            OSCSequence::PromptMark {
+                mark_type,
+                exit_code,
+            } => {
+                self.handle_prompt_mark(*mark_type, *exit_code);
+            }
+            OSCSequence::UserVar { name, value } => {
+                self.handle_user_var(name, value);
             }
             OSCSequence::WaveCommand { command } => {
                 self.handle_wave_command(command)?;
@@ -294,6 +608,47 @@ impl ShellIntegration {
         Ok(())
     }
 
+    /// 检测输出中的链接
+    ///
+    /// OSC 序列之间的文本交给链接检测器，OSC 8 序列用于开始和结束超链接。
+    fn detect_links(&self, data: &[u8], parsed: &[ParsedOSC]) -> Vec<DetectedLink> {
+        let mut detector = self.link_detector.write().unwrap();
+        let mut links = Vec::new();
+        let mut last_end = 0;
+
+        for osc in parsed {
+            links.extend(detector.feed_text(&data[last_end..osc.range.start]));
+            if let OSCSequence::Hyperlink { id, uri } = &osc.sequence {
+                links.extend(detector.feed_hyperlink(id.as_deref(), uri));
+            }
+            last_end = osc.range.end;
+        }
+        links.extend(detector.feed_text(&data[last_end..]));
+        links
+    }
+
+    /// 发送链接事件
+    fn send_links_event(&self, links: Vec<DetectedLink>, cwd: Option<String>) {
+        if links.is_empty() {
+            return;
+        }
+        if let Some(ref app_handle) = self.app_handle {
+            let event = TerminalLinksEvent {
+                session_id: self.block_id.clone(),
+                cwd,
+                links,
+            };
+
+            if let Err(e) = app_handle.emit(event_names::TERMINAL_LINKS, &event) {
+                tracing::warn!(
+                    "[ShellIntegration] 发送链接事件失败: block_id={}, error={}",
+                    self.block_id,
+                    e
+                );
+            }
+        }
+    }
+
     /// 更新当前工作目录
     ///
     /// _Requirements: 6.1_
@@ -323,12 +678,16 @@ impl ShellIntegration {
     /// _Requirements: 6.2_
     fn handle_clipboard(&self, selection: &str, data: &str) -> Result<(), TerminalError> {
         if data == "?" {
-            // 查询请求，暂不支持
             tracing::debug!(
                 "[ShellIntegration] 剪贴板查询请求: block_id={}, selection={}",
                 self.block_id,
                 selection
             );
+            // 未配置剪贴板桥接或无法回复时忽略查询
+            let responder = self.responder.read().unwrap().clone();
+            if let (Some((bridge, host)), Some(responder)) = (&self.clipboard, responder) {
+                bridge.handle_read(&self.block_id, host, selection, responder);
+            }
             return Ok(());
         }
 
@@ -341,8 +700,10 @@ impl ShellIntegration {
                 content.len()
             );
 
-            // 发送剪贴板事件到前端
-            if let Some(ref app_handle) = self.app_handle {
+            if let Some((ref bridge, ref host)) = self.clipboard {
+                bridge.handle_write(&self.block_id, host, selection, content);
+            } else if let Some(ref app_handle) = self.app_handle {
+                // 未配置剪贴板桥接时只发送剪贴板事件到前端
                 let _ = app_handle.emit(
                     event_names::CLIPBOARD_WRITE,
                     serde_json::json!({
@@ -360,11 +721,11 @@ impl ShellIntegration {
     /// 处理命令提示符标记
     ///
     /// _Requirements: 6.3, 6.6, 6.8_
-    fn handle_prompt_mark(&self, mark_type: PromptMarkType) {
+    fn handle_prompt_mark(&self, mark_type: PromptMarkType, exit_code: Option<i32>) {
         match mark_type {
             PromptMarkType::PromptStart => {
                 // 提示符开始，命令已结束
-                self.finish_command();
+                self.finish_command(None);
                 self.set_status(ShellIntegrationStatus::Ready);
             }
             PromptMarkType::CommandStart => {
@@ -378,7 +739,7 @@ impl ShellIntegration {
             }
             PromptMarkType::CommandFinished => {
                 // 命令执行完成
-                self.finish_command();
+                self.finish_command(exit_code);
                 self.set_status(ShellIntegrationStatus::Ready);
             }
             PromptMarkType::Unknown(c) => {
@@ -391,6 +752,21 @@ impl ShellIntegration {
         }
     }
 
+    /// 处理 OSC 1337 用户变量
+    fn handle_user_var(&self, name: &str, value: &str) {
+        if name != GIT_BRANCH_USER_VAR {
+            tracing::debug!(
+                "[ShellIntegration] 忽略用户变量: block_id={}, name={}",
+                self.block_id,
+                name
+            );
+            return;
+        }
+        // 空值表示当前目录不在 Git 仓库中
+        let branch = value.trim();
+        *self.git_branch.write().unwrap() = (!branch.is_empty()).then(|| branch.to_string());
+    }
+
     /// 处理 Wave 命令
     ///
     /// _Requirements: 6.4_
@@ -419,6 +795,12 @@ impl ShellIntegration {
                     self.set_shell_type_from_path(args);
                 }
             }
+            "setcmd" => {
+                // 即将执行的命令文本，在 OSC 133;C 时关联到命令
+                let command = args.trim_end();
+                *self.pending_command.write().unwrap() =
+                    (!command.is_empty()).then(|| command.to_string());
+            }
             _ => {
                 tracing::debug!(
                     "[ShellIntegration] 未知 Wave 命令: block_id={}, cmd={}",
@@ -457,11 +839,34 @@ impl ShellIntegration {
     ///
     /// _Requirements: 6.8_
     fn start_command(&self) {
+        let command = self.pending_command.write().unwrap().take();
+        let mut guard = self.current_command.write().unwrap();
+        if guard.as_ref().is_some_and(|cmd| !cmd.is_finished()) {
+            // 重复的 OSC 133;C（如 bash 的 DEBUG trap 和 PSReadLine 同时触发）
+            return;
+        }
+
         let now = current_timestamp_ms();
         self.last_command_start.store(now, Ordering::SeqCst);
-
-        let mut guard = self.current_command.write().unwrap();
-        *guard = Some(CommandInfo::new());
+        let info = CommandInfo {
+            command,
+            ..CommandInfo::new()
+        };
+        let block = CommandBlock {
+            index: self.next_block_index.fetch_add(1, Ordering::SeqCst),
+            command: info.command.clone(),
+            exit_code: None,
+            start_time: info.start_time,
+            end_time: None,
+            duration_ms: None,
+            cwd: self.get_current_dir(),
+            git_branch: self.get_git_branch(),
+            output_start: self.osc_range.read().unwrap().end,
+            output_end: None,
+        };
+        *guard = Some(info);
+        drop(guard);
+        self.push_block(block);
 
         tracing::debug!(
             "[ShellIntegration] 命令开始: block_id={}, time={}",
@@ -472,15 +877,98 @@ impl ShellIntegration {
 
     /// 结束命令
     ///
+    /// OSC 133;D 和随后的 OSC 133;A 都会结束命令，只有第一次生效。
+    ///
     /// _Requirements: 6.8_
-    fn finish_command(&self) {
-        let mut guard = self.current_command.write().unwrap();
-        if let Some(ref mut cmd) = *guard {
-            cmd.finish();
-            tracing::debug!(
-                "[ShellIntegration] 命令结束: block_id={}, duration_ms={:?}",
+    fn finish_command(&self, exit_code: Option<i32>) {
+        let finished = {
+            let mut guard = self.current_command.write().unwrap();
+            match guard.as_mut() {
+                Some(cmd) if !cmd.is_finished() => {
+                    cmd.finish();
+                    cmd.exit_code = exit_code;
+                    tracing::debug!(
+                        "[ShellIntegration] 命令结束: block_id={}, duration_ms={:?}, exit_code={:?}",
+                        self.block_id,
+                        cmd.duration_ms,
+                        exit_code
+                    );
+                    cmd.clone()
+                }
+                _ => return,
+            }
+        };
+
+        self.record_history(&finished);
+        self.finish_block(&finished);
+    }
+
+    /// 记录开始执行的命令块
+    fn push_block(&self, block: CommandBlock) {
+        {
+            let mut blocks = self.blocks.write().unwrap();
+            if blocks.len() >= MAX_COMMAND_BLOCKS {
+                blocks.pop_front();
+            }
+            blocks.push_back(block.clone());
+        }
+        self.send_block_event(block);
+    }
+
+    /// 用结束的命令信息更新最后一个命令块
+    fn finish_block(&self, info: &CommandInfo) {
+        let block = {
+            let mut blocks = self.blocks.write().unwrap();
+            match blocks.back_mut() {
+                Some(block) if !block.is_finished() => {
+                    block.exit_code = info.exit_code;
+                    block.end_time = info.end_time;
+                    block.duration_ms = info.duration_ms;
+                    block.output_end = Some(self.osc_range.read().unwrap().start);
+                    block.clone()
+                }
+                _ => return,
+            }
+        };
+        self.send_block_event(block);
+    }
+
+    /// 发送命令块事件
+    fn send_block_event(&self, block: CommandBlock) {
+        if let Some(ref app_handle) = self.app_handle {
+            let event = TerminalCommandBlockEvent {
+                session_id: self.block_id.clone(),
+                block,
+            };
+
+            if let Err(e) = app_handle.emit(event_names::COMMAND_BLOCK, &event) {
+                tracing::warn!(
+                    "[ShellIntegration] 发送命令块事件失败: block_id={}, error={}",
+                    self.block_id,
+                    e
+                );
+            }
+        }
+    }
+
+    /// 将结束的命令写入命令历史
+    fn record_history(&self, info: &CommandInfo) {
+        let (Some((store, host)), Some(command)) = (&self.history, &info.command) else {
+            return;
+        };
+
+        let execution = CommandExecution {
+            cwd: self.get_current_dir(),
+            exit_code: info.exit_code,
+            duration_ms: info.duration_ms,
+            finished_at: info.end_time.unwrap_or_else(current_timestamp_ms),
+            ..CommandExecution::new(command.clone(), host.clone())
+        };
+        if let Err(e) = store.record(&execution) {
+            tracing::warn!(
+                "[ShellIntegration] 写入命令历史失败: block_id={}, error={}",
                 self.block_id,
-                cmd.duration_ms
+                e
             );
         }
     }
@@ -521,12 +1009,60 @@ impl ShellIntegration {
             let mut guard = self.current_command.write().unwrap();
             *guard = None;
         }
+        {
+            let mut guard = self.pending_command.write().unwrap();
+            *guard = None;
+        }
+        {
+            let mut guard = self.git_branch.write().unwrap();
+            *guard = None;
+        }
+        self.blocks.write().unwrap().clear();
+        self.next_block_index.store(0, Ordering::SeqCst);
+        {
+            let mut guard = self.partial_osc.write().unwrap();
+            guard.clear();
+        }
+        self.link_detector.write().unwrap().reset();
+        self.prompt_index.write().unwrap().reset();
         self.last_command_start.store(0, Ordering::SeqCst);
 
         tracing::debug!("[ShellIntegration] 状态重置: block_id={}", self.block_id);
     }
 }
 
+impl Drop for ShellIntegration {
+    fn drop(&mut self) {
+        // 会话关闭后丢弃等待确认的剪贴板请求，释放其持有的 PTY 回复通道
+        if let Some((ref bridge, _)) = self.clipboard {
+            bridge.cancel_session(&self.block_id);
+        }
+    }
+}
+
+/// 查找数据末尾未完成的 OSC 序列的起始位置，没有时返回数据长度
+///
+/// 末尾单独的 ESC 可能是 OSC 起始或 ST 的前半部分，同样视为未完成。
+fn partial_osc_start(data: &[u8]) -> usize {
+    let complete_len = match data.last() {
+        Some(0x1b) => data.len() - 1,
+        _ => data.len(),
+    };
+    let body = &data[..complete_len];
+    match body.windows(2).rposition(|w| w == b"\x1b]") {
+        Some(start) => {
+            let rest = &body[start + 2..];
+            let terminated = rest.contains(&0x07) || rest.windows(2).any(|w| w == b"\x1b\\");
+            if terminated {
+                complete_len
+            } else {
+                start
+            }
+        }
+        None => complete_len,
+    }
+}
+
 /// 获取当前时间戳（毫秒）
 fn current_timestamp_ms() -> i64 {
     SystemTime::now()
@@ -549,6 +1085,12 @@ mod tests {
             ShellType::from_path("C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe"),
             ShellType::Pwsh
         );
+        assert_eq!(ShellType::from_path("/usr/local/bin/nu"), ShellType::Nu);
+        assert_eq!(
+            ShellType::from_path("C:\\Program Files\\nu\\bin\\nu.exe"),
+            ShellType::Nu
+        );
+        assert_eq!(ShellType::from_path("/usr/bin/menu"), ShellType::Unknown);
         assert_eq!(ShellType::from_path("/bin/sh"), ShellType::Unknown);
     }
 
@@ -582,6 +1124,7 @@ mod tests {
         // 先设置为 RunningCommand
         let osc_exec = OSCSequence::PromptMark {
             mark_type: PromptMarkType::CommandExecuted,
+            exit_code: None,
         };
         integration.process_osc(&osc_exec).unwrap();
         assert_eq!(
@@ -592,6 +1135,7 @@ mod tests {
         // 然后 PromptStart 应该切换到 Ready
         let osc_prompt = OSCSequence::PromptMark {
             mark_type: PromptMarkType::PromptStart,
+            exit_code: None,
         };
         integration.process_osc(&osc_prompt).unwrap();
         assert_eq!(integration.get_status(), ShellIntegrationStatus::Ready);
@@ -603,6 +1147,7 @@ mod tests {
 
         let osc = OSCSequence::PromptMark {
             mark_type: PromptMarkType::CommandExecuted,
+            exit_code: None,
         };
 
         integration.process_osc(&osc).unwrap();
@@ -620,6 +1165,7 @@ mod tests {
         // 先执行命令
         let osc_exec = OSCSequence::PromptMark {
             mark_type: PromptMarkType::CommandExecuted,
+            exit_code: None,
         };
         integration.process_osc(&osc_exec).unwrap();
 
@@ -629,6 +1175,7 @@ mod tests {
         // 命令结束
         let osc_finish = OSCSequence::PromptMark {
             mark_type: PromptMarkType::CommandFinished,
+            exit_code: Some(2),
         };
         integration.process_osc(&osc_finish).unwrap();
 
@@ -638,6 +1185,137 @@ mod tests {
         assert!(cmd_info.end_time.is_some());
         assert!(cmd_info.duration_ms.is_some());
         assert!(cmd_info.duration_ms.unwrap() >= 10);
+        assert_eq!(cmd_info.exit_code, Some(2));
+
+        // 随后的 PromptStart 不会覆盖结束信息
+        integration
+            .process_osc(&OSCSequence::PromptMark {
+                mark_type: PromptMarkType::PromptStart,
+                exit_code: None,
+            })
+            .unwrap();
+        let after_prompt = integration.get_current_command().unwrap();
+        assert_eq!(after_prompt.end_time, cmd_info.end_time);
+        assert_eq!(after_prompt.exit_code, Some(2));
+    }
+
+    #[test]
+    fn test_command_history_recording() {
+        use crate::database::DbPool;
+        use crate::terminal::persistence::CommandHistoryQuery;
+
+        let db = Arc::new(DbPool::from_connection(
+            rusqlite::Connection::open_in_memory().unwrap(),
+        ));
+        let store = Arc::new(CommandHistoryStore::new(db));
+        store.init_tables().unwrap();
+        let integration = ShellIntegration::new("test-block".to_string())
+            .with_command_history(store.clone(), "ssh-dev".to_string());
+
+        let data = b"\x1b]7;file:///srv/app\x07\x1b]16162;setcmd make test\x1b\\\x1b]133;C\x07ok\r\n\x1b]133;D;1\x1b\\\x1b]133;A\x07";
+        integration.process_output(data);
+        // 没有命令文本的命令（如未写入历史的命令）不记录
+        integration.process_output(b"\x1b]133;C\x07\x1b]133;D;0\x07");
+
+        let entries = store.query(&CommandHistoryQuery::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].command, "make test");
+        assert_eq!(entries[0].host, "ssh-dev");
+        assert_eq!(entries[0].cwd.as_deref(), Some("/srv/app"));
+        assert_eq!(entries[0].exit_code, Some(1));
+        assert_eq!(entries[0].run_count, 1);
+    }
+
+    #[test]
+    fn test_command_blocks() {
+        let integration = ShellIntegration::new("test-block".to_string());
+
+        // bWFpbg== 为 "main" 的 Base64 编码
+        let data = b"\x1b]1337;CurrentDir=/srv/app\x07\x1b]1337;SetUserVar=gitBranch=bWFpbg==\x07\x1b]133;A\x07\x1b]16162;setcmd make test\x07\x1b]133;C\x07";
+        integration.process_output(data);
+
+        // 执行中的命令块
+        let blocks = integration.command_blocks(&CommandBlockQuery::default());
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].index, 0);
+        assert_eq!(blocks[0].command.as_deref(), Some("make test"));
+        assert_eq!(blocks[0].cwd.as_deref(), Some("/srv/app"));
+        assert_eq!(blocks[0].git_branch.as_deref(), Some("main"));
+        assert!(!blocks[0].is_finished());
+
+        integration.process_output(b"\x1b]133;D;2\x07\x1b]1337;SetUserVar=gitBranch=\x07\x1b]7;file:///tmp\x07\x1b]133;A\x07");
+        integration.process_output(
+            b"\x1b]16162;setcmd ls\x07\x1b]133;C\x07\x1b]133;D;0\x07\x1b]133;A\x07",
+        );
+
+        let blocks = integration.command_blocks(&CommandBlockQuery::default());
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].exit_code, Some(2));
+        assert!(blocks[0].is_failed());
+        assert!(blocks[0].duration_ms.is_some());
+        assert_eq!(blocks[1].index, 1);
+        assert_eq!(blocks[1].cwd.as_deref(), Some("/tmp"));
+        assert!(blocks[1].git_branch.is_none());
+        assert!(!blocks[1].is_failed());
+
+        let failed = integration.command_blocks(&CommandBlockQuery {
+            failed_only: true,
+            limit: None,
+        });
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].command.as_deref(), Some("make test"));
+
+        let latest = integration.command_blocks(&CommandBlockQuery {
+            failed_only: false,
+            limit: Some(1),
+        });
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].command.as_deref(), Some("ls"));
+    }
+
+    #[test]
+    fn test_command_block_output_range() {
+        let integration = ShellIntegration::new("test-block".to_string());
+        integration.set_output_offset(100);
+
+        // OSC 133;D 被读取块截断
+        let chunks: [&[u8]; 3] = [
+            b"$ ls\r\n\x1b]133;C\x07hel",
+            b"lo\r\n\x1b]13",
+            b"3;D;0\x07\x1b]133;A\x07$ ",
+        ];
+        let mut output = Vec::new();
+        for chunk in chunks {
+            output.extend_from_slice(chunk);
+            integration.process_output(chunk);
+        }
+
+        let block = integration.command_block(0).unwrap();
+        let start = (block.output_start - 100) as usize;
+        let end = (block.output_end.unwrap() - 100) as usize;
+        assert_eq!(&output[start..end], b"hello\r\n");
+        assert!(integration.command_block(1).is_none());
+    }
+
+    #[test]
+    fn test_prompt_marks_include_history() {
+        let integration = ShellIntegration::new("test-block".to_string());
+
+        // 恢复的会话：块文件中保留了位置 50 之后的历史输出
+        let history = b"\x1b]133;A\x07$ \x1b]133;B\x07pwd\x1b]133;C\x07/root\r\n";
+        integration.index_history(history, 50);
+        integration.set_output_offset(50 + history.len() as u64);
+        integration.process_output(b"\x1b]133;D;0\x07\x1b]133;A\x07$ ");
+
+        let marks = integration.prompt_marks();
+        assert_eq!(marks.marks.len(), 2);
+        assert_eq!(marks.marks[0].offset, 50);
+        assert_eq!(marks.marks[0].command.as_deref(), Some("pwd"));
+        assert_eq!(marks.marks[0].exit_code, Some(0));
+        assert_eq!(marks.marks[1].offset, 50 + history.len() as u64 + 10);
+        assert_eq!(marks.marks[1].line, 1);
+        assert_eq!(marks.line_count, 1);
+        assert_eq!(integration.prompt_mark(1), marks.marks.get(1).cloned());
     }
 
     #[test]
@@ -683,6 +1361,35 @@ mod tests {
         assert_eq!(integration.get_status(), ShellIntegrationStatus::Ready);
     }
 
+    #[test]
+    fn test_process_output_split_sequence() {
+        let integration = ShellIntegration::new("test-block".to_string());
+
+        assert_eq!(integration.process_output(b"ls\r\n\x1b]7;file:///ho"), 0);
+        assert!(integration.get_current_dir().is_none());
+        assert_eq!(integration.process_output(b"me/user\x1b"), 0);
+        assert_eq!(integration.process_output(b"\\$ "), 1);
+        assert_eq!(
+            integration.get_current_dir(),
+            Some("/home/user".to_string())
+        );
+    }
+
+    #[test]
+    fn test_detect_links() {
+        let integration = ShellIntegration::new("test-block".to_string());
+
+        let data = b"\x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x1b\\ src/lib.rs:3\r\n";
+        let links = integration.detect_links(data, &OSCParser::parse(data));
+
+        assert_eq!(links.len(), 2);
+        assert!(links[0].hyperlink);
+        assert_eq!(links[0].text, "docs");
+        assert_eq!(links[0].target, "https://example.com");
+        assert_eq!(links[1].target, "src/lib.rs");
+        assert_eq!(links[1].line, Some(3));
+    }
+
     #[test]
     fn test_reset() {
         let integration = ShellIntegration::new("test-block".to_string());
@@ -696,6 +1403,7 @@ mod tests {
 
         let osc_exec = OSCSequence::PromptMark {
             mark_type: PromptMarkType::CommandExecuted,
+            exit_code: None,
         };
         integration.process_osc(&osc_exec).unwrap();
 
@@ -705,6 +1413,9 @@ mod tests {
         assert!(integration.get_current_dir().is_none());
         assert_eq!(integration.get_status(), ShellIntegrationStatus::Unknown);
         assert!(integration.get_current_command().is_none());
+        assert!(integration
+            .command_blocks(&CommandBlockQuery::default())
+            .is_empty());
     }
 
     #[test]
@@ -6,12 +6,15 @@
 //! - 生成 Shell 集成脚本
 //! - 安装脚本到用户目录
 //! - 构建带集成的 Shell 启动命令
+//! - 按终端启动配置（LaunchProfile）构建 Shell 启动命令
+//! - 构建远程（SSH）Shell 加载集成脚本的启动命令（上传后 source，或内联加载）
 //!
 //! ## 支持的 Shell
 //! - Bash (--rcfile)
 //! - Zsh (ZDOTDIR)
 //! - Fish (-C source)
 //! - PowerShell (pwsh)
+//! - Nushell (-e source)
 //!
 //! ## Requirements
 //! - 17.5: 支持自定义 Shell 路径和参数
@@ -25,12 +28,21 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use portable_pty::CommandBuilder;
+use sha2::{Digest, Sha256};
+
 use crate::terminal::error::TerminalError;
 use crate::terminal::integration::shell_integration::ShellType;
+use crate::terminal::persistence::LaunchProfile;
+use crate::terminal::pty_session::default_shell;
 
 /// Shell 集成脚本目录名
 const SHELL_INTEGRATION_DIR: &str = "shell-integration";
 
+/// 远程主机上的集成脚本目录（相对用户主目录）
+pub const REMOTE_INTEGRATION_DIR: &str = ".proxycast/shell-integration";
+
 /// Bash 集成脚本内容
 const BASH_INTEGRATION_SCRIPT: &str = r#"# ProxyCast Shell Integration for Bash
 # This script provides shell integration features
@@ -59,32 +71,62 @@ __proxycast_command_executed() {
 }
 
 __proxycast_command_finished() {
-    printf '\033]133;D;%s\033\\' "$?"
+    printf '\033]133;D;%s\033\\' "$1"
+}
+
+# OSC 16162 - 报告执行的命令（用于命令历史），去掉会截断 OSC 序列的控制字符
+__proxycast_report_command() {
+    local cmd="${1//[$'\a\e']/}"
+    [ -n "$cmd" ] && printf '\033]16162;setcmd %s\033\\' "$cmd"
+}
+
+# OSC 1337 - 报告当前 Git 分支（用户变量 gitBranch，不在仓库中时为空）
+__proxycast_git_branch() {
+    local branch=
+    if command -v git >/dev/null 2>&1; then
+        branch=$(git symbolic-ref --short -q HEAD 2>/dev/null || git rev-parse --short HEAD 2>/dev/null)
+    fi
+    printf '\033]1337;SetUserVar=gitBranch=%s\033\\' "$(printf '%s' "$branch" | base64 | tr -d '\n')"
 }
 
 # 设置 PROMPT_COMMAND
 __proxycast_precmd() {
     local exit_code=$?
-    __proxycast_command_finished
+    __proxycast_preexec_armed=
+    __proxycast_command_finished "$exit_code"
     __proxycast_osc7
+    __proxycast_git_branch
     __proxycast_prompt_start
     return $exit_code
 }
 
+# PROMPT_COMMAND 执行完后才允许触发 preexec，避免把 PROMPT_COMMAND 本身当成命令
+__proxycast_arm_preexec() {
+    __proxycast_last_histcmd=$(HISTTIMEFORMAT= builtin history 1)
+    __proxycast_preexec_armed=1
+}
+
 __proxycast_preexec() {
+    # 未写入历史的命令（如 HISTCONTROL=ignorespace）不上报命令文本
+    local entry
+    entry=$(HISTTIMEFORMAT= builtin history 1)
+    if [ "$entry" != "$__proxycast_last_histcmd" ] && [[ $entry =~ ^\ *[0-9]+\*?\ +(.*)$ ]]; then
+        __proxycast_report_command "${BASH_REMATCH[1]}"
+    fi
     __proxycast_command_executed
 }
 
 # 安装 preexec 钩子（如果可用）
 if [ -n "$BASH_VERSION" ]; then
-    # 使用 DEBUG trap 模拟 preexec
+    # 使用 DEBUG trap 模拟 preexec，每个提示符只触发一次
     __proxycast_debug_trap() {
-        if [ -n "$COMP_LINE" ]; then
-            return
-        fi
-        if [ "$BASH_COMMAND" = "$PROMPT_COMMAND" ]; then
+        if [ -n "$COMP_LINE" ] || [ -z "$__proxycast_preexec_armed" ]; then
             return
         fi
+        case "$BASH_COMMAND" in
+            __proxycast_*) return ;;
+        esac
+        __proxycast_preexec_armed=
         __proxycast_preexec
     }
     
@@ -93,13 +135,23 @@ fi
 
 # 设置 PROMPT_COMMAND
 if [ -z "$PROMPT_COMMAND" ]; then
-    PROMPT_COMMAND="__proxycast_precmd"
+    PROMPT_COMMAND="__proxycast_precmd;__proxycast_arm_preexec"
 else
-    PROMPT_COMMAND="__proxycast_precmd;$PROMPT_COMMAND"
+    PROMPT_COMMAND="__proxycast_precmd;$PROMPT_COMMAND;__proxycast_arm_preexec"
 fi
 
-# 加载用户的 .bashrc（如果存在且我们是通过 --rcfile 启动的）
-if [ -n "$_PROXYCAST_LOAD_BASHRC" ] && [ -f "$HOME/.bashrc" ]; then
+# 加载用户配置：远程登录 Shell 按登录 Shell 的顺序加载 profile，否则加载 .bashrc
+if [ -n "$_PROXYCAST_LOGIN_SHELL" ]; then
+    unset _PROXYCAST_LOGIN_SHELL
+    [ -f /etc/profile ] && source /etc/profile
+    for __proxycast_profile in "$HOME/.bash_profile" "$HOME/.bash_login" "$HOME/.profile"; do
+        if [ -f "$__proxycast_profile" ]; then
+            source "$__proxycast_profile"
+            break
+        fi
+    done
+    unset __proxycast_profile
+elif [ -n "$_PROXYCAST_LOAD_BASHRC" ] && [ -f "$HOME/.bashrc" ]; then
     source "$HOME/.bashrc"
 fi
 
@@ -130,20 +182,38 @@ __proxycast_command_executed() {
 }
 
 __proxycast_command_finished() {
-    printf '\033]133;D;%s\033\\' "$?"
+    printf '\033]133;D;%s\033\\' "$1"
+}
+
+# OSC 16162 - 报告执行的命令（用于命令历史），去掉会截断 OSC 序列的控制字符
+__proxycast_report_command() {
+    local cmd="${1//[$'\a\e']/}"
+    [ -n "$cmd" ] && printf '\033]16162;setcmd %s\033\\' "$cmd"
+}
+
+# OSC 1337 - 报告当前 Git 分支（用户变量 gitBranch，不在仓库中时为空）
+__proxycast_git_branch() {
+    local branch=
+    if command -v git >/dev/null 2>&1; then
+        branch=$(git symbolic-ref --short -q HEAD 2>/dev/null || git rev-parse --short HEAD 2>/dev/null)
+    fi
+    printf '\033]1337;SetUserVar=gitBranch=%s\033\\' "$(printf '%s' "$branch" | base64 | tr -d '\n')"
 }
 
 # precmd 钩子 - 命令执行后
 __proxycast_precmd() {
     local exit_code=$?
-    __proxycast_command_finished
+    __proxycast_command_finished "$exit_code"
     __proxycast_osc7
+    __proxycast_git_branch
     __proxycast_prompt_start
     return $exit_code
 }
 
 # preexec 钩子 - 命令执行前
+# $1 为输入的命令行，未写入历史的命令（如 HIST_IGNORE_SPACE）为空
 __proxycast_preexec() {
+    __proxycast_report_command "$1"
     __proxycast_command_executed
 }
 
@@ -167,9 +237,9 @@ export PROXYCAST_SHELL_INTEGRATION=1
 
 /// Zsh .zshenv 内容（用于设置 ZDOTDIR）
 const ZSH_ZSHENV_SCRIPT: &str = r#"# ProxyCast Zsh Environment
-# 保存原始 ZDOTDIR
-if [ -z "$_PROXYCAST_ORIG_ZDOTDIR" ]; then
-    export _PROXYCAST_ORIG_ZDOTDIR="${ZDOTDIR:-$HOME}"
+# 保存原始 ZDOTDIR（此时 ZDOTDIR 已指向集成目录，未传入原始值时使用主目录）
+if [ -z "$_PROXYCAST_ORIG_ZDOTDIR" ] || [ "$_PROXYCAST_ORIG_ZDOTDIR" = "$ZDOTDIR" ]; then
+    export _PROXYCAST_ORIG_ZDOTDIR="$HOME"
 fi
 
 # 加载原始 .zshenv
@@ -178,6 +248,14 @@ if [ -f "$_PROXYCAST_ORIG_ZDOTDIR/.zshenv" ]; then
 fi
 "#;
 
+/// Zsh .zprofile 内容（登录 Shell 加载原始 .zprofile）
+const ZSH_ZPROFILE_SCRIPT: &str = r#"# ProxyCast Zsh Login Profile
+# 加载原始 .zprofile
+if [ -f "$_PROXYCAST_ORIG_ZDOTDIR/.zprofile" ]; then
+    source "$_PROXYCAST_ORIG_ZDOTDIR/.zprofile"
+fi
+"#;
+
 /// Fish 集成脚本内容
 const FISH_INTEGRATION_SCRIPT: &str = r#"# ProxyCast Shell Integration for Fish
 # This script provides shell integration features
@@ -197,17 +275,37 @@ function __proxycast_command_executed
 end
 
 function __proxycast_command_finished
-    printf '\033]133;D;%s\033\\' $status
+    printf '\033]133;D;%s\033\\' $argv[1]
+end
+
+# OSC 16162 - 报告执行的命令（用于命令历史），去掉会截断 OSC 序列的控制字符
+function __proxycast_report_command
+    set -l cmd (string replace -ra '[\a\e]' '' -- "$argv" | string collect)
+    if test -n "$cmd"
+        printf '\033]16162;setcmd %s\033\\' "$cmd"
+    end
+end
+
+# OSC 1337 - 报告当前 Git 分支（用户变量 gitBranch，不在仓库中时为空）
+function __proxycast_git_branch
+    set -l branch
+    if command -q git
+        set branch (command git symbolic-ref --short -q HEAD 2>/dev/null; or command git rev-parse --short HEAD 2>/dev/null)
+    end
+    printf '\033]1337;SetUserVar=gitBranch=%s\033\\' (printf '%s' "$branch" | base64 | string join '')
 end
 
 # Fish 事件钩子
 function __proxycast_fish_prompt --on-event fish_prompt
-    __proxycast_command_finished
+    set -l exit_code $status
+    __proxycast_command_finished $exit_code
     __proxycast_osc7
+    __proxycast_git_branch
     __proxycast_prompt_start
 end
 
 function __proxycast_fish_preexec --on-event fish_preexec
+    __proxycast_report_command $argv
     __proxycast_command_executed
 end
 
@@ -238,6 +336,28 @@ function Send-ProxyCastCommandExecuted {
     Write-Host -NoNewline "`e]133;C`e\"
 }
 
+function Send-ProxyCastCommand {
+    param([string]$Command)
+    # OSC 16162 - 报告执行的命令（用于命令历史），去掉会截断 OSC 序列的控制字符
+    $cmd = $Command -replace "[\x07\x1b]", ""
+    if ($cmd) {
+        Write-Host -NoNewline "`e]16162;setcmd $cmd`e\"
+    }
+}
+
+# OSC 1337 - 报告当前 Git 分支（用户变量 gitBranch，不在仓库中时为空）
+function Send-ProxyCastGitBranch {
+    $branch = ""
+    if (Get-Command git -ErrorAction SilentlyContinue) {
+        $branch = git symbolic-ref --short -q HEAD 2>$null
+        if (-not $branch) {
+            $branch = git rev-parse --short HEAD 2>$null
+        }
+    }
+    $encoded = [Convert]::ToBase64String([System.Text.Encoding]::UTF8.GetBytes("$branch"))
+    Write-Host -NoNewline "`e]1337;SetUserVar=gitBranch=$encoded`e\"
+}
+
 function Send-ProxyCastCommandFinished {
     param([int]$ExitCode = 0)
     Write-Host -NoNewline "`e]133;D;$ExitCode`e\"
@@ -257,6 +377,7 @@ function prompt {
     $exitCode = $LASTEXITCODE
     Send-ProxyCastCommandFinished -ExitCode $exitCode
     Send-ProxyCastOsc7
+    Send-ProxyCastGitBranch
     Send-ProxyCastPromptStart
     $LASTEXITCODE = $exitCode
     __ProxyCastOriginalPrompt
@@ -267,6 +388,7 @@ if (Get-Module -ListAvailable -Name PSReadLine) {
     $existingHandler = (Get-PSReadLineOption).AddToHistoryHandler
     Set-PSReadLineOption -AddToHistoryHandler {
         param([string]$line)
+        Send-ProxyCastCommand -Command $line
         Send-ProxyCastCommandExecuted
         if ($existingHandler) {
             return & $existingHandler $line
@@ -279,6 +401,68 @@ if (Get-Module -ListAvailable -Name PSReadLine) {
 $env:PROXYCAST_SHELL_INTEGRATION = "1"
 "#;
 
+/// Nushell 集成脚本内容
+const NU_INTEGRATION_SCRIPT: &str = r#"# ProxyCast Shell Integration for Nushell
+# This script provides shell integration features
+
+# 关闭 Nushell 内置的 OSC 7 / OSC 133 标记，避免重复上报
+$env.config.shell_integration = if (($env.config.shell_integration | describe) starts-with "record") {
+    $env.config.shell_integration | merge { osc7: false, osc133: false, osc633: false }
+} else {
+    false
+}
+
+# 发送 OSC 序列
+def __proxycast_osc [payload: string] {
+    print -n $"\e]($payload)\e\\"
+}
+
+# OSC 7 - 报告当前工作目录
+def __proxycast_osc7 [] {
+    let host = (try { sys host | get hostname } catch { "localhost" })
+    __proxycast_osc $"7;file://($host)($env.PWD)"
+}
+
+# OSC 16162 - 报告执行的命令（用于命令历史），去掉会截断 OSC 序列的控制字符
+def __proxycast_report_command [command: string] {
+    let cmd = ($command | str replace -a -r '[\x07\x1b]' '')
+    if not ($cmd | is-empty) {
+        __proxycast_osc $"16162;setcmd ($cmd)"
+    }
+}
+
+# OSC 1337 - 报告当前 Git 分支（用户变量 gitBranch，不在仓库中时为空）
+def __proxycast_git_branch [] {
+    mut branch = ""
+    if not (which git | is-empty) {
+        let head = (do { ^git symbolic-ref --short -q HEAD } | complete)
+        $branch = if $head.exit_code == 0 {
+            $head.stdout | str trim
+        } else {
+            do { ^git rev-parse --short HEAD } | complete | get stdout | str trim
+        }
+    }
+    __proxycast_osc $"1337;SetUserVar=gitBranch=($branch | encode base64)"
+}
+
+# pre_prompt 钩子 - 命令执行后
+$env.config.hooks.pre_prompt = ($env.config.hooks.pre_prompt? | default [] | append {||
+    __proxycast_osc $"133;D;($env.LAST_EXIT_CODE)"
+    __proxycast_osc7
+    __proxycast_git_branch
+    __proxycast_osc "133;A"
+})
+
+# pre_execution 钩子 - 命令执行前
+$env.config.hooks.pre_execution = ($env.config.hooks.pre_execution? | default [] | append {||
+    __proxycast_report_command (commandline)
+    __proxycast_osc "133;C"
+})
+
+# 标记集成已加载
+$env.PROXYCAST_SHELL_INTEGRATION = "1"
+"#;
+
 /// Shell 集成脚本管理器
 pub struct ShellScripts {
     /// 集成脚本目录
@@ -334,6 +518,9 @@ impl ShellScripts {
         // 安装 PowerShell 脚本
         self.install_pwsh_scripts()?;
 
+        // 安装 Nushell 脚本
+        self.install_nu_scripts()?;
+
         tracing::info!(
             "[ShellScripts] 所有集成脚本已安装: dir={}",
             self.integration_dir.display()
@@ -372,6 +559,11 @@ impl ShellScripts {
         fs::write(&zshenv_path, ZSH_ZSHENV_SCRIPT)
             .map_err(|e| TerminalError::Internal(format!("写入 .zshenv 失败: {}", e)))?;
 
+        // 写入 .zprofile
+        let zprofile_path = zsh_dir.join(".zprofile");
+        fs::write(&zprofile_path, ZSH_ZPROFILE_SCRIPT)
+            .map_err(|e| TerminalError::Internal(format!("写入 .zprofile 失败: {}", e)))?;
+
         // 写入 .zshrc
         let zshrc_path = zsh_dir.join(".zshrc");
         fs::write(&zshrc_path, ZSH_INTEGRATION_SCRIPT)
@@ -417,6 +609,24 @@ impl ShellScripts {
         Ok(())
     }
 
+    /// 安装 Nushell 集成脚本
+    fn install_nu_scripts(&self) -> Result<(), TerminalError> {
+        let nu_dir = self.integration_dir.join("nu");
+        fs::create_dir_all(&nu_dir)
+            .map_err(|e| TerminalError::Internal(format!("创建 nu 目录失败: {}", e)))?;
+
+        let script_path = nu_dir.join("proxycast.nu");
+        fs::write(&script_path, NU_INTEGRATION_SCRIPT)
+            .map_err(|e| TerminalError::Internal(format!("写入 nu 脚本失败: {}", e)))?;
+
+        tracing::debug!(
+            "[ShellScripts] Nushell 脚本已安装: {}",
+            script_path.display()
+        );
+
+        Ok(())
+    }
+
     /// 获取 Bash 集成脚本路径
     pub fn bash_script_path(&self) -> PathBuf {
         self.integration_dir.join("bash").join("proxycast.bash")
@@ -437,13 +647,188 @@ impl ShellScripts {
         self.integration_dir.join("pwsh").join("proxycast.ps1")
     }
 
+    /// 获取 Nushell 集成脚本路径
+    pub fn nu_script_path(&self) -> PathBuf {
+        self.integration_dir.join("nu").join("proxycast.nu")
+    }
+
     /// 检查集成脚本是否已安装
     pub fn is_installed(&self) -> bool {
         self.bash_script_path().exists()
             && self.zsh_integration_dir().join(".zshrc").exists()
+            && self.zsh_integration_dir().join(".zprofile").exists()
             && self.fish_script_path().exists()
             && self.pwsh_script_path().exists()
+            && self.nu_script_path().exists()
+    }
+
+    /// 获取 Shell 的集成脚本文件
+    ///
+    /// 路径相对集成脚本目录，与本机安装的目录结构一致，用于上传到远程主机。
+    ///
+    /// # 返回
+    /// 脚本路径和内容，不支持的 Shell 为空
+    pub fn script_files(shell_type: ShellType) -> &'static [(&'static str, &'static str)] {
+        match shell_type {
+            ShellType::Bash => &[("bash/proxycast.bash", BASH_INTEGRATION_SCRIPT)],
+            ShellType::Zsh => &[
+                ("zsh/.zshenv", ZSH_ZSHENV_SCRIPT),
+                ("zsh/.zprofile", ZSH_ZPROFILE_SCRIPT),
+                ("zsh/.zshrc", ZSH_INTEGRATION_SCRIPT),
+            ],
+            ShellType::Fish => &[("fish/proxycast.fish", FISH_INTEGRATION_SCRIPT)],
+            ShellType::Pwsh => &[("pwsh/proxycast.ps1", PWSH_INTEGRATION_SCRIPT)],
+            ShellType::Nu => &[("nu/proxycast.nu", NU_INTEGRATION_SCRIPT)],
+            ShellType::Unknown => &[],
+        }
+    }
+
+    /// 构建远程 Shell 加载集成脚本的启动命令
+    ///
+    /// 命令由远程用户的登录 Shell（即 `shell_path` 本身）解释执行，通过 `env` 设置环境变量，
+    /// 除 PowerShell 外以登录 Shell 方式启动。
+    ///
+    /// # 参数
+    /// - `shell_type`: 远程 Shell 类型
+    /// - `shell_path`: 远程 Shell 路径（`$SHELL`）
+    /// - `integration_dir`: 远程集成脚本目录（绝对路径）
+    /// - `env`: 额外的环境变量
+    ///
+    /// # 返回
+    /// 不支持的 Shell，或路径、环境变量中包含单引号时返回 `None`
+    pub fn remote_launch_command(
+        shell_type: ShellType,
+        shell_path: &str,
+        integration_dir: &str,
+        env: &[(&str, &str)],
+    ) -> Option<String> {
+        let dir = integration_dir.trim_end_matches('/');
+
+        let (vars, args) = match shell_type {
+            ShellType::Bash => (
+                vec![("_PROXYCAST_LOGIN_SHELL", "1".to_string())],
+                format!(
+                    "--rcfile {} -i",
+                    quote_remote(&format!("{}/bash/proxycast.bash", dir))?
+                ),
+            ),
+            ShellType::Zsh => (vec![("ZDOTDIR", format!("{}/zsh", dir))], "-l".to_string()),
+            ShellType::Fish => (
+                Vec::new(),
+                format!(
+                    "-l -C \"source {}\"",
+                    quote_remote(&format!("{}/fish/proxycast.fish", dir))?
+                ),
+            ),
+            ShellType::Nu => (
+                Vec::new(),
+                format!(
+                    "-l -e \"source {}\"",
+                    quote_remote(&format!("{}/nu/proxycast.nu", dir))?
+                ),
+            ),
+            ShellType::Pwsh => (
+                Vec::new(),
+                format!(
+                    "-NoExit -Command \". {}\"",
+                    quote_remote(&format!("{}/pwsh/proxycast.ps1", dir))?
+                ),
+            ),
+            ShellType::Unknown => return None,
+        };
+
+        format_remote_command(shell_type, shell_path, vars, env, &args)
     }
+
+    /// 构建远程 Shell 内联加载集成脚本的启动命令
+    ///
+    /// 无法上传脚本时使用：脚本以 Base64 编码嵌入命令，由远程 Shell 解码后执行。
+    /// Zsh 依赖 ZDOTDIR 目录、Nushell 只能 source 文件，不支持内联加载。
+    ///
+    /// # 返回
+    /// 不支持内联加载的 Shell，或路径、环境变量中包含单引号时返回 `None`
+    pub fn remote_inline_command(
+        shell_type: ShellType,
+        shell_path: &str,
+        env: &[(&str, &str)],
+    ) -> Option<String> {
+        let (_, content) = Self::script_files(shell_type).first()?;
+        let encoded = BASE64.encode(content);
+
+        let (vars, args) = match shell_type {
+            // 启动命令由 Bash 自身解释，通过进程替换提供 rcfile
+            ShellType::Bash => (
+                vec![("_PROXYCAST_LOGIN_SHELL", "1".to_string())],
+                format!("--rcfile <(printf '%s' '{}' | base64 -d) -i", encoded),
+            ),
+            // 未指定文件时 source 从标准输入读取
+            ShellType::Fish => (
+                Vec::new(),
+                format!("-l -C \"printf '%s' '{}' | base64 -d | source\"", encoded),
+            ),
+            ShellType::Pwsh => {
+                let bytes = format!("[Convert]::FromBase64String('{}')", encoded);
+                (
+                    Vec::new(),
+                    format!(
+                        "-NoExit -Command \"Invoke-Expression ([Text.Encoding]::UTF8.GetString({}))\"",
+                        bytes
+                    ),
+                )
+            }
+            _ => return None,
+        };
+
+        format_remote_command(shell_type, shell_path, vars, env, &args)
+    }
+
+    /// 获取 Shell 集成脚本的版本标识
+    ///
+    /// 由脚本路径和内容计算，脚本变化后版本随之变化，用于判断远程脚本是否需要重新上传。
+    pub fn scripts_version(shell_type: ShellType) -> String {
+        let mut hasher = Sha256::new();
+        for (path, content) in Self::script_files(shell_type) {
+            hasher.update(path.as_bytes());
+            hasher.update([0]);
+            hasher.update(content.as_bytes());
+            hasher.update([0]);
+        }
+        hex::encode(&hasher.finalize()[..8])
+    }
+}
+
+/// 用单引号引用远程命令中的值，值中包含单引号时返回 `None`
+fn quote_remote(value: &str) -> Option<String> {
+    (!value.contains('\'')).then(|| format!("'{}'", value))
+}
+
+/// 组装远程 Shell 启动命令：`exec env <变量>... '<Shell>' <参数>`
+fn format_remote_command<'a>(
+    shell_type: ShellType,
+    shell_path: &str,
+    mut vars: Vec<(&'a str, String)>,
+    env: &[(&'a str, &str)],
+    args: &str,
+) -> Option<String> {
+    vars.extend(env.iter().map(|(key, value)| (*key, value.to_string())));
+
+    let mut assignments = String::new();
+    for (key, value) in &vars {
+        assignments.push_str(&format!("{}={} ", key, quote_remote(value)?));
+    }
+    // PowerShell 没有 exec，通过调用运算符启动
+    let launcher = if shell_type == ShellType::Pwsh {
+        "&"
+    } else {
+        "exec"
+    };
+    Some(format!(
+        "{} env {}{} {}",
+        launcher,
+        assignments,
+        quote_remote(shell_path)?,
+        args
+    ))
 }
 
 /// Shell 启动配置
@@ -480,6 +865,16 @@ impl ShellLaunchConfig {
         self.env.insert(key.into(), value.into());
         self
     }
+
+    /// 转换为 PTY 命令
+    pub fn to_command(&self) -> CommandBuilder {
+        let mut cmd = CommandBuilder::new(&self.shell_path);
+        cmd.args(&self.args);
+        for (key, value) in &self.env {
+            cmd.env(key, value);
+        }
+        cmd
+    }
 }
 
 /// Shell 启动配置构建器
@@ -545,6 +940,7 @@ impl ShellLaunchBuilder {
             ShellType::Zsh => self.configure_zsh(config)?,
             ShellType::Fish => self.configure_fish(config)?,
             ShellType::Pwsh => self.configure_pwsh(config)?,
+            ShellType::Nu => self.configure_nu(config)?,
             ShellType::Unknown => {
                 tracing::warn!(
                     "[ShellLaunchBuilder] 未知 Shell 类型，不加载集成脚本: {}",
@@ -564,6 +960,25 @@ impl ShellLaunchBuilder {
         Ok(config)
     }
 
+    /// 按终端启动配置构建 Shell 启动配置
+    ///
+    /// Shell 为空时使用默认 Shell，配置中的参数追加在集成脚本参数之后，
+    /// 环境变量覆盖通用环境变量。
+    pub fn build_profile(
+        &self,
+        profile: &LaunchProfile,
+    ) -> Result<ShellLaunchConfig, TerminalError> {
+        let shell = profile
+            .shell
+            .clone()
+            .filter(|shell| !shell.trim().is_empty())
+            .unwrap_or_else(default_shell);
+
+        let mut config = self.build(&shell, Some(&profile.env))?;
+        config.args.extend(profile.args.iter().cloned());
+        Ok(config)
+    }
+
     /// 设置通用环境变量
     ///
     /// 设置所有 Shell 类型共用的环境变量，包括：
@@ -642,6 +1057,15 @@ impl ShellLaunchBuilder {
 
         let zsh_dir_str = zsh_dir.to_string_lossy().to_string();
 
+        // 集成目录的 .zshenv / .zshrc 从原始 ZDOTDIR 加载用户配置
+        let orig_zdotdir = std::env::var("ZDOTDIR")
+            .ok()
+            .or_else(|| dirs::home_dir().map(|home| home.to_string_lossy().to_string()));
+        let config = match orig_zdotdir {
+            Some(dir) => config.env("_PROXYCAST_ORIG_ZDOTDIR", dir),
+            None => config,
+        };
+
         Ok(config.env("ZDOTDIR", &zsh_dir_str))
     }
 
@@ -692,6 +1116,25 @@ impl ShellLaunchBuilder {
             .arg("-Command")
             .arg(format!(". '{}'", script_path_str)))
     }
+
+    /// 配置 Nushell 启动
+    ///
+    /// 使用 -e 参数在加载用户配置后 source 集成脚本。
+    fn configure_nu(&self, config: ShellLaunchConfig) -> Result<ShellLaunchConfig, TerminalError> {
+        let script_path = self.scripts.nu_script_path();
+
+        if !script_path.exists() {
+            tracing::warn!(
+                "[ShellLaunchBuilder] Nushell 集成脚本不存在: {}",
+                script_path.display()
+            );
+            return Ok(config);
+        }
+
+        let source_cmd = format!("source '{}'", script_path.to_string_lossy());
+
+        Ok(config.arg("-e").arg(&source_cmd))
+    }
 }
 
 /// 终端环境变量配置
@@ -856,9 +1299,24 @@ mod tests {
         assert!(scripts.zsh_integration_dir().join(".zshenv").exists());
         assert!(scripts.fish_script_path().exists());
         assert!(scripts.pwsh_script_path().exists());
+        assert!(scripts.nu_script_path().exists());
+        assert!(scripts.zsh_integration_dir().join(".zprofile").exists());
         assert!(scripts.is_installed());
     }
 
+    #[test]
+    fn test_scripts_report_git_branch() {
+        for script in [
+            BASH_INTEGRATION_SCRIPT,
+            ZSH_INTEGRATION_SCRIPT,
+            FISH_INTEGRATION_SCRIPT,
+            PWSH_INTEGRATION_SCRIPT,
+        ] {
+            assert!(script.contains("]1337;SetUserVar=gitBranch="));
+        }
+        assert!(NU_INTEGRATION_SCRIPT.contains("1337;SetUserVar=gitBranch="));
+    }
+
     #[test]
     fn test_shell_launch_builder_bash() {
         let temp_dir = TempDir::new().unwrap();
@@ -877,6 +1335,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_shell_launch_builder_profile() {
+        let temp_dir = TempDir::new().unwrap();
+        let builder = ShellLaunchBuilder::new(temp_dir.path(), "test-block".to_string());
+
+        let profile = LaunchProfile {
+            name: "dev".to_string(),
+            shell: Some("/bin/bash".to_string()),
+            args: vec!["-l".to_string()],
+            env: HashMap::from([
+                ("RUST_LOG".to_string(), "debug".to_string()),
+                ("TERM".to_string(), "xterm".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let config = builder.build_profile(&profile).unwrap();
+
+        assert_eq!(config.shell_path, "/bin/bash");
+        assert_eq!(config.args.first().map(String::as_str), Some("--rcfile"));
+        assert_eq!(config.args.last().map(String::as_str), Some("-l"));
+        assert_eq!(config.env.get("RUST_LOG"), Some(&"debug".to_string()));
+        assert_eq!(config.env.get("TERM"), Some(&"xterm".to_string()));
+        assert!(config.env.contains_key("PROXYCAST_BLOCKID"));
+    }
+
     #[test]
     fn test_shell_launch_builder_zsh() {
         let temp_dir = TempDir::new().unwrap();
@@ -917,6 +1400,98 @@ mod tests {
         assert!(config.env.contains_key("WAVETERM_BLOCKID"));
     }
 
+    #[test]
+    fn test_shell_launch_builder_nu() {
+        let temp_dir = TempDir::new().unwrap();
+        let builder = ShellLaunchBuilder::new(temp_dir.path(), "test-block".to_string());
+
+        let config = builder.build("/usr/local/bin/nu", None).unwrap();
+
+        assert_eq!(config.args.first().map(String::as_str), Some("-e"));
+        assert!(config.args[1].starts_with("source '"));
+        assert!(config.args[1].ends_with("proxycast.nu'"));
+        assert!(config.env.contains_key("PROXYCAST_BLOCKID"));
+    }
+
+    #[test]
+    fn test_remote_launch_command() {
+        let dir = "/home/dev/.proxycast/shell-integration";
+        assert_eq!(
+            ShellScripts::remote_launch_command(ShellType::Bash, "/bin/bash", dir, &[]).unwrap(),
+            "exec env _PROXYCAST_LOGIN_SHELL='1' '/bin/bash' --rcfile \
+             '/home/dev/.proxycast/shell-integration/bash/proxycast.bash' -i"
+        );
+        assert_eq!(
+            ShellScripts::remote_launch_command(
+                ShellType::Zsh,
+                "/usr/bin/zsh",
+                dir,
+                &[("DISPLAY", "localhost:10.0")]
+            )
+            .unwrap(),
+            "exec env ZDOTDIR='/home/dev/.proxycast/shell-integration/zsh' \
+             DISPLAY='localhost:10.0' '/usr/bin/zsh' -l"
+        );
+        assert_eq!(
+            ShellScripts::remote_launch_command(ShellType::Nu, "/usr/bin/nu", dir, &[]).unwrap(),
+            "exec env '/usr/bin/nu' -l -e \"source \
+             '/home/dev/.proxycast/shell-integration/nu/proxycast.nu'\""
+        );
+        assert!(
+            ShellScripts::remote_launch_command(ShellType::Pwsh, "/usr/bin/pwsh", dir, &[])
+                .unwrap()
+                .starts_with("& env '/usr/bin/pwsh' -NoExit -Command")
+        );
+        assert!(
+            ShellScripts::remote_launch_command(ShellType::Unknown, "/bin/sh", dir, &[]).is_none()
+        );
+        assert!(ShellScripts::remote_launch_command(
+            ShellType::Fish,
+            "/bin/fish",
+            "/home/o'neil",
+            &[]
+        )
+        .is_none());
+
+        // 内联加载：脚本以 Base64 嵌入命令
+        let inline =
+            ShellScripts::remote_inline_command(ShellType::Bash, "/bin/bash", &[]).unwrap();
+        assert!(inline.starts_with("exec env _PROXYCAST_LOGIN_SHELL='1' '/bin/bash' --rcfile <("));
+        assert!(inline.contains(&BASE64.encode(BASH_INTEGRATION_SCRIPT)));
+        assert!(
+            ShellScripts::remote_inline_command(ShellType::Fish, "/bin/fish", &[])
+                .unwrap()
+                .ends_with("| base64 -d | source\"")
+        );
+        assert!(ShellScripts::remote_inline_command(ShellType::Pwsh, "/bin/pwsh", &[]).is_some());
+        assert!(ShellScripts::remote_inline_command(ShellType::Zsh, "/bin/zsh", &[]).is_none());
+        assert!(ShellScripts::remote_inline_command(ShellType::Nu, "/bin/nu", &[]).is_none());
+
+        // 版本标识稳定且按 Shell 区分
+        assert_eq!(
+            ShellScripts::scripts_version(ShellType::Zsh),
+            ShellScripts::scripts_version(ShellType::Zsh)
+        );
+        assert_eq!(ShellScripts::scripts_version(ShellType::Zsh).len(), 16);
+        assert_ne!(
+            ShellScripts::scripts_version(ShellType::Zsh),
+            ShellScripts::scripts_version(ShellType::Bash)
+        );
+
+        // 上传的脚本与本机安装的目录结构一致
+        for shell_type in [
+            ShellType::Bash,
+            ShellType::Zsh,
+            ShellType::Fish,
+            ShellType::Nu,
+        ] {
+            for (path, content) in ShellScripts::script_files(shell_type) {
+                assert!(path.contains('/'));
+                assert!(content.contains("PROXYCAST"));
+            }
+        }
+    }
+
     #[test]
     fn test_shell_launch_builder_custom_env() {
         let temp_dir = TempDir::new().unwrap();
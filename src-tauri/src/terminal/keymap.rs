@@ -0,0 +1,761 @@
+//! 按键编码
+//!
+//! 在后端把前端的按键组合（如 `ctrl+shift+ArrowUp`）转换为终端程序期望的转义序列，
+//! 前端只需上报按键，不必关心连接类型和终端程序启用的键盘模式。
+//!
+//! ## 功能
+//! - 按 `TERM` 选择编码配置（xterm 兼容、Linux 控制台、VT100）
+//! - 跟踪终端程序启用的键盘模式：应用光标键模式（DECCKM）、xterm modifyOtherKeys、
+//!   kitty 键盘协议（渐进增强标志的栈，主屏幕和备用屏幕各一个）
+//! - 回复 kitty 键盘协议的标志查询（`CSI ? u`）
+//! - 用户自定义按键绑定见 [`crate::terminal::persistence::keymap_settings`]
+//!
+//! ## 设计说明
+//! 按键名称使用浏览器 `KeyboardEvent.key` 的取值（`a`、`Enter`、`ArrowUp`、`F5` 等），
+//! 字符键的 Shift 已由浏览器应用（`shift+a` 上报为 `A`）。
+//! 键盘模式由读取线程从输出中跟踪，前端终端不应再回复 `CSI ? u` 查询。
+
+use serde::{Deserialize, Serialize};
+
+/// CSI 参数的最大长度（超过时忽略该序列）
+const MAX_CSI_PARAMS_LEN: usize = 64;
+
+/// kitty 键盘协议标志栈的最大深度
+const MAX_KITTY_STACK: usize = 16;
+
+/// kitty 标志：消除转义码歧义
+const KITTY_DISAMBIGUATE: u8 = 0b1;
+
+/// kitty 标志：所有按键都以转义码上报
+const KITTY_REPORT_ALL_KEYS: u8 = 0b1000;
+
+/// kitty 标志的有效位
+const KITTY_FLAGS_MASK: u8 = 0b1_1111;
+
+/// 按键组合
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyChord {
+    /// 按键名称（`KeyboardEvent.key`）
+    pub key: String,
+    /// 是否按下 Ctrl
+    #[serde(default)]
+    pub ctrl: bool,
+    /// 是否按下 Alt / Option
+    #[serde(default)]
+    pub alt: bool,
+    /// 是否按下 Shift
+    #[serde(default)]
+    pub shift: bool,
+    /// 是否按下 Meta / Cmd / Super
+    #[serde(default)]
+    pub meta: bool,
+}
+
+impl KeyChord {
+    /// 解析 `ctrl+shift+ArrowUp` 形式的按键组合（修饰键不区分大小写，`+` 键写作 `ctrl++`）
+    pub fn parse(text: &str) -> Option<Self> {
+        // 只有空格时按空格键处理
+        let text = match text.trim() {
+            "" => text,
+            trimmed => trimmed,
+        };
+        let (mods, key) = match text.strip_suffix("++") {
+            Some(mods) => (mods, "+"),
+            None if text == "+" => ("", "+"),
+            None => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+        let mut chord = Self {
+            key: key.to_string(),
+            ..Self::default()
+        };
+        for name in mods.split('+').filter(|name| !name.is_empty()) {
+            match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "alt" | "option" => chord.alt = true,
+                "shift" => chord.shift = true,
+                "meta" | "cmd" | "super" => chord.meta = true,
+                _ => return None,
+            }
+        }
+        Key::parse(&chord.key).map(|_| chord)
+    }
+
+    /// 是否与另一个按键组合相同（修饰键完全一致，字母不区分大小写）
+    pub fn matches(&self, other: &KeyChord) -> bool {
+        self.ctrl == other.ctrl
+            && self.alt == other.alt
+            && self.shift == other.shift
+            && self.meta == other.meta
+            && Key::parse(&self.key).map(Key::normalized)
+                == Key::parse(&other.key).map(Key::normalized)
+    }
+
+    /// xterm 修饰键参数（1 + Shift + 2·Alt + 4·Ctrl + 8·Meta）
+    fn modifier_param(&self) -> u8 {
+        1 + self.shift as u8 + 2 * self.alt as u8 + 4 * self.ctrl as u8 + 8 * self.meta as u8
+    }
+}
+
+/// 按键
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+impl Key {
+    /// 按 `KeyboardEvent.key` 解析按键
+    fn parse(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return (!c.is_control()).then_some(Self::Char(c));
+        }
+        let key = match name {
+            "Space" | "Spacebar" => Self::Char(' '),
+            "Enter" | "Return" => Self::Enter,
+            "Tab" => Self::Tab,
+            "Backspace" => Self::Backspace,
+            "Escape" | "Esc" => Self::Escape,
+            "ArrowUp" | "Up" => Self::Up,
+            "ArrowDown" | "Down" => Self::Down,
+            "ArrowLeft" | "Left" => Self::Left,
+            "ArrowRight" | "Right" => Self::Right,
+            "Home" => Self::Home,
+            "End" => Self::End,
+            "Insert" => Self::Insert,
+            "Delete" | "Del" => Self::Delete,
+            "PageUp" => Self::PageUp,
+            "PageDown" => Self::PageDown,
+            _ => {
+                let n: u8 = name.strip_prefix('F')?.parse().ok()?;
+                return (1..=12).contains(&n).then_some(Self::F(n));
+            }
+        };
+        Some(key)
+    }
+
+    /// 字母统一为小写（用于比较按键组合）
+    fn normalized(self) -> Self {
+        match self {
+            Self::Char(c) => Self::Char(c.to_ascii_lowercase()),
+            key => key,
+        }
+    }
+}
+
+/// 按键编码配置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeymapProfile {
+    /// xterm 兼容终端（xterm、screen、tmux 等），支持修饰键编码和键盘协议
+    #[default]
+    Xterm,
+    /// Linux 控制台（VT220 风格的编辑键和 F1-F5，不编码修饰键）
+    Linux,
+    /// VT100（只有 PF1-PF4，不编码修饰键）
+    Vt100,
+}
+
+impl KeymapProfile {
+    /// 按 `TERM` 选择编码配置
+    pub fn from_term(term: &str) -> Self {
+        match term.trim() {
+            "linux" => Self::Linux,
+            "vt100" | "vt102" => Self::Vt100,
+            _ => Self::Xterm,
+        }
+    }
+}
+
+/// 终端程序启用的键盘模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyboardModes {
+    /// 应用光标键模式（DECCKM）
+    pub application_cursor: bool,
+    /// xterm modifyOtherKeys 级别（0-2）
+    pub modify_other_keys: u8,
+    /// kitty 键盘协议的渐进增强标志（当前屏幕）
+    pub kitty_flags: u8,
+}
+
+/// 按配置和键盘模式把按键组合编码为转义序列
+///
+/// # 返回
+/// 无法编码（未知按键或该配置没有对应的键）时返回 `None`
+pub fn encode_key(
+    chord: &KeyChord,
+    profile: KeymapProfile,
+    modes: KeyboardModes,
+) -> Option<Vec<u8>> {
+    let key = Key::parse(&chord.key)?;
+    if profile == KeymapProfile::Vt100 {
+        return encode_legacy(key, chord, profile, modes);
+    }
+    if modes.kitty_flags & KITTY_REPORT_ALL_KEYS != 0 {
+        return encode_kitty(key, chord);
+    }
+    if modes.kitty_flags & KITTY_DISAMBIGUATE != 0 {
+        if let Some(seq) = encode_kitty_disambiguate(key, chord, modes.application_cursor) {
+            return Some(seq);
+        }
+    }
+    encode_legacy(key, chord, profile, modes)
+}
+
+/// `CSI` 加参数和终止字节
+fn csi(params: &str, final_byte: char) -> Vec<u8> {
+    format!("\x1b[{}{}", params, final_byte).into_bytes()
+}
+
+/// kitty 协议中字符键的编码值（字母使用未按 Shift 的小写）
+fn kitty_code(c: char) -> u32 {
+    c.to_ascii_lowercase() as u32
+}
+
+/// kitty 协议 `CSI code[;m] u`
+fn kitty_u(code: u32, m: u8) -> Vec<u8> {
+    if m > 1 {
+        csi(&format!("{};{}", code, m), 'u')
+    } else {
+        csi(&code.to_string(), 'u')
+    }
+}
+
+/// kitty 协议（所有按键以转义码上报）
+fn encode_kitty(key: Key, chord: &KeyChord) -> Option<Vec<u8>> {
+    let m = chord.modifier_param();
+    let code = match key {
+        Key::Char(c) => kitty_code(c),
+        Key::Enter => 13,
+        Key::Tab => 9,
+        Key::Backspace => 127,
+        Key::Escape => 27,
+        _ => return encode_function_key(key, m, false, KeymapProfile::Xterm, true),
+    };
+    Some(kitty_u(code, m))
+}
+
+/// kitty 协议（消除歧义）：只编码传统方式下有歧义的按键，其余返回 `None`
+fn encode_kitty_disambiguate(
+    key: Key,
+    chord: &KeyChord,
+    application_cursor: bool,
+) -> Option<Vec<u8>> {
+    let m = chord.modifier_param();
+    match key {
+        Key::Char(c) if chord.ctrl || chord.alt || chord.meta => Some(kitty_u(kitty_code(c), m)),
+        Key::Escape => Some(kitty_u(27, m)),
+        // 未修饰的回车、制表符和退格保持传统编码，程序异常退出后仍可输入 `reset`
+        Key::Enter if m > 1 => Some(kitty_u(13, m)),
+        Key::Tab if m > 1 => Some(kitty_u(9, m)),
+        Key::Backspace if m > 1 => Some(kitty_u(127, m)),
+        Key::Char(_) | Key::Enter | Key::Tab | Key::Backspace => None,
+        _ => encode_function_key(key, m, application_cursor, KeymapProfile::Xterm, true),
+    }
+}
+
+/// 传统编码（xterm / Linux 控制台 / VT100，可选 modifyOtherKeys）
+fn encode_legacy(
+    key: Key,
+    chord: &KeyChord,
+    profile: KeymapProfile,
+    modes: KeyboardModes,
+) -> Option<Vec<u8>> {
+    let m = chord.modifier_param();
+    let other_keys = if profile == KeymapProfile::Xterm {
+        modes.modify_other_keys
+    } else {
+        0
+    };
+    // Alt / Meta 作为 ESC 前缀
+    let prefix = |bytes: Vec<u8>| {
+        if chord.alt || chord.meta {
+            [&[0x1b][..], &bytes].concat()
+        } else {
+            bytes
+        }
+    };
+    // modifyOtherKeys：`CSI 27 ; m ; code ~`
+    let other = |code: u32| csi(&format!("27;{};{}", m, code), '~');
+    // Ctrl / Shift 修饰的特殊键在 modifyOtherKeys 下单独编码（只有 Alt 时仍用 ESC 前缀）
+    let special_modified = other_keys > 0 && (chord.ctrl || chord.shift);
+
+    let seq = match key {
+        Key::Char(c) => {
+            let c = if chord.shift {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            };
+            let control = chord.ctrl.then(|| ctrl_byte(c)).flatten();
+            let ambiguous =
+                chord.ctrl && (control.is_none() || (chord.shift && c.is_ascii_alphabetic()));
+            if (other_keys >= 2 && (chord.ctrl || chord.alt || chord.meta))
+                || (other_keys == 1 && ambiguous)
+            {
+                other(c as u32)
+            } else {
+                match control {
+                    Some(byte) => prefix(vec![byte]),
+                    None => prefix(c.to_string().into_bytes()),
+                }
+            }
+        }
+        Key::Enter if special_modified => other(13),
+        Key::Enter => prefix(b"\r".to_vec()),
+        Key::Tab if chord.shift && !chord.ctrl && !chord.alt && !chord.meta => csi("", 'Z'),
+        Key::Tab if special_modified => other(9),
+        Key::Tab => prefix(b"\t".to_vec()),
+        Key::Backspace if special_modified => other(127),
+        Key::Backspace => prefix(vec![if chord.ctrl { 0x08 } else { 0x7f }]),
+        Key::Escape if special_modified => other(27),
+        Key::Escape => prefix(vec![0x1b]),
+        _ => return encode_function_key(key, m, modes.application_cursor, profile, false),
+    };
+    Some(seq)
+}
+
+/// Ctrl 加字符对应的控制字符
+fn ctrl_byte(c: char) -> Option<u8> {
+    let byte = match c {
+        'a'..='z' | 'A'..='Z' => c as u8 & 0x1f,
+        '@' | ' ' | '2' => 0x00,
+        '[' | '3' => 0x1b,
+        '\\' | '4' => 0x1c,
+        ']' | '5' => 0x1d,
+        '^' | '6' => 0x1e,
+        '_' | '-' | '/' | '7' => 0x1f,
+        '?' | '8' => 0x7f,
+        _ => return None,
+    };
+    Some(byte)
+}
+
+/// 功能键（方向键、编辑键、F1-F12）
+///
+/// # 参数
+/// - `m`: 修饰键参数（1 表示无修饰键）
+/// - `application_cursor`: 应用光标键模式
+/// - `kitty`: 按 kitty 协议编码（F3 使用 `CSI 13 ~`，避免与光标位置报告混淆）
+fn encode_function_key(
+    key: Key,
+    m: u8,
+    application_cursor: bool,
+    profile: KeymapProfile,
+    kitty: bool,
+) -> Option<Vec<u8>> {
+    // 只有 xterm 配置编码修饰键
+    let m = if profile == KeymapProfile::Xterm {
+        m
+    } else {
+        1
+    };
+    let ss3 = |final_byte: char| format!("\x1bO{}", final_byte).into_bytes();
+    let letter = |final_byte: char| {
+        if m > 1 {
+            csi(&format!("1;{}", m), final_byte)
+        } else if application_cursor {
+            ss3(final_byte)
+        } else {
+            csi("", final_byte)
+        }
+    };
+    let tilde = |n: u8| {
+        if m > 1 {
+            csi(&format!("{};{}", n, m), '~')
+        } else {
+            csi(&n.to_string(), '~')
+        }
+    };
+
+    let seq = match (key, profile) {
+        (Key::Up, _) => letter('A'),
+        (Key::Down, _) => letter('B'),
+        (Key::Right, _) => letter('C'),
+        (Key::Left, _) => letter('D'),
+        (Key::Home, KeymapProfile::Xterm) => letter('H'),
+        (Key::End, KeymapProfile::Xterm) => letter('F'),
+        (Key::Home, _) => tilde(1),
+        (Key::End, _) => tilde(4),
+        (Key::Insert, _) => tilde(2),
+        (Key::Delete, _) => tilde(3),
+        (Key::PageUp, _) => tilde(5),
+        (Key::PageDown, _) => tilde(6),
+        (Key::F(3), KeymapProfile::Xterm) if kitty && m > 1 => tilde(13),
+        (Key::F(n @ 1..=5), KeymapProfile::Linux) => {
+            csi("", '[').into_iter().chain([b'A' + n - 1]).collect()
+        }
+        (Key::F(n @ 1..=4), _) => {
+            let final_byte = (b'P' + n - 1) as char;
+            if m > 1 {
+                csi(&format!("1;{}", m), final_byte)
+            } else {
+                ss3(final_byte)
+            }
+        }
+        (Key::F(_), KeymapProfile::Vt100) => return None,
+        (Key::F(n), _) => tilde(match n {
+            5 => 15,
+            6..=10 => n + 11,
+            _ => n + 12,
+        }),
+        _ => return None,
+    };
+    Some(seq)
+}
+
+/// kitty 键盘协议的标志栈
+#[derive(Debug, Clone, Default)]
+struct KittyStack {
+    flags: u8,
+    saved: Vec<u8>,
+}
+
+/// 序列解析状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// 普通输出
+    Ground,
+    /// 收到 ESC
+    Escape,
+    /// CSI 序列
+    Csi,
+}
+
+/// 按键编码器
+///
+/// 扫描终端输出中的键盘模式序列（序列可跨读取块），按当前模式编码按键：
+/// - `CSI ? 1 h` / `CSI ? 1 l`：应用光标键模式
+/// - `CSI ? 1049 h` 等：备用屏幕（kitty 标志按屏幕分别保存）
+/// - `CSI > 4 ; n m`：modifyOtherKeys 级别，`CSI > 4 n` 关闭
+/// - `CSI > f u` / `CSI < n u` / `CSI = f ; mode u`：kitty 标志入栈、出栈、设置
+/// - `CSI ? u`：查询 kitty 标志（需回复）
+/// - `CSI ! p`（DECSTR）和 `ESC c`（RIS）：重置
+#[derive(Debug)]
+pub struct KeyEncoder {
+    profile: KeymapProfile,
+    state: State,
+    params: Vec<u8>,
+    intermediates: Vec<u8>,
+    /// 参数过长，忽略该序列
+    ignore: bool,
+    application_cursor: bool,
+    modify_other_keys: u8,
+    alt_screen: bool,
+    /// 主屏幕和备用屏幕的 kitty 标志栈
+    kitty: [KittyStack; 2],
+}
+
+impl Default for KeyEncoder {
+    fn default() -> Self {
+        Self::new(KeymapProfile::default())
+    }
+}
+
+impl KeyEncoder {
+    /// 创建编码器（所有键盘模式关闭）
+    pub fn new(profile: KeymapProfile) -> Self {
+        Self {
+            profile,
+            state: State::Ground,
+            params: Vec::new(),
+            intermediates: Vec::new(),
+            ignore: false,
+            application_cursor: false,
+            modify_other_keys: 0,
+            alt_screen: false,
+            kitty: Default::default(),
+        }
+    }
+
+    /// 编码配置
+    pub fn profile(&self) -> KeymapProfile {
+        self.profile
+    }
+
+    /// 设置编码配置
+    pub fn set_profile(&mut self, profile: KeymapProfile) {
+        self.profile = profile;
+    }
+
+    /// 当前键盘模式
+    pub fn modes(&self) -> KeyboardModes {
+        KeyboardModes {
+            application_cursor: self.application_cursor,
+            modify_other_keys: self.modify_other_keys,
+            kitty_flags: self.kitty().flags,
+        }
+    }
+
+    /// 按当前配置和键盘模式编码按键
+    pub fn encode(&self, chord: &KeyChord) -> Option<Vec<u8>> {
+        encode_key(chord, self.profile, self.modes())
+    }
+
+    /// 处理终端输出
+    ///
+    /// # 返回
+    /// 需要写回终端程序的回复（kitty 标志查询）
+    pub fn feed(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut replies = Vec::new();
+        for &byte in data {
+            match self.state {
+                State::Ground => {
+                    if byte == 0x1b {
+                        self.state = State::Escape;
+                    }
+                }
+                State::Escape => match byte {
+                    b'[' => {
+                        self.state = State::Csi;
+                        self.params.clear();
+                        self.intermediates.clear();
+                        self.ignore = false;
+                    }
+                    b'c' => {
+                        // RIS：完全重置终端
+                        *self = Self::new(self.profile);
+                    }
+                    0x1b => {}
+                    _ => self.state = State::Ground,
+                },
+                State::Csi => match byte {
+                    0x30..=0x3f if self.intermediates.is_empty() => {
+                        if self.params.len() < MAX_CSI_PARAMS_LEN {
+                            self.params.push(byte);
+                        } else {
+                            self.ignore = true;
+                        }
+                    }
+                    0x20..=0x2f if self.intermediates.len() < 2 => self.intermediates.push(byte),
+                    0x20..=0x3f => self.ignore = true,
+                    0x40..=0x7e => {
+                        if !self.ignore {
+                            self.finish_csi(byte, &mut replies);
+                        }
+                        self.state = State::Ground;
+                    }
+                    0x1b => self.state = State::Escape,
+                    // CAN / SUB 取消序列
+                    0x18 | 0x1a => self.state = State::Ground,
+                    _ => {}
+                },
+            }
+        }
+        replies
+    }
+
+    fn kitty(&self) -> &KittyStack {
+        &self.kitty[self.alt_screen as usize]
+    }
+
+    fn kitty_mut(&mut self) -> &mut KittyStack {
+        &mut self.kitty[self.alt_screen as usize]
+    }
+
+    /// 处理完整的 CSI 序列
+    fn finish_csi(&mut self, final_byte: u8, replies: &mut Vec<u8>) {
+        if !self.intermediates.is_empty() {
+            // DECSTR：软重置
+            if self.intermediates == b"!" && final_byte == b'p' && self.params.is_empty() {
+                self.application_cursor = false;
+            }
+            return;
+        }
+        let (marker, params) = match self.params.first() {
+            Some(&m @ (b'?' | b'>' | b'<' | b'=')) => (Some(m), &self.params[1..]),
+            _ => (None, &self.params[..]),
+        };
+        let params: Vec<Option<u32>> = String::from_utf8_lossy(params)
+            .split(';')
+            .map(|p| p.parse().ok())
+            .collect();
+        let param = |i: usize| params.get(i).copied().flatten();
+
+        match (marker, final_byte) {
+            (Some(b'?'), b'h' | b'l') => {
+                let set = final_byte == b'h';
+                for mode in params.iter().flatten() {
+                    match mode {
+                        1 => self.application_cursor = set,
+                        47 | 1047 | 1049 => self.alt_screen = set,
+                        _ => {}
+                    }
+                }
+            }
+            (Some(b'>'), b'm') if param(0) == Some(4) => {
+                self.modify_other_keys = param(1).unwrap_or(0).min(2) as u8;
+            }
+            (Some(b'>'), b'n') if param(0) == Some(4) => self.modify_other_keys = 0,
+            (Some(b'?'), b'u') => {
+                replies.extend(csi(&format!("?{}", self.kitty().flags), 'u'));
+            }
+            (Some(b'>'), b'u') => {
+                let flags = param(0).unwrap_or(0) as u8 & KITTY_FLAGS_MASK;
+                let stack = self.kitty_mut();
+                if stack.saved.len() >= MAX_KITTY_STACK {
+                    stack.saved.remove(0);
+                }
+                stack.saved.push(stack.flags);
+                stack.flags = flags;
+            }
+            (Some(b'<'), b'u') => {
+                let stack = self.kitty_mut();
+                let count = param(0).unwrap_or(1).clamp(1, MAX_KITTY_STACK as u32 + 1);
+                for _ in 0..count {
+                    stack.flags = stack.saved.pop().unwrap_or(0);
+                }
+            }
+            (Some(b'='), b'u') => {
+                let flags = param(0).unwrap_or(0) as u8 & KITTY_FLAGS_MASK;
+                let stack = self.kitty_mut();
+                match param(1).unwrap_or(1) {
+                    1 => stack.flags = flags,
+                    2 => stack.flags |= flags,
+                    3 => stack.flags &= !flags,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> KeyChord {
+        KeyChord::parse(text).unwrap()
+    }
+
+    fn encode(text: &str, encoder: &KeyEncoder) -> Vec<u8> {
+        encoder.encode(&chord(text)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_chord() {
+        let parsed = chord("Ctrl+Shift+ArrowUp");
+        assert!(parsed.ctrl && parsed.shift && !parsed.alt && !parsed.meta);
+        assert_eq!(parsed.key, "ArrowUp");
+        assert_eq!(chord("ctrl++").key, "+");
+        assert_eq!(chord("+").key, "+");
+        assert!(KeyChord::parse("hyper+a").is_none());
+        assert!(KeyChord::parse("ctrl+NoSuchKey").is_none());
+        assert!(KeyChord::parse("F13").is_none());
+
+        assert!(chord("ctrl+A").matches(&chord("ctrl+a")));
+        assert!(!chord("ctrl+a").matches(&chord("ctrl+alt+a")));
+        assert!(chord("Space").matches(&chord(" ")));
+    }
+
+    #[test]
+    fn test_legacy_encoding() {
+        let mut encoder = KeyEncoder::new(KeymapProfile::Xterm);
+        assert_eq!(encode("a", &encoder), b"a");
+        assert_eq!(encode("ctrl+c", &encoder), [0x03]);
+        assert_eq!(encode("ctrl+Space", &encoder), [0x00]);
+        assert_eq!(encode("alt+b", &encoder), b"\x1bb");
+        assert_eq!(encode("shift+Tab", &encoder), b"\x1b[Z");
+        assert_eq!(encode("Backspace", &encoder), [0x7f]);
+        assert_eq!(encode("ctrl+Backspace", &encoder), [0x08]);
+        assert_eq!(encode("ArrowUp", &encoder), b"\x1b[A");
+        assert_eq!(encode("ctrl+ArrowRight", &encoder), b"\x1b[1;5C");
+        assert_eq!(encode("shift+Home", &encoder), b"\x1b[1;2H");
+        assert_eq!(encode("alt+Delete", &encoder), b"\x1b[3;3~");
+        assert_eq!(encode("F1", &encoder), b"\x1bOP");
+        assert_eq!(encode("ctrl+F2", &encoder), b"\x1b[1;5Q");
+        assert_eq!(encode("F5", &encoder), b"\x1b[15~");
+        assert_eq!(encode("F6", &encoder), b"\x1b[17~");
+        assert_eq!(encode("F12", &encoder), b"\x1b[24~");
+
+        // 应用光标键模式
+        encoder.feed(b"\x1b[?1h");
+        assert_eq!(encode("ArrowDown", &encoder), b"\x1bOB");
+        assert_eq!(encode("End", &encoder), b"\x1bOF");
+        assert_eq!(encode("ctrl+ArrowDown", &encoder), b"\x1b[1;5B");
+        encoder.feed(b"\x1b[!p");
+        assert_eq!(encode("ArrowDown", &encoder), b"\x1b[B");
+
+        // Linux 控制台和 VT100
+        encoder.set_profile(KeymapProfile::from_term("linux"));
+        assert_eq!(encode("Home", &encoder), b"\x1b[1~");
+        assert_eq!(encode("F3", &encoder), b"\x1b[[C");
+        assert_eq!(encode("ctrl+ArrowUp", &encoder), b"\x1b[A");
+        encoder.set_profile(KeymapProfile::from_term("vt100"));
+        assert_eq!(encode("F4", &encoder), b"\x1bOS");
+        assert_eq!(encoder.encode(&chord("F5")), None);
+    }
+
+    #[test]
+    fn test_modify_other_keys() {
+        let mut encoder = KeyEncoder::new(KeymapProfile::Xterm);
+        encoder.feed(b"\x1b[>4;1m");
+        assert_eq!(encoder.modes().modify_other_keys, 1);
+        // 级别 1 只编码传统方式无法区分的组合
+        assert_eq!(encode("ctrl+a", &encoder), [0x01]);
+        assert_eq!(encode("ctrl+shift+a", &encoder), b"\x1b[27;6;65~");
+        assert_eq!(encode("ctrl+Enter", &encoder), b"\x1b[27;5;13~");
+        assert_eq!(encode("alt+Enter", &encoder), b"\x1b\r");
+
+        encoder.feed(b"\x1b[>4;2m");
+        assert_eq!(encode("ctrl+a", &encoder), b"\x1b[27;5;97~");
+        assert_eq!(encode("a", &encoder), b"a");
+
+        encoder.feed(b"\x1b[>4n");
+        assert_eq!(encode("ctrl+a", &encoder), [0x01]);
+    }
+
+    #[test]
+    fn test_kitty_keyboard_protocol() {
+        let mut encoder = KeyEncoder::new(KeymapProfile::Xterm);
+        // 序列跨读取块，查询需要回复
+        assert!(encoder.feed(b"\x1b[>").is_empty());
+        assert_eq!(encoder.feed(b"1u\x1b[?u"), b"\x1b[?1u");
+        assert_eq!(encode("ctrl+c", &encoder), b"\x1b[99;5u");
+        assert_eq!(encode("ctrl+shift+C", &encoder), b"\x1b[99;6u");
+        assert_eq!(encode("Escape", &encoder), b"\x1b[27u");
+        assert_eq!(encode("Enter", &encoder), b"\r");
+        assert_eq!(encode("shift+Enter", &encoder), b"\x1b[13;2u");
+        assert_eq!(encode("ctrl+F3", &encoder), b"\x1b[13;5~");
+        assert_eq!(encode("x", &encoder), b"x");
+
+        // 设置标志（或运算），所有按键以转义码上报
+        encoder.feed(b"\x1b[=8;2u");
+        assert_eq!(encoder.modes().kitty_flags, 9);
+        assert_eq!(encode("x", &encoder), b"\x1b[120u");
+        assert_eq!(encode("Enter", &encoder), b"\x1b[13u");
+
+        // 备用屏幕有独立的标志栈
+        encoder.feed(b"\x1b[?1049h");
+        assert_eq!(encoder.modes().kitty_flags, 0);
+        assert_eq!(encode("ctrl+c", &encoder), [0x03]);
+        encoder.feed(b"\x1b[?1049l");
+        assert_eq!(encoder.modes().kitty_flags, 9);
+
+        // 出栈恢复之前的标志，RIS 重置所有模式
+        encoder.feed(b"\x1b[<u");
+        assert_eq!(encoder.modes().kitty_flags, 0);
+        encoder.feed(b"\x1b[>1u\x1b[?1h\x1bc");
+        assert_eq!(encoder.modes(), KeyboardModes::default());
+
+        // VT100 不使用键盘协议
+        encoder.set_profile(KeymapProfile::Vt100);
+        encoder.feed(b"\x1b[>1u");
+        assert_eq!(encode("ctrl+c", &encoder), [0x03]);
+    }
+}
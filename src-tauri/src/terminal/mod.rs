@@ -6,11 +6,17 @@
 //! - `error` - 错误类型定义
 //! - `events` - Tauri 事件定义
 //! - `pty_session` - PTY 会话封装
+//! - `flow_control` - 输出流控（合并输出、背压、洪泛检测）
+//! - `keymap` - 按键编码（按 TERM 和键盘模式把按键组合转换为转义序列）
 //! - `session_manager` - 会话管理器
-//! - `persistence` - 持久化存储（块文件、会话元数据）
+//! - `persistence` - 持久化存储（块文件、会话元数据、命令历史）
 //! - `block_controller` - 块控制器抽象层
 //! - `connections` - 连接模块（本地 PTY、SSH、WSL）
 //! - `integration` - 集成模块（Shell 集成、OSC 解析、状态重同步）
+//! - `replay` - 会话回放（按录制节奏回放块文件，支持跳转到命令）
+//! - `detached` - 后台会话（Shell 托管在 tmux 中，应用重启后重新连接）
+//! - `share` - 会话分享（一次性令牌，通过代理服务器的 WebSocket 只读观看或协作）
+//! - `workspace` - 工作区（标签页与分屏布局，持久化后在启动时恢复）
 //!
 //! ## 使用示例
 //! ```ignore
@@ -23,12 +29,18 @@
 
 pub mod block_controller;
 pub mod connections;
+pub mod detached;
 pub mod error;
 pub mod events;
+pub mod flow_control;
 pub mod integration;
+pub mod keymap;
 pub mod persistence;
 pub mod pty_session;
+pub mod replay;
 pub mod session_manager;
+pub mod share;
+pub mod workspace;
 
 #[cfg(test)]
 mod tests;
@@ -39,12 +51,38 @@ pub use block_controller::{
     ControllerStatusEvent, RuntimeOpts, ShellController, TermSize, CONTROLLER_STATUS_EVENT,
 };
 pub use connections::ShellProc;
+pub use detached::{DetachedSessionBackend, DetachedSessionInfo};
 pub use error::TerminalError;
-pub use events::{SessionStatus, TerminalOutputEvent, TerminalStatusEvent};
+pub use events::{
+    SessionStatus, TerminalClipboardRequestEvent, TerminalCommandBlockEvent, TerminalImageEvent,
+    TerminalLinksEvent, TerminalOutputEvent, TerminalOutputFloodEvent, TerminalPasteWarningEvent,
+    TerminalReplayOutputEvent, TerminalReplayStatusEvent, TerminalStatusEvent,
+};
 pub use integration::{
-    resync_controller, ResyncController, ResyncOptions, ResyncResult, TERMINAL_RESET_SEQUENCE,
+    resync_controller, ClipboardBridge, CommandBlock, CommandBlockQuery, CommandOutput,
+    CommandOutputFormat, PasteOutcome, PromptMark, PromptMarks, ResyncController, ResyncOptions,
+    ResyncResult, UploadFileInfo, UploadGrants, TERMINAL_RESET_SEQUENCE,
     TERMINAL_SOFT_RESET_SEQUENCE,
 };
-pub use persistence::{BlockFile, SessionMetadataStore, SessionRecord};
-pub use pty_session::{PtySession, DEFAULT_COLS, DEFAULT_ROWS};
+pub use keymap::{KeyChord, KeyEncoder, KeyboardModes, KeymapProfile};
+pub use persistence::{
+    BlockFile, BlockPruneReport, BlockRetentionPolicy, BlockStorageSettings, BlockUsage,
+    ClipboardPolicy, ClipboardPolicyStore, CommandHistoryEntry, CommandHistoryQuery,
+    CommandHistorySort, CommandHistoryStore, ImageCache, ImageCacheLimits, KeyBinding,
+    KeymapSettings, LaunchProfile, LaunchProfileStore, SessionMetadataStore, SessionRecord,
+};
+pub use pty_session::{
+    IgnoreResize, PtySession, SessionTap, StreamParts, TerminalResizer, DEFAULT_COLS, DEFAULT_ROWS,
+};
+pub use replay::{
+    CommandMarker, ReplayCommand, ReplayHandle, ReplayInfo, ReplayPlayer, ReplayRecording,
+    ReplayState, ReplayStatus,
+};
 pub use session_manager::{SessionMetadata, TerminalSessionManager};
+pub use share::{
+    ShareClientMessage, ShareCloseReason, ShareGrant, ShareMode, ShareServerMessage,
+    TerminalShareInfo, TerminalShareRegistry, TerminalShareTicket,
+};
+pub use workspace::{
+    PaneLayout, PanePlacement, SplitDirection, TerminalWorkspace, DEFAULT_WORKSPACE_ID,
+};
@@ -6,25 +6,35 @@
 //! - 循环缓冲写入（超过最大大小时覆盖旧数据）
 //! - 文件读取和截断
 //! - 可配置最大文件大小
+//! - 记录每次写入的时间（`{block_id}.timing`），供会话回放使用
+//! - 按会话输出位置读取（命令块输出，见 [`BlockFile::read_range`]）
+//! - 可选的冷段存储：被覆盖的输出压缩保存（见 [`ColdSegments`]）
 //!
 //! ## 设计说明
 //! 采用简单的循环缓冲策略：当文件大小超过配置的最大值时，
-//! 保留最新的数据，丢弃最旧的数据。
+//! 保留最新的数据，丢弃最旧的数据。启用冷段存储时，每次至少覆盖最大大小的
+//! 1/[`COLD_EVICT_FRACTION`]，被覆盖的数据作为一个冷段压缩保存，避免产生大量很小的冷段。
 //!
 //! _Requirements: 3.1, 3.2, 3.3, 3.4, 3.7_
 
 use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
+use chrono::Utc;
 use parking_lot::RwLock;
 
+use super::block_timing::{self, TimingRecord};
+use super::cold_segments::ColdSegments;
 use crate::terminal::error::TerminalError;
 
 /// 默认终端块文件最大大小 (256KB)
 pub const DEFAULT_TERM_MAX_FILE_SIZE: usize = 256 * 1024;
 
+/// 启用冷段存储时，每次循环覆盖至少覆盖最大大小的 1/N
+pub const COLD_EVICT_FRACTION: usize = 4;
+
 /// 块文件管理器
 ///
 /// 管理单个终端会话的输出历史文件，使用循环缓冲策略。
@@ -39,10 +49,18 @@ pub struct BlockFile {
     write_pos: AtomicUsize,
     /// 当前文件大小
     current_size: AtomicUsize,
+    /// 写入的字节总数（含打开时已有的数据），文件开头对应的输出位置为 `written - current_size`
+    written: AtomicU64,
     /// 是否已经开始循环（文件已满过一次）
     is_wrapped: RwLock<bool>,
     /// 文件句柄（用于写入）
     file: RwLock<Option<File>>,
+    /// 时间索引文件路径
+    timing_path: PathBuf,
+    /// 时间索引文件句柄（追加模式，与 `file` 在同一把写锁下访问）
+    timing_file: RwLock<Option<File>>,
+    /// 冷段存储（未启用时为 `None`）
+    cold: Option<ColdSegments>,
 }
 
 impl BlockFile {
@@ -87,6 +105,18 @@ impl BlockFile {
             .open(&file_path)
             .map_err(|e| TerminalError::BlockFileError(format!("无法打开文件: {}", e)))?;
 
+        // 时间索引只影响回放，打开失败时不阻止终端输出的持久化
+        let timing_path = base_dir.join(format!("{}.timing", block_id));
+        let timing_file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&timing_path)
+            .map_err(|e| {
+                tracing::warn!("[BlockFile] 无法打开时间索引 {:?}: {}", timing_path, e);
+            })
+            .ok();
+
         tracing::debug!(
             "[BlockFile] 创建块文件: {} (max_size: {}, current_size: {})",
             block_id,
@@ -100,11 +130,25 @@ impl BlockFile {
             max_size,
             write_pos: AtomicUsize::new(current_size),
             current_size: AtomicUsize::new(current_size),
+            written: AtomicU64::new(current_size as u64),
             is_wrapped: RwLock::new(is_wrapped),
             file: RwLock::new(Some(file)),
+            timing_path,
+            timing_file: RwLock::new(timing_file),
+            cold: None,
         })
     }
 
+    /// 启用冷段存储
+    ///
+    /// # 参数
+    /// - `max_bytes`: 冷段总大小上限（压缩后）
+    pub fn with_cold_segments(mut self, max_bytes: u64) -> Result<Self, TerminalError> {
+        let base_dir = self.file_path.parent().unwrap_or(Path::new("."));
+        self.cold = Some(ColdSegments::open(base_dir, &self.block_id, max_bytes)?);
+        Ok(self)
+    }
+
     /// 使用默认最大大小创建块文件
     ///
     /// # 参数
@@ -143,6 +187,11 @@ impl BlockFile {
         self.current_size.load(Ordering::Relaxed)
     }
 
+    /// 获取冷段存储（未启用时为 `None`）
+    pub fn cold_segments(&self) -> Option<&ColdSegments> {
+        self.cold.as_ref()
+    }
+
     /// 追加数据到块文件
     ///
     /// 使用循环缓冲策略：当文件大小超过最大值时，覆盖最旧的数据。
@@ -165,8 +214,8 @@ impl BlockFile {
             .as_mut()
             .ok_or_else(|| TerminalError::BlockFileError("文件已关闭".to_string()))?;
 
-        // 如果数据本身就超过最大大小，只保留最后 max_size 字节
-        let data_to_write = if data.len() >= self.max_size {
+        // 如果数据本身就超过最大大小，只保留最后 max_size 字节（启用冷段时全部数据都要进入冷段）
+        let data_to_write = if self.cold.is_none() && data.len() >= self.max_size {
             &data[data.len() - self.max_size..]
         } else {
             data
@@ -175,7 +224,7 @@ impl BlockFile {
         let current_size = self.current_size.load(Ordering::Relaxed);
         let new_total = current_size + data_to_write.len();
 
-        if new_total <= self.max_size {
+        let dropped = if new_total <= self.max_size {
             // 文件未满，直接追加
             file.seek(SeekFrom::End(0))
                 .map_err(|e| TerminalError::BlockFileError(format!("Seek 失败: {}", e)))?;
@@ -185,20 +234,63 @@ impl BlockFile {
                 .map_err(|e| TerminalError::BlockFileError(format!("Flush 失败: {}", e)))?;
             self.current_size.store(new_total, Ordering::Relaxed);
             self.write_pos.store(new_total, Ordering::Relaxed);
+            0
         } else {
             // 文件将超过最大大小，需要使用循环缓冲策略
             // 策略：读取现有数据，保留最新的部分，然后重写文件
-            self.apply_circular_buffer(file, data_to_write)?;
+            self.apply_circular_buffer(file, data_to_write)?
+        };
+        self.written.fetch_add(data.len() as u64, Ordering::Relaxed);
+
+        if let Err(e) = self.record_timing(data_to_write.len(), dropped) {
+            tracing::warn!(
+                "[BlockFile] 写入时间索引失败: {}, error={}",
+                self.block_id,
+                e
+            );
         }
 
         Ok(())
     }
 
+    /// 追加一条时间记录，块文件循环覆盖了 `dropped` 字节时同步丢弃最旧的记录
+    ///
+    /// 调用方需持有 `file` 的写锁
+    fn record_timing(&self, len: usize, dropped: usize) -> std::io::Result<()> {
+        let mut timing_guard = self.timing_file.write();
+        let Some(timing_file) = timing_guard.as_mut() else {
+            return Ok(());
+        };
+        let record = TimingRecord::new(Utc::now().timestamp_millis(), len);
+
+        if dropped == 0 {
+            return timing_file.write_all(block_timing::format_timing(&[record]).as_bytes());
+        }
+
+        let mut text = String::new();
+        timing_file.seek(SeekFrom::Start(0))?;
+        timing_file.read_to_string(&mut text)?;
+        let mut records = block_timing::parse_timing(&text);
+        records.push(record);
+        block_timing::trim_front(&mut records, dropped);
+
+        // 追加模式下清空后写入即从头开始
+        timing_file.set_len(0)?;
+        timing_file.write_all(block_timing::format_timing(&records).as_bytes())
+    }
+
     /// 应用循环缓冲策略
     ///
     /// 当新数据会导致文件超过最大大小时调用。
-    /// 保留最新的数据，丢弃最旧的数据。
-    fn apply_circular_buffer(&self, file: &mut File, new_data: &[u8]) -> Result<(), TerminalError> {
+    /// 保留最新的数据，丢弃最旧的数据；启用冷段存储时丢弃的数据压缩保存为冷段。
+    ///
+    /// # 返回
+    /// 丢弃的字节数（从现有数据和新数据拼接后的开头算起）
+    fn apply_circular_buffer(
+        &self,
+        file: &mut File,
+        new_data: &[u8],
+    ) -> Result<usize, TerminalError> {
         // 读取现有数据
         file.seek(SeekFrom::Start(0))
             .map_err(|e| TerminalError::BlockFileError(format!("Seek 失败: {}", e)))?;
@@ -212,12 +304,19 @@ impl BlockFile {
         let mut combined = existing_data;
         combined.extend_from_slice(new_data);
 
-        // 只保留最后 max_size 字节
-        let final_data = if combined.len() > self.max_size {
-            &combined[combined.len() - self.max_size..]
-        } else {
-            &combined[..]
-        };
+        // 只保留最后 max_size 字节，启用冷段时至少丢弃 max_size / COLD_EVICT_FRACTION 字节
+        let mut dropped = combined.len().saturating_sub(self.max_size);
+        if let Some(cold) = &self.cold {
+            dropped = dropped
+                .max(self.max_size / COLD_EVICT_FRACTION)
+                .min(combined.len());
+            // 写入字节总数尚未包含新数据，文件开头的输出位置为 written - current_size
+            let start = self.written.load(Ordering::Relaxed) - current_size as u64;
+            if let Err(e) = cold.append(start, &combined[..dropped]) {
+                tracing::warn!("[BlockFile] 写入冷段失败: {}, error={}", self.block_id, e);
+            }
+        }
+        let final_data = &combined[dropped..];
 
         // 重写文件
         file.seek(SeekFrom::Start(0))
@@ -233,7 +332,7 @@ impl BlockFile {
         self.write_pos.store(final_data.len(), Ordering::Relaxed);
         *self.is_wrapped.write() = true;
 
-        Ok(())
+        Ok(dropped)
     }
 
     /// 读取所有数据
@@ -248,7 +347,79 @@ impl BlockFile {
         let file = file_guard
             .as_mut()
             .ok_or_else(|| TerminalError::BlockFileError("文件已关闭".to_string()))?;
+        self.read_data(file)
+    }
 
+    /// 读取所有数据及对应的时间记录
+    ///
+    /// 时间记录的字节数之和与返回的数据长度一致；没有时间索引的旧版块文件
+    /// 整体作为一条记录返回。
+    pub fn read_with_timing(&self) -> Result<(Vec<u8>, Vec<TimingRecord>), TerminalError> {
+        // 与写入使用同一把锁，保证读到的数据和时间索引一致
+        let mut file_guard = self.file.write();
+        let file = file_guard
+            .as_mut()
+            .ok_or_else(|| TerminalError::BlockFileError("文件已关闭".to_string()))?;
+        let data = self.read_data(file)?;
+
+        let mut text = String::new();
+        if let Some(timing_file) = self.timing_file.write().as_mut() {
+            timing_file
+                .seek(SeekFrom::Start(0))
+                .and_then(|_| timing_file.read_to_string(&mut text))
+                .map_err(|e| TerminalError::BlockFileError(format!("读取时间索引失败: {}", e)))?;
+        }
+
+        let mut records = block_timing::parse_timing(&text);
+        block_timing::align_to_size(&mut records, data.len(), Utc::now().timestamp_millis());
+        Ok((data, records))
+    }
+
+    /// 写入的字节总数
+    ///
+    /// 打开时已有的数据计入总数，循环覆盖和截断不减少总数，可作为输出位置使用。
+    pub fn written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    /// 按输出位置读取数据
+    ///
+    /// # 参数
+    /// - `start`: 起始位置（含，见 [`BlockFile::written`]）
+    /// - `end`: 结束位置（不含），超出已写入的位置时读到末尾
+    ///
+    /// # 返回
+    /// 仍保留在文件（及冷段）中的数据，以及起始部分是否已被循环覆盖
+    pub fn read_range(&self, start: u64, end: u64) -> Result<(Vec<u8>, bool), TerminalError> {
+        let mut file_guard = self.file.write();
+        let file = file_guard
+            .as_mut()
+            .ok_or_else(|| TerminalError::BlockFileError("文件已关闭".to_string()))?;
+        let data = self.read_data(file)?;
+
+        let written = self.written.load(Ordering::Relaxed);
+        let first = written.saturating_sub(data.len() as u64);
+        let from = (start.max(first) - first) as usize;
+        let to = (end.min(written).saturating_sub(first) as usize).min(data.len());
+        let hot = if from < to { &data[from..to] } else { &[][..] };
+        if start >= first {
+            return Ok((hot.to_vec(), false));
+        }
+
+        // 被覆盖的部分从冷段读取，只使用与文件内容相连的冷段数据
+        let cold_end = end.min(first);
+        if let Some(cold) = &self.cold {
+            let (mut cold_data, cold_start) = cold.read_range(start, cold_end)?;
+            if !cold_data.is_empty() && cold_start + cold_data.len() as u64 == cold_end {
+                cold_data.extend_from_slice(hot);
+                return Ok((cold_data, cold_start > start));
+            }
+        }
+        Ok((hot.to_vec(), true))
+    }
+
+    /// 从文件开头读取当前大小的数据，调用方需持有 `file` 的写锁
+    fn read_data(&self, file: &mut File) -> Result<Vec<u8>, TerminalError> {
         let current_size = self.current_size.load(Ordering::Relaxed);
         if current_size == 0 {
             return Ok(Vec::new());
@@ -284,6 +455,15 @@ impl BlockFile {
         file.flush()
             .map_err(|e| TerminalError::BlockFileError(format!("Flush 失败: {}", e)))?;
 
+        if let Some(timing_file) = self.timing_file.write().as_mut() {
+            timing_file
+                .set_len(0)
+                .map_err(|e| TerminalError::BlockFileError(format!("截断时间索引失败: {}", e)))?;
+        }
+        if let Some(cold) = &self.cold {
+            cold.clear()?;
+        }
+
         self.current_size.store(0, Ordering::Relaxed);
         self.write_pos.store(0, Ordering::Relaxed);
         *self.is_wrapped.write() = false;
@@ -300,6 +480,7 @@ impl BlockFile {
         {
             let mut file_guard = self.file.write();
             *file_guard = None;
+            *self.timing_file.write() = None;
         }
 
         // 删除文件
@@ -307,6 +488,13 @@ impl BlockFile {
             fs::remove_file(&self.file_path)
                 .map_err(|e| TerminalError::BlockFileError(format!("删除文件失败: {}", e)))?;
         }
+        if self.timing_path.exists() {
+            fs::remove_file(&self.timing_path)
+                .map_err(|e| TerminalError::BlockFileError(format!("删除时间索引失败: {}", e)))?;
+        }
+        if let Some(cold) = &self.cold {
+            cold.clear()?;
+        }
 
         tracing::debug!("[BlockFile] 删除块文件: {}", self.block_id);
         Ok(())
@@ -333,3 +521,92 @@ impl Drop for BlockFile {
         *file_guard = None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_follows_circular_buffer() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_dir = dir.path().to_path_buf();
+        let block_file = BlockFile::new("replay", &base_dir, 8).unwrap();
+
+        block_file.append_data(b"abcd").unwrap();
+        block_file.append_data(b"efgh").unwrap();
+        block_file.append_data(b"ijk").unwrap();
+
+        let (data, records) = block_file.read_with_timing().unwrap();
+        assert_eq!(data, b"defghijk");
+        let lens: Vec<usize> = records.iter().map(|r| r.len).collect();
+        assert_eq!(lens, vec![1, 4, 3]);
+
+        block_file.truncate().unwrap();
+        let (data, records) = block_file.read_with_timing().unwrap();
+        assert!(data.is_empty() && records.is_empty());
+
+        block_file.delete().unwrap();
+        assert!(!base_dir.join("replay.timing").exists());
+    }
+    #[test]
+    fn test_read_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let block_file = BlockFile::new("range", &dir.path().to_path_buf(), 8).unwrap();
+
+        block_file.append_data(b"abcd").unwrap();
+        block_file.append_data(b"efgh").unwrap();
+        assert_eq!(
+            block_file.read_range(2, 6).unwrap(),
+            (b"cdef".to_vec(), false)
+        );
+
+        // 循环覆盖后位置不变，被覆盖的部分标记为截断
+        block_file.append_data(b"ijk").unwrap();
+        assert_eq!(block_file.written(), 11);
+        assert_eq!(
+            block_file.read_range(2, 6).unwrap(),
+            (b"def".to_vec(), true)
+        );
+        assert_eq!(
+            block_file.read_range(8, u64::MAX).unwrap(),
+            (b"ijk".to_vec(), false)
+        );
+
+        block_file.truncate().unwrap();
+        assert_eq!(block_file.written(), 11);
+        assert_eq!(block_file.read_range(8, 11).unwrap(), (Vec::new(), true));
+    }
+
+    #[test]
+    fn test_cold_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_dir = dir.path().to_path_buf();
+        let block_file = BlockFile::new("cold", &base_dir, 8)
+            .unwrap()
+            .with_cold_segments(1024)
+            .unwrap();
+
+        block_file.append_data(b"abcdef").unwrap();
+        // 至少覆盖 8 / COLD_EVICT_FRACTION 字节
+        block_file.append_data(b"g").unwrap();
+        assert_eq!(block_file.read_all().unwrap(), b"abcdefg");
+        block_file.append_data(b"hi").unwrap();
+        assert_eq!(block_file.read_all().unwrap(), b"cdefghi");
+        // 超过最大大小的数据也全部进入冷段
+        block_file.append_data(b"0123456789").unwrap();
+        assert_eq!(block_file.read_all().unwrap(), b"23456789");
+        assert_eq!(block_file.cold_segments().unwrap().count(), 2);
+
+        let (data, records) = block_file.read_with_timing().unwrap();
+        assert_eq!(records.iter().map(|r| r.len).sum::<usize>(), data.len());
+
+        // 被覆盖的部分从冷段读取
+        assert_eq!(
+            block_file.read_range(1, 15).unwrap(),
+            (b"bcdefghi012345".to_vec(), false)
+        );
+
+        block_file.delete().unwrap();
+        assert!(!base_dir.join("cold.cold").exists());
+    }
+}
@@ -0,0 +1,453 @@
+//! 块文件存储设置与清理
+//!
+//! 管理终端输出历史（块文件）的存储设置、磁盘占用统计和跨会话的清理策略。
+//!
+//! ## 功能
+//! - 存储设置：新会话的回滚缓冲大小、冷段压缩、清理策略，保存在块文件目录的 `storage.json`
+//! - 按会话统计磁盘占用（块文件、时间索引、冷段）
+//! - 按最后写入时间和总大小清理已结束会话的块文件
+//!
+//! ## 设计说明
+//! 每个会话在块文件目录中有 `{block_id}.block`、`{block_id}.timing` 和 `{block_id}.cold/`，
+//! 统计和清理按块 ID 聚合这三者。运行中和可恢复的会话由调用方列为受保护的块，不会被清理。
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use super::block_file::DEFAULT_TERM_MAX_FILE_SIZE;
+use super::cold_segments::DEFAULT_COLD_MAX_BYTES;
+use crate::terminal::error::TerminalError;
+
+/// 存储设置文件名
+pub const STORAGE_SETTINGS_FILE: &str = "storage.json";
+
+/// 回滚缓冲大小下限（16 KiB）
+pub const MIN_SCROLLBACK_BYTES: usize = 16 * 1024;
+
+/// 回滚缓冲大小上限（64 MiB）
+pub const MAX_SCROLLBACK_BYTES: usize = 64 * 1024 * 1024;
+
+/// 默认保留天数
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// 默认所有会话块文件总大小上限（512 MiB）
+pub const DEFAULT_RETENTION_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// 块文件清理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlockRetentionPolicy {
+    /// 最后写入超过多少天的会话被清理（`None` 时不按时间清理）
+    pub max_age_days: Option<u32>,
+    /// 所有会话的总大小上限，超出时从最久未写入的会话开始清理（`None` 时不按大小清理）
+    pub max_total_bytes: Option<u64>,
+}
+
+impl Default for BlockRetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(DEFAULT_RETENTION_DAYS),
+            max_total_bytes: Some(DEFAULT_RETENTION_MAX_BYTES),
+        }
+    }
+}
+
+/// 块文件存储设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlockStorageSettings {
+    /// 新会话的回滚缓冲大小（块文件最大大小，字节）
+    pub scrollback_bytes: usize,
+    /// 是否压缩保存被循环覆盖的输出（冷段）
+    pub compress_cold: bool,
+    /// 每个会话冷段总大小上限（压缩后，字节）
+    pub cold_max_bytes: u64,
+    /// 清理策略
+    pub retention: BlockRetentionPolicy,
+}
+
+impl Default for BlockStorageSettings {
+    fn default() -> Self {
+        Self {
+            scrollback_bytes: DEFAULT_TERM_MAX_FILE_SIZE,
+            compress_cold: true,
+            cold_max_bytes: DEFAULT_COLD_MAX_BYTES,
+            retention: BlockRetentionPolicy::default(),
+        }
+    }
+}
+
+impl BlockStorageSettings {
+    /// 从块文件目录加载设置
+    ///
+    /// 文件不存在或无效时返回默认设置。
+    pub fn load(base_dir: &Path) -> Self {
+        let path = base_dir.join(STORAGE_SETTINGS_FILE);
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        let settings = serde_json::from_str::<Self>(&text)
+            .map_err(|e| TerminalError::BlockFileError(format!("解析存储设置失败: {}", e)))
+            .and_then(|settings| settings.validate().map(|_| settings));
+        match settings {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!(
+                    "[BlockStorage] 存储设置无效，使用默认设置: {:?}, error={}",
+                    path,
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// 保存设置到块文件目录
+    pub fn save(&self, base_dir: &Path) -> Result<(), TerminalError> {
+        self.validate()?;
+        fs::create_dir_all(base_dir).map_err(|e| {
+            TerminalError::BlockFileError(format!("无法创建目录 {:?}: {}", base_dir, e))
+        })?;
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| TerminalError::BlockFileError(format!("序列化存储设置失败: {}", e)))?;
+        fs::write(base_dir.join(STORAGE_SETTINGS_FILE), text)
+            .map_err(|e| TerminalError::BlockFileError(format!("保存存储设置失败: {}", e)))
+    }
+
+    /// 校验设置
+    pub fn validate(&self) -> Result<(), TerminalError> {
+        validate_scrollback_bytes(self.scrollback_bytes)?;
+        if self.retention.max_age_days == Some(0) {
+            return Err(TerminalError::BlockFileError(
+                "保留天数必须大于 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 校验回滚缓冲大小
+pub fn validate_scrollback_bytes(bytes: usize) -> Result<usize, TerminalError> {
+    if !(MIN_SCROLLBACK_BYTES..=MAX_SCROLLBACK_BYTES).contains(&bytes) {
+        return Err(TerminalError::BlockFileError(format!(
+            "回滚缓冲大小超出范围 {}..={}: {}",
+            MIN_SCROLLBACK_BYTES, MAX_SCROLLBACK_BYTES, bytes
+        )));
+    }
+    Ok(bytes)
+}
+
+/// 单个会话的磁盘占用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockUsage {
+    /// 块 ID（与会话 ID 相同）
+    pub block_id: String,
+    /// 块文件大小（字节）
+    pub block_bytes: u64,
+    /// 时间索引大小（字节）
+    pub timing_bytes: u64,
+    /// 冷段总大小（压缩后，字节）
+    pub cold_bytes: u64,
+    /// 冷段数量
+    pub cold_segments: usize,
+    /// 总大小（字节）
+    pub total_bytes: u64,
+    /// 最后写入时间（Unix 时间戳，毫秒）
+    pub modified_at: i64,
+    /// 是否为运行中或可恢复的会话（不会被清理）
+    pub active: bool,
+}
+
+impl BlockUsage {
+    /// 创建空的占用记录
+    fn new(block_id: &str) -> Self {
+        Self {
+            block_id: block_id.to_string(),
+            block_bytes: 0,
+            timing_bytes: 0,
+            cold_bytes: 0,
+            cold_segments: 0,
+            total_bytes: 0,
+            modified_at: 0,
+            active: false,
+        }
+    }
+
+    /// 累加一个文件的大小和修改时间
+    fn add(&mut self, len: u64, modified: SystemTime) {
+        self.total_bytes += len;
+        let modified_at = modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        self.modified_at = self.modified_at.max(modified_at);
+    }
+}
+
+/// 清理结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockPruneReport {
+    /// 被清理的块 ID
+    pub removed: Vec<String>,
+    /// 释放的字节数
+    pub freed_bytes: u64,
+}
+
+/// 统计块文件目录中每个会话的磁盘占用
+///
+/// # 参数
+/// - `base_dir`: 块文件目录
+/// - `protected`: 运行中或可恢复的块 ID（结果中标记为 `active`）
+///
+/// # 返回
+/// 按最后写入时间从新到旧排序的占用记录
+pub fn block_usage(
+    base_dir: &Path,
+    protected: &HashSet<String>,
+) -> Result<Vec<BlockUsage>, TerminalError> {
+    if !base_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(base_dir)
+        .map_err(|e| TerminalError::BlockFileError(format!("读取块文件目录失败: {}", e)))?;
+
+    let mut usages: HashMap<String, BlockUsage> = HashMap::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some((block_id, kind)) = name.rsplit_once('.') else {
+            continue;
+        };
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        match kind {
+            "block" if meta.is_file() => {
+                let usage = usages
+                    .entry(block_id.to_string())
+                    .or_insert_with(|| BlockUsage::new(block_id));
+                usage.block_bytes = meta.len();
+                usage.add(meta.len(), modified);
+            }
+            "timing" if meta.is_file() => {
+                let usage = usages
+                    .entry(block_id.to_string())
+                    .or_insert_with(|| BlockUsage::new(block_id));
+                usage.timing_bytes = meta.len();
+                usage.add(meta.len(), modified);
+            }
+            "cold" if meta.is_dir() => {
+                let usage = usages
+                    .entry(block_id.to_string())
+                    .or_insert_with(|| BlockUsage::new(block_id));
+                for segment in fs::read_dir(entry.path())
+                    .map_err(|e| TerminalError::BlockFileError(format!("读取冷段目录失败: {}", e)))?
+                    .filter_map(|entry| entry.ok())
+                    .filter_map(|entry| entry.metadata().ok())
+                    .filter(|meta| meta.is_file())
+                {
+                    usage.cold_bytes += segment.len();
+                    usage.cold_segments += 1;
+                    usage.add(
+                        segment.len(),
+                        segment.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut result: Vec<BlockUsage> = usages
+        .into_values()
+        .map(|mut usage| {
+            usage.active = protected.contains(&usage.block_id);
+            usage
+        })
+        .collect();
+    result.sort_by_key(|usage| Reverse(usage.modified_at));
+    Ok(result)
+}
+
+/// 按清理策略清理已结束会话的块文件
+///
+/// 先清理最后写入时间超过保留天数的会话，总大小仍超过上限时从最久未写入的会话开始清理。
+/// 受保护的会话计入总大小，但不会被清理。
+///
+/// # 参数
+/// - `base_dir`: 块文件目录
+/// - `policy`: 清理策略
+/// - `protected`: 运行中或可恢复的块 ID
+/// - `now`: 当前时间
+pub fn prune_blocks(
+    base_dir: &Path,
+    policy: &BlockRetentionPolicy,
+    protected: &HashSet<String>,
+    now: SystemTime,
+) -> Result<BlockPruneReport, TerminalError> {
+    let mut usages = block_usage(base_dir, protected)?;
+    // 从最久未写入的会话开始
+    usages.reverse();
+
+    let cutoff = policy.max_age_days.and_then(|days| {
+        now.checked_sub(Duration::from_secs(u64::from(days) * 24 * 60 * 60))
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+    });
+    let mut total: u64 = usages.iter().map(|usage| usage.total_bytes).sum();
+    let mut report = BlockPruneReport::default();
+
+    for usage in usages.iter().filter(|usage| !usage.active) {
+        let expired = cutoff.is_some_and(|cutoff| usage.modified_at < cutoff);
+        let over_size = policy.max_total_bytes.is_some_and(|max| total > max);
+        if !expired && !over_size {
+            continue;
+        }
+        match remove_block(base_dir, &usage.block_id) {
+            Ok(()) => {
+                total -= usage.total_bytes;
+                report.freed_bytes += usage.total_bytes;
+                report.removed.push(usage.block_id.clone());
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "[BlockStorage] 清理块文件失败: {}, error={}",
+                    usage.block_id,
+                    e
+                );
+            }
+        }
+    }
+
+    if !report.removed.is_empty() {
+        tracing::info!(
+            "[BlockStorage] 已清理 {} 个会话的块文件，释放 {} 字节",
+            report.removed.len(),
+            report.freed_bytes
+        );
+    }
+    Ok(report)
+}
+
+/// 删除会话的块文件、时间索引和冷段
+pub fn remove_block(base_dir: &Path, block_id: &str) -> Result<(), TerminalError> {
+    for ext in ["block", "timing"] {
+        let path = base_dir.join(format!("{}.{}", block_id, ext));
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| {
+                TerminalError::BlockFileError(format!("删除文件失败 {:?}: {}", path, e))
+            })?;
+        }
+    }
+    let cold_dir = base_dir.join(format!("{}.cold", block_id));
+    if cold_dir.exists() {
+        fs::remove_dir_all(&cold_dir)
+            .map_err(|e| TerminalError::BlockFileError(format!("删除冷段目录失败: {}", e)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 写入文件并设置修改时间
+    fn write_file(path: &Path, len: usize, modified: SystemTime) {
+        fs::write(path, vec![b'x'; len]).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_settings_load_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            BlockStorageSettings::load(dir.path()),
+            BlockStorageSettings::default()
+        );
+
+        let settings = BlockStorageSettings {
+            scrollback_bytes: 1024 * 1024,
+            compress_cold: false,
+            ..Default::default()
+        };
+        settings.save(dir.path()).unwrap();
+        assert_eq!(BlockStorageSettings::load(dir.path()), settings);
+
+        let invalid = BlockStorageSettings {
+            scrollback_bytes: 1,
+            ..Default::default()
+        };
+        assert!(invalid.save(dir.path()).is_err());
+        fs::write(
+            dir.path().join(STORAGE_SETTINGS_FILE),
+            r#"{"scrollback_bytes": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            BlockStorageSettings::load(dir.path()),
+            BlockStorageSettings::default()
+        );
+    }
+
+    #[test]
+    fn test_usage_and_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        // old: 40 天前，mid: 2 天前，new: 现在（运行中）
+        write_file(&base.join("old.block"), 100, now - day * 40);
+        write_file(&base.join("old.timing"), 10, now - day * 40);
+        write_file(&base.join("mid.block"), 200, now - day * 2);
+        fs::create_dir(base.join("mid.cold")).unwrap();
+        write_file(&base.join("mid.cold/0-400.zst"), 50, now - day * 3);
+        write_file(&base.join("new.block"), 300, now);
+        write_file(&base.join("tmux.conf"), 10, now);
+
+        let protected: HashSet<String> = ["new".to_string()].into_iter().collect();
+        let usage = block_usage(base, &protected).unwrap();
+        let ids: Vec<&str> = usage.iter().map(|u| u.block_id.as_str()).collect();
+        assert_eq!(ids, vec!["new", "mid", "old"]);
+        assert!(usage[0].active);
+        assert_eq!(
+            (
+                usage[1].cold_bytes,
+                usage[1].cold_segments,
+                usage[1].total_bytes
+            ),
+            (50, 1, 250)
+        );
+
+        // 按时间清理
+        let policy = BlockRetentionPolicy {
+            max_age_days: Some(30),
+            max_total_bytes: None,
+        };
+        let report = prune_blocks(base, &policy, &protected, now).unwrap();
+        assert_eq!(report.removed, vec!["old".to_string()]);
+        assert_eq!(report.freed_bytes, 110);
+        assert!(!base.join("old.timing").exists());
+
+        // 按总大小清理，受保护的会话不被清理
+        let policy = BlockRetentionPolicy {
+            max_age_days: None,
+            max_total_bytes: Some(100),
+        };
+        let report = prune_blocks(base, &policy, &protected, now).unwrap();
+        assert_eq!(report.removed, vec!["mid".to_string()]);
+        assert!(!base.join("mid.cold").exists());
+        assert!(base.join("new.block").exists());
+        assert!(base.join("tmux.conf").exists());
+    }
+}
@@ -0,0 +1,127 @@
+//! 块文件时间索引
+//!
+//! 块文件只保存原始字节，回放需要知道每段输出写入的时间。时间索引保存在块文件旁的
+//! `{block_id}.timing` 中，每次写入追加一行 `<Unix 时间戳毫秒> <字节数>`。
+//!
+//! ## 设计说明
+//! - 记录的字节数之和与块文件大小一致；块文件循环覆盖时同步丢弃最旧的记录
+//! - 旧版块文件没有时间索引，读取时缺失的部分按一条记录补齐
+
+use std::fmt::Write as _;
+
+/// 单次写入的时间记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingRecord {
+    /// 写入时间（Unix 时间戳，毫秒）
+    pub timestamp_ms: i64,
+    /// 写入的字节数
+    pub len: usize,
+}
+
+impl TimingRecord {
+    /// 创建时间记录
+    pub fn new(timestamp_ms: i64, len: usize) -> Self {
+        Self { timestamp_ms, len }
+    }
+}
+
+/// 解析时间索引文本，跳过无法解析的行
+pub fn parse_timing(text: &str) -> Vec<TimingRecord> {
+    text.lines()
+        .filter_map(|line| {
+            let (timestamp, len) = line.trim().split_once(' ')?;
+            let record = TimingRecord::new(timestamp.parse().ok()?, len.parse().ok()?);
+            (record.len > 0).then_some(record)
+        })
+        .collect()
+}
+
+/// 序列化时间记录
+pub fn format_timing(records: &[TimingRecord]) -> String {
+    let mut text = String::new();
+    for record in records {
+        let _ = writeln!(text, "{} {}", record.timestamp_ms, record.len);
+    }
+    text
+}
+
+/// 从记录开头丢弃 `bytes` 字节，与块文件循环覆盖保持一致
+pub fn trim_front(records: &mut Vec<TimingRecord>, mut bytes: usize) {
+    let mut drop_count = 0;
+    for record in records.iter_mut() {
+        if bytes == 0 {
+            break;
+        }
+        if record.len <= bytes {
+            bytes -= record.len;
+            drop_count += 1;
+        } else {
+            record.len -= bytes;
+            bytes = 0;
+        }
+    }
+    records.drain(..drop_count);
+}
+
+/// 使记录的字节数之和等于块文件大小
+///
+/// 多出的字节从开头丢弃；缺少的字节（旧版块文件或写入时间索引失败）
+/// 作为一条记录补在开头，时间取第一条记录的时间
+pub fn align_to_size(records: &mut Vec<TimingRecord>, size: usize, fallback_ms: i64) {
+    let total: usize = records.iter().map(|r| r.len).sum();
+    if total > size {
+        trim_front(records, total - size);
+    } else if total < size {
+        let timestamp_ms = records.first().map_or(fallback_ms, |r| r.timestamp_ms);
+        records.insert(0, TimingRecord::new(timestamp_ms, size - total));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_roundtrip() {
+        let records = vec![TimingRecord::new(1000, 5), TimingRecord::new(1250, 12)];
+        assert_eq!(parse_timing(&format_timing(&records)), records);
+        // 无效行和零长度记录被跳过
+        assert_eq!(
+            parse_timing("1000 5\ngarbage\n1100 0\n1200 3\n"),
+            vec![TimingRecord::new(1000, 5), TimingRecord::new(1200, 3)]
+        );
+    }
+
+    #[test]
+    fn test_trim_front() {
+        let mut records = vec![
+            TimingRecord::new(1, 4),
+            TimingRecord::new(2, 4),
+            TimingRecord::new(3, 4),
+        ];
+        trim_front(&mut records, 6);
+        assert_eq!(
+            records,
+            vec![TimingRecord::new(2, 2), TimingRecord::new(3, 4)]
+        );
+        trim_front(&mut records, 2);
+        assert_eq!(records, vec![TimingRecord::new(3, 4)]);
+    }
+
+    #[test]
+    fn test_align_to_size() {
+        let mut records = vec![TimingRecord::new(10, 4), TimingRecord::new(20, 4)];
+        align_to_size(&mut records, 6, 0);
+        assert_eq!(
+            records,
+            vec![TimingRecord::new(10, 2), TimingRecord::new(20, 4)]
+        );
+
+        align_to_size(&mut records, 10, 0);
+        assert_eq!(records[0], TimingRecord::new(10, 4));
+
+        let mut empty = Vec::new();
+        align_to_size(&mut empty, 8, 99);
+        assert_eq!(empty, vec![TimingRecord::new(99, 8)]);
+    }
+}
@@ -0,0 +1,339 @@
+//! 终端剪贴板策略存储
+//!
+//! 使用 SQLite 按主机保存 OSC 52 剪贴板访问策略，控制远程程序（tmux、vim 等）
+//! 写入和读取本机剪贴板。
+//!
+//! ## 功能
+//! - 剪贴板策略的新增、更新、删除和查询
+//! - 没有保存策略的主机使用默认策略
+//!
+//! ## 默认策略
+//! - 本地会话：允许写入，读取需确认
+//! - 远程连接：写入需确认，禁止读取（读取会把本机剪贴板内容发送到远程）
+//! - 内容大小上限 1 MiB
+
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::database::DbConnection;
+use crate::terminal::error::TerminalError;
+use crate::terminal::persistence::command_history::LOCAL_HOST;
+
+/// 默认剪贴板内容大小上限（字节）
+pub const DEFAULT_CLIPBOARD_MAX_BYTES: u32 = 1024 * 1024;
+
+/// 剪贴板访问权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardAccess {
+    /// 允许
+    Allow,
+    /// 每次询问用户
+    Prompt,
+    /// 拒绝
+    Deny,
+}
+
+impl ClipboardAccess {
+    /// 转换为数据库存储值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Prompt => "prompt",
+            Self::Deny => "deny",
+        }
+    }
+
+    /// 从数据库存储值解析（无法识别时按拒绝处理）
+    pub fn from_str_lossy(value: &str) -> Self {
+        match value {
+            "allow" => Self::Allow,
+            "prompt" => Self::Prompt,
+            _ => Self::Deny,
+        }
+    }
+}
+
+/// 剪贴板策略
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClipboardPolicy {
+    /// 主机（连接名称，本地为 `local`）
+    pub host: String,
+    /// 写入本机剪贴板的权限
+    pub write: ClipboardAccess,
+    /// 读取本机剪贴板的权限
+    pub read: ClipboardAccess,
+    /// 内容大小上限（字节）
+    pub max_bytes: u32,
+    /// 更新时间（Unix 时间戳，毫秒，默认策略为 0）
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+impl ClipboardPolicy {
+    /// 主机的默认策略
+    pub fn default_for(host: &str) -> Self {
+        let local = host == LOCAL_HOST;
+        Self {
+            host: host.to_string(),
+            write: if local {
+                ClipboardAccess::Allow
+            } else {
+                ClipboardAccess::Prompt
+            },
+            read: if local {
+                ClipboardAccess::Prompt
+            } else {
+                ClipboardAccess::Deny
+            },
+            max_bytes: DEFAULT_CLIPBOARD_MAX_BYTES,
+            updated_at: 0,
+        }
+    }
+}
+
+/// 剪贴板策略存储服务
+pub struct ClipboardPolicyStore {
+    db: DbConnection,
+}
+
+impl ClipboardPolicyStore {
+    /// 创建剪贴板策略存储服务
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+
+    /// 初始化数据库表
+    ///
+    /// 创建 terminal_clipboard_policies 表（如果不存在）。
+    pub fn init_tables(&self) -> Result<(), TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS terminal_clipboard_policies (
+                host TEXT PRIMARY KEY,
+                write_access TEXT NOT NULL,
+                read_access TEXT NOT NULL,
+                max_bytes INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("创建表失败: {}", e)))?;
+
+        tracing::debug!("[ClipboardPolicy] 数据库表初始化完成");
+        Ok(())
+    }
+
+    /// 保存剪贴板策略（同主机的策略会被覆盖）
+    ///
+    /// # 返回
+    /// 保存后的策略（包含更新时间）
+    pub fn save(&self, policy: &ClipboardPolicy) -> Result<ClipboardPolicy, TerminalError> {
+        let host = policy.host.trim();
+        if host.is_empty() {
+            return Err(TerminalError::Internal(
+                "剪贴板策略主机不能为空".to_string(),
+            ));
+        }
+
+        let saved = ClipboardPolicy {
+            host: host.to_string(),
+            updated_at: Utc::now().timestamp_millis(),
+            ..policy.clone()
+        };
+
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO terminal_clipboard_policies
+             (host, write_access, read_access, max_bytes, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                saved.host,
+                saved.write.as_str(),
+                saved.read.as_str(),
+                saved.max_bytes,
+                saved.updated_at,
+            ],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("保存剪贴板策略失败: {}", e)))?;
+
+        tracing::info!(
+            "[ClipboardPolicy] 保存剪贴板策略: host={}, write={:?}, read={:?}, max_bytes={}",
+            saved.host,
+            saved.write,
+            saved.read,
+            saved.max_bytes
+        );
+        Ok(saved)
+    }
+
+    /// 获取主机保存的剪贴板策略
+    pub fn get(&self, host: &str) -> Result<Option<ClipboardPolicy>, TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.query_row(
+            "SELECT host, write_access, read_access, max_bytes, updated_at
+             FROM terminal_clipboard_policies WHERE host = ?1",
+            params![host],
+            Self::row_to_policy,
+        )
+        .optional()
+        .map_err(|e| TerminalError::DatabaseError(format!("查询剪贴板策略失败: {}", e)))
+    }
+
+    /// 获取主机生效的剪贴板策略（没有保存策略时使用默认策略）
+    pub fn resolve(&self, host: &str) -> ClipboardPolicy {
+        match self.get(host) {
+            Ok(Some(policy)) => policy,
+            Ok(None) => ClipboardPolicy::default_for(host),
+            Err(e) => {
+                tracing::warn!(
+                    "[ClipboardPolicy] 读取剪贴板策略失败，使用默认策略: host={}, error={}",
+                    host,
+                    e
+                );
+                ClipboardPolicy::default_for(host)
+            }
+        }
+    }
+
+    /// 获取所有保存的剪贴板策略（按主机排序）
+    pub fn list(&self) -> Result<Vec<ClipboardPolicy>, TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT host, write_access, read_access, max_bytes, updated_at
+                 FROM terminal_clipboard_policies ORDER BY host",
+            )
+            .map_err(|e| TerminalError::DatabaseError(format!("准备查询失败: {}", e)))?;
+
+        let policies = stmt
+            .query_map([], Self::row_to_policy)
+            .map_err(|e| TerminalError::DatabaseError(format!("查询剪贴板策略失败: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TerminalError::DatabaseError(format!("读取剪贴板策略失败: {}", e)))?;
+
+        Ok(policies)
+    }
+
+    /// 删除主机的剪贴板策略（之后使用默认策略）
+    ///
+    /// # 返回
+    /// 是否删除了策略
+    pub fn delete(&self, host: &str) -> Result<bool, TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        let deleted = conn
+            .execute(
+                "DELETE FROM terminal_clipboard_policies WHERE host = ?1",
+                params![host],
+            )
+            .map_err(|e| TerminalError::DatabaseError(format!("删除剪贴板策略失败: {}", e)))?;
+
+        Ok(deleted > 0)
+    }
+
+    /// 将数据库行转换为剪贴板策略
+    fn row_to_policy(row: &Row<'_>) -> rusqlite::Result<ClipboardPolicy> {
+        let write: String = row.get(1)?;
+        let read: String = row.get(2)?;
+        Ok(ClipboardPolicy {
+            host: row.get(0)?,
+            write: ClipboardAccess::from_str_lossy(&write),
+            read: ClipboardAccess::from_str_lossy(&read),
+            max_bytes: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DbPool;
+    use rusqlite::Connection;
+    use std::sync::Arc;
+
+    fn store() -> ClipboardPolicyStore {
+        let db = Arc::new(DbPool::from_connection(
+            Connection::open_in_memory().unwrap(),
+        ));
+        let store = ClipboardPolicyStore::new(db);
+        store.init_tables().unwrap();
+        store
+    }
+
+    #[test]
+    fn test_default_policy() {
+        let local = ClipboardPolicy::default_for(LOCAL_HOST);
+        assert_eq!(local.write, ClipboardAccess::Allow);
+        assert_eq!(local.read, ClipboardAccess::Prompt);
+
+        let remote = ClipboardPolicy::default_for("root@prod");
+        assert_eq!(remote.write, ClipboardAccess::Prompt);
+        assert_eq!(remote.read, ClipboardAccess::Deny);
+        assert_eq!(remote.max_bytes, DEFAULT_CLIPBOARD_MAX_BYTES);
+    }
+
+    #[test]
+    fn test_save_and_resolve() {
+        let store = store();
+        assert_eq!(
+            store.resolve("root@prod"),
+            ClipboardPolicy::default_for("root@prod")
+        );
+
+        let saved = store
+            .save(&ClipboardPolicy {
+                host: " root@prod ".to_string(),
+                write: ClipboardAccess::Allow,
+                read: ClipboardAccess::Prompt,
+                max_bytes: 4096,
+                updated_at: 0,
+            })
+            .unwrap();
+        assert_eq!(saved.host, "root@prod");
+        assert!(saved.updated_at > 0);
+        assert_eq!(store.resolve("root@prod"), saved);
+
+        let updated = store
+            .save(&ClipboardPolicy {
+                write: ClipboardAccess::Deny,
+                ..saved
+            })
+            .unwrap();
+        assert_eq!(store.list().unwrap(), vec![updated]);
+    }
+
+    #[test]
+    fn test_delete() {
+        let store = store();
+        store
+            .save(&ClipboardPolicy::default_for(LOCAL_HOST))
+            .unwrap();
+        assert!(store.save(&ClipboardPolicy::default_for(" ")).is_err());
+
+        assert!(store.delete(LOCAL_HOST).unwrap());
+        assert!(!store.delete(LOCAL_HOST).unwrap());
+        assert!(store.list().unwrap().is_empty());
+    }
+}
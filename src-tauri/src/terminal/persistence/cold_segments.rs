@@ -0,0 +1,261 @@
+//! 块文件冷段存储
+//!
+//! 块文件循环覆盖时，把被覆盖的输出用 zstd 压缩后保存为冷段，延长可读取的输出历史
+//! （如较早命令的输出）。
+//!
+//! ## 功能
+//! - 追加冷段（zstd 压缩）
+//! - 按会话输出位置读取冷段中的数据
+//! - 冷段总大小（压缩后）上限，超出时删除最旧的段
+//!
+//! ## 设计说明
+//! 冷段保存在 `{block_id}.cold/` 目录，每段一个文件，文件名为 `{起始位置}-{原始长度}.zst`，
+//! 起始位置即该段在会话输出流中的位置（见 `BlockFile::written`），读取时无需解压即可定位。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use parking_lot::Mutex;
+
+use crate::terminal::error::TerminalError;
+
+/// 默认每个会话冷段总大小上限（压缩后，4 MiB）
+pub const DEFAULT_COLD_MAX_BYTES: u64 = 4 * 1024 * 1024;
+
+/// 冷段文件扩展名
+const COLD_EXTENSION: &str = "zst";
+
+/// zstd 压缩级别
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// 单个冷段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ColdSegment {
+    /// 在会话输出流中的起始位置
+    start: u64,
+    /// 原始长度
+    len: u64,
+    /// 压缩后的文件大小
+    size: u64,
+}
+
+impl ColdSegment {
+    /// 结束位置（不含）
+    fn end(&self) -> u64 {
+        self.start + self.len
+    }
+
+    /// 冷段文件名
+    fn file_name(&self) -> String {
+        format!("{:020}-{}.{}", self.start, self.len, COLD_EXTENSION)
+    }
+
+    /// 从文件名解析起始位置和原始长度
+    fn parse(name: &str, size: u64) -> Option<Self> {
+        let stem = name.strip_suffix(COLD_EXTENSION)?.strip_suffix('.')?;
+        let (start, len) = stem.split_once('-')?;
+        Some(Self {
+            start: start.parse().ok()?,
+            len: len.parse().ok()?,
+            size,
+        })
+    }
+}
+
+/// 块文件冷段存储
+pub struct ColdSegments {
+    /// 冷段目录（`{block_id}.cold`）
+    dir: PathBuf,
+    /// 冷段总大小上限（压缩后）
+    max_bytes: u64,
+    /// 冷段列表（按起始位置排序），同时作为读写锁
+    segments: Mutex<Vec<ColdSegment>>,
+}
+
+impl ColdSegments {
+    /// 打开块文件的冷段存储
+    ///
+    /// 目录在第一次写入冷段时创建，已有的冷段按文件名恢复。
+    ///
+    /// # 参数
+    /// - `base_dir`: 块文件基础目录
+    /// - `block_id`: 块 ID
+    /// - `max_bytes`: 冷段总大小上限（压缩后）
+    pub fn open(base_dir: &Path, block_id: &str, max_bytes: u64) -> Result<Self, TerminalError> {
+        let dir = Self::dir_for(base_dir, block_id);
+        let mut segments = Vec::new();
+        if dir.is_dir() {
+            let entries = fs::read_dir(&dir)
+                .map_err(|e| TerminalError::BlockFileError(format!("读取冷段目录失败: {}", e)))?;
+            segments = entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let size = entry.metadata().ok()?.len();
+                    ColdSegment::parse(entry.file_name().to_str()?, size)
+                })
+                .collect();
+            segments.sort_by_key(|segment| segment.start);
+        }
+
+        let cold = Self {
+            dir,
+            max_bytes,
+            segments: Mutex::new(segments),
+        };
+        cold.evict(&mut cold.segments.lock());
+        Ok(cold)
+    }
+
+    /// 块文件对应的冷段目录
+    pub fn dir_for(base_dir: &Path, block_id: &str) -> PathBuf {
+        base_dir.join(format!("{}.cold", block_id))
+    }
+
+    /// 冷段总大小（压缩后）
+    pub fn size(&self) -> u64 {
+        self.segments
+            .lock()
+            .iter()
+            .map(|segment| segment.size)
+            .sum()
+    }
+
+    /// 冷段数量
+    pub fn count(&self) -> usize {
+        self.segments.lock().len()
+    }
+
+    /// 追加冷段
+    ///
+    /// # 参数
+    /// - `start`: 数据在会话输出流中的起始位置
+    /// - `data`: 被覆盖的输出
+    pub fn append(&self, start: u64, data: &[u8]) -> Result<(), TerminalError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let compressed = zstd::encode_all(data, COMPRESSION_LEVEL)
+            .map_err(|e| TerminalError::BlockFileError(format!("压缩冷段失败: {}", e)))?;
+
+        let mut segments = self.segments.lock();
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| TerminalError::BlockFileError(format!("创建冷段目录失败: {}", e)))?;
+        let segment = ColdSegment {
+            start,
+            len: data.len() as u64,
+            size: compressed.len() as u64,
+        };
+        fs::write(self.dir.join(segment.file_name()), &compressed)
+            .map_err(|e| TerminalError::BlockFileError(format!("写入冷段失败: {}", e)))?;
+        segments.push(segment);
+        self.evict(&mut segments);
+        Ok(())
+    }
+
+    /// 按输出位置读取冷段中的数据
+    ///
+    /// 只返回连续的数据：范围内的冷段之间有缺口（如冷段已被删除）时，丢弃缺口之前的部分。
+    ///
+    /// # 参数
+    /// - `start`: 起始位置（含）
+    /// - `end`: 结束位置（不含）
+    ///
+    /// # 返回
+    /// 数据及其起始位置，没有数据时返回空数据
+    pub fn read_range(&self, start: u64, end: u64) -> Result<(Vec<u8>, u64), TerminalError> {
+        let segments = self.segments.lock();
+        let mut data = Vec::new();
+        let mut from = start;
+        let mut next = None;
+
+        for segment in segments
+            .iter()
+            .filter(|segment| segment.end() > start && segment.start < end)
+        {
+            if next != Some(segment.start) {
+                data.clear();
+                from = segment.start.max(start);
+            }
+            let bytes = fs::read(self.dir.join(segment.file_name()))
+                .map_err(|e| TerminalError::BlockFileError(format!("读取冷段失败: {}", e)))?;
+            let decoded = zstd::decode_all(bytes.as_slice())
+                .map_err(|e| TerminalError::BlockFileError(format!("解压冷段失败: {}", e)))?;
+
+            let skip = start.saturating_sub(segment.start) as usize;
+            let take = (end.min(segment.end()) - segment.start) as usize;
+            data.extend_from_slice(&decoded[skip.min(decoded.len())..take.min(decoded.len())]);
+            next = Some(segment.end());
+        }
+        Ok((data, from))
+    }
+
+    /// 删除所有冷段
+    pub fn clear(&self) -> Result<(), TerminalError> {
+        let mut segments = self.segments.lock();
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)
+                .map_err(|e| TerminalError::BlockFileError(format!("删除冷段目录失败: {}", e)))?;
+        }
+        segments.clear();
+        Ok(())
+    }
+
+    /// 删除最旧的冷段，直到总大小不超过上限
+    fn evict(&self, segments: &mut Vec<ColdSegment>) {
+        let mut total: u64 = segments.iter().map(|segment| segment.size).sum();
+        while total > self.max_bytes && !segments.is_empty() {
+            let segment = segments.remove(0);
+            if let Err(e) = fs::remove_file(self.dir.join(segment.file_name())) {
+                tracing::warn!("[ColdSegments] 删除冷段失败: {:?}, error={}", self.dir, e);
+            }
+            total -= segment.size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_and_read_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let cold = ColdSegments::open(dir.path(), "cold", DEFAULT_COLD_MAX_BYTES).unwrap();
+        assert!(!ColdSegments::dir_for(dir.path(), "cold").exists());
+
+        cold.append(0, b"hello ").unwrap();
+        cold.append(6, b"cold world").unwrap();
+        assert_eq!(cold.count(), 2);
+        assert_eq!(cold.read_range(3, 10).unwrap(), (b"lo cold".to_vec(), 3));
+        assert_eq!(cold.read_range(20, 30).unwrap(), (Vec::new(), 20));
+
+        // 重新打开时按文件名恢复
+        let reopened = ColdSegments::open(dir.path(), "cold", DEFAULT_COLD_MAX_BYTES).unwrap();
+        assert_eq!(reopened.size(), cold.size());
+        assert_eq!(reopened.read_range(0, 16).unwrap().0, b"hello cold world");
+
+        reopened.clear().unwrap();
+        assert_eq!(reopened.count(), 0);
+        assert!(!ColdSegments::dir_for(dir.path(), "cold").exists());
+    }
+
+    #[test]
+    fn test_evict_oldest_and_gaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let probe = ColdSegments::open(dir.path(), "probe", u64::MAX).unwrap();
+        probe.append(0, b"aaaa").unwrap();
+        let segment_size = probe.size();
+
+        // 只能保留两段
+        let cold = ColdSegments::open(dir.path(), "evict", segment_size * 2).unwrap();
+        cold.append(0, b"aaaa").unwrap();
+        cold.append(4, b"bbbb").unwrap();
+        cold.append(8, b"cccc").unwrap();
+        assert_eq!(cold.count(), 2);
+        assert_eq!(cold.read_range(0, 12).unwrap(), (b"bbbbcccc".to_vec(), 4));
+
+        // 缺口之前的数据被丢弃
+        cold.append(20, b"dddd").unwrap();
+        assert_eq!(cold.read_range(4, 24).unwrap(), (b"dddd".to_vec(), 20));
+    }
+}
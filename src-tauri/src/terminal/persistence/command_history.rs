@@ -0,0 +1,460 @@
+//! 命令历史存储
+//!
+//! 使用 SQLite 保存 Shell 集成上报的已执行命令，供全局命令历史面板查询。
+//!
+//! ## 功能
+//! - 按主机去重：同一主机上的相同命令只保留一条，记录执行次数和最近一次的目录、退出码、耗时
+//! - 前缀搜索、按主机过滤
+//! - 按最近执行时间或执行次数排序
+//!
+//! ## 设计说明
+//! 主机取自会话的连接名称（SSH/WSL），本地会话为 `local`。
+//! 以空格开头的命令不记录，与 Shell 的 `ignorespace` 约定一致。
+
+use chrono::Utc;
+use rusqlite::{params, params_from_iter, types::Value};
+use serde::{Deserialize, Serialize};
+
+use crate::database::DbConnection;
+use crate::terminal::error::TerminalError;
+
+/// 本地会话的主机名
+pub const LOCAL_HOST: &str = "local";
+/// 记录的命令最大长度（字节）
+const MAX_COMMAND_LEN: usize = 8 * 1024;
+/// 默认查询条数
+const DEFAULT_QUERY_LIMIT: usize = 50;
+/// 最大查询条数
+const MAX_QUERY_LIMIT: usize = 1000;
+
+/// 一次命令执行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandExecution {
+    /// 命令文本
+    pub command: String,
+    /// 主机（连接名称，本地为 `local`）
+    pub host: String,
+    /// 执行目录
+    pub cwd: Option<String>,
+    /// 退出码
+    pub exit_code: Option<i32>,
+    /// 耗时（毫秒）
+    pub duration_ms: Option<i64>,
+    /// 执行结束时间（Unix 时间戳，毫秒）
+    pub finished_at: i64,
+}
+
+/// 命令历史记录（按主机去重）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandHistoryEntry {
+    /// 记录 ID
+    pub id: i64,
+    /// 命令文本
+    pub command: String,
+    /// 主机
+    pub host: String,
+    /// 最近一次执行的目录
+    pub cwd: Option<String>,
+    /// 最近一次执行的退出码
+    pub exit_code: Option<i32>,
+    /// 最近一次执行的耗时（毫秒）
+    pub duration_ms: Option<i64>,
+    /// 执行次数
+    pub run_count: i64,
+    /// 首次执行时间（Unix 时间戳，毫秒）
+    pub first_run_at: i64,
+    /// 最近执行时间（Unix 时间戳，毫秒）
+    pub last_run_at: i64,
+}
+
+/// 排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandHistorySort {
+    /// 最近执行的在前
+    #[default]
+    Recent,
+    /// 执行次数多的在前
+    Frequency,
+}
+
+/// 命令历史查询条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandHistoryQuery {
+    /// 命令前缀
+    pub prefix: Option<String>,
+    /// 主机
+    pub host: Option<String>,
+    /// 排序方式
+    pub sort: CommandHistorySort,
+    /// 返回条数（默认 50，最大 1000）
+    pub limit: Option<usize>,
+}
+
+/// 命令历史存储服务
+pub struct CommandHistoryStore {
+    db: DbConnection,
+}
+
+impl CommandHistoryStore {
+    /// 创建命令历史存储服务
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+
+    /// 初始化数据库表
+    ///
+    /// 创建 terminal_command_history 表（如果不存在）。
+    pub fn init_tables(&self) -> Result<(), TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS terminal_command_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                command TEXT NOT NULL,
+                host TEXT NOT NULL,
+                cwd TEXT,
+                exit_code INTEGER,
+                duration_ms INTEGER,
+                run_count INTEGER NOT NULL DEFAULT 1,
+                first_run_at INTEGER NOT NULL,
+                last_run_at INTEGER NOT NULL,
+                UNIQUE(host, command)
+            )",
+            [],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("创建表失败: {}", e)))?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_terminal_command_history_last_run ON terminal_command_history(last_run_at)",
+            [],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("创建索引失败: {}", e)))?;
+
+        tracing::debug!("[CommandHistory] 数据库表初始化完成");
+        Ok(())
+    }
+
+    /// 记录一次命令执行
+    ///
+    /// 同一主机上已有相同命令时累加执行次数并更新最近一次的执行信息。
+    ///
+    /// # 返回
+    /// 是否写入了记录（空命令、以空格开头或过长的命令会被忽略）
+    pub fn record(&self, execution: &CommandExecution) -> Result<bool, TerminalError> {
+        if execution.command.starts_with(' ') {
+            return Ok(false);
+        }
+        let command = execution.command.trim_end();
+        if command.is_empty() || command.len() > MAX_COMMAND_LEN {
+            return Ok(false);
+        }
+
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.execute(
+            "INSERT INTO terminal_command_history
+             (command, host, cwd, exit_code, duration_ms, run_count, first_run_at, last_run_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6, ?6)
+             ON CONFLICT(host, command) DO UPDATE SET
+                cwd = excluded.cwd,
+                exit_code = excluded.exit_code,
+                duration_ms = excluded.duration_ms,
+                run_count = run_count + 1,
+                last_run_at = excluded.last_run_at",
+            params![
+                command,
+                execution.host,
+                execution.cwd,
+                execution.exit_code,
+                execution.duration_ms,
+                execution.finished_at,
+            ],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("记录命令失败: {}", e)))?;
+
+        Ok(true)
+    }
+
+    /// 查询命令历史
+    pub fn query(
+        &self,
+        query: &CommandHistoryQuery,
+    ) -> Result<Vec<CommandHistoryEntry>, TerminalError> {
+        let mut sql = String::from(
+            "SELECT id, command, host, cwd, exit_code, duration_ms, run_count, first_run_at, last_run_at
+             FROM terminal_command_history WHERE 1 = 1",
+        );
+        let mut values: Vec<Value> = Vec::new();
+
+        if let Some(prefix) = query.prefix.as_deref().filter(|p| !p.is_empty()) {
+            sql.push_str(" AND command LIKE ? ESCAPE '\\'");
+            values.push(Value::Text(format!("{}%", escape_like(prefix))));
+        }
+        if let Some(host) = query.host.as_deref() {
+            sql.push_str(" AND host = ?");
+            values.push(Value::Text(host.to_string()));
+        }
+        sql.push_str(match query.sort {
+            CommandHistorySort::Recent => " ORDER BY last_run_at DESC, id DESC",
+            CommandHistorySort::Frequency => " ORDER BY run_count DESC, last_run_at DESC, id DESC",
+        });
+        sql.push_str(" LIMIT ?");
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .min(MAX_QUERY_LIMIT);
+        values.push(Value::Integer(limit as i64));
+
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| TerminalError::DatabaseError(format!("准备查询失败: {}", e)))?;
+
+        let entries = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok(CommandHistoryEntry {
+                    id: row.get(0)?,
+                    command: row.get(1)?,
+                    host: row.get(2)?,
+                    cwd: row.get(3)?,
+                    exit_code: row.get(4)?,
+                    duration_ms: row.get(5)?,
+                    run_count: row.get(6)?,
+                    first_run_at: row.get(7)?,
+                    last_run_at: row.get(8)?,
+                })
+            })
+            .map_err(|e| TerminalError::DatabaseError(format!("查询命令历史失败: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TerminalError::DatabaseError(format!("读取命令历史失败: {}", e)))?;
+
+        Ok(entries)
+    }
+
+    /// 获取有命令历史的主机列表
+    pub fn hosts(&self) -> Result<Vec<String>, TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        let mut stmt = conn
+            .prepare("SELECT DISTINCT host FROM terminal_command_history ORDER BY host")
+            .map_err(|e| TerminalError::DatabaseError(format!("准备查询失败: {}", e)))?;
+
+        let hosts = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| TerminalError::DatabaseError(format!("查询主机失败: {}", e)))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| TerminalError::DatabaseError(format!("读取主机失败: {}", e)))?;
+
+        Ok(hosts)
+    }
+
+    /// 删除单条命令历史
+    pub fn delete(&self, id: i64) -> Result<(), TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.execute(
+            "DELETE FROM terminal_command_history WHERE id = ?1",
+            params![id],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("删除命令历史失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 清空命令历史，指定主机时只清空该主机的记录
+    ///
+    /// # 返回
+    /// 删除的记录数
+    pub fn clear(&self, host: Option<&str>) -> Result<usize, TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        let deleted = match host {
+            Some(host) => conn.execute(
+                "DELETE FROM terminal_command_history WHERE host = ?1",
+                params![host],
+            ),
+            None => conn.execute("DELETE FROM terminal_command_history", []),
+        }
+        .map_err(|e| TerminalError::DatabaseError(format!("清空命令历史失败: {}", e)))?;
+
+        tracing::info!(
+            "[CommandHistory] 清空命令历史: host={:?}, 删除 {} 条",
+            host,
+            deleted
+        );
+        Ok(deleted)
+    }
+}
+
+impl CommandExecution {
+    /// 创建以当前时间结束的命令执行
+    pub fn new(command: String, host: String) -> Self {
+        Self {
+            command,
+            host,
+            cwd: None,
+            exit_code: None,
+            duration_ms: None,
+            finished_at: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// 转义 LIKE 模式中的通配符
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DbPool;
+    use rusqlite::Connection;
+    use std::sync::Arc;
+
+    fn store() -> CommandHistoryStore {
+        let db = Arc::new(DbPool::from_connection(
+            Connection::open_in_memory().unwrap(),
+        ));
+        let store = CommandHistoryStore::new(db);
+        store.init_tables().unwrap();
+        store
+    }
+
+    fn execution(command: &str, host: &str, finished_at: i64) -> CommandExecution {
+        CommandExecution {
+            cwd: Some("/home/user".to_string()),
+            exit_code: Some(0),
+            finished_at,
+            ..CommandExecution::new(command.to_string(), host.to_string())
+        }
+    }
+
+    #[test]
+    fn test_record_dedups_per_host() {
+        let store = store();
+        assert!(store.record(&execution("git status", "local", 1)).unwrap());
+        assert!(store
+            .record(&CommandExecution {
+                exit_code: Some(1),
+                ..execution("git status", "local", 2)
+            })
+            .unwrap());
+        assert!(store
+            .record(&execution("git status", "ssh-dev", 3))
+            .unwrap());
+
+        let entries = store
+            .query(&CommandHistoryQuery {
+                host: Some("local".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].run_count, 2);
+        assert_eq!(entries[0].exit_code, Some(1));
+        assert_eq!((entries[0].first_run_at, entries[0].last_run_at), (1, 2));
+
+        assert_eq!(store.hosts().unwrap(), vec!["local", "ssh-dev"]);
+    }
+
+    #[test]
+    fn test_record_ignores_blank_and_space_prefixed() {
+        let store = store();
+        assert!(!store.record(&execution("   ", "local", 1)).unwrap());
+        assert!(!store.record(&execution(" secret", "local", 1)).unwrap());
+        assert!(store
+            .query(&CommandHistoryQuery::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_query_prefix_and_sort() {
+        let store = store();
+        store.record(&execution("cargo build", "local", 1)).unwrap();
+        store.record(&execution("cargo test", "local", 2)).unwrap();
+        store.record(&execution("cargo build", "local", 3)).unwrap();
+        store.record(&execution("100%_done", "local", 4)).unwrap();
+        store.record(&execution("ls", "local", 5)).unwrap();
+
+        let commands = |query: CommandHistoryQuery| -> Vec<String> {
+            store
+                .query(&query)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.command)
+                .collect()
+        };
+
+        assert_eq!(
+            commands(CommandHistoryQuery {
+                prefix: Some("cargo".to_string()),
+                ..Default::default()
+            }),
+            vec!["cargo build", "cargo test"]
+        );
+        assert_eq!(
+            commands(CommandHistoryQuery {
+                sort: CommandHistorySort::Frequency,
+                limit: Some(2),
+                ..Default::default()
+            }),
+            vec!["cargo build", "ls"]
+        );
+        // 通配符按字面匹配
+        assert_eq!(
+            commands(CommandHistoryQuery {
+                prefix: Some("100%_".to_string()),
+                ..Default::default()
+            }),
+            vec!["100%_done"]
+        );
+        assert!(commands(CommandHistoryQuery {
+            prefix: Some("1_".to_string()),
+            ..Default::default()
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn test_delete_and_clear() {
+        let store = store();
+        store.record(&execution("ls", "local", 1)).unwrap();
+        store.record(&execution("pwd", "local", 2)).unwrap();
+        store.record(&execution("ls", "ssh-dev", 3)).unwrap();
+
+        let latest = store.query(&CommandHistoryQuery::default()).unwrap();
+        store.delete(latest[0].id).unwrap();
+        assert_eq!(store.clear(Some("local")).unwrap(), 2);
+        assert!(store.hosts().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+//! 终端内联图片缓存
+//!
+//! 把终端输出中解码的内联图片（sixel、iTerm2 OSC 1337）保存到磁盘，
+//! 供前端重新渲染（如重新连接、回放）时按图片 ID 读取。
+//!
+//! ## 功能
+//! - 按图片 ID 保存和读取图片（文件扩展名记录图片类型）
+//! - 单张图片大小上限，超过上限的图片不缓存
+//! - 缓存总大小上限，超过时按最近访问时间淘汰最旧的图片
+//!
+//! ## 设计说明
+//! 图片 ID 由图片内容的 SHA-256 生成，相同图片只保存一份。
+//! 读取图片时更新文件修改时间，作为淘汰顺序的依据。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+
+use crate::terminal::error::TerminalError;
+
+/// 默认单张图片大小上限（字节）
+pub const DEFAULT_IMAGE_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// 默认缓存总大小上限（字节）
+pub const DEFAULT_IMAGE_CACHE_MAX_BYTES: u64 = 128 * 1024 * 1024;
+
+/// 支持的图片类型（MIME 类型，文件扩展名）
+const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+];
+
+/// 图片缓存大小限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageCacheLimits {
+    /// 单张图片大小上限（字节）
+    pub max_image_bytes: usize,
+    /// 缓存总大小上限（字节）
+    pub max_total_bytes: u64,
+}
+
+impl Default for ImageCacheLimits {
+    fn default() -> Self {
+        Self {
+            max_image_bytes: DEFAULT_IMAGE_MAX_BYTES,
+            max_total_bytes: DEFAULT_IMAGE_CACHE_MAX_BYTES,
+        }
+    }
+}
+
+/// 缓存的图片
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedImage {
+    /// MIME 类型
+    pub mime: String,
+    /// 图片数据
+    pub data: Vec<u8>,
+}
+
+/// 终端内联图片缓存
+pub struct ImageCache {
+    /// 缓存目录
+    dir: PathBuf,
+    /// 大小限制
+    limits: ImageCacheLimits,
+    /// 缓存总大小（字节），同时作为写入和淘汰的锁
+    total_bytes: Mutex<u64>,
+}
+
+impl ImageCache {
+    /// 创建图片缓存
+    ///
+    /// 目录不存在时自动创建，已有的缓存文件计入总大小。
+    ///
+    /// # 参数
+    /// - `dir`: 缓存目录
+    /// - `limits`: 大小限制
+    pub fn new(dir: PathBuf, limits: ImageCacheLimits) -> Result<Self, TerminalError> {
+        fs::create_dir_all(&dir)
+            .map_err(|e| TerminalError::ImageCacheError(format!("创建缓存目录失败: {}", e)))?;
+
+        let cache = Self {
+            dir,
+            limits,
+            total_bytes: Mutex::new(0),
+        };
+        let total = cache.entries()?.iter().map(|(_, len, _)| len).sum();
+        *cache.total_bytes.lock() = total;
+        cache.evict(&mut cache.total_bytes.lock())?;
+        Ok(cache)
+    }
+
+    /// 获取默认缓存目录
+    pub fn default_dir() -> Result<PathBuf, TerminalError> {
+        let home = dirs::home_dir()
+            .ok_or_else(|| TerminalError::ImageCacheError("无法获取主目录".to_string()))?;
+        Ok(home.join(".proxycast").join("terminal_images"))
+    }
+
+    /// 获取大小限制
+    pub fn limits(&self) -> ImageCacheLimits {
+        self.limits
+    }
+
+    /// 获取缓存总大小（字节）
+    pub fn total_bytes(&self) -> u64 {
+        *self.total_bytes.lock()
+    }
+
+    /// 保存图片
+    ///
+    /// # 参数
+    /// - `id`: 图片 ID
+    /// - `mime`: MIME 类型
+    /// - `data`: 图片数据
+    ///
+    /// # 返回
+    /// 是否保存（超过单张图片大小上限时不保存）
+    pub fn put(&self, id: &str, mime: &str, data: &[u8]) -> Result<bool, TerminalError> {
+        let ext = extension_for(mime)
+            .ok_or_else(|| TerminalError::ImageCacheError(format!("不支持的图片类型: {}", mime)))?;
+        validate_id(id)?;
+        if data.len() > self.limits.max_image_bytes {
+            tracing::debug!(
+                "[ImageCache] 图片超过大小上限，不缓存: id={}, len={}",
+                id,
+                data.len()
+            );
+            return Ok(false);
+        }
+
+        let mut total = self.total_bytes.lock();
+        let path = self.dir.join(format!("{}.{}", id, ext));
+        if path.exists() {
+            // 相同内容的图片已缓存，只更新访问时间
+            touch(&path);
+            return Ok(true);
+        }
+        fs::write(&path, data)
+            .map_err(|e| TerminalError::ImageCacheError(format!("写入图片失败: {}", e)))?;
+        *total += data.len() as u64;
+        self.evict(&mut total)?;
+        Ok(true)
+    }
+
+    /// 读取图片
+    ///
+    /// # 参数
+    /// - `id`: 图片 ID
+    pub fn get(&self, id: &str) -> Result<Option<CachedImage>, TerminalError> {
+        validate_id(id)?;
+        let _guard = self.total_bytes.lock();
+        for (mime, ext) in IMAGE_TYPES {
+            let path = self.dir.join(format!("{}.{}", id, ext));
+            if !path.exists() {
+                continue;
+            }
+            let data = fs::read(&path)
+                .map_err(|e| TerminalError::ImageCacheError(format!("读取图片失败: {}", e)))?;
+            touch(&path);
+            return Ok(Some(CachedImage {
+                mime: mime.to_string(),
+                data,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// 清空缓存
+    ///
+    /// # 返回
+    /// 删除的图片数量
+    pub fn clear(&self) -> Result<usize, TerminalError> {
+        let mut total = self.total_bytes.lock();
+        let entries = self.entries()?;
+        for (path, len, _) in &entries {
+            if fs::remove_file(path).is_ok() {
+                *total = total.saturating_sub(*len);
+            }
+        }
+        tracing::info!("[ImageCache] 已清空图片缓存: count={}", entries.len());
+        Ok(entries.len())
+    }
+
+    /// 淘汰最久未访问的图片，直到总大小不超过上限
+    fn evict(&self, total: &mut u64) -> Result<(), TerminalError> {
+        if *total <= self.limits.max_total_bytes {
+            return Ok(());
+        }
+        let mut entries = self.entries()?;
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (path, len, _) in entries {
+            if *total <= self.limits.max_total_bytes {
+                break;
+            }
+            if let Err(e) = fs::remove_file(&path) {
+                tracing::warn!("[ImageCache] 删除图片失败: {:?}, error={}", path, e);
+                continue;
+            }
+            *total = total.saturating_sub(len);
+            tracing::debug!("[ImageCache] 淘汰图片: {:?}", path);
+        }
+        Ok(())
+    }
+
+    /// 列出缓存文件（路径、大小、修改时间）
+    fn entries(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>, TerminalError> {
+        let dir = fs::read_dir(&self.dir)
+            .map_err(|e| TerminalError::ImageCacheError(format!("读取缓存目录失败: {}", e)))?;
+        Ok(dir
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| IMAGE_TYPES.iter().any(|(_, e)| *e == ext))
+            })
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some((entry.path(), meta.len(), modified))
+            })
+            .collect())
+    }
+}
+
+/// 获取 MIME 类型对应的文件扩展名
+fn extension_for(mime: &str) -> Option<&'static str> {
+    IMAGE_TYPES
+        .iter()
+        .find(|(m, _)| *m == mime)
+        .map(|(_, ext)| *ext)
+}
+
+/// 校验图片 ID（只允许十六进制字符，避免路径穿越）
+fn validate_id(id: &str) -> Result<(), TerminalError> {
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(TerminalError::ImageCacheError(format!(
+            "无效的图片 ID: {}",
+            id
+        )));
+    }
+    Ok(())
+}
+
+/// 更新文件修改时间（作为最近访问时间）
+fn touch(path: &Path) {
+    if let Ok(file) = fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_put_and_get() {
+        let dir = TempDir::new().unwrap();
+        let cache = ImageCache::new(dir.path().to_path_buf(), ImageCacheLimits::default()).unwrap();
+
+        assert!(cache.put("abc123", "image/png", b"png-data").unwrap());
+        assert!(cache.put("abc123", "image/png", b"png-data").unwrap());
+        assert_eq!(cache.total_bytes(), 8);
+        assert_eq!(
+            cache.get("abc123").unwrap(),
+            Some(CachedImage {
+                mime: "image/png".to_string(),
+                data: b"png-data".to_vec(),
+            })
+        );
+        assert_eq!(cache.get("def456").unwrap(), None);
+
+        // 非法 ID 和类型
+        assert!(cache.get("../etc").is_err());
+        assert!(cache.put("abc", "image/bmp", b"x").is_err());
+
+        // 重新打开时统计已有文件
+        let reopened =
+            ImageCache::new(dir.path().to_path_buf(), ImageCacheLimits::default()).unwrap();
+        assert_eq!(reopened.total_bytes(), 8);
+        assert_eq!(reopened.clear().unwrap(), 1);
+        assert_eq!(reopened.total_bytes(), 0);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let dir = TempDir::new().unwrap();
+        let limits = ImageCacheLimits {
+            max_image_bytes: 4,
+            max_total_bytes: 8,
+        };
+        let cache = ImageCache::new(dir.path().to_path_buf(), limits).unwrap();
+
+        assert!(!cache.put("aa", "image/png", b"too large").unwrap());
+        assert_eq!(cache.total_bytes(), 0);
+
+        cache.put("a1", "image/png", b"1111").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        cache.put("a2", "image/gif", b"2222").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        // 访问 a1 后，a2 成为最久未访问的图片
+        cache.get("a1").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        cache.put("a3", "image/jpeg", b"3333").unwrap();
+
+        assert_eq!(cache.total_bytes(), 8);
+        assert!(cache.get("a1").unwrap().is_some());
+        assert!(cache.get("a2").unwrap().is_none());
+        assert!(cache.get("a3").unwrap().is_some());
+    }
+}
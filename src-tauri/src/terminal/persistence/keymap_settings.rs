@@ -0,0 +1,219 @@
+//! 按键绑定设置
+//!
+//! 用户自定义的按键绑定：按下指定按键组合时向终端写入自定义序列，优先于默认编码
+//! （见 [`crate::terminal::keymap`]）。
+//!
+//! ## 设计说明
+//! 设置保存在块文件目录的 `keymap.json`。写入内容使用转义文本表示，
+//! 支持 `\e`、`\r`、`\n`、`\t`、`\\` 和 `\xHH`，例如 `\e[1;5D` 或 `\x02c`。
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::terminal::error::TerminalError;
+use crate::terminal::keymap::KeyChord;
+
+/// 按键绑定设置文件名
+pub const KEYMAP_SETTINGS_FILE: &str = "keymap.json";
+
+/// 按键绑定数量上限
+pub const MAX_KEY_BINDINGS: usize = 256;
+
+/// 单个绑定写入内容的最大字节数
+const MAX_BINDING_BYTES: usize = 256;
+
+/// 按键绑定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    /// 按键组合（如 `ctrl+shift+ArrowLeft`）
+    pub chord: String,
+    /// 写入终端的内容（转义文本）
+    pub send: String,
+}
+
+/// 按键绑定设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeymapSettings {
+    /// 自定义按键绑定
+    pub bindings: Vec<KeyBinding>,
+}
+
+impl KeymapSettings {
+    /// 从块文件目录加载设置
+    ///
+    /// 文件不存在或无效时返回默认设置（没有自定义绑定）。
+    pub fn load(base_dir: &Path) -> Self {
+        let path = base_dir.join(KEYMAP_SETTINGS_FILE);
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        let settings = serde_json::from_str::<Self>(&text)
+            .map_err(|e| TerminalError::InvalidKeyBinding(format!("解析按键绑定失败: {}", e)))
+            .and_then(|settings| settings.validate().map(|_| settings));
+        match settings {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!(
+                    "[Keymap] 按键绑定设置无效，使用默认设置: {:?}, error={}",
+                    path,
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// 保存设置到块文件目录
+    pub fn save(&self, base_dir: &Path) -> Result<(), TerminalError> {
+        self.validate()?;
+        fs::create_dir_all(base_dir).map_err(|e| {
+            TerminalError::BlockFileError(format!("无法创建目录 {:?}: {}", base_dir, e))
+        })?;
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| TerminalError::Internal(format!("序列化按键绑定失败: {}", e)))?;
+        fs::write(base_dir.join(KEYMAP_SETTINGS_FILE), text)
+            .map_err(|e| TerminalError::BlockFileError(format!("保存按键绑定失败: {}", e)))
+    }
+
+    /// 校验设置（按键组合可解析、写入内容有效、没有重复的按键组合）
+    pub fn validate(&self) -> Result<(), TerminalError> {
+        if self.bindings.len() > MAX_KEY_BINDINGS {
+            return Err(TerminalError::InvalidKeyBinding(format!(
+                "按键绑定数量超过上限 {}",
+                MAX_KEY_BINDINGS
+            )));
+        }
+        let mut chords: Vec<KeyChord> = Vec::with_capacity(self.bindings.len());
+        for binding in &self.bindings {
+            let chord = KeyChord::parse(&binding.chord).ok_or_else(|| {
+                TerminalError::InvalidKeyBinding(format!("无法解析按键组合: {}", binding.chord))
+            })?;
+            let data = unescape(&binding.send)?;
+            if data.is_empty() || data.len() > MAX_BINDING_BYTES {
+                return Err(TerminalError::InvalidKeyBinding(format!(
+                    "{} 的写入内容长度必须在 1..={} 字节之间",
+                    binding.chord, MAX_BINDING_BYTES
+                )));
+            }
+            if chords.iter().any(|other| other.matches(&chord)) {
+                return Err(TerminalError::InvalidKeyBinding(format!(
+                    "按键组合重复: {}",
+                    binding.chord
+                )));
+            }
+            chords.push(chord);
+        }
+        Ok(())
+    }
+
+    /// 查找按键组合对应的写入内容
+    pub fn lookup(&self, chord: &KeyChord) -> Option<Vec<u8>> {
+        self.bindings
+            .iter()
+            .find(|binding| KeyChord::parse(&binding.chord).is_some_and(|c| c.matches(chord)))
+            .and_then(|binding| unescape(&binding.send).ok())
+    }
+}
+
+/// 解析转义文本
+pub fn unescape(text: &str) -> Result<Vec<u8>, TerminalError> {
+    let invalid = || TerminalError::InvalidKeyBinding(format!("无效的转义文本: {}", text));
+    let mut data = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            data.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let byte = match chars.next().ok_or_else(invalid)? {
+            'e' => 0x1b,
+            'r' => b'\r',
+            'n' => b'\n',
+            't' => b'\t',
+            '\\' => b'\\',
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if hex.len() != 2 {
+                    return Err(invalid());
+                }
+                u8::from_str_radix(&hex, 16).map_err(|_| invalid())?
+            }
+            _ => return Err(invalid()),
+        };
+        data.push(byte);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(chord: &str, send: &str) -> KeyBinding {
+        KeyBinding {
+            chord: chord.to_string(),
+            send: send.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"\e[1;5D").unwrap(), b"\x1b[1;5D");
+        assert_eq!(unescape(r"\x02c\r").unwrap(), b"\x02c\r");
+        assert_eq!(unescape(r"a\\b").unwrap(), b"a\\b");
+        assert!(unescape(r"\x2").is_err());
+        assert!(unescape(r"\q").is_err());
+        assert!(unescape("\\").is_err());
+    }
+
+    #[test]
+    fn test_validate_and_lookup() {
+        let settings = KeymapSettings {
+            bindings: vec![
+                binding("ctrl+ArrowLeft", r"\eb"),
+                binding("alt+Enter", r"\n"),
+            ],
+        };
+        settings.validate().unwrap();
+
+        let chord = KeyChord::parse("ctrl+ArrowLeft").unwrap();
+        assert_eq!(settings.lookup(&chord), Some(b"\x1bb".to_vec()));
+        assert_eq!(
+            settings.lookup(&KeyChord::parse("ArrowLeft").unwrap()),
+            None
+        );
+
+        let duplicate = KeymapSettings {
+            bindings: vec![binding("ctrl+a", "x"), binding("Ctrl+A", "y")],
+        };
+        assert!(duplicate.validate().is_err());
+        let unknown = KeymapSettings {
+            bindings: vec![binding("ctrl+NoSuchKey", "x")],
+        };
+        assert!(unknown.validate().is_err());
+        let empty = KeymapSettings {
+            bindings: vec![binding("ctrl+a", "")],
+        };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(KeymapSettings::load(dir.path()), KeymapSettings::default());
+
+        let settings = KeymapSettings {
+            bindings: vec![binding("shift+Enter", r"\e\r")],
+        };
+        settings.save(dir.path()).unwrap();
+        assert_eq!(KeymapSettings::load(dir.path()), settings);
+
+        // 无效文件回退到默认设置
+        fs::write(dir.path().join(KEYMAP_SETTINGS_FILE), "{\"bindings\": 1}").unwrap();
+        assert_eq!(KeymapSettings::load(dir.path()), KeymapSettings::default());
+    }
+}
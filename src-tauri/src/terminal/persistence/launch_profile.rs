@@ -0,0 +1,325 @@
+//! 终端启动配置存储
+//!
+//! 使用 SQLite 保存命名的终端启动配置（Shell、参数、环境变量、启动命令、工作目录、连接），
+//! 创建会话时传入配置 ID 即可，不必每次传入零散参数。
+//!
+//! ## 功能
+//! - 启动配置的新增、更新、删除和查询
+//! - 参数和环境变量以 JSON 文本存储
+//!
+//! ## 设计说明
+//! 本地会话的 Shell、参数和环境变量交给 `ShellLaunchBuilder::build_profile` 合成启动命令；
+//! 远程连接忽略 Shell、参数、环境变量和工作目录，只发送启动命令。
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::DbConnection;
+use crate::terminal::error::TerminalError;
+
+/// 终端启动配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchProfile {
+    /// 配置 ID（新建时为空，保存时生成）
+    pub id: String,
+    /// 配置名称
+    pub name: String,
+    /// Shell 路径（为空时使用默认 Shell）
+    pub shell: Option<String>,
+    /// Shell 参数
+    pub args: Vec<String>,
+    /// 环境变量
+    pub env: HashMap<String, String>,
+    /// 启动命令（Shell 启动后输入执行）
+    pub startup_command: Option<String>,
+    /// 工作目录
+    pub cwd: Option<String>,
+    /// 连接名称（为空时为本地 Shell）
+    pub connection: Option<String>,
+    /// 创建时间（Unix 时间戳，毫秒）
+    pub created_at: i64,
+    /// 更新时间（Unix 时间戳，毫秒）
+    pub updated_at: i64,
+}
+
+/// 启动配置存储服务
+pub struct LaunchProfileStore {
+    db: DbConnection,
+}
+
+impl LaunchProfileStore {
+    /// 创建启动配置存储服务
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+
+    /// 初始化数据库表
+    ///
+    /// 创建 terminal_launch_profiles 表（如果不存在）。
+    pub fn init_tables(&self) -> Result<(), TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS terminal_launch_profiles (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                shell TEXT,
+                args TEXT NOT NULL DEFAULT '[]',
+                env TEXT NOT NULL DEFAULT '{}',
+                startup_command TEXT,
+                cwd TEXT,
+                connection TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("创建表失败: {}", e)))?;
+
+        tracing::debug!("[LaunchProfile] 数据库表初始化完成");
+        Ok(())
+    }
+
+    /// 保存启动配置
+    ///
+    /// ID 为空时新建配置，否则更新同 ID 的配置（不存在时按该 ID 新建）。
+    ///
+    /// # 返回
+    /// 保存后的配置（包含 ID 和时间戳）
+    pub fn save(&self, profile: &LaunchProfile) -> Result<LaunchProfile, TerminalError> {
+        let name = profile.name.trim();
+        if name.is_empty() {
+            return Err(TerminalError::Internal("启动配置名称不能为空".to_string()));
+        }
+
+        let now = Utc::now().timestamp_millis();
+        let mut saved = LaunchProfile {
+            name: name.to_string(),
+            updated_at: now,
+            ..profile.clone()
+        };
+        if saved.id.is_empty() {
+            saved.id = Uuid::new_v4().to_string();
+        }
+        if saved.created_at == 0 {
+            saved.created_at = now;
+        }
+
+        let args = serde_json::to_string(&saved.args)
+            .map_err(|e| TerminalError::Internal(format!("序列化参数失败: {}", e)))?;
+        let env = serde_json::to_string(&saved.env)
+            .map_err(|e| TerminalError::Internal(format!("序列化环境变量失败: {}", e)))?;
+
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.execute(
+            "INSERT INTO terminal_launch_profiles
+             (id, name, shell, args, env, startup_command, cwd, connection, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                shell = excluded.shell,
+                args = excluded.args,
+                env = excluded.env,
+                startup_command = excluded.startup_command,
+                cwd = excluded.cwd,
+                connection = excluded.connection,
+                updated_at = excluded.updated_at",
+            params![
+                saved.id,
+                saved.name,
+                saved.shell,
+                args,
+                env,
+                saved.startup_command,
+                saved.cwd,
+                saved.connection,
+                saved.created_at,
+                saved.updated_at,
+            ],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("保存启动配置失败: {}", e)))?;
+
+        tracing::info!(
+            "[LaunchProfile] 保存启动配置: {} ({})",
+            saved.name,
+            saved.id
+        );
+        Ok(saved)
+    }
+
+    /// 获取启动配置
+    pub fn get(&self, id: &str) -> Result<Option<LaunchProfile>, TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.query_row(
+            "SELECT id, name, shell, args, env, startup_command, cwd, connection, created_at, updated_at
+             FROM terminal_launch_profiles WHERE id = ?1",
+            params![id],
+            Self::row_to_profile,
+        )
+        .optional()
+        .map_err(|e| TerminalError::DatabaseError(format!("查询启动配置失败: {}", e)))
+    }
+
+    /// 获取所有启动配置（按名称排序）
+    pub fn list(&self) -> Result<Vec<LaunchProfile>, TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, shell, args, env, startup_command, cwd, connection, created_at, updated_at
+                 FROM terminal_launch_profiles ORDER BY name, created_at",
+            )
+            .map_err(|e| TerminalError::DatabaseError(format!("准备查询失败: {}", e)))?;
+
+        let profiles = stmt
+            .query_map([], Self::row_to_profile)
+            .map_err(|e| TerminalError::DatabaseError(format!("查询启动配置失败: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TerminalError::DatabaseError(format!("读取启动配置失败: {}", e)))?;
+
+        Ok(profiles)
+    }
+
+    /// 删除启动配置
+    ///
+    /// # 返回
+    /// 是否删除了配置
+    pub fn delete(&self, id: &str) -> Result<bool, TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        let deleted = conn
+            .execute(
+                "DELETE FROM terminal_launch_profiles WHERE id = ?1",
+                params![id],
+            )
+            .map_err(|e| TerminalError::DatabaseError(format!("删除启动配置失败: {}", e)))?;
+
+        Ok(deleted > 0)
+    }
+
+    /// 将数据库行转换为启动配置（无法解析的参数和环境变量按空值处理）
+    fn row_to_profile(row: &Row<'_>) -> rusqlite::Result<LaunchProfile> {
+        let args: String = row.get(3)?;
+        let env: String = row.get(4)?;
+        Ok(LaunchProfile {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            shell: row.get(2)?,
+            args: serde_json::from_str(&args).unwrap_or_default(),
+            env: serde_json::from_str(&env).unwrap_or_default(),
+            startup_command: row.get(5)?,
+            cwd: row.get(6)?,
+            connection: row.get(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DbPool;
+    use rusqlite::Connection;
+    use std::sync::Arc;
+
+    fn store() -> LaunchProfileStore {
+        let db = Arc::new(DbPool::from_connection(
+            Connection::open_in_memory().unwrap(),
+        ));
+        let store = LaunchProfileStore::new(db);
+        store.init_tables().unwrap();
+        store
+    }
+
+    #[test]
+    fn test_save_and_get() {
+        let store = store();
+        let saved = store
+            .save(&LaunchProfile {
+                name: " dev ".to_string(),
+                shell: Some("/bin/zsh".to_string()),
+                args: vec!["-l".to_string()],
+                env: HashMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+                startup_command: Some("cargo watch".to_string()),
+                cwd: Some("~/code".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(!saved.id.is_empty());
+        assert_eq!(saved.name, "dev");
+        assert!(saved.created_at > 0);
+
+        assert_eq!(store.get(&saved.id).unwrap(), Some(saved));
+        assert_eq!(store.get("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_update_keeps_created_at() {
+        let store = store();
+        let saved = store
+            .save(&LaunchProfile {
+                name: "prod".to_string(),
+                connection: Some("user@prod".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let updated = store
+            .save(&LaunchProfile {
+                startup_command: Some("tmux attach".to_string()),
+                ..saved.clone()
+            })
+            .unwrap();
+        assert_eq!(updated.id, saved.id);
+        assert_eq!(updated.created_at, saved.created_at);
+
+        let profiles = store.list().unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].startup_command.as_deref(), Some("tmux attach"));
+    }
+
+    #[test]
+    fn test_list_and_delete() {
+        let store = store();
+        for name in ["zsh", "bash"] {
+            store
+                .save(&LaunchProfile {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        assert!(store.save(&LaunchProfile::default()).is_err());
+
+        let profiles = store.list().unwrap();
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["bash", "zsh"]);
+
+        assert!(store.delete(&profiles[0].id).unwrap());
+        assert!(!store.delete(&profiles[0].id).unwrap());
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+}
@@ -4,15 +4,56 @@
 //!
 //! ## 模块结构
 //! - `block_file` - 块文件循环缓冲存储
+//! - `block_storage` - 块文件存储设置、磁盘占用统计和清理策略
+//! - `block_timing` - 块文件时间索引（供会话回放使用）
+//! - `clipboard_policy` - OSC 52 剪贴板策略 SQLite 存储（按主机）
+//! - `cold_segments` - 块文件冷段存储（被覆盖的输出 zstd 压缩保存）
+//! - `command_history` - 命令历史 SQLite 存储（按主机去重）
+//! - `image_cache` - 内联图片磁盘缓存（大小上限、LRU 淘汰）
+//! - `keymap_settings` - 自定义按键绑定设置
+//! - `launch_profile` - 终端启动配置 SQLite 存储
 //! - `session_store` - 会话元数据 SQLite 存储
 //!
 //! ## 功能
-//! - 终端输出历史的文件存储（循环缓冲）
+//! - 终端输出历史的文件存储（循环缓冲，可配置大小，被覆盖的输出压缩保存）
+//! - 按时间和总大小清理已结束会话的输出历史
 //! - 会话元数据的数据库存储
+//! - 已执行命令的历史记录与查询
+//! - 命名的终端启动配置
+//! - 按主机的剪贴板访问策略
+//! - 终端内联图片缓存
+//! - 自定义按键绑定
 //! - 会话恢复支持
 
 pub mod block_file;
+pub mod block_storage;
+pub mod block_timing;
+pub mod clipboard_policy;
+pub mod cold_segments;
+pub mod command_history;
+pub mod image_cache;
+pub mod keymap_settings;
+pub mod launch_profile;
 pub mod session_store;
 
 pub use block_file::BlockFile;
+pub use block_storage::{
+    BlockPruneReport, BlockRetentionPolicy, BlockStorageSettings, BlockUsage, MAX_SCROLLBACK_BYTES,
+    MIN_SCROLLBACK_BYTES,
+};
+pub use block_timing::TimingRecord;
+pub use clipboard_policy::{
+    ClipboardAccess, ClipboardPolicy, ClipboardPolicyStore, DEFAULT_CLIPBOARD_MAX_BYTES,
+};
+pub use cold_segments::{ColdSegments, DEFAULT_COLD_MAX_BYTES};
+pub use command_history::{
+    CommandExecution, CommandHistoryEntry, CommandHistoryQuery, CommandHistorySort,
+    CommandHistoryStore,
+};
+pub use image_cache::{
+    CachedImage, ImageCache, ImageCacheLimits, DEFAULT_IMAGE_CACHE_MAX_BYTES,
+    DEFAULT_IMAGE_MAX_BYTES,
+};
+pub use keymap_settings::{KeyBinding, KeymapSettings, MAX_KEY_BINDINGS};
+pub use launch_profile::{LaunchProfile, LaunchProfileStore};
 pub use session_store::{SessionMetadataStore, SessionRecord};
@@ -6,6 +6,7 @@
 //! - 会话元数据的 CRUD 操作
 //! - 会话状态查询
 //! - 会话恢复支持
+//! - 工作区（标签页与分屏布局）持久化
 //!
 //! _Requirements: 3.5, 3.9_
 
@@ -15,6 +16,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::database::DbConnection;
 use crate::terminal::error::TerminalError;
+use crate::terminal::workspace::{PaneLayout, TerminalWorkspace};
 
 /// 会话记录（存储在 SQLite）
 ///
@@ -82,7 +84,7 @@ impl SessionMetadataStore {
 
     /// 初始化数据库表
     ///
-    /// 创建 terminal_sessions 表（如果不存在）。
+    /// 创建 terminal_sessions 和 terminal_workspaces 表（如果不存在）。
     pub fn init_tables(&self) -> Result<(), TerminalError> {
         let conn = self
             .db
@@ -124,6 +126,20 @@ impl SessionMetadataStore {
         )
         .map_err(|e| TerminalError::DatabaseError(format!("创建索引失败: {}", e)))?;
 
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS terminal_workspaces (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                position INTEGER NOT NULL DEFAULT 0,
+                layout TEXT,
+                active_session_id TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("创建表失败: {}", e)))?;
+
         tracing::debug!("[SessionStore] 数据库表初始化完成");
         Ok(())
     }
@@ -425,4 +441,164 @@ impl SessionMetadataStore {
 
         Ok(count as usize)
     }
+    /// 更新会话所属的标签页
+    pub fn update_tab_id(&self, id: &str, tab_id: &str) -> Result<(), TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        let now = Utc::now().timestamp_millis();
+
+        conn.execute(
+            "UPDATE terminal_sessions SET tab_id = ?1, updated_at = ?2 WHERE id = ?3",
+            params![tab_id, now, id],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("更新会话标签页失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 保存工作区
+    ///
+    /// 如果工作区已存在则更新，否则插入新记录。
+    pub fn save_workspace(&self, workspace: &TerminalWorkspace) -> Result<(), TerminalError> {
+        let layout = workspace
+            .layout
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| TerminalError::DatabaseError(format!("序列化布局失败: {}", e)))?;
+
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO terminal_workspaces
+             (id, name, position, layout, active_session_id, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                workspace.id,
+                workspace.name,
+                workspace.position,
+                layout,
+                workspace.active_session_id,
+                workspace.created_at,
+                workspace.updated_at,
+            ],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("保存工作区失败: {}", e)))?;
+
+        tracing::debug!("[SessionStore] 保存工作区: {}", workspace.id);
+        Ok(())
+    }
+
+    /// 获取所有工作区（按标签页顺序）
+    ///
+    /// 无法解析的布局按空布局处理。
+    pub fn get_workspaces(&self) -> Result<Vec<TerminalWorkspace>, TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, position, layout, active_session_id, created_at, updated_at
+                 FROM terminal_workspaces ORDER BY position ASC, created_at ASC",
+            )
+            .map_err(|e| TerminalError::DatabaseError(format!("准备查询失败: {}", e)))?;
+
+        let workspaces = stmt
+            .query_map([], |row| {
+                let id: String = row.get(0)?;
+                let layout: Option<String> = row.get(3)?;
+                let layout = layout.and_then(|json| {
+                    serde_json::from_str::<PaneLayout>(&json)
+                        .map_err(|e| {
+                            tracing::warn!("[SessionStore] 工作区 {} 的布局无法解析: {}", id, e);
+                        })
+                        .ok()
+                });
+                Ok(TerminalWorkspace {
+                    id,
+                    name: row.get(1)?,
+                    position: row.get(2)?,
+                    layout,
+                    active_session_id: row.get(4)?,
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                })
+            })
+            .map_err(|e| TerminalError::DatabaseError(format!("查询工作区失败: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TerminalError::DatabaseError(format!("读取工作区失败: {}", e)))?;
+
+        Ok(workspaces)
+    }
+
+    /// 删除工作区
+    pub fn delete_workspace(&self, id: &str) -> Result<(), TerminalError> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {}", e)))?;
+
+        conn.execute("DELETE FROM terminal_workspaces WHERE id = ?1", params![id])
+            .map_err(|e| TerminalError::DatabaseError(format!("删除工作区失败: {}", e)))?;
+
+        tracing::debug!("[SessionStore] 删除工作区: {}", id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DbPool;
+    use crate::terminal::workspace::SplitDirection;
+    use rusqlite::Connection;
+    use std::sync::Arc;
+
+    fn store() -> SessionMetadataStore {
+        let db = Arc::new(DbPool::from_connection(
+            Connection::open_in_memory().unwrap(),
+        ));
+        let store = SessionMetadataStore::new(db);
+        store.init_tables().unwrap();
+        store
+    }
+
+    #[test]
+    fn test_workspace_roundtrip() {
+        let store = store();
+        let mut second = TerminalWorkspace::new("second", 1);
+        second
+            .add_session("b", None, SplitDirection::Horizontal)
+            .unwrap();
+        second
+            .add_session("c", None, SplitDirection::Vertical)
+            .unwrap();
+        let first = TerminalWorkspace::new("first", 0);
+        store.save_workspace(&second).unwrap();
+        store.save_workspace(&first).unwrap();
+
+        assert_eq!(store.get_workspaces().unwrap(), vec![first.clone(), second]);
+
+        store.delete_workspace(&first.id).unwrap();
+        assert_eq!(store.get_workspaces().unwrap().len(), 1);
+
+        let record = SessionRecord::new(
+            "b".to_string(),
+            "block".to_string(),
+            "default".to_string(),
+            "shell".to_string(),
+            None,
+        );
+        store.save(&record).unwrap();
+        store.update_tab_id("b", "workspace").unwrap();
+        assert_eq!(store.get_by_id("b").unwrap().unwrap().tab_id, "workspace");
+    }
 }
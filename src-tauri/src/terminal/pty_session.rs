@@ -8,23 +8,48 @@
 //! - 处理 PTY 输入写入
 //! - 监控进程退出状态
 //! - 保存输出历史（循环缓冲区）
+//! - 承载网络 / 串口字节流（Telnet、原始 TCP、串口），与 PTY 会话共用输出和状态事件
+//! - 可选的 Shell 集成（目录跟踪、命令历史、链接检测、OSC 52 剪贴板）
+//! - 解码输出中的内联图片（sixel、iTerm2 OSC 1337），写入图片缓存并推送图片事件
+//! - 跟踪终端程序的括号粘贴模式（DECSET 2004），供粘贴时包裹内容
+//! - 可选的块文件持久化输出（会话回放、单条命令输出）
+//! - 输出订阅和输入写入句柄（`SessionTap`），供会话分享使用
+//! - 会话指标（输入 / 输出字节数、输出速率、回显延迟估算、调整大小次数）
+//! - 输出流控（合并输出事件、背压、洪泛检测，见 [`super::flow_control`]）
+//! - 跟踪终端程序的键盘模式，按 `TERM` 和键盘模式编码按键（见 [`super::keymap`]）
 //!
 //! ## 架构说明
 //! PTY 在后端预创建，使用默认大小 (24x80)。前端连接后通过 resize 同步实际大小。
 //! 输出历史保存在循环缓冲区中，前端连接时可以获取历史数据。
+//!
+//! 读取线程处理输出（历史、块文件、Shell 集成）后经有界队列交给发送线程，
+//! 发送线程合并输出并限制事件频率；队列满时读取线程阻塞，进程随之被挂起。
 
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
 use std::sync::Arc;
+use std::time::Instant;
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use parking_lot::Mutex;
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
-use tauri::Emitter;
-use tokio::sync::RwLock;
+use tauri::{Emitter, Manager};
+use tokio::sync::{broadcast, RwLock};
 
 use super::error::TerminalError;
-use super::events::{event_names, SessionStatus, TerminalOutputEvent, TerminalStatusEvent};
+use super::events::{
+    event_names, SessionStatus, TerminalImageEvent, TerminalOutputEvent, TerminalOutputFloodEvent,
+    TerminalStatusEvent,
+};
+use super::flow_control::{FloodChange, FloodDetector, OutputCoalescer, OUTPUT_HIGH_WATER_CHUNKS};
+use super::integration::{
+    BracketedPasteTracker, InlineImage, InlineImageDecoder, ShellIntegration,
+};
+use super::keymap::{KeyChord, KeyEncoder, KeymapProfile};
+use super::persistence::{BlockFile, ImageCache, DEFAULT_IMAGE_MAX_BYTES};
+use crate::telemetry::{TerminalMetrics, TerminalMetricsSnapshot};
 
 /// 默认终端行数
 pub const DEFAULT_ROWS: u16 = 24;
@@ -32,6 +57,18 @@ pub const DEFAULT_ROWS: u16 = 24;
 pub const DEFAULT_COLS: u16 = 80;
 /// 输出历史缓冲区最大大小 (1MB)
 const OUTPUT_BUFFER_MAX_SIZE: usize = 1024 * 1024;
+/// 输出订阅通道容量（按读取次数计，每次最多 4KB）
+const OUTPUT_CHANNEL_CAPACITY: usize = 256;
+
+/// 读取线程交给发送线程的输出项（按输出顺序发送）
+enum OutputItem {
+    /// 输出数据
+    Data(Vec<u8>),
+    /// 内联图片
+    Image(InlineImage),
+    /// 状态事件（进程退出或读取错误，在最后的输出之后发送）
+    Status(TerminalStatusEvent),
+}
 
 /// 循环缓冲区，用于存储终端输出历史
 struct CircularBuffer {
@@ -71,20 +108,132 @@ impl CircularBuffer {
     }
 }
 
+/// 终端大小调整
+///
+/// PTY 会话调整 PTY 大小；字节流会话由各自的协议处理（如 Telnet NAWS），不支持时忽略。
+pub trait TerminalResizer: Send {
+    /// 调整终端大小
+    fn resize(&self, rows: u16, cols: u16) -> Result<(), TerminalError>;
+}
+
+impl TerminalResizer for Box<dyn portable_pty::MasterPty + Send> {
+    fn resize(&self, rows: u16, cols: u16) -> Result<(), TerminalError> {
+        portable_pty::MasterPty::resize(
+            self.as_ref(),
+            PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            },
+        )
+        .map_err(|e| TerminalError::ResizeFailed(e.to_string()))
+    }
+}
+
+/// 不支持调整大小的字节流（原始 TCP、串口）
+pub struct IgnoreResize;
+
+impl TerminalResizer for IgnoreResize {
+    fn resize(&self, _rows: u16, _cols: u16) -> Result<(), TerminalError> {
+        Ok(())
+    }
+}
+
+/// 字节流会话的组成部分
+pub struct StreamParts {
+    /// 输出读取器
+    pub reader: Box<dyn Read + Send>,
+    /// 输入写入器
+    pub writer: Box<dyn Write + Send>,
+    /// 大小调整器
+    pub resizer: Box<dyn TerminalResizer>,
+}
+
+/// 会话的输出订阅和输入写入句柄
+///
+/// 由 `PtySession::tap` 获取，会话分享通过它把输出推送给观看者、把协作输入写回会话。
+#[derive(Clone)]
+pub struct SessionTap {
+    /// 输出广播
+    output: broadcast::Sender<Vec<u8>>,
+    /// 输出历史缓冲区
+    output_buffer: Arc<Mutex<CircularBuffer>>,
+    /// PTY 写入器
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    /// 会话状态
+    status: Arc<RwLock<SessionStatus>>,
+    /// 会话指标
+    metrics: Arc<TerminalMetrics>,
+}
+
+impl SessionTap {
+    /// 创建不关联会话的句柄（测试使用）
+    #[cfg(test)]
+    pub(crate) fn detached(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            output: broadcast::channel(OUTPUT_CHANNEL_CAPACITY).0,
+            output_buffer: Arc::new(Mutex::new(CircularBuffer::new(OUTPUT_BUFFER_MAX_SIZE))),
+            writer: Arc::new(Mutex::new(writer)),
+            status: Arc::new(RwLock::new(SessionStatus::Running)),
+            metrics: Arc::new(TerminalMetrics::new()),
+        }
+    }
+
+    /// 订阅输出
+    ///
+    /// 与读取线程共用输出历史缓冲区的锁，返回的历史和之后收到的输出之间没有缺口和重复。
+    ///
+    /// # 返回
+    /// 当前的输出历史和输出订阅
+    pub fn subscribe(&self) -> (Vec<u8>, broadcast::Receiver<Vec<u8>>) {
+        let buffer = self.output_buffer.lock();
+        (buffer.get_all(), self.output.subscribe())
+    }
+
+    /// 写入数据到会话
+    pub fn write(&self, data: &[u8]) -> Result<(), TerminalError> {
+        let mut writer = self.writer.lock();
+        writer
+            .write_all(data)
+            .map_err(|e| TerminalError::WriteFailed(e.to_string()))?;
+        writer
+            .flush()
+            .map_err(|e| TerminalError::WriteFailed(e.to_string()))?;
+        self.metrics.record_input(data.len());
+        Ok(())
+    }
+
+    /// 获取当前状态
+    pub async fn status(&self) -> SessionStatus {
+        *self.status.read().await
+    }
+}
+
 /// PTY 会话
 pub struct PtySession {
     /// 会话 ID
     id: String,
     /// PTY 写入器（使用 Mutex 保证线程安全）
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
-    /// PTY Master（用于调整大小，使用 Mutex 保证线程安全）
-    master: Arc<Mutex<Box<dyn portable_pty::MasterPty + Send>>>,
+    /// 大小调整器（PTY Master 或字节流协议，使用 Mutex 保证线程安全）
+    resizer: Arc<Mutex<Box<dyn TerminalResizer>>>,
     /// 会话状态
     status: Arc<RwLock<SessionStatus>>,
     /// 关闭标志
     shutdown_flag: Arc<AtomicBool>,
     /// 输出历史缓冲区
     output_buffer: Arc<Mutex<CircularBuffer>>,
+    /// 输出广播（会话分享订阅）
+    output_tx: broadcast::Sender<Vec<u8>>,
+    /// 终端程序是否启用括号粘贴模式
+    bracketed_paste: Arc<AtomicBool>,
+    /// 按键编码器（由读取线程根据输出跟踪键盘模式）
+    keyboard: Arc<Mutex<KeyEncoder>>,
+    /// Shell 集成处理器（可选）
+    integration: Option<Arc<ShellIntegration>>,
+    /// 会话指标
+    metrics: Arc<TerminalMetrics>,
 }
 
 impl PtySession {
@@ -120,7 +269,7 @@ impl PtySession {
         cols: u16,
         app_handle: tauri::AppHandle,
     ) -> Result<Self, TerminalError> {
-        Self::with_size_and_cwd(id, rows, cols, None, app_handle)
+        Self::with_size_and_cwd(id, rows, cols, None, None, None, app_handle)
     }
 
     /// 创建新的 PTY 会话（指定大小和工作目录）
@@ -130,6 +279,8 @@ impl PtySession {
     /// - `rows`: 终端行数
     /// - `cols`: 终端列数
     /// - `cwd`: 工作目录（可选，默认为用户主目录）
+    /// - `integration`: Shell 集成处理器（可选）
+    /// - `block_file`: 保存输出的块文件（可选）
     /// - `app_handle`: Tauri 应用句柄
     ///
     /// # 返回
@@ -140,6 +291,8 @@ impl PtySession {
         rows: u16,
         cols: u16,
         cwd: Option<String>,
+        integration: Option<Arc<ShellIntegration>>,
+        block_file: Option<Arc<BlockFile>>,
         app_handle: tauri::AppHandle,
     ) -> Result<Self, TerminalError> {
         tracing::info!(
@@ -150,6 +303,43 @@ impl PtySession {
             cwd
         );
 
+        // 构建命令
+        let shell = default_shell();
+        tracing::info!("[终端] 使用 shell: {}", shell);
+        let mut cmd = CommandBuilder::new(&shell);
+        cmd.env("TERM", "xterm-256color");
+        if let Some(dir) = resolve_cwd(cwd) {
+            cmd.cwd(dir);
+        }
+
+        Self::with_command(id, rows, cols, cmd, integration, block_file, app_handle)
+    }
+
+    /// 创建新的 PTY 会话（指定要执行的命令）
+    ///
+    /// 用于后台会话等需要自定义启动命令的场景。
+    ///
+    /// # 参数
+    /// - `id`: 会话 ID
+    /// - `rows`: 终端行数
+    /// - `cols`: 终端列数
+    /// - `cmd`: 要执行的命令
+    /// - `integration`: Shell 集成处理器（可选）
+    /// - `block_file`: 保存输出的块文件（可选）
+    /// - `app_handle`: Tauri 应用句柄
+    ///
+    /// # 返回
+    /// - `Ok(PtySession)`: 创建成功
+    /// - `Err(TerminalError)`: 创建失败
+    pub fn with_command(
+        id: String,
+        rows: u16,
+        cols: u16,
+        cmd: CommandBuilder,
+        integration: Option<Arc<ShellIntegration>>,
+        block_file: Option<Arc<BlockFile>>,
+        app_handle: tauri::AppHandle,
+    ) -> Result<Self, TerminalError> {
         let pty_system = native_pty_system();
 
         // 创建 PTY
@@ -162,45 +352,6 @@ impl PtySession {
             })
             .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
 
-        // 获取用户默认 shell
-        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
-        tracing::info!("[终端] 使用 shell: {}", shell);
-
-        // 构建命令
-        let mut cmd = CommandBuilder::new(&shell);
-        cmd.env("TERM", "xterm-256color");
-
-        // 设置工作目录
-        if let Some(dir) = cwd {
-            // 展开 ~ 为用户主目录
-            let expanded_dir = if dir.starts_with("~/") {
-                if let Some(home) = dirs::home_dir() {
-                    home.join(&dir[2..])
-                } else {
-                    std::path::PathBuf::from(&dir)
-                }
-            } else if dir == "~" {
-                dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from(&dir))
-            } else {
-                std::path::PathBuf::from(&dir)
-            };
-
-            if expanded_dir.exists() && expanded_dir.is_dir() {
-                tracing::info!("[终端] 设置工作目录: {:?}", expanded_dir);
-                cmd.cwd(expanded_dir);
-            } else {
-                tracing::warn!(
-                    "[终端] 工作目录不存在或不是目录: {:?}, 使用主目录",
-                    expanded_dir
-                );
-                if let Some(home) = dirs::home_dir() {
-                    cmd.cwd(home);
-                }
-            }
-        } else if let Some(home) = dirs::home_dir() {
-            cmd.cwd(home);
-        }
-
         // 启动子进程
         let _child = pair
             .slave
@@ -214,11 +365,53 @@ impl PtySession {
             .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
 
         // 获取读取器
-        let mut reader = pair
+        let reader = pair
             .master
             .try_clone_reader()
             .map_err(|e| TerminalError::PtyCreationFailed(e.to_string()))?;
 
+        let parts = StreamParts {
+            reader,
+            writer,
+            resizer: Box::new(pair.master),
+        };
+        let session = Self::with_stream(id, parts, integration, block_file, app_handle);
+        tracing::info!("[终端] 会话 {} 已创建 ({}x{})", session.id, cols, rows);
+        Ok(session)
+    }
+
+    /// 创建字节流会话
+    ///
+    /// 用于 Telnet、原始 TCP 和串口等没有子进程的连接。读取器返回超时类错误时继续等待，
+    /// 以便关闭会话后读取线程能及时退出；返回 EOF 时会话结束。
+    ///
+    /// # 参数
+    /// - `id`: 会话 ID
+    /// - `parts`: 读取器、写入器和大小调整器
+    /// - `integration`: Shell 集成处理器（可选，OSC 52 读取请求的回复写回会话输入）
+    /// - `block_file`: 保存输出的块文件（可选）
+    /// - `app_handle`: Tauri 应用句柄
+    pub fn with_stream(
+        id: String,
+        parts: StreamParts,
+        integration: Option<Arc<ShellIntegration>>,
+        block_file: Option<Arc<BlockFile>>,
+        app_handle: tauri::AppHandle,
+    ) -> Self {
+        let StreamParts {
+            mut reader,
+            writer,
+            resizer,
+        } = parts;
+        let writer = Arc::new(Mutex::new(writer));
+        if let Some(ref integration) = integration {
+            let reply_writer = writer.clone();
+            integration.set_responder(Arc::new(move |data: &[u8]| {
+                let mut w = reply_writer.lock();
+                w.write_all(data)?;
+                w.flush()
+            }));
+        }
         let status = Arc::new(RwLock::new(SessionStatus::Running));
         let status_clone = status.clone();
         let id_clone = id.clone();
@@ -230,13 +423,40 @@ impl PtySession {
         // 创建输出缓冲区
         let output_buffer = Arc::new(Mutex::new(CircularBuffer::new(OUTPUT_BUFFER_MAX_SIZE)));
         let output_buffer_clone = output_buffer.clone();
+        let (output_tx, _) = broadcast::channel(OUTPUT_CHANNEL_CAPACITY);
+        let output_tx_clone = output_tx.clone();
 
         // 获取当前 tokio runtime handle（在主线程中获取）
         let runtime_handle = tokio::runtime::Handle::current();
 
+        // 括号粘贴模式（由读取线程根据输出更新）
+        let bracketed_paste = Arc::new(AtomicBool::new(false));
+        let bracketed_paste_clone = bracketed_paste.clone();
+
+        // 按键编码器（由读取线程根据输出更新，查询的回复写回会话输入）
+        let keyboard = Arc::new(Mutex::new(KeyEncoder::default()));
+        let keyboard_clone = keyboard.clone();
+        let keyboard_writer = writer.clone();
+
+        // 内联图片缓存（未注册时只推送图片事件，不缓存）
+        let image_cache = app_handle
+            .try_state::<Arc<ImageCache>>()
+            .map(|state| state.inner().clone());
+
+        // 会话指标（读取线程记录输出，写入方记录输入）
+        let metrics = Arc::new(TerminalMetrics::new());
+        let metrics_clone = metrics.clone();
+
+        // 启动输出发送任务，队列满时读取线程阻塞（背压）
+        let (item_tx, item_rx) = mpsc::sync_channel(OUTPUT_HIGH_WATER_CHUNKS);
+        spawn_output_emitter(app_handle, id.clone(), image_cache, item_rx);
+
         // 启动输出读取任务（使用独立线程）
+        let integration_clone = integration.clone();
         std::thread::spawn(move || {
             let mut buffer = [0u8; 4096];
+            let mut images = InlineImageDecoder::new();
+            let mut paste_mode = BracketedPasteTracker::new();
 
             loop {
                 // 检查关闭标志
@@ -255,32 +475,83 @@ impl PtySession {
                         });
 
                         // 发送状态事件
-                        let _ = app_handle.emit(
-                            event_names::TERMINAL_STATUS,
-                            TerminalStatusEvent {
-                                session_id: id_clone.clone(),
-                                status: SessionStatus::Done,
-                                exit_code: Some(0),
-                                error: None,
-                            },
-                        );
+                        let _ = item_tx.send(OutputItem::Status(TerminalStatusEvent {
+                            session_id: id_clone.clone(),
+                            status: SessionStatus::Done,
+                            exit_code: Some(0),
+                            error: None,
+                        }));
                         break;
                     }
                     Ok(n) => {
                         let output_data = &buffer[..n];
+                        metrics_clone.record_output(n);
+
+                        // 保存到输出缓冲区，持有锁推送给订阅者（见 `SessionTap::subscribe`）
+                        {
+                            let mut history = output_buffer_clone.lock();
+                            history.append(output_data);
+                            if output_tx_clone.receiver_count() > 0 {
+                                let _ = output_tx_clone.send(output_data.to_vec());
+                            }
+                        }
 
-                        // 保存到输出缓冲区
-                        output_buffer_clone.lock().append(output_data);
+                        // 保存到块文件（在 Shell 集成之前，命令块的输出位置与块文件一致）
+                        if let Some(ref bf) = block_file {
+                            if let Err(e) = bf.append_data(output_data) {
+                                tracing::warn!("[终端] 会话 {} 写入块文件失败: {}", id_clone, e);
+                            }
+                        }
 
-                        // 发送输出事件
-                        let data = BASE64.encode(output_data);
-                        let _ = app_handle.emit(
-                            event_names::TERMINAL_OUTPUT,
-                            TerminalOutputEvent {
-                                session_id: id_clone.clone(),
-                                data,
-                            },
-                        );
+                        // 更新 Shell 集成状态
+                        if let Some(ref integration) = integration_clone {
+                            integration.process_output(output_data);
+                        }
+
+                        // 更新括号粘贴模式
+                        bracketed_paste_clone
+                            .store(paste_mode.feed(output_data), Ordering::Relaxed);
+
+                        // 更新键盘模式，回复 kitty 键盘协议查询
+                        let replies = keyboard_clone.lock().feed(output_data);
+                        if !replies.is_empty() {
+                            let mut w = keyboard_writer.lock();
+                            if let Err(e) = w.write_all(&replies).and_then(|_| w.flush()) {
+                                tracing::warn!(
+                                    "[终端] 会话 {} 回复键盘协议查询失败: {}",
+                                    id_clone,
+                                    e
+                                );
+                            }
+                        }
+
+                        // 交给发送线程，图片插在图片序列结束的位置，保证前端显示顺序
+                        let mut items = Vec::new();
+                        let mut start = 0;
+                        for extracted in images.feed(output_data) {
+                            if extracted.end > start {
+                                items.push(OutputItem::Data(
+                                    output_data[start..extracted.end].to_vec(),
+                                ));
+                            }
+                            items.push(OutputItem::Image(extracted.image));
+                            start = extracted.end;
+                        }
+                        if n > start {
+                            items.push(OutputItem::Data(output_data[start..].to_vec()));
+                        }
+                        if items.into_iter().any(|item| item_tx.send(item).is_err()) {
+                            break;
+                        }
+                    }
+                    Err(e)
+                        if matches!(
+                            e.kind(),
+                            ErrorKind::Interrupted | ErrorKind::TimedOut | ErrorKind::WouldBlock
+                        ) =>
+                    {
+                        // 字节流的读取超时，回到循环开头检查关闭标志
+                        continue;
                     }
                     Err(e) => {
                         // 检查是否是因为关闭导致的错误
@@ -293,31 +564,31 @@ impl PtySession {
                             *status_clone.write().await = SessionStatus::Error;
                         });
 
-                        let _ = app_handle.emit(
-                            event_names::TERMINAL_STATUS,
-                            TerminalStatusEvent {
-                                session_id: id_clone.clone(),
-                                status: SessionStatus::Error,
-                                exit_code: None,
-                                error: Some(e.to_string()),
-                            },
-                        );
+                        let _ = item_tx.send(OutputItem::Status(TerminalStatusEvent {
+                            session_id: id_clone.clone(),
+                            status: SessionStatus::Error,
+                            exit_code: None,
+                            error: Some(e.to_string()),
+                        }));
                         break;
                     }
                 }
             }
         });
 
-        tracing::info!("[终端] 会话 {} 已创建 ({}x{})", id, cols, rows);
-
-        Ok(Self {
+        Self {
             id,
-            writer: Arc::new(Mutex::new(writer)),
-            master: Arc::new(Mutex::new(pair.master)),
+            writer,
+            resizer: Arc::new(Mutex::new(resizer)),
             status,
             shutdown_flag,
             output_buffer,
-        })
+            output_tx,
+            bracketed_paste,
+            keyboard,
+            integration,
+            metrics,
+        }
     }
 
     /// 获取会话 ID
@@ -334,20 +605,14 @@ impl PtySession {
         writer
             .flush()
             .map_err(|e| TerminalError::WriteFailed(e.to_string()))?;
+        self.metrics.record_input(data.len());
         Ok(())
     }
 
     /// 调整 PTY 大小
     pub fn resize(&self, rows: u16, cols: u16) -> Result<(), TerminalError> {
-        let master = self.master.lock();
-        master
-            .resize(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .map_err(|e| TerminalError::ResizeFailed(e.to_string()))?;
+        self.resizer.lock().resize(rows, cols)?;
+        self.metrics.record_resize();
         tracing::debug!("[终端] 会话 {} 调整大小为 {}x{}", self.id, cols, rows);
         Ok(())
     }
@@ -357,6 +622,42 @@ impl PtySession {
         *self.status.read().await
     }
 
+    /// 终端程序是否启用了括号粘贴模式
+    pub fn bracketed_paste(&self) -> bool {
+        self.bracketed_paste.load(Ordering::Relaxed)
+    }
+
+    /// 按 `TERM` 设置按键编码配置
+    pub fn set_keymap_profile(&self, profile: KeymapProfile) {
+        self.keyboard.lock().set_profile(profile);
+    }
+
+    /// 按当前键盘模式编码按键（未知按键返回 `None`）
+    pub fn encode_key(&self, chord: &KeyChord) -> Option<Vec<u8>> {
+        self.keyboard.lock().encode(chord)
+    }
+
+    /// 获取 Shell 集成处理器
+    pub fn integration(&self) -> Option<&Arc<ShellIntegration>> {
+        self.integration.as_ref()
+    }
+
+    /// 获取会话指标快照
+    pub fn metrics(&self) -> TerminalMetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// 获取输出订阅和输入写入句柄
+    pub fn tap(&self) -> SessionTap {
+        SessionTap {
+            output: self.output_tx.clone(),
+            output_buffer: self.output_buffer.clone(),
+            writer: self.writer.clone(),
+            status: self.status.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+
     /// 获取输出历史数据（Base64 编码）
     pub fn get_output_history(&self) -> String {
         let buffer = self.output_buffer.lock();
@@ -372,7 +673,212 @@ impl PtySession {
         // 更新状态
         *self.status.write().await = SessionStatus::Done;
 
-        tracing::info!("[终端] 会话 {} 已关闭", self.id);
+        let metrics = self.metrics.snapshot();
+        tracing::info!(
+            "[终端] 会话 {} 已关闭（输入 {} 字节，输出 {} 字节，回显延迟 p50 {}ms / p95 {}ms）",
+            self.id,
+            metrics.bytes_in,
+            metrics.bytes_out,
+            metrics.echo_latency.p50_ms,
+            metrics.echo_latency.p95_ms
+        );
         Ok(())
     }
 }
+
+/// 启动输出发送线程
+///
+/// 合并读取线程交来的输出，按 [`super::flow_control::OUTPUT_EVENT_INTERVAL`] 限制事件频率；
+/// 攒满一个事件时暂停接收，队列随之填满，读取线程阻塞。读取线程退出后发送剩余输出并结束。
+fn spawn_output_emitter(
+    app_handle: tauri::AppHandle,
+    session_id: String,
+    image_cache: Option<Arc<ImageCache>>,
+    items: Receiver<OutputItem>,
+) {
+    std::thread::spawn(move || {
+        let mut coalescer = OutputCoalescer::new();
+        let mut flood = FloodDetector::new(Instant::now());
+
+        loop {
+            let now = Instant::now();
+            if let Some(change) = flood.tick(now) {
+                emit_flood(&app_handle, &session_id, change);
+            }
+            if let Some(data) = coalescer.take_due(now) {
+                emit_output(&app_handle, &session_id, &data);
+            }
+            if coalescer.is_full() {
+                std::thread::sleep(coalescer.wait_time(Instant::now()));
+                continue;
+            }
+
+            // 有待发送输出时等到下次发送时间，洪泛时等到统计窗口结束，否则一直等待
+            let received = if !coalescer.is_empty() {
+                items.recv_timeout(coalescer.wait_time(now))
+            } else if flood.is_flooding() {
+                items.recv_timeout(flood.wait_time(now))
+            } else {
+                items.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            };
+
+            match received {
+                Ok(OutputItem::Data(data)) => {
+                    if let Some(change) = flood.record(data.len(), Instant::now()) {
+                        emit_flood(&app_handle, &session_id, change);
+                    }
+                    coalescer.push(&data);
+                }
+                Ok(OutputItem::Image(image)) => {
+                    if let Some(data) = coalescer.take_all(Instant::now()) {
+                        emit_output(&app_handle, &session_id, &data);
+                    }
+                    emit_image(&app_handle, &session_id, image_cache.as_deref(), image);
+                }
+                Ok(OutputItem::Status(event)) => {
+                    if let Some(data) = coalescer.take_all(Instant::now()) {
+                        emit_output(&app_handle, &session_id, &data);
+                    }
+                    let _ = app_handle.emit(event_names::TERMINAL_STATUS, event);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    if let Some(data) = coalescer.take_all(Instant::now()) {
+                        emit_output(&app_handle, &session_id, &data);
+                    }
+                    if flood.is_flooding() {
+                        emit_flood(
+                            &app_handle,
+                            &session_id,
+                            FloodChange {
+                                flooding: false,
+                                bytes_per_sec: 0,
+                            },
+                        );
+                    }
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// 发送输出洪泛事件
+fn emit_flood(app_handle: &tauri::AppHandle, session_id: &str, change: FloodChange) {
+    if change.flooding {
+        tracing::warn!(
+            "[终端] 会话 {} 输出过快（{} 字节/秒），已限制输出事件频率",
+            session_id,
+            change.bytes_per_sec
+        );
+    } else {
+        tracing::info!("[终端] 会话 {} 输出速率已恢复", session_id);
+    }
+    let _ = app_handle.emit(
+        event_names::OUTPUT_FLOOD,
+        TerminalOutputFloodEvent {
+            session_id: session_id.to_string(),
+            flooding: change.flooding,
+            bytes_per_sec: change.bytes_per_sec,
+        },
+    );
+}
+
+/// 发送输出事件（空数据不发送）
+fn emit_output(app_handle: &tauri::AppHandle, session_id: &str, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    let _ = app_handle.emit(
+        event_names::TERMINAL_OUTPUT,
+        TerminalOutputEvent {
+            session_id: session_id.to_string(),
+            data: BASE64.encode(data),
+        },
+    );
+}
+
+/// 缓存内联图片并发送图片事件
+///
+/// 超过单张图片大小上限的图片直接丢弃；缓存写入失败不影响图片显示。
+fn emit_image(
+    app_handle: &tauri::AppHandle,
+    session_id: &str,
+    cache: Option<&ImageCache>,
+    image: InlineImage,
+) {
+    let max_bytes = cache.map_or(DEFAULT_IMAGE_MAX_BYTES, |c| c.limits().max_image_bytes);
+    if image.data.len() > max_bytes {
+        tracing::warn!(
+            "[InlineImage] 会话 {} 图片超过大小上限，已丢弃: {}x{}, len={}",
+            session_id,
+            image.width,
+            image.height,
+            image.data.len()
+        );
+        return;
+    }
+    if let Some(cache) = cache {
+        if let Err(e) = cache.put(&image.id, &image.mime, &image.data) {
+            tracing::warn!("[InlineImage] 会话 {} 缓存图片失败: {}", session_id, e);
+        }
+    }
+
+    tracing::debug!(
+        "[InlineImage] 会话 {} 收到图片: protocol={:?}, {}x{}",
+        session_id,
+        image.protocol,
+        image.width,
+        image.height
+    );
+    let _ = app_handle.emit(
+        event_names::TERMINAL_IMAGE,
+        TerminalImageEvent {
+            session_id: session_id.to_string(),
+            image_id: image.id,
+            protocol: image.protocol,
+            mime: image.mime,
+            width: image.width,
+            height: image.height,
+            name: image.name,
+            display_width: image.display_width,
+            display_height: image.display_height,
+            preserve_aspect_ratio: image.preserve_aspect_ratio,
+            data: BASE64.encode(&image.data),
+        },
+    );
+}
+
+/// 获取用户默认 Shell
+pub(crate) fn default_shell() -> String {
+    std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string())
+}
+
+/// 解析工作目录
+///
+/// 展开 `~`，目录不存在时回退到用户主目录。
+pub(crate) fn resolve_cwd(cwd: Option<String>) -> Option<PathBuf> {
+    let Some(dir) = cwd else {
+        return dirs::home_dir();
+    };
+
+    // 展开 ~ 为用户主目录
+    let expanded_dir = if let Some(rest) = dir.strip_prefix("~/") {
+        dirs::home_dir().map_or_else(|| PathBuf::from(&dir), |home| home.join(rest))
+    } else if dir == "~" {
+        dirs::home_dir().unwrap_or_else(|| PathBuf::from(&dir))
+    } else {
+        PathBuf::from(&dir)
+    };
+
+    if expanded_dir.is_dir() {
+        tracing::info!("[终端] 设置工作目录: {:?}", expanded_dir);
+        Some(expanded_dir)
+    } else {
+        tracing::warn!(
+            "[终端] 工作目录不存在或不是目录: {:?}, 使用主目录",
+            expanded_dir
+        );
+        dirs::home_dir()
+    }
+}
@@ -0,0 +1,631 @@
+//! 终端会话回放
+//!
+//! 按块文件的时间索引回放录制的终端输出，推送到前端只读的回放视图。
+//!
+//! ## 功能
+//! - 可调播放速度（0.1x ~ 16x），超过 `max_idle_ms` 的空闲间隔被压缩
+//! - 播放、暂停、按时间跳转
+//! - 根据 OSC 133 提示符标记（`A`）跳转到指定命令
+//!
+//! ## 跳转说明
+//! 终端画面是输出累积的结果，跳转时先发送重置序列，再一次性发送目标位置之前的全部输出，
+//! 之后从目标位置继续按时间播放。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use super::error::TerminalError;
+use super::events::{event_names, TerminalReplayOutputEvent, TerminalReplayStatusEvent};
+use super::integration::{
+    command_text, OSCParser, OSCSequence, PromptMarkType, TERMINAL_RESET_SEQUENCE,
+};
+use super::persistence::{BlockFile, TimingRecord};
+
+/// 最小播放速度
+pub const MIN_REPLAY_SPEED: f64 = 0.1;
+/// 最大播放速度
+pub const MAX_REPLAY_SPEED: f64 = 16.0;
+/// 默认最大空闲间隔（毫秒），更长的停顿在回放时压缩为该值
+pub const DEFAULT_MAX_IDLE_MS: u64 = 2000;
+/// 播放推进间隔
+const REPLAY_TICK: Duration = Duration::from_millis(33);
+
+/// 一次写入在回放时间轴上的位置
+#[derive(Debug, Clone, Copy)]
+struct ReplayFrame {
+    /// 回放时间（毫秒，相对录制开始）
+    at_ms: u64,
+    /// 该次写入结束处的字节偏移
+    end: usize,
+}
+
+/// 命令标记（OSC 133 提示符开始）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandMarker {
+    /// 命令序号（从 0 开始）
+    pub index: usize,
+    /// 回放时间（毫秒）
+    pub position_ms: u64,
+    /// 提示符在录制数据中的字节偏移
+    pub byte_offset: usize,
+    /// 执行的命令文本（来自 OSC 133 `B` 与 `C` 之间的回显，可能为空）
+    pub command: Option<String>,
+}
+
+/// 录制内容
+#[derive(Debug)]
+pub struct ReplayRecording {
+    data: Vec<u8>,
+    frames: Vec<ReplayFrame>,
+    markers: Vec<CommandMarker>,
+    duration_ms: u64,
+}
+
+impl ReplayRecording {
+    /// 从块文件加载录制内容
+    pub fn from_block_file(
+        block_file: &BlockFile,
+        max_idle_ms: u64,
+    ) -> Result<Self, TerminalError> {
+        let (data, records) = block_file.read_with_timing()?;
+        Ok(Self::new(data, &records, max_idle_ms))
+    }
+
+    /// 由原始数据和时间记录构建录制内容
+    ///
+    /// `records` 的字节数之和需与 `data` 长度一致（见 [`BlockFile::read_with_timing`]）
+    pub fn new(data: Vec<u8>, records: &[TimingRecord], max_idle_ms: u64) -> Self {
+        let mut frames = Vec::with_capacity(records.len());
+        let mut at_ms = 0u64;
+        let mut end = 0usize;
+        let mut prev_ts = records.first().map_or(0, |r| r.timestamp_ms);
+        for record in records {
+            let gap = record.timestamp_ms.saturating_sub(prev_ts).max(0) as u64;
+            at_ms += gap.min(max_idle_ms);
+            prev_ts = record.timestamp_ms;
+            end = (end + record.len).min(data.len());
+            frames.push(ReplayFrame { at_ms, end });
+        }
+
+        let mut recording = Self {
+            data,
+            frames,
+            markers: Vec::new(),
+            duration_ms: at_ms,
+        };
+        recording.markers = recording.find_command_markers();
+        recording
+    }
+
+    /// 总时长（毫秒）
+    pub fn duration_ms(&self) -> u64 {
+        self.duration_ms
+    }
+
+    /// 命令标记列表
+    pub fn markers(&self) -> &[CommandMarker] {
+        &self.markers
+    }
+
+    /// 录制数据长度
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// 是否没有录制数据
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// 回放到 `position_ms` 时应已输出的字节数
+    fn offset_at(&self, position_ms: u64) -> usize {
+        let count = self.frames.partition_point(|f| f.at_ms <= position_ms);
+        count.checked_sub(1).map_or(0, |i| self.frames[i].end)
+    }
+
+    /// 字节偏移处的数据被写入时的回放时间
+    fn time_at(&self, byte_offset: usize) -> u64 {
+        let index = self.frames.partition_point(|f| f.end <= byte_offset);
+        self.frames.get(index).map_or(self.duration_ms, |f| f.at_ms)
+    }
+
+    /// 解析 OSC 133 标记，每个提示符开始（`A`）对应一条命令
+    ///
+    /// 命令文本优先取 Shell 通过 `setcmd` 上报的内容，没有时从命令回显中提取。
+    fn find_command_markers(&self) -> Vec<CommandMarker> {
+        let mut markers: Vec<CommandMarker> = Vec::new();
+        let mut command_start = None;
+        let mut reported_command = None;
+        for osc in OSCParser::parse(&self.data) {
+            let mark_type = match osc.sequence {
+                OSCSequence::PromptMark { mark_type, .. } => mark_type,
+                OSCSequence::WaveCommand { command } => {
+                    if let Some(text) = command.strip_prefix("setcmd ") {
+                        reported_command = Some(text.trim_end().to_string());
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            match mark_type {
+                PromptMarkType::PromptStart => {
+                    markers.push(CommandMarker {
+                        index: markers.len(),
+                        position_ms: self.time_at(osc.range.start),
+                        byte_offset: osc.range.start,
+                        command: None,
+                    });
+                    command_start = None;
+                    reported_command = None;
+                }
+                PromptMarkType::CommandStart => command_start = Some(osc.range.end),
+                PromptMarkType::CommandExecuted => {
+                    let echo_start = command_start.take();
+                    if let Some(marker) = markers.last_mut() {
+                        marker.command = reported_command
+                            .take()
+                            .filter(|text| !text.is_empty())
+                            .or_else(|| {
+                                echo_start.and_then(|start| {
+                                    command_text(&self.data[start..osc.range.start])
+                                })
+                            });
+                    }
+                }
+                _ => {}
+            }
+        }
+        markers
+    }
+}
+
+/// 回放状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayState {
+    /// 播放中
+    Playing,
+    /// 已暂停
+    Paused,
+    /// 已播放完毕
+    Finished,
+}
+
+/// 回放进度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayStatus {
+    /// 回放状态
+    pub state: ReplayState,
+    /// 当前回放时间（毫秒）
+    pub position_ms: u64,
+    /// 总时长（毫秒）
+    pub duration_ms: u64,
+    /// 播放速度
+    pub speed: f64,
+    /// 当前所在的命令序号
+    pub command_index: Option<usize>,
+}
+
+/// 回放控制指令
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ReplayCommand {
+    /// 播放（播放完毕后从头开始）
+    Play,
+    /// 暂停
+    Pause,
+    /// 设置播放速度
+    SetSpeed { speed: f64 },
+    /// 跳转到指定时间
+    Seek { position_ms: u64 },
+    /// 跳转到指定命令
+    SeekToCommand { index: usize },
+}
+
+/// 回放播放器
+///
+/// 维护回放进度，每个操作返回需要写入回放视图的数据
+pub struct ReplayPlayer {
+    recording: Arc<ReplayRecording>,
+    /// 已输出的字节数
+    cursor: usize,
+    position_ms: f64,
+    speed: f64,
+    state: ReplayState,
+}
+
+impl ReplayPlayer {
+    /// 创建播放器，初始为暂停状态
+    pub fn new(recording: Arc<ReplayRecording>, speed: f64) -> Self {
+        Self {
+            recording,
+            cursor: 0,
+            position_ms: 0.0,
+            speed: clamp_speed(speed),
+            state: ReplayState::Paused,
+        }
+    }
+
+    /// 当前回放状态
+    pub fn state(&self) -> ReplayState {
+        self.state
+    }
+
+    /// 执行控制指令，返回需要写入回放视图的数据
+    pub fn apply(&mut self, command: ReplayCommand) -> Result<Vec<u8>, TerminalError> {
+        match command {
+            ReplayCommand::Play => {
+                let output = if self.state == ReplayState::Finished {
+                    self.seek(0)
+                } else {
+                    Vec::new()
+                };
+                self.state = ReplayState::Playing;
+                Ok(output)
+            }
+            ReplayCommand::Pause => {
+                if self.state == ReplayState::Playing {
+                    self.state = ReplayState::Paused;
+                }
+                Ok(Vec::new())
+            }
+            ReplayCommand::SetSpeed { speed } => {
+                self.speed = clamp_speed(speed);
+                Ok(Vec::new())
+            }
+            ReplayCommand::Seek { position_ms } => Ok(self.seek(position_ms)),
+            ReplayCommand::SeekToCommand { index } => {
+                let marker = self
+                    .recording
+                    .markers
+                    .get(index)
+                    .ok_or_else(|| TerminalError::Internal(format!("命令不存在: {}", index)))?;
+                let (byte_offset, position_ms) = (marker.byte_offset, marker.position_ms);
+                Ok(self.jump(byte_offset, position_ms))
+            }
+        }
+    }
+
+    /// 按经过的真实时间推进回放
+    pub fn advance(&mut self, elapsed: Duration) -> Vec<u8> {
+        if self.state != ReplayState::Playing {
+            return Vec::new();
+        }
+        let duration_ms = self.recording.duration_ms as f64;
+        self.position_ms =
+            (self.position_ms + elapsed.as_secs_f64() * 1000.0 * self.speed).min(duration_ms);
+
+        let target = self
+            .recording
+            .offset_at(self.position_ms as u64)
+            .max(self.cursor);
+        let output = self.recording.data[self.cursor..target].to_vec();
+        self.cursor = target;
+
+        if self.position_ms >= duration_ms && self.cursor >= self.recording.len() {
+            self.state = ReplayState::Finished;
+        }
+        output
+    }
+
+    /// 当前回放进度
+    pub fn status(&self) -> ReplayStatus {
+        let command_index = self
+            .recording
+            .markers
+            .partition_point(|m| m.byte_offset < self.cursor)
+            .checked_sub(1);
+        ReplayStatus {
+            state: self.state,
+            position_ms: self.position_ms as u64,
+            duration_ms: self.recording.duration_ms,
+            speed: self.speed,
+            command_index,
+        }
+    }
+
+    fn seek(&mut self, position_ms: u64) -> Vec<u8> {
+        let position_ms = position_ms.min(self.recording.duration_ms);
+        let byte_offset = self.recording.offset_at(position_ms);
+        self.jump(byte_offset, position_ms)
+    }
+
+    /// 跳转到字节偏移：重置画面并一次性输出之前的全部数据
+    fn jump(&mut self, byte_offset: usize, position_ms: u64) -> Vec<u8> {
+        self.cursor = byte_offset.min(self.recording.len());
+        self.position_ms = position_ms as f64;
+        if self.state == ReplayState::Finished {
+            self.state = ReplayState::Paused;
+        }
+
+        let mut output = TERMINAL_RESET_SEQUENCE.to_vec();
+        output.extend_from_slice(&self.recording.data[..self.cursor]);
+        output
+    }
+}
+
+fn clamp_speed(speed: f64) -> f64 {
+    if speed.is_finite() {
+        speed.clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED)
+    } else {
+        1.0
+    }
+}
+
+/// 回放信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayInfo {
+    /// 回放 ID
+    pub replay_id: String,
+    /// 录制来源的块 ID
+    pub block_id: String,
+    /// 总时长（毫秒）
+    pub duration_ms: u64,
+    /// 命令标记
+    pub markers: Vec<CommandMarker>,
+    /// 初始播放速度
+    pub speed: f64,
+}
+
+/// 运行中的回放
+pub struct ReplayHandle {
+    info: ReplayInfo,
+    commands: mpsc::UnboundedSender<ReplayCommand>,
+    task: JoinHandle<()>,
+}
+
+impl ReplayHandle {
+    /// 启动回放任务，初始为暂停状态
+    ///
+    /// 前端订阅回放事件后发送 `Play` 开始播放，避免丢失开头的输出
+    pub fn spawn(
+        replay_id: String,
+        block_id: String,
+        recording: ReplayRecording,
+        speed: f64,
+        app_handle: tauri::AppHandle,
+    ) -> Self {
+        let recording = Arc::new(recording);
+        let player = ReplayPlayer::new(recording.clone(), speed);
+        let info = ReplayInfo {
+            replay_id: replay_id.clone(),
+            block_id,
+            duration_ms: recording.duration_ms(),
+            markers: recording.markers().to_vec(),
+            speed: player.status().speed,
+        };
+        let (commands, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_replay(replay_id, player, rx, app_handle));
+        Self {
+            info,
+            commands,
+            task,
+        }
+    }
+
+    /// 回放信息
+    pub fn info(&self) -> &ReplayInfo {
+        &self.info
+    }
+
+    /// 发送控制指令
+    pub fn send(&self, command: ReplayCommand) -> Result<(), TerminalError> {
+        if let ReplayCommand::SeekToCommand { index } = command {
+            if index >= self.info.markers.len() {
+                return Err(TerminalError::Internal(format!("命令不存在: {}", index)));
+            }
+        }
+        self.commands
+            .send(command)
+            .map_err(|_| TerminalError::Internal("回放已结束".to_string()))
+    }
+
+    /// 停止回放
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+/// 回放任务：处理控制指令并按节拍推送输出
+async fn run_replay(
+    replay_id: String,
+    mut player: ReplayPlayer,
+    mut commands: mpsc::UnboundedReceiver<ReplayCommand>,
+    app_handle: tauri::AppHandle,
+) {
+    let mut ticker = tokio::time::interval(REPLAY_TICK);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last_tick = Instant::now();
+    emit_status(&app_handle, &replay_id, &player);
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                let Some(command) = command else {
+                    break;
+                };
+                match player.apply(command) {
+                    Ok(output) => emit_output(&app_handle, &replay_id, &output),
+                    Err(e) => tracing::warn!("[终端] 回放 {} 指令失败: {}", replay_id, e),
+                }
+                last_tick = Instant::now();
+                emit_status(&app_handle, &replay_id, &player);
+            }
+            _ = ticker.tick(), if player.state() == ReplayState::Playing => {
+                let now = Instant::now();
+                let output = player.advance(now - last_tick);
+                last_tick = now;
+                emit_output(&app_handle, &replay_id, &output);
+                if player.state() == ReplayState::Finished {
+                    emit_status(&app_handle, &replay_id, &player);
+                }
+            }
+        }
+    }
+
+    tracing::debug!("[终端] 回放 {} 已结束", replay_id);
+}
+
+fn emit_output(app_handle: &tauri::AppHandle, replay_id: &str, output: &[u8]) {
+    if output.is_empty() {
+        return;
+    }
+    let _ = app_handle.emit(
+        event_names::TERMINAL_REPLAY_OUTPUT,
+        TerminalReplayOutputEvent {
+            replay_id: replay_id.to_string(),
+            data: BASE64.encode(output),
+        },
+    );
+}
+
+fn emit_status(app_handle: &tauri::AppHandle, replay_id: &str, player: &ReplayPlayer) {
+    let _ = app_handle.emit(
+        event_names::TERMINAL_REPLAY_STATUS,
+        TerminalReplayStatusEvent {
+            replay_id: replay_id.to_string(),
+            status: player.status(),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 三条命令的录制：每次写入间隔 1 秒，第二次写入后停顿 60 秒
+    fn recording() -> ReplayRecording {
+        let chunks: [&[u8]; 4] = [
+            b"\x1b]133;A\x07$ \x1b]133;B\x07ls\x1b]133;C\x07",
+            b"a.txt\r\n\x1b]133;D;0\x07",
+            b"\x1b]133;A\x07$ \x1b]133;B\x07echo \x1b[1mhi\x1b[0m\x1b]133;C\x07hi\r\n",
+            b"\x1b]133;A\x07$ ",
+        ];
+        let timestamps = [1_000, 2_000, 62_000, 63_000];
+        let data: Vec<u8> = chunks.concat();
+        let records: Vec<TimingRecord> = chunks
+            .iter()
+            .zip(timestamps)
+            .map(|(chunk, ts)| TimingRecord::new(ts, chunk.len()))
+            .collect();
+        ReplayRecording::new(data, &records, DEFAULT_MAX_IDLE_MS)
+    }
+
+    #[test]
+    fn test_recording_compresses_idle_time() {
+        let recording = recording();
+        // 1s + 压缩后的 2s + 1s
+        assert_eq!(recording.duration_ms(), 4_000);
+    }
+
+    #[test]
+    fn test_command_markers() {
+        let recording = recording();
+        let markers = recording.markers();
+        assert_eq!(markers.len(), 3);
+        assert_eq!(markers[0].command.as_deref(), Some("ls"));
+        assert_eq!(markers[1].command.as_deref(), Some("echo hi"));
+        assert_eq!(markers[2].command, None);
+        assert_eq!(
+            markers.iter().map(|m| m.position_ms).collect::<Vec<_>>(),
+            vec![0, 3_000, 4_000]
+        );
+        assert_eq!(markers[0].byte_offset, 0);
+    }
+
+    #[test]
+    fn test_command_markers_prefer_reported_command() {
+        let data = b"\x1b]133;A\x07$ \x1b]133;B\x07\x1b[7mgit st\x1b[0m\x1b]16162;setcmd git status\x1b\\\x1b]133;C\x07";
+        let recording = ReplayRecording::new(data.to_vec(), &[TimingRecord::new(0, data.len())], 0);
+        assert_eq!(
+            recording.markers()[0].command.as_deref(),
+            Some("git status")
+        );
+    }
+
+    #[test]
+    fn test_player_advance_with_speed() {
+        let recording = Arc::new(recording());
+        let mut player = ReplayPlayer::new(recording.clone(), 2.0);
+
+        // 暂停时不推进
+        assert!(player.advance(Duration::from_secs(1)).is_empty());
+
+        player.apply(ReplayCommand::Play).unwrap();
+        let first = player.advance(Duration::from_millis(100));
+        assert_eq!(first.len(), recording.frames[0].end);
+
+        // 2 倍速下 1 秒推进 2 秒回放时间
+        player.advance(Duration::from_secs(1));
+        assert_eq!(player.status().position_ms, 2_200);
+        assert_eq!(player.status().command_index, Some(0));
+
+        player.advance(Duration::from_secs(5));
+        assert_eq!(player.state(), ReplayState::Finished);
+        assert_eq!(player.status().position_ms, 4_000);
+        assert_eq!(player.status().command_index, Some(2));
+    }
+
+    #[test]
+    fn test_seek_to_command() {
+        let recording = Arc::new(recording());
+        let mut player = ReplayPlayer::new(recording.clone(), 1.0);
+
+        let output = player
+            .apply(ReplayCommand::SeekToCommand { index: 1 })
+            .unwrap();
+        let offset = recording.markers()[1].byte_offset;
+        assert!(output.starts_with(TERMINAL_RESET_SEQUENCE));
+        assert_eq!(
+            &output[TERMINAL_RESET_SEQUENCE.len()..],
+            &recording.data[..offset]
+        );
+        assert_eq!(player.status().position_ms, 3_000);
+        assert_eq!(player.state(), ReplayState::Paused);
+
+        // 从命令提示符处继续播放同一次写入的剩余部分
+        player.apply(ReplayCommand::Play).unwrap();
+        let rest = player.advance(Duration::from_millis(1));
+        assert_eq!(&rest[..], &recording.data[offset..recording.frames[2].end]);
+        assert_eq!(player.status().command_index, Some(1));
+
+        assert!(player
+            .apply(ReplayCommand::SeekToCommand { index: 9 })
+            .is_err());
+    }
+
+    #[test]
+    fn test_play_after_finish_restarts() {
+        let mut player = ReplayPlayer::new(Arc::new(recording()), 16.0);
+        player.apply(ReplayCommand::Play).unwrap();
+        player.advance(Duration::from_secs(1));
+        assert_eq!(player.state(), ReplayState::Finished);
+
+        // 从头开始：重置画面并输出时间点 0 的写入
+        let output = player.apply(ReplayCommand::Play).unwrap();
+        assert!(output.starts_with(TERMINAL_RESET_SEQUENCE));
+        assert!(output.ends_with(b"\x1b]133;C\x07"));
+        assert_eq!(player.state(), ReplayState::Playing);
+        assert_eq!(player.status().position_ms, 0);
+    }
+
+    #[test]
+    fn test_command_deserialize_and_speed_clamp() {
+        let command: ReplayCommand =
+            serde_json::from_str(r#"{"action":"seek_to_command","index":2}"#).unwrap();
+        assert_eq!(command, ReplayCommand::SeekToCommand { index: 2 });
+
+        let mut player = ReplayPlayer::new(Arc::new(recording()), 100.0);
+        assert_eq!(player.status().speed, MAX_REPLAY_SPEED);
+        player
+            .apply(ReplayCommand::SetSpeed { speed: 0.0 })
+            .unwrap();
+        assert_eq!(player.status().speed, MIN_REPLAY_SPEED);
+    }
+}
@@ -8,6 +8,20 @@
 //! - 集成 BlockFile 进行输出持久化
 //! - 集成 SessionMetadataStore 进行元数据存储
 //! - 支持会话状态生命周期管理
+//! - 从块文件回放录制的会话
+//! - 后台会话：Shell 托管在 tmux 中，应用重启后重新连接并回填滚动历史
+//! - Mosh 会话：在 PTY 中运行本机 mosh 客户端
+//! - Telnet / 原始 TCP / 串口会话：字节流直接接入终端
+//! - Docker 会话：通过 Docker Engine API 在容器中执行 Shell
+//! - Kubernetes 会话：在 PTY 中运行本机 `kubectl exec`
+//! - 启动配置：按保存的 Shell、参数、环境变量、工作目录和连接创建会话，并执行启动命令
+//! - 粘贴：按终端程序的括号粘贴模式包裹内容，包含换行或控制字符时等待用户确认
+//! - 输出历史存储：可按会话指定回滚缓冲大小，被覆盖的输出压缩保存，按保留策略清理已结束会话
+//! - 会话分享：生成一次性令牌，通过代理服务器的 WebSocket 只读观看或协作
+//! - 工作区：会话按标签页和分屏布局分组，布局持久化后在启动时随可恢复的会话一起恢复
+//! - 会话指标：输入 / 输出字节数、输出速率、回显延迟估算和调整大小次数，用于诊断终端卡顿
+//! - 提示符索引：OSC 133 提示符位置（含块文件中的历史输出），供跳转到提示符和重新执行命令
+//! - 按键：按会话的 `TERM`、终端程序启用的键盘模式和自定义按键绑定把按键组合编码为转义序列
 //!
 //! ## Requirements
 //! - 3.1: 终端会话创建时创建对应的 Block_File
@@ -16,23 +30,52 @@
 //! - 3.8: Block_File 读取失败时返回错误并允许创建新会话
 //! - 3.9: 会话关闭时更新会话元数据状态为已完成
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::Utc;
+use portable_pty::CommandBuilder;
 use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::database::DbConnection;
+use crate::telemetry::TerminalMetricsSnapshot;
 
 use super::block_controller::ControllerRegistry;
+use super::connections::{
+    find_kubectl, find_mosh_client, is_local_conn_name, ConnectionRouter, ConnectionType,
+    DockerConn, DockerOpts, K8sOpts, MoshOpts, SerialConn, SerialOpts, TelnetConn, TelnetOpts,
+};
+use super::detached::{DetachedSessionBackend, DetachedSessionInfo, DETACHED_HISTORY_LIMIT};
 use super::error::TerminalError;
-use super::events::SessionStatus;
-use super::persistence::{BlockFile, SessionMetadataStore, SessionRecord};
-use super::pty_session::{PtySession, DEFAULT_COLS, DEFAULT_ROWS};
+use super::events::{event_names, SessionStatus, TerminalOutputEvent};
+use super::integration::{
+    encode_paste, CommandBlock, CommandBlockQuery, CommandOutput, CommandOutputFormat, PasteGuard,
+    PasteOutcome, PromptMarks, ShellIntegration, ShellIntegrationStatus, ShellLaunchBuilder,
+    ShellLaunchConfig,
+};
+use super::keymap::{KeyChord, KeymapProfile};
+use super::persistence::block_storage::{self, validate_scrollback_bytes};
+use super::persistence::{
+    BlockFile, BlockPruneReport, BlockStorageSettings, BlockUsage, KeymapSettings, LaunchProfile,
+    SessionMetadataStore, SessionRecord,
+};
+use super::pty_session::{
+    default_shell, resolve_cwd, PtySession, StreamParts, DEFAULT_COLS, DEFAULT_ROWS,
+};
+use super::replay::{
+    ReplayCommand, ReplayHandle, ReplayInfo, ReplayRecording, DEFAULT_MAX_IDLE_MS,
+};
+use super::share::{
+    ShareMode, TerminalShareInfo, TerminalShareRegistry, TerminalShareTicket,
+    DEFAULT_SHARE_TTL_SECS,
+};
+use super::workspace::{PaneLayout, PanePlacement, TerminalWorkspace, DEFAULT_WORKSPACE_ID};
 
 /// 会话元数据（用于前端展示）
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +100,9 @@ pub struct SessionMetadata {
     pub cols: u16,
     /// 退出码
     pub exit_code: Option<i32>,
+    /// 是否为后台会话（应用重启后可重新连接）
+    #[serde(default)]
+    pub detached: bool,
 }
 
 impl SessionMetadata {
@@ -80,10 +126,36 @@ impl SessionMetadata {
             rows,
             cols,
             exit_code: record.exit_code,
+            detached: false,
+        }
+    }
+
+    /// 从存活的后台会话创建
+    pub fn from_detached(info: &DetachedSessionInfo, rows: u16, cols: u16) -> Self {
+        Self {
+            id: info.block_id.clone(),
+            block_id: info.block_id.clone(),
+            tab_id: DEFAULT_WORKSPACE_ID.to_string(),
+            controller_type: "shell".to_string(),
+            connection: None,
+            status: SessionStatus::Running,
+            created_at: info.created_at,
+            rows,
+            cols,
+            exit_code: None,
+            detached: true,
         }
     }
 }
 
+/// 远程连接会话的来源
+enum RemoteSession {
+    /// 在 PTY 中运行的本机客户端（mosh、kubectl）
+    Command(CommandBuilder),
+    /// Telnet / 原始 TCP / 串口 / Docker exec 字节流
+    Stream(StreamParts),
+}
+
 /// 内部会话数据
 struct SessionData {
     /// 会话元数据
@@ -91,6 +163,8 @@ struct SessionData {
     /// 块文件存储
     block_file: Arc<BlockFile>,
     /// 旧版 PTY 会话（兼容模式）
+    ///
+    /// 恢复的后台会话在前端调用 `attach_session` 之前为 `None`。
     legacy_pty: Option<PtySession>,
 }
 
@@ -114,6 +188,20 @@ pub struct TerminalSessionManager {
     session_store: Option<Arc<SessionMetadataStore>>,
     /// 块文件基础目录
     block_file_base_dir: PathBuf,
+    /// 块文件存储设置
+    storage_settings: RwLock<BlockStorageSettings>,
+    /// 自定义按键绑定
+    keymap_settings: RwLock<KeymapSettings>,
+    /// 运行中的回放
+    replays: Arc<RwLock<HashMap<String, ReplayHandle>>>,
+    /// 后台会话后端（tmux 不可用时为 `None`）
+    detached_backend: Option<Arc<DetachedSessionBackend>>,
+    /// 等待确认的粘贴
+    paste_guard: PasteGuard,
+    /// 工作区（标签页与分屏布局）
+    ///
+    /// 与 `sessions` 同时加锁时先锁 `sessions`。
+    workspaces: RwLock<HashMap<String, TerminalWorkspace>>,
     /// Tauri 应用句柄
     app_handle: tauri::AppHandle,
 }
@@ -127,9 +215,36 @@ impl TerminalSessionManager {
         let block_file_base_dir = BlockFile::default_base_dir()
             .unwrap_or_else(|_| PathBuf::from(".proxycast/terminal_blocks"));
 
+        let detached_backend = DetachedSessionBackend::detect(&block_file_base_dir).map(Arc::new);
+
+        // 启动时清理过期的输出历史，保留 tmux 中存活的后台会话（无法列出后台会话时不清理）
+        let storage_settings = BlockStorageSettings::load(&block_file_base_dir);
+        let protected = match &detached_backend {
+            Some(backend) => backend
+                .list_sessions()
+                .map(|infos| infos.into_iter().map(|info| info.block_id).collect())
+                .ok(),
+            None => Some(HashSet::new()),
+        };
+        if let Some(protected) = protected {
+            if let Err(e) = block_storage::prune_blocks(
+                &block_file_base_dir,
+                &storage_settings.retention,
+                &protected,
+                SystemTime::now(),
+            ) {
+                tracing::warn!("[终端] 清理输出历史失败: {}", e);
+            }
+        }
+
         tracing::info!(
-            "[终端] 会话管理器已初始化，块文件目录: {:?}",
-            block_file_base_dir
+            "[终端] 会话管理器已初始化，块文件目录: {:?}，后台会话: {}",
+            block_file_base_dir,
+            if detached_backend.is_some() {
+                "可用"
+            } else {
+                "不可用（未找到 tmux）"
+            }
         );
 
         Self {
@@ -137,6 +252,12 @@ impl TerminalSessionManager {
             controller_registry: Arc::new(ControllerRegistry::new()),
             session_store: None,
             block_file_base_dir,
+            storage_settings: RwLock::new(storage_settings),
+            keymap_settings: RwLock::new(KeymapSettings::load(&block_file_base_dir)),
+            replays: Arc::new(RwLock::new(HashMap::new())),
+            detached_backend,
+            paste_guard: PasteGuard::new(app_handle.clone()),
+            workspaces: RwLock::new(HashMap::new()),
             app_handle,
         }
     }
@@ -158,6 +279,38 @@ impl TerminalSessionManager {
         let session_store = SessionMetadataStore::new(db);
         session_store.init_tables()?;
 
+        // 恢复工作区布局，移除已无法恢复的会话（无法列出后台会话时保留全部窗格）
+        let restorable = match &manager.detached_backend {
+            Some(backend) => backend
+                .list_sessions()
+                .map(|infos| infos.into_iter().map(|info| info.block_id).collect())
+                .ok(),
+            None => Some(HashSet::new()),
+        };
+        let restorable = restorable.map(|mut ids: HashSet<String>| {
+            ids.extend(
+                session_store
+                    .get_by_status("running")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|record| record.id),
+            );
+            ids
+        });
+        let mut workspaces = HashMap::new();
+        for mut workspace in session_store.get_workspaces()? {
+            if let Some(restorable) = &restorable {
+                if workspace.retain_sessions(|id| restorable.contains(id)) {
+                    if let Err(e) = session_store.save_workspace(&workspace) {
+                        tracing::warn!("[终端] 保存工作区 {} 失败: {}", workspace.id, e);
+                    }
+                }
+            }
+            workspaces.insert(workspace.id.clone(), workspace);
+        }
+        tracing::info!("[终端] 恢复了 {} 个工作区", workspaces.len());
+        *manager.workspaces.get_mut() = workspaces;
+
         manager.session_store = Some(Arc::new(session_store));
 
         tracing::info!("[终端] 会话管理器已初始化（带数据库支持）");
@@ -174,6 +327,11 @@ impl TerminalSessionManager {
         self.session_store.as_ref()
     }
 
+    /// 是否支持后台会话
+    pub fn detached_supported(&self) -> bool {
+        self.detached_backend.is_some()
+    }
+
     /// 创建新的终端会话
     ///
     /// 使用默认大小 (24x80) 创建 PTY 会话。
@@ -204,15 +362,24 @@ impl TerminalSessionManager {
         rows: u16,
         cols: u16,
     ) -> Result<String, TerminalError> {
-        self.create_session_with_options(rows, cols, None).await
+        self.create_session_with_options(rows, cols, None, false, None, None)
+            .await
+            .map(|metadata| metadata.id)
     }
 
-    /// 创建新的终端会话（指定大小和工作目录）
+    /// 创建新的终端会话（指定大小、工作目录、是否为后台会话和连接）
+    ///
+    /// 请求后台会话但 tmux 不可用时回退为普通会话，返回的会话元数据中 `detached` 为 `false`。
+    /// 支持本地、Mosh、Telnet / 原始 TCP、串口、Docker 和 Kubernetes 连接；远程连接不托管在 tmux 中。
     ///
     /// # 参数
     /// - `rows`: 终端行数
     /// - `cols`: 终端列数
     /// - `cwd`: 工作目录（可选）
+    /// - `detached`: 是否创建后台会话
+    /// - `connection`: 连接名称（可选，如 `mosh://user@host`、`telnet://host`、
+    ///   `serial:///dev/ttyUSB0`、`docker://web`、`k8s://default/web-0`，为空时为本地 Shell）
+    /// - `scrollback_bytes`: 回滚缓冲大小（可选，为空时使用存储设置中的大小）
     ///
     /// # 返回
     /// - `Ok(String)`: 会话 ID
@@ -224,31 +391,209 @@ impl TerminalSessionManager {
         rows: u16,
         cols: u16,
         cwd: Option<String>,
-    ) -> Result<String, TerminalError> {
+        detached: bool,
+        connection: Option<String>,
+        scrollback_bytes: Option<usize>,
+    ) -> Result<SessionMetadata, TerminalError> {
+        self.create_session_inner(
+            rows,
+            cols,
+            cwd,
+            detached,
+            connection,
+            None,
+            scrollback_bytes,
+        )
+        .await
+    }
+
+    /// 按启动配置创建终端会话
+    ///
+    /// 工作目录和连接取自启动配置；本地会话使用配置中的 Shell、参数和环境变量，
+    /// 远程连接忽略这三项。会话创建后把启动命令输入终端执行。
+    ///
+    /// # 参数
+    /// - `rows`: 终端行数
+    /// - `cols`: 终端列数
+    /// - `profile`: 启动配置
+    /// - `detached`: 是否创建后台会话
+    /// - `scrollback_bytes`: 回滚缓冲大小（可选，为空时使用存储设置中的大小）
+    pub async fn create_session_with_profile(
+        &self,
+        rows: u16,
+        cols: u16,
+        profile: &LaunchProfile,
+        detached: bool,
+        scrollback_bytes: Option<usize>,
+    ) -> Result<SessionMetadata, TerminalError> {
+        tracing::info!("[终端] 使用启动配置 {} ({})", profile.name, profile.id);
+
+        let metadata = self
+            .create_session_inner(
+                rows,
+                cols,
+                profile.cwd.clone(),
+                detached,
+                profile.connection.clone(),
+                Some(profile),
+                scrollback_bytes,
+            )
+            .await?;
+
+        if let Some(command) = profile
+            .startup_command
+            .as_deref()
+            .map(str::trim)
+            .filter(|command| !command.is_empty())
+        {
+            self.write_to_session(&metadata.id, format!("{}\r", command).as_bytes())
+                .await?;
+        }
+        Ok(metadata)
+    }
+
+    /// 创建终端会话（启动配置可选）
+    #[allow(clippy::too_many_arguments)]
+    async fn create_session_inner(
+        &self,
+        rows: u16,
+        cols: u16,
+        cwd: Option<String>,
+        detached: bool,
+        connection: Option<String>,
+        profile: Option<&LaunchProfile>,
+        scrollback_bytes: Option<usize>,
+    ) -> Result<SessionMetadata, TerminalError> {
         let session_id = Uuid::new_v4().to_string();
         let block_id = session_id.clone();
-        let tab_id = "default".to_string(); // TODO: 支持多标签页
+        // 放入工作区后更新（见 `place_session`）
+        let tab_id = DEFAULT_WORKSPACE_ID.to_string();
 
         tracing::info!(
-            "[终端] 创建会话 {}, 大小: {}x{}, cwd: {:?}",
+            "[终端] 创建会话 {}, 大小: {}x{}, cwd: {:?}, detached: {}, connection: {:?}",
             session_id,
             cols,
             rows,
-            cwd
+            cwd,
+            detached,
+            connection
         );
 
+        let connection = connection
+            .map(|conn| conn.trim().to_string())
+            .filter(|conn| !is_local_conn_name(conn));
+        let remote = match connection.as_deref() {
+            Some(conn) => Some(Self::open_remote(conn, rows, cols).await?),
+            None => None,
+        };
+        if remote.is_some()
+            && profile.is_some_and(|p| p.shell.is_some() || !p.args.is_empty() || !p.env.is_empty())
+        {
+            tracing::info!("[终端] 远程连接会话忽略启动配置中的 Shell、参数和环境变量");
+        }
+        let launch = match profile {
+            Some(profile) if remote.is_none() => Some(self.profile_launch(&block_id, profile)?),
+            _ => None,
+        };
+
         // 创建块文件
-        let block_file = BlockFile::with_default_size(&block_id, &self.block_file_base_dir)?;
-        let block_file = Arc::new(block_file);
+        let scrollback_bytes = scrollback_bytes
+            .map(validate_scrollback_bytes)
+            .transpose()?;
+        let block_file = self.open_block_file(&block_id, scrollback_bytes).await?;
 
-        // 创建旧版 PTY 会话（兼容模式）
-        let pty_session = PtySession::with_size_and_cwd(
+        // 创建旧版 PTY 会话（兼容模式），后台会话由 tmux 托管 Shell
+        let backend = match (detached, &self.detached_backend) {
+            (true, _) if remote.is_some() => {
+                tracing::info!("[终端] 远程连接会话不托管在 tmux 中，创建普通会话");
+                None
+            }
+            (true, None) => {
+                tracing::warn!("[终端] 后台会话不可用（未找到 tmux），创建普通会话");
+                None
+            }
+            (true, Some(backend)) => Some(backend),
+            (false, _) => None,
+        };
+        // Shell 集成（目录跟踪、命令历史、链接检测、OSC 52 剪贴板）
+        let integration = Arc::new(ShellIntegration::for_session(
             session_id.clone(),
-            rows,
-            cols,
-            cwd,
             self.app_handle.clone(),
-        )?;
+            connection.as_deref(),
+        ));
+        // 命令块和提示符的位置按块文件已写入的字节数计算，并索引块文件中的历史提示符
+        index_block_history(&integration, &block_file);
+        let integration = Some(integration);
+        let output_file = Some(block_file.clone());
+        // 按键编码配置按会话的 TERM 选择（只有本地非 tmux 会话的 TERM 可由启动配置覆盖）
+        let keymap_profile = match &launch {
+            Some(launch) if remote.is_none() && backend.is_none() => launch
+                .env
+                .get("TERM")
+                .map_or(KeymapProfile::Xterm, |term| KeymapProfile::from_term(term)),
+            _ => KeymapProfile::Xterm,
+        };
+        let pty_session = match (remote, backend) {
+            (Some(RemoteSession::Command(cmd)), _) => PtySession::with_command(
+                session_id.clone(),
+                rows,
+                cols,
+                cmd,
+                integration,
+                output_file,
+                self.app_handle.clone(),
+            )?,
+            (Some(RemoteSession::Stream(parts)), _) => PtySession::with_stream(
+                session_id.clone(),
+                parts,
+                integration,
+                output_file,
+                self.app_handle.clone(),
+            ),
+            (None, Some(backend)) => {
+                let cwd = resolve_cwd(cwd);
+                // tmux 会话内的 TERM 由 tmux 设置
+                let mut launch = launch.unwrap_or_else(|| ShellLaunchConfig::new(default_shell()));
+                launch.env.remove("TERM");
+                let cmd = backend.new_session_command(&block_id, &launch, cwd.as_deref());
+                PtySession::with_command(
+                    session_id.clone(),
+                    rows,
+                    cols,
+                    cmd,
+                    integration,
+                    output_file,
+                    self.app_handle.clone(),
+                )?
+            }
+            (None, None) => match launch {
+                Some(launch) => {
+                    let mut cmd = launch.to_command();
+                    if let Some(dir) = resolve_cwd(cwd) {
+                        cmd.cwd(dir);
+                    }
+                    PtySession::with_command(
+                        session_id.clone(),
+                        rows,
+                        cols,
+                        cmd,
+                        integration,
+                        output_file,
+                        self.app_handle.clone(),
+                    )?
+                }
+                None => PtySession::with_size_and_cwd(
+                    session_id.clone(),
+                    rows,
+                    cols,
+                    cwd,
+                    integration,
+                    output_file,
+                    self.app_handle.clone(),
+                )?,
+            },
+        };
+        pty_session.set_keymap_profile(keymap_profile);
 
         // 创建会话元数据
         let metadata = SessionMetadata {
@@ -256,12 +601,13 @@ impl TerminalSessionManager {
             block_id: block_id.clone(),
             tab_id: tab_id.clone(),
             controller_type: "shell".to_string(),
-            connection: None,
+            connection: connection.clone(),
             status: SessionStatus::Running,
             created_at: Utc::now().timestamp_millis(),
             rows,
             cols,
             exit_code: None,
+            detached: backend.is_some(),
         };
 
         // 保存到数据库
@@ -271,7 +617,7 @@ impl TerminalSessionManager {
                 block_id: block_id.clone(),
                 tab_id: tab_id.clone(),
                 controller_type: "shell".to_string(),
-                connection: None,
+                connection: connection.clone(),
                 status: "running".to_string(),
                 created_at: metadata.created_at,
                 updated_at: metadata.created_at,
@@ -282,7 +628,7 @@ impl TerminalSessionManager {
 
         // 创建会话数据
         let session_data = SessionData {
-            metadata,
+            metadata: metadata.clone(),
             block_file,
             legacy_pty: Some(pty_session),
         };
@@ -292,7 +638,75 @@ impl TerminalSessionManager {
         sessions.insert(session_id.clone(), session_data);
 
         tracing::info!("[终端] 会话 {} 创建成功", session_id);
-        Ok(session_id)
+        Ok(metadata)
+    }
+
+    /// 按启动配置构建本地 Shell 启动配置（带 Shell 集成脚本）
+    fn profile_launch(
+        &self,
+        block_id: &str,
+        profile: &LaunchProfile,
+    ) -> Result<ShellLaunchConfig, TerminalError> {
+        let app_data_dir = self
+            .app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| TerminalError::Internal(format!("获取应用数据目录失败: {}", e)))?;
+        ShellLaunchBuilder::new(&app_data_dir, block_id.to_string()).build_profile(profile)
+    }
+
+    /// 根据连接名称打开远程连接
+    ///
+    /// Telnet 连接和打开串口可能阻塞，放到阻塞线程池中执行。
+    async fn open_remote(conn: &str, rows: u16, cols: u16) -> Result<RemoteSession, TerminalError> {
+        match ConnectionRouter::route(conn) {
+            ConnectionType::Mosh => {
+                let opts = MoshOpts::parse(conn)?;
+                let client = find_mosh_client().ok_or_else(|| {
+                    TerminalError::SSHConnectionFailed("未找到 mosh 客户端，请先安装 mosh".into())
+                })?;
+                tracing::info!("[终端] 使用 mosh 连接: {}", opts);
+                Ok(RemoteSession::Command(opts.build_command(&client)))
+            }
+            ConnectionType::Telnet => {
+                let opts = TelnetOpts::parse(conn)?;
+                tracing::info!("[终端] 使用 Telnet 连接: {}", opts);
+                tokio::task::spawn_blocking(move || TelnetConn::connect(&opts, rows, cols))
+                    .await
+                    .map_err(|e| TerminalError::TelnetConnectionFailed(e.to_string()))?
+                    .map(RemoteSession::Stream)
+            }
+            ConnectionType::Serial => {
+                let opts = SerialOpts::parse(conn)?;
+                tracing::info!("[终端] 使用串口连接: {}", opts);
+                tokio::task::spawn_blocking(move || SerialConn::open(&opts))
+                    .await
+                    .map_err(|e| TerminalError::SerialConnectionFailed(e.to_string()))?
+                    .map(RemoteSession::Stream)
+            }
+            ConnectionType::Docker => {
+                let opts = DockerOpts::parse(conn)?;
+                tracing::info!("[终端] 使用 Docker 连接: {}", opts);
+                tokio::task::spawn_blocking(move || {
+                    DockerConn::from_env()?.exec(&opts, None, rows, cols)
+                })
+                .await
+                .map_err(|e| TerminalError::DockerConnectionFailed(e.to_string()))?
+                .map(RemoteSession::Stream)
+            }
+            ConnectionType::Kubernetes => {
+                let opts = K8sOpts::parse(conn)?;
+                let kubectl = find_kubectl().ok_or_else(|| {
+                    TerminalError::KubernetesConnectionFailed("未找到 kubectl，请先安装".into())
+                })?;
+                tracing::info!("[终端] 使用 Kubernetes 连接: {}", opts);
+                Ok(RemoteSession::Command(opts.build_command(&kubectl, None)))
+            }
+            _ => Err(TerminalError::InvalidConnectionType(format!(
+                "终端会话暂不支持连接 {}",
+                conn
+            ))),
+        }
     }
 
     /// 向会话写入数据（Base64 编码）
@@ -330,17 +744,257 @@ impl TerminalSessionManager {
             .get(session_id)
             .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
 
-        // 使用旧版 PTY 会话写入
+        // 使用旧版 PTY 会话写入（块文件只保存输出，由 PTY 读取线程写入）
         if let Some(pty) = &session.legacy_pty {
             pty.write(data)?;
         }
 
-        // 同时写入块文件（用于持久化）
-        session.block_file.append_data(data)?;
-
         Ok(())
     }
 
+    /// 中断会话的前台进程
+    ///
+    /// 写入 Ctrl+C（ETX），由终端行规程向前台进程组发送 SIGINT。
+    /// 用于输出洪泛时的快捷中断：读取线程因背压阻塞时写入不受影响。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    pub async fn interrupt_session(&self, session_id: &str) -> Result<(), TerminalError> {
+        tracing::info!("[终端] 中断会话 {} 的前台进程", session_id);
+        self.write_to_session(session_id, &[0x03]).await
+    }
+
+    /// 向会话发送按键
+    ///
+    /// 自定义按键绑定优先，否则按会话的 `TERM` 和终端程序启用的键盘模式编码。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `chord`: 按键组合
+    ///
+    /// # 返回
+    /// 是否已写入（无法编码的按键返回 `false`，前端按普通输入处理）
+    pub async fn send_key(
+        &self,
+        session_id: &str,
+        chord: &KeyChord,
+    ) -> Result<bool, TerminalError> {
+        let data = {
+            let sessions = self.sessions.read().await;
+            let session = sessions
+                .get(session_id)
+                .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+            match self.keymap_settings.read().await.lookup(chord) {
+                Some(data) => Some(data),
+                None => session
+                    .legacy_pty
+                    .as_ref()
+                    .and_then(|pty| pty.encode_key(chord)),
+            }
+        };
+        let Some(data) = data else {
+            return Ok(false);
+        };
+        self.write_to_session(session_id, &data).await?;
+        Ok(true)
+    }
+
+    /// 粘贴文本到会话
+    ///
+    /// 终端程序启用括号粘贴模式时包裹粘贴内容。内容包含换行或可疑控制字符且未确认时，
+    /// 发送 `terminal:paste-warning` 事件并等待 `respond_paste`。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `text`: 粘贴的文本
+    /// - `confirmed`: 是否已确认（为 true 时不检查内容）
+    pub async fn paste_to_session(
+        &self,
+        session_id: &str,
+        text: &str,
+        confirmed: bool,
+    ) -> Result<PasteOutcome, TerminalError> {
+        let bracketed = {
+            let sessions = self.sessions.read().await;
+            let session = sessions
+                .get(session_id)
+                .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+            session
+                .legacy_pty
+                .as_ref()
+                .is_some_and(|pty| pty.bracketed_paste())
+        };
+
+        if !confirmed {
+            if let Some(paste_id) = self.paste_guard.check(session_id, text, bracketed)? {
+                return Ok(PasteOutcome::Pending { paste_id });
+            }
+        }
+
+        self.write_to_session(session_id, &encode_paste(text, bracketed))
+            .await?;
+        Ok(PasteOutcome::Written)
+    }
+
+    /// 响应等待确认的粘贴
+    ///
+    /// # 参数
+    /// - `paste_id`: 粘贴 ID
+    /// - `allow`: 是否允许写入
+    pub async fn respond_paste(
+        &self,
+        paste_id: &str,
+        allow: bool,
+    ) -> Result<PasteOutcome, TerminalError> {
+        let paste = self.paste_guard.take(paste_id)?;
+        if !allow {
+            tracing::debug!("[终端] 会话 {} 用户取消粘贴", paste.session_id);
+            return Ok(PasteOutcome::Denied);
+        }
+        // 按写入时的括号粘贴模式重新生成数据
+        self.paste_to_session(&paste.session_id, &paste.text, true)
+            .await
+    }
+
+    /// 查询会话的命令块
+    ///
+    /// 命令块由 Shell 集成在会话连接期间记录，未连接的后台会话返回空列表。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `query`: 查询条件
+    pub async fn command_blocks(
+        &self,
+        session_id: &str,
+        query: &CommandBlockQuery,
+    ) -> Result<Vec<CommandBlock>, TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+        Ok(session
+            .legacy_pty
+            .as_ref()
+            .and_then(|pty| pty.integration())
+            .map(|integration| integration.command_blocks(query))
+            .unwrap_or_default())
+    }
+
+    /// 获取单条命令的输出
+    ///
+    /// 按命令块记录的 OSC 133 输出边界从块文件读取，命令未结束时返回截至当前的输出。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `index`: 命令块序号
+    /// - `format`: 输出格式
+    pub async fn command_output(
+        &self,
+        session_id: &str,
+        index: u64,
+        format: CommandOutputFormat,
+    ) -> Result<CommandOutput, TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+        let block = session
+            .legacy_pty
+            .as_ref()
+            .and_then(|pty| pty.integration())
+            .and_then(|integration| integration.command_block(index))
+            .ok_or(TerminalError::CommandBlockNotFound(index))?;
+
+        let end = block.output_end.unwrap_or(u64::MAX);
+        let (data, truncated) = session.block_file.read_range(block.output_start, end)?;
+        Ok(CommandOutput::new(&block, &data, format, truncated))
+    }
+
+    /// 获取会话的提示符标记
+    ///
+    /// 包含块文件中保留的历史输出里的提示符；没有 Shell 集成的会话返回空列表。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    pub async fn prompt_marks(&self, session_id: &str) -> Result<PromptMarks, TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+        Ok(session
+            .legacy_pty
+            .as_ref()
+            .and_then(|pty| pty.integration())
+            .map(|integration| integration.prompt_marks())
+            .unwrap_or_default())
+    }
+
+    /// 重新执行提示符标记处的命令
+    ///
+    /// 命令按括号粘贴模式写入后发送回车；有命令正在执行时拒绝，避免输入被前台程序读取。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `index`: 提示符标记序号
+    pub async fn rerun_prompt_command(
+        &self,
+        session_id: &str,
+        index: u64,
+    ) -> Result<(), TerminalError> {
+        let input = {
+            let sessions = self.sessions.read().await;
+            let session = sessions
+                .get(session_id)
+                .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+            let pty = session
+                .legacy_pty
+                .as_ref()
+                .ok_or(TerminalError::PromptMarkNotFound(index))?;
+            let integration = pty
+                .integration()
+                .ok_or(TerminalError::PromptMarkNotFound(index))?;
+            if integration.get_status() == ShellIntegrationStatus::RunningCommand {
+                return Err(TerminalError::Internal(
+                    "有命令正在执行，无法重新执行命令".to_string(),
+                ));
+            }
+            let command = integration
+                .prompt_mark(index)
+                .and_then(|mark| mark.command)
+                .ok_or(TerminalError::PromptMarkNotFound(index))?;
+
+            let mut input = encode_paste(&command, pty.bracketed_paste());
+            input.push(b'\r');
+            input
+        };
+
+        tracing::info!(
+            "[终端] 会话 {} 重新执行提示符 {} 处的命令",
+            session_id,
+            index
+        );
+        self.write_to_session(session_id, &input).await
+    }
+
+    /// 获取会话指标（输入 / 输出字节数、输出速率、回显延迟估算、调整大小次数）
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    pub async fn session_metrics(
+        &self,
+        session_id: &str,
+    ) -> Result<TerminalMetricsSnapshot, TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+        Ok(session
+            .legacy_pty
+            .as_ref()
+            .map(|pty| pty.metrics())
+            .unwrap_or_default())
+    }
+
     /// 调整会话终端大小
     ///
     /// # 参数
@@ -379,6 +1033,8 @@ impl TerminalSessionManager {
     ///
     /// _Requirements: 3.9_
     pub async fn close_session(&self, session_id: &str) -> Result<(), TerminalError> {
+        self.paste_guard.discard_session(session_id);
+        TerminalShareRegistry::global().revoke_session(session_id);
         let mut sessions = self.sessions.write().await;
 
         if let Some(mut session) = sessions.remove(session_id) {
@@ -387,11 +1043,26 @@ impl TerminalSessionManager {
                 pty.close().await?;
             }
 
+            // 用户主动关闭的后台会话同时结束 tmux 中的 Shell
+            if session.metadata.detached {
+                if let Some(backend) = &self.detached_backend {
+                    backend.kill_session(&session.metadata.block_id)?;
+                }
+            }
+
             // 更新数据库状态
             if let Some(store) = &self.session_store {
                 store.update_status(session_id, "done", None)?;
             }
 
+            // 从所在工作区的布局中移除
+            let mut workspaces = self.workspaces.write().await;
+            for workspace in workspaces.values_mut() {
+                if workspace.remove_session(session_id) {
+                    self.persist_workspace(workspace)?;
+                }
+            }
+
             tracing::info!("[终端] 会话 {} 已关闭", session_id);
         }
 
@@ -423,6 +1094,16 @@ impl TerminalSessionManager {
         &self,
         session_id: &str,
     ) -> Result<SessionMetadata, TerminalError> {
+        // 已在会话映射表中（重复恢复）
+        if let Some(session) = self.sessions.read().await.get(session_id) {
+            return Ok(session.metadata.clone());
+        }
+
+        // 存活的后台会话，等待前端调用 attach_session 连接
+        if let Some(metadata) = self.restore_detached_session(session_id).await? {
+            return Ok(metadata);
+        }
+
         // 从数据库加载会话记录
         let store = self
             .session_store
@@ -445,8 +1126,7 @@ impl TerminalSessionManager {
         }
 
         // 创建块文件引用
-        let block_file = BlockFile::with_default_size(&record.block_id, &self.block_file_base_dir)?;
-        let block_file = Arc::new(block_file);
+        let block_file = self.open_block_file(&record.block_id, None).await?;
 
         // 读取历史数据
         let _history = block_file.read_all()?;
@@ -501,4 +1181,691 @@ impl TerminalSessionManager {
         tracing::info!("[终端] 加载了 {} 个已保存的会话", result.len());
         Ok(result)
     }
+
+    /// 列出可恢复的后台会话（应用重启后调用）
+    ///
+    /// 只返回 tmux 中存活、且尚未在本次运行中恢复的会话。
+    pub async fn list_detached_sessions(&self) -> Result<Vec<SessionMetadata>, TerminalError> {
+        let Some(backend) = &self.detached_backend else {
+            return Ok(Vec::new());
+        };
+
+        let infos = backend.list_sessions()?;
+        let sessions = self.sessions.read().await;
+        let workspaces = self.workspaces.read().await;
+        let mut result: Vec<SessionMetadata> = infos
+            .iter()
+            .filter(|info| !sessions.contains_key(&info.block_id))
+            .map(|info| {
+                let mut metadata = SessionMetadata::from_detached(info, DEFAULT_ROWS, DEFAULT_COLS);
+                if let Some(workspace) = workspaces.values().find(|w| w.contains(&info.block_id)) {
+                    metadata.tab_id = workspace.id.clone();
+                }
+                metadata
+            })
+            .collect();
+        result.sort_by_key(|m| m.created_at);
+
+        tracing::info!("[终端] 发现 {} 个可恢复的后台会话", result.len());
+        Ok(result)
+    }
+
+    /// 恢复后台会话（只登记会话，不连接 PTY）
+    ///
+    /// # 返回
+    /// 会话不是存活的后台会话时返回 `Ok(None)`
+    async fn restore_detached_session(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<SessionMetadata>, TerminalError> {
+        let Some(backend) = &self.detached_backend else {
+            return Ok(None);
+        };
+        let Some(info) = backend
+            .list_sessions()?
+            .into_iter()
+            .find(|info| info.block_id == session_id)
+        else {
+            return Ok(None);
+        };
+
+        let block_file = self.open_block_file(&info.block_id, None).await?;
+        let mut metadata = SessionMetadata::from_detached(&info, DEFAULT_ROWS, DEFAULT_COLS);
+        if let Some(workspace_id) = self.workspace_of(session_id).await {
+            metadata.tab_id = workspace_id;
+        }
+
+        let mut sessions = self.sessions.write().await;
+        let session = sessions
+            .entry(session_id.to_string())
+            .or_insert_with(|| SessionData {
+                metadata,
+                block_file,
+                legacy_pty: None,
+            });
+
+        tracing::info!("[终端] 后台会话 {} 已恢复，等待连接", session_id);
+        Ok(Some(session.metadata.clone()))
+    }
+
+    /// 连接恢复的后台会话
+    ///
+    /// 先通过 `terminal:output` 发送 tmux 中保存的滚动历史，再以当前终端大小连接 tmux，
+    /// tmux 随后重绘当前屏幕。前端应在订阅输出事件后调用，避免丢失历史。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    pub async fn attach_session(&self, session_id: &str) -> Result<(), TerminalError> {
+        let backend = self.detached_backend.as_ref().ok_or_else(|| {
+            TerminalError::DetachedSessionError("后台会话不可用（未找到 tmux）".to_string())
+        })?;
+
+        let mut sessions = self.sessions.write().await;
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+        if !session.metadata.detached {
+            return Err(TerminalError::DetachedSessionError(format!(
+                "会话 {} 不是后台会话",
+                session_id
+            )));
+        }
+        if session.legacy_pty.is_some() {
+            // 已连接
+            return Ok(());
+        }
+
+        let block_id = session.metadata.block_id.clone();
+        if !backend.has_session(&block_id) {
+            sessions.remove(session_id);
+            return Err(TerminalError::DetachedSessionError(format!(
+                "后台会话 {} 已退出",
+                session_id
+            )));
+        }
+
+        // 回填滚动历史
+        match backend.capture_scrollback(&block_id, DETACHED_HISTORY_LIMIT) {
+            Ok(history) if !history.is_empty() => {
+                let _ = self.app_handle.emit(
+                    event_names::TERMINAL_OUTPUT,
+                    TerminalOutputEvent {
+                        session_id: session_id.to_string(),
+                        data: BASE64.encode(&history),
+                    },
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("[终端] 读取后台会话 {} 滚动历史失败: {}", session_id, e);
+            }
+        }
+
+        let integration = Arc::new(ShellIntegration::for_session(
+            session_id.to_string(),
+            self.app_handle.clone(),
+            session.metadata.connection.as_deref(),
+        ));
+        index_block_history(&integration, &session.block_file);
+        let pty_session = PtySession::with_command(
+            session_id.to_string(),
+            session.metadata.rows,
+            session.metadata.cols,
+            backend.attach_command(&block_id),
+            Some(integration),
+            Some(session.block_file.clone()),
+            self.app_handle.clone(),
+        )?;
+        session.legacy_pty = Some(pty_session);
+        session.metadata.status = SessionStatus::Running;
+
+        tracing::info!("[终端] 后台会话 {} 已连接", session_id);
+        Ok(())
+    }
+
+    /// 断开后台会话
+    ///
+    /// 关闭本地连接，tmux 中的 Shell 继续运行，之后可通过 `list_detached_sessions` 重新恢复。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    pub async fn detach_session(&self, session_id: &str) -> Result<(), TerminalError> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+        if !session.metadata.detached {
+            return Err(TerminalError::DetachedSessionError(format!(
+                "会话 {} 不是后台会话",
+                session_id
+            )));
+        }
+
+        if let Some(mut session) = sessions.remove(session_id) {
+            if let Some(pty) = session.legacy_pty.take() {
+                pty.close().await?;
+            }
+        }
+        TerminalShareRegistry::global().revoke_session(session_id);
+
+        tracing::info!("[终端] 后台会话 {} 已断开", session_id);
+        Ok(())
+    }
+
+    /// 开始回放块文件中录制的会话
+    ///
+    /// 回放创建后处于暂停状态，前端订阅 `terminal:replay-output` 后发送 `play` 开始播放。
+    /// 会话仍在运行时回放截至当前的输出。
+    ///
+    /// # 参数
+    /// - `block_id`: 块 ID
+    /// - `speed`: 初始播放速度（默认 1.0）
+    /// - `max_idle_ms`: 最大空闲间隔（默认 2000 毫秒）
+    pub async fn start_replay(
+        &self,
+        block_id: &str,
+        speed: Option<f64>,
+        max_idle_ms: Option<u64>,
+    ) -> Result<ReplayInfo, TerminalError> {
+        let max_idle_ms = max_idle_ms.unwrap_or(DEFAULT_MAX_IDLE_MS);
+        let active = {
+            let sessions = self.sessions.read().await;
+            sessions
+                .values()
+                .find(|s| s.metadata.block_id == block_id)
+                .map(|s| s.block_file.clone())
+        };
+        let recording = match active {
+            Some(block_file) => ReplayRecording::from_block_file(&block_file, max_idle_ms)?,
+            None => {
+                let block_file_path = self.block_file_base_dir.join(format!("{}.block", block_id));
+                if !block_file_path.exists() {
+                    return Err(TerminalError::BlockFileError(format!(
+                        "块文件不存在: {:?}",
+                        block_file_path
+                    )));
+                }
+                let block_file = BlockFile::with_default_size(block_id, &self.block_file_base_dir)?;
+                ReplayRecording::from_block_file(&block_file, max_idle_ms)?
+            }
+        };
+
+        let replay_id = Uuid::new_v4().to_string();
+        let handle = ReplayHandle::spawn(
+            replay_id.clone(),
+            block_id.to_string(),
+            recording,
+            speed.unwrap_or(1.0),
+            self.app_handle.clone(),
+        );
+        let info = handle.info().clone();
+        self.replays.write().await.insert(replay_id.clone(), handle);
+
+        tracing::info!(
+            "[终端] 开始回放 {}: block_id={}, 时长 {}ms, {} 条命令",
+            replay_id,
+            block_id,
+            info.duration_ms,
+            info.markers.len()
+        );
+        Ok(info)
+    }
+
+    /// 控制回放（播放、暂停、调速、跳转）
+    ///
+    /// # 参数
+    /// - `replay_id`: 回放 ID
+    /// - `command`: 控制指令
+    pub async fn control_replay(
+        &self,
+        replay_id: &str,
+        command: ReplayCommand,
+    ) -> Result<(), TerminalError> {
+        let replays = self.replays.read().await;
+        replays
+            .get(replay_id)
+            .ok_or_else(|| TerminalError::ReplayNotFound(replay_id.to_string()))?
+            .send(command)
+    }
+
+    /// 停止回放
+    ///
+    /// # 参数
+    /// - `replay_id`: 回放 ID
+    pub async fn stop_replay(&self, replay_id: &str) -> Result<(), TerminalError> {
+        let handle = self
+            .replays
+            .write()
+            .await
+            .remove(replay_id)
+            .ok_or_else(|| TerminalError::ReplayNotFound(replay_id.to_string()))?;
+        handle.stop();
+        tracing::info!("[终端] 回放 {} 已停止", replay_id);
+        Ok(())
+    }
+
+    /// 获取块文件存储设置
+    pub async fn storage_settings(&self) -> BlockStorageSettings {
+        *self.storage_settings.read().await
+    }
+
+    /// 保存块文件存储设置
+    ///
+    /// 回滚缓冲大小和冷段设置对之后创建的会话生效。
+    ///
+    /// # 参数
+    /// - `settings`: 存储设置
+    pub async fn save_storage_settings(
+        &self,
+        settings: BlockStorageSettings,
+    ) -> Result<(), TerminalError> {
+        settings.save(&self.block_file_base_dir)?;
+        *self.storage_settings.write().await = settings;
+        tracing::info!("[终端] 已保存输出历史存储设置: {:?}", settings);
+        Ok(())
+    }
+
+    /// 获取自定义按键绑定
+    pub async fn keymap_settings(&self) -> KeymapSettings {
+        self.keymap_settings.read().await.clone()
+    }
+
+    /// 保存自定义按键绑定（立即对所有会话生效）
+    ///
+    /// # 参数
+    /// - `settings`: 按键绑定设置
+    pub async fn save_keymap_settings(
+        &self,
+        settings: KeymapSettings,
+    ) -> Result<(), TerminalError> {
+        settings.save(&self.block_file_base_dir)?;
+        tracing::info!("[终端] 已保存 {} 个自定义按键绑定", settings.bindings.len());
+        *self.keymap_settings.write().await = settings;
+        Ok(())
+    }
+
+    /// 统计每个会话输出历史的磁盘占用
+    pub async fn storage_usage(&self) -> Result<Vec<BlockUsage>, TerminalError> {
+        let protected = self.protected_block_ids().await?;
+        block_storage::block_usage(&self.block_file_base_dir, &protected)
+    }
+
+    /// 按清理策略清理已结束会话的输出历史
+    ///
+    /// 运行中的会话、存活的后台会话和可恢复的会话不会被清理。
+    pub async fn prune_block_files(&self) -> Result<BlockPruneReport, TerminalError> {
+        let protected = self.protected_block_ids().await?;
+        let policy = self.storage_settings.read().await.retention;
+        block_storage::prune_blocks(
+            &self.block_file_base_dir,
+            &policy,
+            &protected,
+            SystemTime::now(),
+        )
+    }
+
+    /// 分享会话
+    ///
+    /// 生成一次性令牌，观看者通过代理服务器的 `/v1/terminal/share/{令牌}/ws` 连接。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `mode`: 分享模式
+    /// - `ttl_secs`: 令牌有效期（秒，默认 10 分钟）
+    pub async fn create_share(
+        &self,
+        session_id: &str,
+        mode: ShareMode,
+        ttl_secs: Option<u64>,
+    ) -> Result<TerminalShareTicket, TerminalError> {
+        let sessions = self.sessions.read().await;
+        let tap = sessions
+            .get(session_id)
+            .and_then(|session| session.legacy_pty.as_ref())
+            .map(|pty| pty.tap())
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+        let ttl = Duration::from_secs(ttl_secs.unwrap_or(DEFAULT_SHARE_TTL_SECS));
+        Ok(TerminalShareRegistry::global().create(session_id, tap, mode, ttl))
+    }
+
+    /// 列出会话分享
+    ///
+    /// # 参数
+    /// - `session_id`: 只列出该会话的分享（可选）
+    pub fn list_shares(&self, session_id: Option<&str>) -> Vec<TerminalShareInfo> {
+        TerminalShareRegistry::global().list(session_id)
+    }
+
+    /// 撤销会话分享，已连接的观看者被断开
+    ///
+    /// # 参数
+    /// - `share_id`: 分享 ID
+    pub fn revoke_share(&self, share_id: &str) -> Result<(), TerminalError> {
+        TerminalShareRegistry::global().revoke(share_id)
+    }
+
+    /// 列出工作区（按标签页顺序）
+    pub async fn list_workspaces(&self) -> Vec<TerminalWorkspace> {
+        let mut workspaces: Vec<TerminalWorkspace> =
+            self.workspaces.read().await.values().cloned().collect();
+        workspaces.sort_by_key(|w| (w.position, w.created_at));
+        workspaces
+    }
+
+    /// 创建空的工作区（排在最后）
+    ///
+    /// # 参数
+    /// - `name`: 名称
+    pub async fn create_workspace(&self, name: &str) -> Result<TerminalWorkspace, TerminalError> {
+        let name = Self::workspace_name(name)?;
+        let mut workspaces = self.workspaces.write().await;
+        let position = workspaces
+            .values()
+            .map(|w| w.position + 1)
+            .max()
+            .unwrap_or(0);
+        let workspace = TerminalWorkspace::new(name, position);
+        self.persist_workspace(&workspace)?;
+        workspaces.insert(workspace.id.clone(), workspace.clone());
+
+        tracing::info!("[终端] 创建工作区 {} ({})", workspace.id, workspace.name);
+        Ok(workspace)
+    }
+
+    /// 重命名工作区
+    ///
+    /// # 参数
+    /// - `workspace_id`: 工作区 ID
+    /// - `name`: 新名称
+    pub async fn rename_workspace(
+        &self,
+        workspace_id: &str,
+        name: &str,
+    ) -> Result<TerminalWorkspace, TerminalError> {
+        let name = Self::workspace_name(name)?;
+        let mut workspaces = self.workspaces.write().await;
+        let workspace = workspaces
+            .get_mut(workspace_id)
+            .ok_or_else(|| TerminalError::WorkspaceNotFound(workspace_id.to_string()))?;
+        workspace.rename(name);
+        self.persist_workspace(workspace)?;
+        Ok(workspace.clone())
+    }
+
+    /// 替换工作区的分屏布局（前端拖动分隔条、移动窗格后调用）
+    ///
+    /// 布局中的会话从其他工作区移除。
+    ///
+    /// # 参数
+    /// - `workspace_id`: 工作区 ID
+    /// - `layout`: 新布局（为空时清空工作区）
+    /// - `active_session_id`: 当前聚焦的会话
+    pub async fn set_workspace_layout(
+        &self,
+        workspace_id: &str,
+        layout: Option<PaneLayout>,
+        active_session_id: Option<String>,
+    ) -> Result<TerminalWorkspace, TerminalError> {
+        let session_ids = layout
+            .as_ref()
+            .map(PaneLayout::session_ids)
+            .unwrap_or_default();
+        {
+            let sessions = self.sessions.read().await;
+            if let Some(missing) = session_ids.iter().find(|id| !sessions.contains_key(*id)) {
+                return Err(TerminalError::SessionNotFound(missing.clone()));
+            }
+        }
+
+        let (updated, removed) = {
+            let mut workspaces = self.workspaces.write().await;
+            let mut updated = workspaces
+                .get(workspace_id)
+                .cloned()
+                .ok_or_else(|| TerminalError::WorkspaceNotFound(workspace_id.to_string()))?;
+            let previous = updated.session_ids();
+            updated.set_layout(layout, active_session_id)?;
+            let removed: Vec<String> = previous
+                .into_iter()
+                .filter(|id| !updated.contains(id))
+                .collect();
+
+            for workspace in workspaces.values_mut() {
+                if workspace.id != workspace_id
+                    && workspace.retain_sessions(|id| !updated.contains(id))
+                {
+                    self.persist_workspace(workspace)?;
+                }
+            }
+            self.persist_workspace(&updated)?;
+            workspaces.insert(updated.id.clone(), updated.clone());
+            (updated, removed)
+        };
+
+        for session_id in &session_ids {
+            self.set_session_tab(session_id, workspace_id).await?;
+        }
+        // 移出布局的会话不再属于任何工作区
+        for session_id in &removed {
+            self.set_session_tab(session_id, DEFAULT_WORKSPACE_ID)
+                .await?;
+        }
+        Ok(updated)
+    }
+
+    /// 调整工作区顺序
+    ///
+    /// 未列出的工作区保持原来的相对顺序，排在列出的工作区之后。
+    ///
+    /// # 参数
+    /// - `workspace_ids`: 工作区 ID（按新顺序）
+    pub async fn reorder_workspaces(
+        &self,
+        workspace_ids: &[String],
+    ) -> Result<Vec<TerminalWorkspace>, TerminalError> {
+        let mut ordered = self.list_workspaces().await;
+        ordered.sort_by_key(|w| {
+            workspace_ids
+                .iter()
+                .position(|id| *id == w.id)
+                .unwrap_or(workspace_ids.len())
+        });
+
+        let mut workspaces = self.workspaces.write().await;
+        for (position, id) in ordered.iter().map(|w| &w.id).enumerate() {
+            if let Some(workspace) = workspaces.get_mut(id) {
+                if workspace.position != position as i64 {
+                    workspace.position = position as i64;
+                    self.persist_workspace(workspace)?;
+                }
+            }
+        }
+        drop(workspaces);
+        Ok(self.list_workspaces().await)
+    }
+
+    /// 把会话放入工作区
+    ///
+    /// 会话在目标窗格旁分屏并设为当前会话，同时从原来所在的工作区移除。
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `placement`: 目标工作区和分屏位置
+    pub async fn place_session(
+        &self,
+        session_id: &str,
+        placement: &PanePlacement,
+    ) -> Result<TerminalWorkspace, TerminalError> {
+        if !self.sessions.read().await.contains_key(session_id) {
+            return Err(TerminalError::SessionNotFound(session_id.to_string()));
+        }
+
+        let updated = {
+            let mut workspaces = self.workspaces.write().await;
+            let mut updated = workspaces
+                .get(&placement.workspace_id)
+                .cloned()
+                .ok_or_else(|| TerminalError::WorkspaceNotFound(placement.workspace_id.clone()))?;
+            // 同一工作区内移动窗格
+            updated.remove_session(session_id);
+            updated.add_session(
+                session_id,
+                placement.target_session_id.as_deref(),
+                placement.direction,
+            )?;
+
+            for workspace in workspaces.values_mut() {
+                if workspace.id != updated.id && workspace.remove_session(session_id) {
+                    self.persist_workspace(workspace)?;
+                }
+            }
+            self.persist_workspace(&updated)?;
+            workspaces.insert(updated.id.clone(), updated.clone());
+            updated
+        };
+
+        self.set_session_tab(session_id, &updated.id).await?;
+        Ok(updated)
+    }
+
+    /// 删除工作区并关闭其中的会话
+    ///
+    /// # 参数
+    /// - `workspace_id`: 工作区 ID
+    pub async fn delete_workspace(&self, workspace_id: &str) -> Result<(), TerminalError> {
+        let workspace = self
+            .workspaces
+            .write()
+            .await
+            .remove(workspace_id)
+            .ok_or_else(|| TerminalError::WorkspaceNotFound(workspace_id.to_string()))?;
+        if let Some(store) = &self.session_store {
+            store.delete_workspace(workspace_id)?;
+        }
+
+        for session_id in workspace.session_ids() {
+            self.close_session(&session_id).await?;
+        }
+
+        tracing::info!("[终端] 工作区 {} 已删除", workspace_id);
+        Ok(())
+    }
+
+    /// 校验工作区名称
+    fn workspace_name(name: &str) -> Result<&str, TerminalError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(TerminalError::Internal("工作区名称不能为空".to_string()));
+        }
+        Ok(name)
+    }
+
+    /// 会话所在的工作区 ID
+    async fn workspace_of(&self, session_id: &str) -> Option<String> {
+        self.workspaces
+            .read()
+            .await
+            .values()
+            .find(|w| w.contains(session_id))
+            .map(|w| w.id.clone())
+    }
+
+    /// 保存工作区（没有数据库时只保存在内存中）
+    fn persist_workspace(&self, workspace: &TerminalWorkspace) -> Result<(), TerminalError> {
+        match &self.session_store {
+            Some(store) => store.save_workspace(workspace),
+            None => Ok(()),
+        }
+    }
+
+    /// 更新会话所属的标签页
+    async fn set_session_tab(&self, session_id: &str, tab_id: &str) -> Result<(), TerminalError> {
+        if let Some(session) = self.sessions.write().await.get_mut(session_id) {
+            session.metadata.tab_id = tab_id.to_string();
+        }
+        if let Some(store) = &self.session_store {
+            store.update_tab_id(session_id, tab_id)?;
+        }
+        Ok(())
+    }
+
+    /// 运行中、存活的后台会话和可恢复会话的块 ID
+    async fn protected_block_ids(&self) -> Result<HashSet<String>, TerminalError> {
+        let mut protected: HashSet<String> = self
+            .sessions
+            .read()
+            .await
+            .values()
+            .map(|s| s.metadata.block_id.clone())
+            .collect();
+        if let Some(backend) = &self.detached_backend {
+            protected.extend(
+                backend
+                    .list_sessions()?
+                    .into_iter()
+                    .map(|info| info.block_id),
+            );
+        }
+        if let Some(store) = &self.session_store {
+            protected.extend(
+                store
+                    .get_all()?
+                    .into_iter()
+                    .filter(|record| record.status == "running")
+                    .map(|record| record.block_id),
+            );
+        }
+        Ok(protected)
+    }
+
+    /// 按存储设置打开块文件
+    ///
+    /// 重新打开已有的块文件时，最大大小不小于文件当前大小，避免丢弃创建时按更大的回滚缓冲保存的历史。
+    ///
+    /// # 参数
+    /// - `block_id`: 块 ID
+    /// - `scrollback_bytes`: 回滚缓冲大小（为空时使用存储设置中的大小）
+    async fn open_block_file(
+        &self,
+        block_id: &str,
+        scrollback_bytes: Option<usize>,
+    ) -> Result<Arc<BlockFile>, TerminalError> {
+        let settings = *self.storage_settings.read().await;
+        let existing =
+            std::fs::metadata(self.block_file_base_dir.join(format!("{}.block", block_id)))
+                .map(|meta| meta.len() as usize)
+                .unwrap_or(0);
+        let max_size = scrollback_bytes
+            .unwrap_or(settings.scrollback_bytes)
+            .max(existing);
+
+        let block_file = BlockFile::new(block_id, &self.block_file_base_dir, max_size)?;
+        let block_file = if settings.compress_cold {
+            block_file.with_cold_segments(settings.cold_max_bytes)?
+        } else {
+            block_file
+        };
+        Ok(Arc::new(block_file))
+    }
+}
+
+/// 索引块文件中保留的历史提示符，并按块文件已写入的字节数设置输出位置
+///
+/// 读取失败时只记录警告，提示符索引从当前位置开始。
+fn index_block_history(integration: &ShellIntegration, block_file: &BlockFile) {
+    let written = block_file.written();
+    match block_file.read_range(0, written) {
+        Ok((data, _)) if !data.is_empty() => {
+            integration.index_history(&data, written - data.len() as u64);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::warn!(
+                "[终端] 读取块文件 {} 历史输出失败，提示符索引不含历史: {}",
+                block_file.block_id(),
+                e
+            );
+        }
+    }
+    integration.set_output_offset(written);
 }
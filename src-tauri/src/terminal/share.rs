@@ -0,0 +1,393 @@
+//! 终端会话分享
+//!
+//! 通过代理服务器的 WebSocket 把终端会话分享给其他人在浏览器中实时观看（只读）或一起操作（协作）。
+//!
+//! ## 功能
+//! - 为会话创建分享，生成一次性令牌（只能用于一次 WebSocket 握手）
+//! - 只读 / 协作两种模式，协作模式接受观看者的输入
+//! - 撤销单个分享，关闭会话时撤销该会话的所有分享
+//!
+//! ## 设计说明
+//! 令牌在有效期内未使用时失效；握手时令牌被消耗，之后连接持续到观看者断开、分享被撤销或会话结束。
+//! 注册表是进程级的全局实例，终端命令创建分享，代理服务器的 WebSocket 处理器兑换令牌。
+//!
+//! ## 协议
+//! 连接后以 JSON 文本帧通信，`type` 字段区分消息（见 `ShareServerMessage` / `ShareClientMessage`）：
+//! 服务端先发送 `hello` 和包含输出历史的 `output`，之后持续推送 `output`；观看者跟不上输出时
+//! 服务端发送重置序列和最新的输出历史重新同步。
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use tokio::sync::watch;
+
+use super::error::TerminalError;
+use super::pty_session::SessionTap;
+
+/// 默认令牌有效期（10 分钟）
+pub const DEFAULT_SHARE_TTL_SECS: u64 = 10 * 60;
+/// 最长令牌有效期（24 小时）
+pub const MAX_SHARE_TTL_SECS: u64 = 24 * 60 * 60;
+/// 分享 WebSocket 路径前缀，完整路径为 `{前缀}/{令牌}/ws`
+pub const SHARE_WS_PATH_PREFIX: &str = "/v1/terminal/share";
+
+/// 全局分享注册表
+static GLOBAL_SHARES: Lazy<TerminalShareRegistry> = Lazy::new(TerminalShareRegistry::new);
+
+/// 分享模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareMode {
+    /// 只读，观看者的输入被拒绝
+    #[default]
+    ReadOnly,
+    /// 协作，观看者的输入写入会话
+    Collaborative,
+}
+
+/// 分享信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminalShareInfo {
+    /// 分享 ID
+    pub share_id: String,
+    /// 会话 ID
+    pub session_id: String,
+    /// 分享模式
+    pub mode: ShareMode,
+    /// 创建时间（毫秒时间戳）
+    pub created_at: i64,
+    /// 令牌失效时间（毫秒时间戳），已连接的分享不受影响
+    pub expires_at: i64,
+    /// 观看者是否已连接（令牌已使用）
+    pub connected: bool,
+}
+
+/// 新建分享的凭据，令牌只在创建时返回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalShareTicket {
+    /// 分享信息
+    pub share: TerminalShareInfo,
+    /// 一次性令牌
+    pub token: String,
+    /// WebSocket 路径（相对代理服务器地址）
+    pub path: String,
+}
+
+/// 兑换令牌得到的连接授权
+pub struct ShareGrant {
+    /// 分享信息
+    pub share: TerminalShareInfo,
+    /// 会话的输出订阅和输入写入句柄
+    pub tap: SessionTap,
+    /// 撤销通知，值变为 `true` 时连接应关闭
+    pub revoked: watch::Receiver<bool>,
+}
+
+/// 连接关闭原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareCloseReason {
+    /// 分享被撤销（包括会话被关闭）
+    Revoked,
+    /// 会话进程已结束
+    SessionEnded,
+}
+
+/// 服务端发送的消息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShareServerMessage {
+    /// 连接建立
+    Hello {
+        share_id: String,
+        session_id: String,
+        mode: ShareMode,
+    },
+    /// 终端输出（Base64 编码）
+    Output { data: String },
+    /// 错误（连接保持）
+    Error { message: String },
+    /// 心跳响应
+    Pong,
+    /// 连接即将关闭
+    Closed { reason: ShareCloseReason },
+}
+
+/// 观看者发送的消息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShareClientMessage {
+    /// 输入（Base64 编码，仅协作模式）
+    Input { data: String },
+    /// 心跳
+    Ping,
+}
+
+/// 注册表中的分享
+struct ShareEntry {
+    info: TerminalShareInfo,
+    /// 未使用的令牌，握手后为 `None`
+    token: Option<String>,
+    tap: SessionTap,
+    revoke: watch::Sender<bool>,
+}
+
+/// 分享注册表
+pub struct TerminalShareRegistry {
+    shares: Mutex<HashMap<String, ShareEntry>>,
+}
+
+impl Default for TerminalShareRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TerminalShareRegistry {
+    /// 创建空的注册表
+    pub fn new() -> Self {
+        Self {
+            shares: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 全局注册表
+    pub fn global() -> &'static Self {
+        &GLOBAL_SHARES
+    }
+
+    /// 为会话创建分享
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `tap`: 会话的输出订阅和输入写入句柄
+    /// - `mode`: 分享模式
+    /// - `ttl`: 令牌有效期，超过 `MAX_SHARE_TTL_SECS` 时按最大值处理
+    pub fn create(
+        &self,
+        session_id: &str,
+        tap: SessionTap,
+        mode: ShareMode,
+        ttl: Duration,
+    ) -> TerminalShareTicket {
+        let ttl = ttl.min(Duration::from_secs(MAX_SHARE_TTL_SECS));
+        let created_at = Utc::now().timestamp_millis();
+        let info = TerminalShareInfo {
+            share_id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            mode,
+            created_at,
+            expires_at: created_at + ttl.as_millis() as i64,
+            connected: false,
+        };
+        let token = generate_token();
+
+        let mut shares = self.shares.lock();
+        purge_expired(&mut shares, created_at);
+        shares.insert(
+            info.share_id.clone(),
+            ShareEntry {
+                info: info.clone(),
+                token: Some(token.clone()),
+                tap,
+                revoke: watch::channel(false).0,
+            },
+        );
+        tracing::info!(
+            "[终端分享] 会话 {} 创建分享 {} ({:?})",
+            session_id,
+            info.share_id,
+            mode
+        );
+
+        TerminalShareTicket {
+            path: format!("{}/{}/ws", SHARE_WS_PATH_PREFIX, token),
+            share: info,
+            token,
+        }
+    }
+
+    /// 兑换令牌
+    ///
+    /// 令牌只能使用一次，过期或已使用的令牌返回 `ShareNotFound`。
+    pub fn redeem(&self, token: &str) -> Result<ShareGrant, TerminalError> {
+        let mut shares = self.shares.lock();
+        purge_expired(&mut shares, Utc::now().timestamp_millis());
+        let entry = shares
+            .values_mut()
+            .find(|entry| {
+                entry
+                    .token
+                    .as_deref()
+                    .is_some_and(|expected| expected.as_bytes().ct_eq(token.as_bytes()).into())
+            })
+            .ok_or_else(|| TerminalError::ShareNotFound("令牌无效、已过期或已使用".to_string()))?;
+
+        entry.token = None;
+        entry.info.connected = true;
+        tracing::info!(
+            "[终端分享] 分享 {} 已连接（会话 {}）",
+            entry.info.share_id,
+            entry.info.session_id
+        );
+        Ok(ShareGrant {
+            share: entry.info.clone(),
+            tap: entry.tap.clone(),
+            revoked: entry.revoke.subscribe(),
+        })
+    }
+
+    /// 观看者断开后移除分享
+    pub fn finish(&self, share_id: &str) {
+        if self.shares.lock().remove(share_id).is_some() {
+            tracing::info!("[终端分享] 分享 {} 已断开", share_id);
+        }
+    }
+
+    /// 撤销分享，已连接的观看者被断开
+    pub fn revoke(&self, share_id: &str) -> Result<(), TerminalError> {
+        let entry = self
+            .shares
+            .lock()
+            .remove(share_id)
+            .ok_or_else(|| TerminalError::ShareNotFound(share_id.to_string()))?;
+        let _ = entry.revoke.send(true);
+        tracing::info!("[终端分享] 分享 {} 已撤销", share_id);
+        Ok(())
+    }
+
+    /// 撤销会话的所有分享
+    ///
+    /// # 返回
+    /// 撤销的分享数量
+    pub fn revoke_session(&self, session_id: &str) -> usize {
+        let mut shares = self.shares.lock();
+        let ids: Vec<String> = shares
+            .values()
+            .filter(|entry| entry.info.session_id == session_id)
+            .map(|entry| entry.info.share_id.clone())
+            .collect();
+        for id in &ids {
+            if let Some(entry) = shares.remove(id) {
+                let _ = entry.revoke.send(true);
+            }
+        }
+        ids.len()
+    }
+
+    /// 列出分享（已过期未使用的分享不列出）
+    ///
+    /// # 参数
+    /// - `session_id`: 只列出该会话的分享（可选）
+    pub fn list(&self, session_id: Option<&str>) -> Vec<TerminalShareInfo> {
+        let mut shares = self.shares.lock();
+        purge_expired(&mut shares, Utc::now().timestamp_millis());
+        let mut list: Vec<TerminalShareInfo> = shares
+            .values()
+            .filter(|entry| session_id.is_none_or(|id| entry.info.session_id == id))
+            .map(|entry| entry.info.clone())
+            .collect();
+        list.sort_by_key(|info| info.created_at);
+        list
+    }
+}
+
+/// 移除令牌已过期且未连接的分享
+fn purge_expired(shares: &mut HashMap<String, ShareEntry>, now: i64) {
+    shares.retain(|_, entry| entry.token.is_none() || entry.info.expires_at > now);
+}
+
+/// 生成一次性令牌
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tap() -> SessionTap {
+        SessionTap::detached(Box::new(std::io::sink()))
+    }
+
+    #[test]
+    fn test_token_is_single_use() {
+        let registry = TerminalShareRegistry::new();
+        let ticket = registry.create(
+            "session-1",
+            tap(),
+            ShareMode::Collaborative,
+            Duration::from_secs(DEFAULT_SHARE_TTL_SECS),
+        );
+        assert_eq!(
+            ticket.path,
+            format!("/v1/terminal/share/{}/ws", ticket.token)
+        );
+        assert!(!registry.list(None)[0].connected);
+
+        let grant = registry.redeem(&ticket.token).unwrap();
+        assert_eq!(grant.share.mode, ShareMode::Collaborative);
+        assert!(registry.list(Some("session-1"))[0].connected);
+        assert!(matches!(
+            registry.redeem(&ticket.token),
+            Err(TerminalError::ShareNotFound(_))
+        ));
+
+        registry.finish(&ticket.share.share_id);
+        assert!(registry.list(None).is_empty());
+    }
+
+    #[test]
+    fn test_expired_and_revoked() {
+        let registry = TerminalShareRegistry::new();
+        let expired = registry.create("session-1", tap(), ShareMode::ReadOnly, Duration::ZERO);
+        assert!(registry.redeem(&expired.token).is_err());
+        assert!(registry.list(None).is_empty());
+
+        let first = registry.create("session-1", tap(), ShareMode::ReadOnly, Duration::MAX);
+        let second = registry.create("session-2", tap(), ShareMode::ReadOnly, Duration::MAX);
+        assert!(
+            first.share.expires_at - first.share.created_at <= MAX_SHARE_TTL_SECS as i64 * 1000
+        );
+
+        let grant = registry.redeem(&first.token).unwrap();
+        assert_eq!(registry.revoke_session("session-1"), 1);
+        assert!(*grant.revoked.borrow());
+        assert_eq!(registry.list(None), vec![second.share.clone()]);
+
+        registry.revoke(&second.share.share_id).unwrap();
+        assert!(registry.revoke(&second.share.share_id).is_err());
+        assert!(registry.redeem(&second.token).is_err());
+    }
+
+    #[test]
+    fn test_message_format() {
+        let hello = serde_json::to_value(ShareServerMessage::Hello {
+            share_id: "s".to_string(),
+            session_id: "t".to_string(),
+            mode: ShareMode::ReadOnly,
+        })
+        .unwrap();
+        assert_eq!(
+            hello,
+            serde_json::json!({"type": "hello", "share_id": "s", "session_id": "t", "mode": "read_only"})
+        );
+
+        let input: ShareClientMessage =
+            serde_json::from_str(r#"{"type":"input","data":"bHM="}"#).unwrap();
+        assert_eq!(
+            input,
+            ShareClientMessage::Input {
+                data: "bHM=".to_string()
+            }
+        );
+    }
+}
@@ -206,6 +206,19 @@ mod tests {
         use super::super::events::event_names;
         assert_eq!(event_names::TERMINAL_OUTPUT, "terminal:output");
         assert_eq!(event_names::TERMINAL_STATUS, "terminal:status");
+        assert_eq!(
+            event_names::TERMINAL_REPLAY_OUTPUT,
+            "terminal:replay-output"
+        );
+        assert_eq!(
+            event_names::TERMINAL_REPLAY_STATUS,
+            "terminal:replay-status"
+        );
+        assert_eq!(event_names::TERMINAL_LINKS, "terminal:links");
+        assert_eq!(event_names::CLIPBOARD_REQUEST, "terminal:clipboard-request");
+        assert_eq!(event_names::TERMINAL_IMAGE, "terminal:image");
+        assert_eq!(event_names::PASTE_WARNING, "terminal:paste-warning");
+        assert_eq!(event_names::COMMAND_BLOCK, "terminal:command-block");
     }
 }
 
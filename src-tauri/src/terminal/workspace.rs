@@ -0,0 +1,462 @@
+//! 终端工作区
+//!
+//! 工作区对应前端的一个标签页，其中的会话按分屏布局排列。布局保存在会话元数据存储中，
+//! 应用重启后随可恢复的会话一起恢复，会话记录的 `tab_id` 即所属工作区 ID。
+//!
+//! ## 布局结构
+//! 布局是一棵树：叶子节点为会话窗格，分屏节点按方向排列子节点，`sizes` 为各子节点所占的
+//! 相对比例（不要求总和为 1）。一个会话最多出现在一个工作区的布局中。
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::error::TerminalError;
+
+/// 未放入工作区的会话记录的 `tab_id`
+pub const DEFAULT_WORKSPACE_ID: &str = "default";
+
+/// 分屏方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitDirection {
+    /// 左右排列
+    #[default]
+    Horizontal,
+    /// 上下排列
+    Vertical,
+}
+
+/// 分屏布局节点
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PaneLayout {
+    /// 会话窗格
+    Pane { session_id: String },
+    /// 分屏
+    Split {
+        /// 分屏方向
+        direction: SplitDirection,
+        /// 子节点所占的相对比例（与 `children` 一一对应）
+        sizes: Vec<f32>,
+        /// 子节点
+        children: Vec<PaneLayout>,
+    },
+}
+
+impl PaneLayout {
+    /// 创建会话窗格
+    pub fn pane(session_id: &str) -> Self {
+        Self::Pane {
+            session_id: session_id.to_string(),
+        }
+    }
+
+    /// 按从左到右、从上到下的顺序列出会话 ID
+    pub fn session_ids(&self) -> Vec<String> {
+        let mut ids = Vec::new();
+        self.collect_session_ids(&mut ids);
+        ids
+    }
+
+    fn collect_session_ids(&self, ids: &mut Vec<String>) {
+        match self {
+            Self::Pane { session_id } => ids.push(session_id.clone()),
+            Self::Split { children, .. } => {
+                for child in children {
+                    child.collect_session_ids(ids);
+                }
+            }
+        }
+    }
+
+    /// 布局中是否包含会话
+    pub fn contains(&self, session_id: &str) -> bool {
+        match self {
+            Self::Pane { session_id: id } => id == session_id,
+            Self::Split { children, .. } => children.iter().any(|child| child.contains(session_id)),
+        }
+    }
+
+    /// 校验布局
+    ///
+    /// 会话不能重复出现，分屏至少有两个子节点，比例为正数且与子节点数量一致。
+    pub fn validate(&self) -> Result<(), TerminalError> {
+        self.validate_node(&mut HashSet::new())
+    }
+
+    fn validate_node<'a>(&'a self, seen: &mut HashSet<&'a str>) -> Result<(), TerminalError> {
+        match self {
+            Self::Pane { session_id } => {
+                if !seen.insert(session_id) {
+                    return Err(TerminalError::InvalidLayout(format!(
+                        "会话 {} 重复出现",
+                        session_id
+                    )));
+                }
+                Ok(())
+            }
+            Self::Split {
+                sizes, children, ..
+            } => {
+                if children.len() < 2 {
+                    return Err(TerminalError::InvalidLayout(
+                        "分屏至少需要两个子节点".to_string(),
+                    ));
+                }
+                if sizes.len() != children.len()
+                    || sizes.iter().any(|size| !size.is_finite() || *size <= 0.0)
+                {
+                    return Err(TerminalError::InvalidLayout(
+                        "分屏比例必须为正数且与子节点数量一致".to_string(),
+                    ));
+                }
+                children
+                    .iter()
+                    .try_for_each(|child| child.validate_node(seen))
+            }
+        }
+    }
+
+    /// 在目标窗格旁分屏放入会话
+    ///
+    /// 目标窗格所在分屏的方向相同时直接插入到目标之后，与目标平分原来的比例。
+    ///
+    /// # 返回
+    /// 是否找到目标窗格
+    pub fn split(&mut self, target: &str, session_id: &str, direction: SplitDirection) -> bool {
+        match self {
+            Self::Pane { session_id: id } if id == target => {
+                *self = Self::Split {
+                    direction,
+                    sizes: vec![1.0, 1.0],
+                    children: vec![Self::pane(target), Self::pane(session_id)],
+                };
+                true
+            }
+            Self::Pane { .. } => false,
+            Self::Split {
+                direction: split_direction,
+                sizes,
+                children,
+            } => {
+                if *split_direction == direction {
+                    let index = children.iter().position(
+                        |child| matches!(child, Self::Pane { session_id: id } if id == target),
+                    );
+                    if let Some(index) = index {
+                        sizes[index] /= 2.0;
+                        sizes.insert(index + 1, sizes[index]);
+                        children.insert(index + 1, Self::pane(session_id));
+                        return true;
+                    }
+                }
+                children
+                    .iter_mut()
+                    .any(|child| child.split(target, session_id, direction))
+            }
+        }
+    }
+
+    /// 移除会话窗格
+    ///
+    /// 只剩一个子节点的分屏由该子节点替代，其余子节点保持原来的相对比例。
+    ///
+    /// # 返回
+    /// 移除后的布局，没有窗格时为 `None`
+    pub fn remove(self, session_id: &str) -> Option<Self> {
+        match self {
+            Self::Pane { session_id: ref id } if id == session_id => None,
+            Self::Pane { .. } => Some(self),
+            Self::Split {
+                direction,
+                sizes,
+                children,
+            } => {
+                let (sizes, mut children): (Vec<f32>, Vec<Self>) = sizes
+                    .into_iter()
+                    .zip(children)
+                    .filter_map(|(size, child)| child.remove(session_id).map(|child| (size, child)))
+                    .unzip();
+                match children.len() {
+                    0 => None,
+                    1 => children.pop(),
+                    _ => Some(Self::Split {
+                        direction,
+                        sizes,
+                        children,
+                    }),
+                }
+            }
+        }
+    }
+}
+
+/// 会话在工作区中的位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PanePlacement {
+    /// 工作区 ID
+    pub workspace_id: String,
+    /// 在该会话旁分屏（为空时在工作区的当前会话旁）
+    #[serde(default)]
+    pub target_session_id: Option<String>,
+    /// 分屏方向
+    #[serde(default)]
+    pub direction: SplitDirection,
+}
+
+/// 终端工作区
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerminalWorkspace {
+    /// 工作区 ID
+    pub id: String,
+    /// 名称
+    pub name: String,
+    /// 标签页顺序（从小到大）
+    pub position: i64,
+    /// 分屏布局（没有会话时为 `None`）
+    pub layout: Option<PaneLayout>,
+    /// 当前聚焦的会话
+    pub active_session_id: Option<String>,
+    /// 创建时间（Unix 时间戳，毫秒）
+    pub created_at: i64,
+    /// 更新时间（Unix 时间戳，毫秒）
+    pub updated_at: i64,
+}
+
+impl TerminalWorkspace {
+    /// 创建空的工作区
+    pub fn new(name: &str, position: i64) -> Self {
+        let now = Utc::now().timestamp_millis();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            position,
+            layout: None,
+            active_session_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// 工作区中是否包含会话
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.layout
+            .as_ref()
+            .is_some_and(|layout| layout.contains(session_id))
+    }
+
+    /// 工作区中的会话 ID
+    pub fn session_ids(&self) -> Vec<String> {
+        self.layout
+            .as_ref()
+            .map(PaneLayout::session_ids)
+            .unwrap_or_default()
+    }
+
+    /// 放入会话并设为当前会话
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID（不能已在布局中）
+    /// - `target`: 在该会话旁分屏（为空时在当前会话旁，没有当前会话时在最后一个窗格旁）
+    /// - `direction`: 分屏方向
+    pub fn add_session(
+        &mut self,
+        session_id: &str,
+        target: Option<&str>,
+        direction: SplitDirection,
+    ) -> Result<(), TerminalError> {
+        if self.contains(session_id) {
+            return Err(TerminalError::InvalidLayout(format!(
+                "会话 {} 已在工作区中",
+                session_id
+            )));
+        }
+
+        match &mut self.layout {
+            Some(layout) => {
+                let target = target
+                    .map(str::to_string)
+                    .or_else(|| {
+                        self.active_session_id
+                            .clone()
+                            .filter(|id| layout.contains(id))
+                    })
+                    .or_else(|| layout.session_ids().pop())
+                    .unwrap_or_default();
+                if !layout.split(&target, session_id, direction) {
+                    return Err(TerminalError::InvalidLayout(format!(
+                        "会话 {} 不在工作区中",
+                        target
+                    )));
+                }
+            }
+            None => self.layout = Some(PaneLayout::pane(session_id)),
+        }
+        self.active_session_id = Some(session_id.to_string());
+        self.touch();
+        Ok(())
+    }
+
+    /// 移除会话，当前会话被移除时改为第一个窗格
+    ///
+    /// # 返回
+    /// 会话是否在工作区中
+    pub fn remove_session(&mut self, session_id: &str) -> bool {
+        if !self.contains(session_id) {
+            return false;
+        }
+        self.layout = self
+            .layout
+            .take()
+            .and_then(|layout| layout.remove(session_id));
+        if self.active_session_id.as_deref() == Some(session_id) {
+            self.active_session_id = self.session_ids().into_iter().next();
+        }
+        self.touch();
+        true
+    }
+
+    /// 替换布局
+    ///
+    /// 当前会话不在新布局中时改为第一个窗格。
+    pub fn set_layout(
+        &mut self,
+        layout: Option<PaneLayout>,
+        active_session_id: Option<String>,
+    ) -> Result<(), TerminalError> {
+        if let Some(layout) = &layout {
+            layout.validate()?;
+        }
+        self.layout = layout;
+        self.active_session_id = active_session_id
+            .filter(|id| self.contains(id))
+            .or_else(|| self.session_ids().into_iter().next());
+        self.touch();
+        Ok(())
+    }
+
+    /// 只保留满足条件的会话
+    ///
+    /// # 返回
+    /// 是否移除了会话
+    pub fn retain_sessions(&mut self, keep: impl Fn(&str) -> bool) -> bool {
+        let removed: Vec<String> = self
+            .session_ids()
+            .into_iter()
+            .filter(|id| !keep(id))
+            .collect();
+        for id in &removed {
+            self.remove_session(id);
+        }
+        !removed.is_empty()
+    }
+
+    /// 设置名称
+    pub fn rename(&mut self, name: &str) {
+        self.name = name.to_string();
+        self.touch();
+    }
+
+    /// 更新修改时间
+    fn touch(&mut self) {
+        self.updated_at = Utc::now().timestamp_millis();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_remove() {
+        let mut layout = PaneLayout::pane("a");
+        assert!(layout.split("a", "b", SplitDirection::Horizontal));
+        // 同方向分屏插入到目标之后
+        assert!(layout.split("a", "c", SplitDirection::Horizontal));
+        assert!(layout.split("c", "d", SplitDirection::Vertical));
+        assert!(!layout.split("x", "e", SplitDirection::Vertical));
+        assert_eq!(layout.session_ids(), vec!["a", "c", "d", "b"]);
+        layout.validate().unwrap();
+        match &layout {
+            PaneLayout::Split { sizes, .. } => assert_eq!(sizes, &vec![0.5, 0.5, 1.0]),
+            PaneLayout::Pane { .. } => panic!("应为分屏"),
+        }
+
+        // 只剩一个子节点的分屏被子节点替代
+        let layout = layout.remove("d").unwrap();
+        assert_eq!(
+            layout,
+            PaneLayout::Split {
+                direction: SplitDirection::Horizontal,
+                sizes: vec![0.5, 0.5, 1.0],
+                children: vec![
+                    PaneLayout::pane("a"),
+                    PaneLayout::pane("c"),
+                    PaneLayout::pane("b")
+                ],
+            }
+        );
+        let layout = layout.remove("a").unwrap().remove("b").unwrap();
+        assert_eq!(layout, PaneLayout::pane("c"));
+        assert!(layout.remove("c").is_none());
+    }
+
+    #[test]
+    fn test_validate() {
+        let duplicate = PaneLayout::Split {
+            direction: SplitDirection::Vertical,
+            sizes: vec![1.0, 1.0],
+            children: vec![PaneLayout::pane("a"), PaneLayout::pane("a")],
+        };
+        assert!(duplicate.validate().is_err());
+
+        let bad_sizes = PaneLayout::Split {
+            direction: SplitDirection::Vertical,
+            sizes: vec![1.0, 0.0],
+            children: vec![PaneLayout::pane("a"), PaneLayout::pane("b")],
+        };
+        assert!(bad_sizes.validate().is_err());
+
+        let json = r#"{"type":"split","direction":"vertical","sizes":[2,1],
+            "children":[{"type":"pane","session_id":"a"},{"type":"pane","session_id":"b"}]}"#;
+        let layout: PaneLayout = serde_json::from_str(json).unwrap();
+        layout.validate().unwrap();
+    }
+
+    #[test]
+    fn test_workspace_sessions() {
+        let mut workspace = TerminalWorkspace::new("dev", 0);
+        workspace
+            .add_session("a", None, SplitDirection::Horizontal)
+            .unwrap();
+        workspace
+            .add_session("b", None, SplitDirection::Vertical)
+            .unwrap();
+        workspace
+            .add_session("c", Some("a"), SplitDirection::Horizontal)
+            .unwrap();
+        assert!(workspace
+            .add_session("d", Some("x"), SplitDirection::Horizontal)
+            .is_err());
+        assert!(workspace
+            .add_session("a", None, SplitDirection::Horizontal)
+            .is_err());
+        assert_eq!(workspace.session_ids(), vec!["a", "c", "b"]);
+        assert_eq!(workspace.active_session_id.as_deref(), Some("c"));
+
+        assert!(workspace.remove_session("c"));
+        assert!(!workspace.remove_session("c"));
+        assert_eq!(workspace.active_session_id.as_deref(), Some("a"));
+        assert!(workspace.retain_sessions(|id| id == "b"));
+        assert_eq!(workspace.session_ids(), vec!["b"]);
+
+        workspace
+            .set_layout(Some(PaneLayout::pane("b")), Some("a".to_string()))
+            .unwrap();
+        assert_eq!(workspace.active_session_id.as_deref(), Some("b"));
+        workspace.set_layout(None, None).unwrap();
+        assert!(workspace.active_session_id.is_none());
+    }
+}
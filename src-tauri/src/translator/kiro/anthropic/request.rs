@@ -3,9 +3,12 @@
 //! 直接将 Anthropic MessagesRequest 转换为 CodeWhisperer API 格式，
 //! 无需经过 OpenAI 中间格式，减少转换开销。
 
+use crate::converter::image_content::ImageContent;
 use crate::models::anthropic::*;
 use crate::models::codewhisperer::*;
-use crate::translator::kiro::openai::request::{get_model_map, DEFAULT_MODEL};
+use crate::translator::kiro::openai::request::{
+    get_model_map, tool_choice_instruction, DEFAULT_MODEL,
+};
 use crate::translator::traits::{RequestTranslator, TranslateError};
 use std::collections::HashSet;
 use uuid::Uuid;
@@ -83,52 +86,51 @@ pub fn convert_anthropic_to_codewhisperer(
     // 提取 system prompt
     let mut system_prompt = extract_system_text(&request.system);
 
-    // 处理 tool_choice: required - CodeWhisperer 不支持此参数，通过 prompt 注入强制
-    if is_tool_choice_required(&request.tool_choice) && request.tools.is_some() {
-        let tool_instruction = "\n\n[CRITICAL INSTRUCTION] You MUST use one of the provided tools to respond. Do NOT respond with plain text. Call a tool function immediately.";
-        system_prompt.push_str(tool_instruction);
-        tracing::info!("[KIRO_TRANSLATE] tool_choice=required detected in Anthropic request, injected tool instruction");
+    // 处理 tool_choice - CodeWhisperer 不支持此参数，通过 prompt 注入约束
+    if request.tools.is_some() {
+        if let Some(tool_instruction) = tool_choice_instruction(&request.tool_choice) {
+            if !system_prompt.is_empty() {
+                system_prompt.push_str("\n\n");
+            }
+            system_prompt.push_str(&tool_instruction);
+            tracing::info!(
+                "[KIRO_TRANSLATE] tool_choice={:?} detected in Anthropic request, injected tool instruction",
+                request.tool_choice
+            );
+        }
     }
 
     // 预处理消息
-    let messages = preprocess_anthropic_messages(&request.messages);
+    let mut messages = preprocess_anthropic_messages(&request.messages);
+
+    // 处理 system prompt - 合并到第一条用户消息，不以用户消息开头时单独作为一条用户消息
+    if !system_prompt.is_empty() {
+        match messages.first_mut() {
+            Some(first) if first.role == "user" => {
+                first.content = if first.content.is_empty() {
+                    system_prompt
+                } else {
+                    format!("{system_prompt}\n\n{}", first.content)
+                };
+            }
+            _ => messages.insert(
+                0,
+                ProcessedMessage {
+                    role: "user".to_string(),
+                    content: system_prompt,
+                    tool_uses: None,
+                    tool_results: None,
+                    images: None,
+                },
+            ),
+        }
+    }
 
     // 构建历史记录
     let mut history: Vec<HistoryItem> = Vec::new();
-    let mut start_idx = 0;
-
-    // 处理 system prompt - 合并到第一条用户消息
-    if !system_prompt.is_empty() && !messages.is_empty() && messages[0].role == "user" {
-        let first_content = &messages[0].content;
-        let combined = format!("{system_prompt}\n\n{first_content}");
-
-        let mut user_msg = UserInputMessage {
-            content: combined,
-            model_id: cw_model.clone(),
-            origin: "AI_EDITOR".to_string(),
-            images: messages[0].images.clone(), // 传递图片
-            user_input_message_context: None,
-        };
-
-        if let Some(ref tool_results) = messages[0].tool_results {
-            user_msg.user_input_message_context = Some(UserInputMessageContext {
-                tools: None,
-                tool_results: Some(tool_results.clone()),
-            });
-        }
-
-        history.push(HistoryItem::User(UserHistoryItem {
-            user_input_message: user_msg,
-        }));
-        start_idx = 1;
-    }
 
     // 处理历史消息（除最后一条）
-    for msg in messages
-        .iter()
-        .take(messages.len().saturating_sub(1))
-        .skip(start_idx)
-    {
+    for msg in messages.iter().take(messages.len().saturating_sub(1)) {
         match msg.role.as_str() {
             "user" => {
                 let content = if msg.content.is_empty() {
@@ -376,37 +378,8 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
                         }
                     }
                     "image" => {
-                        // 处理 Anthropic 格式的图片
-                        // { "type": "image", "source": { "type": "base64", "media_type": "image/jpeg", "data": "..." } }
-                        if let Some(source) = part.get("source") {
-                            let source_type =
-                                source.get("type").and_then(|t| t.as_str()).unwrap_or("");
-                            if source_type == "base64" {
-                                let media_type = source
-                                    .get("media_type")
-                                    .and_then(|m| m.as_str())
-                                    .unwrap_or("image/jpeg");
-                                let data =
-                                    source.get("data").and_then(|d| d.as_str()).unwrap_or("");
-
-                                if !data.is_empty() {
-                                    // 从 media_type 提取格式 (image/jpeg -> jpeg)
-                                    let format =
-                                        media_type.split('/').nth(1).unwrap_or("jpeg").to_string();
-
-                                    images.push(CWImage {
-                                        format,
-                                        source: CWImageSource {
-                                            bytes: data.to_string(),
-                                        },
-                                    });
-                                    tracing::debug!(
-                                        "[KIRO_TRANSLATE] Converted image: media_type={}",
-                                        media_type
-                                    );
-                                }
-                            }
-                        }
+                        // 处理 Anthropic 格式的图片（base64 / url），超出限制时压缩
+                        convert_image_block(part, &mut images, &mut text_parts);
                     }
                     "tool_use" => {
                         let default_id = format!("toolu_{}", &Uuid::new_v4().to_string()[..8]);
@@ -429,6 +402,14 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
                             .and_then(|i| i.as_str())
                             .unwrap_or("");
                         let content_text = extract_tool_result_content(part.get("content"));
+                        // CodeWhisperer 的 tool result 只支持文本，图片随 user 消息发送
+                        if let Some(serde_json::Value::Array(items)) = part.get("content") {
+                            for item in items {
+                                if item.get("type").and_then(|t| t.as_str()) == Some("image") {
+                                    convert_image_block(item, &mut images, &mut text_parts);
+                                }
+                            }
+                        }
                         let is_error = part
                             .get("is_error")
                             .and_then(|e| e.as_bool())
@@ -497,6 +478,24 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ProcessedMessage> {
     result
 }
 
+/// 转换 Anthropic 图片块，URL 图片和无法压缩的图片转为文本
+fn convert_image_block(
+    block: &serde_json::Value,
+    images: &mut Vec<CWImage>,
+    text_parts: &mut Vec<String>,
+) {
+    let Some(image) = ImageContent::from_anthropic(block) else {
+        return;
+    };
+    match image.into_cw_image() {
+        Ok(image) => {
+            tracing::debug!("[KIRO_TRANSLATE] Converted image: format={}", image.format);
+            images.push(image);
+        }
+        Err(text) => text_parts.push(format!("{}\n", text)),
+    }
+}
+
 /// 提取 tool_result 内容
 fn extract_tool_result_content(content: Option<&serde_json::Value>) -> String {
     match content {
@@ -662,26 +661,6 @@ fn fix_history_alternation(history: Vec<HistoryItem>, model_id: &str) -> Vec<His
     fixed
 }
 
-/// 检查 tool_choice 是否为 required
-///
-/// Anthropic tool_choice 可以是:
-/// - {"type": "any"} - 必须调用工具
-/// - {"type": "tool", "name": "xxx"} - 必须调用指定工具
-fn is_tool_choice_required(tool_choice: &Option<serde_json::Value>) -> bool {
-    match tool_choice {
-        Some(serde_json::Value::Object(obj)) => {
-            if let Some(serde_json::Value::String(t)) = obj.get("type") {
-                t == "any" || t == "tool"
-            } else {
-                false
-            }
-        }
-        // OpenAI 风格的 "required" 字符串
-        Some(serde_json::Value::String(s)) => s == "required" || s == "any",
-        _ => false,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -700,6 +679,7 @@ mod tests {
             temperature: None,
             tools: None,
             tool_choice: None,
+            extra: Default::default(),
         };
 
         let translator = AnthropicRequestTranslator::new();
@@ -733,4 +713,60 @@ mod tests {
         let text = extract_system_text(&system);
         assert_eq!(text, "Line 1\nLine 2");
     }
+    #[test]
+    fn test_convert_images_in_user_message_and_tool_result() {
+        let msg = AnthropicMessage {
+            role: "user".to_string(),
+            content: serde_json::json!([
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": [
+                    {"type": "text", "text": "screenshot taken"},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/jpg", "data": "AAAA"}}
+                ]},
+                {"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}},
+                {"type": "text", "text": "What is this?"}
+            ]),
+        };
+
+        let result = convert_anthropic_message(&msg);
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            result[0].tool_results.as_ref().unwrap()[0].content[0].text,
+            "screenshot taken"
+        );
+
+        let images = result[1].images.as_ref().unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].format, "jpeg");
+        assert_eq!(
+            result[1].content,
+            "[Image: https://example.com/a.png]\nWhat is this?"
+        );
+    }
+
+    #[test]
+    fn test_system_and_tool_choice_fidelity() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "system": [
+                {"type": "text", "text": "You are Claude Code."},
+                {"type": "text", "text": "Be concise."}
+            ],
+            "messages": [{"role": "user", "content": "List the files"}],
+            "tools": [{"name": "ls", "input_schema": {"type": "object"}}],
+            "tool_choice": {"type": "tool", "name": "ls"}
+        }))
+        .unwrap();
+        let cw_request = convert_anthropic_to_codewhisperer(&request, None);
+
+        // 单条消息时 system prompt 合并到当前消息，不再重复放入历史
+        assert!(cw_request.conversation_state.history.is_none());
+        let content = &cw_request
+            .conversation_state
+            .current_message
+            .user_input_message
+            .content;
+        assert!(content.starts_with("You are Claude Code.\nBe concise."));
+        assert!(content.contains("You MUST call the `ls` tool"));
+        assert!(content.ends_with("List the files"));
+    }
 }
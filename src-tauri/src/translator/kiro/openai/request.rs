@@ -9,6 +9,7 @@
 //! - claude-sonnet-4-20250514 → CLAUDE_SONNET_4_20250514_V1_0
 //! - claude-haiku-4-5 → claude-haiku-4.5
 
+use crate::converter::image_content::ImageContent;
 use crate::models::codewhisperer::*;
 use crate::models::openai::*;
 use crate::translator::traits::{RequestTranslator, TranslateError};
@@ -139,17 +140,21 @@ pub fn convert_openai_to_codewhisperer(
 
     let conversation_id = Uuid::new_v4().to_string();
 
-    // 提取 system prompt 和消息
-    let mut system_prompt = String::new();
+    // 提取 system prompt 和消息（多条 system 消息按顺序拼接）
+    let mut system_parts: Vec<String> = Vec::new();
     let mut raw_messages: Vec<&ChatMessage> = Vec::new();
 
     for msg in &request.messages {
-        if msg.role == "system" {
-            system_prompt = msg.get_content_text();
+        if msg.role == "system" || msg.role == "developer" {
+            let text = msg.get_content_text();
+            if !text.is_empty() {
+                system_parts.push(text);
+            }
         } else {
             raw_messages.push(msg);
         }
     }
+    let mut system_prompt = system_parts.join("\n\n");
 
     // 调试日志：打印 tool_choice 和 tools 信息
     tracing::info!(
@@ -159,52 +164,51 @@ pub fn convert_openai_to_codewhisperer(
         request.tools.as_ref().map(|t| t.len()).unwrap_or(0)
     );
 
-    // 处理 tool_choice: required - CodeWhisperer 不支持此参数，通过 prompt 注入强制
-    if is_tool_choice_required(&request.tool_choice) && request.tools.is_some() {
-        let tool_instruction = "\n\n[CRITICAL INSTRUCTION] You MUST use one of the provided tools to respond. Do NOT respond with plain text. Call a tool function immediately.";
-        system_prompt.push_str(tool_instruction);
-        tracing::info!("[KIRO_TRANSLATE] tool_choice=required detected, injected tool instruction");
+    // 处理 tool_choice - CodeWhisperer 不支持此参数，通过 prompt 注入约束
+    if request.tools.is_some() {
+        if let Some(tool_instruction) = tool_choice_instruction(&request.tool_choice) {
+            if !system_prompt.is_empty() {
+                system_prompt.push_str("\n\n");
+            }
+            system_prompt.push_str(&tool_instruction);
+            tracing::info!(
+                "[KIRO_TRANSLATE] tool_choice={:?} detected, injected tool instruction",
+                request.tool_choice
+            );
+        }
     }
 
     // 预处理消息：合并 tool 消息
-    let messages = preprocess_messages(&raw_messages);
+    let mut messages = preprocess_messages(&raw_messages);
+
+    // 处理 system prompt - 合并到第一条用户消息，不以用户消息开头时单独作为一条用户消息
+    if !system_prompt.is_empty() {
+        match messages.first_mut() {
+            Some(first) if first.role == "user" => {
+                first.content = if first.content.is_empty() {
+                    system_prompt
+                } else {
+                    format!("{system_prompt}\n\n{}", first.content)
+                };
+            }
+            _ => messages.insert(
+                0,
+                ProcessedMessage {
+                    role: "user".to_string(),
+                    content: system_prompt,
+                    tool_calls: None,
+                    tool_results: None,
+                    images: None,
+                },
+            ),
+        }
+    }
 
     // 构建历史记录
     let mut history: Vec<HistoryItem> = Vec::new();
-    let mut start_idx = 0;
-
-    // 处理 system prompt - 合并到第一条用户消息
-    if !system_prompt.is_empty() && !messages.is_empty() && messages[0].role == "user" {
-        let first_content = &messages[0].content;
-        let combined = format!("{system_prompt}\n\n{first_content}");
-
-        let mut user_msg = UserInputMessage {
-            content: combined,
-            model_id: cw_model.clone(),
-            origin: "AI_EDITOR".to_string(),
-            images: messages[0].images.clone(), // 传递图片
-            user_input_message_context: None,
-        };
-
-        if let Some(ref tool_results) = messages[0].tool_results {
-            user_msg.user_input_message_context = Some(UserInputMessageContext {
-                tools: None,
-                tool_results: Some(tool_results.clone()),
-            });
-        }
-
-        history.push(HistoryItem::User(UserHistoryItem {
-            user_input_message: user_msg,
-        }));
-        start_idx = 1;
-    }
 
     // 处理历史消息（除最后一条）
-    for msg in messages
-        .iter()
-        .take(messages.len().saturating_sub(1))
-        .skip(start_idx)
-    {
+    for msg in messages.iter().take(messages.len().saturating_sub(1)) {
         match msg.role.as_str() {
             "user" => {
                 let content = if msg.content.is_empty() {
@@ -342,7 +346,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
                 });
             }
             "user" => {
-                let content = msg.get_content_text();
+                let mut content = msg.get_content_text();
                 let mut tool_results = pending_tool_results.clone();
                 pending_tool_results.clear();
 
@@ -350,20 +354,27 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
                 let mut seen_ids = HashSet::new();
                 tool_results.retain(|tr| seen_ids.insert(tr.tool_use_id.clone()));
 
-                // 提取图片
-                let raw_images = msg.get_images();
-                let images = if raw_images.is_empty() {
+                // 提取图片（URL 图片和无法压缩的图片转为文本）
+                let mut cw_images: Vec<CWImage> = Vec::new();
+                if let Some(MessageContent::Parts(parts)) = &msg.content {
+                    for part in parts {
+                        if let ContentPart::ImageUrl { image_url } = part {
+                            match ImageContent::from_openai_url(&image_url.url).into_cw_image() {
+                                Ok(image) => cw_images.push(image),
+                                Err(text) => {
+                                    if !content.is_empty() {
+                                        content.push('\n');
+                                    }
+                                    content.push_str(&text);
+                                }
+                            }
+                        }
+                    }
+                }
+                let images = if cw_images.is_empty() {
                     None
                 } else {
-                    Some(
-                        raw_images
-                            .into_iter()
-                            .map(|(format, data)| CWImage {
-                                format,
-                                source: CWImageSource { bytes: data },
-                            })
-                            .collect(),
-                    )
+                    Some(cw_images)
                 };
 
                 if images.is_some() {
@@ -591,23 +602,48 @@ fn fix_history_alternation(history: Vec<HistoryItem>, model_id: &str) -> Vec<His
     fixed
 }
 
-/// 检查 tool_choice 是否为 required
+/// 将 tool_choice 转换为注入 system prompt 的指令，`auto` 或未设置时返回 `None`
 ///
 /// tool_choice 可以是:
-/// - "required" 字符串
-/// - {"type": "any"} 或类似结构
-fn is_tool_choice_required(tool_choice: &Option<serde_json::Value>) -> bool {
-    match tool_choice {
-        Some(serde_json::Value::String(s)) => s == "required" || s == "any",
-        Some(serde_json::Value::Object(obj)) => {
-            // 检查 {"type": "any"} 或 {"type": "tool", ...}
-            if let Some(serde_json::Value::String(t)) = obj.get("type") {
-                t == "any" || t == "tool"
-            } else {
-                false
-            }
-        }
-        _ => false,
+/// - "required" / "any" / "none" 字符串
+/// - {"type": "function", "function": {"name": ...}}（OpenAI 指定函数）
+/// - {"type": "any"} / {"type": "tool", "name": ...} / {"type": "none"}（Anthropic 格式）
+pub(crate) fn tool_choice_instruction(tool_choice: &Option<serde_json::Value>) -> Option<String> {
+    const REQUIRED: &str = "[CRITICAL INSTRUCTION] You MUST use one of the provided tools to respond. Do NOT respond with plain text. Call a tool function immediately.";
+    const NONE: &str =
+        "[CRITICAL INSTRUCTION] Do NOT call any tools. Respond with plain text only.";
+
+    let named = |name: &str| {
+        format!(
+            "[CRITICAL INSTRUCTION] You MUST call the `{}` tool to respond. Do NOT respond with plain text. Call it immediately.",
+            name
+        )
+    };
+    match tool_choice.as_ref()? {
+        serde_json::Value::String(s) => match s.as_str() {
+            "required" | "any" => Some(REQUIRED.to_string()),
+            "none" => Some(NONE.to_string()),
+            _ => None,
+        },
+        serde_json::Value::Object(obj) => match obj.get("type").and_then(|t| t.as_str())? {
+            "any" => Some(REQUIRED.to_string()),
+            "none" => Some(NONE.to_string()),
+            "tool" => Some(
+                obj.get("name")
+                    .and_then(|n| n.as_str())
+                    .map(named)
+                    .unwrap_or_else(|| REQUIRED.to_string()),
+            ),
+            "function" => Some(
+                obj.get("function")
+                    .and_then(|f| f.get("name"))
+                    .and_then(|n| n.as_str())
+                    .map(named)
+                    .unwrap_or_else(|| REQUIRED.to_string()),
+            ),
+            _ => None,
+        },
+        _ => None,
     }
 }
 
@@ -643,6 +679,7 @@ mod tests {
             top_p: None,
             tool_choice: None,
             reasoning_effort: None,
+            extra: Default::default(),
         };
 
         let translator = OpenAiRequestTranslator::new();
@@ -659,4 +696,93 @@ mod tests {
             "CLAUDE_SONNET_4_5_20250929_V1_0"
         );
     }
+
+    #[test]
+    fn test_anthropic_fields_survive_openai_conversion() {
+        use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+
+        let request: crate::models::anthropic::AnthropicMessagesRequest =
+            serde_json::from_value(serde_json::json!({
+                "model": "claude-sonnet-4-5",
+                "system": [
+                    {"type": "text", "text": "You are Claude Code."},
+                    {"type": "text", "text": "Be concise."}
+                ],
+                "messages": [{"role": "user", "content": "List the files"}],
+                "tools": [{"name": "ls", "input_schema": {"type": "object"}}],
+                "tool_choice": {"type": "tool", "name": "ls"}
+            }))
+            .unwrap();
+        let cw_request =
+            convert_openai_to_codewhisperer(&convert_anthropic_to_openai(&request), None);
+
+        // 单条消息时 system prompt 合并到当前消息，不再重复放入历史
+        assert!(cw_request.conversation_state.history.is_none());
+        let content = &cw_request
+            .conversation_state
+            .current_message
+            .user_input_message
+            .content;
+        assert!(content.starts_with("You are Claude Code.\nBe concise."));
+        assert!(content.contains("You MUST call the `ls` tool"));
+        assert!(content.ends_with("List the files"));
+    }
+
+    #[test]
+    fn test_multiple_system_messages_are_joined() {
+        let message = |role: &str, text: &str| ChatMessage {
+            role: role.to_string(),
+            content: Some(MessageContent::Text(text.to_string())),
+            tool_calls: None,
+            tool_call_id: None,
+            reasoning_content: None,
+        };
+        let request = ChatCompletionRequest {
+            model: "claude-sonnet-4-5".to_string(),
+            messages: vec![
+                message("system", "Rule 1"),
+                message("developer", "Rule 2"),
+                message("assistant", "Hi"),
+                message("user", "Hello"),
+            ],
+            tools: None,
+            stream: false,
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            tool_choice: None,
+            reasoning_effort: None,
+            extra: Default::default(),
+        };
+
+        let cw_request = convert_openai_to_codewhisperer(&request, None);
+        let history = cw_request.conversation_state.history.unwrap();
+        // 不以用户消息开头时 system prompt 单独作为第一条用户消息
+        match &history[0] {
+            HistoryItem::User(item) => {
+                assert_eq!(item.user_input_message.content, "Rule 1\n\nRule 2")
+            }
+            _ => panic!("Expected user history item"),
+        }
+    }
+
+    #[test]
+    fn test_tool_choice_instruction() {
+        use serde_json::json;
+
+        assert!(tool_choice_instruction(&None).is_none());
+        assert!(tool_choice_instruction(&Some(json!("auto"))).is_none());
+        assert!(tool_choice_instruction(&Some(json!({"type": "auto"}))).is_none());
+        assert!(tool_choice_instruction(&Some(json!("required")))
+            .unwrap()
+            .contains("MUST use one of the provided tools"));
+        assert!(tool_choice_instruction(&Some(json!("none")))
+            .unwrap()
+            .contains("Do NOT call any tools"));
+        assert!(tool_choice_instruction(&Some(
+            json!({"type": "function", "function": {"name": "ls"}})
+        ))
+        .unwrap()
+        .contains("`ls`"));
+    }
 }
@@ -275,6 +275,24 @@ async fn handle_message(
                 "KiroCredentialEvent messages are server-to-client only",
             )))
         }
+        WsMessage::Subscribe(_) | WsMessage::Unsubscribe(_) => {
+            // 事件订阅在 server/handlers/websocket.rs 中处理
+            Some(WsMessage::Error(WsError::invalid_request(
+                None,
+                "Event subscription is not supported in this handler",
+            )))
+        }
+        WsMessage::Event(_) => Some(WsMessage::Error(WsError::invalid_request(
+            None,
+            "Event messages are server-to-client only",
+        ))),
+        WsMessage::Auth(_) | WsMessage::Resume(_) => {
+            // 首条消息认证和续传在 server/handlers/websocket.rs 中处理
+            Some(WsMessage::Error(WsError::invalid_request(
+                None,
+                "Auth and resume messages are not supported in this handler",
+            )))
+        }
     }
 }
 
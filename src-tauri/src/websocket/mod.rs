@@ -5,10 +5,13 @@
 //! - 消息解析和处理
 //! - 流式响应转发
 //! - 心跳检测和连接生命周期管理
+//! - 服务端事件订阅（日志、请求遥测、凭证健康状态）
+//! - 断线后续传请求响应
 
 mod handler;
 mod lifecycle;
 mod processor;
+mod resume;
 mod stream;
 mod types;
 
@@ -17,11 +20,13 @@ pub use lifecycle::{
     ConnectionLifecycle, GracefulShutdown, HeartbeatManager, LifecycleState, ResourceCleaner,
 };
 pub use processor::MessageProcessor;
+pub use resume::WsStreamBuffer;
 pub use stream::{BackpressureController, StreamForwarder};
 pub use types::{
-    KiroTokenInfo, WsApiRequest, WsApiResponse, WsConfig, WsConnection, WsConnectionStatus,
-    WsEndpoint, WsError, WsErrorCode, WsFlowEvent, WsKiroEvent, WsMessage, WsStats,
-    WsStatsSnapshot, WsStreamChunk, WsStreamEnd,
+    KiroTokenInfo, WsApiRequest, WsApiResponse, WsAuth, WsChannel, WsConfig, WsConnection,
+    WsConnectionStatus, WsEndpoint, WsError, WsErrorCode, WsFlowEvent, WsKiroEvent, WsMessage,
+    WsResume, WsServerEvent, WsStats, WsStatsSnapshot, WsStreamChunk, WsStreamEnd, WsSubscription,
+    WsSubscriptions,
 };
 
 use dashmap::DashMap;
@@ -36,15 +41,22 @@ pub struct WsConnectionManager {
     config: WsConfig,
     /// 统计信息
     stats: Arc<WsStats>,
+    /// 续传缓存（跨连接共享）
+    streams: Arc<WsStreamBuffer>,
 }
 
 impl WsConnectionManager {
     /// 创建新的连接管理器
     pub fn new(config: WsConfig) -> Self {
+        let streams = WsStreamBuffer::new(
+            std::time::Duration::from_secs(config.resume_ttl_secs),
+            config.resume_max_messages,
+        );
         Self {
             connections: DashMap::new(),
             config,
             stats: Arc::new(WsStats::new()),
+            streams: Arc::new(streams),
         }
     }
 
@@ -93,6 +105,13 @@ impl WsConnectionManager {
         }
     }
 
+    /// 更新连接的客户端信息
+    pub fn set_client_info(&self, id: &str, client_info: String) {
+        if let Some(mut conn) = self.connections.get_mut(id) {
+            conn.client_info = Some(client_info);
+        }
+    }
+
     /// 获取活跃连接数
     pub fn active_count(&self) -> usize {
         self.connections.len()
@@ -108,6 +127,11 @@ impl WsConnectionManager {
         &self.stats
     }
 
+    /// 获取续传缓存
+    pub fn streams(&self) -> &Arc<WsStreamBuffer> {
+        &self.streams
+    }
+
     /// 获取配置
     pub fn config(&self) -> &WsConfig {
         &self.config
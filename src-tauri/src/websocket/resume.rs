@@ -0,0 +1,141 @@
+//! WebSocket 流式响应续传
+//!
+//! 每个请求发给客户端的消息按（调用方, `request_id`）缓存，连接中途断开时请求继续执行并写入缓存，
+//! 客户端可在新连接上发送 `resume` 重放缺失的消息。请求结束（`stream_end`、`response`、`error`）
+//! 后缓存保留 `resume_ttl_secs` 秒；单个请求的消息数超过上限时不再支持续传。
+//!
+//! `request_id` 由客户端选择，调用方标识（认证的 Key 与绑定的选择器）由服务端确定：
+//! 不同调用方的相同 `request_id` 互不影响，也不能续传其他调用方的请求。
+
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use super::WsMessage;
+
+/// 单个请求的消息缓存
+#[derive(Debug)]
+struct BufferedStream {
+    messages: Vec<WsMessage>,
+    finished_at: Option<Instant>,
+    /// 已缓存的消息数，供续传方等待新消息
+    len: watch::Sender<usize>,
+}
+
+/// 缓存键（调用方标识, request_id）
+type StreamKey = (String, String);
+
+fn stream_key(owner: &str, request_id: &str) -> StreamKey {
+    (owner.to_string(), request_id.to_string())
+}
+
+/// 续传缓存
+#[derive(Debug)]
+pub struct WsStreamBuffer {
+    streams: DashMap<StreamKey, BufferedStream>,
+    ttl: Duration,
+    max_messages: usize,
+}
+
+impl WsStreamBuffer {
+    /// 创建续传缓存
+    pub fn new(ttl: Duration, max_messages: usize) -> Self {
+        Self {
+            streams: DashMap::new(),
+            ttl,
+            max_messages,
+        }
+    }
+
+    /// 开始缓存请求的消息
+    ///
+    /// 同一调用方相同 `request_id` 的请求仍在进行中时返回 `false`；已结束的旧缓存会被替换
+    pub fn begin(&self, owner: &str, request_id: &str) -> bool {
+        self.prune();
+        let key = stream_key(owner, request_id);
+        if self
+            .streams
+            .get(&key)
+            .is_some_and(|s| s.finished_at.is_none())
+        {
+            return false;
+        }
+        self.streams.insert(
+            key,
+            BufferedStream {
+                messages: Vec::new(),
+                finished_at: None,
+                len: watch::channel(0).0,
+            },
+        );
+        true
+    }
+
+    /// 缓存一条消息，`stream_end`、`response`、`error` 标记请求结束
+    pub fn push(&self, owner: &str, request_id: &str, message: WsMessage) {
+        let key = stream_key(owner, request_id);
+        let Some(mut stream) = self.streams.get_mut(&key) else {
+            return;
+        };
+        if stream.messages.len() >= self.max_messages {
+            drop(stream);
+            tracing::warn!(
+                "[WS] 请求 {} 的消息数超过续传上限 {}，不再缓存",
+                request_id,
+                self.max_messages
+            );
+            self.streams.remove(&key);
+            return;
+        }
+        if matches!(
+            message,
+            WsMessage::StreamEnd(_) | WsMessage::Response(_) | WsMessage::Error(_)
+        ) {
+            stream.finished_at = Some(Instant::now());
+        }
+        stream.messages.push(message);
+        let len = stream.messages.len();
+        stream.len.send_replace(len);
+    }
+
+    /// 订阅请求的新消息通知，缓存不存在时返回 `None`
+    ///
+    /// 需在读取消息之前订阅，避免遗漏读取与等待之间写入的消息
+    pub fn subscribe(&self, owner: &str, request_id: &str) -> Option<watch::Receiver<usize>> {
+        self.streams
+            .get(&stream_key(owner, request_id))
+            .map(|s| s.len.subscribe())
+    }
+
+    /// 读取第 `from` 条起的消息及请求是否已结束，缓存不存在或已过期时返回 `None`
+    pub fn messages_from(
+        &self,
+        owner: &str,
+        request_id: &str,
+        from: usize,
+    ) -> Option<(Vec<WsMessage>, bool)> {
+        let stream = self.streams.get(&stream_key(owner, request_id))?;
+        if stream.finished_at.is_some_and(|t| t.elapsed() > self.ttl) {
+            return None;
+        }
+        let messages = stream.messages.get(from..).unwrap_or_default().to_vec();
+        Some((messages, stream.finished_at.is_some()))
+    }
+
+    /// 缓存的请求数
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// 是否没有缓存的请求
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// 清理已过期的缓存
+    pub fn prune(&self) {
+        let ttl = self.ttl;
+        self.streams
+            .retain(|_, s| s.finished_at.is_none_or(|t| t.elapsed() <= ttl));
+    }
+}
@@ -64,6 +64,148 @@ fn test_ws_message_deserialization() {
     }
 }
 
+#[test]
+fn test_ws_auth_first_message() {
+    let auth = WsAuth::from_first_message(
+        r#"{"type":"auth","api_key":"pc_secret","client_name":"web-console"}"#,
+    )
+    .unwrap();
+    assert_eq!(auth.api_key, "pc_secret");
+    assert_eq!(auth.client_name.as_deref(), Some("web-console"));
+
+    let auth = WsAuth::from_first_message(r#"{"type":"auth","api_key":"pc_secret"}"#).unwrap();
+    assert!(auth.client_name.is_none());
+
+    // 第一条消息不是 auth 或格式错误时拒绝
+    for text in [
+        r#"{"type":"ping","timestamp":1}"#,
+        r#"{"type":"auth"}"#,
+        "not json",
+    ] {
+        let err = WsAuth::from_first_message(text).unwrap_err();
+        assert_eq!(err.code, WsErrorCode::Unauthorized);
+    }
+}
+
+#[test]
+fn test_ws_connection_manager_set_client_info() {
+    let manager = WsConnectionManager::with_defaults();
+    manager
+        .register("conn-1".to_string(), Some("Mozilla/5.0".to_string()))
+        .unwrap();
+    manager.set_client_info("conn-1", "web-console".to_string());
+    assert_eq!(
+        manager.get("conn-1").unwrap().client_info.as_deref(),
+        Some("web-console")
+    );
+}
+
+fn chunk(request_id: &str, index: u32) -> WsMessage {
+    WsMessage::StreamChunk(WsStreamChunk {
+        request_id: request_id.to_string(),
+        index,
+        data: format!("chunk-{}", index),
+    })
+}
+
+#[test]
+fn test_ws_stream_buffer_replay() {
+    let buffer = WsStreamBuffer::new(std::time::Duration::from_secs(60), 16);
+    assert!(buffer.begin("key:a", "req-1"));
+    let mut updates = buffer.subscribe("key:a", "req-1").unwrap();
+
+    buffer.push("key:a", "req-1", chunk("req-1", 0));
+    buffer.push("key:a", "req-1", chunk("req-1", 1));
+    assert!(updates.has_changed().unwrap());
+    // 进行中的请求不能复用 request_id
+    assert!(!buffer.begin("key:a", "req-1"));
+
+    let resume = WsResume {
+        request_id: "req-1".to_string(),
+        last_index: Some(0),
+    };
+    let (messages, finished) = buffer
+        .messages_from("key:a", "req-1", resume.replay_from())
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+    assert!(!finished);
+
+    buffer.push(
+        "key:a",
+        "req-1",
+        WsMessage::StreamEnd(WsStreamEnd {
+            request_id: "req-1".to_string(),
+            total_chunks: 2,
+        }),
+    );
+    let (messages, finished) = buffer.messages_from("key:a", "req-1", 0).unwrap();
+    assert_eq!(messages.len(), 3);
+    assert!(finished);
+    assert!(matches!(messages[2], WsMessage::StreamEnd(_)));
+
+    // 已结束的请求可以复用 request_id
+    assert!(buffer.begin("key:a", "req-1"));
+    assert!(buffer
+        .messages_from("key:a", "req-1", 0)
+        .unwrap()
+        .0
+        .is_empty());
+    assert!(buffer.messages_from("key:a", "unknown", 0).is_none());
+}
+
+#[test]
+fn test_ws_stream_buffer_isolates_callers() {
+    let buffer = WsStreamBuffer::new(std::time::Duration::from_secs(60), 16);
+    assert!(buffer.begin("key:a", "1"));
+    buffer.push("key:a", "1", chunk("1", 0));
+
+    // 其他调用方不能续传，相同 request_id 也不互相阻塞或替换
+    assert!(buffer.subscribe("key:b", "1").is_none());
+    assert!(buffer.messages_from("key:b", "1", 0).is_none());
+    assert!(buffer.begin("key:b", "1"));
+    assert!(buffer.messages_from("key:b", "1", 0).unwrap().0.is_empty());
+    assert_eq!(buffer.messages_from("key:a", "1", 0).unwrap().0.len(), 1);
+}
+
+#[test]
+fn test_ws_stream_buffer_limits() {
+    let buffer = WsStreamBuffer::new(std::time::Duration::from_millis(1), 2);
+
+    // 超过消息数上限后不再支持续传
+    buffer.begin("key:a", "req-1");
+    buffer.push("key:a", "req-1", chunk("req-1", 0));
+    buffer.push("key:a", "req-1", chunk("req-1", 1));
+    buffer.push("key:a", "req-1", chunk("req-1", 2));
+    assert!(buffer.subscribe("key:a", "req-1").is_none());
+
+    // 结束后超过保留时间即过期
+    buffer.begin("key:a", "req-2");
+    buffer.push(
+        "key:a",
+        "req-2",
+        WsMessage::Error(WsError::upstream(Some("req-2".to_string()), "boom")),
+    );
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    assert!(buffer.messages_from("key:a", "req-2", 0).is_none());
+    buffer.prune();
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn test_ws_resume_deserialization() {
+    let msg: WsMessage =
+        serde_json::from_str(r#"{"type":"resume","request_id":"req-1","last_index":4}"#).unwrap();
+    match msg {
+        WsMessage::Resume(resume) => assert_eq!(resume.replay_from(), 5),
+        _ => panic!("Expected Resume message"),
+    }
+    let msg: WsMessage = serde_json::from_str(r#"{"type":"resume","request_id":"req-1"}"#).unwrap();
+    match msg {
+        WsMessage::Resume(resume) => assert_eq!(resume.replay_from(), 0),
+        _ => panic!("Expected Resume message"),
+    }
+}
+
 #[test]
 fn test_ws_error_constructors() {
     let err = WsError::invalid_message("bad format");
@@ -84,6 +226,13 @@ fn test_ws_error_constructors() {
     let err = WsError::upstream(Some("req-2".to_string()), "provider error");
     assert_eq!(err.code, WsErrorCode::UpstreamError);
     assert_eq!(err.request_id, Some("req-2".to_string()));
+
+    let err = WsError::rate_limited(Some("req-3".to_string()), "too many requests");
+    assert_eq!(err.code, WsErrorCode::RateLimited);
+    assert_eq!(
+        serde_json::to_value(err.code).unwrap(),
+        serde_json::json!("rate_limited")
+    );
 }
 
 #[test]
@@ -94,6 +243,10 @@ fn test_ws_config_defaults() {
     assert_eq!(config.heartbeat_timeout_secs, 60);
     assert_eq!(config.max_connections, 100);
     assert_eq!(config.max_message_size, 16 * 1024 * 1024);
+    assert_eq!(config.auth_timeout_secs, 10);
+    assert_eq!(config.resume_ttl_secs, 60);
+    assert_eq!(config.resume_max_messages, 4096);
+    assert_eq!(config.deflate_min_size, None);
 }
 
 #[test]
@@ -268,9 +421,11 @@ fn arb_error_code() -> impl Strategy<Value = WsErrorCode> {
         Just(WsErrorCode::InvalidMessage),
         Just(WsErrorCode::InvalidRequest),
         Just(WsErrorCode::Unauthorized),
+        Just(WsErrorCode::Forbidden),
         Just(WsErrorCode::InternalError),
         Just(WsErrorCode::UpstreamError),
         Just(WsErrorCode::Timeout),
+        Just(WsErrorCode::RateLimited),
     ]
 }
 
@@ -628,3 +783,59 @@ proptest! {
         prop_assert!(forwarder.convert_sse_line("data: [DONE]", index).is_none());
     }
 }
+
+#[test]
+fn test_ws_subscribe_message() {
+    let msg: WsMessage =
+        serde_json::from_str(r#"{"type":"subscribe","channels":["logs","credential_health"]}"#)
+            .unwrap();
+    match msg {
+        WsMessage::Subscribe(sub) => {
+            assert_eq!(
+                sub.resolved_channels(),
+                vec![WsChannel::Logs, WsChannel::CredentialHealth]
+            );
+        }
+        _ => panic!("Expected Subscribe message"),
+    }
+
+    // 未指定频道时表示所有频道
+    let msg: WsMessage = serde_json::from_str(r#"{"type":"unsubscribe"}"#).unwrap();
+    match msg {
+        WsMessage::Unsubscribe(sub) => assert_eq!(sub.resolved_channels(), WsChannel::ALL),
+        _ => panic!("Expected Unsubscribe message"),
+    }
+}
+
+#[test]
+fn test_ws_subscriptions() {
+    let subs = WsSubscriptions::new();
+    assert!(subs.channels().is_empty());
+
+    subs.set(&WsChannel::ALL, true);
+    subs.set(&[WsChannel::Telemetry], false);
+    assert!(subs.is_subscribed(WsChannel::Logs));
+    assert!(!subs.is_subscribed(WsChannel::Telemetry));
+    assert_eq!(
+        subs.channels(),
+        vec![WsChannel::Logs, WsChannel::CredentialHealth]
+    );
+}
+
+#[test]
+fn test_ws_server_event_serialization() {
+    let event = WsServerEvent::Logs {
+        entry: crate::logger::LogEntry {
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            level: "info".to_string(),
+            message: "hello".to_string(),
+            request_id: None,
+        },
+    };
+    assert_eq!(event.channel(), WsChannel::Logs);
+
+    let json = serde_json::to_value(WsMessage::Event(event)).unwrap();
+    assert_eq!(json["type"], "event");
+    assert_eq!(json["channel"], "logs");
+    assert_eq!(json["entry"]["message"], "hello");
+}
@@ -4,12 +4,16 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use utoipa::ToSchema;
 
 use crate::flow_monitor::models::FlowError;
 use crate::flow_monitor::monitor::{
     FlowEvent, FlowSummary, FlowUpdate, NotificationEvent, ThresholdCheckResult,
 };
+use crate::logger::LogEntry;
+use crate::services::provider_pool_service::CredentialHealthInfo;
+use crate::telemetry::RequestLog;
 
 /// WebSocket 连接信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +78,10 @@ pub enum WsMessage {
     Ping { timestamp: i64 },
     /// 心跳响应
     Pong { timestamp: i64 },
+    /// 首条消息认证
+    Auth(WsAuth),
+    /// 续传中断的请求响应
+    Resume(WsResume),
     /// 订阅 Flow 事件
     SubscribeFlowEvents,
     /// 取消订阅 Flow 事件
@@ -86,10 +94,16 @@ pub enum WsMessage {
     UnsubscribeKiroEvents,
     /// Kiro 凭证状态事件通知
     KiroCredentialEvent(WsKiroEvent),
+    /// 订阅服务端事件频道
+    Subscribe(WsSubscription),
+    /// 取消订阅服务端事件频道
+    Unsubscribe(WsSubscription),
+    /// 服务端事件通知
+    Event(WsServerEvent),
 }
 
 /// WebSocket API 请求
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct WsApiRequest {
     /// 请求 ID（用于关联响应）
     pub request_id: String,
@@ -99,8 +113,52 @@ pub struct WsApiRequest {
     pub payload: serde_json::Value,
 }
 
+/// 首条消息认证
+///
+/// 浏览器无法在 WebSocket 握手时设置 `Authorization` 头，握手未携带 API Key 的连接
+/// 需在 `auth_timeout_secs` 内以该消息作为第一条消息完成认证，否则连接被关闭
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WsAuth {
+    /// API Key（服务器主 Key 或客户端 Key）
+    pub api_key: String,
+    /// 客户端名称（替代 User-Agent 显示在连接列表中）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+}
+
+impl WsAuth {
+    /// 解析连接的第一条文本消息，不是 `auth` 消息时返回认证错误
+    pub fn from_first_message(text: &str) -> Result<Self, WsError> {
+        match serde_json::from_str::<WsMessage>(text) {
+            Ok(WsMessage::Auth(auth)) => Ok(auth),
+            _ => Err(WsError::unauthorized(
+                "The first message must be an auth message",
+            )),
+        }
+    }
+}
+
+/// 续传请求
+///
+/// 在新连接上重放 `request_id` 对应请求中 `last_index` 之后的消息；请求仍在进行时继续推送后续消息
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WsResume {
+    /// 原请求 ID
+    pub request_id: String,
+    /// 已收到的最后一个 `stream_chunk` 的 `index`，未收到任何消息时省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_index: Option<u32>,
+}
+
+impl WsResume {
+    /// 需要重放的第一条消息位置
+    pub fn replay_from(&self) -> usize {
+        self.last_index.map_or(0, |i| i as usize + 1)
+    }
+}
+
 /// API 端点类型
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum WsEndpoint {
     /// OpenAI 兼容的 chat completions
@@ -112,7 +170,7 @@ pub enum WsEndpoint {
 }
 
 /// WebSocket API 响应
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct WsApiResponse {
     /// 请求 ID（关联请求）
     pub request_id: String,
@@ -121,7 +179,7 @@ pub struct WsApiResponse {
 }
 
 /// WebSocket 流式响应块
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct WsStreamChunk {
     /// 请求 ID（关联请求）
     pub request_id: String,
@@ -132,7 +190,7 @@ pub struct WsStreamChunk {
 }
 
 /// WebSocket 流式响应结束
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct WsStreamEnd {
     /// 请求 ID（关联请求）
     pub request_id: String,
@@ -141,7 +199,7 @@ pub struct WsStreamEnd {
 }
 
 /// WebSocket 错误
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct WsError {
     /// 请求 ID（如果有关联请求）
     pub request_id: Option<String>,
@@ -152,7 +210,7 @@ pub struct WsError {
 }
 
 /// WebSocket 错误码
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum WsErrorCode {
     /// 无效消息格式
@@ -161,12 +219,16 @@ pub enum WsErrorCode {
     InvalidRequest,
     /// 认证失败
     Unauthorized,
+    /// 无权访问（客户端 Key 作用域不包含请求的模型或 Provider）
+    Forbidden,
     /// 内部错误
     InternalError,
     /// 上游错误
     UpstreamError,
     /// 请求超时
     Timeout,
+    /// 超过限流
+    RateLimited,
 }
 
 impl WsError {
@@ -197,6 +259,15 @@ impl WsError {
         }
     }
 
+    /// 创建无权访问错误
+    pub fn forbidden(request_id: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            request_id,
+            code: WsErrorCode::Forbidden,
+            message: message.into(),
+        }
+    }
+
     /// 创建内部错误
     pub fn internal(request_id: Option<String>, message: impl Into<String>) -> Self {
         Self {
@@ -214,6 +285,15 @@ impl WsError {
             message: message.into(),
         }
     }
+
+    /// 创建限流错误
+    pub fn rate_limited(request_id: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            request_id,
+            code: WsErrorCode::RateLimited,
+            message: message.into(),
+        }
+    }
 }
 
 /// WebSocket 配置
@@ -234,6 +314,18 @@ pub struct WsConfig {
     /// 消息大小限制（字节）
     #[serde(default = "default_max_message_size")]
     pub max_message_size: usize,
+    /// 握手未认证的连接发送 `auth` 消息的最长等待时间（秒）
+    #[serde(default = "default_auth_timeout")]
+    pub auth_timeout_secs: u64,
+    /// 请求结束后响应消息保留供续传的时间（秒）
+    #[serde(default = "default_resume_ttl")]
+    pub resume_ttl_secs: u64,
+    /// 单个请求可续传的最大消息数
+    #[serde(default = "default_resume_max_messages")]
+    pub resume_max_messages: usize,
+    /// 消息达到该字节数时压缩（`proxycast.deflate` 子协议），为 `None` 时握手不协商压缩
+    #[serde(default)]
+    pub deflate_min_size: Option<usize>,
 }
 
 fn default_enabled() -> bool {
@@ -256,6 +348,18 @@ fn default_max_message_size() -> usize {
     16 * 1024 * 1024 // 16MB
 }
 
+fn default_auth_timeout() -> u64 {
+    10
+}
+
+fn default_resume_ttl() -> u64 {
+    60
+}
+
+fn default_resume_max_messages() -> usize {
+    4096
+}
+
 impl Default for WsConfig {
     fn default() -> Self {
         Self {
@@ -264,6 +368,10 @@ impl Default for WsConfig {
             heartbeat_timeout_secs: default_heartbeat_timeout(),
             max_connections: default_max_connections(),
             max_message_size: default_max_message_size(),
+            auth_timeout_secs: default_auth_timeout(),
+            resume_ttl_secs: default_resume_ttl(),
+            resume_max_messages: default_resume_max_messages(),
+            deflate_min_size: None,
         }
     }
 }
@@ -450,3 +558,109 @@ pub struct KiroTokenInfo {
     pub provider: String,
     pub region: String,
 }
+
+/// 服务端事件频道
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WsChannel {
+    /// 服务器日志（LogStore）
+    Logs,
+    /// 请求遥测（每个请求完成时推送）
+    Telemetry,
+    /// 凭证健康状态变化
+    CredentialHealth,
+}
+
+impl WsChannel {
+    /// 所有频道
+    pub const ALL: [WsChannel; 3] = [
+        WsChannel::Logs,
+        WsChannel::Telemetry,
+        WsChannel::CredentialHealth,
+    ];
+}
+
+/// 订阅/取消订阅请求
+///
+/// `channels` 为空表示所有频道
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct WsSubscription {
+    #[serde(default)]
+    pub channels: Vec<WsChannel>,
+}
+
+impl WsSubscription {
+    /// 请求涉及的频道
+    pub fn resolved_channels(&self) -> Vec<WsChannel> {
+        if self.channels.is_empty() {
+            WsChannel::ALL.to_vec()
+        } else {
+            self.channels.clone()
+        }
+    }
+}
+
+/// 服务端事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "channel", rename_all = "snake_case")]
+pub enum WsServerEvent {
+    /// 新的日志条目
+    Logs { entry: LogEntry },
+    /// 请求完成的遥测记录
+    Telemetry { request: RequestLog },
+    /// 凭证健康状态变化
+    CredentialHealth { credential: CredentialHealthInfo },
+}
+
+impl WsServerEvent {
+    /// 事件所属频道
+    pub fn channel(&self) -> WsChannel {
+        match self {
+            WsServerEvent::Logs { .. } => WsChannel::Logs,
+            WsServerEvent::Telemetry { .. } => WsChannel::Telemetry,
+            WsServerEvent::CredentialHealth { .. } => WsChannel::CredentialHealth,
+        }
+    }
+}
+
+/// 单个连接的频道订阅状态
+#[derive(Debug, Default)]
+pub struct WsSubscriptions {
+    logs: AtomicBool,
+    telemetry: AtomicBool,
+    credential_health: AtomicBool,
+}
+
+impl WsSubscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn flag(&self, channel: WsChannel) -> &AtomicBool {
+        match channel {
+            WsChannel::Logs => &self.logs,
+            WsChannel::Telemetry => &self.telemetry,
+            WsChannel::CredentialHealth => &self.credential_health,
+        }
+    }
+
+    /// 设置频道订阅状态
+    pub fn set(&self, channels: &[WsChannel], subscribed: bool) {
+        for channel in channels {
+            self.flag(*channel).store(subscribed, Ordering::Relaxed);
+        }
+    }
+
+    /// 是否订阅了指定频道
+    pub fn is_subscribed(&self, channel: WsChannel) -> bool {
+        self.flag(channel).load(Ordering::Relaxed)
+    }
+
+    /// 当前订阅的频道
+    pub fn channels(&self) -> Vec<WsChannel> {
+        WsChannel::ALL
+            .into_iter()
+            .filter(|c| self.is_subscribed(*c))
+            .collect()
+    }
+}
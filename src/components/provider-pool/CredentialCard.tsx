@@ -255,6 +255,11 @@ export function CredentialCard({
       claude_oauth: "OAuth",
       iflow_oauth: "OAuth",
       iflow_cookie: "Cookie",
+      vertex_service_account: "Service Account",
+      azure_openai_key: "API Key",
+      aws_bedrock: "AWS SigV4",
+      ollama: "Local",
+      mock: "Mock",
     };
     return labels[type] || type;
   };
@@ -58,6 +58,16 @@ const providerModels: Record<PoolProviderType, string[]> = {
     "claude-3-5-haiku-latest",
     "claude-sonnet-4-20250514",
   ], // Claude OAuth
+  qwen: ["qwen3-coder-plus", "qwen3-coder-flash", "vision-model"], // Qwen OAuth
+  vertex: ["gemini-2.5-pro", "gemini-2.5-flash", "gemini-2.0-flash"], // Vertex AI
+  azure_openai: ["gpt-4o", "gpt-4o-mini"], // Azure OpenAI（按部署映射）
+  aws_bedrock: [
+    "anthropic.claude-sonnet-4-5-20250929-v1:0",
+    "anthropic.claude-3-5-haiku-20241022-v1:0",
+    "amazon.nova-pro-v1:0",
+  ], // AWS Bedrock
+  ollama: ["llama3.2", "qwen2.5-coder:7b"], // 本地模型（以实际安装为准）
+  mock: ["mock-model"], // Mock（接受任意模型名）
   gemini_api_key: [
     "gemini-2.5-flash",
     "gemini-2.5-flash-lite",
@@ -94,6 +104,9 @@ export function EditCredentialModal({
   const [proxyUrl, setProxyUrl] = useState("");
   const [proxyError, setProxyError] = useState<string | null>(null);
 
+  // 负载均衡权重
+  const [weight, setWeight] = useState(1);
+
   // 初始化表单数据
   useEffect(() => {
     if (credential) {
@@ -119,6 +132,7 @@ export function EditCredentialModal({
       // 初始化代理 URL 为已保存的值
       setProxyUrl(credential.proxy_url || "");
       setProxyError(null);
+      setWeight(credential.weight ?? 1);
       setError(null);
     }
   }, [credential]);
@@ -218,6 +232,7 @@ export function EditCredentialModal({
         new_api_key: isApiKey ? newApiKey.trim() : undefined,
         // 代理 URL：始终传递当前值，空字符串表示清除代理
         new_proxy_url: proxyUrl.trim(),
+        weight,
       };
 
       console.log("[EditCredentialModal] 提交更新请求:", updateRequest);
@@ -484,6 +499,27 @@ export function EditCredentialModal({
             </div>
           </div>
 
+          {/* 负载均衡权重 */}
+          <div>
+            <label className="block text-sm font-medium mb-1.5">
+              负载均衡权重
+            </label>
+            <input
+              type="number"
+              min={0}
+              step={1}
+              value={weight}
+              onChange={(e) =>
+                setWeight(Math.max(0, Math.floor(Number(e.target.value) || 0)))
+              }
+              className="w-32 rounded-lg border bg-background px-3 py-2 text-sm"
+            />
+            <p className="text-xs text-muted-foreground mt-1">
+              仅在「加权」负载均衡策略下生效，权重越高分配的请求越多；0
+              表示仅在其他凭证不可用时使用
+            </p>
+          </div>
+
           {/* 使用统计（只读） */}
           <div className="rounded-lg bg-muted/50 p-4">
             <label className="mb-3 block text-sm font-medium">使用统计</label>
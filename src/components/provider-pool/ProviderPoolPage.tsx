@@ -73,6 +73,12 @@ const providerLabels: Record<PoolProviderType, string> = {
   claude: "Claude (Anthropic)",
   codex: "Codex (OAuth / API Key)",
   claude_oauth: "Claude OAuth",
+  qwen: "Qwen (通义千问)",
+  vertex: "Vertex AI (服务账号)",
+  azure_openai: "Azure OpenAI",
+  aws_bedrock: "AWS Bedrock",
+  ollama: "本地模型 (Ollama / LM Studio)",
+  mock: "Mock (离线测试)",
   gemini_api_key: "Gemini",
 };
 
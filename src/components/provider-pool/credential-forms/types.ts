@@ -41,6 +41,7 @@ export const defaultCredsPath: Record<string, string> = {
   antigravity: "",
   codex: "~/.codex/auth.json",
   claude_oauth: "~/.claude/oauth.json",
+  qwen: "~/.qwen/oauth_creds.json",
 };
 
 /** Provider 显示名称 */
@@ -52,5 +53,11 @@ export const providerLabels: Record<PoolProviderType, string> = {
   antigravity: "Antigravity (Gemini 3 Pro)",
   codex: "Codex (OpenAI OAuth)",
   claude_oauth: "Claude OAuth",
+  qwen: "Qwen (通义千问 OAuth)",
+  vertex: "Vertex AI (服务账号)",
+  azure_openai: "Azure OpenAI",
+  aws_bedrock: "AWS Bedrock",
+  ollama: "本地模型 (Ollama / LM Studio)",
+  mock: "Mock (离线测试)",
   gemini_api_key: "Gemini API Key",
 };
@@ -2,7 +2,7 @@
  * 连接选择器组件
  *
  * 提供 Waveterm 风格的连接下拉选择器。
- * 支持本地连接、SSH 远程连接和 WSL 连接。
+ * 支持本地连接、SSH / Mosh / Telnet 远程连接、串口连接和 WSL 连接。
  *
  * @module components/terminal/ConnectionSelector
  */
@@ -19,6 +19,11 @@ import {
   Monitor,
   Server,
   Terminal,
+  Wifi,
+  Network,
+  Cpu,
+  Container,
+  Boxes,
   Search,
   Settings,
   Loader2,
@@ -265,6 +270,16 @@ function getConnectionIcon(type: ConnectionType) {
       return <Monitor />;
     case "ssh":
       return <Server />;
+    case "mosh":
+      return <Wifi />;
+    case "telnet":
+      return <Network />;
+    case "serial":
+      return <Cpu />;
+    case "docker":
+      return <Container />;
+    case "k8s":
+      return <Boxes />;
     case "wsl":
       return <Terminal />;
     default:
@@ -16,6 +16,8 @@ import "@xterm/xterm/css/xterm.css";
 import {
   createTerminalSession,
   closeTerminal,
+  listDetachedSessions,
+  restoreTerminalSession,
   type SessionStatus,
 } from "@/lib/terminal-api";
 import { TermWrap } from "./termwrap";
@@ -27,6 +29,8 @@ import {
   loadThemePreference,
   saveFontSizePreference,
   loadFontSizePreference,
+  saveKeepSessionsPreference,
+  loadKeepSessionsPreference,
   MIN_FONT_SIZE,
   MAX_FONT_SIZE,
 } from "@/lib/terminal/themes";
@@ -43,6 +47,7 @@ interface Tab {
   title: string;
   status: SessionStatus;
   isSSH?: boolean;
+  restored?: boolean; // 恢复的后台会话，初始化时需连接并回填历史
   termWrap?: TermWrap; // 保持 TermWrap 实例
 }
 
@@ -156,6 +161,33 @@ const SearchButton: React.FC<{ onClick: () => void }> = ({ onClick }) => (
   </button>
 );
 
+/** 后台会话开关（新建终端在应用重启后保留） */
+const KeepSessionsButton: React.FC<{
+  enabled: boolean;
+  onChange: (enabled: boolean) => void;
+}> = ({ enabled, onChange }) => (
+  <button
+    className={`terminal-new-tab-btn ${enabled ? "active" : ""}`}
+    onClick={() => onChange(!enabled)}
+    aria-pressed={enabled}
+    title={
+      enabled
+        ? "新建终端将在应用重启后保留（点击关闭）"
+        : "新建终端在应用退出时结束（点击开启保留）"
+    }
+  >
+    <svg
+      className="w-4 h-4"
+      viewBox="0 0 24 24"
+      fill={enabled ? "currentColor" : "none"}
+      stroke="currentColor"
+      strokeWidth="2"
+    >
+      <path d="M19 21l-7-5-7 5V5a2 2 0 0 1 2-2h10a2 2 0 0 1 2 2z" />
+    </svg>
+  </button>
+);
+
 /** 主题选择器
  * _Requirements: 12.1, 12.4_
  */
@@ -324,6 +356,8 @@ const TerminalTabs: React.FC<{
   onThemeChange: (theme: ThemeName) => void;
   fontSize: number;
   onFontSizeChange: (size: number) => void;
+  keepSessions: boolean;
+  onKeepSessionsChange: (enabled: boolean) => void;
   isCreating?: boolean;
 }> = ({
   tabs,
@@ -336,6 +370,8 @@ const TerminalTabs: React.FC<{
   onThemeChange,
   fontSize,
   onFontSizeChange,
+  keepSessions,
+  onKeepSessionsChange,
   isCreating,
 }) => (
   <div className="terminal-tabbar" role="tablist">
@@ -353,6 +389,10 @@ const TerminalTabs: React.FC<{
     <SearchButton onClick={onSearchClick} />
     <FontSizeControl fontSize={fontSize} onFontSizeChange={onFontSizeChange} />
     <ThemeSelector currentTheme={currentTheme} onThemeChange={onThemeChange} />
+    <KeepSessionsButton
+      enabled={keepSessions}
+      onChange={onKeepSessionsChange}
+    />
     <NewTabButton onClick={onNewTab} disabled={isCreating} />
   </div>
 );
@@ -391,6 +431,10 @@ export function TerminalPage() {
   // 字体大小状态
   // _Requirements: 8.8_
   const [fontSize, setFontSize] = useState<number>(loadFontSizePreference());
+  // 新建终端是否作为后台会话（应用重启后可恢复）
+  const [keepSessions, setKeepSessions] = useState<boolean>(
+    loadKeepSessionsPreference(),
+  );
   const tabIdCounter = useRef(0);
 
   // 终端容器的父容器引用
@@ -413,7 +457,9 @@ export function TerminalPage() {
 
     try {
       // 先调用后端创建会话
-      const sessionId = await createTerminalSession();
+      const sessionId = await createTerminalSession(undefined, {
+        detached: keepSessions,
+      });
       console.log("[TerminalPage] 会话已创建:", sessionId);
 
       // 创建成功后添加标签页
@@ -434,7 +480,40 @@ export function TerminalPage() {
     } finally {
       setIsCreating(false);
     }
-  }, [isCreating]);
+  }, [isCreating, keepSessions]);
+
+  // 恢复应用重启前仍在运行的后台会话，返回恢复的数量
+  const restoreDetachedSessions = useCallback(async () => {
+    const sessions = await listDetachedSessions();
+    const restoredTabs: Tab[] = [];
+    for (const session of sessions) {
+      try {
+        await restoreTerminalSession(session.id);
+        restoredTabs.push({
+          id: `tab-${++tabIdCounter.current}`,
+          sessionId: session.id,
+          title: "Terminal",
+          status: "running",
+          isSSH: false,
+          restored: true,
+        });
+      } catch (err) {
+        console.error("[TerminalPage] 恢复后台会话失败:", session.id, err);
+      }
+    }
+    if (restoredTabs.length > 0) {
+      console.log("[TerminalPage] 已恢复后台会话:", restoredTabs.length);
+      setTabs((prev) => [...prev, ...restoredTabs]);
+      setActiveTabId(restoredTabs[restoredTabs.length - 1].id);
+    }
+    return restoredTabs.length;
+  }, []);
+
+  // 后台会话开关变化
+  const handleKeepSessionsChange = useCallback((enabled: boolean) => {
+    setKeepSessions(enabled);
+    saveKeepSessionsPreference(enabled);
+  }, []);
 
   // 状态变化
   const handleStatusChange = useCallback(
@@ -582,10 +661,19 @@ export function TerminalPage() {
     }
   }, [error]);
 
-  // 首次挂载时自动创建一个终端
+  // 首次挂载时先恢复后台会话，没有可恢复的会话时自动创建一个终端
   useEffect(() => {
     if (tabs.length === 0 && !isCreating) {
-      handleNewTerminal();
+      restoreDetachedSessions()
+        .catch((err) => {
+          console.error("[TerminalPage] 获取后台会话失败:", err);
+          return 0;
+        })
+        .then((count) => {
+          if (count === 0) {
+            handleNewTerminal();
+          }
+        });
     }
     // 只在首次挂载时执行
     // eslint-disable-next-line react-hooks/exhaustive-deps
@@ -724,6 +812,7 @@ export function TerminalPage() {
         themeName: currentTheme,
         fontSize: fontSize,
         keydownHandler: handleTerminalKeydown,
+        attachOnConnect: tab.restored,
       });
 
       // 保存到标签页
@@ -783,6 +872,8 @@ export function TerminalPage() {
           onThemeChange={handleThemeChange}
           fontSize={fontSize}
           onFontSizeChange={handleFontSizeChange}
+          keepSessions={keepSessions}
+          onKeepSessionsChange={handleKeepSessionsChange}
           isCreating={isCreating}
         />
         <div className="flex-1">
@@ -814,6 +905,8 @@ export function TerminalPage() {
         onThemeChange={handleThemeChange}
         fontSize={fontSize}
         onFontSizeChange={handleFontSizeChange}
+        keepSessions={keepSessions}
+        onKeepSessionsChange={handleKeepSessionsChange}
         isCreating={isCreating}
       />
 
@@ -35,6 +35,8 @@ interface TerminalPanelProps {
   panelId: string;
   /** 工作目录（可选） */
   cwd?: string;
+  /** 连接名称（可选，如 mosh://user@host） */
+  connection?: string;
   /** 会话创建完成回调 */
   onSessionCreated?: (sessionId: string) => void;
   /** 状态变化回调 */
@@ -51,6 +53,7 @@ interface TerminalPanelProps {
 export function TerminalPanel({
   panelId,
   cwd,
+  connection,
   onSessionCreated,
   onStatusChange,
   onSplitHorizontal,
@@ -80,11 +83,12 @@ export function TerminalPanel({
     setError(null);
 
     try {
-      const newSessionId = await createTerminalSession(cwd);
+      const newSessionId = await createTerminalSession(cwd, { connection });
       console.log(
         `[TerminalPanel ${panelId}] 会话已创建:`,
         newSessionId,
         cwd ? `(cwd: ${cwd})` : "",
+        connection ? `(connection: ${connection})` : "",
       );
       setSessionId(newSessionId);
       onSessionCreated?.(newSessionId);
@@ -94,7 +98,7 @@ export function TerminalPanel({
     } finally {
       setIsCreating(false);
     }
-  }, [isCreating, sessionId, panelId, cwd, onSessionCreated]);
+  }, [isCreating, sessionId, panelId, cwd, connection, onSessionCreated]);
 
   // 首次挂载时创建会话
   useEffect(() => {
@@ -262,8 +266,8 @@ export function TerminalPanel({
     try {
       const text = await navigator.clipboard.readText();
       if (text && termWrapRef.current) {
-        // 通过终端发送粘贴的文本
-        termWrapRef.current.sendData(text);
+        // 由后端处理括号粘贴和多行确认
+        termWrapRef.current.paste(text);
       }
     } catch (e) {
       console.error("[TerminalPanel] 粘贴失败:", e);
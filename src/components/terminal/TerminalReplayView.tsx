@@ -0,0 +1,241 @@
+/**
+ * @file TerminalReplayView.tsx
+ * @description 终端会话回放视图
+ * @module components/terminal/TerminalReplayView
+ *
+ * 在只读终端中按录制节奏回放块文件，支持暂停、调速、拖动进度，
+ * 以及通过 OSC 133 提示符标记跳转到指定命令。
+ */
+
+import React, { useCallback, useEffect, useRef, useState } from "react";
+import { Terminal } from "@xterm/xterm";
+import { FitAddon } from "./fitaddon";
+import {
+  controlReplay,
+  onReplayOutput,
+  onReplayStatus,
+  startReplay,
+  stopReplay,
+  type ReplayInfo,
+  type TerminalReplayStatusEvent,
+} from "@/lib/terminal-api";
+import { getTheme, loadThemePreference } from "@/lib/terminal/themes";
+
+// ============================================================================
+// 类型定义
+// ============================================================================
+
+/** 组件属性 */
+export interface TerminalReplayViewProps {
+  /** 要回放的块 ID（会话 ID） */
+  blockId: string;
+  /** 初始播放速度，默认 1 */
+  initialSpeed?: number;
+  /** 加载后自动播放，默认 true */
+  autoPlay?: boolean;
+  /** 关闭回调 */
+  onClose?: () => void;
+}
+
+/** 可选播放速度 */
+const SPEED_OPTIONS = [0.5, 1, 2, 4, 8, 16];
+
+/** 格式化毫秒为 mm:ss */
+function formatTime(ms: number): string {
+  const totalSeconds = Math.floor(ms / 1000);
+  const minutes = Math.floor(totalSeconds / 60);
+  const seconds = totalSeconds % 60;
+  return `${minutes}:${seconds.toString().padStart(2, "0")}`;
+}
+
+// ============================================================================
+// 主组件
+// ============================================================================
+
+/**
+ * 终端会话回放视图
+ *
+ * 终端禁用输入，画面只由回放输出驱动。
+ */
+export const TerminalReplayView: React.FC<TerminalReplayViewProps> = ({
+  blockId,
+  initialSpeed = 1,
+  autoPlay = true,
+  onClose,
+}) => {
+  const containerRef = useRef<HTMLDivElement>(null);
+  const [info, setInfo] = useState<ReplayInfo | null>(null);
+  const [status, setStatus] = useState<TerminalReplayStatusEvent | null>(
+    null,
+  );
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    const container = containerRef.current;
+    if (!container) return;
+
+    const terminal = new Terminal({
+      disableStdin: true,
+      cursorBlink: false,
+      fontSize: 14,
+      fontFamily: 'Hack, Menlo, Monaco, "Courier New", monospace',
+      theme: getTheme(loadThemePreference()),
+      scrollback: 5000,
+    });
+    const fitAddon = new FitAddon();
+    terminal.loadAddon(fitAddon);
+    terminal.open(container);
+    fitAddon.fit();
+
+    const resizeObserver = new ResizeObserver(() => fitAddon.fit());
+    resizeObserver.observe(container);
+
+    let disposed = false;
+    let replayId: string | null = null;
+    const unlisteners: Array<() => void> = [];
+
+    (async () => {
+      try {
+        const replay = await startReplay(blockId, initialSpeed);
+        replayId = replay.replay_id;
+        if (disposed) {
+          await stopReplay(replay.replay_id);
+          return;
+        }
+        setInfo(replay);
+
+        // 先订阅事件再开始播放，避免丢失开头的输出
+        unlisteners.push(
+          await onReplayOutput(replay.replay_id, (data) =>
+            terminal.write(data),
+          ),
+          await onReplayStatus(replay.replay_id, setStatus),
+        );
+        if (autoPlay) {
+          await controlReplay(replay.replay_id, { action: "play" });
+        }
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+      }
+    })();
+
+    return () => {
+      disposed = true;
+      unlisteners.forEach((unlisten) => unlisten());
+      if (replayId) {
+        stopReplay(replayId).catch(() => {});
+      }
+      resizeObserver.disconnect();
+      terminal.dispose();
+    };
+  }, [blockId, initialSpeed, autoPlay]);
+
+  const replayId = info?.replay_id;
+  const playing = status?.state === "playing";
+  const durationMs = status?.duration_ms ?? info?.duration_ms ?? 0;
+
+  const togglePlay = useCallback(() => {
+    if (!replayId) return;
+    controlReplay(replayId, { action: playing ? "pause" : "play" }).catch(
+      (e) => setError(String(e)),
+    );
+  }, [replayId, playing]);
+
+  const handleSpeedChange = useCallback(
+    (e: React.ChangeEvent<HTMLSelectElement>) => {
+      if (!replayId) return;
+      controlReplay(replayId, {
+        action: "set_speed",
+        speed: Number(e.target.value),
+      }).catch((err) => setError(String(err)));
+    },
+    [replayId],
+  );
+
+  const handleSeek = useCallback(
+    (e: React.ChangeEvent<HTMLInputElement>) => {
+      if (!replayId) return;
+      controlReplay(replayId, {
+        action: "seek",
+        position_ms: Number(e.target.value),
+      }).catch((err) => setError(String(err)));
+    },
+    [replayId],
+  );
+
+  const seekToCommand = useCallback(
+    (index: number) => {
+      if (!replayId) return;
+      controlReplay(replayId, { action: "seek_to_command", index }).catch(
+        (e) => setError(String(e)),
+      );
+    },
+    [replayId],
+  );
+
+  return (
+    <div className="terminal-replay view-term terminal-bg">
+      <div className="terminal-replay-toolbar">
+        <button
+          className="terminal-replay-button"
+          onClick={togglePlay}
+          disabled={!replayId}
+          title={playing ? "暂停" : "播放"}
+        >
+          {playing ? "暂停" : "播放"}
+        </button>
+        <input
+          className="terminal-replay-progress"
+          type="range"
+          min={0}
+          max={durationMs}
+          value={status?.position_ms ?? 0}
+          onChange={handleSeek}
+          disabled={!replayId || durationMs === 0}
+        />
+        <span className="terminal-replay-time">
+          {formatTime(status?.position_ms ?? 0)} / {formatTime(durationMs)}
+        </span>
+        <select
+          className="terminal-replay-speed"
+          value={status?.speed ?? initialSpeed}
+          onChange={handleSpeedChange}
+          disabled={!replayId}
+        >
+          {SPEED_OPTIONS.map((speed) => (
+            <option key={speed} value={speed}>
+              {speed}x
+            </option>
+          ))}
+        </select>
+        {onClose && (
+          <button className="terminal-replay-button" onClick={onClose}>
+            关闭
+          </button>
+        )}
+      </div>
+      <div className="terminal-replay-body">
+        <div ref={containerRef} className="term-connectelem" />
+        {info && info.markers.length > 0 && (
+          <ul className="terminal-replay-commands">
+            {info.markers.map((marker) => (
+              <li
+                key={marker.index}
+                className={
+                  status?.command_index === marker.index ? "active" : undefined
+                }
+                onClick={() => seekToCommand(marker.index)}
+                title={formatTime(marker.position_ms)}
+              >
+                {marker.command ?? `#${marker.index + 1}`}
+              </li>
+            ))}
+          </ul>
+        )}
+      </div>
+      {error && <div className="terminal-replay-error">{error}</div>}
+    </div>
+  );
+};
+
+export default TerminalReplayView;
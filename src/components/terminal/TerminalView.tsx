@@ -50,11 +50,7 @@ import {
   type TermMode,
 } from "@/lib/terminal/store";
 import { cleanupVDomStateAtom } from "@/lib/terminal/vdom";
-import {
-  writeToTerminalRaw,
-  encodeBase64,
-  type SessionStatus,
-} from "@/lib/terminal-api";
+import type { SessionStatus } from "@/lib/terminal-api";
 import {
   type ThemeName,
   loadThemePreference,
@@ -446,14 +442,14 @@ export const TerminalView: React.FC<TerminalViewProps> = ({
     try {
       const text = await navigator.clipboard.readText();
       if (text && termWrapRef.current) {
-        const base64 = encodeBase64(text);
-        await writeToTerminalRaw(blockId, base64);
+        // 由后端处理括号粘贴和多行确认
+        termWrapRef.current.paste(text);
       }
     } catch (err) {
       console.error("[TerminalView] 粘贴失败:", err);
     }
     setContextMenu(null);
-  }, [blockId]);
+  }, []);
 
   const getSelectedText = useCallback(() => {
     return termWrapRef.current?.terminal.getSelection() ?? "";
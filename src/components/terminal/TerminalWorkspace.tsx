@@ -30,10 +30,12 @@ import {
 import { TerminalAIPanel } from "./ai";
 import {
   ConnectionSelector,
+  connectionToSessionString,
   type ConnectionListEntry,
 } from "./ConnectionSelector";
 import { ConnectionsEditorModal } from "./ConnectionsEditorModal";
 import { Page } from "@/types/page";
+import type { ConnectionType } from "@/lib/connection-api";
 
 // ============================================================================
 // 类型定义
@@ -55,6 +57,15 @@ export interface SidePanel {
   sessionId?: string;
 }
 
+/** 终端会话在后端直接建立的连接类型 */
+const SESSION_CONNECTION_TYPES: ConnectionType[] = [
+  "mosh",
+  "telnet",
+  "serial",
+  "docker",
+  "k8s",
+];
+
 // ============================================================================
 // 样式组件
 // ============================================================================
@@ -350,6 +361,12 @@ export function TerminalWorkspace({ onNavigate }: TerminalWorkspaceProps) {
           <TerminalPanel
             panelId={panel.id}
             cwd={panel.cwd}
+            connection={
+              panel.connection &&
+              SESSION_CONNECTION_TYPES.includes(panel.connection.type)
+                ? connectionToSessionString(panel.connection)
+                : undefined
+            }
             onSessionCreated={(sessionId) =>
               updatePanelSessionId(panel.id, sessionId)
             }
@@ -9,6 +9,7 @@
 export { TerminalPage } from "./TerminalPage";
 export { TerminalView } from "./TerminalView";
 export { TerminalSearch } from "./TerminalSearch";
+export { TerminalReplayView } from "./TerminalReplayView";
 export { TerminalContextMenu } from "./TerminalContextMenu";
 export { ConnectionStatusIndicator } from "./ConnectionStatusIndicator";
 export { MultiInputIndicator } from "./MultiInputIndicator";
@@ -17,30 +17,67 @@
  * - 搜索功能
  * - 主题切换
  * - IME 输入法支持
+ * - 文件传输：OSC 1337 File= 弹出保存对话框；远程 rz 时通过 zmodem 上传文件，sz 自动取消
+ * - 内联图片：后端解码的 sixel / iTerm2 图片通过装饰层显示在光标位置
+ * - 粘贴安全：粘贴由后端按括号粘贴模式处理，包含换行或控制字符时提示确认
+ * - 拖放文件：通过 zmodem（rz）上传到远程，或将文件路径（按 shell 规则转义）粘贴到命令行
  *
  * _Requirements: 8.1, 8.2, 8.4, 8.5_
  */
 
-import { Terminal } from "@xterm/xterm";
+import { Terminal, type ILink } from "@xterm/xterm";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { open } from "@tauri-apps/plugin-shell";
+import { toast } from "sonner";
 import { WebLinksAddon } from "@xterm/addon-web-links";
 import { SearchAddon, type ISearchOptions } from "@xterm/addon-search";
 import { WebglAddon } from "@xterm/addon-webgl";
 import { Unicode11Addon } from "@xterm/addon-unicode11";
 import { FitAddon } from "./fitaddon";
 import {
+  attachTerminalSession,
   resizeTerminal,
   writeToTerminalRaw,
+  onClipboardRequest,
+  onPasteWarning,
+  onSessionImage,
+  onSessionLinks,
   onSessionOutput,
   onSessionStatus,
   decodeBytes,
   encodeBase64,
+  encodeBytesBase64,
+  getUploadFiles,
+  pasteToTerminal,
+  pickUploadFiles,
+  readUploadFile,
+  respondClipboardRequest,
+  respondPaste,
   type SessionStatus,
+  type TerminalClipboardRequestEvent,
+  type TerminalImageEvent,
+  type TerminalPasteWarningEvent,
+  type UploadFileInfo,
 } from "@/lib/terminal-api";
 import {
   type ThemeName,
   getTheme,
   loadThemePreference,
 } from "@/lib/terminal/themes";
+import {
+  FileTransferReceiver,
+  ITERM_OSC,
+  STAGED_UPLOAD_TTL_MS,
+  ZMODEM_ABORT,
+  ZMODEM_UPLOAD_COMMAND,
+  detectZmodem,
+  saveTransferredFile,
+  shellQuotePath,
+  type TransferredFile,
+} from "@/lib/terminal/file-transfer";
+import { ZmodemSender } from "@/lib/terminal/zmodem";
+import { TerminalLinkRegistry } from "@/lib/terminal/links";
+import { imageDataUrl, layoutInlineImage } from "@/lib/terminal/inline-images";
 
 /** 简单的 debounce 实现（对齐 waveterm 参数顺序） */
 function debounce<T extends (...args: unknown[]) => void>(
@@ -79,6 +116,14 @@ export interface TermWrapOptions {
    * 返回 false 表示事件未处理，继续传递
    */
   keydownHandler?: (e: KeyboardEvent) => boolean;
+  /** 是否为恢复的后台会话
+   * 为 true 时在订阅输出后连接会话，后端会先回填滚动历史
+   */
+  attachOnConnect?: boolean;
+  /** 打开终端输出中的文件链接
+   * 未设置时使用系统默认程序打开文件（忽略行列号）
+   */
+  onOpenFile?: (path: string, line?: number, column?: number) => void;
 }
 
 /** 搜索结果回调 */
@@ -133,6 +178,19 @@ export class TermWrap {
   /** 事件监听器清理函数 */
   private unlistenOutput?: () => void;
   private unlistenStatus?: () => void;
+  private unlistenDragDrop?: () => void;
+  private unlistenLinks?: () => void;
+  private unlistenClipboard?: () => void;
+  private unlistenImage?: () => void;
+  private unlistenPaste?: () => void;
+  /** 输出中检测到的文件链接 */
+  private linkRegistry = new TerminalLinkRegistry();
+  /** OSC 1337 文件接收器 */
+  private fileReceiver: FileTransferReceiver;
+  /** 进行中的 zmodem 上传（输出交给发送端处理） */
+  private zmodem: ZmodemSender | null = null;
+  /** 拖放后等待远程 rz 启动的文件 */
+  private stagedUpload: { files: UploadFileInfo[]; at: number } | null = null;
   /** WebGL 是否启用 */
   private webglEnabled: boolean;
   /** IME 组合状态
@@ -220,6 +278,23 @@ export class TermWrap {
       this.terminal.attachCustomKeyEventHandler(options.keydownHandler);
     }
 
+    // 注册 OSC 1337 文件传输处理器
+    this.fileReceiver = new FileTransferReceiver((file) => {
+      void this.handleFileTransfer(file);
+    });
+    this.toDispose.push(
+      this.terminal.parser.registerOscHandler(ITERM_OSC, (data) =>
+        this.fileReceiver.handle(data),
+      ),
+    );
+
+    // 注册文件链接提供器（链接由后端检测后通过 terminal:links 事件上报）
+    this.toDispose.push(
+      this.terminal.registerLinkProvider({
+        provideLinks: (y, callback) => callback(this.provideFileLinks(y)),
+      }),
+    );
+
     // 尝试加载 WebGL 渲染器
     // _Requirements: 8.2_
     this.tryLoadWebgl();
@@ -307,6 +382,9 @@ export class TermWrap {
     // _Requirements: 8.11_
     this.setupIMEHandlers();
 
+    // 粘贴交给后端处理（括号粘贴、换行和控制字符确认）
+    this.setupPasteHandler();
+
     // 暂存数据队列（对齐 waveterm 的 heldData）
     const heldData: Uint8Array[] = [];
 
@@ -326,11 +404,50 @@ export class TermWrap {
       this.unlistenStatus = await onSessionStatus(this.sessionId, (event) => {
         this.options.onStatusChange?.(event.status);
       });
+
+      // 监听输出中检测到的链接
+      this.unlistenLinks = await onSessionLinks(this.sessionId, (event) => {
+        this.linkRegistry.add(event);
+      });
+
+      // 监听需确认的 OSC 52 剪贴板请求
+      this.unlistenClipboard = await onClipboardRequest(
+        this.sessionId,
+        (event) => this.handleClipboardRequest(event),
+      );
+
+      // 监听需确认的粘贴
+      this.unlistenPaste = await onPasteWarning(this.sessionId, (event) =>
+        this.handlePasteWarning(event),
+      );
+
+      // 监听输出中的内联图片
+      this.unlistenImage = await onSessionImage(this.sessionId, (event) =>
+        this.handleInlineImage(event),
+      );
+
+      // 恢复的后台会话：订阅完成后再连接，确保滚动历史不丢失
+      if (this.options.attachOnConnect) {
+        await attachTerminalSession(this.sessionId);
+      }
     } catch (err) {
       console.error("[TermWrap] 连接失败:", err);
       this.options.onStatusChange?.("error");
     }
 
+    // 监听拖放文件（失败不影响终端使用）
+    try {
+      this.unlistenDragDrop = await getCurrentWindow().onDragDropEvent(
+        (event) => {
+          if (event.payload.type === "drop") {
+            this.handleFileDrop(event.payload.paths, event.payload.position);
+          }
+        },
+      );
+    } catch (err) {
+      console.warn("[TermWrap] 监听拖放事件失败:", err);
+    }
+
     // 标记为已加载（对齐 waveterm）
     this.loaded = true;
 
@@ -387,11 +504,332 @@ export class TermWrap {
     }
     this.writeQueue = [];
 
+    // zmodem 上传进行中的输出交给发送端，结束后剩余的输出交还终端显示
+    let output = merged;
+    if (this.zmodem) {
+      output = this.zmodem.feed(merged);
+    } else {
+      const zmodem = detectZmodem(merged);
+      if (zmodem?.direction === "upload") {
+        output = merged.subarray(0, zmodem.offset);
+        this.startZmodemUpload(merged.subarray(zmodem.offset));
+      } else if (zmodem) {
+        this.abortZmodemDownload();
+      }
+    }
+    if (output.length === 0) {
+      return;
+    }
+
     // 一次性写入
-    const decoded = decodeBytes(merged);
+    const decoded = decodeBytes(output);
     this.terminal.write(decoded);
   }
 
+  /**
+   * 保存远程通过 OSC 1337 发送的文件
+   */
+  private async handleFileTransfer(file: TransferredFile): Promise<void> {
+    try {
+      const path = await saveTransferredFile(file);
+      if (path) {
+        toast.success(`文件已保存: ${path}`);
+      }
+    } catch (err) {
+      console.error("[TermWrap] 保存传输文件失败:", err);
+      toast.error(`保存文件失败: ${err}`);
+    }
+  }
+
+  /**
+   * 询问用户是否允许远程程序访问本机剪贴板
+   *
+   * 关闭或超时未处理的提示按拒绝处理。
+   */
+  private handleClipboardRequest(event: TerminalClipboardRequestEvent): void {
+    let responded = false;
+    const respond = (allow: boolean) => {
+      if (responded) {
+        return;
+      }
+      responded = true;
+      respondClipboardRequest(event.request_id, allow).catch(console.error);
+    };
+    const host = event.host === "local" ? "本地会话" : event.host;
+    const isWrite = event.operation === "write";
+    toast(`${host} 请求${isWrite ? "写入" : "读取"}剪贴板`, {
+      description: isWrite
+        ? `${event.size ?? 0} 字节：${event.preview ?? ""}`
+        : "允许后剪贴板内容将发送给终端中的程序",
+      duration: 15000,
+      action: { label: "允许", onClick: () => respond(true) },
+      cancel: { label: "拒绝", onClick: () => respond(false) },
+      onDismiss: () => respond(false),
+      onAutoClose: () => respond(false),
+    });
+  }
+
+  /**
+   * 询问用户是否粘贴包含换行或控制字符的内容
+   *
+   * 关闭或超时未处理的提示按取消处理。
+   */
+  private handlePasteWarning(event: TerminalPasteWarningEvent): void {
+    let responded = false;
+    const respond = (allow: boolean) => {
+      if (responded) {
+        return;
+      }
+      responded = true;
+      respondPaste(event.paste_id, allow).catch(console.error);
+    };
+    const reasons: string[] = [];
+    if (event.warnings.includes("multi_line")) {
+      reasons.push(`${event.line_breaks} 个换行`);
+    }
+    if (event.warnings.includes("control_characters")) {
+      reasons.push(`${event.control_chars} 个控制字符`);
+    }
+    toast.warning(`粘贴内容包含${reasons.join("、")}`, {
+      description: event.bracketed
+        ? event.preview
+        : `未启用括号粘贴，换行会立即执行命令：${event.preview}`,
+      duration: 15000,
+      action: { label: "粘贴", onClick: () => respond(true) },
+      cancel: { label: "取消", onClick: () => respond(false) },
+      onDismiss: () => respond(false),
+      onAutoClose: () => respond(false),
+    });
+  }
+
+  /**
+   * 显示内联图片
+   *
+   * 先写入图片序列之前的输出，在 xterm 解析完成后把图片放在光标位置。
+   * 加载完成前收到的图片随暂存的输出一起丢弃。
+   */
+  private handleInlineImage(event: TerminalImageEvent): void {
+    if (!this.loaded) {
+      return;
+    }
+    this.flushWriteQueue();
+    this.terminal.write("", () => this.placeInlineImage(event));
+  }
+
+  /**
+   * 在光标位置添加图片装饰，并在主缓冲区中为图片预留行
+   */
+  private placeInlineImage(event: TerminalImageEvent): void {
+    const { cols, rows } = this.terminal;
+    const screen =
+      this.terminal.element?.querySelector<HTMLElement>(".xterm-screen");
+    if (!screen || screen.clientWidth === 0 || screen.clientHeight === 0) {
+      return;
+    }
+    const cell = {
+      width: screen.clientWidth / cols,
+      height: screen.clientHeight / rows,
+    };
+    const layout = layoutInlineImage(event, cell, cols, rows);
+
+    const marker = this.terminal.registerMarker(0);
+    if (!marker) {
+      return;
+    }
+    const decoration = this.terminal.registerDecoration({
+      marker,
+      x: this.terminal.buffer.active.cursorX,
+      width: layout.cols,
+      height: layout.rows,
+      layer: "top",
+    });
+    if (!decoration) {
+      marker.dispose();
+      return;
+    }
+    decoration.onDispose(() => marker.dispose());
+    decoration.onRender((element) => {
+      if (element.firstChild) {
+        return;
+      }
+      const img = document.createElement("img");
+      img.src = imageDataUrl(event.mime, event.data);
+      img.alt = event.name ?? "";
+      img.draggable = false;
+      img.style.width = `${layout.width}px`;
+      img.style.height = `${layout.height}px`;
+      img.style.pointerEvents = "none";
+      element.appendChild(img);
+    });
+
+    // 全屏程序（备用缓冲区）自行管理光标，主缓冲区中后续输出显示在图片下方
+    if (this.terminal.buffer.active.type === "normal") {
+      this.terminal.write("\r\n".repeat(layout.rows));
+    }
+  }
+
+  /**
+   * 取消 zmodem 下载
+   *
+   * 终端不实现 zmodem 下载，向远程发送取消序列，避免 sz 一直等待握手。
+   */
+  private abortZmodemDownload(): void {
+    this.sendData(ZMODEM_ABORT);
+    toast.warning(
+      "终端不支持 zmodem 下载，已取消 sz。可使用 it2dl（OSC 1337）下载文件",
+    );
+  }
+
+  /**
+   * 远程启动了 rz：开始 zmodem 上传
+   *
+   * @param initial - 从 rz 起始帧开始的输出
+   */
+  private startZmodemUpload(initial: Uint8Array): void {
+    const toastId = `zmodem-${this.sessionId}`;
+    const sender = new ZmodemSender({
+      send: (data) =>
+        writeToTerminalRaw(this.sessionId, encodeBytesBase64(data)),
+      read: (file, offset, length) => readUploadFile(file.path, offset, length),
+      onProgress: (file, offset) => {
+        const percent =
+          file.size > 0 ? Math.floor((offset / file.size) * 100) : 100;
+        toast.loading(`正在上传 ${file.name}`, {
+          id: toastId,
+          description: `${percent}%`,
+        });
+      },
+    });
+    this.zmodem = sender;
+    sender.feed(initial);
+    void this.runZmodemUpload(sender, toastId).finally(() => {
+      if (this.zmodem === sender) {
+        this.zmodem = null;
+      }
+    });
+  }
+
+  /**
+   * 发送拖放的文件，没有拖放的文件时弹出文件对话框选择
+   */
+  private async runZmodemUpload(
+    sender: ZmodemSender,
+    toastId: string,
+  ): Promise<void> {
+    try {
+      const staged = this.stagedUpload;
+      this.stagedUpload = null;
+      const files =
+        staged && Date.now() - staged.at < STAGED_UPLOAD_TTL_MS
+          ? staged.files
+          : await pickUploadFiles();
+      if (files.length === 0) {
+        await sender.abort();
+        return;
+      }
+      const result = await sender.upload(files);
+      toast.success(`已上传 ${result.sent.length} 个文件`, {
+        id: toastId,
+        description:
+          result.skipped.length > 0
+            ? `远程拒绝接收（可能已存在）：${result.skipped.join("、")}`
+            : undefined,
+      });
+    } catch (err) {
+      console.error("[TermWrap] zmodem 上传失败:", err);
+      await sender.abort().catch(console.error);
+      toast.error(`上传失败: ${err}`, { id: toastId });
+    }
+  }
+
+  /**
+   * 处理拖放到终端的文件
+   *
+   * @param paths - 拖放的文件路径
+   * @param position - 拖放位置（物理像素）
+   */
+  private handleFileDrop(
+    paths: string[],
+    position: { x: number; y: number },
+  ): void {
+    if (paths.length === 0) {
+      return;
+    }
+    const scale = window.devicePixelRatio || 1;
+    const x = position.x / scale;
+    const y = position.y / scale;
+    const rect = this.connectElem.getBoundingClientRect();
+    if (x < rect.left || x > rect.right || y < rect.top || y > rect.bottom) {
+      return;
+    }
+    void this.offerFileUpload(paths);
+  }
+
+  /**
+   * 询问拖放的文件通过 zmodem 上传到远程，还是将转义后的路径粘贴到命令行
+   *
+   * 选择上传时在远程执行 rz，rz 启动后发送这些文件；不包含普通文件时直接粘贴路径。
+   */
+  private async offerFileUpload(paths: string[]): Promise<void> {
+    const pastePaths = () => {
+      this.terminal.paste(paths.map(shellQuotePath).join(" ") + " ");
+      this.focus();
+    };
+    const files = await getUploadFiles(paths).catch((err) => {
+      console.warn("[TermWrap] 获取拖放文件失败:", err);
+      return [];
+    });
+    if (files.length === 0 || this.zmodem) {
+      pastePaths();
+      return;
+    }
+    toast(`上传 ${files.length} 个文件到远程？`, {
+      description: files.map((file) => file.name).join("、"),
+      duration: 15000,
+      action: {
+        label: "上传 (rz)",
+        onClick: () => {
+          this.stagedUpload = { files, at: Date.now() };
+          this.sendData(ZMODEM_UPLOAD_COMMAND);
+          this.focus();
+        },
+      },
+      cancel: { label: "粘贴路径", onClick: pastePaths },
+    });
+  }
+
+  /**
+   * 提供缓冲区一行中的文件链接
+   *
+   * 按字符位置换算列号，行内有宽字符时链接位置可能偏移。
+   *
+   * @param y - 缓冲区行号（从 1 开始）
+   */
+  private provideFileLinks(y: number): ILink[] | undefined {
+    const line = this.terminal.buffer.active.getLine(y - 1);
+    if (!line) {
+      return undefined;
+    }
+    const matches = this.linkRegistry.match(line.translateToString(true));
+    if (matches.length === 0) {
+      return undefined;
+    }
+    return matches.map(({ link, path, start, end }) => ({
+      range: { start: { x: start + 1, y }, end: { x: end, y } },
+      text: link.text,
+      activate: () => {
+        if (this.options.onOpenFile) {
+          this.options.onOpenFile(path, link.line, link.column);
+        } else {
+          open(path).catch((err) => {
+            console.error("[TermWrap] 打开文件失败:", err);
+            toast.error(`无法打开文件: ${path}`);
+          });
+        }
+      },
+    }));
+  }
+
   /**
    * 设置 IME 输入法事件处理
    *
@@ -427,6 +865,28 @@ export class TermWrap {
     });
   }
 
+  /**
+   * 拦截终端中的粘贴事件，改由后端处理
+   *
+   * 在捕获阶段拦截，xterm 自身的粘贴处理不会收到事件。
+   */
+  private setupPasteHandler(): void {
+    const onPaste = (e: ClipboardEvent) => {
+      const text = e.clipboardData?.getData("text/plain");
+      if (!text) {
+        return;
+      }
+      e.preventDefault();
+      e.stopPropagation();
+      this.paste(text);
+    };
+    this.connectElem.addEventListener("paste", onPaste, true);
+    this.toDispose.push({
+      dispose: () =>
+        this.connectElem.removeEventListener("paste", onPaste, true),
+    });
+  }
+
   /**
    * 处理终端大小变化（对齐 waveterm）
    *
@@ -623,6 +1083,21 @@ export class TermWrap {
     writeToTerminalRaw(this.sessionId, base64).catch(console.error);
   }
 
+  /**
+   * 粘贴文本（由后端按括号粘贴模式处理，包含换行或控制字符时提示确认）
+   *
+   * @param text - 粘贴的文本
+   */
+  paste(text: string): void {
+    if (!this.loaded) {
+      return;
+    }
+    pasteToTerminal(this.sessionId, text).catch((err) => {
+      console.error("[TermWrap] 粘贴失败:", err);
+      toast.error(`粘贴失败: ${err}`);
+    });
+  }
+
   /**
    * 清空终端
    */
@@ -666,6 +1141,15 @@ export class TermWrap {
     // 清理事件监听
     this.unlistenOutput?.();
     this.unlistenStatus?.();
+    this.unlistenDragDrop?.();
+    this.unlistenLinks?.();
+    this.unlistenClipboard?.();
+    this.unlistenImage?.();
+    this.unlistenPaste?.();
+
+    // 取消进行中的 zmodem 上传（会话可能在后台继续运行）
+    this.zmodem?.abort().catch(console.error);
+    this.zmodem = null;
 
     // 清理 WebGL
     this.disposeWebgl();
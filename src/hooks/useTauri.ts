@@ -1,4 +1,5 @@
 // 使用共享的 safeInvoke
+import { Channel } from "@tauri-apps/api/core";
 import { safeInvoke } from "@/lib/dev-bridge";
 
 export interface ServerStatus {
@@ -16,6 +17,13 @@ export interface TlsConfig {
   key_path: string | null;
 }
 
+// HTTP Compression Configuration
+export interface CompressionConfig {
+  enable: boolean;
+  min_size: number;
+  decompress_requests: boolean;
+}
+
 // Remote Management Configuration
 export interface RemoteManagementConfig {
   allow_remote: boolean;
@@ -135,6 +143,8 @@ export interface Config {
     port: number;
     api_key: string;
     tls: TlsConfig;
+    allowed_ips?: string[];
+    compression?: CompressionConfig;
   };
   providers: {
     kiro: {
@@ -166,6 +176,14 @@ export interface Config {
   quota_exceeded: QuotaExceededConfig;
   ampcode: AmpConfig;
   credential_pool: CredentialPoolConfig;
+  /** 凭证池负载均衡策略 */
+  load_balance_strategy?: LoadBalanceStrategy;
+  /** 会话粘性路由 */
+  session_affinity?: SessionAffinityConfig;
+  /** 故障注入（仅用于测试容错逻辑） */
+  chaos?: ChaosConfig;
+  /** 选择器端点（键为 `/{selector}/v1/*` 中的选择器） */
+  selector_endpoints?: Record<string, SelectorEndpointConfig>;
   proxy_url: string | null;
   /** 关闭时最小化到托盘（而不是退出应用） */
   minimize_to_tray: boolean;
@@ -173,12 +191,95 @@ export interface Config {
   language: string;
   /** 实验室功能配置 */
   experimental?: ExperimentalFeatures;
+  /** 当前生效的配置 Profile */
+  active_profile?: string | null;
+  /** 命名配置 Profile（深度合并到基础配置之上） */
+  profiles?: Record<string, unknown>;
+}
+
+export interface ConfigProfiles {
+  active: string | null;
+  profiles: string[];
+}
+
+export type LoadBalanceStrategy =
+  | "smart"
+  | "round_robin"
+  | "weighted"
+  | "least_connections"
+  | "least_recent_error"
+  | "latency_p95";
+
+export interface SessionAffinityConfig {
+  enabled: boolean;
+  /** 客户端传入会话标识的请求头 */
+  header: string;
+  /** 未传请求头时，用首条用户消息的哈希识别会话 */
+  hash_first_message: boolean;
+  ttl_secs: number;
+  max_sessions: number;
+}
+
+export interface ChaosConfig {
+  enabled: boolean;
+  /** 注入故障的 Provider，为空表示所有 Provider */
+  providers?: string[];
+  /** 各类故障的注入概率（0 ~ 1） */
+  error_rate: number;
+  error_status: number;
+  latency_rate: number;
+  latency_ms: number;
+  malformed_stream_rate: number;
+}
+
+export interface SelectorEndpointConfig {
+  /** 端点专用 API Key，配置后只接受该 Key */
+  api_key?: string;
+  /** 实际解析的选择器，为空时使用端点名 */
+  target?: string;
+  /** 允许使用的凭证（名称或 UUID），为空表示不限制 */
+  credentials?: string[];
+  rate_limit_per_minute?: number;
+  /** 遥测分组，默认 `endpoint:<端点名>` */
+  telemetry_bucket?: string;
 }
 
 export interface LogEntry {
   timestamp: string;
   level: string;
   message: string;
+  /** 产生该日志的代理请求 ID */
+  request_id?: string;
+}
+
+export interface LogQuery {
+  /** 日志级别，为空时不过滤 */
+  levels?: string[];
+  /** 只返回指定请求产生的日志 */
+  request_id?: string;
+  /** 搜索关键词（忽略大小写） */
+  search?: string;
+  /** 是否将 search 作为正则表达式 */
+  regex?: boolean;
+  limit?: number;
+  offset?: number;
+}
+
+export interface LogPage {
+  total: number;
+  limit: number;
+  offset: number;
+  /** 按时间倒序 */
+  entries: LogEntry[];
+}
+
+export interface LogStats {
+  total: number;
+  memory_bytes: number;
+  max_memory_bytes: number;
+  by_level: Record<string, number>;
+  /** 因容量或内存上限被丢弃的日志条数 */
+  dropped: Record<string, number>;
 }
 
 export async function startServer(): Promise<string> {
@@ -189,6 +290,10 @@ export async function stopServer(): Promise<string> {
   return safeInvoke("stop_server");
 }
 
+export async function restartServer(): Promise<string> {
+  return safeInvoke("restart_server");
+}
+
 export async function getServerStatus(): Promise<ServerStatus> {
   return safeInvoke("get_server_status");
 }
@@ -201,6 +306,17 @@ export async function saveConfig(config: Config): Promise<void> {
   return safeInvoke("save_config", { config });
 }
 
+export async function getConfigProfiles(): Promise<ConfigProfiles> {
+  return safeInvoke("get_config_profiles");
+}
+
+/** 切换配置 Profile，传 null 仅使用基础配置 */
+export async function switchConfigProfile(
+  profile: string | null,
+): Promise<Config> {
+  return safeInvoke("switch_config_profile", { profile });
+}
+
 export async function getDefaultProvider(): Promise<string> {
   return safeInvoke("get_default_provider");
 }
@@ -247,6 +363,34 @@ export async function getLogs(): Promise<LogEntry[]> {
   }
 }
 
+export async function queryLogs(query?: LogQuery): Promise<LogPage> {
+  return safeInvoke("query_logs", { query });
+}
+
+export async function getLogStats(): Promise<LogStats> {
+  return safeInvoke("get_log_stats");
+}
+
+/**
+ * 将日志导出为 NDJSON 文件（每行一个 JSON 对象，带 request_id），返回导出的条数
+ * @param path 导出文件路径（支持 ~）
+ */
+export async function exportLogs(
+  path: string,
+  query?: LogQuery,
+): Promise<number> {
+  return safeInvoke("export_logs", { path, query });
+}
+
+/** 订阅新日志（实时日志视图），新日志通过 Channel 推送 */
+export async function subscribeLogs(
+  onEntry: (entry: LogEntry) => void,
+): Promise<void> {
+  const channel = new Channel<LogEntry>();
+  channel.onmessage = onEntry;
+  await safeInvoke("subscribe_logs", { channel });
+}
+
 export async function clearLogs(): Promise<void> {
   try {
     await safeInvoke("clear_logs");
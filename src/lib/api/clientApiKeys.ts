@@ -0,0 +1,91 @@
+/**
+ * @file 客户端 API Key API 模块
+ * @description 封装客户端 API Key（访问代理服务的多个 Key 及其作用域）相关的 Tauri 命令调用
+ * @module lib/api/clientApiKeys
+ */
+
+import { safeInvoke } from "@/lib/dev-bridge";
+
+/**
+ * 客户端 API Key
+ */
+export interface ClientApiKey {
+  id: string;
+  name: string;
+  /** Key 前缀（用于展示识别） */
+  key_prefix: string;
+  enabled: boolean;
+  /** 允许的模型（为空表示不限制，支持 `*` 后缀通配） */
+  allowed_models: string[];
+  /** 允许的 Provider（为空表示不限制） */
+  allowed_providers: string[];
+  /** 每分钟请求数上限 */
+  rate_limit_per_minute?: number | null;
+  usage_count: number;
+  last_used_at?: string | null;
+  created_at: string;
+}
+
+/**
+ * 创建结果（明文 Key 仅返回一次）
+ */
+export interface CreatedClientApiKey extends ClientApiKey {
+  api_key: string;
+}
+
+/**
+ * 创建客户端 API Key 请求
+ */
+export interface CreateClientApiKeyRequest {
+  name: string;
+  allowed_models?: string[];
+  allowed_providers?: string[];
+  rate_limit_per_minute?: number;
+}
+
+/**
+ * 更新客户端 API Key 请求（字段为空表示不修改）
+ */
+export interface UpdateClientApiKeyRequest {
+  name?: string;
+  enabled?: boolean;
+  allowed_models?: string[];
+  allowed_providers?: string[];
+  /** 传 0 表示取消限流 */
+  rate_limit_per_minute?: number;
+}
+
+export const clientApiKeysApi = {
+  /**
+   * 获取所有客户端 API Key
+   */
+  async list(): Promise<ClientApiKey[]> {
+    return safeInvoke("get_client_api_keys");
+  },
+
+  /**
+   * 创建客户端 API Key
+   */
+  async create(
+    request: CreateClientApiKeyRequest,
+  ): Promise<CreatedClientApiKey> {
+    return safeInvoke("create_client_api_key", { request });
+  },
+
+  /**
+   * 更新客户端 API Key
+   */
+  async update(
+    id: string,
+    request: UpdateClientApiKeyRequest,
+  ): Promise<ClientApiKey> {
+    return safeInvoke("update_client_api_key", { id, request });
+  },
+
+  /**
+   * 删除客户端 API Key
+   */
+  async delete(id: string): Promise<boolean> {
+    return safeInvoke("delete_client_api_key", { id });
+  },
+};
@@ -3,11 +3,25 @@ import { safeInvoke } from "@/lib/dev-bridge";
 // Injection mode
 export type InjectionMode = "merge" | "override";
 
+// System prompt injection
+export interface SystemPromptInjection {
+  text: string;
+  position?: "prepend" | "append";
+}
+
 // Injection rule
 export interface InjectionRule {
   id: string;
   pattern: string;
   parameters: Record<string, unknown>;
+  // Headers injected into upstream requests
+  headers?: Record<string, string>;
+  // Text prepended/appended to the system prompt
+  system_prompt?: SystemPromptInjection;
+  // Providers the headers and system prompt apply to (empty = all)
+  providers?: string[];
+  // Predicate evaluated against the request body, e.g. `$.tools && !$.temperature`
+  condition?: string;
   mode: InjectionMode;
   priority: number;
   enabled: boolean;
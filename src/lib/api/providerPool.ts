@@ -9,6 +9,12 @@ export type PoolProviderType =
   | "claude"
   | "codex"
   | "claude_oauth"
+  | "qwen"
+  | "vertex"
+  | "azure_openai"
+  | "aws_bedrock"
+  | "ollama"
+  | "mock"
   | "gemini_api_key";
 
 // Credential data types
@@ -58,6 +64,49 @@ export interface ClaudeOAuthCredential {
   creds_file_path: string;
 }
 
+export interface QwenOAuthCredential {
+  type: "qwen_oauth";
+  creds_file_path: string;
+}
+
+export interface VertexServiceAccountCredential {
+  type: "vertex_service_account";
+  creds_file_path: string;
+  project_id?: string;
+  region?: string;
+}
+
+export interface AzureOpenaiKeyCredential {
+  type: "azure_openai_key";
+  api_key: string;
+  resource: string;
+  deployment?: string;
+  api_version?: string;
+  model_deployments?: Record<string, string>;
+}
+
+export interface AwsBedrockCredential {
+  type: "aws_bedrock";
+  region: string;
+  profile?: string;
+  role_arn?: string;
+  access_key_id?: string;
+  secret_access_key?: string;
+}
+
+export interface OllamaCredential {
+  type: "ollama";
+  base_url?: string;
+}
+
+export interface MockCredential {
+  type: "mock";
+  response?: string;
+  latency_ms?: number;
+  error_rate?: number;
+  error_status?: number;
+}
+
 export type CredentialData =
   | KiroOAuthCredential
   | GeminiOAuthCredential
@@ -66,7 +115,13 @@ export type CredentialData =
   | ClaudeKeyCredential
   | GeminiApiKeyCredential
   | CodexOAuthCredential
-  | ClaudeOAuthCredential;
+  | ClaudeOAuthCredential
+  | QwenOAuthCredential
+  | VertexServiceAccountCredential
+  | AzureOpenaiKeyCredential
+  | AwsBedrockCredential
+  | OllamaCredential
+  | MockCredential;
 
 // Provider credential
 export interface ProviderCredential {
@@ -124,9 +179,34 @@ export interface CredentialDisplay {
   api_key?: string;
   // 凭证级代理 URL（可覆盖全局代理设置）
   proxy_url?: string;
+  // 负载均衡权重（加权策略使用）
+  weight?: number;
 }
 
 // Pool statistics
+export type ImportSource =
+  | "claude_code"
+  | "gemini_cli"
+  | "qwen_code"
+  | "codex_cli"
+  | "kiro";
+
+// 可导入的本机凭证
+export interface DetectedCredential {
+  source: ImportSource;
+  provider_type: PoolProviderType;
+  path: string;
+  account?: string;
+  already_imported: boolean;
+}
+
+// 自动发现的本地模型服务
+export interface LocalModelServer {
+  name: string;
+  base_url: string;
+  models: string[];
+}
+
 export interface PoolStats {
   total: number;
   healthy: number;
@@ -197,6 +277,8 @@ export interface UpdateCredentialRequest {
   new_api_key?: string;
   /// 新的代理 URL（可覆盖全局代理设置）
   new_proxy_url?: string;
+  /// 负载均衡权重（加权策略使用，0 表示仅作备用）
+  weight?: number;
 }
 
 export const providerPoolApi = {
@@ -357,6 +439,107 @@ export const providerPoolApi = {
     return safeInvoke("add_claude_oauth_credential", { credsFilePath, name });
   },
 
+  async addQwenOAuth(
+    credsFilePath: string,
+    name?: string,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("add_qwen_oauth_credential", { credsFilePath, name });
+  },
+
+  // projectId 为空时使用服务账号所属项目，region 默认 us-central1
+  async addVertexServiceAccount(
+    credsFilePath: string,
+    options: { projectId?: string; region?: string } = {},
+    name?: string,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("add_vertex_service_account_credential", {
+      credsFilePath,
+      projectId: options.projectId,
+      region: options.region,
+      name,
+    });
+  },
+
+  // modelDeployments 为模型名到部署名的映射，支持 * 通配符
+  async addAzureOpenAI(
+    apiKey: string,
+    resource: string,
+    options: {
+      deployment?: string;
+      apiVersion?: string;
+      modelDeployments?: Record<string, string>;
+    } = {},
+    name?: string,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("add_azure_openai_credential", {
+      apiKey,
+      resource,
+      deployment: options.deployment,
+      apiVersion: options.apiVersion,
+      modelDeployments: options.modelDeployments,
+      name,
+    });
+  },
+
+  // 访问密钥和 profile 都为空时使用环境变量或 default profile
+  async addAwsBedrock(
+    region: string,
+    options: {
+      profile?: string;
+      roleArn?: string;
+      accessKeyId?: string;
+      secretAccessKey?: string;
+    } = {},
+    name?: string,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("add_aws_bedrock_credential", {
+      region,
+      profile: options.profile,
+      roleArn: options.roleArn,
+      accessKeyId: options.accessKeyId,
+      secretAccessKey: options.secretAccessKey,
+      name,
+    });
+  },
+
+  // 本地模型服务（Ollama / LM Studio / llama.cpp），baseUrl 为空时使用 Ollama 默认地址
+  async addOllama(
+    baseUrl?: string,
+    name?: string,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("add_ollama_credential", { baseUrl, name });
+  },
+
+  // Mock 凭证：按模板返回响应，可配置延迟和错误注入（error_rate 为 0 ~ 1）
+  async addMock(
+    options: {
+      response?: string;
+      latencyMs?: number;
+      errorRate?: number;
+      errorStatus?: number;
+    },
+    name?: string,
+  ): Promise<ProviderCredential> {
+    return safeInvoke("add_mock_credential", { ...options, name });
+  },
+
+  // 探测本机常见端口上正在运行的本地模型服务
+  async detectLocalModelServers(): Promise<LocalModelServer[]> {
+    return safeInvoke("detect_local_model_servers");
+  },
+
+  // 扫描其他工具（Claude Code、gemini-cli、qwen-code、Codex CLI、Kiro）的本机凭证
+  async scanImportableCredentials(): Promise<DetectedCredential[]> {
+    return safeInvoke("scan_importable_credentials");
+  },
+
+  // 导入扫描到的凭证
+  async importDetectedCredentials(
+    paths: string[],
+  ): Promise<ProviderCredential[]> {
+    return safeInvoke("import_detected_credentials", { paths });
+  },
+
   // Antigravity OAuth 登录（打开浏览器授权）
   async startAntigravityOAuthLogin(
     name?: string,
@@ -499,6 +682,11 @@ export const providerPoolApi = {
     return safeInvoke("get_pool_credential_oauth_status", { uuid });
   },
 
+  // 启用/禁用凭证静态加密，返回迁移的凭证数量
+  async setCredentialEncryption(enabled: boolean): Promise<number> {
+    return safeInvoke("set_credential_encryption", { enabled });
+  },
+
   // Migration API
   async migratePrivateConfig(config: unknown): Promise<MigrationResult> {
     return safeInvoke("migrate_private_config_to_pool", { config });
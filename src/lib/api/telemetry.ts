@@ -6,6 +6,7 @@ export type RequestStatus =
   | "success"
   | "failed"
   | "timeout"
+  | "rate_limited"
   | "retrying"
   | "cancelled";
 
@@ -15,6 +16,7 @@ export interface RequestLog {
   provider: string;
   model: string;
   duration_ms: number;
+  ttfb_ms?: number;
   status: RequestStatus;
   http_status?: number;
   input_tokens?: number;
@@ -23,6 +25,11 @@ export interface RequestLog {
   error_message?: string;
   is_streaming: boolean;
   credential_id?: string;
+  client_key_id?: string;
+  /** 调用方工具名称（x-proxycast-client 头或 User-Agent 推断） */
+  client_name?: string;
+  /** 降级前请求的模型（超出上下文窗口改用长上下文模型时记录） */
+  fallback_from_model?: string;
   retry_count: number;
 }
 
@@ -31,6 +38,7 @@ export interface StatsSummary {
   successful_requests: number;
   failed_requests: number;
   timeout_requests: number;
+  rate_limited_requests: number;
   success_rate: number;
   avg_latency_ms: number;
   min_latency_ms?: number;
@@ -46,6 +54,7 @@ export interface ProviderStats {
   successful_requests: number;
   failed_requests: number;
   timeout_requests: number;
+  rate_limited_requests: number;
   success_rate: number;
   avg_latency_ms: number;
   min_latency_ms?: number;
@@ -61,6 +70,7 @@ export interface ModelStats {
   successful_requests: number;
   failed_requests: number;
   timeout_requests: number;
+  rate_limited_requests: number;
   success_rate: number;
   avg_latency_ms: number;
   min_latency_ms?: number;
@@ -79,6 +89,9 @@ export interface TokenStatsSummary {
   estimated_count: number;
   avg_input_tokens: number;
   avg_output_tokens: number;
+  total_cache_creation_input_tokens: number;
+  total_cache_read_input_tokens: number;
+  cache_hit_rate: number;
 }
 
 export interface ProviderTokenStats {
@@ -91,6 +104,9 @@ export interface ProviderTokenStats {
   estimated_count: number;
   avg_input_tokens: number;
   avg_output_tokens: number;
+  total_cache_creation_input_tokens: number;
+  total_cache_read_input_tokens: number;
+  cache_hit_rate: number;
 }
 
 export interface ModelTokenStats {
@@ -103,6 +119,9 @@ export interface ModelTokenStats {
   estimated_count: number;
   avg_input_tokens: number;
   avg_output_tokens: number;
+  total_cache_creation_input_tokens: number;
+  total_cache_read_input_tokens: number;
+  cache_hit_rate: number;
 }
 
 export interface PeriodTokenStats {
@@ -116,6 +135,86 @@ export interface PeriodTokenStats {
   estimated_count: number;
   avg_input_tokens: number;
   avg_output_tokens: number;
+  total_cache_creation_input_tokens: number;
+  total_cache_read_input_tokens: number;
+  cache_hit_rate: number;
+}
+
+export interface CostSummary {
+  input_tokens: number;
+  output_tokens: number;
+  input_cost: number;
+  output_cost: number;
+  total_cost: number;
+  record_count: number;
+  unpriced_count: number;
+}
+
+export interface DailyCost extends CostSummary {
+  date: string;
+}
+
+export interface CostReport {
+  currency: string;
+  period_start?: string;
+  period_end?: string;
+  total: CostSummary;
+  by_provider: Record<string, CostSummary>;
+  by_credential: Record<string, CostSummary>;
+  by_client: Record<string, CostSummary>;
+  by_model: Record<string, CostSummary>;
+  by_day: DailyCost[];
+  unpriced_models: string[];
+}
+
+export interface LatencyPercentiles {
+  count: number;
+  min_ms: number;
+  max_ms: number;
+  mean_ms: number;
+  p50_ms: number;
+  p95_ms: number;
+  p99_ms: number;
+}
+
+export type LatencyGroupBy = "provider" | "model" | "credential" | "combined";
+
+export interface LatencyStats {
+  provider?: string;
+  model?: string;
+  credential_id?: string;
+  total: LatencyPercentiles;
+  ttfb: LatencyPercentiles;
+}
+
+export interface LatencyReport {
+  group_by: LatencyGroupBy;
+  overall: LatencyStats;
+  groups: LatencyStats[];
+}
+
+export interface RequestLogQuery {
+  provider?: string;
+  model?: string;
+  status?: RequestStatus;
+  credential_id?: string;
+  client_key_id?: string;
+  client_name?: string;
+  /** ISO 8601 */
+  start?: string;
+  /** ISO 8601 */
+  end?: string;
+  /** 错误信息全文搜索关键词 */
+  search?: string;
+  limit?: number;
+  offset?: number;
+}
+
+export interface RequestLogPage {
+  total: number;
+  limit: number;
+  offset: number;
+  logs: RequestLog[];
 }
 
 export interface TimeRangeParam {
@@ -141,6 +240,12 @@ export async function getRequestLogDetail(
   return safeInvoke("get_request_log_detail", { id });
 }
 
+export async function searchRequestLogs(
+  query?: RequestLogQuery,
+): Promise<RequestLogPage> {
+  return safeInvoke("search_request_logs", { query });
+}
+
 export async function clearRequestLogs(): Promise<void> {
   return safeInvoke("clear_request_logs");
 }
@@ -165,6 +270,16 @@ export async function getStatsByModel(
   return safeInvoke("get_stats_by_model", { time_range: timeRange });
 }
 
+export async function getLatencyPercentiles(
+  timeRange?: TimeRangeParam,
+  groupBy?: LatencyGroupBy,
+): Promise<LatencyReport> {
+  return safeInvoke("get_latency_percentiles", {
+    time_range: timeRange,
+    group_by: groupBy,
+  });
+}
+
 // ========== Token 统计 API ==========
 
 export async function getTokenSummary(
@@ -190,3 +305,47 @@ export async function getTokenStatsByDay(
 ): Promise<PeriodTokenStats[]> {
   return safeInvoke("get_token_stats_by_day", { days });
 }
+
+// ========== 费用估算 API ==========
+
+export async function getCostReport(
+  timeRange?: TimeRangeParam,
+): Promise<CostReport> {
+  return safeInvoke("get_cost_report", { time_range: timeRange });
+}
+
+// ========== 用量报告 API ==========
+
+export type ReportPeriod = "daily" | "weekly";
+
+export type ReportFormat = "json" | "csv" | "markdown";
+
+/**
+ * 生成日报 / 周报
+ * @param date 报告周期内的任意日期（YYYY-MM-DD，UTC），省略时为最近一个已结束的周期
+ */
+export async function generateUsageReport(
+  period: ReportPeriod,
+  format: ReportFormat,
+  date?: string,
+): Promise<string> {
+  return safeInvoke("generate_usage_report", { period, format, date });
+}
+
+// ========== 异常告警 ==========
+
+export type AlertKind = "error_rate" | "daily_spend" | "credential_unhealthy";
+
+export interface TelemetryAlert {
+  id: string;
+  kind: AlertKind;
+  message: string;
+  /** 触发时的观测值（错误率百分比 / 费用 / 不健康分钟数） */
+  value: number;
+  threshold: number;
+  credential_id?: string;
+  fired_at: string;
+}
+
+/** 告警触发时推送的 Tauri 事件，payload 为 TelemetryAlert */
+export const TELEMETRY_ALERT_EVENT = "telemetry-alert";
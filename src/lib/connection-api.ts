@@ -6,12 +6,25 @@
  * @module lib/connection-api
  */
 
-import { safeInvoke } from "@/lib/dev-bridge";
+import { safeInvoke, safeListen } from "@/lib/dev-bridge";
 
 /**
  * 连接类型
  */
-export type ConnectionType = "local" | "ssh" | "wsl";
+export type ConnectionType =
+  | "local"
+  | "ssh"
+  | "mosh"
+  | "telnet"
+  | "serial"
+  | "docker"
+  | "k8s"
+  | "wsl";
+
+/**
+ * Mosh 预测回显模式
+ */
+export type MoshPredict = "adaptive" | "always" | "never" | "experimental";
 
 /**
  * 连接来源
@@ -36,6 +49,22 @@ export interface ConnectionListEntry {
   user?: string;
   /** 端口 */
   port?: number;
+  /** Mosh 预测回显模式 */
+  moshPredict?: MoshPredict;
+  /** 是否为原始 TCP（Telnet） */
+  telnetRaw?: boolean;
+  /** 串口设备路径 */
+  serialPort?: string;
+  /** 串口波特率 */
+  baudRate?: number;
+  /** 容器名称（Docker / Kubernetes） */
+  container?: string;
+  /** Kubernetes 命名空间 */
+  namespace?: string;
+  /** Kubernetes Pod 名称 */
+  pod?: string;
+  /** kubeconfig 上下文 */
+  kubeContext?: string;
 }
 
 /**
@@ -62,6 +91,22 @@ export interface ConnectionConfig {
   hidden?: boolean;
   /** WSL 发行版 */
   wslDistro?: string;
+  /** Mosh 预测回显模式 */
+  moshPredict?: MoshPredict;
+  /** 是否为原始 TCP（Telnet） */
+  telnetRaw?: boolean;
+  /** 串口设备路径 */
+  serialPort?: string;
+  /** 串口波特率 */
+  baudRate?: number;
+  /** 容器名称（Docker / Kubernetes） */
+  container?: string;
+  /** Kubernetes 命名空间 */
+  namespace?: string;
+  /** Kubernetes Pod 名称 */
+  pod?: string;
+  /** kubeconfig 上下文 */
+  kubeContext?: string;
 }
 
 /**
@@ -84,6 +129,22 @@ export interface AddConnectionRequest {
   proxyJump?: string;
   /** WSL 发行版 */
   wslDistro?: string;
+  /** Mosh 预测回显模式 */
+  moshPredict?: MoshPredict;
+  /** 是否为原始 TCP（Telnet） */
+  telnetRaw?: boolean;
+  /** 串口设备路径 */
+  serialPort?: string;
+  /** 串口波特率 */
+  baudRate?: number;
+  /** 容器名称（Docker / Kubernetes） */
+  container?: string;
+  /** Kubernetes 命名空间 */
+  namespace?: string;
+  /** Kubernetes Pod 名称 */
+  pod?: string;
+  /** kubeconfig 上下文 */
+  kubeContext?: string;
 }
 
 /**
@@ -96,6 +157,71 @@ export interface ConnectionResponse {
   error?: string;
 }
 
+/**
+ * SSH 端口转发类型
+ */
+export type ForwardKind = "local" | "remote" | "dynamic";
+
+/**
+ * SSH 端口转发状态
+ */
+export type ForwardState = "starting" | "active" | "stopped" | "error";
+
+/**
+ * SSH 端口转发配置
+ */
+export interface ForwardSpec {
+  /** 转发类型 */
+  kind: ForwardKind;
+  /** 监听地址（null 表示仅回环地址，"*" 表示所有地址） */
+  bind_address: string | null;
+  /** 监听端口（0 表示自动分配） */
+  bind_port: number;
+  /** 目标主机（动态转发为 null） */
+  target_host: string | null;
+  /** 目标端口（动态转发为 null） */
+  target_port: number | null;
+}
+
+/**
+ * SSH 端口转发隧道状态
+ */
+export interface ForwardStatus {
+  /** 隧道 ID */
+  id: string;
+  /** 转发配置 */
+  spec: ForwardSpec;
+  /** 是否来自 ssh_config */
+  from_config: boolean;
+  /** 隧道状态 */
+  state: ForwardState;
+  /** 实际监听端口 */
+  bound_port: number | null;
+  /** 最近一次错误 */
+  error: string | null;
+  /** 当前活跃连接数 */
+  active_connections: number;
+  /** 累计连接数 */
+  total_connections: number;
+  /** 发往远端的字节数 */
+  bytes_sent: number;
+  /** 从远端收到的字节数 */
+  bytes_received: number;
+}
+
+/**
+ * SSH 端口转发状态变更事件
+ */
+export interface SSHForwardChangeEvent {
+  /** 连接名称 */
+  connection: string;
+  /** 隧道状态 */
+  forward: ForwardStatus;
+}
+
+/** SSH 端口转发状态变更事件名 */
+export const SSH_FORWARD_CHANGE_EVENT = "terminal:ssh-forward-change";
+
 /**
  * 获取所有可用连接
  */
@@ -103,6 +229,13 @@ export async function listConnections(): Promise<ConnectionListEntry[]> {
   return safeInvoke<ConnectionListEntry[]>("connection_list");
 }
 
+/**
+ * 列出本机可用串口
+ */
+export async function listSerialPorts(): Promise<string[]> {
+  return safeInvoke<string[]>("connection_list_serial_ports");
+}
+
 /**
  * 添加新连接
  */
@@ -187,6 +320,67 @@ export async function importSSHHost(
   });
 }
 
+/**
+ * 获取 SSH 连接的端口转发
+ *
+ * @param connection - 连接名称（需已建立）
+ */
+export async function listForwards(
+  connection: string,
+): Promise<ForwardStatus[]> {
+  return safeInvoke<ForwardStatus[]>("connection_forward_list", {
+    connection,
+  });
+}
+
+/**
+ * 添加 SSH 端口转发
+ *
+ * 已连接时立即启动，否则在下次认证成功后启动。
+ *
+ * @param connection - 连接名称
+ * @param kind - 转发类型
+ * @param spec - 转发配置，如 "8080:localhost:80"、"127.0.0.1:1080"
+ */
+export async function addForward(
+  connection: string,
+  kind: ForwardKind,
+  spec: string,
+): Promise<ForwardStatus> {
+  return safeInvoke<ForwardStatus>("connection_forward_add", {
+    connection,
+    kind,
+    spec,
+  });
+}
+
+/**
+ * 移除 SSH 端口转发
+ *
+ * @param connection - 连接名称
+ * @param forwardId - 隧道 ID
+ */
+export async function removeForward(
+  connection: string,
+  forwardId: string,
+): Promise<void> {
+  await safeInvoke("connection_forward_remove", { connection, forwardId });
+}
+
+/**
+ * 监听 SSH 端口转发状态变更
+ *
+ * @param callback - 回调函数，接收状态变更事件
+ * @returns 取消监听函数
+ */
+export async function onForwardChange(
+  callback: (event: SSHForwardChangeEvent) => void,
+): Promise<() => void> {
+  return safeListen<SSHForwardChangeEvent>(SSH_FORWARD_CHANGE_EVENT, (event) => {
+    callback(event.payload);
+  });
+}
+
 /**
  * 将连接名称转换为 terminal 会话的 connection 字符串
  *
@@ -215,6 +409,47 @@ export function connectionToSessionString(entry: ConnectionListEntry): string {
     return `${user}@${host}`;
   }
 
+  if (entry.type === "mosh") {
+    const user = entry.user || "root";
+    const host = entry.host || entry.name;
+    const port = entry.port && entry.port !== 22 ? `:${entry.port}` : "";
+    const predict =
+      entry.moshPredict && entry.moshPredict !== "adaptive"
+        ? `?predict=${entry.moshPredict}`
+        : "";
+    return `mosh://${user}@${host}${port}${predict}`;
+  }
+
+  if (entry.type === "telnet") {
+    const host = entry.host || entry.name;
+    if (entry.telnetRaw) {
+      return `tcp://${host}:${entry.port}`;
+    }
+    const port = entry.port && entry.port !== 23 ? `:${entry.port}` : "";
+    return `telnet://${host}${port}`;
+  }
+
+  if (entry.type === "serial") {
+    const baud =
+      entry.baudRate && entry.baudRate !== 115200
+        ? `?baud=${entry.baudRate}`
+        : "";
+    return `serial://${entry.serialPort || entry.name}${baud}`;
+  }
+
+  if (entry.type === "docker") {
+    const user = entry.user ? `?user=${entry.user}` : "";
+    return `docker://${entry.container || entry.name}${user}`;
+  }
+
+  if (entry.type === "k8s") {
+    const namespace = entry.namespace || "default";
+    const pod = entry.pod || entry.name;
+    const container = entry.container ? `/${entry.container}` : "";
+    const context = entry.kubeContext ? `?context=${entry.kubeContext}` : "";
+    return `k8s://${namespace}/${pod}${container}${context}`;
+  }
+
   if (entry.type === "wsl") {
     return `wsl://${entry.name}`;
   }
@@ -115,6 +115,7 @@ const defaultMocks: Record<string, any> = {
   }),
   start_server: () => "Server started (mock)",
   stop_server: () => "Server stopped (mock)",
+  restart_server: () => "Server restarted (mock)",
 
   // 网络相关
   get_network_info: () => ({
@@ -164,8 +165,147 @@ const defaultMocks: Record<string, any> = {
   create_terminal_session: () => ({ uuid: "mock-terminal-uuid" }),
   terminal_create_session: () => ({ uuid: "mock-terminal-uuid" }),
   terminal_write: () => ({}),
+  terminal_interrupt_session: () => ({}),
+  terminal_send_key: () => true,
+  terminal_keymap_get: () => ({ bindings: [] }),
+  terminal_keymap_save: () => ({}),
+  terminal_paste: () => ({ status: "written" }),
+  terminal_paste_respond: (args: any) => ({
+    status: args?.allow ? "written" : "denied",
+  }),
   terminal_resize: () => ({}),
   terminal_close: () => ({}),
+  terminal_list_detached_sessions: () => [],
+  terminal_restore_session: () => ({}),
+  terminal_attach_session: () => ({}),
+  terminal_detach_session: () => ({}),
+  terminal_replay_start: () => ({
+    replay_id: "mock-replay",
+    block_id: "mock-terminal-uuid",
+    duration_ms: 0,
+    markers: [],
+    speed: 1,
+  }),
+  terminal_replay_control: () => ({}),
+  terminal_replay_stop: () => ({}),
+  terminal_history_query: () => [],
+  terminal_history_hosts: () => [],
+  terminal_history_delete: () => ({}),
+  terminal_history_clear: () => 0,
+  terminal_command_blocks: () => [],
+  terminal_command_output: (args: any) => ({
+    index: args?.index ?? 0,
+    command: null,
+    exit_code: null,
+    cwd: null,
+    format: args?.format ?? "plain",
+    output: "",
+    finished: true,
+    truncated: false,
+  }),
+  terminal_prompt_marks: () => ({ marks: [], line_count: 0, offset: 0 }),
+  terminal_prompt_rerun: () => ({}),
+  terminal_session_metrics: () => ({
+    uptime_secs: 0,
+    bytes_in: 0,
+    bytes_out: 0,
+    writes: 0,
+    reads: 0,
+    output_rate_bps: 0,
+    peak_output_rate_bps: 0,
+    echo_latency: {
+      count: 0,
+      min_ms: 0,
+      max_ms: 0,
+      mean_ms: 0,
+      p50_ms: 0,
+      p95_ms: 0,
+      p99_ms: 0,
+    },
+    resize_count: 0,
+  }),
+  terminal_save_file: () => null,
+  terminal_upload_files: () => [],
+  terminal_upload_pick_files: () => [],
+  terminal_upload_read: () => "",
+  terminal_profile_list: () => [],
+  terminal_profile_save: () => ({}),
+  terminal_profile_delete: () => true,
+  terminal_clipboard_respond: () => ({}),
+  terminal_clipboard_policy_get: (args: any) => ({
+    host: args?.host ?? "local",
+    write: args?.host === "local" ? "allow" : "prompt",
+    read: args?.host === "local" ? "prompt" : "deny",
+    max_bytes: 1024 * 1024,
+    updated_at: 0,
+  }),
+  terminal_clipboard_policy_list: () => [],
+  terminal_clipboard_policy_save: (args: any) => args?.policy ?? {},
+  terminal_clipboard_policy_delete: () => true,
+  terminal_image_get: () => null,
+  terminal_image_cache_clear: () => 0,
+  terminal_storage_settings_get: () => ({
+    scrollback_bytes: 256 * 1024,
+    compress_cold: true,
+    cold_max_bytes: 4 * 1024 * 1024,
+    retention: { max_age_days: 30, max_total_bytes: 512 * 1024 * 1024 },
+  }),
+  terminal_storage_settings_save: () => ({}),
+  terminal_storage_usage: () => [],
+  terminal_storage_prune: () => ({ removed: [], freed_bytes: 0 }),
+  terminal_share_create: (args: any) => ({
+    share: {
+      share_id: "mock-share",
+      session_id: args?.sessionId ?? "",
+      mode: args?.mode ?? "read_only",
+      created_at: Date.now(),
+      expires_at: Date.now() + (args?.ttlSecs ?? 600) * 1000,
+      connected: false,
+    },
+    token: "mock-token",
+    path: "/v1/terminal/share/mock-token/ws",
+  }),
+  terminal_share_list: () => [],
+  terminal_share_revoke: () => ({}),
+  terminal_workspace_list: () => [],
+  terminal_workspace_create: (args: any) => ({
+    id: "mock-workspace",
+    name: args?.name ?? "",
+    position: 0,
+    layout: null,
+    active_session_id: null,
+    created_at: Date.now(),
+    updated_at: Date.now(),
+  }),
+  terminal_workspace_rename: (args: any) => ({
+    id: args?.workspaceId ?? "mock-workspace",
+    name: args?.name ?? "",
+    position: 0,
+    layout: null,
+    active_session_id: null,
+    created_at: Date.now(),
+    updated_at: Date.now(),
+  }),
+  terminal_workspace_set_layout: (args: any) => ({
+    id: args?.workspaceId ?? "mock-workspace",
+    name: "",
+    position: 0,
+    layout: args?.layout ?? null,
+    active_session_id: args?.activeSessionId ?? null,
+    created_at: Date.now(),
+    updated_at: Date.now(),
+  }),
+  terminal_workspace_reorder: () => [],
+  terminal_workspace_delete: () => ({}),
+  terminal_workspace_place_session: (args: any) => ({
+    id: args?.placement?.workspace_id ?? "mock-workspace",
+    name: "",
+    position: 0,
+    layout: { type: "pane", session_id: args?.sessionId ?? "" },
+    active_session_id: args?.sessionId ?? null,
+    created_at: Date.now(),
+    updated_at: Date.now(),
+  }),
   read_terminal_output: () => [],
   list_terminal_sessions: () => [],
 
@@ -246,6 +386,7 @@ const defaultMocks: Record<string, any> = {
   add_antigravity_oauth_credential: () => ({ success: true }),
   add_codex_oauth_credential: () => ({ success: true }),
   add_claude_oauth_credential: () => ({ success: true }),
+  add_qwen_oauth_credential: () => ({ success: true }),
   add_iflow_oauth_credential: () => ({ success: true }),
   add_iflow_cookie_credential: () => ({ success: true }),
   start_kiro_builder_id_login: () => ({ success: true }),
@@ -273,6 +414,9 @@ const defaultMocks: Record<string, any> = {
   // 连接相关
   list_connections: () => [],
   connection_list: () => [],
+  connection_forward_list: () => [],
+  connection_forward_add: () => ({}),
+  connection_forward_remove: () => ({}),
   get_oauth_url: () => ({ url: "https://example.com/oauth" }),
   save_oauth_credential: () => ({ success: true }),
   get_oauth_credentials: () => [],
@@ -392,14 +536,31 @@ const defaultMocks: Record<string, any> = {
   // Telemetry 相关
   get_request_logs: () => ({ logs: [] }),
   get_request_log_detail: () => ({ log: null }),
+  search_request_logs: () => ({ total: 0, limit: 50, offset: 0, logs: [] }),
   clear_request_logs: () => ({ success: true }),
   get_stats_summary: () => ({ summary: {} }),
   get_stats_by_provider: () => ({ stats: [] }),
   get_stats_by_model: () => ({ stats: [] }),
+  get_latency_percentiles: () => ({
+    group_by: "provider",
+    overall: { total: {}, ttfb: {} },
+    groups: [],
+  }),
   get_token_summary: () => ({ summary: {} }),
   get_token_stats_by_provider: () => ({ stats: [] }),
   get_token_stats_by_model: () => ({ stats: [] }),
   get_token_stats_by_day: () => ({ stats: [] }),
+  get_cost_report: () => ({
+    currency: "USD",
+    total: {},
+    by_provider: {},
+    by_credential: {},
+    by_client: {},
+    by_model: {},
+    by_day: [],
+    unpriced_models: [],
+  }),
+  generate_usage_report: () => "",
 
   // Routes 相关
   get_available_routes: () => ({ routes: [] }),
@@ -520,6 +681,16 @@ const defaultMocks: Record<string, any> = {
 
   // Log 相关
   get_logs: () => [],
+  query_logs: () => ({ total: 0, limit: 200, offset: 0, entries: [] }),
+  get_log_stats: () => ({
+    total: 0,
+    memory_bytes: 0,
+    max_memory_bytes: 8 * 1024 * 1024,
+    by_level: {},
+    dropped: {},
+  }),
+  subscribe_logs: () => undefined,
+  export_logs: () => 0,
   clear_logs: () => ({}),
 
   // Test 相关
@@ -654,5 +825,12 @@ export function clearMocks() {
   mockCommands.clear();
 }
 
+/**
+ * Mock Channel（纯浏览器开发时不会收到任何消息）
+ */
+export class Channel<T = unknown> {
+  onmessage: (message: T) => void = () => {};
+}
+
 // 导出类型以保持兼容
 export type { InvokeOptions } from "@tauri-apps/api/core";
@@ -130,6 +130,11 @@ class MockWindow {
     console.log(`[Mock] Window ${this.label} onThemeChanged`);
     return () => {};
   }
+
+  async onDragDropEvent(_handler: (event: any) => void): Promise<() => void> {
+    console.log(`[Mock] Window ${this.label} onDragDropEvent`);
+    return () => {};
+  }
 }
 
 // 当前窗口实例
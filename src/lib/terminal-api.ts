@@ -13,6 +13,12 @@
  * - 发送输入到终端
  * - 调整终端大小
  * - 监听终端输出和状态事件
+ * - 监听输出洪泛事件，中断持续大量输出的前台进程
+ * - 监听输出中检测到的链接（URL、文件路径、OSC 8 超链接）
+ * - 查询和监听命令块（命令、退出码、耗时、目录、Git 分支）
+ * - 查询提示符标记（跳转到上一个 / 下一个提示符、重新执行命令）
+ * - 回放录制的会话（调速、跳转到命令）
+ * - 管理终端启动配置（Shell、参数、环境变量、启动命令、工作目录、连接）
  *
  * ## 使用示例
  * ```typescript
@@ -29,6 +35,7 @@
 
 import { safeInvoke, safeListen } from "@/lib/dev-bridge";
 import type { UnlistenFn } from "@tauri-apps/api/event";
+import type { LatencyPercentiles } from "@/lib/api/telemetry";
 
 // ============================================================================
 // 类型定义
@@ -41,6 +48,24 @@ export type SessionStatus = "connecting" | "running" | "done" | "error";
 export interface CreateSessionResponse {
   /** 会话 ID */
   session_id: string;
+  /** 是否为后台会话（请求后台会话但 tmux 不可用时为 false） */
+  detached: boolean;
+  /** 会话所在的工作区（指定 placement 时） */
+  workspace: TerminalWorkspace | null;
+}
+
+/** 创建会话选项 */
+export interface CreateSessionOptions {
+  /** 是否创建后台会话（应用重启后可恢复） */
+  detached?: boolean;
+  /** 连接名称（如 mosh://…、telnet://…、docker://…，为空时为本地 Shell） */
+  connection?: string;
+  /** 启动配置 ID（指定时按启动配置创建，cwd 和 connection 覆盖配置中的值） */
+  profileId?: string;
+  /** 回滚缓冲大小（字节，为空时使用存储设置中的大小） */
+  scrollbackBytes?: number;
+  /** 放入的工作区和分屏位置（放入失败时会话被关闭） */
+  placement?: PanePlacement;
 }
 
 /** 会话元数据 */
@@ -55,6 +80,8 @@ export interface SessionMetadata {
   rows: number;
   /** 终端列数 */
   cols: number;
+  /** 是否为后台会话（应用重启后可重新连接） */
+  detached?: boolean;
 }
 
 /** 终端输出事件 */
@@ -77,12 +104,557 @@ export interface TerminalStatusEvent {
   error?: string;
 }
 
+/** 链接类型 */
+export type TerminalLinkKind = "url" | "file";
+
+/** 终端输出中检测到的链接 */
+export interface TerminalLink {
+  /** 链接类型 */
+  kind: TerminalLinkKind;
+  /** 终端中显示的文本 */
+  text: string;
+  /** 链接目标（URL 或文件路径，相对路径未解析） */
+  target: string;
+  /** 行号（从 1 开始） */
+  line?: number;
+  /** 列号（从 1 开始） */
+  column?: number;
+  /** 是否来自 OSC 8 超链接 */
+  hyperlink: boolean;
+}
+
+/** 终端链接事件 */
+export interface TerminalLinksEvent {
+  /** 会话 ID */
+  session_id: string;
+  /** 当前工作目录（用于解析相对路径） */
+  cwd?: string;
+  /** 检测到的链接 */
+  links: TerminalLink[];
+}
+
+/** 剪贴板访问权限 */
+export type ClipboardAccess = "allow" | "prompt" | "deny";
+
+/** 剪贴板策略（按主机保存，控制 OSC 52 读写本机剪贴板） */
+export interface ClipboardPolicy {
+  /** 主机（连接名称，本地会话为 "local"） */
+  host: string;
+  /** 写入本机剪贴板的权限 */
+  write: ClipboardAccess;
+  /** 读取本机剪贴板的权限 */
+  read: ClipboardAccess;
+  /** 内容大小上限（字节） */
+  max_bytes: number;
+  /** 更新时间（Unix 时间戳，毫秒，默认策略为 0） */
+  updated_at?: number;
+}
+
+/** 剪贴板访问确认事件（策略为需确认时发送） */
+export interface TerminalClipboardRequestEvent {
+  /** 请求 ID */
+  request_id: string;
+  /** 会话 ID */
+  session_id: string;
+  /** 主机 */
+  host: string;
+  /** 操作类型 */
+  operation: "write" | "read";
+  /** OSC 52 选择类型 */
+  selection: string;
+  /** 写入内容大小（字节，仅写入请求） */
+  size?: number | null;
+  /** 写入内容预览（仅写入请求） */
+  preview?: string | null;
+}
+
+/** 粘贴警告 */
+export type PasteWarning = "multi_line" | "control_characters";
+
+/** 粘贴确认事件（粘贴内容包含换行或可疑控制字符时发送） */
+export interface TerminalPasteWarningEvent {
+  /** 粘贴 ID */
+  paste_id: string;
+  /** 会话 ID */
+  session_id: string;
+  /** 警告 */
+  warnings: PasteWarning[];
+  /** 换行数 */
+  line_breaks: number;
+  /** 可疑控制字符数 */
+  control_chars: number;
+  /** 终端程序是否启用括号粘贴模式（启用时换行不会立即执行） */
+  bracketed: boolean;
+  /** 内容大小（字节） */
+  size: number;
+  /** 内容预览（控制字符转换为 ^X 形式） */
+  preview: string;
+}
+
+/** 输出洪泛事件（输出速率过高时发送，回落后再次发送） */
+export interface TerminalOutputFloodEvent {
+  /** 会话 ID */
+  session_id: string;
+  /** 是否处于洪泛状态 */
+  flooding: boolean;
+  /** 最近一秒的输出速率（字节/秒） */
+  bytes_per_sec: number;
+}
+
+/** 粘贴处理结果 */
+export type PasteOutcome =
+  | { status: "written" }
+  | { status: "pending"; paste_id: string }
+  | { status: "denied" };
+
+/** 内联图片显示尺寸（iTerm2 `width=` / `height=` 参数） */
+export interface ImageDimension {
+  /** 单位：字符单元、像素或终端宽高的百分比 */
+  unit: "cells" | "pixels" | "percent";
+  /** 数值 */
+  value: number;
+}
+
+/** 终端内联图片事件（sixel、iTerm2 OSC 1337） */
+export interface TerminalImageEvent {
+  /** 会话 ID */
+  session_id: string;
+  /** 图片 ID（可通过 getTerminalImage 从缓存读取） */
+  image_id: string;
+  /** 协议 */
+  protocol: "sixel" | "iterm";
+  /** MIME 类型 */
+  mime: string;
+  /** 像素宽度 */
+  width: number;
+  /** 像素高度 */
+  height: number;
+  /** 文件名 */
+  name?: string | null;
+  /** 显示宽度（未指定时按像素宽度显示） */
+  display_width?: ImageDimension | null;
+  /** 显示高度（未指定时按像素高度显示） */
+  display_height?: ImageDimension | null;
+  /** 是否保持宽高比 */
+  preserve_aspect_ratio: boolean;
+  /** 图片数据（Base64 编码） */
+  data: string;
+}
+
+/** 缓存的内联图片 */
+export interface TerminalImage {
+  /** MIME 类型 */
+  mime: string;
+  /** 图片数据（Base64 编码） */
+  data: string;
+}
+
+/** 命令标记（OSC 133 提示符开始） */
+export interface ReplayCommandMarker {
+  /** 命令序号 */
+  index: number;
+  /** 回放时间（毫秒） */
+  position_ms: number;
+  /** 提示符在录制数据中的字节偏移 */
+  byte_offset: number;
+  /** 执行的命令文本 */
+  command: string | null;
+}
+
+/** 回放信息 */
+export interface ReplayInfo {
+  /** 回放 ID */
+  replay_id: string;
+  /** 录制来源的块 ID */
+  block_id: string;
+  /** 总时长（毫秒） */
+  duration_ms: number;
+  /** 命令标记 */
+  markers: ReplayCommandMarker[];
+  /** 初始播放速度 */
+  speed: number;
+}
+
+/** 回放状态 */
+export type ReplayState = "playing" | "paused" | "finished";
+
+/** 回放控制指令 */
+export type ReplayCommand =
+  | { action: "play" }
+  | { action: "pause" }
+  | { action: "set_speed"; speed: number }
+  | { action: "seek"; position_ms: number }
+  | { action: "seek_to_command"; index: number };
+
+/** 回放输出事件 */
+export interface TerminalReplayOutputEvent {
+  /** 回放 ID */
+  replay_id: string;
+  /** 输出数据（Base64 编码） */
+  data: string;
+}
+
+/** 回放进度事件 */
+export interface TerminalReplayStatusEvent {
+  /** 回放 ID */
+  replay_id: string;
+  /** 回放状态 */
+  state: ReplayState;
+  /** 当前回放时间（毫秒） */
+  position_ms: number;
+  /** 总时长（毫秒） */
+  duration_ms: number;
+  /** 播放速度 */
+  speed: number;
+  /** 当前所在的命令序号 */
+  command_index: number | null;
+}
+
+/** 命令历史排序方式 */
+export type CommandHistorySort = "recent" | "frequency";
+
+/** 命令历史查询条件 */
+export interface CommandHistoryQuery {
+  /** 命令前缀 */
+  prefix?: string;
+  /** 主机（本地会话为 "local"） */
+  host?: string;
+  /** 排序方式，默认 "recent" */
+  sort?: CommandHistorySort;
+  /** 返回条数（默认 50，最大 1000） */
+  limit?: number;
+}
+
+/** 命令历史记录（同一主机上的相同命令只保留一条） */
+export interface CommandHistoryEntry {
+  /** 记录 ID */
+  id: number;
+  /** 命令文本 */
+  command: string;
+  /** 主机 */
+  host: string;
+  /** 最近一次执行的目录 */
+  cwd: string | null;
+  /** 最近一次执行的退出码 */
+  exit_code: number | null;
+  /** 最近一次执行的耗时（毫秒） */
+  duration_ms: number | null;
+  /** 执行次数 */
+  run_count: number;
+  /** 首次执行时间（Unix 时间戳，毫秒） */
+  first_run_at: number;
+  /** 最近执行时间（Unix 时间戳，毫秒） */
+  last_run_at: number;
+}
+
+/** 命令块（每条开始执行的命令对应一个块） */
+export interface CommandBlock {
+  /** 命令块序号（会话内从 0 开始递增） */
+  index: number;
+  /** 命令文本 */
+  command: string | null;
+  /** 退出码（命令未结束或 Shell 未上报时为 null） */
+  exit_code: number | null;
+  /** 开始时间（Unix 时间戳，毫秒） */
+  start_time: number;
+  /** 结束时间（Unix 时间戳，毫秒，未结束时为 null） */
+  end_time: number | null;
+  /** 耗时（毫秒） */
+  duration_ms: number | null;
+  /** 命令开始执行时的工作目录 */
+  cwd: string | null;
+  /** 命令开始执行时的 Git 分支 */
+  git_branch: string | null;
+}
+
+/** 命令块查询条件 */
+export interface CommandBlockQuery {
+  /** 只返回执行失败的命令块（退出码非 0） */
+  failed_only?: boolean;
+  /** 最多返回最近的多少个命令块 */
+  limit?: number;
+}
+
+/** 命令块事件（命令开始执行和结束时各发送一次） */
+export interface TerminalCommandBlockEvent {
+  /** 会话 ID */
+  session_id: string;
+  /** 命令块 */
+  block: CommandBlock;
+}
+
+/** 命令输出格式：纯文本（去掉 ANSI 控制序列）或保留 ANSI 控制序列 */
+export type CommandOutputFormat = "plain" | "ansi";
+
+/** 单条命令的输出 */
+export interface CommandOutput {
+  /** 命令块序号 */
+  index: number;
+  /** 命令文本 */
+  command: string | null;
+  /** 退出码 */
+  exit_code: number | null;
+  /** 工作目录 */
+  cwd: string | null;
+  /** 输出格式 */
+  format: CommandOutputFormat;
+  /** 输出内容 */
+  output: string;
+  /** 命令是否已结束（未结束时为截至当前的输出） */
+  finished: boolean;
+  /** 输出开头是否已被块文件循环覆盖 */
+  truncated: boolean;
+}
+
+/** 提示符标记（OSC 133 提示符开始） */
+export interface PromptMark {
+  /** 标记序号（会话内从 0 开始递增） */
+  index: number;
+  /** 提示符在会话输出中的位置 */
+  offset: number;
+  /** 提示符之前的换行数（从会话输出开头计算） */
+  line: number;
+  /** 在该提示符执行的命令 */
+  command: string | null;
+  /** 命令退出码 */
+  exit_code: number | null;
+}
+
+/** 会话的提示符标记（含块文件中保留的历史输出） */
+export interface PromptMarks {
+  /** 提示符标记（按输出顺序） */
+  marks: PromptMark[];
+  /** 已索引的输出中的换行数，`line_count - line` 为标记距输出末尾的行数 */
+  line_count: number;
+  /** 已索引的输出结束位置 */
+  offset: number;
+}
+
+/** 会话指标（用于诊断终端卡顿） */
+export interface TerminalMetrics {
+  /** 会话时长（秒） */
+  uptime_secs: number;
+  /** 输入字节数 */
+  bytes_in: number;
+  /** 输出字节数 */
+  bytes_out: number;
+  /** 输入写入次数 */
+  writes: number;
+  /** 输出读取次数 */
+  reads: number;
+  /** 最近 10 秒的平均输出速率（字节/秒） */
+  output_rate_bps: number;
+  /** 单秒最大输出字节数 */
+  peak_output_rate_bps: number;
+  /** 输入到回显的延迟估算（毫秒） */
+  echo_latency: LatencyPercentiles;
+  /** 调整大小次数 */
+  resize_count: number;
+}
+
+/** 可上传的本机文件（拖放到窗口或在文件对话框中选择） */
+export interface UploadFileInfo {
+  /** 本机路径（读取时使用） */
+  path: string;
+  /** 文件名 */
+  name: string;
+  /** 文件大小（字节） */
+  size: number;
+  /** 修改时间（Unix 秒） */
+  modified: number;
+}
+
+/** 按键组合（按键名称使用 `KeyboardEvent.key`） */
+export interface KeyChord {
+  /** 按键名称（如 `a`、`Enter`、`ArrowUp`、`F5`） */
+  key: string;
+  /** 是否按下 Ctrl */
+  ctrl?: boolean;
+  /** 是否按下 Alt / Option */
+  alt?: boolean;
+  /** 是否按下 Shift */
+  shift?: boolean;
+  /** 是否按下 Meta / Cmd / Super */
+  meta?: boolean;
+}
+
+/** 自定义按键绑定 */
+export interface KeyBinding {
+  /** 按键组合（如 `ctrl+shift+ArrowLeft`） */
+  chord: string;
+  /** 写入终端的内容（转义文本，支持 `\e`、`\r`、`\n`、`\t`、`\\`、`\xHH`） */
+  send: string;
+}
+
+/** 自定义按键绑定设置 */
+export interface KeymapSettings {
+  /** 按键绑定（优先于默认编码） */
+  bindings: KeyBinding[];
+}
+
+/** 输出历史清理策略 */
+export interface BlockRetentionPolicy {
+  /** 最后写入超过多少天的会话被清理（null 时不按时间清理） */
+  max_age_days: number | null;
+  /** 所有会话的总大小上限（字节，null 时不按大小清理） */
+  max_total_bytes: number | null;
+}
+
+/** 输出历史存储设置 */
+export interface BlockStorageSettings {
+  /** 新会话的回滚缓冲大小（字节，16 KiB ~ 64 MiB） */
+  scrollback_bytes: number;
+  /** 是否压缩保存被循环覆盖的输出 */
+  compress_cold: boolean;
+  /** 每个会话压缩输出的总大小上限（字节） */
+  cold_max_bytes: number;
+  /** 清理策略 */
+  retention: BlockRetentionPolicy;
+}
+
+/** 单个会话输出历史的磁盘占用 */
+export interface BlockUsage {
+  /** 块 ID（与会话 ID 相同） */
+  block_id: string;
+  /** 块文件大小（字节） */
+  block_bytes: number;
+  /** 时间索引大小（字节） */
+  timing_bytes: number;
+  /** 压缩输出总大小（字节） */
+  cold_bytes: number;
+  /** 压缩输出段数 */
+  cold_segments: number;
+  /** 总大小（字节） */
+  total_bytes: number;
+  /** 最后写入时间（Unix 时间戳，毫秒） */
+  modified_at: number;
+  /** 是否为运行中或可恢复的会话（不会被清理） */
+  active: boolean;
+}
+
+/** 输出历史清理结果 */
+export interface BlockPruneReport {
+  /** 被清理的块 ID */
+  removed: string[];
+  /** 释放的字节数 */
+  freed_bytes: number;
+}
+
+/** 会话分享模式 */
+export type ShareMode = "read_only" | "collaborative";
+
+/** 会话分享信息 */
+export interface TerminalShareInfo {
+  /** 分享 ID */
+  share_id: string;
+  /** 会话 ID */
+  session_id: string;
+  /** 分享模式 */
+  mode: ShareMode;
+  /** 创建时间（Unix 时间戳，毫秒） */
+  created_at: number;
+  /** 令牌失效时间（Unix 时间戳，毫秒），已连接的分享不受影响 */
+  expires_at: number;
+  /** 观看者是否已连接（令牌已使用） */
+  connected: boolean;
+}
+
+/** 新建分享的凭据（令牌只在创建时返回） */
+export interface TerminalShareTicket {
+  /** 分享信息 */
+  share: TerminalShareInfo;
+  /** 一次性令牌 */
+  token: string;
+  /** WebSocket 路径（相对代理服务器地址），如 `/v1/terminal/share/{token}/ws` */
+  path: string;
+}
+
+/** 分屏方向（horizontal 为左右排列，vertical 为上下排列） */
+export type SplitDirection = "horizontal" | "vertical";
+
+/** 分屏布局节点 */
+export type PaneLayout =
+  | {
+      type: "pane";
+      /** 会话 ID */
+      session_id: string;
+    }
+  | {
+      type: "split";
+      /** 分屏方向 */
+      direction: SplitDirection;
+      /** 子节点所占的相对比例（与 children 一一对应） */
+      sizes: number[];
+      /** 子节点 */
+      children: PaneLayout[];
+    };
+
+/** 会话在工作区中的位置 */
+export interface PanePlacement {
+  /** 工作区 ID */
+  workspace_id: string;
+  /** 在该会话旁分屏（为空时在工作区的当前会话旁） */
+  target_session_id?: string;
+  /** 分屏方向（默认 horizontal） */
+  direction?: SplitDirection;
+}
+
+/** 终端工作区（标签页） */
+export interface TerminalWorkspace {
+  /** 工作区 ID */
+  id: string;
+  /** 名称 */
+  name: string;
+  /** 标签页顺序（从小到大） */
+  position: number;
+  /** 分屏布局（没有会话时为 null） */
+  layout: PaneLayout | null;
+  /** 当前聚焦的会话 */
+  active_session_id: string | null;
+  /** 创建时间（Unix 时间戳，毫秒） */
+  created_at: number;
+  /** 更新时间（Unix 时间戳，毫秒） */
+  updated_at: number;
+}
+
+/** 终端启动配置 */
+export interface LaunchProfile {
+  /** 配置 ID（新建时为空字符串） */
+  id: string;
+  /** 配置名称 */
+  name: string;
+  /** Shell 路径（为空时使用默认 Shell） */
+  shell?: string | null;
+  /** Shell 参数 */
+  args: string[];
+  /** 环境变量 */
+  env: Record<string, string>;
+  /** 启动命令（Shell 启动后输入执行） */
+  startup_command?: string | null;
+  /** 工作目录 */
+  cwd?: string | null;
+  /** 连接名称（为空时为本地 Shell） */
+  connection?: string | null;
+  /** 创建时间（Unix 时间戳，毫秒） */
+  created_at: number;
+  /** 更新时间（Unix 时间戳，毫秒） */
+  updated_at: number;
+}
+
 // ============================================================================
 // 事件名称
 // ============================================================================
 
 export const TERMINAL_OUTPUT_EVENT = "terminal:output";
 export const TERMINAL_STATUS_EVENT = "terminal:status";
+export const TERMINAL_REPLAY_OUTPUT_EVENT = "terminal:replay-output";
+export const TERMINAL_REPLAY_STATUS_EVENT = "terminal:replay-status";
+export const TERMINAL_LINKS_EVENT = "terminal:links";
+export const TERMINAL_CLIPBOARD_REQUEST_EVENT = "terminal:clipboard-request";
+export const TERMINAL_IMAGE_EVENT = "terminal:image";
+export const TERMINAL_PASTE_WARNING_EVENT = "terminal:paste-warning";
+export const TERMINAL_OUTPUT_FLOOD_EVENT = "terminal:output-flood";
+export const TERMINAL_COMMAND_BLOCK_EVENT = "terminal:command-block";
 
 // ============================================================================
 // API 函数
@@ -94,12 +666,23 @@ export const TERMINAL_STATUS_EVENT = "terminal:status";
  * PTY 使用默认大小 (24x80) 预创建，前端连接后通过 resizeTerminal 同步实际大小。
  *
  * @param cwd - 工作目录（可选）
+ * @param options - 创建选项（可选）
  * @returns 会话 ID
  */
-export async function createTerminalSession(cwd?: string): Promise<string> {
+export async function createTerminalSession(
+  cwd?: string,
+  options?: CreateSessionOptions,
+): Promise<string> {
   const response = await safeInvoke<CreateSessionResponse>(
     "terminal_create_session",
-    { cwd },
+    {
+      cwd,
+      detached: options?.detached,
+      connection: options?.connection,
+      profileId: options?.profileId,
+      scrollbackBytes: options?.scrollbackBytes,
+      placement: options?.placement,
+    },
   );
   return response.session_id;
 }
@@ -141,6 +724,90 @@ export async function writeToTerminalRaw(
   });
 }
 
+/**
+ * 中断终端的前台进程（发送 Ctrl+C）
+ *
+ * 用于收到输出洪泛事件后快速中断持续大量输出的进程。
+ *
+ * @param sessionId - 会话 ID
+ */
+export async function interruptSession(sessionId: string): Promise<void> {
+  await safeInvoke("terminal_interrupt_session", { sessionId });
+}
+
+/**
+ * 向终端发送按键
+ *
+ * 后端按会话的 TERM、终端程序启用的键盘模式（应用光标键、modifyOtherKeys、
+ * kitty 键盘协议）和自定义按键绑定编码，前端无需关心连接类型。
+ *
+ * @param sessionId - 会话 ID
+ * @param chord - 按键组合
+ * @returns 是否已写入（无法编码的按键返回 false，按普通输入处理）
+ */
+export async function sendKey(
+  sessionId: string,
+  chord: KeyChord,
+): Promise<boolean> {
+  return safeInvoke<boolean>("terminal_send_key", { sessionId, chord });
+}
+
+/**
+ * 获取自定义按键绑定
+ */
+export async function getKeymapSettings(): Promise<KeymapSettings> {
+  return safeInvoke<KeymapSettings>("terminal_keymap_get");
+}
+
+/**
+ * 保存自定义按键绑定（立即对所有会话生效）
+ *
+ * @param settings - 按键绑定设置
+ */
+export async function saveKeymapSettings(
+  settings: KeymapSettings,
+): Promise<void> {
+  await safeInvoke("terminal_keymap_save", { settings });
+}
+
+/**
+ * 粘贴文本到终端
+ *
+ * 后端按终端程序的括号粘贴模式包裹内容；内容包含换行或可疑控制字符且未确认时，
+ * 发送 `terminal:paste-warning` 事件并返回等待确认。
+ *
+ * @param sessionId - 会话 ID
+ * @param text - 粘贴的文本
+ * @param confirmed - 是否已确认（为 true 时直接写入）
+ */
+export async function pasteToTerminal(
+  sessionId: string,
+  text: string,
+  confirmed = false,
+): Promise<PasteOutcome> {
+  return safeInvoke<PasteOutcome>("terminal_paste", {
+    sessionId,
+    text,
+    confirmed,
+  });
+}
+
+/**
+ * 响应需确认的粘贴
+ *
+ * @param pasteId - 粘贴 ID
+ * @param allow - 是否允许写入
+ */
+export async function respondPaste(
+  pasteId: string,
+  allow: boolean,
+): Promise<PasteOutcome> {
+  return safeInvoke<PasteOutcome>("terminal_paste_respond", {
+    pasteId,
+    allow,
+  });
+}
+
 /**
  * 调整终端大小
  *
@@ -194,91 +861,875 @@ export async function getTerminalSession(
   });
 }
 
-// ============================================================================
-// 事件监听
-// ============================================================================
+/**
+ * 获取可恢复的后台会话
+ *
+ * 应用重启后调用，返回仍在运行、尚未恢复的后台会话。
+ *
+ * @returns 会话元数据列表（按创建时间排序）
+ */
+export async function listDetachedSessions(): Promise<SessionMetadata[]> {
+  return safeInvoke<SessionMetadata[]>("terminal_list_detached_sessions");
+}
 
 /**
- * 监听终端输出事件
+ * 恢复终端会话
  *
- * @param callback - 回调函数，接收输出事件
- * @returns 取消监听函数
+ * 后台会话恢复后需在订阅输出事件后调用 attachTerminalSession。
+ *
+ * @param sessionId - 会话 ID
+ * @returns 会话元数据
  */
-export async function onTerminalOutput(
-  callback: (event: TerminalOutputEvent) => void,
-): Promise<UnlistenFn> {
-  return safeListen<TerminalOutputEvent>(TERMINAL_OUTPUT_EVENT, (event) => {
-    callback(event.payload);
+export async function restoreTerminalSession(
+  sessionId: string,
+): Promise<SessionMetadata> {
+  return safeInvoke<SessionMetadata>("terminal_restore_session", {
+    sessionId,
   });
 }
 
 /**
- * 监听终端状态事件
+ * 连接恢复的后台会话
  *
- * @param callback - 回调函数，接收状态事件
- * @returns 取消监听函数
+ * 后端先通过输出事件回填滚动历史，再连接到仍在运行的 Shell。
+ *
+ * @param sessionId - 会话 ID
  */
-export async function onTerminalStatus(
-  callback: (event: TerminalStatusEvent) => void,
-): Promise<UnlistenFn> {
-  return safeListen<TerminalStatusEvent>(TERMINAL_STATUS_EVENT, (event) => {
-    callback(event.payload);
+export async function attachTerminalSession(sessionId: string): Promise<void> {
+  await safeInvoke("terminal_attach_session", {
+    sessionId,
   });
 }
 
 /**
- * 监听特定会话的输出事件
+ * 断开后台会话
+ *
+ * 关闭本地连接，Shell 在后台继续运行。
  *
  * @param sessionId - 会话 ID
- * @param callback - 回调函数，接收解码后的输出数据
- * @returns 取消监听函数
  */
-export async function onSessionOutput(
-  sessionId: string,
-  callback: (data: Uint8Array) => void,
-): Promise<UnlistenFn> {
-  return safeListen<TerminalOutputEvent>(TERMINAL_OUTPUT_EVENT, (event) => {
-    if (event.payload.session_id === sessionId) {
-      // 解码 Base64 数据
-      const binaryString = atob(event.payload.data);
-      const bytes = new Uint8Array(binaryString.length);
-      for (let i = 0; i < binaryString.length; i++) {
-        bytes[i] = binaryString.charCodeAt(i);
-      }
-      callback(bytes);
-    }
+export async function detachTerminalSession(sessionId: string): Promise<void> {
+  await safeInvoke("terminal_detach_session", {
+    sessionId,
   });
 }
 
 /**
- * 监听特定会话的状态事件
+ * 开始回放录制的会话
  *
- * @param sessionId - 会话 ID
- * @param callback - 回调函数，接收状态事件
- * @returns 取消监听函数
+ * 回放创建后处于暂停状态，订阅回放事件后调用 `controlReplay(id, { action: "play" })` 开始播放。
+ *
+ * @param blockId - 块 ID（会话 ID）
+ * @param speed - 初始播放速度（可选，默认 1.0）
+ * @param maxIdleMs - 最大空闲间隔（可选，默认 2000 毫秒）
+ * @returns 回放信息
  */
-export async function onSessionStatus(
-  sessionId: string,
-  callback: (event: TerminalStatusEvent) => void,
-): Promise<UnlistenFn> {
-  return safeListen<TerminalStatusEvent>(TERMINAL_STATUS_EVENT, (event) => {
-    if (event.payload.session_id === sessionId) {
-      callback(event.payload);
-    }
+export async function startReplay(
+  blockId: string,
+  speed?: number,
+  maxIdleMs?: number,
+): Promise<ReplayInfo> {
+  return safeInvoke<ReplayInfo>("terminal_replay_start", {
+    blockId,
+    speed,
+    maxIdleMs,
   });
 }
 
-// ============================================================================
-// 工具函数
-// ============================================================================
+/**
+ * 控制回放（播放、暂停、调速、跳转）
+ *
+ * @param replayId - 回放 ID
+ * @param command - 控制指令
+ */
+export async function controlReplay(
+  replayId: string,
+  command: ReplayCommand,
+): Promise<void> {
+  await safeInvoke("terminal_replay_control", {
+    replayId,
+    command,
+  });
+}
 
 /**
- * 将字符串编码为 Base64
+ * 停止回放
+ *
+ * @param replayId - 回放 ID
  */
-export function encodeBase64(str: string): string {
-  const encoder = new TextEncoder();
-  const bytes = encoder.encode(str);
-  return btoa(String.fromCharCode(...bytes));
+export async function stopReplay(replayId: string): Promise<void> {
+  await safeInvoke("terminal_replay_stop", {
+    replayId,
+  });
+}
+
+/**
+ * 查询命令历史
+ *
+ * @param query - 查询条件（前缀、主机、排序、条数）
+ * @returns 命令历史记录
+ */
+export async function queryCommandHistory(
+  query: CommandHistoryQuery = {},
+): Promise<CommandHistoryEntry[]> {
+  return safeInvoke<CommandHistoryEntry[]>("terminal_history_query", {
+    query,
+  });
+}
+
+/**
+ * 获取有命令历史的主机列表
+ */
+export async function listCommandHistoryHosts(): Promise<string[]> {
+  return safeInvoke<string[]>("terminal_history_hosts");
+}
+
+/**
+ * 删除单条命令历史
+ *
+ * @param id - 记录 ID
+ */
+export async function deleteCommandHistory(id: number): Promise<void> {
+  await safeInvoke("terminal_history_delete", {
+    id,
+  });
+}
+
+/**
+ * 清空命令历史
+ *
+ * @param host - 主机（可选，不指定时清空所有主机）
+ * @returns 删除的记录数
+ */
+export async function clearCommandHistory(host?: string): Promise<number> {
+  return safeInvoke<number>("terminal_history_clear", {
+    host,
+  });
+}
+
+/**
+ * 查询会话的命令块
+ *
+ * @param sessionId - 会话 ID
+ * @param query - 查询条件（只看失败的命令、条数）
+ * @returns 命令块（按开始顺序）
+ */
+export async function queryCommandBlocks(
+  sessionId: string,
+  query: CommandBlockQuery = {},
+): Promise<CommandBlock[]> {
+  return safeInvoke<CommandBlock[]>("terminal_command_blocks", {
+    sessionId,
+    query,
+  });
+}
+
+/**
+ * 获取单条命令的输出（用于“复制命令输出”）
+ *
+ * @param sessionId - 会话 ID
+ * @param index - 命令块序号
+ * @param format - 输出格式（默认纯文本）
+ * @returns 命令输出
+ */
+export async function getCommandOutput(
+  sessionId: string,
+  index: number,
+  format: CommandOutputFormat = "plain",
+): Promise<CommandOutput> {
+  return safeInvoke<CommandOutput>("terminal_command_output", {
+    sessionId,
+    index,
+    format,
+  });
+}
+
+/**
+ * 获取会话的提示符标记
+ *
+ * 用于跳转到上一个 / 下一个提示符：按 `line_count - line` 计算标记距输出末尾的行数，
+ * 再换算为终端缓冲区中的行。
+ *
+ * @param sessionId - 会话 ID
+ * @returns 提示符标记
+ */
+export async function getPromptMarks(sessionId: string): Promise<PromptMarks> {
+  return safeInvoke<PromptMarks>("terminal_prompt_marks", { sessionId });
+}
+
+/**
+ * 重新执行提示符标记处的命令
+ *
+ * 有命令正在执行或标记没有命令时返回错误。
+ *
+ * @param sessionId - 会话 ID
+ * @param index - 提示符标记序号
+ */
+export async function rerunPromptCommand(
+  sessionId: string,
+  index: number,
+): Promise<void> {
+  await safeInvoke("terminal_prompt_rerun", { sessionId, index });
+}
+
+/**
+ * 获取会话指标（字节数、输出速率、回显延迟估算、调整大小次数）
+ *
+ * @param sessionId - 会话 ID
+ * @returns 会话指标
+ */
+export async function getSessionMetrics(
+  sessionId: string,
+): Promise<TerminalMetrics> {
+  return safeInvoke<TerminalMetrics>("terminal_session_metrics", {
+    sessionId,
+  });
+}
+
+/**
+ * 保存终端内传输的文件（OSC 1337 File=）
+ *
+ * 后端弹出保存对话框，保存路径由用户选择。
+ *
+ * @param name - 远程声明的文件名（作为默认文件名）
+ * @param data - Base64 编码的文件内容
+ * @returns 保存路径，用户取消时返回 null
+ */
+export async function saveTerminalFile(
+  name: string,
+  data: string,
+): Promise<string | null> {
+  return safeInvoke<string | null>("terminal_save_file", {
+    name,
+    data,
+  });
+}
+
+/**
+ * 获取拖放到窗口的可上传文件
+ *
+ * 后端只返回由窗口拖放事件授权的普通文件。
+ *
+ * @param paths - 拖放事件中的文件路径
+ */
+export async function getUploadFiles(
+  paths: string[],
+): Promise<UploadFileInfo[]> {
+  return safeInvoke<UploadFileInfo[]>("terminal_upload_files", { paths });
+}
+
+/**
+ * 弹出文件对话框选择要上传的文件
+ *
+ * @returns 选择的文件，用户取消时为空数组
+ */
+export async function pickUploadFiles(): Promise<UploadFileInfo[]> {
+  return safeInvoke<UploadFileInfo[]>("terminal_upload_pick_files");
+}
+
+/**
+ * 读取待上传文件的一段内容
+ *
+ * @param path - 已授权上传的文件路径
+ * @param offset - 起始偏移（字节）
+ * @param length - 读取长度（最多 1 MiB）
+ * @returns 文件内容，到达文件末尾时为空
+ */
+export async function readUploadFile(
+  path: string,
+  offset: number,
+  length: number,
+): Promise<Uint8Array> {
+  const data = await safeInvoke<string>("terminal_upload_read", {
+    path,
+    offset,
+    length,
+  });
+  return decodeBase64(data);
+}
+
+/**
+ * 获取终端启动配置列表（按名称排序）
+ */
+export async function listLaunchProfiles(): Promise<LaunchProfile[]> {
+  return safeInvoke<LaunchProfile[]>("terminal_profile_list");
+}
+
+/**
+ * 新建或更新终端启动配置
+ *
+ * @param profile - 启动配置（id 为空字符串时新建）
+ * @returns 保存后的启动配置
+ */
+export async function saveLaunchProfile(
+  profile: LaunchProfile,
+): Promise<LaunchProfile> {
+  return safeInvoke<LaunchProfile>("terminal_profile_save", {
+    profile,
+  });
+}
+
+/**
+ * 删除终端启动配置
+ *
+ * @param id - 启动配置 ID
+ * @returns 是否删除了配置
+ */
+export async function deleteLaunchProfile(id: string): Promise<boolean> {
+  return safeInvoke<boolean>("terminal_profile_delete", {
+    id,
+  });
+}
+
+/**
+ * 响应需确认的剪贴板请求
+ *
+ * @param requestId - 请求 ID
+ * @param allow - 是否允许
+ * @param remember - 是否记住决定（更新该主机的剪贴板策略）
+ */
+export async function respondClipboardRequest(
+  requestId: string,
+  allow: boolean,
+  remember = false,
+): Promise<void> {
+  await safeInvoke("terminal_clipboard_respond", {
+    requestId,
+    allow,
+    remember,
+  });
+}
+
+/**
+ * 获取主机生效的剪贴板策略（没有保存策略时返回默认策略）
+ *
+ * @param host - 主机（本地会话为 "local"）
+ */
+export async function getClipboardPolicy(
+  host: string,
+): Promise<ClipboardPolicy> {
+  return safeInvoke<ClipboardPolicy>("terminal_clipboard_policy_get", {
+    host,
+  });
+}
+
+/**
+ * 获取保存的剪贴板策略列表（按主机排序）
+ */
+export async function listClipboardPolicies(): Promise<ClipboardPolicy[]> {
+  return safeInvoke<ClipboardPolicy[]>("terminal_clipboard_policy_list");
+}
+
+/**
+ * 保存主机的剪贴板策略
+ *
+ * @param policy - 剪贴板策略
+ * @returns 保存后的剪贴板策略
+ */
+export async function saveClipboardPolicy(
+  policy: ClipboardPolicy,
+): Promise<ClipboardPolicy> {
+  return safeInvoke<ClipboardPolicy>("terminal_clipboard_policy_save", {
+    policy,
+  });
+}
+
+/**
+ * 删除主机的剪贴板策略（之后使用默认策略）
+ *
+ * @param host - 主机
+ * @returns 是否删除了策略
+ */
+export async function deleteClipboardPolicy(host: string): Promise<boolean> {
+  return safeInvoke<boolean>("terminal_clipboard_policy_delete", {
+    host,
+  });
+}
+
+/**
+ * 从缓存读取内联图片
+ *
+ * @param imageId - 图片 ID
+ * @returns 缓存的图片，已被淘汰时返回 null
+ */
+export async function getTerminalImage(
+  imageId: string,
+): Promise<TerminalImage | null> {
+  return safeInvoke<TerminalImage | null>("terminal_image_get", {
+    imageId,
+  });
+}
+
+/**
+ * 清空内联图片缓存
+ *
+ * @returns 删除的图片数量
+ */
+export async function clearTerminalImageCache(): Promise<number> {
+  return safeInvoke<number>("terminal_image_cache_clear");
+}
+
+/**
+ * 获取输出历史存储设置
+ */
+export async function getStorageSettings(): Promise<BlockStorageSettings> {
+  return safeInvoke<BlockStorageSettings>("terminal_storage_settings_get");
+}
+
+/**
+ * 保存输出历史存储设置（对之后创建的会话生效）
+ *
+ * @param settings - 存储设置
+ */
+export async function saveStorageSettings(
+  settings: BlockStorageSettings,
+): Promise<void> {
+  await safeInvoke("terminal_storage_settings_save", {
+    settings,
+  });
+}
+
+/**
+ * 获取每个会话输出历史的磁盘占用
+ *
+ * @returns 占用记录（按最后写入时间从新到旧）
+ */
+export async function getStorageUsage(): Promise<BlockUsage[]> {
+  return safeInvoke<BlockUsage[]>("terminal_storage_usage");
+}
+
+/**
+ * 按清理策略清理已结束会话的输出历史
+ *
+ * @returns 被清理的会话和释放的字节数
+ */
+export async function pruneTerminalStorage(): Promise<BlockPruneReport> {
+  return safeInvoke<BlockPruneReport>("terminal_storage_prune");
+}
+
+/**
+ * 分享会话
+ *
+ * 观看者在令牌有效期内通过代理服务器的 WebSocket（`path`）连接，令牌只能使用一次。
+ *
+ * @param sessionId - 会话 ID
+ * @param mode - 分享模式（默认只读）
+ * @param ttlSecs - 令牌有效期（秒，默认 600，最长 24 小时）
+ * @returns 分享信息、一次性令牌和 WebSocket 路径
+ */
+export async function createTerminalShare(
+  sessionId: string,
+  mode: ShareMode = "read_only",
+  ttlSecs?: number,
+): Promise<TerminalShareTicket> {
+  return safeInvoke<TerminalShareTicket>("terminal_share_create", {
+    sessionId,
+    mode,
+    ttlSecs,
+  });
+}
+
+/**
+ * 获取会话分享列表
+ *
+ * @param sessionId - 只列出该会话的分享（可选）
+ */
+export async function listTerminalShares(
+  sessionId?: string,
+): Promise<TerminalShareInfo[]> {
+  return safeInvoke<TerminalShareInfo[]>("terminal_share_list", {
+    sessionId,
+  });
+}
+
+/**
+ * 撤销会话分享（断开已连接的观看者）
+ *
+ * @param shareId - 分享 ID
+ */
+export async function revokeTerminalShare(shareId: string): Promise<void> {
+  await safeInvoke("terminal_share_revoke", { shareId });
+}
+
+/**
+ * 获取工作区列表（按标签页顺序）
+ *
+ * 应用启动时从数据库恢复，布局中只保留可恢复的会话。
+ */
+export async function listTerminalWorkspaces(): Promise<TerminalWorkspace[]> {
+  return safeInvoke<TerminalWorkspace[]>("terminal_workspace_list");
+}
+
+/**
+ * 创建工作区
+ *
+ * @param name - 名称
+ */
+export async function createTerminalWorkspace(
+  name: string,
+): Promise<TerminalWorkspace> {
+  return safeInvoke<TerminalWorkspace>("terminal_workspace_create", { name });
+}
+
+/**
+ * 重命名工作区
+ *
+ * @param workspaceId - 工作区 ID
+ * @param name - 新名称
+ */
+export async function renameTerminalWorkspace(
+  workspaceId: string,
+  name: string,
+): Promise<TerminalWorkspace> {
+  return safeInvoke<TerminalWorkspace>("terminal_workspace_rename", {
+    workspaceId,
+    name,
+  });
+}
+
+/**
+ * 保存工作区的分屏布局（布局中的会话从其他工作区移除）
+ *
+ * @param workspaceId - 工作区 ID
+ * @param layout - 分屏布局（为 null 时清空工作区）
+ * @param activeSessionId - 当前聚焦的会话（可选）
+ */
+export async function setTerminalWorkspaceLayout(
+  workspaceId: string,
+  layout: PaneLayout | null,
+  activeSessionId?: string,
+): Promise<TerminalWorkspace> {
+  return safeInvoke<TerminalWorkspace>("terminal_workspace_set_layout", {
+    workspaceId,
+    layout,
+    activeSessionId,
+  });
+}
+
+/**
+ * 调整工作区顺序
+ *
+ * @param workspaceIds - 工作区 ID（按新顺序）
+ */
+export async function reorderTerminalWorkspaces(
+  workspaceIds: string[],
+): Promise<TerminalWorkspace[]> {
+  return safeInvoke<TerminalWorkspace[]>("terminal_workspace_reorder", {
+    workspaceIds,
+  });
+}
+
+/**
+ * 删除工作区并关闭其中的会话
+ *
+ * @param workspaceId - 工作区 ID
+ */
+export async function deleteTerminalWorkspace(
+  workspaceId: string,
+): Promise<void> {
+  await safeInvoke("terminal_workspace_delete", { workspaceId });
+}
+
+/**
+ * 把会话放入工作区（在目标窗格旁分屏，并从原来的工作区移除）
+ *
+ * @param sessionId - 会话 ID
+ * @param placement - 目标工作区和分屏位置
+ */
+export async function placeTerminalSession(
+  sessionId: string,
+  placement: PanePlacement,
+): Promise<TerminalWorkspace> {
+  return safeInvoke<TerminalWorkspace>("terminal_workspace_place_session", {
+    sessionId,
+    placement,
+  });
+}
+
+// ============================================================================
+// 事件监听
+// ============================================================================
+
+/**
+ * 监听终端输出事件
+ *
+ * @param callback - 回调函数，接收输出事件
+ * @returns 取消监听函数
+ */
+export async function onTerminalOutput(
+  callback: (event: TerminalOutputEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalOutputEvent>(TERMINAL_OUTPUT_EVENT, (event) => {
+    callback(event.payload);
+  });
+}
+
+/**
+ * 监听终端状态事件
+ *
+ * @param callback - 回调函数，接收状态事件
+ * @returns 取消监听函数
+ */
+export async function onTerminalStatus(
+  callback: (event: TerminalStatusEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalStatusEvent>(TERMINAL_STATUS_EVENT, (event) => {
+    callback(event.payload);
+  });
+}
+
+/**
+ * 监听特定会话的输出事件
+ *
+ * @param sessionId - 会话 ID
+ * @param callback - 回调函数，接收解码后的输出数据
+ * @returns 取消监听函数
+ */
+export async function onSessionOutput(
+  sessionId: string,
+  callback: (data: Uint8Array) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalOutputEvent>(TERMINAL_OUTPUT_EVENT, (event) => {
+    if (event.payload.session_id === sessionId) {
+      // 解码 Base64 数据
+      const binaryString = atob(event.payload.data);
+      const bytes = new Uint8Array(binaryString.length);
+      for (let i = 0; i < binaryString.length; i++) {
+        bytes[i] = binaryString.charCodeAt(i);
+      }
+      callback(bytes);
+    }
+  });
+}
+
+/**
+ * 监听特定会话的状态事件
+ *
+ * @param sessionId - 会话 ID
+ * @param callback - 回调函数，接收状态事件
+ * @returns 取消监听函数
+ */
+export async function onSessionStatus(
+  sessionId: string,
+  callback: (event: TerminalStatusEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalStatusEvent>(TERMINAL_STATUS_EVENT, (event) => {
+    if (event.payload.session_id === sessionId) {
+      callback(event.payload);
+    }
+  });
+}
+
+/**
+ * 监听特定会话的链接事件
+ *
+ * @param sessionId - 会话 ID
+ * @param callback - 回调函数，接收链接事件
+ * @returns 取消监听函数
+ */
+export async function onSessionLinks(
+  sessionId: string,
+  callback: (event: TerminalLinksEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalLinksEvent>(TERMINAL_LINKS_EVENT, (event) => {
+    if (event.payload.session_id === sessionId) {
+      callback(event.payload);
+    }
+  });
+}
+
+/**
+ * 监听特定会话的剪贴板访问确认事件
+ *
+ * @param sessionId - 会话 ID
+ * @param callback - 回调函数，接收确认事件
+ * @returns 取消监听函数
+ */
+export async function onClipboardRequest(
+  sessionId: string,
+  callback: (event: TerminalClipboardRequestEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalClipboardRequestEvent>(
+    TERMINAL_CLIPBOARD_REQUEST_EVENT,
+    (event) => {
+      if (event.payload.session_id === sessionId) {
+        callback(event.payload);
+      }
+    },
+  );
+}
+
+/**
+ * 监听特定会话的粘贴确认事件
+ *
+ * @param sessionId - 会话 ID
+ * @param callback - 回调函数，接收确认事件
+ * @returns 取消监听函数
+ */
+export async function onPasteWarning(
+  sessionId: string,
+  callback: (event: TerminalPasteWarningEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalPasteWarningEvent>(
+    TERMINAL_PASTE_WARNING_EVENT,
+    (event) => {
+      if (event.payload.session_id === sessionId) {
+        callback(event.payload);
+      }
+    },
+  );
+}
+
+/**
+ * 监听特定会话的输出洪泛事件
+ *
+ * 后端已限制输出事件频率并暂停读取；前端可提示用户并调用 `interruptSession` 中断进程。
+ *
+ * @param sessionId - 会话 ID
+ * @param callback - 回调函数，接收洪泛事件
+ * @returns 取消监听函数
+ */
+export async function onOutputFlood(
+  sessionId: string,
+  callback: (event: TerminalOutputFloodEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalOutputFloodEvent>(
+    TERMINAL_OUTPUT_FLOOD_EVENT,
+    (event) => {
+      if (event.payload.session_id === sessionId) {
+        callback(event.payload);
+      }
+    },
+  );
+}
+
+/**
+ * 监听特定会话的内联图片事件
+ *
+ * @param sessionId - 会话 ID
+ * @param callback - 回调函数，接收图片事件
+ * @returns 取消监听函数
+ */
+export async function onSessionImage(
+  sessionId: string,
+  callback: (event: TerminalImageEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalImageEvent>(TERMINAL_IMAGE_EVENT, (event) => {
+    if (event.payload.session_id === sessionId) {
+      callback(event.payload);
+    }
+  });
+}
+
+/**
+ * 监听特定会话的命令块事件
+ *
+ * @param sessionId - 会话 ID
+ * @param callback - 回调函数，接收命令块事件
+ * @returns 取消监听函数
+ */
+export async function onCommandBlock(
+  sessionId: string,
+  callback: (event: TerminalCommandBlockEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalCommandBlockEvent>(
+    TERMINAL_COMMAND_BLOCK_EVENT,
+    (event) => {
+      if (event.payload.session_id === sessionId) {
+        callback(event.payload);
+      }
+    },
+  );
+}
+
+/**
+ * 解析链接目标
+ *
+ * 文件链接的相对路径按事件中的工作目录解析，`~` 开头的路径保持不变。
+ *
+ * @param link - 链接
+ * @param cwd - 当前工作目录
+ * @returns URL 或文件路径
+ */
+export function resolveLinkTarget(link: TerminalLink, cwd?: string): string {
+  if (link.kind === "url" || !cwd) {
+    return link.target;
+  }
+  const target = link.target;
+  if (/^([/~]|[A-Za-z]:[\\/])/.test(target)) {
+    return target;
+  }
+  const separator = cwd.includes("\\") && !cwd.includes("/") ? "\\" : "/";
+  const relative = target.replace(/^\.[\\/]/, "");
+  return cwd.endsWith(separator)
+    ? `${cwd}${relative}`
+    : `${cwd}${separator}${relative}`;
+}
+
+/**
+ * 监听特定回放的输出事件
+ *
+ * @param replayId - 回放 ID
+ * @param callback - 回调函数，接收解码后的输出数据
+ * @returns 取消监听函数
+ */
+export async function onReplayOutput(
+  replayId: string,
+  callback: (data: Uint8Array) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalReplayOutputEvent>(
+    TERMINAL_REPLAY_OUTPUT_EVENT,
+    (event) => {
+      if (event.payload.replay_id === replayId) {
+        callback(decodeBase64(event.payload.data));
+      }
+    },
+  );
+}
+
+/**
+ * 监听特定回放的进度事件
+ *
+ * @param replayId - 回放 ID
+ * @param callback - 回调函数，接收进度事件
+ * @returns 取消监听函数
+ */
+export async function onReplayStatus(
+  replayId: string,
+  callback: (event: TerminalReplayStatusEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<TerminalReplayStatusEvent>(
+    TERMINAL_REPLAY_STATUS_EVENT,
+    (event) => {
+      if (event.payload.replay_id === replayId) {
+        callback(event.payload);
+      }
+    },
+  );
+}
+
+// ============================================================================
+// 工具函数
+// ============================================================================
+
+/**
+ * 将字符串编码为 Base64
+ */
+export function encodeBase64(str: string): string {
+  const encoder = new TextEncoder();
+  const bytes = encoder.encode(str);
+  return btoa(String.fromCharCode(...bytes));
+}
+
+/**
+ * 将 Uint8Array 编码为 Base64
+ */
+export function encodeBytesBase64(bytes: Uint8Array): string {
+  let binary = "";
+  for (let i = 0; i < bytes.length; i += 0x8000) {
+    binary += String.fromCharCode(...bytes.subarray(i, i + 0x8000));
+  }
+  return btoa(binary);
 }
 
 /**
@@ -0,0 +1,78 @@
+/**
+ * @file 终端命令块列表测试
+ * @description 测试命令块合并、失败筛选和耗时格式化
+ * @module lib/terminal/command-blocks.test
+ */
+
+import { describe, it, expect } from "vitest";
+import {
+  filterCommandBlocks,
+  formatBlockDuration,
+  isFailedBlock,
+  mergeCommandBlock,
+} from "./command-blocks";
+import type { CommandBlock } from "@/lib/terminal-api";
+
+function block(
+  index: number,
+  overrides: Partial<CommandBlock> = {},
+): CommandBlock {
+  return {
+    index,
+    command: `cmd ${index}`,
+    exit_code: null,
+    start_time: 1000 + index,
+    end_time: null,
+    duration_ms: null,
+    cwd: "/srv/app",
+    git_branch: "main",
+    ...overrides,
+  };
+}
+
+describe("mergeCommandBlock", () => {
+  it("替换相同序号的块并按序号排序", () => {
+    let blocks = mergeCommandBlock([], block(1));
+    blocks = mergeCommandBlock(blocks, block(0, { exit_code: 0 }));
+    blocks = mergeCommandBlock(
+      blocks,
+      block(1, { exit_code: 2, end_time: 2000, duration_ms: 999 }),
+    );
+
+    expect(blocks.map((b) => [b.index, b.exit_code])).toEqual([
+      [0, 0],
+      [1, 2],
+    ]);
+  });
+
+  it("超出上限时丢弃最早的块", () => {
+    let blocks: CommandBlock[] = [];
+    for (let i = 0; i < 5; i++) {
+      blocks = mergeCommandBlock(blocks, block(i), 3);
+    }
+    expect(blocks.map((b) => b.index)).toEqual([2, 3, 4]);
+  });
+});
+
+describe("filterCommandBlocks", () => {
+  it("只保留退出码非 0 的块", () => {
+    const blocks = [
+      block(0, { exit_code: 0 }),
+      block(1, { exit_code: 127 }),
+      block(2),
+    ];
+    expect(isFailedBlock(blocks[1])).toBe(true);
+    expect(isFailedBlock(blocks[2])).toBe(false);
+    expect(filterCommandBlocks(blocks, true).map((b) => b.index)).toEqual([1]);
+    expect(filterCommandBlocks(blocks, false)).toHaveLength(3);
+  });
+});
+
+describe("formatBlockDuration", () => {
+  it("按量级格式化耗时", () => {
+    expect(formatBlockDuration(850)).toBe("850ms");
+    expect(formatBlockDuration(12_345)).toBe("12.3s");
+    expect(formatBlockDuration(125_000)).toBe("2m 05s");
+    expect(formatBlockDuration(3_720_000)).toBe("1h 02m");
+  });
+});
@@ -0,0 +1,75 @@
+/**
+ * @file command-blocks.ts
+ * @description 终端命令块列表
+ * @module lib/terminal/command-blocks
+ *
+ * 合并 `terminal_command_blocks` 查询结果和 `terminal:command-block` 事件，
+ * 提供块标题所需的失败判断、耗时格式化和失败命令筛选。
+ */
+
+import type { CommandBlock } from "@/lib/terminal-api";
+
+/** 默认保存的命令块数量（与后端每个会话保留的数量一致） */
+export const DEFAULT_COMMAND_BLOCK_LIMIT = 1000;
+
+/** 命令是否执行失败（退出码非 0） */
+export function isFailedBlock(block: CommandBlock): boolean {
+  return block.exit_code !== null && block.exit_code !== 0;
+}
+
+/**
+ * 合并命令块
+ *
+ * 相同序号的块（命令开始和结束各上报一次）替换为最新的数据，结果按序号排序，
+ * 超出上限时丢弃最早的块。
+ *
+ * @param blocks - 已有的命令块
+ * @param block - 新的命令块
+ * @param limit - 保存的数量上限
+ */
+export function mergeCommandBlock(
+  blocks: CommandBlock[],
+  block: CommandBlock,
+  limit = DEFAULT_COMMAND_BLOCK_LIMIT,
+): CommandBlock[] {
+  const merged = blocks.filter((b) => b.index !== block.index);
+  merged.push(block);
+  merged.sort((a, b) => a.index - b.index);
+  return merged.slice(Math.max(0, merged.length - limit));
+}
+
+/**
+ * 筛选命令块
+ *
+ * @param blocks - 命令块
+ * @param failedOnly - 只保留执行失败的命令块
+ */
+export function filterCommandBlocks(
+  blocks: CommandBlock[],
+  failedOnly: boolean,
+): CommandBlock[] {
+  return failedOnly ? blocks.filter(isFailedBlock) : blocks;
+}
+
+/**
+ * 格式化命令耗时
+ *
+ * @param durationMs - 耗时（毫秒）
+ * @returns 如 `850ms`、`12.3s`、`2m 05s`、`1h 02m`
+ */
+export function formatBlockDuration(durationMs: number): string {
+  if (durationMs < 1000) {
+    return `${Math.max(0, Math.round(durationMs))}ms`;
+  }
+  const seconds = durationMs / 1000;
+  if (seconds < 60) {
+    return `${seconds.toFixed(1)}s`;
+  }
+  const totalSeconds = Math.floor(seconds);
+  const pad = (n: number) => String(n).padStart(2, "0");
+  if (totalSeconds < 3600) {
+    return `${Math.floor(totalSeconds / 60)}m ${pad(totalSeconds % 60)}s`;
+  }
+  const minutes = Math.floor(totalSeconds / 60);
+  return `${Math.floor(minutes / 60)}h ${pad(minutes % 60)}m`;
+}
@@ -0,0 +1,90 @@
+/**
+ * @file 终端文件传输测试
+ * @description 测试 OSC 1337 File= 解析、分片组装与 zmodem 检测
+ * @module lib/terminal/file-transfer.test
+ */
+
+import { describe, it, expect, vi } from "vitest";
+import {
+  FileTransferReceiver,
+  detectZmodem,
+  parseFileSequence,
+  sanitizeFileName,
+  shellQuotePath,
+} from "./file-transfer";
+
+const encoder = new TextEncoder();
+
+describe("parseFileSequence", () => {
+  it("解析文件名、大小和内容", () => {
+    // name = base64("报告.txt")
+    const name = btoa(String.fromCharCode(...encoder.encode("报告.txt")));
+    const file = parseFileSequence(`File=name=${name};size=5:aGVsbG8=`);
+    expect(file).toEqual({
+      name: "报告.txt",
+      size: 5,
+      inline: false,
+      data: "aGVsbG8=",
+    });
+  });
+
+  it("识别内联图片", () => {
+    expect(parseFileSequence("File=inline=1:AAAA")?.inline).toBe(true);
+  });
+
+  it("非 File= 序列返回 null", () => {
+    expect(parseFileSequence("SetMark")).toBeNull();
+    expect(parseFileSequence("File=name=YQ==")).toBeNull();
+  });
+});
+
+describe("sanitizeFileName", () => {
+  it("去除路径部分", () => {
+    expect(sanitizeFileName("../../etc/passwd")).toBe("passwd");
+    expect(sanitizeFileName("C:\\Users\\a.txt")).toBe("a.txt");
+    expect(sanitizeFileName("..")).toBe("download");
+  });
+});
+
+describe("FileTransferReceiver", () => {
+  it("组装分片传输", () => {
+    const onFile = vi.fn();
+    const receiver = new FileTransferReceiver(onFile);
+    expect(receiver.handle("MultipartFile=name=YS50eHQ=;size=5")).toBe(true);
+    expect(receiver.handle("FilePart=aGVs")).toBe(true);
+    expect(receiver.handle("FilePart=bG8=")).toBe(true);
+    expect(receiver.handle("FileEnd")).toBe(true);
+    expect(onFile).toHaveBeenCalledWith({
+      name: "a.txt",
+      size: 5,
+      inline: false,
+      data: "aGVsbG8=",
+    });
+  });
+
+  it("忽略内联图片和其他序列", () => {
+    const onFile = vi.fn();
+    const receiver = new FileTransferReceiver(onFile);
+    expect(receiver.handle("File=inline=1:AAAA")).toBe(true);
+    expect(receiver.handle("CurrentDir=/tmp")).toBe(false);
+    expect(onFile).not.toHaveBeenCalled();
+  });
+});
+
+describe("detectZmodem", () => {
+  it("识别 sz 和 rz 的起始帧", () => {
+    const rz = encoder.encode("rz\r**\x18B0100000023be50\r\n");
+    const sz = encoder.encode("**\x18B00000000000000\r\n");
+    expect(detectZmodem(rz)).toEqual({ direction: "upload", offset: 3 });
+    expect(detectZmodem(sz)).toEqual({ direction: "download", offset: 0 });
+    expect(detectZmodem(encoder.encode("ls -la\r\n"))).toBeNull();
+  });
+});
+
+describe("shellQuotePath", () => {
+  it("按需转义路径", () => {
+    expect(shellQuotePath("/tmp/a.txt")).toBe("/tmp/a.txt");
+    expect(shellQuotePath("/tmp/my file")).toBe("'/tmp/my file'");
+    expect(shellQuotePath("/tmp/it's")).toBe("'/tmp/it'\\''s'");
+  });
+});
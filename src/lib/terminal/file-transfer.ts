@@ -0,0 +1,268 @@
+/**
+ * @file file-transfer.ts
+ * @description 终端内文件传输
+ * @module lib/terminal/file-transfer
+ *
+ * 支持远程程序通过终端输出流向本机发送文件，以及通过 zmodem（rz）向远程上传文件。
+ *
+ * ## 支持的协议
+ * - iTerm2 OSC 1337 `File=`：单条序列携带完整文件（it2dl、imgcat 等）
+ * - iTerm2 OSC 1337 `MultipartFile=` / `FilePart=` / `FileEnd`：分片传输大文件
+ * - zmodem 上传：检测 rz 的起始帧，发送拖放或选择的文件（协议见 `zmodem.ts`）
+ * - zmodem 下载：检测 sz 的起始帧并取消传输，避免终端卡在二进制握手中
+ *
+ * 保存路径由后端弹出的保存对话框决定，上传只能读取用户拖放或选择的文件。
+ *
+ * `inline=1` 的文件为内联图片，由后端解码后通过 `terminal:image` 事件显示，这里直接忽略。
+ */
+
+import { saveTerminalFile } from "@/lib/terminal-api";
+
+/** iTerm2 专有 OSC 编号 */
+export const ITERM_OSC = 1337;
+
+/** 分片传输的最大累计大小（Base64 字符数） */
+export const MAX_MULTIPART_LENGTH = 512 * 1024 * 1024;
+
+/** 终端传输的文件 */
+export interface TransferredFile {
+  /** 文件名（已去除路径部分） */
+  name: string;
+  /** 远程声明的文件大小（字节） */
+  size?: number;
+  /** 是否为内联显示（图片） */
+  inline: boolean;
+  /** Base64 编码的文件内容 */
+  data: string;
+}
+
+/** zmodem 传输方向 */
+export type ZmodemDirection = "download" | "upload";
+
+/** 输出中的 zmodem 起始帧 */
+export interface ZmodemStart {
+  /** 传输方向 */
+  direction: ZmodemDirection;
+  /** 起始帧在输出中的位置 */
+  offset: number;
+}
+
+/** 拖放文件后在远程启动 zmodem 接收的命令（重名时由 rz 自动改名） */
+export const ZMODEM_UPLOAD_COMMAND = "rz -E\r";
+
+/** 拖放的文件等待远程 rz 启动的时间（毫秒） */
+export const STAGED_UPLOAD_TTL_MS = 60_000;
+
+/** 默认文件名 */
+const DEFAULT_FILE_NAME = "download";
+
+/** zmodem 十六进制帧头：`**` + ZDLE + `B0` */
+const ZMODEM_HEADER = [0x2a, 0x2a, 0x18, 0x42, 0x30];
+
+/** ZRQINIT 帧类型（sz 发起，远程发送文件） */
+const ZRQINIT = 0x30;
+
+/** ZRINIT 帧类型（rz 发起，远程等待接收文件） */
+const ZRINIT = 0x31;
+
+/**
+ * zmodem 取消序列（与 lrzsz 的 canit 一致）
+ *
+ * 10 个 CAN 使对端中止传输，随后的退格清除对端可能回显的字符。
+ */
+export const ZMODEM_ABORT = "\x18".repeat(10) + "\x08".repeat(10);
+
+/**
+ * 解码 Base64 编码的 UTF-8 字符串
+ */
+function decodeBase64Utf8(value: string): string | null {
+  try {
+    const binary = atob(value);
+    const bytes = Uint8Array.from(binary, (c) => c.charCodeAt(0));
+    return new TextDecoder().decode(bytes);
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * 去除文件名中的路径部分，防止远程指定保存位置
+ */
+export function sanitizeFileName(name: string): string {
+  const base = name.split(/[\\/]/).pop()?.trim() ?? "";
+  if (base === "" || base === "." || base === "..") {
+    return DEFAULT_FILE_NAME;
+  }
+  return base;
+}
+
+/**
+ * 解析 `key=value;key=value` 形式的参数
+ */
+function parseArgs(args: string): Omit<TransferredFile, "data"> {
+  const file: Omit<TransferredFile, "data"> = {
+    name: DEFAULT_FILE_NAME,
+    inline: false,
+  };
+  for (const pair of args.split(";")) {
+    const eq = pair.indexOf("=");
+    if (eq <= 0) {
+      continue;
+    }
+    const key = pair.slice(0, eq).trim();
+    const value = pair.slice(eq + 1).trim();
+    switch (key) {
+      case "name": {
+        const decoded = decodeBase64Utf8(value);
+        if (decoded !== null) {
+          file.name = sanitizeFileName(decoded);
+        }
+        break;
+      }
+      case "size": {
+        const size = Number.parseInt(value, 10);
+        if (Number.isFinite(size) && size >= 0) {
+          file.size = size;
+        }
+        break;
+      }
+      case "inline":
+        file.inline = value === "1";
+        break;
+    }
+  }
+  return file;
+}
+
+/**
+ * 解析 OSC 1337 `File=` 序列的内容
+ *
+ * @param data - OSC 1337 的参数部分，如 `File=name=Zm9v;size=3:YmFy`
+ * @returns 解析结果，不是 `File=` 序列时返回 null
+ */
+export function parseFileSequence(data: string): TransferredFile | null {
+  if (!data.startsWith("File=")) {
+    return null;
+  }
+  const colon = data.indexOf(":");
+  if (colon < 0) {
+    return null;
+  }
+  return {
+    ...parseArgs(data.slice("File=".length, colon)),
+    data: data.slice(colon + 1),
+  };
+}
+
+/**
+ * 在输出中查找 zmodem 起始帧
+ *
+ * @returns 起始帧的位置和方向（sz 发起时为 "download"，rz 发起时为 "upload"），未发现时为 null
+ */
+export function detectZmodem(bytes: Uint8Array): ZmodemStart | null {
+  const last = bytes.length - ZMODEM_HEADER.length;
+  outer: for (let i = 0; i < last; i++) {
+    for (let j = 0; j < ZMODEM_HEADER.length; j++) {
+      if (bytes[i + j] !== ZMODEM_HEADER[j]) {
+        continue outer;
+      }
+    }
+    const frameType = bytes[i + ZMODEM_HEADER.length];
+    if (frameType === ZRQINIT) {
+      return { direction: "download", offset: i };
+    }
+    if (frameType === ZRINIT) {
+      return { direction: "upload", offset: i };
+    }
+  }
+  return null;
+}
+
+/**
+ * OSC 1337 文件接收器
+ *
+ * 每个终端实例持有一个，负责组装分片传输并在文件完整后回调。
+ */
+export class FileTransferReceiver {
+  /** 进行中的分片传输 */
+  private pending: {
+    file: Omit<TransferredFile, "data">;
+    parts: string[];
+  } | null = null;
+  /** 已累计的分片长度 */
+  private pendingLength = 0;
+
+  constructor(private readonly onFile: (file: TransferredFile) => void) {}
+
+  /**
+   * 处理一条 OSC 1337 序列
+   *
+   * @returns 是否为文件传输序列（是则不再交给其他处理器）
+   */
+  handle(data: string): boolean {
+    const file = parseFileSequence(data);
+    if (file) {
+      if (!file.inline) {
+        this.onFile(file);
+      }
+      return true;
+    }
+
+    if (data.startsWith("MultipartFile=")) {
+      this.pending = {
+        file: parseArgs(data.slice("MultipartFile=".length)),
+        parts: [],
+      };
+      this.pendingLength = 0;
+      return true;
+    }
+
+    if (data.startsWith("FilePart=")) {
+      if (!this.pending) {
+        return true;
+      }
+      const part = data.slice("FilePart=".length);
+      this.pendingLength += part.length;
+      if (this.pendingLength > MAX_MULTIPART_LENGTH) {
+        console.warn("[FileTransfer] 分片传输超过大小上限，已丢弃");
+        this.pending = null;
+        return true;
+      }
+      this.pending.parts.push(part);
+      return true;
+    }
+
+    if (data === "FileEnd") {
+      const pending = this.pending;
+      this.pending = null;
+      this.pendingLength = 0;
+      if (pending && !pending.file.inline) {
+        this.onFile({ ...pending.file, data: pending.parts.join("") });
+      }
+      return true;
+    }
+
+    return false;
+  }
+}
+
+/**
+ * 弹出保存对话框（由后端弹出）并写入传输的文件
+ *
+ * @returns 保存路径，用户取消时返回 null
+ */
+export async function saveTransferredFile(
+  file: TransferredFile,
+): Promise<string | null> {
+  return saveTerminalFile(file.name, file.data);
+}
+
+/**
+ * 将路径按 POSIX shell 规则转义，用于拖放文件时粘贴到命令行
+ */
+export function shellQuotePath(path: string): string {
+  if (/^[A-Za-z0-9_\-./:@%+=,]+$/.test(path)) {
+    return path;
+  }
+  return `'${path.replace(/'/g, `'\\''`)}'`;
+}
@@ -0,0 +1,95 @@
+/**
+ * @file 终端内联图片布局测试
+ * @description 测试显示尺寸参数、宽高比和终端宽度限制
+ * @module lib/terminal/inline-images.test
+ */
+
+import { describe, it, expect } from "vitest";
+import { imageDataUrl, layoutInlineImage } from "./inline-images";
+import type { TerminalImageEvent } from "@/lib/terminal-api";
+
+const cell = { width: 10, height: 20 };
+
+function image(
+  overrides: Partial<TerminalImageEvent> = {},
+): TerminalImageEvent {
+  return {
+    session_id: "s1",
+    image_id: "abc",
+    protocol: "iterm",
+    mime: "image/png",
+    width: 200,
+    height: 100,
+    preserve_aspect_ratio: true,
+    data: "",
+    ...overrides,
+  };
+}
+
+describe("layoutInlineImage", () => {
+  it("未指定显示尺寸时按像素大小显示", () => {
+    expect(layoutInlineImage(image(), cell, 80, 24)).toEqual({
+      width: 200,
+      height: 100,
+      cols: 20,
+      rows: 5,
+    });
+  });
+
+  it("按字符单元、像素和百分比计算显示尺寸", () => {
+    expect(
+      layoutInlineImage(
+        image({ display_width: { unit: "cells", value: 10 } }),
+        cell,
+        80,
+        24,
+      ),
+    ).toMatchObject({ width: 100, height: 50, cols: 10, rows: 3 });
+
+    expect(
+      layoutInlineImage(
+        image({ display_height: { unit: "pixels", value: 40 } }),
+        cell,
+        80,
+        24,
+      ),
+    ).toMatchObject({ width: 80, height: 40 });
+
+    expect(
+      layoutInlineImage(
+        image({
+          display_width: { unit: "percent", value: 50 },
+          display_height: { unit: "cells", value: 2 },
+          preserve_aspect_ratio: false,
+        }),
+        cell,
+        80,
+        24,
+      ),
+    ).toMatchObject({ width: 400, height: 40, cols: 40, rows: 2 });
+  });
+
+  it("保持宽高比时缩放到指定区域内，且不超过终端宽度", () => {
+    expect(
+      layoutInlineImage(
+        image({
+          display_width: { unit: "pixels", value: 100 },
+          display_height: { unit: "pixels", value: 100 },
+        }),
+        cell,
+        80,
+        24,
+      ),
+    ).toMatchObject({ width: 100, height: 50 });
+
+    expect(
+      layoutInlineImage(image({ width: 2000, height: 1000 }), cell, 80, 24),
+    ).toEqual({ width: 800, height: 400, cols: 80, rows: 20 });
+  });
+
+  it("生成 data URL", () => {
+    expect(imageDataUrl("image/png", "AAAA")).toBe(
+      "data:image/png;base64,AAAA",
+    );
+  });
+});
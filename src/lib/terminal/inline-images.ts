@@ -0,0 +1,118 @@
+/**
+ * @file inline-images.ts
+ * @description 终端内联图片布局
+ * @module lib/terminal/inline-images
+ *
+ * 根据后端 `terminal:image` 事件（sixel、iTerm2 OSC 1337）的显示尺寸参数，
+ * 计算图片在终端中的像素大小和占用的字符单元数，供 xterm 装饰层渲染。
+ */
+
+import type { ImageDimension, TerminalImageEvent } from "@/lib/terminal-api";
+
+/** 字符单元大小（像素） */
+export interface CellSize {
+  width: number;
+  height: number;
+}
+
+/** 内联图片布局 */
+export interface InlineImageLayout {
+  /** 显示宽度（像素） */
+  width: number;
+  /** 显示高度（像素） */
+  height: number;
+  /** 占用的列数 */
+  cols: number;
+  /** 占用的行数 */
+  rows: number;
+}
+
+/**
+ * 将显示尺寸参数转换为像素
+ *
+ * @param dimension - 显示尺寸
+ * @param cell - 对应方向的字符单元大小
+ * @param total - 对应方向的终端大小（像素）
+ */
+function toPixels(
+  dimension: ImageDimension | null | undefined,
+  cell: number,
+  total: number,
+): number | undefined {
+  if (!dimension) {
+    return undefined;
+  }
+  switch (dimension.unit) {
+    case "cells":
+      return dimension.value * cell;
+    case "pixels":
+      return dimension.value;
+    case "percent":
+      return (dimension.value / 100) * total;
+  }
+}
+
+/**
+ * 计算内联图片布局
+ *
+ * 未指定显示尺寸时按像素大小显示；保持宽高比时只指定一边则按比例计算另一边，
+ * 两边都指定则缩放到不超出指定区域。图片宽度不超过终端宽度。
+ *
+ * @param event - 图片事件
+ * @param cell - 字符单元大小
+ * @param cols - 终端列数
+ * @param rows - 终端行数
+ */
+export function layoutInlineImage(
+  event: TerminalImageEvent,
+  cell: CellSize,
+  cols: number,
+  rows: number,
+): InlineImageLayout {
+  const maxWidth = cols * cell.width;
+  const ratio = event.height > 0 ? event.width / event.height : 1;
+
+  const requestedWidth = toPixels(event.display_width, cell.width, maxWidth);
+  const requestedHeight = toPixels(
+    event.display_height,
+    cell.height,
+    rows * cell.height,
+  );
+
+  let width = requestedWidth ?? event.width;
+  let height = requestedHeight ?? event.height;
+  if (event.preserve_aspect_ratio) {
+    if (requestedWidth !== undefined && requestedHeight === undefined) {
+      height = width / ratio;
+    } else if (requestedHeight !== undefined && requestedWidth === undefined) {
+      width = height * ratio;
+    } else if (requestedWidth !== undefined && requestedHeight !== undefined) {
+      width = Math.min(requestedWidth, requestedHeight * ratio);
+      height = width / ratio;
+    }
+  }
+
+  if (width > maxWidth) {
+    height = event.preserve_aspect_ratio ? maxWidth / ratio : height;
+    width = maxWidth;
+  }
+
+  width = Math.max(1, Math.round(width));
+  height = Math.max(1, Math.round(height));
+  return {
+    width,
+    height,
+    cols: Math.max(1, Math.ceil(width / cell.width)),
+    rows: Math.max(1, Math.ceil(height / cell.height)),
+  };
+}
+
+/**
+ * 生成图片的 data URL
+ *
+ * @param mime - MIME 类型
+ * @param data - 图片数据（Base64 编码）
+ */
+export function imageDataUrl(mime: string, data: string): string {
+  return `data:${mime};base64,${data}`;
+}
@@ -0,0 +1,73 @@
+/**
+ * @file 终端文件链接测试
+ * @description 测试文件链接的保存、路径解析和行内定位
+ * @module lib/terminal/links.test
+ */
+
+import { describe, it, expect } from "vitest";
+import { TerminalLinkRegistry } from "./links";
+import type { TerminalLink } from "@/lib/terminal-api";
+
+function fileLink(text: string, target: string, line?: number): TerminalLink {
+  return { kind: "file", text, target, line, hyperlink: false };
+}
+
+describe("TerminalLinkRegistry", () => {
+  it("按工作目录解析相对路径并在行内定位", () => {
+    const registry = new TerminalLinkRegistry();
+    registry.add({
+      session_id: "s1",
+      cwd: "/home/user/app",
+      links: [
+        fileLink("src/main.rs:12", "src/main.rs", 12),
+        fileLink("/etc/hosts", "/etc/hosts"),
+      ],
+    });
+
+    const matches = registry.match("error: src/main.rs:12 see /etc/hosts.");
+    expect(matches.map((m) => [m.path, m.start, m.end])).toEqual([
+      ["/home/user/app/src/main.rs", 7, 21],
+      ["/etc/hosts", 26, 36],
+    ]);
+    expect(matches[0].link.line).toBe(12);
+  });
+
+  it("忽略 URL、超链接和不完整的匹配", () => {
+    const registry = new TerminalLinkRegistry();
+    registry.add({
+      session_id: "s1",
+      links: [
+        {
+          kind: "url",
+          text: "https://a.dev",
+          target: "https://a.dev",
+          hyperlink: false,
+        },
+        { ...fileLink("b.txt", "/tmp/b.txt"), hyperlink: true },
+        fileLink("lib.rs:3", "lib.rs", 3),
+      ],
+    });
+
+    expect(registry.match("https://a.dev b.txt")).toEqual([]);
+    expect(registry.match("mylib.rs:3 lib.rs:30")).toEqual([]);
+  });
+
+  it("较长的链接优先，超出上限时丢弃最早的链接", () => {
+    const registry = new TerminalLinkRegistry(2);
+    registry.add({
+      session_id: "s1",
+      links: [
+        fileLink("./a.rs", "./a.rs"),
+        fileLink("./a.rs:1:2", "./a.rs", 1),
+        fileLink("./b.rs", "./b.rs"),
+      ],
+    });
+
+    expect(registry.match("./a.rs ./b.rs").map((m) => m.link.text)).toEqual([
+      "./b.rs",
+    ]);
+    expect(registry.match("./a.rs:1:2").map((m) => m.link.text)).toEqual([
+      "./a.rs:1:2",
+    ]);
+  });
+});
@@ -0,0 +1,96 @@
+/**
+ * @file links.ts
+ * @description 终端文件链接
+ * @module lib/terminal/links
+ *
+ * 保存后端 `terminal:links` 事件上报的文件链接，并在终端缓冲区的行文本中定位，
+ * 供 xterm 链接提供器渲染为可点击链接（在指定行列打开文件）。
+ *
+ * URL 由 WebLinksAddon 处理，OSC 8 超链接由 xterm 内置支持处理，这里只处理文件路径。
+ */
+
+import {
+  resolveLinkTarget,
+  type TerminalLink,
+  type TerminalLinksEvent,
+} from "@/lib/terminal-api";
+
+/** 默认保存的链接数量 */
+export const DEFAULT_LINK_LIMIT = 500;
+
+/** 行文本中的文件链接 */
+export interface FileLinkMatch {
+  /** 链接 */
+  link: TerminalLink;
+  /** 解析后的文件路径 */
+  path: string;
+  /** 在行文本中的起始位置 */
+  start: number;
+  /** 在行文本中的结束位置（不含） */
+  end: number;
+}
+
+/** 路径字符（链接文本前紧邻这些字符时不是完整的链接） */
+const PATH_CHAR = /[\w.\-@+/\\~]/;
+/** 链接文本后紧邻这些字符时不是完整的链接（允许句末的句点） */
+const PATH_TAIL_CHAR = /[\w/\\]/;
+
+/** 终端文件链接表 */
+export class TerminalLinkRegistry {
+  /** 链接文本 -> 链接和解析后的路径（按上报时间排序） */
+  private links = new Map<string, { link: TerminalLink; path: string }>();
+
+  constructor(private readonly limit = DEFAULT_LINK_LIMIT) {}
+
+  /** 保存事件中的文件链接，超出上限时丢弃最早的链接 */
+  add(event: TerminalLinksEvent): void {
+    for (const link of event.links) {
+      if (link.kind !== "file" || link.hyperlink) {
+        continue;
+      }
+      this.links.delete(link.text);
+      this.links.set(link.text, {
+        link,
+        path: resolveLinkTarget(link, event.cwd),
+      });
+    }
+    for (const key of this.links.keys()) {
+      if (this.links.size <= this.limit) {
+        break;
+      }
+      this.links.delete(key);
+    }
+  }
+
+  /** 在一行文本中查找已保存的文件链接 */
+  match(lineText: string): FileLinkMatch[] {
+    const matches: FileLinkMatch[] = [];
+    for (const [text, { link, path }] of this.links) {
+      let start = lineText.indexOf(text);
+      while (start !== -1) {
+        const end = start + text.length;
+        const before = lineText[start - 1] ?? "";
+        const after = lineText[end] ?? "";
+        if (!PATH_CHAR.test(before) && !PATH_TAIL_CHAR.test(after)) {
+          matches.push({ link, path, start, end });
+        }
+        start = lineText.indexOf(text, end);
+      }
+    }
+    // 较长的链接优先（如 `a.rs:1:2` 优于 `a.rs`）
+    matches.sort((a, b) => a.start - b.start || b.end - a.end);
+    let lastEnd = 0;
+    return matches.filter((m) => {
+      if (m.start < lastEnd) {
+        return false;
+      }
+      lastEnd = m.end;
+      return true;
+    });
+  }
+
+  /** 清空链接 */
+  clear(): void {
+    this.links.clear();
+  }
+}
@@ -34,6 +34,8 @@ interface RawConnStatus {
   wsh_error?: string;
   no_wsh_reason?: string;
   wsh_version?: string;
+  jump_hosts?: string[];
+  reconnect_attempt?: number;
 }
 
 /**
@@ -125,6 +127,8 @@ export async function subscribeConnChange(
       wshError: payload.status.wsh_error,
       noWshReason: payload.status.no_wsh_reason,
       wshVersion: payload.status.wsh_version,
+      jumpHosts: payload.status.jump_hosts ?? [],
+      reconnectAttempt: payload.status.reconnect_attempt ?? 0,
     };
 
     handler({
@@ -178,6 +182,8 @@ export async function subscribeConnectionStatus(
         wshError: payload.status.wsh_error,
         noWshReason: payload.status.no_wsh_reason,
         wshVersion: payload.status.wsh_version,
+        jumpHosts: payload.status.jump_hosts ?? [],
+        reconnectAttempt: payload.status.reconnect_attempt ?? 0,
       };
 
       handler({
@@ -66,6 +66,10 @@ export interface ConnStatus {
   noWshReason?: string;
   /** wsh 版本 */
   wshVersion?: string;
+  /** 经过的跳板机（ProxyJump，按连接顺序） */
+  jumpHosts?: string[];
+  /** 自动重连的当前尝试次数（0 表示未在重连） */
+  reconnectAttempt?: number;
 }
 
 /**
@@ -305,6 +305,8 @@ export function getThemeList(): TerminalTheme[] {
 const THEME_STORAGE_KEY = "terminal-theme";
 /** 字体大小存储键 */
 const FONT_SIZE_STORAGE_KEY = "terminal-font-size";
+/** 后台会话偏好存储键 */
+const KEEP_SESSIONS_STORAGE_KEY = "terminal-keep-sessions";
 /** 默认字体大小 */
 export const DEFAULT_FONT_SIZE = 14;
 /** 最小字体大小 */
@@ -348,6 +350,16 @@ export function loadFontSizePreference(): number {
   return DEFAULT_FONT_SIZE;
 }
 
+/** 保存后台会话偏好（新建终端是否在应用重启后保留） */
+export function saveKeepSessionsPreference(enabled: boolean): void {
+  localStorage.setItem(KEEP_SESSIONS_STORAGE_KEY, String(enabled));
+}
+
+/** 从本地存储加载后台会话偏好 */
+export function loadKeepSessionsPreference(): boolean {
+  return localStorage.getItem(KEEP_SESSIONS_STORAGE_KEY) === "true";
+}
+
 /** 应用透明度到主题
  * _Requirements: 12.3_
  */
@@ -0,0 +1,289 @@
+/**
+ * @file zmodem 上传测试
+ * @description 测试帧编码、帧头解析，以及与模拟接收端（rz）完成上传
+ * @module lib/terminal/zmodem.test
+ */
+
+import { describe, it, expect } from "vitest";
+import {
+  ZACK,
+  ZDATA,
+  ZEOF,
+  ZFILE,
+  ZFIN,
+  ZRINIT,
+  ZRPOS,
+  ZSKIP,
+  ZmodemHeaderReader,
+  ZmodemSender,
+  crc16,
+  encodeHexHeader,
+  headerPosition,
+  type ZmodemFile,
+  type ZmodemHeader,
+} from "./zmodem";
+
+const ZDLE = 0x18;
+
+function header(type: number, position = 0): ZmodemHeader {
+  return {
+    type,
+    args: [
+      position & 0xff,
+      (position >> 8) & 0xff,
+      (position >> 16) & 0xff,
+      (position >> 24) & 0xff,
+    ],
+  };
+}
+
+/**
+ * 解码发送端输出的二进制帧头和数据子包
+ */
+class FrameDecoder {
+  private pos = 0;
+  constructor(private readonly bytes: Uint8Array) {}
+
+  done(): boolean {
+    return this.pos >= this.bytes.length;
+  }
+
+  /** 读取一个转义后的字节，子包结束时返回结束类型（负数） */
+  private byte(): number {
+    const b = this.bytes[this.pos++];
+    if (b !== ZDLE) {
+      return b;
+    }
+    const next = this.bytes[this.pos++];
+    if (next >= 0x68 && next <= 0x6b) {
+      return -next;
+    }
+    return next ^ 0x40;
+  }
+
+  header(): ZmodemHeader {
+    // 十六进制帧头以两个 ZPAD 开头
+    while (this.bytes[this.pos + 1] === 0x2a) {
+      this.pos++;
+    }
+    expect(this.bytes[this.pos]).toBe(0x2a);
+    expect(this.bytes[this.pos + 1]).toBe(ZDLE);
+    if (this.bytes[this.pos + 2] === 0x42) {
+      const hex = new TextDecoder().decode(
+        this.bytes.slice(this.pos + 3, this.pos + 17),
+      );
+      this.pos = this.bytes.length;
+      const raw = hex.match(/../g)!.map((h) => Number.parseInt(h, 16));
+      return { type: raw[0], args: [raw[1], raw[2], raw[3], raw[4]] };
+    }
+    expect(this.bytes[this.pos + 2]).toBe(0x41);
+    this.pos += 3;
+    const raw = Array.from({ length: 7 }, () => this.byte());
+    expect(crc16(raw.slice(0, 5))).toBe((raw[5] << 8) | raw[6]);
+    return { type: raw[0], args: [raw[1], raw[2], raw[3], raw[4]] };
+  }
+
+  subpacket(): { data: number[]; end: number } {
+    const data: number[] = [];
+    for (;;) {
+      const b = this.byte();
+      if (b < 0) {
+        const end = -b;
+        const crc = (this.byte() << 8) | this.byte();
+        expect(crc16([end], crc16(data))).toBe(crc);
+        if (this.bytes[this.pos] === 0x11) {
+          this.pos++;
+        }
+        return { data, end };
+      }
+      data.push(b);
+    }
+  }
+}
+
+/**
+ * 模拟 rz：按发送端的输出回复帧头
+ */
+function mockReceiver(options: { skip?: string[]; rewindOnce?: number } = {}) {
+  const files = new Map<string, number[]>();
+  let stage: "header" | "file-info" | "data" = "header";
+  let current: number[] = [];
+  let rewound = false;
+  let over = false;
+  let sender: ZmodemSender;
+
+  const reply = (h: ZmodemHeader) => sender.feed(encodeHexHeader(h));
+
+  const send = async (bytes: Uint8Array) => {
+    if (bytes.length === 2 && bytes[0] === 0x4f && bytes[1] === 0x4f) {
+      over = true;
+      return;
+    }
+    const decoder = new FrameDecoder(bytes);
+    if (stage === "file-info") {
+      const { data } = decoder.subpacket();
+      const name = new TextDecoder().decode(
+        Uint8Array.from(data.slice(0, data.indexOf(0))),
+      );
+      stage = "header";
+      if (options.skip?.includes(name)) {
+        reply(header(ZSKIP));
+        return;
+      }
+      current = [];
+      files.set(name, current);
+      reply(header(ZRPOS, 0));
+      return;
+    }
+    if (stage === "data") {
+      while (!decoder.done()) {
+        const { data, end } = decoder.subpacket();
+        current.push(...data);
+        if (end === 0x68) {
+          stage = "header";
+        } else if (end === 0x6b) {
+          stage = "header";
+          reply(header(ZACK, current.length));
+        }
+      }
+      return;
+    }
+    const h = decoder.header();
+    switch (h.type) {
+      case ZFILE:
+        stage = "file-info";
+        break;
+      case ZDATA:
+        expect(headerPosition(h)).toBe(current.length);
+        stage = "data";
+        break;
+      case ZEOF:
+        if (
+          options.rewindOnce !== undefined &&
+          !rewound &&
+          current.length > options.rewindOnce
+        ) {
+          rewound = true;
+          current.length = options.rewindOnce;
+          reply(header(ZRPOS, options.rewindOnce));
+          return;
+        }
+        expect(headerPosition(h)).toBe(current.length);
+        reply(header(ZRINIT));
+        break;
+      case ZFIN:
+        reply(header(ZFIN));
+        break;
+    }
+  };
+
+  return {
+    files,
+    get over() {
+      return over;
+    },
+    attach(s: ZmodemSender) {
+      sender = s;
+    },
+    send,
+  };
+}
+
+function makeFile(name: string, content: Uint8Array): ZmodemFile {
+  return { path: `/tmp/${name}`, name, size: content.length, modified: 0 };
+}
+
+describe("crc16", () => {
+  it("与 CRC-16/XMODEM 一致", () => {
+    expect(crc16(new TextEncoder().encode("123456789"))).toBe(0x31c3);
+  });
+});
+
+describe("ZmodemHeaderReader", () => {
+  it("解析跨数据块的十六进制帧头", () => {
+    const bytes = new Uint8Array([
+      ...new TextEncoder().encode("rz waiting to receive."),
+      ...encodeHexHeader({ type: ZRINIT, args: [0, 0, 0, 0x23] }),
+    ]);
+    const reader = new ZmodemHeaderReader();
+    const headers: ZmodemHeader[] = [];
+    reader.push(bytes.slice(0, 30), (h) => (headers.push(h), false));
+    expect(headers).toEqual([]);
+    reader.push(bytes.slice(30), (h) => (headers.push(h), false));
+    expect(headers).toEqual([{ type: ZRINIT, args: [0, 0, 0, 0x23] }]);
+  });
+
+  it("检测接收端取消", () => {
+    const reader = new ZmodemHeaderReader();
+    reader.push(new Uint8Array(8).fill(ZDLE), () => false);
+    expect(reader.cancelled).toBe(true);
+  });
+});
+
+describe("ZmodemSender", () => {
+  // 包含所有字节值，覆盖 ZDLE 转义
+  const content = Uint8Array.from({ length: 5000 }, (_, i) => (i * 7) % 256);
+
+  async function upload(
+    files: Map<string, Uint8Array>,
+    options: Parameters<typeof mockReceiver>[0] = {},
+    init: ZmodemHeader = header(ZRINIT),
+  ) {
+    const receiver = mockReceiver(options);
+    const sender = new ZmodemSender({
+      send: receiver.send,
+      read: async (file, offset, length) =>
+        files.get(file.name)!.slice(offset, offset + length),
+    });
+    receiver.attach(sender);
+    sender.feed(encodeHexHeader(init));
+    const result = await sender.upload(
+      [...files].map(([name, data]) => makeFile(name, data)),
+    );
+    return { result, receiver, sender };
+  }
+
+  it("上传多个文件并结束会话", async () => {
+    const files = new Map([
+      ["a.bin", content],
+      ["empty.txt", new Uint8Array(0)],
+    ]);
+    const { result, receiver } = await upload(files);
+    expect(result).toEqual({ sent: ["a.bin", "empty.txt"], skipped: [] });
+    expect(Uint8Array.from(receiver.files.get("a.bin")!)).toEqual(content);
+    expect(receiver.files.get("empty.txt")).toEqual([]);
+    expect(receiver.over).toBe(true);
+  });
+
+  it("接收端跳过的文件不发送内容", async () => {
+    const files = new Map([
+      ["exists.txt", content],
+      ["b.txt", content.slice(0, 10)],
+    ]);
+    const { result, receiver } = await upload(files, { skip: ["exists.txt"] });
+    expect(result).toEqual({ sent: ["b.txt"], skipped: ["exists.txt"] });
+    expect(receiver.files.has("exists.txt")).toBe(false);
+  });
+
+  it("按接收端请求的位置重传", async () => {
+    const files = new Map([["a.bin", content]]);
+    const { receiver } = await upload(files, { rewindOnce: 1000 });
+    expect(Uint8Array.from(receiver.files.get("a.bin")!)).toEqual(content);
+  });
+
+  it("按接收端缓冲区大小等待确认并转义控制字符", async () => {
+    const files = new Map([["a.bin", content]]);
+    // 缓冲区 2048 字节，要求转义控制字符
+    const { receiver } = await upload(files, {}, {
+      type: ZRINIT,
+      args: [0x00, 0x08, 0, 0x40],
+    });
+    expect(Uint8Array.from(receiver.files.get("a.bin")!)).toEqual(content);
+  });
+
+  it("结束后输出交还给终端", async () => {
+    const { sender } = await upload(new Map([["a.txt", content]]));
+    const prompt = new TextEncoder().encode("$ ");
+    expect(sender.feed(prompt)).toEqual(prompt);
+  });
+});
@@ -0,0 +1,636 @@
+/**
+ * @file zmodem.ts
+ * @description zmodem 上传（发送端）
+ * @module lib/terminal/zmodem
+ *
+ * 远程执行 `rz` 后，终端作为 zmodem 发送端把本机文件写入 PTY 输入。
+ *
+ * ## 实现范围
+ * - 使用 CRC-16 二进制帧头和数据子包，解析接收端的十六进制帧头
+ * - 支持接收端要求的缓冲区大小（按窗口发送 ZCRCW 并等待 ZACK）和控制字符转义（TESCCTL）
+ * - 接收端跳过文件（ZSKIP）时发送下一个文件，请求重传（ZRPOS）时从指定位置重发
+ * - 不实现下载（sz），远程发送文件请使用 OSC 1337（it2dl）
+ */
+
+/** 帧头前导符 */
+const ZPAD = 0x2a;
+/** 转义符（同 CAN） */
+const ZDLE = 0x18;
+/** CRC-16 二进制帧头 */
+const ZBIN = 0x41;
+/** 十六进制帧头 */
+const ZHEX = 0x42;
+/** XON */
+const XON = 0x11;
+
+/** 帧类型 */
+export const ZRINIT = 0x01;
+export const ZFILE = 0x04;
+export const ZSKIP = 0x05;
+export const ZFIN = 0x08;
+export const ZRPOS = 0x09;
+export const ZDATA = 0x0a;
+export const ZEOF = 0x0b;
+export const ZACK = 0x03;
+
+/** 数据子包结束类型 */
+const ZCRCE = 0x68;
+const ZCRCG = 0x69;
+const ZCRCW = 0x6b;
+
+/** 始终转义的字节（ZDLE、DLE、XON、XOFF、CR，高位版本同样转义） */
+const ESCAPED = new Set([ZDLE, 0x10, XON, 0x13, 0x0d]);
+
+/** ZRINIT 标志：接收端要求转义所有控制字符 */
+const TESCCTL = 0x40;
+
+/** ZFILE 标志：按二进制传输 */
+const ZCBIN = 0x01;
+
+/** 数据子包大小 */
+const SUBPACKET_SIZE = 1024;
+
+/** 每次读取的文件块大小 */
+const READ_CHUNK_SIZE = 64 * 1024;
+
+/** 等待接收端响应的超时时间（毫秒） */
+const RESPONSE_TIMEOUT_MS = 30_000;
+
+/** 发送 ZFILE 后的最大重试次数 */
+const MAX_FILE_OFFERS = 3;
+
+/** 会话结束时发送的 "over and out" */
+const OVER_AND_OUT = [0x4f, 0x4f];
+
+/** 接收端取消传输时连续发送的 CAN 个数下限 */
+const CANCEL_CAN_COUNT = 5;
+
+/** 待上传的文件 */
+export interface ZmodemFile {
+  /** 读取文件时使用的标识（本机路径） */
+  path: string;
+  /** 文件名 */
+  name: string;
+  /** 文件大小（字节） */
+  size: number;
+  /** 修改时间（Unix 秒） */
+  modified: number;
+}
+
+/** zmodem 帧头 */
+export interface ZmodemHeader {
+  /** 帧类型 */
+  type: number;
+  /** 4 字节参数（ZP0..ZP3，ZF0 为第 4 个字节） */
+  args: [number, number, number, number];
+}
+
+/** 上传结果 */
+export interface ZmodemUploadResult {
+  /** 已发送的文件 */
+  sent: string[];
+  /** 接收端跳过的文件（如已存在） */
+  skipped: string[];
+}
+
+/** 发送端依赖的 I/O */
+export interface ZmodemIO {
+  /** 写入 PTY 输入 */
+  send: (data: Uint8Array) => Promise<void>;
+  /** 读取文件从 `offset` 开始的最多 `length` 字节 */
+  read: (
+    file: ZmodemFile,
+    offset: number,
+    length: number,
+  ) => Promise<Uint8Array>;
+  /** 发送进度 */
+  onProgress?: (file: ZmodemFile, offset: number) => void;
+}
+
+/**
+ * 计算 CRC-16/XMODEM
+ */
+export function crc16(bytes: ArrayLike<number>, crc = 0): number {
+  for (let i = 0; i < bytes.length; i++) {
+    crc ^= bytes[i] << 8;
+    for (let bit = 0; bit < 8; bit++) {
+      crc = crc & 0x8000 ? (crc << 1) ^ 0x1021 : crc << 1;
+    }
+    crc &= 0xffff;
+  }
+  return crc;
+}
+
+/** 位置参数（小端） */
+function positionArgs(position: number): [number, number, number, number] {
+  return [
+    position & 0xff,
+    (position >>> 8) & 0xff,
+    (position >>> 16) & 0xff,
+    (position >>> 24) & 0xff,
+  ];
+}
+
+/** 帧头参数中的位置（小端） */
+export function headerPosition(header: ZmodemHeader): number {
+  const [p0, p1, p2, p3] = header.args;
+  return (p0 | (p1 << 8) | (p2 << 16)) + p3 * 0x1000000;
+}
+
+/**
+ * ZDLE 转义
+ *
+ * 转义 ZDLE、XON/XOFF、DLE 和 CR（含高位版本），`escapeControl` 时转义所有控制字符。
+ */
+export function zdleEscape(
+  bytes: ArrayLike<number>,
+  escapeControl = false,
+): number[] {
+  const out: number[] = [];
+  for (let i = 0; i < bytes.length; i++) {
+    const b = bytes[i];
+    if (ESCAPED.has(b & 0x7f) || (escapeControl && (b & 0x60) === 0)) {
+      out.push(ZDLE, b ^ 0x40);
+    } else {
+      out.push(b);
+    }
+  }
+  return out;
+}
+
+/**
+ * 编码 CRC-16 二进制帧头
+ */
+export function encodeBinaryHeader(
+  header: ZmodemHeader,
+  escapeControl = false,
+): Uint8Array {
+  const raw = [header.type, ...header.args];
+  const crc = crc16(raw);
+  return Uint8Array.from([
+    ZPAD,
+    ZDLE,
+    ZBIN,
+    ...zdleEscape([...raw, crc >> 8, crc & 0xff], escapeControl),
+  ]);
+}
+
+/**
+ * 编码十六进制帧头
+ */
+export function encodeHexHeader(header: ZmodemHeader): Uint8Array {
+  const raw = [header.type, ...header.args];
+  const crc = crc16(raw);
+  const hex = [...raw, crc >> 8, crc & 0xff]
+    .map((b) => b.toString(16).padStart(2, "0"))
+    .join("");
+  const bytes = [ZPAD, ZPAD, ZDLE, ZHEX];
+  for (const c of hex) {
+    bytes.push(c.charCodeAt(0));
+  }
+  bytes.push(0x0d, 0x0a);
+  if (header.type !== ZFIN && header.type !== ZACK) {
+    bytes.push(XON);
+  }
+  return Uint8Array.from(bytes);
+}
+
+/**
+ * 编码 CRC-16 数据子包
+ *
+ * @param end - 子包结束类型（ZCRCE / ZCRCG / ZCRCW）
+ */
+export function encodeSubpacket(
+  data: ArrayLike<number>,
+  end: number,
+  escapeControl = false,
+): Uint8Array {
+  const crc = crc16([end], crc16(data));
+  const bytes = [
+    ...zdleEscape(data, escapeControl),
+    ZDLE,
+    end,
+    ...zdleEscape([crc >> 8, crc & 0xff], escapeControl),
+  ];
+  if (end === ZCRCW) {
+    bytes.push(XON);
+  }
+  return Uint8Array.from(bytes);
+}
+
+/**
+ * ZFILE 数据子包的文件信息
+ *
+ * 格式与 lrzsz 一致：文件名 NUL 大小 修改时间（八进制） 权限 序号 剩余文件数 剩余字节数 NUL。
+ * 权限为 0 时由接收端决定。
+ */
+export function encodeFileInfo(
+  file: ZmodemFile,
+  filesLeft: number,
+  bytesLeft: number,
+): Uint8Array {
+  const name = new TextEncoder().encode(file.name);
+  const info = new TextEncoder().encode(
+    `${file.size} ${file.modified.toString(8)} 0 0 ${filesLeft} ${bytesLeft}`,
+  );
+  const bytes = new Uint8Array(name.length + info.length + 2);
+  bytes.set(name, 0);
+  bytes.set(info, name.length + 1);
+  return bytes;
+}
+
+function parseHex(bytes: Uint8Array, start: number): number | null {
+  const hi = hexDigit(bytes[start]);
+  const lo = hexDigit(bytes[start + 1]);
+  return hi === null || lo === null ? null : (hi << 4) | lo;
+}
+
+function hexDigit(c: number): number | null {
+  if (c >= 0x30 && c <= 0x39) {
+    return c - 0x30;
+  }
+  if (c >= 0x61 && c <= 0x66) {
+    return c - 0x61 + 10;
+  }
+  if (c >= 0x41 && c <= 0x46) {
+    return c - 0x41 + 10;
+  }
+  return null;
+}
+
+/**
+ * 接收端输出解析器
+ *
+ * 从输出中提取十六进制帧头，跨数据块的帧头会在后续数据到达后解析。
+ */
+export class ZmodemHeaderReader {
+  private buffer = new Uint8Array(0);
+  /** 末尾连续的 CAN 个数 */
+  private cans = 0;
+  /** 接收端是否取消了传输 */
+  cancelled = false;
+
+  /**
+   * 追加输出并返回其中完整的帧头
+   *
+   * @param onHeader - 返回 true 时停止解析，剩余数据通过 `takeRest` 获取
+   */
+  push(bytes: Uint8Array, onHeader: (header: ZmodemHeader) => boolean): void {
+    for (const b of bytes) {
+      this.cans = b === ZDLE ? this.cans + 1 : 0;
+      if (this.cans >= CANCEL_CAN_COUNT) {
+        this.cancelled = true;
+      }
+    }
+    const merged = new Uint8Array(this.buffer.length + bytes.length);
+    merged.set(this.buffer, 0);
+    merged.set(bytes, this.buffer.length);
+
+    let start = 0;
+    for (;;) {
+      const index = findHexHeader(merged, start);
+      if (index < 0) {
+        // 保留可能是帧头开头的尾部数据
+        start = Math.max(start, merged.length - 3);
+        break;
+      }
+      const body = index + 3;
+      if (merged.length < body + 14) {
+        start = index;
+        break;
+      }
+      start = body + 14;
+      const header = decodeHexHeader(merged, body);
+      if (header && onHeader(header)) {
+        break;
+      }
+    }
+    this.buffer = merged.slice(start);
+  }
+
+  /**
+   * 取出尚未解析的数据（跳过帧头后的换行和 XON）
+   */
+  takeRest(): Uint8Array {
+    let start = 0;
+    while (
+      start < this.buffer.length &&
+      [0x0d, 0x0a, 0x8a, XON].includes(this.buffer[start])
+    ) {
+      start++;
+    }
+    const rest = this.buffer.slice(start);
+    this.buffer = new Uint8Array(0);
+    return rest;
+  }
+}
+
+/** 查找 `*` ZDLE `B` */
+function findHexHeader(bytes: Uint8Array, from: number): number {
+  for (let i = from; i + 2 < bytes.length; i++) {
+    if (bytes[i] === ZPAD && bytes[i + 1] === ZDLE && bytes[i + 2] === ZHEX) {
+      return i;
+    }
+  }
+  return -1;
+}
+
+function decodeHexHeader(
+  bytes: Uint8Array,
+  start: number,
+): ZmodemHeader | null {
+  const raw: number[] = [];
+  for (let i = 0; i < 7; i++) {
+    const b = parseHex(bytes, start + i * 2);
+    if (b === null) {
+      return null;
+    }
+    raw.push(b);
+  }
+  if (crc16(raw.slice(0, 5)) !== ((raw[5] << 8) | raw[6])) {
+    return null;
+  }
+  return { type: raw[0], args: [raw[1], raw[2], raw[3], raw[4]] };
+}
+
+/**
+ * zmodem 发送端
+ *
+ * 创建后把远程输出交给 `feed`，在收到接收端的 ZRINIT 后调用 `upload`。
+ * 传输结束（完成、取消或失败）后 `feed` 原样返回输出，交还给终端显示。
+ */
+export class ZmodemSender {
+  private reader = new ZmodemHeaderReader();
+  private headers: ZmodemHeader[] = [];
+  private waiter: (() => void) | null = null;
+  /** 收到接收端的 ZFIN 或传输中止后不再解析输出 */
+  private finished = false;
+  /** 最近一次 ZRINIT */
+  private init: ZmodemHeader | null = null;
+
+  constructor(private readonly io: ZmodemIO) {}
+
+  /**
+   * 处理远程输出
+   *
+   * @returns 需要显示在终端中的数据（传输进行中为空）
+   */
+  feed(bytes: Uint8Array): Uint8Array {
+    if (this.finished) {
+      return bytes;
+    }
+    this.reader.push(bytes, (header) => {
+      if (header.type === ZRINIT) {
+        this.init = header;
+      }
+      this.headers.push(header);
+      if (header.type === ZFIN) {
+        this.finished = true;
+      }
+      return this.finished;
+    });
+    this.wake();
+    return this.finished ? this.reader.takeRest() : new Uint8Array(0);
+  }
+
+  /**
+   * 中止传输（向接收端发送取消序列）
+   */
+  async abort(): Promise<void> {
+    if (this.finished) {
+      return;
+    }
+    this.finished = true;
+    this.wake();
+    await this.io.send(
+      Uint8Array.from([...Array(10).fill(ZDLE), ...Array(10).fill(0x08)]),
+    );
+  }
+
+  /**
+   * 上传文件
+   *
+   * 应在收到 ZRINIT 后调用，期间重复收到的 ZRINIT 被忽略。
+   */
+  async upload(files: ZmodemFile[]): Promise<ZmodemUploadResult> {
+    const result: ZmodemUploadResult = { sent: [], skipped: [] };
+    try {
+      this.headers = [];
+      let bytesLeft = files.reduce((sum, file) => sum + file.size, 0);
+      for (let i = 0; i < files.length; i++) {
+        const file = files[i];
+        const position = await this.offer(file, files.length - i, bytesLeft);
+        bytesLeft -= file.size;
+        if (position === null) {
+          result.skipped.push(file.name);
+          continue;
+        }
+        await this.sendFile(file, position);
+        result.sent.push(file.name);
+      }
+
+      // 无更多文件：ZFIN 交换后发送 "OO"
+      await this.io.send(encodeHexHeader({ type: ZFIN, args: [0, 0, 0, 0] }));
+      await this.expect([ZFIN]);
+      await this.io.send(Uint8Array.from(OVER_AND_OUT));
+      return result;
+    } catch (err) {
+      await this.abort();
+      throw err;
+    }
+  }
+
+  private get escapeControl(): boolean {
+    return ((this.init?.args[3] ?? 0) & TESCCTL) !== 0;
+  }
+
+  /** 接收端缓冲区大小，0 表示可连续发送 */
+  private get windowSize(): number {
+    const args = this.init?.args ?? [0, 0, 0, 0];
+    return args[0] | (args[1] << 8);
+  }
+
+  /**
+   * 发送 ZFILE，返回接收端请求的起始位置，跳过时返回 null
+   */
+  private async offer(
+    file: ZmodemFile,
+    filesLeft: number,
+    bytesLeft: number,
+  ): Promise<number | null> {
+    for (let attempt = 0; attempt < MAX_FILE_OFFERS; attempt++) {
+      await this.io.send(
+        encodeBinaryHeader(
+          { type: ZFILE, args: [0, 0, 0, ZCBIN] },
+          this.escapeControl,
+        ),
+      );
+      await this.io.send(
+        encodeSubpacket(
+          encodeFileInfo(file, filesLeft, bytesLeft),
+          ZCRCW,
+          this.escapeControl,
+        ),
+      );
+      const header = await this.expect([ZRPOS, ZSKIP, ZRINIT]);
+      if (header.type === ZRPOS) {
+        return headerPosition(header);
+      }
+      if (header.type === ZSKIP) {
+        return null;
+      }
+    }
+    throw new Error("接收端未响应文件信息");
+  }
+
+  /**
+   * 从 `position` 开始发送文件内容，接收端请求重传时从新的位置重发
+   */
+  private async sendFile(file: ZmodemFile, position: number): Promise<void> {
+    for (;;) {
+      const sent = await this.sendData(file, position);
+      if ("rewind" in sent) {
+        position = sent.rewind;
+        continue;
+      }
+      await this.io.send(
+        encodeBinaryHeader(
+          { type: ZEOF, args: positionArgs(sent.end) },
+          this.escapeControl,
+        ),
+      );
+      const header = await this.expect([ZRINIT, ZRPOS]);
+      if (header.type === ZRINIT) {
+        return;
+      }
+      position = headerPosition(header);
+    }
+  }
+
+  /**
+   * 发送 ZDATA 帧
+   *
+   * @returns 发送完成时为结束位置，接收端中途请求重传时为重传位置
+   */
+  private async sendData(
+    file: ZmodemFile,
+    position: number,
+  ): Promise<{ end: number } | { rewind: number }> {
+    await this.io.send(
+      encodeBinaryHeader(
+        { type: ZDATA, args: positionArgs(position) },
+        this.escapeControl,
+      ),
+    );
+    const window = this.windowSize;
+    let unacknowledged = 0;
+    let offset = position;
+    for (;;) {
+      const chunk =
+        offset < file.size
+          ? await this.io.read(file, offset, READ_CHUNK_SIZE)
+          : new Uint8Array(0);
+      // 文件在上传期间变小时按实际内容结束
+      const eof = chunk.length === 0 || offset + chunk.length >= file.size;
+      const packets: Uint8Array[] = [];
+      let sent = 0;
+      let last = false;
+      let waitAck = false;
+      do {
+        const data = chunk.subarray(sent, sent + SUBPACKET_SIZE);
+        sent += data.length;
+        unacknowledged += data.length;
+        last = eof && sent >= chunk.length;
+        waitAck = !last && window > 0 && unacknowledged >= window;
+        const end = last ? ZCRCE : waitAck ? ZCRCW : ZCRCG;
+        packets.push(encodeSubpacket(data, end, this.escapeControl));
+      } while (sent < chunk.length && !waitAck);
+      offset += sent;
+
+      await this.io.send(concat(packets));
+      this.io.onProgress?.(file, offset);
+
+      const rewind = this.takeRewind();
+      if (rewind !== null) {
+        return { rewind };
+      }
+      if (last) {
+        return { end: offset };
+      }
+      if (waitAck) {
+        // ZCRCW 结束当前帧，收到 ZACK 后以新的 ZDATA 帧继续
+        const header = await this.expect([ZACK, ZRPOS]);
+        if (header.type === ZRPOS) {
+          return { rewind: headerPosition(header) };
+        }
+        unacknowledged = 0;
+        await this.io.send(
+          encodeBinaryHeader(
+            { type: ZDATA, args: positionArgs(offset) },
+            this.escapeControl,
+          ),
+        );
+      }
+    }
+  }
+
+  /** 取出传输中收到的 ZRPOS（接收端出错，要求从该位置重传） */
+  private takeRewind(): number | null {
+    const index = this.headers.findIndex((h) => h.type === ZRPOS);
+    if (index < 0) {
+      return null;
+    }
+    const header = this.headers[index];
+    this.headers = this.headers.slice(index + 1);
+    return headerPosition(header);
+  }
+
+  /**
+   * 等待指定类型的帧头，其他帧头被忽略
+   */
+  private async expect(types: number[]): Promise<ZmodemHeader> {
+    const deadline = Date.now() + RESPONSE_TIMEOUT_MS;
+    for (;;) {
+      if (this.reader.cancelled) {
+        throw new Error("远程取消了传输");
+      }
+      const index = this.headers.findIndex((h) => types.includes(h.type));
+      if (index >= 0) {
+        const header = this.headers[index];
+        this.headers = this.headers.slice(index + 1);
+        return header;
+      }
+      this.headers = [];
+      if (this.finished) {
+        throw new Error("传输已中止");
+      }
+      const remaining = deadline - Date.now();
+      if (remaining <= 0) {
+        throw new Error("等待接收端响应超时");
+      }
+      await new Promise<void>((resolve) => {
+        const timer = setTimeout(resolve, remaining);
+        this.waiter = () => {
+          clearTimeout(timer);
+          resolve();
+        };
+      });
+      this.waiter = null;
+    }
+  }
+
+  private wake(): void {
+    this.waiter?.();
+  }
+}
+
+function concat(parts: Uint8Array[]): Uint8Array {
+  const total = parts.reduce((sum, part) => sum + part.length, 0);
+  const out = new Uint8Array(total);
+  let offset = 0;
+  for (const part of parts) {
+    out.set(part, offset);
+    offset += part.length;
+  }
+  return out;
+}